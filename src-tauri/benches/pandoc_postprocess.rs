@@ -0,0 +1,425 @@
+//! pandoc 后处理基准：CSS 类转内联样式的旧实现（每条规则各扫描一遍全文）与当前单遍实现对比。
+//!
+//! ```text
+//! cargo bench --bench pandoc_postprocess
+//! ```
+
+use binder_lib::bench_support::PandocService;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+
+/// 合成的 Pandoc 输出：标签 / 类 / 居中类规则与 data-custom-style 混排
+fn synthetic_document(paragraphs: usize, classes: usize) -> String {
+  let mut css = String::from(
+    "html { color: #1a1a1a; } body { margin: 0 auto; padding-top: 50px; } p { margin: 1em 0; } \
+     h1, h2, h3 { margin-top: 1.4em; } td, th { padding: 0.25em 0.5em; } .center { text-align: center; }",
+  );
+  for i in 0..classes {
+    css.push_str(&format!(
+      " .c{} {{ color: #00{:04}; font-weight: bold; }}",
+      i, i
+    ));
+  }
+  let mut body = String::new();
+  for i in 0..paragraphs {
+    body.push_str(&match i % 5 {
+      0 => format!("<h2 id=\"s{}\">Section {}</h2>\n", i, i),
+      1 => format!("<p class=\"c{} extra\">Para {}</p>\n", i % classes, i),
+      2 => format!(
+        "<p class=\"center\" style=\"margin: 0\">Centered {}</p>\n",
+        i
+      ),
+      3 => format!(
+        "<div data-custom-style=\"c{}\"><p>Styled {}</p></div>\n",
+        i % classes,
+        i
+      ),
+      _ => "<table><tr><td class=\"text-center\">x</td></tr></table>\n".to_string(),
+    });
+  }
+  format!(
+    "<html><head><style>{}</style></head><body>\n{}</body></html>",
+    css, body
+  )
+}
+
+fn inline_css(c: &mut Criterion) {
+  let mut group = c.benchmark_group("convert_css_classes_to_inline_styles");
+  group.sample_size(10);
+  for paragraphs in [500, 3000] {
+    let html = synthetic_document(paragraphs, 50);
+    group.bench_with_input(BenchmarkId::new("legacy", paragraphs), &html, |b, html| {
+      b.iter(|| legacy::convert_css_classes_to_inline_styles(black_box(html)))
+    });
+    group.bench_with_input(
+      BenchmarkId::new("single_pass", paragraphs),
+      &html,
+      |b, html| b.iter(|| PandocService::convert_css_classes_to_inline_styles(black_box(html))),
+    );
+  }
+  group.finish();
+}
+
+criterion_group!(benches, inline_css);
+criterion_main!(benches);
+
+/// 单遍实现之前的版本，原样保留，仅作对比基线
+#[allow(unused, clippy::all)]
+mod legacy {
+  use std::collections::HashMap;
+
+  pub fn convert_css_classes_to_inline_styles(html: &str) -> String {
+    use regex::Regex;
+    let mut result = html.to_string();
+
+    // 1. 提取 <style> 标签中的所有 CSS 规则
+    let style_regex = Regex::new(r#"<style[^>]*>([\s\S]*?)</style>"#).unwrap();
+    let mut style_map: HashMap<String, String> = HashMap::new();
+    let mut tag_style_map: HashMap<String, String> = HashMap::new();
+
+    for cap in style_regex.captures_iter(&result) {
+      let style_content = &cap[1];
+
+      // 解析 CSS 类规则：.class-name { property: value; }
+      let rule_regex = Regex::new(r#"\.([a-zA-Z0-9_-]+)\s*\{([^}]+)\}"#).unwrap();
+      for rule_cap in rule_regex.captures_iter(style_content) {
+        let class_name = rule_cap[1].to_string();
+        let styles = rule_cap[2].trim().to_string();
+        // 合并相同类名的样式（如果存在）
+        style_map
+          .entry(class_name)
+          .and_modify(|e| {
+            *e = format!("{}; {}", e, styles);
+          })
+          .or_insert(styles);
+      }
+
+      // 解析标签选择器规则：p { ... }, h1 { ... }, div { ... } 等
+      // 支持单个标签和组合标签（如 h1, h2, h3 { ... }）
+      let tag_rule_regex = Regex::new(r#"((?:p|h[1-6]|div|span|td|th|body|html)(?:\s*,\s*(?:p|h[1-6]|div|span|td|th|body|html))*)\s*\{([^}]+)\}"#).unwrap();
+      for tag_cap in tag_rule_regex.captures_iter(style_content) {
+        let tag_selector = tag_cap[1].to_string();
+        let styles = tag_cap[2].trim().to_string();
+        // 解析组合选择器中的每个标签
+        let tags: Vec<&str> = tag_selector.split(',').map(|s| s.trim()).collect();
+        for tag_name in tags {
+          // 合并相同标签的样式（追加，而不是覆盖）
+          tag_style_map
+            .entry(tag_name.to_string())
+            .and_modify(|e| {
+              // 先收集现有属性
+              let existing_props: Vec<&str> = e
+                .split(';')
+                .map(|s| s.split(':').next().unwrap_or("").trim())
+                .collect();
+
+              // 收集需要添加的新样式
+              let mut new_styles = Vec::new();
+              for style_part in styles.split(';') {
+                let style_part = style_part.trim();
+                if !style_part.is_empty() {
+                  let prop = style_part.split(':').next().unwrap_or("").trim();
+                  // 如果属性不存在，则添加到列表
+                  if !existing_props.contains(&prop) {
+                    new_styles.push(style_part);
+                  }
+                }
+              }
+
+              // 一次性添加所有新样式
+              if !new_styles.is_empty() {
+                let new_styles_str = new_styles.join("; ");
+                *e = format!("{}; {}", e, new_styles_str);
+              }
+            })
+            .or_insert(styles.clone());
+        }
+
+        // 诊断：如果包含 text-align，输出日志
+        if styles.contains("text-align") {
+          tracing::debug!(
+            "提取到包含 text-align 的规则: {} {{ {} }}",
+            tag_selector,
+            styles
+          );
+        }
+      }
+
+      // 对齐信息通过 CSS 类转换保留（convert_css_classes_to_inline_styles）
+
+      // 解析 ID 选择器规则：#id { ... }（用于特定元素）
+      let id_rule_regex = Regex::new(r#"#([a-zA-Z0-9_-]+)\s*\{([^}]+)\}"#).unwrap();
+      for id_cap in id_rule_regex.captures_iter(style_content) {
+        let id_name = id_cap[1].to_string();
+        let styles = id_cap[2].trim().to_string();
+        // 将 ID 样式也存储到 style_map 中，使用特殊前缀
+        style_map
+          .entry(format!("#{}", id_name))
+          .and_modify(|e| {
+            *e = format!("{}; {}", e, styles);
+          })
+          .or_insert(styles);
+      }
+    }
+
+    // 统计 ID 选择器数量
+    let id_selector_count = style_map.keys().filter(|k| k.starts_with('#')).count();
+    let class_selector_count = style_map.len() - id_selector_count;
+
+    tracing::debug!("提取到 {} 个 CSS 类规则", class_selector_count);
+    tracing::debug!("提取到 {} 个 ID 选择器规则", id_selector_count);
+    tracing::debug!("提取到 {} 个标签样式规则", tag_style_map.len());
+
+    // 2. 将标签样式应用到所有对应标签的元素
+    for (tag_name, styles) in tag_style_map.iter() {
+      // 查找所有该标签的元素（不区分大小写）
+      let tag_pattern = format!(r#"<{}[^>]*>"#, regex::escape(tag_name));
+      let tag_re = Regex::new(&tag_pattern).unwrap();
+      let mut replacements: Vec<(usize, usize, String)> = Vec::new();
+
+      // 诊断：检查样式内容
+      if styles.contains("text-align") {
+        tracing::debug!("为 <{}> 应用样式，包含 text-align: {}", tag_name, styles);
+      }
+
+      for cap in tag_re.captures_iter(&result) {
+        let full_match = cap.get(0).unwrap();
+        let match_start = full_match.start();
+        let match_end = full_match.end();
+        let element = &result[match_start..match_end];
+
+        // 检查是否已有 style 属性
+        let new_element = if element.contains("style=") {
+          let style_re = Regex::new(r#"style="([^"]*)""#).unwrap();
+          style_re
+            .replace(element, |caps: &regex::Captures| {
+              let existing_style = &caps[1];
+              let mut new_styles = existing_style.to_string();
+              for style_part in styles.split(';') {
+                let style_part = style_part.trim();
+                if !style_part.is_empty() {
+                  let prop = style_part.split(':').next().unwrap_or("").trim();
+                  // 对于 text-align，需要检查是否已存在（可能以不同形式存在）
+                  let prop_lower = prop.to_lowercase();
+                  if prop_lower == "text-align" {
+                    // 检查是否已有 text-align 或 textAlign
+                    if !existing_style.to_lowercase().contains("text-align") {
+                      new_styles.push_str(&format!("; {}", style_part));
+                    }
+                  } else if !existing_style.to_lowercase().contains(&prop_lower) {
+                    new_styles.push_str(&format!("; {}", style_part));
+                  }
+                }
+              }
+              format!(r#"style="{}""#, new_styles)
+            })
+            .to_string()
+        } else {
+          element.replace('>', &format!(r#" style="{}">"#, styles))
+        };
+
+        replacements.push((match_start, match_end, new_element));
+      }
+
+      // 从后往前替换，避免索引偏移
+      for (start, end, new_elem) in replacements.iter().rev() {
+        result.replace_range(*start..*end, new_elem);
+      }
+
+      if !replacements.is_empty() {
+        tracing::info!(
+          "为 {} 个 <{}> 元素应用样式: {}",
+          replacements.len(),
+          tag_name,
+          styles
+        );
+      }
+    }
+
+    // 3. 将 CSS 类转换为内联样式
+    for (class_name, styles) in style_map.iter() {
+      let pattern = format!(r#"class="([^"]*\b{}\b[^"]*)""#, regex::escape(class_name));
+      let re = Regex::new(&pattern).unwrap();
+
+      // 收集所有需要替换的位置（从后往前，避免索引偏移）
+      let mut replacements: Vec<(usize, usize, String)> = Vec::new();
+
+      for cap in re.captures_iter(&result) {
+        let full_match = cap.get(0).unwrap();
+        let match_start = full_match.start();
+        let _match_end = full_match.end();
+
+        // 找到对应的元素开始标签
+        if let Some(elem_start) = result[..match_start].rfind('<') {
+          if let Some(elem_end) = result[match_start..].find('>') {
+            let elem_end = match_start + elem_end;
+            let element = &result[elem_start..elem_end + 1];
+
+            // 检查是否已有 style 属性
+            let new_element = if element.contains("style=") {
+              // 追加样式（合并，避免重复）
+              let style_re = Regex::new(r#"style="([^"]*)""#).unwrap();
+              style_re
+                .replace(element, |caps: &regex::Captures| {
+                  let existing_style = &caps[1];
+                  // 检查样式是否已存在，避免重复
+                  let mut new_styles = existing_style.to_string();
+                  for style_part in styles.split(';') {
+                    let style_part = style_part.trim();
+                    if !style_part.is_empty() {
+                      let prop = style_part.split(':').next().unwrap_or("").trim();
+                      if !existing_style.contains(prop) {
+                        new_styles.push_str(&format!("; {}", style_part));
+                      }
+                    }
+                  }
+                  format!(r#"style="{}""#, new_styles)
+                })
+                .to_string()
+            } else {
+              // 添加 style 属性
+              element.replace('>', &format!(r#" style="{}">"#, styles))
+            };
+
+            replacements.push((elem_start, elem_end + 1, new_element));
+          }
+        }
+      }
+
+      // 从后往前替换，避免索引偏移
+      for (start, end, new_elem) in replacements.iter().rev() {
+        result.replace_range(*start..*end, new_elem);
+      }
+    }
+
+    // 3. 处理特定的常见格式类（即使不在 style 标签中）
+    // 处理居中对齐
+    for class_name in &["center", "text-center"] {
+      let pattern = format!(r#"class="([^"]*\b{}\b[^"]*)""#, regex::escape(class_name));
+      let re = Regex::new(&pattern).unwrap();
+      let mut replacements: Vec<(usize, usize, String)> = Vec::new();
+
+      for cap in re.captures_iter(&result) {
+        let full_match = cap.get(0).unwrap();
+        let match_start = full_match.start();
+        let _match_end = full_match.end();
+
+        if let Some(elem_start) = result[..match_start].rfind('<') {
+          if let Some(elem_end) = result[match_start..].find('>') {
+            let elem_end = match_start + elem_end;
+            let element = &result[elem_start..elem_end + 1];
+
+            let new_element = if element.contains("style=") {
+              if !element.contains("text-align") {
+                let style_re = Regex::new(r#"style="([^"]*)""#).unwrap();
+                style_re
+                  .replace(element, |caps: &regex::Captures| {
+                    format!(r#"style="{}; text-align: center""#, &caps[1])
+                  })
+                  .to_string()
+              } else {
+                element.to_string()
+              }
+            } else {
+              element.replace('>', r#" style="text-align: center">"#)
+            };
+
+            replacements.push((elem_start, elem_end + 1, new_element));
+          }
+        }
+      }
+
+      for (start, end, new_elem) in replacements.iter().rev() {
+        result.replace_range(*start..*end, new_elem);
+      }
+    }
+
+    // 4. 处理 data-custom-style 属性（Pandoc 可能使用此属性保留样式信息）
+    // 将 data-custom-style 转换为内联样式
+    let data_style_regex = Regex::new(r#"data-custom-style="([^"]+)""#).unwrap();
+    let mut replacements: Vec<(usize, usize, String)> = Vec::new();
+
+    for cap in data_style_regex.captures_iter(&result) {
+      let full_match = cap.get(0).unwrap();
+      let match_start = full_match.start();
+      let match_end = full_match.end();
+      let style_name = &cap[1];
+
+      // 查找对应的元素开始标签
+      if let Some(elem_start) = result[..match_start].rfind('<') {
+        if let Some(elem_end) = result[match_start..].find('>') {
+          let elem_end = match_start + elem_end;
+          let element = &result[elem_start..elem_end + 1];
+
+          // 检查 style_map 中是否有对应的样式
+          if let Some(styles) = style_map.get(style_name) {
+            let new_element = if element.contains("style=") {
+              // 合并样式
+              let style_re = Regex::new(r#"style="([^"]*)""#).unwrap();
+              style_re
+                .replace(element, |caps: &regex::Captures| {
+                  let existing_style = &caps[1];
+                  let mut new_styles = existing_style.to_string();
+                  for style_part in styles.split(';') {
+                    let style_part = style_part.trim();
+                    if !style_part.is_empty() {
+                      let prop = style_part.split(':').next().unwrap_or("").trim();
+                      if !existing_style.contains(prop) {
+                        new_styles.push_str(&format!("; {}", style_part));
+                      }
+                    }
+                  }
+                  format!(r#"style="{}""#, new_styles)
+                })
+                .to_string()
+            } else {
+              // 添加 style 属性
+              element.replace('>', &format!(r#" style="{}">"#, styles))
+            };
+
+            // 移除 data-custom-style 属性
+            let final_element =
+              new_element.replace(&format!(r#" data-custom-style="{}""#, style_name), "");
+            replacements.push((elem_start, elem_end + 1, final_element));
+          }
+        }
+      }
+    }
+
+    // 从后往前替换
+    for (start, end, new_elem) in replacements.iter().rev() {
+      result.replace_range(*start..*end, new_elem);
+    }
+
+    // Bug1 修复：移除 body 的 padding-top、margin-top，消除 DOCX 顶部空白行
+    // Pandoc 默认模板会给 body 添加 padding-top: 50px、padding: 12px、margin-top: 0.5em 等，导致约 70px 顶部空白
+    let body_tag_re = Regex::new(r#"<body([^>]*)>"#).unwrap();
+    result = body_tag_re
+      .replace_all(&result, |caps: &regex::Captures| {
+        let rest = caps.get(1).map(|m| m.as_str()).unwrap_or("");
+        let new_rest = if rest.contains("style=") {
+          let style_re = Regex::new(r#"style="([^"]*)""#).unwrap();
+          style_re
+            .replace(rest, |scaps: &regex::Captures| {
+              let s = scaps.get(1).map(|m| m.as_str()).unwrap_or("");
+              let override_s = if s.trim().is_empty() {
+                "padding-top: 0; margin-top: 0".to_string()
+              } else {
+                format!(
+                  "{}; padding-top: 0; margin-top: 0",
+                  s.trim_end_matches(';').trim()
+                )
+              };
+              format!(r#"style="{}""#, override_s)
+            })
+            .to_string()
+        } else {
+          format!(r#"{} style="padding-top: 0; margin-top: 0""#, rest)
+        };
+        format!("<body{}>", new_rest)
+      })
+      .into_owned();
+
+    tracing::info!("CSS 类转内联样式处理完成");
+    result
+  }
+}
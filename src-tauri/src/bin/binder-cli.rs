@@ -0,0 +1,5 @@
+//! 命令行工具入口，实现见 `binder_lib::cli`
+
+fn main() {
+  std::process::exit(binder_lib::cli::main());
+}
@@ -0,0 +1,334 @@
+//! 命令行工具 `binder-cli`：不启动界面，直接调用转换、搜索、分类、导出服务，便于脚本调用。
+//!
+//! ```text
+//! binder-cli convert report.docx --to md
+//! binder-cli search "预算" --workspace ~/文档 --reindex
+//! binder-cli classify 下载/*.pdf --workspace ~/文档 --json
+//! binder-cli export 周报.md --to pdf
+//! ```
+//!
+//! 与界面共用同一套服务代码（`src/bin/binder-cli.rs` 只调用 [`main`]）。
+//! 日志输出到 stderr，默认只输出警告，可用 `RUST_LOG` 调整。
+
+use crate::services::ai_providers::AIProvider;
+use crate::services::ai_service::AIService;
+use crate::services::file_classifier::FileClassifierService;
+use crate::services::pandoc_service::PandocService;
+use crate::services::print_service::PrintService;
+use crate::services::search_service::SearchService;
+use crate::services::{export_numbering, plugins, settings_service};
+use clap::{Parser, Subcommand, ValueEnum};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+/// 与 tauri.conf.json 的 identifier 一致，和界面共用应用设置
+const APP_IDENTIFIER: &str = "com.binder.app";
+
+#[derive(Debug, Parser)]
+#[command(name = "binder-cli", version, about = "Binder（合页）命令行")]
+struct Cli {
+  #[command(subcommand)]
+  command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+  /// 用 Pandoc 读取文档（docx/odt/rtf/epub/html）并转换为 md/txt/html，其他格式交给插件转换器
+  Convert {
+    input: PathBuf,
+    /// 目标格式，如 md、txt、html 或插件转换器声明的扩展名
+    #[arg(long)]
+    to: String,
+    /// 输出文件，默认与源文件同目录、同名
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+  },
+  /// 在工作区全文索引中搜索
+  Search {
+    query: String,
+    #[arg(short, long, default_value = ".")]
+    workspace: PathBuf,
+    #[arg(short, long, default_value_t = 20)]
+    limit: usize,
+    /// 搜索前先更新索引（首次使用或文件有变动时）
+    #[arg(long)]
+    reindex: bool,
+    #[arg(long)]
+    json: bool,
+  },
+  /// 按 .binder/classify_rules.json 分类文件，未命中规则的文件交给 AI
+  Classify {
+    #[arg(required = true)]
+    files: Vec<PathBuf>,
+    #[arg(short, long, default_value = ".")]
+    workspace: PathBuf,
+    /// 只使用分类规则，不调用 AI
+    #[arg(long)]
+    no_ai: bool,
+    #[arg(long)]
+    json: bool,
+  },
+  /// 导出为 DOCX（Markdown/HTML，使用工作区参考模板）或 PDF（LibreOffice）
+  Export {
+    input: PathBuf,
+    #[arg(long, value_enum, default_value_t = ExportFormat::Pdf)]
+    to: ExportFormat,
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+  },
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum ExportFormat {
+  Docx,
+  Pdf,
+}
+
+/// 解析命令行参数并执行，返回退出码
+pub fn main() -> i32 {
+  let cli = match Cli::try_parse() {
+    Ok(cli) => cli,
+    Err(e) => {
+      let _ = e.print();
+      return e.exit_code();
+    }
+  };
+  init_cli_logger();
+  if let Some(config_dir) = dirs::config_dir() {
+    settings_service::init(&config_dir.join(APP_IDENTIFIER));
+  }
+
+  let runtime = match tokio::runtime::Runtime::new() {
+    Ok(runtime) => runtime,
+    Err(e) => {
+      eprintln!("错误: 初始化运行时失败: {}", e);
+      return 1;
+    }
+  };
+  match runtime.block_on(run(cli.command)) {
+    Ok(()) => 0,
+    Err(e) => {
+      eprintln!("错误: {}", e);
+      1
+    }
+  }
+}
+
+fn init_cli_logger() {
+  use tracing_subscriber::EnvFilter;
+  let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("warn"));
+  let _ = tracing_subscriber::fmt()
+    .with_env_filter(filter)
+    .with_writer(std::io::stderr)
+    .try_init();
+}
+
+async fn run(command: Command) -> Result<(), String> {
+  match command {
+    Command::Convert { input, to, output } => {
+      let output = convert(&input, &to, output).await?;
+      println!("{}", output.display());
+    }
+    Command::Search {
+      query,
+      workspace,
+      limit,
+      reindex,
+      json,
+    } => {
+      let service =
+        SearchService::new(&workspace).map_err(|e| format!("初始化搜索服务失败: {}", e))?;
+      if reindex {
+        let count = service.build_index(|_| true);
+        eprintln!("已更新 {} 个文件的索引", count);
+      }
+      let results = service
+        .search(&query, limit)
+        .map_err(|e| format!("搜索失败: {}", e))?;
+      if json {
+        print_json(&results)?;
+      } else {
+        for result in &results {
+          println!("{}\t{}", result.path, result.title);
+          println!(
+            "    {}",
+            result.snippet.replace("<mark>", "").replace("</mark>", "")
+          );
+        }
+      }
+    }
+    Command::Classify {
+      files,
+      workspace,
+      no_ai,
+      json,
+    } => {
+      let provider = if no_ai {
+        None
+      } else {
+        classification_provider()
+      };
+      let cancel = AtomicBool::new(false);
+      let classifications =
+        FileClassifierService::classify_files(provider, files, &workspace, |_| {}, &cancel).await?;
+      if json {
+        print_json(&classifications)?;
+      } else {
+        for c in &classifications {
+          println!(
+            "{}\t{}\t{:.2}\t{}",
+            c.file_path, c.category, c.confidence, c.reason
+          );
+        }
+      }
+    }
+    Command::Export { input, to, output } => {
+      let output = export(&input, to, output)?;
+      println!("{}", output.display());
+    }
+  }
+  Ok(())
+}
+
+fn print_json<T: serde::Serialize>(value: &T) -> Result<(), String> {
+  let json = serde_json::to_string_pretty(value).map_err(|e| format!("序列化结果失败: {}", e))?;
+  println!("{}", json);
+  Ok(())
+}
+
+fn extension_of(path: &Path) -> String {
+  path
+    .extension()
+    .and_then(|e| e.to_str())
+    .unwrap_or_default()
+    .to_lowercase()
+}
+
+/// 未指定输出文件时与源文件同目录、扩展名替换为目标格式；不允许覆盖源文件
+fn output_path(input: &Path, extension: &str, output: Option<PathBuf>) -> Result<PathBuf, String> {
+  let output = output.unwrap_or_else(|| input.with_extension(extension));
+  if output == input {
+    return Err(format!("输出文件与源文件相同: {}", output.display()));
+  }
+  Ok(output)
+}
+
+async fn convert(input: &Path, to: &str, output: Option<PathBuf>) -> Result<PathBuf, String> {
+  if !input.is_file() {
+    return Err(format!("文件不存在: {}", input.display()));
+  }
+  let target = to.trim_start_matches('.').to_lowercase();
+  let extension = match target.as_str() {
+    "md" | "markdown" => "md",
+    "txt" | "text" | "plain" => "txt",
+    "html" => "html",
+    _ => {
+      // 非内置格式交给声明了该源/目标扩展名的插件转换器
+      let source = extension_of(input);
+      let converter = plugins::with_registry(|r| {
+        r.converters()
+          .find(|c| {
+            c.to.trim_start_matches('.').eq_ignore_ascii_case(&target)
+              && c
+                .from
+                .iter()
+                .any(|f| f.trim_start_matches('.').eq_ignore_ascii_case(&source))
+          })
+          .map(|c| c.name.clone())
+      })
+      .ok_or_else(|| format!("不支持将 .{} 转换为 {}，也没有可用的插件转换器", source, to))?;
+      return plugins::convert(&converter, input, output).await;
+    }
+  };
+
+  let output = output_path(input, extension, output)?;
+  let pandoc = PandocService::new();
+  if !pandoc.is_available() {
+    return Err(crate::error::AppError::PandocUnavailable.to_string());
+  }
+  let content = match extension {
+    "md" => pandoc.extract_markdown(input)?,
+    "html" => pandoc.convert_document_to_html(input, None)?,
+    _ => pandoc.extract_plain_text(input)?,
+  };
+  std::fs::write(&output, content).map_err(|e| format!("写入文件失败: {}", e))?;
+  Ok(output)
+}
+
+fn export(input: &Path, format: ExportFormat, output: Option<PathBuf>) -> Result<PathBuf, String> {
+  if !input.is_file() {
+    return Err(format!("文件不存在: {}", input.display()));
+  }
+  match format {
+    ExportFormat::Docx => {
+      let output = output_path(input, "docx", output)?;
+      let pandoc = PandocService::new();
+      if !pandoc.is_available() {
+        return Err(crate::error::AppError::PandocUnavailable.to_string());
+      }
+      let content = std::fs::read_to_string(input).map_err(|e| format!("读取文件失败: {}", e))?;
+      match extension_of(input).as_str() {
+        "md" | "markdown" => {
+          let workspace = input.parent().unwrap_or(Path::new("."));
+          let reference = PandocService::workspace_reference_docx(workspace);
+          pandoc.convert_markdown_to_docx(&content, &output, reference.as_deref())?
+        }
+        "html" | "htm" => {
+          let content = export_numbering::apply_workspace_numbering(input, &content);
+          pandoc.convert_html_to_docx(&content, &output)?
+        }
+        _ => {
+          return Err(format!(
+            "只能将 Markdown 或 HTML 导出为 DOCX: {}",
+            input.display()
+          ))
+        }
+      }
+      Ok(output)
+    }
+    ExportFormat::Pdf => {
+      let output = output_path(input, "pdf", output)?;
+      // 转换结果在预览缓存目录，复制到目标位置
+      let pdf = PrintService::ensure_pdf(input)?;
+      std::fs::copy(&pdf, &output).map_err(|e| format!("写入文件失败: {}", e))?;
+      Ok(output)
+    }
+  }
+}
+
+/// 优先使用应用设置中的默认提供商，其次 DeepSeek、OpenAI
+fn classification_provider() -> Option<Arc<dyn AIProvider>> {
+  let service = AIService::new()
+    .map_err(|e| tracing::warn!("初始化 AI 服务失败: {}", e))
+    .ok()?;
+  [
+    settings_service::default_ai_provider().as_str(),
+    "deepseek",
+    "openai",
+  ]
+  .into_iter()
+  .find_map(|name| service.get_provider(name))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn parses_subcommands() {
+    let cli = Cli::try_parse_from(["binder-cli", "convert", "report.docx", "--to", "md"]).unwrap();
+    assert!(matches!(cli.command, Command::Convert { ref to, .. } if to == "md"));
+    let cli = Cli::try_parse_from(["binder-cli", "export", "a.md"]).unwrap();
+    assert!(matches!(
+      cli.command,
+      Command::Export {
+        to: ExportFormat::Pdf,
+        ..
+      }
+    ));
+    assert!(Cli::try_parse_from(["binder-cli", "classify"]).is_err());
+    assert!(Cli::try_parse_from(["binder-cli", "/tmp/report.docx"]).is_err());
+    assert!(output_path(Path::new("a.md"), "md", None).is_err());
+  }
+}
@@ -1,38 +1,63 @@
-use crate::services::ai_providers::{ChatChunk, ChatMessage, ModelConfig};
-use crate::services::ai_service::AIService;
+use crate::error::AppError;
+use crate::services::agent_transaction;
+use crate::services::ai_providers::{AIProvider, ChatChunk, ChatMessage, ModelConfig};
+use crate::services::ai_queue::RequestType;
+use crate::services::ai_service::{AIService, ChatSlot};
+use crate::services::autocomplete_context::{self, AUTOCOMPLETE_TOKEN_BUDGET};
+use crate::services::chat_stream_registry::ChatStreamRegistry;
 use crate::services::context_manager::{
   ContextInfo, ContextManager, EditorState as ContextEditorState, KnowledgeRetrievalContext,
   ReferenceInfo, ReferenceType, TruncationStrategy,
 };
 use crate::services::conversation_manager::ConversationManager;
 use crate::services::document_analysis::{AnalysisType, DocumentAnalysisService};
-use crate::services::file_watcher::FileWatcherService;
+use crate::services::filename_suggestion::{self, FilenameCandidate};
+use crate::services::folder_summary::{
+  self, DocumentSummary, FolderSummary, FolderSummaryOptions, SkippedDocument,
+};
+use crate::services::grounding::{self, GROUNDING_PASSAGE_CHARS, GROUNDING_SOURCE_LIMIT};
+use crate::services::inline_assist::{self, InlineAssistResult};
+use crate::services::job_service::{self, JobContext, JobSpec};
 use crate::services::knowledge::{
   KnowledgeInjectionSlice, KnowledgeQueryRequest, KnowledgeService,
 };
 use crate::services::loop_detector::LoopDetector;
 use crate::services::memory_service::{
-  format_memory_for_injection, MemorySearchScope, MemoryService, SearchMemoriesParams,
+  format_memory_for_injection, merge_with_user_memories, MemorySearchResult, MemorySearchScope,
+  MemoryService, SearchMemoriesParams,
 };
+use crate::services::ocr_service::{OcrService, OCR_DEFAULT_MAX_PAGES};
+use crate::services::pdf_page_renderer::PdfPageRenderer;
+use crate::services::pdf_text;
+use crate::services::plugins;
+use crate::services::proofread::{ProofreadOptions, ProofreadResult, ProofreadService};
 use crate::services::reply_completeness_checker::ReplyCompletenessChecker;
+use crate::services::scanned_pdf::{self, ScannedPdfAnalysis};
+use crate::services::search_service::{
+  format_snippets_for_injection, natural_language_query, SearchService,
+};
+use crate::services::settings_service;
+use crate::services::snippets::{self, SnippetContext};
 use crate::services::stream_state::{
   begin_next_stream_round, finalize_stream, stream_state_label, StreamContext, StreamState,
 };
 use crate::services::streaming_response_handler::StreamingResponseHandler;
 use crate::services::task_progress_analyzer::TaskProgressAnalyzer;
+use crate::services::telemetry;
 use crate::services::template::TemplateService;
-use crate::services::tool_call_handler::ToolCallHandler;
+use crate::services::tool_call_handler::{ToolCallHandler, MAX_PARALLEL_TOOL_CALLS};
 use crate::services::tool_definitions::get_tool_definitions;
 use crate::services::tool_policy::TaskExecutionPolicy;
 use crate::services::tool_service::{ToolCall, ToolService};
-use once_cell::sync::Lazy;
+use crate::services::translation::{self, TermViolation, TranslationProgress, TranslationResult};
+use crate::services::workspace_settings::WorkspaceSettings;
+use crate::utils::i18n;
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
 use tauri::{Emitter, Runtime, State};
-use tokio::sync::oneshot;
 use tokio::time::{timeout, Duration};
 
 // ============================================================================
@@ -46,14 +71,11 @@ use tokio::time::{timeout, Duration};
 // L1/L2 保持兼容链，不得反向污染 L3 主链对象。
 // ============================================================================
 
-// 全局取消通道存储：tab_id -> cancel_tx
-static CANCEL_CHANNELS: Lazy<Arc<Mutex<HashMap<String, oneshot::Sender<()>>>>> =
-  Lazy::new(|| Arc::new(Mutex::new(HashMap::new())));
+/// 对话流登记（managed state）
+type ChatStreamRegistryState = Arc<ChatStreamRegistry>;
 
-// ⚠️ 关键修复：全局取消标志映射：tab_id -> cancel_flag
-// 用于在继续对话时检测取消信号
-static CANCEL_FLAGS: Lazy<Arc<Mutex<HashMap<String, Arc<Mutex<bool>>>>>> =
-  Lazy::new(|| Arc::new(Mutex::new(HashMap::new())));
+/// 取消后流未在该时长内自行收尾时强制终止
+const CHAT_STREAM_CANCEL_GRACE: Duration = Duration::from_secs(3);
 
 /// 安全地截取字符串，确保在字符边界处截取
 fn safe_truncate(s: &str, max_bytes: usize) -> &str {
@@ -192,6 +214,51 @@ fn write_task_stage<R: Runtime>(
   );
 }
 
+/// 部分工具参数的推送步长（字节），避免逐 token 刷新前端
+const PARTIAL_TOOL_CALL_EMIT_STEP: usize = 256;
+
+/// 仍在生成中的工具调用参数：已到达的参数与已推送给前端的字节数
+#[derive(Default)]
+struct PartialToolArguments {
+  arguments: String,
+  emitted: usize,
+}
+
+/// 累积提供商返回的部分参数片段，每增长一个步长向前端推送一次新增部分
+/// （is_complete = false）；前端按 arguments_offset 拼接，只用于实时预览，不会据此执行
+fn emit_partial_tool_call<R: Runtime>(
+  app: &impl Emitter<R>,
+  tab_id: &str,
+  id: &str,
+  name: &str,
+  delta: &str,
+  partials: &mut HashMap<String, PartialToolArguments>,
+) {
+  let partial = partials.entry(id.to_string()).or_default();
+  partial.arguments.push_str(delta);
+  let pending = partial.arguments.len() - partial.emitted;
+  if pending == 0 || (partial.emitted > 0 && pending < PARTIAL_TOOL_CALL_EMIT_STEP) {
+    return;
+  }
+  let payload = serde_json::json!({
+      "tab_id": tab_id,
+      "chunk": "",
+      "done": false,
+      "tool_call": {
+          "id": id,
+          "name": name,
+          "arguments": &partial.arguments[partial.emitted..],
+          "arguments_offset": partial.emitted,
+          "status": "pending",
+          "is_complete": false,
+      },
+  });
+  partial.emitted = partial.arguments.len();
+  if let Err(e) = app.emit("ai-chat-stream", payload) {
+    tracing::warn!("发送部分工具调用事件失败: {}", e);
+  }
+}
+
 fn emit_workflow_execution_runtime<R: Runtime>(
   app: &impl Emitter<R>,
   tab_id: &str,
@@ -231,7 +298,7 @@ fn tool_results_emit_candidate(
     let Some(data) = result.data.as_ref() else {
       return false;
     };
-    if name == "update_file" {
+    if name == "update_file" || name == "apply_patch" {
       return data
         .get("pending_diffs")
         .and_then(|v| v.as_array())
@@ -279,6 +346,9 @@ fn emit_ai_chat_stream_done<R: Runtime>(
   if let Some(e) = error {
     v["error"] = serde_json::Value::String(e.to_string());
   }
+  if let Some(citations) = grounding::finish(tab_id) {
+    v["grounding"] = serde_json::json!(citations);
+  }
   let _ = app.emit("ai-chat-stream", v);
 }
 
@@ -289,8 +359,8 @@ fn push_chat_message_if_allowed(
   msg: ChatMessage,
 ) {
   if msg.role == "assistant" && stream_ctx.state != StreamState::Completed {
-    eprintln!(
-      "⚠️ 跳过 assistant 对话历史写入（流状态非 Completed: {:?}）",
+    tracing::warn!(
+      "跳过 assistant 对话历史写入（流状态非 Completed: {:?}）",
       stream_ctx.state
     );
     return;
@@ -344,6 +414,15 @@ fn now_unix_millis() -> u64 {
   }
 }
 
+/// 流式期间收集、等待统一执行的工具调用
+struct QueuedToolCall {
+  tool_call: ToolCall,
+  /// 模型侧原始 arguments 字符串（用于 assistant tool_calls）
+  arguments: String,
+  /// 解析/增强后的参数（用于发送结果事件）
+  parsed_args_for_result: serde_json::Value,
+}
+
 fn with_execution_observability(
   mut tool_result: crate::services::tool_service::ToolResult,
   tool_name: &str,
@@ -453,7 +532,7 @@ fn tool_result_awaits_confirmation(
 // 这样可以避免重复控制逻辑，确保新的优化能够全面生效
 
 // AI 服务状态（全局单例）
-type AIServiceState = Arc<Mutex<AIService>>;
+type AIServiceState = Arc<AIService>;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChatBuildOutlineStepPayload {
@@ -470,7 +549,11 @@ pub struct ChatBuildOutlinePayload {
   pub steps: Vec<ChatBuildOutlineStepPayload>,
 }
 
-fn chat_build_provider_name(model: &str) -> &'static str {
+/// 根据模型选择提供商：插件声明的模型交给对应插件，其余优先 DeepSeek
+fn provider_name_for_model(model: &str) -> String {
+  if let Some(name) = plugins::provider_for_model(model) {
+    return name;
+  }
   if model.contains("deepseek") {
     "deepseek"
   } else if model.contains("gpt") {
@@ -478,6 +561,7 @@ fn chat_build_provider_name(model: &str) -> &'static str {
   } else {
     "deepseek"
   }
+  .to_string()
 }
 
 fn extract_json_object_block(text: &str) -> Option<&str> {
@@ -496,6 +580,36 @@ fn extract_json_object_block(text: &str) -> Option<&str> {
   Some(&fenced[start..=end])
 }
 
+/// 单轮提问并收集完整回复（不支持取消，用于结构化输出的后台请求）
+async fn collect_chat_text(
+  provider: &Arc<dyn AIProvider>,
+  prompt: String,
+  model_config: &ModelConfig,
+) -> Result<String, AppError> {
+  let messages = vec![ChatMessage {
+    role: "user".to_string(),
+    content: Some(prompt),
+    tool_call_id: None,
+    name: None,
+    tool_calls: None,
+  }];
+  let (_, mut cancel_rx) = tokio::sync::oneshot::channel();
+  let mut stream = provider
+    .chat_stream(&messages, model_config, &mut cancel_rx, None)
+    .await?;
+
+  let mut response = String::new();
+  use tokio_stream::StreamExt;
+  while let Some(chunk_result) = stream.next().await {
+    match chunk_result {
+      Ok(ChatChunk::Text(text)) => response.push_str(&text),
+      Ok(ChatChunk::ToolCall { .. }) => continue,
+      Err(e) => return Err(e.into()),
+    }
+  }
+  Ok(response)
+}
+
 #[derive(serde::Deserialize)]
 pub struct EditorState {
   pub node_type: String,
@@ -522,14 +636,18 @@ pub struct DocumentOverview {
   pub current_section: String,
   pub previous_paragraph: String,
   pub next_paragraph: String,
+  #[serde(default)]
+  pub front_matter: String,
 }
 
 // ============================================================================
 // L1：辅助续写兼容入口
 // ============================================================================
+/// 提供 path 或 content 时由后端按 position（UTF-16 偏移）组织上下文，忽略前端传入的
+/// context_before / context_after / document_overview；content 为空时读取 path 的文件内容
 #[tauri::command]
 pub async fn ai_autocomplete(
-  context_before: String,
+  context_before: Option<String>,
   context_after: Option<String>,
   position: usize,
   max_length: usize,
@@ -537,21 +655,52 @@ pub async fn ai_autocomplete(
   memory_items: Option<Vec<MemoryItem>>,
   document_format: Option<String>,
   document_overview: Option<DocumentOverview>,
+  path: Option<String>,
+  content: Option<String>,
   service: State<'_, AIServiceState>,
-) -> Result<Option<Vec<String>>, String> {
-  // 尝试获取已配置的提供商（优先 DeepSeek，然后是 OpenAI）
-  let provider = {
-    let service_guard = service
-      .lock()
-      .map_err(|e| format!("获取 AI 服务失败: {}", e))?;
-    // 优先使用 DeepSeek，如果没有则使用 OpenAI
-    service_guard
-      .get_provider("deepseek")
-      .or_else(|| service_guard.get_provider("openai"))
+) -> Result<Option<Vec<String>>, AppError> {
+  telemetry::record_feature("ai_autocomplete");
+  let content = match (content, &path) {
+    (Some(content), _) => Some(content),
+    (None, Some(path)) => Some(
+      tokio::fs::read_to_string(path)
+        .await
+        .map_err(|e| format!("读取文件失败: {}", e))?,
+    ),
+    (None, None) => None,
+  };
+  let built = content.map(|content| {
+    let extension = path
+      .as_deref()
+      .and_then(|p| std::path::Path::new(p).extension())
+      .and_then(|e| e.to_str())
+      .or(document_format.as_deref())
+      .unwrap_or("txt");
+    autocomplete_context::build(&content, position, extension, AUTOCOMPLETE_TOKEN_BUDGET)
+  });
+  let context_before = match (&built, context_before) {
+    (Some(built), _) => built.context_before.clone(),
+    (None, Some(context_before)) => context_before,
+    (None, None) => {
+      return Err(AppError::InvalidInput(
+        "缺少续写上下文：需要 path、content 或 context_before".to_string(),
+      ))
+    }
   };
 
-  let provider = provider
-    .ok_or_else(|| "未配置任何 AI 提供商，请先配置 DeepSeek 或 OpenAI API key".to_string())?;
+  // 优先使用应用设置中的默认提供商，没有则依次尝试 DeepSeek、OpenAI
+  let (provider_name, provider) = service
+    .default_provider()
+    .ok_or(AppError::AiNotConfigured)?;
+  // 排队等待名额；有更新的补全请求或排队过久时放弃，不返回结果
+  let _permit = match service
+    .acquire_slot(&provider_name, RequestType::Autocomplete)
+    .await
+  {
+    Ok(permit) => permit,
+    Err(crate::services::ai_error::AIError::Cancelled) => return Ok(None),
+    Err(e) => return Err(e.into()),
+  };
 
   // 转换编辑器状态和记忆库项为 provider 类型
   let editor_state_provider =
@@ -581,8 +730,9 @@ pub async fn ai_autocomplete(
     .unwrap_or_default();
 
   // 转换文档概览为 provider 类型
-  let document_overview_provider =
-    document_overview
+  let document_overview_provider = match &built {
+    Some(built) => Some(built.overview.clone()),
+    None => document_overview
       .as_ref()
       .map(|o| crate::services::ai_providers::DocumentOverview {
         document_start: o.document_start.clone(),
@@ -592,7 +742,13 @@ pub async fn ai_autocomplete(
         current_section: o.current_section.clone(),
         previous_paragraph: o.previous_paragraph.clone(),
         next_paragraph: o.next_paragraph.clone(),
-      });
+        front_matter: o.front_matter.clone(),
+      }),
+  };
+  let (context_after, document_format) = match built {
+    Some(built) => (built.context_after, Some(built.document_format)),
+    None => (context_after, document_format),
+  };
 
   // 调用自动补全（使用增强的提示词）
   // Phase 1a：解析 3 条建议（用 --- 分隔），返回 Vec<String>
@@ -619,10 +775,7 @@ pub async fn ai_autocomplete(
         .filter(|s| !s.is_empty())
         .take(3)
         .collect();
-      eprintln!(
-        "✅ [ai_autocomplete] 成功返回，{} 条建议",
-        suggestions.len()
-      );
+      tracing::info!("[ai_autocomplete] 成功返回，{} 条建议", suggestions.len());
       Ok(if suggestions.is_empty() {
         None
       } else {
@@ -630,8 +783,8 @@ pub async fn ai_autocomplete(
       })
     }
     Err(e) => {
-      eprintln!("❌ [ai_autocomplete] 错误: {}", e);
-      Err(e.to_string())
+      tracing::error!("[ai_autocomplete] 错误: {}", e);
+      Err(e.into())
     }
   }
 }
@@ -646,6 +799,7 @@ pub struct InlineAssistMessage {
 // ============================================================================
 // L2：局部修改兼容入口
 // ============================================================================
+/// 返回 kind / text；修改类结果附带相对选中文本的修改块，供编辑器逐块接受
 #[tauri::command]
 pub async fn ai_inline_assist(
   instruction: String,
@@ -653,16 +807,17 @@ pub async fn ai_inline_assist(
   context: String,
   messages: Option<Vec<InlineAssistMessage>>,
   service: State<'_, AIServiceState>,
-) -> Result<String, String> {
+) -> Result<InlineAssistResult, AppError> {
+  telemetry::record_feature("ai_inline_assist");
   // 记录请求用于调试（不打印完整正文，避免泄露内容）
   let messages_len = messages.as_ref().map(|m| m.len()).unwrap_or(0);
-  eprintln!(
-        "📥 [ai_inline_assist] 收到请求: instruction_len={} text_len={} context_len={} messages_count={}",
-        instruction.chars().count(),
-        text.chars().count(),
-        context.chars().count(),
-        messages_len,
-    );
+  tracing::debug!(
+    "[ai_inline_assist] 收到请求: instruction_len={} text_len={} context_len={} messages_count={}",
+    instruction.chars().count(),
+    text.chars().count(),
+    context.chars().count(),
+    messages_len,
+  );
 
   // Phase 0.4：将历史 messages 拼接到 context 前
   let context_with_history = if let Some(ref msgs) = messages {
@@ -681,19 +836,13 @@ pub async fn ai_inline_assist(
     context
   };
 
-  // 尝试获取已配置的提供商（优先 DeepSeek，然后是 OpenAI）
-  let provider = {
-    let service_guard = service
-      .lock()
-      .map_err(|e| format!("获取 AI 服务失败: {}", e))?;
-    // 优先使用 DeepSeek，如果没有则使用 OpenAI
-    service_guard
-      .get_provider("deepseek")
-      .or_else(|| service_guard.get_provider("openai"))
-  };
-
-  let provider = provider
-    .ok_or_else(|| "未配置任何 AI 提供商，请先配置 DeepSeek 或 OpenAI API key".to_string())?;
+  // 优先使用应用设置中的默认提供商，没有则依次尝试 DeepSeek、OpenAI
+  let (provider_name, provider) = service
+    .default_provider()
+    .ok_or(AppError::AiNotConfigured)?;
+  let _permit = service
+    .acquire_slot(&provider_name, RequestType::InlineAssist)
+    .await?;
 
   // 调用 Inline Assist（使用含历史对话的 context）
   match provider
@@ -701,15 +850,15 @@ pub async fn ai_inline_assist(
     .await
   {
     Ok(result) => {
-      eprintln!(
-        "✅ [ai_inline_assist] 成功返回，结果长度: {} 字符",
+      tracing::info!(
+        "[ai_inline_assist] 成功返回，结果长度: {} 字符",
         result.chars().count()
       );
-      Ok(result)
+      Ok(inline_assist::finish_result(&result, &instruction, &text))
     }
     Err(e) => {
-      eprintln!("❌ [ai_inline_assist] 错误: {}", e);
-      Err(e.to_string())
+      tracing::error!("[ai_inline_assist] 错误: {}", e);
+      Err(e.into())
     }
   }
 }
@@ -932,8 +1081,8 @@ fn sanitize_edit_current_editor_document_arguments(arguments: &mut serde_json::V
     }
   }
   if !removed.is_empty() {
-    eprintln!(
-      "🧹 [edit_current_editor_document] dropped non-whitelisted fields: {:?}",
+    tracing::debug!(
+      "[edit_current_editor_document] dropped non-whitelisted fields: {:?}",
       removed
     );
   }
@@ -1181,9 +1330,11 @@ fn maybe_sync_workflow_execution_from_tool_result<R: Runtime>(
   ) {
     Ok(runtime) => emit_workflow_execution_runtime(app, tab_id, task_id, &runtime),
     Err(error) => {
-      eprintln!(
-        "⚠️ workflow execution feedback sync failed: task_id={} tool={} error={}",
-        task_id, tool_name, error
+      tracing::warn!(
+        "workflow execution feedback sync failed: task_id={} tool={} error={}",
+        task_id,
+        tool_name,
+        error
       );
     }
   }
@@ -1254,94 +1405,88 @@ pub async fn ai_chat_stream(
   cursor_offset: Option<usize>,
   // Phase 6: 前端 shadow task ID，用于 stage 写入与事件推送
   agent_task_id: Option<String>,
+  // 资料问答模式：按消息检索工作区段落并要求回答标注来源，done 事件返回引用的文件
+  grounded: Option<bool>,
   app: tauri::AppHandle,
   service: State<'_, AIServiceState>,
-  watcher: State<'_, Mutex<FileWatcherService>>,
-) -> Result<(), String> {
+  streams: State<'_, ChatStreamRegistryState>,
+) -> Result<(), AppError> {
+  telemetry::record_feature(if enable_tools.unwrap_or(false) {
+    "ai_agent"
+  } else {
+    "ai_chat"
+  });
   // ⚠️ 关键修复：记录 tab_id 以便调试
-  eprintln!(
-    "📥 收到流式聊天请求: tab_id={}, messages_count={}",
+  tracing::debug!(
+    "收到流式聊天请求: tab_id={}, messages_count={}",
     tab_id,
     messages.len()
   );
   if let Some(ref eid) = editor_tab_id {
-    eprintln!(
-      "📎 RequestContext editor_tab_id={} (frontend positioning bucket)",
+    tracing::debug!(
+      "RequestContext editor_tab_id={} (frontend positioning bucket)",
       eid
     );
   }
   if let Some(ref bid) = baseline_id {
-    eprintln!("📎 RequestContext baseline_id={}", bid);
+    tracing::debug!("RequestContext baseline_id={}", bid);
   }
-  // 根据模型选择提供商（优先 DeepSeek）
-  let provider_name = if model_config.model.contains("deepseek") {
-    "deepseek"
-  } else if model_config.model.contains("gpt") {
-    "openai"
-  } else {
-    // 默认优先尝试 DeepSeek，如果没有则使用 OpenAI
-    "deepseek"
-  };
+  // 根据模型选择提供商（插件模型交给插件，其余优先 DeepSeek）
+  let provider_name = provider_name_for_model(&model_config.model);
+  let provider_name = provider_name.as_str();
 
   // 尝试获取提供商（优先选择的，如果没有则尝试另一个）
   let provider = {
-    let service_guard = service
-      .lock()
-      .map_err(|e| format!("获取 AI 服务失败: {}", e))?;
-
     // 优先使用选择的提供商
-    if let Some(p) = service_guard.get_provider(provider_name) {
+    if let Some(p) = service.get_provider(provider_name) {
       Some((p, provider_name))
     } else if provider_name == "deepseek" {
       // 如果没有 DeepSeek，尝试 OpenAI
-      service_guard.get_provider("openai").map(|p| (p, "openai"))
+      service.get_provider("openai").map(|p| (p, "openai"))
     } else {
       // 如果没有 OpenAI，尝试 DeepSeek
-      service_guard
-        .get_provider("deepseek")
-        .map(|p| (p, "deepseek"))
+      service.get_provider("deepseek").map(|p| (p, "deepseek"))
     }
   };
 
-  let (provider, _actual_provider_name) =
-    provider.ok_or_else(|| format!("未配置 {} 提供商，请先配置 API key", provider_name))?;
+  let (provider, actual_provider_name) = provider.ok_or(AppError::AiNotConfigured)?;
 
-  // 创建取消令牌，并存储到全局映射中
-  let (cancel_tx, mut cancel_rx) = tokio::sync::oneshot::channel();
-  {
-    let mut channels = CANCEL_CHANNELS.lock().unwrap();
-    channels.insert(tab_id.clone(), cancel_tx);
-  }
+  // 获取工作区路径（必须由前端 tab 显式提供，禁止 watcher/cwd 静默兜底）
+  // P0-4: workspace_path 为空时直接返回结构化错误，避免跨工作区污染。
+  let workspace_path: PathBuf = if let Some(ws) = workspace_path.filter(|w| !w.trim().is_empty()) {
+    PathBuf::from(ws)
+  } else {
+    return Err(AppError::InvalidInput(
+      "workspace_path is required but was not provided. \
+       Please open a workspace before starting an AI conversation."
+        .to_string(),
+    ));
+  };
 
-  // ⚠️ 关键修复：创建取消标志并注册到全局映射
-  let cancel_flag = Arc::new(Mutex::new(false));
-  let cancel_flag_clone = cancel_flag.clone();
-  let cancel_flag_for_stream = cancel_flag.clone();
+  // 展开最后一条用户消息中的 `{{snippet:触发词}}`
+  let mut messages = messages;
+  if let Some(content) = messages
+    .iter_mut()
+    .rev()
+    .find(|m| m.role == "user")
+    .and_then(|m| m.content.as_mut())
   {
-    let mut flags = CANCEL_FLAGS.lock().unwrap();
-    flags.insert(tab_id.clone(), cancel_flag.clone());
-    eprintln!("✅ 初始流处理时注册取消标志: tab_id={}", tab_id);
+    let context = SnippetContext {
+      file_path: current_file.clone(),
+      selection: selected_text.clone(),
+      ..Default::default()
+    };
+    *content = snippets::expand_snippet_references(&workspace_path, content, &context);
   }
 
   // 根据 enable_tools 参数决定是否获取工具定义（默认为 true，保持向后兼容）
   let enable_tools = enable_tools.unwrap_or(true);
   let tool_definitions = if enable_tools {
-    Some(get_tool_definitions())
+    Some(get_tool_definitions(&workspace_path))
   } else {
     None
   };
 
-  // 获取工作区路径（必须由前端 tab 显式提供，禁止 watcher/cwd 静默兜底）
-  // P0-4: workspace_path 为空时直接返回结构化错误，避免跨工作区污染。
-  let workspace_path: PathBuf = if let Some(ws) = workspace_path.filter(|w| !w.trim().is_empty()) {
-    PathBuf::from(ws)
-  } else {
-    return Err(format!(
-      "workspace_path is required but was not provided. \
-       Please open a workspace before starting an AI conversation."
-    ));
-  };
-
   // 使用 ContextManager 统一构建多层提示词（方案A）
   let context_manager = ContextManager::new(model_config.max_tokens);
 
@@ -1359,7 +1504,9 @@ pub async fn ai_chat_stream(
   let template_reference_ids = extract_template_reference_ids(references.as_ref());
   if !template_reference_ids.is_empty() {
     if template_reference_ids.len() > 1 {
-      return Err("当前阶段仅支持单个工作流模板引用进入执行链".to_string());
+      return Err(AppError::InvalidInput(
+        "当前阶段仅支持单个工作流模板引用进入执行链".to_string(),
+      ));
     }
 
     let task_id = agent_task_id.clone().ok_or_else(|| {
@@ -1449,7 +1596,7 @@ pub async fn ai_chat_stream(
   let selection_start_offset = effective_selection_start_offset;
   let selection_end_block_id = effective_selection_end_block_id.clone();
   let selection_end_offset = effective_selection_end_offset;
-  eprintln!("📌 zero-search selection source=selection");
+  tracing::debug!("zero-search selection source=selection");
 
   // §7.1：先 clone 选区坐标，再移入 context_info（clone 供后续 spawn 闭包使用）
   let selection_start_block_id_for_spawn = selection_start_block_id.clone();
@@ -1487,7 +1634,7 @@ pub async fn ai_chat_stream(
       has_precise_anchor || (has_same_file_text_ref && user_msg_short);
 
     if suppress_task_summary {
-      eprintln!(
+      tracing::debug!(
         "[ai_chat_stream] agent_task_summary suppressed: precise_anchor={}, same_file_text_ref={}, short_msg={}",
         has_precise_anchor, has_same_file_text_ref, user_msg_short
       );
@@ -1561,6 +1708,8 @@ pub async fn ai_chat_stream(
 
   // ExtractionConfig: load once for this request (reads env vars)
   let extraction_cfg = crate::services::memory_service::ExtractionConfig::load();
+  // 工作区上下文注入设置：开关、条数与 token 预算
+  let injection_cfg = WorkspaceSettings::load_or_default(&workspace_path).context;
 
   // S-01: collect memory IDs already explicitly @-referenced by user (to deduplicate auto-injection)
   let explicitly_referenced_memory_ids: std::collections::HashSet<String> = final_references
@@ -1571,24 +1720,41 @@ pub async fn ai_chat_stream(
 
   let memory_context: Option<String> = if extraction_cfg.enabled
     && extraction_cfg.inject_enabled
+    && injection_cfg.inject_memories
     && last_user_message.chars().count() >= 5
   {
     let ws_str = workspace_path.to_string_lossy().to_string();
     match MemoryService::new(&workspace_path) {
       Ok(svc) => {
+        let memory_query = build_memory_query(
+          &last_user_message,
+          current_file.as_deref(),
+          selected_text.as_deref(),
+        );
         let params = SearchMemoriesParams {
-          query: build_memory_query(
-            &last_user_message,
-            current_file.as_deref(),
-            selected_text.as_deref(),
-          ),
+          query: memory_query.clone(),
           tab_id: Some(tab_id.clone()),
           workspace_path: Some(ws_str),
           scope: MemorySearchScope::All,
-          limit: Some(10),
+          limit: Some(injection_cfg.memory_top_k),
           entity_types: None,
+          tags: None,
+        };
+        // 工作区记忆 + 全局用户偏好合并检索
+        let searched = match svc.search_memories(params).await {
+          Ok(mut resp) => {
+            resp.items = merge_with_user_memories(
+              resp.items,
+              &memory_query,
+              injection_cfg.memory_top_k,
+              injection_cfg.inject_global_memories,
+            )
+            .await;
+            Ok(resp)
+          }
+          Err(e) => Err(e),
         };
-        match svc.search_memories(params).await {
+        match searched {
           Ok(resp) if !resp.items.is_empty() => {
             // S-01: exclude items already in user's explicit @-references
             let items_to_inject: Vec<_> = resp
@@ -1598,31 +1764,32 @@ pub async fn ai_chat_stream(
               .cloned()
               .collect();
             if items_to_inject.is_empty() {
-              eprintln!(
+              tracing::debug!(
                 "[memory] S-01: all items already explicitly referenced, skipping injection"
               );
               // still log usage for all retrieved items
-              let ids: Vec<String> = resp.items.iter().map(|r| r.item.id.clone()).collect();
+              let ids = workspace_memory_ids(&resp.items);
               let tab_id_log = tab_id.clone();
               tokio::spawn(async move {
                 if let Err(e) = svc.record_memory_usage(&ids, &tab_id_log).await {
-                  eprintln!("[memory] usage log failed: {:?}", e);
+                  tracing::warn!("[memory] usage log failed: {:?}", e);
                 }
               });
               None
             } else {
-              let formatted = format_memory_for_injection(&items_to_inject);
-              eprintln!(
+              let formatted =
+                format_memory_for_injection(&items_to_inject, injection_cfg.budget_chars());
+              tracing::debug!(
                 "[memory] MEMORY_INJECT_SUCCESS: injecting {} items (of {} retrieved)",
                 items_to_inject.len(),
                 resp.items.len()
               );
               // fire-and-forget usage log for all retrieved items
-              let ids: Vec<String> = resp.items.iter().map(|r| r.item.id.clone()).collect();
+              let ids = workspace_memory_ids(&resp.items);
               let tab_id_log = tab_id.clone();
               tokio::spawn(async move {
                 if let Err(e) = svc.record_memory_usage(&ids, &tab_id_log).await {
-                  eprintln!("[memory] usage log failed: {:?}", e);
+                  tracing::warn!("[memory] usage log failed: {:?}", e);
                 }
               });
               Some(formatted)
@@ -1630,7 +1797,7 @@ pub async fn ai_chat_stream(
           }
           Ok(_) => None,
           Err(e) => {
-            eprintln!("[memory] inject fallback: {:?}", e);
+            tracing::debug!("[memory] inject fallback: {:?}", e);
             None
           }
         }
@@ -1642,6 +1809,68 @@ pub async fn ai_chat_stream(
     None
   };
 
+  // 资料问答：检索到的段落编号注入，取代普通的工作区片段
+  let grounded = grounded.unwrap_or(false);
+  let memory_context = if grounded {
+    let ws = workspace_path.clone();
+    let question = last_user_message.clone();
+    let sources = tokio::task::spawn_blocking(move || {
+      grounding::retrieve_sources(
+        &ws,
+        &question,
+        GROUNDING_SOURCE_LIMIT,
+        GROUNDING_PASSAGE_CHARS,
+      )
+    })
+    .await
+    .map_err(|e| format!("检索工作区资料失败: {}", e))?
+    .unwrap_or_else(|e| {
+      tracing::warn!("[grounding] 检索失败，按普通对话回答: {}", e);
+      Vec::new()
+    });
+    let _ = app.emit(
+      "ai-chat-stream",
+      serde_json::json!({
+          "tab_id": tab_id.clone(),
+          "chunk": "",
+          "done": false,
+          "grounding": { "sources": &sources, "cited": [] },
+      }),
+    );
+    let context = (!sources.is_empty()).then(|| grounding::format_context(&sources));
+    grounding::begin(&tab_id, sources);
+    match (memory_context, context) {
+      (Some(mem), Some(context)) => Some(format!("{}\n\n{}", mem, context)),
+      (mem, context) => mem.or(context),
+    }
+  } else {
+    memory_context
+  };
+
+  // 工作区片段：使用记忆注入后剩余的预算
+  let memory_context = if !grounded
+    && injection_cfg.inject_workspace_snippets
+    && injection_cfg.workspace_snippet_limit > 0
+    && last_user_message.chars().count() >= 5
+  {
+    let used = memory_context.as_ref().map(|m| m.len()).unwrap_or(0);
+    let remaining = injection_cfg.budget_chars().saturating_sub(used);
+    let snippets = build_workspace_snippet_context(
+      &workspace_path,
+      &last_user_message,
+      current_file.as_deref(),
+      injection_cfg.workspace_snippet_limit,
+      remaining,
+    )
+    .await;
+    match (memory_context, snippets) {
+      (Some(mem), Some(snippets)) => Some(format!("{}\n\n{}", mem, snippets)),
+      (mem, snippets) => mem.or(snippets),
+    }
+  } else {
+    memory_context
+  };
+
   let explicit_knowledge_suppression = extract_explicit_knowledge_suppression(references.as_ref());
 
   let knowledge_probe_context = ContextInfo {
@@ -1691,7 +1920,7 @@ pub async fn ai_chat_stream(
     {
         Ok(Ok(summary)) => summary,
         Ok(Err(error)) => {
-            eprintln!("[knowledge] retrieval policy summary failed: {}", error);
+            tracing::warn!("[knowledge] retrieval policy summary failed: {}", error);
             crate::services::knowledge::repository::AutomaticRetrievalPolicySummary {
                 active_entry_count: 0,
                 policy_allowed_entry_count: 1,
@@ -1699,7 +1928,7 @@ pub async fn ai_chat_stream(
             }
         }
         Err(join_error) => {
-            eprintln!("[knowledge] retrieval policy summary join failed: {}", join_error);
+            tracing::warn!("[knowledge] retrieval policy summary join failed: {}", join_error);
             crate::services::knowledge::repository::AutomaticRetrievalPolicySummary {
                 active_entry_count: 0,
                 policy_allowed_entry_count: 1,
@@ -1722,7 +1951,7 @@ pub async fn ai_chat_stream(
 
   let knowledge_decision = context_manager
     .should_trigger_knowledge_retrieval(&knowledge_probe_context, &knowledge_retrieval_context);
-  eprintln!(
+  tracing::debug!(
         "[knowledge] retrieval decision: should_trigger={} reason={:?} explicit_refs={} granular_refs={} auto_candidates={} policy_blocked={}",
         knowledge_decision.should_trigger,
         knowledge_decision.reason,
@@ -1800,7 +2029,7 @@ pub async fn ai_chat_stream(
         knowledge_slices_for_event = deduped.clone();
 
         if !deduped.is_empty() {
-          eprintln!(
+          tracing::debug!(
             "[knowledge] auto retrieval injected {} slices",
             deduped.len()
           );
@@ -1812,7 +2041,7 @@ pub async fn ai_chat_stream(
               message: "自动检索未返回可注入的知识片段".to_string(),
             });
           }
-          eprintln!("[knowledge] auto retrieval returned no usable slices");
+          tracing::debug!("[knowledge] auto retrieval returned no usable slices");
           Vec::new()
         }
       }
@@ -1821,7 +2050,7 @@ pub async fn ai_chat_stream(
           code: "automatic_failed".to_string(),
           message: error.clone(),
         });
-        eprintln!("[knowledge] auto retrieval failed: {}", error);
+        tracing::warn!("[knowledge] auto retrieval failed: {}", error);
         Vec::new()
       }
       Ok(Err(join_error)) => {
@@ -1829,7 +2058,7 @@ pub async fn ai_chat_stream(
           code: "automatic_join_failed".to_string(),
           message: join_error.to_string(),
         });
-        eprintln!("[knowledge] auto retrieval join failed: {}", join_error);
+        tracing::warn!("[knowledge] auto retrieval join failed: {}", join_error);
         Vec::new()
       }
       Err(_) => {
@@ -1837,7 +2066,7 @@ pub async fn ai_chat_stream(
           code: "automatic_timeout".to_string(),
           message: "自动检索超时，已降级为无 augmentation".to_string(),
         });
-        eprintln!("[knowledge] auto retrieval timeout -> degrade to no augmentation");
+        tracing::debug!("[knowledge] auto retrieval timeout -> degrade to no augmentation");
         Vec::new()
       }
     }
@@ -1968,9 +2197,21 @@ pub async fn ai_chat_stream(
     }
   }
 
+  // 登记本标签页的对话流（同一标签页的旧流会被取消），取消通道与标志只属于这一个流
+  let stream_handle = streams.start(&tab_id);
+  let (cancel_tx, mut cancel_rx) = tokio::sync::oneshot::channel();
+  stream_handle.set_cancel_sender(cancel_tx);
+  let cancel_flag = stream_handle.cancel_flag();
+
   // 调用流式聊天（根据模式决定是否传递工具定义）
-  match provider
-    .chat_stream(
+  // 对话优先于 Inline Assist 与自动补全获得名额；名额按轮次获取，每轮响应结束即归还
+  let chat_slot = ChatSlot::default();
+  match service
+    .provider_chat_stream(
+      &chat_slot,
+      &tab_id,
+      actual_provider_name,
+      &provider,
       &enhanced_messages,
       &model_config,
       &mut cancel_rx,
@@ -1985,6 +2226,8 @@ pub async fn ai_chat_stream(
       let tool_service = ToolService::new();
       // 传递必要的参数以便工具调用后继续对话
       let provider_clone = provider.clone();
+      let provider_name_clone = actual_provider_name.to_string();
+      let service_clone = service.inner().clone();
       let model_config_clone = model_config.clone();
       let mut current_messages = enhanced_messages.clone();
       let tool_definitions_clone = tool_definitions.clone();
@@ -2003,8 +2246,7 @@ pub async fn ai_chat_stream(
       let cursor_block_id_clone = cursor_block_id_for_spawn;
       let cursor_offset_clone = cursor_offset_for_spawn;
 
-      // ⚠️ 关键修复：使用已注册的取消标志（已在上面创建并注册到 CANCEL_FLAGS）
-      // cancel_flag 已经在上面注册到 CANCEL_FLAGS 中，这里直接使用
+      // ⚠️ 关键修复：使用已登记的取消标志（已在上面通过 stream_handle 创建）
       let cancel_flag_clone = cancel_flag.clone();
       let cancel_flag_for_stream = cancel_flag.clone();
       // Phase 6: 捕获真实 task ID（优先使用前端传入的，fallback 到 shadow 代理键）
@@ -2016,14 +2258,18 @@ pub async fn ai_chat_stream(
       // 创建一个任务来监听取消信号
       let tab_id_for_cancel = tab_id.clone();
       tokio::spawn(async move {
-        // 等待取消信号
-        let _ = cancel_rx.await;
-        eprintln!("🛑 收到取消信号: tab_id={}", tab_id_for_cancel);
+        // 等待取消信号；通道被替换或关闭不算取消
+        if cancel_rx.await.is_err() {
+          return;
+        }
+        tracing::debug!("收到取消信号: tab_id={}", tab_id_for_cancel);
         let mut flag = cancel_flag_clone.lock().unwrap();
         *flag = true;
       });
 
-      tokio::spawn(async move {
+      let task_stream_handle = stream_handle.clone();
+      let stream_task = tokio::spawn(async move {
+        let stream_handle = task_stream_handle;
         let mut stream_ctx = StreamContext::default();
         // ⚠️ 关键修复：将 cancel_flag 传递到流处理任务中
         let cancel_flag = cancel_flag_for_stream;
@@ -2031,7 +2277,7 @@ pub async fn ai_chat_stream(
 
         // ⚠️ 关键修复：记录 tab_id 以便调试
         let tab_id_clone = tab_id.clone();
-        eprintln!("🚀 开始处理流式响应: tab_id={}", tab_id_clone);
+        tracing::info!("开始处理流式响应: tab_id={}", tab_id_clone);
 
         // 初始化管理器
         let mut conversation_manager = ConversationManager::new();
@@ -2046,6 +2292,11 @@ pub async fn ai_chat_stream(
         let message_id = format!("msg_{}", chrono::Utc::now().timestamp_millis());
         conversation_manager.start_conversation(&tab_id, message_id.clone());
         seed_shadow_artifacts(&tab_id, &workspace_path);
+        // 本轮工具产生的文件改动归入同一个撤销事务
+        agent_transaction::begin_transaction(
+          &workspace_path,
+          &format!("{}:{}", tab_id, message_id),
+        );
         // Phase 6: 跟踪本轮是否已产生候选，用于 review_ready 判断
         let mut candidate_emitted_this_session = false;
 
@@ -2055,12 +2306,18 @@ pub async fn ai_chat_stream(
         // 使用 HashMap 来累积多个工具调用的参数和结果
         use std::collections::HashMap;
         let mut tool_calls: HashMap<String, (String, String)> = HashMap::new(); // (id -> (name, arguments))
+
+        // 仍在生成中的工具调用参数（id -> 已到达 / 已推送），用于节流推送新增片段
+        let mut partial_tool_call_emitted: HashMap<String, PartialToolArguments> = HashMap::new();
         let mut tool_results: Vec<(String, String, crate::services::tool_service::ToolResult)> =
           Vec::new(); // 收集工具调用结果
                       // 与 tool_results 顺序一致：id, name，模型侧原始 arguments 字符串（用于 assistant tool_calls）
         let mut tool_call_specs: Vec<(String, String, String)> = Vec::new();
         let mut has_tool_calls = false; // 标记是否有工具调用
 
+        // 流式期间收集的完整工具调用，流结束后统一执行
+        let mut queued_tool_calls: Vec<QueuedToolCall> = Vec::new();
+
         // ⚠️ 关键修复：使用循环处理流，并在每次迭代前检查取消标志
         loop {
           // 使用 tokio::select! 同时等待流和取消信号
@@ -2091,7 +2348,7 @@ pub async fn ai_chat_stream(
               }
               _ = cancel_check => {
                   // 取消信号已触发
-                  eprintln!("🛑 通过 select! 检测到取消标志，停止流式处理: tab_id={}", tab_id);
+                  tracing::debug!("通过 select! 检测到取消标志，停止流式处理: tab_id={}", tab_id);
                   finalize_stream(&mut stream_ctx, StreamState::Cancelled);
                   emit_ai_chat_stream_done(
                       &app_handle,
@@ -2099,15 +2356,8 @@ pub async fn ai_chat_stream(
                       &stream_ctx,
                       Some("用户取消了请求"),
                   );
-                  // ⚠️ 关键修复：清理取消通道和标志
-                  {
-                      let mut channels = CANCEL_CHANNELS.lock().unwrap();
-                      channels.remove(&tab_id);
-                  }
-                  {
-                      let mut flags = CANCEL_FLAGS.lock().unwrap();
-                      flags.remove(&tab_id);
-                  }
+                  // ⚠️ 关键修复：清理本流的登记
+                  stream_handle.finish();
                   return;
               }
           };
@@ -2134,8 +2384,10 @@ pub async fn ai_chat_stream(
                         "done": false,
                     });
                     if let Err(e) = app_handle.emit("ai-chat-stream", payload) {
-                      eprintln!("发送事件失败: {}", e);
+                      tracing::warn!("发送事件失败: {}", e);
                     }
+                    grounding::record_text(&tab_id, &text_to_send);
+                    stream_handle.push_text(&text_to_send);
                   }
                 }
                 ChatChunk::ToolCall {
@@ -2144,30 +2396,39 @@ pub async fn ai_chat_stream(
                   arguments,
                   is_complete,
                 } => {
-                  // 参考 void 的实现：只处理完整的工具调用
-                  // 不完整的工具调用在 deepseek.rs 中已经被过滤，不会到达这里
+                  // 只执行完整的工具调用；不完整的参数仅推送给前端做实时预览
                   if !is_complete {
-                    eprintln!("⚠️ 收到不完整的工具调用，跳过: id={}, name={}", id, name);
+                    emit_partial_tool_call(
+                      &app_handle,
+                      &tab_id,
+                      &id,
+                      &name,
+                      &arguments,
+                      &mut partial_tool_call_emitted,
+                    );
                     // 保存状态，等待完成
                     let entry = tool_calls
                       .entry(id.clone())
                       .or_insert_with(|| (name.clone(), String::new()));
-                    entry.1 = arguments.clone();
+                    entry.1.push_str(&arguments);
                     continue;
                   }
+                  partial_tool_call_emitted.remove(&id);
 
-                  eprintln!("🔧 收到完整的工具调用 chunk: id={}, name={}, arguments_len={}, arguments_preview={}",
+                  tracing::debug!("收到完整的工具调用 chunk: id={}, name={}, arguments_len={}, arguments_preview={}",
                                         id, name, arguments.len(),
                                         safe_truncate(&arguments, 100));
 
-                  eprintln!(
-                    "✅ 工具调用完成，开始处理: id={}, name={}, arguments={}",
-                    id, name, arguments
+                  tracing::info!(
+                    "工具调用完成，开始处理: id={}, name={}, arguments={}",
+                    id,
+                    name,
+                    arguments
                   );
 
                   // 检测工具调用循环
                   if loop_detector.detect_tool_call_loop(&name, &arguments) {
-                    eprintln!("⚠️ 检测到工具调用循环，跳过: {}", name);
+                    tracing::warn!("检测到工具调用循环，跳过: {}", name);
                     continue;
                   }
 
@@ -2197,8 +2458,8 @@ pub async fn ai_chat_stream(
                       &workspace_path,
                     );
                     if skip {
-                      eprintln!(
-                                                "📝 跳过 edit_current_editor_document 自动注入: primary_edit_target={:?} 与 current_file 不一致",
+                      tracing::debug!(
+                                                "跳过 edit_current_editor_document 自动注入: primary_edit_target={:?} 与 current_file 不一致",
                                                 primary_edit_target
                                             );
                     } else {
@@ -2246,7 +2507,7 @@ pub async fn ai_chat_stream(
                       if let Some(co) = cursor_offset_clone {
                         parsed_arguments["cursor_offset"] = serde_json::json!(co);
                       }
-                      eprintln!("📝 已增强 edit_current_editor_document 参数: current_file={:?}, current_content_len={}",
+                      tracing::debug!("已增强 edit_current_editor_document 参数: current_file={:?}, current_content_len={}",
                                             current_file.as_ref().map(|s| s.as_str()),
                                             current_editor_content.as_ref().map(|s| s.len()).unwrap_or(0));
                       merge_editor_snapshot_ipc_for_tool(
@@ -2272,245 +2533,29 @@ pub async fn ai_chat_stream(
                       },
                   });
                   if let Err(e) = app_handle.emit("ai-chat-stream", payload) {
-                    eprintln!("发送工具调用事件失败: {}", e);
+                    tracing::warn!("发送工具调用事件失败: {}", e);
                   }
 
                   // 保存解析后的参数，用于后续发送结果事件
                   let parsed_args_for_result = parsed_arguments.clone();
 
-                  // 执行工具调用
-                  let tool_call = ToolCall {
-                    id: id.clone(),
-                    name: name.clone(),
-                    arguments: parsed_arguments,
-                  };
-
-                  // ⚠️ 关键修复：在执行工具调用前检查取消标志
-                  {
-                    let flag = cancel_flag.lock().unwrap();
-                    if *flag {
-                      eprintln!(
-                        "🛑 工具调用执行前检测到取消标志，停止执行: tab_id={}",
-                        tab_id
-                      );
-                      finalize_stream(&mut stream_ctx, StreamState::Cancelled);
-                      emit_ai_chat_stream_done(
-                        &app_handle,
-                        &tab_id,
-                        &stream_ctx,
-                        Some("用户取消了请求"),
-                      );
-                      // ⚠️ 关键修复：清理取消通道和标志
-                      {
-                        let mut channels = CANCEL_CHANNELS.lock().unwrap();
-                        channels.remove(&tab_id);
-                      }
-                      {
-                        let mut flags = CANCEL_FLAGS.lock().unwrap();
-                        flags.remove(&tab_id);
-                      }
-                      return;
-                    }
-                  }
-
-                  eprintln!("🚀 开始执行工具调用: {}", name);
-
-                  // 更新工具调用状态：执行中
-                  conversation_manager.update_tool_call_status(
-                    &tab_id,
-                    crate::services::conversation_manager::ToolCallStatus::Executing,
-                  );
-
-                  // 使用 ToolCallHandler 执行工具调用（带重试机制）
-                  let (raw_tool_result, _retry_count) = tool_call_handler
-                    .execute_tool_with_retry(
-                      &tool_call,
-                      &workspace_path,
-                      3, // max_retries
-                    )
-                    .await;
-                  let awaiting_confirmation = tool_result_awaits_confirmation(&raw_tool_result);
-                  let skip_continue = !raw_tool_result.success || awaiting_confirmation;
-                  let tool_result = with_execution_observability(
-                    raw_tool_result,
-                    &name,
-                    Some(&parsed_args_for_result),
-                    skip_continue,
-                  );
-                  if !awaiting_confirmation {
-                    maybe_sync_workflow_execution_from_tool_result(
-                      &app_handle,
-                      &tab_id,
-                      &effective_task_id,
-                      &workspace_path,
-                      &name,
-                      &tool_result,
-                    );
-                  }
-
-                  // ⚠️ 关键修复：在工具调用执行后检查取消标志
-                  {
-                    let flag = cancel_flag.lock().unwrap();
-                    if *flag {
-                      eprintln!(
-                        "🛑 工具调用执行后检测到取消标志，停止处理: tab_id={}",
-                        tab_id
-                      );
-                      finalize_stream(&mut stream_ctx, StreamState::Cancelled);
-                      emit_ai_chat_stream_done(
-                        &app_handle,
-                        &tab_id,
-                        &stream_ctx,
-                        Some("用户取消了请求"),
-                      );
-                      // ⚠️ 关键修复：清理取消通道和标志
-                      {
-                        let mut channels = CANCEL_CHANNELS.lock().unwrap();
-                        channels.remove(&tab_id);
-                      }
-                      {
-                        let mut flags = CANCEL_FLAGS.lock().unwrap();
-                        flags.remove(&tab_id);
-                      }
-                      return;
-                    }
-                  }
-
-                  // 更新工具调用状态：完成或失败
-                  let tool_status = if awaiting_confirmation {
-                    crate::services::conversation_manager::ToolCallStatus::Pending
-                  } else if tool_result.success {
-                    crate::services::conversation_manager::ToolCallStatus::Completed
-                  } else {
-                    crate::services::conversation_manager::ToolCallStatus::Failed
-                  };
-                  conversation_manager.update_tool_call_status(&tab_id, tool_status);
-
-                  if awaiting_confirmation {
-                    let payload = serde_json::json!({
-                        "tab_id": tab_id,
-                        "chunk": "",
-                        "done": false,
-                        "tool_call": {
-                            "id": id,
-                            "name": name,
-                            "arguments": parsed_args_for_result,
-                            "result": tool_result,
-                            "status": "pending",
-                        },
-                    });
-                    if let Err(e) = app_handle.emit("ai-chat-stream", payload) {
-                      eprintln!("发送确认门工具调用失败: {}", e);
-                    }
-                  } else if tool_result.success {
-                    eprintln!("✅ 工具执行成功: {}", name);
-
-                    // 如果是文件操作工具，且执行成功，手动触发文件树刷新事件
-                    let file_operation_tools = [
-                      "create_file",
-                      "create_folder",
-                      "delete_file",
-                      "rename_file",
-                      "move_file",
-                      "update_file",
-                    ];
-
-                    if file_operation_tools.contains(&name.as_str()) && tool_result.success {
-                      let workspace_path_str = workspace_path.to_string_lossy().to_string();
-                      eprintln!(
-                        "🔄 文件操作成功，触发文件树刷新: workspace={}",
-                        workspace_path_str
-                      );
-                      if let Err(e) = app_handle.emit("file-tree-changed", workspace_path_str) {
-                        eprintln!("⚠️ 触发文件树刷新事件失败: {}", e);
-                      }
-                    }
-
-                    // 保存工具调用结果，用于后续继续对话
-                    tool_call_specs.push((id.clone(), name.clone(), arguments.clone()));
-                    tool_results.push((id.clone(), name.clone(), tool_result.clone()));
-
-                    // 将工具结果添加到消息中，继续对话
-                    let tool_result_message = format!(
-                      "\n\n[工具调用: {}]\n结果: {}",
-                      name,
-                      serde_json::to_string_pretty(&tool_result).unwrap_or_default()
-                    );
-
-                    // 发送工具调用结果到前端（使用解析后的 arguments）
-                    let payload = serde_json::json!({
-                        "tab_id": tab_id,
-                        "chunk": tool_result_message,
-                        "done": false,
-                        "tool_call": {
-                            "id": id,
-                            "name": name,
-                            "arguments": parsed_args_for_result, // ✅ 使用解析后的 JSON 对象
-                            "result": tool_result,
-                            "status": "completed",
-                        },
-                    });
-                    if let Err(e) = app_handle.emit("ai-chat-stream", payload) {
-                      eprintln!("发送工具调用结果失败: {}", e);
-                    }
-                  } else {
-                    // 工具执行失败（工具层面的失败，如文件不存在）
-                    eprintln!(
-                      "⚠️ 工具执行失败: {} - {}",
-                      name,
-                      tool_result
-                        .error
-                        .as_ref()
-                        .unwrap_or(&"未知错误".to_string())
-                    );
-
-                    // 保存工具调用结果，用于后续继续对话
-                    tool_call_specs.push((id.clone(), name.clone(), arguments.clone()));
-                    tool_results.push((id.clone(), name.clone(), tool_result.clone()));
-
-                    // 工具执行失败
-                    let error_message = format!(
-                      "\n\n[工具调用失败: {}]\n错误: {}",
-                      name,
-                      tool_result
-                        .error
-                        .as_ref()
-                        .unwrap_or(&"未知错误".to_string())
-                    );
-                    let payload = serde_json::json!({
-                        "tab_id": tab_id,
-                        "chunk": error_message,
-                        "done": false,
-                        "tool_call": {
-                            "id": id,
-                            "name": name,
-                            "arguments": parsed_args_for_result, // ✅ 使用解析后的 JSON 对象
-                            "result": tool_result,
-                            "status": "failed",
-                        },
-                    });
-                    if let Err(e) = app_handle.emit("ai-chat-stream", payload) {
-                      eprintln!("发送工具调用错误失败: {}", e);
-                    }
-                  }
+                  // 收集完整的工具调用，流结束后统一并发执行，结果按调用顺序处理
+                  queued_tool_calls.push(QueuedToolCall {
+                    tool_call: ToolCall::from_model(id.clone(), name.clone(), parsed_arguments),
+                    arguments: arguments.clone(),
+                    parsed_args_for_result,
+                  });
 
                   // 移除已完成的工具调用
                   tool_calls.remove(&id);
-                  eprintln!("✅ 工具调用处理完成，继续处理流式响应");
+                  tracing::info!("工具调用已加入执行队列，继续处理流式响应");
                 }
               }
             }
             Err(e) => {
               finalize_stream(&mut stream_ctx, StreamState::Completed);
               emit_ai_chat_stream_done(&app_handle, &tab_id, &stream_ctx, Some(&e.to_string()));
-              {
-                let mut channels = CANCEL_CHANNELS.lock().unwrap();
-                channels.remove(&tab_id);
-              }
-              {
-                let mut flags = CANCEL_FLAGS.lock().unwrap();
-                flags.remove(&tab_id);
-              }
+              stream_handle.finish();
               return;
             }
           }
@@ -2520,50 +2565,243 @@ pub async fn ai_chat_stream(
         {
           let flag = cancel_flag.lock().unwrap();
           if *flag {
-            eprintln!("🛑 流结束后检测到取消标志，停止处理: tab_id={}", tab_id);
+            tracing::debug!("流结束后检测到取消标志，停止处理: tab_id={}", tab_id);
             finalize_stream(&mut stream_ctx, StreamState::Cancelled);
             emit_ai_chat_stream_done(&app_handle, &tab_id, &stream_ctx, Some("用户取消了请求"));
-            // ⚠️ 关键修复：清理取消通道和标志
-            {
-              let mut channels = CANCEL_CHANNELS.lock().unwrap();
-              channels.remove(&tab_id);
-            }
-            {
-              let mut flags = CANCEL_FLAGS.lock().unwrap();
-              flags.remove(&tab_id);
-            }
+            // ⚠️ 关键修复：清理本流的登记
+            stream_handle.finish();
             return;
           }
         }
 
-        // 第一段流正常结束（非取消）：进入 Completed，允许后续 assistant 对话历史写入
-        if stream_ctx.state == StreamState::Streaming {
-          finalize_stream(&mut stream_ctx, StreamState::Completed);
-        }
-
-        // 流结束时，检查是否有未完成的工具调用
-        if !tool_calls.is_empty() {
-          eprintln!("🔧 流结束，发现 {} 个未完成的工具调用", tool_calls.len());
-          has_tool_calls = true; // 标记有工具调用
-          for (id, (name, arguments)) in tool_calls.iter() {
-            eprintln!(
-              "🔧 流结束，处理未完成的工具调用: id={}, name={}, arguments_len={}",
-              id,
-              name,
-              arguments.len()
+        // 并发执行本轮收集到的工具调用（相同路径串行），结果按调用顺序处理
+        if !queued_tool_calls.is_empty() {
+          let batch: Vec<ToolCall> = queued_tool_calls
+            .iter()
+            .map(|queued| queued.tool_call.clone())
+            .collect();
+          tracing::info!("开始执行 {} 个工具调用", batch.len());
+          // 更新工具调用状态：执行中
+          for tool_call in &batch {
+            conversation_manager.start_tool_call(
+              &tab_id,
+              message_id.clone(),
+              tool_call.id.clone(),
+              tool_call.name.clone(),
+            );
+            conversation_manager.update_tool_call_status(
+              &tab_id,
+              crate::services::conversation_manager::ToolCallStatus::Executing,
+            );
+          }
+          let batch_results = tool_call_handler
+            .execute_tools_concurrently(
+              &batch,
+              &workspace_path,
+              3, // max_retries
+              MAX_PARALLEL_TOOL_CALLS,
+              &cancel_flag,
+            )
+            .await;
+
+          for (queued, (raw_tool_result, _retry_count)) in
+            queued_tool_calls.drain(..).zip(batch_results)
+          {
+            let QueuedToolCall {
+              tool_call: ToolCall { id, name, .. },
+              arguments,
+              parsed_args_for_result,
+            } = queued;
+            conversation_manager.start_tool_call(
+              &tab_id,
+              message_id.clone(),
+              id.clone(),
+              name.clone(),
+            );
+            let awaiting_confirmation = tool_result_awaits_confirmation(&raw_tool_result);
+            let skip_continue = !raw_tool_result.success || awaiting_confirmation;
+            let tool_result = with_execution_observability(
+              raw_tool_result,
+              &name,
+              Some(&parsed_args_for_result),
+              skip_continue,
+            );
+            if !awaiting_confirmation {
+              maybe_sync_workflow_execution_from_tool_result(
+                &app_handle,
+                &tab_id,
+                &effective_task_id,
+                &workspace_path,
+                &name,
+                &tool_result,
+              );
+            }
+
+            // ⚠️ 关键修复：在工具调用执行后检查取消标志
+            {
+              let flag = cancel_flag.lock().unwrap();
+              if *flag {
+                tracing::debug!("工具调用执行后检测到取消标志，停止处理: tab_id={}", tab_id);
+                finalize_stream(&mut stream_ctx, StreamState::Cancelled);
+                emit_ai_chat_stream_done(&app_handle, &tab_id, &stream_ctx, Some("用户取消了请求"));
+                // ⚠️ 关键修复：清理本流的登记
+                stream_handle.finish();
+                return;
+              }
+            }
+
+            // 更新工具调用状态：完成或失败
+            let tool_status = if awaiting_confirmation {
+              crate::services::conversation_manager::ToolCallStatus::Pending
+            } else if tool_result.success {
+              crate::services::conversation_manager::ToolCallStatus::Completed
+            } else {
+              crate::services::conversation_manager::ToolCallStatus::Failed
+            };
+            conversation_manager.update_tool_call_status(&tab_id, tool_status);
+
+            if awaiting_confirmation {
+              let payload = serde_json::json!({
+                  "tab_id": tab_id,
+                  "chunk": "",
+                  "done": false,
+                  "tool_call": {
+                      "id": id,
+                      "name": name,
+                      "arguments": parsed_args_for_result,
+                      "result": tool_result,
+                      "status": "pending",
+                  },
+              });
+              if let Err(e) = app_handle.emit("ai-chat-stream", payload) {
+                tracing::warn!("发送确认门工具调用失败: {}", e);
+              }
+            } else if tool_result.success {
+              tracing::info!("工具执行成功: {}", name);
+
+              // 如果是文件操作工具，且执行成功，手动触发文件树刷新事件
+              let file_operation_tools = [
+                "create_file",
+                "create_folder",
+                "delete_file",
+                "rename_file",
+                "move_file",
+                "copy_file",
+                "create_docx_from_markdown",
+                "update_file",
+                "apply_patch",
+              ];
+
+              if file_operation_tools.contains(&name.as_str()) && tool_result.success {
+                let workspace_path_str = workspace_path.to_string_lossy().to_string();
+                tracing::debug!(
+                  "文件操作成功，触发文件树刷新: workspace={}",
+                  workspace_path_str
+                );
+                if let Err(e) = app_handle.emit("file-tree-changed", workspace_path_str) {
+                  tracing::warn!("触发文件树刷新事件失败: {}", e);
+                }
+              }
+
+              // 保存工具调用结果，用于后续继续对话
+              tool_call_specs.push((id.clone(), name.clone(), arguments.clone()));
+              tool_results.push((id.clone(), name.clone(), tool_result.clone()));
+
+              // 将工具结果添加到消息中，继续对话
+              let tool_result_message = format!(
+                "\n\n[工具调用: {}]\n结果: {}",
+                name,
+                serde_json::to_string_pretty(&tool_result).unwrap_or_default()
+              );
+
+              // 发送工具调用结果到前端（使用解析后的 arguments）
+              let payload = serde_json::json!({
+                  "tab_id": tab_id,
+                  "chunk": tool_result_message,
+                  "done": false,
+                  "tool_call": {
+                      "id": id,
+                      "name": name,
+                      "arguments": parsed_args_for_result, // ✅ 使用解析后的 JSON 对象
+                      "result": tool_result,
+                      "status": "completed",
+                  },
+              });
+              if let Err(e) = app_handle.emit("ai-chat-stream", payload) {
+                tracing::warn!("发送工具调用结果失败: {}", e);
+              }
+            } else {
+              // 工具执行失败（工具层面的失败，如文件不存在）
+              tracing::warn!(
+                "工具执行失败: {} - {}",
+                name,
+                tool_result
+                  .error
+                  .as_ref()
+                  .unwrap_or(&"未知错误".to_string())
+              );
+
+              // 保存工具调用结果，用于后续继续对话
+              tool_call_specs.push((id.clone(), name.clone(), arguments.clone()));
+              tool_results.push((id.clone(), name.clone(), tool_result.clone()));
+
+              // 工具执行失败
+              let error_message = format!(
+                "\n\n[工具调用失败: {}]\n错误: {}",
+                name,
+                tool_result
+                  .error
+                  .as_ref()
+                  .unwrap_or(&"未知错误".to_string())
+              );
+              let payload = serde_json::json!({
+                  "tab_id": tab_id,
+                  "chunk": error_message,
+                  "done": false,
+                  "tool_call": {
+                      "id": id,
+                      "name": name,
+                      "arguments": parsed_args_for_result, // ✅ 使用解析后的 JSON 对象
+                      "result": tool_result,
+                      "status": "failed",
+                  },
+              });
+              if let Err(e) = app_handle.emit("ai-chat-stream", payload) {
+                tracing::warn!("发送工具调用错误失败: {}", e);
+              }
+            }
+          }
+        }
+
+        // 第一段流正常结束（非取消）：进入 Completed，允许后续 assistant 对话历史写入
+        if stream_ctx.state == StreamState::Streaming {
+          finalize_stream(&mut stream_ctx, StreamState::Completed);
+        }
+
+        // 流结束时，检查是否有未完成的工具调用
+        if !tool_calls.is_empty() {
+          tracing::debug!("流结束，发现 {} 个未完成的工具调用", tool_calls.len());
+          has_tool_calls = true; // 标记有工具调用
+          for (id, (name, arguments)) in tool_calls.iter() {
+            tracing::debug!(
+              "流结束，处理未完成的工具调用: id={}, name={}, arguments_len={}",
+              id,
+              name,
+              arguments.len()
             );
-            eprintln!("🔧 工具调用 arguments 内容: {}", arguments);
+            tracing::debug!("工具调用 arguments 内容: {}", arguments);
 
             // 解析工具调用参数（简化修复逻辑）
             let mut parsed_arguments = match serde_json::from_str::<serde_json::Value>(arguments) {
               Ok(args) => {
-                eprintln!("✅ 成功解析工具调用参数");
+                tracing::info!("成功解析工具调用参数");
                 args
               }
               Err(e) => {
-                eprintln!(
-                  "⚠️ 工具调用参数 JSON 解析失败: {}, arguments: {}",
-                  e, arguments
+                tracing::warn!(
+                  "工具调用参数 JSON 解析失败: {}, arguments: {}",
+                  e,
+                  arguments
                 );
                 // 简化修复：只处理缺少闭合括号的情况
                 let fixed_json = arguments.trim();
@@ -2576,16 +2814,16 @@ pub async fn ai_chat_stream(
                   fixed.push('}');
                   match serde_json::from_str::<serde_json::Value>(&fixed) {
                     Ok(args) => {
-                      eprintln!("✅ 修复后成功解析工具调用参数");
+                      tracing::info!("修复后成功解析工具调用参数");
                       args
                     }
                     Err(e2) => {
-                      eprintln!("❌ 修复后仍然解析失败: {}", e2);
+                      tracing::error!("修复后仍然解析失败: {}", e2);
                       serde_json::json!({})
                     }
                   }
                 } else {
-                  eprintln!("❌ 无法修复 JSON，使用空对象");
+                  tracing::error!("无法修复 JSON，使用空对象");
                   serde_json::json!({})
                 }
               }
@@ -2660,40 +2898,29 @@ pub async fn ai_chat_stream(
                 },
             });
             if let Err(e) = app_handle.emit("ai-chat-stream", payload) {
-              eprintln!("发送工具调用事件失败: {}", e);
+              tracing::warn!("发送工具调用事件失败: {}", e);
             }
 
             // ⚠️ 关键修复：在执行工具调用前检查取消标志
             {
               let flag = cancel_flag.lock().unwrap();
               if *flag {
-                eprintln!(
-                  "🛑 流结束后的工具调用执行前检测到取消标志，停止处理: tab_id={}",
+                tracing::debug!(
+                  "流结束后的工具调用执行前检测到取消标志，停止处理: tab_id={}",
                   tab_id
                 );
                 finalize_stream(&mut stream_ctx, StreamState::Cancelled);
                 emit_ai_chat_stream_done(&app_handle, &tab_id, &stream_ctx, Some("用户取消了请求"));
-                // ⚠️ 关键修复：清理取消通道和标志
-                {
-                  let mut channels = CANCEL_CHANNELS.lock().unwrap();
-                  channels.remove(&tab_id);
-                }
-                {
-                  let mut flags = CANCEL_FLAGS.lock().unwrap();
-                  flags.remove(&tab_id);
-                }
+                // ⚠️ 关键修复：清理本流的登记
+                stream_handle.finish();
                 return;
               }
             }
 
             // 执行累积的工具调用
-            let tool_call = ToolCall {
-              id: id.clone(),
-              name: name.clone(),
-              arguments: parsed_arguments,
-            };
+            let tool_call = ToolCall::from_model(id.clone(), name.clone(), parsed_arguments);
 
-            eprintln!("🚀 开始执行工具调用: {}", name);
+            tracing::info!("开始执行工具调用: {}", name);
 
             // 执行工具调用，带重试机制
             let mut tool_result = None;
@@ -2706,7 +2933,7 @@ pub async fn ai_chat_stream(
                   if result.success {
                     tool_result = Some(result);
                     if attempt > 1 {
-                      eprintln!("✅ 工具执行成功（第 {} 次尝试）: {}", attempt, name);
+                      tracing::info!("工具执行成功（第 {} 次尝试）: {}", attempt, name);
                     }
                     break;
                   } else {
@@ -2717,13 +2944,13 @@ pub async fn ai_chat_stream(
                 }
                 Err(e) => {
                   last_error = Some(e.clone());
-                  eprintln!("⚠️ 工具执行失败（第 {} 次尝试）: {} - {}", attempt, name, e);
+                  tracing::warn!("工具执行失败（第 {} 次尝试）: {} - {}", attempt, name, e);
                   if attempt < max_retries {
                     // 等待一小段时间后重试（指数退避）
                     let delay_ms = 100 * attempt;
                     tokio::time::sleep(tokio::time::Duration::from_millis(delay_ms)).await;
-                    eprintln!(
-                      "🔄 重试工具调用: {} (尝试 {}/{})",
+                    tracing::debug!(
+                      "重试工具调用: {} (尝试 {}/{})",
                       name,
                       attempt + 1,
                       max_retries
@@ -2738,9 +2965,11 @@ pub async fn ai_chat_stream(
               None => {
                 // 所有重试都失败了
                 let error_msg = last_error.unwrap_or_else(|| "未知错误".to_string());
-                eprintln!(
-                  "❌ 工具执行最终失败（已重试 {} 次）: {} - {}",
-                  max_retries, name, error_msg
+                tracing::error!(
+                  "工具执行最终失败（已重试 {} 次）: {} - {}",
+                  max_retries,
+                  name,
+                  error_msg
                 );
                 crate::services::tool_service::ToolResult {
                   success: false,
@@ -2789,10 +3018,10 @@ pub async fn ai_chat_stream(
                   },
               });
               if let Err(e) = app_handle.emit("ai-chat-stream", payload) {
-                eprintln!("发送确认门工具调用失败: {}", e);
+                tracing::warn!("发送确认门工具调用失败: {}", e);
               }
             } else if tool_result.success {
-              eprintln!("✅ 工具执行成功: {}", name);
+              tracing::info!("工具执行成功: {}", name);
 
               // 如果是文件操作工具，且执行成功，手动触发文件树刷新事件
               let file_operation_tools = [
@@ -2801,17 +3030,20 @@ pub async fn ai_chat_stream(
                 "delete_file",
                 "rename_file",
                 "move_file",
+                "copy_file",
+                "create_docx_from_markdown",
                 "update_file",
+                "apply_patch",
               ];
 
               if file_operation_tools.contains(&name.as_str()) && tool_result.success {
                 let workspace_path_str = workspace_path.to_string_lossy().to_string();
-                eprintln!(
-                  "🔄 文件操作成功，触发文件树刷新: workspace={}",
+                tracing::debug!(
+                  "文件操作成功，触发文件树刷新: workspace={}",
                   workspace_path_str
                 );
                 if let Err(e) = app_handle.emit("file-tree-changed", workspace_path_str) {
-                  eprintln!("⚠️ 触发文件树刷新事件失败: {}", e);
+                  tracing::warn!("触发文件树刷新事件失败: {}", e);
                 }
               }
 
@@ -2840,12 +3072,12 @@ pub async fn ai_chat_stream(
                   },
               });
               if let Err(e) = app_handle.emit("ai-chat-stream", payload) {
-                eprintln!("发送工具调用结果失败: {}", e);
+                tracing::warn!("发送工具调用结果失败: {}", e);
               }
             } else {
               // 工具执行失败（工具层面的失败，如文件不存在）
-              eprintln!(
-                "⚠️ 工具执行失败: {} - {}",
+              tracing::warn!(
+                "工具执行失败: {} - {}",
                 name,
                 tool_result
                   .error
@@ -2879,7 +3111,7 @@ pub async fn ai_chat_stream(
                   },
               });
               if let Err(e) = app_handle.emit("ai-chat-stream", payload) {
-                eprintln!("发送工具调用错误失败: {}", e);
+                tracing::warn!("发送工具调用错误失败: {}", e);
               }
             }
           }
@@ -2887,8 +3119,8 @@ pub async fn ai_chat_stream(
 
         // 如果有工具调用，需要继续对话
         if has_tool_calls && !tool_results.is_empty() {
-          eprintln!(
-            "🔄 检测到工具调用，准备继续对话: 工具调用数量={}",
+          tracing::debug!(
+            "检测到工具调用，准备继续对话: 工具调用数量={}",
             tool_results.len()
           );
           if tool_results_emit_candidate(&tool_results) {
@@ -3083,7 +3315,7 @@ pub async fn ai_chat_stream(
 
           // 如果任务未完成，添加调试日志
           if task_incomplete {
-            eprintln!("⚠️ 任务未完成，强制要求 AI 继续：{}", task_progress);
+            tracing::warn!("任务未完成，强制要求 AI 继续：{}", task_progress);
           }
 
           // 每条工具结果一条 role=tool；随后单独一条 user 承载 [NEXT_ACTION]（不再拼接 [TOOL_RESULTS]）
@@ -3134,7 +3366,7 @@ pub async fn ai_chat_stream(
             },
           );
 
-          eprintln!("📝 构建新的消息列表，消息数量: {}", current_messages.len());
+          tracing::debug!("构建新的消息列表，消息数量: {}", current_messages.len());
 
           // 估算消息历史长度，如果过长则截断（防止Token超限）
           // 简单估算：1 token ≈ 4 字符，保留约80%的token预算给响应
@@ -3143,43 +3375,26 @@ pub async fn ai_chat_stream(
           let max_context_tokens = (model_config_clone.max_tokens * 10).min(30000); // 假设上下文窗口为32K，保留一些给响应
 
           if estimated_tokens > max_context_tokens {
-            eprintln!(
-              "⚠️ 消息历史过长（估算 {} tokens），截断以预防Token超限",
+            tracing::warn!(
+              "消息历史过长（估算 {} tokens），截断以预防Token超限",
               estimated_tokens
             );
             // 保留系统消息（第一条）和最后10条消息
             if current_messages.len() > 11 {
               ContextManager::default()
                 .truncate_with_strategy(&mut current_messages, TruncationStrategy::KeepRecent(10));
-              eprintln!("📝 截断后消息数量: {}", current_messages.len());
+              tracing::debug!("截断后消息数量: {}", current_messages.len());
             }
           }
 
-          // ⚠️ 关键修复：在继续对话前检查取消标志（使用全局标志映射）
+          // ⚠️ 关键修复：在继续对话前检查本流是否已被取消
           {
-            let should_cancel = {
-              let flags = CANCEL_FLAGS.lock().unwrap();
-              if let Some(flag) = flags.get(&tab_id) {
-                let flag_guard = flag.lock().unwrap();
-                *flag_guard
-              } else {
-                false
-              }
-            }; // 这里 flags 和 flag_guard 都会被释放
-
-            if should_cancel {
-              eprintln!("🛑 继续对话前检测到取消标志，停止处理: tab_id={}", tab_id);
+            if stream_handle.is_cancelled() {
+              tracing::debug!("继续对话前检测到取消标志，停止处理: tab_id={}", tab_id);
               finalize_stream(&mut stream_ctx, StreamState::Cancelled);
               emit_ai_chat_stream_done(&app_handle, &tab_id, &stream_ctx, Some("用户取消了请求"));
-              // ⚠️ 关键修复：清理取消通道和标志
-              {
-                let mut channels = CANCEL_CHANNELS.lock().unwrap();
-                channels.remove(&tab_id);
-              }
-              {
-                let mut flags = CANCEL_FLAGS.lock().unwrap();
-                flags.remove(&tab_id);
-              }
+              // ⚠️ 关键修复：清理本流的登记
+              stream_handle.finish();
               return;
             }
           }
@@ -3188,19 +3403,14 @@ pub async fn ai_chat_stream(
           // ⚠️ 关键修复：为继续对话创建新的取消通道并注册
           let (new_cancel_tx, mut new_cancel_rx) = tokio::sync::oneshot::channel();
           {
-            let mut channels = CANCEL_CHANNELS.lock().unwrap();
-            channels.insert(tab_id.clone(), new_cancel_tx);
-            eprintln!("✅ 继续对话时注册新的取消通道: tab_id={}", tab_id);
+            stream_handle.set_cancel_sender(new_cancel_tx);
+            tracing::info!("继续对话时注册新的取消通道: tab_id={}", tab_id);
           }
 
           // ⚠️ 关键修复：为继续对话创建新的取消标志并注册到全局映射
-          let continue_cancel_flag = Arc::new(Mutex::new(false));
+          let continue_cancel_flag = stream_handle.renew_cancel_flag();
           let continue_cancel_flag_for_stream = continue_cancel_flag.clone();
-          {
-            let mut flags = CANCEL_FLAGS.lock().unwrap();
-            flags.insert(tab_id.clone(), continue_cancel_flag.clone());
-            eprintln!("✅ 继续对话时注册取消标志: tab_id={}", tab_id);
-          }
+          tracing::info!("继续对话时注册取消标志: tab_id={}", tab_id);
 
           let mut retry_count = 0;
           let max_retries = 2;
@@ -3209,13 +3419,8 @@ pub async fn ai_chat_stream(
             {
               let flag = continue_cancel_flag.lock().unwrap();
               if *flag {
-                eprintln!("🛑 继续对话前检测到取消标志，停止处理: tab_id={}", tab_id);
-                // 检查 CANCEL_CHANNELS 中是否还有 new_cancel_tx
-                let has_cancel_tx = {
-                  let channels = CANCEL_CHANNELS.lock().unwrap();
-                  channels.contains_key(&tab_id)
-                };
-                if !has_cancel_tx {
+                tracing::debug!("继续对话前检测到取消标志，停止处理: tab_id={}", tab_id);
+                if stream_handle.is_cancelled() {
                   // 取消信号已发送，返回错误
                   break Err(crate::services::ai_error::AIError::Cancelled);
                 }
@@ -3223,8 +3428,12 @@ pub async fn ai_chat_stream(
             }
 
             begin_next_stream_round(&mut stream_ctx);
-            match provider_clone
-              .chat_stream(
+            match service_clone
+              .provider_chat_stream(
+                &chat_slot,
+                &tab_id,
+                &provider_name_clone,
+                &provider_clone,
                 &current_messages,
                 &model_config_clone,
                 &mut new_cancel_rx,
@@ -3247,29 +3456,25 @@ pub async fn ai_chat_stream(
                 {
                   if retry_count < max_retries {
                     retry_count += 1;
-                    eprintln!(
-                      "⚠️ Token超限，尝试截断消息历史（第 {} 次重试）",
-                      retry_count
-                    );
+                    tracing::warn!("Token超限，尝试截断消息历史（第 {} 次重试）", retry_count);
                     // 更激进的截断：只保留系统消息和最后5条消息
                     if current_messages.len() > 6 {
                       ContextManager::default().truncate_with_strategy(
                         &mut current_messages,
                         TruncationStrategy::KeepRecent(5),
                       );
-                      eprintln!("📝 截断后消息数量: {}", current_messages.len());
+                      tracing::debug!("截断后消息数量: {}", current_messages.len());
                     }
                     // ⚠️ 关键修复：重新创建cancel channel并注册
                     let (new_cancel_tx2, mut new_cancel_rx2) = tokio::sync::oneshot::channel();
                     {
-                      let mut channels = CANCEL_CHANNELS.lock().unwrap();
-                      channels.insert(tab_id.clone(), new_cancel_tx2);
-                      eprintln!("✅ Token超限重试时注册新的取消通道: tab_id={}", tab_id);
+                      stream_handle.set_cancel_sender(new_cancel_tx2);
+                      tracing::info!("Token超限重试时注册新的取消通道: tab_id={}", tab_id);
                     }
                     new_cancel_rx = new_cancel_rx2;
                     continue;
                   } else {
-                    eprintln!("❌ Token超限，已重试 {} 次仍失败", max_retries);
+                    tracing::error!("Token超限，已重试 {} 次仍失败", max_retries);
                     break Err(e);
                   }
                 } else {
@@ -3282,7 +3487,7 @@ pub async fn ai_chat_stream(
 
           match stream_result {
             Ok(mut new_stream) => {
-              eprintln!("✅ 重新调用 chat_stream 成功，继续处理流式响应");
+              tracing::info!("重新调用 chat_stream 成功，继续处理流式响应");
               streaming_handler.clear_accumulated(&tab_id); // 清空累积文本
 
               // 继续处理新的流式响应（支持多轮工具调用）
@@ -3293,6 +3498,7 @@ pub async fn ai_chat_stream(
                 crate::services::tool_service::ToolResult,
               )> = Vec::new();
               let mut new_tool_call_specs: Vec<(String, String, String)> = Vec::new();
+              let mut continue_queued_tool_calls: Vec<QueuedToolCall> = Vec::new();
               // 使用新的流式响应处理器
               let mut new_streaming_handler = StreamingResponseHandler::new();
 
@@ -3348,7 +3554,7 @@ pub async fn ai_chat_stream(
                       }
                       _ = continue_cancel_check => {
                           // 取消信号已触发
-                          eprintln!("🛑 继续对话中通过 select! 检测到取消标志，停止处理: tab_id={}", tab_id);
+                          tracing::debug!("继续对话中通过 select! 检测到取消标志，停止处理: tab_id={}", tab_id);
                           finalize_stream(&mut stream_ctx, StreamState::Cancelled);
                           emit_ai_chat_stream_done(
                               &app_handle,
@@ -3356,15 +3562,8 @@ pub async fn ai_chat_stream(
                               &stream_ctx,
                               Some("用户取消了请求"),
                           );
-                          // ⚠️ 关键修复：清理取消通道和标志
-                          {
-                              let mut channels = CANCEL_CHANNELS.lock().unwrap();
-                              channels.remove(&tab_id);
-                          }
-                          {
-                              let mut flags = CANCEL_FLAGS.lock().unwrap();
-                              flags.remove(&tab_id);
-                          }
+                          // ⚠️ 关键修复：清理本流的登记
+                          stream_handle.finish();
                           return;
                       }
                   };
@@ -3390,8 +3589,10 @@ pub async fn ai_chat_stream(
                                 "done": false,
                             });
                             if let Err(e) = app_handle.emit("ai-chat-stream", payload) {
-                              eprintln!("发送事件失败: {}", e);
+                              tracing::warn!("发送事件失败: {}", e);
                             }
+                            grounding::record_text(&tab_id, &text_to_send);
+                            stream_handle.push_text(&text_to_send);
                           }
                         }
                         ChatChunk::ToolCall {
@@ -3401,11 +3602,20 @@ pub async fn ai_chat_stream(
                           is_complete,
                         } => {
                           if !is_complete {
+                            emit_partial_tool_call(
+                              &app_handle,
+                              &tab_id,
+                              &id,
+                              &name,
+                              &arguments,
+                              &mut partial_tool_call_emitted,
+                            );
                             continue;
                           }
+                          partial_tool_call_emitted.remove(&id);
                           let arguments_for_api_continue = arguments.clone();
 
-                          eprintln!("🔧 继续对话中检测到工具调用: id={}, name={}", id, name);
+                          tracing::debug!("继续对话中检测到工具调用: id={}, name={}", id, name);
 
                           // 使用 ToolCallHandler 解析工具调用参数
                           let mut parsed_arguments =
@@ -3420,8 +3630,8 @@ pub async fn ai_chat_stream(
                               &workspace_path,
                             );
                             if skip {
-                              eprintln!(
-                                                                "📝 [继续对话] 跳过 edit_current_editor_document 自动注入: primary_edit_target={:?}",
+                              tracing::debug!(
+                                                                "[继续对话] 跳过 edit_current_editor_document 自动注入: primary_edit_target={:?}",
                                                                 primary_edit_target_clone
                                                             );
                             } else if let serde_json::Value::Object(ref mut map) = parsed_arguments
@@ -3434,7 +3644,7 @@ pub async fn ai_chat_stream(
                                     "current_file".to_string(),
                                     serde_json::Value::String(file_path.clone()),
                                   );
-                                  eprintln!("📝 [继续对话] 已添加 current_file: {}", file_path);
+                                  tracing::debug!("[继续对话] 已添加 current_file: {}", file_path);
                                 }
                               }
                               if !map.contains_key("current_content") {
@@ -3443,8 +3653,8 @@ pub async fn ai_chat_stream(
                                     "current_content".to_string(),
                                     serde_json::Value::String(content.clone()),
                                   );
-                                  eprintln!(
-                                    "📝 [继续对话] 已添加 current_content (长度: {})",
+                                  tracing::debug!(
+                                    "[继续对话] 已添加 current_content (长度: {})",
                                     content.len()
                                   );
                                 }
@@ -3455,7 +3665,7 @@ pub async fn ai_chat_stream(
                                     "document_revision".to_string(),
                                     serde_json::json!(rev),
                                   );
-                                  eprintln!("📝 [继续对话] 已添加 document_revision: {}", rev);
+                                  tracing::debug!("[继续对话] 已添加 document_revision: {}", rev);
                                 }
                               }
                               if !map.contains_key("baseline_id") {
@@ -3464,7 +3674,7 @@ pub async fn ai_chat_stream(
                                     "baseline_id".to_string(),
                                     serde_json::Value::String(bid.clone()),
                                   );
-                                  eprintln!("📝 [继续对话] 已添加 baseline_id: {}", bid);
+                                  tracing::debug!("[继续对话] 已添加 baseline_id: {}", bid);
                                 }
                               }
                               // 旧的 edit_target / target_content 自动补参与继续对话兼容已禁用。
@@ -3523,10 +3733,12 @@ pub async fn ai_chat_stream(
                               if map.contains_key("current_file")
                                 && map.contains_key("current_content")
                               {
-                                eprintln!("✅ [继续对话] edit_current_editor_document 参数已完整");
+                                tracing::info!(
+                                  "[继续对话] edit_current_editor_document 参数已完整"
+                                );
                               } else {
-                                eprintln!(
-                                  "⚠️ [继续对话] edit_current_editor_document 仍然缺少参数"
+                                tracing::warn!(
+                                  "[继续对话] edit_current_editor_document 仍然缺少参数"
                                 );
                               }
                             }
@@ -3555,288 +3767,235 @@ pub async fn ai_chat_stream(
                               },
                           });
                           if let Err(e) = app_handle.emit("ai-chat-stream", payload) {
-                            eprintln!("发送工具调用事件失败: {}", e);
-                          }
-
-                          // 执行工具调用
-                          let tool_call = ToolCall {
-                            id: id.clone(),
-                            name: name.clone(),
-                            arguments: parsed_arguments,
-                          };
-
-                          // ⚠️ 关键修复：在继续对话的工具调用执行前检查取消标志
-                          {
-                            let flag = continue_cancel_flag_for_stream.lock().unwrap();
-                            if *flag {
-                              eprintln!(
-                                "🛑 继续对话中工具调用执行前检测到取消标志，停止执行: tab_id={}",
-                                tab_id
-                              );
-                              finalize_stream(&mut stream_ctx, StreamState::Cancelled);
-                              emit_ai_chat_stream_done(
-                                &app_handle,
-                                &tab_id,
-                                &stream_ctx,
-                                Some("用户取消了请求"),
-                              );
-                              // ⚠️ 关键修复：清理取消通道和标志
-                              {
-                                let mut channels = CANCEL_CHANNELS.lock().unwrap();
-                                channels.remove(&tab_id);
-                              }
-                              {
-                                let mut flags = CANCEL_FLAGS.lock().unwrap();
-                                flags.remove(&tab_id);
-                              }
-                              return;
-                            }
-                          }
-
-                          eprintln!("🚀 继续对话中执行工具调用: {}", name);
-
-                          // 执行工具调用，带重试机制
-                          // ⚠️ 关键修复：在工具调用执行过程中也要检查取消标志
-                          let mut tool_result = None;
-                          let mut last_error = None;
-                          let max_retries = 3;
-
-                          for attempt in 1..=max_retries {
-                            match tool_service.execute_tool(&tool_call, &workspace_path).await {
-                              Ok(result) => {
-                                if result.success {
-                                  tool_result = Some(result);
-                                  if attempt > 1 {
-                                    eprintln!(
-                                      "✅ 继续对话中工具执行成功（第 {} 次尝试）: {}",
-                                      attempt, name
-                                    );
-                                  }
-                                  break;
-                                } else {
-                                  // 工具返回失败，但这是工具层面的失败（如文件不存在），不需要重试
-                                  tool_result = Some(result);
-                                  break;
-                                }
-                              }
-                              Err(e) => {
-                                last_error = Some(e.clone());
-                                eprintln!(
-                                  "⚠️ 继续对话中工具执行失败（第 {} 次尝试）: {} - {}",
-                                  attempt, name, e
-                                );
-                                if attempt < max_retries {
-                                  // 等待一小段时间后重试（指数退避）
-                                  let delay_ms = 100 * attempt;
-                                  tokio::time::sleep(tokio::time::Duration::from_millis(delay_ms))
-                                    .await;
-                                  eprintln!(
-                                    "🔄 重试工具调用: {} (尝试 {}/{})",
-                                    name,
-                                    attempt + 1,
-                                    max_retries
-                                  );
-                                }
-                              }
-                            }
-                          }
-
-                          let raw_tool_result = match tool_result {
-                            Some(result) => result,
-                            None => {
-                              // 所有重试都失败了
-                              let error_msg = last_error.unwrap_or_else(|| "未知错误".to_string());
-                              eprintln!(
-                                "❌ 继续对话中工具执行最终失败（已重试 {} 次）: {} - {}",
-                                max_retries, name, error_msg
-                              );
-                              crate::services::tool_service::ToolResult {
-                                success: false,
-                                data: None,
-                                error: Some(format!(
-                                  "执行失败（已重试 {} 次）: {}",
-                                  max_retries, error_msg
-                                )),
-                                message: None,
-                                error_kind: None,
-                                display_error: None,
-                                meta: None,
-                              }
-                            }
-                          };
-                          let awaiting_confirmation =
-                            tool_result_awaits_confirmation(&raw_tool_result);
-                          let skip_continue = !raw_tool_result.success || awaiting_confirmation;
-                          let tool_result = with_execution_observability(
-                            raw_tool_result,
-                            &name,
-                            Some(&parsed_args_for_result_continue),
-                            skip_continue,
-                          );
-                          if !awaiting_confirmation {
-                            maybe_sync_workflow_execution_from_tool_result(
-                              &app_handle,
-                              &tab_id,
-                              &effective_task_id,
-                              &workspace_path,
-                              &name,
-                              &tool_result,
-                            );
-                          }
-
-                          // ⚠️ 关键修复：在继续对话的工具调用执行后检查取消标志
-                          {
-                            let flag = continue_cancel_flag_for_stream.lock().unwrap();
-                            if *flag {
-                              eprintln!(
-                                "🛑 继续对话中工具调用执行后检测到取消标志，停止处理: tab_id={}",
-                                tab_id
-                              );
-                              finalize_stream(&mut stream_ctx, StreamState::Cancelled);
-                              emit_ai_chat_stream_done(
-                                &app_handle,
-                                &tab_id,
-                                &stream_ctx,
-                                Some("用户取消了请求"),
-                              );
-                              // ⚠️ 关键修复：清理取消通道和标志
-                              {
-                                let mut channels = CANCEL_CHANNELS.lock().unwrap();
-                                channels.remove(&tab_id);
-                              }
-                              {
-                                let mut flags = CANCEL_FLAGS.lock().unwrap();
-                                flags.remove(&tab_id);
-                              }
-                              return;
-                            }
+                            tracing::warn!("发送工具调用事件失败: {}", e);
                           }
 
-                          if awaiting_confirmation {
-                            let payload = serde_json::json!({
-                                "tab_id": tab_id,
-                                "chunk": "",
-                                "done": false,
-                                "tool_call": {
-                                    "id": id,
-                                    "name": name,
-                                    "arguments": parsed_args_for_result_continue.clone(),
-                                    "result": tool_result,
-                                    "status": "pending",
-                                },
-                            });
-                            if let Err(e) = app_handle.emit("ai-chat-stream", payload) {
-                              eprintln!("发送确认门工具调用失败: {}", e);
-                            }
-                            continue_loop = false;
-                          } else if tool_result.success {
-                            eprintln!("✅ 继续对话中工具执行成功: {}", name);
-
-                            // 保存工具调用结果
-                            new_tool_call_specs.push((
+                          // 收集完整的工具调用，流结束后统一并发执行，结果按调用顺序处理
+                          continue_queued_tool_calls.push(QueuedToolCall {
+                            tool_call: ToolCall::from_model(
                               id.clone(),
                               name.clone(),
-                              arguments_for_api_continue.clone(),
-                            ));
-                            new_tool_results.push((id.clone(), name.clone(), tool_result.clone()));
-
-                            // 立即更新累积结果，用于任务进度分析
-                            all_tool_results.push((id.clone(), name.clone(), tool_result.clone()));
-
-                            // 发送工具调用结果到前端
-                            let tool_result_message = format!(
-                              "\n\n[工具调用: {}]\n结果: {}",
-                              name,
-                              serde_json::to_string_pretty(&tool_result).unwrap_or_default()
-                            );
+                              parsed_arguments,
+                            ),
+                            arguments: arguments_for_api_continue,
+                            parsed_args_for_result: parsed_args_for_result_continue,
+                          });
+                        }
+                      }
+                    }
+                    Err(e) => {
+                      tracing::error!("继续对话时发生错误: {}", e);
+                      // Phase 8: 流错误后仅在允许自动续轮的任务上尝试继续
+                      if loop_execution_policy.allows_tpa_force_continue() {
+                        let err_progress = TaskProgressAnalyzer::analyze(&all_tool_results);
+                        if err_progress.is_incomplete {
+                          tracing::warn!("流错误但任务未完成（允许自动续轮），尝试继续");
+                        } else {
+                          continue_loop = false;
+                        }
+                      } else {
+                        continue_loop = false;
+                      }
+                      break;
+                    }
+                  }
+                }
 
-                            let payload = serde_json::json!({
-                                "tab_id": tab_id,
-                                "chunk": tool_result_message,
-                                "done": false,
-                                "tool_call": {
-                                    "id": id,
-                                    "name": name,
-                                    "arguments": parsed_args_for_result_continue.clone(), // ✅ 使用解析后的 JSON 对象
-                                    "result": tool_result,
-                                    "status": "completed",
-                                },
-                            });
-                            if let Err(e) = app_handle.emit("ai-chat-stream", payload) {
-                              eprintln!("发送工具调用结果失败: {}", e);
-                            }
+                // 并发执行本轮继续对话收集到的工具调用（相同路径串行），结果按调用顺序处理
+                if !continue_queued_tool_calls.is_empty() {
+                  // ⚠️ 关键修复：在继续对话的工具调用执行前检查取消标志
+                  {
+                    let flag = continue_cancel_flag_for_stream.lock().unwrap();
+                    if *flag {
+                      tracing::debug!(
+                        "继续对话中工具调用执行前检测到取消标志，停止执行: tab_id={}",
+                        tab_id
+                      );
+                      finalize_stream(&mut stream_ctx, StreamState::Cancelled);
+                      emit_ai_chat_stream_done(
+                        &app_handle,
+                        &tab_id,
+                        &stream_ctx,
+                        Some("用户取消了请求"),
+                      );
+                      // ⚠️ 关键修复：清理本流的登记
+                      stream_handle.finish();
+                      return;
+                    }
+                  }
 
-                            // 标记需要继续循环
-                            continue_loop = true;
-                          } else {
-                            // 工具执行失败（工具层面的失败，如文件不存在）
-                            eprintln!(
-                              "⚠️ 继续对话中工具执行失败: {} - {}",
-                              name,
-                              tool_result
-                                .error
-                                .as_ref()
-                                .unwrap_or(&"未知错误".to_string())
-                            );
-                            new_tool_call_specs.push((
-                              id.clone(),
-                              name.clone(),
-                              arguments_for_api_continue.clone(),
-                            ));
-                            new_tool_results.push((id.clone(), name.clone(), tool_result.clone()));
-
-                            // 立即更新累积结果
-                            all_tool_results.push((id.clone(), name.clone(), tool_result.clone()));
-
-                            let error_message = format!(
-                              "\n\n[工具调用失败: {}]\n错误: {}",
-                              name,
-                              tool_result
-                                .error
-                                .as_ref()
-                                .unwrap_or(&"未知错误".to_string())
-                            );
-                            let payload = serde_json::json!({
-                                "tab_id": tab_id,
-                                "chunk": error_message,
-                                "done": false,
-                                "tool_call": {
-                                    "id": id,
-                                    "name": name,
-                                    "arguments": parsed_args_for_result_continue.clone(), // ✅ 使用解析后的 JSON 对象
-                                    "result": tool_result,
-                                    "status": "failed",
-                                },
-                            });
-                            if let Err(e) = app_handle.emit("ai-chat-stream", payload) {
-                              eprintln!("发送工具调用错误失败: {}", e);
-                            }
+                  let batch: Vec<ToolCall> = continue_queued_tool_calls
+                    .iter()
+                    .map(|queued| queued.tool_call.clone())
+                    .collect();
+                  tracing::info!("继续对话中执行 {} 个工具调用", batch.len());
+                  let batch_results = tool_call_handler
+                    .execute_tools_concurrently(
+                      &batch,
+                      &workspace_path,
+                      3, // max_retries
+                      MAX_PARALLEL_TOOL_CALLS,
+                      &continue_cancel_flag_for_stream,
+                    )
+                    .await;
 
-                            // 即使失败，也标记需要继续循环（让 AI 知道有错误，可以尝试其他方式）
-                            continue_loop = true;
-                          }
+                  let mut batch_awaits_confirmation = false;
+                  for (queued, (raw_tool_result, _retry_count)) in
+                    continue_queued_tool_calls.drain(..).zip(batch_results)
+                  {
+                    let QueuedToolCall {
+                      tool_call: ToolCall { id, name, .. },
+                      arguments: arguments_for_api_continue,
+                      parsed_args_for_result: parsed_args_for_result_continue,
+                    } = queued;
+                    let awaiting_confirmation = tool_result_awaits_confirmation(&raw_tool_result);
+                    let skip_continue = !raw_tool_result.success || awaiting_confirmation;
+                    let tool_result = with_execution_observability(
+                      raw_tool_result,
+                      &name,
+                      Some(&parsed_args_for_result_continue),
+                      skip_continue,
+                    );
+                    if !awaiting_confirmation {
+                      maybe_sync_workflow_execution_from_tool_result(
+                        &app_handle,
+                        &tab_id,
+                        &effective_task_id,
+                        &workspace_path,
+                        &name,
+                        &tool_result,
+                      );
+                    }
 
-                          // 跳出内层循环，准备继续对话
-                          break;
-                        }
+                    // ⚠️ 关键修复：在继续对话的工具调用执行后检查取消标志
+                    {
+                      let flag = continue_cancel_flag_for_stream.lock().unwrap();
+                      if *flag {
+                        tracing::debug!(
+                          "继续对话中工具调用执行后检测到取消标志，停止处理: tab_id={}",
+                          tab_id
+                        );
+                        finalize_stream(&mut stream_ctx, StreamState::Cancelled);
+                        emit_ai_chat_stream_done(
+                          &app_handle,
+                          &tab_id,
+                          &stream_ctx,
+                          Some("用户取消了请求"),
+                        );
+                        // ⚠️ 关键修复：清理本流的登记
+                        stream_handle.finish();
+                        return;
                       }
                     }
-                    Err(e) => {
-                      eprintln!("❌ 继续对话时发生错误: {}", e);
-                      // Phase 8: 流错误后仅在允许自动续轮的任务上尝试继续
-                      if loop_execution_policy.allows_tpa_force_continue() {
-                        let err_progress = TaskProgressAnalyzer::analyze(&all_tool_results);
-                        if err_progress.is_incomplete {
-                          eprintln!("⚠️ 流错误但任务未完成（允许自动续轮），尝试继续");
-                        } else {
-                          continue_loop = false;
-                        }
-                      } else {
-                        continue_loop = false;
+
+                    if awaiting_confirmation {
+                      let payload = serde_json::json!({
+                          "tab_id": tab_id,
+                          "chunk": "",
+                          "done": false,
+                          "tool_call": {
+                              "id": id,
+                              "name": name,
+                              "arguments": parsed_args_for_result_continue.clone(),
+                              "result": tool_result,
+                              "status": "pending",
+                          },
+                      });
+                      if let Err(e) = app_handle.emit("ai-chat-stream", payload) {
+                        tracing::warn!("发送确认门工具调用失败: {}", e);
                       }
-                      break;
+                      batch_awaits_confirmation = true;
+                    } else if tool_result.success {
+                      tracing::info!("继续对话中工具执行成功: {}", name);
+
+                      // 保存工具调用结果
+                      new_tool_call_specs.push((
+                        id.clone(),
+                        name.clone(),
+                        arguments_for_api_continue.clone(),
+                      ));
+                      new_tool_results.push((id.clone(), name.clone(), tool_result.clone()));
+
+                      // 立即更新累积结果，用于任务进度分析
+                      all_tool_results.push((id.clone(), name.clone(), tool_result.clone()));
+
+                      // 发送工具调用结果到前端
+                      let tool_result_message = format!(
+                        "\n\n[工具调用: {}]\n结果: {}",
+                        name,
+                        serde_json::to_string_pretty(&tool_result).unwrap_or_default()
+                      );
+
+                      let payload = serde_json::json!({
+                          "tab_id": tab_id,
+                          "chunk": tool_result_message,
+                          "done": false,
+                          "tool_call": {
+                              "id": id,
+                              "name": name,
+                              "arguments": parsed_args_for_result_continue.clone(), // ✅ 使用解析后的 JSON 对象
+                              "result": tool_result,
+                              "status": "completed",
+                          },
+                      });
+                      if let Err(e) = app_handle.emit("ai-chat-stream", payload) {
+                        tracing::warn!("发送工具调用结果失败: {}", e);
+                      }
+
+                      // 标记需要继续循环
+                      continue_loop = true;
+                    } else {
+                      // 工具执行失败（工具层面的失败，如文件不存在）
+                      tracing::warn!(
+                        "继续对话中工具执行失败: {} - {}",
+                        name,
+                        tool_result
+                          .error
+                          .as_ref()
+                          .unwrap_or(&"未知错误".to_string())
+                      );
+                      new_tool_call_specs.push((
+                        id.clone(),
+                        name.clone(),
+                        arguments_for_api_continue.clone(),
+                      ));
+                      new_tool_results.push((id.clone(), name.clone(), tool_result.clone()));
+
+                      // 立即更新累积结果
+                      all_tool_results.push((id.clone(), name.clone(), tool_result.clone()));
+
+                      let error_message = format!(
+                        "\n\n[工具调用失败: {}]\n错误: {}",
+                        name,
+                        tool_result
+                          .error
+                          .as_ref()
+                          .unwrap_or(&"未知错误".to_string())
+                      );
+                      let payload = serde_json::json!({
+                          "tab_id": tab_id,
+                          "chunk": error_message,
+                          "done": false,
+                          "tool_call": {
+                              "id": id,
+                              "name": name,
+                              "arguments": parsed_args_for_result_continue.clone(), // ✅ 使用解析后的 JSON 对象
+                              "result": tool_result,
+                              "status": "failed",
+                          },
+                      });
+                      if let Err(e) = app_handle.emit("ai-chat-stream", payload) {
+                        tracing::warn!("发送工具调用错误失败: {}", e);
+                      }
+
+                      // 即使失败，也标记需要继续循环（让 AI 知道有错误，可以尝试其他方式）
+                      continue_loop = true;
                     }
                   }
+                  // 有工具等待用户确认时停止自动续轮
+                  if batch_awaits_confirmation {
+                    continue_loop = false;
+                  }
                 }
 
                 // 继续对话子流：本轮 new_stream 读取结束（非取消路径下收口为 Completed，允许后续 assistant 写入）
@@ -3901,8 +4060,8 @@ pub async fn ai_chat_stream(
                   // 综合判断任务是否未完成
                   let task_really_incomplete = task_incomplete || recursive_check_incomplete;
 
-                  eprintln!(
-                    "🔍 流结束检查：任务未完成={}, 递归检查未完成={}, 回复完整={}, 文本长度={}",
+                  tracing::debug!(
+                    "流结束检查：任务未完成={}, 递归检查未完成={}, 回复完整={}, 文本长度={}",
                     task_incomplete,
                     recursive_check_incomplete,
                     reply_complete,
@@ -3913,7 +4072,7 @@ pub async fn ai_chat_stream(
                   let is_reply_too_short =
                     reply_checker.is_too_short(&new_accumulated_text_clone) && !reply_complete;
                   if is_reply_too_short && !task_really_incomplete {
-                    eprintln!("⚠️ 警告：回复内容可能不完整（长度={}，未以标点符号结尾），但流已结束，保存当前回复", new_accumulated_text_clone.len());
+                    tracing::warn!("警告：回复内容可能不完整（长度={}，未以标点符号结尾），但流已结束，保存当前回复", new_accumulated_text_clone.len());
                   }
 
                   // 更新任务未完成标志
@@ -3922,14 +4081,11 @@ pub async fn ai_chat_stream(
                   if task_incomplete {
                     // 使用 LoopDetector 检查是否超过最大重试次数
                     if force_continue_count >= max_force_continue_retries {
-                      eprintln!(
-                        "⚠️ 已达到最大强制继续重试次数（{}），停止继续请求",
+                      tracing::warn!(
+                        "已达到最大强制继续重试次数（{}），停止继续请求",
                         max_force_continue_retries
                       );
-                      eprintln!(
-                        "📝 保存当前回复（长度={}）",
-                        new_accumulated_text_clone.len()
-                      );
+                      tracing::debug!("保存当前回复（长度={}）", new_accumulated_text_clone.len());
                       // 不再继续，保存当前回复
                       continue_loop = false;
                     } else {
@@ -3993,9 +4149,9 @@ pub async fn ai_chat_stream(
                       }
 
                       if is_same_as_last_force {
-                        eprintln!("⚠️ 检测到循环：回复内容与上次强制继续时相同，停止继续请求");
-                        eprintln!(
-                          "📝 保存当前回复（长度={}）",
+                        tracing::warn!("检测到循环：回复内容与上次强制继续时相同，停止继续请求");
+                        tracing::debug!(
+                          "保存当前回复（长度={}）",
                           new_accumulated_text_clone.len()
                         );
                         // 不再继续，保存当前回复
@@ -4004,11 +4160,11 @@ pub async fn ai_chat_stream(
                         force_continue_count += 1;
                         last_force_continue_content = Some(new_accumulated_text_clone.clone());
 
-                        eprintln!(
-                          "⚠️ 流结束但任务未完成，强制继续对话（第 {} 次）",
+                        tracing::warn!(
+                          "流结束但任务未完成，强制继续对话（第 {} 次）",
                           force_continue_count
                         );
-                        eprintln!("📊 任务进度详情：{}", task_progress);
+                        tracing::debug!("任务进度详情：{}", task_progress);
                         // 任务未完成，强制继续对话
                         continue_loop = true;
 
@@ -4066,21 +4222,24 @@ pub async fn ai_chat_stream(
                     new_streaming_handler.clear_accumulated(&tab_id);
 
                     // ⚠️ 关键修复：任务未完成时，必须重新调用 chat_stream 继续对话
-                    eprintln!("🔄 任务未完成，重新调用 chat_stream 继续执行");
+                    tracing::debug!("任务未完成，重新调用 chat_stream 继续执行");
                     // ⚠️ 关键修复：为强制继续创建新的取消通道并注册
                     let (force_continue_cancel_tx, mut force_continue_cancel_rx) =
                       tokio::sync::oneshot::channel();
                     {
-                      let mut channels = CANCEL_CHANNELS.lock().unwrap();
-                      channels.insert(tab_id.clone(), force_continue_cancel_tx);
-                      eprintln!("✅ 强制继续时注册新的取消通道: tab_id={}", tab_id);
+                      stream_handle.set_cancel_sender(force_continue_cancel_tx);
+                      tracing::info!("强制继续时注册新的取消通道: tab_id={}", tab_id);
                     }
                     let mut force_retry_count = 0;
                     let max_force_retries = 2;
                     let mut force_stream_result = loop {
                       begin_next_stream_round(&mut stream_ctx);
-                      match provider_clone
-                        .chat_stream(
+                      match service_clone
+                        .provider_chat_stream(
+                          &chat_slot,
+                          &tab_id,
+                          &provider_name_clone,
+                          &provider_clone,
                           &current_messages,
                           &model_config_clone,
                           &mut force_continue_cancel_rx,
@@ -4103,8 +4262,8 @@ pub async fn ai_chat_stream(
                           {
                             if force_retry_count < max_force_retries {
                               force_retry_count += 1;
-                              eprintln!(
-                                "⚠️ Token超限，尝试截断消息历史（第 {} 次重试）",
+                              tracing::warn!(
+                                "Token超限，尝试截断消息历史（第 {} 次重试）",
                                 force_retry_count
                               );
                               // 更激进的截断：只保留系统消息和最后5条消息
@@ -4113,23 +4272,22 @@ pub async fn ai_chat_stream(
                                   &mut current_messages,
                                   TruncationStrategy::KeepRecent(5),
                                 );
-                                eprintln!("📝 截断后消息数量: {}", current_messages.len());
+                                tracing::debug!("截断后消息数量: {}", current_messages.len());
                               }
                               // ⚠️ 关键修复：重新创建cancel channel并注册
                               let (force_continue_cancel_tx2, mut force_continue_cancel_rx2) =
                                 tokio::sync::oneshot::channel();
                               {
-                                let mut channels = CANCEL_CHANNELS.lock().unwrap();
-                                channels.insert(tab_id.clone(), force_continue_cancel_tx2);
-                                eprintln!(
-                                  "✅ Token超限重试时注册新的取消通道（强制继续）: tab_id={}",
+                                stream_handle.set_cancel_sender(force_continue_cancel_tx2);
+                                tracing::info!(
+                                  "Token超限重试时注册新的取消通道（强制继续）: tab_id={}",
                                   tab_id
                                 );
                               }
                               force_continue_cancel_rx = force_continue_cancel_rx2;
                               continue;
                             } else {
-                              eprintln!("❌ Token超限，已重试 {} 次仍失败", max_force_retries);
+                              tracing::error!("Token超限，已重试 {} 次仍失败", max_force_retries);
                               break Err(e);
                             }
                           } else {
@@ -4142,12 +4300,12 @@ pub async fn ai_chat_stream(
 
                     match force_stream_result {
                       Ok(force_stream) => {
-                        eprintln!("✅ 强制继续对话，重新调用 chat_stream 成功");
+                        tracing::info!("强制继续对话，重新调用 chat_stream 成功");
                         new_stream = force_stream;
                         // continue_loop 已经是 true，会继续循环
                       }
                       Err(e) => {
-                        eprintln!("❌ 强制继续对话失败: {}", e);
+                        tracing::error!("强制继续对话失败: {}", e);
                         continue_loop = false;
                       }
                     }
@@ -4186,8 +4344,8 @@ pub async fn ai_chat_stream(
 
                     let has_summary = reply_checker.has_summary(&new_accumulated_text_clone);
 
-                    eprintln!(
-                      "📝 流正常结束，任务完成={}, 已有总结={}, 需要总结={}, 文本长度={}",
+                    tracing::debug!(
+                      "流正常结束，任务完成={}, 已有总结={}, 需要总结={}, 文本长度={}",
                       task_completed,
                       has_summary,
                       needs_summary_for_read,
@@ -4197,9 +4355,9 @@ pub async fn ai_chat_stream(
                     if (task_completed || needs_summary_for_read) && !has_summary {
                       // 任务完成但没有总结，或用户要求总结文件内容，要求AI做总结
                       if needs_summary_for_read {
-                        eprintln!("📋 用户要求总结文件内容，但回复不完整，要求AI做内容总结");
+                        tracing::debug!("用户要求总结文件内容，但回复不完整，要求AI做内容总结");
                       } else {
-                        eprintln!("📋 任务已完成，要求AI做工作总结");
+                        tracing::debug!("任务已完成，要求AI做工作总结");
                       }
                       continue_loop = true;
 
@@ -4249,7 +4407,7 @@ pub async fn ai_chat_stream(
                       {
                         let flag = continue_cancel_flag_for_stream.lock().unwrap();
                         if *flag {
-                          eprintln!("🛑 获取总结前检测到取消标志，停止处理: tab_id={}", tab_id);
+                          tracing::debug!("获取总结前检测到取消标志，停止处理: tab_id={}", tab_id);
                           finalize_stream(&mut stream_ctx, StreamState::Cancelled);
                           emit_ai_chat_stream_done(
                             &app_handle,
@@ -4257,35 +4415,31 @@ pub async fn ai_chat_stream(
                             &stream_ctx,
                             Some("用户取消了请求"),
                           );
-                          // ⚠️ 关键修复：清理取消通道和标志
-                          {
-                            let mut channels = CANCEL_CHANNELS.lock().unwrap();
-                            channels.remove(&tab_id);
-                          }
-                          {
-                            let mut flags = CANCEL_FLAGS.lock().unwrap();
-                            flags.remove(&tab_id);
-                          }
+                          // ⚠️ 关键修复：清理本流的登记
+                          stream_handle.finish();
                           return;
                         }
                       }
 
                       // 重新调用 chat_stream 获取总结
-                      eprintln!("🔄 要求AI做工作总结，重新调用 chat_stream");
+                      tracing::debug!("要求AI做工作总结，重新调用 chat_stream");
                       // ⚠️ 关键修复：为总结创建新的取消通道并注册
                       let (summary_cancel_tx, mut summary_cancel_rx) =
                         tokio::sync::oneshot::channel();
                       {
-                        let mut channels = CANCEL_CHANNELS.lock().unwrap();
-                        channels.insert(tab_id.clone(), summary_cancel_tx);
-                        eprintln!("✅ 获取总结时注册新的取消通道: tab_id={}", tab_id);
+                        stream_handle.set_cancel_sender(summary_cancel_tx);
+                        tracing::info!("获取总结时注册新的取消通道: tab_id={}", tab_id);
                       }
                       let mut summary_retry_count = 0;
                       let max_summary_retries = 2;
                       let mut summary_stream_result = loop {
                         begin_next_stream_round(&mut stream_ctx);
-                        match provider_clone
-                          .chat_stream(
+                        match service_clone
+                          .provider_chat_stream(
+                            &chat_slot,
+                            &tab_id,
+                            &provider_name_clone,
+                            &provider_clone,
                             &current_messages,
                             &model_config_clone,
                             &mut summary_cancel_rx,
@@ -4308,8 +4462,8 @@ pub async fn ai_chat_stream(
                             {
                               if summary_retry_count < max_summary_retries {
                                 summary_retry_count += 1;
-                                eprintln!(
-                                  "⚠️ Token超限，尝试截断消息历史（第 {} 次重试）",
+                                tracing::warn!(
+                                  "Token超限，尝试截断消息历史（第 {} 次重试）",
                                   summary_retry_count
                                 );
                                 // 更激进的截断：只保留系统消息和最后5条消息
@@ -4318,23 +4472,25 @@ pub async fn ai_chat_stream(
                                     &mut current_messages,
                                     TruncationStrategy::KeepRecent(5),
                                   );
-                                  eprintln!("📝 截断后消息数量: {}", current_messages.len());
+                                  tracing::debug!("截断后消息数量: {}", current_messages.len());
                                 }
                                 // ⚠️ 关键修复：重新创建cancel channel并注册
                                 let (summary_cancel_tx2, mut summary_cancel_rx2) =
                                   tokio::sync::oneshot::channel();
                                 {
-                                  let mut channels = CANCEL_CHANNELS.lock().unwrap();
-                                  channels.insert(tab_id.clone(), summary_cancel_tx2);
-                                  eprintln!(
-                                    "✅ Token超限重试时注册新的取消通道（总结）: tab_id={}",
+                                  stream_handle.set_cancel_sender(summary_cancel_tx2);
+                                  tracing::info!(
+                                    "Token超限重试时注册新的取消通道（总结）: tab_id={}",
                                     tab_id
                                   );
                                 }
                                 summary_cancel_rx = summary_cancel_rx2;
                                 continue;
                               } else {
-                                eprintln!("❌ Token超限，已重试 {} 次仍失败", max_summary_retries);
+                                tracing::error!(
+                                  "Token超限，已重试 {} 次仍失败",
+                                  max_summary_retries
+                                );
                                 break Err(e);
                               }
                             } else {
@@ -4347,12 +4503,12 @@ pub async fn ai_chat_stream(
 
                       match summary_stream_result {
                         Ok(summary_stream) => {
-                          eprintln!("✅ 获取工作总结，重新调用 chat_stream 成功");
+                          tracing::info!("获取工作总结，重新调用 chat_stream 成功");
                           new_stream = summary_stream;
                           // continue_loop 已经是 true，会继续循环
                         }
                         Err(e) => {
-                          eprintln!("❌ 获取工作总结失败: {}", e);
+                          tracing::error!("获取工作总结失败: {}", e);
                           continue_loop = false;
                         }
                       }
@@ -4436,29 +4592,31 @@ pub async fn ai_chat_stream(
 
                         // 检查是否超过最大重试次数
                         if continue_reply_retry_count >= MAX_CONTINUE_REPLY_RETRIES {
-                          eprintln!(
-                            "⚠️ 已达到最大重试次数（{}），停止继续请求AI完成回复",
+                          tracing::warn!(
+                            "已达到最大重试次数（{}），停止继续请求AI完成回复",
                             MAX_CONTINUE_REPLY_RETRIES
                           );
-                          eprintln!(
-                            "📝 保存当前回复（长度={}）",
+                          tracing::debug!(
+                            "保存当前回复（长度={}）",
                             new_accumulated_text_clone.len()
                           );
                           // 不再继续，保存当前回复
                         } else if is_same_as_last {
-                          eprintln!("⚠️ 检测到循环：回复内容与上一次相同或语义重复，停止继续请求");
-                          eprintln!(
-                            "📝 保存当前回复（长度={}）",
+                          tracing::warn!(
+                            "检测到循环：回复内容与上一次相同或语义重复，停止继续请求"
+                          );
+                          tracing::debug!(
+                            "保存当前回复（长度={}）",
                             new_accumulated_text_clone.len()
                           );
                           // 不再继续，保存当前回复
                         } else {
                           // 根据是否有工具调用结果，生成不同的提示
                           if has_tool_results_but_incomplete {
-                            eprintln!("⚠️ AI已调用工具但回复不完整（长度={}），要求AI总结工具调用结果（第 {} 次）", 
+                            tracing::warn!("AI已调用工具但回复不完整（长度={}），要求AI总结工具调用结果（第 {} 次）", 
                                                             new_accumulated_text_clone.len(), continue_reply_retry_count + 1);
                           } else {
-                            eprintln!("⚠️ 警告：回复内容可能不完整（长度={}，未以标点符号结尾），请求AI继续完成（第 {} 次）", 
+                            tracing::warn!("警告：回复内容可能不完整（长度={}，未以标点符号结尾），请求AI继续完成（第 {} 次）", 
                                                             new_accumulated_text_clone.len(), continue_reply_retry_count + 1);
                           }
 
@@ -4549,18 +4707,21 @@ pub async fn ai_chat_stream(
                           new_streaming_handler.clear_accumulated(&tab_id);
 
                           // 重新调用 chat_stream 继续完成回复
-                          eprintln!("🔄 请求AI继续完成回复");
+                          tracing::debug!("请求AI继续完成回复");
                           // ⚠️ 关键修复：为继续回复创建新的取消通道并注册
                           let (continue_reply_cancel_tx, mut continue_reply_cancel_rx) =
                             tokio::sync::oneshot::channel();
                           {
-                            let mut channels = CANCEL_CHANNELS.lock().unwrap();
-                            channels.insert(tab_id.clone(), continue_reply_cancel_tx);
-                            eprintln!("✅ 继续回复时注册新的取消通道: tab_id={}", tab_id);
+                            stream_handle.set_cancel_sender(continue_reply_cancel_tx);
+                            tracing::info!("继续回复时注册新的取消通道: tab_id={}", tab_id);
                           }
                           begin_next_stream_round(&mut stream_ctx);
-                          match provider_clone
-                            .chat_stream(
+                          match service_clone
+                            .provider_chat_stream(
+                              &chat_slot,
+                              &tab_id,
+                              &provider_name_clone,
+                              &provider_clone,
                               &current_messages,
                               &model_config_clone,
                               &mut continue_reply_cancel_rx,
@@ -4569,17 +4730,17 @@ pub async fn ai_chat_stream(
                             .await
                           {
                             Ok(continue_stream) => {
-                              eprintln!("✅ 成功请求AI继续完成回复");
+                              tracing::info!("成功请求AI继续完成回复");
                               new_stream = continue_stream;
                               continue_loop = true;
                               // 继续循环处理新的流
                               continue;
                             }
                             Err(e) => {
-                              eprintln!("❌ 请求AI继续完成回复失败: {}", e);
+                              tracing::error!("请求AI继续完成回复失败: {}", e);
                               // 如果继续失败，至少保存当前不完整的回复
-                              eprintln!(
-                                "📝 保存不完整的回复（长度={}）",
+                              tracing::debug!(
+                                "保存不完整的回复（长度={}）",
                                 new_accumulated_text_clone.len()
                               );
                             }
@@ -4684,7 +4845,7 @@ pub async fn ai_chat_stream(
                             let is_incomplete = list_files_calls < expected_min_calls;
 
                             if is_incomplete {
-                              eprintln!("⚠️ 递归检查不完整：根目录有 {} 个文件夹，期望至少 {} 次 list_files 调用，实际 {} 次", 
+                              tracing::warn!("递归检查不完整：根目录有 {} 个文件夹，期望至少 {} 次 list_files 调用，实际 {} 次", 
                                                                 root_dirs, expected_min_calls, list_files_calls);
                             }
 
@@ -4701,7 +4862,10 @@ pub async fn ai_chat_stream(
                               .count();
                             // 如果只调用了1次list_files（可能只检查了根目录），认为可能未完成
                             if list_files_calls <= 1 {
-                              eprintln!("⚠️ 可能未完成递归检查：用户要求递归检查，但只调用了 {} 次 list_files", list_files_calls);
+                              tracing::warn!(
+                                "可能未完成递归检查：用户要求递归检查，但只调用了 {} 次 list_files",
+                                list_files_calls
+                              );
                               true
                             } else {
                               false
@@ -4714,7 +4878,7 @@ pub async fn ai_chat_stream(
                         // 4. 针对性的处理
                         if execution_incomplete {
                           if let Some((_root_files, root_dirs)) = root_list_files_result {
-                            eprintln!("⚠️ 检测到执行不完整：用户要求递归检查所有文件，根目录有 {} 个文件夹，但AI可能未检查完所有子文件夹", root_dirs);
+                            tracing::warn!("检测到执行不完整：用户要求递归检查所有文件，根目录有 {} 个文件夹，但AI可能未检查完所有子文件夹", root_dirs);
 
                             // 将当前回复添加到消息历史
                             if !new_accumulated_text_clone.is_empty() {
@@ -4749,11 +4913,15 @@ pub async fn ai_chat_stream(
                             new_streaming_handler.clear_accumulated(&tab_id);
 
                             // 重新调用 chat_stream 继续完成
-                            eprintln!("🔄 请求AI继续完成所有子文件夹的检查");
+                            tracing::debug!("请求AI继续完成所有子文件夹的检查");
                             let (_, mut continue_check_cancel_rx) = tokio::sync::oneshot::channel();
                             begin_next_stream_round(&mut stream_ctx);
-                            match provider_clone
-                              .chat_stream(
+                            match service_clone
+                              .provider_chat_stream(
+                                &chat_slot,
+                                &tab_id,
+                                &provider_name_clone,
+                                &provider_clone,
                                 &current_messages,
                                 &model_config_clone,
                                 &mut continue_check_cancel_rx,
@@ -4762,25 +4930,25 @@ pub async fn ai_chat_stream(
                               .await
                             {
                               Ok(continue_stream) => {
-                                eprintln!("✅ 成功请求AI继续完成文件检查");
+                                tracing::info!("成功请求AI继续完成文件检查");
                                 new_stream = continue_stream;
                                 continue_loop = true;
                                 // 继续循环处理新的流
                                 continue;
                               }
                               Err(e) => {
-                                eprintln!("❌ 请求AI继续完成文件检查失败: {}", e);
+                                tracing::error!("请求AI继续完成文件检查失败: {}", e);
                                 // 如果继续失败，至少保存当前回复
-                                eprintln!(
-                                  "📝 保存当前回复（长度={}）",
+                                tracing::debug!(
+                                  "保存当前回复（长度={}）",
                                   new_accumulated_text_clone.len()
                                 );
                               }
                             }
                           } else {
                             // 无法获取根目录信息，正常保存
-                            eprintln!(
-                              "📝 流正常结束，保存 assistant 回复到消息历史（长度={}，完整={}）",
+                            tracing::debug!(
+                              "流正常结束，保存 assistant 回复到消息历史（长度={}，完整={}）",
                               new_accumulated_text_clone.len(),
                               reply_complete
                             );
@@ -4867,7 +5035,7 @@ pub async fn ai_chat_stream(
                               && has_list_files_tool
                               && !reply_has_file_list)
                           {
-                            eprintln!("⚠️ 用户要求检查文件，AI已调用工具但回复中没有完整列出文件（长度={}），要求AI给出完整的文件列表", new_accumulated_text_clone.len());
+                            tracing::warn!("用户要求检查文件，AI已调用工具但回复中没有完整列出文件（长度={}），要求AI给出完整的文件列表", new_accumulated_text_clone.len());
 
                             // 将当前回复添加到消息历史
                             if !new_accumulated_text_clone.is_empty() {
@@ -4900,11 +5068,15 @@ pub async fn ai_chat_stream(
                             new_streaming_handler.clear_accumulated(&tab_id);
 
                             // 重新调用 chat_stream 继续完成
-                            eprintln!("🔄 要求AI给出完整的文件列表总结");
+                            tracing::debug!("要求AI给出完整的文件列表总结");
                             let (_, mut file_list_cancel_rx) = tokio::sync::oneshot::channel();
                             begin_next_stream_round(&mut stream_ctx);
-                            match provider_clone
-                              .chat_stream(
+                            match service_clone
+                              .provider_chat_stream(
+                                &chat_slot,
+                                &tab_id,
+                                &provider_name_clone,
+                                &provider_clone,
                                 &current_messages,
                                 &model_config_clone,
                                 &mut file_list_cancel_rx,
@@ -4913,25 +5085,25 @@ pub async fn ai_chat_stream(
                               .await
                             {
                               Ok(file_list_stream) => {
-                                eprintln!("✅ 成功要求AI给出完整的文件列表总结");
+                                tracing::info!("成功要求AI给出完整的文件列表总结");
                                 new_stream = file_list_stream;
                                 continue_loop = true;
                                 // 继续循环处理新的流
                                 continue;
                               }
                               Err(e) => {
-                                eprintln!("❌ 要求AI给出完整的文件列表总结失败: {}", e);
+                                tracing::error!("要求AI给出完整的文件列表总结失败: {}", e);
                                 // 如果继续失败，至少保存当前回复
-                                eprintln!(
-                                  "📝 保存当前回复（长度={}）",
+                                tracing::debug!(
+                                  "保存当前回复（长度={}）",
                                   new_accumulated_text_clone.len()
                                 );
                               }
                             }
                           } else {
                             // 正常保存
-                            eprintln!(
-                              "📝 流正常结束，保存 assistant 回复到消息历史（长度={}，完整={}）",
+                            tracing::debug!(
+                              "流正常结束，保存 assistant 回复到消息历史（长度={}，完整={}）",
                               new_accumulated_text_clone.len(),
                               reply_complete
                             );
@@ -4957,15 +5129,15 @@ pub async fn ai_chat_stream(
                 if continue_loop && !new_tool_results.is_empty() {
                   tool_round_count += 1;
                   if tool_round_count > max_tool_rounds {
-                    eprintln!("🚫 工具调用轮次超过上限 ({})，终止循环", max_tool_rounds);
+                    tracing::debug!("工具调用轮次超过上限 ({})，终止循环", max_tool_rounds);
                     let _ = app_handle.emit("ai-stream-error", serde_json::json!({
                                             "tab_id": tab_id,
                                             "error": format!("工具调用轮次超过上限（{}轮），已自动终止。", max_tool_rounds)
                                         }));
                     continue_loop = false;
                   } else {
-                    eprintln!(
-                      "🔄 检测到继续对话中的工具调用，准备再次继续对话: 工具调用数量={}",
+                    tracing::debug!(
+                      "检测到继续对话中的工具调用，准备再次继续对话: 工具调用数量={}",
                       new_tool_results.len()
                     );
 
@@ -4999,8 +5171,8 @@ pub async fn ai_chat_stream(
                     let task_progress_info = TaskProgressAnalyzer::analyze(&all_tool_results);
                     let task_progress = task_progress_info.progress_hint.clone();
 
-                    eprintln!(
-                      "📊 任务进度分析结果：{}",
+                    tracing::debug!(
+                      "任务进度分析结果：{}",
                       if task_progress.is_empty() {
                         "任务已完成或无需进度检查"
                       } else {
@@ -5198,25 +5370,24 @@ pub async fn ai_chat_stream(
                     // ⚠️ 关键修复：为下一轮对话创建新的取消通道并注册
                     let (next_cancel_tx, mut next_cancel_rx) = tokio::sync::oneshot::channel();
                     {
-                      let mut channels = CANCEL_CHANNELS.lock().unwrap();
-                      channels.insert(tab_id.clone(), next_cancel_tx);
-                      eprintln!("✅ 下一轮对话时注册新的取消通道: tab_id={}", tab_id);
+                      stream_handle.set_cancel_sender(next_cancel_tx);
+                      tracing::info!("下一轮对话时注册新的取消通道: tab_id={}", tab_id);
                     }
                     // ⚠️ 关键修复：为下一轮对话创建新的取消标志并注册
-                    let next_cancel_flag = Arc::new(Mutex::new(false));
-                    {
-                      let mut flags = CANCEL_FLAGS.lock().unwrap();
-                      flags.insert(tab_id.clone(), next_cancel_flag.clone());
-                      eprintln!("✅ 下一轮对话时注册新的取消标志: tab_id={}", tab_id);
-                    }
+                    let next_cancel_flag = stream_handle.renew_cancel_flag();
+                    tracing::info!("下一轮对话时注册新的取消标志: tab_id={}", tab_id);
                     // 更新 continue_cancel_flag_for_stream 为新的标志
                     let continue_cancel_flag_for_stream = next_cancel_flag.clone();
                     let mut retry_count_inner = 0;
                     let max_retries_inner = 2;
                     let mut next_stream_result = loop {
                       begin_next_stream_round(&mut stream_ctx);
-                      match provider_clone
-                        .chat_stream(
+                      match service_clone
+                        .provider_chat_stream(
+                          &chat_slot,
+                          &tab_id,
+                          &provider_name_clone,
+                          &provider_clone,
                           &current_messages,
                           &model_config_clone,
                           &mut next_cancel_rx,
@@ -5239,8 +5410,8 @@ pub async fn ai_chat_stream(
                           {
                             if retry_count_inner < max_retries_inner {
                               retry_count_inner += 1;
-                              eprintln!(
-                                "⚠️ Token超限，尝试截断消息历史（第 {} 次重试）",
+                              tracing::warn!(
+                                "Token超限，尝试截断消息历史（第 {} 次重试）",
                                 retry_count_inner
                               );
                               // 更激进的截断：只保留系统消息和最后5条消息
@@ -5249,24 +5420,23 @@ pub async fn ai_chat_stream(
                                   &mut current_messages,
                                   TruncationStrategy::KeepRecent(5),
                                 );
-                                eprintln!("📝 截断后消息数量: {}", current_messages.len());
+                                tracing::debug!("截断后消息数量: {}", current_messages.len());
                               }
                               // 重新创建cancel channel
                               // ⚠️ 关键修复：重新创建cancel channel并注册
                               let (next_cancel_tx2, mut next_cancel_rx2) =
                                 tokio::sync::oneshot::channel();
                               {
-                                let mut channels = CANCEL_CHANNELS.lock().unwrap();
-                                channels.insert(tab_id.clone(), next_cancel_tx2);
-                                eprintln!(
-                                  "✅ Token超限重试时注册新的取消通道（下一轮）: tab_id={}",
+                                stream_handle.set_cancel_sender(next_cancel_tx2);
+                                tracing::info!(
+                                  "Token超限重试时注册新的取消通道（下一轮）: tab_id={}",
                                   tab_id
                                 );
                               }
                               next_cancel_rx = next_cancel_rx2;
                               continue;
                             } else {
-                              eprintln!("❌ Token超限，已重试 {} 次仍失败", max_retries_inner);
+                              tracing::error!("Token超限，已重试 {} 次仍失败", max_retries_inner);
                               break Err(e);
                             }
                           } else {
@@ -5279,12 +5449,12 @@ pub async fn ai_chat_stream(
 
                     match next_stream_result {
                       Ok(next_stream) => {
-                        eprintln!("✅ 再次调用 chat_stream 成功，继续处理流式响应");
+                        tracing::info!("再次调用 chat_stream 成功，继续处理流式响应");
                         new_stream = next_stream;
                         // continue_loop 已经是 true，会继续循环
                       }
                       Err(e) => {
-                        eprintln!("❌ 再次调用 chat_stream 失败: {}", e);
+                        tracing::error!("再次调用 chat_stream 失败: {}", e);
                         continue_loop = false;
                       }
                     }
@@ -5295,8 +5465,8 @@ pub async fn ai_chat_stream(
                 {
                   let flag = continue_cancel_flag_for_stream.lock().unwrap();
                   if *flag {
-                    eprintln!(
-                      "🛑 继续对话循环结束前检测到取消标志，停止处理: tab_id={}",
+                    tracing::debug!(
+                      "继续对话循环结束前检测到取消标志，停止处理: tab_id={}",
                       tab_id
                     );
                     finalize_stream(&mut stream_ctx, StreamState::Cancelled);
@@ -5306,15 +5476,8 @@ pub async fn ai_chat_stream(
                       &stream_ctx,
                       Some("用户取消了请求"),
                     );
-                    // ⚠️ 关键修复：清理取消通道和标志
-                    {
-                      let mut channels = CANCEL_CHANNELS.lock().unwrap();
-                      channels.remove(&tab_id);
-                    }
-                    {
-                      let mut flags = CANCEL_FLAGS.lock().unwrap();
-                      flags.remove(&tab_id);
-                    }
+                    // ⚠️ 关键修复：清理本流的登记
+                    stream_handle.finish();
                     return;
                   }
                 }
@@ -5355,7 +5518,7 @@ pub async fn ai_chat_stream(
 
                   if final_task_completed && !final_has_summary && !current_text_has_summary {
                     // 任务完成但没有总结，要求总结
-                    eprintln!("📋 循环结束，任务已完成但无总结，要求AI做工作总结");
+                    tracing::debug!("循环结束，任务已完成但无总结，要求AI做工作总结");
 
                     // 如果当前有文本，先保存
                     if !new_accumulated_text_clone.is_empty() {
@@ -5391,21 +5554,24 @@ pub async fn ai_chat_stream(
                     );
 
                     // 重新调用 chat_stream 获取总结
-                    eprintln!("🔄 要求AI做工作总结，重新调用 chat_stream");
+                    tracing::debug!("要求AI做工作总结，重新调用 chat_stream");
                     // ⚠️ 关键修复：为最终总结创建新的取消通道并注册
                     let (final_summary_cancel_tx, mut final_summary_cancel_rx) =
                       tokio::sync::oneshot::channel();
                     {
-                      let mut channels = CANCEL_CHANNELS.lock().unwrap();
-                      channels.insert(tab_id.clone(), final_summary_cancel_tx);
-                      eprintln!("✅ 最终总结时注册新的取消通道: tab_id={}", tab_id);
+                      stream_handle.set_cancel_sender(final_summary_cancel_tx);
+                      tracing::info!("最终总结时注册新的取消通道: tab_id={}", tab_id);
                     }
                     let mut final_summary_retry_count = 0;
                     let max_final_summary_retries = 2;
                     let mut final_summary_stream_result = loop {
                       begin_next_stream_round(&mut stream_ctx);
-                      match provider_clone
-                        .chat_stream(
+                      match service_clone
+                        .provider_chat_stream(
+                          &chat_slot,
+                          &tab_id,
+                          &provider_name_clone,
+                          &provider_clone,
                           &current_messages,
                           &model_config_clone,
                           &mut final_summary_cancel_rx,
@@ -5428,8 +5594,8 @@ pub async fn ai_chat_stream(
                           {
                             if final_summary_retry_count < max_final_summary_retries {
                               final_summary_retry_count += 1;
-                              eprintln!(
-                                "⚠️ Token超限，尝试截断消息历史（第 {} 次重试）",
+                              tracing::warn!(
+                                "Token超限，尝试截断消息历史（第 {} 次重试）",
                                 final_summary_retry_count
                               );
                               // 更激进的截断：只保留系统消息和最后5条消息
@@ -5438,25 +5604,24 @@ pub async fn ai_chat_stream(
                                   &mut current_messages,
                                   TruncationStrategy::KeepRecent(5),
                                 );
-                                eprintln!("📝 截断后消息数量: {}", current_messages.len());
+                                tracing::debug!("截断后消息数量: {}", current_messages.len());
                               }
                               // 重新创建cancel channel
                               // ⚠️ 关键修复：重新创建cancel channel并注册
                               let (final_summary_cancel_tx2, mut final_summary_cancel_rx2) =
                                 tokio::sync::oneshot::channel();
                               {
-                                let mut channels = CANCEL_CHANNELS.lock().unwrap();
-                                channels.insert(tab_id.clone(), final_summary_cancel_tx2);
-                                eprintln!(
-                                  "✅ Token超限重试时注册新的取消通道（最终总结）: tab_id={}",
+                                stream_handle.set_cancel_sender(final_summary_cancel_tx2);
+                                tracing::info!(
+                                  "Token超限重试时注册新的取消通道（最终总结）: tab_id={}",
                                   tab_id
                                 );
                               }
                               final_summary_cancel_rx = final_summary_cancel_rx2;
                               continue;
                             } else {
-                              eprintln!(
-                                "❌ Token超限，已重试 {} 次仍失败",
+                              tracing::error!(
+                                "Token超限，已重试 {} 次仍失败",
                                 max_final_summary_retries
                               );
                               break Err(e);
@@ -5471,7 +5636,7 @@ pub async fn ai_chat_stream(
 
                     match final_summary_stream_result {
                       Ok(mut final_summary_stream) => {
-                        eprintln!("✅ 获取工作总结，重新调用 chat_stream 成功");
+                        tracing::info!("获取工作总结，重新调用 chat_stream 成功");
                         // 处理总结流
                         let mut summary_text = String::new();
                         while let Some(result) = final_summary_stream.next().await {
@@ -5488,8 +5653,10 @@ pub async fn ai_chat_stream(
                                         "done": false,
                                     });
                                     if let Err(e) = app_handle.emit("ai-chat-stream", payload) {
-                                      eprintln!("发送事件失败: {}", e);
+                                      tracing::warn!("发送事件失败: {}", e);
                                     }
+                                    grounding::record_text(&tab_id, &text);
+                                    stream_handle.push_text(&text);
                                   }
                                 }
                                 ChatChunk::ToolCall { .. } => {
@@ -5498,17 +5665,17 @@ pub async fn ai_chat_stream(
                               }
                             }
                             Err(e) => {
-                              eprintln!("❌ 获取总结时发生错误: {}", e);
+                              tracing::error!("获取总结时发生错误: {}", e);
                               break;
                             }
                           }
                         }
 
                         // 总结完成
-                        eprintln!("✅ 工作总结完成，文本长度: {}", summary_text.len());
+                        tracing::info!("工作总结完成，文本长度: {}", summary_text.len());
                       }
                       Err(e) => {
-                        eprintln!("❌ 获取工作总结失败: {}", e);
+                        tracing::error!("获取工作总结失败: {}", e);
                       }
                     }
                   }
@@ -5516,21 +5683,18 @@ pub async fn ai_chat_stream(
               }
             }
             Err(e) => {
-              eprintln!("❌ 重新调用 chat_stream 失败: {}", e);
+              tracing::error!("重新调用 chat_stream 失败: {}", e);
             }
           }
         }
 
-        // 清理取消通道
-        {
-          let mut channels = CANCEL_CHANNELS.lock().unwrap();
-          channels.remove(&tab_id_clone);
-          eprintln!("🧹 清理取消通道: tab_id={}", tab_id_clone);
-        }
+        // 清理本流的登记
+        stream_handle.finish();
+        tracing::debug!("清理对话流登记: tab_id={}", tab_id_clone);
 
         // 统一收尾：避免「已取消」又发一次 completed 的 done
         if stream_ctx.state == StreamState::Cancelled {
-          eprintln!("🛑 流已取消，不再发送完成信号: tab_id={}", tab_id);
+          tracing::debug!("流已取消，不再发送完成信号: tab_id={}", tab_id);
         } else {
           if stream_ctx.state == StreamState::Streaming {
             finalize_stream(&mut stream_ctx, StreamState::Completed);
@@ -5562,7 +5726,7 @@ pub async fn ai_chat_stream(
               )
               .await;
             });
-            eprintln!(
+            tracing::debug!(
               "[memory] MEMORY_WRITE_QUEUED: tab memory extraction triggered for tab={}",
               tab_id
             );
@@ -5571,34 +5735,37 @@ pub async fn ai_chat_stream(
           emit_ai_chat_stream_done(&app_handle, &tab_id, &stream_ctx, None);
         }
       });
+      stream_handle.attach_task(stream_task.abort_handle());
 
       Ok(())
     }
+    Err(crate::services::ai_error::AIError::Cancelled) => {
+      // 排队等待名额时被取消（用户取消或同一标签页重新发起对话）
+      tracing::info!("对话在排队时被取消: tab_id={}", tab_id);
+      stream_handle.finish();
+      let mut stream_ctx = StreamContext::default();
+      finalize_stream(&mut stream_ctx, StreamState::Cancelled);
+      emit_ai_chat_stream_done(&app, &tab_id, &stream_ctx, Some("用户取消了请求"));
+      Ok(())
+    }
     Err(e) => {
-      // ⚠️ 关键修复：当 chat_stream 失败时，清理取消通道和标志，并发送错误事件
-      eprintln!("❌ chat_stream 调用失败: {}", e);
+      // ⚠️ 关键修复：当 chat_stream 失败时，清理对话流登记，并发送错误事件
+      tracing::error!("chat_stream 调用失败: {}", e);
 
-      // 清理取消通道和标志
-      {
-        let mut channels = CANCEL_CHANNELS.lock().unwrap();
-        channels.remove(&tab_id);
-      }
-      {
-        let mut flags = CANCEL_FLAGS.lock().unwrap();
-        flags.remove(&tab_id);
-      }
-      eprintln!(
-        "🧹 清理取消通道和标志（chat_stream 失败）: tab_id={}",
-        tab_id
-      );
+      // 清理本流的登记
+      stream_handle.finish();
+      tracing::warn!("清理对话流登记（chat_stream 失败）: tab_id={}", tab_id);
 
       // 发送错误事件给前端（统一 stream_state）
-      let error_message = format!("AI 请求失败: {}", e);
+      let error_message = i18n::t(
+        "error.ai_request_failed",
+        &[("detail", &AppError::from(e.clone()).localized_message())],
+      );
       let mut stream_ctx_err = StreamContext::default();
       finalize_stream(&mut stream_ctx_err, StreamState::Completed);
       emit_ai_chat_stream_done(&app, &tab_id, &stream_ctx_err, Some(&error_message));
 
-      Err(error_message)
+      Err(e.into())
     }
   }
 }
@@ -5608,21 +5775,16 @@ pub async fn ai_save_api_key(
   provider: String,
   key: String,
   service: State<'_, AIServiceState>,
-) -> Result<(), String> {
-  let service_guard = service
-    .lock()
-    .map_err(|e| format!("获取 AI 服务失败: {}", e))?;
-
-  service_guard.save_api_key(&provider, &key)?;
+) -> Result<(), AppError> {
+  service.save_api_key(&provider, &key)?;
+  if plugins::with_registry(|r| r.providers().any(|p| p.name == provider)) {
+    service.register_plugin_providers();
+  }
 
   // 重新注册提供商
   if provider == "openai" {
     let openai_provider = Arc::new(crate::services::ai_providers::OpenAIProvider::new(key));
-    drop(service_guard); // 释放锁
-    let service_guard = service
-      .lock()
-      .map_err(|e| format!("获取 AI 服务失败: {}", e))?;
-    service_guard.register_provider("openai".to_string(), openai_provider);
+    service.register_provider("openai".to_string(), openai_provider);
   }
 
   Ok(())
@@ -5632,68 +5794,99 @@ pub async fn ai_save_api_key(
 pub async fn ai_get_api_key(
   provider: String,
   service: State<'_, AIServiceState>,
-) -> Result<Option<String>, String> {
-  let service_guard = service
-    .lock()
-    .map_err(|e| format!("获取 AI 服务失败: {}", e))?;
-
-  match service_guard.get_api_key(&provider) {
+) -> Result<Option<String>, AppError> {
+  match service.get_api_key(&provider) {
     Ok(key) => Ok(Some(key)),
     Err(_) => Ok(None), // 密钥不存在，返回 None
   }
 }
 
+/// 取消该标签页的对话流（包括仍在排队等待名额的对话），不影响其他标签页；
+/// 对话以标签页 ID 排队，也可直接传入其他请求的 ID 取消排队中的请求
 #[tauri::command]
 pub async fn ai_cancel_request(
-  request_id: String,
+  tab_id: String,
+  app: tauri::AppHandle,
+  streams: State<'_, ChatStreamRegistryState>,
   service: State<'_, AIServiceState>,
-) -> Result<bool, String> {
-  let service_guard = service
-    .lock()
-    .map_err(|e| format!("获取 AI 服务失败: {}", e))?;
+) -> Result<bool, AppError> {
+  let stream_cancelled = cancel_tab_stream(&app, streams.inner(), &tab_id);
+  let queue_cancelled = service.cancel_request(&tab_id);
+  Ok(stream_cancelled || queue_cancelled)
+}
 
-  Ok(service_guard.cancel_request(&request_id))
+/// 通知对话流停止；宽限期后仍未收尾则终止任务，并代为发送已取消的完成事件
+fn cancel_tab_stream(
+  app: &tauri::AppHandle,
+  streams: &ChatStreamRegistryState,
+  tab_id: &str,
+) -> bool {
+  let Some(stream_id) = streams.cancel(tab_id) else {
+    return false;
+  };
+  tracing::info!("已通知对话流取消: tab_id={}", tab_id);
+  let (app, streams, tab_id) = (app.clone(), streams.clone(), tab_id.to_string());
+  tokio::spawn(async move {
+    tokio::time::sleep(CHAT_STREAM_CANCEL_GRACE).await;
+    if let Some(accumulated) = streams.abort(&tab_id, stream_id) {
+      tracing::warn!(
+        "对话流未响应取消，强制终止: tab_id={}, 已输出 {} 字节",
+        tab_id,
+        accumulated.len()
+      );
+      let mut stream_ctx = StreamContext::default();
+      finalize_stream(&mut stream_ctx, StreamState::Cancelled);
+      emit_ai_chat_stream_done(&app, &tab_id, &stream_ctx, Some("用户取消了请求"));
+    }
+  });
+  true
 }
 
 /// AI 文档分析命令
 ///
 /// # 参数
 /// - `content`: 文档内容
-/// - `analysis_type`: 分析类型 ("summarize", "keywords", "references", "entities")
+/// - `analysis_type`: 分析类型 ("summarize", "keywords", "references", "entities", "outline",
+///   "action_items", "sentiment", "qa")
+/// - `question`: "qa" 类型要回答的问题
+/// - `request_id`: 提供时以 `ai-analysis-stream` 事件逐段推送模型输出
 /// - `service`: AI 服务状态
 ///
 /// # 返回
-/// 分析结果的 JSON 字符串
+/// 分析结果；outline / action_items / sentiment / qa 为固定结构的 JSON 字符串
 #[tauri::command]
 pub async fn ai_analyze_document(
   content: String,
   analysis_type: String,
+  question: Option<String>,
+  request_id: Option<String>,
+  app: tauri::AppHandle,
   service: State<'_, AIServiceState>,
-) -> Result<String, String> {
+) -> Result<String, AppError> {
   // 解析分析类型
-  let analysis_type_enum = match analysis_type.as_str() {
-    "summarize" => AnalysisType::Summarize,
-    "keywords" => AnalysisType::ExtractKeywords,
-    "references" => AnalysisType::FindReferences,
-    "entities" => AnalysisType::ExtractEntities,
-    _ => return Err(format!("不支持的分析类型: {}", analysis_type)),
-  };
+  let analysis_type_enum = AnalysisType::parse(&analysis_type)
+    .ok_or_else(|| AppError::InvalidInput(format!("不支持的分析类型: {}", analysis_type)))?;
+  let question = question.filter(|q| !q.trim().is_empty());
+  if matches!(analysis_type_enum, AnalysisType::QuestionAnswer) && question.is_none() {
+    return Err(AppError::InvalidInput("请输入要提问的问题".to_string()));
+  }
 
   // 构建分析提示词
-  let prompt = DocumentAnalysisService::build_analysis_prompt(&content, &analysis_type_enum);
+  let prompt = DocumentAnalysisService::build_analysis_prompt(
+    &content,
+    &analysis_type_enum,
+    question.as_deref(),
+  );
 
   // 获取 AI provider（优先 DeepSeek，然后是 OpenAI）
   let provider = {
-    let service_guard = service
-      .lock()
-      .map_err(|e| format!("获取 AI 服务失败: {}", e))?;
-    service_guard
-      .get_provider("deepseek")
-      .or_else(|| service_guard.get_provider("openai"))
+    service
+      .get_provider(&settings_service::default_ai_provider())
+      .or_else(|| service.get_provider("deepseek"))
+      .or_else(|| service.get_provider("openai"))
   };
 
-  let provider = provider
-    .ok_or_else(|| "未配置任何 AI 提供商，请先配置 DeepSeek 或 OpenAI API key".to_string())?;
+  let provider = provider.ok_or(AppError::AiNotConfigured)?;
 
   // 构建消息
   let messages = vec![ChatMessage {
@@ -5716,25 +5909,572 @@ pub async fn ai_analyze_document(
     .await
     .map_err(|e| format!("AI 分析失败: {}", e))?;
 
-  // 收集响应
+  // 收集响应，同时推送给前端
   let mut response = String::new();
   use tokio_stream::StreamExt;
   while let Some(chunk_result) = stream.next().await {
     match chunk_result {
       Ok(chunk) => {
         match chunk {
-          ChatChunk::Text(text) => response.push_str(&text),
+          ChatChunk::Text(text) => {
+            if let Some(request_id) = &request_id {
+              let _ = app.emit(
+                "ai-analysis-stream",
+                serde_json::json!({
+                    "request_id": request_id,
+                    "chunk": text,
+                    "done": false,
+                }),
+              );
+            }
+            response.push_str(&text);
+          }
           ChatChunk::ToolCall { .. } => {
             // 工具调用在文档分析中不需要处理
             continue;
           }
         }
       }
-      Err(e) => return Err(format!("AI 流式响应错误: {}", e)),
+      Err(e) => {
+        if let Some(request_id) = &request_id {
+          let _ = app.emit(
+            "ai-analysis-stream",
+            serde_json::json!({
+                "request_id": request_id,
+                "chunk": "",
+                "done": true,
+                "error": e.to_string(),
+            }),
+          );
+        }
+        return Err(e.into());
+      }
     }
   }
 
-  Ok(response)
+  let result = DocumentAnalysisService::normalize_response(&response, &analysis_type_enum);
+  if let Some(request_id) = &request_id {
+    let _ = app.emit(
+      "ai-analysis-stream",
+      serde_json::json!({
+          "request_id": request_id,
+          "chunk": "",
+          "done": true,
+          "result": result,
+      }),
+    );
+  }
+  Ok(result)
+}
+
+/// analyze_scanned_pdf 的后台任务：逐页取得文本后分析
+async fn analyze_scanned_pdf_job(
+  ctx: JobContext,
+  pdf_path: PathBuf,
+  analysis_type: String,
+  analysis: AnalysisType,
+  lang: Option<String>,
+  max_pages: u32,
+  provider: Arc<dyn AIProvider>,
+) -> Result<ScannedPdfAnalysis, String> {
+  let layer_path = pdf_path.clone();
+  let (renderer, text_layer, page_count) = tokio::task::spawn_blocking(move || {
+    let renderer = PdfPageRenderer::new()?;
+    let pages: Vec<u32> = (1..=max_pages).collect();
+    // 文本层解析失败时全部页面走 OCR
+    match pdf_text::extract_pdf_text(&layer_path, Some(&pages)) {
+      Ok(extraction) if extraction.encrypted => Err("PDF 已加密，需要密码才能读取".to_string()),
+      Ok(extraction) => Ok((renderer, extraction.pages, extraction.page_count)),
+      Err(_) => {
+        let page_count = renderer.page_count(&layer_path)?;
+        Ok((renderer, Vec::new(), page_count))
+      }
+    }
+  })
+  .await
+  .map_err(|e| format!("读取 PDF 失败: {}", e))??;
+
+  let renderer = Arc::new(renderer);
+  let ocr = Arc::new(OcrService::new());
+  let processed = page_count.min(max_pages);
+  let needs_ocr = (1..=processed).any(|page| {
+    !text_layer
+      .iter()
+      .any(|l| l.page == page && scanned_pdf::has_text_layer(l))
+  });
+  let lang = if needs_ocr {
+    if !ocr.is_available() {
+      return Err("未检测到 Tesseract，无法识别扫描页面".to_string());
+    }
+    let ocr = ocr.clone();
+    Some(
+      tokio::task::spawn_blocking(move || match lang {
+        Some(lang) if !lang.trim().is_empty() => lang.trim().to_string(),
+        _ => ocr.default_language(),
+      })
+      .await
+      .map_err(|e| format!("读取识别语言失败: {}", e))?,
+    )
+  } else {
+    None
+  };
+
+  let mut pages = Vec::with_capacity(processed as usize);
+  for page in 1..=processed {
+    if ctx.is_cancelled() {
+      return Err("分析已取消".to_string());
+    }
+    ctx.set_progress(
+      Some((page - 1) as f64 / (processed + 1) as f64),
+      Some(format!("正在读取第 {}/{} 页", page, processed)),
+    );
+    let layer = text_layer.iter().find(|l| l.page == page).cloned();
+    let (renderer, ocr, page_path) = (renderer.clone(), ocr.clone(), pdf_path.clone());
+    let page_lang = lang.clone().unwrap_or_default();
+    let scanned = tokio::task::spawn_blocking(move || {
+      scanned_pdf::page_text(
+        &renderer,
+        &ocr,
+        &page_path,
+        page,
+        layer.as_ref(),
+        &page_lang,
+      )
+    })
+    .await
+    .map_err(|e| format!("读取页面失败: {}", e))??;
+    pages.push(scanned);
+  }
+
+  let text = scanned_pdf::combined_text(&pages);
+  if text.trim().is_empty() {
+    return Err("未能从 PDF 中识别出文字".to_string());
+  }
+  ctx.set_progress(
+    Some(processed as f64 / (processed + 1) as f64),
+    Some("正在分析".to_string()),
+  );
+  let prompt = DocumentAnalysisService::build_analysis_prompt(&text, &analysis, None);
+  let analysis_text = collect_chat_text(&provider, prompt, &ModelConfig::default())
+    .await
+    .map_err(|e| e.to_string())?;
+  Ok(ScannedPdfAnalysis {
+    path: pdf_path.to_string_lossy().to_string(),
+    analysis_type,
+    analysis: DocumentAnalysisService::normalize_response(&analysis_text, &analysis),
+    lang,
+    page_count,
+    pages,
+    truncated: page_count > processed,
+  })
+}
+
+/// 扫描版 PDF 分析：逐页取得文本（有文本层的页面直接提取，其余渲染后 OCR），再按
+/// analysis_type（summarize / keywords / references / entities / outline / action_items /
+/// sentiment）交给 AI 分析
+///
+/// 作为后台任务运行，最多处理 max_pages 页（默认同 OCR 上限）。
+#[tauri::command]
+pub async fn analyze_scanned_pdf(
+  path: String,
+  analysis_type: String,
+  lang: Option<String>,
+  max_pages: Option<u32>,
+  service: State<'_, AIServiceState>,
+) -> Result<ScannedPdfAnalysis, AppError> {
+  telemetry::record_feature("analyze_scanned_pdf");
+  let pdf_path = PathBuf::from(&path);
+  if !pdf_path.is_file() {
+    return Err(AppError::InvalidInput(format!("文件不存在: {}", path)));
+  }
+  let analysis = AnalysisType::parse(&analysis_type)
+    .filter(|a| !matches!(a, AnalysisType::QuestionAnswer))
+    .ok_or_else(|| AppError::InvalidInput(format!("不支持的分析类型: {}", analysis_type)))?;
+  let max_pages = max_pages.unwrap_or(OCR_DEFAULT_MAX_PAGES).max(1);
+
+  let provider = service
+    .get_provider(&settings_service::default_ai_provider())
+    .or_else(|| service.get_provider("deepseek"))
+    .or_else(|| service.get_provider("openai"))
+    .ok_or(AppError::AiNotConfigured)?;
+
+  let title = format!(
+    "分析扫描件 {}",
+    pdf_path.file_name().unwrap_or_default().to_string_lossy()
+  );
+  Ok(
+    job_service::jobs()
+      .run(JobSpec::new("analyze_scanned_pdf", title), move |ctx| {
+        analyze_scanned_pdf_job(
+          ctx,
+          pdf_path,
+          analysis_type,
+          analysis,
+          lang,
+          max_pages,
+          provider,
+        )
+      })
+      .await?,
+  )
+}
+
+/// AI 校对：返回逐条问题（字符范围、类别、原文、建议、说明），供编辑器逐条接受 / 拒绝
+///
+/// 长文档按行切分后依次校对，问题数达到 `options.max_issues` 后停止。
+#[tauri::command]
+pub async fn ai_proofread(
+  content: String,
+  options: Option<ProofreadOptions>,
+  service: State<'_, AIServiceState>,
+) -> Result<ProofreadResult, AppError> {
+  telemetry::record_feature("ai_proofread");
+  let options = options.unwrap_or_default();
+  if options.max_issues == 0 {
+    return Err(AppError::InvalidInput("max_issues 必须大于 0".to_string()));
+  }
+
+  let provider = service
+    .get_provider(&settings_service::default_ai_provider())
+    .or_else(|| service.get_provider("deepseek"))
+    .or_else(|| service.get_provider("openai"))
+    .ok_or(AppError::AiNotConfigured)?;
+  // 校对需要稳定、可复现的输出
+  let model_config = ModelConfig {
+    temperature: 0.2,
+    max_tokens: 4000,
+    ..ModelConfig::default()
+  };
+
+  let mut issues = Vec::new();
+  let mut stopped_early = false;
+  for chunk in ProofreadService::split_chunks(&content) {
+    if issues.len() >= options.max_issues {
+      stopped_early = true;
+      break;
+    }
+    let prompt = ProofreadService::build_prompt(chunk.text, &options);
+    let response = collect_chat_text(&provider, prompt, &model_config).await?;
+    issues.extend(ProofreadService::parse_response(
+      &response, &chunk, &options,
+    )?);
+  }
+  let mut result = ProofreadService::finish(issues, &options);
+  result.truncated |= stopped_early;
+  Ok(result)
+}
+
+/// AI 翻译：长文档按段落分段逐段翻译，按术语表约束译名
+///
+/// 作为后台任务运行，每译完一段发送 `ai-translate-progress` 事件；译文缺少规定译名时重译一次，
+/// 仍缺少的在 `glossary_violations` 中返回。
+#[tauri::command]
+pub async fn ai_translate(
+  content: String,
+  target_lang: String,
+  glossary_id: Option<String>,
+  workspace_path: Option<String>,
+  app: tauri::AppHandle,
+  service: State<'_, AIServiceState>,
+) -> Result<TranslationResult, AppError> {
+  telemetry::record_feature("ai_translate");
+  let target_lang = target_lang.trim().to_string();
+  if target_lang.is_empty() {
+    return Err(AppError::InvalidInput("目标语言不能为空".to_string()));
+  }
+  let terms = match glossary_id.as_deref().filter(|id| !id.is_empty()) {
+    Some(id) => {
+      let workspace = workspace_path
+        .as_deref()
+        .ok_or_else(|| AppError::InvalidInput("使用术语表时需要工作区路径".to_string()))?;
+      translation::get_glossary(std::path::Path::new(workspace), id)?
+        .ok_or_else(|| AppError::InvalidInput(format!("术语表不存在: {}", id)))?
+        .terms
+    }
+    None => Vec::new(),
+  };
+
+  let provider = service
+    .get_provider(&settings_service::default_ai_provider())
+    .or_else(|| service.get_provider("deepseek"))
+    .or_else(|| service.get_provider("openai"))
+    .ok_or(AppError::AiNotConfigured)?;
+  // 低温度保证术语与风格前后一致
+  let model_config = ModelConfig {
+    temperature: 0.2,
+    max_tokens: 4000,
+    ..ModelConfig::default()
+  };
+
+  let result = job_service::jobs()
+    .run(
+      JobSpec::new("ai_translate", format!("翻译为{}", target_lang)),
+      move |ctx| async move {
+        let chunks = translation::split_chunks(&content);
+        let total = chunks.len();
+        let mut text = String::with_capacity(content.len());
+        let mut glossary_violations = Vec::new();
+        for (index, chunk) in chunks.into_iter().enumerate() {
+          if ctx.is_cancelled() {
+            return Err("翻译已取消".to_string());
+          }
+          if chunk.trim().is_empty() {
+            text.push_str(chunk);
+            continue;
+          }
+          ctx.set_progress(
+            Some(index as f64 / total as f64),
+            Some(format!("正在翻译第 {}/{} 段", index + 1, total)),
+          );
+          let relevant = translation::relevant_terms(&terms, chunk);
+          let prompt = translation::build_prompt(chunk, &target_lang, &relevant);
+          let mut translated = collect_chat_text(&provider, prompt, &model_config)
+            .await
+            .map_err(|e| e.to_string())?;
+          let missing = translation::missing_terms(&translated, &relevant);
+          if !missing.is_empty() {
+            let prompt =
+              translation::build_correction_prompt(chunk, &translated, &target_lang, &missing);
+            translated = collect_chat_text(&provider, prompt, &model_config)
+              .await
+              .map_err(|e| e.to_string())?;
+          }
+          glossary_violations.extend(
+            translation::missing_terms(&translated, &relevant)
+              .into_iter()
+              .map(|t| TermViolation {
+                chunk: index,
+                source: t.source.clone(),
+                target: t.target.clone(),
+              }),
+          );
+          let translated = translation::finish_chunk(chunk, &translated);
+          let _ = app.emit(
+            "ai-translate-progress",
+            TranslationProgress {
+              job_id: ctx.id().to_string(),
+              index,
+              total,
+              text: translated.clone(),
+            },
+          );
+          text.push_str(&translated);
+        }
+        ctx.set_progress(Some(1.0), None);
+        Ok(TranslationResult {
+          text,
+          chunks: total,
+          glossary_violations,
+        })
+      },
+    )
+    .await?;
+  Ok(result)
+}
+
+/// AI 建议文件名：返回若干候选（标题 + 文件名），供保存未命名文档与导入时选择
+///
+/// extension 默认为 md；传入 directory 时避开其中已有的文件名。文档自带标题时作为首个候选。
+#[tauri::command]
+pub async fn ai_suggest_filename(
+  content: String,
+  extension: Option<String>,
+  directory: Option<String>,
+  count: Option<usize>,
+  service: State<'_, AIServiceState>,
+) -> Result<Vec<FilenameCandidate>, AppError> {
+  telemetry::record_feature("ai_suggest_filename");
+  if content.trim().is_empty() {
+    return Err(AppError::InvalidInput("文档内容为空".to_string()));
+  }
+  let count = count
+    .unwrap_or(filename_suggestion::DEFAULT_CANDIDATES)
+    .clamp(1, filename_suggestion::MAX_CANDIDATES);
+
+  let provider = service
+    .get_provider(&settings_service::default_ai_provider())
+    .or_else(|| service.get_provider("deepseek"))
+    .or_else(|| service.get_provider("openai"))
+    .ok_or(AppError::AiNotConfigured)?;
+  let model_config = ModelConfig {
+    temperature: 0.7,
+    max_tokens: 800,
+    ..ModelConfig::default()
+  };
+  let prompt = filename_suggestion::build_prompt(&content, count);
+  let response = collect_chat_text(&provider, prompt, &model_config).await?;
+
+  let candidates = filename_suggestion::finish_candidates(
+    &response,
+    &content,
+    extension.as_deref().unwrap_or("md"),
+    count,
+    directory.as_deref().map(std::path::Path::new),
+  );
+  if candidates.is_empty() {
+    return Err(AppError::from(format!(
+      "未能生成文件名: {}",
+      safe_truncate(&response, 200)
+    )));
+  }
+  Ok(candidates)
+}
+
+/// ai_summarize_folder 的后台任务：map 各文档要点，再 reduce 为总览
+async fn summarize_folder(
+  ctx: JobContext,
+  folder: PathBuf,
+  options: FolderSummaryOptions,
+  provider: Arc<dyn AIProvider>,
+  model_config: ModelConfig,
+) -> Result<FolderSummary, String> {
+  let walk_folder = folder.clone();
+  let walk_options = options.clone();
+  let (paths, overflow) = tokio::task::spawn_blocking(move || {
+    folder_summary::collect_documents(&walk_folder, &walk_options)
+  })
+  .await
+  .map_err(|e| format!("扫描文件夹失败: {}", e))?;
+  let mut skipped: Vec<SkippedDocument> = overflow
+    .iter()
+    .map(|p| SkippedDocument {
+      path: p.to_string_lossy().to_string(),
+      reason: format!("超出文档数上限 {}", options.max_documents),
+    })
+    .collect();
+
+  let total = paths.len();
+  let mut documents = Vec::new();
+  for (index, document_path) in paths.into_iter().enumerate() {
+    if ctx.is_cancelled() {
+      return Err("汇总已取消".to_string());
+    }
+    let relative_path = folder_summary::relative_path(&folder, &document_path);
+    ctx.set_progress(
+      Some(index as f64 / (total + 1) as f64),
+      Some(format!("正在总结 {}", relative_path)),
+    );
+    let max_chars = options.max_chars_per_document;
+    let extract_path = document_path.clone();
+    let extracted =
+      tokio::task::spawn_blocking(move || folder_summary::document_text(&extract_path, max_chars))
+        .await
+        .map_err(|e| format!("提取文本失败: {}", e))?;
+    let (text, truncated) = match extracted {
+      Ok((text, _)) if text.is_empty() => {
+        skipped.push(SkippedDocument {
+          path: document_path.to_string_lossy().to_string(),
+          reason: "没有可提取的文本".to_string(),
+        });
+        continue;
+      }
+      Ok(extracted) => extracted,
+      Err(e) => {
+        skipped.push(SkippedDocument {
+          path: document_path.to_string_lossy().to_string(),
+          reason: e,
+        });
+        continue;
+      }
+    };
+
+    let chunks = folder_summary::split_text(&text);
+    let parts = chunks.len();
+    let mut bullets = Vec::new();
+    for (part, chunk) in chunks.iter().enumerate() {
+      let prompt = folder_summary::build_map_prompt(
+        &relative_path,
+        chunk,
+        (parts > 1).then_some((part, parts)),
+        &options,
+      );
+      let response = collect_chat_text(&provider, prompt, &model_config)
+        .await
+        .map_err(|e| e.to_string())?;
+      bullets.extend(folder_summary::parse_bullets(
+        &response,
+        options.bullets_per_document,
+      ));
+    }
+    if parts > 1 {
+      let prompt = folder_summary::build_merge_prompt(&relative_path, &bullets, &options);
+      let response = collect_chat_text(&provider, prompt, &model_config)
+        .await
+        .map_err(|e| e.to_string())?;
+      bullets = folder_summary::parse_bullets(&response, options.bullets_per_document);
+    }
+    documents.push(DocumentSummary {
+      path: document_path.to_string_lossy().to_string(),
+      relative_path,
+      bullets,
+      truncated,
+    });
+  }
+  if documents.is_empty() {
+    return Err("文件夹中没有可汇总的 Markdown / DOCX / PDF 文档".to_string());
+  }
+
+  ctx.set_progress(
+    Some(total as f64 / (total + 1) as f64),
+    Some("正在综合各文档要点".to_string()),
+  );
+  let prompt = folder_summary::build_reduce_prompt(&documents, &options);
+  let response = collect_chat_text(&provider, prompt, &model_config)
+    .await
+    .map_err(|e| e.to_string())?;
+  let (overview, key_points) = folder_summary::parse_reduce_response(&response, &documents);
+  Ok(FolderSummary {
+    folder: folder.to_string_lossy().to_string(),
+    overview,
+    key_points,
+    documents,
+    skipped,
+  })
+}
+
+/// 文件夹多文档摘要：逐个文档总结要点（map），再综合为总览与带来源引用的结论（reduce）
+///
+/// 支持 Markdown / DOCX / PDF，作为后台任务运行；无法提取文本的文档记入 `skipped`。
+#[tauri::command]
+pub async fn ai_summarize_folder(
+  path: String,
+  options: Option<FolderSummaryOptions>,
+  service: State<'_, AIServiceState>,
+) -> Result<FolderSummary, AppError> {
+  telemetry::record_feature("ai_summarize_folder");
+  let folder = PathBuf::from(&path);
+  if !folder.is_dir() {
+    return Err(AppError::InvalidInput(format!("不是文件夹: {}", path)));
+  }
+  let options = options.unwrap_or_default();
+  if options.max_documents == 0 || options.max_chars_per_document == 0 {
+    return Err(AppError::InvalidInput(
+      "max_documents 与 max_chars_per_document 必须大于 0".to_string(),
+    ));
+  }
+
+  let provider = service
+    .get_provider(&settings_service::default_ai_provider())
+    .or_else(|| service.get_provider("deepseek"))
+    .or_else(|| service.get_provider("openai"))
+    .ok_or(AppError::AiNotConfigured)?;
+  let model_config = ModelConfig {
+    temperature: 0.3,
+    max_tokens: 4000,
+    ..ModelConfig::default()
+  };
+
+  let title = format!(
+    "汇总文件夹 {}",
+    folder.file_name().unwrap_or_default().to_string_lossy()
+  );
+  Ok(
+    job_service::jobs()
+      .run(JobSpec::new("ai_summarize_folder", title), move |ctx| {
+        summarize_folder(ctx, folder, options, provider, model_config)
+      })
+      .await?,
+  )
 }
 
 #[tauri::command]
@@ -5742,22 +6482,19 @@ pub async fn chat_build_generate_outline(
   discussion_context: String,
   model_config: ModelConfig,
   service: State<'_, AIServiceState>,
-) -> Result<ChatBuildOutlinePayload, String> {
-  let provider_name = chat_build_provider_name(&model_config.model);
+) -> Result<ChatBuildOutlinePayload, AppError> {
+  let provider_name = provider_name_for_model(&model_config.model);
+  let provider_name = provider_name.as_str();
   let provider = {
-    let service_guard = service
-      .lock()
-      .map_err(|e| format!("获取 AI 服务失败: {}", e))?;
-
-    if let Some(p) = service_guard.get_provider(provider_name) {
+    if let Some(p) = service.get_provider(provider_name) {
       Some(p)
     } else if provider_name == "deepseek" {
-      service_guard.get_provider("openai")
+      service.get_provider("openai")
     } else {
-      service_guard.get_provider("deepseek")
+      service.get_provider("deepseek")
     }
   }
-  .ok_or_else(|| format!("未配置 {} 提供商，请先配置 API key", provider_name))?;
+  .ok_or(AppError::AiNotConfigured)?;
 
   let prompt = format!(
     concat!(
@@ -5802,7 +6539,7 @@ pub async fn chat_build_generate_outline(
     match chunk_result {
       Ok(ChatChunk::Text(text)) => response.push_str(&text),
       Ok(ChatChunk::ToolCall { .. }) => continue,
-      Err(e) => return Err(format!("生成 Build Outline 失败: {}", e)),
+      Err(e) => return Err(e.into()),
     }
   }
 
@@ -5817,7 +6554,7 @@ pub async fn chat_build_generate_outline(
     serde_json::from_str(json).map_err(|e| format!("Build Outline JSON 解析失败: {}", e))?;
 
   if payload.steps.is_empty() {
-    return Err("Build Outline 至少需要一个步骤".to_string());
+    return Err("Build Outline 至少需要一个步骤".into());
   }
 
   for (index, step) in payload.steps.iter_mut().enumerate() {
@@ -5825,7 +6562,7 @@ pub async fn chat_build_generate_outline(
       step.id = format!("step_{}", index + 1);
     }
     if step.name.trim().is_empty() {
-      return Err(format!("Build Outline 第 {} 步缺少名称", index + 1));
+      return Err(format!("Build Outline 第 {} 步缺少名称", index + 1).into());
     }
     if step.summary.trim().is_empty() {
       step.summary = "未提供步骤说明".to_string();
@@ -5847,45 +6584,66 @@ pub async fn chat_build_generate_outline(
 
 /// 取消正在进行的 AI 聊天流
 #[tauri::command]
-pub async fn ai_cancel_chat_stream(tab_id: String) -> Result<(), String> {
-  eprintln!("🛑 收到取消请求: tab_id={}", tab_id);
-
-  // ⚠️ 关键修复：同时设置取消标志和发送取消信号
-  // 1. 设置取消标志（用于继续对话的流处理循环）
-  {
-    let flags = CANCEL_FLAGS.lock().unwrap();
-    if let Some(flag) = flags.get(&tab_id) {
-      let mut flag_guard = flag.lock().unwrap();
-      *flag_guard = true;
-      eprintln!("✅ 设置取消标志: tab_id={}", tab_id);
-    }
-  }
-
-  // 2. 发送取消信号（用于初始流的取消）
-  let mut channels = CANCEL_CHANNELS.lock().unwrap();
-  if let Some(cancel_tx) = channels.remove(&tab_id) {
-    // 发送取消信号
-    if let Err(_) = cancel_tx.send(()) {
-      eprintln!("⚠️ 取消通道已关闭，可能任务已完成");
-    } else {
-      eprintln!("✅ 成功发送取消信号: tab_id={}", tab_id);
-    }
+pub async fn ai_cancel_chat_stream(
+  tab_id: String,
+  app: tauri::AppHandle,
+  streams: State<'_, ChatStreamRegistryState>,
+) -> Result<(), AppError> {
+  tracing::debug!("收到取消请求: tab_id={}", tab_id);
+  if cancel_tab_stream(&app, streams.inner(), &tab_id) {
     Ok(())
   } else {
-    // 即使没有找到通道，如果找到了标志，也算成功
-    let flags = CANCEL_FLAGS.lock().unwrap();
-    if flags.contains_key(&tab_id) {
-      eprintln!("⚠️ 未找到取消通道，但已设置取消标志: tab_id={}", tab_id);
-      Ok(())
-    } else {
-      eprintln!("⚠️ 未找到对应的取消通道或标志: tab_id={}", tab_id);
-      Err(format!("未找到对应的任务: {}", tab_id))
-    }
+    tracing::warn!("未找到对应的对话流: tab_id={}", tab_id);
+    Err(format!("未找到对应的任务: {}", tab_id).into())
   }
 }
 
 // ── 记忆辅助函数 ──────────────────────────────────────────────────────────────
 
+/// 注入日志只记录 workspace.db 中的记忆（全局记忆存于 user_memory.db）
+fn workspace_memory_ids(items: &[MemorySearchResult]) -> Vec<String> {
+  items
+    .iter()
+    .filter(|r| r.item.layer != "user")
+    .map(|r| r.item.id.clone())
+    .collect()
+}
+
+/// 从搜索索引检索与用户消息相关的工作区片段（跳过当前文档，索引不存在时返回 None）
+async fn build_workspace_snippet_context(
+  workspace_path: &std::path::Path,
+  user_msg: &str,
+  current_file: Option<&str>,
+  limit: usize,
+  budget_chars: usize,
+) -> Option<String> {
+  let query = natural_language_query(user_msg);
+  if query.is_empty() || budget_chars < 100 {
+    return None;
+  }
+  let workspace_path = workspace_path.to_path_buf();
+  let current_file = current_file.map(std::path::PathBuf::from);
+  tokio::task::spawn_blocking(move || {
+    if !workspace_path.join(".binder").join("search.db").exists() {
+      return None;
+    }
+    let service = SearchService::new(&workspace_path).ok()?;
+    let results: Vec<_> = service
+      .search(&query, limit + 1)
+      .map_err(|e| tracing::warn!("[search] snippet injection failed: {}", e))
+      .ok()?
+      .into_iter()
+      .filter(|r| current_file.as_deref() != Some(r.full_path(&workspace_path).as_path()))
+      .take(limit)
+      .collect();
+    let formatted = format_snippets_for_injection(&results, budget_chars);
+    (!formatted.is_empty()).then_some(formatted)
+  })
+  .await
+  .ok()
+  .flatten()
+}
+
 /// 构造记忆检索 query：用户消息前200字符 + 当前文件名 + 选区前100字符
 fn build_memory_query(
   user_msg: &str,
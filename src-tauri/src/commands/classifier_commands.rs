@@ -1,155 +1,234 @@
+use crate::commands::file_commands::emit_full_refresh;
+use crate::services::ai_providers::AIProvider;
 use crate::services::ai_service::AIService;
 use crate::services::file_classifier::{FileClassification, FileClassifierService};
-use std::path::PathBuf;
+use crate::services::file_organizer::{
+  FileBatchProgress, FileMoveResult, FileOrganizer, OrganizePlan,
+};
+use crate::services::file_watcher::WatcherPause;
+use crate::services::organize_history::OrganizeHistory;
+use crate::services::telemetry;
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
-use tauri::State;
+use tauri::{AppHandle, Emitter, State};
 
 // AI 服务状态（全局单例）
-type AIServiceState = Arc<Mutex<AIService>>;
+type AIServiceState = Arc<AIService>;
+
+// 进行中的分类 / 整理任务：request_id -> 取消标志
+static ORGANIZE_CANCEL_FLAGS: Lazy<Mutex<HashMap<String, Arc<AtomicBool>>>> =
+  Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// 注册取消标志，任务结束（drop）时自动移除
+struct CancelRegistration {
+  request_id: Option<String>,
+  flag: Arc<AtomicBool>,
+}
+
+impl CancelRegistration {
+  fn new(request_id: Option<String>) -> Self {
+    let flag = Arc::new(AtomicBool::new(false));
+    if let Some(id) = &request_id {
+      ORGANIZE_CANCEL_FLAGS
+        .lock()
+        .unwrap()
+        .insert(id.clone(), flag.clone());
+    }
+    Self { request_id, flag }
+  }
+}
+
+impl Drop for CancelRegistration {
+  fn drop(&mut self) {
+    if let Some(id) = &self.request_id {
+      ORGANIZE_CANCEL_FLAGS.lock().unwrap().remove(id);
+    }
+  }
+}
+
+fn emit_batch_progress(
+  app: &AppHandle,
+  event: &str,
+  request_id: &Option<String>,
+  progress: &FileBatchProgress,
+) {
+  app
+    .emit(
+      event,
+      serde_json::json!({
+        "request_id": request_id,
+        "current_file": progress.current_file,
+        "processed": progress.processed,
+        "total": progress.total,
+        "errors": progress.errors,
+      }),
+    )
+    .ok();
+}
+
+// 优先使用 DeepSeek，如果没有则使用 OpenAI
+fn classification_provider(service: &AIService) -> Option<Arc<dyn AIProvider>> {
+  service
+    .get_provider("deepseek")
+    .or_else(|| service.get_provider("openai"))
+}
 
 // ⚠️ Week 20.2：批量分类文件
+// 每处理一个文件发送 classify-progress 事件；传入 request_id 时可用 cancel_file_organize 取消
 #[tauri::command]
 pub async fn classify_files(
   file_paths: Vec<String>,
   workspace_path: String,
+  request_id: Option<String>,
+  app: AppHandle,
   service: State<'_, AIServiceState>,
 ) -> Result<Vec<FileClassification>, String> {
   let files: Vec<PathBuf> = file_paths.iter().map(PathBuf::from).collect();
   let workspace = PathBuf::from(&workspace_path);
 
-  // 获取 AI provider
-  let provider = {
-    let service_guard = service
-      .lock()
-      .map_err(|e| format!("获取 AI 服务失败: {}", e))?;
-    // 优先使用 DeepSeek，如果没有则使用 OpenAI
-    service_guard
-      .get_provider("deepseek")
-      .or_else(|| service_guard.get_provider("openai"))
-      .ok_or_else(|| "未配置任何 AI 提供商，请先配置 DeepSeek 或 OpenAI API key".to_string())?
-  };
-
-  FileClassifierService::classify_files(provider, files, &workspace)
-    .await
-    .map_err(|e| format!("分类文件失败: {}", e))
+  // 获取 AI provider（全部文件都命中分类规则时可以不配置）
+  let provider = classification_provider(&service);
+  let registration = CancelRegistration::new(request_id.clone());
+
+  FileClassifierService::classify_files(
+    provider,
+    files,
+    &workspace,
+    |progress| emit_batch_progress(&app, "classify-progress", &request_id, progress),
+    &registration.flag,
+  )
+  .await
+  .map_err(|e| format!("分类文件失败: {}", e))
 }
 
 // ⚠️ Week 20.3：整理文件（分类并移动）
+// dry_run 为 true 时只返回移动计划，不改动磁盘；
+// 分类阶段发送 classify-progress，移动阶段发送 organize-progress
 #[tauri::command]
 pub async fn organize_files(
   file_paths: Vec<String>,
   workspace_path: String,
+  dry_run: Option<bool>,
+  request_id: Option<String>,
+  app: AppHandle,
   service: State<'_, AIServiceState>,
-) -> Result<Vec<FileMoveResult>, String> {
-  use std::fs;
-
+) -> Result<OrganizeReport, String> {
   let files: Vec<PathBuf> = file_paths.iter().map(PathBuf::from).collect();
   let workspace = PathBuf::from(&workspace_path);
+  telemetry::record_feature("organize_files");
 
-  // 获取 AI provider
-  let provider = {
-    let service_guard = service
-      .lock()
-      .map_err(|e| format!("获取 AI 服务失败: {}", e))?;
-    // 优先使用 DeepSeek，如果没有则使用 OpenAI
-    service_guard
-      .get_provider("deepseek")
-      .or_else(|| service_guard.get_provider("openai"))
-      .ok_or_else(|| "未配置任何 AI 提供商，请先配置 DeepSeek 或 OpenAI API key".to_string())?
-  };
+  // 获取 AI provider（全部文件都命中分类规则时可以不配置）
+  let provider = classification_provider(&service);
+  let registration = CancelRegistration::new(request_id.clone());
 
   // 先分类文件
-  let classifications = FileClassifierService::classify_files(provider, files.clone(), &workspace)
-    .await
-    .map_err(|e| format!("分类文件失败: {}", e))?;
-
-  let mut results = Vec::new();
-
-  // 移动文件到分类文件夹
-  for (file_path, classification) in file_paths.iter().zip(classifications.iter()) {
-    let source = PathBuf::from(file_path);
-    let category_dir = workspace.join(&classification.category);
-
-    // 创建分类文件夹（如果不存在）
-    if let Err(e) = fs::create_dir_all(&category_dir) {
-      results.push(FileMoveResult {
-        file_path: file_path.clone(),
-        success: false,
-        message: format!("创建文件夹失败: {}", e),
-      });
-      continue;
-    }
+  let classifications = FileClassifierService::classify_files(
+    provider,
+    files,
+    &workspace,
+    |progress| emit_batch_progress(&app, "classify-progress", &request_id, progress),
+    &registration.flag,
+  )
+  .await
+  .map_err(|e| format!("分类文件失败: {}", e))?;
+
+  let plan = FileOrganizer::plan(&workspace, &classifications);
+  let dry_run = dry_run.unwrap_or(false);
+  let results = if dry_run {
+    Vec::new()
+  } else {
+    execute_plan(app, workspace, plan.clone(), request_id, registration).await?
+  };
+
+  Ok(OrganizeReport {
+    dry_run,
+    plan,
+    results,
+  })
+}
+
+// 按（预览后可能经用户调整的）计划移动文件
+#[tauri::command]
+pub async fn apply_organize_plan(
+  workspace_path: String,
+  plan: OrganizePlan,
+  request_id: Option<String>,
+  app: AppHandle,
+) -> Result<Vec<FileMoveResult>, String> {
+  let registration = CancelRegistration::new(request_id.clone());
+  execute_plan(
+    app,
+    PathBuf::from(&workspace_path),
+    plan,
+    request_id,
+    registration,
+  )
+  .await
+}
+
+async fn execute_plan(
+  app: AppHandle,
+  workspace: PathBuf,
+  plan: OrganizePlan,
+  request_id: Option<String>,
+  registration: CancelRegistration,
+) -> Result<Vec<FileMoveResult>, String> {
+  tokio::task::spawn_blocking(move || {
+    let _pause = pause_watcher_for(&app, &workspace);
+    FileOrganizer::execute(
+      &workspace,
+      &plan,
+      |progress| emit_batch_progress(&app, "organize-progress", &request_id, progress),
+      &registration.flag,
+    )
+  })
+  .await
+  .map_err(|e| format!("整理文件失败: {}", e))
+}
 
-    let file_name = source
-      .file_name()
-      .ok_or_else(|| format!("无法获取文件名: {}", file_path))?;
-    let dest = category_dir.join(file_name);
-
-    // 检查目标文件是否已存在
-    if dest.exists() {
-      // 如果已存在，添加时间戳后缀
-      let stem = source
-        .file_stem()
-        .and_then(|s| s.to_str())
-        .unwrap_or("file");
-      let ext = source
-        .extension()
-        .and_then(|e| e.to_str())
-        .map(|e| format!(".{}", e))
-        .unwrap_or_default();
-
-      let timestamp = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_secs();
-
-      let new_name = format!("{}_{}{}", stem, timestamp, ext);
-      let dest = category_dir.join(&new_name);
-
-      // 移动文件
-      match fs::rename(&source, &dest) {
-        Ok(_) => {
-          results.push(FileMoveResult {
-            file_path: file_path.clone(),
-            success: true,
-            message: format!("已移动到 {}/{}", classification.category, new_name),
-          });
-        }
-        Err(e) => {
-          results.push(FileMoveResult {
-            file_path: file_path.clone(),
-            success: false,
-            message: format!("移动失败: {}", e),
-          });
-        }
-      }
-    } else {
-      // 移动文件
-      match fs::rename(&source, &dest) {
-        Ok(_) => {
-          results.push(FileMoveResult {
-            file_path: file_path.clone(),
-            success: true,
-            message: format!("已移动到 {}", classification.category),
-          });
-        }
-        Err(e) => {
-          results.push(FileMoveResult {
-            file_path: file_path.clone(),
-            success: false,
-            message: format!("移动失败: {}", e),
-          });
-        }
-      }
+// 取消进行中的分类 / 整理；已移动的文件保留在新位置，可通过撤销还原
+#[tauri::command]
+pub async fn cancel_file_organize(request_id: String) -> Result<bool, String> {
+  let flags = ORGANIZE_CANCEL_FLAGS.lock().unwrap();
+  Ok(match flags.get(&request_id) {
+    Some(flag) => {
+      flag.store(true, Ordering::Relaxed);
+      true
     }
-  }
+    None => false,
+  })
+}
+
+// 撤销最近一次整理，把文件移回原位置
+#[tauri::command]
+pub async fn undo_last_organize(
+  workspace_path: String,
+  app: AppHandle,
+) -> Result<Vec<FileMoveResult>, String> {
+  let workspace = PathBuf::from(&workspace_path);
+  telemetry::record_feature("undo_organize");
+  tokio::task::spawn_blocking(move || {
+    let _pause = pause_watcher_for(&app, &workspace);
+    OrganizeHistory::new(&workspace).undo_last()
+  })
+  .await
+  .map_err(|e| format!("撤销整理失败: {}", e))?
+}
 
-  Ok(results)
+/// 批量移动期间暂停文件监听，结束后让前端整体刷新一次
+fn pause_watcher_for(app: &AppHandle, workspace: &Path) -> WatcherPause {
+  let app = app.clone();
+  let workspace_path = workspace.to_string_lossy().to_string();
+  WatcherPause::new(workspace, move || emit_full_refresh(&app, &workspace_path))
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
-pub struct FileMoveResult {
-  pub file_path: String,
-  pub success: bool,
-  pub message: String,
+pub struct OrganizeReport {
+  pub dry_run: bool,
+  pub plan: OrganizePlan,
+  /// 实际移动结果（dry_run 时为空）
+  pub results: Vec<FileMoveResult>,
 }
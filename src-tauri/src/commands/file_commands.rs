@@ -1,24 +1,53 @@
+use crate::error::AppError;
+use crate::services::document_diff::{self, DiffFormat, DocumentDiff, DocumentRevision};
+use crate::services::document_merge::{self, MergeOptions, MergeResult};
+use crate::services::document_split::{self, SplitResult};
+use crate::services::encryption;
 use crate::services::file_system::FileSystemService;
+use crate::services::file_templates::{self, TemplateContext, TemplateFileOutcome};
 use crate::services::file_tree::{FileTreeNode, FileTreeService};
-use crate::services::file_watcher::FileWatcherService;
-use crate::services::libreoffice_service::LibreOfficeService;
+use crate::services::file_watcher::{
+  count_watched_directories, is_reference_path, pause_workspace_events, resume_workspace_events,
+  FileChangeBatch, FileChangeKind, FileWatcherHandle, WatchSession, WatcherStatus,
+  WorkspaceFileChange,
+};
+use crate::services::footnotes::{self, Footnote, FootnoteEdit, FootnoteFormat};
+use crate::services::html_sanitizer::sanitize_html_document;
+use crate::services::libreoffice_installer::{install_plan, LIBREOFFICE_DOWNLOAD_URL};
+use crate::services::libreoffice_service::{
+  estimate_conversion_progress, LibreOfficeService, OfficeDocumentKind,
+};
+use crate::services::link_graph;
+use crate::services::markdown_format::{self, MarkdownFormatResult, MarkdownFormatRules};
+use crate::services::memory_service::check_anchors_after_save;
+use crate::services::open_file_tracker;
 use crate::services::pandoc_service::PandocService;
+use crate::services::pdf_page_renderer::{PdfPageRenderer, PDF_PAGE_DEFAULT_WIDTH};
+use crate::services::pdf_text::PdfTextExtraction;
+use crate::services::preview_cache::{PreviewCache, PreviewCacheClearResult, PreviewCacheInfo};
+use crate::services::readability::{self, ReadabilityReport};
+use crate::services::search_service::{refresh_index_after_save, remove_index_after_delete};
+use crate::services::settings_service;
+use crate::services::temp_service::{self, TempArtifact};
+use crate::services::word_count::{self, CountFormat, WordCountResult};
 use crate::services::workspace::{Workspace, WorkspaceService};
+use crate::services::workspace_settings::WorkspaceSettings;
+use crate::utils::disk_space;
+use crate::utils::i18n;
 use crate::utils::path_validator::PathValidator;
 use crate::workspace::timeline_support::record_resource_structure_timeline_node;
 use crate::workspace::workspace_db::WorkspaceDb;
-use dirs;
 use once_cell::sync::Lazy;
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 use std::time::SystemTime;
 use tauri::{AppHandle, Emitter, State};
-use tokio::sync::oneshot;
+use tokio::sync::{broadcast, oneshot};
 use uuid::Uuid;
 
 // 全局文件监听器（单例）
-type FileWatcherState = Mutex<FileWatcherService>;
+type FileWatcherState = FileWatcherHandle;
 
 // 全局预览请求去重机制：防止同一文件的并发预览请求
 // Key: 文件路径（规范化），Value: (发送器, 接收器) - 用于等待第一个请求完成
@@ -186,16 +215,27 @@ fn create_empty_pptx(path: &Path) -> Result<(), String> {
 }
 
 #[tauri::command]
-pub async fn build_file_tree(root_path: String, max_depth: usize) -> Result<FileTreeNode, String> {
+pub async fn build_file_tree(
+  root_path: String,
+  max_depth: usize,
+) -> Result<FileTreeNode, AppError> {
   let service = FileTreeService::new();
   let root = PathBuf::from(root_path);
-  service.build_tree(&root, max_depth)
+  Ok(service.build_tree(&root, max_depth)?)
 }
 
 #[tauri::command]
-pub async fn read_file_content(path: String) -> Result<String, String> {
+pub async fn read_file_content(path: String) -> Result<String, AppError> {
   let path_buf = std::path::PathBuf::from(&path);
 
+  // 加密文件：用本次运行缓存的密钥解密，未解锁时前端收到 ENCRYPTED_FILE_LOCKED 后请求口令
+  if encryption::is_encrypted_path(&path_buf) {
+    let bytes = encryption::read_encrypted(&path_buf)?;
+    return String::from_utf8(bytes).map_err(|_| {
+      AppError::InvalidInput("加密文件的内容不是文本，请用预览打开或先解密".to_string())
+    });
+  }
+
   // 检查文件大小，如果超过 10MB，使用流式读取
   let metadata = std::fs::metadata(&path_buf).map_err(|e| format!("获取文件信息失败: {}", e))?;
 
@@ -222,50 +262,141 @@ pub async fn read_file_content(path: String) -> Result<String, String> {
     ))
   } else {
     // 小文件：正常读取
-    std::fs::read_to_string(&path_buf).map_err(|e| format!("读取文件失败: {}", e))
+    std::fs::read_to_string(&path_buf).map_err(|e| AppError::io("读取文件失败", e))
   }
 }
 
 #[tauri::command]
-pub async fn read_file_as_base64(path: String) -> Result<String, String> {
+pub async fn read_file_as_base64(path: String) -> Result<String, AppError> {
   use base64::Engine;
-  let bytes = std::fs::read(&path).map_err(|e| format!("读取文件失败: {}", e))?;
+  let path_buf = PathBuf::from(&path);
+  // 加密文件返回解密后的字节，供图片、表格等二进制预览使用
+  let bytes = if encryption::is_encrypted_path(&path_buf) {
+    encryption::read_encrypted(&path_buf)?
+  } else {
+    std::fs::read(&path).map_err(|e| format!("读取文件失败: {}", e))?
+  };
   let base64 = base64::engine::general_purpose::STANDARD.encode(&bytes);
   Ok(base64)
 }
 
 #[tauri::command]
-pub async fn write_file(path: String, content: String) -> Result<(), String> {
+pub async fn write_file(path: String, content: String) -> Result<(), AppError> {
   let path_buf = PathBuf::from(&path);
   let workspace_root = require_workspace_root_for_path(&path_buf)?;
   let target = PathValidator::validate_workspace_write_target(&path_buf, &workspace_root)
     .map_err(|e| format!("写入路径非法: {}", e))?;
-  std::fs::write(&target, content).map_err(|e| format!("写入文件失败: {}", e))
+  // 加密文件：重新加密后保存，明文不进入快照与搜索索引
+  if encryption::is_encrypted_path(&target) {
+    return Ok(encryption::write_encrypted(&target, content.as_bytes())?);
+  }
+  std::fs::write(&target, &content).map_err(|e| format!("写入文件失败: {}", e))?;
+  open_file_tracker::update_snapshot(&target, &content);
+  refresh_index_after_save(&workspace_root, &target, Some(content));
+  check_anchors_after_save(&workspace_root, &target);
+  Ok(())
+}
+
+/// 用口令加密文件为 `<文件名>.binder.enc`，默认删除明文原文件；返回加密文件路径
+#[tauri::command]
+pub async fn encrypt_file(
+  path: String,
+  passphrase: String,
+  keep_original: Option<bool>,
+) -> Result<String, AppError> {
+  let path_buf = PathBuf::from(&path);
+  let workspace_root = require_workspace_root_for_path(&path_buf)?;
+  let source = PathValidator::validate_workspace_write_target(&path_buf, &workspace_root)
+    .map_err(|e| format!("加密路径非法: {}", e))?;
+  if !source.is_file() {
+    return Err(AppError::NotFound(path));
+  }
+  let keep_original = keep_original.unwrap_or(false);
+  let source_for_task = source.clone();
+  let target = tokio::task::spawn_blocking(move || {
+    encryption::encrypt_file(&source_for_task, &passphrase, keep_original)
+  })
+  .await
+  .map_err(|e| AppError::from(format!("加密文件失败: {}", e)))??;
+  if !keep_original {
+    remove_index_after_delete(&workspace_root, &source);
+  }
+  Ok(target.to_string_lossy().to_string())
+}
+
+/// 把 `.binder.enc` 文件解密为原文件，默认删除加密文件；未提供口令时使用已解锁的密钥
+#[tauri::command]
+pub async fn decrypt_file(
+  path: String,
+  passphrase: Option<String>,
+  keep_encrypted: Option<bool>,
+) -> Result<String, AppError> {
+  let path_buf = PathBuf::from(&path);
+  let workspace_root = require_workspace_root_for_path(&path_buf)?;
+  let source = PathValidator::validate_workspace_write_target(&path_buf, &workspace_root)
+    .map_err(|e| format!("解密路径非法: {}", e))?;
+  let target = tokio::task::spawn_blocking(move || {
+    encryption::decrypt_file(
+      &source,
+      passphrase.as_deref(),
+      keep_encrypted.unwrap_or(false),
+    )
+  })
+  .await
+  .map_err(|e| AppError::from(format!("解密文件失败: {}", e)))??;
+  refresh_index_after_save(&workspace_root, &target, None);
+  Ok(target.to_string_lossy().to_string())
+}
+
+/// 用口令解锁加密文件：校验口令并在本次运行内缓存密钥，之后可直接读取与保存
+#[tauri::command]
+pub async fn unlock_encrypted_file(path: String, passphrase: String) -> Result<(), AppError> {
+  let path_buf = PathBuf::from(&path);
+  let workspace_root = require_workspace_root_for_path(&path_buf)?;
+  let source = PathValidator::validate_workspace_path(&path_buf, &workspace_root)
+    .map_err(|e| format!("解锁路径非法: {}", e))?;
+  tokio::task::spawn_blocking(move || encryption::unlock(&source, &passphrase).map(|_| ()))
+    .await
+    .map_err(|e| AppError::from(format!("解锁加密文件失败: {}", e)))??;
+  Ok(())
+}
+
+/// 加密文件在本次运行内是否已解锁（已缓存密钥）
+#[tauri::command]
+pub async fn is_encrypted_file_unlocked(path: String) -> Result<bool, AppError> {
+  Ok(encryption::is_unlocked(Path::new(&path)))
 }
 
+/// 清除已解锁文件的密钥；不传 path 时锁定全部，返回锁定的文件数
 #[tauri::command]
-pub async fn create_file(path: String, file_type: String) -> Result<(), String> {
+pub async fn lock_encrypted_files(path: Option<String>) -> Result<usize, AppError> {
+  Ok(encryption::lock(path.as_deref().map(Path::new)))
+}
+
+#[tauri::command]
+pub async fn create_file(path: String, file_type: String) -> Result<(), AppError> {
   let path_buf = PathBuf::from(&path);
   let workspace_root = require_workspace_root_for_path(&path_buf)?;
   let safe_path = PathValidator::validate_workspace_write_target(&path_buf, &workspace_root)
     .map_err(|e| format!("创建路径非法: {}", e))?;
 
-  eprintln!(
+  tracing::debug!(
     "[create_file] 开始创建文件: path={}, type={}",
-    path, file_type
+    path,
+    file_type
   );
 
   // 检查文件是否已存在
   if safe_path.exists() {
-    eprintln!("[create_file] 文件已存在: {}", path);
-    return Err(format!("文件已存在: {}", path));
+    tracing::debug!("[create_file] 文件已存在: {}", path);
+    return Err(AppError::AlreadyExists(path));
   }
 
   // 确保父目录存在
   if let Some(parent) = safe_path.parent() {
-    eprintln!("[create_file] 创建父目录: {:?}", parent);
+    tracing::debug!("[create_file] 创建父目录: {:?}", parent);
     std::fs::create_dir_all(parent).map_err(|e| {
-      eprintln!("[create_file] 创建父目录失败: {}", e);
+      tracing::warn!("[create_file] 创建父目录失败: {}", e);
       format!("创建目录失败: {}", e)
     })?;
   }
@@ -286,15 +417,15 @@ pub async fn create_file(path: String, file_type: String) -> Result<(), String>
         _ => "",
       };
 
-      eprintln!("[create_file] 写入文件内容: path={}", path);
+      tracing::debug!("[create_file] 写入文件内容: path={}", path);
       std::fs::write(&safe_path, content).map_err(|e| {
-        eprintln!("[create_file] 写入文件失败: {}", e);
+        tracing::warn!("[create_file] 写入文件失败: {}", e);
         format!("创建文件失败: {}", e)
       })?;
     }
   }
 
-  eprintln!("[create_file] 文件创建成功: {}", path);
+  tracing::debug!("[create_file] 文件创建成功: {}", path);
   let db = WorkspaceDb::new(&workspace_root)?;
   let _ = record_resource_structure_timeline_node(
     &db,
@@ -313,37 +444,92 @@ pub async fn create_file(path: String, file_type: String) -> Result<(), String>
   Ok(())
 }
 
+/// 用文档模板新建文件：展开内置变量与 front matter `prompts` 中声明的变量。
+/// 模板声明了需填写的变量而 values 为空时只返回提示列表，前端收集后带 values 再次调用；
+/// template_path 可为绝对路径或相对工作区根目录的路径
+#[tauri::command]
+pub async fn create_file_from_template(
+  template_path: String,
+  path: String,
+  values: Option<HashMap<String, String>>,
+) -> Result<TemplateFileOutcome, AppError> {
+  let path_buf = PathBuf::from(&path);
+  let workspace_root = require_workspace_root_for_path(&path_buf)?;
+  let safe_path = PathValidator::validate_workspace_write_target(&path_buf, &workspace_root)
+    .map_err(|e| format!("创建路径非法: {}", e))?;
+  if safe_path.exists() {
+    return Err(AppError::AlreadyExists(path));
+  }
+
+  let template = file_templates::load_template(&workspace_root.join(&template_path))?;
+  let values = match values {
+    Some(values) => values,
+    None if !template.prompts.is_empty() => {
+      return Ok(TemplateFileOutcome::NeedsInput {
+        prompts: template.prompts,
+      })
+    }
+    None => HashMap::new(),
+  };
+  let content = template.render(&TemplateContext::new(&workspace_root, &safe_path), &values);
+
+  if let Some(parent) = safe_path.parent() {
+    std::fs::create_dir_all(parent).map_err(|e| AppError::io("创建目录失败", e))?;
+  }
+  std::fs::write(&safe_path, &content).map_err(|e| AppError::io("创建文件失败", e))?;
+  tracing::debug!("[create_file_from_template] 文件创建成功: {}", path);
+
+  let db = WorkspaceDb::new(&workspace_root)?;
+  let _ = record_resource_structure_timeline_node(
+    &db,
+    &workspace_root,
+    "create_file",
+    &format!(
+      "从模板创建文件：{}",
+      safe_path
+        .file_name()
+        .and_then(|s| s.to_str())
+        .unwrap_or(&path)
+    ),
+    "user",
+    std::slice::from_ref(&safe_path),
+  )?;
+  Ok(TemplateFileOutcome::Created {
+    path: safe_path.to_string_lossy().to_string(),
+  })
+}
+
 #[tauri::command]
-pub async fn create_folder(path: String) -> Result<(), String> {
+pub async fn create_folder(path: String) -> Result<(), AppError> {
   let path_buf = PathBuf::from(&path);
   let workspace_root = require_workspace_root_for_path(&path_buf)?;
   let safe_path = PathValidator::validate_workspace_write_target(&path_buf, &workspace_root)
     .map_err(|e| format!("创建路径非法: {}", e))?;
 
-  eprintln!("[create_folder] 开始创建文件夹: path={}", path);
+  tracing::debug!("[create_folder] 开始创建文件夹: path={}", path);
 
   // 检查文件夹是否已存在
   if safe_path.exists() {
-    eprintln!("[create_folder] 文件夹已存在: {}", path);
-    return Err(format!("文件夹已存在: {}", path));
+    tracing::debug!("[create_folder] 文件夹已存在: {}", path);
+    return Err(AppError::AlreadyExists(path));
   }
 
   // 确保父目录存在
   if let Some(parent) = safe_path.parent() {
-    eprintln!("[create_folder] 创建父目录: {:?}", parent);
+    tracing::debug!("[create_folder] 创建父目录: {:?}", parent);
     std::fs::create_dir_all(parent).map_err(|e| {
-      eprintln!("[create_folder] 创建父目录失败: {}", e);
+      tracing::warn!("[create_folder] 创建父目录失败: {}", e);
       format!("创建目录失败: {}", e)
     })?;
   }
 
-  eprintln!("[create_folder] 创建文件夹: path={}", path);
+  tracing::debug!("[create_folder] 创建文件夹: path={}", path);
   std::fs::create_dir_all(&safe_path).map_err(|e| {
-    eprintln!("[create_folder] 创建文件夹失败: {}", e);
+    tracing::warn!("[create_folder] 创建文件夹失败: {}", e);
     format!("创建文件夹失败: {}", e)
   })?;
 
-  eprintln!("[create_folder] 文件夹创建成功: {}", path);
+  tracing::debug!("[create_folder] 文件夹创建成功: {}", path);
   let db = WorkspaceDb::new(&workspace_root)?;
   let _ = record_resource_structure_timeline_node(
     &db,
@@ -363,16 +549,16 @@ pub async fn create_folder(path: String) -> Result<(), String> {
 }
 
 #[tauri::command]
-pub async fn open_workspace_dialog() -> Result<Option<String>, String> {
+pub async fn open_workspace_dialog() -> Result<Option<String>, AppError> {
   // 注意：在 Tauri 2.x 中，对话框功能由前端插件处理
   // 这个命令保留用于兼容，但实际由前端调用插件
   Ok(None)
 }
 
 #[tauri::command]
-pub async fn load_workspaces() -> Result<Vec<Workspace>, String> {
+pub async fn load_workspaces() -> Result<Vec<Workspace>, AppError> {
   let service = WorkspaceService::new()?;
-  service.load_workspaces()
+  Ok(service.load_workspaces()?)
 }
 
 #[tauri::command]
@@ -380,125 +566,290 @@ pub async fn open_workspace(
   path: String,
   watcher: State<'_, FileWatcherState>,
   app: tauri::AppHandle,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
   let service = WorkspaceService::new()?;
   service.open_workspace(&path)?;
 
   // 启动文件监听
-  let mut watcher_service = watcher
-    .lock()
-    .map_err(|e| format!("获取文件监听服务失败: {}", e))?;
-  let workspace_path = PathBuf::from(&path);
-  watcher_service.watch_workspace(workspace_path)?;
+  let WatchSession {
+    reference_roots,
+    changes: mut rx,
+    stats,
+  } = watcher.watch_workspace(PathBuf::from(&path)).await?;
+  if !reference_roots.is_empty() {
+    let workspace_path = PathBuf::from(&path);
+    let roots = reference_roots.clone();
+    tokio::task::spawn_blocking(move || {
+      let synced = crate::services::search_service::SearchService::new(&workspace_path)
+        .and_then(|service| service.sync_reference_folders(&roots));
+      if let Err(e) = synced {
+        tracing::warn!("索引参考资料文件夹失败: {}", e);
+      }
+    });
+  }
 
-  // 订阅文件变化事件
-  let mut rx = watcher_service.subscribe();
-  let app_handle = app.clone();
-  let path_clone = path.clone();
+  // 订阅文件变化事件：防抖与按路径合并在 FileWatcherService 中完成，每批通知一次
+  let workspace_path = PathBuf::from(&path);
 
-  // ⚠️ Week 17 优化：实现防抖机制（500ms）
   // ⚠️ Week 19.1：集成索引更新
-  let workspace_path_for_index = PathBuf::from(&path);
   tokio::spawn(async move {
     use crate::services::search_service::SearchService;
-    use std::fs;
-    use tokio::time::{sleep, Duration, Instant};
 
-    let mut last_event_time = Instant::now();
-    let debounce_duration = Duration::from_millis(500);
-    let mut debounce_task: Option<tokio::task::JoinHandle<()>> = None;
-
-    // 创建搜索服务实例用于索引更新（使用 Arc 包装以便在闭包中使用）
-    use std::sync::Arc;
-    let search_service = match SearchService::new(&workspace_path_for_index) {
+    // 创建搜索服务实例用于索引更新（使用 Arc 包装以便在后台线程中使用）
+    let search_service = match SearchService::new(&workspace_path) {
       Ok(service) => Some(Arc::new(service)),
       Err(e) => {
-        eprintln!("初始化搜索服务失败（索引更新将跳过）: {}", e);
+        tracing::warn!("初始化搜索服务失败（索引更新将跳过）: {}", e);
         None
       }
     };
 
-    while let Ok(_event) = rx.recv().await {
-      last_event_time = Instant::now();
-
-      // 取消之前的防抖任务（如果存在）
-      if let Some(task) = debounce_task.take() {
-        task.abort();
+    loop {
+      let batch = match rx.recv().await {
+        Ok(batch) => batch,
+        // 处理不及时丢失了批次：前端整体刷新
+        Err(broadcast::error::RecvError::Lagged(count)) => {
+          stats.record_lagged(count);
+          FileChangeBatch {
+            events: Vec::new(),
+            overflowed: true,
+          }
+        }
+        Err(broadcast::error::RecvError::Closed) => break,
+      };
+      let changes: Vec<WorkspaceFileChange> = batch
+        .events
+        .iter()
+        .map(|event| WorkspaceFileChange::from_event(event, &workspace_path, &reference_roots))
+        .collect();
+      update_reference_index(&workspace_path, &changes);
+      {
+        let workspace_path = workspace_path.clone();
+        let changes = changes.clone();
+        let overflowed = batch.overflowed;
+        tokio::task::spawn_blocking(move || {
+          link_graph::apply_changes(&workspace_path, &changes, overflowed)
+        });
       }
+      emit_external_changes(&app, &changes);
 
-      // 创建新的防抖任务
-      let app_handle_clone = app_handle.clone();
-      let path_clone_for_task = path_clone.clone();
-      let workspace_path_clone = workspace_path_for_index.clone();
-      let search_service_clone = search_service.clone();
+      // 发送文件树变化事件到前端（兼容旧监听）
+      app.emit("file-tree-changed", &path).unwrap_or_else(|e| {
+        tracing::warn!("发送文件树变化事件失败: {}", e);
+      });
+      // 逐条变化（created / modified / deleted / renamed），前端据此局部更新
+      app
+        .emit(
+          "workspace-file-changes",
+          serde_json::json!({
+            "workspace_path": path,
+            "changes": changes,
+            "overflowed": batch.overflowed,
+          }),
+        )
+        .unwrap_or_else(|e| {
+          tracing::warn!("发送文件变化事件失败: {}", e);
+        });
 
-      debounce_task = Some(tokio::spawn(async move {
-        // 等待 500ms
-        sleep(debounce_duration).await;
+      if let Some(service) = search_service.clone() {
+        let workspace_path = workspace_path.clone();
+        tokio::task::spawn_blocking(move || index_top_level_files(&service, &workspace_path));
+      }
+    }
+  });
 
-        // 发送文件树变化事件到前端
-        app_handle_clone
-          .emit("file-tree-changed", &path_clone_for_task)
-          .unwrap_or_else(|e| {
-            eprintln!("发送文件树变化事件失败: {}", e);
-          });
+  Ok(())
+}
 
-        // ⚠️ Week 19.1：自动更新索引（扫描变化的文件）
-        // 注意：这里简化实现，只扫描一级目录，避免性能问题
-        // 完整的递归扫描应该在 build_index_async 中完成
-        if let Some(ref service) = search_service_clone {
-          if let Ok(entries) = fs::read_dir(&workspace_path_clone) {
-            let mut updates = Vec::new();
-
-            for entry in entries.flatten() {
-              let path = entry.path();
-              if path.is_file() {
-                // 检查是否需要索引
-                if let Ok(should_index) = service.should_index(&path) {
-                  if should_index {
-                    // 读取文件内容
-                    if let Ok(content) = fs::read_to_string(&path) {
-                      updates.push((path.clone(), content));
-
-                      // 每 50 个文件批量提交一次
-                      if updates.len() >= 50 {
-                        if let Err(e) = service.batch_update_index(updates.clone()) {
-                          eprintln!("批量更新索引失败: {}", e);
-                        }
-                        updates.clear();
-                      }
-                    }
-                  }
-                }
-              }
-            }
+// ⚠️ Week 19.1：自动更新索引（扫描变化的文件）
+// 注意：这里简化实现，只扫描一级目录，避免性能问题
+// 完整的递归扫描应该在 build_index_async 中完成
+fn index_top_level_files(
+  service: &crate::services::search_service::SearchService,
+  workspace_path: &Path,
+) {
+  let Ok(entries) = std::fs::read_dir(workspace_path) else {
+    return;
+  };
+  let mut updates = Vec::new();
 
-            // 提交剩余的文件
-            if !updates.is_empty() {
-              if let Err(e) = service.batch_update_index(updates) {
-                eprintln!("批量更新索引失败: {}", e);
+  for entry in entries.flatten() {
+    let path = entry.path();
+    if path.is_file() {
+      // 检查是否需要索引
+      if let Ok(should_index) = service.should_index(&path) {
+        if should_index {
+          // 读取文件内容
+          if let Ok(content) = std::fs::read_to_string(&path) {
+            updates.push((path.clone(), content));
+
+            // 每 50 个文件批量提交一次
+            if updates.len() >= 50 {
+              if let Err(e) = service.batch_update_index(updates.clone()) {
+                tracing::warn!("批量更新索引失败: {}", e);
               }
+              updates.clear();
             }
           }
         }
-      }));
+      }
+    }
+  }
+
+  // 提交剩余的文件
+  if !updates.is_empty() {
+    if let Err(e) = service.batch_update_index(updates) {
+      tracing::warn!("批量更新索引失败: {}", e);
     }
+  }
+}
+
+/// 通知前端整体刷新文件树（暂停监听期间的变化没有逐条事件）
+pub fn emit_full_refresh(app: &AppHandle, workspace_path: &str) {
+  app
+    .emit("file-tree-changed", workspace_path)
+    .unwrap_or_else(|e| {
+      tracing::warn!("发送文件树变化事件失败: {}", e);
+    });
+  app
+    .emit(
+      "workspace-file-changes",
+      serde_json::json!({
+        "workspace_path": workspace_path,
+        "changes": [],
+        "overflowed": true,
+      }),
+    )
+    .unwrap_or_else(|e| {
+      tracing::warn!("发送文件变化事件失败: {}", e);
+    });
+}
+
+// 批量操作前暂停工作区的文件变化通知（可嵌套，需与 resume_watcher 成对调用）
+#[tauri::command]
+pub async fn pause_watcher(workspace_path: String) -> Result<(), AppError> {
+  pause_workspace_events(Path::new(&workspace_path));
+  Ok(())
+}
+
+// 恢复文件变化通知；全部恢复后通知前端整体刷新，返回是否已完全恢复
+#[tauri::command]
+pub async fn resume_watcher(workspace_path: String, app: AppHandle) -> Result<bool, AppError> {
+  let resumed = resume_workspace_events(Path::new(&workspace_path));
+  if resumed {
+    emit_full_refresh(&app, &workspace_path);
+  }
+  Ok(resumed)
+}
+
+// 文件监听状态（是否在监听、监听的文件夹数、丢失的事件数、底层机制），用于排查文件树不刷新等问题
+#[tauri::command]
+pub async fn get_watcher_status(
+  workspace_path: String,
+  watcher: State<'_, FileWatcherState>,
+) -> Result<WatcherStatus, AppError> {
+  let mut status = watcher.status(PathBuf::from(&workspace_path)).await?;
+  let roots = status.watched_paths.clone();
+  status.watched_directories =
+    tokio::task::spawn_blocking(move || count_watched_directories(&roots))
+      .await
+      .map_err(|e| format!("统计监听文件夹失败: {}", e))?;
+  Ok(status)
+}
 
-    // 清理：如果有未完成的防抖任务，等待它完成
-    if let Some(task) = debounce_task {
-      let _ = task.await;
+/// 已打开的文件被外部修改时发送 `external-file-changed`（含行级变化摘要）
+fn emit_external_changes(app: &AppHandle, changes: &[WorkspaceFileChange]) {
+  let paths: Vec<PathBuf> = changes
+    .iter()
+    .filter(|c| !c.is_dir)
+    .flat_map(|c| std::iter::once(&c.absolute_path).chain(c.old_absolute_path.as_ref()))
+    .map(PathBuf::from)
+    .collect();
+  let app = app.clone();
+  tokio::task::spawn_blocking(move || {
+    for path in &paths {
+      if let Some(change) = open_file_tracker::detect_external_change(path) {
+        app
+          .emit("external-file-changed", &change)
+          .unwrap_or_else(|e| {
+            tracing::warn!("发送外部修改事件失败: {}", e);
+          });
+      }
     }
   });
+}
 
+// 编辑器打开文件后开始跟踪，外部修改时推送 external-file-changed
+#[tauri::command]
+pub async fn track_open_file(path: String) -> Result<(), AppError> {
+  tokio::task::spawn_blocking(move || open_file_tracker::track_open_file(Path::new(&path)))
+    .await
+    .map_err(|e| format!("跟踪文件失败: {}", e))??;
+  Ok(())
+}
+
+#[tauri::command]
+pub async fn untrack_open_file(path: String) -> Result<(), AppError> {
+  open_file_tracker::untrack_open_file(Path::new(&path));
   Ok(())
 }
 
+/// 参考资料文件夹不在工作区内，不会被下面的工作区扫描覆盖，按变化逐条更新索引
+fn update_reference_index(workspace_path: &Path, changes: &[WorkspaceFileChange]) {
+  for change in changes.iter().filter(|c| c.reference_root.is_some()) {
+    let path = Path::new(&change.absolute_path);
+    if let Some(old_path) = &change.old_absolute_path {
+      remove_index_after_delete(workspace_path, Path::new(old_path));
+    }
+    match change.kind {
+      FileChangeKind::Deleted => remove_index_after_delete(workspace_path, path),
+      _ if !change.is_dir => refresh_index_after_save(workspace_path, path, None),
+      _ => {}
+    }
+  }
+}
+
+/// 只读参考资料文件夹（工作区设置 `watcher.reference_folders`）
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ReferenceFolder {
+  pub path: String,
+  pub name: String,
+  /// 文件夹存在且未与工作区互相包含，正在监听
+  pub available: bool,
+}
+
+#[tauri::command]
+pub async fn list_reference_folders(
+  workspace_path: String,
+) -> Result<Vec<ReferenceFolder>, AppError> {
+  let workspace = PathBuf::from(&workspace_path);
+  let settings = WorkspaceSettings::load_or_default(&workspace).watcher;
+  let available = settings.reference_roots(&workspace);
+  Ok(
+    settings
+      .reference_folders
+      .iter()
+      .map(|folder| {
+        let path = PathBuf::from(folder.trim());
+        ReferenceFolder {
+          name: path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| folder.clone()),
+          available: available.contains(&path),
+          path: path.to_string_lossy().to_string(),
+        }
+      })
+      .collect(),
+  )
+}
+
 // ⚠️ Week 17.1.2：检查文件是否被外部修改
 #[tauri::command]
 pub async fn check_external_modification(
   path: String,
   last_modified_ms: u64, // 毫秒时间戳
-) -> Result<bool, String> {
+) -> Result<bool, AppError> {
   let file_path = PathBuf::from(&path);
 
   // 将毫秒时间戳转换为 SystemTime
@@ -507,12 +858,12 @@ pub async fn check_external_modification(
     .ok_or("时间戳转换失败")?;
 
   let service = FileSystemService::new();
-  service.check_external_modification(&file_path, last_modified)
+  Ok(service.check_external_modification(&file_path, last_modified)?)
 }
 
 // 获取文件大小
 #[tauri::command]
-pub async fn get_file_size(path: String) -> Result<u64, String> {
+pub async fn get_file_size(path: String) -> Result<u64, AppError> {
   let file_path = PathBuf::from(&path);
   let metadata = std::fs::metadata(&file_path).map_err(|e| format!("获取文件信息失败: {}", e))?;
   Ok(metadata.len())
@@ -520,7 +871,7 @@ pub async fn get_file_size(path: String) -> Result<u64, String> {
 
 // 获取文件修改时间
 #[tauri::command]
-pub async fn get_file_modified_time(path: String) -> Result<u64, String> {
+pub async fn get_file_modified_time(path: String) -> Result<u64, AppError> {
   let file_path = PathBuf::from(&path);
   let modified_time = FileSystemService::get_file_modified_time(&file_path)?;
 
@@ -537,20 +888,21 @@ pub async fn get_file_modified_time(path: String) -> Result<u64, String> {
 pub async fn move_file_to_workspace(
   source_path: String,
   workspace_path: String,
-) -> Result<String, String> {
+) -> Result<String, AppError> {
   let source = PathBuf::from(&source_path);
+  ensure_not_reference_path(&source)?;
   let dest_dir = PathBuf::from(&workspace_path);
   let workspace_root = PathValidator::validate_workspace_path(&dest_dir, &dest_dir)
     .map_err(|e| format!("目标工作区非法: {}", e))?;
 
   // 检查源文件是否存在
   if !source.exists() {
-    return Err(format!("源文件不存在: {}", source_path));
+    return Err(AppError::NotFound(source_path));
   }
 
   // 检查目标目录是否存在
   if !dest_dir.exists() {
-    return Err(format!("目标目录不存在: {}", workspace_path));
+    return Err(AppError::NotFound(workspace_path));
   }
 
   // 获取文件名
@@ -614,7 +966,7 @@ pub async fn move_file_to_workspace(
 
 // ⚠️ Week 18.2：重命名文件或文件夹
 #[tauri::command]
-pub async fn rename_file(path: String, new_name: String) -> Result<(), String> {
+pub async fn rename_file(path: String, new_name: String) -> Result<(), AppError> {
   let source = PathBuf::from(&path);
   let workspace_root = require_workspace_root_for_path(&source)?;
   let safe_source = PathValidator::validate_workspace_path(&source, &workspace_root)
@@ -628,7 +980,7 @@ pub async fn rename_file(path: String, new_name: String) -> Result<(), String> {
     .map_err(|e| format!("目标路径非法: {}", e))?;
 
   if safe_dest.exists() {
-    return Err(format!("文件已存在: {}", new_name));
+    return Err(AppError::AlreadyExists(new_name));
   }
 
   std::fs::rename(&safe_source, &safe_dest).map_err(|e| format!("重命名失败: {}", e))?;
@@ -660,13 +1012,13 @@ pub async fn rename_file(path: String, new_name: String) -> Result<(), String> {
         )
         .await
       {
-        eprintln!(
+        tracing::warn!(
           "[memory] rename_file: rebind content memories failed: {:?}",
           e
         );
       }
     }
-    Err(e) => eprintln!("[memory] rename_file: MemoryService init failed: {}", e),
+    Err(e) => tracing::warn!("[memory] rename_file: MemoryService init failed: {}", e),
   }
 
   Ok(())
@@ -674,7 +1026,7 @@ pub async fn rename_file(path: String, new_name: String) -> Result<(), String> {
 
 // ⚠️ Week 18.2：删除文件或文件夹
 #[tauri::command]
-pub async fn delete_file(path: String) -> Result<(), String> {
+pub async fn delete_file(path: String) -> Result<(), AppError> {
   let path_buf = PathBuf::from(&path);
   let workspace_root = require_workspace_root_for_path(&path_buf)?;
   let safe_path = PathValidator::validate_workspace_path(&path_buf, &workspace_root)
@@ -682,7 +1034,7 @@ pub async fn delete_file(path: String) -> Result<(), String> {
   let is_dir_delete = path_buf.is_dir();
 
   if !safe_path.exists() {
-    return Err(format!("文件不存在: {}", path));
+    return Err(AppError::NotFound(path));
   }
 
   if safe_path.is_dir() {
@@ -690,6 +1042,7 @@ pub async fn delete_file(path: String) -> Result<(), String> {
   } else {
     std::fs::remove_file(&safe_path).map_err(|e| format!("删除文件失败: {}", e))?;
   }
+  remove_index_after_delete(&workspace_root, &safe_path);
 
   let db = WorkspaceDb::new(&workspace_root)?;
   let _ = record_resource_structure_timeline_node(
@@ -713,13 +1066,13 @@ pub async fn delete_file(path: String) -> Result<(), String> {
         .expire_content_memories_for_path(&safe_path.to_string_lossy(), is_dir_delete)
         .await
       {
-        eprintln!(
+        tracing::warn!(
           "[memory] delete_file: expire content memories failed: {:?}",
           e
         );
       }
     }
-    Err(e) => eprintln!("[memory] delete_file: MemoryService init failed: {}", e),
+    Err(e) => tracing::warn!("[memory] delete_file: MemoryService init failed: {}", e),
   }
 
   Ok(())
@@ -743,29 +1096,38 @@ fn infer_workspace_root_from_path(path: &Path) -> Option<PathBuf> {
   None
 }
 
-fn require_workspace_root_for_path(path: &Path) -> Result<PathBuf, String> {
+/// 参考资料文件夹只读：拒绝写入、重命名、删除或移出
+fn ensure_not_reference_path(path: &Path) -> Result<(), AppError> {
+  if is_reference_path(path) {
+    return Err(AppError::ReadOnly(path.to_string_lossy().to_string()));
+  }
+  Ok(())
+}
+
+fn require_workspace_root_for_path(path: &Path) -> Result<PathBuf, AppError> {
+  ensure_not_reference_path(path)?;
   infer_workspace_root_from_path(path).ok_or_else(|| {
-    format!(
+    AppError::InvalidInput(format!(
       "无法识别工作区根目录，拒绝工作区外写入: {}",
       path.to_string_lossy()
-    )
+    ))
   })
 }
 
 // ⚠️ Week 18.2：复制文件
 #[tauri::command]
-pub async fn duplicate_file(path: String) -> Result<String, String> {
+pub async fn duplicate_file(path: String) -> Result<String, AppError> {
   let source = PathBuf::from(&path);
   let workspace_root = require_workspace_root_for_path(&source)?;
   let safe_source = PathValidator::validate_workspace_path(&source, &workspace_root)
     .map_err(|e| format!("复制路径非法: {}", e))?;
 
   if !safe_source.exists() {
-    return Err(format!("文件不存在: {}", path));
+    return Err(AppError::NotFound(path));
   }
 
   if safe_source.is_dir() {
-    return Err("暂不支持复制文件夹".to_string());
+    return Err(AppError::InvalidInput("暂不支持复制文件夹".to_string()));
   }
 
   let parent = safe_source
@@ -825,9 +1187,10 @@ pub async fn move_file(
   destination_path: String,
   workspace_path: Option<String>,
   app: tauri::AppHandle,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
   let source = PathBuf::from(&source_path);
   let dest = PathBuf::from(&destination_path);
+  ensure_not_reference_path(&source)?;
   let workspace_root = workspace_path
     .as_ref()
     .and_then(|ws| {
@@ -847,17 +1210,19 @@ pub async fn move_file(
 
   // 检查源文件是否存在
   if !safe_source.exists() {
-    return Err(format!("源文件不存在: {}", source_path));
+    return Err(AppError::NotFound(source_path));
   }
 
   // 检查目标文件是否已存在
   if safe_dest.exists() {
-    return Err(format!("目标文件已存在: {}", destination_path));
+    return Err(AppError::AlreadyExists(destination_path));
   }
 
   // 检查是否尝试移动到自己的子目录
   if safe_dest.starts_with(&safe_source) {
-    return Err("不能将文件移动到自己的子目录中".to_string());
+    return Err(AppError::InvalidInput(
+      "不能将文件移动到自己的子目录中".to_string(),
+    ));
   }
 
   // 创建目标目录的父目录（如果不存在）
@@ -898,13 +1263,13 @@ pub async fn move_file(
         )
         .await
       {
-        eprintln!(
+        tracing::warn!(
           "[memory] move_file: rebind content memories failed: {:?}",
           e
         );
       }
     }
-    Err(e) => eprintln!("[memory] move_file: MemoryService init failed: {}", e),
+    Err(e) => tracing::warn!("[memory] move_file: MemoryService init failed: {}", e),
   }
 
   // 触发文件树变化事件
@@ -960,7 +1325,7 @@ fn copy_dir_all(src: &PathBuf, dst: &PathBuf) -> Result<(), String> {
 
 /// 检查 Pandoc 是否可用
 #[tauri::command]
-pub async fn check_pandoc_available() -> Result<serde_json::Value, String> {
+pub async fn check_pandoc_available() -> Result<serde_json::Value, AppError> {
   let pandoc_service = PandocService::new();
 
   let is_available = pandoc_service.is_available();
@@ -981,15 +1346,126 @@ pub async fn check_pandoc_available() -> Result<serde_json::Value, String> {
   }))
 }
 
+/// 检查 LibreOffice 是否可用（路径、版本、是否内置），不可用时附带安装指引
+#[tauri::command]
+pub async fn check_libreoffice_available() -> Result<serde_json::Value, AppError> {
+  let (path, is_bundled) = tokio::task::spawn_blocking(|| {
+    let service = LibreOfficeService::new()?;
+    Ok::<_, String>((service.get_libreoffice_path().ok(), service.is_bundled()))
+  })
+  .await
+  .map_err(|e| format!("检测 LibreOffice 失败: {}", e))??;
+
+  let version = match &path {
+    Some(path) => {
+      let path = path.clone();
+      tokio::task::spawn_blocking(move || LibreOfficeService::get_version(&path))
+        .await
+        .unwrap_or(None)
+    }
+    None => None,
+  };
+
+  Ok(serde_json::json!({
+      "available": path.is_some(),
+      "is_bundled": is_bundled,
+      "path": path.map(|p| p.to_string_lossy().to_string()),
+      "version": version,
+      "install_command": install_plan().map(|plan| plan.display()),
+      "download_url": LIBREOFFICE_DOWNLOAD_URL,
+  }))
+}
+
+/// 通过平台包管理器安装 LibreOffice，安装输出以 `libreoffice-install-progress` 事件逐行推送
+#[tauri::command]
+pub async fn install_libreoffice(app: AppHandle) -> Result<serde_json::Value, AppError> {
+  use tokio::io::{AsyncBufReadExt, BufReader};
+
+  let plan = install_plan().ok_or_else(|| {
+    format!(
+      "未找到可用的包管理器，请前往官网下载安装 LibreOffice：{}",
+      LIBREOFFICE_DOWNLOAD_URL
+    )
+  })?;
+  let emit = |status: &str, message: &str| {
+    app
+      .emit(
+        "libreoffice-install-progress",
+        serde_json::json!({ "status": status, "message": message }),
+      )
+      .ok();
+  };
+  emit("started", &plan.display());
+
+  let mut child = tokio::process::Command::new(&plan.program)
+    .args(&plan.args)
+    .stdout(std::process::Stdio::piped())
+    .stderr(std::process::Stdio::piped())
+    .spawn()
+    .map_err(|e| format!("启动安装程序失败: {}", e))?;
+
+  // stdout 与 stderr 同时转发，避免任一管道写满阻塞安装进程
+  let forward = |pipe: Option<Box<dyn tokio::io::AsyncRead + Unpin + Send>>| {
+    let app = app.clone();
+    tokio::spawn(async move {
+      let Some(pipe) = pipe else {
+        return;
+      };
+      let mut lines = BufReader::new(pipe).lines();
+      while let Ok(Some(line)) = lines.next_line().await {
+        if !line.trim().is_empty() {
+          app
+            .emit(
+              "libreoffice-install-progress",
+              serde_json::json!({ "status": "output", "message": line }),
+            )
+            .ok();
+        }
+      }
+    })
+  };
+  let stdout = forward(child.stdout.take().map(|p| Box::new(p) as _));
+  let stderr = forward(child.stderr.take().map(|p| Box::new(p) as _));
+
+  let status = child
+    .wait()
+    .await
+    .map_err(|e| format!("等待安装程序失败: {}", e))?;
+  let _ = tokio::join!(stdout, stderr);
+
+  if !status.success() {
+    let error_msg = format!(
+      "LibreOffice 安装失败（{}），可前往官网手动安装：{}",
+      status, LIBREOFFICE_DOWNLOAD_URL
+    );
+    emit("failed", &error_msg);
+    return Err(error_msg.into());
+  }
+
+  let result = check_libreoffice_available().await?;
+  if result["available"].as_bool() == Some(true) {
+    emit("completed", "LibreOffice 安装完成");
+  } else {
+    emit(
+      "failed",
+      "安装命令已完成，但仍未检测到 LibreOffice，请重启应用后重试",
+    );
+  }
+  Ok(result)
+}
+
 /// 打开 DOCX 文件进行编辑（使用 Pandoc 转换）
 /// 返回 HTML 内容，供 TipTap 编辑器使用
 #[tauri::command]
-pub async fn open_docx_for_edit(path: String) -> Result<String, String> {
+pub async fn open_docx_for_edit(
+  path: String,
+  workspace_path: Option<String>,
+) -> Result<String, AppError> {
   let docx_path = PathBuf::from(&path);
 
   // 1. 检查文件是否存在
   if !docx_path.exists() {
-    return Err(format!("文件不存在: {}", path));
+    return Err(AppError::NotFound(path));
   }
 
   // 2. 检查文件大小（限制 100MB）
@@ -998,69 +1474,81 @@ pub async fn open_docx_for_edit(path: String) -> Result<String, String> {
   const MAX_FILE_SIZE: u64 = 100 * 1024 * 1024; // 100MB
 
   if file_size > MAX_FILE_SIZE {
-    return Err(format!(
+    return Err(AppError::InvalidInput(format!(
       "文件过大（{:.2} MB），超过限制（100 MB）。请使用较小的文件。",
       file_size as f64 / 1024.0 / 1024.0
-    ));
+    )));
   }
 
-  eprintln!(
-    "📂 [open_docx_for_edit] 开始打开 DOCX 文件进行编辑: {}",
-    path
-  );
-  eprintln!("📂 [open_docx_for_edit] 文件路径: {:?}", docx_path);
+  tracing::debug!("[open_docx_for_edit] 开始打开 DOCX 文件进行编辑: {}", path);
+  tracing::debug!("[open_docx_for_edit] 文件路径: {:?}", docx_path);
 
   // 3. 使用 Pandoc 方案（与预览模式相同）
-  eprintln!("📂 [open_docx_for_edit] 创建 PandocService...");
+  tracing::debug!("[open_docx_for_edit] 创建 PandocService...");
   let pandoc_service = PandocService::new();
 
-  eprintln!("📂 [open_docx_for_edit] 检查 Pandoc 可用性...");
+  tracing::debug!("[open_docx_for_edit] 检查 Pandoc 可用性...");
   if !pandoc_service.is_available() {
-    eprintln!("❌ [open_docx_for_edit] Pandoc 不可用");
-    return Err("Pandoc 不可用，请安装 Pandoc 或确保内置 Pandoc 可用。\n访问 https://pandoc.org/installing.html 获取安装指南。".to_string());
+    tracing::error!("[open_docx_for_edit] Pandoc 不可用");
+    return Err(AppError::PandocUnavailable);
   }
-  eprintln!("✅ [open_docx_for_edit] Pandoc 可用");
+  tracing::info!("[open_docx_for_edit] Pandoc 可用");
 
   // 4. 转换 DOCX 到 HTML（使用与预览模式相同的逻辑）
-  eprintln!("📂 [open_docx_for_edit] 开始转换 DOCX 到 HTML...");
+  tracing::debug!("[open_docx_for_edit] 开始转换 DOCX 到 HTML...");
   let html = match std::panic::catch_unwind(|| {
     // 编辑模式：传入文档所在目录，使 Pandoc --extract-media=. 解压到该目录，图片能被找到并转 base64；预览等其它路径不调用本函数
     pandoc_service.convert_document_to_html(&docx_path, docx_path.parent())
   }) {
     Ok(Ok(html)) => {
-      eprintln!(
-        "✅ [open_docx_for_edit] Pandoc 转换成功，HTML 长度: {} 字节",
+      tracing::info!(
+        "[open_docx_for_edit] Pandoc 转换成功，HTML 长度: {} 字节",
         html.len()
       );
       html
     }
     Ok(Err(e)) => {
-      eprintln!("❌ [open_docx_for_edit] Pandoc 转换失败: {}", e);
-      return Err(format!("DOCX 转换失败: {}", e));
+      tracing::error!("[open_docx_for_edit] Pandoc 转换失败: {}", e);
+      return Err(AppError::ConversionFailed {
+        tool: "Pandoc",
+        message: e,
+      });
     }
     Err(panic_info) => {
-      eprintln!(
-        "❌ [open_docx_for_edit] Pandoc 转换 panic: {:?}",
-        panic_info
-      );
-      return Err("DOCX 转换失败（panic）".to_string());
+      tracing::error!("[open_docx_for_edit] Pandoc 转换 panic: {:?}", panic_info);
+      return Err(AppError::ConversionFailed {
+        tool: "Pandoc",
+        message: "panic".to_string(),
+      });
     }
   };
 
+  // 清理文档中携带的原始 HTML（脚本、iframe、事件属性），文档所在工作区被用户信任时跳过
+  let trusted = workspace_path.is_some_and(|workspace| {
+    let workspace = Path::new(&workspace);
+    PathValidator::validate_workspace_path(&docx_path, workspace).is_ok()
+      && settings_service::document_html_trusted(workspace)
+  });
+  let html = if trusted {
+    html
+  } else {
+    sanitize_html_document(&html)
+  };
+
   // 5. 限制返回 HTML 大小，避免超大内容导致 WebView/编辑器崩溃（OOM 或闪退）
   const MAX_HTML_BYTES: usize = 15 * 1024 * 1024; // 15MB
   if html.len() > MAX_HTML_BYTES {
-    eprintln!("❌ [open_docx_for_edit] 转换后 HTML 过大 ({} MB)，超过编辑模式限制 (15 MB)，可能导致应用崩溃", html.len() / 1024 / 1024);
-    return Err(format!(
+    tracing::error!(
+      "[open_docx_for_edit] 转换后 HTML 过大 ({} MB)，超过编辑模式限制 (15 MB)，可能导致应用崩溃",
+      html.len() / 1024 / 1024
+    );
+    return Err(AppError::InvalidInput(format!(
             "文档内容过大（转换后约 {:.1} MB），编辑模式暂不支持超过 15 MB 的文档，可能造成应用卡顿或闪退。\n建议：使用「预览」模式查看，或先缩小文档（如减少图片、分拆文档）后再编辑。",
             html.len() as f64 / 1024.0 / 1024.0
-        ));
+        )));
   }
 
-  eprintln!(
-    "✅ [open_docx_for_edit] 完成，返回 HTML ({} 字节)",
-    html.len()
-  );
+  tracing::info!("[open_docx_for_edit] 完成，返回 HTML ({} 字节)", html.len());
   // [Bug1-Debug] 返回前：body 开头字节（用于定位空白行根因）
   if let Some(pos) = html.find("<body") {
     let body_open = html[pos..]
@@ -1089,11 +1577,11 @@ pub async fn open_docx_for_edit(path: String) -> Result<String, String> {
         }
       })
       .collect();
-    eprintln!(
+    tracing::debug!(
       "[Bug1-Debug] open_docx_for_edit 返回前: body>后首30字节(hex)={}",
       first_30_hex
     );
-    eprintln!(
+    tracing::debug!(
       "[Bug1-Debug] open_docx_for_edit 返回前: body>后首200字符(repr)={}",
       first_200_repr
     );
@@ -1104,11 +1592,11 @@ pub async fn open_docx_for_edit(path: String) -> Result<String, String> {
 /// 创建 DOCX 文件的草稿副本
 /// 返回草稿文件路径
 #[tauri::command]
-pub async fn create_draft_docx(original_path: String) -> Result<String, String> {
+pub async fn create_draft_docx(original_path: String) -> Result<String, AppError> {
   let original = PathBuf::from(&original_path);
 
   if !original.exists() {
-    return Err(format!("原文件不存在: {}", original_path));
+    return Err(AppError::NotFound(original_path));
   }
 
   // 生成草稿文件路径：document.docx -> document.draft.docx
@@ -1139,11 +1627,11 @@ pub async fn create_draft_docx(original_path: String) -> Result<String, String>
 /// 创建文件的草稿副本（通用方法，支持所有文件类型）
 /// 返回草稿文件路径
 #[tauri::command]
-pub async fn create_draft_file(original_path: String) -> Result<String, String> {
+pub async fn create_draft_file(original_path: String) -> Result<String, AppError> {
   let original = PathBuf::from(&original_path);
 
   if !original.exists() {
-    return Err(format!("原文件不存在: {}", original_path));
+    return Err(AppError::NotFound(original_path));
   }
 
   // 生成草稿文件路径：document.html -> document.draft.html
@@ -1176,15 +1664,15 @@ pub async fn create_draft_file(original_path: String) -> Result<String, String>
 /// 保存 DOCX 文件（将 HTML 内容转换为 DOCX）
 /// 列出文件夹内的所有文件路径（递归）
 #[tauri::command]
-pub async fn list_folder_files(path: String) -> Result<Vec<String>, String> {
+pub async fn list_folder_files(path: String) -> Result<Vec<String>, AppError> {
   let folder_path = PathBuf::from(&path);
 
   if !folder_path.exists() {
-    return Err(format!("文件夹不存在: {}", path));
+    return Err(AppError::NotFound(path));
   }
 
   if !folder_path.is_dir() {
-    return Err(format!("路径不是文件夹: {}", path));
+    return Err(AppError::InvalidInput(format!("路径不是文件夹: {}", path)));
   }
 
   let mut files = Vec::new();
@@ -1219,13 +1707,16 @@ pub async fn list_folder_files(path: String) -> Result<Vec<String>, String> {
   Ok(files)
 }
 
+/// 外部文件引用的临时副本保留时长
+const EXTERNAL_FILE_TTL: std::time::Duration = std::time::Duration::from_secs(24 * 3600);
+
 /// 保存外部文件到临时目录（用于文件引用）
 #[tauri::command]
 pub async fn save_external_file(
   workspace_path: String,
   file_data: Vec<u8>,
   file_name: String,
-) -> Result<String, String> {
+) -> Result<String, AppError> {
   let workspace = PathBuf::from(&workspace_path);
 
   // 1. 确定临时文件目录（工作区根目录下的 .binder/temp 目录）
@@ -1269,6 +1760,8 @@ pub async fn save_external_file(
 
   // 4. 写入文件
   std::fs::write(&temp_file_path, file_data).map_err(|e| format!("写入临时文件失败: {}", e))?;
+  // 引用随消息发送后即可删除；未被清理的 24 小时后过期，由定期清理或下次启动时删除
+  temp_service::register(&temp_file_path, "external_file", Some(EXTERNAL_FILE_TTL));
 
   // 5. 返回相对路径（相对于工作区）
   let relative_path = temp_file_path
@@ -1286,7 +1779,7 @@ pub async fn save_external_file(
 pub async fn cleanup_temp_files(
   workspace_path: String,
   file_paths: Vec<String>,
-) -> Result<usize, String> {
+) -> Result<usize, AppError> {
   let workspace = PathBuf::from(&workspace_path);
   let mut cleaned_count = 0;
 
@@ -1295,21 +1788,14 @@ pub async fn cleanup_temp_files(
 
     // 验证路径安全性：确保路径在 .binder/temp 目录下
     if !file_path.starts_with(".binder/temp/") {
-      eprintln!("⚠️ 跳过不安全的路径: {}", file_path);
+      tracing::warn!("跳过不安全的路径: {}", file_path);
       continue;
     }
 
-    // 删除文件
-    if full_path.exists() && full_path.is_file() {
-      match std::fs::remove_file(&full_path) {
-        Ok(_) => {
-          cleaned_count += 1;
-          eprintln!("✅ 已清理临时文件: {}", file_path);
-        }
-        Err(e) => {
-          eprintln!("⚠️ 清理临时文件失败: {} - {}", file_path, e);
-        }
-      }
+    // 删除文件并注销
+    if full_path.is_file() && temp_service::remove(&full_path) {
+      cleaned_count += 1;
+      tracing::info!("已清理临时文件: {}", file_path);
     }
   }
 
@@ -1321,125 +1807,65 @@ pub async fn cleanup_temp_files(
 pub async fn cleanup_expired_temp_files(
   workspace_path: String,
   max_age_hours: u64,
-) -> Result<usize, String> {
-  let workspace = PathBuf::from(&workspace_path);
-  let temp_dir = workspace.join(".binder").join("temp");
-
-  if !temp_dir.exists() {
-    return Ok(0);
-  }
-
+) -> Result<usize, AppError> {
+  let temp_dir = PathBuf::from(&workspace_path).join(".binder").join("temp");
   let max_age = std::time::Duration::from_secs(max_age_hours * 3600);
-  let now = SystemTime::now();
-  let mut cleaned_count = 0;
-
-  // 遍历临时目录中的所有文件
-  let entries = std::fs::read_dir(&temp_dir).map_err(|e| format!("读取临时目录失败: {}", e))?;
-
-  for entry in entries {
-    let entry = entry.map_err(|e| format!("读取目录项失败: {}", e))?;
-    let path = entry.path();
-
-    if !path.is_file() {
-      continue;
-    }
-
-    // 获取文件修改时间
-    if let Ok(metadata) = path.metadata() {
-      if let Ok(modified) = metadata.modified() {
-        if let Ok(age) = now.duration_since(modified) {
-          // 如果文件超过指定时间，删除它
-          if age > max_age {
-            match std::fs::remove_file(&path) {
-              Ok(_) => {
-                cleaned_count += 1;
-                eprintln!("✅ 已清理过期临时文件: {:?}", path);
-              }
-              Err(e) => {
-                eprintln!("⚠️ 清理过期临时文件失败: {:?} - {}", path, e);
-              }
-            }
-          }
-        }
-      }
-    }
+  let cleaned_count = temp_service::cleanup_dir(&temp_dir, Some(max_age))?;
+  if cleaned_count > 0 {
+    tracing::info!("已清理 {} 个过期临时文件", cleaned_count);
   }
-
   Ok(cleaned_count)
 }
 
-/// 清理所有临时文件（谨慎使用）
+/// 本应用登记的临时文件（所属模块、创建与过期时间），按创建时间排序
 #[tauri::command]
-pub async fn cleanup_all_temp_files(workspace_path: String) -> Result<usize, String> {
-  let workspace = PathBuf::from(&workspace_path);
-  let temp_dir = workspace.join(".binder").join("temp");
-
-  if !temp_dir.exists() {
-    return Ok(0);
-  }
+pub async fn list_temp_files() -> Result<Vec<TempArtifact>, AppError> {
+  Ok(temp_service::list())
+}
 
-  let mut cleaned_count = 0;
+/// 清理所有临时文件（谨慎使用）
+#[tauri::command]
+pub async fn cleanup_all_temp_files(workspace_path: String) -> Result<usize, AppError> {
+  let temp_dir = PathBuf::from(&workspace_path).join(".binder").join("temp");
+  Ok(temp_service::cleanup_dir(&temp_dir, None)?)
+}
 
-  // 遍历临时目录中的所有文件
-  let entries = std::fs::read_dir(&temp_dir).map_err(|e| format!("读取临时目录失败: {}", e))?;
+/// 预览缓存占用情况（条目、总大小、上限）
+#[tauri::command]
+pub async fn get_preview_cache_info() -> Result<PreviewCacheInfo, AppError> {
+  Ok(PreviewCache::new(PreviewCache::default_dir()?).info()?)
+}
 
-  for entry in entries {
-    let entry = entry.map_err(|e| format!("读取目录项失败: {}", e))?;
-    let path = entry.path();
-
-    if path.is_file() {
-      match std::fs::remove_file(&path) {
-        Ok(_) => {
-          cleaned_count += 1;
-        }
-        Err(e) => {
-          eprintln!("⚠️ 清理临时文件失败: {:?} - {}", path, e);
-        }
-      }
-    }
-  }
-
-  Ok(cleaned_count)
-}
-
-/// 一键清除预览缓存（仅清除 PDF 缓存与 temp，保留 lo_user 以保持预览默认字体一致）
-#[tauri::command]
-pub async fn clear_preview_cache() -> Result<String, String> {
-  let app_data_dir = dirs::data_dir().ok_or_else(|| "无法获取应用数据目录".to_string())?;
-  let cache_dir = app_data_dir.join("binder").join("cache").join("preview");
-  if !cache_dir.exists() {
-    return Ok("预览缓存目录不存在，无需清除".to_string());
-  }
-  let mut removed = 0u32;
-  // 只删除缓存的 PDF 文件与 temp 目录，保留 lo_user（字体配置 profile），避免清除后预览字体随机
-  if let Ok(entries) = std::fs::read_dir(&cache_dir) {
-    for entry in entries.flatten() {
-      let path = entry.path();
-      let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
-      if name.ends_with(".pdf") {
-        if std::fs::remove_file(&path).is_ok() {
-          removed += 1;
-        }
-      } else if name == "temp" && path.is_dir() {
-        let _ = std::fs::remove_dir_all(&path);
-        removed += 1;
-      }
-    }
-  }
-  // 不删除 lo_user，保证 DOCX/PPTX/Excel 转 PDF 时默认字体（如 PingFang SC / Arial）稳定
-  eprintln!(
-    "✅ [clear_preview_cache] 已清除 PDF 与 temp，保留 lo_user: {:?}",
-    cache_dir
-  );
-  Ok("预览缓存已清除，下次预览将重新生成（默认字体配置已保留）".to_string())
-}
+/// 清除预览缓存；传入 older_than_secs 时只清除超过该时长未访问的 PDF，
+/// 否则清除全部 PDF 与 temp。始终保留 lo_user 以保持预览默认字体一致
+#[tauri::command]
+pub async fn clear_preview_cache(
+  older_than_secs: Option<u64>,
+) -> Result<PreviewCacheClearResult, AppError> {
+  let cache_dir = PreviewCache::default_dir()?;
+  if !cache_dir.exists() {
+    return Ok(PreviewCacheClearResult {
+      removed_count: 0,
+      freed_bytes: 0,
+    });
+  }
+  let result = PreviewCache::new(cache_dir.clone())
+    .clear(older_than_secs.map(std::time::Duration::from_secs))?;
+  tracing::info!(
+    "[clear_preview_cache] 已清除 {} 个 PDF（{} 字节），保留 lo_user: {:?}",
+    result.removed_count,
+    result.freed_bytes,
+    cache_dir
+  );
+  Ok(result)
+}
 
 #[tauri::command]
 pub async fn save_docx(
   path: String,
   html_content: String,
   app: tauri::AppHandle,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
   // [BlankLineDebug] Rust 端保存日志：用于与前端、重开后对比
   let first = html_content.chars().take(300).collect::<String>();
   let last = html_content
@@ -1453,14 +1879,14 @@ pub async fn save_docx(
   let empty_p_count = html_content.matches("<p></p>").count()
     + html_content.matches("<p><br></p>").count()
     + html_content.matches("<p><br/></p>").count();
-  eprintln!("[BlankLineDebug] Rust save_docx 收到请求: path={}, htmlLen={}, emptyPCount≈{}, first300=[...], last300=[...]", path, html_content.len(), empty_p_count);
-  eprintln!("[BlankLineDebug] first300: {}", first);
-  eprintln!("[BlankLineDebug] last300: {}", last);
+  tracing::debug!("[BlankLineDebug] Rust save_docx 收到请求: path={}, htmlLen={}, emptyPCount≈{}, first300=[...], last300=[...]", path, html_content.len(), empty_p_count);
+  tracing::debug!("[BlankLineDebug] first300: {}", first);
+  tracing::debug!("[BlankLineDebug] last300: {}", last);
 
   let pandoc_service = PandocService::new();
 
   if !pandoc_service.is_available() {
-    return Err("Pandoc 不可用，请安装 Pandoc 以支持 DOCX 文件".to_string());
+    return Err(AppError::PandocUnavailable);
   }
 
   let docx_path = PathBuf::from(&path);
@@ -1489,8 +1915,17 @@ pub async fn save_docx(
     )
     .map_err(|e| format!("发送进度事件失败: {}", e))?;
 
-  pandoc_service.convert_html_to_docx(&html_content, &docx_path)?;
-  eprintln!("[BlankLineDebug] Rust save_docx 转换完成: path={}", path);
+  pandoc_service
+    .convert_html_to_docx(&html_content, &docx_path)
+    .map_err(|message| AppError::ConversionFailed {
+      tool: "Pandoc",
+      message,
+    })?;
+  tracing::debug!("[BlankLineDebug] Rust save_docx 转换完成: path={}", path);
+
+  if let Some(workspace_root) = infer_workspace_root_from_path(&docx_path) {
+    refresh_index_after_save(&workspace_root, &docx_path, None);
+  }
 
   // 触发完成事件
   app
@@ -1509,1033 +1944,754 @@ pub async fn save_docx(
 
 // ==================== 预览相关命令 ====================
 
-/// 预览 DOCX 文件为 PDF（新方案）
-///
-/// **功能**：转换 DOCX → PDF，返回 PDF 文件路径
-///
-/// **使用场景**：
-/// - DocxPdfPreview 组件内部调用
-/// - 预览模式（isReadOnly = true）
-///
-/// **返回**：PDF 文件路径（file:// 绝对路径）
-///
-/// **缓存机制**：
-/// - 缓存键：文件路径 + 修改时间
-/// - 缓存过期：1 小时
-/// - 缓存位置：应用缓存目录
-#[tauri::command]
-pub async fn preview_docx_as_pdf(path: String, app: AppHandle) -> Result<String, String> {
-  let docx_path = PathBuf::from(&path);
-
-  // 检查文件是否存在
-  if !docx_path.exists() {
-    return Err(format!("文件不存在: {}", path));
-  }
-
-  // 规范化文件路径（用于去重）
-  let normalized_path = docx_path
-    .canonicalize()
-    .unwrap_or_else(|_| docx_path.clone())
-    .to_string_lossy()
-    .to_string();
-
-  eprintln!(
-    "🔍 [preview_docx_as_pdf] 开始预览: {:?} (规范化路径: {})",
-    docx_path, normalized_path
-  );
-
-  // 检查是否有正在进行的预览请求
-  let (tx, rx) = oneshot::channel();
-  let is_first_request = {
-    let mut requests = PREVIEW_REQUESTS.lock().unwrap();
-    if requests.contains_key(&normalized_path) {
-      // 已有请求在进行，等待第一个请求完成
-      eprintln!(
-        "⏳ [preview_docx_as_pdf] 检测到并发请求，等待第一个请求完成: {}",
-        normalized_path
-      );
-      false
-    } else {
-      // 这是第一个请求，注册它
-      requests.insert(normalized_path.clone(), tx);
-      eprintln!("✅ [preview_docx_as_pdf] 注册为新请求: {}", normalized_path);
-      true
-    }
-  };
-
-  // 如果不是第一个请求，等待第一个请求的结果
-  if !is_first_request {
-    eprintln!("⏳ [preview_docx_as_pdf] 等待第一个请求完成...");
-    match rx.await {
-      Ok(result) => {
-        eprintln!("✅ [preview_docx_as_pdf] 收到第一个请求的结果");
-        return result;
-      }
-      Err(_) => {
-        eprintln!("⚠️ [preview_docx_as_pdf] 第一个请求的发送器已关闭，重新发起请求");
-        // 发送器已关闭，说明第一个请求失败了，重新发起
-        let mut requests = PREVIEW_REQUESTS.lock().unwrap();
-        requests.remove(&normalized_path);
-      }
-    }
-  }
-
-  // 发送预览进度事件：开始
-  app
-    .emit(
-      "preview-progress",
-      serde_json::json!({
-          "status": "started",
-          "message": "正在预览..."
-      }),
-    )
-    .ok();
-
-  // 创建 LibreOffice 服务
-  let lo_service = LibreOfficeService::new().map_err(|e| {
-    let error_msg = format!("LibreOffice 服务初始化失败: {}", e);
-    app
-      .emit(
-        "preview-progress",
-        serde_json::json!({
-            "status": "failed",
-            "message": &error_msg
-        }),
-      )
-      .ok();
-    error_msg
-  })?;
+fn emit_preview_progress(app: &AppHandle, payload: serde_json::Value) {
+  app.emit("preview-progress", payload).ok();
+}
 
-  // 检查 LibreOffice 是否可用（获取实际错误消息）
-  let libreoffice_path_result = lo_service.get_libreoffice_path();
-  if libreoffice_path_result.is_err() {
-    let error_msg = libreoffice_path_result.unwrap_err();
-    app
-      .emit(
-        "preview-progress",
-        serde_json::json!({
-            "status": "failed",
-            "message": &error_msg
-        }),
-      )
-      .ok();
-    return Err(error_msg);
+/// 结束预览请求：从 PREVIEW_REQUESTS 移除并把结果转发给等待的并发请求
+fn finish_preview_request(normalized_path: &str, result: &Result<String, String>) {
+  let mut requests = PREVIEW_REQUESTS.lock().unwrap();
+  if let Some(tx) = requests.remove(normalized_path) {
+    let _ = tx.send(result.clone());
   }
+}
 
-  // 发送预览进度事件：预览中
-  app
-    .emit(
-      "preview-progress",
-      serde_json::json!({
-          "status": "converting",
-          "message": "正在预览..."
-      }),
-    )
-    .ok();
-
-  // 执行转换（带超时：30秒）
-  let docx_path_clone = docx_path.clone();
-  let lo_service_arc = Arc::new(lo_service);
-  let pdf_path_result = tokio::time::timeout(
-    std::time::Duration::from_secs(30),
-    tokio::task::spawn_blocking(move || lo_service_arc.convert_docx_to_pdf(&docx_path_clone)),
-  )
-  .await;
-
-  let pdf_path = match pdf_path_result {
-    Ok(Ok(Ok(path))) => path,
-    Ok(Ok(Err(e))) => {
-      // 转换失败 - 收集详细的诊断信息
-      let mut diagnostics = Vec::new();
-
-      // 重新创建服务实例以获取缓存目录（因为之前的实例在闭包内被移动了）
-      // 或者直接使用相同的逻辑获取缓存目录路径
-      let app_data_dir = dirs::data_dir().ok_or_else(|| "无法获取应用数据目录".to_string())?;
-      let cache_dir = app_data_dir.join("binder").join("cache").join("preview");
-      let output_dir = cache_dir.join("temp");
-
-      // 检查输出目录
-      diagnostics.push(format!("输出目录: {:?}", output_dir));
-
-      if output_dir.exists() {
-        diagnostics.push("输出目录存在".to_string());
-        // 列出输出目录内容
-        if let Ok(entries) = std::fs::read_dir(&output_dir) {
-          let mut file_list = Vec::new();
-          for entry in entries {
-            if let Ok(entry) = entry {
-              let path = entry.path();
-              if let Ok(metadata) = std::fs::metadata(&path) {
-                file_list.push(format!(
-                  "{:?} ({} 字节)",
-                  path.file_name().unwrap_or_default(),
-                  metadata.len()
-                ));
-              }
-            }
-          }
-          if file_list.is_empty() {
-            diagnostics.push("输出目录为空".to_string());
-          } else {
-            diagnostics.push(format!("输出目录内容: {}", file_list.join(", ")));
-          }
+/// 转换失败时收集诊断信息（输出目录内容、LibreOffice 路径、输入文件）
+fn preview_failure_diagnostics(source_path: &Path) -> Vec<String> {
+  let mut diagnostics = Vec::new();
+
+  if let Ok(cache_dir) = PreviewCache::default_dir() {
+    let output_dir = cache_dir.join("temp");
+    diagnostics.push(format!("输出目录: {:?}", output_dir));
+    match std::fs::read_dir(&output_dir) {
+      Ok(entries) => {
+        let file_list: Vec<String> = entries
+          .flatten()
+          .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            Some(format!("{:?} ({} 字节)", entry.file_name(), metadata.len()))
+          })
+          .collect();
+        if file_list.is_empty() {
+          diagnostics.push("输出目录为空".to_string());
         } else {
-          diagnostics.push("无法读取输出目录".to_string());
+          diagnostics.push(format!("输出目录内容: {}", file_list.join(", ")));
         }
-      } else {
-        diagnostics.push("输出目录不存在".to_string());
       }
+      Err(_) if output_dir.exists() => diagnostics.push("无法读取输出目录".to_string()),
+      Err(_) => diagnostics.push("输出目录不存在".to_string()),
+    }
+  }
 
-      // 检查 LibreOffice 路径
-      if let Ok(diag_service) = LibreOfficeService::new() {
-        if let Ok(lo_path) = diag_service.get_libreoffice_path() {
-          diagnostics.push(format!("LibreOffice 路径: {:?}", lo_path));
-          if lo_path.exists() {
-            diagnostics.push("LibreOffice 可执行文件存在".to_string());
-          } else {
-            diagnostics.push("LibreOffice 可执行文件不存在".to_string());
-          }
+  if let Ok(diag_service) = LibreOfficeService::new() {
+    match diag_service.get_libreoffice_path() {
+      Ok(lo_path) => {
+        diagnostics.push(format!("LibreOffice 路径: {:?}", lo_path));
+        if lo_path.exists() {
+          diagnostics.push("LibreOffice 可执行文件存在".to_string());
         } else {
-          diagnostics.push("无法获取 LibreOffice 路径".to_string());
-        }
-      }
-
-      // 检查输入文件
-      diagnostics.push(format!("输入文件: {:?}", docx_path));
-      if docx_path.exists() {
-        if let Ok(metadata) = std::fs::metadata(&docx_path) {
-          diagnostics.push(format!("输入文件大小: {} 字节", metadata.len()));
+          diagnostics.push("LibreOffice 可执行文件不存在".to_string());
         }
-      } else {
-        diagnostics.push("输入文件不存在".to_string());
-      }
-
-      let error_msg = format!("预览失败: {}\n\n诊断信息:\n{}", e, diagnostics.join("\n"));
-
-      // 发送详细的错误信息到前端
-      app
-        .emit(
-          "preview-progress",
-          serde_json::json!({
-              "status": "failed",
-              "message": &error_msg,
-              "diagnostics": diagnostics
-          }),
-        )
-        .ok();
-
-      eprintln!("❌ [preview_docx_as_pdf] 转换失败:");
-      eprintln!("   错误: {}", e);
-      eprintln!("   诊断信息:");
-      for diag in &diagnostics {
-        eprintln!("     - {}", diag);
-      }
-
-      // 清理请求注册并通知等待的请求
-      let mut requests = PREVIEW_REQUESTS.lock().unwrap();
-      if let Some(tx) = requests.remove(&normalized_path) {
-        let _ = tx.send(Err(error_msg.clone()));
-      }
-
-      return Err(error_msg);
-    }
-    Ok(Err(e)) => {
-      // spawn_blocking 失败
-      let error_msg = format!("预览失败: {}", e);
-      app
-        .emit(
-          "preview-progress",
-          serde_json::json!({
-              "status": "failed",
-              "message": &error_msg
-          }),
-        )
-        .ok();
-
-      // 清理请求注册并通知等待的请求
-      let mut requests = PREVIEW_REQUESTS.lock().unwrap();
-      if let Some(tx) = requests.remove(&normalized_path) {
-        let _ = tx.send(Err(error_msg.clone()));
-      }
-
-      return Err(error_msg);
-    }
-    Err(_) => {
-      // 超时
-      let error_msg = "预览失败，你的文件过大或存在无法预览的格式，请调整文档。".to_string();
-      app
-        .emit(
-          "preview-progress",
-          serde_json::json!({
-              "status": "failed",
-              "message": &error_msg
-          }),
-        )
-        .ok();
-      eprintln!("⏱️ [preview_docx_as_pdf] 预览超时（30秒）");
-
-      // 清理请求注册并通知等待的请求
-      let mut requests = PREVIEW_REQUESTS.lock().unwrap();
-      if let Some(tx) = requests.remove(&normalized_path) {
-        let _ = tx.send(Err(error_msg.clone()));
       }
-
-      return Err(error_msg);
+      Err(_) => diagnostics.push("无法获取 LibreOffice 路径".to_string()),
     }
-  };
-
-  // 转换为 file:// URL
-  let pdf_url = format!("file://{}", pdf_path.to_string_lossy());
-
-  eprintln!("✅ [preview_docx_as_pdf] 转换完成: {}", pdf_url);
-
-  // 发送预览进度事件：完成
-  app
-    .emit(
-      "preview-progress",
-      serde_json::json!({
-          "status": "completed",
-          "message": "预览完成",
-          "pdf_path": &pdf_url
-      }),
-    )
-    .ok();
-
-  // 清理请求注册并通知等待的请求
-  let mut requests = PREVIEW_REQUESTS.lock().unwrap();
-  if let Some(tx) = requests.remove(&normalized_path) {
-    let _ = tx.send(Ok(pdf_url.clone()));
-    eprintln!("✅ [preview_docx_as_pdf] 已通知等待的请求");
   }
 
-  Ok(pdf_url)
+  diagnostics.push(format!("输入文件: {:?}", source_path));
+  match std::fs::metadata(source_path) {
+    Ok(metadata) => diagnostics.push(format!("输入文件大小: {} 字节", metadata.len())),
+    Err(_) => diagnostics.push("输入文件不存在".to_string()),
+  }
+  diagnostics
 }
 
-/// 预览 Excel 文件为 PDF（XLSX, XLS, ODS）
+/// 预览 Office 文档为 PDF（文档、表格、演示文稿）
 ///
-/// **功能**：转换 Excel → PDF，返回 PDF 文件路径
+/// **功能**：按扩展名选择转换方式，通过 LibreOffice 转为 PDF，返回 PDF 文件路径
 ///
-/// **使用场景**：
-/// - ExcelPreview 组件内部调用
-/// - 预览模式（isReadOnly = true）
+/// **支持格式**：DOCX/DOC/ODT/RTF、XLSX/XLS/ODS、PPTX/PPT/PPSX/PPS/ODP（CSV 由前端直接解析）
 ///
 /// **返回**：PDF 文件路径（file:// 绝对路径）
 ///
-/// **缓存机制**：
-/// - 缓存键：文件路径 + 修改时间
-/// - 缓存过期：1 小时
-/// - 缓存位置：应用缓存目录
-///
-/// **注意**：CSV 文件不使用此命令，使用前端直接解析
-#[tauri::command]
-pub async fn preview_excel_as_pdf(path: String, app: AppHandle) -> Result<String, String> {
-  let excel_path = PathBuf::from(&path);
-
-  // 检查文件是否存在
-  if !excel_path.exists() {
-    return Err(format!("文件不存在: {}", path));
-  }
-  if std::fs::metadata(&excel_path)
-    .map(|meta| meta.len() == 0)
-    .unwrap_or(false)
-  {
-    return Err("Excel 文件为空，可能是旧版本创建的无效空白文件，请重新创建该文件。".to_string());
-  }
-
-  eprintln!("🔍 [preview_excel_as_pdf] 开始预览: {:?}", excel_path);
-
-  // 发送预览进度事件：开始
-  app
-    .emit(
-      "preview-progress",
-      serde_json::json!({
-          "status": "started",
-          "message": "正在预览..."
-      }),
-    )
-    .ok();
-
-  // 创建 LibreOffice 服务
-  let lo_service = LibreOfficeService::new().map_err(|e| {
-    let error_msg = format!("LibreOffice 服务初始化失败: {}", e);
-    app
-      .emit(
-        "preview-progress",
-        serde_json::json!({
-            "status": "failed",
-            "message": &error_msg
-        }),
-      )
-      .ok();
-    error_msg
-  })?;
-
-  // 检查 LibreOffice 是否可用
-  let libreoffice_path_result = lo_service.get_libreoffice_path();
-  if libreoffice_path_result.is_err() {
-    let error_msg = libreoffice_path_result.unwrap_err();
-    app
-      .emit(
-        "preview-progress",
-        serde_json::json!({
-            "status": "failed",
-            "message": &error_msg
-        }),
-      )
-      .ok();
-    return Err(error_msg);
-  }
-
-  // 发送预览进度事件：预览中
-  app
-    .emit(
-      "preview-progress",
-      serde_json::json!({
-          "status": "converting",
-          "message": "正在预览..."
-      }),
-    )
-    .ok();
-
-  // 执行转换（带超时：30秒）
-  let excel_path_clone = excel_path.clone();
-  let lo_service_arc = Arc::new(lo_service);
-  let pdf_path_result = tokio::time::timeout(
-    std::time::Duration::from_secs(30),
-    tokio::task::spawn_blocking(move || lo_service_arc.convert_excel_to_pdf(&excel_path_clone)),
-  )
-  .await;
-
-  let pdf_path = match pdf_path_result {
-    Ok(Ok(Ok(path))) => path,
-    Ok(Ok(Err(e))) => {
-      let error_msg = format!("预览失败: {}", e);
-      app
-        .emit(
-          "preview-progress",
-          serde_json::json!({
-              "status": "failed",
-              "message": &error_msg
-          }),
-        )
-        .ok();
-      return Err(error_msg);
-    }
-    Ok(Err(e)) => {
-      let error_msg = format!("预览失败: {}", e);
-      app
-        .emit(
-          "preview-progress",
-          serde_json::json!({
-              "status": "failed",
-              "message": &error_msg
-          }),
-        )
-        .ok();
-      return Err(error_msg);
-    }
-    Err(_) => {
-      let error_msg = "预览失败，你的文件过大或存在无法预览的格式，请调整文档。".to_string();
-      app
-        .emit(
-          "preview-progress",
-          serde_json::json!({
-              "status": "failed",
-              "message": &error_msg
-          }),
-        )
-        .ok();
-      eprintln!("⏱️ [preview_excel_as_pdf] 预览超时（30秒）");
-      return Err(error_msg);
-    }
-  };
-
-  // 转换为 file:// URL
-  let pdf_url = format!("file://{}", pdf_path.to_string_lossy());
-
-  eprintln!("✅ [preview_excel_as_pdf] 转换完成: {}", pdf_url);
-
-  // 发送预览进度事件：完成
-  app
-    .emit(
-      "preview-progress",
-      serde_json::json!({
-          "status": "completed",
-          "message": "预览完成",
-          "pdf_path": &pdf_url
-      }),
-    )
-    .ok();
-
-  Ok(pdf_url)
-}
-
-/// 预览演示文稿文件为 PDF（PPTX, PPT, PPSX, PPS, ODP）
-///
-/// **功能**：转换演示文稿 → PDF，返回 PDF 文件路径
+/// **事件**：`preview-progress`（started / converting / completed / failed），
+/// 转换期间每 500ms 推送一次按文件大小与已耗时估算的 `progress`（10-95）
 ///
-/// **使用场景**：
-/// - PresentationPreview 组件内部调用
-/// - 预览模式（isReadOnly = true）
+/// **超时**：基础 30 秒，按文件大小放宽，可在工作区设置 `preview` 中调整
 ///
-/// **返回**：PDF 文件路径（file:// 绝对路径）
+/// **并发**：同一文件的并发请求共用 PREVIEW_REQUESTS 去重，避免 temp 争用与字体不一致
 ///
 /// **缓存机制**：
 /// - 缓存键：文件路径 + 修改时间
-/// - 缓存过期：1 小时
+/// - 缓存过期：1 小时；源文件修改后旧缓存立即删除
+/// - 总大小上限 512MB，超出时按最近访问时间淘汰
 /// - 缓存位置：应用缓存目录
 #[tauri::command]
-pub async fn preview_presentation_as_pdf(path: String, app: AppHandle) -> Result<String, String> {
-  let presentation_path = PathBuf::from(&path);
+pub async fn preview_office_document(path: String, app: AppHandle) -> Result<String, AppError> {
+  let source_path = PathBuf::from(&path);
 
   // 检查文件是否存在
-  if !presentation_path.exists() {
-    return Err(format!("文件不存在: {}", path));
-  }
-  if std::fs::metadata(&presentation_path)
-    .map(|meta| meta.len() == 0)
-    .unwrap_or(false)
+  if !source_path.exists() {
+    return Err(AppError::NotFound(path));
+  }
+  let kind = OfficeDocumentKind::from_path(&source_path)
+    .ok_or_else(|| format!("不支持预览该文件类型: {}", path))?;
+  if kind != OfficeDocumentKind::Document
+    && std::fs::metadata(&source_path)
+      .map(|meta| meta.len() == 0)
+      .unwrap_or(false)
   {
-    return Err("演示文稿文件为空，可能是旧版本创建的无效空白文件，请重新创建该文件。".to_string());
+    return Err(AppError::InvalidInput(format!(
+      "{}为空，可能是旧版本创建的无效空白文件，请重新创建该文件。",
+      kind.label()
+    )));
   }
 
-  // 规范化路径（与 preview_docx_as_pdf 共用 PREVIEW_REQUESTS，按路径去重，避免同一文件并发转换导致 temp 争用与字体不一致）
-  let normalized_path = presentation_path
+  // 规范化文件路径（用于去重）
+  let normalized_path = source_path
     .canonicalize()
-    .unwrap_or_else(|_| presentation_path.clone())
+    .unwrap_or_else(|_| source_path.clone())
     .to_string_lossy()
     .to_string();
 
-  eprintln!(
-    "🔍 [preview_presentation_as_pdf] 开始预览: {:?} (规范化路径: {})",
-    presentation_path, normalized_path
+  tracing::debug!(
+    "[preview_office_document] 开始预览{}: {:?} (规范化路径: {})",
+    kind.label(),
+    source_path,
+    normalized_path
   );
 
-  // 检查是否有正在进行的预览请求（同一文件只允许一个转换，后续请求等待第一个结果）
+  // 检查是否有正在进行的预览请求
   let (tx, rx) = oneshot::channel();
   let is_first_request = {
     let mut requests = PREVIEW_REQUESTS.lock().unwrap();
     if requests.contains_key(&normalized_path) {
-      eprintln!(
-        "⏳ [preview_presentation_as_pdf] 检测到并发请求，等待第一个请求完成: {}",
+      // 已有请求在进行，等待第一个请求完成
+      tracing::debug!(
+        "[preview_office_document] 检测到并发请求，等待第一个请求完成: {}",
         normalized_path
       );
       false
     } else {
+      // 这是第一个请求，注册它
       requests.insert(normalized_path.clone(), tx);
-      eprintln!(
-        "✅ [preview_presentation_as_pdf] 注册为新请求: {}",
+      tracing::info!(
+        "[preview_office_document] 注册为新请求: {}",
         normalized_path
       );
       true
     }
   };
 
+  // 如果不是第一个请求，等待第一个请求的结果
   if !is_first_request {
-    eprintln!("⏳ [preview_presentation_as_pdf] 等待第一个请求完成...");
+    tracing::debug!("[preview_office_document] 等待第一个请求完成...");
     match rx.await {
       Ok(result) => {
-        eprintln!("✅ [preview_presentation_as_pdf] 收到第一个请求的结果");
-        return result;
+        tracing::info!("[preview_office_document] 收到第一个请求的结果");
+        return result.map_err(AppError::from);
       }
       Err(_) => {
-        eprintln!("⚠️ [preview_presentation_as_pdf] 第一个请求的发送器已关闭，重新发起请求");
+        tracing::warn!("[preview_office_document] 第一个请求的发送器已关闭，重新发起请求");
+        // 发送器已关闭，说明第一个请求失败了，重新发起
         let mut requests = PREVIEW_REQUESTS.lock().unwrap();
         requests.remove(&normalized_path);
       }
     }
   }
 
-  // 发送预览进度事件：开始
-  app
-    .emit(
-      "preview-progress",
+  let fail = |error: AppError, diagnostics: Option<Vec<String>>| {
+    let error_msg = error.localized_message();
+    emit_preview_progress(
+      &app,
       serde_json::json!({
-          "status": "started",
-          "message": "正在预览..."
+          "status": "failed",
+          "message": &error_msg,
+          "diagnostics": diagnostics
       }),
-    )
-    .ok();
+    );
+    finish_preview_request(&normalized_path, &Err(error_msg));
+    Err(error)
+  };
 
-  // 创建 LibreOffice 服务
+  // 发送预览进度事件：开始
+  emit_preview_progress(
+    &app,
+    serde_json::json!({
+        "status": "started",
+        "message": i18n::t("preview.converting", &[])
+    }),
+  );
+
+  // 创建 LibreOffice 服务并检查是否可用（获取实际错误消息）
   let lo_service = match LibreOfficeService::new() {
-    Ok(s) => s,
+    Ok(service) => service,
     Err(e) => {
-      let error_msg = format!("LibreOffice 服务初始化失败: {}", e);
-      app
-        .emit(
-          "preview-progress",
-          serde_json::json!({
-              "status": "failed",
-              "message": &error_msg
-          }),
-        )
-        .ok();
-      let mut requests = PREVIEW_REQUESTS.lock().unwrap();
-      if let Some(tx) = requests.remove(&normalized_path) {
-        let _ = tx.send(Err(error_msg.clone()));
-      }
-      return Err(error_msg);
-    }
-  };
-
-  // 检查 LibreOffice 是否可用
-  let libreoffice_path_result = lo_service.get_libreoffice_path();
-  if libreoffice_path_result.is_err() {
-    let error_msg = libreoffice_path_result.unwrap_err();
-    app
-      .emit(
-        "preview-progress",
-        serde_json::json!({
-            "status": "failed",
-            "message": &error_msg
-        }),
+      return fail(
+        AppError::LibreOfficeUnavailable(format!("LibreOffice 服务初始化失败: {}", e)),
+        None,
       )
-      .ok();
-    let mut requests = PREVIEW_REQUESTS.lock().unwrap();
-    if let Some(tx) = requests.remove(&normalized_path) {
-      let _ = tx.send(Err(error_msg.clone()));
     }
-    return Err(error_msg);
+  };
+  if let Err(error_msg) = lo_service.get_libreoffice_path() {
+    return fail(AppError::LibreOfficeUnavailable(error_msg), None);
   }
 
-  // 发送预览进度事件：预览中
-  app
-    .emit(
-      "preview-progress",
-      serde_json::json!({
-          "status": "converting",
-          "message": "正在预览..."
-      }),
-    )
-    .ok();
+  // 转换输出写入预览缓存目录，需要至少 2 倍源文件大小的可用空间
+  let file_size = std::fs::metadata(&source_path)
+    .map(|meta| meta.len())
+    .unwrap_or(0);
+  if let Err(error) = disk_space::ensure_space_for_conversion(lo_service.cache_dir(), file_size) {
+    return fail(error, None);
+  }
 
-  // 执行转换（带超时：30秒）
-  let presentation_path_clone = presentation_path.clone();
-  let lo_service_arc = Arc::new(lo_service);
-  let pdf_path_result = tokio::time::timeout(
-    std::time::Duration::from_secs(30),
-    tokio::task::spawn_blocking(move || {
-      lo_service_arc.convert_presentation_to_pdf(&presentation_path_clone)
+  // 发送预览进度事件：预览中
+  emit_preview_progress(
+    &app,
+    serde_json::json!({
+        "status": "converting",
+        "message": i18n::t("preview.converting", &[]),
+        "progress": 10
     }),
-  )
-  .await;
+  );
 
-  let pdf_path = match pdf_path_result {
-    Ok(Ok(Ok(path))) => path,
-    Ok(Ok(Err(e))) => {
-      let error_msg = format!("预览失败: {}", e);
-      app
-        .emit(
-          "preview-progress",
-          serde_json::json!({
-              "status": "failed",
-              "message": &error_msg
-          }),
-        )
-        .ok();
-      let mut requests = PREVIEW_REQUESTS.lock().unwrap();
-      if let Some(tx) = requests.remove(&normalized_path) {
-        let _ = tx.send(Err(error_msg.clone()));
+  // 执行转换：超时按文件大小放宽（工作区设置 preview），转换期间按预计耗时推送进度
+  let timeout = infer_workspace_root_from_path(&source_path)
+    .map(|root| WorkspaceSettings::load_or_default(&root).preview)
+    .unwrap_or_default()
+    .conversion_timeout(file_size);
+  let expected_secs = kind.estimated_conversion_secs(file_size);
+
+  let source_path_clone = source_path.clone();
+  let lo_service_arc = Arc::new(lo_service);
+  let conversion =
+    tokio::task::spawn_blocking(move || lo_service_arc.convert_office_to_pdf(&source_path_clone));
+  tokio::pin!(conversion);
+  let started_at = std::time::Instant::now();
+  let deadline = tokio::time::sleep(timeout);
+  tokio::pin!(deadline);
+  let mut ticker = tokio::time::interval(std::time::Duration::from_millis(500));
+  let mut last_progress = 0;
+  let pdf_path_result = loop {
+    tokio::select! {
+      result = &mut conversion => break Some(result),
+      _ = &mut deadline => break None,
+      _ = ticker.tick() => {
+        let progress =
+          estimate_conversion_progress(started_at.elapsed().as_secs_f64(), expected_secs);
+        if progress != last_progress {
+          last_progress = progress;
+          emit_preview_progress(
+            &app,
+            serde_json::json!({
+                "status": "converting",
+                "message": i18n::t("preview.converting_progress", &[("progress", &progress.to_string())]),
+                "progress": progress
+            }),
+          );
+        }
       }
-      return Err(error_msg);
     }
-    Ok(Err(e)) => {
-      let error_msg = format!("预览失败: {}", e);
-      app
-        .emit(
-          "preview-progress",
-          serde_json::json!({
-              "status": "failed",
-              "message": &error_msg
-          }),
-        )
-        .ok();
-      let mut requests = PREVIEW_REQUESTS.lock().unwrap();
-      if let Some(tx) = requests.remove(&normalized_path) {
-        let _ = tx.send(Err(error_msg.clone()));
+  };
+
+  let pdf_path = match pdf_path_result {
+    Some(Ok(Ok(path))) => path,
+    Some(Ok(Err(e))) => {
+      // 转换失败 - 收集详细的诊断信息
+      let diagnostics = preview_failure_diagnostics(&source_path);
+      tracing::error!("[preview_office_document] 转换失败:");
+      tracing::warn!("   错误: {}", e);
+      tracing::debug!("   诊断信息:");
+      for diag in &diagnostics {
+        tracing::debug!("     - {}", diag);
       }
-      return Err(error_msg);
+      let error = AppError::ConversionFailed {
+        tool: "LibreOffice",
+        message: format!("{}\n\n诊断信息:\n{}", e, diagnostics.join("\n")),
+      };
+      return fail(error, Some(diagnostics));
     }
-    Err(_) => {
-      let error_msg = "预览失败，你的文件过大或存在无法预览的格式，请调整文档。".to_string();
-      app
-        .emit(
-          "preview-progress",
-          serde_json::json!({
-              "status": "failed",
-              "message": &error_msg
-          }),
-        )
-        .ok();
-      eprintln!("⏱️ [preview_presentation_as_pdf] 预览超时（30秒）");
-      let mut requests = PREVIEW_REQUESTS.lock().unwrap();
-      if let Some(tx) = requests.remove(&normalized_path) {
-        let _ = tx.send(Err(error_msg.clone()));
-      }
-      return Err(error_msg);
+    // spawn_blocking 失败
+    Some(Err(e)) => return fail(format!("预览失败: {}", e).into(), None),
+    None => {
+      tracing::debug!(
+        "[preview_office_document] 预览超时（{}秒）",
+        timeout.as_secs()
+      );
+      return fail(i18n::t("preview.timeout", &[]).into(), None);
     }
   };
 
   // 转换为 file:// URL
   let pdf_url = format!("file://{}", pdf_path.to_string_lossy());
 
-  eprintln!("✅ [preview_presentation_as_pdf] 转换完成: {}", pdf_url);
+  tracing::info!("[preview_office_document] 转换完成: {}", pdf_url);
 
   // 发送预览进度事件：完成
-  app
-    .emit(
-      "preview-progress",
-      serde_json::json!({
-          "status": "completed",
-          "message": "预览完成",
-          "pdf_path": &pdf_url
-      }),
-    )
-    .ok();
-
-  // 通知等待的并发请求使用同一结果
-  let mut requests = PREVIEW_REQUESTS.lock().unwrap();
-  if let Some(tx) = requests.remove(&normalized_path) {
-    let _ = tx.send(Ok(pdf_url.clone()));
-    eprintln!("✅ [preview_presentation_as_pdf] 已通知等待的请求");
-  }
+  emit_preview_progress(
+    &app,
+    serde_json::json!({
+        "status": "completed",
+        "message": i18n::t("preview.completed", &[]),
+        "pdf_path": &pdf_url
+    }),
+  );
 
+  // 清理请求注册并通知等待的请求
+  finish_preview_request(&normalized_path, &Ok(pdf_url.clone()));
   Ok(pdf_url)
 }
 
-/// 记录文件为 Binder 创建的文件
+/// 获取 PDF 总页数（按页预览用，需要 poppler 的 pdfinfo）
 #[tauri::command]
-pub async fn record_binder_file(
-  file_path: String,
-  source: String,                 // "new" 或 "ai_generated"
-  workspace_path: Option<String>, // 可选的工作区路径（如果提供，直接使用；否则从文件路径推断）
-) -> Result<(), String> {
-  use serde_json;
-  use std::fs;
-
-  // 确定工作区路径
-  let workspace_path = if let Some(ws_path) = workspace_path {
-    // 如果提供了工作区路径，直接使用
-    PathBuf::from(&ws_path)
-  } else {
-    // 否则从文件路径推断工作区路径
-    let path_buf = PathBuf::from(&file_path);
-    if let Some(parent) = path_buf.parent() {
-      // 向上查找 .binder 目录来确定工作区根目录
-      let mut current = parent;
-      loop {
-        let binder_dir = current.join(".binder");
-        if binder_dir.exists() {
-          break current.to_path_buf();
-        }
-        if let Some(p) = current.parent() {
-          current = p;
-        } else {
-          // 如果找不到 .binder 目录，使用文件所在目录作为工作区
-          break parent.to_path_buf();
-        }
-      }
-    } else {
-      return Err("无法确定工作区路径".to_string());
-    }
-  };
+pub async fn get_pdf_page_count(path: String) -> Result<u32, AppError> {
+  tokio::task::spawn_blocking(move || PdfPageRenderer::new()?.page_count(Path::new(&path)))
+    .await
+    .map_err(|e| format!("读取 PDF 页数失败: {}", e))?
+    .map_err(AppError::from)
+}
 
-  let metadata_file = workspace_path.join(".binder").join("files_metadata.json");
+/// 将 PDF 的单页渲染为 PNG（page 从 1 开始，width 默认 1200 像素），返回图片绝对路径
+///
+/// 前端只为可见页调用，结果缓存在预览缓存目录，重复请求直接返回；dark 为 true 时返回适合暗色主题的版本
+#[tauri::command]
+pub async fn render_pdf_page(
+  path: String,
+  page: u32,
+  width: Option<u32>,
+  dark: Option<bool>,
+) -> Result<String, AppError> {
+  tokio::task::spawn_blocking(move || {
+    PdfPageRenderer::new()?
+      .render_page(
+        Path::new(&path),
+        page,
+        width.unwrap_or(PDF_PAGE_DEFAULT_WIDTH),
+        dark.unwrap_or(false),
+      )
+      .map(|p| p.to_string_lossy().to_string())
+  })
+  .await
+  .map_err(|e| format!("渲染 PDF 页面失败: {}", e))?
+  .map_err(AppError::from)
+}
 
-  // 确保 .binder 目录存在
-  if let Some(parent) = metadata_file.parent() {
-    fs::create_dir_all(parent).map_err(|e| format!("创建 .binder 目录失败: {}", e))?;
-  }
+/// 按页提取 PDF 文本（pages 为页码列表，从 1 开始，省略时提取全部页面）
+///
+/// 加密且无法用空密码打开的 PDF 返回 `encrypted: true`，不视为错误
+#[tauri::command]
+pub async fn extract_pdf_text(
+  path: String,
+  pages: Option<Vec<u32>>,
+) -> Result<PdfTextExtraction, AppError> {
+  tokio::task::spawn_blocking(move || {
+    crate::services::pdf_text::extract_pdf_text(Path::new(&path), pages.as_deref())
+  })
+  .await
+  .map_err(|e| format!("提取 PDF 文本失败: {}", e))?
+  .map_err(AppError::from)
+}
 
-  // 读取现有元数据
-  let mut metadata: HashMap<String, serde_json::Value> = if metadata_file.exists() {
-    let content =
-      fs::read_to_string(&metadata_file).map_err(|e| format!("读取元数据文件失败: {}", e))?;
-    serde_json::from_str(&content).unwrap_or_else(|_| HashMap::new())
-  } else {
-    HashMap::new()
+/// 字数统计（中日韩文字按字计，并按标题分节）
+///
+/// 传 path 时读取文件（Word / ODT / RTF 经 Pandoc 提取，PDF 取文本层），否则统计 content。
+/// format 为 `markdown` / `html` / `plain`；统计文件时未传则按扩展名判断
+#[tauri::command]
+pub async fn count_words(
+  path: Option<String>,
+  content: Option<String>,
+  format: Option<String>,
+) -> Result<WordCountResult, AppError> {
+  let format = match format.as_deref() {
+    Some(value) => Some(
+      CountFormat::parse(value)
+        .ok_or_else(|| AppError::InvalidInput(format!("不支持的统计格式: {}", value)))?,
+    ),
+    None => None,
   };
+  tokio::task::spawn_blocking(move || match (path, content) {
+    (Some(path), _) => word_count::count_file(Path::new(&path), format).map_err(AppError::from),
+    (None, Some(content)) => Ok(word_count::count_text(
+      &content,
+      format.unwrap_or(CountFormat::Markdown),
+    )),
+    (None, None) => Err(AppError::InvalidInput(
+      "需要提供 path 或 content".to_string(),
+    )),
+  })
+  .await
+  .map_err(|e| format!("字数统计失败: {}", e))?
+}
 
-  // 规范化文件路径（使用相对路径）
-  // ⚠️ 关键：统一使用正斜杠，确保与前端一致
-  let workspace_path_str = workspace_path
-    .to_string_lossy()
-    .to_string()
-    .replace('\\', "/");
-  let file_path_normalized = file_path.replace('\\', "/");
-
-  // 规范化工作区路径和文件路径，移除末尾的斜杠
-  let workspace_path_clean = workspace_path_str.trim_end_matches('/');
-  let file_path_clean = file_path_normalized.trim_end_matches('/');
+/// 可读性分析：阅读时长、句长分布与可读性分数（写作统计面板使用）
+///
+/// path / content / format 的含义同 count_words
+#[tauri::command]
+pub async fn analyze_readability(
+  path: Option<String>,
+  content: Option<String>,
+  format: Option<String>,
+) -> Result<ReadabilityReport, AppError> {
+  let format = match format.as_deref() {
+    Some(value) => Some(
+      CountFormat::parse(value)
+        .ok_or_else(|| AppError::InvalidInput(format!("不支持的统计格式: {}", value)))?,
+    ),
+    None => None,
+  };
+  tokio::task::spawn_blocking(move || {
+    let text = match (path, content) {
+      (Some(path), _) => word_count::file_plain_text(Path::new(&path), format)?,
+      (None, Some(content)) => {
+        word_count::plain_text(&content, format.unwrap_or(CountFormat::Markdown))
+      }
+      (None, None) => {
+        return Err(AppError::InvalidInput(
+          "需要提供 path 或 content".to_string(),
+        ))
+      }
+    };
+    Ok(readability::analyze_text(&text))
+  })
+  .await
+  .map_err(|e| format!("可读性分析失败: {}", e))?
+}
 
-  let normalized_path = if file_path_clean.starts_with(workspace_path_clean) {
-    file_path_clean
-      .strip_prefix(workspace_path_clean)
-      .unwrap_or(file_path_clean)
-      .trim_start_matches('/')
-      .trim_start_matches('\\')
-      .to_string()
-  } else {
-    // 如果路径不匹配，尝试规范化后再次匹配
-    // 可能是路径格式不一致导致的
-    eprintln!(
-      "⚠️ [record_binder_file] 路径不匹配，使用完整路径: file_path={}, workspace={}",
-      file_path_clean, workspace_path_clean
-    );
-    file_path_clean.to_string()
+/// Markdown 格式化；check_only 时只返回问题列表（编辑器检查面板使用）
+///
+/// 传 path 时读取文件，否则处理 content；结果不写回文件，由前端应用到编辑器
+#[tauri::command]
+pub async fn format_markdown(
+  path: Option<String>,
+  content: Option<String>,
+  rules: Option<MarkdownFormatRules>,
+  check_only: Option<bool>,
+) -> Result<MarkdownFormatResult, AppError> {
+  let rules = rules.unwrap_or_default();
+  rules.validate().map_err(AppError::InvalidInput)?;
+  let content = match (path, content) {
+    (Some(path), _) => {
+      std::fs::read_to_string(&path).map_err(|e| AppError::io("读取文件失败", e))?
+    }
+    (None, Some(content)) => content,
+    (None, None) => {
+      return Err(AppError::InvalidInput(
+        "需要提供 path 或 content".to_string(),
+      ))
+    }
   };
+  tokio::task::spawn_blocking(move || {
+    markdown_format::format_markdown(&content, &rules, check_only.unwrap_or(false))
+  })
+  .await
+  .map_err(|e| AppError::from(format!("Markdown 格式化失败: {}", e)))
+}
 
-  // 记录文件元数据
-  metadata.insert(
-    normalized_path.clone(),
-    serde_json::json!({
-        "source": source,
-        "created_at": SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs()
-    }),
-  );
+fn footnote_format(format: &str) -> Result<FootnoteFormat, AppError> {
+  FootnoteFormat::parse(format)
+    .ok_or_else(|| AppError::InvalidInput(format!("不支持的脚注格式: {}", format)))
+}
 
-  // 写回文件
-  let json_content =
-    serde_json::to_string_pretty(&metadata).map_err(|e| format!("序列化元数据失败: {}", e))?;
-  fs::write(&metadata_file, json_content).map_err(|e| format!("写入元数据文件失败: {}", e))?;
+/// 列出编辑器内容中的脚注；format 为 `markdown` 或 `html`（DOCX 编辑内容）
+#[tauri::command]
+pub async fn list_footnotes(content: String, format: String) -> Result<Vec<Footnote>, AppError> {
+  let format = footnote_format(&format)?;
+  Ok(footnotes::list_footnotes(&content, format))
+}
 
-  eprintln!("✅ [record_binder_file] 已记录文件:");
-  eprintln!("   原始文件路径: {}", file_path);
-  eprintln!("   工作区路径: {}", workspace_path_str);
-  eprintln!("   规范化路径: {} (source: {})", normalized_path, source);
-  eprintln!("   元数据文件: {:?}", metadata_file);
-  eprintln!("   元数据条目数（记录后）: {}", metadata.len());
+/// 在 position（按字符计的偏移）处插入脚注并整体重新编号，返回新内容由前端应用到编辑器
+#[tauri::command]
+pub async fn add_footnote(
+  content: String,
+  format: String,
+  position: usize,
+  text: String,
+) -> Result<FootnoteEdit, AppError> {
+  footnotes::add_footnote(&content, footnote_format(&format)?, position, &text)
+    .map_err(AppError::InvalidInput)
+}
 
-  Ok(())
+/// 按引用顺序重新编号，补齐缺失的定义并删除没有引用的定义
+#[tauri::command]
+pub async fn renumber_footnotes(content: String, format: String) -> Result<FootnoteEdit, AppError> {
+  let format = footnote_format(&format)?;
+  Ok(footnotes::renumber_footnotes(&content, format))
 }
 
-/// 获取文件的来源（如果是 Binder 创建的文件）
+/// 删除脚注的全部引用与定义，其余脚注重新编号
 #[tauri::command]
-pub async fn get_binder_file_source(
-  file_path: String,
-  workspace_path: Option<String>, // 可选的工作区路径（如果提供，直接使用；否则从文件路径推断）
-) -> Result<Option<String>, String> {
-  use serde_json;
-  use std::fs;
-
-  // 确定工作区路径
-  let workspace_path = if let Some(ws_path) = workspace_path {
-    // 如果提供了工作区路径，直接使用
-    PathBuf::from(&ws_path)
-  } else {
-    // 否则从文件路径推断工作区路径
-    let path_buf = PathBuf::from(&file_path);
-    if let Some(parent) = path_buf.parent() {
-      let mut current = parent;
-      loop {
-        let binder_dir = current.join(".binder");
-        if binder_dir.exists() {
-          break current.to_path_buf();
-        }
-        if let Some(p) = current.parent() {
-          current = p;
-        } else {
-          // 如果找不到 .binder 目录，使用文件所在目录作为工作区
-          break parent.to_path_buf();
-        }
-      }
-    } else {
-      return Ok(None);
+pub async fn delete_footnote(
+  content: String,
+  format: String,
+  label: String,
+) -> Result<FootnoteEdit, AppError> {
+  footnotes::delete_footnote(&content, footnote_format(&format)?, &label)
+    .map_err(AppError::InvalidInput)
+}
+
+/// 对比两个文档版本，生成逐词标注的 HTML；format 为 `inline`（默认）或 `side_by_side`
+#[tauri::command]
+pub async fn diff_documents(
+  old: DocumentRevision,
+  new: DocumentRevision,
+  format: Option<DiffFormat>,
+) -> Result<DocumentDiff, AppError> {
+  let format = format.unwrap_or(DiffFormat::Inline);
+  tokio::task::spawn_blocking(move || document_diff::diff_documents(&old, &new, format))
+    .await
+    .map_err(|e| AppError::from(format!("文档对比失败: {}", e)))?
+    .map_err(AppError::from)
+}
+
+/// 按顺序合并多个 Markdown / DOCX 文档，输出格式由 output 的扩展名（.md / .docx）决定
+#[tauri::command]
+pub async fn merge_documents(
+  paths: Vec<String>,
+  output: String,
+  options: Option<MergeOptions>,
+) -> Result<MergeResult, AppError> {
+  let output_buf = PathBuf::from(&output);
+  let workspace_root = require_workspace_root_for_path(&output_buf)?;
+  let safe_output = PathValidator::validate_workspace_write_target(&output_buf, &workspace_root)
+    .map_err(|e| format!("输出路径非法: {}", e))?;
+  if safe_output.exists() {
+    return Err(AppError::AlreadyExists(output));
+  }
+  let inputs = paths
+    .iter()
+    .map(|path| {
+      PathValidator::validate_workspace_path(Path::new(path), &workspace_root)
+        .map_err(|e| format!("文档路径非法 {}: {}", path, e))
+    })
+    .collect::<Result<Vec<_>, _>>()?;
+  if !PandocService::new().is_available() {
+    return Err(AppError::PandocUnavailable);
+  }
+
+  let options = options.unwrap_or_default();
+  let reference_doc = PandocService::workspace_reference_docx(&workspace_root);
+  let target = safe_output.clone();
+  let result = tokio::task::spawn_blocking(move || {
+    document_merge::merge_documents(&inputs, &target, &options, reference_doc.as_deref())
+  })
+  .await
+  .map_err(|e| AppError::from(format!("合并文档失败: {}", e)))?;
+  let result = match result {
+    Ok(result) => result,
+    Err(e) => {
+      let _ = std::fs::remove_file(&safe_output);
+      return Err(AppError::from(e));
     }
   };
 
-  let metadata_file = workspace_path.join(".binder").join("files_metadata.json");
+  let db = WorkspaceDb::new(&workspace_root)?;
+  let _ = record_resource_structure_timeline_node(
+    &db,
+    &workspace_root,
+    "create_file",
+    &format!("合并 {} 个文档：{}", result.documents, output),
+    "user",
+    std::slice::from_ref(&safe_output),
+  )?;
+  Ok(result)
+}
 
-  if !metadata_file.exists() {
-    return Ok(None);
+/// 按 H{level} 标题把 Markdown / DOCX 文档拆分为每节一个文件，并生成 index.md 索引；
+/// dest_dir 为空时拆分到文档旁与文档同名的文件夹
+#[tauri::command]
+pub async fn split_document(
+  path: String,
+  level: u8,
+  dest_dir: Option<String>,
+) -> Result<SplitResult, AppError> {
+  let path_buf = PathBuf::from(&path);
+  let workspace_root = require_workspace_root_for_path(&path_buf)?;
+  let safe_path = PathValidator::validate_workspace_path(&path_buf, &workspace_root)
+    .map_err(|e| format!("文档路径非法: {}", e))?;
+  let dest = match dest_dir {
+    Some(dir) => PathBuf::from(dir),
+    None => safe_path.with_extension(""),
+  };
+  let safe_dest = PathValidator::validate_workspace_write_target(&dest, &workspace_root)
+    .map_err(|e| format!("目标目录非法: {}", e))?;
+  let is_docx = safe_path
+    .extension()
+    .is_some_and(|ext| ext.eq_ignore_ascii_case("docx"));
+  if is_docx && !PandocService::new().is_available() {
+    return Err(AppError::PandocUnavailable);
   }
 
-  // 读取元数据
-  let content =
-    fs::read_to_string(&metadata_file).map_err(|e| format!("读取元数据文件失败: {}", e))?;
-  let metadata: HashMap<String, serde_json::Value> =
-    serde_json::from_str(&content).map_err(|e| format!("解析元数据文件失败: {}", e))?;
+  let reference_doc = PandocService::workspace_reference_docx(&workspace_root);
+  let result = tokio::task::spawn_blocking(move || {
+    document_split::split_document(&safe_path, level, &safe_dest, reference_doc.as_deref())
+  })
+  .await
+  .map_err(|e| AppError::from(format!("拆分文档失败: {}", e)))??;
+
+  let mut created: Vec<PathBuf> = result
+    .sections
+    .iter()
+    .map(|s| PathBuf::from(&s.path))
+    .collect();
+  created.push(PathBuf::from(&result.index));
+  let db = WorkspaceDb::new(&workspace_root)?;
+  let _ = record_resource_structure_timeline_node(
+    &db,
+    &workspace_root,
+    "create_file",
+    &format!("拆分文档为 {} 节：{}", result.sections.len(), path),
+    "user",
+    &created,
+  )?;
+  Ok(result)
+}
 
-  // 规范化文件路径
-  // ⚠️ 关键：统一使用正斜杠，确保与前端一致
-  let workspace_path_str = workspace_path
-    .to_string_lossy()
-    .to_string()
-    .replace('\\', "/");
-  let file_path_normalized = file_path.replace('\\', "/");
+/// 预览 DOCX 文件为 PDF（DocxPdfPreview 组件使用，等同于 preview_office_document）
+#[tauri::command]
+pub async fn preview_docx_as_pdf(path: String, app: AppHandle) -> Result<String, AppError> {
+  preview_office_document(path, app).await
+}
+
+/// 预览 Excel 文件为 PDF（XLSX, XLS, ODS；ExcelPreview 组件使用，等同于 preview_office_document）
+#[tauri::command]
+pub async fn preview_excel_as_pdf(path: String, app: AppHandle) -> Result<String, AppError> {
+  preview_office_document(path, app).await
+}
 
-  // 规范化工作区路径和文件路径，移除末尾的斜杠
+/// 预览演示文稿为 PDF（PPTX, PPT, PPSX, PPS, ODP；PresentationPreview 组件使用，等同于 preview_office_document）
+#[tauri::command]
+pub async fn preview_presentation_as_pdf(path: String, app: AppHandle) -> Result<String, AppError> {
+  preview_office_document(path, app).await
+}
+
+/// 确定文件所属工作区：优先使用传入的工作区路径，否则向上查找含 .binder 目录的祖先目录，
+/// 找不到时使用文件所在目录
+fn binder_metadata_workspace(file_path: &str, workspace_path: Option<String>) -> Option<PathBuf> {
+  if let Some(ws_path) = workspace_path {
+    return Some(PathBuf::from(ws_path));
+  }
+  let parent = Path::new(file_path).parent()?;
+  Some(
+    parent
+      .ancestors()
+      .find(|dir| dir.join(".binder").exists())
+      .unwrap_or(parent)
+      .to_path_buf(),
+  )
+}
+
+/// 元数据中的文件路径为工作区相对路径，统一使用正斜杠（与前端一致）
+fn binder_metadata_key(workspace_path: &Path, file_path: &str) -> String {
+  let workspace_path_str = workspace_path.to_string_lossy().replace('\\', "/");
   let workspace_path_clean = workspace_path_str.trim_end_matches('/');
+  let file_path_normalized = file_path.replace('\\', "/");
   let file_path_clean = file_path_normalized.trim_end_matches('/');
 
-  let normalized_path = if file_path_clean.starts_with(workspace_path_clean) {
-    file_path_clean
-      .strip_prefix(workspace_path_clean)
-      .unwrap_or(file_path_clean)
-      .trim_start_matches('/')
-      .trim_start_matches('\\')
-      .to_string()
-  } else {
-    // 如果路径不匹配，尝试规范化后再次匹配
-    // 可能是路径格式不一致导致的
-    eprintln!(
-      "⚠️ [get_binder_file_source] 路径不匹配，使用完整路径: file_path={}, workspace={}",
-      file_path_clean, workspace_path_clean
-    );
-    file_path_clean.to_string()
-  };
-
-  // 查找文件元数据
-  eprintln!("🔍 [get_binder_file_source] 查询文件:");
-  eprintln!("   文件路径: {}", file_path);
-  eprintln!("   工作区路径: {}", workspace_path_str);
-  eprintln!("   规范化路径: {}", normalized_path);
-  eprintln!("   元数据文件: {:?}", metadata_file);
-  eprintln!("   元数据条目数: {}", metadata.len());
-
-  if let Some(entry) = metadata.get(&normalized_path) {
-    if let Some(source) = entry.get("source").and_then(|s| s.as_str()) {
-      eprintln!("✅ [get_binder_file_source] 找到元数据: {}", source);
-      return Ok(Some(source.to_string()));
+  match file_path_clean.strip_prefix(workspace_path_clean) {
+    Some(relative) => relative.trim_start_matches('/').to_string(),
+    None => {
+      tracing::warn!(
+        "[binder_metadata_key] 路径不匹配，使用完整路径: file_path={}, workspace={}",
+        file_path_clean,
+        workspace_path_clean
+      );
+      file_path_clean.to_string()
     }
   }
+}
+
+/// 记录文件为 Binder 创建的文件
+#[tauri::command]
+pub async fn record_binder_file(
+  file_path: String,
+  source: String,                 // "new" 或 "ai_generated"
+  workspace_path: Option<String>, // 可选的工作区路径（如果提供，直接使用；否则从文件路径推断）
+) -> Result<(), AppError> {
+  let workspace_path =
+    binder_metadata_workspace(&file_path, workspace_path).ok_or("无法确定工作区路径")?;
+  let normalized_path = binder_metadata_key(&workspace_path, &file_path);
+
+  let db = WorkspaceDb::new(&workspace_path)?;
+  db.set_file_source(&normalized_path, &source)?;
+
+  tracing::info!(
+    "[record_binder_file] 已记录文件: {} (source: {})",
+    normalized_path,
+    source
+  );
+  Ok(())
+}
 
-  // 如果直接匹配失败，尝试所有可能的路径变体
-  eprintln!("⚠️ [get_binder_file_source] 直接匹配失败，尝试路径变体...");
-  eprintln!("   尝试匹配的路径: {}", normalized_path);
+/// 获取文件的来源（如果是 Binder 创建的文件）
+#[tauri::command]
+pub async fn get_binder_file_source(
+  file_path: String,
+  workspace_path: Option<String>, // 可选的工作区路径（如果提供，直接使用；否则从文件路径推断）
+) -> Result<Option<String>, AppError> {
+  let Some(workspace_path) = binder_metadata_workspace(&file_path, workspace_path) else {
+    return Ok(None);
+  };
+  // 没有 .binder 目录说明从未记录过，不为查询创建数据库
+  if !workspace_path.join(".binder").exists() {
+    return Ok(None);
+  }
+  let normalized_path = binder_metadata_key(&workspace_path, &file_path);
+  let db = WorkspaceDb::new(&workspace_path)?;
 
-  // 打印所有元数据键，用于调试
-  eprintln!("   元数据文件中的所有键:");
-  for key in metadata.keys() {
-    eprintln!("     - {}", key);
+  if let Some(source) = db.get_file_source(&normalized_path)? {
+    return Ok(Some(source));
   }
 
-  // 尝试不同的路径分隔符和格式
+  // 旧版 files_metadata.json 导入的记录可能使用反斜杠、前导斜杠或只有文件名（历史遗留问题）
+  let trimmed = normalized_path.trim_start_matches('/');
   let mut variants = vec![
-    normalized_path.clone(),
     normalized_path.replace('/', "\\"),
-    normalized_path.replace('\\', "/"),
-    format!(
-      "/{}",
-      normalized_path
-        .trim_start_matches('/')
-        .trim_start_matches('\\')
-    ),
-    format!(
-      "\\{}",
-      normalized_path
-        .trim_start_matches('/')
-        .trim_start_matches('\\')
-    ),
-    normalized_path
-      .trim_start_matches('/')
-      .trim_start_matches('\\')
-      .to_string(),
+    format!("/{}", trimmed),
+    format!("\\{}", trimmed.replace('/', "\\")),
   ];
-
-  // ⚠️ 关键修复：如果路径匹配失败，尝试只用文件名匹配
-  // 因为有些旧文件可能只存储了文件名（历史遗留问题）
-  if let Some(file_name) = normalized_path
-    .split('/')
-    .last()
-    .or_else(|| normalized_path.split('\\').last())
-  {
-    if !file_name.is_empty() && file_name != &normalized_path {
-      // 文件名与完整路径不同，添加文件名到变体列表
+  if let Some(file_name) = normalized_path.rsplit('/').next() {
+    if !file_name.is_empty() && file_name != normalized_path {
       variants.push(file_name.to_string());
-      eprintln!("⚠️ [get_binder_file_source] 添加文件名变体: {}", file_name);
     }
   }
-
   for variant in variants {
-    if let Some(entry) = metadata.get(&variant) {
-      if let Some(source) = entry.get("source").and_then(|s| s.as_str()) {
-        eprintln!(
-          "✅ [get_binder_file_source] 通过路径变体找到: {} (variant: {})",
-          source, variant
-        );
-        return Ok(Some(source.to_string()));
-      }
+    if let Some(source) = db.get_file_source(&variant)? {
+      tracing::info!(
+        "[get_binder_file_source] 通过路径变体找到: {} (variant: {})",
+        source,
+        variant
+      );
+      return Ok(Some(source));
     }
   }
 
-  eprintln!("❌ [get_binder_file_source] 未找到元数据");
+  tracing::debug!("[get_binder_file_source] 未找到元数据: {}", normalized_path);
   Ok(None)
 }
 
 /// 删除文件的元数据记录
 #[tauri::command]
-pub async fn remove_binder_file_record(file_path: String) -> Result<(), String> {
-  use serde_json;
-  use std::fs;
-
-  // 从文件路径推断工作区路径
-  let path_buf = PathBuf::from(&file_path);
-  let workspace_path = if let Some(parent) = path_buf.parent() {
-    let mut current = parent;
-    loop {
-      let binder_dir = current.join(".binder");
-      if binder_dir.exists() {
-        break current.to_path_buf();
-      }
-      if let Some(p) = current.parent() {
-        current = p;
-      } else {
-        break parent.to_path_buf();
-      }
-    }
-  } else {
-    return Err("无法确定工作区路径".to_string());
-  };
-
-  let metadata_file = workspace_path.join(".binder").join("files_metadata.json");
-
-  if !metadata_file.exists() {
-    return Ok(()); // 文件不存在，无需删除
+pub async fn remove_binder_file_record(file_path: String) -> Result<(), AppError> {
+  let workspace_path = binder_metadata_workspace(&file_path, None).ok_or("无法确定工作区路径")?;
+  if !workspace_path.join(".binder").exists() {
+    return Ok(()); // 没有记录，无需删除
   }
+  let normalized_path = binder_metadata_key(&workspace_path, &file_path);
+  WorkspaceDb::new(&workspace_path)?.remove_file_source(&normalized_path)?;
 
-  // 读取现有元数据
-  let content =
-    fs::read_to_string(&metadata_file).map_err(|e| format!("读取元数据文件失败: {}", e))?;
-  let mut metadata: HashMap<String, serde_json::Value> =
-    serde_json::from_str(&content).map_err(|e| format!("解析元数据文件失败: {}", e))?;
-
-  // 规范化文件路径
-  let workspace_path_str = workspace_path.to_string_lossy().to_string();
-  let normalized_path = if file_path.starts_with(&workspace_path_str) {
-    file_path
-      .strip_prefix(&workspace_path_str)
-      .unwrap_or(&file_path)
-      .trim_start_matches('/')
-      .trim_start_matches('\\')
-      .to_string()
-  } else {
-    file_path.clone()
-  };
-
-  // 删除记录
-  metadata.remove(&normalized_path);
-
-  // 写回文件
-  let json_content =
-    serde_json::to_string_pretty(&metadata).map_err(|e| format!("序列化元数据失败: {}", e))?;
-  fs::write(&metadata_file, json_content).map_err(|e| format!("写入元数据文件失败: {}", e))?;
-
-  eprintln!(
-    "✅ [remove_binder_file_record] 已删除文件记录: {}",
+  tracing::info!(
+    "[remove_binder_file_record] 已删除文件记录: {}",
     normalized_path
   );
-
   Ok(())
 }
 
 #[cfg(test)]
 mod tests {
-  use super::{create_empty_pptx, create_empty_xlsx, delete_file, rename_file};
+  use super::{
+    add_footnote, create_empty_pptx, create_empty_xlsx, delete_file, get_binder_file_source,
+    list_footnotes, record_binder_file, remove_binder_file_record, rename_file,
+  };
+  use crate::error::AppError;
   use crate::services::memory_service::{
     MemoryItemInput, MemoryLayer, MemoryScopeType, MemorySearchScope, MemoryService,
     MemorySourceKind, SearchMemoriesParams,
@@ -2678,6 +2834,43 @@ mod tests {
     );
   }
 
+  #[tokio::test]
+  async fn binder_file_source_imports_legacy_json_and_round_trips() {
+    let workspace = TestWorkspace::new("file-source");
+    let binder_dir = workspace.path().join(".binder");
+    std::fs::create_dir_all(&binder_dir).expect("create .binder");
+    std::fs::write(
+      binder_dir.join("files_metadata.json"),
+      r#"{"old.md": {"source": "ai_generated", "created_at": 1700000000}}"#,
+    )
+    .expect("write legacy metadata");
+
+    let old = workspace.path().join("old.md");
+    let source = get_binder_file_source(old.to_string_lossy().to_string(), None)
+      .await
+      .expect("query legacy source");
+    assert_eq!(source.as_deref(), Some("ai_generated"));
+    assert!(!binder_dir.join("files_metadata.json").exists());
+    assert!(binder_dir.join("files_metadata.json.migrated").exists());
+
+    let fresh = workspace.path().join("notes").join("fresh.md");
+    record_binder_file(fresh.to_string_lossy().to_string(), "new".to_string(), None)
+      .await
+      .expect("record source");
+    let source = get_binder_file_source(fresh.to_string_lossy().to_string(), None)
+      .await
+      .expect("query source");
+    assert_eq!(source.as_deref(), Some("new"));
+
+    remove_binder_file_record(fresh.to_string_lossy().to_string())
+      .await
+      .expect("remove source");
+    let source = get_binder_file_source(fresh.to_string_lossy().to_string(), None)
+      .await
+      .expect("query removed source");
+    assert_eq!(source, None);
+  }
+
   #[tokio::test]
   async fn rename_file_rebinds_content_memory_source_ref() {
     let workspace = TestWorkspace::new("rename");
@@ -2773,6 +2966,7 @@ mod tests {
         scope: MemorySearchScope::Content,
         limit: Some(10),
         entity_types: Some(vec!["note".to_string()]),
+        tags: None,
       })
       .await
       .expect("search content memories after move");
@@ -2782,4 +2976,29 @@ mod tests {
       destination.to_string_lossy()
     );
   }
+
+  #[tokio::test]
+  async fn footnote_commands_accept_docx_html_and_reject_bad_input() {
+    let html = "<p>正文</p>".to_string();
+    let added = add_footnote(html, "docx".to_string(), 5, "注释".to_string())
+      .await
+      .unwrap();
+    let listed = list_footnotes(added.content, "HTML".to_string())
+      .await
+      .unwrap();
+    assert_eq!(listed.len(), 1);
+    assert_eq!((listed[0].number, listed[0].text.as_str()), (1, "注释"));
+
+    let unsupported = list_footnotes(String::new(), "odt".to_string()).await;
+    assert!(matches!(unsupported, Err(AppError::InvalidInput(_))));
+    // 代码块中的 [^n] 不是脚注，不能在其中插入
+    let in_code = add_footnote(
+      "```\ncode\n```\n".to_string(),
+      "markdown".to_string(),
+      5,
+      "注释".to_string(),
+    )
+    .await;
+    assert!(matches!(in_code, Err(AppError::InvalidInput(_))));
+  }
 }
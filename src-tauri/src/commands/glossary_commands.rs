@@ -0,0 +1,20 @@
+use crate::services::translation::{self, Glossary};
+use std::path::PathBuf;
+
+/// 列出工作区的翻译术语表
+#[tauri::command]
+pub async fn list_glossaries(workspace_path: String) -> Result<Vec<Glossary>, String> {
+  translation::list_glossaries(&PathBuf::from(workspace_path))
+}
+
+/// 新增或更新术语表；id 为空时新建
+#[tauri::command]
+pub async fn save_glossary(workspace_path: String, glossary: Glossary) -> Result<Glossary, String> {
+  translation::save_glossary(&PathBuf::from(workspace_path), glossary)
+}
+
+/// 删除术语表，返回是否存在
+#[tauri::command]
+pub async fn delete_glossary(workspace_path: String, id: String) -> Result<bool, String> {
+  translation::delete_glossary(&PathBuf::from(workspace_path), &id)
+}
@@ -1,16 +1,27 @@
+use crate::services::image_conversion::{self, ImageConversionReport, TargetImageFormat};
+use crate::services::image_dedupe::{self, ImageDedupeReport, DEFAULT_NEAR_THRESHOLD};
+use crate::services::image_metadata::{self, MetadataStripResult};
 use crate::services::image_service::{ImageService, InsertImageResult};
+use crate::services::job_service::{self, JobSpec};
+use crate::services::workspace_settings::WorkspaceSettings;
 use std::path::PathBuf;
+use tauri::Emitter;
 
+/// 插入图片；传入 workspace_path 时按工作区图片设置优化
 #[tauri::command]
 pub async fn insert_image(
   document_path: String,
   image_source: String,
+  workspace_path: Option<String>,
 ) -> Result<InsertImageResult, String> {
   let service = ImageService::new();
   let doc_path = PathBuf::from(document_path);
   let img_path = PathBuf::from(image_source);
+  let settings = workspace_path
+    .map(|ws| WorkspaceSettings::load_or_default(&PathBuf::from(ws)).images)
+    .unwrap_or_default();
 
-  service.insert_image(&doc_path, &img_path).await
+  service.insert_image(&doc_path, &img_path, &settings).await
 }
 
 #[tauri::command]
@@ -37,8 +48,93 @@ pub async fn save_chat_image(
 ) -> Result<String, String> {
   let service = ImageService::new();
   let workspace = PathBuf::from(workspace_path);
+  let settings = WorkspaceSettings::load_or_default(&workspace).images;
 
   service
-    .save_chat_image(&workspace, image_data, file_name)
+    .save_chat_image(&workspace, image_data, file_name, &settings)
     .await
 }
+
+/// 移除已有图片的 EXIF 等元数据（原地改写，不重新编码）
+#[tauri::command]
+pub async fn strip_image_metadata(path: String) -> Result<MetadataStripResult, String> {
+  let path = PathBuf::from(path);
+  if !path.is_file() {
+    return Err(format!("图片不存在: {}", path.display()));
+  }
+  tokio::task::spawn_blocking(move || image_metadata::strip_metadata_file(&path))
+    .await
+    .map_err(|e| format!("清理图片元数据失败: {}", e))?
+}
+
+/// 将工作区图片批量转换为 WebP / AVIF 并更新文档引用；quality 缺省时使用工作区图片设置
+#[tauri::command]
+pub async fn convert_workspace_images(
+  workspace_path: String,
+  format: String,
+  quality: Option<u8>,
+  dry_run: bool,
+  app: tauri::AppHandle,
+) -> Result<ImageConversionReport, String> {
+  let format = TargetImageFormat::parse(&format)?;
+  let workspace = PathBuf::from(&workspace_path);
+  let quality = quality.unwrap_or_else(|| {
+    WorkspaceSettings::load_or_default(&workspace)
+      .images
+      .quality
+  });
+
+  let report = job_service::jobs()
+    .run(
+      JobSpec::new("convert_images", "转换工作区图片格式"),
+      move |_| async move {
+        tokio::task::spawn_blocking(move || {
+          image_conversion::convert_workspace_images(&workspace, format, quality, dry_run)
+        })
+        .await
+        .map_err(|e| format!("图片转换任务失败: {}", e))?
+      },
+    )
+    .await?;
+
+  if !dry_run && report.converted_count > 0 {
+    let _ = app.emit("file-tree-changed", workspace_path);
+  }
+  Ok(report)
+}
+
+/// 资源文件夹图片去重：完全相同的副本合并为一份；include_near 时同时合并近似重复
+#[tauri::command]
+pub async fn dedupe_images(
+  workspace_path: String,
+  dry_run: bool,
+  include_near: Option<bool>,
+  near_threshold: Option<u32>,
+  app: tauri::AppHandle,
+) -> Result<ImageDedupeReport, String> {
+  let workspace = PathBuf::from(&workspace_path);
+  let threshold = near_threshold.unwrap_or(DEFAULT_NEAR_THRESHOLD).min(16);
+
+  let report = job_service::jobs()
+    .run(
+      JobSpec::new("dedupe_images", "资源文件夹图片去重"),
+      move |_| async move {
+        tokio::task::spawn_blocking(move || {
+          image_dedupe::dedupe_images(
+            &workspace,
+            dry_run,
+            include_near.unwrap_or(false),
+            threshold,
+          )
+        })
+        .await
+        .map_err(|e| format!("图片去重任务失败: {}", e))?
+      },
+    )
+    .await?;
+
+  if !dry_run && !report.groups.is_empty() {
+    let _ = app.emit("file-tree-changed", workspace_path);
+  }
+  Ok(report)
+}
@@ -0,0 +1,20 @@
+use crate::services::job_service::{self, JobInfo};
+
+/// 全部后台任务（排队、运行中与最近结束的），状态变化另有 `job-updated` 事件
+#[tauri::command]
+pub async fn list_jobs() -> Result<Vec<JobInfo>, String> {
+  Ok(job_service::jobs().list())
+}
+
+/// 取消后台任务；任务不存在或已结束时返回 false
+#[tauri::command]
+pub async fn cancel_job(id: String) -> Result<bool, String> {
+  Ok(job_service::jobs().cancel(&id))
+}
+
+/// 清除已结束的任务记录
+#[tauri::command]
+pub async fn clear_finished_jobs() -> Result<(), String> {
+  job_service::jobs().clear_finished();
+  Ok(())
+}
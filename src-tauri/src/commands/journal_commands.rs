@@ -0,0 +1,29 @@
+use crate::services::buffer_journal::{self, BufferDelta, RecoverableBuffer};
+
+/// 追加编辑器缓冲区增量；首次记录或重新同步时传入 `base`（完整内容）
+#[tauri::command]
+pub async fn journal_buffer(
+  path: String,
+  base: Option<String>,
+  deltas: Vec<BufferDelta>,
+) -> Result<(), String> {
+  tokio::task::spawn_blocking(move || {
+    buffer_journal::journal().append(&path, base.as_deref(), &deltas)
+  })
+  .await
+  .map_err(|e| format!("写入日志失败: {}", e))?
+}
+
+/// 删除文件的未保存内容日志（已保存或放弃修改后调用）
+#[tauri::command]
+pub async fn discard_buffer_journal(path: String) -> Result<(), String> {
+  buffer_journal::journal().discard(&path)
+}
+
+/// 启动时列出上次异常退出前未保存的内容
+#[tauri::command]
+pub async fn list_recoverable_buffers() -> Result<Vec<RecoverableBuffer>, String> {
+  tokio::task::spawn_blocking(|| buffer_journal::journal().recoverable())
+    .await
+    .map_err(|e| format!("读取日志失败: {}", e))
+}
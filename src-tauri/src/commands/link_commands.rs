@@ -0,0 +1,39 @@
+use crate::services::link_graph::{self, Backlink, LinkGraphSnapshot};
+use crate::services::wikilink::{self, WikiLinkResolution};
+use std::path::PathBuf;
+
+/// 引用了 path 的全部文档位置（反向链接）
+#[tauri::command]
+pub async fn get_backlinks(workspace_path: String, path: String) -> Result<Vec<Backlink>, String> {
+  tokio::task::spawn_blocking(move || {
+    link_graph::get_backlinks(&PathBuf::from(workspace_path), &PathBuf::from(path))
+  })
+  .await
+  .map_err(|e| format!("获取反向链接失败: {}", e))
+}
+
+/// 工作区文档之间的链接关系图
+#[tauri::command]
+pub async fn get_link_graph(workspace_path: String) -> Result<LinkGraphSnapshot, String> {
+  tokio::task::spawn_blocking(move || link_graph::get_link_graph(&PathBuf::from(workspace_path)))
+    .await
+    .map_err(|e| format!("获取链接关系图失败: {}", e))
+}
+
+/// 把 `[[笔记]]` 解析为笔记路径；笔记不存在时默认按模板新建（create 为 false 时返回 None）
+#[tauri::command]
+pub async fn resolve_wikilink(
+  workspace_path: String,
+  text: String,
+  create: Option<bool>,
+) -> Result<Option<WikiLinkResolution>, String> {
+  tokio::task::spawn_blocking(move || {
+    wikilink::resolve_wikilink(
+      &PathBuf::from(workspace_path),
+      &text,
+      create.unwrap_or(true),
+    )
+  })
+  .await
+  .map_err(|e| format!("解析 Wiki 链接失败: {}", e))?
+}
@@ -1,13 +1,17 @@
 //! 记忆库 Tauri 命令（P0.5 + P0 + P1 + P2）
 
+use crate::services::memory_anchor::AnchorKind;
 use crate::services::memory_service::{
-  MemorySearchResponse, MemorySearchScope, MemoryService, SearchMemoriesParams,
+  expire_user_memory, upsert_user_memory, MemoryConsistencyReport, MemoryDedupeReport,
+  MemoryImportSummary, MemoryItem, MemoryItemInput, MemoryLayer, MemoryScopeType,
+  MemorySearchResponse, MemorySearchScope, MemoryService, MemorySourceKind, MemoryStats,
+  MemoryStoreScope, MemoryUpdateFields, ResolvedMemoryAnchor, SearchMemoriesParams,
 };
 use std::path::Path;
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
 use tauri::State;
 
-type AIServiceState = Arc<Mutex<crate::services::ai_service::AIService>>;
+type AIServiceState = Arc<crate::services::ai_service::AIService>;
 
 // ── P0.5：孤立 tab 记忆清理 ─────────────────────────────────────────────────
 
@@ -27,8 +31,11 @@ pub async fn mark_orphan_tab_memories_stale(
 
 // ── P0：检索命令 ─────────────────────────────────────────────────────────────
 
-/// P0/P2: 检索记忆（workspace + 可选 user_memory 合并）
+/// P0/P2: 检索记忆（workspace + user_memory 合并）
+///
+/// scope 为 all / user 时默认合并全局记忆，可用 include_user_memory 显式关闭。
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn search_memories_cmd(
   query: String,
   tab_id: Option<String>,
@@ -36,6 +43,7 @@ pub async fn search_memories_cmd(
   scope: Option<String>,
   limit: Option<usize>,
   entity_types: Option<Vec<String>>,
+  tags: Option<Vec<String>>,
   include_user_memory: Option<bool>, // P2: 是否合并 user_memory.db
 ) -> Result<MemorySearchResponse, String> {
   let ws_path = workspace_path.clone().unwrap_or_default();
@@ -54,8 +62,11 @@ pub async fn search_memories_cmd(
     .map(MemorySearchScope::from_str)
     .unwrap_or(MemorySearchScope::All);
 
-  // 阶段边界收口：默认不合并 user_memory，只有显式开启才合并（P2）。
-  let should_include_user = include_user_memory.unwrap_or(false);
+  // 只有 all / user 范围才合并全局记忆；tab / content 等工作区范围保持隔离
+  let should_include_user = include_user_memory.unwrap_or(matches!(
+    scope_parsed,
+    MemorySearchScope::All | MemorySearchScope::User
+  ));
 
   let service = MemoryService::new(Path::new(&db_workspace))
     .map_err(|e| format!("MemoryService 初始化失败: {}", e))?;
@@ -68,6 +79,7 @@ pub async fn search_memories_cmd(
     scope: scope_parsed,
     limit: Some(total_limit),
     entity_types,
+    tags,
   };
 
   let mut resp = service
@@ -91,6 +103,51 @@ pub async fn search_memories_cmd(
   Ok(resp)
 }
 
+/// 语义检索记忆：使用支持 embeddings 的提供商计算向量，按余弦相似度返回 top_k
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn search_memories_semantic(
+  query: String,
+  top_k: Option<usize>,
+  tab_id: Option<String>,
+  workspace_path: String,
+  scope: Option<String>,
+  entity_types: Option<Vec<String>>,
+  tags: Option<Vec<String>>,
+  service: State<'_, AIServiceState>,
+) -> Result<MemorySearchResponse, String> {
+  if workspace_path.is_empty() {
+    return Ok(MemorySearchResponse::empty());
+  }
+
+  let provider = ["openai", "deepseek"]
+    .iter()
+    .filter_map(|name| service.get_provider(name))
+    .find(|p| p.embedding_model().is_some())
+    .ok_or_else(|| "没有可用的 embeddings 提供商，请先配置 OpenAI API key".to_string())?;
+
+  let memory_service = MemoryService::new(Path::new(&workspace_path))
+    .map_err(|e| format!("MemoryService 初始化失败: {}", e))?;
+
+  let params = SearchMemoriesParams {
+    query,
+    tab_id,
+    workspace_path: Some(workspace_path),
+    scope: scope
+      .as_deref()
+      .map(MemorySearchScope::from_str)
+      .unwrap_or(MemorySearchScope::All),
+    limit: Some(top_k.unwrap_or(10)),
+    entity_types,
+    tags,
+  };
+
+  memory_service
+    .search_memories_semantic(&provider, params)
+    .await
+    .map_err(|e| e.to_string())
+}
+
 // ── P1：Tab 删除升格 ──────────────────────────────────────────────────────────
 
 #[tauri::command]
@@ -105,12 +162,9 @@ pub async fn on_tab_deleted_cmd(
     _ => return Ok(()), // 无工作区时不升格
   };
 
-  let provider = {
-    let guard = service.lock().map_err(|e| e.to_string())?;
-    guard
-      .get_provider("deepseek")
-      .or_else(|| guard.get_provider("openai"))
-  };
+  let provider = service
+    .get_provider("deepseek")
+    .or_else(|| service.get_provider("openai"));
 
   let ws = std::path::PathBuf::from(ws_str);
   tokio::spawn(async move {
@@ -120,12 +174,88 @@ pub async fn on_tab_deleted_cmd(
   Ok(())
 }
 
+// ── 手动添加 ─────────────────────────────────────────────────────────────────
+
+/// 手动添加记忆，返回新记忆 id
+///
+/// store = "global"：用户偏好，写入 user_memory.db，所有工作区可检索；
+/// 默认 "workspace"：项目事实，只写入当前工作区的 workspace.db。
+#[tauri::command]
+pub async fn add_memory(
+  workspace_path: String,
+  store: Option<String>,
+  entity_type: String,
+  entity_name: String,
+  content: String,
+  tags: Option<Vec<String>>,
+) -> Result<String, String> {
+  let entity_name = entity_name.trim().to_string();
+  let content = content.trim().to_string();
+  if entity_name.is_empty() || content.is_empty() {
+    return Err("实体名称和内容不能为空".to_string());
+  }
+  if content.chars().count() > 500 {
+    return Err("记忆内容不能超过 500 字".to_string());
+  }
+  let entity_type = match entity_type.trim() {
+    "" => "note".to_string(),
+    t => t.to_string(),
+  };
+  let store = store
+    .as_deref()
+    .map(MemoryStoreScope::from_str)
+    .unwrap_or(MemoryStoreScope::Workspace);
+
+  let mut item = MemoryItemInput {
+    layer: MemoryLayer::WorkspaceLongTerm,
+    scope_type: MemoryScopeType::Workspace,
+    scope_id: workspace_path.clone(),
+    entity_type,
+    entity_name,
+    summary: content.chars().take(50).collect(),
+    content,
+    tags: tags.unwrap_or_default(),
+    source_kind: MemorySourceKind::UserPreference,
+    source_ref: String::new(),
+    confidence: 1.0,
+  };
+
+  match store {
+    MemoryStoreScope::Global => {
+      item.layer = MemoryLayer::User;
+      item.scope_type = MemoryScopeType::User;
+      upsert_user_memory(item).await
+    }
+    MemoryStoreScope::Workspace => {
+      if workspace_path.is_empty() {
+        return Err("添加工作区记忆需要先打开工作区".to_string());
+      }
+      let service = MemoryService::new(Path::new(&workspace_path))
+        .map_err(|e| format!("MemoryService 初始化失败: {}", e))?;
+      service
+        .upsert_workspace_long_term_memory(item)
+        .await
+        .map_err(|e| e.to_string())
+    }
+  }
+}
+
 // ── P2：用户手动屏蔽记忆项 ────────────────────────────────────────────────────
 
-/// P2: 将指定记忆项标记为 expired（用户主动屏蔽）
+/// P2: 将指定记忆项标记为 expired（用户主动屏蔽）；store = "global" 时屏蔽全局记忆
 #[tauri::command]
-pub async fn expire_memory_item(memory_id: String, workspace_path: String) -> Result<(), String> {
-  if workspace_path.is_empty() || memory_id.is_empty() {
+pub async fn expire_memory_item(
+  memory_id: String,
+  workspace_path: String,
+  store: Option<String>,
+) -> Result<(), String> {
+  if memory_id.is_empty() {
+    return Ok(());
+  }
+  if store.as_deref().map(MemoryStoreScope::from_str) == Some(MemoryStoreScope::Global) {
+    return expire_user_memory(&memory_id).await;
+  }
+  if workspace_path.is_empty() {
     return Ok(());
   }
   let service = MemoryService::new(Path::new(&workspace_path))
@@ -136,6 +266,28 @@ pub async fn expire_memory_item(memory_id: String, workspace_path: String) -> Re
     .map_err(|e| e.to_string())
 }
 
+// ── 编辑 ─────────────────────────────────────────────────────────────────────
+
+/// 原地编辑记忆文本/标签；版本冲突时返回 "version conflict: current version N"，
+/// 前端应重新拉取后再提交
+#[tauri::command]
+pub async fn update_memory(
+  workspace_path: String,
+  memory_id: String,
+  expected_version: i64,
+  fields: MemoryUpdateFields,
+) -> Result<MemoryItem, String> {
+  if workspace_path.is_empty() || memory_id.is_empty() {
+    return Err("workspace_path 和 memory_id 不能为空".to_string());
+  }
+  let service = MemoryService::new(Path::new(&workspace_path))
+    .map_err(|e| format!("MemoryService 初始化失败: {}", e))?;
+  service
+    .update_memory(&memory_id, expected_version, fields)
+    .await
+    .map_err(|e| e.to_string())
+}
+
 // ── P2：批量屏蔽（按 layer）───────────────────────────────────────────────────
 
 /// P2: 将指定 layer 的所有记忆标记为 expired（批量屏蔽）
@@ -152,6 +304,149 @@ pub async fn expire_memory_layer(layer: String, workspace_path: String) -> Resul
     .map_err(|e| e.to_string())
 }
 
+// ── 去重 ─────────────────────────────────────────────────────────────────────
+
+/// 合并近似重复的记忆；dry_run=true 时只返回报告不写库
+#[tauri::command]
+pub async fn dedupe_memories(
+  workspace_path: String,
+  threshold: Option<f64>,
+  dry_run: Option<bool>,
+) -> Result<MemoryDedupeReport, String> {
+  let service = MemoryService::new(Path::new(&workspace_path))
+    .map_err(|e| format!("MemoryService 初始化失败: {}", e))?;
+  service
+    .dedupe_memories(threshold, dry_run.unwrap_or(false))
+    .await
+    .map_err(|e| e.to_string())
+}
+
+// ── 文档锚点 ─────────────────────────────────────────────────────────────────
+
+/// 将记忆锚定到文档中的标题或段落（anchor_kind: heading / paragraph，text 为块文本）
+#[tauri::command]
+pub async fn anchor_memory(
+  workspace_path: String,
+  memory_id: String,
+  doc_path: String,
+  anchor_kind: String,
+  text: String,
+) -> Result<ResolvedMemoryAnchor, String> {
+  let kind = AnchorKind::from_str(&anchor_kind)
+    .ok_or_else(|| format!("不支持的锚点类型: {}", anchor_kind))?;
+  let service = MemoryService::new(Path::new(&workspace_path))
+    .map_err(|e| format!("MemoryService 初始化失败: {}", e))?;
+  service
+    .set_memory_anchor(&memory_id, &doc_path, kind, &text)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// 将文档上的记忆锚点解析为当前行号/偏移
+#[tauri::command]
+pub async fn resolve_memory_anchors(
+  workspace_path: String,
+  doc_path: String,
+) -> Result<Vec<ResolvedMemoryAnchor>, String> {
+  let service = MemoryService::new(Path::new(&workspace_path))
+    .map_err(|e| format!("MemoryService 初始化失败: {}", e))?;
+  service
+    .resolve_memory_anchors(&doc_path)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// 检查并修复记忆锚点（doc_path 为空时检查全部文档）
+#[tauri::command]
+pub async fn check_memory_consistency(
+  workspace_path: String,
+  doc_path: Option<String>,
+) -> Result<MemoryConsistencyReport, String> {
+  let service = MemoryService::new(Path::new(&workspace_path))
+    .map_err(|e| format!("MemoryService 初始化失败: {}", e))?;
+  service
+    .check_memory_consistency(doc_path.as_deref())
+    .await
+    .map_err(|e| e.to_string())
+}
+
+// ── 统计 ─────────────────────────────────────────────────────────────────────
+
+/// 记忆库概览：总数、按文档/标签/层级/状态计数、存储大小与最近修改时间
+#[tauri::command]
+pub async fn get_memory_stats(workspace_path: String) -> Result<MemoryStats, String> {
+  let service = MemoryService::new(Path::new(&workspace_path))
+    .map_err(|e| format!("MemoryService 初始化失败: {}", e))?;
+  service.get_memory_stats().await.map_err(|e| e.to_string())
+}
+
+// ── 标签 ─────────────────────────────────────────────────────────────────────
+
+/// 批量改标签（如区分 style_preference / project_fact），返回变更条数
+#[tauri::command]
+pub async fn retag_memories(
+  workspace_path: String,
+  memory_ids: Vec<String>,
+  add_tags: Option<Vec<String>>,
+  remove_tags: Option<Vec<String>>,
+) -> Result<u64, String> {
+  if workspace_path.is_empty() || memory_ids.is_empty() {
+    return Ok(0);
+  }
+  let service = MemoryService::new(Path::new(&workspace_path))
+    .map_err(|e| format!("MemoryService 初始化失败: {}", e))?;
+  service
+    .retag_memories(
+      memory_ids,
+      add_tags.unwrap_or_default(),
+      remove_tags.unwrap_or_default(),
+    )
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// 获取某个文档的 content 层记忆，可按标签过滤
+#[tauri::command]
+pub async fn get_document_memories(
+  workspace_path: String,
+  file_path: String,
+  tags: Option<Vec<String>>,
+) -> Result<Vec<MemoryItem>, String> {
+  let service = MemoryService::new(Path::new(&workspace_path))
+    .map_err(|e| format!("MemoryService 初始化失败: {}", e))?;
+  service
+    .get_document_memories(&file_path, tags.unwrap_or_default())
+    .await
+    .map_err(|e| e.to_string())
+}
+
+// ── 导出 / 导入 ───────────────────────────────────────────────────────────────
+
+/// 将工作区记忆导出为 JSON 文件（带 schema 版本），返回导出条数
+#[tauri::command]
+pub async fn export_memories(workspace_path: String, path: String) -> Result<usize, String> {
+  let service = MemoryService::new(Path::new(&workspace_path))
+    .map_err(|e| format!("MemoryService 初始化失败: {}", e))?;
+  service
+    .export_memories(Path::new(&path))
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// 从 JSON 文件导入记忆，与现有记忆合并去重
+#[tauri::command]
+pub async fn import_memories(
+  workspace_path: String,
+  path: String,
+) -> Result<MemoryImportSummary, String> {
+  let service = MemoryService::new(Path::new(&workspace_path))
+    .map_err(|e| format!("MemoryService 初始化失败: {}", e))?;
+  service
+    .import_memories(Path::new(&path))
+    .await
+    .map_err(|e| e.to_string())
+}
+
 // ── P2：user_memory.db 初始化与信息获取 ──────────────────────────────────────
 
 /// P2: 获取 user_id 和 user_memory.db 路径（前端初始化时调用）
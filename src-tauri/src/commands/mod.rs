@@ -1,10 +1,23 @@
 pub mod ai_commands;
 pub mod classifier_commands;
 pub mod file_commands;
+pub mod glossary_commands;
 pub mod image_commands;
+pub mod job_commands;
+pub mod journal_commands;
 pub mod knowledge_commands;
+pub mod link_commands;
 pub mod memory_commands;
+pub mod ocr_commands;
+pub mod plugin_commands;
 pub mod positioning_snapshot;
+pub mod print_commands;
+pub mod prompt_commands;
 pub mod search_commands;
+pub mod settings_commands;
+pub mod snippet_commands;
+pub mod spellcheck_commands;
+pub mod telemetry_commands;
 pub mod template_commands;
 pub mod tool_commands;
+pub mod update_commands;
@@ -0,0 +1,60 @@
+use crate::services::ocr_service::{OcrOutput, OcrService, OCR_DEFAULT_MAX_PAGES};
+use crate::services::search_service::SearchService;
+use std::path::PathBuf;
+
+/// 检查文字识别（Tesseract）是否可用
+#[tauri::command]
+pub async fn check_ocr_available() -> Result<serde_json::Value, String> {
+  tokio::task::spawn_blocking(|| {
+    let service = OcrService::new();
+    let languages = if service.is_available() {
+      service.available_languages().unwrap_or_default()
+    } else {
+      Vec::new()
+    };
+    serde_json::json!({
+        "available": service.is_available(),
+        "path": service.get_path().map(|p| p.to_string_lossy().to_string()),
+        "pdf_supported": service.supports_pdf(),
+        "languages": languages,
+    })
+  })
+  .await
+  .map_err(|e| format!("检查文字识别失败: {}", e))
+}
+
+/// 识别图片或扫描版 PDF 中的文字
+///
+/// 传入 workspace_path 且 index 为 true 时，识别结果写入该工作区的搜索索引。
+#[tauri::command]
+pub async fn ocr_image(
+  path: String,
+  lang: Option<String>,
+  max_pages: Option<u32>,
+  workspace_path: Option<String>,
+  index: Option<bool>,
+) -> Result<OcrOutput, String> {
+  let file_path = PathBuf::from(&path);
+  if !file_path.is_file() {
+    return Err(format!("文件不存在: {}", path));
+  }
+
+  tokio::task::spawn_blocking(move || {
+    let output = OcrService::new().recognize(
+      &file_path,
+      lang.as_deref(),
+      max_pages.unwrap_or(OCR_DEFAULT_MAX_PAGES),
+    )?;
+
+    if let (Some(workspace), true) = (workspace_path, index.unwrap_or(false)) {
+      if !output.text.is_empty() {
+        SearchService::new(&PathBuf::from(workspace))
+          .and_then(|service| service.index_document(&file_path, &output.text))
+          .map_err(|e| format!("写入搜索索引失败: {}", e))?;
+      }
+    }
+    Ok(output)
+  })
+  .await
+  .map_err(|e| format!("文字识别失败: {}", e))?
+}
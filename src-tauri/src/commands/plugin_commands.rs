@@ -0,0 +1,51 @@
+use crate::services::ai_service::AIService;
+use crate::services::plugins::{self, PluginInfo};
+use crate::utils::path_validator::PathValidator;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tauri::State;
+
+type AIServiceState = Arc<AIService>;
+
+/// 已发现的插件（包括加载失败的，附带原因）
+#[tauri::command]
+pub async fn list_plugins() -> Result<Vec<PluginInfo>, String> {
+  Ok(plugins::with_registry(|r| r.infos()))
+}
+
+/// 插件目录（`plugins/<插件>/plugin.toml`）
+#[tauri::command]
+pub async fn get_plugins_dir() -> Result<String, String> {
+  Ok(plugins::plugins_dir().to_string_lossy().to_string())
+}
+
+/// 重新扫描插件目录，并重新注册插件提供商
+#[tauri::command]
+pub async fn reload_plugins(service: State<'_, AIServiceState>) -> Result<Vec<PluginInfo>, String> {
+  let infos = plugins::reload();
+  service.register_plugin_providers();
+  Ok(infos)
+}
+
+/// 用插件转换器转换文档，返回输出文件路径；源文件与输出路径都必须位于工作区内
+#[tauri::command]
+pub async fn convert_with_plugin(
+  converter: String,
+  path: String,
+  output_path: Option<String>,
+  workspace_path: String,
+) -> Result<String, String> {
+  let workspace = Path::new(&workspace_path);
+  let input = PathBuf::from(path);
+  let input = PathValidator::validate_workspace_path(&input, workspace)
+    .map_err(|e| format!("{}: {}", e, input.display()))?;
+  let output = output_path
+    .map(|output| {
+      let output = PathBuf::from(output);
+      PathValidator::validate_workspace_write_target(&output, workspace)
+        .map_err(|e| format!("{}: {}", e, output.display()))
+    })
+    .transpose()?;
+  let output = plugins::convert(&converter, &input, output).await?;
+  Ok(output.to_string_lossy().to_string())
+}
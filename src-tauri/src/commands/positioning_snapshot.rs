@@ -31,7 +31,7 @@ pub fn positioning_submit_editor_snapshot(
       document_revision,
     });
   } else {
-    eprintln!(
+    tracing::debug!(
       "[positioning] submit_editor_snapshot: stale or unknown request_id={}",
       request_id
     );
@@ -56,7 +56,7 @@ pub async fn request_editor_snapshot_ipc(
       "file_path": file_path,
   });
   if let Err(e) = app.emit("positioning-request-editor-snapshot", payload) {
-    eprintln!(
+    tracing::warn!(
       "[positioning] emit positioning-request-editor-snapshot failed: {}",
       e
     );
@@ -67,24 +67,25 @@ pub async fn request_editor_snapshot_ipc(
   match tokio::time::timeout(std::time::Duration::from_millis(timeout_ms), rx).await {
     Ok(Ok(payload)) => {
       if payload.html.as_ref().is_some_and(|s| !s.is_empty()) {
-        eprintln!(
+        tracing::debug!(
           "[positioning] IPC refreshed L len={} document_revision={:?}",
           payload.html.as_ref().map(|s| s.len()).unwrap_or(0),
           payload.document_revision
         );
       } else {
-        eprintln!("[positioning] IPC snapshot missing/empty; keeping request-time L");
+        tracing::debug!("[positioning] IPC snapshot missing/empty; keeping request-time L");
       }
       Some(payload)
     }
     Ok(Err(_)) => {
-      eprintln!("[positioning] snapshot channel closed without payload");
+      tracing::debug!("[positioning] snapshot channel closed without payload");
       None
     }
     Err(_) => {
-      eprintln!(
+      tracing::debug!(
         "[positioning] snapshot IPC timeout ({}ms) request_id={}",
-        timeout_ms, request_id
+        timeout_ms,
+        request_id
       );
       let _ = SNAPSHOT_WAITERS.lock().ok()?.remove(&request_id);
       None
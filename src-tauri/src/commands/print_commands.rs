@@ -0,0 +1,16 @@
+use crate::services::print_service::{PrintOptions, PrintResult, PrintService};
+use std::path::PathBuf;
+
+/// 打印文档：非 PDF 先转换为 PDF，再交给系统打印（可指定页码范围与份数）
+#[tauri::command]
+pub async fn print_document(
+  path: String,
+  options: Option<PrintOptions>,
+) -> Result<PrintResult, String> {
+  let file_path = PathBuf::from(&path);
+  tokio::task::spawn_blocking(move || {
+    PrintService::print_document(&file_path, &options.unwrap_or_default())
+  })
+  .await
+  .map_err(|e| format!("打印失败: {}", e))?
+}
@@ -0,0 +1,51 @@
+use crate::services::prompt_library::{
+  self, PromptRenderContext, PromptTarget, RenderedPrompt, SavedPrompt,
+};
+use std::path::PathBuf;
+
+/// 列出工作区的提示词；指定 target 时只返回该位置可用的提示词
+#[tauri::command]
+pub async fn list_prompts(
+  workspace_path: String,
+  target: Option<PromptTarget>,
+) -> Result<Vec<SavedPrompt>, String> {
+  prompt_library::list_prompts(&PathBuf::from(workspace_path), target)
+}
+
+/// 新建提示词，返回带 id 的提示词
+#[tauri::command]
+pub async fn create_prompt(
+  workspace_path: String,
+  prompt: SavedPrompt,
+) -> Result<SavedPrompt, String> {
+  prompt_library::create_prompt(&PathBuf::from(workspace_path), prompt)
+}
+
+/// 按 id 更新提示词
+#[tauri::command]
+pub async fn update_prompt(
+  workspace_path: String,
+  prompt: SavedPrompt,
+) -> Result<SavedPrompt, String> {
+  prompt_library::update_prompt(&PathBuf::from(workspace_path), prompt)
+}
+
+/// 删除提示词，返回是否存在
+#[tauri::command]
+pub async fn delete_prompt(workspace_path: String, id: String) -> Result<bool, String> {
+  prompt_library::delete_prompt(&PathBuf::from(workspace_path), &id)
+}
+
+/// 按 id 或斜杠命令渲染提示词，填入选中文字与文档内容；不存在时返回 null
+#[tauri::command]
+pub async fn render_prompt(
+  workspace_path: String,
+  key: String,
+  context: Option<PromptRenderContext>,
+) -> Result<Option<RenderedPrompt>, String> {
+  prompt_library::render_prompt(
+    &PathBuf::from(workspace_path),
+    &key,
+    &context.unwrap_or_default(),
+  )
+}
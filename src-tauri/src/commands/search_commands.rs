@@ -1,5 +1,18 @@
-use crate::services::search_service::{SearchResult, SearchService};
-use std::path::PathBuf;
+use crate::services::encryption;
+use crate::services::file_watcher::is_reference_path;
+use crate::services::in_file_search::{
+  InFileReplaceOptions, InFileReplaceResult, InFileSearchOptions, InFileSearchResult,
+  InFileSearchService,
+};
+use crate::services::job_service::{self, JobPriority, JobSpec};
+use crate::services::memory_service::check_anchors_after_save;
+use crate::services::open_file_tracker;
+use crate::services::search_service::{refresh_index_after_save, SearchResult, SearchService};
+use crate::services::telemetry;
+use crate::services::workspace::WorkspaceService;
+use crate::utils::path_validator::PathValidator;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
 
 // 搜索服务不需要全局状态，每次使用时创建新的实例（因为需要 workspace_path）
 
@@ -11,12 +24,199 @@ pub async fn search_documents(
 ) -> Result<Vec<SearchResult>, String> {
   let path = PathBuf::from(workspace_path);
   let service = SearchService::new(&path).map_err(|e| format!("初始化搜索服务失败: {}", e))?;
+  telemetry::record_feature("search_documents");
 
   service
     .search(&query, limit)
     .map_err(|e| format!("搜索失败: {}", e))
 }
 
+/// 跨工作区搜索结果（附带来源工作区信息）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkspaceSearchResult {
+  pub workspace_path: String,
+  pub workspace_name: String,
+  /// 按所有工作区中最相关的结果归一化的相关度（0~1），用于跨工作区排序
+  pub score: f64,
+  #[serde(flatten)]
+  pub result: SearchResult,
+}
+
+/// 在多个工作区中并发搜索，并按相关度合并结果
+///
+/// 未指定 `workspace_paths` 时使用最近打开的工作区列表。
+/// 尚未建立索引的工作区会被跳过，单个工作区搜索失败不影响整体结果。
+#[tauri::command]
+pub async fn search_all_workspaces(
+  query: String,
+  limit: usize,
+  workspace_paths: Option<Vec<String>>,
+) -> Result<Vec<WorkspaceSearchResult>, String> {
+  let workspaces: Vec<(String, String)> = match workspace_paths {
+    Some(paths) => paths
+      .into_iter()
+      .map(|p| {
+        let name = PathBuf::from(&p)
+          .file_name()
+          .and_then(|n| n.to_str())
+          .unwrap_or("未命名工作区")
+          .to_string();
+        (p, name)
+      })
+      .collect(),
+    None => WorkspaceService::new()?
+      .load_workspaces()?
+      .into_iter()
+      .map(|w| (w.path, w.name))
+      .collect(),
+  };
+
+  let mut tasks = tokio::task::JoinSet::new();
+  for (workspace_path, workspace_name) in workspaces {
+    let query = query.clone();
+    tasks.spawn_blocking(move || {
+      let root = PathBuf::from(&workspace_path);
+      // 只搜索已有索引的工作区，避免在未打开过的目录中创建 .binder
+      if !root.join(".binder").join("search.db").exists() {
+        return Vec::new();
+      }
+      let results = SearchService::new(&root).and_then(|service| service.search(&query, limit));
+      match results {
+        Ok(results) => vec![(workspace_path, workspace_name, results)],
+        Err(e) => {
+          tracing::warn!("搜索工作区失败 ({}): {}", workspace_path, e);
+          Vec::new()
+        }
+      }
+    });
+  }
+
+  let mut per_workspace = Vec::new();
+  while let Some(joined) = tasks.join_next().await {
+    match joined {
+      Ok(results) => per_workspace.extend(results),
+      Err(e) => tracing::warn!("工作区搜索任务异常: {}", e),
+    }
+  }
+
+  let mut merged = normalize_scores(per_workspace);
+  merge_ranked_results(&mut merged, limit);
+  Ok(merged)
+}
+
+/// rank 越小越相关（为负的 BM25 分数），统一按所有工作区中最相关结果的 rank 归一化到 0~1。
+/// 不按工作区各自归一化：否则只有弱匹配的工作区，其最佳结果也会得到 1 而排到其它工作区的强匹配前面
+fn normalize_scores(
+  per_workspace: Vec<(String, String, Vec<SearchResult>)>,
+) -> Vec<WorkspaceSearchResult> {
+  let best = per_workspace
+    .iter()
+    .flat_map(|(_, _, results)| results.iter().map(|r| r.rank))
+    .fold(0.0_f64, f64::min);
+  per_workspace
+    .into_iter()
+    .flat_map(|(workspace_path, workspace_name, results)| {
+      results
+        .into_iter()
+        .map(move |result| WorkspaceSearchResult {
+          workspace_path: workspace_path.clone(),
+          workspace_name: workspace_name.clone(),
+          score: if best < 0.0 {
+            (result.rank / best).clamp(0.0, 1.0)
+          } else {
+            1.0
+          },
+          result,
+        })
+    })
+    .collect()
+}
+
+/// 按归一化相关度合并；相同分数时按工作区和路径排序保证结果稳定
+fn merge_ranked_results(results: &mut Vec<WorkspaceSearchResult>, limit: usize) {
+  results.sort_by(|a, b| {
+    b.score
+      .partial_cmp(&a.score)
+      .unwrap_or(std::cmp::Ordering::Equal)
+      .then_with(|| a.workspace_path.cmp(&b.workspace_path))
+      .then_with(|| a.result.path.cmp(&b.result.path))
+  });
+  results.truncate(limit);
+}
+
+/// 在单个（可能很大的）文件中搜索，返回匹配的字节范围，供分块阅读器定位
+#[tauri::command]
+pub async fn search_in_file(
+  path: String,
+  query: String,
+  options: Option<InFileSearchOptions>,
+  workspace_path: String,
+) -> Result<InFileSearchResult, String> {
+  let options = options.unwrap_or_default();
+  let path = PathBuf::from(path);
+  // 只允许搜索工作区内或已挂载的参考资料文件夹中的文件
+  let path = if is_reference_path(&path) {
+    path
+  } else {
+    PathValidator::validate_workspace_path(&path, Path::new(&workspace_path))
+      .map_err(|e| format!("{}: {}", e, path.display()))?
+  };
+  tokio::task::spawn_blocking(move || InFileSearchService::search_file(&path, &query, &options))
+    .await
+    .map_err(|e| format!("文件内搜索任务异常: {}", e))?
+}
+
+/// 在单个文件中查找并替换（正则时支持 `$1` 捕获组），preview 时只返回替换片段；
+/// 在后端流式处理并原子写回，适用于编辑器无法完整加载的大文件。
+/// 写回后与 `write_file` 一样更新打开文件快照、搜索索引与记忆锚点
+#[tauri::command]
+pub async fn replace_in_file(
+  path: String,
+  query: String,
+  replacement: String,
+  options: Option<InFileReplaceOptions>,
+  workspace_path: String,
+) -> Result<InFileReplaceResult, String> {
+  let options = options.unwrap_or_default();
+  let path = PathBuf::from(path);
+  let workspace_root = PathBuf::from(workspace_path);
+  // 预览可以读取参考资料文件夹，写回只允许工作区内的文件
+  let target = if options.preview && is_reference_path(&path) {
+    path
+  } else if is_reference_path(&path) {
+    return Err(format!("参考资料文件夹为只读: {}", path.display()));
+  } else if options.preview {
+    PathValidator::validate_workspace_path(&path, &workspace_root)
+      .map_err(|e| format!("{}: {}", e, path.display()))?
+  } else {
+    PathValidator::validate_workspace_write_target(&path, &workspace_root)
+      .map_err(|e| format!("{}: {}", e, path.display()))?
+  };
+  if encryption::is_encrypted_path(&target) {
+    return Err(format!("加密文件不支持文件内替换: {}", target.display()));
+  }
+
+  let result = {
+    let target = target.clone();
+    tokio::task::spawn_blocking(move || {
+      InFileSearchService::replace_in_file(&target, &query, &replacement, &options)
+    })
+    .await
+    .map_err(|e| format!("文件内替换任务异常: {}", e))??
+  };
+  if result.written {
+    match std::fs::read_to_string(&target) {
+      Ok(content) => {
+        open_file_tracker::update_snapshot(&target, &content);
+        refresh_index_after_save(&workspace_root, &target, Some(content));
+      }
+      Err(_) => refresh_index_after_save(&workspace_root, &target, None),
+    }
+    check_anchors_after_save(&workspace_root, &target);
+  }
+  Ok(result)
+}
+
 #[tauri::command]
 pub async fn index_document(
   file_path: String,
@@ -46,69 +246,117 @@ pub async fn remove_document_index(
     .map_err(|e| format!("删除索引失败: {}", e))
 }
 
+/// 清理已索引但命中排除规则的文档（修改排除规则后调用），返回清理数量
+#[tauri::command]
+pub async fn purge_excluded_index(workspace_path: String) -> Result<usize, String> {
+  let workspace = PathBuf::from(workspace_path);
+  let service = SearchService::new(&workspace).map_err(|e| format!("初始化搜索服务失败: {}", e))?;
+
+  service
+    .purge_excluded_documents()
+    .map_err(|e| format!("清理排除索引失败: {}", e))
+}
+
 // ⚠️ Week 19.2：异步构建初始索引
+// 作为后台任务执行，返回任务 id，可用 cancel_job 取消
 #[tauri::command]
-pub async fn build_index_async(workspace_path: String) -> Result<(), String> {
+pub async fn build_index_async(workspace_path: String) -> Result<String, String> {
   let workspace = PathBuf::from(&workspace_path);
+  let name = workspace
+    .file_name()
+    .map(|n| n.to_string_lossy().to_string())
+    .unwrap_or_else(|| workspace_path.clone());
 
-  tokio::spawn(async move {
-    use std::fs;
-    use walkdir::WalkDir;
+  let id = job_service::jobs().submit(
+    JobSpec::new("build_index", format!("构建搜索索引：{}", name)).priority(JobPriority::Low),
+    move |ctx| async move {
+      tokio::task::spawn_blocking(move || {
+        let service =
+          SearchService::new(&workspace).map_err(|e| format!("初始化搜索服务失败: {}", e))?;
+        service.build_index(|count| {
+          if count % 50 == 0 {
+            ctx.set_progress(None, Some(format!("已索引 {} 个文件", count)));
+          }
+          !ctx.is_cancelled()
+        });
+        Ok(())
+      })
+      .await
+      .map_err(|e| format!("构建索引任务失败: {}", e))?
+    },
+  );
+  Ok(id)
+}
 
-    let service = match SearchService::new(&workspace) {
-      Ok(s) => s,
-      Err(e) => {
-        eprintln!("初始化搜索服务失败: {}", e);
-        return;
-      }
-    };
+#[cfg(test)]
+mod tests {
+  use super::*;
 
-    let mut updates = Vec::new();
-    let mut count = 0;
+  fn result(path: &str, rank: f64) -> SearchResult {
+    SearchResult {
+      path: path.to_string(),
+      title: path.to_string(),
+      snippet: String::new(),
+      rank,
+    }
+  }
 
-    println!("开始构建索引: {}", workspace.display());
+  fn workspace(path: &str, results: Vec<SearchResult>) -> (String, String, Vec<SearchResult>) {
+    (
+      path.to_string(),
+      path.trim_start_matches('/').to_string(),
+      results,
+    )
+  }
 
-    // 遍历所有文件
-    for entry in WalkDir::new(&workspace)
-      .follow_links(false)
-      .into_iter()
-      .filter_map(|e| e.ok())
-    {
-      let path = entry.path();
-      if path.is_file() {
-        // 检查是否需要索引
-        if let Ok(should_index) = service.should_index(path) {
-          if should_index {
-            // 读取文件内容
-            if let Ok(content) = fs::read_to_string(path) {
-              updates.push((path.to_path_buf(), content));
-              count += 1;
-
-              // 每 100 个文件批量提交一次
-              if updates.len() >= 100 {
-                if let Err(e) = service.batch_update_index(updates.clone()) {
-                  eprintln!("批量更新索引失败: {}", e);
-                }
-
-                println!("已索引 {} 个文件...", count);
-
-                updates.clear();
-              }
-            }
-          }
-        }
-      }
-    }
+  fn order(merged: &[WorkspaceSearchResult]) -> Vec<(&str, &str, f64)> {
+    merged
+      .iter()
+      .map(|r| (r.workspace_name.as_str(), r.result.path.as_str(), r.score))
+      .collect()
+  }
 
-    // 提交剩余的文件
-    if !updates.is_empty() {
-      if let Err(e) = service.batch_update_index(updates) {
-        eprintln!("批量更新索引失败: {}", e);
-      }
-    }
+  #[test]
+  fn normalizes_scores_against_the_best_result_across_workspaces() {
+    let mut merged = normalize_scores(vec![
+      workspace("/large", vec![result("a.md", -30.0), result("b.md", -12.0)]),
+      workspace("/small", vec![result("c.md", -15.0), result("d.md", -3.0)]),
+    ]);
+    merge_ranked_results(&mut merged, 3);
+    assert_eq!(
+      order(&merged),
+      vec![
+        ("large", "a.md", 1.0),
+        ("small", "c.md", 0.5),
+        ("large", "b.md", 0.4),
+      ]
+    );
+  }
 
-    println!("索引构建完成，共索引 {} 个文件", count);
-  });
+  #[test]
+  fn weak_only_workspace_does_not_outrank_strong_matches() {
+    let mut merged = normalize_scores(vec![
+      workspace(
+        "/strong",
+        vec![result("a.md", -20.0), result("b.md", -10.0)],
+      ),
+      workspace("/weak", vec![result("c.md", -2.0)]),
+    ]);
+    merge_ranked_results(&mut merged, 3);
+    assert_eq!(
+      order(&merged),
+      vec![
+        ("strong", "a.md", 1.0),
+        ("strong", "b.md", 0.5),
+        ("weak", "c.md", 0.1),
+      ]
+    );
+  }
 
-  Ok(())
+  #[test]
+  fn zero_ranks_score_as_fully_relevant() {
+    let merged = normalize_scores(vec![workspace("/ws", vec![result("a.md", 0.0)])]);
+    assert_eq!(merged[0].score, 1.0);
+    assert!(normalize_scores(vec![workspace("/ws", Vec::new())]).is_empty());
+  }
 }
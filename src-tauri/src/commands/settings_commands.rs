@@ -0,0 +1,69 @@
+use crate::services::settings_service::{self, AppSettings};
+use crate::services::workspace_settings::WorkspaceSettings;
+use crate::utils::logger::{LogEntry, LogFilter};
+use crate::utils::perf::PerformanceReport;
+use std::path::PathBuf;
+use tauri::{AppHandle, Emitter};
+
+/// 获取工作区设置（未配置时返回默认值）
+#[tauri::command]
+pub async fn get_workspace_settings(workspace_path: String) -> Result<WorkspaceSettings, String> {
+  WorkspaceSettings::load(&PathBuf::from(workspace_path))
+}
+
+/// 保存工作区设置
+#[tauri::command]
+pub async fn update_workspace_settings(
+  workspace_path: String,
+  settings: WorkspaceSettings,
+) -> Result<WorkspaceSettings, String> {
+  settings.save(&PathBuf::from(workspace_path))?;
+  Ok(settings)
+}
+
+/// 获取应用级设置（默认 AI 提供商、自动保存间隔、预览方式、代理、更新渠道）
+#[tauri::command]
+pub async fn get_settings() -> Result<AppSettings, String> {
+  Ok(settings_service::current())
+}
+
+/// 保存应用级设置，成功后发送 `app-settings-changed` 事件
+#[tauri::command]
+pub async fn update_settings(settings: AppSettings, app: AppHandle) -> Result<AppSettings, String> {
+  let saved = settings_service::service()?.update(settings)?;
+  if let Err(e) = app.emit("app-settings-changed", &saved) {
+    tracing::warn!("发送应用设置变更事件失败: {}", e);
+  }
+  Ok(saved)
+}
+
+/// 设置后端消息语言（BCP 47 标签，如 `en-US`、`ja`），返回生效的语言 `zh` / `en` / `ja`
+#[tauri::command]
+pub async fn set_locale(locale: String) -> Result<String, String> {
+  Ok(crate::utils::i18n::set_locale(&locale).as_str().to_string())
+}
+
+/// 设置日志级别（off / error / warn / info / debug / trace）；module 为空时设置整个应用，
+/// 否则只设置该模块，如 `services::pandoc_service`。返回生效的过滤规则
+#[tauri::command]
+pub async fn set_log_level(level: String, module: Option<String>) -> Result<String, String> {
+  crate::utils::logger::set_log_level(module.as_deref(), &level)
+}
+
+/// 最近的后端日志（按时间先后），默认最多 200 条；警告与错误另以 `log-event` 事件实时推送
+#[tauri::command]
+pub async fn get_recent_logs(
+  filter: Option<LogFilter>,
+  limit: Option<usize>,
+) -> Result<Vec<LogEntry>, String> {
+  crate::utils::logger::recent_logs(&filter.unwrap_or_default(), limit.unwrap_or(200))
+}
+
+/// 耗时统计：文件树构建、文档转换、索引构建、AI 调用等操作的次数与耗时分布，
+/// 附最近 50 条记录；operation 不为空时只统计名称包含该文字的操作
+#[tauri::command]
+pub async fn get_performance_report(
+  operation: Option<String>,
+) -> Result<PerformanceReport, String> {
+  crate::utils::perf::performance_report(operation.as_deref())
+}
@@ -0,0 +1,51 @@
+use crate::services::snippets::{self, ExpandedSnippet, Snippet, SnippetContext};
+use std::path::PathBuf;
+
+/// 列出工作区的文本片段
+#[tauri::command]
+pub async fn list_snippets(workspace_path: String) -> Result<Vec<Snippet>, String> {
+  snippets::list_snippets(&PathBuf::from(workspace_path))
+}
+
+/// 按触发词读取文本片段（不展开）；不存在时返回 null
+#[tauri::command]
+pub async fn get_snippet(
+  workspace_path: String,
+  trigger: String,
+) -> Result<Option<Snippet>, String> {
+  snippets::get_snippet(&PathBuf::from(workspace_path), &trigger)
+}
+
+/// 新增或更新文本片段；修改触发词时传入 previous_trigger
+#[tauri::command]
+pub async fn save_snippet(
+  workspace_path: String,
+  snippet: Snippet,
+  previous_trigger: Option<String>,
+) -> Result<Snippet, String> {
+  snippets::save_snippet(
+    &PathBuf::from(workspace_path),
+    snippet,
+    previous_trigger.as_deref(),
+  )
+}
+
+/// 删除文本片段，返回是否存在
+#[tauri::command]
+pub async fn delete_snippet(workspace_path: String, trigger: String) -> Result<bool, String> {
+  snippets::delete_snippet(&PathBuf::from(workspace_path), &trigger)
+}
+
+/// 按触发词展开文本片段；触发词不存在时返回 null
+#[tauri::command]
+pub async fn expand_snippet(
+  workspace_path: String,
+  trigger: String,
+  context: Option<SnippetContext>,
+) -> Result<Option<ExpandedSnippet>, String> {
+  snippets::expand_snippet(
+    &PathBuf::from(workspace_path),
+    &trigger,
+    &context.unwrap_or_default(),
+  )
+}
@@ -0,0 +1,55 @@
+use crate::services::spellcheck_service::{self, Misspelling, SpellcheckService};
+use std::path::PathBuf;
+
+/// 检查拼写检查（Hunspell）是否可用，以及已安装的词典与工作区使用的语言
+#[tauri::command]
+pub async fn check_spellcheck_available(
+  workspace_path: Option<String>,
+) -> Result<serde_json::Value, String> {
+  tokio::task::spawn_blocking(move || {
+    let workspace = workspace_path.map(PathBuf::from);
+    let service = SpellcheckService::new(workspace.as_deref());
+    serde_json::json!({
+        "available": service.is_available(),
+        "path": service.get_path().map(|p| p.to_string_lossy().to_string()),
+        "languages": service.available_languages(),
+        "language": service.resolve_language(None).ok(),
+        "user_words": service.user_words(),
+    })
+  })
+  .await
+  .map_err(|e| format!("检查拼写检查失败: {}", e))
+}
+
+/// 检查文本拼写；未指定 lang 时使用工作区设置的语言
+#[tauri::command]
+pub async fn check_spelling(
+  text: String,
+  lang: Option<String>,
+  workspace_path: Option<String>,
+) -> Result<Vec<Misspelling>, String> {
+  tokio::task::spawn_blocking(move || {
+    let workspace = workspace_path.map(PathBuf::from);
+    SpellcheckService::new(workspace.as_deref()).check_text(&text, lang.as_deref())
+  })
+  .await
+  .map_err(|e| format!("拼写检查失败: {}", e))?
+}
+
+/// 将词加入工作区用户词典
+#[tauri::command]
+pub async fn add_spellcheck_word(workspace_path: String, word: String) -> Result<(), String> {
+  SpellcheckService::new(Some(&PathBuf::from(workspace_path))).add_word(&word)
+}
+
+/// 从工作区用户词典中移除词
+#[tauri::command]
+pub async fn remove_spellcheck_word(workspace_path: String, word: String) -> Result<bool, String> {
+  SpellcheckService::new(Some(&PathBuf::from(workspace_path))).remove_word(&word)
+}
+
+/// 本次运行内忽略该词
+#[tauri::command]
+pub async fn ignore_spellcheck_word(word: String) -> Result<(), String> {
+  spellcheck_service::ignore_word(&word)
+}
@@ -0,0 +1,40 @@
+use crate::services::telemetry::{self, TelemetryPayload, TelemetrySettings};
+
+/// 使用统计设置（默认关闭）
+#[tauri::command]
+pub async fn get_telemetry_settings() -> Result<TelemetrySettings, String> {
+  telemetry::with_telemetry(|t| t.settings())
+}
+
+/// 开启 / 关闭使用统计；关闭时清除已记录的数据
+#[tauri::command]
+pub async fn update_telemetry_settings(
+  settings: TelemetrySettings,
+) -> Result<TelemetrySettings, String> {
+  telemetry::with_telemetry(|t| t.set_settings(settings.clone()))??;
+  Ok(settings)
+}
+
+/// 下次上传的完整内容
+#[tauri::command]
+pub async fn preview_telemetry_payload() -> Result<TelemetryPayload, String> {
+  telemetry::with_telemetry(|t| t.payload())
+}
+
+/// 立即上传；返回是否有数据被发送
+#[tauri::command]
+pub async fn upload_telemetry() -> Result<bool, String> {
+  telemetry::upload(true).await
+}
+
+/// 清除本地记录的使用统计和崩溃信息
+#[tauri::command]
+pub async fn purge_telemetry_data() -> Result<(), String> {
+  telemetry::with_telemetry(|t| t.purge())?
+}
+
+/// 前端记录功能使用（未开启统计时忽略）
+#[tauri::command]
+pub async fn record_feature_usage(feature: String) -> Result<(), String> {
+  telemetry::with_telemetry(|t| t.record_feature(&feature))?
+}
@@ -1,3 +1,4 @@
+use crate::services::agent_transaction::{self, AgentUndoResult};
 use crate::services::tool_service::{ToolCall, ToolResult, ToolService};
 use std::path::PathBuf;
 use tauri::{AppHandle, Emitter};
@@ -13,7 +14,15 @@ fn should_emit_file_tree_refresh(tool_call: &ToolCall, result: &ToolResult) -> b
     && !awaiting_confirmation
     && matches!(
       tool_call.name.as_str(),
-      "create_file" | "create_folder" | "delete_file" | "rename_file" | "move_file" | "update_file"
+      "create_file"
+        | "create_folder"
+        | "delete_file"
+        | "rename_file"
+        | "move_file"
+        | "copy_file"
+        | "create_docx_from_markdown"
+        | "update_file"
+        | "apply_patch"
     )
 }
 
@@ -33,6 +42,27 @@ pub async fn execute_tool(
   Ok(result)
 }
 
+/// 用户在确认卡片上批准或拒绝待确认的工具调用；`confirmation_id` 为后端签发的一次性记录 ID
+#[tauri::command]
+pub async fn resolve_tool_confirmation(
+  tool_call: ToolCall,
+  workspace_path: String,
+  confirmation_id: String,
+  approved: bool,
+  app: AppHandle,
+) -> Result<ToolResult, String> {
+  let service = ToolService::new();
+  let ws_path = PathBuf::from(workspace_path);
+
+  let result = service
+    .resolve_confirmation(&tool_call, &ws_path, &confirmation_id, approved)
+    .await?;
+  if should_emit_file_tree_refresh(&tool_call, &result) {
+    let _ = app.emit("file-tree-changed", ws_path.to_string_lossy().to_string());
+  }
+  Ok(result)
+}
+
 #[tauri::command]
 pub async fn execute_tool_with_retry(
   tool_call: ToolCall,
@@ -87,6 +117,29 @@ pub async fn execute_tool_with_retry(
   })
 }
 
+/// 撤销最近一轮对话中 AI 工具对文件的修改；文件在 AI 修改后又有改动时返回错误，
+/// 用户确认后传 `force = true` 覆盖
+#[tauri::command]
+pub async fn undo_last_agent_transaction(
+  workspace_path: String,
+  force: Option<bool>,
+  app: AppHandle,
+) -> Result<AgentUndoResult, String> {
+  let ws_path = PathBuf::from(workspace_path);
+  let result = {
+    let ws_path = ws_path.clone();
+    tokio::task::spawn_blocking(move || {
+      agent_transaction::undo_last_transaction(&ws_path, force.unwrap_or(false))
+    })
+    .await
+    .map_err(|e| format!("撤销任务异常: {}", e))??
+  };
+  if !result.restored_paths.is_empty() {
+    let _ = app.emit("file-tree-changed", ws_path.to_string_lossy().to_string());
+  }
+  Ok(result)
+}
+
 fn is_retriable_error(error: &Option<String>) -> bool {
   if let Some(err) = error {
     let retriable_messages = ["网络错误", "权限不足", "文件被锁定", "超时", "临时"];
@@ -0,0 +1,20 @@
+use crate::services::settings_service::{self, UpdateChannel};
+use crate::services::update_service::{self, UpdateInfo};
+use tauri::AppHandle;
+
+/// 检查更新（未指定渠道时使用应用设置中的渠道），有新版本时返回版本与发布说明并开始后台下载；
+/// 下载完成后发送 `update-ready` 事件
+#[tauri::command]
+pub async fn check_for_updates(
+  channel: Option<UpdateChannel>,
+  app: AppHandle,
+) -> Result<Option<UpdateInfo>, String> {
+  let channel = channel.unwrap_or_else(|| settings_service::current().update_channel);
+  update_service::check_and_download(&app, channel).await
+}
+
+/// 安装已下载的更新并重启应用
+#[tauri::command]
+pub async fn install_update(app: AppHandle) -> Result<(), String> {
+  update_service::install_and_restart(&app)
+}
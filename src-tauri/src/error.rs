@@ -0,0 +1,281 @@
+//! 命令统一错误类型：序列化为 `{ code, message, details }`，前端按 `code` 分支处理，`message` 直接展示。
+//!
+//! 错误码保持稳定，新增错误时只能追加，不能修改已有的码。尚未细分的错误（多为服务层返回的 `String`）
+//! 统一为 `UNKNOWN`。
+
+use crate::services::ai_error::AIError;
+use crate::services::encryption::EncryptionError;
+use crate::utils::i18n::{self, Locale};
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+
+#[derive(Debug, thiserror::Error)]
+pub enum AppError {
+  #[error("文件不存在: {0}")]
+  NotFound(String),
+  #[error("文件已存在: {0}")]
+  AlreadyExists(String),
+  #[error("没有权限访问: {0}")]
+  PermissionDenied(String),
+  #[error("参考资料文件夹为只读，不能修改: {0}")]
+  ReadOnly(String),
+  #[error("{0}")]
+  InvalidInput(String),
+  #[error("{context}: {source}")]
+  Io {
+    context: String,
+    #[source]
+    source: std::io::Error,
+  },
+  #[error("Pandoc 不可用，请安装 Pandoc 或确保内置 Pandoc 可用。\n访问 https://pandoc.org/installing.html 获取安装指南。")]
+  PandocUnavailable,
+  /// 附带检测时给出的原因
+  #[error("{0}")]
+  LibreOfficeUnavailable(String),
+  #[error("{tool} 转换失败: {message}")]
+  ConversionFailed { tool: &'static str, message: String },
+  #[error("磁盘空间不足: {path} 所在磁盘需要 {} 可用空间，当前仅剩 {}。请清理磁盘或预览缓存后重试。", megabytes(.required), megabytes(.available))]
+  InsufficientDiskSpace {
+    path: String,
+    required: u64,
+    available: u64,
+  },
+  #[error("未配置 AI 提供商，请先设置 API 密钥")]
+  AiNotConfigured,
+  #[error(transparent)]
+  Ai(#[from] AIError),
+  #[error(transparent)]
+  Encryption(#[from] EncryptionError),
+  #[error("{0}")]
+  Other(String),
+}
+
+impl AppError {
+  pub fn io(context: impl Into<String>, source: std::io::Error) -> Self {
+    AppError::Io {
+      context: context.into(),
+      source,
+    }
+  }
+
+  pub fn code(&self) -> &'static str {
+    match self {
+      AppError::NotFound(_) => "FILE_NOT_FOUND",
+      AppError::AlreadyExists(_) => "FILE_EXISTS",
+      AppError::PermissionDenied(_) => "PERMISSION_DENIED",
+      AppError::ReadOnly(_) => "READ_ONLY",
+      AppError::InvalidInput(_) => "INVALID_INPUT",
+      AppError::Io { source, .. } => match source.kind() {
+        std::io::ErrorKind::NotFound => "FILE_NOT_FOUND",
+        std::io::ErrorKind::AlreadyExists => "FILE_EXISTS",
+        std::io::ErrorKind::PermissionDenied => "PERMISSION_DENIED",
+        _ => "IO_ERROR",
+      },
+      AppError::PandocUnavailable => "PANDOC_UNAVAILABLE",
+      AppError::LibreOfficeUnavailable(_) => "LIBREOFFICE_UNAVAILABLE",
+      AppError::ConversionFailed { .. } => "CONVERSION_FAILED",
+      AppError::InsufficientDiskSpace { .. } => "INSUFFICIENT_DISK_SPACE",
+      AppError::AiNotConfigured => "AI_NOT_CONFIGURED",
+      AppError::Ai(e) => match e {
+        AIError::NetworkError(_) => "AI_NETWORK_ERROR",
+        AIError::RateLimit { .. } => "AI_RATE_LIMITED",
+        AIError::ModelUnavailable => "AI_MODEL_UNAVAILABLE",
+        AIError::ContextTooLong => "AI_CONTEXT_TOO_LONG",
+        AIError::Timeout => "AI_TIMEOUT",
+        AIError::Cancelled => "AI_CANCELLED",
+        AIError::Unknown(_) => "AI_ERROR",
+      },
+      AppError::Encryption(e) => match e {
+        EncryptionError::Locked(_) => "ENCRYPTED_FILE_LOCKED",
+        EncryptionError::WrongPassphrase => "WRONG_PASSPHRASE",
+        EncryptionError::InvalidContainer(_) => "INVALID_ENCRYPTED_FILE",
+        EncryptionError::Failed(_) => "ENCRYPTION_FAILED",
+      },
+      AppError::Other(_) => "UNKNOWN",
+    }
+  }
+
+  /// 按当前语言（`set_locale`）返回给前端的消息；`Display` 保持中文用于日志
+  pub fn localized_message(&self) -> String {
+    self.message_in(i18n::current_locale())
+  }
+
+  fn message_in(&self, locale: Locale) -> String {
+    let tr = |key: &str, args: &[(&str, &str)]| i18n::translate(locale, key, args);
+    match self {
+      AppError::NotFound(path) => tr("error.not_found", &[("path", path)]),
+      AppError::AlreadyExists(path) => tr("error.already_exists", &[("path", path)]),
+      AppError::PermissionDenied(path) => tr("error.permission_denied", &[("path", path)]),
+      AppError::ReadOnly(path) => tr("error.read_only", &[("path", path)]),
+      AppError::PandocUnavailable => tr("error.pandoc_unavailable", &[]),
+      AppError::LibreOfficeUnavailable(reason) => {
+        tr("error.libreoffice_unavailable", &[("reason", reason)])
+      }
+      AppError::ConversionFailed { tool, message } => tr(
+        "error.conversion_failed",
+        &[("tool", tool), ("message", message)],
+      ),
+      AppError::InsufficientDiskSpace {
+        path,
+        required,
+        available,
+      } => tr(
+        "error.insufficient_disk_space",
+        &[
+          ("path", path),
+          ("required", &megabytes(required)),
+          ("available", &megabytes(available)),
+        ],
+      ),
+      AppError::AiNotConfigured => tr("error.ai_not_configured", &[]),
+      AppError::Ai(e) => match e {
+        AIError::NetworkError(detail) => tr("error.ai_network", &[("detail", detail)]),
+        AIError::RateLimit { retry_after } => tr(
+          "error.ai_rate_limited",
+          &[("seconds", &retry_after.to_string())],
+        ),
+        AIError::ModelUnavailable => tr("error.ai_model_unavailable", &[]),
+        AIError::ContextTooLong => tr("error.ai_context_too_long", &[]),
+        AIError::Timeout => tr("error.ai_timeout", &[]),
+        AIError::Cancelled => tr("error.ai_cancelled", &[]),
+        AIError::Unknown(detail) => tr("error.ai_unknown", &[("detail", detail)]),
+      },
+      AppError::Encryption(EncryptionError::Locked(path)) => {
+        tr("error.encrypted_file_locked", &[("path", path)])
+      }
+      AppError::Encryption(EncryptionError::WrongPassphrase) => tr("error.wrong_passphrase", &[]),
+      AppError::InvalidInput(_)
+      | AppError::Io { .. }
+      | AppError::Encryption(_)
+      | AppError::Other(_) => self.to_string(),
+    }
+  }
+
+  fn details(&self) -> Option<serde_json::Value> {
+    match self {
+      AppError::NotFound(path)
+      | AppError::AlreadyExists(path)
+      | AppError::PermissionDenied(path)
+      | AppError::ReadOnly(path) => Some(serde_json::json!({ "path": path })),
+      AppError::ConversionFailed { tool, .. } => Some(serde_json::json!({ "tool": tool })),
+      AppError::Encryption(EncryptionError::Locked(path)) => {
+        Some(serde_json::json!({ "path": path }))
+      }
+      AppError::InsufficientDiskSpace {
+        path,
+        required,
+        available,
+      } => Some(serde_json::json!({
+        "path": path,
+        "required_bytes": required,
+        "available_bytes": available,
+      })),
+      AppError::Ai(e) => e
+        .retry_after()
+        .map(|secs| serde_json::json!({ "retry_after": secs })),
+      _ => None,
+    }
+  }
+}
+
+fn megabytes(bytes: &u64) -> String {
+  format!("{:.1} MB", *bytes as f64 / (1024.0 * 1024.0))
+}
+
+impl Serialize for AppError {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    let mut state = serializer.serialize_struct("AppError", 3)?;
+    state.serialize_field("code", self.code())?;
+    state.serialize_field("message", &self.localized_message())?;
+    state.serialize_field("details", &self.details())?;
+    state.end()
+  }
+}
+
+impl From<String> for AppError {
+  fn from(message: String) -> Self {
+    AppError::Other(message)
+  }
+}
+
+impl From<&str> for AppError {
+  fn from(message: &str) -> Self {
+    AppError::Other(message.to_string())
+  }
+}
+
+/// 仍返回 `String` 的函数可以直接用 `?` 传递 `AppError`
+impl From<AppError> for String {
+  fn from(error: AppError) -> Self {
+    error.to_string()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn serializes_stable_code_and_message() {
+    let value = serde_json::to_value(AppError::NotFound("/ws/a.md".to_string())).unwrap();
+    assert_eq!(
+      value,
+      serde_json::json!({
+        "code": "FILE_NOT_FOUND",
+        "message": "文件不存在: /ws/a.md",
+        "details": { "path": "/ws/a.md" },
+      })
+    );
+
+    let denied = AppError::io(
+      "写入文件失败",
+      std::io::Error::from(std::io::ErrorKind::PermissionDenied),
+    );
+    assert_eq!(denied.code(), "PERMISSION_DENIED");
+    assert!(denied.to_string().starts_with("写入文件失败: "));
+
+    let limited =
+      serde_json::to_value(AppError::from(AIError::RateLimit { retry_after: 30 })).unwrap();
+    assert_eq!(limited["code"], "AI_RATE_LIMITED");
+    assert_eq!(limited["details"]["retry_after"], 30);
+
+    let legacy: AppError = format!("搜索失败: {}", "x").into();
+    assert_eq!(
+      (legacy.code(), legacy.to_string().as_str()),
+      ("UNKNOWN", "搜索失败: x")
+    );
+    assert_eq!(String::from(AppError::PandocUnavailable).lines().count(), 2);
+  }
+
+  #[test]
+  fn localized_messages_match_display_in_chinese() {
+    let errors = [
+      AppError::NotFound("/ws/a.md".to_string()),
+      AppError::ReadOnly("/ws/参考资料/a.md".to_string()),
+      AppError::PandocUnavailable,
+      AppError::ConversionFailed {
+        tool: "Pandoc",
+        message: "exit 1".to_string(),
+      },
+      AppError::AiNotConfigured,
+      AIError::RateLimit { retry_after: 30 }.into(),
+      AIError::Timeout.into(),
+      AppError::InsufficientDiskSpace {
+        path: "/cache".to_string(),
+        required: 20 * 1024 * 1024,
+        available: 1536 * 1024,
+      },
+    ];
+    for error in &errors {
+      assert_eq!(error.message_in(Locale::Zh), error.to_string());
+    }
+    assert_eq!(
+      errors[5].message_in(Locale::En),
+      "Rate limited. Please retry in 30 seconds."
+    );
+    assert_eq!(
+      AppError::InvalidInput("x".to_string()).message_in(Locale::Ja),
+      "x"
+    );
+  }
+}
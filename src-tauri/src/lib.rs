@@ -0,0 +1,317 @@
+pub mod cli;
+mod commands;
+mod error;
+mod models;
+mod services;
+mod utils;
+mod workspace;
+
+use services::ai_service::AIService;
+use services::chat_stream_registry::ChatStreamRegistry;
+use services::file_watcher::FileWatcherHandle;
+use std::sync::Arc;
+use tauri::Manager;
+
+/// 供 benches/ 调用的内部实现，不属于公开接口
+#[doc(hidden)]
+pub mod bench_support {
+  pub use crate::services::pandoc_service::PandocService;
+}
+
+/// 启动桌面应用；命令行工具见 [`cli`]（`binder-cli` 可执行文件）
+pub fn run() {
+  tauri::Builder::default()
+    .plugin(tauri_plugin_dialog::init())
+    .plugin(tauri_plugin_updater::Builder::new().build())
+    .manage(FileWatcherHandle::spawn())
+    .manage(Arc::new(ChatStreamRegistry::default()))
+    .setup(|app| {
+      // 日志写入应用日志目录，打包后也能查看
+      match app.path().app_log_dir() {
+        Ok(log_dir) => utils::logger::init_logger(&log_dir)
+          .unwrap_or_else(|e| eprintln!("初始化日志失败: {}", e)),
+        Err(e) => eprintln!("获取日志目录失败: {}", e),
+      }
+      utils::logger::attach_app_handle(app.handle().clone());
+      services::job_service::init(app.handle().clone());
+      match app.path().app_config_dir() {
+        Ok(config_dir) => services::settings_service::init(&config_dir),
+        Err(e) => tracing::warn!("获取应用配置目录失败，使用默认设置: {}", e),
+      }
+      match app.path().app_cache_dir() {
+        Ok(cache_dir) => {
+          services::temp_service::init(&cache_dir);
+          services::temp_service::spawn_expiry_sweep();
+        }
+        Err(e) => tracing::warn!("获取应用缓存目录失败，临时文件索引不持久化: {}", e),
+      }
+      services::telemetry::install_panic_hook();
+      tauri::async_runtime::spawn(services::telemetry::upload_if_due());
+      services::update_service::spawn_startup_check(app.handle().clone());
+
+      // 初始化 AI 服务
+      let ai_service = Arc::new(AIService::new().unwrap_or_else(|e| {
+        tracing::warn!("初始化 AI 服务失败: {}，使用默认配置", e);
+        // 尝试使用默认配置创建服务
+        AIService::new().unwrap_or_else(|_| {
+          tracing::error!("无法创建 AI 服务，某些功能可能不可用");
+          panic!("AI 服务初始化失败")
+        })
+      }));
+      app.manage(ai_service.clone());
+
+      // 代理变更后重建 AI 提供商，使新的 HTTP 客户端生效
+      if let Ok(settings) = services::settings_service::service() {
+        let mut changes = settings.subscribe();
+        tauri::async_runtime::spawn(async move {
+          let mut proxy = changes.borrow_and_update().proxy.clone();
+          while changes.changed().await.is_ok() {
+            let current = changes.borrow_and_update().proxy.clone();
+            if current == proxy {
+              continue;
+            }
+            proxy = current;
+            ai_service.reload_providers();
+            tracing::info!("代理设置已变更，AI 提供商已重建");
+          }
+        });
+      }
+
+      // 确保窗口显示
+      if let Some(window) = app.get_webview_window("main") {
+        window.show().unwrap_or_else(|e| {
+          tracing::warn!("显示窗口失败: {}", e);
+        });
+        window.set_focus().unwrap_or_else(|e| {
+          tracing::warn!("聚焦窗口失败: {}", e);
+        });
+
+        // 默认不自动打开开发者工具，需要时可手动打开（如 F12 或右键）
+      } else {
+        tracing::warn!("无法获取主窗口");
+      }
+      Ok(())
+    })
+    .invoke_handler(tauri::generate_handler![
+      commands::file_commands::build_file_tree,
+      commands::file_commands::read_file_content,
+      commands::file_commands::read_file_as_base64,
+      commands::file_commands::write_file,
+      commands::file_commands::encrypt_file,
+      commands::file_commands::decrypt_file,
+      commands::file_commands::unlock_encrypted_file,
+      commands::file_commands::lock_encrypted_files,
+      commands::file_commands::is_encrypted_file_unlocked,
+      commands::file_commands::create_file,
+      commands::file_commands::create_file_from_template,
+      commands::file_commands::create_folder,
+      commands::file_commands::open_workspace_dialog,
+      commands::file_commands::load_workspaces,
+      commands::file_commands::open_workspace,
+      commands::file_commands::list_reference_folders,
+      commands::file_commands::pause_watcher,
+      commands::file_commands::resume_watcher,
+      commands::file_commands::get_watcher_status,
+      commands::file_commands::track_open_file,
+      commands::file_commands::untrack_open_file,
+      commands::file_commands::check_external_modification,
+      commands::file_commands::get_file_modified_time,
+      commands::file_commands::get_file_size,
+      commands::file_commands::move_file_to_workspace,
+      commands::file_commands::move_file,
+      commands::file_commands::rename_file,
+      commands::file_commands::delete_file,
+      commands::file_commands::duplicate_file,
+      commands::file_commands::check_pandoc_available,
+      commands::file_commands::check_libreoffice_available,
+      commands::file_commands::install_libreoffice,
+      commands::file_commands::open_docx_for_edit,
+      commands::file_commands::preview_office_document,
+      commands::file_commands::get_pdf_page_count,
+      commands::file_commands::render_pdf_page,
+      commands::file_commands::extract_pdf_text,
+      commands::file_commands::count_words,
+      commands::file_commands::analyze_readability,
+      commands::file_commands::format_markdown,
+      commands::file_commands::list_footnotes,
+      commands::file_commands::add_footnote,
+      commands::file_commands::renumber_footnotes,
+      commands::file_commands::delete_footnote,
+      commands::file_commands::diff_documents,
+      commands::file_commands::merge_documents,
+      commands::file_commands::split_document,
+      commands::file_commands::preview_docx_as_pdf,
+      commands::file_commands::preview_excel_as_pdf,
+      commands::file_commands::preview_presentation_as_pdf,
+      commands::file_commands::create_draft_docx,
+      commands::file_commands::create_draft_file,
+      commands::file_commands::save_docx,
+      commands::file_commands::list_folder_files,
+      commands::file_commands::save_external_file,
+      commands::file_commands::cleanup_temp_files,
+      commands::file_commands::cleanup_expired_temp_files,
+      commands::file_commands::cleanup_all_temp_files,
+      commands::file_commands::list_temp_files,
+      commands::file_commands::record_binder_file,
+      commands::file_commands::get_binder_file_source,
+      commands::file_commands::remove_binder_file_record,
+      commands::file_commands::get_preview_cache_info,
+      commands::file_commands::clear_preview_cache,
+      commands::image_commands::insert_image,
+      commands::image_commands::check_image_exists,
+      commands::image_commands::delete_image,
+      commands::image_commands::save_chat_image,
+      commands::image_commands::strip_image_metadata,
+      commands::image_commands::convert_workspace_images,
+      commands::image_commands::dedupe_images,
+      commands::ocr_commands::check_ocr_available,
+      commands::ocr_commands::ocr_image,
+      commands::print_commands::print_document,
+      commands::spellcheck_commands::check_spellcheck_available,
+      commands::spellcheck_commands::check_spelling,
+      commands::spellcheck_commands::add_spellcheck_word,
+      commands::spellcheck_commands::remove_spellcheck_word,
+      commands::spellcheck_commands::ignore_spellcheck_word,
+      commands::link_commands::get_backlinks,
+      commands::link_commands::get_link_graph,
+      commands::link_commands::resolve_wikilink,
+      commands::snippet_commands::list_snippets,
+      commands::snippet_commands::get_snippet,
+      commands::snippet_commands::save_snippet,
+      commands::snippet_commands::delete_snippet,
+      commands::snippet_commands::expand_snippet,
+      commands::prompt_commands::list_prompts,
+      commands::prompt_commands::create_prompt,
+      commands::prompt_commands::update_prompt,
+      commands::prompt_commands::delete_prompt,
+      commands::prompt_commands::render_prompt,
+      commands::ai_commands::ai_autocomplete,
+      commands::ai_commands::ai_inline_assist,
+      commands::ai_commands::ai_chat_stream,
+      commands::ai_commands::chat_build_generate_outline,
+      commands::positioning_snapshot::positioning_submit_editor_snapshot,
+      commands::ai_commands::ai_save_api_key,
+      commands::ai_commands::ai_get_api_key,
+      commands::ai_commands::ai_cancel_request,
+      commands::ai_commands::ai_cancel_chat_stream,
+      commands::ai_commands::ai_analyze_document,
+      commands::ai_commands::analyze_scanned_pdf,
+      commands::ai_commands::ai_proofread,
+      commands::ai_commands::ai_translate,
+      commands::ai_commands::ai_summarize_folder,
+      commands::ai_commands::ai_suggest_filename,
+      commands::glossary_commands::list_glossaries,
+      commands::glossary_commands::save_glossary,
+      commands::glossary_commands::delete_glossary,
+      commands::search_commands::search_documents,
+      commands::search_commands::search_all_workspaces,
+      commands::search_commands::search_in_file,
+      commands::search_commands::replace_in_file,
+      commands::search_commands::index_document,
+      commands::search_commands::remove_document_index,
+      commands::search_commands::build_index_async,
+      commands::search_commands::purge_excluded_index,
+      commands::settings_commands::get_workspace_settings,
+      commands::settings_commands::update_workspace_settings,
+      commands::plugin_commands::list_plugins,
+      commands::plugin_commands::get_plugins_dir,
+      commands::plugin_commands::reload_plugins,
+      commands::plugin_commands::convert_with_plugin,
+      commands::settings_commands::get_settings,
+      commands::settings_commands::update_settings,
+      commands::settings_commands::set_locale,
+      commands::settings_commands::set_log_level,
+      commands::settings_commands::get_recent_logs,
+      commands::settings_commands::get_performance_report,
+      commands::telemetry_commands::get_telemetry_settings,
+      commands::telemetry_commands::update_telemetry_settings,
+      commands::telemetry_commands::preview_telemetry_payload,
+      commands::telemetry_commands::upload_telemetry,
+      commands::telemetry_commands::purge_telemetry_data,
+      commands::telemetry_commands::record_feature_usage,
+      commands::job_commands::list_jobs,
+      commands::job_commands::cancel_job,
+      commands::job_commands::clear_finished_jobs,
+      commands::journal_commands::journal_buffer,
+      commands::journal_commands::discard_buffer_journal,
+      commands::journal_commands::list_recoverable_buffers,
+      commands::update_commands::check_for_updates,
+      commands::update_commands::install_update,
+      commands::memory_commands::mark_orphan_tab_memories_stale,
+      commands::memory_commands::search_memories_cmd,
+      commands::memory_commands::search_memories_semantic,
+      commands::memory_commands::on_tab_deleted_cmd,
+      commands::memory_commands::startup_memory_maintenance,
+      commands::memory_commands::add_memory,
+      commands::memory_commands::expire_memory_item,
+      commands::memory_commands::update_memory,
+      commands::memory_commands::export_memories,
+      commands::memory_commands::import_memories,
+      commands::memory_commands::dedupe_memories,
+      commands::memory_commands::anchor_memory,
+      commands::memory_commands::resolve_memory_anchors,
+      commands::memory_commands::check_memory_consistency,
+      commands::memory_commands::get_memory_stats,
+      commands::memory_commands::retag_memories,
+      commands::memory_commands::get_document_memories,
+      commands::memory_commands::expire_memory_layer,
+      commands::memory_commands::get_memory_user_data,
+      commands::knowledge_commands::ingest_knowledge_document,
+      commands::knowledge_commands::replace_knowledge_document,
+      commands::knowledge_commands::upsert_workspace_snapshot_to_knowledge,
+      commands::knowledge_commands::delete_knowledge_entry,
+      commands::knowledge_commands::rename_knowledge_entry,
+      commands::knowledge_commands::move_knowledge_entry,
+      commands::knowledge_commands::query_knowledge_base,
+      commands::knowledge_commands::rebuild_knowledge_entry,
+      commands::knowledge_commands::retry_knowledge_entry,
+      commands::knowledge_commands::update_knowledge_verification,
+      commands::knowledge_commands::update_knowledge_entry_policy,
+      commands::knowledge_commands::list_knowledge_entries,
+      commands::classifier_commands::classify_files,
+      commands::classifier_commands::organize_files,
+      commands::classifier_commands::apply_organize_plan,
+      commands::classifier_commands::undo_last_organize,
+      commands::classifier_commands::cancel_file_organize,
+      commands::tool_commands::execute_tool,
+      commands::tool_commands::resolve_tool_confirmation,
+      commands::tool_commands::execute_tool_with_retry,
+      commands::tool_commands::undo_last_agent_transaction,
+      commands::template_commands::create_workflow_template,
+      commands::template_commands::list_workflow_templates,
+      commands::template_commands::load_workflow_template,
+      commands::template_commands::save_workflow_template_document,
+      commands::template_commands::update_workflow_template_status,
+      commands::template_commands::parse_workflow_template,
+      commands::template_commands::compile_workflow_template,
+      commands::template_commands::get_workflow_execution_runtime,
+      commands::template_commands::request_workflow_manual_intervention,
+      commands::template_commands::resume_workflow_execution,
+      commands::template_commands::mark_current_workflow_step_failed,
+      commands::template_commands::advance_workflow_execution_step,
+      workspace::workspace_commands::open_file_with_cache,
+      workspace::workspace_commands::open_docx_with_cache,
+      workspace::workspace_commands::ai_edit_file_with_diff,
+      workspace::workspace_commands::accept_file_diffs,
+      workspace::workspace_commands::reject_file_diffs,
+      workspace::workspace_commands::sync_workspace_file_cache_after_save,
+      workspace::workspace_commands::record_saved_file_timeline_node,
+      workspace::workspace_commands::list_timeline_nodes,
+      workspace::workspace_commands::get_timeline_restore_preview,
+      workspace::workspace_commands::restore_timeline_node,
+      workspace::workspace_commands::get_file_dependencies,
+      workspace::workspace_commands::save_file_dependency,
+      workspace::workspace_commands::upsert_agent_task,
+      workspace::workspace_commands::update_agent_task_stage,
+      workspace::workspace_commands::get_agent_tasks_for_chat_tab,
+      workspace::workspace_commands::upsert_agent_artifact,
+      workspace::workspace_commands::get_agent_artifacts_for_task,
+    ])
+    .build(tauri::generate_context!())
+    .expect("error while building tauri application")
+    .run(|_app, event| {
+      if let tauri::RunEvent::Exit = event {
+        services::temp_service::cleanup_session();
+      }
+    });
+}
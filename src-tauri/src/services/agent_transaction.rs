@@ -0,0 +1,554 @@
+//! AI 文件操作事务：同一轮对话中由工具产生的文件改动归入一个事务，
+//! 执行前快照被修改的路径，撤销时按相反顺序恢复。
+//!
+//! 事务只保存在内存中，按工作区分别记录最近若干轮，快照总大小超过上限时淘汰最早的事务。
+//! 同时记录工具写入后的内容摘要，撤销前发现文件又被改动时拒绝覆盖（除非调用方确认强制撤销）。
+//! run_command 与自定义工具的副作用无法快照，只记录工具名供撤销时提示。
+
+use crate::services::tool_service::ToolCall;
+use crate::utils::path_validator::PathValidator;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// 每个工作区保留的事务数
+const MAX_TRANSACTIONS_PER_WORKSPACE: usize = 20;
+/// 单个路径快照的大小上限，超过则不再快照（撤销时提示无法恢复）
+const MAX_SNAPSHOT_BYTES: u64 = 50 * 1024 * 1024;
+/// 每个工作区全部事务快照的总大小上限
+const MAX_JOURNAL_BYTES: usize = 100 * 1024 * 1024;
+
+static JOURNALS: Lazy<Mutex<HashMap<PathBuf, Vec<AgentTransaction>>>> =
+  Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// 路径在修改前的状态
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum PathState {
+  Missing,
+  File(Vec<u8>),
+  /// 目录及其下全部内容（相对路径 -> 文件内容，None 表示子目录），按路径排序
+  Dir(Vec<(PathBuf, Option<Vec<u8>>)>),
+}
+
+impl PathState {
+  fn size(&self) -> usize {
+    match self {
+      PathState::Missing => 0,
+      PathState::File(bytes) => bytes.len(),
+      PathState::Dir(items) => items
+        .iter()
+        .map(|(rel, content)| rel.as_os_str().len() + content.as_ref().map_or(0, Vec::len))
+        .sum(),
+    }
+  }
+
+  fn digest(&self) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    match self {
+      PathState::Missing => hasher.update(b"missing"),
+      PathState::File(bytes) => {
+        hasher.update(b"file");
+        hasher.update(bytes);
+      }
+      PathState::Dir(items) => {
+        hasher.update(b"dir");
+        for (rel, content) in items {
+          hasher.update(rel.to_string_lossy().as_bytes());
+          hasher.update([0]);
+          match content {
+            None => hasher.update(b"/"),
+            Some(bytes) => {
+              hasher.update((bytes.len() as u64).to_le_bytes());
+              hasher.update(bytes);
+            }
+          }
+        }
+      }
+    }
+    hasher.finalize().into()
+  }
+}
+
+#[derive(Debug, Clone)]
+struct SnapshotEntry {
+  path: PathBuf,
+  before: PathState,
+  /// 本事务最后一次写入后的内容摘要；None 表示写入后超出快照上限、无法校验
+  after: Option<[u8; 32]>,
+}
+
+#[derive(Debug, Clone)]
+struct AgentTransaction {
+  id: String,
+  label: String,
+  entries: Vec<SnapshotEntry>,
+  /// 无法快照的操作（工具名或超出大小上限的路径）
+  untracked: Vec<String>,
+}
+
+/// 单次工具调用前采集的快照，调用结束后由 `record_tool_changes` 写入当前事务
+#[derive(Debug)]
+pub struct PendingSnapshot {
+  tool_name: String,
+  snapshots: Vec<(PathBuf, Option<PathState>)>,
+  untracked_tool: bool,
+}
+
+/// 撤销结果
+#[derive(Debug, Clone, Serialize)]
+pub struct AgentUndoResult {
+  pub transaction_id: String,
+  pub label: String,
+  pub restored_paths: Vec<String>,
+  pub failed_paths: Vec<String>,
+  pub untracked_operations: Vec<String>,
+}
+
+fn journal_key(workspace_path: &Path) -> PathBuf {
+  fs::canonicalize(workspace_path).unwrap_or_else(|_| workspace_path.to_path_buf())
+}
+
+fn read_state(path: &Path) -> Option<PathState> {
+  let meta = match fs::symlink_metadata(path) {
+    Ok(meta) => meta,
+    Err(_) => return Some(PathState::Missing),
+  };
+  if meta.is_file() {
+    if meta.len() > MAX_SNAPSHOT_BYTES {
+      return None;
+    }
+    return fs::read(path).ok().map(PathState::File);
+  }
+  if !meta.is_dir() {
+    return None;
+  }
+
+  let mut items = Vec::new();
+  let mut total = 0u64;
+  let mut stack = vec![path.to_path_buf()];
+  while let Some(dir) = stack.pop() {
+    for entry in fs::read_dir(&dir).ok()?.flatten() {
+      let child = entry.path();
+      let rel = child.strip_prefix(path).ok()?.to_path_buf();
+      let child_meta = entry.metadata().ok()?;
+      if child_meta.is_dir() {
+        items.push((rel, None));
+        stack.push(child);
+      } else {
+        total += child_meta.len();
+        if total > MAX_SNAPSHOT_BYTES {
+          return None;
+        }
+        items.push((rel, Some(fs::read(&child).ok()?)));
+      }
+    }
+  }
+  items.sort_by(|a, b| a.0.cmp(&b.0));
+  Some(PathState::Dir(items))
+}
+
+fn remove_path(path: &Path) -> std::io::Result<()> {
+  match fs::symlink_metadata(path) {
+    Ok(meta) if meta.is_dir() => fs::remove_dir_all(path),
+    Ok(_) => fs::remove_file(path),
+    Err(_) => Ok(()),
+  }
+}
+
+fn restore_state(path: &Path, state: &PathState) -> std::io::Result<()> {
+  if read_state(path).as_ref() == Some(state) {
+    return Ok(());
+  }
+  remove_path(path)?;
+  match state {
+    PathState::Missing => Ok(()),
+    PathState::File(bytes) => {
+      if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+      }
+      fs::write(path, bytes)
+    }
+    PathState::Dir(items) => {
+      fs::create_dir_all(path)?;
+      for (rel, content) in items {
+        let target = path.join(rel);
+        match content {
+          None => fs::create_dir_all(&target)?,
+          Some(bytes) => {
+            if let Some(parent) = target.parent() {
+              fs::create_dir_all(parent)?;
+            }
+            fs::write(&target, bytes)?;
+          }
+        }
+      }
+      Ok(())
+    }
+  }
+}
+
+/// 工具调用会修改的路径；None 表示不修改文件
+fn mutated_paths(tool_call: &ToolCall, workspace_path: &Path) -> Option<Vec<PathBuf>> {
+  let arg = |key: &str| tool_call.arguments.get(key).and_then(|v| v.as_str());
+  let resolve =
+    |raw: &str| PathValidator::resolve_workspace_relative_path(workspace_path, raw).ok();
+
+  let paths = match tool_call.name.as_str() {
+    "create_file"
+    | "create_docx_from_markdown"
+    | "update_file"
+    | "apply_patch"
+    | "delete_file"
+    | "create_folder" => arg("path").and_then(resolve).into_iter().collect(),
+    "copy_file" => arg("destination").and_then(resolve).into_iter().collect(),
+    "move_file" => [arg("source"), arg("destination")]
+      .into_iter()
+      .flatten()
+      .filter_map(resolve)
+      .collect(),
+    "rename_file" => {
+      let source = arg("path").and_then(resolve);
+      let target = source
+        .as_ref()
+        .zip(arg("new_name"))
+        .and_then(|(source, name)| Some(source.parent()?.join(name)));
+      source.into_iter().chain(target).collect()
+    }
+    _ => return None,
+  };
+  Some(paths)
+}
+
+/// 工具执行前调用：为会修改的路径采集快照
+pub fn prepare_snapshot(
+  tool_call: &ToolCall,
+  workspace_path: &Path,
+  custom_tool: bool,
+) -> Option<PendingSnapshot> {
+  if tool_call.name == "run_command" || custom_tool {
+    return Some(PendingSnapshot {
+      tool_name: tool_call.name.clone(),
+      snapshots: Vec::new(),
+      untracked_tool: true,
+    });
+  }
+  let paths = mutated_paths(tool_call, workspace_path)?;
+  Some(PendingSnapshot {
+    tool_name: tool_call.name.clone(),
+    snapshots: paths
+      .into_iter()
+      .map(|path| {
+        let state = read_state(&path);
+        (path, state)
+      })
+      .collect(),
+    untracked_tool: false,
+  })
+}
+
+/// 工具执行后调用：把实际发生变化的路径记入当前事务（同一路径只保留最早的快照）
+pub fn record_tool_changes(workspace_path: &Path, pending: PendingSnapshot, succeeded: bool) {
+  let mut entries = Vec::new();
+  let mut untracked = Vec::new();
+  if pending.untracked_tool {
+    if succeeded {
+      untracked.push(pending.tool_name.clone());
+    }
+  } else {
+    for (path, before) in pending.snapshots {
+      let after = read_state(&path);
+      match before {
+        Some(before) if after.as_ref() != Some(&before) => entries.push(SnapshotEntry {
+          path,
+          before,
+          after: after.as_ref().map(PathState::digest),
+        }),
+        Some(_) => {}
+        None if succeeded => untracked.push(format!("{}: {}", pending.tool_name, path.display())),
+        None => {}
+      }
+    }
+  }
+  if entries.is_empty() && untracked.is_empty() {
+    return;
+  }
+
+  let mut journals = JOURNALS.lock().unwrap();
+  let transactions = journals.entry(journal_key(workspace_path)).or_default();
+  if transactions.is_empty() {
+    transactions.push(new_transaction("AI 操作"));
+  }
+  let current = transactions.last_mut().unwrap();
+  // 祖先或子路径被本次调用改动（如移动了所在目录）时，刷新已记录路径写入后的摘要
+  let changed: Vec<PathBuf> = entries.iter().map(|e| e.path.clone()).collect();
+  for entry in current.entries.iter_mut() {
+    if changed
+      .iter()
+      .any(|p| p != &entry.path && (entry.path.starts_with(p) || p.starts_with(&entry.path)))
+    {
+      entry.after = read_state(&entry.path).map(|state| state.digest());
+    }
+  }
+  for entry in entries {
+    match current.entries.iter_mut().find(|e| e.path == entry.path) {
+      Some(existing) => existing.after = entry.after,
+      None => current.entries.push(entry),
+    }
+  }
+  for item in untracked {
+    if !current.untracked.contains(&item) {
+      current.untracked.push(item);
+    }
+  }
+  enforce_journal_limit(transactions, &pending.tool_name, MAX_JOURNAL_BYTES);
+}
+
+fn transaction_bytes(transaction: &AgentTransaction) -> usize {
+  transaction.entries.iter().map(|e| e.before.size()).sum()
+}
+
+/// 快照总大小超过上限时先淘汰最早的事务；当前事务自身仍超限时丢弃最新的快照，改为提示无法撤销
+fn enforce_journal_limit(transactions: &mut Vec<AgentTransaction>, tool_name: &str, limit: usize) {
+  let mut total: usize = transactions.iter().map(transaction_bytes).sum();
+  while total > limit && transactions.len() > 1 {
+    total -= transaction_bytes(&transactions.remove(0));
+  }
+  let Some(current) = transactions.last_mut() else {
+    return;
+  };
+  while total > limit {
+    let Some(entry) = current.entries.pop() else {
+      break;
+    };
+    total -= entry.before.size();
+    current.untracked.push(format!(
+      "{}: {}（快照超出大小上限）",
+      tool_name,
+      entry.path.display()
+    ));
+  }
+}
+
+fn new_transaction(label: &str) -> AgentTransaction {
+  AgentTransaction {
+    id: format!("agent_tx_{}", uuid::Uuid::new_v4()),
+    label: label.to_string(),
+    entries: Vec::new(),
+    untracked: Vec::new(),
+  }
+}
+
+/// 新一轮对话开始时调用，之后的工具改动都归入该事务；上一轮没有任何改动时直接复用
+pub fn begin_transaction(workspace_path: &Path, label: &str) {
+  let mut journals = JOURNALS.lock().unwrap();
+  let transactions = journals.entry(journal_key(workspace_path)).or_default();
+  if transactions
+    .last()
+    .is_some_and(|t| t.entries.is_empty() && t.untracked.is_empty())
+  {
+    transactions.pop();
+  }
+  transactions.push(new_transaction(label));
+  if transactions.len() > MAX_TRANSACTIONS_PER_WORKSPACE {
+    let excess = transactions.len() - MAX_TRANSACTIONS_PER_WORKSPACE;
+    transactions.drain(..excess);
+  }
+}
+
+/// 撤销最近一个有改动的事务：按记录的相反顺序恢复各路径的原始内容。
+///
+/// 路径在 AI 写入后又被改动（内容摘要不一致）时默认拒绝撤销并保留事务，
+/// 由调用方向用户确认后以 `force = true` 重试
+pub fn undo_last_transaction(
+  workspace_path: &Path,
+  force: bool,
+) -> Result<AgentUndoResult, String> {
+  let display = |path: &Path| {
+    path
+      .strip_prefix(journal_key(workspace_path))
+      .unwrap_or(path)
+      .to_string_lossy()
+      .replace('\\', "/")
+  };
+  let transaction = {
+    let mut journals = JOURNALS.lock().unwrap();
+    let transactions = journals
+      .get_mut(&journal_key(workspace_path))
+      .ok_or_else(|| "没有可撤销的 AI 操作".to_string())?;
+    while transactions
+      .last()
+      .is_some_and(|t| t.entries.is_empty() && t.untracked.is_empty())
+    {
+      transactions.pop();
+    }
+    let last = transactions
+      .last()
+      .ok_or_else(|| "没有可撤销的 AI 操作".to_string())?;
+    if !force {
+      let modified: Vec<String> = last
+        .entries
+        .iter()
+        .filter(|entry| {
+          let current = read_state(&entry.path).map(|state| state.digest());
+          entry.after.is_none() || current != entry.after
+        })
+        .map(|entry| display(&entry.path))
+        .collect();
+      if !modified.is_empty() {
+        return Err(format!(
+          "以下文件在 AI 修改后又有改动，撤销会覆盖这些改动: {}",
+          modified.join(", ")
+        ));
+      }
+    }
+    transactions.pop().unwrap()
+  };
+
+  let mut restored_paths = Vec::new();
+  let mut failed_paths = Vec::new();
+  for entry in transaction.entries.iter().rev() {
+    match restore_state(&entry.path, &entry.before) {
+      Ok(()) => restored_paths.push(display(&entry.path)),
+      Err(e) => {
+        tracing::warn!("撤销 AI 操作失败 {}: {}", entry.path.display(), e);
+        failed_paths.push(display(&entry.path));
+      }
+    }
+  }
+
+  Ok(AgentUndoResult {
+    transaction_id: transaction.id,
+    label: transaction.label,
+    restored_paths,
+    failed_paths,
+    untracked_operations: transaction.untracked,
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn call(name: &str, arguments: serde_json::Value) -> ToolCall {
+    ToolCall {
+      id: "t".to_string(),
+      name: name.to_string(),
+      arguments,
+    }
+  }
+
+  #[test]
+  fn undo_restores_files_touched_in_the_last_turn() {
+    let root = std::env::temp_dir().join(format!("binder_agent_tx_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(root.join("docs")).unwrap();
+    fs::write(root.join("docs/a.md"), "original").unwrap();
+    fs::write(root.join("b.md"), "keep").unwrap();
+
+    begin_transaction(&root, "turn-1");
+    let ops: Vec<(ToolCall, Box<dyn Fn()>)> = vec![
+      (
+        call("update_file", serde_json::json!({ "path": "docs/a.md" })),
+        Box::new(|| fs::write(root.join("docs/a.md"), "changed").unwrap()),
+      ),
+      (
+        call("create_file", serde_json::json!({ "path": "c.md" })),
+        Box::new(|| fs::write(root.join("c.md"), "new").unwrap()),
+      ),
+      (
+        call(
+          "move_file",
+          serde_json::json!({ "source": "docs", "destination": "archive/docs" }),
+        ),
+        Box::new(|| {
+          fs::create_dir_all(root.join("archive")).unwrap();
+          fs::rename(root.join("docs"), root.join("archive/docs")).unwrap();
+        }),
+      ),
+      (
+        call("read_file", serde_json::json!({ "path": "b.md" })),
+        Box::new(|| {}),
+      ),
+    ];
+    for (tool_call, apply) in ops {
+      let pending = prepare_snapshot(&tool_call, &root, false);
+      apply();
+      if let Some(pending) = pending {
+        record_tool_changes(&root, pending, true);
+      }
+    }
+
+    let result = undo_last_transaction(&root, false).unwrap();
+    assert_eq!(result.label, "turn-1");
+    assert_eq!(result.restored_paths.len(), 4);
+    assert!(result.failed_paths.is_empty());
+    assert_eq!(
+      fs::read_to_string(root.join("docs/a.md")).unwrap(),
+      "original"
+    );
+    assert!(!root.join("c.md").exists());
+    assert!(!root.join("archive/docs").exists());
+    assert_eq!(fs::read_to_string(root.join("b.md")).unwrap(), "keep");
+    assert!(undo_last_transaction(&root, false).is_err());
+
+    let _ = fs::remove_dir_all(&root);
+  }
+
+  fn apply_update(root: &Path, content: &str) {
+    let tool_call = call("update_file", serde_json::json!({ "path": "a.md" }));
+    let pending = prepare_snapshot(&tool_call, root, false).unwrap();
+    fs::write(root.join("a.md"), content).unwrap();
+    record_tool_changes(root, pending, true);
+  }
+
+  #[test]
+  fn undo_refuses_to_overwrite_later_edits_unless_forced() {
+    let root = std::env::temp_dir().join(format!("binder_agent_tx_edit_{}", uuid::Uuid::new_v4()));
+    fs::create_dir_all(&root).unwrap();
+    fs::write(root.join("a.md"), "original").unwrap();
+
+    begin_transaction(&root, "turn-1");
+    apply_update(&root, "ai draft");
+    apply_update(&root, "ai final");
+    // 用户在 AI 写入后手动改了文件
+    fs::write(root.join("a.md"), "user edit").unwrap();
+
+    let err = undo_last_transaction(&root, false).unwrap_err();
+    assert!(err.contains("a.md"));
+    assert_eq!(fs::read_to_string(root.join("a.md")).unwrap(), "user edit");
+
+    let result = undo_last_transaction(&root, true).unwrap();
+    assert_eq!(result.restored_paths, vec!["a.md".to_string()]);
+    assert_eq!(fs::read_to_string(root.join("a.md")).unwrap(), "original");
+
+    let _ = fs::remove_dir_all(&root);
+  }
+
+  #[test]
+  fn journal_limit_evicts_oldest_transactions_first() {
+    let entry = |name: &str, size: usize| SnapshotEntry {
+      path: PathBuf::from(name),
+      before: PathState::File(vec![0; size]),
+      after: None,
+    };
+    let mut transactions = vec![new_transaction("old"), new_transaction("current")];
+    transactions[0].entries.push(entry("old.md", 60));
+    transactions[1].entries.push(entry("a.md", 30));
+    transactions[1].entries.push(entry("b.md", 30));
+
+    enforce_journal_limit(&mut transactions, "update_file", 100);
+    assert_eq!(transactions.len(), 1);
+    assert_eq!(transactions[0].label, "current");
+    assert_eq!(transactions[0].entries.len(), 2);
+
+    transactions[0].entries.push(entry("c.md", 50));
+    enforce_journal_limit(&mut transactions, "update_file", 100);
+    assert_eq!(transactions[0].entries.len(), 2);
+    assert_eq!(transactions[0].untracked.len(), 1);
+    assert!(transactions[0].untracked[0].contains("c.md"));
+  }
+}
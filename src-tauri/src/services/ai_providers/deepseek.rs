@@ -1,7 +1,8 @@
 use crate::services::ai_error::AIError;
 use crate::services::ai_providers::{
-  AIProvider, ChatChunk, ChatMessage, ModelConfig, ToolDefinition,
+  AIProvider, ChatChunk, ChatMessage, ModelConfig, PartialArgumentsCursor, ToolDefinition,
 };
+use crate::services::settings_service;
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use std::sync::{Arc, Mutex};
@@ -30,7 +31,7 @@ impl DeepSeekProvider {
   pub fn new(api_key: String) -> Self {
     // 创建带超时配置的 HTTP 客户端
     // ⚠️ 关键修复：优化网络连接配置，提高稳定性
-    let mut client_builder = reqwest::Client::builder()
+    let client_builder = reqwest::Client::builder()
       .timeout(std::time::Duration::from_secs(120)) // 增加请求超时到 120 秒
       .connect_timeout(std::time::Duration::from_secs(30)) // 增加连接超时到 30 秒
       .tcp_keepalive(std::time::Duration::from_secs(30)) // 保持 TCP 连接
@@ -40,18 +41,20 @@ impl DeepSeekProvider {
       .user_agent("Binder/1.0") // 添加 User-Agent
       .danger_accept_invalid_certs(false); // 确保 SSL 证书验证
 
-    // 支持从环境变量读取代理配置
-    // reqwest 默认会从 HTTP_PROXY 和 HTTPS_PROXY 环境变量读取代理
+    // 应用设置中的代理优先；未设置时 reqwest 默认会从 HTTP_PROXY 和 HTTPS_PROXY 环境变量读取代理
     // 这里显式检查并记录，方便调试
-    if let Ok(proxy_url) = std::env::var("HTTPS_PROXY")
+    let client_builder = settings_service::apply_proxy(client_builder);
+    if let Some(proxy_url) = settings_service::current().proxy {
+      tracing::debug!("使用应用设置中的代理: {}", proxy_url);
+    } else if let Ok(proxy_url) = std::env::var("HTTPS_PROXY")
       .or_else(|_| std::env::var("https_proxy"))
       .or_else(|_| std::env::var("HTTP_PROXY"))
       .or_else(|_| std::env::var("http_proxy"))
     {
-      eprintln!("🌐 检测到代理配置: {}", proxy_url);
+      tracing::debug!("检测到代理配置: {}", proxy_url);
       // reqwest 会自动使用环境变量中的代理，无需手动配置
     } else {
-      eprintln!("ℹ️ 未检测到代理配置，使用直连");
+      tracing::debug!("ℹ️ 未检测到代理配置，使用直连");
     }
 
     let client = client_builder
@@ -296,38 +299,40 @@ impl AIProvider for DeepSeekProvider {
     );
 
     // 为自动补全创建带短超时的客户端（10秒超时，快速失败）
-    let autocomplete_client = reqwest::Client::builder()
-      .timeout(std::time::Duration::from_secs(10)) // 自动补全10秒超时
-      .connect_timeout(std::time::Duration::from_secs(5))
-      .http1_only()
-      .user_agent("Binder/1.0")
-      .build()
-      .map_err(|e| AIError::NetworkError(format!("创建客户端失败: {}", e)))?;
+    let autocomplete_client = settings_service::apply_proxy(
+      reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(10)) // 自动补全10秒超时
+        .connect_timeout(std::time::Duration::from_secs(5))
+        .http1_only()
+        .user_agent("Binder/1.0"),
+    )
+    .build()
+    .map_err(|e| AIError::NetworkError(format!("创建客户端失败: {}", e)))?;
 
     // 输出完整的提示词用于调试
-    eprintln!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
-    eprintln!("📝 [自动补全] 完整提示词");
-    eprintln!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
-    eprintln!("🔧 参数配置:");
-    eprintln!("  - 模型: deepseek-chat");
-    eprintln!("  - 最大Token: {}", max_length);
-    eprintln!("  - 文档格式: {}", document_format);
-    eprintln!("  - 温度: 0.7");
-    eprintln!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
-    eprintln!("💬 系统提示词 (System Prompt):");
-    eprintln!("{}", system_prompt);
-    eprintln!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
-    eprintln!("👤 用户提示词 (User Prompt):");
-    eprintln!("{}", user_prompt);
-    eprintln!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
-    eprintln!("📊 提示词统计:");
-    eprintln!("  - 系统提示词长度: {} 字符", system_prompt.len());
-    eprintln!("  - 用户提示词长度: {} 字符", user_prompt.len());
-    eprintln!(
+    tracing::debug!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    tracing::debug!("[自动补全] 完整提示词");
+    tracing::debug!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    tracing::debug!("参数配置:");
+    tracing::debug!("  - 模型: deepseek-chat");
+    tracing::debug!("  - 最大Token: {}", max_length);
+    tracing::debug!("  - 文档格式: {}", document_format);
+    tracing::debug!("  - 温度: 0.7");
+    tracing::debug!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    tracing::debug!("系统提示词 (System Prompt):");
+    tracing::debug!("{}", system_prompt);
+    tracing::debug!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    tracing::debug!("用户提示词 (User Prompt):");
+    tracing::debug!("{}", user_prompt);
+    tracing::debug!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    tracing::debug!("提示词统计:");
+    tracing::debug!("  - 系统提示词长度: {} 字符", system_prompt.len());
+    tracing::debug!("  - 用户提示词长度: {} 字符", user_prompt.len());
+    tracing::debug!(
       "  - 总长度: {} 字符",
       system_prompt.len() + user_prompt.len()
     );
-    eprintln!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    tracing::debug!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
 
     let request = ChatRequest {
       model: "deepseek-chat".to_string(),
@@ -374,10 +379,10 @@ impl AIProvider for DeepSeekProvider {
               .unwrap_or_default();
 
             // 记录响应内容用于调试
-            eprintln!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
-            eprintln!("✅ [自动补全] 收到响应");
-            eprintln!("  - 内容长度: {} 字符", content.len());
-            eprintln!(
+            tracing::debug!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+            tracing::info!("[自动补全] 收到响应");
+            tracing::debug!("  - 内容长度: {} 字符", content.len());
+            tracing::debug!(
               "  - 内容预览: {}",
               if content.len() > 50 {
                 format!("{}...", &content.chars().take(50).collect::<String>())
@@ -385,19 +390,19 @@ impl AIProvider for DeepSeekProvider {
                 content.clone()
               }
             );
-            eprintln!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+            tracing::debug!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
 
             // 允许空内容（补全内容允许为空，允许为单独的标点符号）
             Ok(content)
           }
           Err(e) => {
-            eprintln!("❌ [自动补全] 解析响应失败: {}", e);
+            tracing::error!("[自动补全] 解析响应失败: {}", e);
             Err(AIError::NetworkError(format!("解析响应失败: {}", e)))
           }
         }
       }
       Err(e) => {
-        eprintln!("❌ [自动补全] 请求失败: {}", e);
+        tracing::error!("[自动补全] 请求失败: {}", e);
         Err(AIError::NetworkError(format!("请求失败: {}", e)))
       }
     }
@@ -574,27 +579,23 @@ impl AIProvider for DeepSeekProvider {
         Err(e) => {
           let error_str = e.to_string();
           last_error = Some(e);
-          eprintln!("⚠️ 网络连接失败 (尝试 {}/3): {}", attempt + 1, error_str);
+          tracing::warn!("网络连接失败 (尝试 {}/3): {}", attempt + 1, error_str);
 
           // 如果是连接错误，等待后重试
           if attempt < 2 {
             let delay = std::time::Duration::from_millis(1000 * (attempt + 1) as u64); // 增加重试延迟：1s, 2s
             tokio::time::sleep(delay).await;
-            eprintln!(
-              "⏳ {}ms 后重试 (尝试 {}/3)...",
-              delay.as_millis(),
-              attempt + 2
-            );
+            tracing::debug!("{}ms 后重试 (尝试 {}/3)...", delay.as_millis(), attempt + 2);
           } else {
             // 最后一次尝试失败，输出详细错误信息
-            eprintln!("❌ 所有重试均失败，最终错误: {}", error_str);
+            tracing::error!("所有重试均失败，最终错误: {}", error_str);
             if error_str.contains("Connection refused") || error_str.contains("tcp connect") {
-              eprintln!("💡 提示: Connection refused 通常表示：");
-              eprintln!("   1. 网络连接问题（请检查网络连接）");
-              eprintln!("   2. 防火墙或代理阻止（检查防火墙设置）");
-              eprintln!("   3. 服务器不可达（检查 DNS 解析）");
-              eprintln!("   4. 需要配置代理（如果使用代理，请设置 HTTPS_PROXY 环境变量）");
-              eprintln!("   5. 检查系统代理设置");
+              tracing::debug!("提示: Connection refused 通常表示：");
+              tracing::debug!("   1. 网络连接问题（请检查网络连接）");
+              tracing::debug!("   2. 防火墙或代理阻止（检查防火墙设置）");
+              tracing::debug!("   3. 服务器不可达（检查 DNS 解析）");
+              tracing::debug!("   4. 需要配置代理（如果使用代理，请设置 HTTPS_PROXY 环境变量）");
+              tracing::debug!("   5. 检查系统代理设置");
 
               // 检查环境变量
               let has_proxy = std::env::var("HTTPS_PROXY").is_ok()
@@ -603,15 +604,15 @@ impl AIProvider for DeepSeekProvider {
                 || std::env::var("http_proxy").is_ok();
 
               if !has_proxy {
-                eprintln!("   ⚠️ 未检测到代理环境变量，如果使用代理，请设置：");
-                eprintln!("      export HTTPS_PROXY=http://proxy.example.com:8080");
+                tracing::warn!("   未检测到代理环境变量，如果使用代理，请设置：");
+                tracing::debug!("      export HTTPS_PROXY=http://proxy.example.com:8080");
               }
 
               // 尝试 DNS 解析测试
-              eprintln!("   🔍 诊断建议：");
-              eprintln!("      - 尝试 ping api.deepseek.com");
-              eprintln!("      - 检查防火墙是否阻止了连接");
-              eprintln!("      - 如果使用代理，确保代理服务器正常运行");
+              tracing::debug!("   诊断建议：");
+              tracing::debug!("      - 尝试 ping api.deepseek.com");
+              tracing::debug!("      - 检查防火墙是否阻止了连接");
+              tracing::debug!("      - 如果使用代理，确保代理服务器正常运行");
             }
           }
         }
@@ -641,7 +642,7 @@ impl AIProvider for DeepSeekProvider {
           || error_lower.contains("exceeded")
           || error_lower.contains("too long")
         {
-          eprintln!("⚠️ 检测到Token超限错误: {}", error_text);
+          tracing::warn!("检测到Token超限错误: {}", error_text);
           return Err(AIError::Unknown(format!("Token超限: {}", error_text)));
         }
       }
@@ -659,6 +660,7 @@ impl AIProvider for DeepSeekProvider {
       Option::<String>::None, // tool_call_name
       String::new(),          // tool_call_arguments
     )));
+    let partial_cursor = Arc::new(Mutex::new(PartialArgumentsCursor::default()));
 
     // 使用缓冲来处理可能跨 chunk 的 SSE 行
     let buffer = Arc::new(Mutex::new(String::new()));
@@ -674,6 +676,7 @@ impl AIProvider for DeepSeekProvider {
     let stream = response.bytes_stream();
     let stream = stream.map(move |result| {
                 let state = tool_call_state.clone();
+                let cursor = partial_cursor.clone();
                 let buf = buffer.clone();
                 let acc_text = accumulated_text_state.clone();
                 
@@ -687,7 +690,7 @@ impl AIProvider for DeepSeekProvider {
                             }
                             Err(e) => {
                                 // UTF-8 解码失败，使用 lossy 转换并记录警告
-                                eprintln!("⚠️ UTF-8 解码失败，使用 lossy 转换: {}", e);
+                                tracing::warn!("UTF-8 解码失败，使用 lossy 转换: {}", e);
                                 let lossy = String::from_utf8_lossy(&bytes);
                                 buf_guard.push_str(&lossy);
                             }
@@ -783,11 +786,11 @@ impl AIProvider for DeepSeekProvider {
                                                                 state_guard.1 = Some(name.clone());
                                                             }
                                                             if let Some(arguments) = &function.arguments {
-                                                                eprintln!("📝 累积工具调用 arguments: 当前长度={}, 新增长度={}, 新增内容={}", 
+                                                                tracing::debug!("累积工具调用 arguments: 当前长度={}, 新增长度={}, 新增内容={}", 
                                                                     state_guard.2.len(), arguments.len(),
                                                                     safe_truncate(arguments, 50));
                                                                 state_guard.2.push_str(arguments);
-                                                                eprintln!("📝 累积后总长度={}, 内容预览={}", 
+                                                                tracing::debug!("累积后总长度={}, 内容预览={}", 
                                                                     state_guard.2.len(),
                                                                     safe_truncate(&state_guard.2, 100));
                                                             }
@@ -798,7 +801,7 @@ impl AIProvider for DeepSeekProvider {
                                                         if !state_guard.2.is_empty() {
                                                             // 检查 arguments 是否是完整的 JSON
                                                             let args_str = state_guard.2.clone();
-                                                            eprintln!("🔍 检查 JSON 完整性: 长度={}, 内容={}", args_str.len(), 
+                                                            tracing::debug!("检查 JSON 完整性: 长度={}, 内容={}", args_str.len(), 
                                                                 if args_str.len() > 200 { format!("{}...", safe_truncate(&args_str, 200)) } else { args_str.clone() });
                                                             
                                                             // ⚠️ 增强检查：不仅检查是否以 } 结尾，还要验证 JSON 是否有效
@@ -822,9 +825,9 @@ impl AIProvider for DeepSeekProvider {
                                                                               parse_ok;
                                                             
                                                             if is_complete {
-                                                                eprintln!("✅ JSON 完整，标记为完成");
+                                                                tracing::info!("JSON 完整，标记为完成");
                                                             } else {
-                                                                eprintln!("⏳ JSON 不完整，继续累积 (括号匹配: {}, 引号偶数: {}, 解析: {})", 
+                                                                tracing::debug!("JSON 不完整，继续累积 (括号匹配: {}, 引号偶数: {}, 解析: {})", 
                                                                     braces_match, quotes_even, parse_ok);
                                                             }
                                                             
@@ -842,10 +845,6 @@ impl AIProvider for DeepSeekProvider {
                                                                 });
                                                                 processed_any = true;
                                                                 break; // 工具调用完成后不再处理其他行
-                                                            } else {
-                                                                // 未完成，不返回 chunk，继续累积
-                                                                // 参考 void 的实现：只有完整的工具调用才返回
-                                                                // 这样可以避免前端收到不完整的 JSON 导致解析失败
                                                             }
                                                         }
                                                     }
@@ -863,7 +862,7 @@ impl AIProvider for DeepSeekProvider {
                                                             if acc_guard.ends_with(content) {
                                                                 // 只在调试模式显示，避免日志过多
                                                                 #[cfg(debug_assertions)]
-                                                                eprintln!("⚠️ [deepseek] 检测到重复 content（完全重复），跳过: '{}'", 
+                                                                tracing::warn!("[deepseek] 检测到重复 content（完全重复），跳过: '{}'", 
                                                                     safe_truncate(content, 50));
                                                                 continue;
                                                             }
@@ -886,7 +885,7 @@ impl AIProvider for DeepSeekProvider {
                                                                     // 如果content在最后部分出现了两次或更多，说明是重复的
                                                                     let occurrences = last_part.matches(content).count();
                                                                     if occurrences >= 2 {
-                                                                        eprintln!("⚠️ [deepseek] 检测到重复 content（部分重复，出现{}次），跳过: '{}'", 
+                                                                        tracing::warn!("[deepseek] 检测到重复 content（部分重复，出现{}次），跳过: '{}'", 
                                                                             occurrences, safe_truncate(content, 50));
                                                                         continue;
                                                                     }
@@ -908,7 +907,7 @@ impl AIProvider for DeepSeekProvider {
                                                                     // 检查是否形成了明显的重复模式（连续出现2次或更多）
                                                                     let pattern = format!("{}{}", content, content);
                                                                     if check_part.contains(&pattern) {
-                                                                        eprintln!("⚠️ [deepseek] 检测到重复 content（重复模式），跳过: '{}'", 
+                                                                        tracing::warn!("[deepseek] 检测到重复 content（重复模式），跳过: '{}'", 
                                                                             safe_truncate(content, 50));
                                                                         continue;
                                                                     }
@@ -928,7 +927,7 @@ impl AIProvider for DeepSeekProvider {
                                         }
                                     }
                                     Err(e) => {
-                                        eprintln!("⚠️ JSON 解析失败，跳过该行: {}, 内容: {}", e, json_str);
+                                        tracing::warn!("JSON 解析失败，跳过该行: {}, 内容: {}", e, json_str);
                                         continue; // Ignore malformed JSON lines
                                     }
                                 }
@@ -938,10 +937,19 @@ impl AIProvider for DeepSeekProvider {
                         // 更新缓冲区（保留未完成的行）
                         *buf_guard = new_buffer;
                         
+                        // 未完成的工具调用：本批没有文本时只返回新到达的参数片段（is_complete = false），
+                        // 仅用于前端实时预览；有文本时留到下一批一起返回，片段不会丢失
+                        let has_text = result_chunks.iter().any(|c| matches!(c, ChatChunk::Text(t) if !t.is_empty()));
+                        let has_complete_call = result_chunks.iter().any(|c| matches!(c, ChatChunk::ToolCall { .. }));
+                        if !has_text && !has_complete_call {
+                            if let Some(partial) = cursor.lock().unwrap().next_chunk(&state.lock().unwrap()) {
+                                result_chunks.push(partial);
+                            }
+                        }
+
                         // 按照文档：合并同一 bytes chunk 中的多个文本 content 为一个，避免丢失
                         // 工具调用单独返回
                         if !result_chunks.is_empty() {
-                            // 优先返回工具调用
                             if let Some(tool_call) = result_chunks.iter().find(|c| matches!(c, ChatChunk::ToolCall { .. })) {
                                 Ok(tool_call.clone())
                             } else {
@@ -1020,6 +1028,9 @@ fn build_autocomplete_prompt(
     if !overview.document_structure.is_empty() && overview.document_structure != "无标题结构" {
       user_prompt.push_str(&format!("文档结构：{}\n", overview.document_structure));
     }
+    if !overview.front_matter.is_empty() {
+      user_prompt.push_str(&format!("文档元数据：{}\n", overview.front_matter));
+    }
     user_prompt.push_str(&format!("文档开头：{}\n", overview.document_start));
     if !overview.document_end.is_empty() {
       user_prompt.push_str(&format!("文档结尾：{}\n", overview.document_end));
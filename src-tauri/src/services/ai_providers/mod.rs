@@ -14,6 +14,8 @@ use serde::{Deserialize, Serialize};
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ChatChunk {
   Text(String),
+  /// is_complete = true 时 arguments 为完整参数；
+  /// false 时只是上次部分参数之后新到达的片段，仅供实时预览，不能据此执行
   ToolCall {
     id: String,
     name: String,
@@ -22,6 +24,40 @@ pub enum ChatChunk {
   },
 }
 
+/// 流式工具调用的部分参数游标：记录已作为部分参数返回的字节数，之后只返回新增片段
+#[derive(Debug, Default)]
+pub(crate) struct PartialArgumentsCursor {
+  id: String,
+  sent: usize,
+}
+
+impl PartialArgumentsCursor {
+  /// 由累积状态 (id, name, arguments) 生成部分参数 chunk；没有新增参数时返回 None
+  pub(crate) fn next_chunk(
+    &mut self,
+    state: &(Option<String>, Option<String>, String),
+  ) -> Option<ChatChunk> {
+    let (Some(id), Some(name), arguments) = state else {
+      return None;
+    };
+    if self.id != *id || !arguments.is_char_boundary(self.sent) {
+      self.id = id.clone();
+      self.sent = 0;
+    }
+    if arguments.len() <= self.sent {
+      return None;
+    }
+    let delta = arguments[self.sent..].to_string();
+    self.sent = arguments.len();
+    Some(ChatChunk::ToolCall {
+      id: id.clone(),
+      name: name.clone(),
+      arguments: delta,
+      is_complete: false,
+    })
+  }
+}
+
 // 编辑器状态（用于提示词构建）
 #[derive(Debug, Clone)]
 pub struct EditorState {
@@ -51,6 +87,8 @@ pub struct DocumentOverview {
   pub current_section: String,
   pub previous_paragraph: String,
   pub next_paragraph: String,
+  /// Markdown front matter（压缩为一行），没有时为空
+  pub front_matter: String,
 }
 
 #[async_trait]
@@ -125,6 +163,16 @@ pub trait AIProvider: Send + Sync {
     Ok(result)
   }
 
+  /// 语义检索使用的 embedding 模型名；None 表示该提供商不支持 embeddings
+  fn embedding_model(&self) -> Option<&str> {
+    None
+  }
+
+  /// 批量计算文本向量，返回顺序与输入一致
+  async fn embed(&self, _texts: &[String]) -> Result<Vec<Vec<f32>>, AIError> {
+    Err(AIError::ModelUnavailable)
+  }
+
   /// 聊天（流式响应）
   /// 返回一个异步流，每个 item 是一个 chunk 或工具调用
   async fn chat_stream(
@@ -186,3 +234,48 @@ impl Default for ModelConfig {
     }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn delta(chunk: Option<ChatChunk>) -> Option<String> {
+    match chunk? {
+      ChatChunk::ToolCall {
+        arguments,
+        is_complete: false,
+        ..
+      } => Some(arguments),
+      _ => None,
+    }
+  }
+
+  #[test]
+  fn partial_arguments_are_returned_once() {
+    let mut cursor = PartialArgumentsCursor::default();
+    let mut state = (
+      Some("call-1".to_string()),
+      Some("create_file".to_string()),
+      String::new(),
+    );
+    assert!(cursor.next_chunk(&state).is_none());
+
+    state.2.push_str("{\"path\":\"笔记");
+    assert_eq!(
+      delta(cursor.next_chunk(&state)).as_deref(),
+      Some("{\"path\":\"笔记")
+    );
+    assert!(cursor.next_chunk(&state).is_none());
+    state.2.push_str(".md\"");
+    assert_eq!(delta(cursor.next_chunk(&state)).as_deref(), Some(".md\""));
+
+    // 新的工具调用从头开始
+    let next = (
+      Some("call-2".to_string()),
+      Some("read_file".to_string()),
+      "{".to_string(),
+    );
+    assert_eq!(delta(cursor.next_chunk(&next)).as_deref(), Some("{"));
+    assert!(cursor.next_chunk(&(None, None, "{}".to_string())).is_none());
+  }
+}
@@ -1,7 +1,8 @@
 use crate::services::ai_error::AIError;
 use crate::services::ai_providers::{
-  AIProvider, ChatChunk, ChatMessage, ModelConfig, ToolDefinition,
+  AIProvider, ChatChunk, ChatMessage, ModelConfig, PartialArgumentsCursor, ToolDefinition,
 };
+use crate::services::settings_service;
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use std::sync::{Arc, Mutex};
@@ -15,10 +16,17 @@ pub struct OpenAIProvider {
 
 impl OpenAIProvider {
   pub fn new(api_key: String) -> Self {
+    Self::with_base_url(api_key, "https://api.openai.com/v1".to_string())
+  }
+
+  /// OpenAI 兼容接口（插件提供商使用）
+  pub fn with_base_url(api_key: String, base_url: String) -> Self {
     Self {
       api_key,
-      base_url: "https://api.openai.com/v1".to_string(),
-      client: reqwest::Client::new(),
+      base_url,
+      client: settings_service::apply_proxy(reqwest::Client::builder())
+        .build()
+        .unwrap_or_default(),
     }
   }
 
@@ -148,6 +156,25 @@ struct FunctionCallDelta {
   arguments: Option<String>,
 }
 
+#[derive(Debug, Serialize)]
+struct EmbeddingRequest<'a> {
+  model: &'a str,
+  input: &'a [String],
+}
+
+#[derive(Debug, Deserialize)]
+struct EmbeddingResponse {
+  data: Vec<EmbeddingData>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EmbeddingData {
+  index: usize,
+  embedding: Vec<f32>,
+}
+
+const EMBEDDING_MODEL: &str = "text-embedding-3-small";
+
 #[async_trait]
 impl AIProvider for OpenAIProvider {
   async fn autocomplete(&self, context: &str, max_length: usize) -> Result<String, AIError> {
@@ -333,6 +360,69 @@ impl AIProvider for OpenAIProvider {
     Ok(content)
   }
 
+  fn embedding_model(&self) -> Option<&str> {
+    Some(EMBEDDING_MODEL)
+  }
+
+  async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, AIError> {
+    if texts.is_empty() {
+      return Ok(Vec::new());
+    }
+
+    let url = format!("{}/embeddings", self.base_url);
+    let request_body = EmbeddingRequest {
+      model: EMBEDDING_MODEL,
+      input: texts,
+    };
+
+    let response = self
+      .client
+      .post(&url)
+      .headers(self.build_headers())
+      .json(&request_body)
+      .send()
+      .await
+      .map_err(|e| AIError::NetworkError(e.to_string()))?;
+
+    if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
+      let retry_after = response
+        .headers()
+        .get("retry-after")
+        .and_then(|h| h.to_str().ok())
+        .and_then(|s| s.parse::<u64>().ok())
+        .unwrap_or(60);
+      return Err(AIError::RateLimit { retry_after });
+    }
+
+    if !response.status().is_success() {
+      let error_text = response.text().await.unwrap_or_default();
+      return Err(AIError::Unknown(format!("API 错误: {}", error_text)));
+    }
+
+    let mut embedding_response: EmbeddingResponse = response
+      .json()
+      .await
+      .map_err(|e| AIError::NetworkError(e.to_string()))?;
+
+    if embedding_response.data.len() != texts.len() {
+      return Err(AIError::Unknown(format!(
+        "embedding 数量不匹配: 期望 {}，实际 {}",
+        texts.len(),
+        embedding_response.data.len()
+      )));
+    }
+
+    // 接口不保证返回顺序，按 index 还原
+    embedding_response.data.sort_by_key(|d| d.index);
+    Ok(
+      embedding_response
+        .data
+        .into_iter()
+        .map(|d| d.embedding)
+        .collect(),
+    )
+  }
+
   async fn chat_stream(
     &self,
     messages: &[ChatMessage],
@@ -409,12 +499,14 @@ impl AIProvider for OpenAIProvider {
       Option::<String>::None, // tool_call_name
       String::new(),          // tool_call_arguments
     )));
+    let partial_cursor = Arc::new(Mutex::new(PartialArgumentsCursor::default()));
     let buffer = Arc::new(Mutex::new(String::new()));
 
     // 创建流式响应处理（支持 content + tool_calls）
     let stream = response.bytes_stream();
     let stream = stream.map(move |result| {
       let state = tool_call_state.clone();
+      let cursor = partial_cursor.clone();
       let buf = buffer.clone();
       match result {
         Ok(bytes) => {
@@ -538,6 +630,11 @@ impl AIProvider for OpenAIProvider {
             return Ok(chunk);
           }
           if merged_text.is_empty() {
+            // 参数仍在生成中：只返回新到达的参数片段，供前端实时预览
+            let state_guard = state.lock().unwrap();
+            if let Some(partial) = cursor.lock().unwrap().next_chunk(&state_guard) {
+              return Ok(partial);
+            }
             Ok(ChatChunk::Text(String::new()))
           } else {
             Ok(ChatChunk::Text(merged_text))
@@ -1,131 +1,358 @@
+//! AI 请求调度：按提供商限制并发，排队的请求按优先级（对话 > Inline Assist > 自动补全）
+//! 获得名额，同优先级先到先得。
+//!
+//! 自动补全只保留最新的一个：同一提供商有新的自动补全排队时，之前排队的自动补全直接丢弃，
+//! 排队超过 `AUTOCOMPLETE_STALE_AFTER` 的在轮到时丢弃。每个提供商同时最多运行一个自动补全，
+//! 避免连续触发的补全占满名额、拖慢进行中的对话。
+
+use crate::services::ai_error::AIError;
 use serde::{Deserialize, Serialize};
-use std::collections::VecDeque;
-use std::sync::{Arc, Mutex};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
 use tokio::sync::oneshot;
 
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+/// 排队超过该时长的自动补全已无意义（用户早已继续输入）
+const AUTOCOMPLETE_STALE_AFTER: Duration = Duration::from_secs(3);
+/// 每个提供商同时运行的自动补全数
+const MAX_CONCURRENT_AUTOCOMPLETE: usize = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum RequestPriority {
   Low,    // 自动补全
-  Normal, // 聊天
-  High,   // Inline Assist
+  Normal, // Inline Assist
+  High,   // 聊天
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum RequestType {
   Autocomplete,
   InlineAssist,
   Chat,
 }
 
-#[derive(Debug)]
-pub struct AIRequest {
-  pub id: String,
-  pub priority: RequestPriority,
-  pub request_type: RequestType,
-  pub cancel_tx: Option<oneshot::Sender<()>>,
-  pub created_at: std::time::Instant,
+impl RequestType {
+  pub fn priority(self) -> RequestPriority {
+    match self {
+      RequestType::Autocomplete => RequestPriority::Low,
+      RequestType::InlineAssist => RequestPriority::Normal,
+      RequestType::Chat => RequestPriority::High,
+    }
+  }
 }
 
-impl AIRequest {
-  pub fn new(
-    id: String,
-    priority: RequestPriority,
-    request_type: RequestType,
-  ) -> (Self, oneshot::Receiver<()>) {
-    let (tx, rx) = oneshot::channel();
-    let request = Self {
-      id,
-      priority,
-      request_type,
-      cancel_tx: Some(tx),
-      created_at: std::time::Instant::now(),
-    };
-    (request, rx)
-  }
+/// 排队中的请求
+struct PendingRequest {
+  id: String,
+  provider: String,
+  request_type: RequestType,
+  seq: u64,
+  created_at: Instant,
+  /// 获得名额时发送；丢弃即表示请求被取消或丢弃
+  ready_tx: oneshot::Sender<()>,
+}
 
-  pub fn is_cancelled(&self) -> bool {
-    self.cancel_tx.is_none()
-  }
+#[derive(Default)]
+struct ProviderSlots {
+  running: usize,
+  autocomplete: usize,
+}
 
-  pub fn cancel(&mut self) {
-    self.cancel_tx.take();
-  }
+#[derive(Default)]
+struct QueueState {
+  pending: Vec<PendingRequest>,
+  providers: HashMap<String, ProviderSlots>,
 }
 
 pub struct AIRequestQueue {
-  queue: Arc<Mutex<VecDeque<AIRequest>>>,
-  active_requests: Arc<Mutex<usize>>,
+  state: Mutex<QueueState>,
+  /// 每个提供商的并发上限
   max_concurrent: usize,
+  next_seq: AtomicU64,
+}
+
+/// 运行名额；请求结束时丢弃以让出名额
+pub struct RequestPermit {
+  queue: Arc<AIRequestQueue>,
+  provider: String,
+  request_type: RequestType,
+}
+
+impl Drop for RequestPermit {
+  fn drop(&mut self) {
+    self.queue.release(&self.provider, self.request_type);
+  }
+}
+
+/// 等待中的 acquire 被丢弃时移出队列；已获得但未取走的名额归还
+struct WaitGuard<'a> {
+  queue: &'a Arc<AIRequestQueue>,
+  /// 按排队序号移除：同一 ID（如同一标签页）可能先后排队多次
+  seq: u64,
+  provider: String,
+  request_type: RequestType,
+  ready_rx: Option<oneshot::Receiver<()>>,
+}
+
+impl Drop for WaitGuard<'_> {
+  fn drop(&mut self) {
+    let Some(mut ready_rx) = self.ready_rx.take() else {
+      return;
+    };
+    if ready_rx.try_recv().is_ok() {
+      self.queue.release(&self.provider, self.request_type);
+    } else {
+      self.queue.lock().pending.retain(|p| p.seq != self.seq);
+    }
+  }
 }
 
 impl AIRequestQueue {
   pub fn new(max_concurrent: usize) -> Self {
     Self {
-      queue: Arc::new(Mutex::new(VecDeque::new())),
-      active_requests: Arc::new(Mutex::new(0)),
-      max_concurrent,
+      state: Mutex::new(QueueState::default()),
+      max_concurrent: max_concurrent.max(1),
+      next_seq: AtomicU64::new(0),
     }
   }
 
-  pub fn enqueue(&self, request: AIRequest) -> Result<(), String> {
-    let mut queue = self
-      .queue
-      .lock()
-      .map_err(|e| format!("获取队列锁失败: {}", e))?;
+  fn lock(&self) -> MutexGuard<'_, QueueState> {
+    self.state.lock().unwrap_or_else(|e| e.into_inner())
+  }
 
-    // 按优先级插入（高优先级在前）
-    let pos = queue
-      .iter()
-      .position(|r| r.priority < request.priority)
-      .unwrap_or(queue.len());
-    queue.insert(pos, request);
+  /// 排队等待该提供商的名额；被取消、被更新的自动补全替换或排队过久时返回 Cancelled
+  pub async fn acquire(
+    self: &Arc<Self>,
+    id: String,
+    provider: &str,
+    request_type: RequestType,
+  ) -> Result<RequestPermit, AIError> {
+    let (ready_tx, ready_rx) = oneshot::channel();
+    let seq = self.next_seq.fetch_add(1, Ordering::Relaxed);
+    {
+      let mut state = self.lock();
+      if request_type == RequestType::Autocomplete {
+        state
+          .pending
+          .retain(|p| p.provider != provider || p.request_type != RequestType::Autocomplete);
+      }
+      state.pending.push(PendingRequest {
+        id,
+        provider: provider.to_string(),
+        request_type,
+        seq,
+        created_at: Instant::now(),
+        ready_tx,
+      });
+    }
+    self.pump();
 
-    Ok(())
+    let mut guard = WaitGuard {
+      queue: self,
+      seq,
+      provider: provider.to_string(),
+      request_type,
+      ready_rx: Some(ready_rx),
+    };
+    let ready = guard
+      .ready_rx
+      .as_mut()
+      .expect("ready_rx 只在 drop 时取走")
+      .await;
+    guard.ready_rx = None;
+    ready.map_err(|_| AIError::Cancelled)?;
+    Ok(RequestPermit {
+      queue: self.clone(),
+      provider: provider.to_string(),
+      request_type,
+    })
   }
 
-  pub fn dequeue(&self) -> Option<AIRequest> {
-    let mut queue = self.queue.lock().ok()?;
-    let mut active = self.active_requests.lock().ok()?;
+  /// 按优先级把空出的名额分给排队的请求
+  fn pump(&self) {
+    let mut state = self.lock();
+    let now = Instant::now();
+    state.pending.retain(|p| {
+      p.request_type != RequestType::Autocomplete
+        || now.duration_since(p.created_at) < AUTOCOMPLETE_STALE_AFTER
+    });
+    state
+      .pending
+      .sort_by_key(|p| (std::cmp::Reverse(p.request_type.priority()), p.seq));
 
-    // 检查是否达到最大并发数
-    if *active >= self.max_concurrent {
-      return None;
+    let mut index = 0;
+    while index < state.pending.len() {
+      let pending = &state.pending[index];
+      let slots = state.providers.get(&pending.provider);
+      let running = slots.map_or(0, |s| s.running);
+      let autocomplete = slots.map_or(0, |s| s.autocomplete);
+      let is_autocomplete = pending.request_type == RequestType::Autocomplete;
+      if running >= self.max_concurrent
+        || (is_autocomplete && autocomplete >= MAX_CONCURRENT_AUTOCOMPLETE)
+      {
+        index += 1;
+        continue;
+      }
+      let pending = state.pending.remove(index);
+      // 等待方已离开时不占用名额
+      if pending.ready_tx.send(()).is_err() {
+        continue;
+      }
+      let slots = state.providers.entry(pending.provider).or_default();
+      slots.running += 1;
+      if is_autocomplete {
+        slots.autocomplete += 1;
+      }
     }
+  }
 
-    let request = queue.pop_front()?;
-    *active += 1;
-    Some(request)
+  fn release(&self, provider: &str, request_type: RequestType) {
+    {
+      let mut state = self.lock();
+      if let Some(slots) = state.providers.get_mut(provider) {
+        slots.running = slots.running.saturating_sub(1);
+        if request_type == RequestType::Autocomplete {
+          slots.autocomplete = slots.autocomplete.saturating_sub(1);
+        }
+      }
+    }
+    self.pump();
   }
 
+  /// 取消排队中的请求；请求不在队列中时返回 false
   pub fn cancel(&self, request_id: &str) -> bool {
-    let Ok(mut queue) = self.queue.lock() else {
-      return false;
-    };
+    let mut state = self.lock();
+    let before = state.pending.len();
+    state.pending.retain(|p| p.id != request_id);
+    state.pending.len() != before
+  }
+}
 
-    // 在队列中查找并取消
-    if let Some(pos) = queue.iter().position(|r| r.id == request_id) {
-      if let Some(mut request) = queue.remove(pos) {
-        request.cancel();
-        return true;
-      }
-    }
+#[cfg(test)]
+mod tests {
+  use super::*;
 
-    false
+  fn queue(max_concurrent: usize) -> Arc<AIRequestQueue> {
+    Arc::new(AIRequestQueue::new(max_concurrent))
   }
 
-  pub fn release_slot(&self) {
-    if let Ok(mut active) = self.active_requests.lock() {
-      if *active > 0 {
-        *active -= 1;
-      }
+  #[tokio::test]
+  async fn grants_freed_slot_by_priority() {
+    let queue = queue(1);
+    let running = queue
+      .acquire("a".to_string(), "deepseek", RequestType::InlineAssist)
+      .await
+      .unwrap();
+    // 其他提供商不受影响
+    let _other = queue
+      .acquire("b".to_string(), "openai", RequestType::Chat)
+      .await
+      .unwrap();
+
+    let (order_tx, mut order_rx) = tokio::sync::mpsc::unbounded_channel();
+    let mut tasks = Vec::new();
+    for (id, request_type) in [
+      ("inline", RequestType::InlineAssist),
+      ("chat", RequestType::Chat),
+    ] {
+      let (queue, order_tx) = (queue.clone(), order_tx.clone());
+      tasks.push(tokio::spawn(async move {
+        let _permit = queue
+          .acquire(id.to_string(), "deepseek", request_type)
+          .await
+          .unwrap();
+        order_tx.send(id).unwrap();
+      }));
+      tokio::task::yield_now().await;
     }
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    assert!(order_rx.try_recv().is_err());
+
+    drop(running);
+    for task in tasks {
+      task.await.unwrap();
+    }
+    assert_eq!(order_rx.recv().await, Some("chat"));
+    assert_eq!(order_rx.recv().await, Some("inline"));
   }
 
-  pub fn active_count(&self) -> usize {
-    self.active_requests.lock().map(|a| *a).unwrap_or(0)
+  #[tokio::test]
+  async fn drops_superseded_and_cancelled_requests() {
+    let queue = queue(2);
+    let first = queue
+      .acquire("ac-1".to_string(), "deepseek", RequestType::Autocomplete)
+      .await
+      .unwrap();
+
+    // 同时只运行一个自动补全；排队中的旧补全被新补全替换
+    let stale = tokio::spawn({
+      let queue = queue.clone();
+      async move {
+        queue
+          .acquire("ac-2".to_string(), "deepseek", RequestType::Autocomplete)
+          .await
+          .map(|_| ())
+      }
+    });
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    let latest = tokio::spawn({
+      let queue = queue.clone();
+      async move {
+        queue
+          .acquire("ac-3".to_string(), "deepseek", RequestType::Autocomplete)
+          .await
+          .map(|_| ())
+      }
+    });
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    assert!(matches!(stale.await.unwrap(), Err(AIError::Cancelled)));
+
+    // 自动补全占着名额时对话仍能运行
+    let chat = queue
+      .acquire("chat".to_string(), "deepseek", RequestType::Chat)
+      .await
+      .unwrap();
+    assert!(queue.cancel("ac-3"));
+    assert!(matches!(latest.await.unwrap(), Err(AIError::Cancelled)));
+    assert!(!queue.cancel("ac-3"));
+    drop((first, chat));
+    assert!(queue.lock().providers["deepseek"].running == 0);
   }
 
-  pub fn queue_size(&self) -> usize {
-    self.queue.lock().map(|q| q.len()).unwrap_or(0)
+  #[tokio::test]
+  async fn abandoning_a_wait_keeps_later_requests_with_the_same_id() {
+    let queue = queue(1);
+    let running = queue
+      .acquire("other".to_string(), "deepseek", RequestType::Chat)
+      .await
+      .unwrap();
+    let old = tokio::spawn({
+      let queue = queue.clone();
+      async move {
+        queue
+          .acquire("tab-1".to_string(), "deepseek", RequestType::Chat)
+          .await
+          .map(|_| ())
+      }
+    });
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    let new = tokio::spawn({
+      let queue = queue.clone();
+      async move {
+        queue
+          .acquire("tab-1".to_string(), "deepseek", RequestType::Chat)
+          .await
+          .map(|_| ())
+      }
+    });
+    tokio::time::sleep(Duration::from_millis(20)).await;
+
+    // 同一标签页重新发起对话后旧的等待被丢弃，只移出旧的排队项
+    old.abort();
+    let _ = old.await;
+    assert_eq!(queue.lock().pending.len(), 1);
+    drop(running);
+    assert!(new.await.unwrap().is_ok());
   }
 }
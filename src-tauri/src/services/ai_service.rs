@@ -1,14 +1,73 @@
 use crate::services::ai_config::AIConfig;
 use crate::services::ai_error::AIError;
-use crate::services::ai_providers::{AIProvider, ChatChunk, ChatMessage, ModelConfig};
-use crate::services::ai_queue::{AIRequest, AIRequestQueue, RequestPriority, RequestType};
+use crate::services::ai_providers::{
+  AIProvider, ChatChunk, ChatMessage, ModelConfig, ToolDefinition,
+};
+use crate::services::ai_queue::{AIRequestQueue, RequestPermit, RequestType};
 use crate::services::api_key_manager::APIKeyManager;
+use arc_swap::ArcSwap;
 use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::task::{ready, Context, Poll};
+use tokio_stream::Stream;
 use uuid::Uuid;
 
+type ProviderMap = HashMap<String, Arc<dyn AIProvider>>;
+
+pub type ChatStream = Box<dyn Stream<Item = Result<ChatChunk, AIError>> + Send + Unpin>;
+
+type PermitCell = Arc<Mutex<Option<RequestPermit>>>;
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+  mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// 一次多轮对话共用的名额槽：每轮调用提供商前先归还上一轮仍占用的名额，
+/// 每轮的流结束或出错时立即归还，执行工具等轮次间隙不占用名额
+#[derive(Clone, Default)]
+pub struct ChatSlot {
+  current: Arc<Mutex<Option<PermitCell>>>,
+}
+
+impl ChatSlot {
+  fn release(&self) {
+    if let Some(cell) = lock(&self.current).take() {
+      lock(&cell).take();
+    }
+  }
+
+  fn hold(&self, permit: RequestPermit) -> PermitCell {
+    let cell = Arc::new(Mutex::new(Some(permit)));
+    *lock(&self.current) = Some(cell.clone());
+    cell
+  }
+}
+
+/// 持有本轮名额的流：流结束或出错时立即归还，不必等流对象被丢弃
+struct PermitStream {
+  inner: ChatStream,
+  permit: PermitCell,
+}
+
+impl Stream for PermitStream {
+  type Item = Result<ChatChunk, AIError>;
+
+  fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+    let item = ready!(Pin::new(&mut self.inner).poll_next(cx));
+    if matches!(item, None | Some(Err(_))) {
+      lock(&self.permit).take();
+    }
+    Poll::Ready(item)
+  }
+}
+
+/// AI 服务；所有方法只需 `&self`，以 `Arc<AIService>` 共享，不需要外层锁。
+///
+/// 提供商表用 ArcSwap 存放：流式对话等热路径查找提供商时无锁，
+/// 注册 / 重建提供商时复制整张表再原子替换。
 pub struct AIService {
-  providers: Arc<Mutex<HashMap<String, Arc<dyn AIProvider>>>>,
+  providers: ArcSwap<ProviderMap>,
   queue: Arc<AIRequestQueue>,
   config: Arc<AIConfig>,
   key_manager: APIKeyManager,
@@ -18,76 +77,166 @@ impl AIService {
   pub fn new() -> Result<Self, String> {
     let config = Arc::new(AIConfig::load()?);
     let queue = Arc::new(AIRequestQueue::new(config.max_concurrent_requests));
+    let key_manager = APIKeyManager::new();
+    let providers = ArcSwap::from_pointee(Self::load_providers(&key_manager));
 
-    let providers: HashMap<String, Arc<dyn AIProvider>> = HashMap::new();
-
-    // 初始化 OpenAI 提供商（如果 API 密钥存在）
-    // TODO: 从密钥链加载 API 密钥
+    Ok(Self {
+      providers,
+      queue,
+      config,
+      key_manager,
+    })
+  }
 
-    let key_manager = APIKeyManager::new();
-    let providers = Arc::new(Mutex::new(providers));
+  /// 按密钥链中的 API 密钥和插件声明创建全部提供商
+  fn load_providers(key_manager: &APIKeyManager) -> ProviderMap {
+    let mut providers: ProviderMap = HashMap::new();
 
     // 尝试加载 OpenAI API 密钥并注册提供商
     match key_manager.get_key("openai") {
       Ok(api_key) => {
-        eprintln!("✅ 成功加载 OpenAI API key");
+        tracing::info!("成功加载 OpenAI API key");
         let openai_provider = Arc::new(crate::services::ai_providers::OpenAIProvider::new(api_key));
-        if let Ok(mut providers) = providers.lock() {
-          providers.insert("openai".to_string(), openai_provider);
-          eprintln!("✅ OpenAI 提供商已注册");
-        }
+        providers.insert("openai".to_string(), openai_provider);
+        tracing::info!("OpenAI 提供商已注册");
       }
       Err(e) => {
-        eprintln!("⚠️ 未找到 OpenAI API key: {}", e);
+        tracing::warn!("未找到 OpenAI API key: {}", e);
       }
     }
 
     // 尝试加载 DeepSeek API 密钥并注册提供商
     match key_manager.get_key("deepseek") {
       Ok(api_key) => {
-        eprintln!("✅ 成功加载 DeepSeek API key");
+        tracing::info!("成功加载 DeepSeek API key");
         let deepseek_provider = Arc::new(crate::services::ai_providers::DeepSeekProvider::new(
           api_key,
         ));
-        if let Ok(mut providers) = providers.lock() {
-          providers.insert("deepseek".to_string(), deepseek_provider);
-          eprintln!("✅ DeepSeek 提供商已注册");
-        }
+        providers.insert("deepseek".to_string(), deepseek_provider);
+        tracing::info!("DeepSeek 提供商已注册");
       }
       Err(e) => {
-        eprintln!("⚠️ 未找到 DeepSeek API key: {}", e);
+        tracing::warn!("未找到 DeepSeek API key: {}", e);
       }
     }
 
-    // 检查已注册的提供商
-    if let Ok(providers_guard) = providers.lock() {
-      let provider_names: Vec<String> = providers_guard.keys().cloned().collect();
-      eprintln!("📋 已注册的 AI 提供商: {:?}", provider_names);
+    // 插件声明的 OpenAI 兼容提供商
+    for (name, provider) in crate::services::plugins::build_providers(key_manager) {
+      tracing::info!("插件提供商已注册: {}", name);
+      providers.insert(name, provider);
     }
 
-    Ok(Self {
-      providers,
-      queue,
-      config,
-      key_manager,
-    })
+    let provider_names: Vec<&String> = providers.keys().collect();
+    tracing::debug!("已注册的 AI 提供商: {:?}", provider_names);
+    providers
+  }
+
+  /// 重新创建全部提供商（代理变更后调用，使新的 HTTP 客户端生效）
+  pub fn reload_providers(&self) {
+    self
+      .providers
+      .store(Arc::new(Self::load_providers(&self.key_manager)));
   }
 
   pub fn register_provider(&self, name: String, provider: Arc<dyn AIProvider>) {
-    if let Ok(mut providers) = self.providers.lock() {
-      providers.insert(name, provider);
-    }
+    self.providers.rcu(|providers| {
+      let mut providers = ProviderMap::clone(providers);
+      providers.insert(name.clone(), provider.clone());
+      providers
+    });
+  }
+
+  /// 重新注册插件提供商（插件重新加载或保存密钥后调用），移除已不存在的插件提供商
+  pub fn register_plugin_providers(&self) {
+    let plugin_providers = crate::services::plugins::build_providers(&self.key_manager);
+    self.providers.rcu(|providers| {
+      let mut providers = ProviderMap::clone(providers);
+      providers.retain(|name, _| name == "openai" || name == "deepseek");
+      providers.extend(plugin_providers.iter().cloned());
+      providers
+    });
   }
 
   pub fn get_provider(&self, name: &str) -> Option<Arc<dyn AIProvider>> {
+    self.providers.load().get(name).cloned()
+  }
+
+  /// 默认提供商及其名称：应用设置中的默认提供商，没有则依次尝试 DeepSeek、OpenAI
+  pub fn default_provider(&self) -> Option<(String, Arc<dyn AIProvider>)> {
+    [
+      crate::services::settings_service::default_ai_provider(),
+      "deepseek".to_string(),
+      "openai".to_string(),
+    ]
+    .into_iter()
+    .find_map(|name| self.get_provider(&name).map(|provider| (name, provider)))
+  }
+
+  /// 排队等待提供商的运行名额（对话 > Inline Assist > 自动补全），请求结束时丢弃许可
+  pub async fn acquire_slot(
+    &self,
+    provider_name: &str,
+    request_type: RequestType,
+  ) -> Result<RequestPermit, AIError> {
+    let prefix = match request_type {
+      RequestType::Autocomplete => "autocomplete",
+      RequestType::InlineAssist => "inline-assist",
+      RequestType::Chat => "chat",
+    };
     self
-      .providers
-      .lock()
-      .ok()
-      .and_then(|providers| providers.get(name).cloned())
+      .acquire_slot_with_id(
+        &format!("{}-{}", prefix, Uuid::new_v4()),
+        provider_name,
+        request_type,
+      )
+      .await
+  }
+
+  /// 以调用方给定的 ID 排队，排队期间可用 `cancel_request` 按该 ID 取消
+  pub async fn acquire_slot_with_id(
+    &self,
+    request_id: &str,
+    provider_name: &str,
+    request_type: RequestType,
+  ) -> Result<RequestPermit, AIError> {
+    self
+      .queue
+      .acquire(request_id.to_string(), provider_name, request_type)
+      .await
+  }
+
+  /// 为对话的一轮获取名额并调用提供商的流式接口，名额在这一轮响应结束时归还。
+  /// 排队期间收到取消信号，或按 request_id 调用 `cancel_request` 时返回 Cancelled
+  #[allow(clippy::too_many_arguments)]
+  pub async fn provider_chat_stream(
+    &self,
+    slot: &ChatSlot,
+    request_id: &str,
+    provider_name: &str,
+    provider: &Arc<dyn AIProvider>,
+    messages: &[ChatMessage],
+    model_config: &ModelConfig,
+    cancel_rx: &mut tokio::sync::oneshot::Receiver<()>,
+    tools: Option<&[ToolDefinition]>,
+  ) -> Result<ChatStream, AIError> {
+    slot.release();
+    let permit = tokio::select! {
+      permit = self.acquire_slot_with_id(request_id, provider_name, RequestType::Chat) => permit?,
+      Ok(()) = &mut *cancel_rx => return Err(AIError::Cancelled),
+    };
+    let permit = slot.hold(permit);
+    let inner = provider
+      .chat_stream(messages, model_config, cancel_rx, tools)
+      .await
+      .map_err(|e| {
+        slot.release();
+        e
+      })?;
+    Ok(Box::new(PermitStream { inner, permit }))
   }
 
   /// 自动补全
+  #[tracing::instrument(skip_all, fields(provider = provider_name))]
   pub async fn autocomplete(
     &self,
     provider_name: &str,
@@ -98,28 +247,10 @@ impl AIService {
       .get_provider(provider_name)
       .ok_or_else(|| AIError::Unknown(format!("提供商 {} 不存在", provider_name)))?;
 
-    let request_id = format!("autocomplete-{}", Uuid::new_v4());
-    let (request, mut cancel_rx) = AIRequest::new(
-      request_id.clone(),
-      RequestPriority::Low,
-      RequestType::Autocomplete,
-    );
-
-    // 将请求加入队列
-    self
-      .queue
-      .enqueue(request)
-      .map_err(|e| AIError::Unknown(e))?;
+    let _permit = self
+      .acquire_slot(provider_name, RequestType::Autocomplete)
+      .await?;
 
-    // 检查是否已取消
-    if cancel_rx.try_recv().is_ok() {
-      return Err(AIError::Cancelled);
-    }
-
-    // 等待队列处理（简化版，实际应该异步处理）
-    // TODO: 实现异步队列处理
-
-    // 直接调用提供商（临时实现）
     match provider.autocomplete(context, max_length).await {
       Ok(result) => Ok(Some(result)),
       Err(e) => {
@@ -136,6 +267,7 @@ impl AIService {
   }
 
   /// Inline Assist
+  #[tracing::instrument(skip_all, fields(provider = provider_name))]
   pub async fn inline_assist(
     &self,
     provider_name: &str,
@@ -147,21 +279,9 @@ impl AIService {
       .get_provider(provider_name)
       .ok_or_else(|| AIError::Unknown(format!("提供商 {} 不存在", provider_name)))?;
 
-    let request_id = format!("inline-assist-{}", Uuid::new_v4());
-    let (request, mut cancel_rx) = AIRequest::new(
-      request_id.clone(),
-      RequestPriority::High,
-      RequestType::InlineAssist,
-    );
-
-    self
-      .queue
-      .enqueue(request)
-      .map_err(|e| AIError::Unknown(e))?;
-
-    if cancel_rx.try_recv().is_ok() {
-      return Err(AIError::Cancelled);
-    }
+    let _permit = self
+      .acquire_slot(provider_name, RequestType::InlineAssist)
+      .await?;
 
     match provider.inline_assist(instruction, text, context).await {
       Ok(result) => Ok(result),
@@ -178,41 +298,33 @@ impl AIService {
   }
 
   /// 聊天（流式响应）
+  #[tracing::instrument(skip_all, fields(provider = provider_name, model = %model_config.model))]
   pub async fn chat_stream(
     &self,
     provider_name: &str,
     messages: &[ChatMessage],
     model_config: &ModelConfig,
-  ) -> Result<
-    Box<dyn tokio_stream::Stream<Item = Result<ChatChunk, AIError>> + Send + Unpin>,
-    AIError,
-  > {
+  ) -> Result<ChatStream, AIError> {
     let provider = self
       .get_provider(provider_name)
       .ok_or_else(|| AIError::Unknown(format!("提供商 {} 不存在", provider_name)))?;
 
-    let request_id = format!("chat-{}", Uuid::new_v4());
-    let (request, mut cancel_rx) = AIRequest::new(
-      request_id.clone(),
-      RequestPriority::Normal,
-      RequestType::Chat,
-    );
-
+    let (_cancel_tx, mut cancel_rx) = tokio::sync::oneshot::channel();
     self
-      .queue
-      .enqueue(request)
-      .map_err(|e| AIError::Unknown(e))?;
-
-    if cancel_rx.try_recv().is_ok() {
-      return Err(AIError::Cancelled);
-    }
-
-    provider
-      .chat_stream(messages, model_config, &mut cancel_rx, None)
+      .provider_chat_stream(
+        &ChatSlot::default(),
+        &format!("chat-{}", Uuid::new_v4()),
+        provider_name,
+        &provider,
+        messages,
+        model_config,
+        &mut cancel_rx,
+        None,
+      )
       .await
   }
 
-  /// 取消请求
+  /// 取消排队中的请求
   pub fn cancel_request(&self, request_id: &str) -> bool {
     self.queue.cancel(request_id)
   }
@@ -294,3 +406,190 @@ impl AIService {
     self.key_manager.get_key(provider)
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::services::ai_providers::OpenAIProvider;
+  use std::time::Duration;
+  use tokio::sync::oneshot;
+  use tokio_stream::StreamExt;
+
+  /// 每轮只回复一段文本的提供商
+  struct EchoProvider;
+
+  #[async_trait::async_trait]
+  impl AIProvider for EchoProvider {
+    async fn autocomplete(&self, _context: &str, _max_length: usize) -> Result<String, AIError> {
+      Ok(String::new())
+    }
+
+    async fn inline_assist(
+      &self,
+      _instruction: &str,
+      _text: &str,
+      _context: &str,
+    ) -> Result<String, AIError> {
+      Ok(String::new())
+    }
+
+    async fn chat_stream(
+      &self,
+      _messages: &[ChatMessage],
+      _model_config: &ModelConfig,
+      _cancel_rx: &mut oneshot::Receiver<()>,
+      _tools: Option<&[ToolDefinition]>,
+    ) -> Result<ChatStream, AIError> {
+      Ok(Box::new(tokio_stream::iter(vec![Ok(ChatChunk::Text(
+        "ok".to_string(),
+      ))])))
+    }
+  }
+
+  fn model_config() -> ModelConfig {
+    ModelConfig {
+      model: "echo".to_string(),
+      max_tokens: 16,
+      temperature: 0.0,
+      top_p: 1.0,
+    }
+  }
+
+  fn service() -> AIService {
+    AIService {
+      providers: ArcSwap::from_pointee(HashMap::new()),
+      queue: Arc::new(AIRequestQueue::new(1)),
+      config: Arc::new(AIConfig::default()),
+      key_manager: APIKeyManager::new(),
+    }
+  }
+
+  /// 只比较数据指针，忽略 trait object 的 vtable
+  fn same(a: &Arc<dyn AIProvider>, b: &Arc<dyn AIProvider>) -> bool {
+    std::ptr::eq(Arc::as_ptr(a) as *const u8, Arc::as_ptr(b) as *const u8)
+  }
+
+  #[test]
+  fn concurrent_registrations_are_all_kept() {
+    let service = Arc::new(service());
+    let handles: Vec<_> = (0..8)
+      .map(|i| {
+        let service = service.clone();
+        std::thread::spawn(move || {
+          let name = format!("provider-{}", i);
+          service.register_provider(name.clone(), Arc::new(OpenAIProvider::new(name.clone())));
+          // 读者不需要锁，注册过程中随时能查到已注册的提供商
+          assert!(service.get_provider(&name).is_some());
+        })
+      })
+      .collect();
+    for handle in handles {
+      handle.join().unwrap();
+    }
+    assert_eq!(service.providers.load().len(), 8);
+  }
+
+  #[test]
+  fn lookups_keep_the_provider_they_loaded_across_replacement() {
+    let service = service();
+    let first: Arc<dyn AIProvider> = Arc::new(OpenAIProvider::new("old".to_string()));
+    service.register_provider("openai".to_string(), first.clone());
+    let held = service.get_provider("openai").unwrap();
+
+    let second: Arc<dyn AIProvider> = Arc::new(OpenAIProvider::new("new".to_string()));
+    service.register_provider("openai".to_string(), second.clone());
+    assert!(same(&held, &first));
+    assert!(same(&service.get_provider("openai").unwrap(), &second));
+    assert!(service.get_provider("missing").is_none());
+  }
+
+  /// 在限时内开始对话的一轮，超时说明名额未被归还
+  async fn start_turn(
+    service: &AIService,
+    provider: &Arc<dyn AIProvider>,
+    slot: &ChatSlot,
+    request_id: &str,
+  ) -> ChatStream {
+    let config = model_config();
+    let (_cancel_tx, mut cancel_rx) = oneshot::channel();
+    let turn = service.provider_chat_stream(
+      slot,
+      request_id,
+      "echo",
+      provider,
+      &[],
+      &config,
+      &mut cancel_rx,
+      None,
+    );
+    tokio::time::timeout(Duration::from_secs(1), turn)
+      .await
+      .expect("名额未归还")
+      .unwrap()
+  }
+
+  #[tokio::test]
+  async fn chat_turns_hold_the_slot_only_while_streaming() {
+    let service = service();
+    let provider: Arc<dyn AIProvider> = Arc::new(EchoProvider);
+    let slot = ChatSlot::default();
+
+    // 上一轮的流对象还在时，同一对话的下一轮也能拿到名额
+    let _first = start_turn(&service, &provider, &slot, "tab-1").await;
+    let mut second = start_turn(&service, &provider, &slot, "tab-1").await;
+
+    // 这一轮的流结束即归还名额，轮次间隙其他对话不必等待
+    while second.next().await.is_some() {}
+    let _other = start_turn(&service, &provider, &ChatSlot::default(), "tab-2").await;
+  }
+
+  #[tokio::test]
+  async fn queued_chat_turns_can_be_cancelled() {
+    let service = Arc::new(service());
+    let provider: Arc<dyn AIProvider> = Arc::new(EchoProvider);
+    let _running = start_turn(&service, &provider, &ChatSlot::default(), "tab-1").await;
+
+    // 排队期间收到本轮的取消信号
+    let config = model_config();
+    let (cancel_tx, mut cancel_rx) = oneshot::channel();
+    cancel_tx.send(()).unwrap();
+    let queued = service
+      .provider_chat_stream(
+        &ChatSlot::default(),
+        "tab-2",
+        "echo",
+        &provider,
+        &[],
+        &config,
+        &mut cancel_rx,
+        None,
+      )
+      .await;
+    assert!(matches!(queued, Err(AIError::Cancelled)));
+
+    // 对话以标签页 ID 排队，可按标签页取消
+    let queued = tokio::spawn({
+      let (service, provider) = (service.clone(), provider.clone());
+      async move {
+        let (_cancel_tx, mut cancel_rx) = oneshot::channel();
+        service
+          .provider_chat_stream(
+            &ChatSlot::default(),
+            "tab-3",
+            "echo",
+            &provider,
+            &[],
+            &model_config(),
+            &mut cancel_rx,
+            None,
+          )
+          .await
+          .map(|_| ())
+      }
+    });
+    while !service.cancel_request("tab-3") {
+      tokio::task::yield_now().await;
+    }
+    assert!(matches!(queued.await.unwrap(), Err(AIError::Cancelled)));
+  }
+}
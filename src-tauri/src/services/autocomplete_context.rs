@@ -0,0 +1,275 @@
+//! 辅助续写上下文：由后端根据文档内容与光标位置组织续写提示词所需的上下文。
+//!
+//! 取光标前后的文本、前后段落、标题大纲与当前章节、Markdown front matter 及文件类型，
+//! 按 token 预算分配给各部分，使不同文件类型的续写质量一致。HTML 先把标题转为
+//! Markdown 形式再去掉标签，光标偏移仍以传入的内容为准。
+
+use crate::services::ai_providers::DocumentOverview;
+use crate::services::word_count::is_cjk;
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+/// 默认 token 预算
+pub const AUTOCOMPLETE_TOKEN_BUDGET: usize = 1200;
+
+static HTML_HEADING: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)<h([1-6])\b[^>]*>").unwrap());
+static HTML_BLOCK_END: Lazy<Regex> =
+  Lazy::new(|| Regex::new(r"(?i)</(p|div|h[1-6]|li|blockquote|pre|tr)>|<br\s*/?>").unwrap());
+static HTML_TAG: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?s)<[^>]*>").unwrap());
+static BLANK_LINES: Lazy<Regex> = Lazy::new(|| Regex::new(r"\n\s*\n\s*").unwrap());
+
+#[derive(Debug, Clone)]
+pub struct AutocompleteContext {
+  pub context_before: String,
+  pub context_after: Option<String>,
+  /// 传给提示词的文档格式：md / html / t-docx / txt
+  pub document_format: String,
+  pub overview: DocumentOverview,
+}
+
+/// 文件扩展名对应的文档格式
+pub fn document_format(extension: &str) -> &'static str {
+  match extension.to_lowercase().as_str() {
+    "md" | "markdown" => "md",
+    "html" | "htm" => "html",
+    "docx" => "t-docx",
+    _ => "txt",
+  }
+}
+
+/// 从开头截取不超过 tokens 的文本
+fn head_tokens(text: &str, tokens: usize) -> &str {
+  let mut used = 0.0;
+  for (index, ch) in text.char_indices() {
+    used += if is_cjk(ch) { 1.0 } else { 0.25 };
+    if used > tokens as f64 {
+      return &text[..index];
+    }
+  }
+  text
+}
+
+/// 从末尾截取不超过 tokens 的文本
+fn tail_tokens(text: &str, tokens: usize) -> &str {
+  let mut used = 0.0;
+  for (index, ch) in text.char_indices().rev() {
+    used += if is_cjk(ch) { 1.0 } else { 0.25 };
+    if used > tokens as f64 {
+      return &text[index + ch.len_utf8()..];
+    }
+  }
+  text
+}
+
+/// UTF-16 偏移（前端字符串下标）对应的字节位置，超出时取末尾
+fn byte_offset(text: &str, utf16_offset: usize) -> usize {
+  let mut units = 0;
+  for (index, ch) in text.char_indices() {
+    if units >= utf16_offset {
+      return index;
+    }
+    units += ch.len_utf16();
+  }
+  text.len()
+}
+
+fn is_html(content: &str) -> bool {
+  let trimmed = content.trim_start();
+  trimmed.starts_with('<') && trimmed.contains("</")
+}
+
+/// HTML 转为段落文本，标题保留为 `#` 前缀
+fn html_text(html: &str) -> String {
+  let text = HTML_HEADING.replace_all(html, |c: &regex::Captures| {
+    format!("\n\n{} ", "#".repeat(c[1].parse().unwrap_or(1)))
+  });
+  let text = HTML_BLOCK_END.replace_all(&text, "\n\n");
+  HTML_TAG
+    .replace_all(&text, "")
+    .replace("&nbsp;", " ")
+    .replace("&lt;", "<")
+    .replace("&gt;", ">")
+    .replace("&amp;", "&")
+}
+
+/// 拆出 Markdown front matter，返回（压缩为一行的元数据，正文）
+fn split_front_matter(text: &str) -> (String, &str) {
+  let Some(rest) = text.strip_prefix("---\n") else {
+    return (String::new(), text);
+  };
+  let Some(end) = rest.find("\n---") else {
+    return (String::new(), text);
+  };
+  let body = rest[end + 4..]
+    .split_once('\n')
+    .map_or("", |(_, body)| body);
+  let fields: Vec<&str> = rest[..end]
+    .lines()
+    .map(str::trim)
+    .filter(|l| !l.is_empty() && !l.starts_with('#'))
+    .collect();
+  (fields.join("；"), body)
+}
+
+/// Markdown 标题：(层级, 标题)
+fn heading(line: &str) -> Option<(usize, &str)> {
+  let level = line.chars().take_while(|&c| c == '#').count();
+  let title = line[level..]
+    .strip_prefix(' ')?
+    .trim()
+    .trim_end_matches('#')
+    .trim();
+  ((1..=6).contains(&level) && !title.is_empty()).then_some((level, title))
+}
+
+fn headings(text: &str) -> Vec<(usize, &str)> {
+  let mut in_fence = false;
+  let mut result = Vec::new();
+  for line in text.lines() {
+    let trimmed = line.trim_start();
+    if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+      in_fence = !in_fence;
+    } else if !in_fence {
+      result.extend(heading(trimmed));
+    }
+  }
+  result
+}
+
+fn paragraphs(text: &str) -> Vec<&str> {
+  BLANK_LINES
+    .split(text)
+    .map(str::trim)
+    .filter(|p| !p.is_empty())
+    .collect()
+}
+
+/// 按光标位置组织续写上下文；cursor 为 UTF-16 偏移。content 可以是文件原文（HTML 会去掉
+/// 标签），也可以是编辑器导出的段落文本（标题以 `#` 开头、段落以空行分隔）
+pub fn build(
+  content: &str,
+  cursor: usize,
+  extension: &str,
+  token_budget: usize,
+) -> AutocompleteContext {
+  let format = document_format(extension);
+  let (before, after) = content.split_at(byte_offset(content, cursor));
+  let (before, after) = if is_html(content) {
+    (html_text(before), html_text(after))
+  } else {
+    (before.to_string(), after.to_string())
+  };
+  let (front_matter, before) = split_front_matter(&before);
+  let before = BLANK_LINES.replace_all(before, "\n\n");
+  let after = BLANK_LINES.replace_all(&after, "\n\n");
+  let (before, after) = (before.trim_start(), after.trim_end());
+
+  // 预算分配：上文 45%，下文 20%，大纲 10%，开头与结尾各 5%，前后段落各 5%，元数据 5%
+  let share = |percent: usize| (token_budget * percent / 100).max(1);
+
+  let mut sections: Vec<String> = Vec::new();
+  let mut current: Vec<&str> = Vec::new();
+  for (level, title) in headings(before) {
+    current.truncate(level - 1);
+    current.push(title);
+    sections.push(format!("{}{}", "  ".repeat(level - 1), title));
+  }
+  sections.extend(
+    headings(after)
+      .iter()
+      .map(|(level, title)| format!("{}{}", "  ".repeat(level - 1), title)),
+  );
+  let structure = if sections.is_empty() {
+    "无标题结构".to_string()
+  } else {
+    head_tokens(&sections.join("\n"), share(10))
+      .trim_end()
+      .to_string()
+  };
+
+  let before_paragraphs = paragraphs(before);
+  let after_paragraphs = paragraphs(after);
+  // 光标所在段落已包含在上下文中，前一段与后一段跳过它
+  let previous = before_paragraphs
+    .len()
+    .checked_sub(2)
+    .map(|i| before_paragraphs[i])
+    .unwrap_or_default();
+  let next = after_paragraphs.get(1).copied().unwrap_or_default();
+  let document_start = if before_paragraphs.len() > 2 {
+    head_tokens(before, share(5)).trim_end()
+  } else {
+    ""
+  };
+  let document_end = if after_paragraphs.len() > 2 {
+    tail_tokens(after, share(5)).trim_start()
+  } else {
+    ""
+  };
+
+  let context_after = head_tokens(after, share(20));
+  AutocompleteContext {
+    context_before: tail_tokens(before, share(45)).to_string(),
+    context_after: (!context_after.trim().is_empty()).then(|| context_after.to_string()),
+    document_format: format.to_string(),
+    overview: DocumentOverview {
+      document_start: document_start.to_string(),
+      document_end: document_end.to_string(),
+      document_structure: structure,
+      document_length: before.chars().count() + after.chars().count(),
+      current_section: if current.is_empty() {
+        "无".to_string()
+      } else {
+        current.join(" > ")
+      },
+      previous_paragraph: tail_tokens(previous, share(5)).to_string(),
+      next_paragraph: head_tokens(next, share(5)).to_string(),
+      front_matter: head_tokens(&front_matter, share(5)).to_string(),
+    },
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn builds_markdown_context_around_cursor() {
+    let content = "---\ntitle: 年度报告\ntags: [财务]\n---\n\n# 概述\n\n引言。\n\n## 收入\n\n\
+                   第一段。\n\n今年收入增长了|\n\n第三段。\n\n# 展望\n\n结尾。";
+    let cursor = content.find('|').unwrap();
+    let cursor = content[..cursor].encode_utf16().count();
+    let context = build(content, cursor, "md", AUTOCOMPLETE_TOKEN_BUDGET);
+    assert_eq!(context.document_format, "md");
+    assert!(context.context_before.ends_with("今年收入增长了"));
+    assert!(context.context_before.starts_with("# 概述"));
+    assert_eq!(
+      context.context_after.as_deref(),
+      Some("|\n\n第三段。\n\n# 展望\n\n结尾。")
+    );
+    let overview = &context.overview;
+    assert_eq!(overview.front_matter, "title: 年度报告；tags: [财务]");
+    assert_eq!(overview.current_section, "概述 > 收入");
+    assert_eq!(overview.document_structure, "概述\n  收入\n展望");
+    assert_eq!(overview.previous_paragraph, "第一段。");
+    assert_eq!(overview.next_paragraph, "第三段。");
+    assert_eq!(overview.document_end, "|\n\n第三段。\n\n# 展望\n\n结尾。");
+  }
+
+  #[test]
+  fn strips_html_and_respects_budget() {
+    let html = "<h1>标题</h1><p>第一段<b>加粗</b>文字</p><p>光标在这里</p><p>后文</p>";
+    let cursor = html[..html.find("在这里").unwrap()].encode_utf16().count();
+    let context = build(html, cursor, "html", AUTOCOMPLETE_TOKEN_BUDGET);
+    assert_eq!(context.context_before, "# 标题\n\n第一段加粗文字\n\n光标");
+    assert_eq!(context.context_after.as_deref(), Some("在这里\n\n后文"));
+    assert_eq!(context.overview.current_section, "标题");
+    assert_eq!(document_format("DOCX"), "t-docx");
+
+    let long = "字".repeat(2000);
+    let context = build(&long, 2000, "txt", 100);
+    assert_eq!(context.context_before.chars().count(), 45);
+    assert_eq!(context.context_after, None);
+    assert_eq!(tail_tokens("hello world", 1), "orld");
+  }
+}
@@ -0,0 +1,274 @@
+//! 未保存内容日志：前端每隔几秒把编辑器缓冲区的增量追加写入应用数据目录，
+//! 应用异常退出后启动时据此恢复未保存的内容。与自动保存相互独立，不改动原文件。
+//!
+//! 每个文件一个 `journal/<路径哈希>.jsonl`，首行为基准内容，其后每行一个增量；每次追加后 fsync。
+//! 增量位置以 UTF-16 码元计（与前端 JS 字符串下标一致）。日志过大时重放并压缩为一条基准记录。
+//! 崩溃时写了一半的末行在重放时忽略。文件保存或放弃修改后由前端删除对应日志。
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+const JOURNAL_EXTENSION: &str = "jsonl";
+/// 日志超过该大小时压缩
+const COMPACT_THRESHOLD_BYTES: u64 = 1024 * 1024;
+
+/// 一次编辑：从 `start` 起删除 `delete` 个 UTF-16 码元并插入 `insert`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BufferDelta {
+  pub start: usize,
+  pub delete: usize,
+  pub insert: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+enum JournalRecord {
+  Base {
+    path: String,
+    content: String,
+    timestamp: i64,
+  },
+  Delta {
+    #[serde(flatten)]
+    delta: BufferDelta,
+    timestamp: i64,
+  },
+}
+
+/// 可恢复的缓冲区
+#[derive(Debug, Clone, Serialize)]
+pub struct RecoverableBuffer {
+  pub path: String,
+  pub content: String,
+  /// 最后一次记录的时间（毫秒时间戳）
+  pub updated_at: i64,
+}
+
+pub struct BufferJournal {
+  dir: PathBuf,
+  write_lock: Mutex<()>,
+}
+
+impl BufferJournal {
+  pub fn new(dir: PathBuf) -> Self {
+    Self {
+      dir,
+      write_lock: Mutex::new(()),
+    }
+  }
+
+  fn journal_path(&self, path: &str) -> PathBuf {
+    let hash = Sha256::digest(path.as_bytes());
+    let name: String = hash[..16].iter().map(|b| format!("{:02x}", b)).collect();
+    self.dir.join(format!("{}.{}", name, JOURNAL_EXTENSION))
+  }
+
+  /// 追加增量；传入 `base` 时先用它重建日志（首次记录或前端重新同步）。
+  /// 没有基准记录时返回错误，前端应重新发送完整内容
+  pub fn append(
+    &self,
+    path: &str,
+    base: Option<&str>,
+    deltas: &[BufferDelta],
+  ) -> Result<(), String> {
+    let _guard = self.write_lock.lock().unwrap_or_else(|e| e.into_inner());
+    let journal = self.journal_path(path);
+    let now = chrono::Utc::now().timestamp_millis();
+
+    if let Some(content) = base {
+      let record = JournalRecord::Base {
+        path: path.to_string(),
+        content: content.to_string(),
+        timestamp: now,
+      };
+      write_records_atomically(&journal, &[record])?;
+    } else if !journal.is_file() {
+      return Err(format!("没有 {} 的基准内容，请重新发送完整内容", path));
+    }
+    if deltas.is_empty() {
+      return Ok(());
+    }
+
+    let mut lines = String::new();
+    for delta in deltas {
+      let record = JournalRecord::Delta {
+        delta: delta.clone(),
+        timestamp: now,
+      };
+      lines
+        .push_str(&serde_json::to_string(&record).map_err(|e| format!("序列化日志失败: {}", e))?);
+      lines.push('\n');
+    }
+    let mut file = OpenOptions::new()
+      .append(true)
+      .open(&journal)
+      .map_err(|e| format!("打开日志失败: {}", e))?;
+    file
+      .write_all(lines.as_bytes())
+      .and_then(|_| file.sync_data())
+      .map_err(|e| format!("写入日志失败: {}", e))?;
+
+    if file.metadata().map(|m| m.len()).unwrap_or(0) > COMPACT_THRESHOLD_BYTES {
+      if let Some(buffer) = replay(&journal) {
+        let record = JournalRecord::Base {
+          path: buffer.path,
+          content: buffer.content,
+          timestamp: buffer.updated_at,
+        };
+        write_records_atomically(&journal, &[record])?;
+      }
+    }
+    Ok(())
+  }
+
+  /// 删除文件的日志（已保存或放弃修改）
+  pub fn discard(&self, path: &str) -> Result<(), String> {
+    let _guard = self.write_lock.lock().unwrap_or_else(|e| e.into_inner());
+    match fs::remove_file(self.journal_path(path)) {
+      Ok(()) => Ok(()),
+      Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+      Err(e) => Err(format!("删除日志失败: {}", e)),
+    }
+  }
+
+  /// 重放全部日志；内容与磁盘上的文件相同（已保存）的日志直接删除
+  pub fn recoverable(&self) -> Vec<RecoverableBuffer> {
+    let _guard = self.write_lock.lock().unwrap_or_else(|e| e.into_inner());
+    let Ok(entries) = fs::read_dir(&self.dir) else {
+      return Vec::new();
+    };
+    let mut buffers = Vec::new();
+    for journal in entries.flatten().map(|e| e.path()) {
+      if journal.extension().and_then(|e| e.to_str()) != Some(JOURNAL_EXTENSION) {
+        continue;
+      }
+      match replay(&journal) {
+        Some(buffer) if fs::read_to_string(&buffer.path).ok().as_ref() != Some(&buffer.content) => {
+          buffers.push(buffer)
+        }
+        _ => {
+          let _ = fs::remove_file(&journal);
+        }
+      }
+    }
+    buffers.sort_by_key(|b| std::cmp::Reverse(b.updated_at));
+    buffers
+  }
+}
+
+/// 写入临时文件后重命名，避免压缩或重建时崩溃留下残缺日志
+fn write_records_atomically(journal: &Path, records: &[JournalRecord]) -> Result<(), String> {
+  if let Some(parent) = journal.parent() {
+    fs::create_dir_all(parent).map_err(|e| format!("创建日志目录失败: {}", e))?;
+  }
+  let temp = journal.with_extension("tmp");
+  let mut file = File::create(&temp).map_err(|e| format!("创建日志失败: {}", e))?;
+  for record in records {
+    let line = serde_json::to_string(record).map_err(|e| format!("序列化日志失败: {}", e))?;
+    writeln!(file, "{}", line).map_err(|e| format!("写入日志失败: {}", e))?;
+  }
+  file
+    .sync_all()
+    .map_err(|e| format!("写入日志失败: {}", e))?;
+  fs::rename(&temp, journal).map_err(|e| format!("写入日志失败: {}", e))
+}
+
+/// 按顺序应用基准与增量；遇到损坏的行（通常是崩溃时未写完的末行）或越界增量时停止
+fn replay(journal: &Path) -> Option<RecoverableBuffer> {
+  let content = fs::read_to_string(journal).ok()?;
+  let mut lines = content.lines();
+  let JournalRecord::Base {
+    path,
+    content,
+    timestamp,
+  } = serde_json::from_str(lines.next()?).ok()?
+  else {
+    return None;
+  };
+
+  let mut buffer: Vec<u16> = content.encode_utf16().collect();
+  let mut updated_at = timestamp;
+  for line in lines {
+    let Ok(JournalRecord::Delta { delta, timestamp }) = serde_json::from_str(line) else {
+      tracing::warn!("日志 {} 存在损坏的记录，忽略之后的内容", journal.display());
+      break;
+    };
+    let end = delta.start.saturating_add(delta.delete);
+    if end > buffer.len() {
+      tracing::warn!("日志 {} 的增量越界，忽略之后的内容", journal.display());
+      break;
+    }
+    buffer.splice(delta.start..end, delta.insert.encode_utf16());
+    updated_at = timestamp;
+  }
+
+  Some(RecoverableBuffer {
+    path,
+    content: String::from_utf16_lossy(&buffer),
+    updated_at,
+  })
+}
+
+pub fn journal_dir() -> PathBuf {
+  dirs::data_dir()
+    .unwrap_or_else(std::env::temp_dir)
+    .join("binder")
+    .join("journal")
+}
+
+static JOURNAL: Lazy<BufferJournal> = Lazy::new(|| BufferJournal::new(journal_dir()));
+
+pub fn journal() -> &'static BufferJournal {
+  &JOURNAL
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn delta(start: usize, delete: usize, insert: &str) -> BufferDelta {
+    BufferDelta {
+      start,
+      delete,
+      insert: insert.to_string(),
+    }
+  }
+
+  #[test]
+  fn replays_deltas_and_skips_torn_tail() {
+    let dir = std::env::temp_dir().join(format!("binder-journal-{}", uuid::Uuid::new_v4()));
+    let journal = BufferJournal::new(dir.clone());
+    let path = dir.join("笔记.md").to_string_lossy().to_string();
+
+    assert!(journal.append(&path, None, &[delta(0, 0, "x")]).is_err());
+    journal
+      .append(&path, Some("你好 world"), &[delta(3, 5, "😀")])
+      .unwrap();
+    journal.append(&path, None, &[delta(5, 0, "!")]).unwrap();
+    // 模拟崩溃时写了一半的末行
+    let mut file = OpenOptions::new()
+      .append(true)
+      .open(journal.journal_path(&path))
+      .unwrap();
+    file.write_all(b"{\"op\":\"delta\",\"start\":0,").unwrap();
+
+    let buffers = journal.recoverable();
+    assert_eq!(buffers.len(), 1);
+    assert_eq!(buffers[0].content, "你好 😀!");
+
+    // 内容与磁盘一致时视为已保存，日志被清理
+    fs::write(&path, "你好 😀!").unwrap();
+    assert!(journal.recoverable().is_empty());
+    assert!(!journal.journal_path(&path).exists());
+
+    journal.append(&path, Some("a"), &[]).unwrap();
+    journal.discard(&path).unwrap();
+    assert!(journal.recoverable().is_empty());
+    let _ = fs::remove_dir_all(&dir);
+  }
+}
@@ -0,0 +1,232 @@
+//! 对话流登记：每个标签页同时只有一个对话流，登记其任务句柄、当前轮次的取消通道、
+//! 取消标志与已输出的文本。
+//!
+//! 每次登记分配新的 stream_id，取消、续轮与清理都只作用于 stream_id 相同的登记：
+//! 同一标签页重新发起对话时旧流被取消，旧流收尾时不会清掉新流的登记；
+//! 多个标签页同时对话互不影响。
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use tokio::sync::oneshot;
+use tokio::task::AbortHandle;
+
+struct TabStream {
+  stream_id: u64,
+  cancelled: bool,
+  /// 当前轮次的取消标志，流处理循环轮询
+  cancel_flag: Arc<Mutex<bool>>,
+  /// 当前轮次的取消通道，交给提供商中断请求
+  cancel_tx: Option<oneshot::Sender<()>>,
+  task: Option<AbortHandle>,
+  accumulated: String,
+}
+
+impl TabStream {
+  fn signal_cancel(&mut self) {
+    self.cancelled = true;
+    *self.cancel_flag.lock().unwrap_or_else(|e| e.into_inner()) = true;
+    if let Some(cancel_tx) = self.cancel_tx.take() {
+      let _ = cancel_tx.send(());
+    }
+  }
+}
+
+#[derive(Default)]
+pub struct ChatStreamRegistry {
+  streams: Mutex<HashMap<String, TabStream>>,
+  next_id: AtomicU64,
+}
+
+impl ChatStreamRegistry {
+  fn lock(&self) -> MutexGuard<'_, HashMap<String, TabStream>> {
+    self.streams.lock().unwrap_or_else(|e| e.into_inner())
+  }
+
+  /// 登记标签页的新对话流；该标签页已有流时先取消旧流
+  pub fn start(self: &Arc<Self>, tab_id: &str) -> ChatStreamHandle {
+    let stream_id = self.next_id.fetch_add(1, Ordering::Relaxed);
+    let previous = self.lock().insert(
+      tab_id.to_string(),
+      TabStream {
+        stream_id,
+        cancelled: false,
+        cancel_flag: Arc::new(Mutex::new(false)),
+        cancel_tx: None,
+        task: None,
+        accumulated: String::new(),
+      },
+    );
+    if let Some(mut previous) = previous {
+      tracing::info!("标签页已有对话流，取消旧流: tab_id={}", tab_id);
+      previous.signal_cancel();
+    }
+    ChatStreamHandle {
+      registry: self.clone(),
+      tab_id: tab_id.to_string(),
+      stream_id,
+    }
+  }
+
+  /// 通知标签页的对话流停止，返回被取消流的 stream_id；登记保留到流自行收尾
+  pub fn cancel(&self, tab_id: &str) -> Option<u64> {
+    let mut streams = self.lock();
+    let stream = streams.get_mut(tab_id)?;
+    stream.signal_cancel();
+    Some(stream.stream_id)
+  }
+
+  /// 强制终止仍未收尾的流并移除登记，返回已输出的文本；流已结束或被替换时返回 None
+  pub fn abort(&self, tab_id: &str, stream_id: u64) -> Option<String> {
+    let mut streams = self.lock();
+    if streams.get(tab_id)?.stream_id != stream_id {
+      return None;
+    }
+    let stream = streams.remove(tab_id)?;
+    if let Some(task) = stream.task {
+      task.abort();
+    }
+    Some(stream.accumulated)
+  }
+
+  #[cfg(test)]
+  pub fn is_streaming(&self, tab_id: &str) -> bool {
+    self.lock().contains_key(tab_id)
+  }
+}
+
+/// 单个对话流持有的登记句柄
+#[derive(Clone)]
+pub struct ChatStreamHandle {
+  registry: Arc<ChatStreamRegistry>,
+  tab_id: String,
+  stream_id: u64,
+}
+
+impl ChatStreamHandle {
+  /// 在本流的登记上操作；登记已被替换或移除时返回 None
+  fn with_stream<T>(&self, f: impl FnOnce(&mut TabStream) -> T) -> Option<T> {
+    let mut streams = self.registry.lock();
+    streams
+      .get_mut(&self.tab_id)
+      .filter(|s| s.stream_id == self.stream_id)
+      .map(f)
+  }
+
+  /// 当前轮次的取消标志
+  pub fn cancel_flag(&self) -> Arc<Mutex<bool>> {
+    self
+      .with_stream(|s| s.cancel_flag.clone())
+      .unwrap_or_else(|| Arc::new(Mutex::new(true)))
+  }
+
+  /// 新一轮对话换用新的取消标志；已取消时新标志直接为 true
+  pub fn renew_cancel_flag(&self) -> Arc<Mutex<bool>> {
+    self
+      .with_stream(|s| {
+        s.cancel_flag = Arc::new(Mutex::new(s.cancelled));
+        s.cancel_flag.clone()
+      })
+      .unwrap_or_else(|| Arc::new(Mutex::new(true)))
+  }
+
+  /// 登记本轮的取消通道；已取消时立即发送取消信号
+  pub fn set_cancel_sender(&self, cancel_tx: oneshot::Sender<()>) {
+    let rejected = self
+      .with_stream(|s| {
+        if s.cancelled {
+          Some(cancel_tx)
+        } else {
+          s.cancel_tx = Some(cancel_tx);
+          None
+        }
+      })
+      .flatten();
+    if let Some(cancel_tx) = rejected {
+      let _ = cancel_tx.send(());
+    }
+  }
+
+  pub fn attach_task(&self, task: AbortHandle) {
+    self.with_stream(|s| s.task = Some(task));
+  }
+
+  pub fn push_text(&self, text: &str) {
+    self.with_stream(|s| s.accumulated.push_str(text));
+  }
+
+  /// 已被取消，或登记已被新流替换
+  pub fn is_cancelled(&self) -> bool {
+    self.with_stream(|s| s.cancelled).unwrap_or(true)
+  }
+
+  /// 流收尾时移除自己的登记
+  pub fn finish(&self) {
+    let mut streams = self.registry.lock();
+    if streams
+      .get(&self.tab_id)
+      .is_some_and(|s| s.stream_id == self.stream_id)
+    {
+      streams.remove(&self.tab_id);
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn is_set(flag: &Arc<Mutex<bool>>) -> bool {
+    *flag.lock().unwrap()
+  }
+
+  #[test]
+  fn cancels_only_the_requested_tab() {
+    let registry = Arc::new(ChatStreamRegistry::default());
+    let first = registry.start("tab-1");
+    let second = registry.start("tab-2");
+    let (tx, mut rx) = oneshot::channel();
+    first.set_cancel_sender(tx);
+    let second_flag = second.cancel_flag();
+    first.push_text("部分");
+    first.push_text("回复");
+
+    let stream_id = registry.cancel("tab-1").unwrap();
+    assert!(rx.try_recv().is_ok());
+    assert!(first.is_cancelled());
+    assert!(is_set(&first.cancel_flag()));
+    assert!(!second.is_cancelled());
+    assert!(!is_set(&second_flag));
+
+    // 取消对之后的轮次同样生效
+    assert!(is_set(&first.renew_cancel_flag()));
+    let (tx, mut rx) = oneshot::channel();
+    first.set_cancel_sender(tx);
+    assert!(rx.try_recv().is_ok());
+
+    assert_eq!(
+      registry.abort("tab-1", stream_id).as_deref(),
+      Some("部分回复")
+    );
+    assert!(!registry.is_streaming("tab-1"));
+    assert!(registry.is_streaming("tab-2"));
+    assert_eq!(registry.cancel("tab-3"), None);
+  }
+
+  #[test]
+  fn restarting_a_tab_supersedes_the_old_stream() {
+    let registry = Arc::new(ChatStreamRegistry::default());
+    let old = registry.start("tab-1");
+    let old_flag = old.cancel_flag();
+    let new = registry.start("tab-1");
+    assert!(is_set(&old_flag));
+    assert!(old.is_cancelled());
+
+    // 旧流收尾不影响新流的登记
+    old.finish();
+    assert!(registry.is_streaming("tab-1"));
+    assert!(!new.is_cancelled());
+    new.finish();
+    assert!(!registry.is_streaming("tab-1"));
+  }
+}
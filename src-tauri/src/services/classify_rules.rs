@@ -0,0 +1,272 @@
+//! 用户自定义分类规则：读取 `.binder/classify_rules.json`，`classify_files` / `organize_files`
+//! 先按规则匹配，未命中的文件再交给 AI 分类。
+//!
+//! 规则按顺序匹配，第一条满足全部条件的规则生效；未填写的条件视为满足。示例：
+//!
+//! ```json
+//! {
+//!   "rules": [
+//!     { "name": "发票", "extensions": ["pdf"], "name_regex": "发票|invoice", "folder": "财务/发票", "tag": "发票" },
+//!     { "name": "旧截图", "glob": "截图/**", "older_than_days": 90, "folder": "归档/截图" }
+//!   ]
+//! }
+//! ```
+
+use crate::services::file_classifier::FileClassification;
+use crate::utils::glob_patterns::{build_glob_set, relative_slash_path};
+use chrono::{DateTime, Local, NaiveDate};
+use globset::GlobSet;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::{Component, Path, PathBuf};
+use std::time::SystemTime;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ClassifyRulesFile {
+  #[serde(default)]
+  pub rules: Vec<ClassifyRule>,
+}
+
+/// 单条规则：匹配条件 + 目标文件夹 / 标签（至少填写一个）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClassifyRule {
+  pub name: Option<String>,
+  /// 相对工作区的路径 glob，如 `截图/**`、`**/*.log`
+  pub glob: Option<String>,
+  /// 扩展名（不含点，忽略大小写）
+  pub extensions: Vec<String>,
+  /// 文件名（含扩展名）正则
+  pub name_regex: Option<String>,
+  /// 文件大小范围（字节）
+  pub min_size: Option<u64>,
+  pub max_size: Option<u64>,
+  /// 修改日期范围（YYYY-MM-DD，含当天）
+  pub modified_after: Option<String>,
+  pub modified_before: Option<String>,
+  /// 最后修改距今超过的天数
+  pub older_than_days: Option<u64>,
+  /// 目标文件夹（相对工作区）
+  pub folder: Option<String>,
+  pub tag: Option<String>,
+}
+
+struct CompiledRule {
+  label: String,
+  glob: Option<GlobSet>,
+  extensions: Vec<String>,
+  name_regex: Option<Regex>,
+  min_size: Option<u64>,
+  max_size: Option<u64>,
+  modified_after: Option<NaiveDate>,
+  modified_before: Option<NaiveDate>,
+  older_than_days: Option<u64>,
+  folder: Option<String>,
+  tag: Option<String>,
+}
+
+/// 编译后的规则集
+pub struct ClassifyRuleSet {
+  workspace_path: PathBuf,
+  rules: Vec<CompiledRule>,
+}
+
+impl ClassifyRuleSet {
+  pub fn rules_path(workspace_path: &Path) -> PathBuf {
+    workspace_path.join(".binder").join("classify_rules.json")
+  }
+
+  /// 加载工作区规则；文件不存在时返回空规则集，格式错误时返回错误
+  pub fn load(workspace_path: &Path) -> Result<Self, String> {
+    let path = Self::rules_path(workspace_path);
+    let file = if path.exists() {
+      let content =
+        std::fs::read_to_string(&path).map_err(|e| format!("读取分类规则失败: {}", e))?;
+      serde_json::from_str(&content).map_err(|e| format!("解析分类规则失败: {}", e))?
+    } else {
+      ClassifyRulesFile::default()
+    };
+    Self::compile(workspace_path, &file)
+  }
+
+  pub fn compile(workspace_path: &Path, file: &ClassifyRulesFile) -> Result<Self, String> {
+    let rules = file
+      .rules
+      .iter()
+      .enumerate()
+      .map(|(index, rule)| {
+        let label = rule
+          .name
+          .clone()
+          .unwrap_or_else(|| format!("规则 {}", index + 1));
+        let err = |msg: String| format!("分类规则「{}」无效: {}", label, msg);
+
+        let folder = rule
+          .folder
+          .as_deref()
+          .map(str::trim)
+          .filter(|f| !f.is_empty())
+          .map(|f| f.trim_matches('/').to_string());
+        if let Some(folder) = &folder {
+          let escapes = Path::new(folder)
+            .components()
+            .any(|c| !matches!(c, Component::Normal(_)));
+          if escapes {
+            return Err(err(format!(
+              "目标文件夹必须是工作区内的相对路径: {}",
+              folder
+            )));
+          }
+        }
+        let tag = rule.tag.clone().filter(|t| !t.trim().is_empty());
+        if folder.is_none() && tag.is_none() {
+          return Err(err("至少需要填写 folder 或 tag".to_string()));
+        }
+
+        let parse_date = |value: &Option<String>| {
+          value
+            .as_deref()
+            .map(|d| {
+              NaiveDate::parse_from_str(d.trim(), "%Y-%m-%d")
+                .map_err(|_| err(format!("日期格式应为 YYYY-MM-DD: {}", d)))
+            })
+            .transpose()
+        };
+
+        Ok(CompiledRule {
+          glob: rule
+            .glob
+            .as_ref()
+            .map(|g| build_glob_set(std::slice::from_ref(g)))
+            .transpose()
+            .map_err(err)?,
+          extensions: rule
+            .extensions
+            .iter()
+            .map(|e| e.trim().trim_start_matches('.').to_lowercase())
+            .collect(),
+          name_regex: rule
+            .name_regex
+            .as_deref()
+            .map(Regex::new)
+            .transpose()
+            .map_err(|e| err(format!("文件名正则错误: {}", e)))?,
+          min_size: rule.min_size,
+          max_size: rule.max_size,
+          modified_after: parse_date(&rule.modified_after)?,
+          modified_before: parse_date(&rule.modified_before)?,
+          older_than_days: rule.older_than_days,
+          folder,
+          tag,
+          label,
+        })
+      })
+      .collect::<Result<Vec<_>, String>>()?;
+
+    Ok(Self {
+      workspace_path: workspace_path.to_path_buf(),
+      rules,
+    })
+  }
+
+  /// 返回第一条命中规则给出的分类；没有规则命中时返回 None
+  pub fn classify(&self, path: &Path) -> Option<FileClassification> {
+    let metadata = std::fs::metadata(path).ok();
+    let modified = metadata.as_ref().and_then(|m| m.modified().ok());
+    let rule = self
+      .rules
+      .iter()
+      .find(|rule| self.matches(rule, path, metadata.as_ref().map(|m| m.len()), modified))?;
+
+    Some(FileClassification {
+      file_path: path.to_string_lossy().to_string(),
+      category: rule.folder.clone().unwrap_or_default(),
+      reason: format!("匹配分类规则「{}」", rule.label),
+      confidence: 1.0,
+      tag: rule.tag.clone(),
+      content_type: None,
+    })
+  }
+
+  fn matches(
+    &self,
+    rule: &CompiledRule,
+    path: &Path,
+    size: Option<u64>,
+    modified: Option<SystemTime>,
+  ) -> bool {
+    let file_name = path
+      .file_name()
+      .map(|n| n.to_string_lossy().to_string())
+      .unwrap_or_default();
+    let ext = path
+      .extension()
+      .map(|e| e.to_string_lossy().to_lowercase())
+      .unwrap_or_default();
+    let modified_date = modified.map(|t| DateTime::<Local>::from(t).date_naive());
+    let age_days = modified
+      .and_then(|t| SystemTime::now().duration_since(t).ok())
+      .map(|d| d.as_secs() / 86_400);
+
+    rule.glob.as_ref().map_or(true, |g| {
+      g.is_match(relative_slash_path(path, &self.workspace_path))
+    }) && (rule.extensions.is_empty() || rule.extensions.contains(&ext))
+      && rule
+        .name_regex
+        .as_ref()
+        .map_or(true, |r| r.is_match(&file_name))
+      && rule
+        .min_size
+        .map_or(true, |min| size.is_some_and(|s| s >= min))
+      && rule
+        .max_size
+        .map_or(true, |max| size.is_some_and(|s| s <= max))
+      && rule
+        .modified_after
+        .map_or(true, |after| modified_date.is_some_and(|d| d >= after))
+      && rule
+        .modified_before
+        .map_or(true, |before| modified_date.is_some_and(|d| d <= before))
+      && rule
+        .older_than_days
+        .map_or(true, |days| age_days.is_some_and(|age| age >= days))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn first_matching_rule_wins() {
+    let workspace =
+      std::env::temp_dir().join(format!("binder_classify_rules_{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(workspace.join("inbox")).unwrap();
+    let invoice = workspace.join("inbox/2024-发票.PDF");
+    let notes = workspace.join("inbox/notes.md");
+    std::fs::write(&invoice, vec![0u8; 2048]).unwrap();
+    std::fs::write(&notes, "# 笔记").unwrap();
+
+    let file: ClassifyRulesFile = serde_json::from_str(
+      r#"{ "rules": [
+        { "name": "大文件", "min_size": 1048576, "folder": "大文件" },
+        { "name": "发票", "glob": "inbox/**", "extensions": [".pdf"], "name_regex": "发票", "folder": "财务/发票", "tag": "发票" },
+        { "name": "未来", "modified_after": "2999-01-01", "tag": "未来" }
+      ] }"#,
+    )
+    .unwrap();
+    let rules = ClassifyRuleSet::compile(&workspace, &file).unwrap();
+
+    let hit = rules.classify(&invoice).unwrap();
+    assert_eq!(hit.category, "财务/发票");
+    assert_eq!(hit.tag.as_deref(), Some("发票"));
+    assert!(hit.reason.contains("发票"));
+    assert!(rules.classify(&notes).is_none());
+
+    let escaping: ClassifyRulesFile =
+      serde_json::from_str(r#"{ "rules": [{ "folder": "../outside" }] }"#).unwrap();
+    assert!(ClassifyRuleSet::compile(&workspace, &escaping).is_err());
+
+    let _ = std::fs::remove_dir_all(&workspace);
+  }
+}
@@ -41,9 +41,10 @@ impl ColumnService {
     for cap in cols_pattern.captures_iter(xml) {
       // 限制节的数量（日常办公场景）
       if section_count >= MAX_SECTIONS {
-        eprintln!(
+        tracing::warn!(
           "警告：文档包含超过 {} 个节，只处理前 {} 个节的分栏设置",
-          MAX_SECTIONS, MAX_SECTIONS
+          MAX_SECTIONS,
+          MAX_SECTIONS
         );
         break;
       }
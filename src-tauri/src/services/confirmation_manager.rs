@@ -90,9 +90,11 @@ impl ConfirmationManager {
           OperationType::Create
         }
       }
-      "read_file" | "list_files" | "search_files" => OperationType::Query,
-      "create_folder" => OperationType::Create,
-      "update_file" => OperationType::SimpleModify,
+      "read_file" | "read_document" | "list_files" | "list_directory" | "search_files"
+      | "search_workspace" | "search_memories" => OperationType::Query,
+      "create_folder" | "copy_file" | "create_docx_from_markdown" => OperationType::Create,
+      "update_file" | "apply_patch" => OperationType::SimpleModify,
+      "run_command" => OperationType::HighRiskOperation,
       _ => OperationType::SimpleModify,
     }
   }
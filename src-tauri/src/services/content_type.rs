@@ -0,0 +1,216 @@
+//! 文档内容类型识别：同为 .pdf / .docx 的发票、合同、会议纪要、扫描件，按正文关键词与版面特征区分。
+//!
+//! 启发式结果优先；启发式无法判断时由 AI 分类一并给出（见 `FileClassifierService`）。
+
+use crate::services::pandoc_service::PandocService;
+use crate::services::pdf_text::extract_pdf_text;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContentType {
+  Invoice,
+  Contract,
+  MeetingNotes,
+  /// 只有图像、没有文本层的扫描件
+  ScannedDocument,
+}
+
+impl ContentType {
+  pub const ALL: [ContentType; 4] = [
+    ContentType::Invoice,
+    ContentType::Contract,
+    ContentType::MeetingNotes,
+    ContentType::ScannedDocument,
+  ];
+
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      ContentType::Invoice => "invoice",
+      ContentType::Contract => "contract",
+      ContentType::MeetingNotes => "meeting_notes",
+      ContentType::ScannedDocument => "scanned_document",
+    }
+  }
+
+  pub fn parse(value: &str) -> Option<Self> {
+    let value = value.trim().to_lowercase();
+    Self::ALL.into_iter().find(|t| t.as_str() == value)
+  }
+
+  /// 关键词（小写）；命中越多越可信
+  fn keywords(&self) -> &'static [&'static str] {
+    match self {
+      ContentType::Invoice => &[
+        "发票",
+        "增值税",
+        "纳税人识别号",
+        "价税合计",
+        "开票日期",
+        "税额",
+        "invoice",
+        "bill to",
+        "amount due",
+        "tax id",
+      ],
+      ContentType::Contract => &[
+        "合同",
+        "协议",
+        "甲方",
+        "乙方",
+        "违约",
+        "签订",
+        "争议解决",
+        "agreement",
+        "party a",
+        "hereinafter",
+        "governing law",
+        "terms and conditions",
+      ],
+      ContentType::MeetingNotes => &[
+        "会议纪要",
+        "会议记录",
+        "参会人",
+        "与会",
+        "议题",
+        "待办",
+        "meeting notes",
+        "minutes",
+        "attendees",
+        "agenda",
+        "action items",
+      ],
+      ContentType::ScannedDocument => &[],
+    }
+  }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContentTypeGuess {
+  pub content_type: ContentType,
+  pub confidence: f64,
+}
+
+/// 用于识别的文档内容
+#[derive(Debug, Default)]
+pub struct ContentProbe {
+  pub text: String,
+  /// PDF 每一页都没有文本层（需要 OCR）
+  pub image_only: bool,
+}
+
+/// 读取文档文本：PDF 取文本层，Office / ODF 经 Pandoc 提取，其余按 UTF-8 文本读取
+pub fn probe_content(path: &Path) -> Result<ContentProbe, String> {
+  let ext = path
+    .extension()
+    .and_then(|e| e.to_str())
+    .map(|e| e.to_lowercase())
+    .unwrap_or_default();
+  match ext.as_str() {
+    "pdf" => {
+      let extraction = extract_pdf_text(path, None)?;
+      let image_only = !extraction.encrypted
+        && !extraction.pages.is_empty()
+        && extraction.pages.iter().all(|p| p.layout.needs_ocr);
+      Ok(ContentProbe {
+        text: extraction.full_text(),
+        image_only,
+      })
+    }
+    "docx" | "doc" | "odt" | "rtf" => Ok(ContentProbe {
+      text: PandocService::new().extract_plain_text(path)?,
+      image_only: false,
+    }),
+    _ => Ok(ContentProbe {
+      text: std::fs::read_to_string(path).map_err(|e| format!("读取文件失败: {}", e))?,
+      image_only: false,
+    }),
+  }
+}
+
+/// 按关键词与版面特征判断内容类型；证据不足时返回 None
+pub fn detect_content_type(path: &Path, probe: &ContentProbe) -> Option<ContentTypeGuess> {
+  if probe.image_only {
+    return Some(ContentTypeGuess {
+      content_type: ContentType::ScannedDocument,
+      confidence: 0.9,
+    });
+  }
+
+  // 关键词多出现在开头，只看前 5000 字符
+  let text: String = probe
+    .text
+    .chars()
+    .take(5000)
+    .collect::<String>()
+    .to_lowercase();
+  let file_name = path
+    .file_stem()
+    .map(|n| n.to_string_lossy().to_lowercase())
+    .unwrap_or_default();
+
+  ContentType::ALL
+    .into_iter()
+    .map(|content_type| {
+      let keywords = content_type.keywords();
+      let mut score = keywords.iter().filter(|k| text.contains(*k)).count();
+      // 文件名命中关键词算两条证据
+      if keywords.iter().any(|k| file_name.contains(k)) {
+        score += 2;
+      }
+      (content_type, score)
+    })
+    .filter(|(_, score)| *score >= 2)
+    .max_by_key(|(_, score)| *score)
+    .map(|(content_type, score)| ContentTypeGuess {
+      content_type,
+      confidence: (0.5 + 0.1 * score as f64).min(0.95),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn detect(name: &str, text: &str) -> Option<ContentType> {
+    let probe = ContentProbe {
+      text: text.to_string(),
+      image_only: false,
+    };
+    detect_content_type(Path::new(name), &probe).map(|g| g.content_type)
+  }
+
+  #[test]
+  fn detects_types_from_keywords_and_layout() {
+    assert_eq!(
+      detect(
+        "scan_001.pdf",
+        "增值税专用发票 开票日期：2024年3月1日 价税合计 ¥1,200"
+      ),
+      Some(ContentType::Invoice)
+    );
+    assert_eq!(
+      detect("服务合同.docx", "本合同由以下双方签订"),
+      Some(ContentType::Contract)
+    );
+    assert_eq!(
+      detect("0312.md", "# 周会\n参会人：张三、李四\n## 议题\n## 待办"),
+      Some(ContentType::MeetingNotes)
+    );
+    assert_eq!(detect("notes.md", "今天读了一本书，关于合同法"), None);
+
+    let scan = ContentProbe {
+      text: String::new(),
+      image_only: true,
+    };
+    assert_eq!(
+      detect_content_type(Path::new("x.pdf"), &scan).map(|g| g.content_type),
+      Some(ContentType::ScannedDocument)
+    );
+    assert_eq!(
+      ContentType::parse(" Meeting_Notes "),
+      Some(ContentType::MeetingNotes)
+    );
+  }
+}
@@ -0,0 +1,478 @@
+//! 用户自定义工具：从 `.binder/tools/*.toml` 加载。
+//!
+//! 每个文件声明一个工具（名称、描述、JSON Schema 参数与动作），动作可以是 shell 命令模板
+//! 或 HTTP 请求。shell 命令中的 `{{参数名}}` 替换为环境变量引用，参数值经环境变量传入、
+//! 不拼进命令文本；URL 与请求体中的占位符分别做百分号编码和 JSON 字符串内容转义。
+//! shell 动作只在用户于应用设置中允许该工作区执行命令后可用，且执行前总是需要用户确认；
+//! HTTP 动作默认需要确认，只有声明 `confirm = false` 且用户在应用设置中允许后才免确认。
+
+use crate::services::ai_providers::ToolDefinition;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+static TOOL_NAME: Lazy<Regex> = Lazy::new(|| Regex::new(r"^[A-Za-z][A-Za-z0-9_]{0,63}$").unwrap());
+static PLACEHOLDER: Lazy<Regex> =
+  Lazy::new(|| Regex::new(r"\{\{\s*([A-Za-z_][A-Za-z0-9_]*)\s*\}\}").unwrap());
+
+/// 自定义工具声明
+#[derive(Debug, Clone, Deserialize)]
+pub struct CustomToolSpec {
+  pub name: String,
+  pub description: String,
+  /// 参数的 JSON Schema（`type = "object"`）
+  #[serde(default = "default_parameters")]
+  pub parameters: serde_json::Value,
+  /// 声明为 false 时请求免确认执行，还需用户在应用设置中允许（shell 动作总是需要确认）
+  #[serde(default = "default_confirm")]
+  pub confirm: bool,
+  pub action: CustomToolAction,
+}
+
+/// 自定义工具的动作
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum CustomToolAction {
+  /// 在工作区内执行 shell 命令模板
+  Shell {
+    command: String,
+    /// 工作目录（相对工作区根目录）
+    cwd: Option<String>,
+    timeout_secs: Option<u64>,
+  },
+  /// 发送 HTTP 请求
+  Http {
+    #[serde(default = "default_method")]
+    method: String,
+    url: String,
+    #[serde(default)]
+    headers: HashMap<String, String>,
+    body: Option<String>,
+    timeout_secs: Option<u64>,
+  },
+}
+
+fn default_parameters() -> serde_json::Value {
+  serde_json::json!({ "type": "object", "properties": {} })
+}
+
+fn default_confirm() -> bool {
+  true
+}
+
+fn default_method() -> String {
+  "GET".to_string()
+}
+
+/// 模板占位符的转义方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TemplateEscape {
+  Url,
+  Json,
+  /// 仅去除换行（用于请求头）
+  Plain,
+}
+
+impl CustomToolSpec {
+  pub fn to_definition(&self) -> ToolDefinition {
+    ToolDefinition {
+      name: self.name.clone(),
+      description: self.description.clone(),
+      parameters: self.parameters.clone(),
+    }
+  }
+
+  pub fn is_shell(&self) -> bool {
+    matches!(self.action, CustomToolAction::Shell { .. })
+  }
+
+  /// shell 动作总是需要确认；其余动作声明 `confirm = false` 且用户已在应用设置中允许时免确认，
+  /// 工作区内的声明文件无法自行取消确认
+  pub fn requires_confirmation(&self, workspace_path: &Path) -> bool {
+    self.confirm
+      || self.is_shell()
+      || !crate::services::settings_service::custom_tool_approved(workspace_path, &self.name)
+  }
+
+  fn declared_parameters(&self) -> Vec<String> {
+    self
+      .parameters
+      .get("properties")
+      .and_then(|p| p.as_object())
+      .map(|props| props.keys().cloned().collect())
+      .unwrap_or_default()
+  }
+
+  fn templates(&self) -> Vec<&str> {
+    match &self.action {
+      CustomToolAction::Shell { command, .. } => vec![command.as_str()],
+      CustomToolAction::Http {
+        url, headers, body, ..
+      } => std::iter::once(url.as_str())
+        .chain(headers.values().map(String::as_str))
+        .chain(body.as_deref())
+        .collect(),
+    }
+  }
+
+  /// 校验声明：名称合法且不与内置工具冲突，模板只引用已声明的参数
+  pub fn validate(&self, builtin_names: &[String]) -> Result<(), String> {
+    if !TOOL_NAME.is_match(&self.name) {
+      return Err(format!(
+        "工具名 {} 不合法（字母开头，仅含字母、数字、下划线）",
+        self.name
+      ));
+    }
+    if builtin_names.iter().any(|n| n == &self.name) {
+      return Err(format!("工具名 {} 与内置工具冲突", self.name));
+    }
+    if self.description.trim().is_empty() {
+      return Err(format!("工具 {} 缺少 description", self.name));
+    }
+    if self.parameters.get("type").and_then(|t| t.as_str()) != Some("object") {
+      return Err(format!(
+        "工具 {} 的 parameters 必须是 type = \"object\"",
+        self.name
+      ));
+    }
+
+    match &self.action {
+      CustomToolAction::Shell { command, .. } if command.trim().is_empty() => {
+        return Err(format!("工具 {} 的 command 不能为空", self.name));
+      }
+      CustomToolAction::Http { method, url, .. } => {
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+          return Err(format!(
+            "工具 {} 的 url 必须以 http:// 或 https:// 开头",
+            self.name
+          ));
+        }
+        let method = method.to_ascii_uppercase();
+        if !["GET", "POST", "PUT", "PATCH", "DELETE"].contains(&method.as_str()) {
+          return Err(format!("工具 {} 不支持的请求方法: {}", self.name, method));
+        }
+      }
+      _ => {}
+    }
+
+    let declared = self.declared_parameters();
+    for template in self.templates() {
+      for cap in PLACEHOLDER.captures_iter(template) {
+        if !declared.iter().any(|d| d == &cap[1]) {
+          return Err(format!(
+            "工具 {} 的模板引用了未声明的参数: {}",
+            self.name, &cap[1]
+          ));
+        }
+      }
+    }
+    Ok(())
+  }
+
+  /// 检查 parameters.required 中的参数是否都已提供
+  pub fn check_required_arguments(&self, arguments: &serde_json::Value) -> Result<(), String> {
+    let required = self
+      .parameters
+      .get("required")
+      .and_then(|r| r.as_array())
+      .map(|r| r.iter().filter_map(|v| v.as_str()).collect::<Vec<_>>())
+      .unwrap_or_default();
+    let missing: Vec<&str> = required
+      .into_iter()
+      .filter(|key| arguments.get(*key).map_or(true, |v| v.is_null()))
+      .collect();
+    if missing.is_empty() {
+      Ok(())
+    } else {
+      Err(format!("缺少参数: {}", missing.join(", ")))
+    }
+  }
+}
+
+fn argument_text(value: Option<&serde_json::Value>) -> String {
+  match value {
+    None | Some(serde_json::Value::Null) => String::new(),
+    Some(serde_json::Value::String(s)) => s.clone(),
+    Some(other) => other.to_string(),
+  }
+}
+
+fn percent_encode(text: &str) -> String {
+  text
+    .bytes()
+    .map(|b| match b {
+      b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+        (b as char).to_string()
+      }
+      _ => format!("%{:02X}", b),
+    })
+    .collect()
+}
+
+/// 用参数替换模板中的 `{{name}}`，未提供的参数替换为空值
+pub fn render_template(
+  template: &str,
+  arguments: &serde_json::Value,
+  escape: TemplateEscape,
+) -> String {
+  PLACEHOLDER
+    .replace_all(template, |cap: &regex::Captures| {
+      let text = argument_text(arguments.get(&cap[1]));
+      match escape {
+        TemplateEscape::Url => percent_encode(&text),
+        TemplateEscape::Json => {
+          let quoted = serde_json::Value::String(text).to_string();
+          quoted[1..quoted.len() - 1].to_string()
+        }
+        TemplateEscape::Plain => text.replace(['\r', '\n'], " "),
+      }
+    })
+    .into_owned()
+}
+
+/// shell 模板渲染结果：命令文本只含环境变量引用，参数值在 `envs` 中
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShellInvocation {
+  pub command: String,
+  pub envs: Vec<(String, String)>,
+}
+
+/// 把 shell 模板中的 `{{name}}` 替换为环境变量 `BINDER_ARG_name` 的引用（带引号，作为单个参数），
+/// 未提供的参数为空值。Windows 下使用延迟展开 `!VAR!`，需配合 `cmd /V:ON` 执行
+pub fn render_shell_template(template: &str, arguments: &serde_json::Value) -> ShellInvocation {
+  let mut envs: Vec<(String, String)> = Vec::new();
+  let command = PLACEHOLDER
+    .replace_all(template, |cap: &regex::Captures| {
+      let var = format!("BINDER_ARG_{}", &cap[1]);
+      if !envs.iter().any(|(name, _)| name == &var) {
+        envs.push((var.clone(), argument_text(arguments.get(&cap[1]))));
+      }
+      if cfg!(target_os = "windows") {
+        format!("\"!{}!\"", var)
+      } else {
+        format!("\"${}\"", var)
+      }
+    })
+    .into_owned();
+  ShellInvocation { command, envs }
+}
+
+pub fn tools_dir(workspace_path: &Path) -> PathBuf {
+  workspace_path.join(".binder").join("tools")
+}
+
+/// 加载工作区内全部自定义工具；解析或校验失败的文件打印日志后跳过，同名工具以先加载者为准
+pub fn load_custom_tools(workspace_path: &Path) -> Vec<CustomToolSpec> {
+  let dir = tools_dir(workspace_path);
+  let Ok(entries) = fs::read_dir(&dir) else {
+    return Vec::new();
+  };
+  let mut files: Vec<PathBuf> = entries
+    .flatten()
+    .map(|e| e.path())
+    .filter(|p| p.extension().and_then(|e| e.to_str()) == Some("toml"))
+    .collect();
+  files.sort();
+
+  let builtin_names = crate::services::tool_matrix::builtin_tool_names();
+  let mut tools: Vec<CustomToolSpec> = Vec::new();
+  for file in files {
+    let parsed = fs::read_to_string(&file)
+      .map_err(|e| e.to_string())
+      .and_then(|content| toml::from_str::<CustomToolSpec>(&content).map_err(|e| e.to_string()))
+      .and_then(|spec| spec.validate(&builtin_names).map(|_| spec));
+    match parsed {
+      Ok(spec) if tools.iter().any(|t| t.name == spec.name) => {
+        tracing::warn!("自定义工具重名，已忽略: {}", file.display());
+      }
+      Ok(spec) => tools.push(spec),
+      Err(e) => tracing::warn!("加载自定义工具失败 {}: {}", file.display(), e),
+    }
+  }
+  tools
+}
+
+/// 工作区自定义工具与插件工具，同名时工作区工具优先；
+/// 应用设置未允许该工作区执行命令时不提供 shell 动作的工具
+pub fn available_custom_tools(workspace_path: &Path) -> Vec<CustomToolSpec> {
+  let allow_shell = crate::services::settings_service::shell_commands_allowed(workspace_path);
+  merge_available_tools(
+    load_custom_tools(workspace_path),
+    crate::services::plugins::plugin_tools(),
+    allow_shell,
+  )
+}
+
+/// 插件工具与工作区工具受同一个 shell 开关约束
+fn merge_available_tools(
+  mut tools: Vec<CustomToolSpec>,
+  plugin_tools: Vec<CustomToolSpec>,
+  allow_shell: bool,
+) -> Vec<CustomToolSpec> {
+  for tool in plugin_tools {
+    if !tools.iter().any(|t| t.name == tool.name) {
+      tools.push(tool);
+    }
+  }
+  tools.retain(|tool| allow_shell || !tool.is_shell());
+  tools
+}
+
+pub fn find_custom_tool(workspace_path: &Path, name: &str) -> Option<CustomToolSpec> {
+  available_custom_tools(workspace_path)
+    .into_iter()
+    .find(|t| t.name == name)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const SAMPLE: &str = r#"
+name = "word_count"
+description = "Counts words in a workspace file"
+
+[parameters]
+type = "object"
+required = ["path"]
+
+[parameters.properties.path]
+type = "string"
+
+[action]
+type = "shell"
+command = "wc -w {{ path }}"
+"#;
+
+  #[test]
+  fn parses_and_validates_tool_declarations() {
+    let spec: CustomToolSpec = toml::from_str(SAMPLE).unwrap();
+    assert!(spec.confirm);
+    assert!(spec.validate(&["read_file".to_string()]).is_ok());
+    assert!(spec.validate(&["word_count".to_string()]).is_err());
+    assert!(spec
+      .check_required_arguments(&serde_json::json!({}))
+      .is_err());
+
+    let undeclared = SAMPLE.replace("{{ path }}", "{{ file }}");
+    let spec: CustomToolSpec = toml::from_str(&undeclared).unwrap();
+    assert!(spec.validate(&[]).unwrap_err().contains("file"));
+  }
+
+  #[test]
+  fn template_values_are_escaped_for_their_context() {
+    let args = serde_json::json!({ "q": "a b'&\"c", "n": 3 });
+    assert_eq!(
+      render_template("https://x/?q={{q}}", &args, TemplateEscape::Url),
+      "https://x/?q=a%20b%27%26%22c"
+    );
+    assert_eq!(
+      render_template(
+        r#"{"q": "{{q}}", "missing": "{{other}}"}"#,
+        &args,
+        TemplateEscape::Json
+      ),
+      r#"{"q": "a b'&\"c", "missing": ""}"#
+    );
+  }
+
+  #[test]
+  fn shell_arguments_are_passed_as_environment_variables() {
+    let args = serde_json::json!({ "q": "x; rm -rf ~ $(id)", "n": 3 });
+    let invocation = render_shell_template("grep {{q}} -m {{n}} {{q}} {{missing}}", &args);
+    if !cfg!(target_os = "windows") {
+      assert_eq!(
+        invocation.command,
+        "grep \"$BINDER_ARG_q\" -m \"$BINDER_ARG_n\" \"$BINDER_ARG_q\" \"$BINDER_ARG_missing\""
+      );
+    }
+    assert_eq!(
+      invocation.envs,
+      vec![
+        ("BINDER_ARG_q".to_string(), "x; rm -rf ~ $(id)".to_string()),
+        ("BINDER_ARG_n".to_string(), "3".to_string()),
+        ("BINDER_ARG_missing".to_string(), String::new()),
+      ]
+    );
+
+    let spec: CustomToolSpec =
+      toml::from_str(&SAMPLE.replace("[parameters]", "confirm = false\n\n[parameters]")).unwrap();
+    assert!(!spec.confirm);
+    assert!(spec.requires_confirmation(Path::new("/workspace")));
+  }
+
+  #[test]
+  fn http_tools_skip_confirmation_only_when_the_user_approved_them() {
+    let workspace = std::env::temp_dir().join(format!("binder-http-tool-{}", uuid::Uuid::new_v4()));
+    let spec: CustomToolSpec = toml::from_str(
+      r#"
+name = "post_notes"
+description = "Posts notes"
+confirm = false
+
+[action]
+type = "http"
+method = "POST"
+url = "https://example.com/notes"
+"#,
+    )
+    .unwrap();
+    // 声明文件自行设置 confirm = false 不足以免确认
+    assert!(spec.requires_confirmation(&workspace));
+
+    crate::services::settings_service::update_for_tests(|s| {
+      s.approved_custom_tools
+        .push(crate::services::settings_service::ApprovedCustomTool {
+          workspace: workspace.clone(),
+          name: "post_notes".to_string(),
+        })
+    });
+    assert!(!spec.requires_confirmation(&workspace));
+    assert!(spec.requires_confirmation(&workspace.join("other")));
+  }
+
+  #[test]
+  fn plugin_shell_tools_require_the_shell_opt_in() {
+    let workspace: CustomToolSpec = toml::from_str(SAMPLE).unwrap();
+    let plugin_shell: CustomToolSpec =
+      toml::from_str(&SAMPLE.replace("word_count", "plugin_count")).unwrap();
+    let plugin_http: CustomToolSpec = toml::from_str(
+      r#"
+name = "lookup"
+description = "Looks up a term"
+
+[action]
+type = "http"
+url = "https://example.com/?q={{ q }}"
+"#,
+    )
+    .unwrap();
+    let shadowed: CustomToolSpec =
+      toml::from_str(&SAMPLE.replace("Counts words in a workspace file", "Plugin copy")).unwrap();
+    let plugin_tools = vec![plugin_shell, plugin_http, shadowed];
+
+    let names =
+      |tools: Vec<CustomToolSpec>| -> Vec<String> { tools.into_iter().map(|t| t.name).collect() };
+    assert_eq!(
+      names(merge_available_tools(
+        vec![workspace.clone()],
+        plugin_tools.clone(),
+        false
+      )),
+      vec!["lookup"]
+    );
+    let allowed = merge_available_tools(vec![workspace], plugin_tools, true);
+    assert_eq!(
+      allowed
+        .iter()
+        .find(|t| t.name == "word_count")
+        .unwrap()
+        .description,
+      "Counts words in a workspace file"
+    );
+    assert_eq!(names(allowed), vec!["word_count", "plugin_count", "lookup"]);
+  }
+}
@@ -7,6 +7,28 @@ pub enum AnalysisType {
   ExtractKeywords, // 提取关键词
   FindReferences,  // 查找引用
   ExtractEntities, // 提取实体（人物、地点、事件等）
+  Outline,         // 生成大纲
+  ActionItems,     // 提取待办事项
+  Sentiment,       // 情感与语气
+  QuestionAnswer,  // 基于文档回答问题
+}
+
+impl AnalysisType {
+  /// 解析前端传入的分析类型：summarize / keywords / references / entities / outline /
+  /// action_items / sentiment / qa
+  pub fn parse(value: &str) -> Option<Self> {
+    match value {
+      "summarize" => Some(AnalysisType::Summarize),
+      "keywords" => Some(AnalysisType::ExtractKeywords),
+      "references" => Some(AnalysisType::FindReferences),
+      "entities" => Some(AnalysisType::ExtractEntities),
+      "outline" => Some(AnalysisType::Outline),
+      "action_items" => Some(AnalysisType::ActionItems),
+      "sentiment" => Some(AnalysisType::Sentiment),
+      "qa" => Some(AnalysisType::QuestionAnswer),
+      _ => None,
+    }
+  }
 }
 
 /// 引用信息
@@ -36,53 +58,295 @@ pub struct DocumentAnalysisResult {
   pub entities: Vec<Entity>,
 }
 
+/// 大纲中的一节
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutlineSection {
+  pub heading: String,
+  /// 层级，1 为顶层
+  #[serde(default = "default_level")]
+  pub level: u8,
+  #[serde(default)]
+  pub summary: String,
+}
+
+fn default_level() -> u8 {
+  1
+}
+
+/// 文档大纲
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DocumentOutline {
+  #[serde(default)]
+  pub title: String,
+  #[serde(default)]
+  pub sections: Vec<OutlineSection>,
+}
+
+/// 待办事项
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ActionItem {
+  pub task: String,
+  #[serde(default)]
+  pub owner: Option<String>,
+  #[serde(default)]
+  pub due: Option<String>,
+  /// high / medium / low
+  #[serde(default = "default_priority")]
+  pub priority: String,
+}
+
+fn default_priority() -> String {
+  "medium".to_string()
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ActionItemList {
+  #[serde(default)]
+  pub action_items: Vec<ActionItem>,
+}
+
+/// 情感与语气
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SentimentAnalysis {
+  /// positive / neutral / negative / mixed
+  pub sentiment: String,
+  /// -1（负面）到 1（正面）
+  #[serde(default)]
+  pub score: f64,
+  /// 语气描述，如 正式、紧迫、友好
+  #[serde(default)]
+  pub tones: Vec<String>,
+  #[serde(default)]
+  pub explanation: String,
+}
+
+/// 基于文档的问答结果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DocumentAnswer {
+  pub answer: String,
+  /// 文档中是否找到了答案
+  #[serde(default)]
+  pub found: bool,
+  /// 支持答案的原文摘录
+  #[serde(default)]
+  pub quotes: Vec<String>,
+}
+
 /// 文档分析服务
 pub struct DocumentAnalysisService;
 
 impl DocumentAnalysisService {
-  /// 构建分析提示词
-  pub fn build_analysis_prompt(content: &str, analysis_type: &AnalysisType) -> String {
+  /// 构建分析提示词；question 仅用于 QuestionAnswer
+  pub fn build_analysis_prompt(
+    content: &str,
+    analysis_type: &AnalysisType,
+    question: Option<&str>,
+  ) -> String {
     // 限制内容长度，避免超出 token 限制
     let content_preview: String = content.chars().take(4000).collect();
 
     match analysis_type {
-            AnalysisType::Summarize => format!(
-                "请对以下文档进行总结，要求：\n\
-                1. 总结主要内容（3-5 点）\n\
-                2. 提取关键信息\n\
-                3. 保持简洁准确\n\
-                4. 使用中文输出\n\n\
-                文档内容：\n{}",
-                content_preview
-            ),
-            AnalysisType::ExtractKeywords => format!(
-                "请从以下文档中提取关键词，要求：\n\
-                1. 提取 5-10 个关键词\n\
-                2. 按重要性排序\n\
-                3. 使用中文\n\
-                4. 返回 JSON 格式：{{\"keywords\": [\"关键词1\", \"关键词2\", ...]}}\n\n\
-                文档内容：\n{}",
-                content_preview
-            ),
-            AnalysisType::FindReferences => format!(
-                "请分析以下文档，找出：\n\
-                1. 引用的其他文档或资料\n\
-                2. 提到的关键人物、事件、概念\n\
-                3. 需要进一步了解的内容\n\
-                4. 返回 JSON 格式：{{\"references\": [{{\"text\": \"引用文本\", \"source\": \"来源\", \"type\": \"类型\"}}]}}\n\n\
-                文档内容：\n{}",
-                content_preview
-            ),
-            AnalysisType::ExtractEntities => format!(
-                "请从以下文档中提取实体信息，包括：\n\
-                1. 人物（姓名、角色）\n\
-                2. 地点（地名、位置）\n\
-                3. 事件（事件名称、时间）\n\
-                4. 概念（专业术语、概念）\n\
-                5. 返回 JSON 格式：{{\"entities\": [{{\"name\": \"实体名\", \"type\": \"类型\", \"description\": \"描述\"}}]}}\n\n\
-                文档内容：\n{}",
-                content_preview
-            ),
+      AnalysisType::Summarize => format!(
+        "请对以下文档进行总结，要求：\n\
+         1. 总结主要内容（3-5 点）\n\
+         2. 提取关键信息\n\
+         3. 保持简洁准确\n\
+         4. 使用中文输出\n\n\
+         文档内容：\n{}",
+        content_preview
+      ),
+      AnalysisType::ExtractKeywords => format!(
+        "请从以下文档中提取关键词，要求：\n\
+         1. 提取 5-10 个关键词\n\
+         2. 按重要性排序\n\
+         3. 使用中文\n\
+         4. 返回 JSON 格式：{{\"keywords\": [\"关键词1\", \"关键词2\", ...]}}\n\n\
+         文档内容：\n{}",
+        content_preview
+      ),
+      AnalysisType::FindReferences => format!(
+        "请分析以下文档，找出：\n\
+         1. 引用的其他文档或资料\n\
+         2. 提到的关键人物、事件、概念\n\
+         3. 需要进一步了解的内容\n\
+         4. 返回 JSON 格式：{{\"references\": [{{\"text\": \"引用文本\", \"source\": \"来源\", \
+         \"type\": \"类型\"}}]}}\n\n\
+         文档内容：\n{}",
+        content_preview
+      ),
+      AnalysisType::ExtractEntities => format!(
+        "请从以下文档中提取实体信息，包括：\n\
+         1. 人物（姓名、角色）\n\
+         2. 地点（地名、位置）\n\
+         3. 事件（事件名称、时间）\n\
+         4. 概念（专业术语、概念）\n\
+         5. 返回 JSON 格式：{{\"entities\": [{{\"name\": \"实体名\", \"type\": \"类型\", \
+         \"description\": \"描述\"}}]}}\n\n\
+         文档内容：\n{}",
+        content_preview
+      ),
+      AnalysisType::Outline => format!(
+        "请为以下文档生成层级大纲，要求：\n\
+         1. 按文档的论述顺序列出各节，层级从 1 开始，最多 3 级\n\
+         2. 每节给出标题与一句话概要\n\
+         3. 使用与文档相同的语言\n\
+         4. 只返回 JSON：{{\"title\": \"文档标题\", \"sections\": [{{\"heading\": \"标题\", \
+         \"level\": 1, \"summary\": \"概要\"}}]}}\n\n\
+         文档内容：\n{}",
+        content_preview
+      ),
+      AnalysisType::ActionItems => format!(
+        "请从以下文档中提取待办事项（需要某人完成的任务、决定的后续行动），要求：\n\
+         1. 每项写明要做的事，负责人与截止时间仅在文档提到时填写，否则为 null\n\
+         2. 优先级为 high / medium / low\n\
+         3. 没有待办事项时返回空列表\n\
+         4. 只返回 JSON：{{\"action_items\": [{{\"task\": \"任务\", \"owner\": \"负责人\", \
+         \"due\": \"截止时间\", \"priority\": \"medium\"}}]}}\n\n\
+         文档内容：\n{}",
+        content_preview
+      ),
+      AnalysisType::Sentiment => format!(
+        "请分析以下文档的情感倾向与语气，要求：\n\
+         1. sentiment 为 positive / neutral / negative / mixed 之一\n\
+         2. score 为 -1（非常负面）到 1（非常正面）之间的数\n\
+         3. tones 列出 1-5 个语气描述，如 正式、紧迫、友好\n\
+         4. explanation 用一两句话说明依据\n\
+         5. 只返回 JSON：{{\"sentiment\": \"neutral\", \"score\": 0.0, \"tones\": [\"正式\"], \
+         \"explanation\": \"依据\"}}\n\n\
+         文档内容：\n{}",
+        content_preview
+      ),
+      AnalysisType::QuestionAnswer => format!(
+        "请仅依据以下文档回答问题，要求：\n\
+         1. 文档中找不到答案时 found 为 false，并在 answer 中说明，不要编造\n\
+         2. quotes 列出支持答案的原文摘录（逐字引用）\n\
+         3. 使用与问题相同的语言\n\
+         4. 只返回 JSON：{{\"answer\": \"答案\", \"found\": true, \"quotes\": [\"原文\"]}}\n\n\
+         问题：{}\n\n\
+         文档内容：\n{}",
+        question.unwrap_or_default().trim(),
+        content_preview
+      ),
+    }
+  }
+
+  /// 整理模型输出：大纲、待办、情感、问答校验为固定结构的 JSON；无法解析或其他类型时
+  /// 原样返回（去掉首尾空白）
+  pub fn normalize_response(response: &str, analysis_type: &AnalysisType) -> String {
+    let normalized = match analysis_type {
+      AnalysisType::Outline => parse_json::<DocumentOutline>(response).map(|mut outline| {
+        outline.sections.retain(|s| !s.heading.trim().is_empty());
+        for section in &mut outline.sections {
+          section.level = section.level.clamp(1, 3);
         }
+        serde_json::to_string(&outline)
+      }),
+      AnalysisType::ActionItems => parse_json::<ActionItemList>(response).map(|mut list| {
+        list
+          .action_items
+          .retain(|item| !item.task.trim().is_empty());
+        for item in &mut list.action_items {
+          item.owner = item.owner.take().filter(|o| !o.trim().is_empty());
+          item.due = item.due.take().filter(|d| !d.trim().is_empty());
+          if !matches!(item.priority.as_str(), "high" | "medium" | "low") {
+            item.priority = default_priority();
+          }
+        }
+        serde_json::to_string(&list)
+      }),
+      AnalysisType::Sentiment => parse_json::<SentimentAnalysis>(response).map(|mut result| {
+        result.score = if result.score.is_finite() {
+          result.score.clamp(-1.0, 1.0)
+        } else {
+          0.0
+        };
+        serde_json::to_string(&result)
+      }),
+      AnalysisType::QuestionAnswer => {
+        parse_json::<DocumentAnswer>(response).map(|answer| serde_json::to_string(&answer))
+      }
+      _ => None,
+    };
+    match normalized {
+      Some(Ok(json)) => json,
+      _ => response.trim().to_string(),
+    }
+  }
+}
+
+/// 取出模型输出中的 JSON 对象（允许前后有说明文字或代码块标记）
+fn parse_json<T: serde::de::DeserializeOwned>(response: &str) -> Option<T> {
+  let start = response.find('{')?;
+  let end = response.rfind('}')?;
+  if start >= end {
+    return None;
+  }
+  serde_json::from_str(&response[start..=end]).ok()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn parses_new_analysis_types() {
+    assert!(matches!(
+      AnalysisType::parse("outline"),
+      Some(AnalysisType::Outline)
+    ));
+    assert!(matches!(
+      AnalysisType::parse("action_items"),
+      Some(AnalysisType::ActionItems)
+    ));
+    assert!(matches!(
+      AnalysisType::parse("qa"),
+      Some(AnalysisType::QuestionAnswer)
+    ));
+    assert!(AnalysisType::parse("unknown").is_none());
+    let prompt = DocumentAnalysisService::build_analysis_prompt(
+      "合同正文",
+      &AnalysisType::QuestionAnswer,
+      Some(" 付款期限是多久？ "),
+    );
+    assert!(prompt.contains("问题：付款期限是多久？\n\n文档内容：\n合同正文"));
+  }
+
+  #[test]
+  fn normalizes_structured_responses() {
+    let response = "好的：\n```json\n{\"action_items\": [\
+      {\"task\": \"提交预算\", \"owner\": \"张三\", \"due\": \"\", \"priority\": \"urgent\"},\
+      {\"task\": \" \"}]}\n```";
+    let list: ActionItemList = serde_json::from_str(&DocumentAnalysisService::normalize_response(
+      response,
+      &AnalysisType::ActionItems,
+    ))
+    .unwrap();
+    assert_eq!(
+      list.action_items,
+      vec![ActionItem {
+        task: "提交预算".to_string(),
+        owner: Some("张三".to_string()),
+        due: None,
+        priority: "medium".to_string(),
+      }]
+    );
+
+    let sentiment: SentimentAnalysis =
+      serde_json::from_str(&DocumentAnalysisService::normalize_response(
+        r#"{"sentiment": "positive", "score": 3, "tones": ["友好"]}"#,
+        &AnalysisType::Sentiment,
+      ))
+      .unwrap();
+    assert_eq!(sentiment.score, 1.0);
+    assert_eq!(sentiment.tones, vec!["友好"]);
+
+    assert_eq!(
+      DocumentAnalysisService::normalize_response(" 无法解析 ", &AnalysisType::Outline),
+      "无法解析"
+    );
+    assert_eq!(
+      DocumentAnalysisService::normalize_response(" 总结 ", &AnalysisType::Summarize),
+      "总结"
+    );
   }
 }
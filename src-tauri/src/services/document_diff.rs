@@ -0,0 +1,641 @@
+//! 文档版本对比：把两个版本（编辑器内容、磁盘文件、时间轴快照或 git 历史）转为纯文本，
+//! 先按段落对齐，再在修改过的段落内按词比较，生成并排或行内对比视图使用的 HTML。
+//!
+//! Word / ODT / RTF 经 Pandoc 提取纯文本，PDF 取文本层，HTML 取正文文本。
+//! 中日韩文字按单字比较，其余按单词比较。
+
+use crate::services::pandoc_service::PandocService;
+use crate::services::pdf_text::extract_pdf_plain_text;
+use crate::services::temp_service;
+use crate::services::word_count::is_cjk;
+use crate::workspace::workspace_db::WorkspaceDb;
+use base64::{engine::general_purpose, Engine as _};
+use scraper::{Html, Node};
+use serde::{Deserialize, Serialize};
+use similar::{ChangeTag, DiffTag, TextDiff};
+use std::path::Path;
+use std::process::Command;
+
+/// 需要先提取文本的格式
+const EXTRACTED_EXTENSIONS: [&str; 5] = ["docx", "doc", "odt", "rtf", "pdf"];
+
+/// 参与对比的一个版本
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "source", rename_all = "snake_case")]
+pub enum DocumentRevision {
+  /// 直接给出的内容；format 为 `html` / `markdown` / `plain`，未指定时按内容判断是否为 HTML
+  Text {
+    content: String,
+    #[serde(default)]
+    format: Option<String>,
+  },
+  /// 磁盘上的当前文件
+  File { path: String },
+  /// 时间轴节点保存的文件内容；节点涉及多个文件时用 file_path（相对工作区）选择
+  Timeline {
+    workspace_path: String,
+    node_id: String,
+    #[serde(default)]
+    file_path: Option<String>,
+  },
+  /// git 历史中的版本（如 `HEAD`、`HEAD~1`、提交哈希）
+  Git { path: String, rev: String },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffFormat {
+  /// 单栏，删除与插入的内容依次标出
+  Inline,
+  /// 两栏表格，左侧旧版本、右侧新版本
+  SideBySide,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DiffStats {
+  /// 插入 / 删除的词数（中日韩文字按字计）
+  pub inserted_words: usize,
+  pub deleted_words: usize,
+  pub changed_paragraphs: usize,
+  pub inserted_paragraphs: usize,
+  pub deleted_paragraphs: usize,
+  pub unchanged_paragraphs: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocumentDiff {
+  pub html: String,
+  pub identical: bool,
+  pub stats: DiffStats,
+}
+
+fn extension_of(path: &str) -> String {
+  Path::new(path)
+    .extension()
+    .and_then(|e| e.to_str())
+    .map(|e| e.to_lowercase())
+    .unwrap_or_default()
+}
+
+/// HTML 正文按块级元素分段后的纯文本
+fn html_to_text(html: &str) -> String {
+  const BLOCK_TAGS: [&str; 16] = [
+    "p",
+    "div",
+    "li",
+    "tr",
+    "br",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "pre",
+    "blockquote",
+    "section",
+    "article",
+    "table",
+  ];
+  let document = Html::parse_document(html);
+  let mut text = String::new();
+  for node in document.root_element().descendants() {
+    match node.value() {
+      Node::Element(element) if BLOCK_TAGS.contains(&element.name()) => text.push('\n'),
+      Node::Element(element) if matches!(element.name(), "td" | "th") => text.push(' '),
+      Node::Text(content) => {
+        let hidden = node.ancestors().any(|a| {
+          a.value()
+            .as_element()
+            .is_some_and(|e| matches!(e.name(), "script" | "style" | "head"))
+        });
+        if !hidden {
+          text.push_str(content);
+        }
+      }
+      _ => {}
+    }
+  }
+  text
+}
+
+fn looks_like_html(content: &str) -> bool {
+  let trimmed = content.trim_start();
+  trimmed.starts_with('<') && trimmed.contains("</")
+}
+
+/// 读取文件文本：Office 文档与 PDF 先提取，HTML 取正文
+fn file_text(path: &Path) -> Result<String, String> {
+  let ext = extension_of(&path.to_string_lossy());
+  match ext.as_str() {
+    "pdf" => extract_pdf_plain_text(path),
+    "docx" | "doc" | "odt" | "rtf" => PandocService::new().extract_plain_text(path),
+    _ => {
+      let content = std::fs::read_to_string(path).map_err(|e| format!("读取文件失败: {}", e))?;
+      Ok(if matches!(ext.as_str(), "html" | "htm") {
+        html_to_text(&content)
+      } else {
+        content
+      })
+    }
+  }
+}
+
+/// 历史版本的字节内容按原文件扩展名转为文本；需要提取的格式先写入临时文件
+fn bytes_text(bytes: Vec<u8>, file_path: &str) -> Result<String, String> {
+  let ext = extension_of(file_path);
+  if EXTRACTED_EXTENSIONS.contains(&ext.as_str()) {
+    let temp = temp_service::temp_file("diff", &ext);
+    std::fs::write(temp.path(), bytes).map_err(|e| format!("写入临时文件失败: {}", e))?;
+    return file_text(temp.path());
+  }
+  let content = String::from_utf8_lossy(&bytes).to_string();
+  Ok(if matches!(ext.as_str(), "html" | "htm") {
+    html_to_text(&content)
+  } else {
+    content
+  })
+}
+
+fn timeline_text(
+  workspace_path: &str,
+  node_id: &str,
+  file_path: Option<&str>,
+) -> Result<String, String> {
+  let db = WorkspaceDb::new(Path::new(workspace_path))?;
+  let node = db
+    .get_timeline_node(node_id)?
+    .ok_or_else(|| format!("时间轴节点不存在: {}", node_id))?;
+  let payload = db
+    .get_timeline_restore_payload(&node.restore_payload_id)?
+    .ok_or_else(|| format!("时间轴载荷不存在: {}", node.restore_payload_id))?;
+  if payload.payload_kind != "file_content" {
+    return Err("该时间轴节点不包含文件内容".to_string());
+  }
+  let files = payload
+    .payload_json
+    .get("files")
+    .and_then(|v| v.as_array())
+    .ok_or_else(|| "file_content payload 缺少 files".to_string())?;
+  let file = files
+    .iter()
+    .find(|f| {
+      file_path.map_or(true, |wanted| {
+        f.get("filePath").and_then(|v| v.as_str()) == Some(wanted)
+      })
+    })
+    .ok_or_else(|| "时间轴节点中没有该文件".to_string())?;
+  let path = file
+    .get("filePath")
+    .and_then(|v| v.as_str())
+    .unwrap_or_default();
+  let bytes = general_purpose::STANDARD
+    .decode(
+      file
+        .get("contentBase64")
+        .and_then(|v| v.as_str())
+        .unwrap_or_default(),
+    )
+    .map_err(|e| format!("解码文件内容失败: {}", e))?;
+  bytes_text(bytes, path)
+}
+
+/// `git show <rev>:./<文件名>`，在文件所在目录执行，不需要知道仓库根目录
+fn git_text(path: &str, rev: &str) -> Result<String, String> {
+  let valid_rev = !rev.is_empty()
+    && !rev.starts_with('-')
+    && rev
+      .chars()
+      .all(|c| c.is_ascii_alphanumeric() || "_./~^@{}-".contains(c));
+  if !valid_rev {
+    return Err(format!("无效的 git 版本: {}", rev));
+  }
+  let git = which::which("git").map_err(|_| "未找到 git，无法读取历史版本".to_string())?;
+  let file = Path::new(path);
+  let dir = file
+    .parent()
+    .ok_or_else(|| format!("无效的文件路径: {}", path))?;
+  let name = file
+    .file_name()
+    .ok_or_else(|| format!("无效的文件路径: {}", path))?
+    .to_string_lossy();
+  let output = Command::new(git)
+    .arg("-C")
+    .arg(dir)
+    .arg("show")
+    .arg(format!("{}:./{}", rev, name))
+    .output()
+    .map_err(|e| format!("执行 git 失败: {}", e))?;
+  if !output.status.success() {
+    return Err(format!(
+      "读取 git 版本失败: {}",
+      String::from_utf8_lossy(&output.stderr).trim()
+    ));
+  }
+  bytes_text(output.stdout, path)
+}
+
+impl DocumentRevision {
+  /// 该版本的纯文本
+  pub fn text(&self) -> Result<String, String> {
+    match self {
+      DocumentRevision::Text { content, format } => {
+        let is_html = match format.as_deref() {
+          Some(format) => format.eq_ignore_ascii_case("html"),
+          None => looks_like_html(content),
+        };
+        Ok(if is_html {
+          html_to_text(content)
+        } else {
+          content.clone()
+        })
+      }
+      DocumentRevision::File { path } => file_text(Path::new(path)),
+      DocumentRevision::Timeline {
+        workspace_path,
+        node_id,
+        file_path,
+      } => timeline_text(workspace_path, node_id, file_path.as_deref()),
+      DocumentRevision::Git { path, rev } => git_text(path, rev),
+    }
+  }
+}
+
+/// 比较单位：中日韩文字单字、连续的字母数字、连续空白，其余符号单独成词
+fn tokenize(text: &str) -> Vec<&str> {
+  let mut tokens = Vec::new();
+  let mut start = None;
+  let mut kind = 0u8;
+  for (index, ch) in text.char_indices() {
+    let ch_kind = if is_cjk(ch) {
+      0
+    } else if ch.is_alphanumeric() || ch == '_' {
+      1
+    } else if ch.is_whitespace() {
+      2
+    } else {
+      3
+    };
+    if let Some(s) = start {
+      if ch_kind == 0 || ch_kind == 3 || ch_kind != kind {
+        tokens.push(&text[s..index]);
+        start = None;
+      }
+    }
+    if start.is_none() {
+      start = Some(index);
+      kind = ch_kind;
+    }
+  }
+  if let Some(s) = start {
+    tokens.push(&text[s..]);
+  }
+  tokens
+}
+
+fn escape_html(text: &str) -> String {
+  text
+    .replace('&', "&amp;")
+    .replace('<', "&lt;")
+    .replace('>', "&gt;")
+    .replace('"', "&quot;")
+}
+
+fn count_words(tokens: &str) -> usize {
+  tokenize(tokens)
+    .iter()
+    .filter(|t| !t.trim().is_empty())
+    .count()
+}
+
+/// 两个段落的逐词对比：(旧版本 HTML, 新版本 HTML, 行内 HTML)
+fn word_diff(old: &str, new: &str, stats: &mut DiffStats) -> (String, String, String) {
+  let old_tokens = tokenize(old);
+  let new_tokens = tokenize(new);
+  let diff = TextDiff::from_slices(&old_tokens, &new_tokens);
+
+  // 合并相邻的同类变化，避免每个词一个标签
+  let mut segments: Vec<(ChangeTag, String)> = Vec::new();
+  for change in diff.iter_all_changes() {
+    match segments.last_mut() {
+      Some((tag, text)) if *tag == change.tag() => text.push_str(change.value()),
+      _ => segments.push((change.tag(), change.value().to_string())),
+    }
+  }
+
+  let (mut old_html, mut new_html, mut inline) = (String::new(), String::new(), String::new());
+  for (tag, text) in segments {
+    let escaped = escape_html(&text);
+    match tag {
+      ChangeTag::Equal => {
+        old_html.push_str(&escaped);
+        new_html.push_str(&escaped);
+        inline.push_str(&escaped);
+      }
+      ChangeTag::Delete => {
+        stats.deleted_words += count_words(&text);
+        let marked = format!("<del class=\"diff-del\">{}</del>", escaped);
+        old_html.push_str(&marked);
+        inline.push_str(&marked);
+      }
+      ChangeTag::Insert => {
+        stats.inserted_words += count_words(&text);
+        let marked = format!("<ins class=\"diff-ins\">{}</ins>", escaped);
+        new_html.push_str(&marked);
+        inline.push_str(&marked);
+      }
+    }
+  }
+  (old_html, new_html, inline)
+}
+
+/// 渲染中的一行（段落）：类名、旧版本 HTML、新版本 HTML、行内 HTML
+struct DiffRow {
+  class: &'static str,
+  old: String,
+  new: String,
+  inline: String,
+}
+
+fn paragraphs(text: &str) -> Vec<&str> {
+  text
+    .lines()
+    .map(str::trim)
+    .filter(|line| !line.is_empty())
+    .collect()
+}
+
+/// 行内修改块：把原文 UTF-16 偏移 [start, end) 处的 before 替换为 after；
+/// 偏移与前端字符串下标一致，便于逐块接受修改
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TextHunk {
+  pub start: usize,
+  pub end: usize,
+  pub before: String,
+  pub after: String,
+}
+
+/// 逐词对比两段文本，返回修改块；只隔空白的相邻修改合并为一块
+pub fn diff_hunks(old: &str, new: &str) -> Vec<TextHunk> {
+  let old_tokens = tokenize(old);
+  let new_tokens = tokenize(new);
+  let diff = TextDiff::from_slices(&old_tokens, &new_tokens);
+  let utf16_len = |s: &str| s.encode_utf16().count();
+
+  let mut hunks: Vec<TextHunk> = Vec::new();
+  let mut offset = 0;
+  // 紧跟在修改块之后的空白，下一处也是修改时并入该块
+  let mut gap: Option<String> = None;
+  for op in diff.ops() {
+    let (tag, old_range, new_range) = op.as_tag_tuple();
+    let before: String = old_tokens[old_range].concat();
+    if tag == DiffTag::Equal {
+      if let Some(gap) = gap.take() {
+        offset += utf16_len(&gap);
+      }
+      if hunks.last().is_some_and(|h| h.end == offset) && before.trim().is_empty() {
+        gap = Some(before);
+      } else {
+        offset += utf16_len(&before);
+      }
+      continue;
+    }
+    let after: String = new_tokens[new_range].concat();
+    let end = offset + gap.as_deref().map_or(0, utf16_len) + utf16_len(&before);
+    match (gap.take(), hunks.last_mut()) {
+      (Some(gap), Some(last)) => {
+        last.before.push_str(&gap);
+        last.before.push_str(&before);
+        last.after.push_str(&gap);
+        last.after.push_str(&after);
+        last.end = end;
+      }
+      _ => hunks.push(TextHunk {
+        start: offset,
+        end,
+        before,
+        after,
+      }),
+    }
+    offset = end;
+  }
+  hunks
+}
+
+/// 对比两段纯文本
+pub fn diff_texts(old: &str, new: &str, format: DiffFormat) -> DocumentDiff {
+  let old_paragraphs = paragraphs(old);
+  let new_paragraphs = paragraphs(new);
+  let diff = TextDiff::from_slices(&old_paragraphs, &new_paragraphs);
+  let mut stats = DiffStats::default();
+  let mut rows = Vec::new();
+
+  let deleted = |text: &str, stats: &mut DiffStats| {
+    stats.deleted_paragraphs += 1;
+    stats.deleted_words += count_words(text);
+    let marked = format!("<del class=\"diff-del\">{}</del>", escape_html(text));
+    DiffRow {
+      class: "diff-deleted",
+      old: marked.clone(),
+      new: String::new(),
+      inline: marked,
+    }
+  };
+  let inserted = |text: &str, stats: &mut DiffStats| {
+    stats.inserted_paragraphs += 1;
+    stats.inserted_words += count_words(text);
+    let marked = format!("<ins class=\"diff-ins\">{}</ins>", escape_html(text));
+    DiffRow {
+      class: "diff-inserted",
+      old: String::new(),
+      new: marked.clone(),
+      inline: marked,
+    }
+  };
+
+  for op in diff.ops() {
+    let (tag, old_range, new_range) = op.as_tag_tuple();
+    match tag {
+      DiffTag::Equal => {
+        for text in &old_paragraphs[old_range] {
+          stats.unchanged_paragraphs += 1;
+          let escaped = escape_html(text);
+          rows.push(DiffRow {
+            class: "diff-equal",
+            old: escaped.clone(),
+            new: escaped.clone(),
+            inline: escaped,
+          });
+        }
+      }
+      DiffTag::Delete => {
+        for text in &old_paragraphs[old_range] {
+          rows.push(deleted(text, &mut stats));
+        }
+      }
+      DiffTag::Insert => {
+        for text in &new_paragraphs[new_range] {
+          rows.push(inserted(text, &mut stats));
+        }
+      }
+      DiffTag::Replace => {
+        // 按位置配对修改过的段落，多出的段落视为整段删除或插入
+        let old_texts = &old_paragraphs[old_range];
+        let new_texts = &new_paragraphs[new_range];
+        for (old_text, new_text) in old_texts.iter().zip(new_texts) {
+          stats.changed_paragraphs += 1;
+          let (old, new, inline) = word_diff(old_text, new_text, &mut stats);
+          rows.push(DiffRow {
+            class: "diff-changed",
+            old,
+            new,
+            inline,
+          });
+        }
+        let paired = old_texts.len().min(new_texts.len());
+        for text in &old_texts[paired..] {
+          rows.push(deleted(text, &mut stats));
+        }
+        for text in &new_texts[paired..] {
+          rows.push(inserted(text, &mut stats));
+        }
+      }
+    }
+  }
+
+  let html = match format {
+    DiffFormat::Inline => {
+      let body: String = rows
+        .iter()
+        .map(|row| format!("<p class=\"{}\">{}</p>\n", row.class, row.inline))
+        .collect();
+      format!(
+        "<div class=\"binder-diff binder-diff-inline\">\n{}</div>",
+        body
+      )
+    }
+    DiffFormat::SideBySide => {
+      let body: String = rows
+        .iter()
+        .map(|row| {
+          format!(
+            "<tr class=\"{}\"><td class=\"diff-old\">{}</td><td class=\"diff-new\">{}</td></tr>\n",
+            row.class, row.old, row.new
+          )
+        })
+        .collect();
+      format!(
+        "<table class=\"binder-diff binder-diff-side-by-side\"><tbody>\n{}</tbody></table>",
+        body
+      )
+    }
+  };
+  DocumentDiff {
+    html,
+    identical: rows.iter().all(|row| row.class == "diff-equal"),
+    stats,
+  }
+}
+
+/// 对比两个版本
+pub fn diff_documents(
+  old: &DocumentRevision,
+  new: &DocumentRevision,
+  format: DiffFormat,
+) -> Result<DocumentDiff, String> {
+  Ok(diff_texts(&old.text()?, &new.text()?, format))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn tokenizes_cjk_per_character_and_latin_per_word() {
+    assert_eq!(
+      tokenize("合同 v2.1 生效"),
+      vec!["合", "同", " ", "v2", ".", "1", " ", "生", "效"]
+    );
+  }
+
+  #[test]
+  fn marks_word_changes_inside_paragraphs() {
+    let old = "第一段保持不变。\n\n甲方应在 30 日内付款。\n删除的段落\n";
+    let new = "第一段保持不变。\n\n甲方应在 15 日内付款 <全额>。\n";
+    let inline = diff_texts(old, new, DiffFormat::Inline);
+    assert!(!inline.identical);
+    assert!(inline
+      .html
+      .contains("<p class=\"diff-equal\">第一段保持不变。</p>"));
+    assert!(inline.html.contains(
+      "甲方应在 <del class=\"diff-del\">30</del><ins class=\"diff-ins\">15</ins> 日内付款"
+    ));
+    assert!(inline.html.contains("&lt;全额&gt;"));
+    assert!(inline
+      .html
+      .contains("<p class=\"diff-deleted\"><del class=\"diff-del\">删除的段落</del></p>"));
+    assert_eq!(inline.stats.changed_paragraphs, 1);
+    assert_eq!(inline.stats.deleted_paragraphs, 1);
+    assert_eq!(inline.stats.unchanged_paragraphs, 1);
+    // 30 + 删除的段落（5 字）；15 + <全额>（4 个符号与字）
+    assert_eq!(inline.stats.deleted_words, 1 + 5);
+    assert_eq!(inline.stats.inserted_words, 1 + 4);
+
+    let side = diff_texts(old, new, DiffFormat::SideBySide);
+    assert!(side.html.contains(
+      "<tr class=\"diff-deleted\"><td class=\"diff-old\"><del class=\"diff-del\">删除的段落</del></td><td class=\"diff-new\"></td></tr>"
+    ));
+  }
+
+  #[test]
+  fn reads_html_revisions_as_text() {
+    let old = DocumentRevision::Text {
+      content: "<html><head><style>p{}</style></head><body><h1>标题</h1><p>Hello <b>world</b></p></body></html>".to_string(),
+      format: None,
+    };
+    let new = DocumentRevision::Text {
+      content: "标题\nHello world".to_string(),
+      format: Some("markdown".to_string()),
+    };
+    let diff = diff_documents(&old, &new, DiffFormat::Inline).unwrap();
+    assert!(diff.identical, "{}", diff.html);
+  }
+
+  #[test]
+  fn builds_hunks_with_utf16_offsets() {
+    let old = "😀 The quick brown fox，跳过了懒狗。";
+    let new = "😀 The slow red fox，跃过了懒狗！";
+    let hunks = diff_hunks(old, new);
+    assert_eq!(
+      hunks,
+      vec![
+        TextHunk {
+          start: 7,
+          end: 18,
+          before: "quick brown".to_string(),
+          after: "slow red".to_string(),
+        },
+        TextHunk {
+          start: 23,
+          end: 24,
+          before: "跳".to_string(),
+          after: "跃".to_string(),
+        },
+        TextHunk {
+          start: 28,
+          end: 29,
+          before: "。".to_string(),
+          after: "！".to_string(),
+        },
+      ]
+    );
+    let units: Vec<u16> = old.encode_utf16().collect();
+    for hunk in &hunks {
+      assert_eq!(
+        String::from_utf16_lossy(&units[hunk.start..hunk.end]),
+        hunk.before
+      );
+    }
+    assert!(diff_hunks("相同", "相同").is_empty());
+  }
+}
@@ -0,0 +1,260 @@
+//! 合并文档：按顺序把多个 Markdown / DOCX 文档拼接为一个文档，用于把各章节汇编成书稿。
+//!
+//! DOCX 先经 Pandoc 转为 Markdown（图片提取到媒体目录），与 Markdown 原文按顺序拼接后
+//! 再由 Pandoc 输出为 DOCX 或 Markdown，输出格式由目标文件扩展名决定。
+//! 生成的 DOCX 目录是 Word 域，在 Word 中更新域后才显示页码。
+
+use crate::services::pandoc_service::PandocService;
+use crate::services::temp_service;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+const DEFAULT_TOC_DEPTH: u8 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MergeFormat {
+  Markdown,
+  Docx,
+}
+
+impl MergeFormat {
+  pub fn from_path(path: &Path) -> Option<Self> {
+    match extension_of(path).as_str() {
+      "md" | "markdown" => Some(MergeFormat::Markdown),
+      "docx" => Some(MergeFormat::Docx),
+      _ => None,
+    }
+  }
+
+  fn writer(self) -> &'static str {
+    match self {
+      MergeFormat::Markdown => "gfm",
+      MergeFormat::Docx => "docx",
+    }
+  }
+
+  /// 分页符：DOCX 用 OpenXML 分页，Markdown 用打印时分页的 HTML 块
+  fn page_break(self) -> &'static str {
+    match self {
+      MergeFormat::Markdown => "```{=html}\n<div style=\"page-break-after: always;\"></div>\n```",
+      MergeFormat::Docx => "```{=openxml}\n<w:p><w:r><w:br w:type=\"page\"/></w:r></w:p>\n```",
+    }
+  }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MergeOptions {
+  /// 在相邻文档之间插入分页符
+  pub page_breaks: bool,
+  /// 在开头生成目录
+  pub toc: bool,
+  /// 目录包含的标题层级，默认 3
+  pub toc_depth: Option<u8>,
+  /// 目录标题，默认由 Pandoc 决定
+  pub toc_title: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MergeResult {
+  pub output: String,
+  pub format: MergeFormat,
+  pub documents: usize,
+}
+
+fn extension_of(path: &Path) -> String {
+  path
+    .extension()
+    .and_then(|e| e.to_str())
+    .unwrap_or_default()
+    .to_lowercase()
+}
+
+/// 按顺序拼接各文档的 Markdown，文档之间空一行或插入分页符
+fn join_documents(parts: &[String], format: MergeFormat, page_breaks: bool) -> String {
+  let separator = if page_breaks {
+    format!("\n\n{}\n\n", format.page_break())
+  } else {
+    "\n\n".to_string()
+  };
+  let mut merged = parts
+    .iter()
+    .map(|part| part.trim_end())
+    .filter(|part| !part.trim().is_empty())
+    .collect::<Vec<_>>()
+    .join(&separator);
+  merged.push('\n');
+  merged
+}
+
+fn pandoc_args(
+  format: MergeFormat,
+  options: &MergeOptions,
+  resource_dirs: &[PathBuf],
+  reference_doc: Option<&Path>,
+) -> Vec<String> {
+  let mut args = Vec::new();
+  if options.toc {
+    args.push("--standalone".to_string());
+    args.push("--toc".to_string());
+    args.push(format!(
+      "--toc-depth={}",
+      options.toc_depth.unwrap_or(DEFAULT_TOC_DEPTH).clamp(1, 6)
+    ));
+    if let Some(title) = options
+      .toc_title
+      .as_deref()
+      .filter(|t| !t.trim().is_empty())
+    {
+      args.push(format!("--metadata=toc-title:{}", title.trim()));
+    }
+  }
+  match format {
+    MergeFormat::Markdown => args.push("--wrap=none".to_string()),
+    MergeFormat::Docx => {
+      // Markdown 章节里的相对图片按各自所在目录查找
+      if !resource_dirs.is_empty() {
+        let separator = if cfg!(windows) { ";" } else { ":" };
+        let dirs: Vec<String> = resource_dirs
+          .iter()
+          .map(|d| d.to_string_lossy().to_string())
+          .collect();
+        args.push(format!("--resource-path={}", dirs.join(separator)));
+      }
+      if let Some(reference) = reference_doc {
+        args.push(format!("--reference-doc={}", reference.display()));
+      }
+    }
+  }
+  args
+}
+
+/// 合并文档；DOCX 章节的图片在输出 Markdown 时提取到 `<输出文件名>_media/`，
+/// 输出 DOCX 时提取到临时目录并嵌入文档
+pub fn merge_documents(
+  inputs: &[PathBuf],
+  output: &Path,
+  options: &MergeOptions,
+  reference_doc: Option<&Path>,
+) -> Result<MergeResult, String> {
+  if inputs.is_empty() {
+    return Err("没有要合并的文档".to_string());
+  }
+  let format = MergeFormat::from_path(output)
+    .ok_or_else(|| format!("只能合并为 Markdown 或 DOCX 文件: {}", output.display()))?;
+  let working_dir = output
+    .parent()
+    .ok_or_else(|| format!("无法获取输出目录: {}", output.display()))?;
+
+  let pandoc = PandocService::new();
+  let temp_media = match format {
+    MergeFormat::Docx => Some(temp_service::temp_dir("merge")?),
+    MergeFormat::Markdown => None,
+  };
+  let media_root = match &temp_media {
+    Some(dir) => dir.path().to_path_buf(),
+    None => {
+      let stem = output.file_stem().unwrap_or_default().to_string_lossy();
+      PathBuf::from(format!("{}_media", stem))
+    }
+  };
+
+  let mut parts = Vec::with_capacity(inputs.len());
+  let mut resource_dirs: Vec<PathBuf> = Vec::new();
+  for (index, input) in inputs.iter().enumerate() {
+    let part = match extension_of(input).as_str() {
+      "md" | "markdown" => {
+        if let Some(dir) = input.parent() {
+          if !resource_dirs.iter().any(|d| d == dir) {
+            resource_dirs.push(dir.to_path_buf());
+          }
+        }
+        std::fs::read_to_string(input)
+          .map_err(|e| format!("读取文件失败 {}: {}", input.display(), e))?
+      }
+      "docx" => {
+        let media_dir = media_root.join(format!("{:02}", index + 1));
+        pandoc.convert_docx_to_markdown(input, &media_dir, working_dir)?
+      }
+      _ => {
+        return Err(format!(
+          "只能合并 Markdown 或 DOCX 文档: {}",
+          input.display()
+        ))
+      }
+    };
+    parts.push(part);
+  }
+
+  let merged = join_documents(&parts, format, options.page_breaks);
+  let args = pandoc_args(format, options, &resource_dirs, reference_doc);
+  let result = pandoc.convert_markdown_to(&merged, format.writer(), output, &args);
+  drop(temp_media);
+  result?;
+
+  tracing::info!("合并 {} 个文档到 {}", inputs.len(), output.display());
+  Ok(MergeResult {
+    output: output.to_string_lossy().to_string(),
+    format,
+    documents: inputs.len(),
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn joins_documents_with_page_breaks() {
+    let parts = vec![
+      "# 第一章\n\n正文\n\n".to_string(),
+      "   \n".to_string(),
+      "# 第二章\n".to_string(),
+    ];
+    assert_eq!(
+      join_documents(&parts, MergeFormat::Markdown, false),
+      "# 第一章\n\n正文\n\n# 第二章\n"
+    );
+    let merged = join_documents(&parts, MergeFormat::Docx, true);
+    assert_eq!(merged.matches("w:type=\"page\"").count(), 1);
+    assert!(merged.starts_with("# 第一章\n\n正文\n\n```{=openxml}"));
+    assert!(merged.ends_with("```\n\n# 第二章\n"));
+  }
+
+  #[test]
+  fn builds_toc_and_reference_args() {
+    let options = MergeOptions {
+      toc: true,
+      toc_depth: Some(9),
+      toc_title: Some(" 目录 ".to_string()),
+      ..Default::default()
+    };
+    let args = pandoc_args(
+      MergeFormat::Docx,
+      &options,
+      &[PathBuf::from("/ws/a"), PathBuf::from("/ws/b")],
+      Some(Path::new("/ws/.binder/reference.docx")),
+    );
+    let separator = if cfg!(windows) { ";" } else { ":" };
+    assert_eq!(
+      args,
+      vec![
+        "--standalone".to_string(),
+        "--toc".to_string(),
+        "--toc-depth=6".to_string(),
+        "--metadata=toc-title:目录".to_string(),
+        format!("--resource-path=/ws/a{}/ws/b", separator),
+        "--reference-doc=/ws/.binder/reference.docx".to_string(),
+      ]
+    );
+
+    let args = pandoc_args(MergeFormat::Markdown, &MergeOptions::default(), &[], None);
+    assert_eq!(args, vec!["--wrap=none".to_string()]);
+    assert_eq!(
+      MergeFormat::from_path(Path::new("书稿.DOCX")),
+      Some(MergeFormat::Docx)
+    );
+    assert_eq!(MergeFormat::from_path(Path::new("a.pdf")), None);
+  }
+}
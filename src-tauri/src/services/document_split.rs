@@ -0,0 +1,322 @@
+//! 按标题拆分文档：把长篇 Markdown / DOCX 按 Hn 标题拆成每节一个文件，并生成链接各节的索引。
+//!
+//! 级别不高于 n 的 ATX 标题都会开始新的一节，代码块与 front matter 中的 `#` 不算标题；
+//! 第一个标题之前的内容单独成节。文件名为 `序号-标题`，与原文档同格式。
+//! Markdown 中的相对图片与链接改写为相对目标目录；DOCX 的图片经 Pandoc 提取后重新嵌入各节。
+
+use crate::services::image_references::{relative_reference, rewrite_image_references};
+use crate::services::pandoc_service::PandocService;
+use crate::services::temp_service;
+use crate::utils::path_validator::PathValidator;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+const INDEX_FILE_NAME: &str = "index.md";
+const MAX_TITLE_CHARS: usize = 60;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SplitSection {
+  pub title: String,
+  /// 标题级别，第一个标题之前的内容为 0
+  pub level: u8,
+  pub path: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SplitResult {
+  pub index: String,
+  pub sections: Vec<SplitSection>,
+}
+
+/// 拆分出的一节 Markdown
+#[derive(Debug, Clone, PartialEq)]
+struct Section<'a> {
+  title: String,
+  level: u8,
+  content: &'a str,
+}
+
+/// 解析 ATX 标题，返回（级别，标题文本）；去掉结尾的 `#`、`{#id}` 属性与强调标记
+fn parse_heading(line: &str) -> Option<(u8, String)> {
+  let trimmed = line.trim_end();
+  if line.len() - line.trim_start().len() > 3 {
+    return None;
+  }
+  let trimmed = trimmed.trim_start();
+  let level = trimmed.chars().take_while(|&c| c == '#').count();
+  if !(1..=6).contains(&level) {
+    return None;
+  }
+  let rest = &trimmed[level..];
+  if !rest.is_empty() && !rest.starts_with([' ', '\t']) {
+    return None;
+  }
+  let mut title = rest.trim();
+  if title.ends_with('}') {
+    if let Some(start) = title.rfind(" {") {
+      title = title[..start].trim_end();
+    }
+  }
+  let title = title.trim_end_matches('#').trim_end();
+  let title: String = title.chars().filter(|c| !matches!(c, '*' | '`')).collect();
+  Some((level as u8, title.trim().to_string()))
+}
+
+/// 按级别不高于 max_level 的标题切分 Markdown
+fn split_sections<'a>(markdown: &'a str, max_level: u8, preamble_title: &str) -> Vec<Section<'a>> {
+  let mut sections = Vec::new();
+  let mut start = 0;
+  let mut current: Option<(u8, String)> = None;
+  let mut offset = 0;
+  let mut fence: Option<String> = None;
+  let mut in_front_matter = false;
+
+  for (index, line) in markdown.split_inclusive('\n').enumerate() {
+    let line_start = offset;
+    offset += line.len();
+    let trimmed = line.trim();
+    if index == 0 && trimmed == "---" {
+      in_front_matter = true;
+      continue;
+    }
+    if in_front_matter {
+      in_front_matter = !matches!(trimmed, "---" | "...");
+      continue;
+    }
+    if let Some(marker) = &fence {
+      if trimmed.starts_with(marker.as_str())
+        && trimmed.trim_start_matches(marker.as_str()).is_empty()
+      {
+        fence = None;
+      }
+      continue;
+    }
+    if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+      let ch = trimmed.chars().next().unwrap_or('`');
+      fence = Some(trimmed.chars().take_while(|&c| c == ch).collect());
+      continue;
+    }
+    let Some((level, title)) = parse_heading(line).filter(|(level, _)| *level <= max_level) else {
+      continue;
+    };
+
+    let content = &markdown[start..line_start];
+    match current.take() {
+      Some((level, title)) => sections.push(Section {
+        title,
+        level,
+        content,
+      }),
+      None if !content.trim().is_empty() => sections.push(Section {
+        title: preamble_title.to_string(),
+        level: 0,
+        content,
+      }),
+      None => {}
+    }
+    start = line_start;
+    current = Some((level, title));
+  }
+
+  let content = &markdown[start..];
+  match current {
+    Some((level, title)) => sections.push(Section {
+      title,
+      level,
+      content,
+    }),
+    None if !content.trim().is_empty() => sections.push(Section {
+      title: preamble_title.to_string(),
+      level: 0,
+      content,
+    }),
+    None => {}
+  }
+  sections
+}
+
+/// 各节文件名：`01-标题.ext`，序号至少两位
+fn section_file_names(sections: &[Section], extension: &str) -> Vec<String> {
+  let width = sections.len().to_string().len().max(2);
+  sections
+    .iter()
+    .enumerate()
+    .map(|(i, section)| {
+      let title = PathValidator::sanitize_filename(&section.title, MAX_TITLE_CHARS)
+        .unwrap_or_else(|| "section".to_string());
+      format!("{:0width$}-{}.{}", i + 1, title, extension, width = width)
+    })
+    .collect()
+}
+
+/// 索引文件：按标题级别缩进的链接列表
+fn build_index(title: &str, sections: &[Section], file_names: &[String]) -> String {
+  let min_level = sections.iter().map(|s| s.level.max(1)).min().unwrap_or(1);
+  let mut index = format!("# {}\n\n", title);
+  for (section, file_name) in sections.iter().zip(file_names) {
+    let indent = "  ".repeat((section.level.max(1) - min_level) as usize);
+    let label = if section.title.is_empty() {
+      file_name.as_str()
+    } else {
+      section.title.as_str()
+    };
+    index.push_str(&format!("{}- [{}](<{}>)\n", indent, label, file_name));
+  }
+  index
+}
+
+fn remove_files(paths: &[PathBuf]) {
+  for path in paths {
+    let _ = std::fs::remove_file(path);
+  }
+}
+
+/// 拆分文档到 dest_dir；目标文件已存在时不覆盖，中途失败时删除已写入的文件
+pub fn split_document(
+  path: &Path,
+  level: u8,
+  dest_dir: &Path,
+  reference_doc: Option<&Path>,
+) -> Result<SplitResult, String> {
+  if !(1..=6).contains(&level) {
+    return Err(format!("标题级别必须在 1 到 6 之间: {}", level));
+  }
+  let extension = path
+    .extension()
+    .and_then(|e| e.to_str())
+    .unwrap_or_default()
+    .to_lowercase();
+  let stem = path
+    .file_stem()
+    .map(|s| s.to_string_lossy().to_string())
+    .unwrap_or_default();
+  std::fs::create_dir_all(dest_dir).map_err(|e| format!("创建目录失败: {}", e))?;
+
+  let pandoc = PandocService::new();
+  let temp_media = match extension.as_str() {
+    "docx" => Some(temp_service::temp_dir("split")?),
+    _ => None,
+  };
+  let markdown = match (&temp_media, extension.as_str()) {
+    (Some(media), _) => pandoc.convert_docx_to_markdown(path, media.path(), dest_dir)?,
+    (None, "md" | "markdown") => {
+      std::fs::read_to_string(path).map_err(|e| format!("读取文件失败: {}", e))?
+    }
+    _ => {
+      return Err(format!(
+        "只能拆分 Markdown 或 DOCX 文档: {}",
+        path.display()
+      ))
+    }
+  };
+
+  let sections = split_sections(&markdown, level, &stem);
+  if sections.len() < 2 {
+    return Err(format!("文档中没有可拆分的 H{} 标题", level));
+  }
+  let file_names = section_file_names(&sections, &extension);
+  let index_path = dest_dir.join(INDEX_FILE_NAME);
+  let targets: Vec<PathBuf> = file_names.iter().map(|name| dest_dir.join(name)).collect();
+  if let Some(existing) = targets
+    .iter()
+    .chain([&index_path])
+    .find(|target| target.exists())
+  {
+    return Err(format!("文件已存在: {}", existing.display()));
+  }
+
+  let source_dir = path.parent().unwrap_or(dest_dir);
+  let mut written = Vec::new();
+  for (section, target) in sections.iter().zip(&targets) {
+    let result = if temp_media.is_some() {
+      let args: Vec<String> = reference_doc
+        .map(|r| vec![format!("--reference-doc={}", r.display())])
+        .unwrap_or_default();
+      pandoc.convert_markdown_to(section.content, "docx", target, &args)
+    } else {
+      // 页内锚点与 mailto: 等链接保持原样
+      let content = rewrite_image_references(section.content, source_dir, |reference, target| {
+        (source_dir != dest_dir && !reference.starts_with('#') && !reference.contains(':'))
+          .then(|| relative_reference(dest_dir, target))
+      })
+      .unwrap_or_else(|| section.content.to_string());
+      std::fs::write(target, content).map_err(|e| format!("写入文件失败: {}", e))
+    };
+    if let Err(e) = result {
+      remove_files(&written);
+      return Err(e);
+    }
+    written.push(target.clone());
+  }
+  drop(temp_media);
+
+  if let Err(e) = std::fs::write(&index_path, build_index(&stem, &sections, &file_names)) {
+    remove_files(&written);
+    return Err(format!("写入索引失败: {}", e));
+  }
+  tracing::info!("文档已拆分为 {} 节: {}", sections.len(), path.display());
+
+  Ok(SplitResult {
+    index: index_path.to_string_lossy().to_string(),
+    sections: sections
+      .iter()
+      .zip(&targets)
+      .map(|(section, target)| SplitSection {
+        title: section.title.clone(),
+        level: section.level,
+        path: target.to_string_lossy().to_string(),
+      })
+      .collect(),
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const DOC: &str = "---\ntitle: 书稿\n# 不是标题\n---\n前言\n\n# 第一章: 起点 {#ch1}\n正文\n```\n# 代码\n```\n## 1.1 小节\n内容\n# **第二章** ##\n结尾\n";
+
+  #[test]
+  fn splits_on_headings_outside_code_and_front_matter() {
+    let sections = split_sections(DOC, 1, "书稿");
+    let titles: Vec<(&str, u8)> = sections
+      .iter()
+      .map(|s| (s.title.as_str(), s.level))
+      .collect();
+    assert_eq!(
+      titles,
+      vec![("书稿", 0), ("第一章: 起点", 1), ("第二章", 1)]
+    );
+    assert!(sections[0].content.starts_with("---\ntitle"));
+    assert!(sections[1].content.contains("# 代码\n```\n## 1.1 小节"));
+    assert_eq!(sections[2].content, "# **第二章** ##\n结尾\n");
+    assert_eq!(sections.iter().map(|s| s.content).collect::<String>(), DOC);
+
+    let sections = split_sections(DOC, 2, "书稿");
+    assert_eq!(sections.len(), 4);
+    assert_eq!(sections[2].title, "1.1 小节");
+  }
+
+  #[test]
+  fn names_files_and_builds_index() {
+    let sections = split_sections(DOC, 2, "书稿");
+    let names = section_file_names(&sections, "md");
+    assert_eq!(
+      names,
+      vec![
+        "01-书稿.md",
+        "02-第一章 起点.md",
+        "03-1.1 小节.md",
+        "04-第二章.md"
+      ]
+    );
+    assert_eq!(
+      build_index("书稿", &sections, &names),
+      "# 书稿\n\n- [书稿](<01-书稿.md>)\n- [第一章: 起点](<02-第一章 起点.md>)\n  - [1.1 小节](<03-1.1 小节.md>)\n- [第二章](<04-第二章.md>)\n"
+    );
+    assert_eq!(
+      PathValidator::sanitize_filename("  ..CON.. ", 10),
+      Some("_CON".to_string())
+    );
+  }
+}
@@ -0,0 +1,490 @@
+//! 文档加密：把文件加密为 `<原文件名>.binder.enc` 容器，口令经 Argon2id 派生密钥，
+//! 内容用 XChaCha20-Poly1305 加密。
+//!
+//! 容器格式：8 字节魔数 `BNDRENC1`、4 字节小端头部长度、JSON 头部（KDF 参数、盐、随机数），
+//! 其后为密文；魔数与头部作为附加认证数据，被篡改时解密失败。
+//!
+//! 解锁后的密钥按规范化后的文件路径缓存在内存中（仅本次运行），打开与保存不再询问口令；
+//! 保存时沿用同一盐与密钥，每次重新生成随机数。
+//!
+//! 透明打开只提供明文字节：文本经 `read_file_content`，图片、表格等二进制预览经 `read_file_as_base64`。
+//! DOCX 编辑需要 Pandoc 读取磁盘上的明文文件，加密的 DOCX 须先解密再编辑。
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::{engine::general_purpose, Engine as _};
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, KeyInit, OsRng, Payload};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use zeroize::Zeroizing;
+
+pub const ENCRYPTED_SUFFIX: &str = ".binder.enc";
+const MAGIC: &[u8; 8] = b"BNDRENC1";
+const FORMAT_VERSION: u32 = 1;
+const KEY_LEN: usize = 32;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+/// 头部长度上限，防止读取损坏文件时分配过大内存
+const MAX_HEADER_LEN: usize = 4096;
+/// 头部中 KDF 参数的上限（1 GiB 内存、100 轮），防止恶意文件耗尽资源
+const MAX_KDF_MEMORY_KIB: u32 = 1024 * 1024;
+const MAX_KDF_ITERATIONS: u32 = 100;
+const MIN_PASSPHRASE_CHARS: usize = 8;
+
+/// 已解锁文件的密钥缓存
+static KEY_CACHE: Lazy<Mutex<HashMap<PathBuf, CachedKey>>> =
+  Lazy::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Debug, thiserror::Error)]
+pub enum EncryptionError {
+  #[error("加密文件尚未解锁，请输入口令: {0}")]
+  Locked(String),
+  #[error("口令错误或文件已损坏")]
+  WrongPassphrase,
+  #[error("不是有效的加密文件: {0}")]
+  InvalidContainer(String),
+  #[error("{0}")]
+  Failed(String),
+}
+
+/// Argon2id 参数
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KdfParams {
+  pub memory_kib: u32,
+  pub iterations: u32,
+  pub parallelism: u32,
+}
+
+impl Default for KdfParams {
+  fn default() -> Self {
+    KdfParams {
+      memory_kib: 64 * 1024,
+      iterations: 3,
+      parallelism: 1,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ContainerHeader {
+  version: u32,
+  kdf: String,
+  kdf_params: KdfParams,
+  salt: String,
+  cipher: String,
+  nonce: String,
+}
+
+struct Container<'a> {
+  params: KdfParams,
+  salt: Vec<u8>,
+  nonce: Vec<u8>,
+  /// 魔数 + 头部长度 + 头部，作为附加认证数据
+  aad: &'a [u8],
+  ciphertext: &'a [u8],
+}
+
+struct CachedKey {
+  salt: Vec<u8>,
+  params: KdfParams,
+  key: Zeroizing<[u8; KEY_LEN]>,
+}
+
+pub fn is_encrypted_path(path: &Path) -> bool {
+  path
+    .file_name()
+    .and_then(|n| n.to_str())
+    .is_some_and(|n| n.len() > ENCRYPTED_SUFFIX.len() && n.ends_with(ENCRYPTED_SUFFIX))
+}
+
+/// `合同.docx` → `合同.docx.binder.enc`
+pub fn encrypted_path_for(path: &Path) -> PathBuf {
+  let mut name = path.as_os_str().to_os_string();
+  name.push(ENCRYPTED_SUFFIX);
+  PathBuf::from(name)
+}
+
+/// `合同.docx.binder.enc` → `合同.docx`
+pub fn decrypted_path_for(path: &Path) -> Option<PathBuf> {
+  let text = path.to_str()?;
+  is_encrypted_path(path).then(|| PathBuf::from(&text[..text.len() - ENCRYPTED_SUFFIX.len()]))
+}
+
+fn failed(context: &str, e: impl std::fmt::Display) -> EncryptionError {
+  EncryptionError::Failed(format!("{}: {}", context, e))
+}
+
+fn derive_key(
+  passphrase: &str,
+  salt: &[u8],
+  params: KdfParams,
+) -> Result<Zeroizing<[u8; KEY_LEN]>, EncryptionError> {
+  let argon_params = Params::new(
+    params.memory_kib,
+    params.iterations,
+    params.parallelism,
+    Some(KEY_LEN),
+  )
+  .map_err(|e| EncryptionError::InvalidContainer(format!("KDF 参数无效: {}", e)))?;
+  let mut key = Zeroizing::new([0u8; KEY_LEN]);
+  Argon2::new(Algorithm::Argon2id, Version::V0x13, argon_params)
+    .hash_password_into(passphrase.as_bytes(), salt, &mut *key)
+    .map_err(|e| failed("派生密钥失败", e))?;
+  Ok(key)
+}
+
+fn seal(
+  plaintext: &[u8],
+  salt: &[u8],
+  params: KdfParams,
+  key: &[u8; KEY_LEN],
+) -> Result<Vec<u8>, EncryptionError> {
+  let mut nonce = [0u8; NONCE_LEN];
+  OsRng.fill_bytes(&mut nonce);
+  let header = ContainerHeader {
+    version: FORMAT_VERSION,
+    kdf: "argon2id".to_string(),
+    kdf_params: params,
+    salt: general_purpose::STANDARD.encode(salt),
+    cipher: "xchacha20poly1305".to_string(),
+    nonce: general_purpose::STANDARD.encode(nonce),
+  };
+  let header_json = serde_json::to_vec(&header).map_err(|e| failed("序列化加密头部失败", e))?;
+
+  let mut output = Vec::with_capacity(MAGIC.len() + 4 + header_json.len() + plaintext.len() + 16);
+  output.extend_from_slice(MAGIC);
+  output.extend_from_slice(&(header_json.len() as u32).to_le_bytes());
+  output.extend_from_slice(&header_json);
+  let ciphertext = XChaCha20Poly1305::new(Key::from_slice(key))
+    .encrypt(
+      XNonce::from_slice(&nonce),
+      Payload {
+        msg: plaintext,
+        aad: &output,
+      },
+    )
+    .map_err(|_| EncryptionError::Failed("加密失败".to_string()))?;
+  output.extend_from_slice(&ciphertext);
+  Ok(output)
+}
+
+fn parse_container(data: &[u8]) -> Result<Container<'_>, EncryptionError> {
+  let invalid = |msg: &str| EncryptionError::InvalidContainer(msg.to_string());
+  if data.len() < MAGIC.len() + 4 || &data[..MAGIC.len()] != MAGIC {
+    return Err(invalid("缺少文件标识"));
+  }
+  let mut len_bytes = [0u8; 4];
+  len_bytes.copy_from_slice(&data[MAGIC.len()..MAGIC.len() + 4]);
+  let header_len = u32::from_le_bytes(len_bytes) as usize;
+  let header_end = MAGIC.len() + 4 + header_len;
+  if header_len > MAX_HEADER_LEN || data.len() < header_end {
+    return Err(invalid("头部长度无效"));
+  }
+  let header: ContainerHeader = serde_json::from_slice(&data[MAGIC.len() + 4..header_end])
+    .map_err(|e| EncryptionError::InvalidContainer(format!("头部解析失败: {}", e)))?;
+  if header.version != FORMAT_VERSION
+    || header.kdf != "argon2id"
+    || header.cipher != "xchacha20poly1305"
+  {
+    return Err(invalid("不支持的版本或算法"));
+  }
+  let params = header.kdf_params;
+  if params.memory_kib > MAX_KDF_MEMORY_KIB || params.iterations > MAX_KDF_ITERATIONS {
+    return Err(invalid("KDF 参数超出范围"));
+  }
+  let decode = |value: &str, len: usize| {
+    general_purpose::STANDARD
+      .decode(value)
+      .ok()
+      .filter(|bytes| bytes.len() == len)
+      .ok_or_else(|| invalid("盐或随机数无效"))
+  };
+  Ok(Container {
+    params,
+    salt: decode(&header.salt, SALT_LEN)?,
+    nonce: decode(&header.nonce, NONCE_LEN)?,
+    aad: &data[..header_end],
+    ciphertext: &data[header_end..],
+  })
+}
+
+fn open(container: &Container, key: &[u8; KEY_LEN]) -> Result<Vec<u8>, EncryptionError> {
+  XChaCha20Poly1305::new(Key::from_slice(key))
+    .decrypt(
+      XNonce::from_slice(&container.nonce),
+      Payload {
+        msg: container.ciphertext,
+        aad: container.aad,
+      },
+    )
+    .map_err(|_| EncryptionError::WrongPassphrase)
+}
+
+fn read_container(path: &Path) -> Result<Vec<u8>, EncryptionError> {
+  fs::read(path).map_err(|e| failed("读取加密文件失败", e))
+}
+
+/// 写入临时文件后重命名，避免保存中断时留下不完整的密文
+fn write_atomically(path: &Path, data: &[u8]) -> Result<(), EncryptionError> {
+  let file_name = path
+    .file_name()
+    .map(|n| n.to_string_lossy().to_string())
+    .unwrap_or_default();
+  let temp = path.with_file_name(format!(
+    ".{}.binder-enc-{}.tmp",
+    file_name,
+    uuid::Uuid::new_v4()
+  ));
+  fs::write(&temp, data).map_err(|e| failed("写入加密文件失败", e))?;
+  fs::rename(&temp, path).map_err(|e| {
+    let _ = fs::remove_file(&temp);
+    failed("写入加密文件失败", e)
+  })
+}
+
+/// 密钥缓存的键：同一文件经不同写法（`..`、符号链接）访问时使用同一把密钥
+fn cache_path(path: &Path) -> PathBuf {
+  fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+fn cache_key(path: &Path, salt: Vec<u8>, params: KdfParams, key: Zeroizing<[u8; KEY_LEN]>) {
+  let mut cache = KEY_CACHE.lock().unwrap_or_else(|e| e.into_inner());
+  cache.insert(cache_path(path), CachedKey { salt, params, key });
+}
+
+/// 用口令解密加密文件并缓存密钥，返回明文
+pub fn unlock(path: &Path, passphrase: &str) -> Result<Vec<u8>, EncryptionError> {
+  let data = read_container(path)?;
+  let container = parse_container(&data)?;
+  let key = derive_key(passphrase, &container.salt, container.params)?;
+  let plaintext = open(&container, &key)?;
+  cache_key(path, container.salt.clone(), container.params, key);
+  Ok(plaintext)
+}
+
+pub fn is_unlocked(path: &Path) -> bool {
+  let cache = KEY_CACHE.lock().unwrap_or_else(|e| e.into_inner());
+  cache.contains_key(&cache_path(path))
+}
+
+/// 清除缓存的密钥；path 为 None 时清除全部，返回清除的数量
+pub fn lock(path: Option<&Path>) -> usize {
+  let mut cache = KEY_CACHE.lock().unwrap_or_else(|e| e.into_inner());
+  match path {
+    Some(path) => usize::from(cache.remove(&cache_path(path)).is_some()),
+    None => {
+      let count = cache.len();
+      cache.clear();
+      count
+    }
+  }
+}
+
+/// 用缓存的密钥读取明文；未解锁或文件已用其他口令重新加密时返回 Locked
+pub fn read_encrypted(path: &Path) -> Result<Vec<u8>, EncryptionError> {
+  let data = read_container(path)?;
+  let container = parse_container(&data)?;
+  let cache = KEY_CACHE.lock().unwrap_or_else(|e| e.into_inner());
+  let cached = cache
+    .get(&cache_path(path))
+    .filter(|cached| cached.salt == container.salt && cached.params == container.params)
+    .ok_or_else(|| EncryptionError::Locked(path.to_string_lossy().to_string()))?;
+  open(&container, &cached.key)
+}
+
+/// 用缓存的密钥加密保存明文
+pub fn write_encrypted(path: &Path, plaintext: &[u8]) -> Result<(), EncryptionError> {
+  let sealed = {
+    let cache = KEY_CACHE.lock().unwrap_or_else(|e| e.into_inner());
+    let cached = cache
+      .get(&cache_path(path))
+      .ok_or_else(|| EncryptionError::Locked(path.to_string_lossy().to_string()))?;
+    seal(plaintext, &cached.salt, cached.params, &cached.key)?
+  };
+  write_atomically(path, &sealed)
+}
+
+fn check_passphrase(passphrase: &str) -> Result<(), EncryptionError> {
+  if passphrase.chars().count() < MIN_PASSPHRASE_CHARS {
+    return Err(EncryptionError::Failed(format!(
+      "口令至少需要 {} 个字符",
+      MIN_PASSPHRASE_CHARS
+    )));
+  }
+  Ok(())
+}
+
+fn encrypt_file_with(
+  path: &Path,
+  passphrase: &str,
+  keep_original: bool,
+  params: KdfParams,
+) -> Result<PathBuf, EncryptionError> {
+  check_passphrase(passphrase)?;
+  if is_encrypted_path(path) {
+    return Err(EncryptionError::Failed("文件已加密".to_string()));
+  }
+  let target = encrypted_path_for(path);
+  if target.exists() {
+    return Err(EncryptionError::Failed(format!(
+      "加密文件已存在: {}",
+      target.display()
+    )));
+  }
+  let plaintext = Zeroizing::new(fs::read(path).map_err(|e| failed("读取文件失败", e))?);
+  let mut salt = vec![0u8; SALT_LEN];
+  OsRng.fill_bytes(&mut salt);
+  let key = derive_key(passphrase, &salt, params)?;
+  let sealed = seal(&plaintext, &salt, params, &key)?;
+  write_atomically(&target, &sealed)?;
+  if !keep_original {
+    fs::remove_file(path).map_err(|e| failed("删除原文件失败", e))?;
+  }
+  cache_key(&target, salt, params, key);
+  Ok(target)
+}
+
+/// 加密文件为 `<文件名>.binder.enc`，默认删除明文原文件；返回加密文件路径
+pub fn encrypt_file(
+  path: &Path,
+  passphrase: &str,
+  keep_original: bool,
+) -> Result<PathBuf, EncryptionError> {
+  encrypt_file_with(path, passphrase, keep_original, KdfParams::default())
+}
+
+/// 解密为去掉 `.binder.enc` 后缀的原文件，默认删除加密文件；未提供口令时使用缓存的密钥
+pub fn decrypt_file(
+  path: &Path,
+  passphrase: Option<&str>,
+  keep_encrypted: bool,
+) -> Result<PathBuf, EncryptionError> {
+  let target = decrypted_path_for(path)
+    .ok_or_else(|| EncryptionError::InvalidContainer(path.to_string_lossy().to_string()))?;
+  if target.exists() {
+    return Err(EncryptionError::Failed(format!(
+      "目标文件已存在: {}",
+      target.display()
+    )));
+  }
+  let plaintext = Zeroizing::new(match passphrase {
+    Some(passphrase) => unlock(path, passphrase)?,
+    None => read_encrypted(path)?,
+  });
+  fs::write(&target, &*plaintext).map_err(|e| failed("写入解密文件失败", e))?;
+  if !keep_encrypted {
+    // 删除前锁定，文件不存在后无法再规范化路径
+    lock(Some(path));
+    fs::remove_file(path).map_err(|e| failed("删除加密文件失败", e))?;
+  }
+  Ok(target)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// 测试用的低成本 KDF 参数
+  const FAST: KdfParams = KdfParams {
+    memory_kib: 64,
+    iterations: 1,
+    parallelism: 1,
+  };
+
+  #[test]
+  fn container_round_trip_detects_wrong_key_and_tampering() {
+    let salt = [7u8; SALT_LEN];
+    let key = derive_key("correct horse", &salt, FAST).unwrap();
+    let sealed = seal("保密合同".as_bytes(), &salt, FAST, &key).unwrap();
+    assert!(sealed.starts_with(MAGIC));
+
+    let container = parse_container(&sealed).unwrap();
+    assert_eq!(container.params, FAST);
+    assert_eq!(open(&container, &key).unwrap(), "保密合同".as_bytes());
+
+    let wrong = derive_key("wrong horse", &salt, FAST).unwrap();
+    assert!(matches!(
+      open(&container, &wrong),
+      Err(EncryptionError::WrongPassphrase)
+    ));
+
+    // 头部属于认证数据：改动盐（仍为合法头部）后无法解密
+    let mut tampered = sealed.clone();
+    let marker = b"\"salt\":\"";
+    let pos = tampered
+      .windows(marker.len())
+      .position(|w| w == marker)
+      .unwrap();
+    tampered[pos + marker.len()] = b'C';
+    assert!(matches!(
+      open(&parse_container(&tampered).unwrap(), &key),
+      Err(EncryptionError::WrongPassphrase)
+    ));
+    let mut flipped = sealed;
+    let last = flipped.len() - 1;
+    flipped[last] ^= 1;
+    assert!(matches!(
+      open(&parse_container(&flipped).unwrap(), &key),
+      Err(EncryptionError::WrongPassphrase)
+    ));
+    assert!(matches!(
+      parse_container(b"not encrypted"),
+      Err(EncryptionError::InvalidContainer(_))
+    ));
+  }
+
+  #[test]
+  fn encrypts_saves_locks_and_decrypts_files() {
+    let dir = std::env::temp_dir().join(format!("binder_enc_{}", uuid::Uuid::new_v4()));
+    fs::create_dir_all(&dir).unwrap();
+    let plain = dir.join("合同.md");
+    fs::write(&plain, "# 合同\n").unwrap();
+
+    assert!(encrypt_file_with(&plain, "short", false, FAST).is_err());
+    let encrypted = encrypt_file_with(&plain, "passphrase-123", false, FAST).unwrap();
+    assert_eq!(encrypted, dir.join("合同.md.binder.enc"));
+    assert!(!plain.exists());
+    assert!(is_encrypted_path(&encrypted));
+    assert_eq!(decrypted_path_for(&encrypted).unwrap(), plain);
+
+    // 加密后即处于解锁状态，可直接读写
+    assert_eq!(read_encrypted(&encrypted).unwrap(), "# 合同\n".as_bytes());
+    write_encrypted(&encrypted, "# 合同 v2\n".as_bytes()).unwrap();
+    assert!(!fs::read(&encrypted)
+      .unwrap()
+      .windows(3)
+      .any(|w| w == b"v2\n"));
+
+    // 经 `..` 访问同一文件时沿用已缓存的密钥
+    fs::create_dir_all(dir.join("sub")).unwrap();
+    let alias = dir.join("sub").join("..").join("合同.md.binder.enc");
+    assert!(is_unlocked(&alias));
+    assert_eq!(read_encrypted(&alias).unwrap(), "# 合同 v2\n".as_bytes());
+
+    assert_eq!(lock(Some(&alias)), 1);
+    assert!(matches!(
+      read_encrypted(&encrypted),
+      Err(EncryptionError::Locked(_))
+    ));
+    assert!(matches!(
+      unlock(&encrypted, "passphrase-999"),
+      Err(EncryptionError::WrongPassphrase)
+    ));
+    assert_eq!(
+      unlock(&encrypted, "passphrase-123").unwrap(),
+      "# 合同 v2\n".as_bytes()
+    );
+
+    let restored = decrypt_file(&encrypted, None, false).unwrap();
+    assert_eq!(restored, plain);
+    assert_eq!(fs::read_to_string(&plain).unwrap(), "# 合同 v2\n");
+    assert!(!encrypted.exists());
+    assert!(!is_unlocked(&encrypted));
+    let _ = fs::remove_dir_all(&dir);
+  }
+}
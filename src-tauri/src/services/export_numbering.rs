@@ -0,0 +1,241 @@
+//! 导出编号：HTML 转 DOCX / PDF 前为标题、图片与表格加编号，并解析交叉引用。
+//!
+//! - 标题：从文档中最高一级标题起编号（`1.`、`1.1`、`1.1.1`），层数由设置决定
+//! - 图片：`<figure>` 的题注，以及单独成段的 `<img>`（在其后补一段题注，取 alt 文本）
+//! - 表格：已有 `<caption>` 时加前缀，否则补一个题注
+//! - 交叉引用：`<a href="#id">` 指向已编号元素且链接文字为空、等于 id 或以 `@` 开头时，
+//!   替换为编号（如“图 2”“1.3”）
+//!
+//! 编号是否启用由工作区设置 `export` 决定。
+
+use crate::services::workspace_settings::{ExportSettings, WorkspaceSettings};
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use std::collections::HashMap;
+use std::path::Path;
+
+static HEADING_TAG: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)<h([1-6])(\s[^>]*)?>").unwrap());
+static FIGURE_BLOCK: Lazy<Regex> = Lazy::new(|| {
+  Regex::new(r"(?is)<figure\b[^>]*>.*?</figure>|<p\b[^>]*>\s*<img\b[^>]*>\s*</p>").unwrap()
+});
+static FIGCAPTION_TAG: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)<figcaption\b[^>]*>").unwrap());
+static IMG_TAG: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)<img\b[^>]*>").unwrap());
+static TABLE_TAG: Lazy<Regex> =
+  Lazy::new(|| Regex::new(r"(?is)<table\b[^>]*>(\s*<caption\b[^>]*>)?").unwrap());
+static CROSS_REFERENCE: Lazy<Regex> = Lazy::new(|| {
+  Regex::new(r#"(?is)(<a\b[^>]*\bhref\s*=\s*["']#([^"']+)["'][^>]*>)(.*?)(</a>)"#).unwrap()
+});
+static ID_ATTR: Lazy<Regex> =
+  Lazy::new(|| Regex::new(r#"(?i)\sid\s*=\s*["']([^"']+)["']"#).unwrap());
+static ALT_ATTR: Lazy<Regex> =
+  Lazy::new(|| Regex::new(r#"(?i)\salt\s*=\s*["']([^"']*)["']"#).unwrap());
+static TAG: Lazy<Regex> = Lazy::new(|| Regex::new(r"<[^>]+>").unwrap());
+
+fn first_id(html: &str) -> Option<String> {
+  ID_ATTR.captures(html).map(|c| c[1].to_string())
+}
+
+/// 标题编号；顶层为 `1.`，其下为 `1.1`、`1.1.1`
+fn number_headings(html: &str, depth: u8, labels: &mut HashMap<String, String>) -> String {
+  let Some(base) = HEADING_TAG
+    .captures_iter(html)
+    .filter_map(|c| c[1].parse::<u8>().ok())
+    .min()
+  else {
+    return html.to_string();
+  };
+  let mut counters = [0usize; 6];
+  HEADING_TAG
+    .replace_all(html, |caps: &Captures| {
+      let level = caps[1].parse::<u8>().unwrap_or(base);
+      let relative = (level - base) as usize;
+      if relative >= depth as usize {
+        return caps[0].to_string();
+      }
+      counters[relative] += 1;
+      counters[relative + 1..].iter_mut().for_each(|c| *c = 0);
+      let number = counters[..=relative]
+        .iter()
+        .map(|c| c.to_string())
+        .collect::<Vec<_>>()
+        .join(".");
+      let display = if relative == 0 {
+        format!("{}.", number)
+      } else {
+        number.clone()
+      };
+      if let Some(id) = caps.get(2).and_then(|attrs| first_id(attrs.as_str())) {
+        labels.insert(id, number);
+      }
+      format!(
+        "{}<span class=\"heading-number\">{}</span> ",
+        &caps[0], display
+      )
+    })
+    .into_owned()
+}
+
+/// 图片编号：figure 的题注加前缀（没有题注时补一个），单独成段的图片在其后补题注段落
+fn number_figures(html: &str, label: &str, labels: &mut HashMap<String, String>) -> String {
+  let mut count = 0;
+  FIGURE_BLOCK
+    .replace_all(html, |caps: &Captures| {
+      let block = &caps[0];
+      count += 1;
+      let caption = format!("{} {}", label, count);
+      if let Some(id) = first_id(block) {
+        labels.insert(id, caption.clone());
+      }
+      let is_figure = block
+        .as_bytes()
+        .get(..7)
+        .is_some_and(|tag| tag.eq_ignore_ascii_case(b"<figure"));
+      if is_figure {
+        return match FIGCAPTION_TAG.find(block) {
+          Some(tag) => format!("{}{} {}", &block[..tag.end()], caption, &block[tag.end()..]),
+          None => {
+            let end = block.len() - "</figure>".len();
+            format!(
+              "{}<figcaption>{}</figcaption>{}",
+              &block[..end],
+              caption,
+              &block[end..]
+            )
+          }
+        };
+      }
+      let alt = IMG_TAG
+        .find(block)
+        .and_then(|img| ALT_ATTR.captures(img.as_str()))
+        .map(|c| c[1].trim().to_string())
+        .filter(|alt| !alt.is_empty());
+      let text = match alt {
+        Some(alt) => format!("{} {}", caption, alt),
+        None => caption,
+      };
+      format!("{}<p class=\"figure-caption\">{}</p>", block, text)
+    })
+    .into_owned()
+}
+
+/// 表格编号：已有 caption 时加前缀，否则在表格开头补一个
+fn number_tables(html: &str, label: &str, labels: &mut HashMap<String, String>) -> String {
+  let mut count = 0;
+  TABLE_TAG
+    .replace_all(html, |caps: &Captures| {
+      count += 1;
+      let caption = format!("{} {}", label, count);
+      if let Some(id) = first_id(&caps[0]) {
+        labels.insert(id, caption.clone());
+      }
+      if caps.get(1).is_some() {
+        format!("{}{} ", &caps[0], caption)
+      } else {
+        format!("{}<caption>{}</caption>", &caps[0], caption)
+      }
+    })
+    .into_owned()
+}
+
+/// 把指向已编号元素的空链接或占位链接替换为编号
+fn resolve_cross_references(html: &str, labels: &HashMap<String, String>) -> String {
+  CROSS_REFERENCE
+    .replace_all(html, |caps: &Captures| {
+      let id = &caps[2];
+      let text = TAG.replace_all(&caps[3], "");
+      let text = text.trim();
+      match labels.get(id) {
+        Some(label) if text.is_empty() || text == id || text.starts_with('@') => {
+          format!("{}{}{}", &caps[1], label, &caps[4])
+        }
+        _ => caps[0].to_string(),
+      }
+    })
+    .into_owned()
+}
+
+/// 按设置为 HTML 加编号；未启用任何编号时原样返回
+pub fn apply_numbering(html: &str, settings: &ExportSettings) -> String {
+  if !settings.is_enabled() {
+    return html.to_string();
+  }
+  let mut labels = HashMap::new();
+  let mut html = html.to_string();
+  if settings.number_headings {
+    html = number_headings(&html, settings.heading_number_depth, &mut labels);
+  }
+  if settings.number_figures {
+    html = number_figures(&html, settings.figure_label.trim(), &mut labels);
+  }
+  if settings.number_tables {
+    html = number_tables(&html, settings.table_label.trim(), &mut labels);
+  }
+  resolve_cross_references(&html, &labels)
+}
+
+/// 按文档所在工作区（向上查找含 .binder 的目录）的导出设置加编号
+pub fn apply_workspace_numbering(path: &Path, html: &str) -> String {
+  let Some(workspace) = path
+    .parent()
+    .and_then(|dir| dir.ancestors().find(|d| d.join(".binder").is_dir()))
+  else {
+    return html.to_string();
+  };
+  apply_numbering(html, &WorkspaceSettings::load_or_default(workspace).export)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn settings() -> ExportSettings {
+    ExportSettings {
+      number_headings: true,
+      heading_number_depth: 2,
+      number_figures: true,
+      number_tables: true,
+      ..Default::default()
+    }
+  }
+
+  #[test]
+  fn numbers_headings_from_top_level() {
+    let html = "<h2 id=\"intro\">引言</h2><h3>背景</h3><h4>细节</h4><h3 id=\"goal\">目标</h3><h2>方法</h2><h3>步骤</h3>";
+    let numbered = apply_numbering(html, &settings());
+    let numbers: Vec<String> = Regex::new(r#"heading-number">([^<]+)<"#)
+      .unwrap()
+      .captures_iter(&numbered)
+      .map(|c| c[1].to_string())
+      .collect();
+    assert_eq!(numbers, vec!["1.", "1.1", "1.2", "2.", "2.1"]);
+    assert!(numbered.contains("<h4>细节</h4>"));
+    assert_eq!(
+      apply_numbering(html, &ExportSettings::default()),
+      html.to_string()
+    );
+  }
+
+  #[test]
+  fn numbers_figures_and_tables_and_resolves_references() {
+    let html = concat!(
+      "<p>见<a href=\"#fig-arch\"></a>与<a href=\"#tbl-cost\">@tbl-cost</a>，",
+      "参考<a href=\"#goal\">目标一节</a>、<a href=\"#sec\">@sec</a>。</p>",
+      "<p><img src=\"a.png\" alt=\"流程\"></p>",
+      "<figure id=\"fig-arch\"><img src=\"b.png\"><figcaption>架构</figcaption></figure>",
+      "<figure><img src=\"c.png\"></figure>",
+      "<table id=\"tbl-cost\"><tr><td>1</td></tr></table>",
+      "<table><caption>汇总</caption><tr><td>2</td></tr></table>",
+      "<h1 id=\"sec\">结论</h1>",
+    );
+    let numbered = apply_numbering(html, &settings());
+    assert!(numbered.contains("<p class=\"figure-caption\">图 1 流程</p>"));
+    assert!(numbered.contains("<figcaption>图 2 架构</figcaption>"));
+    assert!(numbered.contains("<figure><img src=\"c.png\"><figcaption>图 3</figcaption></figure>"));
+    assert!(numbered.contains("<table id=\"tbl-cost\"><caption>表 1</caption>"));
+    assert!(numbered.contains("<caption>表 2 汇总</caption>"));
+    assert!(numbered.contains("见<a href=\"#fig-arch\">图 2</a>与<a href=\"#tbl-cost\">表 1</a>"));
+    // 有文字的链接与未编号的目标保持原样
+    assert!(numbered.contains("<a href=\"#goal\">目标一节</a>"));
+    assert!(numbered.contains("<a href=\"#sec\">1</a>"));
+  }
+}
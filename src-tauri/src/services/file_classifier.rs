@@ -1,7 +1,11 @@
 use crate::services::ai_providers::AIProvider;
+use crate::services::classify_rules::ClassifyRuleSet;
+use crate::services::content_type::{detect_content_type, probe_content, ContentType};
+use crate::services::file_organizer::FileBatchProgress;
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -10,6 +14,12 @@ pub struct FileClassification {
   pub category: String,
   pub reason: String,
   pub confidence: f64,
+  /// 分类规则给出的标签（AI 分类时为空）
+  #[serde(default)]
+  pub tag: Option<String>,
+  /// 内容类型（invoice / contract / meeting_notes / scanned_document），无法判断时为空
+  #[serde(default)]
+  pub content_type: Option<String>,
 }
 
 pub struct FileClassifierService;
@@ -19,25 +29,6 @@ impl FileClassifierService {
     Self
   }
 
-  // ⚠️ Week 20.1：提取文件内容（纯文本）
-  pub fn extract_text_content(path: &Path) -> Result<String, String> {
-    let ext = path
-      .extension()
-      .and_then(|s| s.to_str())
-      .unwrap_or("")
-      .to_lowercase();
-
-    match ext.as_str() {
-      "txt" | "md" | "html" | "htm" | "css" | "js" | "ts" | "json" | "xml" | "yaml" | "yml" => {
-        fs::read_to_string(path).map_err(|e| format!("读取文件失败: {}", e))
-      }
-      _ => {
-        // 尝试作为文本读取（UTF-8）
-        fs::read_to_string(path).map_err(|e| format!("读取文件失败: {}", e))
-      }
-    }
-  }
-
   // ⚠️ Week 20.1：获取现有文件夹列表
   pub fn get_existing_folders(workspace_path: &Path) -> Result<Vec<String>, String> {
     let mut folders = Vec::new();
@@ -65,6 +56,7 @@ impl FileClassifierService {
     path: &Path,
     content: &str,
     existing_folders: &[String],
+    detected_type: Option<ContentType>,
   ) -> Result<FileClassification, String> {
     // 构建分类提示词
     let content_preview = content.chars().take(2000).collect::<String>();
@@ -109,10 +101,13 @@ impl FileClassifierService {
 
     // 使用 inline_assist 方法进行简单分类（更简单且不需要流式处理）
     let instruction = format!(
-            "分析以下文件内容，并建议将其分类到哪个文件夹。\n\n文件路径：{}\n文件内容（前 2000 字符）：\n{}\n\n现有文件夹：{}\n\n请根据文件内容、文件名和路径，建议一个合适的文件夹名称。如果现有文件夹都不合适，可以建议创建新文件夹。\n\n请返回 JSON 格式（必须是有效的 JSON）：\n{{\n    \"category\": \"文件夹名称（建议使用中文，如：文档、代码、图片、设计等）\",\n    \"reason\": \"分类原因（简短说明）\",\n    \"confidence\": 0.9\n}}\n\n只返回 JSON，不要其他文字。",
+            "分析以下文件内容，并建议将其分类到哪个文件夹。\n\n文件路径：{}\n文件内容（前 2000 字符）：\n{}\n\n现有文件夹：{}\n\n请根据文件内容、文件名和路径，建议一个合适的文件夹名称。如果现有文件夹都不合适，可以建议创建新文件夹。\n{}\n请返回 JSON 格式（必须是有效的 JSON）：\n{{\n    \"category\": \"文件夹名称（建议使用中文，如：文档、代码、图片、设计等）\",\n    \"reason\": \"分类原因（简短说明）\",\n    \"confidence\": 0.9,\n    \"content_type\": \"内容类型：invoice（发票）、contract（合同）、meeting_notes（会议纪要）、scanned_document（扫描件），都不是则为 null\"\n}}\n\n只返回 JSON，不要其他文字。",
             path.display(),
             content_preview,
-            folders_list
+            folders_list,
+            detected_type
+              .map(|t| format!("\n初步识别的内容类型：{}\n", t.as_str()))
+              .unwrap_or_default()
         );
 
     let response = provider
@@ -131,57 +126,159 @@ impl FileClassifierService {
 
     // 设置文件路径
     classification.file_path = path.to_string_lossy().to_string();
+    // 启发式识别结果优先，AI 给出的类型只接受已知取值
+    classification.content_type = detected_type
+      .or_else(|| {
+        classification
+          .content_type
+          .as_deref()
+          .and_then(ContentType::parse)
+      })
+      .map(|t| t.as_str().to_string());
 
     Ok(classification)
   }
 
   // ⚠️ Week 20.2：批量分类文件
+  // 先按 .binder/classify_rules.json 中的用户规则分类，未命中的文件再交给 AI；
+  // 每处理完一个文件回调一次进度，cancel 被置位时中止
   pub async fn classify_files(
-    provider: Arc<dyn AIProvider>,
+    provider: Option<Arc<dyn AIProvider>>,
     files: Vec<PathBuf>,
     workspace_path: &Path,
+    on_progress: impl Fn(&FileBatchProgress),
+    cancel: &AtomicBool,
   ) -> Result<Vec<FileClassification>, String> {
+    let rules = ClassifyRuleSet::load(workspace_path)?;
+
     // 获取现有文件夹列表
     let existing_folders = Self::get_existing_folders(workspace_path)?;
 
+    let total = files.len();
     let mut classifications = Vec::new();
+    let mut errors = 0;
 
     for file in files {
-      // 提取文件内容
-      match Self::extract_text_content(&file) {
-        Ok(content) => {
-          // 使用 AI 分类
-          match Self::classify_file_with_ai(provider.clone(), &file, &content, &existing_folders)
-            .await
-          {
-            Ok(classification) => {
-              classifications.push(classification);
-            }
-            Err(e) => {
-              eprintln!("分类文件失败 {}: {}", file.display(), e);
-              // 使用默认分类
-              classifications.push(FileClassification {
-                file_path: file.to_string_lossy().to_string(),
-                category: "未分类".to_string(),
-                reason: format!("分类失败: {}", e),
-                confidence: 0.0,
-              });
+      if cancel.load(Ordering::Relaxed) {
+        return Err("分类已取消".to_string());
+      }
+
+      let probe = probe_content(&file);
+      let detected_type = probe
+        .as_ref()
+        .ok()
+        .and_then(|p| detect_content_type(&file, p))
+        .map(|guess| guess.content_type);
+
+      let classification = if let Some(mut classification) = rules.classify(&file) {
+        classification.content_type = detected_type.map(|t| t.as_str().to_string());
+        classification
+      } else {
+        let Some(provider) = &provider else {
+          return Err(format!(
+            "{} 未匹配任何分类规则，且未配置任何 AI 提供商，请先配置 DeepSeek 或 OpenAI API key",
+            file.display()
+          ));
+        };
+
+        // 提取文件内容后使用 AI 分类
+        let result = match probe {
+          Ok(probe) => Self::classify_file_with_ai(
+            provider.clone(),
+            &file,
+            &probe.text,
+            &existing_folders,
+            detected_type,
+          )
+          .await
+          .map_err(|e| format!("分类失败: {}", e)),
+          Err(e) => Err(format!("无法读取文件: {}", e)),
+        };
+        match result {
+          Ok(classification) => classification,
+          Err(reason) => {
+            tracing::warn!("分类文件失败 {}: {}", file.display(), reason);
+            errors += 1;
+            // 使用默认分类
+            FileClassification {
+              file_path: file.to_string_lossy().to_string(),
+              category: "未分类".to_string(),
+              reason,
+              confidence: 0.0,
+              tag: None,
+              content_type: detected_type.map(|t| t.as_str().to_string()),
             }
           }
         }
-        Err(e) => {
-          eprintln!("读取文件失败 {}: {}", file.display(), e);
-          // 使用默认分类
-          classifications.push(FileClassification {
-            file_path: file.to_string_lossy().to_string(),
-            category: "未分类".to_string(),
-            reason: format!("无法读取文件: {}", e),
-            confidence: 0.0,
-          });
-        }
-      }
+      };
+
+      on_progress(&FileBatchProgress {
+        current_file: classification.file_path.clone(),
+        processed: classifications.len() + 1,
+        total,
+        errors,
+      });
+      classifications.push(classification);
     }
 
     Ok(classifications)
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[tokio::test]
+  async fn rule_matches_report_progress_and_honour_cancellation() {
+    let workspace = std::env::temp_dir().join(format!("binder_classify_{}", uuid::Uuid::new_v4()));
+    fs::create_dir_all(workspace.join(".binder")).unwrap();
+    fs::write(
+      ClassifyRuleSet::rules_path(&workspace),
+      r#"{ "rules": [{ "extensions": ["md"], "folder": "笔记" }] }"#,
+    )
+    .unwrap();
+    let files: Vec<PathBuf> = ["a.md", "b.md"].iter().map(|n| workspace.join(n)).collect();
+    for file in &files {
+      fs::write(file, "# 笔记").unwrap();
+    }
+
+    let progress = std::cell::RefCell::new(Vec::new());
+    let classified = FileClassifierService::classify_files(
+      None,
+      files.clone(),
+      &workspace,
+      |p| progress.borrow_mut().push((p.processed, p.total, p.errors)),
+      &AtomicBool::new(false),
+    )
+    .await
+    .unwrap();
+    assert!(classified.iter().all(|c| c.category == "笔记"));
+    assert_eq!(progress.into_inner(), vec![(1, 2, 0), (2, 2, 0)]);
+
+    let cancelled = FileClassifierService::classify_files(
+      None,
+      files,
+      &workspace,
+      |_| panic!("已取消时不应再报告进度"),
+      &AtomicBool::new(true),
+    )
+    .await;
+    assert!(cancelled.is_err());
+
+    // 未命中规则且没有 AI 提供商时报错，而不是静默归为未分类
+    let other = workspace.join("c.txt");
+    fs::write(&other, "text").unwrap();
+    let unmatched = FileClassifierService::classify_files(
+      None,
+      vec![other],
+      &workspace,
+      |_| {},
+      &AtomicBool::new(false),
+    )
+    .await;
+    assert!(unmatched.unwrap_err().contains("c.txt"));
+
+    let _ = fs::remove_dir_all(&workspace);
+  }
+}
@@ -0,0 +1,348 @@
+//! 文件整理：根据分类结果生成移动计划（可先预览、调整），再按计划移动文件。
+
+use crate::services::file_classifier::FileClassification;
+use crate::services::organize_history::{JournalMove, OrganizeHistory};
+use crate::utils::glob_patterns::relative_slash_path;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileMoveResult {
+  pub file_path: String,
+  pub success: bool,
+  pub message: String,
+}
+
+/// 批量分类 / 整理的进度
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileBatchProgress {
+  pub current_file: String,
+  pub processed: usize,
+  pub total: usize,
+  pub errors: usize,
+}
+
+/// 计划中的单个文件移动
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlannedMove {
+  pub file_path: String,
+  pub category: String,
+  #[serde(default)]
+  pub tag: Option<String>,
+  #[serde(default)]
+  pub reason: String,
+  #[serde(default)]
+  pub content_type: Option<String>,
+  /// 目标路径；为空表示保持原位置
+  pub destination: Option<String>,
+  /// 目标位置已有同名文件，已改用带时间戳的文件名
+  #[serde(default)]
+  pub conflict: bool,
+}
+
+/// 整理计划（只描述移动，不改动磁盘）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrganizePlan {
+  pub moves: Vec<PlannedMove>,
+  pub total: usize,
+  pub move_count: usize,
+  pub unchanged_count: usize,
+  pub conflict_count: usize,
+  /// 需要新建的分类文件夹（相对工作区）
+  pub new_folders: Vec<String>,
+}
+
+pub struct FileOrganizer;
+
+impl FileOrganizer {
+  /// 根据分类结果生成移动计划
+  pub fn plan(workspace_path: &Path, classifications: &[FileClassification]) -> OrganizePlan {
+    let timestamp = std::time::SystemTime::now()
+      .duration_since(std::time::UNIX_EPOCH)
+      .unwrap_or_default()
+      .as_secs();
+    // 计划内已占用的目标路径，避免两个同名文件移动到同一位置
+    let mut claimed: HashSet<PathBuf> = HashSet::new();
+    let mut new_folders: Vec<String> = Vec::new();
+
+    let moves: Vec<PlannedMove> = classifications
+      .iter()
+      .map(|classification| {
+        let source = PathBuf::from(&classification.file_path);
+        let mut planned = PlannedMove {
+          file_path: classification.file_path.clone(),
+          category: classification.category.clone(),
+          tag: classification.tag.clone(),
+          reason: classification.reason.clone(),
+          content_type: classification.content_type.clone(),
+          destination: None,
+          conflict: false,
+        };
+        let Some(file_name) = source.file_name() else {
+          return planned;
+        };
+        if classification.category.is_empty() || !is_workspace_relative(&classification.category) {
+          return planned;
+        }
+
+        let category_dir = workspace_path.join(&classification.category);
+        let mut dest = category_dir.join(file_name);
+        // 已经在目标文件夹中
+        if dest == source {
+          return planned;
+        }
+
+        if dest.exists() || claimed.contains(&dest) {
+          planned.conflict = true;
+          let stem = source
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("file");
+          let ext = source
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| format!(".{}", e))
+            .unwrap_or_default();
+          dest = category_dir.join(format!("{}_{}{}", stem, timestamp, ext));
+          let mut n = 2;
+          while dest.exists() || claimed.contains(&dest) {
+            dest = category_dir.join(format!("{}_{}_{}{}", stem, timestamp, n, ext));
+            n += 1;
+          }
+        }
+
+        if !category_dir.exists() && !new_folders.contains(&classification.category) {
+          new_folders.push(classification.category.clone());
+        }
+        claimed.insert(dest.clone());
+        planned.destination = Some(dest.to_string_lossy().to_string());
+        planned
+      })
+      .collect();
+
+    let move_count = moves.iter().filter(|m| m.destination.is_some()).count();
+    OrganizePlan {
+      total: moves.len(),
+      move_count,
+      unchanged_count: moves.len() - move_count,
+      conflict_count: moves.iter().filter(|m| m.conflict).count(),
+      new_folders,
+      moves,
+    }
+  }
+
+  /// 按计划移动文件；计划可能经用户调整，执行前再次校验目标路径。
+  /// 实际发生的移动记入整理历史，供 `undo_last_organize` 撤销。
+  /// cancel 被置位后剩余文件不再移动，已移动的文件仍可撤销
+  pub fn execute(
+    workspace_path: &Path,
+    plan: &OrganizePlan,
+    on_progress: impl Fn(&FileBatchProgress),
+    cancel: &AtomicBool,
+  ) -> Vec<FileMoveResult> {
+    let history = OrganizeHistory::new(workspace_path);
+    let mut journal = Vec::new();
+    let mut created_folders = Vec::new();
+    let total = plan.moves.len();
+    let mut errors = 0;
+
+    let results = plan
+      .moves
+      .iter()
+      .enumerate()
+      .map(|(index, planned)| {
+        let moved = Self::execute_move(
+          workspace_path,
+          planned,
+          &history,
+          &mut journal,
+          &mut created_folders,
+          cancel,
+        );
+        if !moved.success {
+          errors += 1;
+        }
+        on_progress(&FileBatchProgress {
+          current_file: planned.file_path.clone(),
+          processed: index + 1,
+          total,
+          errors,
+        });
+        moved
+      })
+      .collect();
+
+    if let Err(e) = history.record(journal, created_folders) {
+      tracing::warn!("记录整理历史失败: {}", e);
+    }
+    results
+  }
+
+  /// 移动单个文件，成功时追加整理记录
+  fn execute_move(
+    workspace_path: &Path,
+    planned: &PlannedMove,
+    history: &OrganizeHistory,
+    journal: &mut Vec<JournalMove>,
+    created_folders: &mut Vec<String>,
+    cancel: &AtomicBool,
+  ) -> FileMoveResult {
+    let result = |success: bool, message: String| FileMoveResult {
+      file_path: planned.file_path.clone(),
+      success,
+      message,
+    };
+    if cancel.load(Ordering::Relaxed) {
+      return result(false, "整理已取消，未移动".to_string());
+    }
+
+    let Some(destination) = &planned.destination else {
+      let message = match &planned.tag {
+        Some(tag) => format!("{}，仅标记为「{}」，未移动", planned.reason, tag),
+        None => "保持原位置".to_string(),
+      };
+      return result(true, message);
+    };
+
+    let source = PathBuf::from(&planned.file_path);
+    let dest = PathBuf::from(destination);
+    let relative = match dest.strip_prefix(workspace_path) {
+      Ok(relative) if is_workspace_relative(&relative.to_string_lossy()) => relative,
+      _ => return result(false, format!("目标路径不在工作区内: {}", destination)),
+    };
+    if !source.exists() {
+      return result(false, "源文件不存在".to_string());
+    }
+    if dest.exists() {
+      return result(false, format!("目标文件已存在: {}", relative.display()));
+    }
+    if let Some(parent) = dest.parent() {
+      let missing: Vec<String> = parent
+        .ancestors()
+        .take_while(|dir| *dir != workspace_path && !dir.exists())
+        .map(|dir| relative_slash_path(dir, workspace_path))
+        .collect();
+      if let Err(e) = fs::create_dir_all(parent) {
+        return result(false, format!("创建文件夹失败: {}", e));
+      }
+      created_folders.extend(missing);
+    }
+    match fs::rename(&source, &dest) {
+      Ok(_) => {
+        journal.push(history.journal_move(&source, &dest));
+        result(true, format!("已移动到 {}", relative.display()))
+      }
+      Err(e) => result(false, format!("移动失败: {}", e)),
+    }
+  }
+}
+
+/// 只允许工作区内的相对路径
+fn is_workspace_relative(path: &str) -> bool {
+  let path = Path::new(path);
+  path.components().next().is_some() && path.components().all(|c| matches!(c, Component::Normal(_)))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn classification(path: &Path, category: &str) -> FileClassification {
+    FileClassification {
+      file_path: path.to_string_lossy().to_string(),
+      category: category.to_string(),
+      reason: "测试".to_string(),
+      confidence: 1.0,
+      tag: None,
+      content_type: None,
+    }
+  }
+
+  #[test]
+  fn plan_is_dry_run_and_execute_follows_it() {
+    let workspace = std::env::temp_dir().join(format!("binder_organize_{}", uuid::Uuid::new_v4()));
+    fs::create_dir_all(workspace.join("a")).unwrap();
+    fs::create_dir_all(workspace.join("文档")).unwrap();
+    let first = workspace.join("report.md");
+    let second = workspace.join("a/report.md");
+    let existing = workspace.join("文档/report.md");
+    for path in [&first, &second, &existing] {
+      fs::write(path, "x").unwrap();
+    }
+
+    let plan = FileOrganizer::plan(
+      &workspace,
+      &[
+        classification(&first, "报告"),
+        classification(&second, "报告"),
+        classification(&existing, "文档"),
+        classification(&first, "../外部"),
+      ],
+    );
+    assert_eq!(
+      (plan.total, plan.move_count, plan.unchanged_count),
+      (4, 2, 2)
+    );
+    assert_eq!(plan.conflict_count, 1);
+    assert_eq!(plan.new_folders, vec!["报告".to_string()]);
+    assert!(!workspace.join("报告").exists());
+
+    let plan = OrganizePlan {
+      moves: plan.moves[..2].to_vec(),
+      ..plan
+    };
+    let cancelled = FileOrganizer::execute(&workspace, &plan, |_| {}, &AtomicBool::new(true));
+    assert!(cancelled.iter().all(|r| !r.success));
+    assert!(!workspace.join("报告").exists());
+
+    let progress = std::cell::RefCell::new(Vec::new());
+    let results = FileOrganizer::execute(
+      &workspace,
+      &plan,
+      |p| progress.borrow_mut().push(p.processed),
+      &AtomicBool::new(false),
+    );
+    assert!(results.iter().all(|r| r.success), "{:?}", results);
+    assert_eq!(progress.into_inner(), vec![1, 2]);
+    assert!(workspace.join("报告/report.md").exists());
+    assert_eq!(fs::read_dir(workspace.join("报告")).unwrap().count(), 2);
+
+    let _ = fs::remove_dir_all(&workspace);
+  }
+
+  #[test]
+  fn cancelling_mid_batch_keeps_finished_moves_undoable() {
+    let workspace = std::env::temp_dir().join(format!("binder_organize_{}", uuid::Uuid::new_v4()));
+    fs::create_dir_all(&workspace).unwrap();
+    let files: Vec<PathBuf> = ["one.md", "two.md", "three.md"]
+      .iter()
+      .map(|n| workspace.join(n))
+      .collect();
+    for path in &files {
+      fs::write(path, "x").unwrap();
+    }
+    let classifications: Vec<_> = files.iter().map(|f| classification(f, "笔记")).collect();
+    let plan = FileOrganizer::plan(&workspace, &classifications);
+
+    // 第一个文件处理完后取消
+    let cancel = AtomicBool::new(false);
+    let results = FileOrganizer::execute(
+      &workspace,
+      &plan,
+      |_| cancel.store(true, Ordering::Relaxed),
+      &cancel,
+    );
+    let moved: Vec<bool> = results.iter().map(|r| r.success).collect();
+    assert_eq!(moved, vec![true, false, false]);
+    assert!(workspace.join("two.md").exists() && workspace.join("three.md").exists());
+
+    OrganizeHistory::new(&workspace).undo_last().unwrap();
+    assert!(files.iter().all(|f| f.exists()));
+    assert!(!workspace.join("笔记").join("one.md").exists());
+
+    let _ = fs::remove_dir_all(&workspace);
+  }
+}
@@ -0,0 +1,295 @@
+//! 文档模板：新建文件时展开 `{{变量}}`。
+//!
+//! 内置变量：`{{title}}`（新文件名，不含扩展名）、`{{date}}`、`{{time}}`、`{{datetime}}`、
+//! `{{workspace}}`（工作区文件夹名）、`{{uuid}}`。需要用户填写的变量在模板 front matter 的
+//! `prompts` 中声明，生成文件时从 front matter 中移除：
+//!
+//! ```yaml
+//! ---
+//! title: "{{title}}"
+//! prompts:
+//!   - name: client
+//!     label: 客户名称
+//!     default: ACME
+//!   - due
+//! ---
+//! ```
+
+use chrono::{DateTime, Local};
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+static VARIABLE_RE: Lazy<Regex> =
+  Lazy::new(|| Regex::new(r"\{\{\s*([A-Za-z_][A-Za-z0-9_-]*)\s*\}\}").unwrap());
+
+/// 需要用户填写的模板变量
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TemplatePrompt {
+  pub name: String,
+  /// 显示给用户的说明，未声明时与 name 相同
+  pub label: String,
+  pub default: Option<String>,
+}
+
+/// 解析后的模板：正文（已去掉 `prompts` 声明）与变量提示
+#[derive(Debug, Clone)]
+pub struct FileTemplate {
+  body: String,
+  pub prompts: Vec<TemplatePrompt>,
+}
+
+/// 内置变量的取值来源
+#[derive(Debug, Clone)]
+pub struct TemplateContext {
+  pub title: String,
+  pub workspace: String,
+  pub now: DateTime<Local>,
+}
+
+impl TemplateContext {
+  /// 以目标文件名与工作区目录名构造，时间取当前时间
+  pub fn new(workspace_path: &Path, target: &Path) -> Self {
+    let name = |path: &Path, stem: bool| {
+      let part = if stem {
+        path.file_stem()
+      } else {
+        path.file_name()
+      };
+      part
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default()
+    };
+    TemplateContext {
+      title: name(target, true),
+      workspace: name(workspace_path, false),
+      now: Local::now(),
+    }
+  }
+
+  /// 内置变量的值；不是内置变量时返回 None
+  pub fn value(&self, name: &str) -> Option<String> {
+    Some(match name {
+      "title" => self.title.clone(),
+      "workspace" => self.workspace.clone(),
+      "date" => self.now.format("%Y-%m-%d").to_string(),
+      "time" => self.now.format("%H:%M").to_string(),
+      "datetime" => self.now.format("%Y-%m-%d %H:%M").to_string(),
+      "uuid" => uuid::Uuid::new_v4().to_string(),
+      _ => return None,
+    })
+  }
+}
+
+fn unquote(value: &str) -> String {
+  value
+    .trim()
+    .trim_matches(|c| c == '"' || c == '\'')
+    .to_string()
+}
+
+/// 解析 `prompts` 下的列表项：`- name` 或 `- name: x` 后跟缩进的 `label` / `default`
+fn parse_prompts(lines: &[&str]) -> Vec<TemplatePrompt> {
+  let mut prompts: Vec<TemplatePrompt> = Vec::new();
+  for line in lines {
+    let trimmed = line.trim();
+    let (is_item, entry) = match trimmed.strip_prefix('-') {
+      Some(rest) => (true, rest.trim()),
+      None => (false, trimmed),
+    };
+    let field = entry
+      .split_once(':')
+      .map(|(key, value)| (key.trim(), unquote(value)));
+    if is_item {
+      let name = match field {
+        Some(("name", value)) => value,
+        Some(_) => continue,
+        None => unquote(entry),
+      };
+      prompts.push(TemplatePrompt {
+        label: name.clone(),
+        name,
+        default: None,
+      });
+      continue;
+    }
+    let (Some(prompt), Some((key, value))) = (prompts.last_mut(), field) else {
+      continue;
+    };
+    match key {
+      "name" => prompt.name = value,
+      "label" => prompt.label = value,
+      "default" => prompt.default = Some(value),
+      _ => {}
+    }
+  }
+  prompts.retain(|p| is_variable_name(&p.name));
+  prompts
+}
+
+fn is_variable_name(name: &str) -> bool {
+  let mut chars = name.chars();
+  chars
+    .next()
+    .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+    && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+impl FileTemplate {
+  pub fn parse(content: &str) -> Self {
+    let mut lines: Vec<&str> = content.split_inclusive('\n').collect();
+    if lines.first().map(|l| l.trim_end()) != Some("---") {
+      return FileTemplate {
+        body: content.to_string(),
+        prompts: Vec::new(),
+      };
+    }
+    let Some(end) = lines
+      .iter()
+      .skip(1)
+      .position(|l| matches!(l.trim_end(), "---" | "..."))
+      .map(|i| i + 1)
+    else {
+      return FileTemplate {
+        body: content.to_string(),
+        prompts: Vec::new(),
+      };
+    };
+
+    // prompts 块：`prompts:` 行及其后缩进或以 `-` 开头的行
+    let Some(start) = (1..end).find(|&i| lines[i].trim_end() == "prompts:") else {
+      return FileTemplate {
+        body: content.to_string(),
+        prompts: Vec::new(),
+      };
+    };
+    let block_end = (start + 1..end)
+      .find(|&i| {
+        let line = lines[i];
+        !line.trim().is_empty() && !line.starts_with([' ', '\t']) && !line.starts_with('-')
+      })
+      .unwrap_or(end);
+    let prompts = parse_prompts(&lines[start + 1..block_end]);
+    lines.drain(start..block_end);
+
+    // 只剩空 front matter 时整体去掉
+    let end = end - (block_end - start);
+    if lines[1..end].iter().all(|l| l.trim().is_empty()) {
+      lines.drain(..=end);
+    }
+    FileTemplate {
+      body: lines.concat(),
+      prompts,
+    }
+  }
+
+  /// 展开变量：用户填写的值优先，其次是提示的默认值与内置变量，未知变量保持原样
+  pub fn render(&self, context: &TemplateContext, values: &HashMap<String, String>) -> String {
+    expand_variables(&self.body, |name| {
+      values
+        .get(name)
+        .cloned()
+        .or_else(|| {
+          self
+            .prompts
+            .iter()
+            .find(|p| p.name == name)
+            .map(|p| p.default.clone().unwrap_or_default())
+        })
+        .or_else(|| context.value(name))
+    })
+  }
+}
+
+/// 替换文本中的 `{{变量}}`；lookup 返回 None 的变量保持原样，替换结果不再展开
+pub fn expand_variables(text: &str, lookup: impl Fn(&str) -> Option<String>) -> String {
+  VARIABLE_RE
+    .replace_all(text, |caps: &Captures| {
+      lookup(&caps[1]).unwrap_or_else(|| caps[0].to_string())
+    })
+    .into_owned()
+}
+
+/// 新建文件的结果：模板需要用户填写变量且未提供 values 时先返回提示，不创建文件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum TemplateFileOutcome {
+  NeedsInput { prompts: Vec<TemplatePrompt> },
+  Created { path: String },
+}
+
+/// 读取模板文件；模板须为 UTF-8 文本
+pub fn load_template(path: &Path) -> Result<FileTemplate, String> {
+  let content = std::fs::read_to_string(path).map_err(|e| format!("读取模板失败: {}", e))?;
+  Ok(FileTemplate::parse(&content))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use chrono::TimeZone;
+
+  fn context() -> TemplateContext {
+    TemplateContext {
+      title: "周报".to_string(),
+      workspace: "binder".to_string(),
+      now: Local.with_ymd_and_hms(2024, 3, 5, 9, 7, 0).unwrap(),
+    }
+  }
+
+  #[test]
+  fn parses_prompts_and_strips_them_from_front_matter() {
+    let template = FileTemplate::parse(
+      "---\ntitle: \"{{title}}\"\nprompts:\n  - name: client\n    label: 客户名称\n    default: ACME\n  - due\ntags: [a]\n---\n# {{title}}\n",
+    );
+    assert_eq!(
+      template.prompts,
+      vec![
+        TemplatePrompt {
+          name: "client".to_string(),
+          label: "客户名称".to_string(),
+          default: Some("ACME".to_string()),
+        },
+        TemplatePrompt {
+          name: "due".to_string(),
+          label: "due".to_string(),
+          default: None,
+        },
+      ]
+    );
+    assert_eq!(
+      template.body,
+      "---\ntitle: \"{{title}}\"\ntags: [a]\n---\n# {{title}}\n"
+    );
+
+    let only_prompts = FileTemplate::parse("---\nprompts:\n- topic\n---\n{{topic}}\n");
+    assert_eq!(only_prompts.body, "{{topic}}\n");
+    assert_eq!(only_prompts.prompts[0].name, "topic");
+  }
+
+  #[test]
+  fn renders_builtin_and_prompted_variables() {
+    let template = FileTemplate::parse(
+      "---\nprompts:\n  - name: client\n    default: ACME\n  - owner\n---\n{{ title }} {{date}} {{time}} {{workspace}}\n{{client}}/{{owner}}/{{unknown}}\n",
+    );
+    let rendered = template.render(&context(), &HashMap::new());
+    assert_eq!(
+      rendered,
+      "周报 2024-03-05 09:07 binder\nACME//{{unknown}}\n"
+    );
+
+    let values = HashMap::from([
+      ("owner".to_string(), "张三 {{date}}".to_string()),
+      ("title".to_string(), "自定义".to_string()),
+    ]);
+    let rendered = template.render(&context(), &values);
+    // 用户填写的内容不再展开
+    assert!(rendered.starts_with("自定义 2024-03-05"));
+    assert!(rendered.contains("ACME/张三 {{date}}/"));
+
+    let id = FileTemplate::parse("{{uuid}}").render(&context(), &HashMap::new());
+    assert!(uuid::Uuid::parse_str(&id).is_ok());
+  }
+}
@@ -16,6 +16,7 @@ impl FileTreeService {
     Self
   }
 
+  #[tracing::instrument(skip_all, fields(root = %root.display()))]
   pub fn build_tree(&self, root: &Path, max_depth: usize) -> Result<FileTreeNode, String> {
     if !root.exists() {
       return Err(format!("路径不存在: {}", root.display()));
@@ -1,15 +1,24 @@
+use crate::services::workspace_settings::{WatcherSettings, WorkspaceSettings};
+use crate::utils::glob_patterns::{build_glob_set, relative_slash_path};
+use globset::GlobSet;
+use notify::event::{ModifyKind, RenameMode};
 use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, VecDeque};
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
-use std::sync::mpsc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex, RwLock};
 use std::time::{Duration, Instant};
-use tokio::sync::broadcast;
+use tokio::sync::{broadcast, mpsc as async_mpsc, oneshot};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FileChangeEvent {
   pub path: PathBuf,
   pub kind: FileChangeKind,
+  /// 重命名前的路径（仅 Renamed）
+  pub old_path: Option<PathBuf>,
   pub timestamp_ms: u64, // 用于序列化（毫秒时间戳）
 }
 
@@ -18,100 +27,491 @@ impl FileChangeEvent {
     Self {
       path,
       kind,
+      old_path: None,
       timestamp_ms: std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
         .unwrap_or_default()
         .as_millis() as u64,
     }
   }
+
+  pub fn renamed(from: PathBuf, to: PathBuf) -> Self {
+    Self {
+      old_path: Some(from),
+      ..Self::new(to, FileChangeKind::Renamed)
+    }
+  }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum FileChangeKind {
-  Create,
-  Modify,
-  Remove,
+  Created,
+  Modified,
+  Deleted,
+  Renamed,
 }
 
-pub struct FileWatcherService {
-  workspace_path: Option<PathBuf>,
-  _watcher: Option<RecommendedWatcher>,
-  event_sender: broadcast::Sender<String>,
-  // ⚠️ Week 17 优化：事件去重和防抖相关字段
-  pending_events: VecDeque<FileChangeEvent>,
-  last_events: HashMap<PathBuf, Instant>,
-  debounce_timer: Option<Instant>,
+/// 发送给前端的单个变化，路径相对所在根目录（`/` 分隔）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkspaceFileChange {
+  pub kind: FileChangeKind,
+  pub path: String,
+  pub old_path: Option<String>,
+  pub absolute_path: String,
+  pub old_absolute_path: Option<String>,
+  pub is_dir: bool,
+  /// 变化发生在只读参考资料文件夹中时为该文件夹路径，`path` 相对于它
+  pub reference_root: Option<String>,
 }
 
-impl FileWatcherService {
-  pub fn new() -> Self {
-    let (tx, _) = broadcast::channel(100);
+impl WorkspaceFileChange {
+  pub fn from_event(
+    event: &FileChangeEvent,
+    workspace_path: &Path,
+    reference_roots: &[PathBuf],
+  ) -> Self {
+    let reference_root = reference_roots
+      .iter()
+      .find(|root| event.path.starts_with(root));
+    let root = reference_root.map_or(workspace_path, |r| r.as_path());
     Self {
-      workspace_path: None,
-      _watcher: None,
-      event_sender: tx,
-      pending_events: VecDeque::new(),
-      last_events: HashMap::new(),
-      debounce_timer: None,
+      kind: event.kind,
+      path: relative_slash_path(&event.path, root),
+      old_path: event
+        .old_path
+        .as_ref()
+        .map(|p| relative_slash_path(p, root)),
+      absolute_path: event.path.to_string_lossy().to_string(),
+      old_absolute_path: event
+        .old_path
+        .as_ref()
+        .map(|p| p.to_string_lossy().to_string()),
+      is_dir: event.path.is_dir(),
+      reference_root: reference_root.map(|r| r.to_string_lossy().to_string()),
     }
   }
+}
 
-  // ⚠️ Week 17 优化：过滤临时文件、隐藏文件、系统文件
-  fn should_process_event(&self, path: &Path) -> bool {
-    let path_str = path.to_string_lossy().to_lowercase();
+/// 把 notify 事件转换为变化列表（已按忽略规则过滤）
+fn changes_from_notify(
+  kind: &EventKind,
+  paths: Vec<PathBuf>,
+  filter: &WatchFilter,
+) -> Vec<FileChangeEvent> {
+  // 无法区分重命名的哪一端时按文件是否存在判断
+  let by_existence = |path: PathBuf| {
+    let kind = if path.exists() {
+      FileChangeKind::Created
+    } else {
+      FileChangeKind::Deleted
+    };
+    FileChangeEvent::new(path, kind)
+  };
 
-    // 忽略临时文件
-    if path_str.contains(".tmp") || path_str.contains(".swp") || path_str.contains("~$") {
-      return false;
+  let events: Vec<FileChangeEvent> = match kind {
+    EventKind::Create(_) => paths
+      .into_iter()
+      .map(|p| FileChangeEvent::new(p, FileChangeKind::Created))
+      .collect(),
+    EventKind::Remove(_) => paths
+      .into_iter()
+      .map(|p| FileChangeEvent::new(p, FileChangeKind::Deleted))
+      .collect(),
+    EventKind::Modify(ModifyKind::Name(RenameMode::Both)) if paths.len() == 2 => {
+      let mut paths = paths.into_iter();
+      let (from, to) = (paths.next().unwrap(), paths.next().unwrap());
+      match (filter.allows(&from), filter.allows(&to)) {
+        (true, true) => vec![FileChangeEvent::renamed(from, to)],
+        // 从忽略目录移入 / 移出工作区可见范围
+        (false, true) => vec![FileChangeEvent::new(to, FileChangeKind::Created)],
+        (true, false) => vec![FileChangeEvent::new(from, FileChangeKind::Deleted)],
+        (false, false) => Vec::new(),
+      }
     }
+    EventKind::Modify(ModifyKind::Name(RenameMode::From)) => paths
+      .into_iter()
+      .map(|p| FileChangeEvent::new(p, FileChangeKind::Deleted))
+      .collect(),
+    EventKind::Modify(ModifyKind::Name(RenameMode::To)) => paths
+      .into_iter()
+      .map(|p| FileChangeEvent::new(p, FileChangeKind::Created))
+      .collect(),
+    EventKind::Modify(ModifyKind::Name(_)) => paths.into_iter().map(by_existence).collect(),
+    // 仅元数据变化（访问时间、权限）不影响文件树与内容
+    EventKind::Modify(ModifyKind::Metadata(_)) => Vec::new(),
+    EventKind::Modify(_) => paths
+      .into_iter()
+      .map(|p| FileChangeEvent::new(p, FileChangeKind::Modified))
+      .collect(),
+    _ => Vec::new(),
+  };
 
-    // 忽略隐藏文件（.开头的，除了 .binder）
-    if let Some(file_name) = path.file_name().and_then(|n| n.to_str()) {
-      if file_name.starts_with(".") && file_name != ".binder" {
-        return false;
+  events
+    .into_iter()
+    .filter(|e| e.kind == FileChangeKind::Renamed || filter.allows(&e.path))
+    .collect()
+}
+
+/// 合并防抖窗口内的变化：同一路径只保留一条，创建后修改仍视为创建，创建后删除则抵消
+pub fn coalesce_changes(events: Vec<FileChangeEvent>) -> Vec<FileChangeEvent> {
+  let mut merged: Vec<FileChangeEvent> = Vec::new();
+  for event in events {
+    let Some(index) = merged.iter().position(|e| e.path == event.path) else {
+      merged.push(event);
+      continue;
+    };
+    let previous = merged[index].kind;
+    match (previous, event.kind) {
+      (FileChangeKind::Created, FileChangeKind::Modified) => {}
+      (FileChangeKind::Created, FileChangeKind::Deleted) => {
+        merged.remove(index);
+      }
+      (FileChangeKind::Deleted, FileChangeKind::Created) => {
+        merged[index] = FileChangeEvent::new(event.path, FileChangeKind::Modified);
       }
+      (FileChangeKind::Renamed, FileChangeKind::Modified) => {}
+      _ => merged[index] = event,
+    }
+  }
+  merged
+}
+
+/// 默认忽略的路径：索引 / 临时文件写入 `.binder` 会触发事件，进而再次写索引，形成循环
+pub const DEFAULT_WATCHER_IGNORE: [&str; 2] = [".binder/**", ".git/**"];
+
+/// 当前监听的只读参考资料文件夹，写入类命令据此拒绝修改
+static REFERENCE_ROOTS: Lazy<RwLock<Vec<PathBuf>>> = Lazy::new(Default::default);
+
+/// 路径是否位于只读参考资料文件夹中
+pub fn is_reference_path(path: &Path) -> bool {
+  REFERENCE_ROOTS
+    .read()
+    .map(|roots| roots.iter().any(|root| path.starts_with(root)))
+    .unwrap_or(false)
+}
+
+/// 暂停通知的工作区及暂停次数（允许嵌套暂停），暂停期间该工作区的变化直接丢弃
+static PAUSED_WORKSPACES: Lazy<Mutex<HashMap<PathBuf, usize>>> = Lazy::new(Default::default);
+
+/// 暂停工作区的文件变化通知，避免批量操作（整理、撤销整理等）产生大量事件
+pub fn pause_workspace_events(workspace_path: &Path) {
+  if let Ok(mut paused) = PAUSED_WORKSPACES.lock() {
+    *paused.entry(workspace_path.to_path_buf()).or_insert(0) += 1;
+  }
+}
+
+/// 恢复通知；所有暂停都已恢复时返回 true，调用方应通知前端整体刷新
+pub fn resume_workspace_events(workspace_path: &Path) -> bool {
+  let Ok(mut paused) = PAUSED_WORKSPACES.lock() else {
+    return false;
+  };
+  match paused.get_mut(workspace_path) {
+    Some(count) if *count > 1 => {
+      *count -= 1;
+      false
+    }
+    Some(_) => {
+      paused.remove(workspace_path);
+      true
+    }
+    None => false,
+  }
+}
+
+fn is_paused(path: &Path) -> bool {
+  PAUSED_WORKSPACES
+    .lock()
+    .map(|paused| paused.keys().any(|workspace| path.starts_with(workspace)))
+    .unwrap_or(false)
+}
+
+/// 在作用域内暂停工作区通知，drop 时恢复；全部恢复后调用 `on_resume`
+pub struct WatcherPause {
+  workspace_path: PathBuf,
+  on_resume: Option<Box<dyn FnOnce() + Send>>,
+}
+
+impl WatcherPause {
+  pub fn new(workspace_path: &Path, on_resume: impl FnOnce() + Send + 'static) -> Self {
+    pause_workspace_events(workspace_path);
+    Self {
+      workspace_path: workspace_path.to_path_buf(),
+      on_resume: Some(Box::new(on_resume)),
+    }
+  }
+}
 
-      // 忽略系统文件
-      if file_name == ".ds_store" || file_name == "thumbs.db" || file_name == ".git" {
-        return false;
+impl Drop for WatcherPause {
+  fn drop(&mut self) {
+    if resume_workspace_events(&self.workspace_path) {
+      if let Some(on_resume) = self.on_resume.take() {
+        on_resume();
       }
     }
+  }
+}
+
+/// 事件路径过滤：工作区与参考资料文件夹之外、忽略规则命中、编辑器临时文件都不通知
+#[derive(Clone)]
+struct WatchFilter {
+  workspace_path: PathBuf,
+  ignore: GlobSet,
+  reference_roots: Vec<PathBuf>,
+}
+
+impl WatchFilter {
+  fn new(
+    workspace_path: &Path,
+    ignore_patterns: &[String],
+    reference_roots: &[PathBuf],
+  ) -> Result<Self, String> {
+    Ok(Self {
+      workspace_path: workspace_path.to_path_buf(),
+      ignore: build_glob_set(ignore_patterns)?,
+      reference_roots: reference_roots.to_vec(),
+    })
+  }
 
-    // 忽略 node_modules、target 等大型目录
-    if path_str.contains("node_modules") || path_str.contains("/target/") {
+  fn allows(&self, path: &Path) -> bool {
+    if !path.starts_with(&self.workspace_path) {
+      // 忽略规则只作用于工作区
+      return self
+        .reference_roots
+        .iter()
+        .any(|root| path.starts_with(root))
+        && is_relevant_file(path);
+    }
+    let relative = relative_slash_path(path, &self.workspace_path);
+    // 目录本身（如 `.binder`）也按 `.binder/**` 忽略
+    if self.ignore.is_match(&relative) || self.ignore.is_match(format!("{}/", relative)) {
       return false;
     }
+    is_relevant_file(path)
+  }
+}
+
+// ⚠️ Week 17 优化：过滤临时文件、隐藏文件、系统文件
+fn is_relevant_file(path: &Path) -> bool {
+  let path_str = path.to_string_lossy().to_lowercase();
 
-    true
+  // 忽略临时文件
+  if path_str.contains(".tmp") || path_str.contains(".swp") || path_str.contains("~$") {
+    return false;
   }
 
-  // ⚠️ Week 17 优化：事件去重 - 相同路径的连续事件只保留最后一个
-  fn deduplicate_events(&mut self, events: Vec<FileChangeEvent>) -> Vec<FileChangeEvent> {
-    let mut unique_events: HashMap<PathBuf, FileChangeEvent> = HashMap::new();
-    let now = Instant::now();
+  // 忽略隐藏文件（.开头的，除了 .binder）
+  if let Some(file_name) = path.file_name().and_then(|n| n.to_str()) {
+    if file_name.starts_with(".") && file_name != ".binder" {
+      return false;
+    }
+
+    // 忽略系统文件
+    if file_name == ".ds_store" || file_name == "thumbs.db" || file_name == ".git" {
+      return false;
+    }
+  }
+
+  // 忽略 node_modules、target 等大型目录
+  if path_str.contains("node_modules") || path_str.contains("/target/") {
+    return false;
+  }
+
+  true
+}
+
+/// 一个防抖窗口内的变化（已按路径合并）
+#[derive(Debug, Clone, Default)]
+pub struct FileChangeBatch {
+  pub events: Vec<FileChangeEvent>,
+  /// 变化过多或事件积压丢失：`events` 不完整，接收方应整体刷新
+  pub overflowed: bool,
+}
 
-    for event in events {
-      // 只处理应该处理的文件
-      if !self.should_process_event(&event.path) {
-        continue;
+/// 防抖批处理：最后一次变化后静默 `debounce` 或距第一次变化超过 `max_wait` 时输出一批；
+/// 合并后的变化超过 `max_batch_size` 条时不再逐条保留，只标记 overflowed
+struct EventBatcher {
+  debounce: Duration,
+  max_wait: Duration,
+  max_batch_size: usize,
+  pending: Vec<FileChangeEvent>,
+  overflowed: bool,
+  /// 因溢出未逐条保留的事件数
+  dropped: u64,
+  first_at: Option<Instant>,
+  last_at: Option<Instant>,
+}
+
+impl EventBatcher {
+  fn new(settings: &WatcherSettings) -> Self {
+    Self {
+      debounce: Duration::from_millis(settings.debounce_ms),
+      max_wait: Duration::from_millis(settings.max_wait_ms.max(settings.debounce_ms)),
+      max_batch_size: settings.max_batch_size.max(1),
+      pending: Vec::new(),
+      overflowed: false,
+      dropped: 0,
+      first_at: None,
+      last_at: None,
+    }
+  }
+
+  fn push(&mut self, event: FileChangeEvent, now: Instant) {
+    self.first_at.get_or_insert(now);
+    self.last_at = Some(now);
+    if self.overflowed {
+      self.dropped += 1;
+      return;
+    }
+    self.pending.push(event);
+    if self.pending.len() > self.max_batch_size {
+      self.pending = coalesce_changes(std::mem::take(&mut self.pending));
+      if self.pending.len() > self.max_batch_size {
+        self.dropped += self.pending.len() as u64;
+        self.pending.clear();
+        self.overflowed = true;
       }
+    }
+  }
+
+  /// 下一次应输出的时间；没有待发送的变化时为 None
+  fn deadline(&self) -> Option<Instant> {
+    let (first, last) = (self.first_at?, self.last_at?);
+    Some((last + self.debounce).min(first + self.max_wait))
+  }
 
-      // 更新最后事件时间
-      self.last_events.insert(event.path.clone(), now);
+  fn take(&mut self) -> Option<FileChangeBatch> {
+    self.first_at.take()?;
+    self.last_at = None;
+    Some(FileChangeBatch {
+      events: coalesce_changes(std::mem::take(&mut self.pending)),
+      overflowed: std::mem::take(&mut self.overflowed),
+    })
+  }
+}
+
+/// 监听运行计数（每次开始监听时重置）
+#[derive(Debug, Default)]
+pub struct WatcherStats {
+  events: AtomicU64,
+  batches: AtomicU64,
+  dropped_events: AtomicU64,
+  lagged_batches: AtomicU64,
+  errors: AtomicU64,
+  last_error: Mutex<Option<String>>,
+}
+
+impl WatcherStats {
+  /// 接收方处理不及时，丢失了 `count` 批变化
+  pub fn record_lagged(&self, count: u64) {
+    self.lagged_batches.fetch_add(count, Ordering::Relaxed);
+  }
 
-      // 相同路径的事件，保留最新的
-      unique_events.insert(event.path.clone(), event);
+  fn record_error(&self, error: String) {
+    self.errors.fetch_add(1, Ordering::Relaxed);
+    if let Ok(mut last_error) = self.last_error.lock() {
+      *last_error = Some(error);
     }
+  }
+}
 
-    // 清理过期的事件记录（超过 5 秒）
-    self
-      .last_events
-      .retain(|_, timestamp| now.duration_since(*timestamp) < Duration::from_secs(5));
+/// `get_watcher_status` 返回的监听状态，用于排查「文件树不刷新」等问题
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatcherStatus {
+  /// 是否正在监听请求的工作区
+  pub active: bool,
+  pub paused: bool,
+  /// 底层通知机制：FSEvents / inotify / ReadDirectoryChanges / kqueue
+  pub backend: String,
+  /// 监听的根目录（工作区及参考资料文件夹）
+  pub watched_paths: Vec<String>,
+  /// 根目录下的文件夹总数（inotify 每个文件夹占用一个 watch）
+  pub watched_directories: usize,
+  /// inotify 可用的 watch 上限（仅 Linux）
+  pub max_user_watches: Option<u64>,
+  pub events_received: u64,
+  pub batches_sent: u64,
+  /// 一批变化过多时未逐条通知的事件数
+  pub dropped_events: u64,
+  /// 前端处理不及时丢失的批次数
+  pub lagged_batches: u64,
+  pub errors: u64,
+  pub last_error: Option<String>,
+}
 
-    unique_events.into_values().collect()
+/// 当前平台 `RecommendedWatcher` 使用的通知机制
+pub fn watcher_backend() -> &'static str {
+  if cfg!(target_os = "macos") {
+    "FSEvents"
+  } else if cfg!(any(target_os = "linux", target_os = "android")) {
+    "inotify"
+  } else if cfg!(windows) {
+    "ReadDirectoryChanges"
+  } else if cfg!(any(
+    target_os = "freebsd",
+    target_os = "openbsd",
+    target_os = "netbsd",
+    target_os = "dragonfly"
+  )) {
+    "kqueue"
+  } else {
+    "poll"
   }
+}
+
+/// 统计监听根目录下的文件夹数（含根目录），可能较慢，应在后台线程调用
+pub fn count_watched_directories(roots: &[String]) -> usize {
+  roots
+    .iter()
+    .flat_map(|root| walkdir::WalkDir::new(root).into_iter().flatten())
+    .filter(|entry| entry.file_type().is_dir())
+    .count()
+}
+
+fn max_user_watches() -> Option<u64> {
+  if !cfg!(target_os = "linux") {
+    return None;
+  }
+  std::fs::read_to_string("/proc/sys/fs/inotify/max_user_watches")
+    .ok()
+    .and_then(|value| value.trim().parse().ok())
+}
 
+pub struct FileWatcherService {
+  workspace_path: Option<PathBuf>,
+  reference_roots: Vec<PathBuf>,
+  _watcher: Option<RecommendedWatcher>,
+  event_sender: broadcast::Sender<FileChangeBatch>,
+  stats: Arc<WatcherStats>,
+}
+
+impl FileWatcherService {
+  pub fn new() -> Self {
+    let (tx, _) = broadcast::channel(256);
+    Self {
+      workspace_path: None,
+      reference_roots: Vec::new(),
+      _watcher: None,
+      event_sender: tx,
+      stats: Arc::default(),
+    }
+  }
+
+  /// 按工作区设置 `watcher` 监听工作区及参考资料文件夹
+  /// （忽略规则默认 `.binder/**`、`.git/**`，防抖窗口默认 500ms）
   pub fn watch_workspace(&mut self, workspace_path: PathBuf) -> Result<(), String> {
+    let settings = WorkspaceSettings::load_or_default(&workspace_path).watcher;
+    self.watch_workspace_with_settings(workspace_path, &settings)
+  }
+
+  pub fn watch_workspace_with_settings(
+    &mut self,
+    workspace_path: PathBuf,
+    settings: &WatcherSettings,
+  ) -> Result<(), String> {
+    let reference_roots = settings.reference_roots(&workspace_path);
+    let filter = WatchFilter::new(&workspace_path, &settings.ignore_patterns, &reference_roots)?;
+
     // 停止之前的监听
     self.stop_watching();
 
@@ -124,50 +524,70 @@ impl FileWatcherService {
     watcher
       .watch(&workspace_path, RecursiveMode::Recursive)
       .map_err(|e| format!("监听目录失败: {}", e))?;
+    // 参考资料文件夹监听失败（如网络盘未挂载）不影响工作区
+    let reference_roots: Vec<PathBuf> = reference_roots
+      .iter()
+      .filter(|root| match watcher.watch(root, RecursiveMode::Recursive) {
+        Ok(_) => true,
+        Err(e) => {
+          tracing::warn!("监听参考资料文件夹失败 ({}): {}", root.display(), e);
+          false
+        }
+      })
+      .cloned()
+      .collect();
 
-    let workspace_path_clone = workspace_path.clone();
     let event_sender = self.event_sender.clone();
+    let mut batcher = EventBatcher::new(settings);
+    let stats = Arc::new(WatcherStats::default());
+    let thread_stats = stats.clone();
 
-    // 在后台线程处理文件系统事件
-    std::thread::spawn(move || {
-      loop {
-        match rx.recv() {
-          Ok(event) => {
-            match event {
-              Ok(Event { kind, paths, .. }) => {
-                // 只处理创建、删除、修改事件
-                // 注意：notify 6.0 中 Rename 事件已移除，重命名会触发 Create + Remove
-                let should_notify = matches!(
-                  kind,
-                  EventKind::Create(_) | EventKind::Remove(_) | EventKind::Modify(_)
-                );
-
-                if should_notify {
-                  // 检查事件路径是否在工作区内
-                  for path in paths {
-                    if path.starts_with(&workspace_path_clone) {
-                      // 发送事件通知
-                      let _ = event_sender.send(workspace_path_clone.to_string_lossy().to_string());
-                      break; // 一个事件只通知一次
-                    }
-                  }
-                }
-              }
-              Err(e) => {
-                eprintln!("文件监听错误: {}", e);
-              }
+    // 在后台线程过滤、合并文件系统事件，每个防抖窗口发送一批
+    std::thread::spawn(move || loop {
+      let received = match batcher.deadline() {
+        Some(deadline) => rx.recv_timeout(deadline.saturating_duration_since(Instant::now())),
+        None => rx.recv().map_err(|_| RecvTimeoutError::Disconnected),
+      };
+      let disconnected = match received {
+        Ok(Ok(Event { kind, paths, .. })) => {
+          thread_stats.events.fetch_add(1, Ordering::Relaxed);
+          for change in changes_from_notify(&kind, paths, &filter) {
+            if !is_paused(&change.path) {
+              batcher.push(change, Instant::now());
             }
           }
-          Err(_) => {
-            // 通道关闭，退出循环
-            break;
-          }
+          false
+        }
+        Ok(Err(e)) => {
+          tracing::warn!("文件监听错误: {}", e);
+          thread_stats.record_error(e.to_string());
+          false
         }
+        Err(RecvTimeoutError::Timeout) => false,
+        // 监听器已释放，发送剩余变化后退出
+        Err(RecvTimeoutError::Disconnected) => true,
+      };
+      if disconnected || batcher.deadline().is_some_and(|d| d <= Instant::now()) {
+        if let Some(batch) = batcher.take() {
+          thread_stats.batches.fetch_add(1, Ordering::Relaxed);
+          thread_stats
+            .dropped_events
+            .fetch_add(std::mem::take(&mut batcher.dropped), Ordering::Relaxed);
+          let _ = event_sender.send(batch);
+        }
+      }
+      if disconnected {
+        break;
       }
     });
 
+    if let Ok(mut roots) = REFERENCE_ROOTS.write() {
+      roots.clone_from(&reference_roots);
+    }
     self.workspace_path = Some(workspace_path);
+    self.reference_roots = reference_roots;
     self._watcher = Some(watcher);
+    self.stats = stats;
 
     Ok(())
   }
@@ -175,13 +595,13 @@ impl FileWatcherService {
   pub fn stop_watching(&mut self) {
     self._watcher = None;
     self.workspace_path = None;
-    // 清理事件队列
-    self.pending_events.clear();
-    self.last_events.clear();
-    self.debounce_timer = None;
+    self.reference_roots.clear();
+    if let Ok(mut roots) = REFERENCE_ROOTS.write() {
+      roots.clear();
+    }
   }
 
-  pub fn subscribe(&self) -> broadcast::Receiver<String> {
+  pub fn subscribe(&self) -> broadcast::Receiver<FileChangeBatch> {
     self.event_sender.subscribe()
   }
 
@@ -189,6 +609,43 @@ impl FileWatcherService {
   pub fn get_workspace_path(&self) -> Option<PathBuf> {
     self.workspace_path.clone()
   }
+
+  /// 正在监听的只读参考资料文件夹
+  pub fn reference_roots(&self) -> &[PathBuf] {
+    &self.reference_roots
+  }
+
+  pub fn stats(&self) -> Arc<WatcherStats> {
+    self.stats.clone()
+  }
+
+  /// 监听状态；`watched_directories` 需另行用 `count_watched_directories` 统计
+  pub fn status(&self, workspace_path: &Path) -> WatcherStatus {
+    let active = self._watcher.is_some() && self.workspace_path.as_deref() == Some(workspace_path);
+    let watched_paths = if active {
+      std::iter::once(workspace_path)
+        .chain(self.reference_roots.iter().map(PathBuf::as_path))
+        .map(|path| path.to_string_lossy().to_string())
+        .collect()
+    } else {
+      Vec::new()
+    };
+    let stats = &self.stats;
+    WatcherStatus {
+      active,
+      paused: is_paused(workspace_path),
+      backend: watcher_backend().to_string(),
+      watched_paths,
+      watched_directories: 0,
+      max_user_watches: max_user_watches(),
+      events_received: stats.events.load(Ordering::Relaxed),
+      batches_sent: stats.batches.load(Ordering::Relaxed),
+      dropped_events: stats.dropped_events.load(Ordering::Relaxed),
+      lagged_batches: stats.lagged_batches.load(Ordering::Relaxed),
+      errors: stats.errors.load(Ordering::Relaxed),
+      last_error: stats.last_error.lock().ok().and_then(|e| e.clone()),
+    }
+  }
 }
 
 impl Default for FileWatcherService {
@@ -196,3 +653,281 @@ impl Default for FileWatcherService {
     Self::new()
   }
 }
+
+/// 一次监听开始后的订阅信息
+pub struct WatchSession {
+  /// 实际监听成功的参考资料文件夹
+  pub reference_roots: Vec<PathBuf>,
+  pub changes: broadcast::Receiver<FileChangeBatch>,
+  pub stats: Arc<WatcherStats>,
+}
+
+enum WatcherCommand {
+  Watch {
+    workspace_path: PathBuf,
+    reply: oneshot::Sender<Result<WatchSession, String>>,
+  },
+  Status {
+    workspace_path: PathBuf,
+    reply: oneshot::Sender<WatcherStatus>,
+  },
+}
+
+/// 文件监听服务的句柄（Tauri 托管状态）。
+///
+/// `FileWatcherService` 由独立线程持有，命令通过 channel 发送并等待回复，
+/// 异步命令不会持有同步锁，也不会因其他命令 panic 而中毒；
+/// 开始监听（递归注册目录，可能较慢）也不占用异步运行时线程。
+#[derive(Clone)]
+pub struct FileWatcherHandle {
+  commands: async_mpsc::UnboundedSender<WatcherCommand>,
+}
+
+impl FileWatcherHandle {
+  pub fn spawn() -> Self {
+    let (commands, mut rx) = async_mpsc::unbounded_channel();
+    std::thread::Builder::new()
+      .name("file-watcher".to_string())
+      .spawn(move || {
+        let mut service = FileWatcherService::new();
+        while let Some(command) = rx.blocking_recv() {
+          match command {
+            WatcherCommand::Watch {
+              workspace_path,
+              reply,
+            } => {
+              let result = service
+                .watch_workspace(workspace_path)
+                .map(|_| WatchSession {
+                  reference_roots: service.reference_roots().to_vec(),
+                  changes: service.subscribe(),
+                  stats: service.stats(),
+                });
+              let _ = reply.send(result);
+            }
+            WatcherCommand::Status {
+              workspace_path,
+              reply,
+            } => {
+              let _ = reply.send(service.status(&workspace_path));
+            }
+          }
+        }
+      })
+      .expect("启动文件监听线程失败");
+    Self { commands }
+  }
+
+  async fn request<T>(
+    &self,
+    command: impl FnOnce(oneshot::Sender<T>) -> WatcherCommand,
+  ) -> Result<T, String> {
+    let (reply, rx) = oneshot::channel();
+    self
+      .commands
+      .send(command(reply))
+      .map_err(|_| "文件监听服务已停止".to_string())?;
+    rx.await.map_err(|_| "文件监听服务已停止".to_string())
+  }
+
+  /// 按工作区设置开始监听工作区（替换之前的监听）
+  pub async fn watch_workspace(&self, workspace_path: PathBuf) -> Result<WatchSession, String> {
+    self
+      .request(|reply| WatcherCommand::Watch {
+        workspace_path,
+        reply,
+      })
+      .await?
+  }
+
+  pub async fn status(&self, workspace_path: PathBuf) -> Result<WatcherStatus, String> {
+    self
+      .request(|reply| WatcherCommand::Status {
+        workspace_path,
+        reply,
+      })
+      .await
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn coalesces_changes_per_path() {
+    let event = |path: &str, kind| FileChangeEvent::new(PathBuf::from(path), kind);
+    let merged = coalesce_changes(vec![
+      event("/ws/a.md", FileChangeKind::Created),
+      event("/ws/a.md", FileChangeKind::Modified),
+      event("/ws/b.md", FileChangeKind::Created),
+      event("/ws/b.md", FileChangeKind::Deleted),
+      event("/ws/c.md", FileChangeKind::Deleted),
+      event("/ws/c.md", FileChangeKind::Created),
+      FileChangeEvent::renamed(PathBuf::from("/ws/d.md"), PathBuf::from("/ws/e.md")),
+      event("/ws/e.md", FileChangeKind::Modified),
+    ]);
+    let kinds: Vec<(String, FileChangeKind)> = merged
+      .iter()
+      .map(|e| (e.path.to_string_lossy().to_string(), e.kind))
+      .collect();
+    assert_eq!(
+      kinds,
+      vec![
+        ("/ws/a.md".to_string(), FileChangeKind::Created),
+        ("/ws/c.md".to_string(), FileChangeKind::Modified),
+        ("/ws/e.md".to_string(), FileChangeKind::Renamed),
+      ]
+    );
+
+    let change = WorkspaceFileChange::from_event(&merged[2], Path::new("/ws"), &[]);
+    assert_eq!(
+      (change.path.as_str(), change.old_path.as_deref()),
+      ("e.md", Some("d.md"))
+    );
+
+    let reference =
+      FileChangeEvent::new(PathBuf::from("/refs/论文/a.pdf"), FileChangeKind::Created);
+    let change =
+      WorkspaceFileChange::from_event(&reference, Path::new("/ws"), &[PathBuf::from("/refs")]);
+    assert_eq!(change.path, "论文/a.pdf");
+    assert_eq!(change.reference_root.as_deref(), Some("/refs"));
+  }
+
+  #[test]
+  fn filter_ignores_binder_git_and_custom_patterns() {
+    let workspace = PathBuf::from("/ws");
+    let mut patterns: Vec<String> = DEFAULT_WATCHER_IGNORE
+      .iter()
+      .map(|p| p.to_string())
+      .collect();
+    patterns.push("build/**".to_string());
+    let filter = WatchFilter::new(&workspace, &patterns, &[PathBuf::from("/refs")]).unwrap();
+
+    for ignored in [
+      "/ws/.binder",
+      "/ws/.binder/temp/preview.pdf",
+      "/ws/.binder/workspace.db-journal",
+      "/ws/.git/index",
+      "/ws/build/out.html",
+      "/ws/notes/draft.md.swp",
+      "/other/notes.md",
+      "/refs/.DS_Store",
+    ] {
+      assert!(!filter.allows(Path::new(ignored)), "{}", ignored);
+    }
+    for kept in ["/ws/notes/a.md", "/ws/docs/build.md", "/refs/build/a.md"] {
+      assert!(filter.allows(Path::new(kept)), "{}", kept);
+    }
+  }
+
+  #[test]
+  fn nested_pauses_resume_once() {
+    let workspace = PathBuf::from("/ws-pause-test");
+    let resumed = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let on_resume = {
+      let resumed = resumed.clone();
+      move || {
+        resumed.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+      }
+    };
+
+    let outer = WatcherPause::new(&workspace, on_resume);
+    pause_workspace_events(&workspace);
+    assert!(is_paused(&workspace.join("a.md")));
+    assert!(!is_paused(Path::new("/other/a.md")));
+
+    assert!(!resume_workspace_events(&workspace));
+    assert!(is_paused(&workspace.join("a.md")));
+    drop(outer);
+    assert!(!is_paused(&workspace.join("a.md")));
+    assert_eq!(resumed.load(std::sync::atomic::Ordering::SeqCst), 1);
+    assert!(!resume_workspace_events(&workspace));
+  }
+
+  #[test]
+  fn batcher_debounces_and_overflows() {
+    let settings = WatcherSettings {
+      debounce_ms: 100,
+      max_wait_ms: 1000,
+      max_batch_size: 3,
+      ..WatcherSettings::default()
+    };
+    let mut batcher = EventBatcher::new(&settings);
+    let start = Instant::now();
+    assert!(batcher.deadline().is_none());
+
+    let event = |name: &str| FileChangeEvent::new(PathBuf::from(name), FileChangeKind::Modified);
+    batcher.push(event("/ws/a.md"), start);
+    for _ in 0..10 {
+      batcher.push(event("/ws/b.md"), start + Duration::from_millis(50));
+    }
+    assert_eq!(batcher.deadline(), Some(start + Duration::from_millis(150)));
+    let batch = batcher.take().unwrap();
+    assert_eq!((batch.events.len(), batch.overflowed), (2, false));
+    assert!(batcher.take().is_none());
+
+    // 持续变化时最迟 max_wait 输出；合并后超过上限只标记 overflowed
+    for i in 0..10 {
+      batcher.push(
+        event(&format!("/ws/{}.md", i)),
+        start + Duration::from_millis(90 * i),
+      );
+    }
+    assert_eq!(batcher.deadline(), Some(start + Duration::from_millis(910)));
+    let batch = batcher.take().unwrap();
+    assert!(batch.overflowed && batch.events.is_empty());
+    assert_eq!(batcher.dropped, 10);
+  }
+
+  #[test]
+  fn status_reports_watched_workspace() {
+    let workspace =
+      std::env::temp_dir().join(format!("binder_watch_status_{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(workspace.join("a/b")).unwrap();
+    let mut service = FileWatcherService::new();
+    assert!(!service.status(&workspace).active);
+
+    service
+      .watch_workspace_with_settings(workspace.clone(), &WatcherSettings::default())
+      .unwrap();
+    let status = service.status(&workspace);
+    assert!(status.active && !status.paused);
+    assert_eq!(status.backend, watcher_backend());
+    assert_eq!(
+      status.watched_paths,
+      vec![workspace.to_string_lossy().to_string()]
+    );
+    assert_eq!(count_watched_directories(&status.watched_paths), 3);
+    assert!(!service.status(&workspace.join("a")).active);
+
+    service.stop_watching();
+    assert!(!service.status(&workspace).active);
+    let _ = std::fs::remove_dir_all(&workspace);
+  }
+
+  #[tokio::test]
+  async fn handle_serves_commands_from_watcher_thread() {
+    let workspace =
+      std::env::temp_dir().join(format!("binder_watch_handle_{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&workspace).unwrap();
+    let handle = FileWatcherHandle::spawn();
+    assert_eq!(handle.workspace_path().await.unwrap(), None);
+
+    let session = handle.watch_workspace(workspace.clone()).await.unwrap();
+    assert!(session.reference_roots.is_empty());
+    assert_eq!(
+      handle.workspace_path().await.unwrap(),
+      Some(workspace.clone())
+    );
+    assert!(handle.status(workspace.clone()).await.unwrap().active);
+
+    handle.stop_watching();
+    assert!(!handle.status(workspace.clone()).await.unwrap().active);
+    assert!(handle
+      .watch_workspace(workspace.join("missing"))
+      .await
+      .is_err());
+    let _ = std::fs::remove_dir_all(&workspace);
+  }
+}
@@ -0,0 +1,285 @@
+//! AI 文件名建议：根据文档内容生成若干候选标题与可直接使用的文件名。
+//!
+//! 用于保存未命名文档与导入文档时的命名。模型返回的文件名经 `PathValidator::sanitize_filename`
+//! 清理，与目标目录中已有文件重名时追加序号；文档自带的标题（front matter `title`、
+//! 第一个 H1）作为首个候选。
+
+use crate::utils::path_validator::PathValidator;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// 发送给模型的内容上限
+const EXCERPT_CHARS: usize = 3000;
+const MAX_FILENAME_CHARS: usize = 60;
+pub const DEFAULT_CANDIDATES: usize = 3;
+pub const MAX_CANDIDATES: usize = 8;
+
+static HTML_TAG: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?s)<[^>]+>").unwrap());
+static HTML_H1: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?is)<h1\b[^>]*>(.*?)</h1>").unwrap());
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FilenameCandidate {
+  /// 文档标题，可写入 front matter
+  pub title: String,
+  /// 含扩展名的文件名
+  pub filename: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawCandidates {
+  #[serde(default)]
+  candidates: Vec<RawCandidate>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawCandidate {
+  #[serde(default)]
+  title: String,
+  #[serde(default)]
+  filename: String,
+}
+
+fn is_html(content: &str) -> bool {
+  let trimmed = content.trim_start();
+  trimmed.starts_with('<') && trimmed.contains("</")
+}
+
+/// 发送给模型的正文摘录；HTML 去掉标签
+pub fn excerpt(content: &str) -> String {
+  let text = if is_html(content) {
+    HTML_TAG.replace_all(content, " ").to_string()
+  } else {
+    content.to_string()
+  };
+  let text = text.trim();
+  match text.char_indices().nth(EXCERPT_CHARS) {
+    Some((end, _)) => text[..end].to_string(),
+    None => text.to_string(),
+  }
+}
+
+/// 文档自带的标题：front matter 的 title，其次是第一个 H1
+pub fn existing_title(content: &str) -> Option<String> {
+  if is_html(content) {
+    return HTML_H1
+      .captures(content)
+      .map(|c| HTML_TAG.replace_all(&c[1], "").trim().to_string())
+      .filter(|t| !t.is_empty());
+  }
+  let mut lines = content.lines();
+  if content.starts_with("---") && lines.next().is_some_and(|l| l.trim() == "---") {
+    for line in lines.by_ref() {
+      let line = line.trim();
+      if matches!(line, "---" | "...") {
+        break;
+      }
+      if let Some(title) = line.strip_prefix("title:") {
+        let title = title.trim().trim_matches(['"', '\'']).trim();
+        if !title.is_empty() {
+          return Some(title.to_string());
+        }
+      }
+    }
+  }
+  let mut in_fence = false;
+  for line in lines {
+    let trimmed = line.trim_start();
+    if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+      in_fence = !in_fence;
+      continue;
+    }
+    if in_fence {
+      continue;
+    }
+    if let Some(title) = trimmed.strip_prefix("# ") {
+      let title = title.trim().trim_end_matches('#').trim();
+      if !title.is_empty() {
+        return Some(title.to_string());
+      }
+    }
+  }
+  None
+}
+
+pub fn build_prompt(content: &str, count: usize) -> String {
+  format!(
+    "请根据下面的文档内容给出 {} 个不同的候选标题与文件名。\n要求：\n\
+     1. 标题简洁准确地概括文档主题，使用与文档相同的语言\n\
+     2. 文件名由标题精简而来，不超过 30 个字，不含扩展名与日期，不含 / \\ : * ? \" < > | 等字符\n\
+     3. 只输出 JSON：{{\"candidates\": [{{\"title\": \"标题\", \"filename\": \"文件名\"}}]}}\n\n\
+     文档：\n{}",
+    count,
+    excerpt(content)
+  )
+}
+
+/// 解析模型输出；不是 JSON 时把每个非空行当作标题
+fn parse_raw(response: &str) -> Vec<RawCandidate> {
+  let json = response
+    .find('{')
+    .zip(response.rfind('}'))
+    .filter(|(start, end)| start < end)
+    .map(|(start, end)| &response[start..=end]);
+  if let Some(parsed) = json.and_then(|j| serde_json::from_str::<RawCandidates>(j).ok()) {
+    return parsed.candidates;
+  }
+  response
+    .lines()
+    .map(|l| {
+      l.trim()
+        .trim_start_matches(['-', '*', '•'])
+        .trim_start_matches(|c: char| c.is_ascii_digit())
+        .trim_start_matches(['.', ')', '、'])
+        .trim()
+    })
+    .filter(|l| !l.is_empty() && !l.starts_with("```"))
+    .map(|l| RawCandidate {
+      title: l.to_string(),
+      filename: String::new(),
+    })
+    .collect()
+}
+
+/// 目录中不存在的文件名：重名时追加 ` 2`、` 3`……
+fn available_filename(stem: &str, extension: &str, directory: Option<&Path>) -> String {
+  let name = |n: usize| match n {
+    1 => format!("{}.{}", stem, extension),
+    n => format!("{} {}.{}", stem, n, extension),
+  };
+  let Some(directory) = directory else {
+    return name(1);
+  };
+  (1..)
+    .map(name)
+    .find(|candidate| !directory.join(candidate).exists())
+    .unwrap_or_else(|| name(1))
+}
+
+/// 整理候选：清理文件名、去重、避开已有文件，文档自带标题排在最前
+pub fn finish_candidates(
+  response: &str,
+  content: &str,
+  extension: &str,
+  count: usize,
+  directory: Option<&Path>,
+) -> Vec<FilenameCandidate> {
+  let extension = extension.trim().trim_start_matches('.');
+  let extension = if extension.is_empty() {
+    "md"
+  } else {
+    extension
+  };
+  let existing = existing_title(content).map(|title| RawCandidate {
+    filename: title.clone(),
+    title,
+  });
+  let mut candidates: Vec<FilenameCandidate> = Vec::new();
+  let mut stems: Vec<String> = Vec::new();
+  for raw in existing.into_iter().chain(parse_raw(response)) {
+    let title = raw.title.trim().to_string();
+    let source = if raw.filename.trim().is_empty() {
+      &title
+    } else {
+      &raw.filename
+    };
+    let source = source
+      .trim()
+      .strip_suffix(&format!(".{}", extension))
+      .unwrap_or(source.trim());
+    let Some(stem) = PathValidator::sanitize_filename(source, MAX_FILENAME_CHARS) else {
+      continue;
+    };
+    if stems
+      .iter()
+      .any(|s| s.to_lowercase() == stem.to_lowercase())
+    {
+      continue;
+    }
+    candidates.push(FilenameCandidate {
+      title: if title.is_empty() {
+        stem.clone()
+      } else {
+        title
+      },
+      filename: available_filename(&stem, extension, directory),
+    });
+    stems.push(stem);
+    if candidates.len() >= count {
+      break;
+    }
+  }
+  candidates
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn finds_existing_titles() {
+    assert_eq!(
+      existing_title("---\ntitle: \"季度报告\"\n---\n# 其他\n"),
+      Some("季度报告".to_string())
+    );
+    assert_eq!(
+      existing_title("```\n# 注释\n```\n正文\n# 项目计划 #\n"),
+      Some("项目计划".to_string())
+    );
+    assert_eq!(
+      existing_title("<html><body><h1>会议<b>纪要</b></h1></body></html>"),
+      Some("会议纪要".to_string())
+    );
+    assert_eq!(existing_title("只有正文"), None);
+    assert_eq!(excerpt("<p>你好</p>").trim(), "你好");
+  }
+
+  #[test]
+  fn sanitizes_and_deduplicates_candidates() {
+    let dir = std::env::temp_dir().join(format!("binder_filename_{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("预算 分析.md"), "").unwrap();
+    let response = r#"好的：
+```json
+{"candidates": [
+  {"title": "预算分析", "filename": "预算/分析.md"},
+  {"title": "项目计划", "filename": "项目计划"},
+  {"title": "Q3: 回顾", "filename": ""},
+  {"title": "多余", "filename": "多余"}
+]}
+```"#;
+    let candidates = finish_candidates(response, "# 项目计划\n正文", ".md", 3, Some(&dir));
+    assert_eq!(
+      candidates,
+      vec![
+        FilenameCandidate {
+          title: "项目计划".to_string(),
+          filename: "项目计划.md".to_string(),
+        },
+        FilenameCandidate {
+          title: "预算分析".to_string(),
+          filename: "预算 分析 2.md".to_string(),
+        },
+        FilenameCandidate {
+          title: "Q3: 回顾".to_string(),
+          filename: "Q3 回顾.md".to_string(),
+        },
+      ]
+    );
+    assert_eq!(
+      finish_candidates("1. 读书笔记\n2. 笔记", "", "docx", 5, None),
+      vec![
+        FilenameCandidate {
+          title: "读书笔记".to_string(),
+          filename: "读书笔记.docx".to_string(),
+        },
+        FilenameCandidate {
+          title: "笔记".to_string(),
+          filename: "笔记.docx".to_string(),
+        },
+      ]
+    );
+    let _ = std::fs::remove_dir_all(&dir);
+  }
+}
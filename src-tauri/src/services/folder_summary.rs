@@ -0,0 +1,413 @@
+//! 文件夹多文档摘要：提取文件夹内 Markdown / DOCX / PDF 的正文，按 map-reduce 方式汇总。
+//!
+//! map：每个文档（过长时先分段）各自总结为若干要点；reduce：把各文档要点编号为 `[n]`
+//! 交给模型写出总览与关键结论，结论后的 `[n]` 引用解析回文件路径。
+
+use crate::services::word_count;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// map 阶段每次请求的最大字符数
+pub const SUMMARY_CHUNK_CHARS: usize = 6000;
+const SUPPORTED_EXTENSIONS: &[&str] = &["md", "markdown", "docx", "pdf"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FolderSummaryOptions {
+  /// 是否包含子文件夹
+  pub recursive: bool,
+  /// 最多汇总的文档数，超出的按路径顺序跳过
+  pub max_documents: usize,
+  /// 每个文档最多读取的字符数
+  pub max_chars_per_document: usize,
+  /// 每个文档的要点数上限
+  pub bullets_per_document: usize,
+  /// 摘要语言，为空时与文档一致
+  pub language: Option<String>,
+  /// 关注的主题或问题
+  pub focus: Option<String>,
+}
+
+impl Default for FolderSummaryOptions {
+  fn default() -> Self {
+    Self {
+      recursive: true,
+      max_documents: 50,
+      max_chars_per_document: 30_000,
+      bullets_per_document: 5,
+      language: None,
+      focus: None,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocumentSummary {
+  pub path: String,
+  /// 相对文件夹的路径
+  pub relative_path: String,
+  pub bullets: Vec<String>,
+  /// 超出 max_chars_per_document 被截断
+  pub truncated: bool,
+}
+
+/// 总结中的一条结论及其引用的文档路径
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SummaryPoint {
+  pub text: String,
+  pub sources: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SkippedDocument {
+  pub path: String,
+  pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FolderSummary {
+  pub folder: String,
+  pub overview: String,
+  pub key_points: Vec<SummaryPoint>,
+  pub documents: Vec<DocumentSummary>,
+  pub skipped: Vec<SkippedDocument>,
+}
+
+/// 待汇总的文档：隐藏文件与隐藏目录不计，按路径排序；返回（文档，因数量上限跳过的文档）
+pub fn collect_documents(
+  folder: &Path,
+  options: &FolderSummaryOptions,
+) -> (Vec<PathBuf>, Vec<PathBuf>) {
+  let max_depth = if options.recursive { usize::MAX } else { 1 };
+  let mut documents: Vec<PathBuf> = WalkDir::new(folder)
+    .max_depth(max_depth)
+    .follow_links(false)
+    .into_iter()
+    .filter_entry(|e| e.depth() == 0 || !e.file_name().to_string_lossy().starts_with('.'))
+    .flatten()
+    .filter(|e| e.file_type().is_file())
+    .map(|e| e.into_path())
+    .filter(|p| {
+      p.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| SUPPORTED_EXTENSIONS.contains(&e.to_lowercase().as_str()))
+    })
+    .collect();
+  documents.sort();
+  let overflow = documents.split_off(documents.len().min(options.max_documents));
+  (documents, overflow)
+}
+
+/// 提取文档正文，超过 max_chars 时截断；返回（正文，是否截断）
+pub fn document_text(path: &Path, max_chars: usize) -> Result<(String, bool), String> {
+  let text = word_count::file_plain_text(path, None)?;
+  let text = text.trim();
+  match text.char_indices().nth(max_chars) {
+    Some((end, _)) => Ok((text[..end].to_string(), true)),
+    None => Ok((text.to_string(), false)),
+  }
+}
+
+/// 按行把正文切为不超过 SUMMARY_CHUNK_CHARS 的分段；单行超长时按字符切开
+pub fn split_text(text: &str) -> Vec<String> {
+  let mut chunks = Vec::new();
+  let mut current = String::new();
+  let mut current_chars = 0;
+  for line in text.lines() {
+    let chars: Vec<char> = line.chars().collect();
+    for piece in chars.chunks(SUMMARY_CHUNK_CHARS) {
+      if current_chars > 0 && current_chars + piece.len() > SUMMARY_CHUNK_CHARS {
+        chunks.push(std::mem::take(&mut current));
+        current_chars = 0;
+      }
+      current.extend(piece);
+      current.push('\n');
+      current_chars += piece.len() + 1;
+    }
+  }
+  if !current.trim().is_empty() {
+    chunks.push(current);
+  }
+  chunks
+}
+
+fn language_line(options: &FolderSummaryOptions) -> String {
+  match options.language.as_deref().filter(|l| !l.trim().is_empty()) {
+    Some(language) => format!("使用{}输出。\n", language.trim()),
+    None => "使用与原文相同的语言输出。\n".to_string(),
+  }
+}
+
+fn focus_line(options: &FolderSummaryOptions) -> String {
+  match options.focus.as_deref().filter(|f| !f.trim().is_empty()) {
+    Some(focus) => format!("重点关注：{}\n", focus.trim()),
+    None => String::new(),
+  }
+}
+
+/// map 阶段：总结一段文档内容
+pub fn build_map_prompt(
+  relative_path: &str,
+  text: &str,
+  part: Option<(usize, usize)>,
+  options: &FolderSummaryOptions,
+) -> String {
+  let part = part
+    .map(|(index, total)| format!("（第 {}/{} 部分）", index + 1, total))
+    .unwrap_or_default();
+  format!(
+    "请总结下面的文档内容{}，输出不超过 {} 条要点，每条以 \"- \" 开头、一行一条，\
+     只写文档中的事实与结论，不要输出其他内容。\n{}{}\n文档：{}\n\n{}",
+    part,
+    options.bullets_per_document.max(1),
+    language_line(options),
+    focus_line(options),
+    relative_path,
+    text
+  )
+}
+
+/// 长文档各部分要点合并为文档要点
+pub fn build_merge_prompt(
+  relative_path: &str,
+  bullets: &[String],
+  options: &FolderSummaryOptions,
+) -> String {
+  format!(
+    "下面是文档「{}」各部分的要点，请合并去重为不超过 {} 条要点，每条以 \"- \" 开头、一行一条，\
+     不要输出其他内容。\n{}\n{}",
+    relative_path,
+    options.bullets_per_document.max(1),
+    language_line(options),
+    bullets
+      .iter()
+      .map(|b| format!("- {}\n", b))
+      .collect::<String>()
+  )
+}
+
+/// reduce 阶段：按编号列出各文档要点，要求结论后标注来源编号
+pub fn build_reduce_prompt(
+  documents: &[DocumentSummary],
+  options: &FolderSummaryOptions,
+) -> String {
+  let mut listing = String::new();
+  for (index, document) in documents.iter().enumerate() {
+    listing.push_str(&format!("[{}] {}\n", index + 1, document.relative_path));
+    for bullet in &document.bullets {
+      listing.push_str(&format!("  - {}\n", bullet));
+    }
+  }
+  format!(
+    "下面是一个文件夹中各文档的要点，方括号中是文档编号。请综合这些文档：\n\
+     1. 第一段写 2-4 句总览\n\
+     2. 然后列出关键结论，每条以 \"- \" 开头、一行一条，并在句末用 [编号] 标注依据的文档，\
+     可标注多个，如 [1][3]\n\
+     不要编造文档中没有的信息，不要输出其他内容。\n{}{}\n{}",
+    language_line(options),
+    focus_line(options),
+    listing
+  )
+}
+
+fn bullet_text(line: &str) -> Option<&str> {
+  let trimmed = line.trim_start();
+  let rest = ["- ", "* ", "• ", "· "]
+    .iter()
+    .find_map(|marker| trimmed.strip_prefix(marker))
+    .or_else(|| {
+      // 有序列表 `1. `、`1) `
+      let digits = trimmed.chars().take_while(|c| c.is_ascii_digit()).count();
+      (digits > 0)
+        .then(|| &trimmed[digits..])
+        .and_then(|r| r.strip_prefix(". ").or_else(|| r.strip_prefix(") ")))
+    })?;
+  let rest = rest.trim();
+  (!rest.is_empty()).then_some(rest)
+}
+
+/// 解析模型输出的要点列表；没有列表标记时按非空行处理
+pub fn parse_bullets(response: &str, limit: usize) -> Vec<String> {
+  let bullets: Vec<String> = response
+    .lines()
+    .filter_map(bullet_text)
+    .map(str::to_string)
+    .collect();
+  let bullets = if bullets.is_empty() {
+    response
+      .lines()
+      .map(str::trim)
+      .filter(|l| !l.is_empty())
+      .map(str::to_string)
+      .collect()
+  } else {
+    bullets
+  };
+  bullets.into_iter().take(limit.max(1)).collect()
+}
+
+/// 取出句末的 `[n]` 引用，返回（去掉引用后的文本，编号）；引用之后的句号保留
+fn split_citations(text: &str) -> (String, Vec<usize>) {
+  let trimmed = text.trim();
+  let body = trimmed.trim_end_matches(['。', '.']);
+  let punctuation = &trimmed[body.len()..];
+  let mut numbers = Vec::new();
+  let mut rest = body.trim_end();
+  while let Some(inner) = rest.strip_suffix(']') {
+    let Some(open) = inner.rfind('[') else {
+      break;
+    };
+    let Ok(cited) = inner[open + 1..]
+      .split([',', '，', ' '])
+      .filter(|n| !n.is_empty())
+      .map(|n| n.parse::<usize>())
+      .collect::<Result<Vec<_>, _>>()
+    else {
+      break;
+    };
+    numbers.splice(0..0, cited);
+    rest = inner[..open].trim_end();
+  }
+  if numbers.is_empty() {
+    return (trimmed.to_string(), numbers);
+  }
+  (format!("{}{}", rest, punctuation), numbers)
+}
+
+/// 解析 reduce 输出：列表之前的文字为总览，列表项为结论，引用编号转为文档路径
+pub fn parse_reduce_response(
+  response: &str,
+  documents: &[DocumentSummary],
+) -> (String, Vec<SummaryPoint>) {
+  let mut overview = Vec::new();
+  let mut key_points = Vec::new();
+  for line in response.lines() {
+    match bullet_text(line) {
+      Some(text) => {
+        let (text, numbers) = split_citations(text);
+        let mut sources: Vec<String> = Vec::new();
+        for number in numbers {
+          if let Some(document) = number.checked_sub(1).and_then(|i| documents.get(i)) {
+            if !sources.contains(&document.path) {
+              sources.push(document.path.clone());
+            }
+          }
+        }
+        key_points.push(SummaryPoint { text, sources });
+      }
+      None if key_points.is_empty() && !line.trim().is_empty() => {
+        overview.push(line.trim().trim_start_matches('#').trim())
+      }
+      None => {}
+    }
+  }
+  (overview.join("\n"), key_points)
+}
+
+pub fn relative_path(folder: &Path, path: &Path) -> String {
+  path
+    .strip_prefix(folder)
+    .unwrap_or(path)
+    .to_string_lossy()
+    .replace('\\', "/")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn document(path: &str) -> DocumentSummary {
+    DocumentSummary {
+      path: format!("/notes/{}", path),
+      relative_path: path.to_string(),
+      bullets: vec!["要点".to_string()],
+      truncated: false,
+    }
+  }
+
+  #[test]
+  fn collects_supported_documents_in_order() {
+    let dir = std::env::temp_dir().join(format!("binder_folder_summary_{}", uuid::Uuid::new_v4()));
+    for file in [
+      "b.md",
+      "a.PDF",
+      "c.txt",
+      "sub/d.docx",
+      ".binder/e.md",
+      "sub/.f.md",
+    ] {
+      let path = dir.join(file);
+      std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+      std::fs::write(path, "x").unwrap();
+    }
+    let (documents, overflow) = collect_documents(&dir, &FolderSummaryOptions::default());
+    let names: Vec<String> = documents.iter().map(|p| relative_path(&dir, p)).collect();
+    assert_eq!(names, vec!["a.PDF", "b.md", "sub/d.docx"]);
+    assert!(overflow.is_empty());
+
+    let options = FolderSummaryOptions {
+      recursive: false,
+      max_documents: 1,
+      ..Default::default()
+    };
+    let (documents, overflow) = collect_documents(&dir, &options);
+    assert_eq!(documents, vec![dir.join("a.PDF")]);
+    assert_eq!(overflow, vec![dir.join("b.md")]);
+    let _ = std::fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn parses_bullets_and_citations() {
+    assert_eq!(
+      parse_bullets("以下是要点：\n- 甲\n* 乙\n2. 丙\n", 2),
+      vec!["甲", "乙"]
+    );
+    assert_eq!(parse_bullets("只有一句话", 5), vec!["只有一句话"]);
+
+    let documents = vec![document("a.md"), document("b.docx"), document("c.pdf")];
+    let response = "## 总览\n三份文档讨论了预算。\n\n- 预算超支 [1][3]。\n- Costs rose [2, 3].\n- 没有来源\n- 越界引用 [9]\n";
+    let (overview, points) = parse_reduce_response(response, &documents);
+    assert_eq!(overview, "总览\n三份文档讨论了预算。");
+    assert_eq!(
+      points,
+      vec![
+        SummaryPoint {
+          text: "预算超支。".to_string(),
+          sources: vec!["/notes/a.md".to_string(), "/notes/c.pdf".to_string()],
+        },
+        SummaryPoint {
+          text: "Costs rose.".to_string(),
+          sources: vec!["/notes/b.docx".to_string(), "/notes/c.pdf".to_string()],
+        },
+        SummaryPoint {
+          text: "没有来源".to_string(),
+          sources: vec![],
+        },
+        SummaryPoint {
+          text: "越界引用".to_string(),
+          sources: vec![],
+        },
+      ]
+    );
+  }
+
+  #[test]
+  fn splits_long_text_into_chunks() {
+    let line = "字".repeat(SUMMARY_CHUNK_CHARS / 2);
+    let text = format!(
+      "{}\n{}\n{}",
+      line,
+      line,
+      "长".repeat(SUMMARY_CHUNK_CHARS + 10)
+    );
+    let chunks = split_text(&text);
+    assert_eq!(chunks.len(), 4);
+    assert!(chunks
+      .iter()
+      .all(|c| c.chars().count() <= SUMMARY_CHUNK_CHARS + 1));
+    assert_eq!(chunks[3], format!("{}\n", "长".repeat(10)));
+    let prompt = build_reduce_prompt(&[document("a.md")], &FolderSummaryOptions::default());
+    assert!(prompt.contains("[1] a.md\n  - 要点\n"));
+  }
+}
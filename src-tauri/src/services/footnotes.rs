@@ -0,0 +1,536 @@
+//! 脚注管理：列出、插入、重新编号与删除脚注，保证引用标记与脚注定义一一对应。
+//!
+//! - Markdown：`[^label]` 引用与 `[^label]: 内容` 定义（续行缩进），代码块与行内代码中的不算
+//! - HTML（DOCX 在编辑器中的形态）：Pandoc 的 `<a class="footnote-ref" href="#fn1">` 引用与
+//!   `<section class="footnotes">` 中的 `<li id="fn1">` 定义，保存时 Pandoc 据此写回 Word 脚注
+//!
+//! 修改操作都会整体重新编号：按引用首次出现的顺序编为 1..n，定义按同样顺序集中到文末，
+//! 缺少定义的引用补空定义，没有引用的定义删除。
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::ops::Range;
+
+static MD_MARKER: Lazy<Regex> = Lazy::new(|| Regex::new(r"\[\^([^\]\s]+)\]").unwrap());
+static MD_DEFINITION: Lazy<Regex> =
+  Lazy::new(|| Regex::new(r"^ {0,3}\[\^([^\]\s]+)\]:[ \t]?(.*)$").unwrap());
+static HTML_LINK: Lazy<Regex> =
+  Lazy::new(|| Regex::new(r"(?is)(?:<sup>\s*)?<a\b([^>]*)>.*?</a>(?:\s*</sup>)?").unwrap());
+static HREF_FRAGMENT: Lazy<Regex> =
+  Lazy::new(|| Regex::new(r#"(?i)\bhref\s*=\s*["']#([^"']+)["']"#).unwrap());
+static HTML_SECTION: Lazy<Regex> = Lazy::new(|| {
+  Regex::new(r#"(?is)\s*<section\b[^>]*(?:footnotes|doc-endnotes)[^>]*>.*?</section>"#).unwrap()
+});
+static HTML_ITEM: Lazy<Regex> = Lazy::new(|| {
+  Regex::new(r#"(?is)<li\b[^>]*\bid\s*=\s*["']([^"']+)["'][^>]*>(.*?)</li>"#).unwrap()
+});
+static HTML_BACKLINK: Lazy<Regex> =
+  Lazy::new(|| Regex::new(r#"(?is)<a\b[^>]*(?:footnote-back|doc-backlink)[^>]*>.*?</a>"#).unwrap());
+static HTML_TAG: Lazy<Regex> = Lazy::new(|| Regex::new(r"<[^>]+>").unwrap());
+static BODY_END: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)</body>").unwrap());
+
+/// 插入新脚注时使用的临时标签，重新编号后替换为数字
+const NEW_LABEL: &str = "binder-new-footnote";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FootnoteFormat {
+  Markdown,
+  /// Pandoc 风格的 HTML，DOCX 编辑内容使用此格式
+  Html,
+}
+
+impl FootnoteFormat {
+  pub fn parse(value: &str) -> Option<Self> {
+    match value.trim().to_lowercase().as_str() {
+      "md" | "markdown" => Some(FootnoteFormat::Markdown),
+      "html" | "htm" | "docx" => Some(FootnoteFormat::Html),
+      _ => None,
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Footnote {
+  /// 按引用顺序的序号（从 1 开始），没有引用的定义排在最后
+  pub number: usize,
+  /// 文档中的标签（Markdown 为 `[^label]` 中的 label，HTML 为定义的 id）
+  pub label: String,
+  /// 脚注内容的纯文本
+  pub text: String,
+  pub references: usize,
+  pub defined: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FootnoteEdit {
+  pub content: String,
+  pub footnotes: Vec<Footnote>,
+}
+
+struct Marker {
+  range: Range<usize>,
+  label: String,
+}
+
+struct Definition {
+  label: String,
+  /// Markdown 为去掉续行缩进的原文，HTML 为去掉返回链接的 li 内容
+  body: String,
+}
+
+/// 拆出脚注定义后的文档
+struct FootnoteDocument {
+  format: FootnoteFormat,
+  body: String,
+  markers: Vec<Marker>,
+  definitions: Vec<Definition>,
+}
+
+/// 行内代码范围（成对的反引号之间）
+fn inline_code_ranges(line: &str) -> Vec<Range<usize>> {
+  let mut ranges = Vec::new();
+  let mut open: Option<usize> = None;
+  for (i, ch) in line.char_indices() {
+    if ch == '`' {
+      match open.take() {
+        Some(start) => ranges.push(start..i + 1),
+        None => open = Some(i),
+      }
+    }
+  }
+  ranges
+}
+
+fn parse_markdown(content: &str) -> FootnoteDocument {
+  let mut body = String::with_capacity(content.len());
+  let mut markers = Vec::new();
+  let mut definitions: Vec<Definition> = Vec::new();
+  let mut fence: Option<String> = None;
+  let mut in_definition = false;
+
+  for line in content.split_inclusive('\n') {
+    let text = line.trim_end_matches(['\n', '\r']);
+    let trimmed = text.trim();
+    if let Some(marker) = &fence {
+      if trimmed.starts_with(marker.as_str())
+        && trimmed.trim_start_matches(marker.as_str()).is_empty()
+      {
+        fence = None;
+      }
+      body.push_str(line);
+      continue;
+    }
+    if in_definition {
+      let indented = text.starts_with("    ") || text.starts_with('\t');
+      if indented || trimmed.is_empty() {
+        if let Some(definition) = definitions.last_mut() {
+          let stripped = text
+            .strip_prefix("    ")
+            .or_else(|| text.strip_prefix('\t'))
+            .unwrap_or("");
+          definition.body.push('\n');
+          definition.body.push_str(stripped);
+        }
+        continue;
+      }
+      in_definition = false;
+    }
+    if let Some(caps) = MD_DEFINITION.captures(text) {
+      definitions.push(Definition {
+        label: caps[1].to_string(),
+        body: caps[2].to_string(),
+      });
+      in_definition = true;
+      continue;
+    }
+    if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+      let ch = trimmed.chars().next().unwrap_or('`');
+      fence = Some(trimmed.chars().take_while(|&c| c == ch).collect());
+      body.push_str(line);
+      continue;
+    }
+    let code = inline_code_ranges(text);
+    for caps in MD_MARKER.captures_iter(text) {
+      let range = caps.get(0).map(|m| m.range()).unwrap_or_default();
+      if code.iter().any(|c| c.contains(&range.start)) {
+        continue;
+      }
+      markers.push(Marker {
+        range: body.len() + range.start..body.len() + range.end,
+        label: caps[1].to_string(),
+      });
+    }
+    body.push_str(line);
+  }
+
+  for definition in &mut definitions {
+    definition.body = definition.body.trim_end().to_string();
+  }
+  FootnoteDocument {
+    format: FootnoteFormat::Markdown,
+    body,
+    markers,
+    definitions,
+  }
+}
+
+fn parse_html(content: &str) -> FootnoteDocument {
+  let mut definitions = Vec::new();
+  let mut body = content.to_string();
+  if let Some(section) = HTML_SECTION.find(content) {
+    for caps in HTML_ITEM.captures_iter(section.as_str()) {
+      definitions.push(Definition {
+        label: caps[1].to_string(),
+        body: HTML_BACKLINK.replace_all(&caps[2], "").trim().to_string(),
+      });
+    }
+    body.replace_range(section.range(), "");
+  }
+  let markers = HTML_LINK
+    .captures_iter(&body)
+    .filter(|caps| caps[1].contains("footnote-ref") || caps[1].contains("doc-noteref"))
+    .filter_map(|caps| {
+      let label = HREF_FRAGMENT.captures(&caps[1])?[1].to_string();
+      Some(Marker {
+        range: caps.get(0)?.range(),
+        label,
+      })
+    })
+    .collect();
+  FootnoteDocument {
+    format: FootnoteFormat::Html,
+    body,
+    markers,
+    definitions,
+  }
+}
+
+fn parse(content: &str, format: FootnoteFormat) -> FootnoteDocument {
+  match format {
+    FootnoteFormat::Markdown => parse_markdown(content),
+    FootnoteFormat::Html => parse_html(content),
+  }
+}
+
+fn plain_text(body: &str, format: FootnoteFormat) -> String {
+  let text = match format {
+    FootnoteFormat::Markdown => body.to_string(),
+    FootnoteFormat::Html => HTML_TAG.replace_all(body, "").to_string(),
+  };
+  text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+impl FootnoteDocument {
+  /// 引用首次出现的顺序
+  fn referenced_labels(&self) -> Vec<&str> {
+    let mut labels: Vec<&str> = Vec::new();
+    for marker in &self.markers {
+      if !labels.contains(&marker.label.as_str()) {
+        labels.push(&marker.label);
+      }
+    }
+    labels
+  }
+
+  fn definition(&self, label: &str) -> Option<&Definition> {
+    self.definitions.iter().find(|d| d.label == label)
+  }
+
+  fn footnotes(&self) -> Vec<Footnote> {
+    let referenced = self.referenced_labels();
+    let orphans = self
+      .definitions
+      .iter()
+      .map(|d| d.label.as_str())
+      .filter(|label| !referenced.contains(label));
+    referenced
+      .iter()
+      .copied()
+      .chain(orphans)
+      .enumerate()
+      .map(|(i, label)| {
+        let definition = self.definition(label);
+        Footnote {
+          number: i + 1,
+          label: label.to_string(),
+          text: definition
+            .map(|d| plain_text(&d.body, self.format))
+            .unwrap_or_default(),
+          references: self.markers.iter().filter(|m| m.label == label).count(),
+          defined: definition.is_some(),
+        }
+      })
+      .collect()
+  }
+
+  fn marker(&self, number: usize, occurrence: usize) -> String {
+    match self.format {
+      FootnoteFormat::Markdown => format!("[^{}]", number),
+      FootnoteFormat::Html => {
+        let id = if occurrence == 1 {
+          format!("fnref{}", number)
+        } else {
+          format!("fnref{}-{}", number, occurrence)
+        };
+        format!(
+          "<a href=\"#fn{n}\" class=\"footnote-ref\" id=\"{id}\" role=\"doc-noteref\"><sup>{n}</sup></a>",
+          n = number,
+          id = id
+        )
+      }
+    }
+  }
+
+  fn definition_block(&self, number: usize, body: &str) -> String {
+    match self.format {
+      FootnoteFormat::Markdown => {
+        let mut lines = body.lines();
+        let mut block = format!("[^{}]: {}", number, lines.next().unwrap_or_default());
+        for line in lines {
+          block.push('\n');
+          if !line.is_empty() {
+            block.push_str("    ");
+            block.push_str(line);
+          }
+        }
+        block.trim_end().to_string()
+      }
+      FootnoteFormat::Html => {
+        let backlink = format!(
+          "<a href=\"#fnref{}\" class=\"footnote-back\" role=\"doc-backlink\">↩︎</a>",
+          number
+        );
+        let body = if body.trim_start().starts_with("<p") {
+          body.to_string()
+        } else {
+          format!("<p>{}</p>", body)
+        };
+        let body = match body.rfind("</p>") {
+          Some(end) => format!("{}{}{}", &body[..end], backlink, &body[end..]),
+          None => format!("{}{}", body, backlink),
+        };
+        format!("<li id=\"fn{}\">{}</li>", number, body)
+      }
+    }
+  }
+
+  /// 按引用顺序重新编号并在文末重建全部定义；removed 对应的引用一并删除
+  fn renumber(&self, removed: Option<&str>) -> String {
+    let mut order = self.referenced_labels();
+    order.retain(|label| Some(*label) != removed);
+    let numbers: HashMap<&str, usize> = order
+      .iter()
+      .enumerate()
+      .map(|(i, label)| (*label, i + 1))
+      .collect();
+
+    let mut body = String::with_capacity(self.body.len());
+    let mut occurrences: HashMap<&str, usize> = HashMap::new();
+    let mut last = 0;
+    for marker in &self.markers {
+      body.push_str(&self.body[last..marker.range.start]);
+      last = marker.range.end;
+      let Some(&number) = numbers.get(marker.label.as_str()) else {
+        continue;
+      };
+      let occurrence = occurrences.entry(marker.label.as_str()).or_default();
+      *occurrence += 1;
+      body.push_str(&self.marker(number, *occurrence));
+    }
+    body.push_str(&self.body[last..]);
+
+    let blocks: Vec<String> = order
+      .iter()
+      .enumerate()
+      .map(|(i, label)| {
+        let text = self
+          .definition(label)
+          .map(|d| d.body.as_str())
+          .unwrap_or("");
+        self.definition_block(i + 1, text)
+      })
+      .collect();
+
+    match self.format {
+      FootnoteFormat::Markdown => {
+        let mut content = body.trim_end().to_string();
+        if !blocks.is_empty() {
+          content.push_str("\n\n");
+          content.push_str(&blocks.join("\n"));
+        }
+        content.push('\n');
+        content
+      }
+      FootnoteFormat::Html => {
+        if blocks.is_empty() {
+          return body;
+        }
+        let section = format!(
+          "\n<section id=\"footnotes\" class=\"footnotes footnotes-end-of-document\" role=\"doc-endnotes\">\n<hr />\n<ol>\n{}\n</ol>\n</section>\n",
+          blocks.join("\n")
+        );
+        match BODY_END.find(&body) {
+          Some(end) => format!(
+            "{}{}{}",
+            body[..end.start()].trim_end(),
+            section,
+            &body[end.start()..]
+          ),
+          None => format!("{}{}", body.trim_end(), section),
+        }
+      }
+    }
+  }
+}
+
+fn edit(content: String, format: FootnoteFormat) -> FootnoteEdit {
+  let footnotes = parse(&content, format).footnotes();
+  FootnoteEdit { content, footnotes }
+}
+
+pub fn list_footnotes(content: &str, format: FootnoteFormat) -> Vec<Footnote> {
+  parse(content, format).footnotes()
+}
+
+pub fn renumber_footnotes(content: &str, format: FootnoteFormat) -> FootnoteEdit {
+  edit(parse(content, format).renumber(None), format)
+}
+
+/// 在 position（按 Unicode 字符计的偏移）处插入脚注，落在 HTML 标签内部时移到标签之后；
+/// 插入点位于脚注定义内时返回错误
+pub fn add_footnote(
+  content: &str,
+  format: FootnoteFormat,
+  position: usize,
+  text: &str,
+) -> Result<FootnoteEdit, String> {
+  let mut offset = content
+    .char_indices()
+    .nth(position)
+    .map_or(content.len(), |(i, _)| i);
+  let (marker, body) = match format {
+    FootnoteFormat::Markdown => (format!("[^{}]", NEW_LABEL), text.trim().to_string()),
+    FootnoteFormat::Html => {
+      let before = &content[..offset];
+      if before.rfind('<') > before.rfind('>') {
+        offset = content[offset..]
+          .find('>')
+          .map_or(content.len(), |i| offset + i + 1);
+      }
+      (
+        format!("<a href=\"#{}\" class=\"footnote-ref\"></a>", NEW_LABEL),
+        html_escape(text.trim()),
+      )
+    }
+  };
+
+  let mut inserted = content.to_string();
+  inserted.insert_str(offset, &marker);
+  let mut document = parse(&inserted, format);
+  if !document.markers.iter().any(|m| m.label == NEW_LABEL) {
+    return Err("不能在脚注内容或代码中插入脚注".to_string());
+  }
+  document.definitions.push(Definition {
+    label: NEW_LABEL.to_string(),
+    body,
+  });
+  Ok(edit(document.renumber(None), format))
+}
+
+/// 删除 label 对应的脚注（全部引用与定义），其余脚注重新编号
+pub fn delete_footnote(
+  content: &str,
+  format: FootnoteFormat,
+  label: &str,
+) -> Result<FootnoteEdit, String> {
+  let document = parse(content, format);
+  let exists = document.markers.iter().any(|m| m.label == label)
+    || document.definitions.iter().any(|d| d.label == label);
+  if !exists {
+    return Err(format!("脚注不存在: {}", label));
+  }
+  Ok(edit(document.renumber(Some(label)), format))
+}
+
+fn html_escape(text: &str) -> String {
+  text
+    .replace('&', "&amp;")
+    .replace('<', "&lt;")
+    .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn renumbers_markdown_footnotes_by_reference_order() {
+    let content = "正文[^b]，再引[^a]。\n\n[^a]: 甲\n    第二行\n[^orphan]: 无引用\n\n```\n[^b] 代码\n```\n`[^x]` 与 [^b] 重复\n\n[^b]: 乙\n";
+    let listed = list_footnotes(content, FootnoteFormat::Markdown);
+    let summary: Vec<(&str, usize, bool)> = listed
+      .iter()
+      .map(|f| (f.label.as_str(), f.references, f.defined))
+      .collect();
+    assert_eq!(
+      summary,
+      vec![("b", 2, true), ("a", 1, true), ("orphan", 0, true)]
+    );
+    assert_eq!(listed[1].text, "甲 第二行");
+
+    let edit = renumber_footnotes(content, FootnoteFormat::Markdown);
+    assert_eq!(
+      edit.content,
+      "正文[^1]，再引[^2]。\n\n```\n[^b] 代码\n```\n`[^x]` 与 [^1] 重复\n\n[^1]: 乙\n[^2]: 甲\n    第二行\n"
+    );
+
+    let added = add_footnote(&edit.content, FootnoteFormat::Markdown, 2, "新注").unwrap();
+    assert!(added.content.starts_with("正文[^1][^2]，再引[^3]。"));
+    assert!(added
+      .content
+      .ends_with("[^1]: 新注\n[^2]: 乙\n[^3]: 甲\n    第二行\n"));
+
+    let deleted = delete_footnote(&added.content, FootnoteFormat::Markdown, "2").unwrap();
+    assert!(deleted.content.starts_with("正文[^1]，再引[^2]。"));
+    assert!(deleted.content.contains("`[^x]` 与  重复"));
+    assert_eq!(deleted.footnotes.len(), 2);
+    assert!(delete_footnote(&deleted.content, FootnoteFormat::Markdown, "9").is_err());
+  }
+
+  #[test]
+  fn keeps_html_markers_and_definitions_consistent() {
+    let html = concat!(
+      "<html><body><p>一<a href=\"#fn2\" class=\"footnote-ref\" id=\"fnref2\" role=\"doc-noteref\"><sup>2</sup></a>",
+      "二<sup><a href=\"#fn1\" class=\"footnote-ref\">1</a></sup></p>",
+      "<section class=\"footnotes\" role=\"doc-endnotes\"><hr /><ol>",
+      "<li id=\"fn1\"><p>注一<a href=\"#fnref1\" class=\"footnote-back\">↩︎</a></p></li>",
+      "<li id=\"fn2\"><p>注二<a href=\"#fnref2\" class=\"footnote-back\">↩︎</a></p></li>",
+      "</ol></section></body></html>",
+    );
+    let edit = renumber_footnotes(html, FootnoteFormat::Html);
+    assert_eq!(
+      edit
+        .footnotes
+        .iter()
+        .map(|f| f.text.as_str())
+        .collect::<Vec<_>>(),
+      vec!["注二", "注一"]
+    );
+    assert!(edit.content.contains("一<a href=\"#fn1\" class=\"footnote-ref\" id=\"fnref1\" role=\"doc-noteref\"><sup>1</sup></a>二<a href=\"#fn2\""));
+    assert!(edit.content.contains("<li id=\"fn1\"><p>注二<a href=\"#fnref1\" class=\"footnote-back\" role=\"doc-backlink\">↩︎</a></p></li>"));
+    assert!(edit.content.ends_with("</section>\n</body></html>"));
+    assert_eq!(edit.content.matches("<section").count(), 1);
+
+    // 插入点落在 <p> 标签内部时移到标签之后
+    let added = add_footnote(&edit.content, FootnoteFormat::Html, 14, "a<b").unwrap();
+    assert!(added.content.starts_with("<html><body><p><a href=\"#fn1\""));
+    assert_eq!(added.footnotes[0].text, "a&lt;b");
+    assert_eq!(added.footnotes.len(), 3);
+
+    let deleted = delete_footnote(&added.content, FootnoteFormat::Html, "fn1").unwrap();
+    assert_eq!(deleted.footnotes.len(), 2);
+    assert_eq!(deleted.footnotes[0].text, "注二");
+  }
+}
@@ -0,0 +1,263 @@
+//! 工作区资料问答（RAG）：按用户消息从工作区搜索索引检索相关段落，编号后注入上下文，
+//! 要求模型在回答中用 `[n]` 标注来源；流结束时把回答中实际引用的文件随 done 事件返回。
+//!
+//! 每个聊天 tab 同一时间只有一轮检索结果，流式文本边推送边记录，done 时按引用编号解析。
+
+use crate::services::search_service::{natural_language_query, SearchService};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+
+/// 默认检索的段落数
+pub const GROUNDING_SOURCE_LIMIT: usize = 6;
+/// 每个段落最多注入的字符数
+pub const GROUNDING_PASSAGE_CHARS: usize = 800;
+
+/// 进行中的资料问答：tab_id → 检索结果与已推送的回答
+static SESSIONS: Lazy<Mutex<HashMap<String, GroundingSession>>> =
+  Lazy::new(|| Mutex::new(HashMap::new()));
+
+struct GroundingSession {
+  sources: Vec<GroundingSource>,
+  answer: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GroundingSource {
+  /// 引用编号（从 1 开始），对应回答中的 `[n]`
+  pub id: usize,
+  /// 相对工作区的路径
+  pub path: String,
+  pub title: String,
+  pub excerpt: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroundingCitations {
+  /// 本轮检索并注入的全部段落
+  pub sources: Vec<GroundingSource>,
+  /// 回答中实际引用的段落，按首次引用顺序
+  pub cited: Vec<GroundingSource>,
+}
+
+/// 查询词：与搜索索引一致，按非字母数字切分，至少两个字符
+fn terms(question: &str) -> Vec<String> {
+  let mut terms: Vec<String> = Vec::new();
+  for word in question.split(|c: char| !c.is_alphanumeric()) {
+    let word = word.to_lowercase();
+    if word.chars().count() >= 2 && !terms.contains(&word) {
+      terms.push(word);
+    }
+  }
+  terms
+}
+
+fn truncate_chars(text: &str, max_chars: usize) -> String {
+  match text.char_indices().nth(max_chars) {
+    Some((end, _)) => format!("{}…", text[..end].trim_end()),
+    None => text.to_string(),
+  }
+}
+
+/// 选出命中查询词最多的段落，并向前后扩展相邻段落直到 max_chars
+pub fn best_passage(content: &str, terms: &[String], max_chars: usize) -> String {
+  let paragraphs: Vec<&str> = content
+    .split("\n\n")
+    .map(str::trim)
+    .filter(|p| !p.is_empty())
+    .collect();
+  if paragraphs.is_empty() {
+    return String::new();
+  }
+  let score = |p: &str| {
+    let lower = p.to_lowercase();
+    terms.iter().filter(|t| lower.contains(t.as_str())).count()
+  };
+  let best = (0..paragraphs.len())
+    .max_by_key(|&i| (score(paragraphs[i]), std::cmp::Reverse(i)))
+    .unwrap_or(0);
+
+  let chars = |p: &str| p.chars().count() + 2;
+  let (mut start, mut end) = (best, best + 1);
+  let mut used = chars(paragraphs[best]);
+  loop {
+    let before = start
+      .checked_sub(1)
+      .filter(|&i| used + chars(paragraphs[i]) <= max_chars);
+    if let Some(i) = before {
+      start = i;
+      used += chars(paragraphs[i]);
+    }
+    let after = (end < paragraphs.len())
+      .then_some(end)
+      .filter(|&i| used + chars(paragraphs[i]) <= max_chars);
+    if let Some(i) = after {
+      end = i + 1;
+      used += chars(paragraphs[i]);
+    }
+    if before.is_none() && after.is_none() {
+      break;
+    }
+  }
+  truncate_chars(&paragraphs[start..end].join("\n\n"), max_chars)
+}
+
+/// 从搜索索引检索相关段落；索引不存在或没有结果时返回空列表
+pub fn retrieve_sources(
+  workspace_path: &Path,
+  question: &str,
+  limit: usize,
+  passage_chars: usize,
+) -> Result<Vec<GroundingSource>, String> {
+  let query = natural_language_query(question);
+  if query.is_empty() || !workspace_path.join(".binder").join("search.db").exists() {
+    return Ok(Vec::new());
+  }
+  let service =
+    SearchService::new(workspace_path).map_err(|e| format!("打开搜索索引失败: {}", e))?;
+  let results = service
+    .search(&query, limit)
+    .map_err(|e| format!("检索工作区失败: {}", e))?;
+  let terms = terms(question);
+  let mut sources = Vec::new();
+  for result in results {
+    let content = service
+      .document_content(&result.path)
+      .map_err(|e| format!("读取索引内容失败: {}", e))?
+      .unwrap_or_default();
+    let excerpt = best_passage(&content, &terms, passage_chars);
+    if excerpt.is_empty() {
+      continue;
+    }
+    sources.push(GroundingSource {
+      id: sources.len() + 1,
+      path: result.path,
+      title: result.title,
+      excerpt,
+    });
+  }
+  Ok(sources)
+}
+
+/// 注入上下文的资料段落与引用要求
+pub fn format_context(sources: &[GroundingSource]) -> String {
+  let mut context = String::from(
+    "[工作区资料]\n请依据以下资料回答，在用到资料的句子末尾标注来源编号，如 [1] 或 [2][3]；\
+     资料不足以回答时请直接说明，不要编造。\n\n",
+  );
+  for source in sources {
+    context.push_str(&format!(
+      "[{}] {}（{}）\n{}\n\n",
+      source.id, source.title, source.path, source.excerpt
+    ));
+  }
+  context.push_str("[/工作区资料]");
+  context
+}
+
+/// 回答中引用的段落，按首次出现顺序；不存在的编号忽略
+pub fn cited_sources(answer: &str, sources: &[GroundingSource]) -> Vec<GroundingSource> {
+  let mut cited: Vec<GroundingSource> = Vec::new();
+  let mut rest = answer;
+  while let Some(open) = rest.find('[') {
+    rest = &rest[open + 1..];
+    let Some(close) = rest.find(']') else {
+      break;
+    };
+    for number in rest[..close].split([',', '，', ' ']) {
+      let Ok(id) = number.trim().parse::<usize>() else {
+        continue;
+      };
+      if let Some(source) = sources.iter().find(|s| s.id == id) {
+        if !cited.iter().any(|c| c.id == id) {
+          cited.push(source.clone());
+        }
+      }
+    }
+  }
+  cited
+}
+
+fn sessions() -> std::sync::MutexGuard<'static, HashMap<String, GroundingSession>> {
+  SESSIONS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// 开始一轮资料问答，替换该 tab 之前的记录
+pub fn begin(tab_id: &str, sources: Vec<GroundingSource>) {
+  sessions().insert(
+    tab_id.to_string(),
+    GroundingSession {
+      sources,
+      answer: String::new(),
+    },
+  );
+}
+
+/// 记录已推送给前端的回答文本；该 tab 不在资料问答中时忽略
+pub fn record_text(tab_id: &str, text: &str) {
+  if let Some(session) = sessions().get_mut(tab_id) {
+    session.answer.push_str(text);
+  }
+}
+
+/// 结束该 tab 的资料问答，返回检索结果与回答中的引用
+pub fn finish(tab_id: &str) -> Option<GroundingCitations> {
+  let session = sessions().remove(tab_id)?;
+  let cited = cited_sources(&session.answer, &session.sources);
+  Some(GroundingCitations {
+    sources: session.sources,
+    cited,
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn source(id: usize, path: &str) -> GroundingSource {
+    GroundingSource {
+      id,
+      path: path.to_string(),
+      title: path.to_string(),
+      excerpt: "内容".to_string(),
+    }
+  }
+
+  #[test]
+  fn picks_passage_with_most_terms() {
+    let content =
+      "# 合同\n\n第一条 总则。\n\n第二条 付款方式：按月付款。\n\n第三条 违约责任。\n\n附件";
+    let terms = terms("付款方式 是什么");
+    assert_eq!(terms, vec!["付款方式", "是什么"]);
+    assert_eq!(
+      best_passage(content, &terms, 30),
+      "第一条 总则。\n\n第二条 付款方式：按月付款。"
+    );
+    assert_eq!(best_passage(content, &terms, 5), "第二条 付…");
+    assert_eq!(best_passage("", &terms, 100), "");
+  }
+
+  #[test]
+  fn resolves_citations_per_tab() {
+    let sources = vec![source(1, "a.md"), source(2, "b.md"), source(3, "c.md")];
+    begin("tab-1", sources.clone());
+    record_text("tab-1", "按月付款 [2]");
+    record_text("tab-1", "，逾期需赔偿 [3][2]，另见[1, 9]。[注]");
+    record_text("tab-2", "[1]");
+    let citations = finish("tab-1").unwrap();
+    assert_eq!(citations.sources, sources);
+    assert_eq!(
+      citations
+        .cited
+        .iter()
+        .map(|s| s.path.as_str())
+        .collect::<Vec<_>>(),
+      vec!["b.md", "c.md", "a.md"]
+    );
+    assert!(finish("tab-1").is_none());
+    assert!(finish("tab-2").is_none());
+    assert!(format_context(&sources[..1]).contains("[1] a.md（a.md）\n内容\n\n[/工作区资料]"));
+  }
+}
@@ -0,0 +1,138 @@
+//! 文档 HTML 清理：Pandoc 输出可能携带文档中的原始 HTML（脚本、iframe、事件属性），
+//! 在交给预览 / 编辑器之前用 ammonia 移除可执行内容，保留排版所需的内联样式、class 与 `<style>` 样式表。
+//!
+//! 工作区在应用设置 `trusted_html_workspaces` 中时跳过清理（仅用于完全可信的文档）。
+
+use ammonia::Builder;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::borrow::Cow;
+
+/// Pandoc 常用、ammonia 默认白名单之外的结构标签
+const EXTRA_TAGS: [&str; 5] = ["section", "main", "tfoot", "font", "figure"];
+
+/// 排版相关的通用属性
+const STYLE_ATTRIBUTES: [&str; 6] = ["style", "class", "id", "dir", "align", "width"];
+
+static SANITIZER: Lazy<Builder<'static>> = Lazy::new(|| {
+  let mut builder = Builder::default();
+  builder
+    .add_tags(EXTRA_TAGS)
+    .add_generic_attributes(STYLE_ATTRIBUTES)
+    .add_generic_attribute_prefixes(["data-"])
+    // 编辑模式图片内嵌为 data URL，本地图片经 asset 协议加载
+    .add_url_schemes(["data", "asset"])
+    .attribute_filter(|_element, attribute, value| match attribute {
+      "style" if !is_safe_css(value) => None,
+      "href" if value.trim_start().to_ascii_lowercase().starts_with("data:") => None,
+      "src" if is_unsafe_data_url(value) => None,
+      _ => Some(Cow::Borrowed(value)),
+    });
+  builder
+});
+
+static STYLE_BLOCK: Lazy<Regex> =
+  Lazy::new(|| Regex::new(r"(?is)<style[^>]*>(.*?)</style\s*>").unwrap());
+static BODY: Lazy<Regex> =
+  Lazy::new(|| Regex::new(r"(?is)<body[^>]*>(.*?)(?:</body\s*>|$)").unwrap());
+static TITLE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?is)<title[^>]*>(.*?)</title\s*>").unwrap());
+
+/// CSS 中可能发起外部请求或执行脚本的写法
+fn is_safe_css(css: &str) -> bool {
+  let css: String = css
+    .chars()
+    .filter(|c| !c.is_whitespace())
+    .collect::<String>()
+    .to_ascii_lowercase();
+  !(css.contains("javascript:")
+    || css.contains("expression(")
+    || css.contains("@import")
+    || css.contains("behavior:")
+    || css.contains("-moz-binding")
+    || css.match_indices("url(").any(|(i, _)| {
+      !css[i + 4..]
+        .trim_start_matches(['\'', '"'])
+        .starts_with("data:image/")
+    }))
+}
+
+/// 只允许位图 data URL，内嵌 SVG / HTML 可能带脚本
+fn is_unsafe_data_url(value: &str) -> bool {
+  let value = value.trim_start().to_ascii_lowercase();
+  value.starts_with("data:")
+    && (!value.starts_with("data:image/") || value.starts_with("data:image/svg"))
+}
+
+/// 清理 HTML 片段
+pub fn sanitize_html_fragment(html: &str) -> String {
+  SANITIZER.clean(html).to_string()
+}
+
+/// 清理完整 HTML 文档：正文逐元素清理，`<head>` 只保留标题与通过检查的 `<style>` 样式表
+pub fn sanitize_html_document(html: &str) -> String {
+  let Some(body) = BODY.captures(html).and_then(|c| c.get(1)) else {
+    return sanitize_html_fragment(html);
+  };
+  let head = &html[..body.start()];
+  let styles: Vec<&str> = STYLE_BLOCK
+    .captures_iter(head)
+    .filter_map(|c| c.get(1).map(|m| m.as_str()))
+    .filter(|css| is_safe_css(css))
+    .collect();
+  let title = TITLE
+    .captures(head)
+    .and_then(|c| c.get(1))
+    .map(|m| sanitize_html_fragment(m.as_str()))
+    .unwrap_or_default();
+
+  let mut document = String::from("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\" />\n");
+  if !title.is_empty() {
+    document.push_str(&format!("<title>{}</title>\n", title));
+  }
+  for css in styles {
+    document.push_str(&format!("<style>{}</style>\n", css));
+  }
+  // 正文前后不额外插入换行，编辑器会把前导空白当作空段落
+  document.push_str("</head>\n<body>");
+  document.push_str(&sanitize_html_fragment(body.as_str()));
+  document.push_str("</body>\n</html>\n");
+  document
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn strips_active_content_but_keeps_styling() {
+    let html = r#"<html><head><title>报告</title><style>p { color: red; }</style>
+<style>@import url(https://evil/x.css);</style><script>alert(1)</script></head>
+<body><p style="text-align: center; color: #333" class="lead" onclick="x()">正文</p>
+<iframe src="https://evil"></iframe><div data-custom-style="Title"><span style="background: url(https://tracker/p.png)">标题</span></div>
+<a href="javascript:alert(1)">链接</a><img src="data:image/png;base64,AAAA"><img src="data:text/html,<script>">
+</body></html>"#;
+
+    let cleaned = sanitize_html_document(html);
+    for removed in [
+      "<script",
+      "alert",
+      "iframe",
+      "onclick",
+      "@import",
+      "tracker",
+      "data:text",
+    ] {
+      assert!(!cleaned.contains(removed), "{} 未被移除", removed);
+    }
+    for kept in [
+      "<title>报告</title>",
+      "<style>p { color: red; }</style>",
+      r#"style="text-align: center; color: #333""#,
+      r#"class="lead""#,
+      r#"data-custom-style="Title""#,
+      r#"src="data:image/png;base64,AAAA""#,
+    ] {
+      assert!(cleaned.contains(kept), "{} 未保留", kept);
+    }
+  }
+}
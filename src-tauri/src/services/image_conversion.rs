@@ -0,0 +1,240 @@
+//! 批量将工作区内的图片转换为 WebP / AVIF，并同步更新 Markdown / HTML 文档中的引用。
+//!
+//! 只处理转换后体积确实变小的图片；dry_run 模式只编码不写盘，用于预估可节省的空间。
+//! AVIF 编码依赖 rav1e（构建时需要 nasm），仅在启用 `avif` 特性时可用。
+
+use crate::services::image_references::{
+  has_extension, normalize_lexically, relative_display, rewrite_workspace_references,
+  workspace_files,
+};
+use image::DynamicImage;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TargetImageFormat {
+  Webp,
+  Avif,
+}
+
+impl TargetImageFormat {
+  pub fn parse(value: &str) -> Result<Self, String> {
+    match value.to_ascii_lowercase().as_str() {
+      "webp" => Ok(Self::Webp),
+      "avif" => Ok(Self::Avif),
+      other => Err(format!("不支持的目标格式: {}（可选 webp / avif）", other)),
+    }
+  }
+
+  fn extension(self) -> &'static str {
+    match self {
+      Self::Webp => "webp",
+      Self::Avif => "avif",
+    }
+  }
+
+  /// 参与转换的源图片扩展名；GIF（可能含动画）与 SVG 不处理
+  fn source_extensions(self) -> &'static [&'static str] {
+    match self {
+      Self::Webp => &["png", "jpg", "jpeg"],
+      Self::Avif => &["png", "jpg", "jpeg", "webp"],
+    }
+  }
+}
+
+/// 单张图片的转换结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImageConversionEntry {
+  /// 相对工作区根目录的原路径
+  pub path: String,
+  pub new_path: String,
+  pub original_bytes: u64,
+  pub converted_bytes: Option<u64>,
+  /// 未转换的原因（解码失败、目标已存在、转换后未变小等）
+  pub skipped: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImageConversionReport {
+  pub format: TargetImageFormat,
+  pub quality: u8,
+  pub dry_run: bool,
+  pub entries: Vec<ImageConversionEntry>,
+  pub converted_count: usize,
+  pub total_original_bytes: u64,
+  pub total_converted_bytes: u64,
+  pub saved_bytes: u64,
+  /// 引用已更新的文档（相对路径）；dry_run 时为将要更新的文档
+  pub updated_documents: Vec<String>,
+}
+
+/// 按目标格式编码图片
+pub fn encode_image(
+  img: &DynamicImage,
+  format: TargetImageFormat,
+  quality: u8,
+) -> Result<Vec<u8>, String> {
+  let rgba = img.to_rgba8();
+  let (width, height) = rgba.dimensions();
+  match format {
+    TargetImageFormat::Webp => Ok(
+      webp::Encoder::from_rgba(rgba.as_raw(), width, height)
+        .encode(quality as f32)
+        .to_vec(),
+    ),
+    #[cfg(feature = "avif")]
+    TargetImageFormat::Avif => {
+      use image::ImageEncoder;
+      let mut encoded = Vec::new();
+      image::codecs::avif::AvifEncoder::new_with_speed_quality(&mut encoded, 6, quality)
+        .write_image(rgba.as_raw(), width, height, image::ColorType::Rgba8)
+        .map_err(|e| format!("AVIF 编码失败: {}", e))?;
+      Ok(encoded)
+    }
+    #[cfg(not(feature = "avif"))]
+    TargetImageFormat::Avif => Err(avif_unavailable()),
+  }
+}
+
+#[cfg(not(feature = "avif"))]
+fn avif_unavailable() -> String {
+  "当前版本未启用 AVIF 编码（需以 avif 特性构建）".to_string()
+}
+
+/// 指向已转换图片的引用：保留原写法，只替换扩展名
+fn converted_reference(
+  reference: &str,
+  target: &Path,
+  converted: &HashMap<PathBuf, PathBuf>,
+) -> Option<String> {
+  let new_ext = converted.get(target)?.extension()?.to_str()?;
+  let dot = reference.rfind('.')?;
+  Some(format!("{}.{}", &reference[..dot], new_ext))
+}
+
+/// 转换工作区图片；dry_run 时只统计大小，不写入任何文件
+pub fn convert_workspace_images(
+  workspace_path: &Path,
+  format: TargetImageFormat,
+  quality: u8,
+  dry_run: bool,
+) -> Result<ImageConversionReport, String> {
+  if !(1..=100).contains(&quality) {
+    return Err("图片压缩质量必须在 1-100 之间".to_string());
+  }
+  #[cfg(not(feature = "avif"))]
+  if format == TargetImageFormat::Avif {
+    return Err(avif_unavailable());
+  }
+
+  let workspace_path = normalize_lexically(workspace_path);
+  let files: Vec<PathBuf> = workspace_files(&workspace_path).collect();
+
+  let mut entries = Vec::new();
+  let mut converted: HashMap<PathBuf, PathBuf> = HashMap::new();
+  for path in files
+    .iter()
+    .filter(|p| has_extension(p, format.source_extensions()))
+  {
+    let new_path = path.with_extension(format.extension());
+    let original = fs::read(path).map_err(|e| format!("读取图片失败 {}: {}", path.display(), e))?;
+    let mut entry = ImageConversionEntry {
+      path: relative_display(path, &workspace_path),
+      new_path: relative_display(&new_path, &workspace_path),
+      original_bytes: original.len() as u64,
+      converted_bytes: None,
+      skipped: None,
+    };
+
+    let encoded = if new_path.exists() {
+      Err("目标文件已存在".to_string())
+    } else {
+      image::load_from_memory(&original)
+        .map_err(|e| format!("解码失败: {}", e))
+        .and_then(|img| encode_image(&img, format, quality))
+    };
+    match encoded {
+      Ok(data) if data.len() < original.len() => {
+        entry.converted_bytes = Some(data.len() as u64);
+        if !dry_run {
+          fs::write(&new_path, &data)
+            .map_err(|e| format!("写入图片失败 {}: {}", new_path.display(), e))?;
+        }
+        converted.insert(path.clone(), new_path);
+      }
+      Ok(_) => entry.skipped = Some("转换后体积未减小".to_string()),
+      Err(e) => entry.skipped = Some(e),
+    }
+    entries.push(entry);
+  }
+
+  // 先更新文档引用，再删除原图，避免中途失败留下失效引用
+  let mut updated_documents = Vec::new();
+  if !converted.is_empty() {
+    updated_documents =
+      rewrite_workspace_references(&workspace_path, &files, dry_run, |reference, target, _| {
+        converted_reference(reference, target, &converted)
+      })?;
+    if !dry_run {
+      for original in converted.keys() {
+        if let Err(e) = fs::remove_file(original) {
+          tracing::warn!("删除原图失败 {}: {}", original.display(), e);
+        }
+      }
+    }
+  }
+
+  let converted_entries = entries.iter().filter(|e| e.converted_bytes.is_some());
+  let total_original_bytes: u64 = converted_entries.clone().map(|e| e.original_bytes).sum();
+  let total_converted_bytes: u64 = converted_entries
+    .clone()
+    .filter_map(|e| e.converted_bytes)
+    .sum();
+  Ok(ImageConversionReport {
+    format,
+    quality,
+    dry_run,
+    converted_count: converted_entries.count(),
+    entries,
+    total_original_bytes,
+    total_converted_bytes,
+    saved_bytes: total_original_bytes.saturating_sub(total_converted_bytes),
+    updated_documents,
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::services::image_references::rewrite_image_references;
+
+  #[test]
+  fn rewrites_only_references_to_converted_images() {
+    let root = PathBuf::from("/ws");
+    let converted = HashMap::from([
+      (root.join("assets/a.png"), root.join("assets/a.webp")),
+      (root.join("img/b.JPG"), root.join("img/b.webp")),
+    ]);
+    let doc = "![a](assets/a.png) ![b](../img/b.JPG) ![c](assets/c.png)\n\
+      <img src=\"./assets/a.png\"> <img src='https://x/assets/a.png'>";
+
+    let rewrite = |content: &str, dir: &Path| {
+      rewrite_image_references(content, dir, |reference, target| {
+        converted_reference(reference, target, &converted)
+      })
+    };
+
+    let rewritten = rewrite(doc, &root).unwrap();
+    assert_eq!(
+      rewritten,
+      "![a](assets/a.webp) ![b](../img/b.JPG) ![c](assets/c.png)\n\
+      <img src=\"./assets/a.webp\"> <img src='https://x/assets/a.png'>"
+    );
+
+    let nested = rewrite("![b](../img/b.JPG)", &root.join("docs"));
+    assert_eq!(nested.as_deref(), Some("![b](../img/b.webp)"));
+  }
+}
@@ -0,0 +1,248 @@
+//! 资源文件夹图片去重：路径中含 `assets` 目录的图片按内容哈希找出完全相同的副本，
+//! 可选按差值哈希（dHash）找出近似重复，把文档引用改指向保留的那一份后删除其余副本。
+
+use crate::services::image_references::{
+  has_extension, normalize_lexically, relative_display, relative_reference,
+  rewrite_workspace_references, workspace_files,
+};
+use image::{DynamicImage, GenericImageView};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 参与去重的图片格式（SVG 只做完全相同比较）
+const DEDUPE_IMAGE_EXTENSIONS: [&str; 7] = ["png", "jpg", "jpeg", "gif", "webp", "bmp", "svg"];
+
+/// 近似重复的默认阈值：64 位 dHash 的汉明距离
+pub const DEFAULT_NEAR_THRESHOLD: u32 = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DuplicateKind {
+  Exact,
+  Near,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DuplicateGroup {
+  pub kind: DuplicateKind,
+  /// 保留的图片（相对工作区根目录）
+  pub canonical: String,
+  pub duplicates: Vec<String>,
+  /// 删除副本可回收的字节数
+  pub reclaimable_bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImageDedupeReport {
+  pub dry_run: bool,
+  pub scanned_count: usize,
+  pub groups: Vec<DuplicateGroup>,
+  pub reclaimed_bytes: u64,
+  /// 引用已改指向保留图片的文档；dry_run 时为将要更新的文档
+  pub updated_documents: Vec<String>,
+}
+
+/// 差值哈希：缩放为 9×8 灰度图，逐行比较相邻像素亮度
+pub fn difference_hash(img: &DynamicImage) -> u64 {
+  let small = img
+    .resize_exact(9, 8, image::imageops::FilterType::Triangle)
+    .to_luma8();
+  let mut hash = 0u64;
+  for y in 0..8 {
+    for x in 0..8 {
+      hash <<= 1;
+      if small.get_pixel(x, y)[0] < small.get_pixel(x + 1, y)[0] {
+        hash |= 1;
+      }
+    }
+  }
+  hash
+}
+
+/// 内容相同的一组图片
+struct ContentGroup {
+  paths: Vec<PathBuf>,
+  bytes: u64,
+  dhash: Option<u64>,
+  pixels: u64,
+}
+
+fn in_assets_folder(path: &Path, workspace_path: &Path) -> bool {
+  path
+    .strip_prefix(workspace_path)
+    .unwrap_or(path)
+    .parent()
+    .is_some_and(|dir| dir.components().any(|c| c.as_os_str() == "assets"))
+}
+
+fn find(parent: &mut [usize], i: usize) -> usize {
+  let mut root = i;
+  while parent[root] != root {
+    root = parent[root];
+  }
+  parent[i] = root;
+  root
+}
+
+/// 查找并合并重复图片；dry_run 时只生成报告
+pub fn dedupe_images(
+  workspace_path: &Path,
+  dry_run: bool,
+  include_near: bool,
+  near_threshold: u32,
+) -> Result<ImageDedupeReport, String> {
+  let workspace_path = normalize_lexically(workspace_path);
+  let files: Vec<PathBuf> = workspace_files(&workspace_path).collect();
+  let mut images: Vec<&PathBuf> = files
+    .iter()
+    .filter(|p| has_extension(p, &DEDUPE_IMAGE_EXTENSIONS) && in_assets_folder(p, &workspace_path))
+    .collect();
+  images.sort();
+
+  // 1. 按内容哈希归组（完全相同）
+  let mut by_hash: HashMap<String, usize> = HashMap::new();
+  let mut groups: Vec<ContentGroup> = Vec::new();
+  for path in &images {
+    let data = fs::read(path).map_err(|e| format!("读取图片失败 {}: {}", path.display(), e))?;
+    let hash = format!("{:x}", Sha256::digest(&data));
+    if let Some(&index) = by_hash.get(&hash) {
+      groups[index].paths.push((*path).clone());
+      continue;
+    }
+
+    let decoded = if include_near && !has_extension(path, &["svg"]) {
+      image::load_from_memory(&data).ok()
+    } else {
+      None
+    };
+    by_hash.insert(hash, groups.len());
+    groups.push(ContentGroup {
+      paths: vec![(*path).clone()],
+      bytes: data.len() as u64,
+      dhash: decoded.as_ref().map(difference_hash),
+      pixels: decoded
+        .map(|img| {
+          let (w, h) = img.dimensions();
+          w as u64 * h as u64
+        })
+        .unwrap_or(0),
+    });
+  }
+
+  // 2. 近似重复：dHash 汉明距离不超过阈值的内容组合并
+  let mut parent: Vec<usize> = (0..groups.len()).collect();
+  if include_near {
+    for i in 0..groups.len() {
+      for j in (i + 1)..groups.len() {
+        if let (Some(a), Some(b)) = (groups[i].dhash, groups[j].dhash) {
+          if (a ^ b).count_ones() <= near_threshold {
+            let (ri, rj) = (find(&mut parent, i), find(&mut parent, j));
+            parent[rj] = ri;
+          }
+        }
+      }
+    }
+  }
+  let mut clusters: HashMap<usize, Vec<usize>> = HashMap::new();
+  for i in 0..groups.len() {
+    let root = find(&mut parent, i);
+    clusters.entry(root).or_default().push(i);
+  }
+
+  // 3. 每组保留一份：近似重复保留分辨率最高（其次体积最大）的，完全相同保留路径排序最前的
+  let mut replacements: HashMap<PathBuf, PathBuf> = HashMap::new();
+  let mut report_groups = Vec::new();
+  let mut cluster_list: Vec<Vec<usize>> = clusters.into_values().collect();
+  cluster_list.sort();
+  for members in cluster_list {
+    let keep = *members
+      .iter()
+      .max_by_key(|&&i| (groups[i].pixels, groups[i].bytes, std::cmp::Reverse(i)))
+      .expect("cluster is never empty");
+    let canonical = groups[keep].paths[0].clone();
+    let duplicates: Vec<(PathBuf, u64)> = members
+      .iter()
+      .flat_map(|&i| {
+        let bytes = groups[i].bytes;
+        groups[i].paths.iter().map(move |p| (p.clone(), bytes))
+      })
+      .filter(|(p, _)| p != &canonical)
+      .collect();
+    if duplicates.is_empty() {
+      continue;
+    }
+
+    for (path, _) in &duplicates {
+      replacements.insert(path.clone(), canonical.clone());
+    }
+    report_groups.push(DuplicateGroup {
+      kind: if members.len() > 1 {
+        DuplicateKind::Near
+      } else {
+        DuplicateKind::Exact
+      },
+      canonical: relative_display(&canonical, &workspace_path),
+      duplicates: duplicates
+        .iter()
+        .map(|(p, _)| relative_display(p, &workspace_path))
+        .collect(),
+      reclaimable_bytes: duplicates.iter().map(|(_, bytes)| bytes).sum(),
+    });
+  }
+
+  // 4. 先改写引用再删除副本，避免留下失效引用
+  let mut updated_documents = Vec::new();
+  if !replacements.is_empty() {
+    updated_documents = rewrite_workspace_references(
+      &workspace_path,
+      &files,
+      dry_run,
+      |_, target, document_dir| {
+        replacements
+          .get(target)
+          .map(|canonical| relative_reference(document_dir, canonical))
+      },
+    )?;
+    if !dry_run {
+      for duplicate in replacements.keys() {
+        if let Err(e) = fs::remove_file(duplicate) {
+          tracing::warn!("删除重复图片失败 {}: {}", duplicate.display(), e);
+        }
+      }
+    }
+  }
+
+  Ok(ImageDedupeReport {
+    dry_run,
+    scanned_count: images.len(),
+    reclaimed_bytes: report_groups.iter().map(|g| g.reclaimable_bytes).sum(),
+    groups: report_groups,
+    updated_documents,
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn difference_hash_tolerates_resizing() {
+    let gradient = image::RgbImage::from_fn(64, 48, |x, y| {
+      image::Rgb([(x * 4) as u8, (y * 5) as u8, ((x + y) * 2) as u8])
+    });
+    let original = DynamicImage::ImageRgb8(gradient);
+    let resized = original.resize_exact(32, 24, image::imageops::FilterType::Lanczos3);
+    let flipped = original.fliph();
+
+    let (a, b, c) = (
+      difference_hash(&original),
+      difference_hash(&resized),
+      difference_hash(&flipped),
+    );
+    assert!((a ^ b).count_ones() <= DEFAULT_NEAR_THRESHOLD);
+    assert!((a ^ c).count_ones() > DEFAULT_NEAR_THRESHOLD);
+  }
+}
@@ -0,0 +1,242 @@
+//! 图片元数据清理：移除 EXIF（含 GPS、相机信息）、XMP、文本注释等，不重新编码像素数据。
+//!
+//! 支持 JPEG / PNG / WebP。JPEG 的 EXIF 方向标记会保留为一个最小 EXIF 段，避免照片显示时被转回横向。
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetadataStripResult {
+  pub path: String,
+  /// 是否移除了元数据（格式不支持或本就没有元数据时为 false）
+  pub stripped: bool,
+  pub original_bytes: u64,
+  pub final_bytes: u64,
+}
+
+/// 清理图片元数据；格式不支持、数据无法解析或没有可移除的元数据时返回 None
+pub fn strip_metadata(data: &[u8]) -> Option<Vec<u8>> {
+  if data.starts_with(&[0xFF, 0xD8]) {
+    strip_jpeg(data)
+  } else if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+    strip_png(data)
+  } else if data.len() >= 12 && &data[0..4] == b"RIFF" && &data[8..12] == b"WEBP" {
+    strip_webp(data)
+  } else {
+    None
+  }
+}
+
+/// 原地清理图片文件的元数据
+pub fn strip_metadata_file(path: &Path) -> Result<MetadataStripResult, String> {
+  let data = std::fs::read(path).map_err(|e| format!("读取图片失败: {}", e))?;
+  let stripped = strip_metadata(&data);
+  if let Some(stripped) = &stripped {
+    std::fs::write(path, stripped).map_err(|e| format!("写入图片失败: {}", e))?;
+  }
+  Ok(MetadataStripResult {
+    path: path.to_string_lossy().to_string(),
+    stripped: stripped.is_some(),
+    original_bytes: data.len() as u64,
+    final_bytes: stripped.as_ref().map_or(data.len(), Vec::len) as u64,
+  })
+}
+
+/// 移除 APP1（EXIF / XMP）、APP13（IPTC）与 COM 段，扫描数据（SOS 之后）原样保留
+fn strip_jpeg(data: &[u8]) -> Option<Vec<u8>> {
+  let mut out = vec![0xFF, 0xD8];
+  let mut pos = 2;
+  let mut orientation = None;
+  let mut changed = false;
+  loop {
+    if pos + 2 > data.len() || data[pos] != 0xFF {
+      return None;
+    }
+    let marker = data[pos + 1];
+    match marker {
+      // 填充字节
+      0xFF => {
+        pos += 1;
+        continue;
+      }
+      // SOS / EOI：之后是压缩数据
+      0xDA | 0xD9 => {
+        out.extend_from_slice(&data[pos..]);
+        break;
+      }
+      // 无长度字段的独立标记
+      0x01 | 0xD0..=0xD7 => {
+        out.extend_from_slice(&data[pos..pos + 2]);
+        pos += 2;
+        continue;
+      }
+      _ => {}
+    }
+
+    let len = u16::from_be_bytes([*data.get(pos + 2)?, *data.get(pos + 3)?]) as usize;
+    let segment_end = pos + 2 + len;
+    if len < 2 || segment_end > data.len() {
+      return None;
+    }
+    let payload = &data[pos + 4..segment_end];
+    match marker {
+      0xE1 | 0xED | 0xFE => {
+        if marker == 0xE1 && payload.starts_with(b"Exif\0\0") {
+          orientation = exif_orientation(&payload[6..]).or(orientation);
+        }
+        changed = true;
+      }
+      _ => out.extend_from_slice(&data[pos..segment_end]),
+    }
+    pos = segment_end;
+  }
+
+  if !changed {
+    return None;
+  }
+  if let Some(orientation) = orientation.filter(|&o| o != 1) {
+    out.splice(2..2, orientation_segment(orientation));
+  }
+  Some(out)
+}
+
+/// 读取 TIFF 结构中 IFD0 的方向标记（0x0112）
+fn exif_orientation(tiff: &[u8]) -> Option<u16> {
+  let little_endian = match tiff.get(0..2)? {
+    b"II" => true,
+    b"MM" => false,
+    _ => return None,
+  };
+  let read_u16 = |offset: usize| {
+    let bytes = [*tiff.get(offset)?, *tiff.get(offset + 1)?];
+    Some(if little_endian {
+      u16::from_le_bytes(bytes)
+    } else {
+      u16::from_be_bytes(bytes)
+    })
+  };
+  let ifd = {
+    let bytes: [u8; 4] = tiff.get(4..8)?.try_into().ok()?;
+    if little_endian {
+      u32::from_le_bytes(bytes)
+    } else {
+      u32::from_be_bytes(bytes)
+    }
+  } as usize;
+
+  let count = read_u16(ifd)? as usize;
+  (0..count)
+    .map(|i| ifd + 2 + i * 12)
+    .find(|&entry| read_u16(entry) == Some(0x0112))
+    .and_then(|entry| read_u16(entry + 8))
+}
+
+/// 只含方向标记的最小 EXIF 段
+fn orientation_segment(orientation: u16) -> Vec<u8> {
+  let mut payload = b"Exif\0\0MM\0\x2a\0\0\0\x08".to_vec();
+  payload.extend_from_slice(&[0x00, 0x01, 0x01, 0x12, 0x00, 0x03, 0x00, 0x00, 0x00, 0x01]);
+  payload.extend_from_slice(&orientation.to_be_bytes());
+  payload.extend_from_slice(&[0, 0, 0, 0, 0, 0]);
+
+  let mut segment = vec![0xFF, 0xE1];
+  segment.extend_from_slice(&((payload.len() + 2) as u16).to_be_bytes());
+  segment.extend_from_slice(&payload);
+  segment
+}
+
+/// 移除 eXIf 与文本类辅助块（tEXt / zTXt / iTXt / tIME）
+fn strip_png(data: &[u8]) -> Option<Vec<u8>> {
+  const REMOVED: [&[u8; 4]; 5] = [b"eXIf", b"tEXt", b"zTXt", b"iTXt", b"tIME"];
+  let mut out = data[..8].to_vec();
+  let mut pos = 8;
+  let mut changed = false;
+  while pos < data.len() {
+    let len = u32::from_be_bytes(data.get(pos..pos + 4)?.try_into().ok()?) as usize;
+    let chunk_end = pos + 12 + len;
+    let chunk_type = data.get(pos + 4..pos + 8)?;
+    if chunk_end > data.len() {
+      return None;
+    }
+    if REMOVED.iter().any(|t| &t[..] == chunk_type) {
+      changed = true;
+    } else {
+      out.extend_from_slice(&data[pos..chunk_end]);
+    }
+    pos = chunk_end;
+  }
+  changed.then_some(out)
+}
+
+/// 移除 EXIF / XMP 块，同时清除 VP8X 头中的对应标志位并更新 RIFF 长度
+fn strip_webp(data: &[u8]) -> Option<Vec<u8>> {
+  let mut out = data[..12].to_vec();
+  let mut pos = 12;
+  let mut changed = false;
+  while pos < data.len() {
+    let fourcc = data.get(pos..pos + 4)?;
+    let len = u32::from_le_bytes(data.get(pos + 4..pos + 8)?.try_into().ok()?) as usize;
+    // 块数据按偶数字节对齐
+    let chunk_end = (pos + 8 + len + (len & 1)).min(data.len());
+    if pos + 8 + len > data.len() {
+      return None;
+    }
+    match fourcc {
+      b"EXIF" | b"XMP " => changed = true,
+      b"VP8X" => {
+        let start = out.len();
+        out.extend_from_slice(&data[pos..chunk_end]);
+        if let Some(flags) = out.get_mut(start + 8) {
+          *flags &= !(0x08 | 0x04);
+        }
+      }
+      _ => out.extend_from_slice(&data[pos..chunk_end]),
+    }
+    pos = chunk_end;
+  }
+
+  if !changed {
+    return None;
+  }
+  let riff_size = (out.len() - 8) as u32;
+  out[4..8].copy_from_slice(&riff_size.to_le_bytes());
+  Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn strips_exif_but_keeps_jpeg_orientation() {
+    let img = image::DynamicImage::ImageRgb8(image::RgbImage::new(8, 4));
+    let mut jpeg = std::io::Cursor::new(Vec::new());
+    img.write_to(&mut jpeg, image::ImageFormat::Jpeg).unwrap();
+    let jpeg = jpeg.into_inner();
+
+    // 小端 EXIF：Orientation = 6，另带一个 GPS IFD 指针
+    let mut exif = b"Exif\0\0II\x2a\0\x08\0\0\0\x02\0".to_vec();
+    exif.extend_from_slice(&[
+      0x12, 0x01, 0x03, 0x00, 0x01, 0x00, 0x00, 0x00, 0x06, 0x00, 0x00, 0x00,
+    ]);
+    exif.extend_from_slice(&[
+      0x25, 0x88, 0x04, 0x00, 0x01, 0x00, 0x00, 0x00, 0x26, 0x00, 0x00, 0x00,
+    ]);
+    exif.extend_from_slice(&[0, 0, 0, 0]);
+    let mut tagged = jpeg[..2].to_vec();
+    tagged.extend_from_slice(&[0xFF, 0xE1]);
+    tagged.extend_from_slice(&((exif.len() + 2) as u16).to_be_bytes());
+    tagged.extend_from_slice(&exif);
+    tagged.extend_from_slice(&jpeg[2..]);
+
+    let stripped = strip_metadata(&tagged).unwrap();
+    let mut expected = jpeg[..2].to_vec();
+    expected.extend_from_slice(&orientation_segment(6));
+    expected.extend_from_slice(&jpeg[2..]);
+    assert_eq!(stripped, expected);
+    assert_eq!(exif_orientation(&orientation_segment(6)[10..]), Some(6));
+    assert!(image::load_from_memory(&stripped).is_ok());
+
+    // 没有元数据时不改动
+    assert!(strip_metadata(&jpeg).is_none());
+  }
+}
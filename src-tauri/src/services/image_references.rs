@@ -0,0 +1,165 @@
+//! 工作区图片引用：遍历工作区文件，解析并改写 Markdown / HTML 文档中的图片路径。
+//!
+//! 供图片格式转换、图片去重等会移动或删除图片的批量操作复用。
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use walkdir::WalkDir;
+
+/// 会被检查图片引用的文档类型
+pub const DOCUMENT_EXTENSIONS: [&str; 4] = ["md", "markdown", "html", "htm"];
+
+/// Markdown `](path)` 与 HTML `src="path"` / `href="path"` 中的路径
+static IMAGE_REFERENCE: Lazy<Regex> =
+  Lazy::new(|| Regex::new(r#"(\]\(\s*<?|(?:src|href)\s*=\s*["'])([^)"'\s>]+)"#).unwrap());
+
+/// 相对工作区根目录的显示路径（统一使用 `/`）
+pub fn relative_display(path: &Path, workspace_path: &Path) -> String {
+  path
+    .strip_prefix(workspace_path)
+    .unwrap_or(path)
+    .to_string_lossy()
+    .replace('\\', "/")
+}
+
+/// 按路径组件归一化（处理 `.` 与 `..`），不访问文件系统
+pub fn normalize_lexically(path: &Path) -> PathBuf {
+  let mut normalized = PathBuf::new();
+  for component in path.components() {
+    match component {
+      Component::CurDir => {}
+      Component::ParentDir => {
+        normalized.pop();
+      }
+      other => normalized.push(other),
+    }
+  }
+  normalized
+}
+
+/// 从 from_dir 指向 target 的相对引用路径（如 `../assets/a.png`）
+pub fn relative_reference(from_dir: &Path, target: &Path) -> String {
+  let from: Vec<Component> = from_dir.components().collect();
+  let to: Vec<Component> = target.components().collect();
+  let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+  std::iter::repeat("..".to_string())
+    .take(from.len() - common)
+    .chain(
+      to[common..]
+        .iter()
+        .map(|c| c.as_os_str().to_string_lossy().to_string()),
+    )
+    .collect::<Vec<_>>()
+    .join("/")
+}
+
+/// 遍历工作区文件，跳过隐藏目录（如 `.binder`、`.git`）与 node_modules
+pub fn workspace_files(workspace_path: &Path) -> impl Iterator<Item = PathBuf> {
+  WalkDir::new(workspace_path)
+    .follow_links(false)
+    .into_iter()
+    .filter_entry(|e| {
+      e.depth() == 0 || {
+        let name = e.file_name().to_string_lossy();
+        !name.starts_with('.') && name != "node_modules"
+      }
+    })
+    .filter_map(|e| e.ok())
+    .filter(|e| e.file_type().is_file())
+    .map(|e| e.into_path())
+}
+
+pub fn has_extension(path: &Path, extensions: &[&str]) -> bool {
+  path
+    .extension()
+    .and_then(|e| e.to_str())
+    .is_some_and(|ext| extensions.iter().any(|x| ext.eq_ignore_ascii_case(x)))
+}
+
+/// 改写文档中的本地图片引用；没有命中时返回 None
+///
+/// `replacement` 接收原引用文本和归一化后的目标绝对路径，返回新的引用文本。
+pub fn rewrite_image_references(
+  content: &str,
+  document_dir: &Path,
+  replacement: impl Fn(&str, &Path) -> Option<String>,
+) -> Option<String> {
+  let mut changed = false;
+  let rewritten = IMAGE_REFERENCE.replace_all(content, |cap: &regex::Captures| {
+    let reference = &cap[2];
+    if reference.contains("://") || reference.starts_with("data:") {
+      return cap[0].to_string();
+    }
+    let target = normalize_lexically(&document_dir.join(reference));
+    match replacement(reference, &target) {
+      Some(new_reference) => {
+        changed = true;
+        format!("{}{}", &cap[1], new_reference)
+      }
+      None => cap[0].to_string(),
+    }
+  });
+  changed.then(|| rewritten.into_owned())
+}
+
+/// 对 files 中的全部文档执行引用改写，返回（将要）更新的文档相对路径；dry_run 时不写盘
+///
+/// `replacement` 额外接收文档所在目录，便于生成相对引用。
+pub fn rewrite_workspace_references(
+  workspace_path: &Path,
+  files: &[PathBuf],
+  dry_run: bool,
+  replacement: impl Fn(&str, &Path, &Path) -> Option<String>,
+) -> Result<Vec<String>, String> {
+  let mut updated_documents = Vec::new();
+  for doc in files
+    .iter()
+    .filter(|p| has_extension(p, &DOCUMENT_EXTENSIONS))
+  {
+    let Ok(content) = fs::read_to_string(doc) else {
+      continue;
+    };
+    let document_dir = doc.parent().unwrap_or(workspace_path);
+    let rewritten = rewrite_image_references(&content, document_dir, |reference, target| {
+      replacement(reference, target, document_dir)
+    });
+    if let Some(rewritten) = rewritten {
+      if !dry_run {
+        fs::write(doc, rewritten)
+          .map_err(|e| format!("更新文档引用失败 {}: {}", doc.display(), e))?;
+      }
+      updated_documents.push(relative_display(doc, workspace_path));
+    }
+  }
+  Ok(updated_documents)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn builds_relative_references_between_folders() {
+    let root = PathBuf::from("/ws");
+    assert_eq!(
+      relative_reference(&root.join("docs/guide"), &root.join("assets/a.png")),
+      "../../assets/a.png"
+    );
+    assert_eq!(
+      relative_reference(&root.join("docs"), &root.join("docs/assets/a.png")),
+      "assets/a.png"
+    );
+
+    let rewritten = rewrite_image_references(
+      "![x](./assets/a.png) ![y](https://x/a.png) ![z](assets/b.png)",
+      &root.join("docs"),
+      |_, target| (target == root.join("docs/assets/a.png")).then(|| "../shared/a.png".to_string()),
+    );
+    assert_eq!(
+      rewritten.as_deref(),
+      Some("![x](../shared/a.png) ![y](https://x/a.png) ![z](assets/b.png)")
+    );
+  }
+}
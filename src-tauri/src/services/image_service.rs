@@ -1,5 +1,8 @@
+use crate::services::image_metadata::{strip_metadata, strip_metadata_file};
+use crate::services::svg_sanitizer::{is_svg_path, sanitize_svg};
+use crate::services::workspace_settings::ImageSettings;
 use base64::{engine::general_purpose, Engine as _};
-use image::{DynamicImage, ImageFormat};
+use image::{DynamicImage, GenericImageView, ImageEncoder, ImageFormat};
 use serde::{Deserialize, Serialize};
 use std::path::Path;
 use uuid::Uuid;
@@ -8,6 +11,86 @@ use uuid::Uuid;
 pub struct InsertImageResult {
   pub data_url: String,
   pub relative_path: String,
+  /// 开启插入优化时的处理结果
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub optimization: Option<ImageOptimizationReport>,
+}
+
+/// 插入图片优化报告
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImageOptimizationReport {
+  pub original_bytes: u64,
+  pub final_bytes: u64,
+  pub saved_bytes: u64,
+  pub original_width: u32,
+  pub original_height: u32,
+  pub width: u32,
+  pub height: u32,
+}
+
+/// 重新编码后的图片数据
+pub struct OptimizedImage {
+  pub data: Vec<u8>,
+  pub original_size: (u32, u32),
+  pub size: (u32, u32),
+}
+
+/// 缩放（长边不超过 max_dimension）并按原格式重新编码
+///
+/// 仅处理 JPEG / PNG / WebP；GIF（可能含动画）、SVG 等返回 None
+pub fn optimize_image_bytes(
+  data: &[u8],
+  format: ImageFormat,
+  settings: &ImageSettings,
+) -> Result<Option<OptimizedImage>, String> {
+  if !matches!(
+    format,
+    ImageFormat::Jpeg | ImageFormat::Png | ImageFormat::WebP
+  ) {
+    return Ok(None);
+  }
+
+  let mut img = image::load_from_memory_with_format(data, format)
+    .map_err(|e| format!("解码图片失败: {}", e))?;
+  let original_size = img.dimensions();
+  if original_size.0.max(original_size.1) > settings.max_dimension {
+    img = img.resize(
+      settings.max_dimension,
+      settings.max_dimension,
+      image::imageops::FilterType::Lanczos3,
+    );
+  }
+  let (width, height) = img.dimensions();
+
+  let mut encoded = Vec::new();
+  match format {
+    ImageFormat::Jpeg => {
+      let rgb = img.to_rgb8();
+      image::codecs::jpeg::JpegEncoder::new_with_quality(&mut encoded, settings.quality)
+        .write_image(rgb.as_raw(), width, height, image::ColorType::Rgb8)
+        .map_err(|e| format!("编码 JPEG 失败: {}", e))?;
+    }
+    ImageFormat::Png => {
+      image::codecs::png::PngEncoder::new_with_quality(
+        &mut encoded,
+        image::codecs::png::CompressionType::Best,
+        image::codecs::png::FilterType::Adaptive,
+      )
+      .write_image(img.as_bytes(), width, height, img.color())
+      .map_err(|e| format!("编码 PNG 失败: {}", e))?;
+    }
+    _ => {
+      let rgba = img.to_rgba8();
+      encoded = webp::Encoder::from_rgba(rgba.as_raw(), width, height)
+        .encode(settings.quality as f32)
+        .to_vec();
+    }
+  }
+  Ok(Some(OptimizedImage {
+    data: encoded,
+    original_size,
+    size: (width, height),
+  }))
 }
 
 pub struct ImageService;
@@ -21,7 +104,12 @@ impl ImageService {
     &self,
     document_path: &Path,
     image_source: &Path,
+    settings: &ImageSettings,
   ) -> Result<InsertImageResult, String> {
+    if is_svg_path(image_source) && !settings.allow_svg {
+      return Err("当前工作区设置不允许插入 SVG 图片".to_string());
+    }
+
     // 1. 确定 assets/ 文件夹路径
     let assets_dir = document_path
       .parent()
@@ -41,8 +129,26 @@ impl ImageService {
     let filename = format!("{}.{}", Uuid::new_v4(), ext);
     let dest_path = assets_dir.join(&filename);
 
-    // 4. 复制图片文件
-    std::fs::copy(image_source, &dest_path).map_err(|e| format!("复制图片失败: {}", e))?;
+    // 4. 复制图片文件（开启优化时缩放并重新编码，结果不比原图小则保留原图）
+    let is_svg = is_svg_path(image_source);
+    let optimization = if is_svg {
+      // SVG 写入清理后的内容，不做位图优化
+      let svg =
+        std::fs::read_to_string(image_source).map_err(|e| format!("读取图片失败: {}", e))?;
+      std::fs::write(&dest_path, sanitize_svg(&svg)?)
+        .map_err(|e| format!("写入图片失败: {}", e))?;
+      None
+    } else if settings.optimize_on_insert {
+      self.write_optimized_image(image_source, &dest_path, settings)?
+    } else {
+      None
+    };
+    if optimization.is_none() && !is_svg {
+      std::fs::copy(image_source, &dest_path).map_err(|e| format!("复制图片失败: {}", e))?;
+      if settings.strip_metadata_on_insert {
+        strip_metadata_file(&dest_path)?;
+      }
+    }
 
     // 5. 处理图片（压缩 + base64 转换）
     let metadata =
@@ -77,9 +183,47 @@ impl ImageService {
     Ok(InsertImageResult {
       data_url,
       relative_path: format!("assets/{}", filename),
+      optimization,
     })
   }
 
+  /// 优化后写入 dest_path；格式不支持或优化后未变小时返回 None（由调用方原样复制）
+  fn write_optimized_image(
+    &self,
+    image_source: &Path,
+    dest_path: &Path,
+    settings: &ImageSettings,
+  ) -> Result<Option<ImageOptimizationReport>, String> {
+    let Ok(format) = ImageFormat::from_path(image_source) else {
+      return Ok(None);
+    };
+    let original = std::fs::read(image_source).map_err(|e| format!("读取图片失败: {}", e))?;
+
+    let optimized = match optimize_image_bytes(&original, format, settings) {
+      Ok(Some(optimized)) => optimized,
+      Ok(None) => return Ok(None),
+      Err(e) => {
+        tracing::warn!("图片优化失败，按原图插入: {}", e);
+        return Ok(None);
+      }
+    };
+    if optimized.data.len() >= original.len() {
+      return Ok(None);
+    }
+
+    std::fs::write(dest_path, &optimized.data).map_err(|e| format!("写入图片失败: {}", e))?;
+    let (original_bytes, final_bytes) = (original.len() as u64, optimized.data.len() as u64);
+    Ok(Some(ImageOptimizationReport {
+      original_bytes,
+      final_bytes,
+      saved_bytes: original_bytes - final_bytes,
+      original_width: optimized.original_size.0,
+      original_height: optimized.original_size.1,
+      width: optimized.size.0,
+      height: optimized.size.1,
+    }))
+  }
+
   pub fn check_image_exists(&self, document_path: &Path, image_path: &str) -> bool {
     let assets_dir = document_path.parent().unwrap().join("assets");
     let image_file = assets_dir.join(image_path.strip_prefix("assets/").unwrap_or(image_path));
@@ -106,6 +250,7 @@ impl ImageService {
     workspace_path: &Path,
     image_data: Vec<u8>,
     file_name: String,
+    settings: &ImageSettings,
   ) -> Result<String, String> {
     // 1. 确定 assets/ 文件夹路径（在工作区根目录）
     let assets_dir = workspace_path.join("assets");
@@ -135,7 +280,19 @@ impl ImageService {
     let filename = format!("chat-{}-{}", timestamp, sanitized_name);
     let dest_path = assets_dir.join(&filename);
 
-    // 4. 保存图片
+    // 4. 保存图片（SVG 先清理脚本与外部引用；位图按设置移除 EXIF 等元数据）
+    let image_data = if is_svg_path(&dest_path) {
+      if !settings.allow_svg {
+        return Err("当前工作区设置不允许插入 SVG 图片".to_string());
+      }
+      let svg =
+        String::from_utf8(image_data).map_err(|_| "SVG 不是有效的 UTF-8 文本".to_string())?;
+      sanitize_svg(&svg)?.into_bytes()
+    } else if settings.strip_metadata_on_insert {
+      strip_metadata(&image_data).unwrap_or(image_data)
+    } else {
+      image_data
+    };
     std::fs::write(&dest_path, image_data).map_err(|e| format!("保存图片失败: {}", e))?;
 
     // 5. 返回相对路径（assets/xxx.png）
@@ -167,10 +324,21 @@ impl ImageService {
 
     let file_size = metadata.len();
 
+    // SVG 只嵌入清理后的内容
+    let sanitized_svg = if is_svg_path(image_path) {
+      let svg = std::fs::read_to_string(image_path).map_err(|e| format!("读取图片失败: {}", e))?;
+      Some(sanitize_svg(&svg)?)
+    } else {
+      None
+    };
+
     // 3. 小图片使用 base64，大图片使用绝对路径
     if file_size < 1024 * 1024 {
       // 小于 1MB，使用 base64
-      let img_data = std::fs::read(image_path).map_err(|e| format!("读取图片失败: {}", e))?;
+      let img_data = match sanitized_svg {
+        Some(svg) => svg.into_bytes(),
+        None => std::fs::read(image_path).map_err(|e| format!("读取图片失败: {}", e))?,
+      };
 
       // 检测图片格式
       let mime_type = self.detect_image_mime_type(image_path)?;
@@ -187,7 +355,9 @@ impl ImageService {
       let dest_path = media_dir.join(file_name);
 
       // 如果目标文件已存在，跳过复制
-      if !dest_path.exists() {
+      if let Some(svg) = sanitized_svg {
+        std::fs::write(&dest_path, svg).map_err(|e| format!("写入图片失败: {}", e))?;
+      } else if !dest_path.exists() {
         std::fs::copy(image_path, &dest_path).map_err(|e| format!("复制图片失败: {}", e))?;
       }
 
@@ -229,39 +399,32 @@ impl ImageService {
     max_size_kb: usize,
     quality: u8,
   ) -> Result<Vec<u8>, String> {
-    eprintln!("  🔧 [压缩] 开始压缩图片: {:?}", image_path);
-    eprintln!(
-      "  🔧 [压缩] 目标大小: {} KB, 质量: {}",
-      max_size_kb, quality
-    );
+    tracing::debug!("  [压缩] 开始压缩图片: {:?}", image_path);
+    tracing::debug!("  [压缩] 目标大小: {} KB, 质量: {}", max_size_kb, quality);
 
     // 1. 读取图片
-    eprintln!("  🔧 [压缩] 步骤 1: 读取图片...");
+    tracing::debug!("  [压缩] 步骤 1: 读取图片...");
     let mut img = match image::open(image_path) {
       Ok(img) => {
-        eprintln!(
-          "  🔧 [压缩] 读取成功，尺寸: {}x{}",
-          img.width(),
-          img.height()
-        );
+        tracing::debug!("  [压缩] 读取成功，尺寸: {}x{}", img.width(), img.height());
         img
       }
       Err(e) => {
-        eprintln!("  ❌ [压缩] 读取图片失败: {}", e);
+        tracing::error!("  [压缩] 读取图片失败: {}", e);
         return Err(format!("读取图片失败: {}", e));
       }
     };
 
     // 2. 检测格式
-    eprintln!("  🔧 [压缩] 步骤 2: 检测格式...");
+    tracing::debug!("  [压缩] 步骤 2: 检测格式...");
     let format = ImageFormat::from_path(image_path).unwrap_or(ImageFormat::Png);
-    eprintln!("  🔧 [压缩] 检测到的格式: {:?}", format);
+    tracing::debug!("  [压缩] 检测到的格式: {:?}", format);
 
     // 3. 尺寸限制（如果图片过大）
     if img.width() > 2000 || img.height() > 2000 {
-      eprintln!("  🔧 [压缩] 步骤 3: 图片过大，缩小尺寸...");
+      tracing::debug!("  [压缩] 步骤 3: 图片过大，缩小尺寸...");
       let scale = 2000.0 / img.width().max(img.height()) as f32;
-      eprintln!("  🔧 [压缩] 缩放比例: {}", scale);
+      tracing::debug!("  [压缩] 缩放比例: {}", scale);
       img = match std::panic::catch_unwind(|| {
         img.resize(
           (img.width() as f32 * scale) as u32,
@@ -270,61 +433,61 @@ impl ImageService {
         )
       }) {
         Ok(resized) => {
-          eprintln!(
-            "  🔧 [压缩] 缩小成功，新尺寸: {}x{}",
+          tracing::debug!(
+            "  [压缩] 缩小成功，新尺寸: {}x{}",
             resized.width(),
             resized.height()
           );
           resized
         }
         Err(e) => {
-          eprintln!("  ❌ [压缩] 缩小尺寸 panic: {:?}", e);
+          tracing::error!("  [压缩] 缩小尺寸 panic: {:?}", e);
           return Err("缩小图片尺寸失败（panic）".to_string());
         }
       };
     } else {
-      eprintln!("  🔧 [压缩] 步骤 3: 尺寸无需缩小");
+      tracing::debug!("  [压缩] 步骤 3: 尺寸无需缩小");
     }
 
     // 4. 压缩策略（统一转换为 WebP）
-    eprintln!("  🔧 [压缩] 步骤 4: 转换为 WebP...");
+    tracing::debug!("  [压缩] 步骤 4: 转换为 WebP...");
     let mut compressed = match self.encode_to_webp(&img, quality) {
       Ok(data) => {
-        eprintln!("  🔧 [压缩] WebP 编码成功，大小: {} 字节", data.len());
+        tracing::debug!("  [压缩] WebP 编码成功，大小: {} 字节", data.len());
         data
       }
       Err(e) => {
-        eprintln!("  ❌ [压缩] WebP 编码失败: {}", e);
+        tracing::error!("  [压缩] WebP 编码失败: {}", e);
         return Err(e);
       }
     };
 
     // 5. 验证大小，如果超过限制则降级重试
     if compressed.len() > max_size_kb * 1024 {
-      eprintln!("  🔧 [压缩] 步骤 5: 压缩后仍超过限制，开始降级重试...");
+      tracing::debug!("  [压缩] 步骤 5: 压缩后仍超过限制，开始降级重试...");
       let mut current_quality = quality;
       let mut current_img = img;
 
       for attempt in 1..=3 {
-        eprintln!(
-          "  🔧 [压缩] 降级尝试 {}: 当前大小 {} KB, 目标 {} KB",
+        tracing::debug!(
+          "  [压缩] 降级尝试 {}: 当前大小 {} KB, 目标 {} KB",
           attempt,
           compressed.len() / 1024,
           max_size_kb
         );
 
         if compressed.len() <= max_size_kb * 1024 {
-          eprintln!("  🔧 [压缩] 降级成功，达到目标大小");
+          tracing::debug!("  [压缩] 降级成功，达到目标大小");
           break;
         }
 
         // 降低质量
         current_quality = current_quality.saturating_sub(10);
-        eprintln!("  🔧 [压缩] 降低质量: {}", current_quality);
+        tracing::debug!("  [压缩] 降低质量: {}", current_quality);
 
         if current_quality < 50 {
           // 如果质量太低，尝试缩小尺寸
-          eprintln!("  🔧 [压缩] 质量过低，缩小尺寸...");
+          tracing::debug!("  [压缩] 质量过低，缩小尺寸...");
           let scale = 0.8;
           current_img = match std::panic::catch_unwind(|| {
             current_img.resize(
@@ -334,35 +497,35 @@ impl ImageService {
             )
           }) {
             Ok(resized) => {
-              eprintln!(
-                "  🔧 [压缩] 缩小成功，新尺寸: {}x{}",
+              tracing::debug!(
+                "  [压缩] 缩小成功，新尺寸: {}x{}",
                 resized.width(),
                 resized.height()
               );
               resized
             }
             Err(e) => {
-              eprintln!("  ❌ [压缩] 缩小尺寸 panic: {:?}", e);
+              tracing::error!("  [压缩] 缩小尺寸 panic: {:?}", e);
               return Err("缩小图片尺寸失败（panic）".to_string());
             }
           };
           current_quality = 75; // 重置质量
-          eprintln!("  🔧 [压缩] 重置质量: {}", current_quality);
+          tracing::debug!("  [压缩] 重置质量: {}", current_quality);
         }
 
         compressed = match self.encode_to_webp(&current_img, current_quality) {
           Ok(data) => {
-            eprintln!("  🔧 [压缩] 重新编码成功，大小: {} 字节", data.len());
+            tracing::debug!("  [压缩] 重新编码成功，大小: {} 字节", data.len());
             data
           }
           Err(e) => {
-            eprintln!("  ❌ [压缩] 重新编码失败: {}", e);
+            tracing::error!("  [压缩] 重新编码失败: {}", e);
             return Err(e);
           }
         };
 
         if attempt == 3 && compressed.len() > max_size_kb * 1024 {
-          eprintln!("  ❌ [压缩] 3次降级后仍超过限制");
+          tracing::error!("  [压缩] 3次降级后仍超过限制");
           return Err(format!(
             "图片压缩后仍超过限制 ({}KB > {}KB)，请使用较小的图片",
             compressed.len() / 1024,
@@ -371,11 +534,11 @@ impl ImageService {
         }
       }
     } else {
-      eprintln!("  🔧 [压缩] 步骤 5: 压缩后大小符合要求");
+      tracing::debug!("  [压缩] 步骤 5: 压缩后大小符合要求");
     }
 
-    eprintln!(
-      "  ✅ [压缩] 压缩完成，最终大小: {} 字节 ({} KB)",
+    tracing::info!(
+      "  [压缩] 压缩完成，最终大小: {} 字节 ({} KB)",
       compressed.len(),
       compressed.len() / 1024
     );
@@ -386,57 +549,94 @@ impl ImageService {
   fn encode_to_webp(&self, img: &DynamicImage, quality: u8) -> Result<Vec<u8>, String> {
     use webp::Encoder;
 
-    eprintln!("    🎨 [WebP编码] 开始编码，质量: {}", quality);
+    tracing::debug!("    [WebP编码] 开始编码，质量: {}", quality);
 
     // 将 DynamicImage 转换为 RGBA
-    eprintln!("    🎨 [WebP编码] 转换为 RGBA...");
+    tracing::debug!("    [WebP编码] 转换为 RGBA...");
     let rgba = match std::panic::catch_unwind(|| img.to_rgba8()) {
       Ok(rgba) => {
-        eprintln!("    🎨 [WebP编码] RGBA 转换成功");
+        tracing::debug!("    [WebP编码] RGBA 转换成功");
         rgba
       }
       Err(e) => {
-        eprintln!("    ❌ [WebP编码] RGBA 转换 panic: {:?}", e);
+        tracing::error!("    [WebP编码] RGBA 转换 panic: {:?}", e);
         return Err("RGBA 转换失败（panic）".to_string());
       }
     };
 
     let (width, height) = rgba.dimensions();
-    eprintln!(
-      "    🎨 [WebP编码] 尺寸: {}x{}, RGBA 数据大小: {} 字节",
+    tracing::debug!(
+      "    [WebP编码] 尺寸: {}x{}, RGBA 数据大小: {} 字节",
       width,
       height,
       rgba.len()
     );
 
     // 使用 webp crate 进行有损编码
-    eprintln!("    🎨 [WebP编码] 创建编码器...");
+    tracing::debug!("    [WebP编码] 创建编码器...");
     let encoder =
       match std::panic::catch_unwind(|| Encoder::from_rgba(rgba.as_raw(), width, height)) {
         Ok(enc) => {
-          eprintln!("    🎨 [WebP编码] 编码器创建成功");
+          tracing::debug!("    [WebP编码] 编码器创建成功");
           enc
         }
         Err(e) => {
-          eprintln!("    ❌ [WebP编码] 编码器创建 panic: {:?}", e);
+          tracing::error!("    [WebP编码] 编码器创建 panic: {:?}", e);
           return Err("编码器创建失败（panic）".to_string());
         }
       };
 
-    eprintln!("    🎨 [WebP编码] 执行编码...");
+    tracing::debug!("    [WebP编码] 执行编码...");
     let webp = match std::panic::catch_unwind(|| encoder.encode(quality as f32)) {
       Ok(w) => {
-        eprintln!("    🎨 [WebP编码] 编码成功，大小: {} 字节", w.len());
+        tracing::debug!("    [WebP编码] 编码成功，大小: {} 字节", w.len());
         w
       }
       Err(e) => {
-        eprintln!("    ❌ [WebP编码] 编码 panic: {:?}", e);
+        tracing::error!("    [WebP编码] 编码 panic: {:?}", e);
         return Err("WebP 编码失败（panic）".to_string());
       }
     };
 
     let result = webp.to_vec();
-    eprintln!("    ✅ [WebP编码] 完成，最终大小: {} 字节", result.len());
+    tracing::info!("    [WebP编码] 完成，最终大小: {} 字节", result.len());
     Ok(result)
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn optimization_downscales_to_max_dimension() {
+    let img = DynamicImage::ImageRgb8(image::RgbImage::from_pixel(
+      800,
+      400,
+      image::Rgb([200, 120, 40]),
+    ));
+    let mut png = std::io::Cursor::new(Vec::new());
+    img.write_to(&mut png, ImageFormat::Png).unwrap();
+    let settings = ImageSettings {
+      optimize_on_insert: true,
+      max_dimension: 256,
+      quality: 80,
+      strip_metadata_on_insert: true,
+      allow_svg: true,
+    };
+
+    let optimized = optimize_image_bytes(png.get_ref(), ImageFormat::Png, &settings)
+      .unwrap()
+      .unwrap();
+    assert_eq!(optimized.original_size, (800, 400));
+    assert_eq!(optimized.size, (256, 128));
+    let decoded = image::load_from_memory(&optimized.data).unwrap();
+    assert_eq!(decoded.dimensions(), (256, 128));
+
+    assert!(
+      optimize_image_bytes(png.get_ref(), ImageFormat::Gif, &settings)
+        .unwrap()
+        .is_none()
+    );
+  }
+}
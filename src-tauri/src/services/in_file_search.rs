@@ -0,0 +1,796 @@
+use crate::utils::glob_patterns::relative_slash_path;
+use globset::GlobSet;
+use memmap2::Mmap;
+use regex::bytes::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// 目录扫描：跳过超过该大小的文件
+pub const SCAN_MAX_FILE_BYTES: u64 = 2 * 1024 * 1024;
+/// 目录扫描：最多检查的文件数
+pub const SCAN_MAX_FILES: usize = 5000;
+/// 目录扫描：每个文件最多返回的匹配数
+pub const SCAN_MATCHES_PER_FILE: usize = 5;
+
+/// 单文件搜索选项
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InFileSearchOptions {
+  /// 按正则表达式匹配（否则按字面量匹配）
+  pub regex: bool,
+  pub case_sensitive: bool,
+  pub whole_word: bool,
+  /// 最多返回的匹配数，超过后 `truncated = true`
+  pub max_results: usize,
+  /// 从该字节偏移开始搜索（配合分块阅读器“查找下一个”）
+  pub start_offset: u64,
+  /// 每条匹配附带的行预览最大字节数
+  pub preview_bytes: usize,
+}
+
+impl Default for InFileSearchOptions {
+  fn default() -> Self {
+    Self {
+      regex: false,
+      case_sensitive: false,
+      whole_word: false,
+      max_results: 1000,
+      start_offset: 0,
+      preview_bytes: 200,
+    }
+  }
+}
+
+/// 单个匹配：字节范围 + 行号（从 1 开始）+ 所在行预览
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InFileMatch {
+  pub start: u64,
+  pub end: u64,
+  pub line: u64,
+  pub column: u64,
+  pub preview: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InFileSearchResult {
+  pub path: String,
+  pub file_size: u64,
+  pub matches: Vec<InFileMatch>,
+  pub truncated: bool,
+}
+
+/// 目录扫描中的一条匹配（路径相对扫描根目录，使用 `/` 分隔）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DirectoryMatch {
+  pub path: String,
+  pub line: u64,
+  pub preview: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DirectorySearchResult {
+  pub matches: Vec<DirectoryMatch>,
+  pub files_scanned: usize,
+  /// 命中结果数、文件数上限之一时为 true
+  pub truncated: bool,
+}
+
+/// 单文件替换选项
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InFileReplaceOptions {
+  /// 按正则表达式匹配，替换文本中可用 `$1`、`${name}` 引用捕获组
+  pub regex: bool,
+  pub case_sensitive: bool,
+  pub whole_word: bool,
+  /// 只返回替换预览，不写文件
+  pub preview: bool,
+  /// 最多返回的片段数（替换本身不受限制）
+  pub max_hunks: usize,
+  /// 每个片段展示的原文窗口最大字节数（超长的单行只截取匹配附近）
+  pub preview_bytes: usize,
+  /// 预览时的匹配数；实际匹配数不同（文件已被修改）时拒绝替换
+  pub expected_replacements: Option<usize>,
+}
+
+impl Default for InFileReplaceOptions {
+  fn default() -> Self {
+    Self {
+      regex: false,
+      case_sensitive: false,
+      whole_word: false,
+      preview: false,
+      max_hunks: 200,
+      preview_bytes: 200,
+      expected_replacements: None,
+    }
+  }
+}
+
+/// 替换片段：匹配附近的原文窗口在替换前后的内容（窗口不跨行，同一窗口内的多处匹配合并为一个片段）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplaceHunk {
+  /// 起始行号（从 1 开始）
+  pub line: u64,
+  /// 窗口在原文件中的字节范围
+  pub start: u64,
+  pub end: u64,
+  pub replacements: usize,
+  pub before: String,
+  pub after: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InFileReplaceResult {
+  pub path: String,
+  /// 匹配（替换）总数
+  pub replacements: usize,
+  pub hunks: Vec<ReplaceHunk>,
+  /// 片段数超过 max_hunks，其余片段未返回
+  pub hunks_truncated: bool,
+  /// 已写回文件
+  pub written: bool,
+}
+
+pub struct InFileSearchService;
+
+impl InFileSearchService {
+  pub fn build_pattern(query: &str, options: &InFileSearchOptions) -> Result<Regex, String> {
+    if query.is_empty() {
+      return Err("搜索内容不能为空".to_string());
+    }
+    let mut pattern = if options.regex {
+      query.to_string()
+    } else {
+      regex::escape(query)
+    };
+    if options.whole_word {
+      pattern = format!(r"\b(?:{})\b", pattern);
+    }
+    RegexBuilder::new(&pattern)
+      .case_insensitive(!options.case_sensitive)
+      .multi_line(true)
+      .build()
+      .map_err(|e| format!("无效的搜索表达式: {}", e))
+  }
+
+  /// 使用内存映射扫描文件，不把整个文件读入内存
+  pub fn search_file(
+    path: &Path,
+    query: &str,
+    options: &InFileSearchOptions,
+  ) -> Result<InFileSearchResult, String> {
+    let pattern = Self::build_pattern(query, options)?;
+    let file = File::open(path).map_err(|e| format!("打开文件失败: {}", e))?;
+    let file_size = file
+      .metadata()
+      .map_err(|e| format!("获取文件信息失败: {}", e))?
+      .len();
+
+    if file_size == 0 {
+      return Ok(InFileSearchResult {
+        path: path.to_string_lossy().to_string(),
+        file_size,
+        matches: Vec::new(),
+        truncated: false,
+      });
+    }
+
+    // SAFETY: 只读映射；文件在搜索期间被外部截断属于未定义行为的已知限制，
+    // 与编辑器读取大文件的其它路径一致，搜索结果仅用于展示。
+    let mmap = unsafe { Mmap::map(&file) }.map_err(|e| format!("映射文件失败: {}", e))?;
+    let (matches, truncated) = Self::search_bytes(&mmap, &pattern, options);
+
+    Ok(InFileSearchResult {
+      path: path.to_string_lossy().to_string(),
+      file_size,
+      matches,
+      truncated,
+    })
+  }
+
+  pub fn search_bytes(
+    haystack: &[u8],
+    pattern: &Regex,
+    options: &InFileSearchOptions,
+  ) -> (Vec<InFileMatch>, bool) {
+    let start_offset = (options.start_offset as usize).min(haystack.len());
+    let mut matches = Vec::new();
+    let mut truncated = false;
+
+    // 行号与行首增量计算：只扫描上一个匹配到当前匹配之间的字节，整体为一遍扫描
+    let mut line = 1 + count_newlines(&haystack[..start_offset]) as u64;
+    let mut line_start = haystack[..start_offset]
+      .iter()
+      .rposition(|b| *b == b'\n')
+      .map_or(0, |i| i + 1);
+    let mut scanned_to = start_offset;
+
+    for m in pattern.find_iter(&haystack[start_offset..]) {
+      if matches.len() >= options.max_results {
+        truncated = true;
+        break;
+      }
+      let start = start_offset + m.start();
+      let end = start_offset + m.end();
+      let gap = &haystack[scanned_to..start];
+      line += count_newlines(gap) as u64;
+      if let Some(i) = gap.iter().rposition(|b| *b == b'\n') {
+        line_start = scanned_to + i + 1;
+      }
+      scanned_to = start;
+
+      // 预览只取匹配附近的窗口（匹配前至多 1/4 预算），单行的压缩文件也不会复制整行
+      let before = (options.preview_bytes / 4).min(start - line_start);
+      let preview_start = char_floor(haystack, start - before);
+      let limit = (preview_start + options.preview_bytes)
+        .max(start)
+        .min(haystack.len());
+      let preview_end = haystack[start..limit]
+        .iter()
+        .position(|b| *b == b'\n')
+        .map_or_else(|| char_floor(haystack, limit), |i| start + i);
+
+      matches.push(InFileMatch {
+        start: start as u64,
+        end: end as u64,
+        line,
+        column: (start - line_start) as u64,
+        preview: String::from_utf8_lossy(&haystack[preview_start..preview_end])
+          .trim_end_matches('\r')
+          .to_string(),
+      });
+    }
+
+    (matches, truncated)
+  }
+}
+
+impl InFileSearchService {
+  /// 递归扫描目录中的文本文件（ripgrep 风格）
+  ///
+  /// 跳过隐藏目录、node_modules、target、命中 `exclude` 的路径、超大文件和二进制文件；
+  /// `options.max_results` 为总匹配上限。
+  pub fn search_directory(
+    root: &Path,
+    dir: &Path,
+    query: &str,
+    options: &InFileSearchOptions,
+    exclude: Option<&GlobSet>,
+  ) -> Result<DirectorySearchResult, String> {
+    let pattern = Self::build_pattern(query, options)?;
+    let mut result = DirectorySearchResult {
+      matches: Vec::new(),
+      files_scanned: 0,
+      truncated: false,
+    };
+    let mut stack: Vec<PathBuf> = vec![dir.to_path_buf()];
+
+    while let Some(current) = stack.pop() {
+      let Ok(entries) = std::fs::read_dir(&current) else {
+        continue;
+      };
+      let mut entries: Vec<_> = entries.flatten().map(|e| e.path()).collect();
+      entries.sort();
+      let mut subdirs = Vec::new();
+      for path in entries {
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+        if name.starts_with('.') {
+          continue;
+        }
+        let relative = relative_slash_path(&path, root);
+        if exclude.is_some_and(|set| set.is_match(&relative)) {
+          continue;
+        }
+        if path.is_dir() {
+          if name != "node_modules" && name != "target" {
+            subdirs.push(path);
+          }
+          continue;
+        }
+        if result.files_scanned >= SCAN_MAX_FILES {
+          result.truncated = true;
+          return Ok(result);
+        }
+        let size = path.metadata().map(|m| m.len()).unwrap_or(0);
+        if size == 0 || size > SCAN_MAX_FILE_BYTES {
+          continue;
+        }
+        let Ok(bytes) = std::fs::read(&path) else {
+          continue;
+        };
+        result.files_scanned += 1;
+        if bytes[..bytes.len().min(8192)].contains(&0) {
+          continue; // 二进制文件
+        }
+
+        let remaining = options.max_results - result.matches.len();
+        let file_options = InFileSearchOptions {
+          max_results: remaining.min(SCAN_MATCHES_PER_FILE),
+          start_offset: 0,
+          ..options.clone()
+        };
+        let (matches, _) = Self::search_bytes(&bytes, &pattern, &file_options);
+        result
+          .matches
+          .extend(matches.into_iter().map(|m| DirectoryMatch {
+            path: relative.clone(),
+            line: m.line,
+            preview: m.preview.trim().to_string(),
+          }));
+        if result.matches.len() >= options.max_results {
+          result.truncated = true;
+          return Ok(result);
+        }
+      }
+      // 逆序入栈，保证子目录按名称顺序扫描
+      stack.extend(subdirs.into_iter().rev());
+    }
+    Ok(result)
+  }
+}
+
+impl InFileSearchService {
+  /// 在单个文件中查找并替换。preview 时只返回片段；否则把结果流式写入同目录的临时文件，
+  /// 成功后原子地替换原文件（保留文件权限），文件不必整体读入内存
+  pub fn replace_in_file(
+    path: &Path,
+    query: &str,
+    replacement: &str,
+    options: &InFileReplaceOptions,
+  ) -> Result<InFileReplaceResult, String> {
+    let search_options = InFileSearchOptions {
+      regex: options.regex,
+      case_sensitive: options.case_sensitive,
+      whole_word: options.whole_word,
+      ..InFileSearchOptions::default()
+    };
+    let pattern = Self::build_pattern(query, &search_options)?;
+    let file = File::open(path).map_err(|e| format!("打开文件失败: {}", e))?;
+    let metadata = file
+      .metadata()
+      .map_err(|e| format!("获取文件信息失败: {}", e))?;
+
+    let mut result = InFileReplaceResult {
+      path: path.to_string_lossy().to_string(),
+      replacements: 0,
+      hunks: Vec::new(),
+      hunks_truncated: false,
+      written: false,
+    };
+    if metadata.len() == 0 {
+      return Ok(result);
+    }
+    // SAFETY: 只读映射，与 search_file 相同；写入的是另一个临时文件
+    let mmap = unsafe { Mmap::map(&file) }.map_err(|e| format!("映射文件失败: {}", e))?;
+
+    // 先统计并生成片段，确认有匹配（且与预览一致）后再写临时文件
+    let (replacements, hunks, hunks_truncated) =
+      Self::replace_bytes(&mmap, &pattern, replacement, options, None::<&mut Vec<u8>>)
+        .map_err(|e| format!("生成替换结果失败: {}", e))?;
+    result.replacements = replacements;
+    result.hunks = hunks;
+    result.hunks_truncated = hunks_truncated;
+    if let Some(expected) = options.expected_replacements {
+      if expected != replacements {
+        return Err(format!(
+          "文件内容已变化：预览时 {} 处匹配，当前 {} 处，请重新预览",
+          expected, replacements
+        ));
+      }
+    }
+    if options.preview || replacements == 0 {
+      return Ok(result);
+    }
+
+    let file_name = path
+      .file_name()
+      .map(|n| n.to_string_lossy().to_string())
+      .unwrap_or_default();
+    let temp_path = path.with_file_name(format!(
+      ".{}.binder-replace-{}.tmp",
+      file_name,
+      uuid::Uuid::new_v4()
+    ));
+    let written = (|| {
+      let mut writer = BufWriter::new(File::create(&temp_path)?);
+      Self::replace_bytes(&mmap, &pattern, replacement, options, Some(&mut writer))?;
+      writer
+        .into_inner()
+        .map_err(|e| e.into_error())?
+        .sync_all()?;
+      std::fs::set_permissions(&temp_path, metadata.permissions())
+    })();
+    drop(mmap);
+    drop(file);
+    if let Err(e) = written.and_then(|_| std::fs::rename(&temp_path, path)) {
+      let _ = std::fs::remove_file(&temp_path);
+      return Err(format!("写入替换结果失败: {}", e));
+    }
+    result.written = true;
+    Ok(result)
+  }
+
+  /// 逐个匹配替换：返回（替换数, 片段, 片段是否截断）；传入 output 时同时写出替换后的完整内容
+  pub fn replace_bytes<W: Write>(
+    haystack: &[u8],
+    pattern: &Regex,
+    replacement: &str,
+    options: &InFileReplaceOptions,
+    mut output: Option<&mut W>,
+  ) -> std::io::Result<(usize, Vec<ReplaceHunk>, bool)> {
+    let mut replacements = 0;
+    let mut hunks: Vec<ReplaceHunk> = Vec::new();
+    let mut hunks_truncated = false;
+    // 正在生成的片段：(片段, 替换后文本, 已处理到的原文位置)
+    let mut current: Option<(ReplaceHunk, Vec<u8>, usize)> = None;
+    let mut line = 1u64;
+    let mut line_start = 0;
+    let mut scanned_to = 0;
+    let mut copied_to = 0;
+    let mut expanded = Vec::new();
+
+    let finish = |(mut hunk, mut after, cursor): (ReplaceHunk, Vec<u8>, usize)| {
+      after.extend_from_slice(&haystack[cursor..hunk.end as usize]);
+      hunk.before = String::from_utf8_lossy(&haystack[hunk.start as usize..hunk.end as usize])
+        .trim_end_matches('\r')
+        .to_string();
+      hunk.after = String::from_utf8_lossy(&after)
+        .trim_end_matches('\r')
+        .to_string();
+      hunk
+    };
+
+    for caps in pattern.captures_iter(haystack) {
+      let m = caps.get(0).expect("整体匹配总是存在");
+      replacements += 1;
+      expanded.clear();
+      if options.regex {
+        caps.expand(replacement.as_bytes(), &mut expanded);
+      } else {
+        expanded.extend_from_slice(replacement.as_bytes());
+      }
+      if let Some(out) = output.as_mut() {
+        out.write_all(&haystack[copied_to..m.start()])?;
+        out.write_all(&expanded)?;
+        copied_to = m.end();
+      }
+      if hunks_truncated {
+        continue;
+      }
+
+      // 行号与行首增量计算，与 search_bytes 相同，整体为一遍扫描
+      let gap = &haystack[scanned_to..m.start()];
+      line += count_newlines(gap) as u64;
+      if let Some(i) = gap.iter().rposition(|b| *b == b'\n') {
+        line_start = scanned_to + i + 1;
+      }
+      scanned_to = m.start();
+
+      match current.as_mut() {
+        // 落在上一片段的窗口内：合并
+        Some((hunk, after, cursor)) if m.start() < hunk.end as usize => {
+          after.extend_from_slice(&haystack[*cursor..m.start()]);
+          after.extend_from_slice(&expanded);
+          *cursor = m.end();
+          hunk.end = hunk.end.max(m.end() as u64);
+          hunk.replacements += 1;
+        }
+        _ => {
+          if let Some(done) = current.take() {
+            hunks.push(finish(done));
+          }
+          if hunks.len() >= options.max_hunks {
+            hunks_truncated = true;
+            continue;
+          }
+          // 窗口规则与搜索预览一致：匹配前至多 1/4 预算，向后到行尾或预算用完
+          let before = (options.preview_bytes / 4).min(m.start() - line_start);
+          let window_start = char_floor(haystack, m.start() - before);
+          let limit = (window_start + options.preview_bytes)
+            .max(m.end())
+            .min(haystack.len());
+          let window_end = haystack[m.end()..limit]
+            .iter()
+            .position(|b| *b == b'\n')
+            .map_or_else(|| char_floor(haystack, limit), |i| m.end() + i);
+          let mut after = haystack[window_start..m.start()].to_vec();
+          after.extend_from_slice(&expanded);
+          current = Some((
+            ReplaceHunk {
+              line,
+              start: window_start as u64,
+              end: window_end as u64,
+              replacements: 1,
+              before: String::new(),
+              after: String::new(),
+            },
+            after,
+            m.end(),
+          ));
+        }
+      }
+    }
+    if let Some(done) = current.take() {
+      hunks.push(finish(done));
+    }
+    if let Some(out) = output.as_mut() {
+      out.write_all(&haystack[copied_to..])?;
+    }
+    Ok((replacements, hunks, hunks_truncated))
+  }
+}
+
+/// 向前退到 UTF-8 字符边界，避免预览截断在多字节字符中间
+fn char_floor(bytes: &[u8], mut index: usize) -> usize {
+  while index > 0 && index < bytes.len() && (bytes[index] & 0xC0) == 0x80 {
+    index -= 1;
+  }
+  index
+}
+
+fn count_newlines(bytes: &[u8]) -> usize {
+  bytes.iter().filter(|b| **b == b'\n').count()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{InFileReplaceOptions, InFileSearchOptions, InFileSearchService};
+
+  fn search(haystack: &str, query: &str, options: InFileSearchOptions) -> Vec<(u64, u64, u64)> {
+    let pattern = InFileSearchService::build_pattern(query, &options).expect("build pattern");
+    let (matches, _) = InFileSearchService::search_bytes(haystack.as_bytes(), &pattern, &options);
+    matches.iter().map(|m| (m.start, m.end, m.line)).collect()
+  }
+
+  #[test]
+  fn literal_search_is_case_insensitive_by_default() {
+    let found = search(
+      "Alpha\nbeta alpha\n",
+      "alpha",
+      InFileSearchOptions::default(),
+    );
+    assert_eq!(found, vec![(0, 5, 1), (11, 16, 2)]);
+  }
+
+  #[test]
+  fn literal_search_escapes_regex_metacharacters() {
+    let found = search("a.b axb", "a.b", InFileSearchOptions::default());
+    assert_eq!(found, vec![(0, 3, 1)]);
+  }
+
+  #[test]
+  fn regex_whole_word_and_offset() {
+    let options = InFileSearchOptions {
+      regex: true,
+      whole_word: true,
+      start_offset: 4,
+      ..InFileSearchOptions::default()
+    };
+    let found = search("cat\ncat catalog\ncat", "c.t", options);
+    assert_eq!(found, vec![(4, 7, 2), (16, 19, 3)]);
+  }
+
+  #[test]
+  fn preview_is_a_window_around_the_match() {
+    let options = InFileSearchOptions {
+      preview_bytes: 40,
+      ..InFileSearchOptions::default()
+    };
+    // 压缩成单行的大文件：预览不应包含整行
+    let haystack = format!(
+      "{}needle{}\n第二行 needle",
+      "中".repeat(10_000),
+      "x".repeat(10_000)
+    );
+    let pattern = InFileSearchService::build_pattern("needle", &options).expect("build pattern");
+    let (matches, _) = InFileSearchService::search_bytes(haystack.as_bytes(), &pattern, &options);
+    assert_eq!(matches.len(), 2);
+    assert!(matches[0].preview.len() <= 40);
+    assert!(matches[0].preview.starts_with('中'));
+    assert!(matches[0].preview.contains("needle"));
+    assert_eq!(matches[0].column, 30_000);
+    assert_eq!((matches[1].line, matches[1].column), (2, 10));
+    assert_eq!(matches[1].preview, "第二行 needle");
+  }
+
+  #[test]
+  fn max_results_marks_truncation() {
+    let options = InFileSearchOptions {
+      max_results: 2,
+      ..InFileSearchOptions::default()
+    };
+    let pattern = InFileSearchService::build_pattern("x", &options).expect("build pattern");
+    let (matches, truncated) = InFileSearchService::search_bytes(b"xxxx", &pattern, &options);
+    assert_eq!(matches.len(), 2);
+    assert!(truncated);
+  }
+
+  #[test]
+  fn directory_search_skips_hidden_binary_and_caps_results() {
+    let root = std::env::temp_dir().join(format!("binder-grep-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(root.join("docs")).unwrap();
+    std::fs::create_dir_all(root.join(".binder")).unwrap();
+    std::fs::write(
+      root.join("docs/a.md"),
+      "Budget plan\nno match\nbudget review\n",
+    )
+    .unwrap();
+    std::fs::write(root.join("notes.txt"), "budget".repeat(3)).unwrap();
+    std::fs::write(root.join(".binder/cache.txt"), "budget").unwrap();
+    std::fs::write(root.join("image.bin"), b"budget\0\x01").unwrap();
+
+    let result = InFileSearchService::search_directory(
+      &root,
+      &root,
+      "budget",
+      &InFileSearchOptions::default(),
+      None,
+    )
+    .expect("scan");
+    let found: Vec<(&str, u64)> = result
+      .matches
+      .iter()
+      .map(|m| (m.path.as_str(), m.line))
+      .collect();
+    assert_eq!(
+      found,
+      vec![
+        ("notes.txt", 1),
+        ("notes.txt", 1),
+        ("notes.txt", 1),
+        ("docs/a.md", 1),
+        ("docs/a.md", 3)
+      ]
+    );
+    assert!(!result.truncated);
+
+    let capped = InFileSearchService::search_directory(
+      &root,
+      &root.join("docs"),
+      "budget",
+      &InFileSearchOptions {
+        max_results: 1,
+        ..InFileSearchOptions::default()
+      },
+      None,
+    )
+    .expect("capped scan");
+    assert_eq!(capped.matches.len(), 1);
+    assert!(capped.truncated);
+
+    let _ = std::fs::remove_dir_all(&root);
+  }
+
+  #[test]
+  fn replace_merges_hunks_per_line_and_expands_captures() {
+    let options = InFileReplaceOptions {
+      regex: true,
+      case_sensitive: true,
+      ..InFileReplaceOptions::default()
+    };
+    let search_options = InFileSearchOptions {
+      regex: true,
+      case_sensitive: true,
+      ..InFileSearchOptions::default()
+    };
+    let pattern =
+      InFileSearchService::build_pattern(r"(\w+)@(\w+)", &search_options).expect("build pattern");
+    let haystack = b"a@b c@d\r\nnone\nx@y\n";
+    let mut output = Vec::new();
+    let (count, hunks, truncated) =
+      InFileSearchService::replace_bytes(haystack, &pattern, "$2@$1", &options, Some(&mut output))
+        .expect("replace");
+    assert_eq!(count, 3);
+    assert!(!truncated);
+    assert_eq!(output, b"b@a d@c\r\nnone\ny@x\n");
+    let summary: Vec<(u64, usize, &str, &str)> = hunks
+      .iter()
+      .map(|h| (h.line, h.replacements, h.before.as_str(), h.after.as_str()))
+      .collect();
+    assert_eq!(
+      summary,
+      vec![(1, 2, "a@b c@d", "b@a d@c"), (3, 1, "x@y", "y@x")]
+    );
+
+    // 字面量替换中的 `$1` 原样保留
+    let literal = InFileReplaceOptions::default();
+    let pattern = InFileSearchService::build_pattern("A", &InFileSearchOptions::default())
+      .expect("build pattern");
+    let mut output = Vec::new();
+    InFileSearchService::replace_bytes(b"a-A", &pattern, "$1", &literal, Some(&mut output))
+      .expect("replace");
+    assert_eq!(output, b"$1-$1");
+  }
+
+  #[test]
+  fn replace_hunks_are_windows_on_long_lines() {
+    let options = InFileReplaceOptions {
+      preview_bytes: 40,
+      ..InFileReplaceOptions::default()
+    };
+    let pattern = InFileSearchService::build_pattern("needle", &InFileSearchOptions::default())
+      .expect("build pattern");
+    // 压缩成一行的大文件：片段只截取匹配附近，同一行上相距较远的匹配拆成多个片段
+    let haystack = format!(
+      "{}needle{}needle{}",
+      "x".repeat(500),
+      "y".repeat(500),
+      "z".repeat(500)
+    );
+    let (count, hunks, _) = InFileSearchService::replace_bytes(
+      haystack.as_bytes(),
+      &pattern,
+      "pin",
+      &options,
+      None::<&mut Vec<u8>>,
+    )
+    .expect("replace");
+    assert_eq!(count, 2);
+    assert_eq!(hunks.len(), 2);
+    for hunk in &hunks {
+      assert_eq!(hunk.line, 1);
+      assert!(hunk.before.len() <= 40);
+      assert!(hunk.before.contains("needle"));
+      assert!(hunk.after.contains("pin"));
+    }
+    assert_eq!(hunks[0].start, 490);
+  }
+
+  #[test]
+  fn replace_in_file_previews_then_writes_atomically() {
+    let path = std::env::temp_dir().join(format!("binder-replace-{}.md", uuid::Uuid::new_v4()));
+    std::fs::write(&path, "todo: one\nTODO: two\n").unwrap();
+    let preview = InFileSearchService::replace_in_file(
+      &path,
+      "todo",
+      "done",
+      &InFileReplaceOptions {
+        preview: true,
+        ..InFileReplaceOptions::default()
+      },
+    )
+    .expect("preview");
+    assert_eq!(preview.replacements, 2);
+    assert!(!preview.written);
+    assert_eq!(
+      std::fs::read_to_string(&path).unwrap(),
+      "todo: one\nTODO: two\n"
+    );
+
+    let stale = InFileSearchService::replace_in_file(
+      &path,
+      "todo",
+      "done",
+      &InFileReplaceOptions {
+        expected_replacements: Some(3),
+        ..InFileReplaceOptions::default()
+      },
+    );
+    assert!(stale.is_err());
+
+    let applied = InFileSearchService::replace_in_file(
+      &path,
+      "todo",
+      "done",
+      &InFileReplaceOptions {
+        expected_replacements: Some(2),
+        ..InFileReplaceOptions::default()
+      },
+    )
+    .expect("replace");
+    assert!(applied.written);
+    assert_eq!(
+      std::fs::read_to_string(&path).unwrap(),
+      "done: one\ndone: two\n"
+    );
+    let leftovers = std::fs::read_dir(path.parent().unwrap())
+      .unwrap()
+      .flatten()
+      .any(|e| e.file_name().to_string_lossy().contains(".binder-replace-"));
+    assert!(!leftovers);
+    let _ = std::fs::remove_file(&path);
+  }
+}
@@ -0,0 +1,128 @@
+//! Inline Assist 结果整理：解析模型返回的 `{"kind", "text"}`，修改类结果附带与选中文本的
+//! 逐词对比，供编辑器以修订模式预览并逐块接受。
+
+use crate::services::document_diff::{diff_hunks, TextHunk};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InlineAssistKind {
+  /// 修改选中文本，text 为修改后的完整文本
+  Edit,
+  /// 分析、解释等回复，不修改文档
+  Reply,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InlineAssistResult {
+  pub kind: InlineAssistKind,
+  pub text: String,
+  /// 修改前的选中文本，仅 edit 时有值
+  pub before: Option<String>,
+  /// 相对选中文本的修改块，偏移为 UTF-16 下标；reply 时为空
+  pub changes: Vec<TextHunk>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawResult {
+  kind: Option<String>,
+  text: Option<String>,
+}
+
+/// 模型未返回 JSON 时按指令判断是否为修改
+fn is_edit_instruction(instruction: &str) -> bool {
+  ["改", "润色", "翻译", "优化", "修正", "调整"]
+    .iter()
+    .any(|word| instruction.contains(word))
+}
+
+/// 解析模型输出并生成修改块；没有选中文本的修改（生成新内容）是一整块插入
+pub fn finish_result(response: &str, instruction: &str, selected: &str) -> InlineAssistResult {
+  let raw = response
+    .find('{')
+    .zip(response.rfind('}'))
+    .filter(|(start, end)| start < end)
+    .and_then(|(start, end)| serde_json::from_str::<RawResult>(&response[start..=end]).ok())
+    .filter(|raw| raw.text.is_some());
+  let (kind, text) = match raw {
+    Some(raw) => (
+      raw.kind.as_deref() == Some("edit"),
+      raw.text.unwrap_or_default(),
+    ),
+    None => (
+      !selected.is_empty() && is_edit_instruction(instruction),
+      response.trim().to_string(),
+    ),
+  };
+  if !kind {
+    return InlineAssistResult {
+      kind: InlineAssistKind::Reply,
+      text,
+      before: None,
+      changes: Vec::new(),
+    };
+  }
+  InlineAssistResult {
+    kind: InlineAssistKind::Edit,
+    changes: diff_hunks(selected, &text),
+    before: Some(selected.to_string()),
+    text,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn diffs_edit_results_against_selection() {
+    let response = "```json\n{\"kind\": \"edit\", \"text\": \"会议改到周三下午\"}\n```";
+    let result = finish_result(response, "改成周三", "会议定在周二下午");
+    assert_eq!(result.kind, InlineAssistKind::Edit);
+    assert_eq!(result.before.as_deref(), Some("会议定在周二下午"));
+    assert_eq!(
+      result.changes,
+      vec![
+        TextHunk {
+          start: 2,
+          end: 4,
+          before: "定在".to_string(),
+          after: "改到".to_string(),
+        },
+        TextHunk {
+          start: 5,
+          end: 6,
+          before: "二".to_string(),
+          after: "三".to_string(),
+        },
+      ]
+    );
+  }
+
+  #[test]
+  fn falls_back_to_instruction_without_json() {
+    let result = finish_result("这段话语气偏正式。", "分析语气", "各位同事：");
+    assert_eq!(result.kind, InlineAssistKind::Reply);
+    assert_eq!(result.text, "这段话语气偏正式。");
+    assert!(result.changes.is_empty());
+
+    let result = finish_result("新的句子", "润色", "原文");
+    assert_eq!(result.kind, InlineAssistKind::Edit);
+    assert_eq!(result.changes.len(), 1);
+
+    let result = finish_result("改写后的文本", "改写", "");
+    assert_eq!(result.kind, InlineAssistKind::Reply);
+    assert_eq!(result.before, None);
+
+    let result = finish_result(r#"{"kind": "edit", "text": "新段落"}"#, "续写", "");
+    assert_eq!(
+      result.changes,
+      vec![TextHunk {
+        start: 0,
+        end: 0,
+        before: String::new(),
+        after: "新段落".to_string(),
+      }]
+    );
+  }
+}
@@ -0,0 +1,513 @@
+//! 后台任务队列：统一调度索引构建、批量转换等耗时任务，前端通过 `list_jobs` 与 `job-updated`
+//! 事件展示“后台任务”面板。
+//!
+//! 任务按优先级（同优先级先提交先执行）排队，最多同时运行 `MAX_CONCURRENT_JOBS` 个。
+//! 取消排队中的任务直接移出队列；取消运行中的任务时丢弃任务的 future，
+//! 已交给阻塞线程的工作需自行检查 `JobContext::is_cancelled`。
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering as AtomicOrdering};
+use std::sync::{Arc, Mutex, MutexGuard, RwLock};
+use tokio_util::sync::CancellationToken;
+
+const MAX_CONCURRENT_JOBS: usize = 2;
+/// 保留的已结束任务数量，超出时移除最早结束的任务
+const MAX_FINISHED_JOBS: usize = 50;
+const CANCELLED_MESSAGE: &str = "任务已取消";
+
+pub type JobId = String;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobPriority {
+  Low,
+  #[default]
+  Normal,
+  High,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+  Queued,
+  Running,
+  Completed,
+  Failed,
+  Cancelled,
+}
+
+impl JobStatus {
+  pub fn is_finished(&self) -> bool {
+    matches!(
+      self,
+      JobStatus::Completed | JobStatus::Failed | JobStatus::Cancelled
+    )
+  }
+}
+
+/// 任务状态快照（`list_jobs` 返回值与 `job-updated` 事件载荷）
+#[derive(Debug, Clone, Serialize)]
+pub struct JobInfo {
+  pub id: JobId,
+  /// 任务类型，如 `build_index`、`convert_images`
+  pub kind: String,
+  pub title: String,
+  pub priority: JobPriority,
+  pub status: JobStatus,
+  /// 0.0-1.0，无法估计时为空
+  pub progress: Option<f64>,
+  pub message: Option<String>,
+  pub error: Option<String>,
+  pub created_at: i64,
+  pub started_at: Option<i64>,
+  pub finished_at: Option<i64>,
+}
+
+/// 提交任务时的描述
+pub struct JobSpec {
+  pub kind: String,
+  pub title: String,
+  pub priority: JobPriority,
+}
+
+impl JobSpec {
+  pub fn new(kind: impl Into<String>, title: impl Into<String>) -> Self {
+    Self {
+      kind: kind.into(),
+      title: title.into(),
+      priority: JobPriority::default(),
+    }
+  }
+
+  pub fn priority(mut self, priority: JobPriority) -> Self {
+    self.priority = priority;
+    self
+  }
+}
+
+type JobFuture = Pin<Box<dyn Future<Output = Result<(), String>> + Send>>;
+type JobTask = Box<dyn FnOnce(JobContext) -> JobFuture + Send>;
+type JobSink = Box<dyn Fn(&JobInfo) + Send + Sync>;
+
+struct PendingJob {
+  priority: JobPriority,
+  seq: u64,
+  id: JobId,
+  task: JobTask,
+}
+
+impl PartialEq for PendingJob {
+  fn eq(&self, other: &Self) -> bool {
+    self.cmp(other) == Ordering::Equal
+  }
+}
+
+impl Eq for PendingJob {}
+
+impl PartialOrd for PendingJob {
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+impl Ord for PendingJob {
+  /// 优先级高的先出队；同优先级时序号小（先提交）的先出队
+  fn cmp(&self, other: &Self) -> Ordering {
+    self
+      .priority
+      .cmp(&other.priority)
+      .then_with(|| other.seq.cmp(&self.seq))
+  }
+}
+
+struct JobEntry {
+  seq: u64,
+  info: JobInfo,
+  cancel: CancellationToken,
+}
+
+#[derive(Default)]
+struct JobState {
+  jobs: HashMap<JobId, JobEntry>,
+  queue: BinaryHeap<PendingJob>,
+  running: usize,
+}
+
+pub struct JobManager {
+  state: Mutex<JobState>,
+  max_concurrent: usize,
+  next_seq: AtomicU64,
+  sink: RwLock<Option<JobSink>>,
+}
+
+/// 运行中的任务用来报告进度、检查取消
+#[derive(Clone)]
+pub struct JobContext {
+  id: JobId,
+  manager: Arc<JobManager>,
+  cancel: CancellationToken,
+}
+
+impl JobContext {
+  pub fn id(&self) -> &str {
+    &self.id
+  }
+
+  pub fn is_cancelled(&self) -> bool {
+    self.cancel.is_cancelled()
+  }
+
+  /// 更新进度（0.0-1.0）与说明；进度变化不足 1% 且说明不变时不发送事件
+  pub fn set_progress(&self, progress: Option<f64>, message: Option<String>) {
+    self.manager.update(&self.id, |info| {
+      let progress = progress.map(|p| p.clamp(0.0, 1.0));
+      let changed = match (info.progress, progress) {
+        (Some(old), Some(new)) => (new - old).abs() >= 0.01 || new >= 1.0,
+        (old, new) => old.is_some() != new.is_some(),
+      };
+      if !changed && info.message == message {
+        return false;
+      }
+      info.progress = progress;
+      info.message = message;
+      true
+    });
+  }
+}
+
+fn now_millis() -> i64 {
+  chrono::Utc::now().timestamp_millis()
+}
+
+impl JobManager {
+  pub fn new(max_concurrent: usize) -> Arc<Self> {
+    Arc::new(Self {
+      state: Mutex::new(JobState::default()),
+      max_concurrent: max_concurrent.max(1),
+      next_seq: AtomicU64::new(0),
+      sink: RwLock::new(None),
+    })
+  }
+
+  /// 设置任务状态变化的通知（应用中为发送 `job-updated` 事件）
+  pub fn set_sink(&self, sink: impl Fn(&JobInfo) + Send + Sync + 'static) {
+    if let Ok(mut current) = self.sink.write() {
+      *current = Some(Box::new(sink));
+    }
+  }
+
+  fn lock(&self) -> MutexGuard<'_, JobState> {
+    self.state.lock().unwrap_or_else(|e| e.into_inner())
+  }
+
+  fn notify(&self, info: &JobInfo) {
+    if let Ok(sink) = self.sink.read() {
+      if let Some(sink) = sink.as_ref() {
+        sink(info);
+      }
+    }
+  }
+
+  /// 修改任务信息，`f` 返回 true 时发送通知
+  fn update(&self, id: &str, f: impl FnOnce(&mut JobInfo) -> bool) {
+    let info = {
+      let mut state = self.lock();
+      let Some(entry) = state.jobs.get_mut(id) else {
+        return;
+      };
+      if !f(&mut entry.info) {
+        return;
+      }
+      entry.info.clone()
+    };
+    self.notify(&info);
+  }
+
+  /// 提交任务并立即返回任务 id；需在 tokio 运行时中调用
+  pub fn submit<F, Fut>(self: &Arc<Self>, spec: JobSpec, task: F) -> JobId
+  where
+    F: FnOnce(JobContext) -> Fut + Send + 'static,
+    Fut: Future<Output = Result<(), String>> + Send + 'static,
+  {
+    let id = uuid::Uuid::new_v4().to_string();
+    let seq = self.next_seq.fetch_add(1, AtomicOrdering::Relaxed);
+    let info = JobInfo {
+      id: id.clone(),
+      kind: spec.kind,
+      title: spec.title,
+      priority: spec.priority,
+      status: JobStatus::Queued,
+      progress: None,
+      message: None,
+      error: None,
+      created_at: now_millis(),
+      started_at: None,
+      finished_at: None,
+    };
+    {
+      let mut state = self.lock();
+      state.jobs.insert(
+        id.clone(),
+        JobEntry {
+          seq,
+          info: info.clone(),
+          cancel: CancellationToken::new(),
+        },
+      );
+      state.queue.push(PendingJob {
+        priority: spec.priority,
+        seq,
+        id: id.clone(),
+        task: Box::new(move |ctx| Box::pin(task(ctx))),
+      });
+    }
+    self.notify(&info);
+    self.pump();
+    id
+  }
+
+  /// 提交任务并等待完成，返回任务结果
+  pub async fn run<T, F, Fut>(self: &Arc<Self>, spec: JobSpec, task: F) -> Result<T, String>
+  where
+    T: Send + 'static,
+    F: FnOnce(JobContext) -> Fut + Send + 'static,
+    Fut: Future<Output = Result<T, String>> + Send + 'static,
+  {
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    self.submit(spec, move |ctx| async move {
+      let result = task(ctx).await;
+      let status = result.as_ref().map(|_| ()).map_err(|e| e.clone());
+      let _ = tx.send(result);
+      status
+    });
+    rx.await
+      .unwrap_or_else(|_| Err(CANCELLED_MESSAGE.to_string()))
+  }
+
+  /// 在并发上限内启动排队中的任务
+  fn pump(self: &Arc<Self>) {
+    loop {
+      let (pending, ctx, info) = {
+        let mut state = self.lock();
+        if state.running >= self.max_concurrent {
+          return;
+        }
+        let Some(pending) = state.queue.pop() else {
+          return;
+        };
+        let Some(entry) = state.jobs.get_mut(&pending.id) else {
+          continue;
+        };
+        entry.info.status = JobStatus::Running;
+        entry.info.started_at = Some(now_millis());
+        let ctx = JobContext {
+          id: pending.id.clone(),
+          manager: self.clone(),
+          cancel: entry.cancel.clone(),
+        };
+        let info = entry.info.clone();
+        state.running += 1;
+        (pending, ctx, info)
+      };
+      self.notify(&info);
+
+      let manager = self.clone();
+      tokio::spawn(async move {
+        let cancel = ctx.cancel.clone();
+        let future = (pending.task)(ctx);
+        let result = tokio::select! {
+          result = future => result,
+          _ = cancel.cancelled() => Err(CANCELLED_MESSAGE.to_string()),
+        };
+        manager.finish(&pending.id, result);
+      });
+    }
+  }
+
+  fn finish(self: &Arc<Self>, id: &str, result: Result<(), String>) {
+    let info = {
+      let mut state = self.lock();
+      state.running = state.running.saturating_sub(1);
+      let info = state.jobs.get_mut(id).map(|entry| {
+        entry.info.status = if entry.cancel.is_cancelled() {
+          JobStatus::Cancelled
+        } else if result.is_ok() {
+          JobStatus::Completed
+        } else {
+          JobStatus::Failed
+        };
+        if entry.info.status == JobStatus::Completed {
+          entry.info.progress = Some(1.0);
+        }
+        entry.info.error = result.err();
+        entry.info.finished_at = Some(now_millis());
+        entry.info.clone()
+      });
+      Self::prune_finished(&mut state);
+      info
+    };
+    if let Some(info) = info {
+      if info.status == JobStatus::Failed {
+        tracing::warn!(
+          "后台任务失败 {}（{}）: {}",
+          info.title,
+          info.kind,
+          info.error.as_deref().unwrap_or_default()
+        );
+      }
+      self.notify(&info);
+    }
+    self.pump();
+  }
+
+  fn prune_finished(state: &mut JobState) {
+    let mut finished: Vec<(i64, JobId)> = state
+      .jobs
+      .values()
+      .filter(|e| e.info.status.is_finished())
+      .map(|e| (e.info.finished_at.unwrap_or_default(), e.info.id.clone()))
+      .collect();
+    if finished.len() <= MAX_FINISHED_JOBS {
+      return;
+    }
+    finished.sort();
+    for (_, id) in finished.iter().take(finished.len() - MAX_FINISHED_JOBS) {
+      state.jobs.remove(id);
+    }
+  }
+
+  /// 取消任务；任务不存在或已结束时返回 false
+  pub fn cancel(&self, id: &str) -> bool {
+    let info = {
+      let mut state = self.lock();
+      let Some(entry) = state.jobs.get_mut(id) else {
+        return false;
+      };
+      match entry.info.status {
+        JobStatus::Queued => {
+          entry.cancel.cancel();
+          entry.info.status = JobStatus::Cancelled;
+          entry.info.finished_at = Some(now_millis());
+          let info = entry.info.clone();
+          state.queue.retain(|pending| pending.id != id);
+          info
+        }
+        JobStatus::Running => {
+          entry.cancel.cancel();
+          return true;
+        }
+        _ => return false,
+      }
+    };
+    self.notify(&info);
+    true
+  }
+
+  /// 全部任务，按提交顺序
+  pub fn list(&self) -> Vec<JobInfo> {
+    let state = self.lock();
+    let mut entries: Vec<&JobEntry> = state.jobs.values().collect();
+    entries.sort_by_key(|e| e.seq);
+    entries.into_iter().map(|e| e.info.clone()).collect()
+  }
+
+  /// 移除已结束的任务
+  pub fn clear_finished(&self) {
+    self
+      .lock()
+      .jobs
+      .retain(|_, entry| !entry.info.status.is_finished());
+  }
+}
+
+static JOBS: Lazy<Arc<JobManager>> = Lazy::new(|| JobManager::new(MAX_CONCURRENT_JOBS));
+
+pub fn jobs() -> &'static Arc<JobManager> {
+  &JOBS
+}
+
+/// 任务状态变化时向前端发送 `job-updated` 事件（应用启动时调用）
+pub fn init(app: tauri::AppHandle) {
+  use tauri::Emitter;
+  jobs().set_sink(move |info| {
+    if let Err(e) = app.emit("job-updated", info) {
+      tracing::warn!("发送任务状态事件失败: {}", e);
+    }
+  });
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::time::Duration;
+
+  #[tokio::test]
+  async fn runs_by_priority_and_cancels() {
+    let manager = JobManager::new(1);
+    let order = Arc::new(Mutex::new(Vec::new()));
+    let (release, gate) = tokio::sync::oneshot::channel::<()>();
+
+    // 占住唯一的并发名额
+    let blocker = manager.submit(JobSpec::new("test", "blocker"), |_| async move {
+      let _ = gate.await;
+      Ok(())
+    });
+    for (name, priority) in [
+      ("low", JobPriority::Low),
+      ("high", JobPriority::High),
+      ("normal", JobPriority::Normal),
+    ] {
+      let order = order.clone();
+      manager.submit(
+        JobSpec::new("test", name).priority(priority),
+        move |_| async move {
+          order.lock().unwrap().push(name);
+          Ok(())
+        },
+      );
+    }
+    let cancelled = manager.submit(JobSpec::new("test", "cancelled"), |_| async { Ok(()) });
+    assert!(manager.cancel(&cancelled));
+    assert!(!manager.cancel(&cancelled));
+
+    let _ = release.send(());
+    let result = manager
+      .run(
+        JobSpec::new("test", "last").priority(JobPriority::Low),
+        |ctx| async move {
+          ctx.set_progress(Some(0.5), Some("一半".to_string()));
+          Ok(42)
+        },
+      )
+      .await;
+    assert_eq!(result, Ok(42));
+    assert_eq!(*order.lock().unwrap(), vec!["high", "normal", "low"]);
+
+    let long = manager.submit(JobSpec::new("test", "long"), |_| async move {
+      std::future::pending::<()>().await;
+      Ok(())
+    });
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    assert!(manager.cancel(&long));
+    tokio::time::sleep(Duration::from_millis(20)).await;
+
+    let status = |id: &str| {
+      manager
+        .list()
+        .into_iter()
+        .find(|j| j.id == id)
+        .unwrap()
+        .status
+    };
+    assert_eq!(status(&blocker), JobStatus::Completed);
+    assert_eq!(status(&cancelled), JobStatus::Cancelled);
+    assert_eq!(status(&long), JobStatus::Cancelled);
+    manager.clear_finished();
+    assert!(manager.list().is_empty());
+  }
+}
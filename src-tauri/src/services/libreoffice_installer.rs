@@ -0,0 +1,108 @@
+//! LibreOffice 安装辅助：按平台选择可用的包管理器（Homebrew / winget / Flatpak）执行安装。
+//!
+//! 都不可用时由调用方引导用户前往官网下载；Linux 发行版的系统包管理器需要 root 权限，不在此自动执行。
+
+use std::path::PathBuf;
+use which::which;
+
+pub const LIBREOFFICE_DOWNLOAD_URL: &str =
+  "https://www.libreoffice.org/download/download-libreoffice/";
+
+/// 一次安装要执行的命令
+#[derive(Debug, Clone)]
+pub struct InstallPlan {
+  pub program: PathBuf,
+  pub args: Vec<String>,
+}
+
+impl InstallPlan {
+  /// 展示给用户的完整命令
+  pub fn display(&self) -> String {
+    let program = self
+      .program
+      .file_stem()
+      .map(|n| n.to_string_lossy().to_string())
+      .unwrap_or_default();
+    std::iter::once(program)
+      .chain(self.args.iter().cloned())
+      .collect::<Vec<_>>()
+      .join(" ")
+  }
+}
+
+/// GUI 启动的应用在 macOS 上通常拿不到 Homebrew 的 PATH，额外检查默认安装位置
+fn find_package_manager(name: &str, fallbacks: &[&str]) -> Option<PathBuf> {
+  which(name)
+    .ok()
+    .or_else(|| fallbacks.iter().map(PathBuf::from).find(|p| p.is_file()))
+}
+
+/// 当前平台可自动执行的安装命令；没有可用包管理器时返回 None
+pub fn install_plan() -> Option<InstallPlan> {
+  let (program, args): (PathBuf, &[&str]) = if cfg!(target_os = "macos") {
+    (
+      find_package_manager("brew", &["/opt/homebrew/bin/brew", "/usr/local/bin/brew"])?,
+      &["install", "--cask", "libreoffice"],
+    )
+  } else if cfg!(target_os = "windows") {
+    (
+      find_package_manager("winget", &[])?,
+      &[
+        "install",
+        "-e",
+        "--id",
+        "TheDocumentFoundation.LibreOffice",
+        "--accept-package-agreements",
+        "--accept-source-agreements",
+      ],
+    )
+  } else {
+    (
+      find_package_manager("flatpak", &["/usr/bin/flatpak"])?,
+      &[
+        "install",
+        "-y",
+        "--user",
+        "flathub",
+        "org.libreoffice.LibreOffice",
+      ],
+    )
+  };
+  Some(InstallPlan {
+    program,
+    args: args.iter().map(|a| a.to_string()).collect(),
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn displays_the_command_without_the_program_directory() {
+    let plan = InstallPlan {
+      program: PathBuf::from("/opt/homebrew/bin/brew"),
+      args: vec!["install".into(), "--cask".into(), "libreoffice".into()],
+    };
+    assert_eq!(plan.display(), "brew install --cask libreoffice");
+  }
+
+  #[test]
+  fn falls_back_to_default_install_locations() {
+    let dir = std::env::temp_dir().join(format!("binder-pm-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let fallback = dir.join("pm");
+    std::fs::write(&fallback, b"").unwrap();
+    let missing = dir.join("missing");
+
+    let name = "binder-no-such-package-manager";
+    let fallbacks = [missing.to_str().unwrap(), fallback.to_str().unwrap()];
+    assert_eq!(
+      find_package_manager(name, &fallbacks),
+      Some(fallback.clone())
+    );
+    assert_eq!(find_package_manager(name, &fallbacks[..1]), None);
+
+    let _ = std::fs::remove_dir_all(&dir);
+  }
+}
@@ -5,6 +5,7 @@
 // - Excel (XLSX/XLS/ODS) → PDF 转换（预览模式）
 // - 演示文稿 (PPTX/PPT/PPSX/PPS/ODP) → PDF 转换（预览模式）
 
+use crate::services::preview_cache::PreviewCache;
 use once_cell::sync::Lazy;
 use sha2::{Digest, Sha256};
 use std::fs;
@@ -43,6 +44,53 @@ fn escape_xml(s: &str) -> String {
   out
 }
 
+/// 可通过 LibreOffice 转为 PDF 预览的 Office 文档类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OfficeDocumentKind {
+  /// DOCX, DOC, ODT, RTF
+  Document,
+  /// XLSX, XLS, ODS（CSV 由前端直接解析）
+  Spreadsheet,
+  /// PPTX, PPT, PPSX, PPS, ODP
+  Presentation,
+}
+
+impl OfficeDocumentKind {
+  pub fn from_path(path: &Path) -> Option<Self> {
+    let ext = path.extension()?.to_str()?.to_lowercase();
+    match ext.as_str() {
+      "docx" | "doc" | "odt" | "rtf" => Some(Self::Document),
+      "xlsx" | "xls" | "ods" => Some(Self::Spreadsheet),
+      "pptx" | "ppt" | "ppsx" | "pps" | "odp" => Some(Self::Presentation),
+      _ => None,
+    }
+  }
+
+  pub fn label(self) -> &'static str {
+    match self {
+      Self::Document => "文档",
+      Self::Spreadsheet => "Excel 文件",
+      Self::Presentation => "演示文稿文件",
+    }
+  }
+
+  /// 预计转换耗时（秒）：LibreOffice 启动约 2 秒，之后大致与文件大小成正比
+  pub fn estimated_conversion_secs(self, size_bytes: u64) -> f64 {
+    let secs_per_mb = match self {
+      Self::Document => 1.5,
+      Self::Spreadsheet => 2.5,
+      Self::Presentation => 3.0,
+    };
+    2.0 + size_bytes as f64 / (1024.0 * 1024.0) * secs_per_mb
+  }
+}
+
+/// 按已耗时估算转换进度（10-95）：越接近预计耗时增长越慢，转换完成前不会到 100
+pub fn estimate_conversion_progress(elapsed_secs: f64, expected_secs: f64) -> u8 {
+  let ratio = elapsed_secs.max(0.0) / expected_secs.max(0.1);
+  (10.0 + 85.0 * (1.0 - (-ratio).exp())).round().min(95.0) as u8
+}
+
 pub struct LibreOfficeService {
   builtin_path: Option<PathBuf>, // 内置 LibreOffice 路径（优先使用）
   cache_dir: PathBuf,            // PDF 缓存目录（预览模式）
@@ -79,7 +127,7 @@ impl LibreOfficeService {
 
     // 初始化字体替换配置（确保预览字体一致）
     if let Err(e) = service.initialize_font_substitution() {
-      eprintln!("⚠️ 初始化字体替换配置失败: {}，将使用系统默认字体", e);
+      tracing::warn!("初始化字体替换配置失败: {}，将使用系统默认字体", e);
       // 不返回错误，允许继续使用系统默认字体
     }
 
@@ -95,15 +143,15 @@ impl LibreOfficeService {
         // 验证可执行文件
         if Self::verify_executable(&builtin_path) {
           self.builtin_path = Some(builtin_path);
-          eprintln!("✅ 检测到内置 LibreOffice: {:?}", self.builtin_path);
+          tracing::info!("检测到内置 LibreOffice: {:?}", self.builtin_path);
         } else {
-          eprintln!("⚠️ 内置 LibreOffice 可执行文件验证失败");
+          tracing::warn!("内置 LibreOffice 可执行文件验证失败");
         }
       } else {
-        eprintln!("⚠️ 内置 LibreOffice 路径不存在");
+        tracing::warn!("内置 LibreOffice 路径不存在");
       }
     } else {
-      eprintln!("⚠️ 未找到内置 LibreOffice 资源文件，将在需要时使用系统版本");
+      tracing::warn!("未找到内置 LibreOffice 资源文件，将在需要时使用系统版本");
     }
 
     // 不在这里检测系统版本，只在需要时（内置不可用时）才检测
@@ -117,7 +165,7 @@ impl LibreOfficeService {
       let soffice_path =
         Self::get_soffice_path_from_dir(&PathBuf::from(resource_dir).join("libreoffice"));
       if soffice_path.is_some() {
-        eprintln!("✅ 从 TAURI_RESOURCE_DIR 找到内置 LibreOffice");
+        tracing::info!("从 TAURI_RESOURCE_DIR 找到内置 LibreOffice");
         return soffice_path;
       }
     }
@@ -132,7 +180,7 @@ impl LibreOfficeService {
             let resources_dir = contents_dir.join("Resources");
             let soffice_path = Self::get_soffice_path_from_dir(&resources_dir.join("libreoffice"));
             if soffice_path.is_some() {
-              eprintln!("✅ 从打包后路径找到内置 LibreOffice");
+              tracing::info!("从打包后路径找到内置 LibreOffice");
               return soffice_path;
             }
           }
@@ -150,7 +198,7 @@ impl LibreOfficeService {
       for libreoffice_dir in possible_paths {
         let soffice_path = Self::get_soffice_path_from_dir(&libreoffice_dir);
         if soffice_path.is_some() {
-          eprintln!("✅ 从开发模式路径找到内置 LibreOffice");
+          tracing::info!("从开发模式路径找到内置 LibreOffice");
           return soffice_path;
         }
       }
@@ -164,7 +212,7 @@ impl LibreOfficeService {
     // macOS: libreoffice/LibreOffice.app/Contents/MacOS/soffice
     let soffice_path = libreoffice_dir.join("LibreOffice.app/Contents/MacOS/soffice");
     if soffice_path.exists() {
-      eprintln!("✅ 找到 soffice 可执行文件: {:?}", soffice_path);
+      tracing::info!("找到 soffice 可执行文件: {:?}", soffice_path);
       return Some(soffice_path);
     }
 
@@ -187,61 +235,114 @@ impl LibreOfficeService {
     }
   }
 
-  /// 检测系统 LibreOffice（macOS 专用，降级方案）
+  /// 各平台常见的 LibreOffice 安装位置
+  fn system_install_candidates() -> Vec<PathBuf> {
+    let mut candidates = Vec::new();
+    if cfg!(target_os = "macos") {
+      candidates.push(PathBuf::from(
+        "/Applications/LibreOffice.app/Contents/MacOS/soffice",
+      ));
+      candidates.push(PathBuf::from(
+        "/Applications/LibreOffice.app/Contents/MacOS/soffice.bin",
+      ));
+      if let Some(home) = dirs::home_dir() {
+        candidates.push(home.join("Applications/LibreOffice.app/Contents/MacOS/soffice"));
+      }
+    } else if cfg!(target_os = "windows") {
+      for program_files in [r"C:\Program Files", r"C:\Program Files (x86)"] {
+        candidates.push(PathBuf::from(program_files).join(r"LibreOffice\program\soffice.exe"));
+      }
+    } else {
+      candidates.extend(
+        [
+          "/usr/bin/soffice",
+          "/usr/bin/libreoffice",
+          "/usr/lib/libreoffice/program/soffice",
+          "/usr/lib64/libreoffice/program/soffice",
+          "/snap/bin/libreoffice",
+          "/var/lib/flatpak/exports/bin/org.libreoffice.LibreOffice",
+        ]
+        .map(PathBuf::from),
+      );
+      if let Some(home) = dirs::home_dir() {
+        candidates.push(home.join(".local/share/flatpak/exports/bin/org.libreoffice.LibreOffice"));
+      }
+      // 官方 deb/rpm 包安装到 /opt/libreofficeX.Y
+      if let Ok(entries) = fs::read_dir("/opt") {
+        let mut versioned: Vec<PathBuf> = entries
+          .flatten()
+          .map(|e| e.path())
+          .filter(|p| {
+            p.file_name()
+              .and_then(|n| n.to_str())
+              .is_some_and(|n| n.starts_with("libreoffice"))
+          })
+          .map(|p| p.join("program/soffice"))
+          .collect();
+        versioned.sort();
+        versioned.reverse();
+        candidates.extend(versioned);
+      }
+    }
+    candidates
+  }
+
+  /// 检测系统 LibreOffice（降级方案）：先查 PATH，再查各平台常见安装位置
   fn detect_system_libreoffice() -> Option<PathBuf> {
-    eprintln!("🔍 开始检测系统 LibreOffice（降级方案）...");
+    tracing::debug!("开始检测系统 LibreOffice（降级方案）...");
 
-    // macOS: 先尝试使用 which 查找（更通用）
-    if let Ok(path) = which::which("soffice") {
-      eprintln!("✅ 通过 which 检测到系统 LibreOffice: {:?}", path);
-      if path.exists() {
-        return Some(path);
-      } else {
-        eprintln!("⚠️ which 返回的路径不存在: {:?}", path);
+    for name in ["soffice", "libreoffice"] {
+      if let Ok(path) = which::which(name) {
+        tracing::info!("通过 which 检测到系统 LibreOffice: {:?}", path);
+        if path.exists() {
+          return Some(path);
+        } else {
+          tracing::warn!("which 返回的路径不存在: {:?}", path);
+        }
       }
     }
 
     // 备用方案：检查常见的安装路径
-    let common_paths = vec![
-      PathBuf::from("/Applications/LibreOffice.app/Contents/MacOS/soffice"),
-      PathBuf::from("/Applications/LibreOffice.app/Contents/MacOS/soffice.bin"),
-    ];
-
-    for path in common_paths {
+    for path in Self::system_install_candidates() {
       if path.exists() {
-        eprintln!("✅ 通过常见路径检测到系统 LibreOffice: {:?}", path);
+        tracing::info!("通过常见路径检测到系统 LibreOffice: {:?}", path);
         return Some(path);
       }
     }
 
-    eprintln!("❌ 未检测到系统 LibreOffice");
+    tracing::error!("未检测到系统 LibreOffice");
     None
   }
 
+  /// PDF 预览缓存目录（转换输出也写在其下）
+  pub fn cache_dir(&self) -> &Path {
+    &self.cache_dir
+  }
+
   /// 获取可用的 LibreOffice 路径（优先使用内置版本）
   pub fn get_libreoffice_path(&self) -> Result<PathBuf, String> {
     // 1. 优先使用内置版本
     if let Some(ref path) = self.builtin_path {
-      eprintln!("🔍 检查内置 LibreOffice: {:?}", path);
+      tracing::debug!("检查内置 LibreOffice: {:?}", path);
       if path.exists() {
-        eprintln!("✅ 使用内置 LibreOffice: {:?}", path);
+        tracing::info!("使用内置 LibreOffice: {:?}", path);
         return Ok(path.clone());
       } else {
-        eprintln!("⚠️ 内置 LibreOffice 路径不存在: {:?}", path);
+        tracing::warn!("内置 LibreOffice 路径不存在: {:?}", path);
       }
     } else {
-      eprintln!("⚠️ 内置 LibreOffice 未初始化");
+      tracing::warn!("内置 LibreOffice 未初始化");
     }
 
     // 2. 内置不可用时，检测并使用系统版本（懒加载）
-    eprintln!("🔄 内置 LibreOffice 不可用，检测系统 LibreOffice...");
+    tracing::debug!("内置 LibreOffice 不可用，检测系统 LibreOffice...");
     if let Some(system_path) = Self::detect_system_libreoffice() {
-      eprintln!("🔍 检查系统 LibreOffice 路径: {:?}", system_path);
+      tracing::debug!("检查系统 LibreOffice 路径: {:?}", system_path);
       if system_path.exists() {
-        eprintln!("✅ 使用系统 LibreOffice: {:?}", system_path);
+        tracing::info!("使用系统 LibreOffice: {:?}", system_path);
         return Ok(system_path);
       } else {
-        eprintln!("⚠️ 系统 LibreOffice 路径不存在: {:?}", system_path);
+        tracing::warn!("系统 LibreOffice 路径不存在: {:?}", system_path);
       }
     }
 
@@ -251,7 +352,7 @@ impl LibreOfficeService {
     } else {
       "LibreOffice 不可用：内置版本资源文件不存在，且未检测到系统 LibreOffice。请安装 LibreOffice 或检查内置版本部署。建议手动创建草稿进行编辑。".to_string()
     };
-    eprintln!("❌ {}", error_msg);
+    tracing::error!("{}", error_msg);
     Err(error_msg)
   }
 
@@ -260,15 +361,33 @@ impl LibreOfficeService {
     self.get_libreoffice_path().is_ok()
   }
 
+  /// 当前使用的是否为内置 LibreOffice
+  pub fn is_bundled(&self) -> bool {
+    self.builtin_path.as_ref().is_some_and(|p| p.exists())
+  }
+
+  /// 读取 `soffice --version` 输出（如 "LibreOffice 7.6.4.1 ..."）
+  pub fn get_version(path: &Path) -> Option<String> {
+    let output = Command::new(path).arg("--version").output().ok()?;
+    if !output.status.success() {
+      return None;
+    }
+    String::from_utf8_lossy(&output.stdout)
+      .lines()
+      .map(str::trim)
+      .find(|l| !l.is_empty())
+      .map(str::to_string)
+  }
+
   /// 初始化字体替换配置
   /// 设置固定的默认字体，确保预览时字体显示一致
   fn initialize_font_substitution(&self) -> Result<(), String> {
     let (cjk, latin) = Self::get_default_fonts();
-    eprintln!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
-    eprintln!("🔤 [预览模式] 选择字体（缺失字体将替换为以下默认）:");
-    eprintln!("   - 中文/ CJK 默认: {}", cjk);
-    eprintln!("   - 英文/ Latin 默认: {}", latin);
-    eprintln!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    tracing::debug!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    tracing::debug!("[预览模式] 选择字体（缺失字体将替换为以下默认）:");
+    tracing::debug!("   - 中文/ CJK 默认: {}", cjk);
+    tracing::debug!("   - 英文/ Latin 默认: {}", latin);
+    tracing::debug!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
     self.write_font_substitution_config()
   }
 
@@ -282,16 +401,13 @@ impl LibreOfficeService {
       Self::generate_fontsubst_content(&default_cjk_font, &default_latin_font);
 
     let content_preview = fontsubst_content.chars().take(200).collect::<String>();
-    eprintln!(
-      "🔤 [字体调试] 写入字体配置: 内容长度={} 字节, 默认CJK={}, 默认Latin={}",
+    tracing::debug!(
+      "[字体调试] 写入字体配置: 内容长度={} 字节, 默认CJK={}, 默认Latin={}",
       fontsubst_content.len(),
       default_cjk_font,
       default_latin_font
     );
-    eprintln!(
-      "🔤 [字体调试] 配置内容片段(前200字符): {}...",
-      content_preview
-    );
+    tracing::debug!("[字体调试] 配置内容片段(前200字符): {}...", content_preview);
 
     let config_paths: Vec<PathBuf> = {
       let mut paths = vec![user_config_dir.join("user").join("config")];
@@ -311,13 +427,15 @@ impl LibreOfficeService {
         .map_err(|e| format!("写入 LibreOffice 字体配置失败: {}", e))?;
       let exists_after = fontsubst_file.exists();
       let size_after = fs::metadata(&fontsubst_file).map(|m| m.len()).unwrap_or(0);
-      eprintln!("✅ [预览模式] 字体替换配置已写入: {:?}", fontsubst_file);
-      eprintln!(
-        "🔤 [字体调试] 写入后校验: 路径={:?}, 存在={}, 大小={} 字节",
-        fontsubst_file, exists_after, size_after
+      tracing::info!("[预览模式] 字体替换配置已写入: {:?}", fontsubst_file);
+      tracing::debug!(
+        "[字体调试] 写入后校验: 路径={:?}, 存在={}, 大小={} 字节",
+        fontsubst_file,
+        exists_after,
+        size_after
       );
     }
-    eprintln!("   （预览时通过 -env:UserInstallation=file:///.../lo_user 加载此 profile）");
+    tracing::debug!("   （预览时通过 -env:UserInstallation=file:///.../lo_user 加载此 profile）");
     Ok(())
   }
 
@@ -439,7 +557,18 @@ impl LibreOfficeService {
     )
   }
 
+  /// 按文档类型转换为 PDF（预览模式）
+  pub fn convert_office_to_pdf(&self, path: &Path) -> Result<PathBuf, String> {
+    match OfficeDocumentKind::from_path(path) {
+      Some(OfficeDocumentKind::Document) => self.convert_docx_to_pdf(path),
+      Some(OfficeDocumentKind::Spreadsheet) => self.convert_excel_to_pdf(path),
+      Some(OfficeDocumentKind::Presentation) => self.convert_presentation_to_pdf(path),
+      None => Err(format!("不支持预览该文件类型: {}", path.display())),
+    }
+  }
+
   /// 转换 DOCX → PDF
+  #[tracing::instrument(skip_all, fields(path = %docx_path.display()))]
   pub fn convert_docx_to_pdf(&self, docx_path: &Path) -> Result<PathBuf, String> {
     // 1. 检查 LibreOffice 可用性
     let libreoffice_path = self.get_libreoffice_path()?;
@@ -455,22 +584,22 @@ impl LibreOfficeService {
     // 2. 某些特殊文件可能确实是0字节但有效
     // 3. LibreOffice 会自己处理空文件，返回明确的错误信息
     if let Ok(metadata) = std::fs::metadata(docx_path) {
-      eprintln!("📄 输入文件大小: {} 字节", metadata.len());
+      tracing::debug!("输入文件大小: {} 字节", metadata.len());
     } else {
       return Err(format!("无法读取输入文件: {:?}", docx_path));
     }
 
     // 3. 检查缓存
     if let Some(cached_pdf) = self.check_cache(docx_path)? {
-      eprintln!("✅ 使用缓存 PDF: {:?}", cached_pdf);
-      eprintln!("🔤 [字体调试] 使用缓存 PDF，未重新转换，当前看到的字体为历史转换结果");
+      tracing::info!("使用缓存 PDF: {:?}", cached_pdf);
+      tracing::debug!("[字体调试] 使用缓存 PDF，未重新转换，当前看到的字体为历史转换结果");
       return Ok(cached_pdf);
     }
 
     // 4. 执行转换（每次转换前确保字体配置已写入，清除缓存后也能生效）
-    eprintln!("🔤 [字体调试] 转换类型=DOCX 输入={:?}", docx_path);
+    tracing::debug!("[字体调试] 转换类型=DOCX 输入={:?}", docx_path);
     let _ = self.write_font_substitution_config();
-    eprintln!("🔄 开始转换 DOCX → PDF: {:?}", docx_path);
+    tracing::debug!("开始转换 DOCX → PDF: {:?}", docx_path);
 
     // 创建临时输出目录
     let output_dir = self.cache_dir.join("temp");
@@ -482,7 +611,7 @@ impl LibreOfficeService {
       return Err(format!("输出目录无写入权限: {:?}, 错误: {}", output_dir, e));
     }
     let _ = std::fs::remove_file(&test_file);
-    eprintln!("✅ 输出目录写入权限验证通过: {:?}", output_dir);
+    tracing::info!("输出目录写入权限验证通过: {:?}", output_dir);
 
     // 4. 配置 LibreOffice 运行环境（macOS 专用）
     let mut cmd = Command::new(&libreoffice_path);
@@ -495,7 +624,7 @@ impl LibreOfficeService {
     // Contents
     {
       cmd.current_dir(&contents_dir);
-      eprintln!("📁 设置工作目录: {:?}", contents_dir);
+      tracing::debug!("设置工作目录: {:?}", contents_dir);
 
       // 设置 DYLD_LIBRARY_PATH 指向 LibreOffice 的库目录
       // LibreOffice.app/Contents/Frameworks 包含所有动态库
@@ -509,14 +638,14 @@ impl LibreOfficeService {
       let mut dyld_paths = vec![];
       if frameworks_dir.exists() {
         dyld_paths.push(frameworks_dir.to_string_lossy().to_string());
-        eprintln!(
-          "📦 添加 Frameworks 目录到 DYLD_LIBRARY_PATH: {:?}",
+        tracing::debug!(
+          "添加 Frameworks 目录到 DYLD_LIBRARY_PATH: {:?}",
           frameworks_dir
         );
       }
       if program_dir.exists() {
         dyld_paths.push(program_dir.to_string_lossy().to_string());
-        eprintln!("📦 添加 MacOS 目录到 DYLD_LIBRARY_PATH: {:?}", program_dir);
+        tracing::debug!("添加 MacOS 目录到 DYLD_LIBRARY_PATH: {:?}", program_dir);
       }
       if !existing_dyld.is_empty() {
         dyld_paths.push(existing_dyld);
@@ -525,7 +654,7 @@ impl LibreOfficeService {
       let dyld_library_path = dyld_paths.join(":");
       if !dyld_library_path.is_empty() {
         cmd.env("DYLD_LIBRARY_PATH", &dyld_library_path);
-        eprintln!("🔧 设置 DYLD_LIBRARY_PATH: {}", dyld_library_path);
+        tracing::debug!("设置 DYLD_LIBRARY_PATH: {}", dyld_library_path);
       }
 
       // 设置其他必要的环境变量
@@ -552,19 +681,19 @@ impl LibreOfficeService {
         .join("config")
         .join("fontsubst.xcu");
       let (cjk, latin) = Self::get_default_fonts();
-      eprintln!("🔤 [预览模式] 本次转换使用的字体配置:");
-      eprintln!("   - UserInstallation: {}", installation_url);
-      eprintln!("   - 字体配置文件: {:?}", fontsubst_path);
-      eprintln!("   - 预期默认字体: 中文={}, 英文={}", cjk, latin);
+      tracing::debug!("[预览模式] 本次转换使用的字体配置:");
+      tracing::debug!("   - UserInstallation: {}", installation_url);
+      tracing::debug!("   - 字体配置文件: {:?}", fontsubst_path);
+      tracing::debug!("   - 预期默认字体: 中文={}, 英文={}", cjk, latin);
       let exist1 = fontsubst_path.exists();
       let size1 = fs::metadata(&fontsubst_path).map(|m| m.len()).unwrap_or(0);
       let exist2 = fontsubst_4_path.exists();
       let size2 = fs::metadata(&fontsubst_4_path)
         .map(|m| m.len())
         .unwrap_or(0);
-      eprintln!("🔤 [字体调试] DOCX 转换启动 soffice 前: user/config/fontsubst.xcu 存在={} 大小={} 字节, 4/user/config/fontsubst.xcu 存在={} 大小={} 字节", exist1, size1, exist2, size2);
+      tracing::debug!("[字体调试] DOCX 转换启动 soffice 前: user/config/fontsubst.xcu 存在={} 大小={} 字节, 4/user/config/fontsubst.xcu 存在={} 大小={} 字节", exist1, size1, exist2, size2);
     } else {
-      eprintln!("⚠️ 无法确定 LibreOffice Contents 目录，可能影响运行");
+      tracing::warn!("无法确定 LibreOffice Contents 目录，可能影响运行");
     }
 
     // 执行 LibreOffice 转换命令
@@ -583,8 +712,8 @@ impl LibreOfficeService {
       .canonicalize()
       .map_err(|e| format!("无法获取输出目录的绝对路径: {}", e))?;
 
-    eprintln!("📄 输入文件绝对路径: {:?}", docx_absolute);
-    eprintln!("📁 输出目录绝对路径: {:?}", output_dir_absolute);
+    tracing::debug!("输入文件绝对路径: {:?}", docx_absolute);
+    tracing::debug!("输出目录绝对路径: {:?}", output_dir_absolute);
 
     cmd.arg("--headless")
             .arg("--convert-to")
@@ -593,58 +722,58 @@ impl LibreOfficeService {
             .arg(&output_dir_absolute)
             .arg(&docx_absolute);
 
-    eprintln!("📝 执行命令: {:?}", cmd);
-    eprintln!("📝 命令参数详情:");
-    eprintln!("   - LibreOffice 路径: {:?}", libreoffice_path);
-    eprintln!("   - 输入文件: {:?}", docx_absolute);
-    eprintln!("   - 输出目录: {:?}", output_dir_absolute);
+    tracing::debug!("执行命令: {:?}", cmd);
+    tracing::debug!("命令参数详情:");
+    tracing::debug!("   - LibreOffice 路径: {:?}", libreoffice_path);
+    tracing::debug!("   - 输入文件: {:?}", docx_absolute);
+    tracing::debug!("   - 输出目录: {:?}", output_dir_absolute);
 
     // 记录命令执行开始时间
     let start_time = std::time::Instant::now();
 
     let output = cmd.output().map_err(|e| {
       let error_msg = format!("执行 LibreOffice 命令失败: {}", e);
-      eprintln!("❌ {}", error_msg);
-      eprintln!("   可能的原因:");
-      eprintln!("   1. LibreOffice 可执行文件损坏或不存在");
-      eprintln!("   2. 系统权限不足");
-      eprintln!("   3. 动态库加载失败（macOS DYLD_LIBRARY_PATH 问题）");
+      tracing::error!("{}", error_msg);
+      tracing::debug!("   可能的原因:");
+      tracing::debug!("   1. LibreOffice 可执行文件损坏或不存在");
+      tracing::debug!("   2. 系统权限不足");
+      tracing::warn!("   3. 动态库加载失败（macOS DYLD_LIBRARY_PATH 问题）");
       error_msg
     })?;
 
     let elapsed = start_time.elapsed();
-    eprintln!("⏱️  LibreOffice 命令执行耗时: {:?}", elapsed);
+    tracing::debug!("LibreOffice 命令执行耗时: {:?}", elapsed);
 
     // 记录命令输出（无论成功与否，都记录用于调试）
     let stderr = String::from_utf8_lossy(&output.stderr);
     let stdout = String::from_utf8_lossy(&output.stdout);
 
-    eprintln!("📋 LibreOffice 命令执行结果:");
-    eprintln!("   - 退出码: {:?}", output.status.code());
-    eprintln!("   - 成功: {}", output.status.success());
+    tracing::debug!("LibreOffice 命令执行结果:");
+    tracing::debug!("   - 退出码: {:?}", output.status.code());
+    tracing::debug!("   - 成功: {}", output.status.success());
 
     if !stderr.is_empty() {
-      eprintln!("📋 LibreOffice STDERR ({} 字节):", stderr.len());
+      tracing::debug!("LibreOffice STDERR ({} 字节):", stderr.len());
       for line in stderr.lines() {
-        eprintln!("   {}", line);
+        tracing::debug!("   {}", line);
       }
     } else {
-      eprintln!("📋 LibreOffice STDERR: (空)");
+      tracing::debug!("LibreOffice STDERR: (空)");
     }
 
     if !stdout.is_empty() {
-      eprintln!("📋 LibreOffice STDOUT ({} 字节):", stdout.len());
+      tracing::debug!("LibreOffice STDOUT ({} 字节):", stdout.len());
       for line in stdout.lines() {
-        eprintln!("   {}", line);
+        tracing::debug!("   {}", line);
       }
     } else {
-      eprintln!("📋 LibreOffice STDOUT: (空)");
+      tracing::debug!("LibreOffice STDOUT: (空)");
     }
 
     // 检查命令执行状态
     if !output.status.success() {
-      eprintln!(
-        "❌ LibreOffice 命令执行失败，退出码: {:?}",
+      tracing::error!(
+        "LibreOffice 命令执行失败，退出码: {:?}",
         output.status.code()
       );
       let error_detail = if !stderr.is_empty() {
@@ -659,13 +788,13 @@ impl LibreOfficeService {
 
     // ⚠️ 关键：即使命令返回成功，也可能没有生成文件
     // 先检查输出目录中是否有文件，如果没有，再尝试查找
-    eprintln!("🔍 检查输出目录是否存在: {:?}", output_dir_absolute);
+    tracing::debug!("检查输出目录是否存在: {:?}", output_dir_absolute);
     if !output_dir_absolute.exists() {
       return Err(format!("输出目录不存在: {:?}", output_dir_absolute));
     }
 
     // 立即检查输出目录内容（不等待重试）
-    eprintln!("🔍 立即检查输出目录内容:");
+    tracing::debug!("立即检查输出目录内容:");
     if let Ok(entries) = std::fs::read_dir(&output_dir_absolute) {
       let mut file_count = 0;
       for entry in entries {
@@ -673,7 +802,7 @@ impl LibreOfficeService {
           let path = entry.path();
           if let Ok(metadata) = std::fs::metadata(&path) {
             file_count += 1;
-            eprintln!(
+            tracing::debug!(
               "   - {:?} (大小: {} 字节, 类型: {})",
               path.file_name().unwrap_or_default(),
               metadata.len(),
@@ -687,27 +816,27 @@ impl LibreOfficeService {
         }
       }
       if file_count == 0 {
-        eprintln!("⚠️  输出目录为空，LibreOffice 可能未生成文件");
+        tracing::warn!("输出目录为空，LibreOffice 可能未生成文件");
       }
     } else {
-      eprintln!("⚠️  无法读取输出目录");
+      tracing::warn!("无法读取输出目录");
     }
 
     // 5. 查找生成的 PDF 文件（使用带重试机制的查找方法）
     // LibreOffice 可能使用不同的文件名，需要扫描输出目录
     // ⚠️ 关键优化：添加重试机制，等待文件完全写入磁盘
     // 但重试次数应该减少，因为如果文件真的没生成，重试也没用
-    eprintln!("🔍 [预览] 开始查找生成的 PDF 文件...");
+    tracing::debug!("[预览] 开始查找生成的 PDF 文件...");
     let temp_pdf_path = match self.find_generated_pdf(&output_dir_absolute, &docx_absolute) {
       Ok(path) => {
-        eprintln!("✅ [预览] 成功找到 PDF 文件: {:?}", path);
+        tracing::info!("[预览] 成功找到 PDF 文件: {:?}", path);
         path
       }
       Err(e) => {
-        eprintln!("❌ [预览] 查找 PDF 文件失败: {}", e);
+        tracing::error!("[预览] 查找 PDF 文件失败: {}", e);
         // 如果找不到文件，输出详细的调试信息
-        eprintln!("❌ 查找 PDF 文件失败: {}", e);
-        eprintln!("📋 输出目录完整内容:");
+        tracing::error!("查找 PDF 文件失败: {}", e);
+        tracing::debug!("输出目录完整内容:");
         if let Ok(entries) = std::fs::read_dir(&output_dir_absolute) {
           let mut has_files = false;
           for entry in entries {
@@ -715,7 +844,7 @@ impl LibreOfficeService {
               has_files = true;
               let path = entry.path();
               if let Ok(metadata) = std::fs::metadata(&path) {
-                eprintln!(
+                tracing::debug!(
                   "  - {:?} (大小: {} 字节, 类型: {})",
                   path.file_name().unwrap_or_default(),
                   metadata.len(),
@@ -726,7 +855,7 @@ impl LibreOfficeService {
                   }
                 );
               } else {
-                eprintln!(
+                tracing::warn!(
                   "  - {:?} (无法读取元数据)",
                   path.file_name().unwrap_or_default()
                 );
@@ -734,18 +863,18 @@ impl LibreOfficeService {
             }
           }
           if !has_files {
-            eprintln!("  - (目录为空)");
+            tracing::debug!("  - (目录为空)");
           }
         } else {
-          eprintln!("  - 无法读取输出目录");
+          tracing::warn!("  - 无法读取输出目录");
         }
 
         // 提供诊断建议
-        eprintln!("🔍 诊断建议:");
-        eprintln!("   1. 检查输入文件是否损坏: {:?}", docx_absolute);
-        eprintln!("   2. 检查 LibreOffice 是否正常工作");
-        eprintln!("   3. 检查输出目录权限: {:?}", output_dir_absolute);
-        eprintln!("   4. 查看上方的 LibreOffice 命令输出，查找错误信息");
+        tracing::debug!("诊断建议:");
+        tracing::debug!("   1. 检查输入文件是否损坏: {:?}", docx_absolute);
+        tracing::debug!("   2. 检查 LibreOffice 是否正常工作");
+        tracing::debug!("   3. 检查输出目录权限: {:?}", output_dir_absolute);
+        tracing::warn!("   4. 查看上方的 LibreOffice 命令输出，查找错误信息");
 
         // 构建详细的错误消息
         let mut error_msg = format!("PDF 文件未生成在输出目录: {:?}\n", output_dir_absolute);
@@ -770,14 +899,15 @@ impl LibreOfficeService {
 
     fs::copy(&temp_pdf_path, &cached_pdf_path)
       .map_err(|e| format!("复制 PDF 到缓存目录失败: {}", e))?;
+    self.record_cache(docx_path, &cached_pdf_path);
 
     // ⚠️ 优化：延迟删除临时文件，避免并发请求时文件被过早删除
     // 临时文件会在系统清理时自动删除，或者由清理任务定期清理
     // 不立即删除，给并发请求更多时间找到文件
     // let _ = fs::remove_file(&temp_pdf_path);
 
-    eprintln!("✅ PDF 转换成功: {:?}", cached_pdf_path);
-    eprintln!("🔤 [字体调试] 本 PDF 由本次 DOCX 转换生成，profile=lo_user 字体配置应已生效");
+    tracing::info!("PDF 转换成功: {:?}", cached_pdf_path);
+    tracing::debug!("[字体调试] 本 PDF 由本次 DOCX 转换生成，profile=lo_user 字体配置应已生效");
 
     Ok(cached_pdf_path)
   }
@@ -785,21 +915,22 @@ impl LibreOfficeService {
   /// 转换 Excel → PDF（预览模式）
   /// 支持格式：XLSX, XLS, ODS
   /// 注意：CSV 不使用此方法，使用前端直接解析
+  #[tracing::instrument(skip_all, fields(path = %excel_path.display()))]
   pub fn convert_excel_to_pdf(&self, excel_path: &Path) -> Result<PathBuf, String> {
     // 1. 检查 LibreOffice 可用性
     let libreoffice_path = self.get_libreoffice_path()?;
 
     // 2. 检查缓存
     if let Some(cached_pdf) = self.check_cache(excel_path)? {
-      eprintln!("✅ 使用缓存 PDF: {:?}", cached_pdf);
-      eprintln!("🔤 [字体调试] 使用缓存 PDF，未重新转换，当前看到的字体为历史转换结果");
+      tracing::info!("使用缓存 PDF: {:?}", cached_pdf);
+      tracing::debug!("[字体调试] 使用缓存 PDF，未重新转换，当前看到的字体为历史转换结果");
       return Ok(cached_pdf);
     }
 
     // 3. 执行转换（每次转换前确保字体配置已写入）
-    eprintln!("🔤 [字体调试] 转换类型=Excel 输入={:?}", excel_path);
+    tracing::debug!("[字体调试] 转换类型=Excel 输入={:?}", excel_path);
     let _ = self.write_font_substitution_config();
-    eprintln!("🔄 开始转换 Excel → PDF: {:?}", excel_path);
+    tracing::debug!("开始转换 Excel → PDF: {:?}", excel_path);
 
     // 创建临时输出目录
     let output_dir = self.cache_dir.join("temp");
@@ -817,7 +948,7 @@ impl LibreOfficeService {
             .arg(&output_dir)
             .arg(excel_path);
 
-    eprintln!("📝 执行命令: {:?}", cmd);
+    tracing::debug!("执行命令: {:?}", cmd);
 
     let output = cmd
       .output()
@@ -828,15 +959,15 @@ impl LibreOfficeService {
     let stdout = String::from_utf8_lossy(&output.stdout);
 
     if !stderr.is_empty() {
-      eprintln!("📋 LibreOffice STDERR: {}", stderr);
+      tracing::debug!("LibreOffice STDERR: {}", stderr);
     }
     if !stdout.is_empty() {
-      eprintln!("📋 LibreOffice STDOUT: {}", stdout);
+      tracing::debug!("LibreOffice STDOUT: {}", stdout);
     }
 
     if !output.status.success() {
-      eprintln!(
-        "❌ LibreOffice 命令执行失败，退出码: {:?}",
+      tracing::error!(
+        "LibreOffice 命令执行失败，退出码: {:?}",
         output.status.code()
       );
       return Err(format!(
@@ -850,7 +981,7 @@ impl LibreOfficeService {
     }
 
     // ⚠️ 关键：即使命令返回成功，也可能没有生成文件
-    eprintln!("🔍 检查输出目录是否存在: {:?}", output_dir);
+    tracing::debug!("检查输出目录是否存在: {:?}", output_dir);
     if !output_dir.exists() {
       return Err(format!("输出目录不存在: {:?}", output_dir));
     }
@@ -859,14 +990,14 @@ impl LibreOfficeService {
     let temp_pdf_path = match self.find_generated_pdf(&output_dir, excel_path) {
       Ok(path) => path,
       Err(e) => {
-        eprintln!("❌ 查找 PDF 文件失败: {}", e);
-        eprintln!("📋 输出目录完整内容:");
+        tracing::error!("查找 PDF 文件失败: {}", e);
+        tracing::debug!("输出目录完整内容:");
         if let Ok(entries) = std::fs::read_dir(&output_dir) {
           for entry in entries {
             if let Ok(entry) = entry {
               let path = entry.path();
               if let Ok(metadata) = std::fs::metadata(&path) {
-                eprintln!(
+                tracing::debug!(
                   "  - {:?} (大小: {} 字节, 类型: {})",
                   path,
                   metadata.len(),
@@ -877,12 +1008,12 @@ impl LibreOfficeService {
                   }
                 );
               } else {
-                eprintln!("  - {:?} (无法读取元数据)", path);
+                tracing::warn!("  - {:?} (无法读取元数据)", path);
               }
             }
           }
         } else {
-          eprintln!("  - 无法读取输出目录");
+          tracing::warn!("  - 无法读取输出目录");
         }
         return Err(format!("PDF 文件未生成。LibreOffice 命令可能已执行，但未生成输出文件。\n错误: {}\n请检查文件是否损坏或格式不支持。", e));
       }
@@ -894,36 +1025,38 @@ impl LibreOfficeService {
 
     fs::copy(&temp_pdf_path, &cached_pdf_path)
       .map_err(|e| format!("复制 PDF 到缓存目录失败: {}", e))?;
+    self.record_cache(excel_path, &cached_pdf_path);
 
     // ⚠️ 优化：延迟删除临时文件，避免并发请求时文件被过早删除
     // let _ = fs::remove_file(&temp_pdf_path);
 
-    eprintln!("✅ Excel PDF 转换成功: {:?}", cached_pdf_path);
-    eprintln!("🔤 [字体调试] 本 PDF 由本次 Excel 转换生成，profile=lo_user 字体配置应已生效");
+    tracing::info!("Excel PDF 转换成功: {:?}", cached_pdf_path);
+    tracing::debug!("[字体调试] 本 PDF 由本次 Excel 转换生成，profile=lo_user 字体配置应已生效");
 
     Ok(cached_pdf_path)
   }
 
   /// 转换演示文稿 → PDF（预览模式）
   /// 支持格式：PPTX, PPT, PPSX, PPS, ODP
+  #[tracing::instrument(skip_all, fields(path = %presentation_path.display()))]
   pub fn convert_presentation_to_pdf(&self, presentation_path: &Path) -> Result<PathBuf, String> {
     // 1. 检查 LibreOffice 可用性
     let libreoffice_path = self.get_libreoffice_path()?;
 
     // 2. 检查缓存
     if let Some(cached_pdf) = self.check_cache(presentation_path)? {
-      eprintln!("✅ 使用缓存 PDF: {:?}", cached_pdf);
-      eprintln!("🔤 [字体调试] 使用缓存 PDF，未重新转换，当前看到的字体为历史转换结果");
+      tracing::info!("使用缓存 PDF: {:?}", cached_pdf);
+      tracing::debug!("[字体调试] 使用缓存 PDF，未重新转换，当前看到的字体为历史转换结果");
       return Ok(cached_pdf);
     }
 
     // 3. 执行转换（每次转换前确保字体配置已写入）
-    eprintln!(
-      "🔤 [字体调试] 转换类型=演示文稿(PPT) 输入={:?}",
+    tracing::debug!(
+      "[字体调试] 转换类型=演示文稿(PPT) 输入={:?}",
       presentation_path
     );
     let _ = self.write_font_substitution_config();
-    eprintln!("🔄 开始转换演示文稿 → PDF: {:?}", presentation_path);
+    tracing::debug!("开始转换演示文稿 → PDF: {:?}", presentation_path);
 
     // 创建临时输出目录
     let output_dir = self.cache_dir.join("temp");
@@ -941,7 +1074,7 @@ impl LibreOfficeService {
             .arg(&output_dir)
             .arg(presentation_path);
 
-    eprintln!("📝 执行命令: {:?}", cmd);
+    tracing::debug!("执行命令: {:?}", cmd);
 
     let output = cmd
       .output()
@@ -952,15 +1085,15 @@ impl LibreOfficeService {
     let stdout = String::from_utf8_lossy(&output.stdout);
 
     if !stderr.is_empty() {
-      eprintln!("📋 LibreOffice STDERR: {}", stderr);
+      tracing::debug!("LibreOffice STDERR: {}", stderr);
     }
     if !stdout.is_empty() {
-      eprintln!("📋 LibreOffice STDOUT: {}", stdout);
+      tracing::debug!("LibreOffice STDOUT: {}", stdout);
     }
 
     if !output.status.success() {
-      eprintln!(
-        "❌ LibreOffice 命令执行失败，退出码: {:?}",
+      tracing::error!(
+        "LibreOffice 命令执行失败，退出码: {:?}",
         output.status.code()
       );
       return Err(format!(
@@ -977,7 +1110,7 @@ impl LibreOfficeService {
     let temp_pdf_path = match self.find_generated_pdf(&output_dir, presentation_path) {
       Ok(path) => path,
       Err(e) => {
-        eprintln!("❌ 查找 PDF 文件失败: {}", e);
+        tracing::error!("查找 PDF 文件失败: {}", e);
         return Err(format!(
           "PDF 文件未生成。LibreOffice 命令可能已执行，但未生成输出文件。\n错误: {}",
           e
@@ -991,12 +1124,13 @@ impl LibreOfficeService {
 
     fs::copy(&temp_pdf_path, &cached_pdf_path)
       .map_err(|e| format!("复制 PDF 到缓存目录失败: {}", e))?;
+    self.record_cache(presentation_path, &cached_pdf_path);
 
     // ⚠️ 优化：延迟删除临时文件，避免并发请求时文件被过早删除
     // let _ = fs::remove_file(&temp_pdf_path);
 
-    eprintln!("✅ 演示文稿 PDF 转换成功: {:?}", cached_pdf_path);
-    eprintln!("🔤 [字体调试] 本 PDF 由本次演示文稿转换生成，profile=lo_user 字体配置应已生效");
+    tracing::info!("演示文稿 PDF 转换成功: {:?}", cached_pdf_path);
+    tracing::debug!("[字体调试] 本 PDF 由本次演示文稿转换生成，profile=lo_user 字体配置应已生效");
 
     Ok(cached_pdf_path)
   }
@@ -1013,7 +1147,7 @@ impl LibreOfficeService {
     // Contents
     {
       cmd.current_dir(&contents_dir);
-      eprintln!("📁 设置工作目录: {:?}", contents_dir);
+      tracing::debug!("设置工作目录: {:?}", contents_dir);
 
       // 设置 DYLD_LIBRARY_PATH 指向 LibreOffice 的库目录
       let frameworks_dir = contents_dir.join("Frameworks");
@@ -1058,9 +1192,12 @@ impl LibreOfficeService {
         .join("config")
         .join("fontsubst.xcu");
       let (cjk, latin) = Self::get_default_fonts();
-      eprintln!(
-        "🔤 [预览模式] 字体配置: UserInstallation={}, 字体文件={:?}, 默认 中文={} 英文={}",
-        installation_url, fontsubst_path, cjk, latin
+      tracing::debug!(
+        "[预览模式] 字体配置: UserInstallation={}, 字体文件={:?}, 默认 中文={} 英文={}",
+        installation_url,
+        fontsubst_path,
+        cjk,
+        latin
       );
       let exist1 = fontsubst_path.exists();
       let size1 = fs::metadata(&fontsubst_path).map(|m| m.len()).unwrap_or(0);
@@ -1068,9 +1205,9 @@ impl LibreOfficeService {
       let size2 = fs::metadata(&fontsubst_4_path)
         .map(|m| m.len())
         .unwrap_or(0);
-      eprintln!("🔤 [字体调试] 启动 soffice 前: user/config/fontsubst.xcu 存在={} 大小={} 字节, 4/user/config/fontsubst.xcu 存在={} 大小={} 字节", exist1, size1, exist2, size2);
+      tracing::debug!("[字体调试] 启动 soffice 前: user/config/fontsubst.xcu 存在={} 大小={} 字节, 4/user/config/fontsubst.xcu 存在={} 大小={} 字节", exist1, size1, exist2, size2);
     } else {
-      eprintln!("⚠️ 无法确定 LibreOffice Contents 目录，可能影响运行");
+      tracing::warn!("无法确定 LibreOffice Contents 目录，可能影响运行");
     }
 
     Ok(cmd)
@@ -1084,7 +1221,7 @@ impl LibreOfficeService {
   /// 3. 如果找不到精确匹配，返回最新的 PDF 文件（按修改时间）
   /// 4. 文件大小检查：只要 > 100 字节就认为可用
   fn find_generated_pdf(&self, output_dir: &Path, source_path: &Path) -> Result<PathBuf, String> {
-    eprintln!("🔍 [预览] 扫描输出目录查找 PDF 文件: {:?}", output_dir);
+    tracing::debug!("[预览] 扫描输出目录查找 PDF 文件: {:?}", output_dir);
 
     // 预期的 PDF 文件名
     let expected_pdf_filename = source_path
@@ -1092,22 +1229,22 @@ impl LibreOfficeService {
       .and_then(|s| s.to_str())
       .map(|s| s.to_string() + ".pdf");
 
-    eprintln!("🔍 [预览] 预期的 PDF 文件名: {:?}", expected_pdf_filename);
+    tracing::debug!("[预览] 预期的 PDF 文件名: {:?}", expected_pdf_filename);
 
     // 增加重试机制：最多重试 10 次，每次等待 500ms，总等待时间最多 5 秒
     // 确保有足够时间等待文件系统刷新文件到磁盘
     const MAX_RETRIES: u32 = 10;
     const RETRY_DELAY_MS: u64 = 500;
 
-    eprintln!(
-      "🔍 [预览] 开始查找，最多重试 {} 次，每次等待 {}ms",
+    tracing::debug!(
+      "[预览] 开始查找，最多重试 {} 次，每次等待 {}ms",
       MAX_RETRIES + 1,
       RETRY_DELAY_MS
     );
 
     // 简化查找逻辑：直接扫描目录，优先精确匹配，其次选择最新文件
     for attempt in 0..=MAX_RETRIES {
-      eprintln!("🔍 [预览] 尝试 {}/{}", attempt + 1, MAX_RETRIES + 1);
+      tracing::debug!("[预览] 尝试 {}/{}", attempt + 1, MAX_RETRIES + 1);
       // 收集所有 PDF 文件及其信息
       let mut pdf_files: Vec<(PathBuf, String, SystemTime, u64)> = Vec::new();
 
@@ -1144,7 +1281,7 @@ impl LibreOfficeService {
       if let Some(ref expected_filename) = expected_pdf_filename {
         for (path, file_name, _, size) in &pdf_files {
           if file_name.eq_ignore_ascii_case(expected_filename) {
-            eprintln!("✅ [预览] 找到 PDF 文件: {:?} (大小: {} 字节)", path, size);
+            tracing::info!("[预览] 找到 PDF 文件: {:?} (大小: {} 字节)", path, size);
             return Ok(path.clone());
           }
         }
@@ -1154,29 +1291,30 @@ impl LibreOfficeService {
       if !pdf_files.is_empty() {
         pdf_files.sort_by(|a, b| b.2.cmp(&a.2)); // 按修改时间降序排序
         let (path, file_name, _, size) = &pdf_files[0];
-        eprintln!(
-          "✅ [预览] 选择最新的 PDF 文件: {} (大小: {} 字节)",
-          file_name, size
+        tracing::info!(
+          "[预览] 选择最新的 PDF 文件: {} (大小: {} 字节)",
+          file_name,
+          size
         );
         return Ok(path.clone());
       }
 
       // 如果没找到文件且不是最后一次尝试，等待后重试
       if attempt < MAX_RETRIES {
-        eprintln!(
-          "⏳ [预览] 未找到 PDF 文件，等待 {}ms 后重试... (尝试 {}/{})",
+        tracing::debug!(
+          "[预览] 未找到 PDF 文件，等待 {}ms 后重试... (尝试 {}/{})",
           RETRY_DELAY_MS,
           attempt + 1,
           MAX_RETRIES + 1
         );
         std::thread::sleep(Duration::from_millis(RETRY_DELAY_MS));
       } else {
-        eprintln!("❌ [预览] 已达到最大重试次数，停止查找");
+        tracing::error!("[预览] 已达到最大重试次数，停止查找");
       }
     }
 
     // 所有重试都失败，返回错误
-    eprintln!("❌ [预览] 所有重试都失败，返回错误");
+    tracing::error!("[预览] 所有重试都失败，返回错误");
     Err(format!(
       "PDF 文件未生成在输出目录: {:?} (已重试 {} 次)",
       output_dir,
@@ -1187,18 +1325,19 @@ impl LibreOfficeService {
   /// 转换 DOCX → ODT（编辑模式）
   /// 使用独立的 ODT 缓存目录（cache/odt/），与 PDF 缓存分离
   /// 编辑模式和预览模式共享 ODT 缓存
+  #[tracing::instrument(skip_all, fields(path = %docx_path.display()))]
   pub fn convert_docx_to_odt(&self, docx_path: &Path) -> Result<PathBuf, String> {
     // 1. 检查 LibreOffice 可用性
     let libreoffice_path = self.get_libreoffice_path()?;
 
     // 2. 检查 ODT 缓存（使用独立的 cache/odt/ 目录）
     if let Some(cached_odt) = self.check_odt_cache(docx_path)? {
-      eprintln!("✅ 使用缓存 ODT: {:?}", cached_odt);
+      tracing::info!("使用缓存 ODT: {:?}", cached_odt);
       return Ok(cached_odt);
     }
 
     // 3. 执行转换
-    eprintln!("🔄 开始转换 DOCX → ODT: {:?}", docx_path);
+    tracing::debug!("开始转换 DOCX → ODT: {:?}", docx_path);
 
     // 创建临时输出目录
     let output_dir = self.odt_cache_dir.join("temp");
@@ -1215,7 +1354,7 @@ impl LibreOfficeService {
     // Contents
     {
       cmd.current_dir(&contents_dir);
-      eprintln!("📁 设置工作目录: {:?}", contents_dir);
+      tracing::debug!("设置工作目录: {:?}", contents_dir);
 
       // 设置 DYLD_LIBRARY_PATH 指向 LibreOffice 的库目录
       let frameworks_dir = contents_dir.join("Frameworks");
@@ -1258,7 +1397,7 @@ impl LibreOfficeService {
       .arg(&output_dir)
       .arg(docx_path);
 
-    eprintln!("📝 执行命令: {:?}", cmd);
+    tracing::debug!("执行命令: {:?}", cmd);
 
     let output = cmd
       .output()
@@ -1267,13 +1406,13 @@ impl LibreOfficeService {
     if !output.status.success() {
       let stderr = String::from_utf8_lossy(&output.stderr);
       let stdout = String::from_utf8_lossy(&output.stdout);
-      eprintln!("❌ LibreOffice 标准错误: {}", stderr);
-      eprintln!("❌ LibreOffice 标准输出: {}", stdout);
+      tracing::error!("LibreOffice 标准错误: {}", stderr);
+      tracing::error!("LibreOffice 标准输出: {}", stdout);
       return Err(format!("LibreOffice 转换失败: {}", stderr));
     }
 
     // 5. 查找生成的 ODT 文件
-    eprintln!("🔍 扫描输出目录查找 ODT 文件: {:?}", output_dir);
+    tracing::debug!("扫描输出目录查找 ODT 文件: {:?}", output_dir);
 
     let mut temp_odt_path: Option<PathBuf> = None;
 
@@ -1287,7 +1426,7 @@ impl LibreOfficeService {
       let expected_path = output_dir.join(filename);
       if expected_path.exists() {
         temp_odt_path = Some(expected_path);
-        eprintln!("✅ 找到预期的 ODT 文件: {:?}", temp_odt_path);
+        tracing::info!("找到预期的 ODT 文件: {:?}", temp_odt_path);
       }
     }
 
@@ -1302,7 +1441,7 @@ impl LibreOfficeService {
               if let Some(ext) = path.extension() {
                 if ext == "odt" {
                   temp_odt_path = Some(path);
-                  eprintln!("✅ 找到 ODT 文件: {:?}", temp_odt_path);
+                  tracing::info!("找到 ODT 文件: {:?}", temp_odt_path);
                   break;
                 }
               }
@@ -1326,7 +1465,7 @@ impl LibreOfficeService {
     // 清理临时文件
     let _ = fs::remove_file(&temp_odt_path);
 
-    eprintln!("✅ ODT 转换成功: {:?}", cached_odt_path);
+    tracing::info!("ODT 转换成功: {:?}", cached_odt_path);
 
     Ok(cached_odt_path)
   }
@@ -1387,8 +1526,19 @@ impl LibreOfficeService {
     Ok(format!("{:x}", hash))
   }
 
+  /// 登记新生成的预览 PDF（替换同一文件的旧版本，超出上限时按 LRU 淘汰）
+  fn record_cache(&self, source: &Path, cached_pdf_path: &Path) {
+    if let Err(e) = PreviewCache::new(self.cache_dir.clone()).record(source, cached_pdf_path) {
+      tracing::warn!("更新预览缓存索引失败: {}", e);
+    }
+  }
+
   /// 检查缓存
   fn check_cache(&self, file_path: &Path) -> Result<Option<PathBuf>, String> {
+    // 源文件修改后旧缓存不会再命中，直接删除
+    let preview_cache = PreviewCache::new(self.cache_dir.clone());
+    preview_cache.invalidate_if_modified(file_path);
+
     let cache_key = self.generate_cache_key(file_path)?;
     let cached_pdf_path = self.cache_dir.join(format!("{}.pdf", cache_key));
 
@@ -1406,6 +1556,7 @@ impl LibreOfficeService {
         .unwrap_or(Duration::from_secs(0));
 
       if elapsed < self.cache_duration {
+        preview_cache.touch(&cached_pdf_path);
         return Ok(Some(cached_pdf_path));
       } else {
         // 缓存过期，删除
@@ -1478,6 +1629,64 @@ pub fn get_global_libreoffice_service() -> Result<Arc<LibreOfficeService>, Strin
 mod tests {
   use super::*;
 
+  #[test]
+  fn office_documents_are_classified_by_extension() {
+    let kind = |name: &str| OfficeDocumentKind::from_path(Path::new(name));
+    assert_eq!(kind("report.DOCX"), Some(OfficeDocumentKind::Document));
+    assert_eq!(kind("notes.rtf"), Some(OfficeDocumentKind::Document));
+    assert_eq!(kind("budget.xls"), Some(OfficeDocumentKind::Spreadsheet));
+    assert_eq!(kind("data.ods"), Some(OfficeDocumentKind::Spreadsheet));
+    assert_eq!(kind("deck.ppsx"), Some(OfficeDocumentKind::Presentation));
+    assert_eq!(kind("slides.odp"), Some(OfficeDocumentKind::Presentation));
+    // CSV 由前端直接解析，不走 LibreOffice
+    assert_eq!(kind("table.csv"), None);
+    assert_eq!(kind("README"), None);
+    assert_eq!(OfficeDocumentKind::Spreadsheet.label(), "Excel 文件");
+  }
+
+  #[test]
+  fn conversion_progress_grows_but_never_completes() {
+    let expected = OfficeDocumentKind::Presentation.estimated_conversion_secs(4 * 1024 * 1024);
+    assert_eq!(expected, 14.0);
+    assert_eq!(estimate_conversion_progress(0.0, expected), 10);
+    let samples: Vec<u8> = [1.0, 7.0, 14.0, 60.0, 600.0]
+      .iter()
+      .map(|&t| estimate_conversion_progress(t, expected))
+      .collect();
+    assert!(samples.windows(2).all(|w| w[0] <= w[1]));
+    assert_eq!(samples[2], 64);
+    assert_eq!(*samples.last().unwrap(), 95);
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn reads_the_first_version_line() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = std::env::temp_dir().join(format!("binder-soffice-{}", uuid::Uuid::new_v4()));
+    fs::create_dir_all(&dir).unwrap();
+    let script = |name: &str, body: &str| {
+      let path = dir.join(name);
+      fs::write(&path, format!("#!/bin/sh\n{}\n", body)).unwrap();
+      fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+      path
+    };
+
+    let soffice = script(
+      "soffice",
+      "printf '\\nLibreOffice 7.6.4.1 60(Build:1)\\nextra\\n'",
+    );
+    assert_eq!(
+      LibreOfficeService::get_version(&soffice).as_deref(),
+      Some("LibreOffice 7.6.4.1 60(Build:1)")
+    );
+    let broken = script("broken", "exit 1");
+    assert_eq!(LibreOfficeService::get_version(&broken), None);
+    assert_eq!(LibreOfficeService::get_version(&dir.join("missing")), None);
+
+    let _ = fs::remove_dir_all(&dir);
+  }
+
   #[test]
   fn test_libreoffice_service_new() {
     // 这个测试需要实际环境，暂时跳过
@@ -0,0 +1,585 @@
+//! 文档链接图：解析工作区 Markdown 文档中指向本地文件的链接，记录文档之间的引用关系，
+//! 用于反向链接（哪些文档引用了当前文档）与关系图展示。
+//!
+//! Wiki 链接（`[[笔记]]`）保存笔记名，查询时按笔记索引（文件名、title、aliases）解析，
+//! 因此笔记新建、删除或重命名后，指向它的 Wiki 链接随之更新。
+//!
+//! 每个工作区在内存中保存一份图：首次查询时全量扫描，之后由文件监听的变化批次增量更新；
+//! 变化批次溢出时丢弃整张图，下次查询重新扫描。
+
+use crate::services::file_watcher::{FileChangeKind, WorkspaceFileChange};
+use crate::services::image_references::{has_extension, workspace_files};
+use crate::services::wikilink::{self, WikiLink};
+use crate::services::workspace_settings::WorkspaceSettings;
+use crate::utils::glob_patterns::{build_glob_set, relative_slash_path};
+use once_cell::sync::Lazy;
+use pulldown_cmark::{Event, LinkType, Options, Parser, Tag, TagEnd};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// 会被解析链接的文档类型
+pub const LINK_SOURCE_EXTENSIONS: [&str; 2] = ["md", "markdown"];
+
+/// 链接所在行的上下文最多保留的字符数
+const CONTEXT_MAX_CHARS: usize = 200;
+
+static GRAPHS: Lazy<Mutex<HashMap<PathBuf, LinkGraph>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkKind {
+  Markdown, // [文字](路径)
+  Wiki,     // [[笔记]]
+}
+
+/// 文档中的一个本地链接；路径均相对工作区根目录（`/` 分隔）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DocumentLink {
+  pub kind: LinkKind,
+  /// Markdown 链接为目标路径，Wiki 链接为笔记名
+  pub target: String,
+  /// `#` 之后的锚点（如标题 id）
+  pub anchor: Option<String>,
+  /// 链接文字
+  pub text: String,
+  /// 链接所在行（从 1 开始）
+  pub line: usize,
+  /// 链接所在行的文本
+  pub context: String,
+}
+
+/// 指向某个文档的一条反向链接
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Backlink {
+  pub source: String,
+  pub kind: LinkKind,
+  pub anchor: Option<String>,
+  pub text: String,
+  pub line: usize,
+  pub context: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LinkNode {
+  pub path: String,
+  /// 链接目标不存在时为 false（失效链接）
+  pub exists: bool,
+  pub outgoing: usize,
+  pub incoming: usize,
+}
+
+/// 两个文档之间的链接（同一对文档的多个链接合并为一条，count 为链接数）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LinkEdge {
+  pub source: String,
+  pub target: String,
+  pub count: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LinkGraphSnapshot {
+  pub nodes: Vec<LinkNode>,
+  pub edges: Vec<LinkEdge>,
+}
+
+/// 一个工作区的链接图：文档相对路径 → 其中的链接，以及供 Wiki 链接解析的笔记索引
+#[derive(Debug, Default)]
+pub struct LinkGraph {
+  outgoing: BTreeMap<String, Vec<DocumentLink>>,
+  /// 文档相对路径 → 可被 Wiki 链接匹配的名字
+  note_names: BTreeMap<String, Vec<String>>,
+  /// 名字 → 文档相对路径
+  notes_by_name: HashMap<String, BTreeSet<String>>,
+}
+
+impl LinkGraph {
+  /// 全量扫描工作区（跳过隐藏目录与 `watcher.ignore_patterns` 匹配的文件）
+  pub fn build(workspace_path: &Path) -> Self {
+    let ignore = build_glob_set(
+      &WorkspaceSettings::load_or_default(workspace_path)
+        .watcher
+        .ignore_patterns,
+    )
+    .unwrap_or_default();
+    let mut graph = LinkGraph::default();
+    for path in workspace_files(workspace_path)
+      .filter(|p| has_extension(p, &LINK_SOURCE_EXTENSIONS))
+      .filter(|p| !ignore.is_match(relative_slash_path(p, workspace_path)))
+    {
+      graph.index_file(workspace_path, &path);
+    }
+    graph
+  }
+
+  /// 重新解析一个文档；读取失败（已删除或不是文本）时移除其链接
+  fn index_file(&mut self, workspace_path: &Path, path: &Path) {
+    let source = relative_slash_path(path, workspace_path);
+    match std::fs::read_to_string(path) {
+      Ok(content) => {
+        self.set_note_names(&source, wikilink::note_keys(&source, &content));
+        let links = extract_links(&content, &source);
+        if links.is_empty() {
+          self.outgoing.remove(&source);
+        } else {
+          self.outgoing.insert(source, links);
+        }
+      }
+      Err(_) => self.remove_path(&source),
+    }
+  }
+
+  fn set_note_names(&mut self, source: &str, names: Vec<String>) {
+    self.remove_note_names(source);
+    for name in &names {
+      self
+        .notes_by_name
+        .entry(name.clone())
+        .or_default()
+        .insert(source.to_string());
+    }
+    self.note_names.insert(source.to_string(), names);
+  }
+
+  fn remove_note_names(&mut self, source: &str) {
+    for name in self.note_names.remove(source).unwrap_or_default() {
+      if let Some(paths) = self.notes_by_name.get_mut(&name) {
+        paths.remove(source);
+        if paths.is_empty() {
+          self.notes_by_name.remove(&name);
+        }
+      }
+    }
+  }
+
+  /// 移除文档及（为目录时）其下全部文档的链接与笔记名
+  fn remove_path(&mut self, relative: &str) {
+    let prefix = format!("{}/", relative);
+    let inside = |source: &String| source == relative || source.starts_with(&prefix);
+    self.outgoing.retain(|source, _| !inside(source));
+    let removed: Vec<String> = self
+      .note_names
+      .keys()
+      .filter(|source| inside(source))
+      .cloned()
+      .collect();
+    for source in removed {
+      self.remove_note_names(&source);
+    }
+  }
+
+  /// Wiki 链接可能指向的笔记，按目录层级由浅到深排序；含 `/` 时按相对路径匹配（可省略扩展名）
+  pub fn find_notes(&self, name: &str) -> Vec<String> {
+    let key = wikilink::note_key(name.trim_start_matches('/'));
+    let mut paths: Vec<String> = if key.contains('/') {
+      self
+        .note_names
+        .keys()
+        .filter(|path| wikilink::note_key(path) == key)
+        .cloned()
+        .collect()
+    } else {
+      self
+        .notes_by_name
+        .get(&key)
+        .map(|paths| paths.iter().cloned().collect())
+        .unwrap_or_default()
+    };
+    paths.sort_by_key(|path| path.matches('/').count());
+    paths
+  }
+
+  /// 链接指向的文档相对路径；Wiki 链接找不到笔记时返回 None
+  fn resolve(&self, link: &DocumentLink) -> Option<String> {
+    match link.kind {
+      LinkKind::Markdown => Some(link.target.clone()),
+      LinkKind::Wiki => self.find_notes(&link.target).into_iter().next(),
+    }
+  }
+
+  /// 按监听到的变化更新（参考资料文件夹中的变化忽略）
+  pub fn apply_changes(&mut self, workspace_path: &Path, changes: &[WorkspaceFileChange]) {
+    for change in changes.iter().filter(|c| c.reference_root.is_none()) {
+      if let Some(old_path) = &change.old_path {
+        self.remove_path(old_path);
+      }
+      let path = Path::new(&change.absolute_path);
+      match change.kind {
+        FileChangeKind::Deleted => self.remove_path(&change.path),
+        _ if change.is_dir => {
+          for file in workspace_files(path).filter(|p| has_extension(p, &LINK_SOURCE_EXTENSIONS)) {
+            self.index_file(workspace_path, &file);
+          }
+        }
+        _ if has_extension(path, &LINK_SOURCE_EXTENSIONS) => self.index_file(workspace_path, path),
+        _ => {}
+      }
+    }
+  }
+
+  /// 链接到 target（相对路径）的全部位置，按来源文档与行号排序
+  pub fn backlinks(&self, target: &str) -> Vec<Backlink> {
+    self
+      .outgoing
+      .iter()
+      .flat_map(|(source, links)| {
+        links
+          .iter()
+          .filter(|link| self.resolve(link).as_deref() == Some(target))
+          .map(move |link| Backlink {
+            source: source.clone(),
+            kind: link.kind,
+            anchor: link.anchor.clone(),
+            text: link.text.clone(),
+            line: link.line,
+            context: link.context.clone(),
+          })
+      })
+      .collect()
+  }
+
+  /// 导出节点与边；exists 按当前文件系统判断，找不到笔记的 Wiki 链接以笔记名作为目标节点
+  pub fn snapshot(&self, workspace_path: &Path) -> LinkGraphSnapshot {
+    let mut edges: BTreeMap<(String, String), usize> = BTreeMap::new();
+    for (source, links) in &self.outgoing {
+      for link in links {
+        let target = self.resolve(link).unwrap_or_else(|| link.target.clone());
+        if &target != source {
+          *edges.entry((source.clone(), target)).or_default() += 1;
+        }
+      }
+    }
+
+    let mut nodes: BTreeMap<String, (usize, usize)> = BTreeMap::new();
+    for (source, target) in edges.keys() {
+      nodes.entry(source.clone()).or_default().0 += 1;
+      nodes.entry(target.clone()).or_default().1 += 1;
+    }
+    LinkGraphSnapshot {
+      nodes: nodes
+        .into_iter()
+        .map(|(path, (outgoing, incoming))| LinkNode {
+          exists: workspace_path.join(&path).exists(),
+          path,
+          outgoing,
+          incoming,
+        })
+        .collect(),
+      edges: edges
+        .into_iter()
+        .map(|((source, target), count)| LinkEdge {
+          source,
+          target,
+          count,
+        })
+        .collect(),
+    }
+  }
+}
+
+/// 解析 Markdown 中的本地链接（不含图片、外部网址与页内锚点），source 为文档相对路径
+pub fn extract_links(content: &str, source: &str) -> Vec<DocumentLink> {
+  let options = Options::ENABLE_TABLES
+    | Options::ENABLE_STRIKETHROUGH
+    | Options::ENABLE_TASKLISTS
+    | Options::ENABLE_FOOTNOTES
+    | Options::ENABLE_YAML_STYLE_METADATA_BLOCKS
+    | Options::ENABLE_WIKILINKS;
+  let mut links = Vec::new();
+  // 正在收集文字的链接：(类型, 目标, 锚点, 起始字节, 文字)
+  let mut current: Option<(LinkKind, String, Option<String>, usize, String)> = None;
+  for (event, range) in Parser::new_ext(content, options).into_offset_iter() {
+    match event {
+      Event::Start(Tag::Link {
+        link_type: LinkType::WikiLink { .. },
+        dest_url,
+        ..
+      }) => {
+        current = WikiLink::parse(&dest_url).map(|link| {
+          (
+            LinkKind::Wiki,
+            link.target,
+            link.heading,
+            range.start,
+            String::new(),
+          )
+        });
+      }
+      Event::Start(Tag::Link { dest_url, .. }) => {
+        current = resolve_link(source, &dest_url).map(|(target, anchor)| {
+          (
+            LinkKind::Markdown,
+            target,
+            anchor,
+            range.start,
+            String::new(),
+          )
+        });
+      }
+      Event::Text(text) | Event::Code(text) => {
+        if let Some((_, _, _, _, link_text)) = current.as_mut() {
+          link_text.push_str(&text);
+        }
+      }
+      Event::End(TagEnd::Link) => {
+        if let Some((kind, target, anchor, start, text)) = current.take() {
+          let line_start = content[..start].rfind('\n').map_or(0, |i| i + 1);
+          let line_end = content[start..]
+            .find('\n')
+            .map_or(content.len(), |i| start + i);
+          links.push(DocumentLink {
+            kind,
+            target,
+            anchor,
+            text,
+            line: content[..start].matches('\n').count() + 1,
+            context: content[line_start..line_end]
+              .trim()
+              .chars()
+              .take(CONTEXT_MAX_CHARS)
+              .collect(),
+          });
+        }
+      }
+      _ => {}
+    }
+  }
+  links
+}
+
+/// 把链接地址解析为工作区相对路径与锚点；外部链接、页内锚点或超出工作区时返回 None
+fn resolve_link(source: &str, dest: &str) -> Option<(String, Option<String>)> {
+  let dest = dest.trim();
+  let has_scheme = dest.split_once(':').is_some_and(|(scheme, _)| {
+    !scheme.is_empty()
+      && scheme
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || "+.-".contains(c))
+  });
+  if dest.is_empty() || dest.starts_with('#') || has_scheme {
+    return None;
+  }
+  let (path, anchor) = match dest.split_once('#') {
+    Some((path, anchor)) => (path, Some(percent_decode(anchor))),
+    None => (dest, None),
+  };
+  let path = path.split('?').next().unwrap_or_default();
+  let path = percent_decode(path);
+
+  // 以 `/` 开头的地址相对工作区根目录，其余相对文档所在目录
+  let mut segments: Vec<&str> = if path.starts_with('/') {
+    Vec::new()
+  } else {
+    source.split('/').collect::<Vec<_>>()
+  };
+  segments.pop();
+  for segment in path.split(['/', '\\']) {
+    match segment {
+      "" | "." => {}
+      ".." => {
+        segments.pop()?;
+      }
+      other => segments.push(other),
+    }
+  }
+  (!segments.is_empty()).then(|| (segments.join("/"), anchor.filter(|a| !a.is_empty())))
+}
+
+/// 解码 `%20` 等百分号转义；不是合法 UTF-8 时保留原文
+fn percent_decode(value: &str) -> String {
+  let bytes = value.as_bytes();
+  let mut decoded = Vec::with_capacity(bytes.len());
+  let mut i = 0;
+  while i < bytes.len() {
+    let hex = (bytes[i] == b'%')
+      .then(|| value.get(i + 1..i + 3))
+      .flatten()
+      .and_then(|h| u8::from_str_radix(h, 16).ok());
+    match hex {
+      Some(byte) => {
+        decoded.push(byte);
+        i += 3;
+      }
+      None => {
+        decoded.push(bytes[i]);
+        i += 1;
+      }
+    }
+  }
+  String::from_utf8(decoded).unwrap_or_else(|_| value.to_string())
+}
+
+fn with_graph<T>(workspace_path: &Path, f: impl FnOnce(&LinkGraph) -> T) -> T {
+  let mut graphs = GRAPHS.lock().unwrap_or_else(|e| e.into_inner());
+  let graph = graphs
+    .entry(workspace_path.to_path_buf())
+    .or_insert_with(|| LinkGraph::build(workspace_path));
+  f(graph)
+}
+
+/// 链接到 path（绝对路径或相对工作区的路径）的全部反向链接
+pub fn get_backlinks(workspace_path: &Path, path: &Path) -> Vec<Backlink> {
+  let target = relative_slash_path(path, workspace_path);
+  with_graph(workspace_path, |graph| graph.backlinks(&target))
+}
+
+pub fn get_link_graph(workspace_path: &Path) -> LinkGraphSnapshot {
+  with_graph(workspace_path, |graph| graph.snapshot(workspace_path))
+}
+
+/// Wiki 链接可能指向的笔记（相对路径）
+pub fn find_notes(workspace_path: &Path, name: &str) -> Vec<String> {
+  with_graph(workspace_path, |graph| graph.find_notes(name))
+}
+
+/// 立即重新解析一个文档（如刚新建的笔记），不必等待文件监听；图尚未建立时不处理
+pub fn refresh_file(workspace_path: &Path, path: &Path) {
+  let mut graphs = GRAPHS.lock().unwrap_or_else(|e| e.into_inner());
+  if let Some(graph) = graphs.get_mut(workspace_path) {
+    graph.index_file(workspace_path, path);
+  }
+}
+
+/// 处理文件监听的变化批次；尚未建立的图不处理（查询时再全量扫描），overflowed 时丢弃重建
+pub fn apply_changes(workspace_path: &Path, changes: &[WorkspaceFileChange], overflowed: bool) {
+  let mut graphs = GRAPHS.lock().unwrap_or_else(|e| e.into_inner());
+  if overflowed {
+    graphs.remove(workspace_path);
+  } else if let Some(graph) = graphs.get_mut(workspace_path) {
+    graph.apply_changes(workspace_path, changes);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::fs;
+
+  #[test]
+  fn extracts_local_links_relative_to_the_document() {
+    let content = "# 标题\n\n见 [第二章](../chapters/two%20b.md#intro) 与 [附录](/appendix.md)。\n\
+                   [网站](https://example.com) [锚点](#top) [邮件](mailto:a@b.c) ![图](a.png)\n\
+                   [越界](../../outside.md) [引用式][ref]\n\n[ref]: ./same.md?x=1\n";
+    let links = extract_links(content, "notes/index.md");
+    let targets: Vec<(&str, Option<&str>, usize)> = links
+      .iter()
+      .map(|l| (l.target.as_str(), l.anchor.as_deref(), l.line))
+      .collect();
+    assert_eq!(
+      targets,
+      vec![
+        ("chapters/two b.md", Some("intro"), 3),
+        ("appendix.md", None, 3),
+        ("notes/same.md", None, 5),
+      ]
+    );
+    assert_eq!(links[0].text, "第二章");
+    assert!(links[0].context.starts_with("见 [第二章]"));
+  }
+
+  #[test]
+  fn backlinks_follow_incremental_changes() {
+    let workspace = std::env::temp_dir().join(format!("binder_links_{}", uuid::Uuid::new_v4()));
+    fs::create_dir_all(workspace.join("docs")).unwrap();
+    fs::write(workspace.join("target.md"), "# 目标").unwrap();
+    fs::write(workspace.join("docs/a.md"), "[目标](../target.md)").unwrap();
+    fs::write(workspace.join(".binder_note.md"), "[隐藏](target.md)").unwrap();
+
+    let mut graph = LinkGraph::build(&workspace);
+    let sources = |graph: &LinkGraph| {
+      graph
+        .backlinks("target.md")
+        .into_iter()
+        .map(|b| b.source)
+        .collect::<Vec<_>>()
+    };
+    assert_eq!(sources(&graph), vec!["docs/a.md"]);
+
+    let change = |kind, path: &str, old_path: Option<&str>| WorkspaceFileChange {
+      kind,
+      path: path.to_string(),
+      old_path: old_path.map(str::to_string),
+      absolute_path: workspace.join(path).to_string_lossy().to_string(),
+      old_absolute_path: None,
+      is_dir: workspace.join(path).is_dir(),
+      reference_root: None,
+    };
+    fs::write(workspace.join("b.md"), "[1](target.md) [2](./target.md#x)").unwrap();
+    fs::rename(workspace.join("docs"), workspace.join("moved")).unwrap();
+    graph.apply_changes(
+      &workspace,
+      &[
+        change(FileChangeKind::Created, "b.md", None),
+        change(FileChangeKind::Renamed, "moved", Some("docs")),
+      ],
+    );
+    assert_eq!(sources(&graph), vec!["b.md", "b.md", "moved/a.md"]);
+
+    let snapshot = graph.snapshot(&workspace);
+    let edge = snapshot.edges.iter().find(|e| e.source == "b.md").unwrap();
+    assert_eq!(edge.count, 2);
+    assert!(snapshot.nodes.iter().all(|n| n.exists));
+
+    fs::remove_file(workspace.join("b.md")).unwrap();
+    graph.apply_changes(&workspace, &[change(FileChangeKind::Deleted, "b.md", None)]);
+    assert_eq!(sources(&graph), vec!["moved/a.md"]);
+    let _ = fs::remove_dir_all(&workspace);
+  }
+
+  #[test]
+  fn wiki_links_resolve_by_note_name_and_follow_renames() {
+    let workspace =
+      std::env::temp_dir().join(format!("binder_wiki_links_{}", uuid::Uuid::new_v4()));
+    fs::create_dir_all(workspace.join("notes")).unwrap();
+    fs::write(
+      workspace.join("notes/Plan.md"),
+      "---\naliases: [路线图]\n---\n",
+    )
+    .unwrap();
+    fs::write(
+      workspace.join("index.md"),
+      "[[plan]] 与 [[路线图#里程碑|路线]]\n\n[[缺失的笔记]] `[[代码中]]`",
+    )
+    .unwrap();
+
+    let mut graph = LinkGraph::build(&workspace);
+    let backlinks = graph.backlinks("notes/Plan.md");
+    assert_eq!(backlinks.len(), 2);
+    assert!(backlinks.iter().all(|b| b.kind == LinkKind::Wiki));
+    assert_eq!(backlinks[1].anchor.as_deref(), Some("里程碑"));
+    assert_eq!(backlinks[1].text, "路线");
+    let snapshot = graph.snapshot(&workspace);
+    assert_eq!(snapshot.edges.len(), 2);
+    assert!(snapshot
+      .nodes
+      .iter()
+      .any(|n| n.path == "缺失的笔记" && !n.exists));
+
+    // 重命名后文件名不再匹配，别名仍然匹配
+    fs::rename(
+      workspace.join("notes/Plan.md"),
+      workspace.join("notes/Roadmap.md"),
+    )
+    .unwrap();
+    graph.apply_changes(
+      &workspace,
+      &[WorkspaceFileChange {
+        kind: FileChangeKind::Renamed,
+        path: "notes/Roadmap.md".to_string(),
+        old_path: Some("notes/Plan.md".to_string()),
+        absolute_path: workspace
+          .join("notes/Roadmap.md")
+          .to_string_lossy()
+          .to_string(),
+        old_absolute_path: None,
+        is_dir: false,
+        reference_root: None,
+      }],
+    );
+    assert!(graph.backlinks("notes/Plan.md").is_empty());
+    assert_eq!(graph.backlinks("notes/Roadmap.md").len(), 1);
+    assert_eq!(graph.find_notes("notes/roadmap"), vec!["notes/Roadmap.md"]);
+    let _ = fs::remove_dir_all(&workspace);
+  }
+}
@@ -45,7 +45,7 @@ impl LoopDetector {
     for recent in &self.recent_replies {
       let trimmed_recent = recent.trim();
       if trimmed_current == trimmed_recent {
-        eprintln!("⚠️ 检测到内容重复：与最近回复相同");
+        tracing::warn!("检测到内容重复：与最近回复相同");
         return true;
       }
     }
@@ -68,7 +68,7 @@ impl LoopDetector {
         // 检查最近回复中是否也包含这个短语
         for recent in &self.recent_replies {
           if recent.contains(phrase) {
-            eprintln!("⚠️ 检测到语义重复：关键短语 '{}' 重复出现", phrase);
+            tracing::warn!("检测到语义重复：关键短语 '{}' 重复出现", phrase);
             return true;
           }
         }
@@ -94,7 +94,7 @@ impl LoopDetector {
     // 检查是否与最近N次工具调用相同
     for recent in &self.recent_tool_calls {
       if *recent == record {
-        eprintln!("⚠️ 检测到工具调用循环：{} 使用相同参数重复调用", tool_name);
+        tracing::warn!("检测到工具调用循环：{} 使用相同参数重复调用", tool_name);
         return true;
       }
     }
@@ -0,0 +1,615 @@
+//! Markdown 格式化与检查：标题前后空行、无序列表标记、表格列对齐、长行折行、行尾空白与多余空行。
+//!
+//! 先用 pulldown-cmark 解析出各块元素的位置，再按行修改原文，不重新渲染整篇文档，
+//! 因此未涉及的内容（强调写法、链接形式等）保持原样。代码块、HTML 块与 front matter 不做任何修改；
+//! 标题空行、表格对齐与折行只处理顶层块，不改动列表、引用中的内容。
+
+use pulldown_cmark::{Alignment, Event, Options, Parser, Tag, TagEnd};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+
+/// 格式化规则
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MarkdownFormatRules {
+  /// 标题前后各空一行，`#` 后只保留一个空格
+  pub heading_spacing: bool,
+  /// 无序列表统一使用的标记（`-`、`*`、`+`），None 时不修改
+  pub list_marker: Option<char>,
+  /// 按列宽对齐表格（中日韩文字按两列计）
+  pub align_tables: bool,
+  /// 顶层段落的最大行宽，超出时在空白处折行；None 时不折行
+  pub wrap_width: Option<usize>,
+  /// 删除行尾空白（行尾两个以上空格表示的硬换行保留）
+  pub trim_trailing_whitespace: bool,
+  /// 最多连续的空行数
+  pub max_blank_lines: usize,
+  /// 文件以且仅以一个换行结尾
+  pub final_newline: bool,
+}
+
+impl Default for MarkdownFormatRules {
+  fn default() -> Self {
+    Self {
+      heading_spacing: true,
+      list_marker: Some('-'),
+      align_tables: true,
+      wrap_width: None,
+      trim_trailing_whitespace: true,
+      max_blank_lines: 1,
+      final_newline: true,
+    }
+  }
+}
+
+impl MarkdownFormatRules {
+  pub fn validate(&self) -> Result<(), String> {
+    if let Some(marker) = self.list_marker {
+      if !matches!(marker, '-' | '*' | '+') {
+        return Err(format!("列表标记只能是 -、* 或 +: {}", marker));
+      }
+    }
+    if self.wrap_width.is_some_and(|width| width < 20) {
+      return Err("折行宽度不能小于 20".to_string());
+    }
+    if self.max_blank_lines == 0 {
+      return Err("最多连续空行数不能为 0".to_string());
+    }
+    Ok(())
+  }
+}
+
+/// 一处不符合规则的位置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MarkdownViolation {
+  /// 规则名，与 MarkdownFormatRules 的字段名一致
+  pub rule: String,
+  /// 原文中的行号（从 1 开始）
+  pub line: usize,
+  pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarkdownFormatResult {
+  /// 格式化后的内容；仅检查时为 None
+  pub content: Option<String>,
+  pub changed: bool,
+  /// 原文中的问题（按行号排序）
+  pub violations: Vec<MarkdownViolation>,
+}
+
+/// 原文的块结构（行号从 0 开始）
+#[derive(Default)]
+struct BlockLayout {
+  /// 代码块、HTML 块与 front matter 所在行
+  protected: HashSet<usize>,
+  /// 顶层标题所占的行
+  headings: Vec<Range<usize>>,
+  /// 无序列表项标记的字节位置
+  bullet_markers: Vec<usize>,
+  /// 顶层表格所占的行与各列对齐方式
+  tables: Vec<(Range<usize>, Vec<Alignment>)>,
+  /// 可以折行的顶层段落（不含硬换行与行内 HTML）
+  paragraphs: Vec<Range<usize>>,
+}
+
+/// 偏移所在行（从 0 开始）
+fn line_of(line_starts: &[usize], offset: usize) -> usize {
+  line_starts.partition_point(|&start| start <= offset) - 1
+}
+
+fn analyze(body: &str, line_starts: &[usize]) -> BlockLayout {
+  let options = Options::ENABLE_TABLES
+    | Options::ENABLE_STRIKETHROUGH
+    | Options::ENABLE_TASKLISTS
+    | Options::ENABLE_FOOTNOTES
+    | Options::ENABLE_YAML_STYLE_METADATA_BLOCKS;
+  let mut layout = BlockLayout::default();
+  let lines_of = |range: &Range<usize>| {
+    line_of(line_starts, range.start)..line_of(line_starts, range.end.max(range.start + 1) - 1) + 1
+  };
+  // 列表、引用、脚注定义的嵌套层数
+  let mut depth = 0usize;
+  let mut unordered_lists: Vec<bool> = Vec::new();
+  // 当前顶层段落及其是否可以折行
+  let mut paragraph: Option<(Range<usize>, bool)> = None;
+
+  for (event, range) in Parser::new_ext(body, options).into_offset_iter() {
+    match event {
+      Event::Start(Tag::CodeBlock(_) | Tag::HtmlBlock | Tag::MetadataBlock(_)) => {
+        layout.protected.extend(lines_of(&range));
+      }
+      Event::Start(Tag::List(start)) => {
+        depth += 1;
+        unordered_lists.push(start.is_none());
+      }
+      Event::End(TagEnd::List(_)) => {
+        depth -= 1;
+        unordered_lists.pop();
+      }
+      Event::Start(Tag::Item) if unordered_lists.last() == Some(&true) => {
+        layout.bullet_markers.push(range.start);
+      }
+      Event::Start(Tag::BlockQuote(_) | Tag::FootnoteDefinition(_)) => depth += 1,
+      Event::End(TagEnd::BlockQuote(_) | TagEnd::FootnoteDefinition) => depth -= 1,
+      _ if depth > 0 => {}
+      Event::Start(Tag::Heading { .. }) => layout.headings.push(lines_of(&range)),
+      Event::Start(Tag::Table(alignments)) => layout.tables.push((lines_of(&range), alignments)),
+      Event::Start(Tag::Paragraph) => paragraph = Some((lines_of(&range), true)),
+      Event::HardBreak | Event::InlineHtml(_) | Event::Html(_) => {
+        if let Some((_, wrappable)) = paragraph.as_mut() {
+          *wrappable = false;
+        }
+      }
+      Event::End(TagEnd::Paragraph) => {
+        if let Some((lines, true)) = paragraph.take() {
+          layout.paragraphs.push(lines);
+        }
+      }
+      _ => {}
+    }
+  }
+  layout
+}
+
+/// 显示宽度：中日韩文字与全角符号按两列计
+fn display_width(text: &str) -> usize {
+  text
+    .chars()
+    .map(|ch| match ch as u32 {
+      0x1100..=0x115F
+      | 0x2E80..=0x303E
+      | 0x3040..=0xA4CF
+      | 0xAC00..=0xD7A3
+      | 0xF900..=0xFAFF
+      | 0xFE30..=0xFE4F
+      | 0xFF00..=0xFF60
+      | 0xFFE0..=0xFFE6
+      | 0x20000..=0x3FFFD => 2,
+      _ => 1,
+    })
+    .sum()
+}
+
+fn is_blank(line: &str) -> bool {
+  line.trim().is_empty()
+}
+
+/// 规范 ATX 标题：去掉行首缩进，`#` 后只保留一个空格；不是 ATX 标题时返回 None
+fn normalize_atx_heading(line: &str) -> Option<String> {
+  let trimmed = line.trim_start();
+  let hashes = trimmed.len() - trimmed.trim_start_matches('#').len();
+  if !(1..=6).contains(&hashes) {
+    return None;
+  }
+  let text = trimmed[hashes..].trim();
+  Some(if text.is_empty() {
+    trimmed[..hashes].to_string()
+  } else {
+    format!("{} {}", &trimmed[..hashes], text)
+  })
+}
+
+/// 按未转义的 `|` 拆分表格行
+fn split_table_row(line: &str) -> Vec<String> {
+  let row = line.trim();
+  let row = row.strip_prefix('|').unwrap_or(row);
+  let row = match row.strip_suffix('|') {
+    Some(rest) if !rest.ends_with('\\') => rest,
+    _ => row,
+  };
+  let mut cells = Vec::new();
+  let mut cell = String::new();
+  let mut escaped = false;
+  for ch in row.chars() {
+    if ch == '|' && !escaped {
+      cells.push(cell.trim().to_string());
+      cell.clear();
+    } else {
+      cell.push(ch);
+    }
+    escaped = ch == '\\' && !escaped;
+  }
+  cells.push(cell.trim().to_string());
+  cells
+}
+
+/// 对齐表格：第二行为分隔行，按各列最宽的单元格补齐
+fn align_table(lines: &[String], alignments: &[Alignment]) -> Vec<String> {
+  let columns = alignments.len();
+  let rows: Vec<Vec<String>> = lines
+    .iter()
+    .enumerate()
+    .map(|(index, line)| {
+      let mut cells = if index == 1 {
+        Vec::new()
+      } else {
+        split_table_row(line)
+      };
+      cells.resize(columns, String::new());
+      cells
+    })
+    .collect();
+  let widths: Vec<usize> = (0..columns)
+    .map(|column| {
+      rows
+        .iter()
+        .map(|row| display_width(&row[column]))
+        .max()
+        .unwrap_or(0)
+        .max(3)
+    })
+    .collect();
+
+  rows
+    .iter()
+    .enumerate()
+    .map(|(index, row)| {
+      let cells: Vec<String> = (0..columns)
+        .map(|column| {
+          let width = widths[column];
+          if index == 1 {
+            return match alignments[column] {
+              Alignment::Left => format!(":{}", "-".repeat(width - 1)),
+              Alignment::Center => format!(":{}:", "-".repeat(width - 2)),
+              Alignment::Right => format!("{}:", "-".repeat(width - 1)),
+              Alignment::None => "-".repeat(width),
+            };
+          }
+          let cell = &row[column];
+          let padding = width - display_width(cell);
+          match alignments[column] {
+            Alignment::Right => format!("{}{}", " ".repeat(padding), cell),
+            Alignment::Center => format!(
+              "{}{}{}",
+              " ".repeat(padding / 2),
+              cell,
+              " ".repeat(padding - padding / 2)
+            ),
+            _ => format!("{}{}", cell, " ".repeat(padding)),
+          }
+        })
+        .collect();
+      format!("| {} |", cells.join(" | "))
+    })
+    .collect()
+}
+
+/// 行首出现时会被解析为其他块的单词（标题、列表、引用、表格等）
+fn starts_block(word: &str) -> bool {
+  let digits = word.len() - word.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+  matches!(
+    word.chars().next(),
+    Some('#' | '-' | '*' | '+' | '>' | '=' | '|' | '`' | '~')
+  ) || (digits > 0 && matches!(word[digits..].chars().next(), Some('.' | ')')))
+}
+
+/// 段落按空白重新折行；单个词超过行宽时单独成行
+fn wrap_paragraph(lines: &[String], width: usize) -> Vec<String> {
+  let mut wrapped: Vec<String> = Vec::new();
+  let mut current = String::new();
+  for word in lines.iter().flat_map(|line| line.split_whitespace()) {
+    let fits = display_width(&current) + 1 + display_width(word) <= width;
+    if current.is_empty() || fits || starts_block(word) {
+      if !current.is_empty() {
+        current.push(' ');
+      }
+      current.push_str(word);
+    } else {
+      wrapped.push(std::mem::take(&mut current));
+      current.push_str(word);
+    }
+  }
+  if !current.is_empty() {
+    wrapped.push(current);
+  }
+  wrapped
+}
+
+/// 保留行尾的硬换行（两个以上空格且下一行不是空行），其余行尾空白删除
+fn trim_trailing(line: &str, next: Option<&str>) -> String {
+  let trimmed = line.trim_end_matches([' ', '\t']);
+  let hard_break = line.ends_with("  ") && !is_blank(line) && next.is_some_and(|n| !is_blank(n));
+  if hard_break {
+    line.to_string()
+  } else {
+    trimmed.to_string()
+  }
+}
+
+/// 检查并（check_only 为 false 时）格式化 Markdown
+pub fn format_markdown(
+  content: &str,
+  rules: &MarkdownFormatRules,
+  check_only: bool,
+) -> MarkdownFormatResult {
+  let crlf = content.contains("\r\n");
+  let body = if crlf {
+    content.replace("\r\n", "\n")
+  } else {
+    content.to_string()
+  };
+  let lines: Vec<String> = body.lines().map(str::to_string).collect();
+  let line_starts: Vec<usize> = std::iter::once(0)
+    .chain(body.match_indices('\n').map(|(i, _)| i + 1))
+    .take(lines.len().max(1))
+    .collect();
+  let layout = analyze(&body, &line_starts);
+
+  let mut violations = Vec::new();
+  let mut violation = |rule: &str, line: usize, message: String| {
+    violations.push(MarkdownViolation {
+      rule: rule.to_string(),
+      line: line + 1,
+      message,
+    })
+  };
+  // 行内修改（行号 → 新内容）、整段替换（起始行 → (结束行, 新行)）与需要补的空行
+  let mut edits: HashMap<usize, String> = HashMap::new();
+  let mut replacements: HashMap<usize, (usize, Vec<String>)> = HashMap::new();
+  let mut blank_before: HashSet<usize> = HashSet::new();
+  let mut blank_after: HashSet<usize> = HashSet::new();
+
+  if rules.heading_spacing {
+    for heading in &layout.headings {
+      let (first, last) = (heading.start, heading.end - 1);
+      if let Some(normalized) = normalize_atx_heading(&lines[first]) {
+        if normalized != lines[first] {
+          violation(
+            "heading_spacing",
+            first,
+            "标题的 # 后应只有一个空格且不缩进".to_string(),
+          );
+          edits.insert(first, normalized);
+        }
+      }
+      if first > 0 && !is_blank(&lines[first - 1]) {
+        violation("heading_spacing", first, "标题前应空一行".to_string());
+        blank_before.insert(first);
+      }
+      if last + 1 < lines.len() && !is_blank(&lines[last + 1]) {
+        violation("heading_spacing", last, "标题后应空一行".to_string());
+        blank_after.insert(last);
+      }
+    }
+  }
+
+  if let Some(marker) = rules.list_marker {
+    for &offset in &layout.bullet_markers {
+      let current = body[offset..].chars().next().unwrap_or(marker);
+      if current != marker && matches!(current, '-' | '*' | '+') {
+        let line = line_of(&line_starts, offset);
+        violation(
+          "list_marker",
+          line,
+          format!("无序列表应使用 {} 作为标记", marker),
+        );
+        let edited = edits.entry(line).or_insert_with(|| lines[line].clone());
+        edited.replace_range(
+          offset - line_starts[line]..offset - line_starts[line] + 1,
+          &marker.to_string(),
+        );
+      }
+    }
+  }
+
+  if rules.align_tables {
+    for (range, alignments) in &layout.tables {
+      let original = &lines[range.clone()];
+      let aligned = align_table(original, alignments);
+      if aligned != original {
+        violation("align_tables", range.start, "表格列未对齐".to_string());
+        replacements.insert(range.start, (range.end - 1, aligned));
+      }
+    }
+  }
+
+  if let Some(width) = rules.wrap_width {
+    for range in &layout.paragraphs {
+      let long_lines: Vec<usize> = range
+        .clone()
+        .filter(|&line| display_width(lines[line].trim_end()) > width)
+        .collect();
+      for &line in &long_lines {
+        violation("wrap_width", line, format!("行宽超过 {}", width));
+      }
+      if !long_lines.is_empty() {
+        replacements.insert(
+          range.start,
+          (range.end - 1, wrap_paragraph(&lines[range.clone()], width)),
+        );
+      }
+    }
+  }
+
+  for (index, line) in lines.iter().enumerate() {
+    if layout.protected.contains(&index) {
+      continue;
+    }
+    if rules.trim_trailing_whitespace
+      && trim_trailing(line, lines.get(index + 1).map(String::as_str)) != *line
+    {
+      violation(
+        "trim_trailing_whitespace",
+        index,
+        "行尾有多余空白".to_string(),
+      );
+    }
+    let blank_run = lines[..=index]
+      .iter()
+      .rev()
+      .take_while(|l| is_blank(l))
+      .count();
+    if blank_run == rules.max_blank_lines + 1 && index + 1 < lines.len() {
+      violation(
+        "max_blank_lines",
+        index,
+        format!("连续空行超过 {} 行", rules.max_blank_lines),
+      );
+    }
+  }
+  let trailing_blank = lines.iter().rev().take_while(|l| is_blank(l)).count();
+  if rules.final_newline && !lines.is_empty() && (!body.ends_with('\n') || trailing_blank > 0) {
+    violation(
+      "final_newline",
+      lines.len() - 1,
+      "文件应以一个换行结尾".to_string(),
+    );
+  }
+  violations.sort_by_key(|v| v.line);
+
+  if check_only {
+    return MarkdownFormatResult {
+      content: None,
+      changed: !violations.is_empty(),
+      violations,
+    };
+  }
+
+  // 按计划生成新行，同时记录哪些行属于受保护的块
+  let mut output: Vec<(String, bool)> = Vec::new();
+  let mut index = 0;
+  while index < lines.len() {
+    if blank_before.contains(&index) && output.last().is_some_and(|(l, _)| !is_blank(l)) {
+      output.push((String::new(), false));
+    }
+    let last = match replacements.get(&index) {
+      Some((end, replacement)) => {
+        output.extend(replacement.iter().map(|l| (l.clone(), false)));
+        *end
+      }
+      None => {
+        let line = edits.get(&index).unwrap_or(&lines[index]).clone();
+        output.push((line, layout.protected.contains(&index)));
+        index
+      }
+    };
+    if blank_after.contains(&last) {
+      output.push((String::new(), false));
+    }
+    index = last + 1;
+  }
+
+  let mut formatted: Vec<String> = Vec::with_capacity(output.len());
+  let mut blank_run = 0;
+  for (index, (line, protected)) in output.iter().enumerate() {
+    if *protected {
+      blank_run = 0;
+      formatted.push(line.clone());
+      continue;
+    }
+    if is_blank(line) {
+      blank_run += 1;
+      if blank_run > rules.max_blank_lines {
+        continue;
+      }
+    } else {
+      blank_run = 0;
+    }
+    formatted.push(if rules.trim_trailing_whitespace {
+      trim_trailing(line, output.get(index + 1).map(|(l, _)| l.as_str()))
+    } else {
+      line.clone()
+    });
+  }
+
+  let mut result = if rules.final_newline {
+    while formatted.last().is_some_and(|l| is_blank(l)) {
+      formatted.pop();
+    }
+    let mut joined = formatted.join("\n");
+    if !joined.is_empty() {
+      joined.push('\n');
+    }
+    joined
+  } else {
+    let mut joined = formatted.join("\n");
+    if body.ends_with('\n') {
+      joined.push('\n');
+    }
+    joined
+  };
+  if crlf {
+    result = result.replace('\n', "\r\n");
+  }
+  MarkdownFormatResult {
+    changed: result != content,
+    content: Some(result),
+    violations,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn fixes_headings_lists_and_blank_lines() {
+    let content = "---\ntitle: x  \n---\n#  标题\n正文  \n第二行\n\n\n\n* 一\n* 二\n  + 嵌套\n\n```\n* 代码   \n\n\n\n```\n## 小节 ##\n结尾   ";
+    let result = format_markdown(content, &MarkdownFormatRules::default(), false);
+    assert_eq!(
+      result.content.as_deref(),
+      Some(
+        "---\ntitle: x  \n---\n\n# 标题\n\n正文  \n第二行\n\n- 一\n- 二\n  - 嵌套\n\n```\n* 代码   \n\n\n\n```\n\n## 小节 ##\n\n结尾\n"
+      )
+    );
+    let rules: Vec<(&str, usize)> = result
+      .violations
+      .iter()
+      .map(|v| (v.rule.as_str(), v.line))
+      .collect();
+    assert!(rules.contains(&("heading_spacing", 4)));
+    assert!(rules.contains(&("max_blank_lines", 8)));
+    assert!(rules.contains(&("list_marker", 12)));
+    assert!(rules.contains(&("final_newline", 21)));
+    // 硬换行与代码块内的空白不报告
+    assert!(!rules.contains(&("trim_trailing_whitespace", 5)));
+    assert!(!rules.iter().any(|(_, line)| (14..=19).contains(line)));
+
+    let checked = format_markdown(content, &MarkdownFormatRules::default(), true);
+    assert!(checked.content.is_none() && checked.changed);
+    assert_eq!(checked.violations, result.violations);
+    let formatted = result.content.unwrap();
+    let again = format_markdown(&formatted, &MarkdownFormatRules::default(), true);
+    assert!(again.violations.is_empty(), "{:?}", again.violations);
+  }
+
+  #[test]
+  fn aligns_tables_by_display_width() {
+    let content = "| 名称 | Qty | 说明 |\n|:--|--:|:-:|\n| 苹果 | 3 | ok |\n| kiwi | 12 |\n";
+    let result = format_markdown(content, &MarkdownFormatRules::default(), false);
+    assert_eq!(
+      result.content.unwrap(),
+      "| 名称 | Qty | 说明 |\n| :--- | --: | :--: |\n| 苹果 |   3 |  ok  |\n| kiwi |  12 |      |\n"
+    );
+    assert_eq!(result.violations[0].rule, "align_tables");
+  }
+
+  #[test]
+  fn wraps_long_paragraphs_without_starting_new_blocks() {
+    let rules = MarkdownFormatRules {
+      wrap_width: Some(20),
+      ..Default::default()
+    };
+    let content = "short line\n\nThis paragraph is long enough to wrap - 1. and # should stay inline.\n\n- list items are never wrapped even when they are long\n";
+    let result = format_markdown(content, &rules, false);
+    let formatted = result.content.unwrap();
+    assert!(
+      formatted.starts_with(
+        "short line\n\nThis paragraph is\nlong enough to wrap - 1.\nand # should stay\ninline.\n"
+      ),
+      "{}",
+      formatted
+    );
+    assert!(formatted.ends_with("- list items are never wrapped even when they are long\n"));
+    assert_eq!(result.violations.len(), 1);
+    assert!(rules.validate().is_ok());
+    assert!(MarkdownFormatRules {
+      list_marker: Some('x'),
+      ..Default::default()
+    }
+    .validate()
+    .is_err());
+  }
+}
@@ -0,0 +1,251 @@
+//! 记忆锚点：把记忆绑定到文档中的稳定位置
+//!
+//! - 标题锚点：HTML 标题有 id 时直接使用，否则按标题文本生成 slug（重名追加 `-1`、`-2`）
+//! - 段落锚点：规范化段落文本后的 SHA-256 前 16 位
+//!
+//! 本模块只负责从文档内容中提取可锚定块，锚点的存储与修复见 memory_service。
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::Path;
+
+static HTML_BLOCK_RE: Lazy<Regex> =
+  Lazy::new(|| Regex::new(r#"(?is)<(h[1-6]|p)\b([^>]*)>(.*?)</(?:h[1-6]|p)\s*>"#).unwrap());
+static HTML_ID_RE: Lazy<Regex> =
+  Lazy::new(|| Regex::new(r#"(?i)\bid\s*=\s*["']([^"']+)["']"#).unwrap());
+static HTML_TAG_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?s)<[^>]+>").unwrap());
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnchorKind {
+  Heading,
+  Paragraph,
+}
+
+impl AnchorKind {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      AnchorKind::Heading => "heading",
+      AnchorKind::Paragraph => "paragraph",
+    }
+  }
+
+  pub fn from_str(s: &str) -> Option<Self> {
+    match s {
+      "heading" => Some(AnchorKind::Heading),
+      "paragraph" => Some(AnchorKind::Paragraph),
+      _ => None,
+    }
+  }
+}
+
+/// 文档中的一个可锚定块（标题或段落）
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentBlock {
+  pub kind: AnchorKind,
+  pub anchor: String,
+  pub text: String,
+  /// 块起始行号（从 1 开始）
+  pub line: usize,
+  /// 块起始字节偏移
+  pub offset: usize,
+}
+
+/// 标题 slug：小写，字母数字（含中文）保留，其余字符折叠为 `-`
+pub fn heading_slug(text: &str) -> String {
+  let mut slug = String::new();
+  for c in text.trim().chars().flat_map(|c| c.to_lowercase()) {
+    if c.is_alphanumeric() {
+      slug.push(c);
+    } else if !slug.is_empty() && !slug.ends_with('-') {
+      slug.push('-');
+    }
+  }
+  slug.trim_end_matches('-').to_string()
+}
+
+/// 段落哈希：忽略空白差异
+pub fn paragraph_hash(text: &str) -> String {
+  let normalized = text.split_whitespace().collect::<Vec<_>>().join(" ");
+  let digest = Sha256::digest(normalized.as_bytes());
+  digest
+    .iter()
+    .take(8)
+    .map(|b| format!("{:02x}", b))
+    .collect()
+}
+
+/// 提取文档中的标题和段落块；不支持的格式返回空列表
+pub fn extract_blocks(path: &Path, content: &str) -> Vec<DocumentBlock> {
+  let ext = path
+    .extension()
+    .and_then(|e| e.to_str())
+    .map(|e| e.to_lowercase())
+    .unwrap_or_default();
+
+  let mut blocks = match ext.as_str() {
+    "md" | "markdown" | "txt" => extract_text_blocks(content),
+    "html" | "htm" => extract_html_blocks(content),
+    _ => Vec::new(),
+  };
+  dedupe_heading_anchors(&mut blocks);
+  blocks
+}
+
+/// 按锚点查找当前位置
+pub fn find_block<'a>(
+  blocks: &'a [DocumentBlock],
+  kind: AnchorKind,
+  anchor: &str,
+) -> Option<&'a DocumentBlock> {
+  blocks.iter().find(|b| b.kind == kind && b.anchor == anchor)
+}
+
+fn extract_text_blocks(content: &str) -> Vec<DocumentBlock> {
+  let mut blocks = Vec::new();
+  let mut paragraph: Vec<&str> = Vec::new();
+  let mut paragraph_start = (0usize, 0usize);
+  let mut in_code_fence = false;
+  let mut offset = 0usize;
+
+  let flush =
+    |paragraph: &mut Vec<&str>, start: (usize, usize), blocks: &mut Vec<DocumentBlock>| {
+      if paragraph.is_empty() {
+        return;
+      }
+      let text = paragraph.join(" ");
+      blocks.push(DocumentBlock {
+        kind: AnchorKind::Paragraph,
+        anchor: paragraph_hash(&text),
+        text,
+        line: start.0,
+        offset: start.1,
+      });
+      paragraph.clear();
+    };
+
+  for (index, raw_line) in content.split_inclusive('\n').enumerate() {
+    let line_no = index + 1;
+    let line = raw_line.trim();
+    if line.starts_with("```") {
+      in_code_fence = !in_code_fence;
+      flush(&mut paragraph, paragraph_start, &mut blocks);
+    } else if in_code_fence {
+      // 代码块内容不作为锚点
+    } else if line.is_empty() {
+      flush(&mut paragraph, paragraph_start, &mut blocks);
+    } else if line.starts_with('#') {
+      flush(&mut paragraph, paragraph_start, &mut blocks);
+      let text = line.trim_start_matches('#').trim().to_string();
+      if !text.is_empty() {
+        blocks.push(DocumentBlock {
+          kind: AnchorKind::Heading,
+          anchor: heading_slug(&text),
+          text,
+          line: line_no,
+          offset,
+        });
+      }
+    } else {
+      if paragraph.is_empty() {
+        paragraph_start = (line_no, offset);
+      }
+      paragraph.push(line);
+    }
+    offset += raw_line.len();
+  }
+  flush(&mut paragraph, paragraph_start, &mut blocks);
+  blocks
+}
+
+fn extract_html_blocks(content: &str) -> Vec<DocumentBlock> {
+  HTML_BLOCK_RE
+    .captures_iter(content)
+    .filter_map(|caps| {
+      let whole = caps.get(0)?;
+      let tag = caps[1].to_lowercase();
+      let text = HTML_TAG_RE.replace_all(&caps[3], "");
+      let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+      if text.is_empty() {
+        return None;
+      }
+      let line = content[..whole.start()].matches('\n').count() + 1;
+      let (kind, anchor) = if tag == "p" {
+        (AnchorKind::Paragraph, paragraph_hash(&text))
+      } else {
+        let anchor = HTML_ID_RE
+          .captures(&caps[2])
+          .map(|id| id[1].to_string())
+          .unwrap_or_else(|| heading_slug(&text));
+        (AnchorKind::Heading, anchor)
+      };
+      Some(DocumentBlock {
+        kind,
+        anchor,
+        text,
+        line,
+        offset: whole.start(),
+      })
+    })
+    .collect()
+}
+
+/// 重名标题追加序号，保证同一文档内标题锚点唯一
+fn dedupe_heading_anchors(blocks: &mut [DocumentBlock]) {
+  let mut seen: HashMap<String, usize> = HashMap::new();
+  for block in blocks.iter_mut().filter(|b| b.kind == AnchorKind::Heading) {
+    let count = seen.entry(block.anchor.clone()).or_insert(0);
+    if *count > 0 {
+      block.anchor = format!("{}-{}", block.anchor, count);
+    }
+    *count += 1;
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn markdown_blocks_have_stable_anchors_and_positions() {
+    let content = "# 项目概览\n\nFirst line\nsecond line.\n\n```\n# not a heading\n```\n## Details\n## Details\n";
+    let blocks = extract_blocks(Path::new("notes.md"), content);
+
+    let anchors: Vec<(&str, &str, usize)> = blocks
+      .iter()
+      .map(|b| (b.kind.as_str(), b.anchor.as_str(), b.line))
+      .collect();
+    assert_eq!(anchors[0], ("heading", "项目概览", 1));
+    assert_eq!(anchors[1].0, "paragraph");
+    assert_eq!(anchors[1].2, 3);
+    assert_eq!(anchors[2], ("heading", "details", 9));
+    assert_eq!(anchors[3], ("heading", "details-1", 10));
+    assert_eq!(blocks[1].text, "First line second line.");
+    assert_eq!(
+      &content[blocks[2].offset..blocks[2].offset + 10],
+      "## Details"
+    );
+    assert_eq!(
+      blocks[1].anchor,
+      paragraph_hash("First   line\nsecond line.")
+    );
+  }
+
+  #[test]
+  fn html_headings_prefer_id_attribute() {
+    let content = "<h1 id=\"intro\">Intro <em>text</em></h1>\n<h2>Next Steps!</h2>\n<p>Body</p>";
+    let blocks = extract_blocks(Path::new("doc.html"), content);
+
+    assert_eq!(blocks.len(), 3);
+    assert_eq!(blocks[0].anchor, "intro");
+    assert_eq!(blocks[0].text, "Intro text");
+    assert_eq!(blocks[1].anchor, "next-steps");
+    assert_eq!(blocks[1].line, 2);
+    assert_eq!(blocks[2].kind, AnchorKind::Paragraph);
+    assert!(find_block(&blocks, AnchorKind::Heading, "next-steps").is_some());
+  }
+}
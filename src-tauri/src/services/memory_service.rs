@@ -1,12 +1,14 @@
 //! 记忆服务 (P0–P2 实现)
 //!
-//! - 数据库：workspace.db（与 WorkspaceDb 共用同一文件，WAL 模式允许并发读）
+//! - 数据库：workspace.db（与 WorkspaceDb 共用同一文件和 migration，WAL 模式允许并发读）
 //! - user_memory.db：位于 {data_dir}/binder/user_memory.db，跨工作区用户级记忆
 //! - 无 AppState：每次调用按 workspace_path 打开连接（与 WorkspaceDb 模式一致）
 //! - 写入：tokio::spawn fire-and-forget，不阻塞主链（MC-WRITE-001）
 //! - 检索：500ms 超时，超时返回空结果（A-AST-M-S-02 §六）
 
-use rusqlite::{params, Connection};
+use crate::services::memory_anchor::{extract_blocks, find_block, AnchorKind, DocumentBlock};
+use crate::workspace::workspace_db::WorkspaceDb;
+use rusqlite::{params, Connection, OptionalExtension};
 use serde::{Deserialize, Serialize};
 use std::fmt;
 use std::path::{Path, PathBuf};
@@ -14,8 +16,8 @@ use std::sync::{Arc, Mutex};
 
 // ── Workspace memory schema (P0) ────────────────────────────────────────────
 
-/// workspace.db 中的记忆库主表、FTS、注入日志和关键索引。
-/// 幂等执行：可在 migration 和运行时初始化阶段重复调用。
+/// workspace.db 中的记忆库主表、FTS、注入日志、语义向量、文档锚点和关键索引。
+/// 幂等执行，由 WorkspaceDb migration（3 与 10）调用。
 const WORKSPACE_MEMORY_DDL: &str = "
 CREATE TABLE IF NOT EXISTS memory_items (
     id TEXT PRIMARY KEY,
@@ -35,7 +37,8 @@ CREATE TABLE IF NOT EXISTS memory_items (
     access_count INTEGER NOT NULL DEFAULT 0,
     last_accessed_at INTEGER,
     created_at INTEGER NOT NULL,
-    updated_at INTEGER NOT NULL
+    updated_at INTEGER NOT NULL,
+    version INTEGER NOT NULL DEFAULT 1
 );
 
 CREATE VIRTUAL TABLE IF NOT EXISTS memory_items_fts USING fts5(
@@ -72,18 +75,54 @@ CREATE TABLE IF NOT EXISTS memory_usage_logs (
     FOREIGN KEY(memory_id) REFERENCES memory_items(id)
 );
 
+CREATE TABLE IF NOT EXISTS memory_embeddings (
+    memory_id TEXT PRIMARY KEY,
+    model TEXT NOT NULL,
+    dim INTEGER NOT NULL,
+    vector BLOB NOT NULL,
+    source_updated_at INTEGER NOT NULL,
+    FOREIGN KEY(memory_id) REFERENCES memory_items(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS memory_anchors (
+    memory_id TEXT PRIMARY KEY,
+    doc_path TEXT NOT NULL,
+    anchor_kind TEXT NOT NULL,
+    anchor TEXT NOT NULL,
+    anchor_text TEXT NOT NULL DEFAULT '',
+    status TEXT NOT NULL DEFAULT 'ok',
+    updated_at INTEGER NOT NULL,
+    FOREIGN KEY(memory_id) REFERENCES memory_items(id) ON DELETE CASCADE
+);
+
 CREATE INDEX IF NOT EXISTS idx_memory_scope ON memory_items(scope_type, scope_id, layer);
 CREATE INDEX IF NOT EXISTS idx_memory_entity ON memory_items(scope_id, layer, entity_name);
 CREATE INDEX IF NOT EXISTS idx_memory_freshness ON memory_items(freshness_status, updated_at);
 CREATE INDEX IF NOT EXISTS idx_memory_source_ref ON memory_items(source_ref, layer, updated_at);
 CREATE INDEX IF NOT EXISTS idx_memory_usage_memory ON memory_usage_logs(memory_id, injected_at);
 CREATE INDEX IF NOT EXISTS idx_memory_usage_tab ON memory_usage_logs(tab_id, injected_at);
+CREATE INDEX IF NOT EXISTS idx_memory_anchor_doc ON memory_anchors(doc_path);
 ";
 
 pub fn ensure_workspace_memory_schema(conn: &Connection) -> Result<(), String> {
   conn
     .execute_batch(WORKSPACE_MEMORY_DDL)
-    .map_err(|e| format!("初始化 workspace memory schema 失败: {}", e))
+    .map_err(|e| format!("初始化 workspace memory schema 失败: {}", e))?;
+  migrate_memory_version_column(conn)
+}
+
+/// 旧库补充 version 列（乐观并发控制用）
+fn migrate_memory_version_column(conn: &Connection) -> Result<(), String> {
+  let has_version = conn
+    .prepare("SELECT 1 FROM pragma_table_info('memory_items') WHERE name = 'version'")
+    .and_then(|mut stmt| stmt.exists([]))
+    .map_err(|e| format!("检查 memory_items.version 失败: {}", e))?;
+  if !has_version {
+    conn
+      .execute_batch("ALTER TABLE memory_items ADD COLUMN version INTEGER NOT NULL DEFAULT 1")
+      .map_err(|e| format!("迁移 memory_items.version 失败: {}", e))?;
+  }
+  Ok(())
 }
 
 // ── P2: ExtractionConfig ────────────────────────────────────────────────────
@@ -146,6 +185,10 @@ pub enum MemoryError {
   DbError(rusqlite::Error),
   ParseError(serde_json::Error),
   ValidationError(String),
+  /// 乐观并发冲突：记忆已被其他写入修改，附带当前版本号
+  VersionConflict {
+    current: i64,
+  },
   Timeout,
   AiCallFailed(String),
 }
@@ -163,6 +206,9 @@ impl fmt::Display for MemoryError {
       MemoryError::DbError(e) => write!(f, "db error: {}", e),
       MemoryError::ParseError(e) => write!(f, "parse error: {}", e),
       MemoryError::ValidationError(s) => write!(f, "validation error: {}", s),
+      MemoryError::VersionConflict { current } => {
+        write!(f, "version conflict: current version {}", current)
+      }
       MemoryError::Timeout => write!(f, "timeout"),
       MemoryError::AiCallFailed(s) => write!(f, "ai call failed: {}", s),
     }
@@ -306,6 +352,13 @@ pub struct MemoryItem {
   pub last_accessed_at: Option<i64>,
   pub created_at: i64,
   pub updated_at: i64,
+  /// 编辑版本号，每次用户可见字段变更后递增
+  #[serde(default = "default_memory_version")]
+  pub version: i64,
+}
+
+fn default_memory_version() -> i64 {
+  1
 }
 
 #[derive(Debug, Clone)]
@@ -345,6 +398,24 @@ impl MemorySearchScope {
   }
 }
 
+/// 记忆存储范围：global 写入 user_memory.db，对所有工作区生效；
+/// workspace 写入当前工作区的 workspace.db，不会出现在其他工作区
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum MemoryStoreScope {
+  Workspace,
+  Global,
+}
+
+impl MemoryStoreScope {
+  pub fn from_str(s: &str) -> Self {
+    match s {
+      "global" | "user" => MemoryStoreScope::Global,
+      _ => MemoryStoreScope::Workspace,
+    }
+  }
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct SearchMemoriesParams {
   pub query: String,
@@ -353,6 +424,9 @@ pub struct SearchMemoriesParams {
   pub scope: MemorySearchScope,
   pub limit: Option<usize>,
   pub entity_types: Option<Vec<String>>,
+  /// 标签过滤：命中任一标签即可（如 style / project_fact）
+  #[serde(default)]
+  pub tags: Option<Vec<String>>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -383,6 +457,126 @@ impl MemorySearchResponse {
   }
 }
 
+/// 记忆导出文件格式版本，结构不兼容变更时递增
+pub const MEMORY_EXPORT_SCHEMA_VERSION: u32 = 1;
+
+/// 记忆导出包（JSON），用于备份或在机器/工作区之间迁移
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoryExportBundle {
+  pub schema_version: u32,
+  pub exported_at: i64,
+  /// 导出时的工作区路径；导入到其他工作区时据此改写 workspace 级记忆的 scope_id
+  pub workspace_path: String,
+  pub items: Vec<MemoryItem>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoryImportSummary {
+  pub imported: usize,
+  /// 合并到现有记忆（同 id 或同名记忆，导入项更新）的条数
+  pub updated: usize,
+  /// 与现有记忆重复（同内容，或现有记忆不旧于导入项）而跳过的条数
+  pub skipped: usize,
+}
+
+/// 去重报告中的一组重复记忆
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoryDuplicateGroup {
+  pub kept_id: String,
+  pub kept_entity_name: String,
+  pub merged_ids: Vec<String>,
+  /// 组内与保留项的最低相似度
+  pub min_similarity: f64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoryDedupeReport {
+  pub scanned: usize,
+  pub merged: usize,
+  pub dry_run: bool,
+  pub groups: Vec<MemoryDuplicateGroup>,
+}
+
+/// 记忆在文档中的锚点（标题 id 或段落哈希）
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoryAnchor {
+  pub memory_id: String,
+  pub doc_path: String,
+  pub anchor_kind: AnchorKind,
+  pub anchor: String,
+  /// 建立锚点时的块文本，用于锚点失效后按相似度修复
+  pub anchor_text: String,
+  /// ok / repaired / broken
+  pub status: String,
+}
+
+/// 锚点解析结果：status 为 ok/repaired 时带当前位置
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResolvedMemoryAnchor {
+  pub anchor: MemoryAnchor,
+  pub line: Option<usize>,
+  pub offset: Option<usize>,
+  pub text: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoryConsistencyReport {
+  pub checked: usize,
+  pub ok: usize,
+  pub repaired: usize,
+  pub broken: usize,
+  pub anchors: Vec<ResolvedMemoryAnchor>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoryCountEntry {
+  /// 文档路径 / 标签 / 层级 / 状态
+  pub key: String,
+  pub count: usize,
+  pub last_updated_at: Option<i64>,
+}
+
+/// 记忆库概览（记忆管理界面使用）
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoryStats {
+  pub total: usize,
+  /// fresh + stale（参与检索的记忆）
+  pub active: usize,
+  pub by_layer: Vec<MemoryCountEntry>,
+  pub by_status: Vec<MemoryCountEntry>,
+  /// content 层记忆按来源文档统计（仅 active）
+  pub by_document: Vec<MemoryCountEntry>,
+  /// 按标签统计（仅 active）
+  pub by_tag: Vec<MemoryCountEntry>,
+  /// 记忆文本占用的字节数（content + summary + tags + entity_name）
+  pub content_bytes: u64,
+  /// workspace.db（含 WAL）文件大小
+  pub database_bytes: u64,
+  pub last_created_at: Option<i64>,
+  pub last_updated_at: Option<i64>,
+}
+
+/// 记忆编辑：只更新提供的字段
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoryUpdateFields {
+  pub entity_name: Option<String>,
+  pub entity_type: Option<String>,
+  pub content: Option<String>,
+  pub summary: Option<String>,
+  pub tags: Option<Vec<String>>,
+  pub confidence: Option<f64>,
+}
+
 // ── 服务 ──────────────────────────────────────────────────────────────────────
 
 pub struct MemoryService {
@@ -391,20 +585,19 @@ pub struct MemoryService {
 }
 
 impl MemoryService {
-  /// 打开工作区的 workspace.db（记忆表已由 WorkspaceDb migration 创建）
+  /// 打开工作区的 workspace.db；先经 WorkspaceDb 执行未完成的 migration，记忆表结构只在那里维护
   pub fn new(workspace_path: &Path) -> Result<Self, String> {
     let db_path = workspace_path.join(".binder").join("workspace.db");
     if !db_path.exists() {
       return Err(format!("workspace.db 不存在: {}", db_path.display()));
     }
+    WorkspaceDb::new(workspace_path)?;
     let conn =
       rusqlite::Connection::open(&db_path).map_err(|e| format!("打开 workspace.db 失败: {}", e))?;
     // WAL 模式（与 WorkspaceDb 保持一致）
     conn
       .execute_batch("PRAGMA journal_mode=WAL; PRAGMA synchronous=NORMAL; PRAGMA foreign_keys=ON;")
       .map_err(|e| format!("设置 pragma 失败: {}", e))?;
-    // 运行时兜底：即使 migration 漏跑，也保证主链可用。
-    ensure_workspace_memory_schema(&conn)?;
     Ok(Self {
       db: Arc::new(Mutex::new(conn)),
       workspace_path: workspace_path.to_path_buf(),
@@ -479,15 +672,15 @@ impl MemoryService {
 
             for item in &items {
                 if item.entity_name.is_empty() || item.content.is_empty() {
-                    eprintln!("skip invalid memory item: entity_name or content empty");
+                    tracing::debug!("skip invalid memory item: entity_name or content empty");
                     continue;
                 }
                 if item.content.chars().count() > 500 {
-                    eprintln!("skip memory item: content too long (>500 chars)");
+                    tracing::debug!("skip memory item: content too long (>500 chars)");
                     continue;
                 }
                 if item.confidence < 0.3 {
-                    eprintln!("skip memory item: confidence too low ({:.2})", item.confidence);
+                    tracing::debug!("skip memory item: confidence too low ({:.2})", item.confidence);
                     continue;
                 }
 
@@ -548,7 +741,7 @@ impl MemoryService {
                     continue;
                 }
                 if item.content.chars().count() > 500 {
-                    eprintln!("skip content memory item: content too long");
+                    tracing::debug!("skip content memory item: content too long");
                     continue;
                 }
                 if item.confidence < 0.3 {
@@ -609,9 +802,10 @@ impl MemoryService {
 
       // LLM 仲裁
       let action = llm_judge_action(provider, item, &similar).await;
-      eprintln!(
+      tracing::debug!(
         "[memory] judge: {:?} for entity={}",
-        action, item.entity_name
+        action,
+        item.entity_name
       );
 
       // 按动作执行
@@ -691,9 +885,10 @@ impl MemoryService {
       };
 
       let action = llm_judge_action(provider, item, &similar).await;
-      eprintln!(
+      tracing::debug!(
         "[memory] content judge: {:?} for entity={}",
-        action, item.entity_name
+        action,
+        item.entity_name
       );
 
       let db_clone = db.clone();
@@ -746,7 +941,7 @@ impl MemoryService {
   pub async fn upsert_workspace_long_term_memory(
     &self,
     item: MemoryItemInput,
-  ) -> Result<(), MemoryError> {
+  ) -> Result<String, MemoryError> {
     let db = self.db.clone();
     tokio::task::spawn_blocking(move || {
             let conn = db.lock().map_err(MemoryError::lock_error)?;
@@ -757,17 +952,18 @@ impl MemoryService {
                     id, layer, scope_type, scope_id, entity_type, entity_name,
                     content, summary, tags, source_kind, source_ref,
                     confidence, freshness_status, readonly, created_at, updated_at
-                 ) VALUES (?1, 'workspace_long_term', ?2, ?3, ?4, ?5, ?6, ?7, '', ?8, ?9, ?10, 'fresh', 1, ?11, ?11)",
+                 ) VALUES (?1, 'workspace_long_term', ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, 'fresh', 1, ?12, ?12)",
                 params![
                     id,
                     item.scope_type.as_str(), item.scope_id,
                     item.entity_type, item.entity_name,
                     item.content, item.summary,
+                    normalize_tags(&item.tags).join(" "),
                     item.source_kind.as_str(), item.source_ref,
                     item.confidence, now,
                 ],
             )?;
-            Ok::<(), MemoryError>(())
+            Ok::<String, MemoryError>(id)
         })
         .await
         .map_err(|e| MemoryError::LockError(e.to_string()))?
@@ -821,7 +1017,7 @@ impl MemoryService {
     match result {
       Ok(inner) => inner,
       Err(_) => {
-        eprintln!("memory search timed out after 500ms");
+        tracing::debug!("memory search timed out after 500ms");
         Ok(MemorySearchResponse {
           items: vec![],
           total_found: 0,
@@ -842,6 +1038,676 @@ impl MemoryService {
       .map_err(|e| MemoryError::LockError(e.to_string()))?
   }
 
+  // ── 语义检索（embeddings）────────────────────────────────────────────
+
+  /// 为缺少向量或内容已更新的记忆补算 embedding，返回本次写入的条数。
+  /// 每次最多处理 max_items 条，避免一次检索触发过多 API 调用。
+  pub async fn sync_memory_embeddings(
+    &self,
+    provider: &Arc<dyn crate::services::ai_providers::AIProvider>,
+    max_items: usize,
+  ) -> Result<usize, MemoryError> {
+    let model = provider
+      .embedding_model()
+      .ok_or_else(|| MemoryError::ValidationError("当前 AI 提供商不支持 embeddings".to_string()))?
+      .to_string();
+
+    let db = self.db.clone();
+    let model_for_query = model.clone();
+    let pending: Vec<(String, String, i64)> = tokio::task::spawn_blocking(move || {
+      let conn = db.lock().map_err(MemoryError::lock_error)?;
+      let mut stmt = conn.prepare(
+        "SELECT m.id, m.entity_name, m.content, m.summary, m.updated_at
+         FROM memory_items m
+         LEFT JOIN memory_embeddings e ON e.memory_id = m.id AND e.model = ?1
+         WHERE m.freshness_status IN ('fresh', 'stale')
+           AND (e.memory_id IS NULL OR e.source_updated_at < m.updated_at)
+         ORDER BY m.updated_at DESC
+         LIMIT ?2",
+      )?;
+      let rows = stmt
+        .query_map(params![model_for_query, max_items as i64], |row| {
+          let id: String = row.get(0)?;
+          let name: String = row.get(1)?;
+          let content: String = row.get(2)?;
+          let summary: String = row.get(3)?;
+          let updated_at: i64 = row.get(4)?;
+          Ok((id, embedding_text(&name, &content, &summary), updated_at))
+        })?
+        .filter_map(|r| r.ok())
+        .collect();
+      Ok::<_, MemoryError>(rows)
+    })
+    .await
+    .map_err(|e| MemoryError::LockError(e.to_string()))??;
+
+    let mut written = 0;
+    for batch in pending.chunks(EMBEDDING_BATCH_SIZE) {
+      let texts: Vec<String> = batch.iter().map(|(_, text, _)| text.clone()).collect();
+      let vectors = provider
+        .embed(&texts)
+        .await
+        .map_err(|e| MemoryError::AiCallFailed(e.to_string()))?;
+
+      let db = self.db.clone();
+      let model = model.clone();
+      let rows: Vec<(String, i64, Vec<f32>)> = batch
+        .iter()
+        .zip(vectors)
+        .map(|((id, _, updated_at), vector)| (id.clone(), *updated_at, vector))
+        .collect();
+      written += tokio::task::spawn_blocking(move || {
+        let conn = db.lock().map_err(MemoryError::lock_error)?;
+        for (id, updated_at, vector) in &rows {
+          conn.execute(
+            "INSERT OR REPLACE INTO memory_embeddings (memory_id, model, dim, vector, source_updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![id, model, vector.len() as i64, encode_embedding(vector), updated_at],
+          )?;
+        }
+        Ok::<_, MemoryError>(rows.len())
+      })
+      .await
+      .map_err(|e| MemoryError::LockError(e.to_string()))??;
+    }
+
+    Ok(written)
+  }
+
+  /// 语义检索：按 query 向量与记忆向量的余弦相似度排序，即使没有关键词重合也能召回。
+  /// scope / entity_types 的过滤规则与 search_memories 一致，limit 即 top_k。
+  pub async fn search_memories_semantic(
+    &self,
+    provider: &Arc<dyn crate::services::ai_providers::AIProvider>,
+    params: SearchMemoriesParams,
+  ) -> Result<MemorySearchResponse, MemoryError> {
+    let query = params.query.trim().to_string();
+    if query.is_empty() {
+      return Ok(MemorySearchResponse::empty());
+    }
+    let model = provider
+      .embedding_model()
+      .ok_or_else(|| MemoryError::ValidationError("当前 AI 提供商不支持 embeddings".to_string()))?
+      .to_string();
+
+    self
+      .sync_memory_embeddings(provider, EMBEDDING_SYNC_LIMIT)
+      .await?;
+
+    let query_vector = provider
+      .embed(&[query])
+      .await
+      .map_err(|e| MemoryError::AiCallFailed(e.to_string()))?
+      .into_iter()
+      .next()
+      .ok_or_else(|| MemoryError::AiCallFailed("embedding 返回为空".to_string()))?;
+
+    let db = self.db.clone();
+    tokio::task::spawn_blocking(move || {
+      execute_semantic_search(&db, &params, &model, &query_vector)
+    })
+    .await
+    .map_err(|e| MemoryError::LockError(e.to_string()))?
+  }
+
+  // ── 去重 ────────────────────────────────────────────────────────────────
+
+  /// 检测同层同作用域内的近似重复记忆并合并：保留置信度/使用次数最高的一条，
+  /// 其余标记为 superseded，使用次数与标签并入保留项。
+  /// 文本相似度（字符二元组 Dice 系数）达到 threshold，或已有向量的余弦相似度
+  /// 达到 EMBEDDING_DUPLICATE_THRESHOLD 即视为重复。dry_run 时只生成报告。
+  pub async fn dedupe_memories(
+    &self,
+    threshold: Option<f64>,
+    dry_run: bool,
+  ) -> Result<MemoryDedupeReport, MemoryError> {
+    let threshold = threshold.unwrap_or(DEFAULT_DEDUPE_THRESHOLD);
+    if threshold <= 0.0 || threshold > 1.0 {
+      return Err(MemoryError::ValidationError(
+        "去重阈值必须在 (0, 1] 之间".to_string(),
+      ));
+    }
+    let db = self.db.clone();
+    tokio::task::spawn_blocking(move || {
+      let mut conn = db.lock().map_err(MemoryError::lock_error)?;
+      let items: Vec<MemoryItem> = {
+        let mut stmt = conn.prepare(
+          "SELECT id, layer, scope_type, scope_id, entity_type, entity_name,
+                  content, summary, tags, source_kind, source_ref, confidence,
+                  freshness_status, readonly, access_count, last_accessed_at,
+                  created_at, updated_at, version
+           FROM memory_items
+           WHERE freshness_status IN ('fresh', 'stale')",
+        )?;
+        let rows = stmt
+          .query_map([], map_row_to_memory_item)?
+          .collect::<Result<Vec<_>, _>>()?;
+        rows
+      };
+      let embeddings: std::collections::HashMap<String, (String, Vec<f32>)> = {
+        let mut stmt = conn.prepare("SELECT memory_id, model, vector FROM memory_embeddings")?;
+        let rows = stmt
+          .query_map([], |row| {
+            let id: String = row.get(0)?;
+            let model: String = row.get(1)?;
+            let vector: Vec<u8> = row.get(2)?;
+            Ok((id, (model, decode_embedding(&vector))))
+          })?
+          .filter_map(|r| r.ok())
+          .collect();
+        rows
+      };
+
+      let groups = find_duplicate_groups(&items, &embeddings, threshold);
+      let report = MemoryDedupeReport {
+        scanned: items.len(),
+        merged: groups.iter().map(|g| g.merged_ids.len()).sum(),
+        dry_run,
+        groups,
+      };
+      if dry_run || report.groups.is_empty() {
+        return Ok(report);
+      }
+
+      let by_id: std::collections::HashMap<&str, &MemoryItem> =
+        items.iter().map(|m| (m.id.as_str(), m)).collect();
+      let tx = conn.transaction()?;
+      let now = now_secs();
+      for group in &report.groups {
+        let kept = by_id[group.kept_id.as_str()];
+        let mut tags = parse_tags(&kept.tags);
+        let mut access_count = kept.access_count;
+        for id in &group.merged_ids {
+          let merged = by_id[id.as_str()];
+          access_count += merged.access_count;
+          for tag in parse_tags(&merged.tags) {
+            if !tags.contains(&tag) {
+              tags.push(tag);
+            }
+          }
+          tx.execute(
+            "UPDATE memory_items SET freshness_status = 'superseded', updated_at = ?1 WHERE id = ?2",
+            params![now, id],
+          )?;
+        }
+        tx.execute(
+          "UPDATE memory_items SET tags = ?1, access_count = ?2, updated_at = ?3, version = version + 1 WHERE id = ?4",
+          params![tags.join(" "), access_count, now, kept.id],
+        )?;
+      }
+      tx.commit()?;
+      tracing::debug!(
+        "[memory] dedupe finished: scanned={}, merged={}",
+        report.scanned, report.merged
+      );
+      Ok(report)
+    })
+    .await
+    .map_err(|e| MemoryError::LockError(e.to_string()))?
+  }
+
+  // ── 文档锚点 ────────────────────────────────────────────────────────────
+
+  /// 将记忆锚定到文档中与 text 对应的标题/段落；找不到足够相似的块时返回错误
+  pub async fn set_memory_anchor(
+    &self,
+    memory_id: &str,
+    doc_path: &str,
+    kind: AnchorKind,
+    text: &str,
+  ) -> Result<ResolvedMemoryAnchor, MemoryError> {
+    let db = self.db.clone();
+    let memory_id = memory_id.to_string();
+    let doc_path = doc_path_key(&self.workspace_path, doc_path);
+    let full_path = PathBuf::from(&doc_path);
+    let text = text.to_string();
+    tokio::task::spawn_blocking(move || {
+      let blocks = read_document_blocks(&full_path)?;
+      let (block, _) = best_matching_block(&blocks, kind, &text, 0.0)
+        .filter(|(block, similarity)| {
+          *similarity >= ANCHOR_REPAIR_THRESHOLD || block.text.trim() == text.trim()
+        })
+        .ok_or_else(|| {
+          MemoryError::ValidationError(format!("文档中找不到对应的{}", anchor_kind_label(kind)))
+        })?;
+
+      let conn = db.lock().map_err(MemoryError::lock_error)?;
+      let exists: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM memory_items WHERE id = ?1)",
+        params![memory_id],
+        |row| row.get(0),
+      )?;
+      if !exists {
+        return Err(MemoryError::ValidationError(format!(
+          "记忆不存在: {}",
+          memory_id
+        )));
+      }
+      let anchor = MemoryAnchor {
+        memory_id,
+        doc_path,
+        anchor_kind: kind,
+        anchor: block.anchor.clone(),
+        anchor_text: block.text.clone(),
+        status: "ok".to_string(),
+      };
+      save_memory_anchor(&conn, &anchor)?;
+      Ok(ResolvedMemoryAnchor {
+        anchor,
+        line: Some(block.line),
+        offset: Some(block.offset),
+        text: Some(block.text.clone()),
+      })
+    })
+    .await
+    .map_err(|e| MemoryError::LockError(e.to_string()))?
+  }
+
+  /// 将文档上的锚点解析为当前位置（只读，不做修复）
+  pub async fn resolve_memory_anchors(
+    &self,
+    doc_path: &str,
+  ) -> Result<Vec<ResolvedMemoryAnchor>, MemoryError> {
+    let db = self.db.clone();
+    let doc_path = doc_path_key(&self.workspace_path, doc_path);
+    let full_path = PathBuf::from(&doc_path);
+    tokio::task::spawn_blocking(move || {
+      let anchors = {
+        let conn = db.lock().map_err(MemoryError::lock_error)?;
+        load_memory_anchors(&conn, Some(&doc_path))?
+      };
+      let blocks = read_document_blocks(&full_path).unwrap_or_default();
+      Ok(
+        anchors
+          .into_iter()
+          .map(
+            |mut anchor| match find_block(&blocks, anchor.anchor_kind, &anchor.anchor) {
+              Some(block) => ResolvedMemoryAnchor {
+                line: Some(block.line),
+                offset: Some(block.offset),
+                text: Some(block.text.clone()),
+                anchor,
+              },
+              None => {
+                anchor.status = "broken".to_string();
+                ResolvedMemoryAnchor {
+                  anchor,
+                  line: None,
+                  offset: None,
+                  text: None,
+                }
+              }
+            },
+          )
+          .collect(),
+      )
+    })
+    .await
+    .map_err(|e| MemoryError::LockError(e.to_string()))?
+  }
+
+  /// 检查记忆锚点一致性：锚点失效时按建立时的块文本找最相似的块修复，
+  /// 找不到则标记为 broken。doc_path 为 None 时检查全部文档。
+  pub async fn check_memory_consistency(
+    &self,
+    doc_path: Option<&str>,
+  ) -> Result<MemoryConsistencyReport, MemoryError> {
+    let db = self.db.clone();
+    let doc_path = doc_path.map(|p| doc_path_key(&self.workspace_path, p));
+    tokio::task::spawn_blocking(move || {
+      let conn = db.lock().map_err(MemoryError::lock_error)?;
+      let anchors = load_memory_anchors(&conn, doc_path.as_deref())?;
+      let mut report = MemoryConsistencyReport {
+        checked: anchors.len(),
+        ..Default::default()
+      };
+
+      let mut blocks_by_doc: std::collections::HashMap<String, Vec<DocumentBlock>> =
+        std::collections::HashMap::new();
+      for mut anchor in anchors {
+        let blocks = blocks_by_doc
+          .entry(anchor.doc_path.clone())
+          .or_insert_with(|| read_document_blocks(Path::new(&anchor.doc_path)).unwrap_or_default());
+
+        let resolved = if let Some(block) = find_block(blocks, anchor.anchor_kind, &anchor.anchor) {
+          report.ok += 1;
+          if anchor.status != "ok" {
+            anchor.status = "ok".to_string();
+            save_memory_anchor(&conn, &anchor)?;
+          }
+          Some(block.clone())
+        } else if let Some((block, _)) = best_matching_block(
+          blocks,
+          anchor.anchor_kind,
+          &anchor.anchor_text,
+          ANCHOR_REPAIR_THRESHOLD,
+        ) {
+          report.repaired += 1;
+          anchor.anchor = block.anchor.clone();
+          anchor.anchor_text = block.text.clone();
+          anchor.status = "repaired".to_string();
+          save_memory_anchor(&conn, &anchor)?;
+          Some(block.clone())
+        } else {
+          report.broken += 1;
+          if anchor.status != "broken" {
+            anchor.status = "broken".to_string();
+            save_memory_anchor(&conn, &anchor)?;
+          }
+          None
+        };
+
+        report.anchors.push(ResolvedMemoryAnchor {
+          line: resolved.as_ref().map(|b| b.line),
+          offset: resolved.as_ref().map(|b| b.offset),
+          text: resolved.map(|b| b.text),
+          anchor,
+        });
+      }
+
+      if report.repaired > 0 || report.broken > 0 {
+        tracing::debug!(
+          "[memory] anchor consistency: checked={}, repaired={}, broken={}",
+          report.checked,
+          report.repaired,
+          report.broken
+        );
+      }
+      Ok(report)
+    })
+    .await
+    .map_err(|e| MemoryError::LockError(e.to_string()))?
+  }
+
+  // ── 统计 ────────────────────────────────────────────────────────────────
+
+  pub async fn get_memory_stats(&self) -> Result<MemoryStats, MemoryError> {
+    let db = self.db.clone();
+    let db_path = self.workspace_path.join(".binder").join("workspace.db");
+    tokio::task::spawn_blocking(move || {
+      let conn = db.lock().map_err(MemoryError::lock_error)?;
+      let mut stats = MemoryStats::default();
+
+      let (total, content_bytes, last_created_at, last_updated_at): (
+        i64,
+        i64,
+        Option<i64>,
+        Option<i64>,
+      ) = conn.query_row(
+        "SELECT COUNT(*),
+                  COALESCE(SUM(length(CAST(content AS BLOB)) + length(CAST(summary AS BLOB))
+                    + length(CAST(tags AS BLOB)) + length(CAST(entity_name AS BLOB))), 0),
+                  MAX(created_at), MAX(updated_at)
+           FROM memory_items",
+        [],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+      )?;
+      stats.total = total as usize;
+      stats.content_bytes = content_bytes as u64;
+      stats.last_created_at = last_created_at;
+      stats.last_updated_at = last_updated_at;
+
+      stats.by_layer = count_memories_by(&conn, "layer")?;
+      stats.by_status = count_memories_by(&conn, "freshness_status")?;
+      stats.active = stats
+        .by_status
+        .iter()
+        .filter(|e| e.key == "fresh" || e.key == "stale")
+        .map(|e| e.count)
+        .sum();
+
+      stats.by_document = {
+        let mut stmt = conn.prepare(
+          "SELECT source_ref, COUNT(*), MAX(updated_at) FROM memory_items
+           WHERE layer = 'content' AND source_ref != ''
+             AND freshness_status IN ('fresh', 'stale')
+           GROUP BY source_ref
+           ORDER BY COUNT(*) DESC, source_ref",
+        )?;
+        let rows = stmt
+          .query_map([], map_count_entry)?
+          .filter_map(|r| r.ok())
+          .collect();
+        rows
+      };
+
+      let mut tags: std::collections::BTreeMap<String, (usize, i64)> =
+        std::collections::BTreeMap::new();
+      {
+        let mut stmt = conn.prepare(
+          "SELECT tags, updated_at FROM memory_items WHERE freshness_status IN ('fresh', 'stale')",
+        )?;
+        let rows = stmt.query_map([], |row| {
+          Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+        })?;
+        for (raw, updated_at) in rows.filter_map(|r| r.ok()) {
+          for tag in parse_tags(&raw) {
+            let entry = tags.entry(tag).or_insert((0, updated_at));
+            entry.0 += 1;
+            entry.1 = entry.1.max(updated_at);
+          }
+        }
+      }
+      stats.by_tag = tags
+        .into_iter()
+        .map(|(key, (count, last))| MemoryCountEntry {
+          key,
+          count,
+          last_updated_at: Some(last),
+        })
+        .collect();
+      stats.by_tag.sort_by_key(|e| std::cmp::Reverse(e.count));
+
+      stats.database_bytes = ["", "-wal"]
+        .iter()
+        .filter_map(|suffix| {
+          let mut path = db_path.clone().into_os_string();
+          path.push(suffix);
+          std::fs::metadata(path).ok()
+        })
+        .map(|m| m.len())
+        .sum();
+
+      Ok(stats)
+    })
+    .await
+    .map_err(|e| MemoryError::LockError(e.to_string()))?
+  }
+
+  // ── 标签 ────────────────────────────────────────────────────────────────
+
+  /// 批量改标签：先移除 remove_tags，再追加 add_tags，返回实际变更的记忆条数
+  pub async fn retag_memories(
+    &self,
+    memory_ids: Vec<String>,
+    add_tags: Vec<String>,
+    remove_tags: Vec<String>,
+  ) -> Result<u64, MemoryError> {
+    let add_tags = normalize_tags(&add_tags);
+    let remove_tags = normalize_tags(&remove_tags);
+    let db = self.db.clone();
+    tokio::task::spawn_blocking(move || {
+      let mut conn = db.lock().map_err(MemoryError::lock_error)?;
+      let tx = conn.transaction()?;
+      let now = now_secs();
+      let mut changed = 0u64;
+      for id in &memory_ids {
+        let current: Option<String> = tx
+          .query_row(
+            "SELECT tags FROM memory_items WHERE id = ?1",
+            params![id],
+            |row| row.get(0),
+          )
+          .ok();
+        let Some(current) = current else {
+          continue;
+        };
+        let old_tags = parse_tags(&current);
+        let mut new_tags: Vec<String> = old_tags
+          .iter()
+          .filter(|t| !remove_tags.contains(t))
+          .cloned()
+          .collect();
+        for tag in &add_tags {
+          if !new_tags.contains(tag) {
+            new_tags.push(tag.clone());
+          }
+        }
+        if new_tags == old_tags {
+          continue;
+        }
+        tx.execute(
+          "UPDATE memory_items SET tags = ?1, updated_at = ?2, version = version + 1 WHERE id = ?3",
+          params![new_tags.join(" "), now, id],
+        )?;
+        changed += 1;
+      }
+      tx.commit()?;
+      Ok(changed)
+    })
+    .await
+    .map_err(|e| MemoryError::LockError(e.to_string()))?
+  }
+
+  /// 原地编辑记忆；`expected_version` 与当前版本不一致时返回 VersionConflict
+  ///
+  /// 保留 id、来源、访问统计与锚点，stale 记忆编辑后恢复为 fresh。
+  pub async fn update_memory(
+    &self,
+    memory_id: &str,
+    expected_version: i64,
+    fields: MemoryUpdateFields,
+  ) -> Result<MemoryItem, MemoryError> {
+    let db = self.db.clone();
+    let memory_id = memory_id.to_string();
+    tokio::task::spawn_blocking(move || {
+      let mut conn = db.lock().map_err(MemoryError::lock_error)?;
+      let tx = conn.transaction()?;
+      let current = load_memory_item(&tx, &memory_id)?
+        .ok_or_else(|| MemoryError::ValidationError(format!("记忆不存在: {}", memory_id)))?;
+      if current.version != expected_version {
+        return Err(MemoryError::VersionConflict {
+          current: current.version,
+        });
+      }
+      if matches!(current.freshness_status.as_str(), "expired" | "superseded") {
+        return Err(MemoryError::ValidationError(
+          "已失效的记忆不能编辑".to_string(),
+        ));
+      }
+
+      let entity_name = fields
+        .entity_name
+        .map(|s| s.trim().to_string())
+        .unwrap_or(current.entity_name);
+      let entity_type = fields
+        .entity_type
+        .map(|s| s.trim().to_string())
+        .unwrap_or(current.entity_type);
+      let content = fields
+        .content
+        .map(|s| s.trim().to_string())
+        .unwrap_or(current.content);
+      let summary = fields
+        .summary
+        .map(|s| s.trim().to_string())
+        .unwrap_or(current.summary);
+      let tags = fields
+        .tags
+        .map(|t| normalize_tags(&t).join(" "))
+        .unwrap_or(current.tags);
+      let confidence = fields.confidence.unwrap_or(current.confidence);
+
+      if entity_name.is_empty() || entity_type.is_empty() || content.is_empty() {
+        return Err(MemoryError::ValidationError(
+          "实体名称、类型和内容不能为空".to_string(),
+        ));
+      }
+      if content.chars().count() > 500 {
+        return Err(MemoryError::ValidationError(
+          "记忆内容不能超过 500 字".to_string(),
+        ));
+      }
+      if !(0.0..=1.0).contains(&confidence) {
+        return Err(MemoryError::ValidationError(
+          "confidence 必须在 0 到 1 之间".to_string(),
+        ));
+      }
+
+      // version 条件再校验一次，防止并发写入穿插
+      let changed = tx.execute(
+        "UPDATE memory_items
+         SET entity_name = ?1, entity_type = ?2, content = ?3, summary = ?4, tags = ?5,
+             confidence = ?6, freshness_status = 'fresh', updated_at = ?7,
+             version = version + 1
+         WHERE id = ?8 AND version = ?9",
+        params![
+          entity_name,
+          entity_type,
+          content,
+          summary,
+          tags,
+          confidence,
+          now_secs(),
+          memory_id,
+          expected_version
+        ],
+      )?;
+      if changed == 0 {
+        let current: i64 = tx.query_row(
+          "SELECT version FROM memory_items WHERE id = ?1",
+          params![memory_id],
+          |row| row.get(0),
+        )?;
+        return Err(MemoryError::VersionConflict { current });
+      }
+      let updated = load_memory_item(&tx, &memory_id)?
+        .ok_or_else(|| MemoryError::ValidationError(format!("记忆不存在: {}", memory_id)))?;
+      tx.commit()?;
+      Ok(updated)
+    })
+    .await
+    .map_err(|e| MemoryError::LockError(e.to_string()))?
+  }
+
+  /// 获取某个文档的 content 层记忆（fresh/stale），可按标签过滤
+  pub async fn get_document_memories(
+    &self,
+    file_path: &str,
+    tags: Vec<String>,
+  ) -> Result<Vec<MemoryItem>, MemoryError> {
+    let db = self.db.clone();
+    let file_path = file_path.replace('\\', "/");
+    let tags = normalize_tags(&tags);
+    tokio::task::spawn_blocking(move || {
+      let conn = db.lock().map_err(MemoryError::lock_error)?;
+      let sql = format!(
+        "SELECT id, layer, scope_type, scope_id, entity_type, entity_name, content, summary,
+                tags, source_kind, source_ref, confidence, freshness_status, readonly,
+                access_count, last_accessed_at, created_at, updated_at, version
+         FROM memory_items
+         WHERE layer = 'content' AND source_ref = ?1
+           AND freshness_status IN ('fresh', 'stale'){}
+         ORDER BY updated_at DESC",
+        tag_filter_clause("tags", tags.len(), 2)
+      );
+      let mut param_values: Vec<Box<dyn rusqlite::ToSql>> = vec![Box::new(file_path)];
+      for tag in &tags {
+        param_values.push(Box::new(tag.clone()));
+      }
+      let params_refs: Vec<&dyn rusqlite::ToSql> =
+        param_values.iter().map(|b| b.as_ref()).collect();
+      let mut stmt = conn.prepare(&sql)?;
+      let items = stmt
+        .query_map(params_refs.as_slice(), map_row_to_memory_item)?
+        .filter_map(|r| r.ok())
+        .collect();
+      Ok(items)
+    })
+    .await
+    .map_err(|e| MemoryError::LockError(e.to_string()))?
+  }
+
   /// 检索是否存在 content 层记忆的最近提取时间（用于写入节流）
   pub async fn get_last_content_extraction_time(&self, file_path: &str) -> Option<i64> {
     let db = self.db.clone();
@@ -957,9 +1823,10 @@ impl MemoryService {
                  WHERE layer = ?2 AND freshness_status NOT IN ('expired', 'superseded')",
         params![now, layer],
       )?;
-      eprintln!(
+      tracing::debug!(
         "[memory] P2: batch expired layer={}, count={}",
-        layer, count
+        layer,
+        count
       );
       Ok(count as u64)
     })
@@ -978,12 +1845,173 @@ impl MemoryService {
         "UPDATE memory_items SET freshness_status = 'expired', updated_at = ?1 WHERE id = ?2",
         params![now, id],
       )?;
-      eprintln!("[memory] P2: user expired memory_id={}", id);
+      tracing::debug!("[memory] P2: user expired memory_id={}", id);
       Ok(())
     })
     .await
     .map_err(|e| MemoryError::AiCallFailed(e.to_string()))?
   }
+
+  // ── 导出 / 导入 ─────────────────────────────────────────────────────────
+
+  /// 将工作区全部记忆（含已过期项，保留状态）导出为 JSON 文件，返回导出条数
+  pub async fn export_memories(&self, path: &Path) -> Result<usize, MemoryError> {
+    let db = self.db.clone();
+    let workspace_path = self.workspace_path.to_string_lossy().to_string();
+    let path = path.to_path_buf();
+    tokio::task::spawn_blocking(move || {
+      let items: Vec<MemoryItem> = {
+        let conn = db.lock().map_err(MemoryError::lock_error)?;
+        let mut stmt = conn.prepare(
+          "SELECT id, layer, scope_type, scope_id, entity_type, entity_name,
+                  content, summary, tags, source_kind, source_ref, confidence,
+                  freshness_status, readonly, access_count, last_accessed_at,
+                  created_at, updated_at, version
+           FROM memory_items
+           ORDER BY created_at ASC",
+        )?;
+        let rows = stmt
+          .query_map([], map_row_to_memory_item)?
+          .collect::<Result<Vec<_>, _>>()?;
+        rows
+      };
+
+      let bundle = MemoryExportBundle {
+        schema_version: MEMORY_EXPORT_SCHEMA_VERSION,
+        exported_at: now_secs(),
+        workspace_path,
+        items,
+      };
+      let json = serde_json::to_string_pretty(&bundle)?;
+      if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+          .map_err(|e| MemoryError::ValidationError(format!("创建导出目录失败: {}", e)))?;
+      }
+      std::fs::write(&path, json)
+        .map_err(|e| MemoryError::ValidationError(format!("写入导出文件失败: {}", e)))?;
+      Ok(bundle.items.len())
+    })
+    .await
+    .map_err(|e| MemoryError::LockError(e.to_string()))?
+  }
+
+  /// 从导出包合并记忆：同层同作用域下已有相同名称和内容的记忆时跳过；
+  /// 同 id 或同名的有效记忆在导入项更新时用导入内容覆盖，否则跳过；其余新增。
+  /// 来自其他工作区的 workspace 级记忆会改写到当前工作区。
+  pub async fn import_memories(&self, path: &Path) -> Result<MemoryImportSummary, MemoryError> {
+    let content = tokio::fs::read_to_string(path)
+      .await
+      .map_err(|e| MemoryError::ValidationError(format!("读取导入文件失败: {}", e)))?;
+    let bundle: MemoryExportBundle = serde_json::from_str(&content)?;
+    if bundle.schema_version > MEMORY_EXPORT_SCHEMA_VERSION {
+      return Err(MemoryError::ValidationError(format!(
+        "导入文件版本 {} 高于当前支持的版本 {}",
+        bundle.schema_version, MEMORY_EXPORT_SCHEMA_VERSION
+      )));
+    }
+
+    let db = self.db.clone();
+    let workspace_path = self.workspace_path.to_string_lossy().to_string();
+    tokio::task::spawn_blocking(move || {
+      let mut conn = db.lock().map_err(MemoryError::lock_error)?;
+      let tx = conn.transaction()?;
+      let mut summary = MemoryImportSummary::default();
+
+      for mut item in bundle.items {
+        if item.scope_type == MemoryScopeType::Workspace.as_str()
+          && item.scope_id == bundle.workspace_path
+        {
+          item.scope_id = workspace_path.clone();
+        }
+
+        // 同 id 的记忆优先；否则按同层同作用域下的名称匹配仍有效的记忆
+        let existing: Option<(String, String, i64, bool)> = tx
+          .query_row(
+            "SELECT id, content, updated_at, id = ?1 FROM memory_items
+             WHERE id = ?1
+                OR (layer = ?2 AND scope_id = ?3 AND entity_name = ?4
+                    AND (content = ?5 OR freshness_status = 'fresh'))
+             ORDER BY id = ?1 DESC, content = ?5 DESC, updated_at DESC
+             LIMIT 1",
+            params![
+              item.id,
+              item.layer,
+              item.scope_id,
+              item.entity_name,
+              item.content
+            ],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+          )
+          .optional()?;
+
+        if let Some((existing_id, existing_content, existing_updated_at, same_id)) = existing {
+          // 内容相同（且不是同一条）视为重复；只有导入项更新时才覆盖现有记忆
+          let is_duplicate = !same_id && existing_content == item.content;
+          if is_duplicate || item.updated_at <= existing_updated_at {
+            summary.skipped += 1;
+            continue;
+          }
+          tx.execute(
+            "UPDATE memory_items
+             SET entity_type = ?1, content = ?2, summary = ?3, tags = ?4, confidence = ?5,
+                 freshness_status = ?6, updated_at = ?7, version = version + 1
+             WHERE id = ?8",
+            params![
+              item.entity_type,
+              item.content,
+              item.summary,
+              item.tags,
+              item.confidence,
+              item.freshness_status,
+              item.updated_at,
+              existing_id,
+            ],
+          )?;
+          summary.updated += 1;
+          continue;
+        }
+
+        tx.execute(
+          "INSERT INTO memory_items (
+              id, layer, scope_type, scope_id, entity_type, entity_name,
+              content, summary, tags, source_kind, source_ref, confidence,
+              freshness_status, readonly, access_count, last_accessed_at,
+              created_at, updated_at
+           ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18)",
+          params![
+            item.id,
+            item.layer,
+            item.scope_type,
+            item.scope_id,
+            item.entity_type,
+            item.entity_name,
+            item.content,
+            item.summary,
+            item.tags,
+            item.source_kind,
+            item.source_ref,
+            item.confidence,
+            item.freshness_status,
+            item.readonly as i64,
+            item.access_count,
+            item.last_accessed_at,
+            item.created_at,
+            item.updated_at,
+          ],
+        )?;
+        summary.imported += 1;
+      }
+
+      tx.commit()?;
+      tracing::debug!(
+        "[memory] import finished: imported={}, updated={}, skipped={}",
+        summary.imported, summary.updated, summary.skipped
+      );
+      Ok(summary)
+    })
+    .await
+    .map_err(|e| MemoryError::LockError(e.to_string()))?
+  }
 }
 
 // ── FTS5 检索实现 ──────────────────────────────────────────────────────────
@@ -1017,6 +2045,7 @@ fn execute_fts_search(
     MemorySearchScope::User => return Ok(MemorySearchResponse::empty()),
     MemorySearchScope::All => vec!["tab", "content", "workspace_long_term"],
   };
+  let tags = normalize_tags(params.tags.as_deref().unwrap_or_default());
 
   let scope_used: Vec<String> = scope_ids.iter().map(|s| s.clone()).collect();
 
@@ -1027,6 +2056,7 @@ fn execute_fts_search(
       &scope_ids,
       &layer_filter,
       &entity_types,
+      &tags,
       limit,
       &scope_used,
     );
@@ -1059,11 +2089,16 @@ fn execute_fts_search(
     )
   };
 
-  let limit_idx = 2 + scope_ids.len() + layer_filter.len() + entity_types.len();
+  let tag_idx = 2 + scope_ids.len() + layer_filter.len() + entity_types.len();
+  let limit_idx = tag_idx + tags.len();
   let entity_filter_sql = if let Some(ph) = &entity_placeholders {
-    format!(" AND m.entity_type IN ({})", ph)
+    format!(
+      " AND m.entity_type IN ({}){}",
+      ph,
+      tag_filter_clause("m.tags", tags.len(), tag_idx)
+    )
   } else {
-    String::new()
+    tag_filter_clause("m.tags", tags.len(), tag_idx)
   };
 
   let sql = format!(
@@ -1074,7 +2109,7 @@ fn execute_fts_search(
             m.tags, m.source_kind, m.source_ref, m.confidence,
             m.freshness_status, m.readonly, m.access_count,
             m.last_accessed_at, m.created_at, m.updated_at,
-            memory_items_fts.rank AS fts_rank
+            memory_items_fts.rank AS fts_rank, m.version
         FROM memory_items_fts
         JOIN memory_items m ON memory_items_fts.rowid = m.rowid
         WHERE memory_items_fts MATCH ?1
@@ -1108,6 +2143,9 @@ fn execute_fts_search(
   for et in &entity_types {
     param_values.push(Box::new(et.clone()));
   }
+  for tag in &tags {
+    param_values.push(Box::new(tag.clone()));
+  }
   param_values.push(Box::new(limit as i64));
 
   let params_refs: Vec<&dyn rusqlite::ToSql> = param_values.iter().map(|b| b.as_ref()).collect();
@@ -1115,12 +2153,13 @@ fn execute_fts_search(
   let mut stmt = match conn.prepare(&sql) {
     Ok(s) => s,
     Err(e) => {
-      eprintln!("FTS5 prepare failed, fallback to recent: {:?}", e);
+      tracing::warn!("FTS5 prepare failed, fallback to recent: {:?}", e);
       return fetch_recent_memories(
         &conn,
         &scope_ids,
         &layer_filter,
         &entity_types,
+        &tags,
         limit,
         &scope_used,
       );
@@ -1159,34 +2198,467 @@ fn execute_fts_search(
     }
   }
 
-  let items: Vec<MemorySearchResult> = rows
-    .into_iter()
-    .map(|(mut item, rank)| {
-      // 同步更新返回对象的 freshness_status
-      if stale_ids.contains(&item.id) {
-        item.freshness_status = "stale".to_string();
-      }
-      let relevance_score = if max_rank > 0.0 {
-        rank.abs() / max_rank
-      } else {
-        0.5
-      };
-      let source_label = format_source_label(&item);
-      MemorySearchResult {
-        item,
-        relevance_score,
-        source_label,
-      }
+  let items: Vec<MemorySearchResult> = rows
+    .into_iter()
+    .map(|(mut item, rank)| {
+      // 同步更新返回对象的 freshness_status
+      if stale_ids.contains(&item.id) {
+        item.freshness_status = "stale".to_string();
+      }
+      let relevance_score = if max_rank > 0.0 {
+        rank.abs() / max_rank
+      } else {
+        0.5
+      };
+      let source_label = format_source_label(&item);
+      MemorySearchResult {
+        item,
+        relevance_score,
+        source_label,
+      }
+    })
+    .collect();
+
+  let total = items.len();
+  Ok(MemorySearchResponse {
+    items,
+    total_found: total,
+    scope_used,
+    timed_out: false,
+  })
+}
+
+// ── 语义检索实现 ────────────────────────────────────────────────────────────
+
+const EMBEDDING_BATCH_SIZE: usize = 32;
+const EMBEDDING_SYNC_LIMIT: usize = 256;
+
+fn embedding_text(entity_name: &str, content: &str, summary: &str) -> String {
+  let mut text = format!("{}\n{}", entity_name, content);
+  if !summary.is_empty() {
+    text.push('\n');
+    text.push_str(summary);
+  }
+  text
+}
+
+/// 向量以小端 f32 序列存入 BLOB
+fn encode_embedding(vector: &[f32]) -> Vec<u8> {
+  vector.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+fn decode_embedding(bytes: &[u8]) -> Vec<f32> {
+  bytes
+    .chunks_exact(4)
+    .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    .collect()
+}
+
+fn cosine_similarity(a: &[f32], b: &[f32]) -> f64 {
+  if a.len() != b.len() || a.is_empty() {
+    return 0.0;
+  }
+  let mut dot = 0.0f64;
+  let mut norm_a = 0.0f64;
+  let mut norm_b = 0.0f64;
+  for (x, y) in a.iter().zip(b) {
+    let (x, y) = (*x as f64, *y as f64);
+    dot += x * y;
+    norm_a += x * x;
+    norm_b += y * y;
+  }
+  if norm_a == 0.0 || norm_b == 0.0 {
+    return 0.0;
+  }
+  dot / (norm_a.sqrt() * norm_b.sqrt())
+}
+
+fn execute_semantic_search(
+  db: &Arc<Mutex<Connection>>,
+  params: &SearchMemoriesParams,
+  model: &str,
+  query_vector: &[f32],
+) -> Result<MemorySearchResponse, MemoryError> {
+  let scope_ids = build_scope_ids(params);
+  if scope_ids.is_empty() {
+    return Ok(MemorySearchResponse::empty());
+  }
+
+  let layer_filter: Vec<&str> = match &params.scope {
+    MemorySearchScope::Tab => vec!["tab"],
+    MemorySearchScope::Content => vec!["content"],
+    MemorySearchScope::WorkspaceLongTerm => vec!["workspace_long_term"],
+    MemorySearchScope::User => return Ok(MemorySearchResponse::empty()),
+    MemorySearchScope::All => vec!["tab", "content", "workspace_long_term"],
+  };
+  let entity_types: Vec<String> = params
+    .entity_types
+    .clone()
+    .unwrap_or_default()
+    .into_iter()
+    .map(|s| s.trim().to_string())
+    .filter(|s| !s.is_empty())
+    .collect();
+  let tags = normalize_tags(params.tags.as_deref().unwrap_or_default());
+  let limit = params.limit.unwrap_or(10).min(50);
+
+  let conn = db.lock().map_err(MemoryError::lock_error)?;
+  let mut stmt = conn.prepare(
+    "SELECT
+         m.id, m.layer, m.scope_type, m.scope_id,
+         m.entity_type, m.entity_name, m.content, m.summary,
+         m.tags, m.source_kind, m.source_ref, m.confidence,
+         m.freshness_status, m.readonly, m.access_count,
+         m.last_accessed_at, m.created_at, m.updated_at,
+         e.vector, m.version
+     FROM memory_embeddings e
+     JOIN memory_items m ON m.id = e.memory_id
+     WHERE e.model = ?1
+       AND m.freshness_status IN ('fresh', 'stale')",
+  )?;
+
+  let mut scored: Vec<(MemoryItem, f64)> = stmt
+    .query_map(params![model], |row| {
+      let item = map_row_to_memory_item(row)?;
+      let vector: Vec<u8> = row.get(18)?;
+      Ok((item, vector))
+    })?
+    .filter_map(|r| r.ok())
+    .filter(|(item, _)| {
+      scope_ids.contains(&item.scope_id)
+        && layer_filter.contains(&item.layer.as_str())
+        && (entity_types.is_empty() || entity_types.contains(&item.entity_type))
+        && (tags.is_empty() || parse_tags(&item.tags).iter().any(|t| tags.contains(t)))
+    })
+    .map(|(item, vector)| {
+      let score = cosine_similarity(query_vector, &decode_embedding(&vector));
+      (item, score)
+    })
+    .collect();
+
+  scored.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+  scored.truncate(limit);
+
+  let items: Vec<MemorySearchResult> = scored
+    .into_iter()
+    .map(|(item, relevance_score)| {
+      let source_label = format_source_label(&item);
+      MemorySearchResult {
+        item,
+        relevance_score,
+        source_label,
+      }
+    })
+    .collect();
+
+  let total = items.len();
+  Ok(MemorySearchResponse {
+    items,
+    total_found: total,
+    scope_used: scope_ids,
+    timed_out: false,
+  })
+}
+
+// ── 去重实现 ────────────────────────────────────────────────────────────────
+
+const DEFAULT_DEDUPE_THRESHOLD: f64 = 0.85;
+const EMBEDDING_DUPLICATE_THRESHOLD: f64 = 0.95;
+
+/// 归一化文本：小写，去掉空白与标点
+fn normalize_for_dedupe(text: &str) -> Vec<char> {
+  text
+    .chars()
+    .filter(|c| c.is_alphanumeric())
+    .flat_map(|c| c.to_lowercase())
+    .collect()
+}
+
+/// 字符二元组 Dice 系数，对中英文都适用；返回 0..=1
+fn text_similarity(a: &str, b: &str) -> f64 {
+  let a = normalize_for_dedupe(a);
+  let b = normalize_for_dedupe(b);
+  if a == b {
+    return if a.is_empty() { 0.0 } else { 1.0 };
+  }
+  if a.len() < 2 || b.len() < 2 {
+    return 0.0;
+  }
+  let mut counts: std::collections::HashMap<(char, char), i32> = std::collections::HashMap::new();
+  for w in a.windows(2) {
+    *counts.entry((w[0], w[1])).or_insert(0) += 1;
+  }
+  let mut overlap = 0;
+  for w in b.windows(2) {
+    if let Some(c) = counts.get_mut(&(w[0], w[1])) {
+      if *c > 0 {
+        *c -= 1;
+        overlap += 1;
+      }
+    }
+  }
+  2.0 * overlap as f64 / (a.len() - 1 + b.len() - 1) as f64
+}
+
+fn memory_similarity(
+  a: &MemoryItem,
+  b: &MemoryItem,
+  embeddings: &std::collections::HashMap<String, (String, Vec<f32>)>,
+) -> (f64, bool) {
+  let text = text_similarity(
+    &format!("{} {}", a.entity_name, a.content),
+    &format!("{} {}", b.entity_name, b.content),
+  );
+  let semantic = match (embeddings.get(&a.id), embeddings.get(&b.id)) {
+    (Some((model_a, va)), Some((model_b, vb))) if model_a == model_b => cosine_similarity(va, vb),
+    _ => 0.0,
+  };
+  (
+    text.max(semantic),
+    semantic >= EMBEDDING_DUPLICATE_THRESHOLD,
+  )
+}
+
+/// 按保留优先级（置信度 > 使用次数 > 更新时间）贪心聚类：
+/// 每条记忆与已有组的保留项比较，相似则并入，否则自成一组
+fn find_duplicate_groups(
+  items: &[MemoryItem],
+  embeddings: &std::collections::HashMap<String, (String, Vec<f32>)>,
+  threshold: f64,
+) -> Vec<MemoryDuplicateGroup> {
+  let mut sorted: Vec<&MemoryItem> = items.iter().collect();
+  sorted.sort_by(|a, b| {
+    b.confidence
+      .partial_cmp(&a.confidence)
+      .unwrap_or(std::cmp::Ordering::Equal)
+      .then(b.access_count.cmp(&a.access_count))
+      .then(b.updated_at.cmp(&a.updated_at))
+  });
+
+  let mut clusters: Vec<(&MemoryItem, MemoryDuplicateGroup)> = Vec::new();
+  for item in sorted {
+    let matched = clusters.iter_mut().find_map(|(kept, group)| {
+      if kept.layer != item.layer || kept.scope_id != item.scope_id {
+        return None;
+      }
+      let (similarity, semantic_hit) = memory_similarity(kept, item, embeddings);
+      (similarity >= threshold || semantic_hit).then_some((group, similarity))
+    });
+    match matched {
+      Some((group, similarity)) => {
+        group.merged_ids.push(item.id.clone());
+        group.min_similarity = group.min_similarity.min(similarity);
+      }
+      None => clusters.push((
+        item,
+        MemoryDuplicateGroup {
+          kept_id: item.id.clone(),
+          kept_entity_name: item.entity_name.clone(),
+          merged_ids: Vec::new(),
+          min_similarity: 1.0,
+        },
+      )),
+    }
+  }
+
+  clusters
+    .into_iter()
+    .map(|(_, group)| group)
+    .filter(|group| !group.merged_ids.is_empty())
+    .collect()
+}
+
+// ── 文档锚点实现 ────────────────────────────────────────────────────────────
+
+/// 锚点失效后，块文本相似度达到该值才自动修复
+const ANCHOR_REPAIR_THRESHOLD: f64 = 0.6;
+
+fn anchor_kind_label(kind: AnchorKind) -> &'static str {
+  match kind {
+    AnchorKind::Heading => "标题",
+    AnchorKind::Paragraph => "段落",
+  }
+}
+
+/// 锚点中的文档路径统一存为绝对路径（`/` 分隔），相对路径按工作区根目录解析
+fn doc_path_key(workspace_path: &Path, doc_path: &str) -> String {
+  let path = Path::new(doc_path);
+  let full = if path.is_absolute() {
+    path.to_path_buf()
+  } else {
+    workspace_path.join(path)
+  };
+  full.to_string_lossy().replace('\\', "/")
+}
+
+/// 文档保存后后台检查其记忆锚点（fire-and-forget，工作区无 workspace.db 时跳过）
+pub fn check_anchors_after_save(workspace_path: &Path, path: &Path) {
+  let workspace_path = workspace_path.to_path_buf();
+  let path = path.to_string_lossy().to_string();
+  tokio::spawn(async move {
+    let Ok(service) = MemoryService::new(&workspace_path) else {
+      return;
+    };
+    if let Err(e) = service.check_memory_consistency(Some(&path)).await {
+      tracing::warn!("[memory] 保存后检查锚点失败 ({}): {}", path, e);
+    }
+  });
+}
+
+fn read_document_blocks(path: &Path) -> Result<Vec<DocumentBlock>, MemoryError> {
+  let content = std::fs::read_to_string(path)
+    .map_err(|e| MemoryError::ValidationError(format!("读取文档失败 {}: {}", path.display(), e)))?;
+  Ok(extract_blocks(path, &content))
+}
+
+fn best_matching_block<'a>(
+  blocks: &'a [DocumentBlock],
+  kind: AnchorKind,
+  text: &str,
+  min_similarity: f64,
+) -> Option<(&'a DocumentBlock, f64)> {
+  blocks
+    .iter()
+    .filter(|b| b.kind == kind)
+    .map(|b| (b, text_similarity(&b.text, text)))
+    .filter(|(_, similarity)| *similarity >= min_similarity)
+    .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal))
+}
+
+fn load_memory_anchors(
+  conn: &Connection,
+  doc_path: Option<&str>,
+) -> Result<Vec<MemoryAnchor>, MemoryError> {
+  let mut stmt = conn.prepare(
+    "SELECT a.memory_id, a.doc_path, a.anchor_kind, a.anchor, a.anchor_text, a.status
+     FROM memory_anchors a
+     JOIN memory_items m ON m.id = a.memory_id
+     WHERE (?1 IS NULL OR a.doc_path = ?1)
+       AND m.freshness_status IN ('fresh', 'stale')
+     ORDER BY a.doc_path, a.updated_at",
+  )?;
+  let anchors = stmt
+    .query_map(params![doc_path], |row| {
+      let kind: String = row.get(2)?;
+      Ok((
+        MemoryAnchor {
+          memory_id: row.get(0)?,
+          doc_path: row.get(1)?,
+          anchor_kind: AnchorKind::Heading,
+          anchor: row.get(3)?,
+          anchor_text: row.get(4)?,
+          status: row.get(5)?,
+        },
+        kind,
+      ))
+    })?
+    .filter_map(|r| r.ok())
+    .filter_map(|(mut anchor, kind)| {
+      anchor.anchor_kind = AnchorKind::from_str(&kind)?;
+      Some(anchor)
+    })
+    .collect();
+  Ok(anchors)
+}
+
+fn save_memory_anchor(conn: &Connection, anchor: &MemoryAnchor) -> Result<(), MemoryError> {
+  conn.execute(
+    "INSERT OR REPLACE INTO memory_anchors
+        (memory_id, doc_path, anchor_kind, anchor, anchor_text, status, updated_at)
+     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+    params![
+      anchor.memory_id,
+      anchor.doc_path,
+      anchor.anchor_kind.as_str(),
+      anchor.anchor,
+      anchor.anchor_text,
+      anchor.status,
+      now_secs(),
+    ],
+  )?;
+  Ok(())
+}
+
+// ── 统计实现 ────────────────────────────────────────────────────────────────
+
+fn map_count_entry(row: &rusqlite::Row<'_>) -> rusqlite::Result<MemoryCountEntry> {
+  Ok(MemoryCountEntry {
+    key: row.get(0)?,
+    count: row.get::<_, i64>(1)? as usize,
+    last_updated_at: row.get(2)?,
+  })
+}
+
+/// 按列分组计数；column 只接受内部常量
+fn count_memories_by(
+  conn: &Connection,
+  column: &str,
+) -> Result<Vec<MemoryCountEntry>, MemoryError> {
+  let sql = format!(
+    "SELECT {col}, COUNT(*), MAX(updated_at) FROM memory_items
+     GROUP BY {col} ORDER BY COUNT(*) DESC",
+    col = column
+  );
+  let mut stmt = conn.prepare(&sql)?;
+  let rows = stmt
+    .query_map([], map_count_entry)?
+    .filter_map(|r| r.ok())
+    .collect();
+  Ok(rows)
+}
+
+// ── 标签 ────────────────────────────────────────────────────────────────────
+
+/// 标签规范化：去空白、转小写，内部空白替换为 `_`（tags 列以空格分隔存储）
+pub fn normalize_tag(tag: &str) -> String {
+  tag
+    .split_whitespace()
+    .collect::<Vec<_>>()
+    .join("_")
+    .to_lowercase()
+}
+
+fn normalize_tags(tags: &[String]) -> Vec<String> {
+  let mut normalized: Vec<String> = Vec::new();
+  for tag in tags.iter().map(|t| normalize_tag(t)) {
+    if !tag.is_empty() && !normalized.contains(&tag) {
+      normalized.push(tag);
+    }
+  }
+  normalized
+}
+
+/// 解析 tags 列：兼容空格分隔与 JSON 数组（列默认值 / user_memory.db）两种格式
+pub fn parse_tags(raw: &str) -> Vec<String> {
+  let raw = raw.trim();
+  if raw.starts_with('[') {
+    if let Ok(list) = serde_json::from_str::<Vec<String>>(raw) {
+      return normalize_tags(&list);
+    }
+  }
+  let list: Vec<String> = raw.split_whitespace().map(|s| s.to_string()).collect();
+  normalize_tags(&list)
+}
+
+/// 生成“命中任一标签”的 SQL 片段，占位符从 start_idx 开始编号；
+/// tags 列可能是空格分隔或 JSON 数组，两种格式都按整个标签匹配
+fn tag_filter_clause(column: &str, tag_count: usize, start_idx: usize) -> String {
+  if tag_count == 0 {
+    return String::new();
+  }
+  let conditions: Vec<String> = (0..tag_count)
+    .map(|i| {
+      let idx = start_idx + i;
+      format!(
+        "(EXISTS (SELECT 1 FROM json_each(CASE WHEN json_valid({col}) THEN {col} ELSE '[]' END) AS jt
+                  WHERE replace(lower(trim(jt.value)), ' ', '_') = ?{idx})
+          OR instr(' ' || lower({col}) || ' ', ' ' || ?{idx} || ' ') > 0)",
+        col = column,
+        idx = idx
+      )
     })
     .collect();
-
-  let total = items.len();
-  Ok(MemorySearchResponse {
-    items,
-    total_found: total,
-    scope_used,
-    timed_out: false,
-  })
+  format!(" AND ({})", conditions.join(" OR "))
 }
 
 fn fetch_recent_memories(
@@ -1194,6 +2666,7 @@ fn fetch_recent_memories(
   scope_ids: &[String],
   layer_filter: &[&str],
   entity_types: &[String],
+  tags: &[String],
   limit: usize,
   scope_used: &[String],
 ) -> Result<MemorySearchResponse, MemoryError> {
@@ -1225,17 +2698,22 @@ fn fetch_recent_memories(
         .join(", "),
     )
   };
+  let tag_idx = 1 + scope_ids.len() + layer_filter.len() + entity_types.len();
   let entity_filter_sql = if let Some(ph) = &entity_ph {
-    format!(" AND entity_type IN ({})", ph)
+    format!(
+      " AND entity_type IN ({}){}",
+      ph,
+      tag_filter_clause("tags", tags.len(), tag_idx)
+    )
   } else {
-    String::new()
+    tag_filter_clause("tags", tags.len(), tag_idx)
   };
-  let limit_idx = 1 + scope_ids.len() + layer_filter.len() + entity_types.len();
+  let limit_idx = tag_idx + tags.len();
 
   let sql = format!(
     "SELECT id, layer, scope_type, scope_id, entity_type, entity_name, content, summary,
                 tags, source_kind, source_ref, confidence, freshness_status, readonly,
-                access_count, last_accessed_at, created_at, updated_at
+                access_count, last_accessed_at, created_at, updated_at, version
          FROM memory_items
          WHERE scope_id IN ({scope_ph}) AND layer IN ({layer_ph})
            {entity_filter}
@@ -1258,6 +2736,9 @@ fn fetch_recent_memories(
   for et in entity_types {
     param_values.push(Box::new(et.clone()));
   }
+  for tag in tags {
+    param_values.push(Box::new(tag.clone()));
+  }
   param_values.push(Box::new(limit as i64));
   let params_refs: Vec<&dyn rusqlite::ToSql> = param_values.iter().map(|b| b.as_ref()).collect();
 
@@ -1404,9 +2885,10 @@ fn cascade_supersede_related(
       params![now, sim.id],
     )?;
     count += 1;
-    eprintln!(
+    tracing::debug!(
       "[memory] evolution: cascade superseded id={} (related to updated {})",
-      sim.id, new_item_id
+      sim.id,
+      new_item_id
     );
   }
   Ok(count)
@@ -1483,9 +2965,23 @@ fn map_row_to_memory_item(row: &rusqlite::Row<'_>) -> rusqlite::Result<MemoryIte
     last_accessed_at: row.get(15)?,
     created_at: row.get(16)?,
     updated_at: row.get(17)?,
+    version: row.get("version")?,
   })
 }
 
+fn load_memory_item(conn: &Connection, memory_id: &str) -> rusqlite::Result<Option<MemoryItem>> {
+  conn
+    .query_row(
+      "SELECT id, layer, scope_type, scope_id, entity_type, entity_name, content, summary,
+              tags, source_kind, source_ref, confidence, freshness_status, readonly,
+              access_count, last_accessed_at, created_at, updated_at, version
+       FROM memory_items WHERE id = ?1",
+      params![memory_id],
+      map_row_to_memory_item,
+    )
+    .optional()
+}
+
 fn map_row_to_memory_item_no_rank(row: &rusqlite::Row<'_>) -> rusqlite::Result<MemoryItem> {
   // Same column order as SELECT without rank column
   map_row_to_memory_item(row)
@@ -1532,7 +3028,7 @@ pub fn find_similar_memories(
   let sql = "SELECT m.id, m.layer, m.scope_type, m.scope_id, m.entity_type, m.entity_name,
                       m.content, m.summary, m.tags, m.source_kind, m.source_ref, m.confidence,
                       m.freshness_status, m.readonly, m.access_count, m.last_accessed_at,
-                      m.created_at, m.updated_at
+                      m.created_at, m.updated_at, m.version
                FROM memory_items m
                WHERE m.scope_id = ?1 AND m.layer = ?2
                  AND m.freshness_status IN ('fresh', 'stale')
@@ -1608,7 +3104,7 @@ pub async fn llm_judge_action(
   let response = match provider.chat_simple(&prompt, 100).await {
     Ok(r) => r,
     Err(e) => {
-      eprintln!(
+      tracing::warn!(
         "[memory] llm_judge_action: AI call failed: {:?}, defaulting to ADD",
         e
       );
@@ -1624,7 +3120,7 @@ pub async fn llm_judge_action(
   let v: serde_json::Value = match serde_json::from_str(json_str) {
     Ok(v) => v,
     Err(_) => {
-      eprintln!(
+      tracing::warn!(
         "[memory] llm_judge_action: parse failed, defaulting to ADD. response={}",
         response
       );
@@ -1776,11 +3272,12 @@ pub fn parse_memory_candidates(
 /// Token budget: augmentation layer uses at most ~10% of a typical 4096-token context.
 /// Rough heuristic: 1 token ≈ 4 chars, 10% of 4096 ≈ 400 tokens ≈ 1600 chars.
 /// We reserve a slightly larger budget (2000 chars) to allow for varied model sizes.
-const MEMORY_INJECT_BUDGET_CHARS: usize = 2000;
+/// 工作区设置中的 context.token_budget 默认值即由此换算。
+pub const MEMORY_INJECT_BUDGET_CHARS: usize = 2000;
 
 /// Trim formatted memory block to fit within the token budget.
 /// If the block is too long, removes items from the end (lowest priority) until it fits.
-fn trim_memory_to_budget(lines: &mut Vec<String>, header: Option<&str>) {
+fn trim_memory_to_budget(lines: &mut Vec<String>, header: Option<&str>, budget_chars: usize) {
   // Calculate total size including wrapper tags
   let wrapper_overhead = "[记忆库信息]\n\n[/记忆库信息]".len();
   let header_len = header.map(|h| h.len() + 1).unwrap_or(0);
@@ -1788,14 +3285,14 @@ fn trim_memory_to_budget(lines: &mut Vec<String>, header: Option<&str>) {
   loop {
     let current_len: usize =
       lines.iter().map(|l| l.len() + 1).sum::<usize>() + wrapper_overhead + header_len;
-    if current_len <= MEMORY_INJECT_BUDGET_CHARS || lines.is_empty() {
+    if current_len <= budget_chars || lines.is_empty() {
       break;
     }
     lines.pop(); // remove lowest-priority item (last in list)
   }
 }
 
-pub fn format_memory_for_injection(items: &[MemorySearchResult]) -> String {
+pub fn format_memory_for_injection(items: &[MemorySearchResult], budget_chars: usize) -> String {
   if items.is_empty() {
     return String::new();
   }
@@ -1829,7 +3326,7 @@ pub fn format_memory_for_injection(items: &[MemorySearchResult]) -> String {
   }
 
   // Token budget trimming: drop lowest-priority items if block is too large
-  trim_memory_to_budget(&mut lines, anchor_header);
+  trim_memory_to_budget(&mut lines, anchor_header, budget_chars);
 
   if lines.is_empty() {
     return String::new();
@@ -1856,6 +3353,7 @@ fn format_source_label_with_file(item: &MemoryItem) -> String {
       format!("[项目内容 · {}]", file)
     }
     "workspace_long_term" => "[工作区记忆]".to_string(),
+    "user" => "[用户偏好]".to_string(),
     _ => "[记忆]".to_string(),
   }
 }
@@ -1870,13 +3368,13 @@ pub async fn memory_generation_task_tab(
 ) {
   let cfg = ExtractionConfig::load();
   if !cfg.enabled || !cfg.write_enabled {
-    eprintln!("[memory] tab extraction skipped: disabled by ExtractionConfig");
+    tracing::debug!("[memory] tab extraction skipped: disabled by ExtractionConfig");
     return;
   }
   let svc = match MemoryService::new(&workspace_path) {
     Ok(s) => s,
     Err(e) => {
-      eprintln!("[memory] tab extraction: MemoryService init failed: {}", e);
+      tracing::warn!("[memory] tab extraction: MemoryService init failed: {}", e);
       return;
     }
   };
@@ -1913,7 +3411,7 @@ pub async fn memory_generation_task_tab(
   let ai_output = match provider.chat_with_model(&prompt, 500, &cfg.model).await {
     Ok(text) => text,
     Err(e) => {
-      eprintln!("[memory] tab extraction: AI call failed: {:?}", e);
+      tracing::warn!("[memory] tab extraction: AI call failed: {:?}", e);
       return;
     }
   };
@@ -1928,7 +3426,7 @@ pub async fn memory_generation_task_tab(
   ) {
     Ok(c) => c,
     Err(e) => {
-      eprintln!("[memory] tab extraction: parse failed: {:?}", e);
+      tracing::warn!("[memory] tab extraction: parse failed: {:?}", e);
       return;
     }
   };
@@ -1942,9 +3440,9 @@ pub async fn memory_generation_task_tab(
     .upsert_tab_memories_with_judge(&tab_id, candidates, &provider)
     .await
   {
-    eprintln!("[memory] tab extraction: upsert_with_judge failed: {:?}", e);
+    tracing::warn!("[memory] tab extraction: upsert_with_judge failed: {:?}", e);
   } else {
-    eprintln!("[memory] tab extraction: done for tab={}", tab_id);
+    tracing::debug!("[memory] tab extraction: done for tab={}", tab_id);
   }
 }
 
@@ -1957,14 +3455,14 @@ pub async fn memory_generation_task_content(
 ) {
   let cfg = ExtractionConfig::load();
   if !cfg.enabled || !cfg.write_enabled {
-    eprintln!("[memory] content extraction skipped: disabled by ExtractionConfig");
+    tracing::debug!("[memory] content extraction skipped: disabled by ExtractionConfig");
     return;
   }
 
   let svc = match MemoryService::new(&workspace_path) {
     Ok(s) => s,
     Err(e) => {
-      eprintln!(
+      tracing::warn!(
         "[memory] content extraction: MemoryService init failed: {}",
         e
       );
@@ -1985,7 +3483,7 @@ pub async fn memory_generation_task_content(
   let ai_output = match provider.chat_with_model(&prompt, 500, &cfg.model).await {
     Ok(text) => text,
     Err(e) => {
-      eprintln!("[memory] content extraction: AI call failed: {:?}", e);
+      tracing::warn!("[memory] content extraction: AI call failed: {:?}", e);
       return;
     }
   };
@@ -2000,7 +3498,7 @@ pub async fn memory_generation_task_content(
   ) {
     Ok(c) => c,
     Err(e) => {
-      eprintln!("[memory] content extraction: parse failed: {:?}", e);
+      tracing::warn!("[memory] content extraction: parse failed: {:?}", e);
       return;
     }
   };
@@ -2014,12 +3512,12 @@ pub async fn memory_generation_task_content(
     .upsert_content_memories_with_judge(&file_path, candidates, &provider)
     .await
   {
-    eprintln!(
+    tracing::warn!(
       "[memory] content extraction: upsert_with_judge failed: {:?}",
       e
     );
   } else {
-    eprintln!("[memory] content extraction: done for file={}", file_path);
+    tracing::debug!("[memory] content extraction: done for file={}", file_path);
   }
 }
 
@@ -2028,7 +3526,7 @@ pub async fn startup_maintenance(workspace_path: &std::path::Path) {
   let svc = match MemoryService::new(workspace_path) {
     Ok(s) => s,
     Err(e) => {
-      eprintln!("[memory] startup_maintenance: init failed: {}", e);
+      tracing::warn!("[memory] startup_maintenance: init failed: {}", e);
       return;
     }
   };
@@ -2037,7 +3535,7 @@ pub async fn startup_maintenance(workspace_path: &std::path::Path) {
         let conn = match db.lock() {
             Ok(c) => c,
             Err(e) => {
-                eprintln!("[memory] startup_maintenance: lock failed: {}", e);
+                tracing::warn!("[memory] startup_maintenance: lock failed: {}", e);
                 return;
             }
         };
@@ -2057,7 +3555,7 @@ pub async fn startup_maintenance(workspace_path: &std::path::Path) {
             rusqlite::params![now - seven_days],
         ).unwrap_or(0);
 
-        eprintln!("[memory] startup_maintenance: deleted {} stale/expired + {} superseded", n1, n2);
+        tracing::debug!("[memory] startup_maintenance: deleted {} stale/expired + {} superseded", n1, n2);
     }).await;
 }
 
@@ -2072,7 +3570,7 @@ pub async fn on_tab_deleted(
   let svc = match MemoryService::new(&workspace_path) {
     Ok(s) => s,
     Err(e) => {
-      eprintln!("[memory] on_tab_deleted: init failed: {}", e);
+      tracing::warn!("[memory] on_tab_deleted: init failed: {}", e);
       return;
     }
   };
@@ -2080,7 +3578,7 @@ pub async fn on_tab_deleted(
   // 升格条件：轮次 >= 5 且有可用 provider
   if user_message_count >= 5 {
     let Some(provider) = provider else {
-      eprintln!(
+      tracing::debug!(
         "[memory] on_tab_deleted: provider unavailable, skip promotion for tab={}",
         tab_id
       );
@@ -2098,7 +3596,7 @@ pub async fn on_tab_deleted(
                      WHERE scope_type = 'tab' AND scope_id = ?2",
           rusqlite::params![now, tab_id_clone],
         );
-        eprintln!(
+        tracing::debug!(
           "[memory] on_tab_deleted: expired all tab memories for tab={}",
           tab_id_clone
         );
@@ -2147,7 +3645,7 @@ pub async fn on_tab_deleted(
              WHERE scope_type = 'tab' AND scope_id = ?2",
       rusqlite::params![now, tab_id_clone],
     );
-    eprintln!(
+    tracing::debug!(
       "[memory] on_tab_deleted: expired all tab memories for tab={}",
       tab_id_clone
     );
@@ -2220,7 +3718,7 @@ pub fn filter_sensitive_content(text: &str) -> String {
 
   // If >80% of lines are suspicious, the whole document may be adversarial — reject entirely
   if total > 5 && removed * 100 / total > 80 {
-    eprintln!(
+    tracing::debug!(
       "[memory] S-04: document rejected — {}% of lines flagged as sensitive",
       removed * 100 / total
     );
@@ -2366,13 +3864,13 @@ pub fn open_user_memory_db() -> Result<Connection, String> {
   Ok(conn)
 }
 
-/// P2: 向 user_memory.db 写入用户级记忆条目
-pub async fn upsert_user_memory(item: MemoryItemInput) -> Result<(), String> {
+/// P2: 向 user_memory.db 写入用户级记忆条目，返回记忆 id
+pub async fn upsert_user_memory(item: MemoryItemInput) -> Result<String, String> {
   tokio::task::spawn_blocking(move || {
     let conn = open_user_memory_db()?;
     let user_id = get_or_create_user_id()?;
     let now = now_secs();
-    let id = format!("usr-{}-{}", &user_id[..8.min(user_id.len())], now);
+    let id = format!("usr-{}", uuid::Uuid::new_v4());
     let tags_json = serde_json::to_string(&item.tags).unwrap_or_else(|_| "[]".to_string());
     conn
       .execute(
@@ -2397,6 +3895,23 @@ pub async fn upsert_user_memory(item: MemoryItemInput) -> Result<(), String> {
         ],
       )
       .map_err(|e| e.to_string())?;
+    Ok(id)
+  })
+  .await
+  .map_err(|e| e.to_string())?
+}
+
+/// 屏蔽一条全局记忆（标记为 expired）
+pub async fn expire_user_memory(memory_id: &str) -> Result<(), String> {
+  let id = memory_id.to_string();
+  tokio::task::spawn_blocking(move || {
+    let conn = open_user_memory_db()?;
+    conn
+      .execute(
+        "UPDATE memory_items SET freshness_status = 'expired', updated_at = ?1 WHERE id = ?2",
+        params![now_secs(), id],
+      )
+      .map_err(|e| e.to_string())?;
     Ok(())
   })
   .await
@@ -2413,24 +3928,40 @@ pub async fn search_user_memories(
   tokio::task::spawn_blocking(move || {
     let conn = open_user_memory_db().map_err(|e| e)?;
     let fts_query = sanitize_fts_query(&q);
-    let sql = format!(
-      "SELECT m.id, m.layer, m.scope_type, m.scope_id,
+    let columns = "m.id, m.layer, m.scope_type, m.scope_id,
                     m.entity_type, m.entity_name, m.content, m.summary,
                     m.tags, m.source_kind, m.source_ref, m.confidence,
                     m.freshness_status, m.readonly, m.access_count,
-                    m.last_accessed_at, m.created_at, m.updated_at,
-                    (-fts.rank) AS score
+                    m.last_accessed_at, m.created_at, m.updated_at";
+    // 空查询：按更新时间列出全局记忆（记忆管理界面）
+    let (sql, args): (String, Vec<&dyn rusqlite::ToSql>) = if fts_query.is_empty() {
+      (
+        format!(
+          "SELECT {columns}
+             FROM memory_items m
+             WHERE m.freshness_status NOT IN ('expired', 'superseded')
+             ORDER BY m.updated_at DESC
+             LIMIT {limit}"
+        ),
+        vec![],
+      )
+    } else {
+      (
+        format!(
+          "SELECT {columns}, (-fts.rank) AS score
              FROM memory_items_fts fts
              JOIN memory_items m ON m.rowid = fts.rowid
              WHERE memory_items_fts MATCH ?1
                AND m.freshness_status NOT IN ('expired', 'superseded')
              ORDER BY score * m.confidence DESC
-             LIMIT {}",
-      limit
-    );
+             LIMIT {limit}"
+        ),
+        vec![&fts_query],
+      )
+    };
     let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
     let rows = stmt
-      .query_map(rusqlite::params![fts_query], |row| {
+      .query_map(args.as_slice(), |row| {
         Ok(MemoryItem {
           id: row.get(0)?,
           layer: row.get(1)?,
@@ -2450,6 +3981,7 @@ pub async fn search_user_memories(
           last_accessed_at: row.get(15)?,
           created_at: row.get(16)?,
           updated_at: row.get(17)?,
+          version: 1,
         })
       })
       .map_err(|e| e.to_string())?;
@@ -2515,7 +4047,6 @@ pub struct UserMemoryInfo {
 #[cfg(test)]
 mod tests {
   use super::*;
-  use crate::workspace::workspace_db::WorkspaceDb;
   use rusqlite::{params, Connection};
   use std::path::{Path, PathBuf};
 
@@ -2575,13 +4106,646 @@ mod tests {
     }
   }
 
+  /// 测试用 embedding：按概念词表映射到固定维度，同义词落在同一维
+  struct ConceptEmbeddingProvider;
+
+  const CONCEPTS: [&[&str]; 3] = [
+    &["cat", "feline", "kitten"],
+    &["invoice", "billing"],
+    &["font", "typeface"],
+  ];
+
+  #[async_trait::async_trait]
+  impl crate::services::ai_providers::AIProvider for ConceptEmbeddingProvider {
+    async fn autocomplete(
+      &self,
+      _context: &str,
+      _max_length: usize,
+    ) -> Result<String, crate::services::ai_error::AIError> {
+      Err(crate::services::ai_error::AIError::ModelUnavailable)
+    }
+
+    async fn inline_assist(
+      &self,
+      _instruction: &str,
+      _text: &str,
+      _context: &str,
+    ) -> Result<String, crate::services::ai_error::AIError> {
+      Err(crate::services::ai_error::AIError::ModelUnavailable)
+    }
+
+    async fn chat_stream(
+      &self,
+      _messages: &[crate::services::ai_providers::ChatMessage],
+      _model_config: &crate::services::ai_providers::ModelConfig,
+      _cancel_rx: &mut tokio::sync::oneshot::Receiver<()>,
+      _tools: Option<&[crate::services::ai_providers::ToolDefinition]>,
+    ) -> Result<
+      Box<
+        dyn tokio_stream::Stream<
+            Item = Result<
+              crate::services::ai_providers::ChatChunk,
+              crate::services::ai_error::AIError,
+            >,
+          > + Send
+          + Unpin,
+      >,
+      crate::services::ai_error::AIError,
+    > {
+      Err(crate::services::ai_error::AIError::ModelUnavailable)
+    }
+
+    fn embedding_model(&self) -> Option<&str> {
+      Some("concept-test")
+    }
+
+    async fn embed(
+      &self,
+      texts: &[String],
+    ) -> Result<Vec<Vec<f32>>, crate::services::ai_error::AIError> {
+      Ok(
+        texts
+          .iter()
+          .map(|text| {
+            let lower = text.to_lowercase();
+            CONCEPTS
+              .iter()
+              .map(|words| words.iter().filter(|w| lower.contains(*w)).count() as f32)
+              .collect()
+          })
+          .collect(),
+      )
+    }
+  }
+
+  #[test]
+  fn embedding_roundtrip_and_cosine_similarity() {
+    let vector = vec![0.5f32, -1.25, 3.0];
+    assert_eq!(decode_embedding(&encode_embedding(&vector)), vector);
+    assert!((cosine_similarity(&[1.0, 0.0], &[2.0, 0.0]) - 1.0).abs() < 1e-9);
+    assert_eq!(cosine_similarity(&[1.0, 0.0], &[0.0, 1.0]), 0.0);
+    assert_eq!(cosine_similarity(&[1.0], &[1.0, 0.0]), 0.0);
+  }
+
+  #[tokio::test]
+  async fn semantic_search_finds_memories_without_keyword_overlap() {
+    let workspace = TestWorkspace::new("semantic");
+    let _db = WorkspaceDb::new(workspace.path()).expect("workspace db init");
+    let tab_id = "semantic-tab";
+    let service = MemoryService::new(workspace.path()).expect("memory service init");
+
+    let mut pet = sample_tab_memory(tab_id);
+    pet.entity_name = "pet".to_string();
+    pet.content = "The kitten sleeps on the keyboard.".to_string();
+    pet.summary = String::new();
+    let mut billing = sample_tab_memory(tab_id);
+    billing.entity_name = "billing".to_string();
+    billing.content = "Invoice is sent on the first day of each month.".to_string();
+    billing.summary = String::new();
+    service
+      .upsert_tab_memories(tab_id, vec![pet, billing])
+      .await
+      .expect("insert tab memories");
+
+    let provider: Arc<dyn crate::services::ai_providers::AIProvider> =
+      Arc::new(ConceptEmbeddingProvider);
+    let response = service
+      .search_memories_semantic(
+        &provider,
+        SearchMemoriesParams {
+          query: "feline".to_string(),
+          tab_id: Some(tab_id.to_string()),
+          workspace_path: None,
+          scope: MemorySearchScope::Tab,
+          limit: Some(1),
+          entity_types: None,
+          tags: None,
+        },
+      )
+      .await
+      .expect("semantic search");
+
+    assert_eq!(response.items.len(), 1);
+    assert_eq!(response.items[0].item.entity_name, "pet");
+    assert!(response.items[0].relevance_score > 0.9);
+
+    // 向量已缓存，再次同步不会重复计算
+    let resynced = service
+      .sync_memory_embeddings(&provider, 100)
+      .await
+      .expect("resync embeddings");
+    assert_eq!(resynced, 0);
+  }
+
+  #[tokio::test]
+  async fn exported_memories_import_into_another_workspace_without_duplicates() {
+    let source = TestWorkspace::new("export-src");
+    let _source_db = WorkspaceDb::new(source.path()).expect("source db init");
+    let target = TestWorkspace::new("export-dst");
+    let _target_db = WorkspaceDb::new(target.path()).expect("target db init");
+    let tab_id = "export-tab";
+
+    let source_service = MemoryService::new(source.path()).expect("source service");
+    source_service
+      .upsert_tab_memories(tab_id, vec![sample_tab_memory(tab_id)])
+      .await
+      .expect("insert tab memory");
+    let bundle_path = source.path().join("export").join("memories.json");
+    let exported = source_service
+      .export_memories(&bundle_path)
+      .await
+      .expect("export memories");
+    assert_eq!(exported, 1);
+
+    let target_service = MemoryService::new(target.path()).expect("target service");
+    let first = target_service
+      .import_memories(&bundle_path)
+      .await
+      .expect("first import");
+    assert_eq!((first.imported, first.skipped), (1, 0));
+    let second = target_service
+      .import_memories(&bundle_path)
+      .await
+      .expect("second import");
+    assert_eq!((second.imported, second.skipped), (0, 1));
+
+    let conn = Connection::open(target.db_path()).expect("open target db");
+    let count: i64 = conn
+      .query_row("SELECT COUNT(*) FROM memory_items", [], |row| row.get(0))
+      .expect("count memories");
+    assert_eq!(count, 1);
+  }
+
+  #[tokio::test]
+  async fn importing_merges_into_existing_memories() {
+    let source = TestWorkspace::new("merge-src");
+    let _source_db = WorkspaceDb::new(source.path()).expect("source db init");
+    let target = TestWorkspace::new("merge-dst");
+    let _target_db = WorkspaceDb::new(target.path()).expect("target db init");
+    let tab_id = "merge-tab";
+    let memory = |name: &str, content: &str| {
+      let mut item = sample_tab_memory(tab_id);
+      item.entity_name = name.to_string();
+      item.content = content.to_string();
+      item
+    };
+
+    let target_service = MemoryService::new(target.path()).expect("target service");
+    target_service
+      .upsert_tab_memories(
+        tab_id,
+        vec![
+          memory("tone", "Prefer a formal tone."),
+          memory("deadline", "The release ships in May."),
+        ],
+      )
+      .await
+      .expect("insert target memories");
+    // 目标工作区的记忆早于导出包
+    let conn = Connection::open(target.db_path()).expect("open target db");
+    conn
+      .execute("UPDATE memory_items SET updated_at = updated_at - 100", [])
+      .expect("age target memories");
+
+    let source_service = MemoryService::new(source.path()).expect("source service");
+    source_service
+      .upsert_tab_memories(
+        tab_id,
+        vec![
+          memory("tone", "Prefer a concise tone."),
+          memory("deadline", "The release ships in May."),
+          memory("owner", "Alice owns the release."),
+        ],
+      )
+      .await
+      .expect("insert source memories");
+    let bundle_path = source.path().join("export").join("memories.json");
+    source_service
+      .export_memories(&bundle_path)
+      .await
+      .expect("export memories");
+
+    let summary = target_service
+      .import_memories(&bundle_path)
+      .await
+      .expect("import memories");
+    assert_eq!(
+      (summary.imported, summary.updated, summary.skipped),
+      (1, 1, 1)
+    );
+
+    let tone: Vec<(String, i64)> = conn
+      .prepare("SELECT content, version FROM memory_items WHERE entity_name = 'tone'")
+      .expect("prepare")
+      .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+      .expect("query tone")
+      .collect::<Result<_, _>>()
+      .expect("read tone");
+    assert_eq!(tone, vec![("Prefer a concise tone.".to_string(), 2)]);
+    let count: i64 = conn
+      .query_row("SELECT COUNT(*) FROM memory_items", [], |row| row.get(0))
+      .expect("count memories");
+    assert_eq!(count, 3);
+
+    // 再次导入时全部跳过
+    let again = target_service
+      .import_memories(&bundle_path)
+      .await
+      .expect("second import");
+    assert_eq!((again.imported, again.updated, again.skipped), (0, 0, 3));
+  }
+
+  #[tokio::test]
+  async fn retagged_memories_can_be_filtered_by_tag() {
+    let workspace = TestWorkspace::new("tags");
+    let _db = WorkspaceDb::new(workspace.path()).expect("workspace db init");
+    let tab_id = "tag-tab";
+    let service = MemoryService::new(workspace.path()).expect("memory service init");
+
+    let mut style = sample_tab_memory(tab_id);
+    style.entity_name = "tone".to_string();
+    style.content = "Prefer a concise memory tone.".to_string();
+    let mut fact = sample_tab_memory(tab_id);
+    fact.entity_name = "deadline".to_string();
+    fact.content = "The memory release ships in May.".to_string();
+    service
+      .upsert_tab_memories(tab_id, vec![style, fact])
+      .await
+      .expect("insert tab memories");
+
+    let search = |tags: Option<Vec<String>>| SearchMemoriesParams {
+      query: "memory".to_string(),
+      tab_id: Some(tab_id.to_string()),
+      workspace_path: None,
+      scope: MemorySearchScope::Tab,
+      limit: Some(10),
+      entity_types: None,
+      tags,
+    };
+    let all = service.search_memories(search(None)).await.expect("search");
+    assert_eq!(all.total_found, 2);
+    let tone_id = all
+      .items
+      .iter()
+      .find(|r| r.item.entity_name == "tone")
+      .map(|r| r.item.id.clone())
+      .expect("tone memory");
+
+    let changed = service
+      .retag_memories(
+        vec![tone_id.clone()],
+        vec!["Style Preference".to_string()],
+        vec!["tab".to_string()],
+      )
+      .await
+      .expect("retag");
+    assert_eq!(changed, 1);
+
+    let filtered = service
+      .search_memories(search(Some(vec!["style_preference".to_string()])))
+      .await
+      .expect("filtered search");
+    assert_eq!(filtered.total_found, 1);
+    assert_eq!(filtered.items[0].item.id, tone_id);
+    assert_eq!(
+      parse_tags(&filtered.items[0].item.tags),
+      vec!["dedupe".to_string(), "style_preference".to_string()]
+    );
+
+    let recent = service
+      .search_memories(SearchMemoriesParams {
+        query: String::new(),
+        ..search(Some(vec!["tab".to_string()]))
+      })
+      .await
+      .expect("recent filtered");
+    assert_eq!(recent.total_found, 1);
+    assert_eq!(recent.items[0].item.entity_name, "deadline");
+  }
+
+  #[tokio::test]
+  async fn tag_filter_matches_json_array_tags() {
+    let workspace = TestWorkspace::new("json-tags");
+    let _db = WorkspaceDb::new(workspace.path()).expect("workspace db init");
+    let tab_id = "json-tag-tab";
+    let service = MemoryService::new(workspace.path()).expect("memory service init");
+
+    let mut style = sample_tab_memory(tab_id);
+    style.entity_name = "tone".to_string();
+    style.content = "Prefer a concise memory tone.".to_string();
+    let mut fact = sample_tab_memory(tab_id);
+    fact.entity_name = "deadline".to_string();
+    fact.content = "The memory release ships in May.".to_string();
+    service
+      .upsert_tab_memories(tab_id, vec![style, fact])
+      .await
+      .expect("insert tab memories");
+    // 旧数据 / 列默认值以 JSON 数组存储标签
+    let conn = Connection::open(workspace.db_path()).expect("open db");
+    conn
+      .execute(
+        "UPDATE memory_items SET tags = '[\"Style Preference\",\"tab\"]' WHERE entity_name = 'tone'",
+        [],
+      )
+      .expect("store json tags");
+    conn
+      .execute(
+        "UPDATE memory_items SET tags = '[\"style\"]' WHERE entity_name = 'deadline'",
+        [],
+      )
+      .expect("store json tags");
+
+    let search = |query: &str| SearchMemoriesParams {
+      query: query.to_string(),
+      tab_id: Some(tab_id.to_string()),
+      workspace_path: None,
+      scope: MemorySearchScope::Tab,
+      limit: Some(10),
+      entity_types: None,
+      tags: Some(vec!["style_preference".to_string()]),
+    };
+    for query in ["memory", ""] {
+      let found = service
+        .search_memories(search(query))
+        .await
+        .expect("search");
+      assert_eq!(found.total_found, 1, "query {:?}", query);
+      assert_eq!(found.items[0].item.entity_name, "tone");
+    }
+  }
+
+  #[tokio::test]
+  async fn update_memory_bumps_version_and_rejects_stale_edits() {
+    let workspace = TestWorkspace::new("update");
+    let _db = WorkspaceDb::new(workspace.path()).expect("workspace db init");
+    let tab_id = "update-tab";
+    let service = MemoryService::new(workspace.path()).expect("memory service init");
+    service
+      .upsert_tab_memories(tab_id, vec![sample_tab_memory(tab_id)])
+      .await
+      .expect("insert tab memory");
+
+    let conn = Connection::open(workspace.path().join(".binder").join("workspace.db")).unwrap();
+    let original = load_memory_item(&conn, &memory_id_for(&conn, tab_id))
+      .unwrap()
+      .expect("memory exists");
+    assert_eq!(original.version, 1);
+
+    let updated = service
+      .update_memory(
+        &original.id,
+        original.version,
+        MemoryUpdateFields {
+          content: Some("Edited memory content.".to_string()),
+          tags: Some(vec!["Style Preference".to_string()]),
+          ..Default::default()
+        },
+      )
+      .await
+      .expect("update memory");
+    assert_eq!(updated.id, original.id);
+    assert_eq!(updated.version, 2);
+    assert_eq!(updated.content, "Edited memory content.");
+    assert_eq!(updated.tags, "style_preference");
+    assert_eq!(updated.entity_name, original.entity_name);
+    assert_eq!(updated.created_at, original.created_at);
+
+    let stale = service
+      .update_memory(
+        &original.id,
+        original.version,
+        MemoryUpdateFields {
+          content: Some("Lost update.".to_string()),
+          ..Default::default()
+        },
+      )
+      .await;
+    assert!(matches!(
+      stale,
+      Err(MemoryError::VersionConflict { current: 2 })
+    ));
+  }
+
+  fn memory_id_for(conn: &Connection, tab_id: &str) -> String {
+    conn
+      .query_row(
+        "SELECT id FROM memory_items WHERE scope_id = ?1",
+        params![tab_id],
+        |row| row.get(0),
+      )
+      .unwrap()
+  }
+
+  #[tokio::test]
+  async fn workspace_memories_do_not_leak_into_other_workspaces() {
+    let project = TestWorkspace::new("scope-a");
+    let other = TestWorkspace::new("scope-b");
+    let _db_a = WorkspaceDb::new(project.path()).expect("workspace db init");
+    let _db_b = WorkspaceDb::new(other.path()).expect("workspace db init");
+
+    let project_path = project.path().to_string_lossy().to_string();
+    let id = MemoryService::new(project.path())
+      .expect("memory service init")
+      .upsert_workspace_long_term_memory(MemoryItemInput {
+        layer: MemoryLayer::WorkspaceLongTerm,
+        scope_type: MemoryScopeType::Workspace,
+        scope_id: project_path.clone(),
+        entity_type: "project_fact".to_string(),
+        entity_name: "release".to_string(),
+        content: "The release branch is frozen on Fridays.".to_string(),
+        summary: String::new(),
+        tags: vec!["Project Fact".to_string()],
+        source_kind: MemorySourceKind::UserPreference,
+        source_ref: String::new(),
+        confidence: 1.0,
+      })
+      .await
+      .expect("add workspace memory");
+
+    let search = |workspace: &Path| SearchMemoriesParams {
+      query: "release branch".to_string(),
+      tab_id: None,
+      workspace_path: Some(workspace.to_string_lossy().to_string()),
+      scope: MemorySearchScope::All,
+      limit: Some(10),
+      entity_types: None,
+      tags: None,
+    };
+    let found = MemoryService::new(project.path())
+      .unwrap()
+      .search_memories(search(project.path()))
+      .await
+      .expect("search project");
+    assert_eq!(found.total_found, 1);
+    assert_eq!(found.items[0].item.id, id);
+    assert_eq!(found.items[0].item.tags, "project_fact");
+
+    let leaked = MemoryService::new(other.path())
+      .unwrap()
+      .search_memories(search(other.path()))
+      .await
+      .expect("search other");
+    assert_eq!(leaked.total_found, 0);
+    assert_eq!(
+      MemoryStoreScope::from_str("global"),
+      MemoryStoreScope::Global
+    );
+    assert_eq!(
+      MemoryStoreScope::from_str("anything"),
+      MemoryStoreScope::Workspace
+    );
+  }
+
+  #[test]
+  fn text_similarity_ignores_case_and_punctuation() {
+    assert_eq!(
+      text_similarity("Use tabs, not spaces.", "use tabs not spaces"),
+      1.0
+    );
+    assert!(text_similarity("使用简洁的语气写作", "使用简洁语气写作") >= 0.75);
+    assert!(text_similarity("deadline is May", "prefer serif fonts") < 0.3);
+    assert_eq!(text_similarity("", ""), 0.0);
+  }
+
+  #[tokio::test]
+  async fn dedupe_merges_near_duplicates_and_keeps_distinct_memories() {
+    let workspace = TestWorkspace::new("dedupe");
+    let _db = WorkspaceDb::new(workspace.path()).expect("workspace db init");
+    let tab_id = "dedupe-tab";
+    let service = MemoryService::new(workspace.path()).expect("memory service init");
+
+    let mut first = sample_tab_memory(tab_id);
+    first.entity_name = "style".to_string();
+    first.content = "Prefer short sentences and an active voice.".to_string();
+    let mut second = sample_tab_memory(tab_id);
+    second.entity_name = "writing-style".to_string();
+    second.content = "Prefer short sentences, and an active voice!".to_string();
+    second.confidence = 0.6;
+    second.tags = vec!["style".to_string()];
+    let mut other = sample_tab_memory(tab_id);
+    other.entity_name = "deadline".to_string();
+    other.content = "The draft is due at the end of May.".to_string();
+    service
+      .upsert_tab_memories(tab_id, vec![first, second, other])
+      .await
+      .expect("insert tab memories");
+
+    let preview = service.dedupe_memories(None, true).await.expect("dry run");
+    assert_eq!((preview.scanned, preview.merged), (3, 1));
+
+    let report = service.dedupe_memories(None, false).await.expect("dedupe");
+    assert_eq!(report.merged, 1);
+    assert_eq!(report.groups[0].kept_entity_name, "style");
+
+    let conn = Connection::open(workspace.db_path()).expect("open workspace db");
+    let (fresh, tags): (i64, String) = conn
+      .query_row(
+        "SELECT COUNT(*), MAX(CASE WHEN entity_name = 'style' THEN tags END)
+         FROM memory_items WHERE freshness_status = 'fresh'",
+        [],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+      )
+      .expect("count fresh memories");
+    assert_eq!(fresh, 2);
+    assert!(parse_tags(&tags).contains(&"style".to_string()));
+
+    let again = service
+      .dedupe_memories(None, false)
+      .await
+      .expect("second dedupe");
+    assert_eq!(again.merged, 0);
+  }
+
+  #[tokio::test]
+  async fn consistency_check_repairs_anchor_after_heading_edit() {
+    let workspace = TestWorkspace::new("anchors");
+    let _db = WorkspaceDb::new(workspace.path()).expect("workspace db init");
+    let tab_id = "anchor-tab";
+    let service = MemoryService::new(workspace.path()).expect("memory service init");
+    service
+      .upsert_tab_memories(tab_id, vec![sample_tab_memory(tab_id)])
+      .await
+      .expect("insert tab memory");
+    let conn = Connection::open(workspace.db_path()).expect("open workspace db");
+    let memory_id: String = conn
+      .query_row("SELECT id FROM memory_items LIMIT 1", [], |row| row.get(0))
+      .expect("memory id");
+
+    let doc = workspace.path().join("plan.md");
+    std::fs::write(&doc, "# Release Plan\n\nShip the beta.\n\n# Risks\n").expect("write doc");
+    let anchored = service
+      .set_memory_anchor(&memory_id, "plan.md", AnchorKind::Heading, "Release Plan")
+      .await
+      .expect("set anchor");
+    assert_eq!(anchored.anchor.anchor, "release-plan");
+    assert_eq!(anchored.line, Some(1));
+
+    std::fs::write(
+      &doc,
+      "# Intro\n\n# Release Plans\n\nShip the beta.\n\n# Risks\n",
+    )
+    .expect("edit doc");
+    let resolved = service
+      .resolve_memory_anchors("plan.md")
+      .await
+      .expect("resolve anchors");
+    assert_eq!(resolved[0].anchor.status, "broken");
+
+    let report = service
+      .check_memory_consistency(Some("plan.md"))
+      .await
+      .expect("check consistency");
+    assert_eq!((report.checked, report.repaired, report.broken), (1, 1, 0));
+    assert_eq!(report.anchors[0].anchor.anchor, "release-plans");
+    assert_eq!(report.anchors[0].line, Some(3));
+
+    std::fs::write(&doc, "# Something else entirely\n").expect("rewrite doc");
+    let report = service
+      .check_memory_consistency(None)
+      .await
+      .expect("check consistency again");
+    assert_eq!(report.broken, 1);
+  }
+
+  #[tokio::test]
+  async fn memory_stats_count_documents_and_tags() {
+    let workspace = TestWorkspace::new("stats");
+    let _db = WorkspaceDb::new(workspace.path()).expect("workspace db init");
+    let tab_id = "stats-tab";
+    let service = MemoryService::new(workspace.path()).expect("memory service init");
+
+    let mut other = sample_tab_memory(tab_id);
+    other.entity_name = "another".to_string();
+    other.tags = vec!["dedupe".to_string()];
+    service
+      .upsert_tab_memories(tab_id, vec![sample_tab_memory(tab_id), other])
+      .await
+      .expect("insert tab memories");
+
+    let stats = service.get_memory_stats().await.expect("memory stats");
+    assert_eq!((stats.total, stats.active), (2, 2));
+    assert_eq!(stats.by_layer[0].key, "tab");
+    assert_eq!(stats.by_tag[0].key, "dedupe");
+    assert_eq!(stats.by_tag[0].count, 2);
+    assert!(stats.by_document.is_empty());
+    assert!(stats.content_bytes > 0);
+    assert!(stats.database_bytes > 0);
+    assert!(stats.last_updated_at.is_some());
+  }
+
   #[test]
   fn workspace_db_initializes_memory_schema_and_memory_service_new_is_idempotent() {
     let workspace = TestWorkspace::new("schema-init");
     let _db = WorkspaceDb::new(workspace.path()).expect("workspace db init");
 
     let conn = Connection::open(workspace.db_path()).expect("open workspace db");
-    for table in ["memory_items", "memory_items_fts", "memory_usage_logs"] {
+    for table in [
+      "memory_items",
+      "memory_items_fts",
+      "memory_usage_logs",
+      "memory_embeddings",
+      "memory_anchors",
+    ] {
       assert!(
         object_exists(&conn, "table", table),
         "missing table: {table}"
@@ -2618,6 +4782,37 @@ mod tests {
     assert!(object_exists(&conn, "table", "memory_items_fts"));
   }
 
+  #[test]
+  fn memory_service_new_runs_pending_workspace_migrations() {
+    let workspace = TestWorkspace::new("schema-upgrade");
+    let _db = WorkspaceDb::new(workspace.path()).expect("workspace db init");
+    // 模拟 migration 10 之前的旧库：记忆表缺少 version 列与来源索引
+    let conn = Connection::open(workspace.db_path()).expect("open workspace db");
+    conn
+      .execute_batch(
+        "DROP INDEX idx_memory_source_ref;
+         ALTER TABLE memory_items DROP COLUMN version;
+         DELETE FROM _schema_version WHERE version = 10;",
+      )
+      .expect("downgrade schema");
+    drop(conn);
+
+    let _service = MemoryService::new(workspace.path()).expect("memory service init");
+    let conn = Connection::open(workspace.db_path()).expect("reopen workspace db");
+    assert!(object_exists(&conn, "index", "idx_memory_source_ref"));
+    let has_version: bool = conn
+      .prepare("SELECT 1 FROM pragma_table_info('memory_items') WHERE name = 'version'")
+      .and_then(|mut stmt| stmt.exists([]))
+      .unwrap();
+    assert!(has_version);
+    let schema_version: i64 = conn
+      .query_row("SELECT MAX(version) FROM _schema_version", [], |row| {
+        row.get(0)
+      })
+      .unwrap();
+    assert_eq!(schema_version, 10);
+  }
+
   #[tokio::test]
   async fn tab_memory_survives_service_restart_and_active_tab_is_not_marked_orphan() {
     let workspace = TestWorkspace::new("tab-restart");
@@ -2640,6 +4835,7 @@ mod tests {
         scope: MemorySearchScope::All,
         limit: Some(10),
         entity_types: None,
+        tags: None,
       })
       .await
       .expect("search memories after restart");
@@ -1,33 +1,81 @@
+pub mod agent_transaction;
 pub mod ai_config;
 pub mod ai_error;
 pub mod ai_providers;
 pub mod ai_queue;
 pub mod ai_service;
 pub mod api_key_manager;
+pub mod autocomplete_context;
 pub mod block_tree_index;
+pub mod buffer_journal;
+pub mod chat_stream_registry;
+pub mod classify_rules;
 pub mod column_service;
 pub mod confirmation_manager;
+pub mod content_type;
 pub mod context_manager;
 pub mod conversation_manager;
+pub mod custom_tools;
 pub mod document_analysis;
+pub mod document_diff;
+pub mod document_merge;
+pub mod document_split;
+pub mod encryption;
+pub mod export_numbering;
 pub mod file_classifier;
+pub mod file_organizer;
 pub mod file_system;
+pub mod file_templates;
 pub mod file_tree;
 pub mod file_watcher;
+pub mod filename_suggestion;
+pub mod folder_summary;
+pub mod footnotes;
+pub mod grounding;
+pub mod html_sanitizer;
+pub mod image_conversion;
+pub mod image_dedupe;
+pub mod image_metadata;
+pub mod image_references;
 pub mod image_service;
+pub mod in_file_search;
+pub mod inline_assist;
+pub mod job_service;
 pub mod knowledge;
+pub mod libreoffice_installer;
 pub mod libreoffice_service;
+pub mod link_graph;
 pub mod loop_detector;
+pub mod markdown_format;
+pub mod memory_anchor;
 pub mod memory_service;
+pub mod ocr_service;
+pub mod open_file_tracker;
+pub mod organize_history;
 pub mod pandoc_service;
+pub mod pdf_page_renderer;
+pub mod pdf_text;
+pub mod plugins;
 pub mod positioning_resolver;
+pub mod preview_cache;
 pub mod preview_service;
+pub mod print_service;
+pub mod prompt_library;
+pub mod proofread;
+pub mod readability;
 pub mod reply_completeness_checker;
+pub mod scanned_pdf;
 pub mod search_service;
+pub mod settings_service;
+pub mod snippets;
+pub mod spellcheck_service;
 pub mod stage_transition_guard;
 pub mod stream_state;
 pub mod streaming_response_handler;
+pub mod svg_sanitizer;
 pub mod task_progress_analyzer;
+pub mod telemetry;
+pub mod temp_service;
 pub mod template;
 pub mod textbox_service;
 pub mod tool_call_handler;
@@ -35,4 +83,9 @@ pub mod tool_definitions;
 pub mod tool_matrix;
 pub mod tool_policy;
 pub mod tool_service;
+pub mod translation;
+pub mod update_service;
+pub mod wikilink;
+pub mod word_count;
 pub mod workspace;
+pub mod workspace_settings;
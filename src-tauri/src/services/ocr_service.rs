@@ -0,0 +1,306 @@
+//! 图片 / 扫描件文字识别：调用系统安装的 Tesseract 命令行
+//!
+//! 扫描版 PDF 需要先用 poppler 的 `pdftoppm` 逐页渲染为图片再识别，两者都未安装时识别不可用。
+
+use crate::services::temp_service;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use which::which;
+
+/// 可直接交给 Tesseract 识别的图片格式
+pub const OCR_IMAGE_EXTENSIONS: [&str; 8] =
+  ["png", "jpg", "jpeg", "tif", "tiff", "bmp", "webp", "gif"];
+
+/// 扫描版 PDF 默认最多识别的页数
+pub const OCR_DEFAULT_MAX_PAGES: u32 = 20;
+
+/// 语言参数形如 `eng`、`chi_sim+eng`
+static LANG_PATTERN: Lazy<Regex> =
+  Lazy::new(|| Regex::new(r"^[A-Za-z_]+(\+[A-Za-z_]+)*$").unwrap());
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OcrOutput {
+  pub text: String,
+  pub lang: String,
+  /// 每页识别结果（图片只有一页）
+  pub pages: Vec<String>,
+}
+
+pub struct OcrService {
+  tesseract_path: Option<PathBuf>,
+  pdftoppm_path: Option<PathBuf>,
+}
+
+impl OcrService {
+  pub fn new() -> Self {
+    Self {
+      tesseract_path: Self::find_executable("tesseract"),
+      pdftoppm_path: Self::find_executable("pdftoppm"),
+    }
+  }
+
+  /// 先查 PATH，再查各平台常见安装位置（GUI 启动的应用在 macOS 上通常拿不到 Homebrew 的 PATH）
+  fn find_executable(name: &str) -> Option<PathBuf> {
+    if let Ok(path) = which(name) {
+      return Some(path);
+    }
+
+    let mut candidates = vec![
+      PathBuf::from("/opt/homebrew/bin").join(name),
+      PathBuf::from("/usr/local/bin").join(name),
+      PathBuf::from("/usr/bin").join(name),
+    ];
+    if cfg!(target_os = "windows") {
+      let exe = format!("{}.exe", name);
+      candidates.push(PathBuf::from(r"C:\Program Files\Tesseract-OCR").join(&exe));
+      candidates.push(PathBuf::from(r"C:\Program Files (x86)\Tesseract-OCR").join(&exe));
+    }
+    candidates.into_iter().find(|p| p.is_file())
+  }
+
+  pub fn is_available(&self) -> bool {
+    self.tesseract_path.is_some()
+  }
+
+  pub fn get_path(&self) -> Option<&PathBuf> {
+    self.tesseract_path.as_ref()
+  }
+
+  /// 是否能识别扫描版 PDF（需要 pdftoppm）
+  pub fn supports_pdf(&self) -> bool {
+    self.tesseract_path.is_some() && self.pdftoppm_path.is_some()
+  }
+
+  fn tesseract(&self) -> Result<&PathBuf, String> {
+    self
+      .tesseract_path
+      .as_ref()
+      .ok_or_else(|| "未检测到 Tesseract，无法进行文字识别。请先安装 Tesseract OCR。".to_string())
+  }
+
+  /// 已安装的语言包
+  pub fn available_languages(&self) -> Result<Vec<String>, String> {
+    let output = Command::new(self.tesseract()?)
+      .arg("--list-langs")
+      .output()
+      .map_err(|e| format!("执行 Tesseract 失败: {}", e))?;
+    // 首行是 "List of available languages ..." 说明
+    Ok(
+      String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .skip(1)
+        .map(|l| l.trim().to_string())
+        .filter(|l| !l.is_empty())
+        .collect(),
+    )
+  }
+
+  /// 未指定语言时：装了简体中文语言包用 `chi_sim+eng`，否则用 `eng`
+  pub fn default_language(&self) -> String {
+    let has_chinese = self
+      .available_languages()
+      .map(|langs| langs.iter().any(|l| l == "chi_sim"))
+      .unwrap_or(false);
+    if has_chinese {
+      "chi_sim+eng".to_string()
+    } else {
+      "eng".to_string()
+    }
+  }
+
+  fn resolve_language(&self, lang: Option<&str>) -> Result<String, String> {
+    match lang.map(str::trim).filter(|l| !l.is_empty()) {
+      Some(lang) if LANG_PATTERN.is_match(lang) => Ok(lang.to_string()),
+      Some(lang) => Err(format!("无效的识别语言: {}", lang)),
+      None => Ok(self.default_language()),
+    }
+  }
+
+  fn recognize_file(&self, image_path: &Path, lang: &str) -> Result<String, String> {
+    let output = Command::new(self.tesseract()?)
+      .arg(image_path.as_os_str())
+      .arg("stdout")
+      .arg("-l")
+      .arg(lang)
+      .output()
+      .map_err(|e| format!("执行 Tesseract 失败: {}", e))?;
+    if !output.status.success() {
+      return Err(format!(
+        "文字识别失败: {}",
+        String::from_utf8_lossy(&output.stderr).trim()
+      ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+  }
+
+  /// 识别单张图片
+  pub fn recognize_image(
+    &self,
+    image_path: &Path,
+    lang: Option<&str>,
+  ) -> Result<OcrOutput, String> {
+    let lang = self.resolve_language(lang)?;
+    let text = self.recognize_file(image_path, &lang)?;
+    Ok(OcrOutput {
+      text: text.clone(),
+      lang,
+      pages: vec![text],
+    })
+  }
+
+  /// 逐页渲染扫描版 PDF 后识别，最多处理 max_pages 页
+  pub fn recognize_pdf(
+    &self,
+    pdf_path: &Path,
+    lang: Option<&str>,
+    max_pages: u32,
+  ) -> Result<OcrOutput, String> {
+    let lang = self.resolve_language(lang)?;
+    let pdftoppm = self
+      .pdftoppm_path
+      .as_ref()
+      .ok_or_else(|| "未检测到 pdftoppm（poppler），无法识别扫描版 PDF".to_string())?;
+
+    let render_dir = temp_service::temp_dir("ocr")?;
+    let render_dir_path = render_dir.path();
+    let result = (|| {
+      let output = Command::new(pdftoppm)
+        .arg("-r")
+        .arg("300")
+        .arg("-png")
+        .arg("-f")
+        .arg("1")
+        .arg("-l")
+        .arg(max_pages.max(1).to_string())
+        .arg(pdf_path.as_os_str())
+        .arg(render_dir_path.join("page"))
+        .output()
+        .map_err(|e| format!("执行 pdftoppm 失败: {}", e))?;
+      if !output.status.success() {
+        return Err(format!(
+          "渲染 PDF 页面失败: {}",
+          String::from_utf8_lossy(&output.stderr).trim()
+        ));
+      }
+
+      // pdftoppm 输出 page-01.png、page-02.png …，页码按总页数补零，排序即页序
+      let mut pages: Vec<PathBuf> = std::fs::read_dir(render_dir_path)
+        .map_err(|e| format!("读取渲染结果失败: {}", e))?
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.extension().and_then(|e| e.to_str()) == Some("png"))
+        .collect();
+      pages.sort();
+      pages
+        .iter()
+        .map(|page| self.recognize_file(page, &lang))
+        .collect::<Result<Vec<_>, _>>()
+    })();
+    drop(render_dir);
+
+    let pages = result?;
+    Ok(OcrOutput {
+      text: pages.join("\n\n"),
+      lang,
+      pages,
+    })
+  }
+
+  /// 按扩展名识别图片或扫描版 PDF
+  pub fn recognize(
+    &self,
+    path: &Path,
+    lang: Option<&str>,
+    max_pages: u32,
+  ) -> Result<OcrOutput, String> {
+    let ext = path
+      .extension()
+      .and_then(|e| e.to_str())
+      .map(|e| e.to_lowercase())
+      .unwrap_or_default();
+    if ext == "pdf" {
+      self.recognize_pdf(path, lang, max_pages)
+    } else if OCR_IMAGE_EXTENSIONS.contains(&ext.as_str()) {
+      self.recognize_image(path, lang)
+    } else {
+      Err(format!("不支持识别该文件类型: {}", path.display()))
+    }
+  }
+}
+
+impl Default for OcrService {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// 用脚本冒充 Tesseract：列出语言包，识别时回显图片名与语言
+  #[cfg(unix)]
+  fn fake_tesseract(dir: &Path) -> OcrService {
+    use std::os::unix::fs::PermissionsExt;
+
+    let script = dir.join("tesseract");
+    std::fs::write(
+      &script,
+      "#!/bin/sh\nif [ \"$1\" = \"--list-langs\" ]; then\n  printf 'List of available languages (2):\\nchi_sim\\neng\\n'\nelse\n  printf '%s %s\\n' \"$(basename \"$1\")\" \"$4\"\nfi\n",
+    )
+    .unwrap();
+    std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+    OcrService {
+      tesseract_path: Some(script),
+      pdftoppm_path: None,
+    }
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn recognizes_images_with_the_resolved_language() {
+    let dir = temp_service::temp_dir("ocr-test").unwrap();
+    let service = fake_tesseract(dir.path());
+    let image = dir.path().join("scan.png");
+    std::fs::write(&image, b"png").unwrap();
+
+    assert_eq!(service.default_language(), "chi_sim+eng");
+    let output = service.recognize(&image, None, 1).unwrap();
+    assert_eq!(output.text, "scan.png chi_sim+eng");
+    assert_eq!(output.pages, vec![output.text.clone()]);
+    assert_eq!(
+      service
+        .recognize_image(&image, Some(" eng "))
+        .map(|o| o.lang),
+      Ok("eng".to_string())
+    );
+
+    // 语言参数会拼进命令行，非法值直接拒绝
+    assert!(service.recognize_image(&image, Some("eng;rm -rf")).is_err());
+    assert!(service
+      .recognize(&dir.path().join("notes.txt"), None, 1)
+      .is_err());
+    assert!(service
+      .recognize(&dir.path().join("scan.pdf"), None, 1)
+      .unwrap_err()
+      .contains("pdftoppm"));
+  }
+
+  #[test]
+  fn reports_missing_tesseract() {
+    let service = OcrService {
+      tesseract_path: None,
+      pdftoppm_path: None,
+    };
+    assert!(!service.is_available());
+    assert!(!service.supports_pdf());
+    assert_eq!(service.default_language(), "eng");
+    assert!(service
+      .recognize_image(Path::new("scan.png"), Some("eng"))
+      .unwrap_err()
+      .contains("Tesseract"));
+  }
+}
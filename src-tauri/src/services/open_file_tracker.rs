@@ -0,0 +1,153 @@
+//! 已打开文件的磁盘快照：文件监听发现变化时与快照比较，生成外部修改事件（含行级变化摘要），
+//! 编辑器据此自动重新加载或提示「重新加载 / 保留我的修改」，不必轮询 `check_external_modification`。
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use similar::{ChangeTag, TextDiff};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::UNIX_EPOCH;
+
+/// 快照内容；非 UTF-8 文件（DOCX 等）只记录修改时间，不做行级比较
+#[derive(Debug, Clone, PartialEq)]
+enum Snapshot {
+  Text(String),
+  Binary,
+}
+
+static SNAPSHOTS: Lazy<Mutex<HashMap<PathBuf, Snapshot>>> = Lazy::new(Default::default);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiffSummary {
+  pub lines_added: usize,
+  pub lines_removed: usize,
+}
+
+/// `external-file-changed` 事件内容
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExternalFileChange {
+  pub path: String,
+  /// 新的修改时间（毫秒）；文件被删除时为 None
+  pub modified_ms: Option<u64>,
+  /// 行级变化摘要；二进制文件或文件被删除时为 None
+  pub diff: Option<DiffSummary>,
+  pub deleted: bool,
+}
+
+fn read_snapshot(path: &Path) -> Result<Snapshot, String> {
+  let bytes = std::fs::read(path).map_err(|e| format!("读取文件失败: {}", e))?;
+  Ok(match String::from_utf8(bytes) {
+    Ok(text) => Snapshot::Text(text),
+    Err(_) => Snapshot::Binary,
+  })
+}
+
+fn modified_ms(path: &Path) -> Option<u64> {
+  std::fs::metadata(path)
+    .and_then(|m| m.modified())
+    .ok()
+    .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+    .map(|d| d.as_millis() as u64)
+}
+
+/// 统计从 old 到 new 新增、删除的行数
+pub fn diff_summary(old: &str, new: &str) -> DiffSummary {
+  let diff = TextDiff::from_lines(old, new);
+  let mut summary = DiffSummary {
+    lines_added: 0,
+    lines_removed: 0,
+  };
+  for change in diff.iter_all_changes() {
+    match change.tag() {
+      ChangeTag::Insert => summary.lines_added += 1,
+      ChangeTag::Delete => summary.lines_removed += 1,
+      ChangeTag::Equal => {}
+    }
+  }
+  summary
+}
+
+/// 开始跟踪已打开的文件（记录当前磁盘内容）
+pub fn track_open_file(path: &Path) -> Result<(), String> {
+  let snapshot = read_snapshot(path)?;
+  if let Ok(mut snapshots) = SNAPSHOTS.lock() {
+    snapshots.insert(path.to_path_buf(), snapshot);
+  }
+  Ok(())
+}
+
+pub fn untrack_open_file(path: &Path) {
+  if let Ok(mut snapshots) = SNAPSHOTS.lock() {
+    snapshots.remove(path);
+  }
+}
+
+/// 编辑器自身保存后更新快照，避免把自己的保存当成外部修改
+pub fn update_snapshot(path: &Path, content: &str) {
+  if let Ok(mut snapshots) = SNAPSHOTS.lock() {
+    if let Some(snapshot) = snapshots.get_mut(path) {
+      *snapshot = Snapshot::Text(content.to_string());
+    }
+  }
+}
+
+/// 文件监听报告变化后调用：未跟踪或内容未变时返回 None，否则更新快照并返回变化
+pub fn detect_external_change(path: &Path) -> Option<ExternalFileChange> {
+  let previous = SNAPSHOTS.lock().ok()?.get(path).cloned()?;
+  let event = |modified_ms, diff, deleted| ExternalFileChange {
+    path: path.to_string_lossy().to_string(),
+    modified_ms,
+    diff,
+    deleted,
+  };
+
+  if !path.exists() {
+    return Some(event(None, None, true));
+  }
+  let current = read_snapshot(path).ok()?;
+  let diff = match (&previous, &current) {
+    (Snapshot::Text(old), Snapshot::Text(new)) if old == new => return None,
+    (Snapshot::Text(old), Snapshot::Text(new)) => Some(diff_summary(old, new)),
+    _ => None,
+  };
+  if let Ok(mut snapshots) = SNAPSHOTS.lock() {
+    snapshots.insert(path.to_path_buf(), current);
+  }
+  Some(event(modified_ms(path), diff, false))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn reports_line_changes_but_not_own_saves() {
+    let path = std::env::temp_dir().join(format!("binder_tracker_{}.md", uuid::Uuid::new_v4()));
+    std::fs::write(&path, "# 标题\n第一段\n第二段\n").unwrap();
+    assert!(detect_external_change(&path).is_none());
+    track_open_file(&path).unwrap();
+
+    // 编辑器自己保存：内容与快照一致，不算外部修改
+    std::fs::write(&path, "# 标题\n第一段（已改）\n第二段\n").unwrap();
+    update_snapshot(&path, "# 标题\n第一段（已改）\n第二段\n");
+    assert!(detect_external_change(&path).is_none());
+
+    std::fs::write(&path, "# 标题\n第一段（已改）\n新增一\n新增二\n").unwrap();
+    let change = detect_external_change(&path).unwrap();
+    assert_eq!(
+      change.diff,
+      Some(DiffSummary {
+        lines_added: 2,
+        lines_removed: 1
+      })
+    );
+    assert!(change.modified_ms.is_some() && !change.deleted);
+    assert!(detect_external_change(&path).is_none());
+
+    std::fs::remove_file(&path).unwrap();
+    assert!(detect_external_change(&path).unwrap().deleted);
+    untrack_open_file(&path);
+    assert!(detect_external_change(&path).is_none());
+  }
+}
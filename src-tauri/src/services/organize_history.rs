@@ -0,0 +1,238 @@
+//! 文件整理历史：每次整理的移动记录写入 `.binder/organize_history.json`，用于撤销最近一次整理。
+
+use crate::services::file_organizer::FileMoveResult;
+use crate::utils::glob_patterns::relative_slash_path;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// 最多保留的整理记录数
+const MAX_HISTORY_ENTRIES: usize = 20;
+
+/// 单个文件的移动记录（路径相对工作区）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalMove {
+  pub from: String,
+  pub to: String,
+  pub size: u64,
+  /// 移动时的修改时间（Unix 秒），用于判断之后是否被修改
+  pub modified: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrganizeJournal {
+  pub id: String,
+  pub created_at: String,
+  pub moves: Vec<JournalMove>,
+  /// 整理时新建的文件夹（撤销时若为空则删除）
+  #[serde(default)]
+  pub created_folders: Vec<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct OrganizeHistoryFile {
+  entries: Vec<OrganizeJournal>,
+}
+
+pub struct OrganizeHistory {
+  workspace_path: PathBuf,
+}
+
+impl OrganizeHistory {
+  pub fn new(workspace_path: &Path) -> Self {
+    Self {
+      workspace_path: workspace_path.to_path_buf(),
+    }
+  }
+
+  fn history_path(&self) -> PathBuf {
+    self
+      .workspace_path
+      .join(".binder")
+      .join("organize_history.json")
+  }
+
+  fn load(&self) -> Result<OrganizeHistoryFile, String> {
+    let path = self.history_path();
+    if !path.exists() {
+      return Ok(OrganizeHistoryFile::default());
+    }
+    let content = fs::read_to_string(&path).map_err(|e| format!("读取整理历史失败: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("解析整理历史失败: {}", e))
+  }
+
+  fn save(&self, history: &OrganizeHistoryFile) -> Result<(), String> {
+    let path = self.history_path();
+    if let Some(parent) = path.parent() {
+      fs::create_dir_all(parent).map_err(|e| format!("创建 .binder 目录失败: {}", e))?;
+    }
+    let content =
+      serde_json::to_string_pretty(history).map_err(|e| format!("序列化整理历史失败: {}", e))?;
+    fs::write(&path, content).map_err(|e| format!("写入整理历史失败: {}", e))
+  }
+
+  /// 生成一条移动记录；需在移动完成后调用（此时文件位于 `to`）
+  pub fn journal_move(&self, from: &Path, to: &Path) -> JournalMove {
+    let metadata = fs::metadata(to).ok();
+    JournalMove {
+      from: relative_slash_path(from, &self.workspace_path),
+      to: relative_slash_path(to, &self.workspace_path),
+      size: metadata.as_ref().map(|m| m.len()).unwrap_or(0),
+      modified: metadata.as_ref().and_then(modified_secs),
+    }
+  }
+
+  /// 追加一次整理记录；没有实际移动时不记录
+  pub fn record(
+    &self,
+    moves: Vec<JournalMove>,
+    created_folders: Vec<String>,
+  ) -> Result<(), String> {
+    if moves.is_empty() {
+      return Ok(());
+    }
+    let mut history = self.load()?;
+    history.entries.push(OrganizeJournal {
+      id: uuid::Uuid::new_v4().to_string(),
+      created_at: chrono::Local::now().to_rfc3339(),
+      moves,
+      created_folders,
+    });
+    let overflow = history.entries.len().saturating_sub(MAX_HISTORY_ENTRIES);
+    history.entries.drain(..overflow);
+    self.save(&history)
+  }
+
+  /// 撤销最近一次整理：把文件移回原位置
+  ///
+  /// - 文件已被删除或移走：跳过并报告
+  /// - 文件整理后被修改：仍移回（保留修改），并在结果中说明
+  /// - 原位置已有同名文件：移回原文件夹并加时间戳后缀
+  pub fn undo_last(&self) -> Result<Vec<FileMoveResult>, String> {
+    let mut history = self.load()?;
+    let journal = history
+      .entries
+      .pop()
+      .ok_or_else(|| "没有可撤销的整理记录".to_string())?;
+
+    let results = journal
+      .moves
+      .iter()
+      .rev()
+      .map(|entry| self.restore(entry))
+      .collect();
+
+    // 删除整理时新建、现已为空的文件夹（先删深层）
+    let mut folders = journal.created_folders.clone();
+    folders.sort_by_key(|f| std::cmp::Reverse(f.matches('/').count()));
+    for folder in folders {
+      let _ = fs::remove_dir(self.workspace_path.join(folder));
+    }
+
+    self.save(&history)?;
+    Ok(results)
+  }
+
+  fn restore(&self, entry: &JournalMove) -> FileMoveResult {
+    let current = self.workspace_path.join(&entry.to);
+    let original = self.workspace_path.join(&entry.from);
+    let result = |success: bool, message: String| FileMoveResult {
+      file_path: original.to_string_lossy().to_string(),
+      success,
+      message,
+    };
+
+    let Ok(metadata) = fs::metadata(&current) else {
+      return result(false, format!("{} 已被删除或移动，无法还原", entry.to));
+    };
+    let modified = metadata.len() != entry.size || modified_secs(&metadata) != entry.modified;
+
+    let mut dest = original.clone();
+    if dest.exists() {
+      let stem = original
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("file");
+      let ext = original
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| format!(".{}", e))
+        .unwrap_or_default();
+      let timestamp = std::time::SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+      dest = original.with_file_name(format!("{}_{}{}", stem, timestamp, ext));
+    }
+    if let Some(parent) = dest.parent() {
+      if let Err(e) = fs::create_dir_all(parent) {
+        return result(false, format!("创建文件夹失败: {}", e));
+      }
+    }
+    if let Err(e) = fs::rename(&current, &dest) {
+      return result(false, format!("还原失败: {}", e));
+    }
+
+    let mut message = format!(
+      "已还原到 {}",
+      relative_slash_path(&dest, &self.workspace_path)
+    );
+    if dest != original {
+      message.push_str("（原位置已有同名文件）");
+    }
+    if modified {
+      message.push_str("，整理后的修改已保留");
+    }
+    FileMoveResult {
+      file_path: dest.to_string_lossy().to_string(),
+      success: true,
+      message,
+    }
+  }
+}
+
+fn modified_secs(metadata: &fs::Metadata) -> Option<u64> {
+  metadata
+    .modified()
+    .ok()
+    .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+    .map(|d| d.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn undo_restores_files_and_reports_missing_ones() {
+    let workspace =
+      std::env::temp_dir().join(format!("binder_organize_history_{}", uuid::Uuid::new_v4()));
+    fs::create_dir_all(workspace.join("报告")).unwrap();
+    let history = OrganizeHistory::new(&workspace);
+
+    let kept_from = workspace.join("a.md");
+    let kept_to = workspace.join("报告/a.md");
+    let gone_to = workspace.join("报告/b.md");
+    fs::write(&kept_to, "a").unwrap();
+    fs::write(&gone_to, "b").unwrap();
+    let moves = vec![
+      history.journal_move(&kept_from, &kept_to),
+      history.journal_move(&workspace.join("b.md"), &gone_to),
+    ];
+    history.record(moves, vec!["报告".to_string()]).unwrap();
+
+    fs::write(&kept_to, "a 已修改").unwrap();
+    fs::remove_file(&gone_to).unwrap();
+
+    let results = history.undo_last().unwrap();
+    assert_eq!(results.len(), 2);
+    assert!(!results[0].success);
+    assert!(results[1].success && results[1].message.contains("修改"));
+    assert_eq!(fs::read_to_string(&kept_from).unwrap(), "a 已修改");
+    assert!(!workspace.join("报告").exists());
+    assert!(history.undo_last().is_err());
+
+    let _ = fs::remove_dir_all(&workspace);
+  }
+}
@@ -1,10 +1,217 @@
+use crate::services::temp_service;
+use once_cell::sync::Lazy;
+use regex::Regex;
 use scraper::{Html, Selector};
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::io::Read;
 use std::path::{Path, PathBuf};
 use std::process::Command;
 use which::which;
 
+static STYLE_BLOCK_RE: Lazy<Regex> =
+  Lazy::new(|| Regex::new(r#"<style[^>]*>([\s\S]*?)</style>"#).unwrap());
+static CSS_CLASS_RULE_RE: Lazy<Regex> =
+  Lazy::new(|| Regex::new(r#"\.([a-zA-Z0-9_-]+)\s*\{([^}]+)\}"#).unwrap());
+static CSS_TAG_RULE_RE: Lazy<Regex> = Lazy::new(|| {
+  Regex::new(r#"((?:p|h[1-6]|div|span|td|th|body|html)(?:\s*,\s*(?:p|h[1-6]|div|span|td|th|body|html))*)\s*\{([^}]+)\}"#).unwrap()
+});
+static CSS_ID_RULE_RE: Lazy<Regex> =
+  Lazy::new(|| Regex::new(r#"#([a-zA-Z0-9_-]+)\s*\{([^}]+)\}"#).unwrap());
+static START_TAG_RE: Lazy<Regex> =
+  Lazy::new(|| Regex::new(r#"<([a-zA-Z][a-zA-Z0-9]*)[^>]*>"#).unwrap());
+/// style 属性（前面必须是空白，避免匹配 data-custom-style）
+static STYLE_ATTR_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r#"\sstyle="([^"]*)""#).unwrap());
+static CLASS_ATTR_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r#"\sclass="([^"]*)""#).unwrap());
+static DATA_CUSTOM_STYLE_RE: Lazy<Regex> =
+  Lazy::new(|| Regex::new(r#"data-custom-style="([^"]+)""#).unwrap());
+
+static LEADING_EMPTY_PARAGRAPHS_RE: Lazy<Regex> = Lazy::new(|| {
+  Regex::new(r#"(?s)^\s*(<p[^>]*>\s*</p>\s*|<p[^>]*>\s*<br\s*/?>\s*</p>\s*)+"#).unwrap()
+});
+/// 开头的一个完整块（含 section/blockquote 等 Pandoc 可能输出的结构）
+static LEADING_BLOCK_RES: Lazy<Vec<Regex>> = Lazy::new(|| {
+  [
+    r"(?s)^\s*<p[^>]*>.*?</p>\s*",
+    r"(?s)^\s*<div[^>]*>.*?</div>\s*",
+    r"(?s)^\s*<section[^>]*>.*?</section>\s*",
+    r"(?s)^\s*<blockquote[^>]*>.*?</blockquote>\s*",
+    r"(?s)^\s*<h1[^>]*>.*?</h1>\s*",
+    r"(?s)^\s*<h2[^>]*>.*?</h2>\s*",
+    r"(?s)^\s*<h3[^>]*>.*?</h3>\s*",
+    r"(?s)^\s*<h4[^>]*>.*?</h4>\s*",
+    r"(?s)^\s*<h5[^>]*>.*?</h5>\s*",
+    r"(?s)^\s*<h6[^>]*>.*?</h6>\s*",
+  ]
+  .iter()
+  .map(|p| Regex::new(p).unwrap())
+  .collect()
+});
+static EMPTY_BR_PARAGRAPH_RE: Lazy<Regex> =
+  Lazy::new(|| Regex::new(r#"<p([^>]*)>\s*<br\s*/?>\s*</p>"#).unwrap());
+static EMPTY_PARAGRAPH_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r#"<p([^>]*)>\s*</p>"#).unwrap());
+static EMPTY_SPAN_PARAGRAPH_RE: Lazy<Regex> =
+  Lazy::new(|| Regex::new(r#"<p([^>]*)>\s*<span[^>]*>\s*</span>\s*</p>"#).unwrap());
+/// 仅含占位符 \u{FEFF}（字符或实体）的段落
+static PLACEHOLDER_PARAGRAPH_RES: Lazy<[Regex; 3]> = Lazy::new(|| {
+  [
+    Regex::new(r#"<p([^>]*)>\s*\x{FEFF}\s*</p>"#).unwrap(),
+    Regex::new(r#"<p([^>]*)>\s*&#xFEFF;\s*</p>"#).unwrap(),
+    Regex::new(r#"<p([^>]*)>\s*&#65279;\s*</p>"#).unwrap(),
+  ]
+});
+static CUSTOM_STYLE_DIV_RE: Lazy<Regex> =
+  Lazy::new(|| Regex::new(r#"<div[^>]*data-custom-style="[^"]*"[^>]*>([\s\S]*?)</div>"#).unwrap());
+static BLOCK_GAP_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r">\s*\n+\s*<").unwrap());
+static BODY_RE: Lazy<Regex> =
+  Lazy::new(|| Regex::new(r#"<body([^>]*)>([\s\S]*?)</body>"#).unwrap());
+static HTML_TAG_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"<[^>]+>").unwrap());
+static WHITESPACE_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\s+").unwrap());
+static TEXT_BLOCK_START_RE: Lazy<Regex> =
+  Lazy::new(|| Regex::new(r#"<(h[1-6]|p)([^>]*)>"#).unwrap());
+static TEXT_BLOCK_WITH_ID_RE: Lazy<Regex> =
+  Lazy::new(|| Regex::new(r#"<(h[1-6]|p)[^>]*id="[^"]*"[^>]*>"#).unwrap());
+/// 只含文本的段落 / 标题
+static PLAIN_TEXT_BLOCK_RE: Lazy<Regex> =
+  Lazy::new(|| Regex::new(r#"<(h[1-6]|p)([^>]*)>([^<]*)</(h[1-6]|p)>"#).unwrap());
+static WRAPPED_EM_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r#"^<em([^>]*)>(.*)</em>$"#).unwrap());
+static WRAPPED_STRONG_RE: Lazy<Regex> =
+  Lazy::new(|| Regex::new(r#"^<strong([^>]*)>(.*)</strong>$"#).unwrap());
+static WRAPPED_U_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r#"^<u([^>]*)>(.*)</u>$"#).unwrap());
+/// 不完整的属性片段，如 `style="...">`
+static INCOMPLETE_ATTR_RE: Lazy<Regex> =
+  Lazy::new(|| Regex::new(r#"[a-zA-Z-]+="[^"]*">"#).unwrap());
+/// 残留的 CSS 声明片段，如 `color: #FF0000;`
+static CSS_DECLARATION_FRAGMENT_RE: Lazy<Regex> = Lazy::new(|| {
+  Regex::new(r#"(color|font-weight|font-style|font-size|text-align|text-decoration|font-family|background-color|text-decoration-line|text-decoration-style|text-decoration-color|vertical-align|letter-spacing|word-spacing|line-height|text-indent|margin|padding|border|width|height|display|position|float|clear|overflow|z-index|opacity|visibility|white-space|word-wrap|word-break|text-overflow|text-transform|text-shadow|box-shadow|transform|transition|animation):\s*[^;"]*[;"]?"#).unwrap()
+});
+/// 残留的颜色值片段，如 `#FF0000;`、`rgb(255,0,0)`
+static COLOR_FRAGMENT_RE: Lazy<Regex> = Lazy::new(|| {
+  Regex::new(r#"#?[0-9A-Fa-f]{3,8}[;:"]?|rgb\([^)]*\)|rgba\([^)]*\)|hsl\([^)]*\)|hsla\([^)]*\)"#)
+    .unwrap()
+});
+/// 残留的样式关键字片段，如 `font-s`、`italicorlor`
+static STYLE_WORD_FRAGMENT_RE: Lazy<Regex> = Lazy::new(|| {
+  Regex::new(r#"\b(font|style|span|color|weight|size|align|decoration|italic|bold|underline|normal|inherit|initial|unset|transparent|auto|none|solid|dashed|dotted|double|groove|ridge|inset|outset|left|right|center|justify|start|end|baseline|top|middle|bottom|sub|super|text-top|text-bottom|block|inline|inline-block|flex|grid|table|list-item|run-in|table-row-group|table-header-group|table-footer-group|table-row|table-cell|table-column-group|table-column|table-caption|inherit|initial|unset|normal|bold|bolder|lighter|100|200|300|400|500|600|700|800|900|italic|oblique|normal|small-caps|all-small-caps|petite-caps|all-petite-caps|unicase|titling-caps)\w*\b"#).unwrap()
+});
+
+/// CSS 声明中的属性名（`color: red` → `color`）
+fn css_property(declaration: &str) -> &str {
+  declaration.split(':').next().unwrap_or("").trim()
+}
+
+/// 给没有 style 属性的开始标签加上 style
+fn add_style_attr(element: &str, styles: &str) -> String {
+  let end = if element.ends_with("/>") {
+    element.len() - 2
+  } else {
+    element.len() - 1
+  };
+  format!(
+    r#"{} style="{}"{}"#,
+    element[..end].trim_end(),
+    styles,
+    &element[end..]
+  )
+}
+
+/// 将样式合并到开始标签的 style 属性（没有则新增），只追加原样式中没有出现的属性；
+/// ignore_case 为 true 时不区分大小写判断
+fn merge_inline_style(element: &str, styles: &str, ignore_case: bool) -> String {
+  let Some(existing) = STYLE_ATTR_RE.captures(element).and_then(|c| c.get(1)) else {
+    return add_style_attr(element, styles);
+  };
+  let existing_style = existing.as_str();
+  let existing_lower = existing_style.to_lowercase();
+  let mut merged = existing_style.to_string();
+  for part in styles.split(';').map(str::trim).filter(|p| !p.is_empty()) {
+    let prop = css_property(part);
+    let present = if ignore_case {
+      existing_lower.contains(&prop.to_lowercase())
+    } else {
+      existing_style.contains(prop)
+    };
+    if !present {
+      merged.push_str("; ");
+      merged.push_str(part);
+    }
+  }
+  format!(
+    "{}{}{}",
+    &element[..existing.start()],
+    merged,
+    &element[existing.end()..]
+  )
+}
+
+/// 清除 body 开始标签的顶部内外边距
+fn override_body_top_spacing(element: &str) -> String {
+  const NO_TOP_SPACING: &str = "padding-top: 0; margin-top: 0";
+  let Some(existing) = STYLE_ATTR_RE.captures(element).and_then(|c| c.get(1)) else {
+    return add_style_attr(element, NO_TOP_SPACING);
+  };
+  let style = existing.as_str().trim_end_matches(';').trim();
+  let style = if style.is_empty() {
+    NO_TOP_SPACING.to_string()
+  } else {
+    format!("{}; {}", style, NO_TOP_SPACING)
+  };
+  format!(
+    "{}{}{}",
+    &element[..existing.start()],
+    style,
+    &element[existing.end()..]
+  )
+}
+
+/// 格式增强（CSS 内联、DOCX 格式提取与应用）期间同时存在的 HTML 副本数估计
+const ENRICHMENT_HTML_COPIES: u64 = 4;
+
+/// 大文档转换的处理档位：预计内存超出上限时降级，而不是直接失败
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ConversionLevel {
+  /// 完整处理
+  Full,
+  /// 只做必要处理（空段落还原、图片等），跳过格式增强
+  Essential,
+}
+
+impl ConversionLevel {
+  fn for_output(html_bytes: u64, memory_limit_bytes: u64) -> Self {
+    if html_bytes.saturating_mul(ENRICHMENT_HTML_COPIES) > memory_limit_bytes {
+      ConversionLevel::Essential
+    } else {
+      ConversionLevel::Full
+    }
+  }
+}
+
+/// Pandoc 的输出文件：用 `--output` 写入临时文件而不是经 stdout 管道读取，
+/// 读取前即可知道大小，读取时按文件大小一次分配。由 temp_service 登记，离开作用域时删除
+struct PandocOutputFile {
+  temp: temp_service::TempPath,
+}
+
+impl PandocOutputFile {
+  fn new() -> Self {
+    Self {
+      temp: temp_service::temp_file("pandoc", "html"),
+    }
+  }
+
+  fn path(&self) -> &Path {
+    self.temp.path()
+  }
+
+  fn len(&self) -> u64 {
+    std::fs::metadata(self.path()).map(|m| m.len()).unwrap_or(0)
+  }
+
+  fn read(&self) -> Result<String, String> {
+    std::fs::read_to_string(self.path()).map_err(|e| format!("读取 Pandoc 输出失败: {}", e))
+  }
+}
+
 /// 运行格式信息（单个文本运行的格式）
 #[derive(Debug, Clone)]
 struct RunFormatting {
@@ -138,7 +345,7 @@ impl PandocService {
     let system_pandoc = which("pandoc").ok();
 
     if let Some(path) = system_pandoc {
-      eprintln!("✅ 使用系统 Pandoc: {:?}", path);
+      tracing::info!("使用系统 Pandoc: {:?}", path);
       return Self {
         pandoc_path: Some(path),
         is_bundled: false,
@@ -146,17 +353,17 @@ impl PandocService {
     }
 
     // 2. 如果系统没有，尝试使用内置 Pandoc
-    eprintln!("⚠️ 系统未安装 Pandoc，尝试使用内置 Pandoc...");
+    tracing::warn!("系统未安装 Pandoc，尝试使用内置 Pandoc...");
     let bundled_pandoc = Self::get_bundled_pandoc_path();
 
     if let Some(path) = bundled_pandoc {
-      eprintln!("✅ 使用内置 Pandoc: {:?}", path);
+      tracing::info!("使用内置 Pandoc: {:?}", path);
       Self {
         pandoc_path: Some(path),
         is_bundled: true,
       }
     } else {
-      eprintln!("❌ 未找到内置 Pandoc");
+      tracing::error!("未找到内置 Pandoc");
       Self {
         pandoc_path: None,
         is_bundled: false,
@@ -236,7 +443,7 @@ impl PandocService {
 
       for path in possible_paths {
         if path.exists() {
-          eprintln!("✅ 找到开发模式 Pandoc: {:?}", path);
+          tracing::info!("找到开发模式 Pandoc: {:?}", path);
           return Some(path);
         }
       }
@@ -311,13 +518,13 @@ impl PandocService {
 
       for path in possible_paths {
         if path.exists() {
-          eprintln!("✅ 找到参考文档: {:?}", path);
+          tracing::info!("找到参考文档: {:?}", path);
           return Some(path);
         }
       }
     }
 
-    eprintln!("⚠️ 未找到参考文档模板，将使用默认转换");
+    tracing::warn!("未找到参考文档模板，将使用默认转换");
     None
   }
 
@@ -373,13 +580,13 @@ impl PandocService {
 
       for path in possible_paths {
         if path.exists() {
-          eprintln!("✅ 找到 Lua 过滤器: {:?}", path);
+          tracing::info!("找到 Lua 过滤器: {:?}", path);
           return Some(path);
         }
       }
     }
 
-    eprintln!("⚠️ 未找到 Lua 过滤器，将使用默认转换");
+    tracing::warn!("未找到 Lua 过滤器，将使用默认转换");
     None
   }
 
@@ -399,6 +606,7 @@ impl PandocService {
   /// 将文档文件转换为 HTML（供编辑或其它用途）
   /// 支持格式：.docx, .doc, .odt, .rtf
   /// - work_dir_for_extract_media: 若为 Some，Pandoc 在该目录执行，--extract-media=. 解压到该目录（编辑模式传 doc_path.parent()，图片才能被找到）；若为 None 不设置工作目录，保持原行为。
+  #[tracing::instrument(skip_all, fields(path = %doc_path.display()))]
   pub fn convert_document_to_html(
     &self,
     doc_path: &Path,
@@ -424,7 +632,7 @@ impl PandocService {
           file_size
         ));
       }
-      eprintln!("📄 文件大小: {} 字节", file_size);
+      tracing::debug!("文件大小: {} 字节", file_size);
     }
 
     // 获取文件扩展名，确定输入格式
@@ -434,8 +642,8 @@ impl PandocService {
       .unwrap_or("docx")
       .to_lowercase();
 
-    eprintln!("🔄 开始转换文档到 HTML: {:?} (格式: {})", doc_path, ext);
-    eprintln!("📝 使用 Pandoc: {:?}", pandoc_path);
+    tracing::debug!("开始转换文档到 HTML: {:?} (格式: {})", doc_path, ext);
+    tracing::debug!("使用 Pandoc: {:?}", pandoc_path);
 
     // 构建 Pandoc 命令，优化格式保留
     // 注意：扩展参数必须作为格式字符串的一部分，不能作为独立参数
@@ -451,27 +659,26 @@ impl PandocService {
       .arg("--extract-media=.") // 提取媒体文件
       .arg("--preserve-tabs"); // 保留制表符
                                // 注意：不再使用 --variable 强制设置字体和字号，避免与文档原有样式冲突
+    let output_file = PandocOutputFile::new();
+    cmd.arg("--output").arg(output_file.path());
 
     // 尝试使用 Lua 过滤器来保留格式（如果存在）
     if let Some(lua_filter) = Self::get_lua_filter_path() {
-      eprintln!("📝 使用 Lua 过滤器: {:?}", lua_filter);
+      tracing::debug!("使用 Lua 过滤器: {:?}", lua_filter);
       cmd.arg("--lua-filter").arg(lua_filter);
     } else {
-      eprintln!("⚠️ 未找到 Lua 过滤器，格式保留可能不完整");
+      tracing::warn!("未找到 Lua 过滤器，格式保留可能不完整");
     }
 
     // 仅当调用方指定时设置工作目录（编辑模式传 doc_path.parent()，使图片解压到文档目录并被 process_images_for_edit 找到；预览/其它路径不传则不改动）
     if let Some(work_dir) = work_dir_for_extract_media {
       cmd.current_dir(work_dir);
-      eprintln!(
-        "📂 [convert_document_to_html] Pandoc 工作目录: {:?}",
-        work_dir
-      );
+      tracing::debug!("[convert_document_to_html] Pandoc 工作目录: {:?}", work_dir);
     }
 
     let output = cmd.output().map_err(|e| {
       let error_msg = format!("执行 Pandoc 失败: {}\nPandoc 路径: {:?}", e, pandoc_path);
-      eprintln!("❌ {}", error_msg);
+      tracing::error!("{}", error_msg);
       error_msg
     })?;
 
@@ -482,15 +689,25 @@ impl PandocService {
         "Pandoc 转换失败:\nSTDERR: {}\nSTDOUT: {}",
         error_msg, stdout_msg
       );
-      eprintln!("❌ {}", full_error);
+      tracing::error!("{}", full_error);
       return Err(full_error);
     }
 
-    let html = String::from_utf8(output.stdout).map_err(|e| {
-      let error_msg = format!("解析 Pandoc 输出失败: {}", e);
-      eprintln!("❌ {}", error_msg);
-      error_msg
+    let level = ConversionLevel::for_output(
+      output_file.len(),
+      crate::services::settings_service::conversion_memory_limit_bytes(),
+    );
+    if level == ConversionLevel::Essential {
+      tracing::warn!(
+        "Pandoc 输出 {} 字节，预计超出转换内存上限，跳过格式增强",
+        output_file.len()
+      );
+    }
+    let html = output_file.read().map_err(|e| {
+      tracing::error!("{}", e);
+      e
     })?;
+    drop(output_file);
 
     // 诊断：检查 Pandoc 输出的 HTML 是否包含样式信息
     let has_inline_styles = html.matches("style=\"").count();
@@ -498,11 +715,11 @@ impl PandocService {
     let has_color = html.matches("color:").count();
     let has_font_size = html.matches("font-size:").count();
 
-    eprintln!("🔍 Pandoc 输出诊断:");
-    eprintln!("   - 内联样式数: {}", has_inline_styles);
-    eprintln!("   - data-custom-style 属性数: {}", has_data_custom_style);
-    eprintln!("   - 颜色样式数: {}", has_color);
-    eprintln!("   - 字号样式数: {}", has_font_size);
+    tracing::debug!("Pandoc 输出诊断:");
+    tracing::debug!("   - 内联样式数: {}", has_inline_styles);
+    tracing::debug!("   - data-custom-style 属性数: {}", has_data_custom_style);
+    tracing::debug!("   - 颜色样式数: {}", has_color);
+    tracing::debug!("   - 字号样式数: {}", has_font_size);
 
     // [Bug1-Debug] 步骤1：Pandoc 原始输出的 body 开头结构
     if let Some(body_start) = html.find("<body") {
@@ -516,7 +733,7 @@ impl PandocService {
         .take(600)
         .collect::<String>()
         .replace('\n', "↵");
-      eprintln!(
+      tracing::debug!(
         "[Bug1-Debug] 步骤1-Pandoc原始body开头(len={}): {}",
         body_end - body_start,
         body_snippet
@@ -538,7 +755,7 @@ impl PandocService {
         .take(600)
         .collect::<String>()
         .replace('\n', "↵");
-      eprintln!(
+      tracing::debug!(
         "[Bug1-Debug] 步骤2-restore后body开头(len={}): {}",
         body_end - body_start,
         body_snippet
@@ -562,11 +779,11 @@ impl PandocService {
         .collect::<Vec<_>>()
         .join(" ");
       let starts_with_newline = after_body.starts_with('\n') || after_body.starts_with("\r\n");
-      eprintln!(
+      tracing::debug!(
         "[Bug1-Debug] 步骤3-strip后: body>后首50字节(hex)={}",
         first_50_hex
       );
-      eprintln!(
+      tracing::debug!(
         "[Bug1-Debug] 步骤3-strip后: body>后是否以换行开头={}",
         starts_with_newline
       );
@@ -575,7 +792,10 @@ impl PandocService {
     // 4. CSS 类转换为内联样式（段落对齐）
     // 只做必要的 CSS 类转换，满足 AI 样式子集要求
     // Pandoc 可能以 CSS 类形式输出段落对齐，TipTap 编辑器需要内联样式才能正确解析
-    let html = Self::convert_css_classes_to_inline_styles(&html);
+    let html = match level {
+      ConversionLevel::Full => Self::convert_css_classes_to_inline_styles(&html),
+      ConversionLevel::Essential => html,
+    };
 
     // 5. 推荐方案：移除 div data-custom-style 包裹，只保留内部 p/h 内容
     // 消除 TipTap 对 div 结构处理可能导致的空白行问题
@@ -583,8 +803,8 @@ impl PandocService {
 
     // 诊断：检查转换后的 HTML
     let after_inline_styles = html.matches("style=\"").count();
-    eprintln!("🔍 转换后诊断:");
-    eprintln!(
+    tracing::debug!("转换后诊断:");
+    tracing::debug!(
       "   - 内联样式数: {} (增加: {})",
       after_inline_styles,
       after_inline_styles as i32 - has_inline_styles as i32
@@ -602,7 +822,7 @@ impl PandocService {
         .map(|b| format!("{:02x}", b))
         .collect::<Vec<_>>()
         .join(" ");
-      eprintln!(
+      tracing::debug!(
         "[Bug1-Debug] 步骤4-convert_css后: body>后首30字节(hex)={}, 以换行开头={}",
         first_30_hex,
         after_body.starts_with('\n') || after_body.starts_with("\r\n")
@@ -610,16 +830,16 @@ impl PandocService {
     }
 
     // 6. 处理图片（编辑模式：所有图片转换为 base64）
-    eprintln!("🖼️ [convert_document_to_html] 开始处理图片...");
+    tracing::debug!("[convert_document_to_html] 开始处理图片...");
     let html = match Self::process_images_for_edit(&html, doc_path) {
       Ok(processed) => {
-        eprintln!("🖼️ [convert_document_to_html] 图片处理成功");
+        tracing::debug!("[convert_document_to_html] 图片处理成功");
         processed
       }
       Err(e) => {
-        eprintln!("❌ [convert_document_to_html] 图片处理失败: {}", e);
+        tracing::error!("[convert_document_to_html] 图片处理失败: {}", e);
         // 即使图片处理失败，也返回 HTML（图片可能无法显示，但不应该导致崩溃）
-        eprintln!("⚠️ [convert_document_to_html] 继续返回 HTML，图片可能无法显示");
+        tracing::warn!("[convert_document_to_html] 继续返回 HTML，图片可能无法显示");
         html
       }
     };
@@ -641,7 +861,7 @@ impl PandocService {
         .map(|b| format!("{:02x}", b))
         .collect::<Vec<_>>()
         .join(" ");
-      eprintln!(
+      tracing::debug!(
         "[Bug1-Debug] 步骤5-process_images后: body>后首30字节(hex)={}, 以换行开头={}",
         first_30_hex,
         after_body.starts_with('\n') || after_body.starts_with("\r\n")
@@ -649,7 +869,6 @@ impl PandocService {
     }
     // [Bug1-Debug] 步骤6：最终返回给前端的 body 开头（经过所有处理）
     if let Some(body_start) = html.find("<body") {
-      use regex::Regex;
       let body_end = html[body_start..]
         .find("</body>")
         .map(|i| body_start + i)
@@ -666,14 +885,14 @@ impl PandocService {
           .and_then(|re| re.find(body_content))
           .map(|m| m.as_str().matches("<p").count())
           .unwrap_or(0);
-      eprintln!(
+      tracing::debug!(
         "[Bug1-Debug] 步骤6-最终返回body: len={}, 开头空p块数={}, 开头={}",
         body_content.len(),
         leading_empty_count,
         body_snippet
       );
     }
-    eprintln!("✅ DOCX 转换成功，HTML 长度: {} 字符", html.len());
+    tracing::info!("DOCX 转换成功，HTML 长度: {} 字符", html.len());
     Ok(html)
   }
 
@@ -736,7 +955,69 @@ impl PandocService {
     }
   }
 
+  /// 提取文档纯文本（用于搜索索引、字数统计等，不保留格式）
+  /// 支持格式：.docx, .odt, .rtf 等 Pandoc 可读取的格式
+  #[tracing::instrument(skip_all, fields(path = %doc_path.display()))]
+  pub fn extract_plain_text(&self, doc_path: &Path) -> Result<String, String> {
+    let pandoc_path = self
+      .pandoc_path
+      .as_ref()
+      .ok_or_else(|| "Pandoc 不可用，无法提取文档文本".to_string())?;
+
+    if !doc_path.exists() {
+      return Err(format!("文件不存在: {}", doc_path.display()));
+    }
+
+    let output = Command::new(pandoc_path)
+      .arg(doc_path.as_os_str())
+      .arg("--to")
+      .arg("plain")
+      .arg("--wrap=none")
+      .output()
+      .map_err(|e| format!("执行 Pandoc 失败: {}", e))?;
+
+    if !output.status.success() {
+      return Err(format!(
+        "Pandoc 提取文本失败: {}",
+        String::from_utf8_lossy(&output.stderr)
+      ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+  }
+
+  /// 将文档转换为 Markdown（GFM，不折行），供 AI 阅读 DOCX/ODT/RTF/EPUB/HTML 等格式
+  #[tracing::instrument(skip_all, fields(path = %doc_path.display()))]
+  pub fn extract_markdown(&self, doc_path: &Path) -> Result<String, String> {
+    let pandoc_path = self
+      .pandoc_path
+      .as_ref()
+      .ok_or_else(|| "Pandoc 不可用，无法提取文档内容".to_string())?;
+
+    if !doc_path.exists() {
+      return Err(format!("文件不存在: {}", doc_path.display()));
+    }
+
+    let output = Command::new(pandoc_path)
+      .arg(doc_path.as_os_str())
+      .arg("--to")
+      .arg("gfm")
+      .arg("--wrap=none")
+      .output()
+      .map_err(|e| format!("执行 Pandoc 失败: {}", e))?;
+
+    if !output.status.success() {
+      return Err(format!(
+        "Pandoc 转换 Markdown 失败: {}",
+        String::from_utf8_lossy(&output.stderr)
+      ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+  }
+
   /// 将 HTML 转换为 DOCX 文件
+  #[tracing::instrument(skip_all, fields(path = %docx_path.display()))]
   pub fn convert_html_to_docx(&self, html_content: &str, docx_path: &Path) -> Result<(), String> {
     if !self.is_available() {
       return Err("Pandoc 不可用，请安装 Pandoc 或确保内置 Pandoc 可用。\n访问 https://pandoc.org/installing.html 获取安装指南。".to_string());
@@ -748,35 +1029,32 @@ impl PandocService {
     let html_content = Self::ensure_empty_paragraphs_placeholder(html_content);
 
     // 创建临时 HTML 文件
-    let temp_html = std::env::temp_dir().join(format!("pandoc_temp_{}.html", uuid::Uuid::new_v4()));
-    std::fs::write(&temp_html, &html_content).map_err(|e| {
+    let temp_html = temp_service::temp_file("pandoc", "html");
+    std::fs::write(temp_html.path(), &html_content).map_err(|e| {
       let error_msg = format!("创建临时文件失败: {}", e);
-      eprintln!("❌ {}", error_msg);
+      tracing::error!("{}", error_msg);
       error_msg
     })?;
 
-    eprintln!("🔄 开始转换 HTML 到 DOCX");
-    eprintln!(
+    tracing::debug!("开始转换 HTML 到 DOCX");
+    tracing::debug!(
       "[BlankLineDebug] Pandoc convert_html_to_docx: htmlLen={}, outPath={:?}",
       html_content.len(),
       docx_path
     );
-    eprintln!("📝 使用 Pandoc: {:?}", pandoc_path);
-    eprintln!("📄 输出路径: {:?}", docx_path);
+    tracing::debug!("使用 Pandoc: {:?}", pandoc_path);
+    tracing::debug!("输出路径: {:?}", docx_path);
 
     // 确保输出目录存在
     if let Some(parent) = docx_path.parent() {
-      if let Err(e) = std::fs::create_dir_all(parent) {
-        let _ = std::fs::remove_file(&temp_html);
-        return Err(format!("创建输出目录失败: {}", e));
-      }
+      std::fs::create_dir_all(parent).map_err(|e| format!("创建输出目录失败: {}", e))?;
     }
 
     // 执行转换（保留格式）
     // 注意：扩展参数必须作为格式字符串的一部分
     let mut cmd = Command::new(pandoc_path);
     cmd
-      .arg(&temp_html)
+      .arg(temp_html.path())
       .arg("--from")
       .arg("html+raw_html+native_divs+native_spans") // 扩展作为格式字符串的一部分
       .arg("--to")
@@ -788,21 +1066,20 @@ impl PandocService {
 
     // 如果找到参考文档，使用它来保留格式
     if let Some(ref_doc) = Self::get_reference_docx_path() {
-      eprintln!("📄 使用参考文档: {:?}", ref_doc);
+      tracing::debug!("使用参考文档: {:?}", ref_doc);
       cmd.arg("--reference-doc").arg(ref_doc);
     } else {
-      eprintln!("⚠️ 未使用参考文档，格式保留可能不完整");
+      tracing::warn!("未使用参考文档，格式保留可能不完整");
     }
 
     let output = cmd.output().map_err(|e| {
-      let _ = std::fs::remove_file(&temp_html);
       let error_msg = format!("执行 Pandoc 失败: {}\nPandoc 路径: {:?}", e, pandoc_path);
-      eprintln!("❌ {}", error_msg);
+      tracing::error!("{}", error_msg);
       error_msg
     })?;
 
     // 清理临时文件
-    let _ = std::fs::remove_file(&temp_html);
+    drop(temp_html);
 
     if !output.status.success() {
       let error_msg = String::from_utf8_lossy(&output.stderr);
@@ -811,34 +1088,165 @@ impl PandocService {
         "Pandoc 转换失败:\nSTDERR: {}\nSTDOUT: {}",
         error_msg, stdout_msg
       );
-      eprintln!("❌ {}", full_error);
+      tracing::error!("{}", full_error);
       return Err(full_error);
     }
 
-    eprintln!("✅ HTML 转换 DOCX 成功: {:?}", docx_path);
+    tracing::info!("HTML 转换 DOCX 成功: {:?}", docx_path);
+    Ok(())
+  }
+
+  /// 工作区参考模板：优先使用 `.binder/reference.docx`，否则回退到内置模板
+  pub fn workspace_reference_docx(workspace_path: &Path) -> Option<PathBuf> {
+    let custom = workspace_path.join(".binder").join("reference.docx");
+    if custom.is_file() {
+      return Some(custom);
+    }
+    Self::get_reference_docx_path()
+  }
+
+  /// 将 Markdown 转换为 DOCX 文件，样式取自给定的参考模板
+  #[tracing::instrument(skip_all, fields(path = %docx_path.display()))]
+  pub fn convert_markdown_to_docx(
+    &self,
+    markdown: &str,
+    docx_path: &Path,
+    reference_doc: Option<&Path>,
+  ) -> Result<(), String> {
+    let pandoc_path = self
+      .pandoc_path
+      .as_ref()
+      .ok_or_else(|| "Pandoc 不可用，无法生成 DOCX 文件".to_string())?;
+
+    let temp_md = temp_service::temp_file("pandoc", "md");
+    std::fs::write(temp_md.path(), markdown).map_err(|e| format!("创建临时文件失败: {}", e))?;
+
+    let mut cmd = Command::new(pandoc_path);
+    cmd
+      .arg(temp_md.path())
+      .arg("--from")
+      .arg("markdown")
+      .arg("--to")
+      .arg("docx")
+      .arg("--output")
+      .arg(docx_path.as_os_str());
+    if let Some(ref_doc) = reference_doc {
+      cmd.arg("--reference-doc").arg(ref_doc);
+    }
+
+    let output = cmd.output();
+    drop(temp_md);
+    let output = output.map_err(|e| format!("执行 Pandoc 失败: {}", e))?;
+
+    if !output.status.success() {
+      return Err(format!(
+        "Pandoc 转换 DOCX 失败: {}",
+        String::from_utf8_lossy(&output.stderr)
+      ));
+    }
+
+    Ok(())
+  }
+
+  /// 将 DOCX 转换为 Pandoc Markdown（不折行，ATX 标题），图片提取到 media_dir。
+  /// media_dir 为相对路径时相对 working_dir，Markdown 中的图片链接也保持相对
+  #[tracing::instrument(skip_all, fields(path = %doc_path.display()))]
+  pub fn convert_docx_to_markdown(
+    &self,
+    doc_path: &Path,
+    media_dir: &Path,
+    working_dir: &Path,
+  ) -> Result<String, String> {
+    let pandoc_path = self
+      .pandoc_path
+      .as_ref()
+      .ok_or_else(|| "Pandoc 不可用，无法转换 DOCX 文件".to_string())?;
+
+    let output = Command::new(pandoc_path)
+      .current_dir(working_dir)
+      .arg(doc_path.as_os_str())
+      .arg("--from")
+      .arg("docx")
+      .arg("--to")
+      .arg("markdown")
+      .arg("--wrap=none")
+      .arg("--markdown-headings=atx")
+      .arg(format!("--extract-media={}", media_dir.display()))
+      .output()
+      .map_err(|e| format!("执行 Pandoc 失败: {}", e))?;
+
+    if !output.status.success() {
+      return Err(format!(
+        "Pandoc 转换 Markdown 失败: {}",
+        String::from_utf8_lossy(&output.stderr)
+      ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+  }
+
+  /// 将 Pandoc Markdown 转换为 to 指定的格式写入 output_path，extra_args 原样传给 Pandoc；
+  /// 以输出文件所在目录为工作目录，相对图片链接据此解析
+  #[tracing::instrument(skip_all, fields(path = %output_path.display(), to))]
+  pub fn convert_markdown_to(
+    &self,
+    markdown: &str,
+    to: &str,
+    output_path: &Path,
+    extra_args: &[String],
+  ) -> Result<(), String> {
+    let pandoc_path = self
+      .pandoc_path
+      .as_ref()
+      .ok_or_else(|| "Pandoc 不可用，无法转换文档".to_string())?;
+
+    let temp_md = temp_service::temp_file("pandoc", "md");
+    std::fs::write(temp_md.path(), markdown).map_err(|e| format!("创建临时文件失败: {}", e))?;
+
+    let mut cmd = Command::new(pandoc_path);
+    if let Some(parent) = output_path.parent().filter(|p| p.is_dir()) {
+      cmd.current_dir(parent);
+    }
+    cmd
+      .arg(temp_md.path())
+      .arg("--from")
+      .arg("markdown")
+      .arg("--to")
+      .arg(to)
+      .arg("--output")
+      .arg(output_path.as_os_str())
+      .args(extra_args);
+
+    let output = cmd.output();
+    drop(temp_md);
+    let output = output.map_err(|e| format!("执行 Pandoc 失败: {}", e))?;
+
+    if !output.status.success() {
+      return Err(format!(
+        "Pandoc 转换失败: {}",
+        String::from_utf8_lossy(&output.stderr)
+      ));
+    }
+
     Ok(())
   }
 
   /// Bug 3 保存前：将空段落替换为含零宽不换行空格（\uFEFF）的段落，Pandoc 会写入 DOCX
   /// 覆盖 <p></p>、<p> </p>、<p><br></p>、<p><span></span></p> 等变体
   fn ensure_empty_paragraphs_placeholder(html: &str) -> String {
-    use regex::Regex;
     const PLACEHOLDER: char = '\u{FEFF}';
     let placeholder_str = format!("{}", PLACEHOLDER);
     let replacement = format!("<p$1>{}</p>", placeholder_str);
     let mut result = html.to_string();
     // 顺序重要：先匹配含 <br> 的，再匹配完全空的
-    let re_br = Regex::new(r#"<p([^>]*)>\s*<br\s*/?>\s*</p>"#).unwrap();
-    let re_empty = Regex::new(r#"<p([^>]*)>\s*</p>"#).unwrap();
-    result = re_br
+    result = EMPTY_BR_PARAGRAPH_RE
       .replace_all(&result, replacement.as_str())
       .into_owned();
-    result = re_empty
+    result = EMPTY_PARAGRAPH_RE
       .replace_all(&result, replacement.as_str())
       .into_owned();
     // 覆盖 <p><span></span></p> 等仅含空标签的变体
-    let re_empty_span = Regex::new(r#"<p([^>]*)>\s*<span[^>]*>\s*</span>\s*</p>"#).unwrap();
-    result = re_empty_span
+    result = EMPTY_SPAN_PARAGRAPH_RE
       .replace_all(&result, replacement.as_str())
       .into_owned();
     result
@@ -847,11 +1255,9 @@ impl PandocService {
   /// 推荐方案：移除 Pandoc 的 div data-custom-style 包裹层，只保留内部内容。
   /// TipTap 对 div 结构的处理可能导致空白行，扁平化为纯 p/h 序列可消除该问题。
   fn unwrap_div_data_custom_style(html: &str) -> String {
-    use regex::Regex;
-    let re = Regex::new(r#"<div[^>]*data-custom-style="[^"]*"[^>]*>([\s\S]*?)</div>"#).unwrap();
     let mut result = html.to_string();
     loop {
-      let new_result = re
+      let new_result = CUSTOM_STYLE_DIV_RE
         .replace_all(&result, |caps: &regex::Captures| caps[1].trim().to_string())
         .into_owned();
       if new_result == result {
@@ -860,53 +1266,32 @@ impl PandocService {
       result = new_result;
     }
     // 折叠 block 元素之间的换行（来自原 </div>\n<div> 等），避免 TipTap 解析时产生多余空白行
-    let re_collapse = Regex::new(r">\s*\n+\s*<").unwrap();
-    result = re_collapse.replace_all(&result, "><").into_owned();
+    result = BLOCK_GAP_RE.replace_all(&result, "><").into_owned();
     result
   }
 
   /// Bug 3 加载后：将含 \uFEFF 的占位段落还原为空段落，用户完全感知不到
   fn restore_empty_paragraphs_placeholder(html: &str) -> String {
-    use regex::Regex;
-    const PLACEHOLDER: char = '\u{FEFF}';
-    let mut result = html.to_string();
-    // 匹配 <p>...</p> 中仅含占位符（字符或实体）的段落
-    let re_char = Regex::new(&format!(r#"<p([^>]*)>\s*{}\s*</p>"#, PLACEHOLDER)).unwrap();
-    let re_entity_hex = Regex::new(r#"<p([^>]*)>\s*&#xFEFF;\s*</p>"#).unwrap();
-    let re_entity_dec = Regex::new(r#"<p([^>]*)>\s*&#65279;\s*</p>"#).unwrap();
-    result = re_char.replace_all(&result, "<p$1></p>").into_owned();
-    result = re_entity_hex.replace_all(&result, "<p$1></p>").into_owned();
-    result = re_entity_dec.replace_all(&result, "<p$1></p>").into_owned();
-    result
+    let mut result = Cow::Borrowed(html);
+    // 匹配 <p>...</p> 中仅含占位符（字符或实体）的段落；没有命中时不复制整篇 HTML
+    for re in PLACEHOLDER_PARAGRAPH_RES.iter() {
+      if let Cow::Owned(replaced) = re.replace_all(&result, "<p$1></p>") {
+        result = Cow::Owned(replaced);
+      }
+    }
+    result.into_owned()
   }
 
   /// 从 HTML 片段中移除开头的空块（供 strip_leading_empty_blocks 递归处理 div/section 内部）
   fn strip_leading_empty_blocks_from_fragment(fragment: &str) -> String {
-    use regex::Regex;
-    let re_leading_empty_ps =
-      Regex::new(r#"(?s)^\s*(<p[^>]*>\s*</p>\s*|<p[^>]*>\s*<br\s*/?>\s*</p>\s*)+"#).unwrap();
-    let mut result = re_leading_empty_ps.replace(fragment, "").into_owned();
-    let block_patterns: [&str; 10] = [
-      r"(?s)^\s*<p[^>]*>.*?</p>\s*",
-      r"(?s)^\s*<div[^>]*>.*?</div>\s*",
-      r"(?s)^\s*<section[^>]*>.*?</section>\s*",
-      r"(?s)^\s*<blockquote[^>]*>.*?</blockquote>\s*",
-      r"(?s)^\s*<h1[^>]*>.*?</h1>\s*",
-      r"(?s)^\s*<h2[^>]*>.*?</h2>\s*",
-      r"(?s)^\s*<h3[^>]*>.*?</h3>\s*",
-      r"(?s)^\s*<h4[^>]*>.*?</h4>\s*",
-      r"(?s)^\s*<h5[^>]*>.*?</h5>\s*",
-      r"(?s)^\s*<h6[^>]*>.*?</h6>\s*",
-    ];
-    let block_res: Vec<Regex> = block_patterns
-      .iter()
-      .filter_map(|p| Regex::new(p).ok())
-      .collect();
+    let mut result = LEADING_EMPTY_PARAGRAPHS_RE
+      .replace(fragment, "")
+      .into_owned();
     let block_selector =
       Selector::parse("p, div, section, blockquote, h1, h2, h3, h4, h5, h6").unwrap();
     loop {
       let mut removed = false;
-      for re in &block_res {
+      for re in LEADING_BLOCK_RES.iter() {
         if let Some(cap) = re.captures(result.as_str()) {
           let matched = cap.get(0).map(|m| m.as_str()).unwrap_or("");
           let frag = Html::parse_fragment(matched.trim());
@@ -932,15 +1317,10 @@ impl PandocService {
   /// 用 scraper 解析 body 子节点，移除开头无实质文本的块（element.text().trim().is_empty()），
   /// 覆盖 <p></p>、<p> </p>、<p><br></p>、<p><span></span></p> 等变体。
   fn strip_leading_empty_blocks(html: &str) -> String {
-    use regex::Regex;
-    let body_re = match Regex::new(r#"<body([^>]*)>([\s\S]*?)</body>"#) {
-      Ok(r) => r,
-      Err(_) => return html.to_string(),
-    };
-    let body_caps = match body_re.captures(html) {
+    let body_caps = match BODY_RE.captures(html) {
       Some(c) => c,
       None => {
-        eprintln!("[Bug1-Debug] strip_leading_empty_blocks: 未找到 <body>...</body>，跳过");
+        tracing::debug!("[Bug1-Debug] strip_leading_empty_blocks: 未找到 <body>...</body>，跳过");
         return html.to_string();
       }
     };
@@ -952,44 +1332,28 @@ impl PandocService {
       .to_string();
     let original_len = body_content.len();
     let head60: String = body_content.chars().take(60).collect();
-    eprintln!(
+    tracing::debug!(
       "[Bug1-Debug] strip_leading_empty_blocks 入口: body_content.len={}, 开头60字符: {:?}",
       body_content.len(),
       head60
     );
     // 预移除：连续多个空段落（Pandoc 常输出多行），正则一次性移除更可靠
-    let re_leading_empty_ps =
-      Regex::new(r#"(?s)^\s*(<p[^>]*>\s*</p>\s*|<p[^>]*>\s*<br\s*/?>\s*</p>\s*)+"#).unwrap();
     let before_pre = body_content.len();
-    body_content = re_leading_empty_ps.replace(&body_content, "").into_owned();
+    body_content = LEADING_EMPTY_PARAGRAPHS_RE
+      .replace(&body_content, "")
+      .into_owned();
     if body_content.len() < before_pre {
-      eprintln!(
+      tracing::debug!(
         "[Bug1-Debug] 预移除正则命中: 移除 {} 字节",
         before_pre - body_content.len()
       );
     }
-    // 块级标签正则（匹配一个完整块，含 section/blockquote 等 Pandoc 可能输出的结构）
-    let block_patterns: [&str; 10] = [
-      r"(?s)^\s*<p[^>]*>.*?</p>\s*",
-      r"(?s)^\s*<div[^>]*>.*?</div>\s*",
-      r"(?s)^\s*<section[^>]*>.*?</section>\s*",
-      r"(?s)^\s*<blockquote[^>]*>.*?</blockquote>\s*",
-      r"(?s)^\s*<h1[^>]*>.*?</h1>\s*",
-      r"(?s)^\s*<h2[^>]*>.*?</h2>\s*",
-      r"(?s)^\s*<h3[^>]*>.*?</h3>\s*",
-      r"(?s)^\s*<h4[^>]*>.*?</h4>\s*",
-      r"(?s)^\s*<h5[^>]*>.*?</h5>\s*",
-      r"(?s)^\s*<h6[^>]*>.*?</h6>\s*",
-    ];
-    let block_res: Vec<Regex> = block_patterns
-      .iter()
-      .filter_map(|p| Regex::new(p).ok())
-      .collect();
+    // 逐个匹配开头的完整块（含 section/blockquote 等 Pandoc 可能输出的结构），无文本则移除
     let block_selector =
       Selector::parse("p, div, section, blockquote, h1, h2, h3, h4, h5, h6").unwrap();
     loop {
       let mut removed = false;
-      for re in &block_res {
+      for re in LEADING_BLOCK_RES.iter() {
         if let Some(cap) = re.captures(body_content.as_str()) {
           let matched = cap.get(0).map(|m| m.as_str()).unwrap_or("");
           let fragment = Html::parse_fragment(matched.trim());
@@ -998,7 +1362,7 @@ impl PandocService {
             let tag: &str = el.value().name().as_ref();
             if text.trim().is_empty() {
               let match_preview: String = matched.chars().take(80).collect();
-              eprintln!(
+              tracing::debug!(
                 "[Bug1-Debug] 移除空块 tag={} len={} preview={:?}",
                 tag,
                 matched.len(),
@@ -1016,7 +1380,7 @@ impl PandocService {
               let inner = &matched[open_tag_end..inner_end];
               let stripped_inner = Self::strip_leading_empty_blocks_from_fragment(inner);
               if stripped_inner != inner {
-                eprintln!(
+                tracing::debug!(
                   "[Bug1-Debug] 递归剥离 {} 内部空块: inner_len {} -> {}",
                   tag,
                   inner.len(),
@@ -1036,7 +1400,7 @@ impl PandocService {
             // 首个块有内容，停止剥离
             if !removed {
               let preview: String = matched.chars().take(100).collect();
-              eprintln!(
+              tracing::debug!(
                 "[Bug1-Debug] 首个块 tag={} 有内容(text_len={})，停止: {:?}",
                 tag,
                 text.len(),
@@ -1045,7 +1409,7 @@ impl PandocService {
             }
           } else {
             let preview: String = matched.chars().take(100).collect();
-            eprintln!(
+            tracing::warn!(
               "[Bug1-Debug] 块正则命中但 selector 未匹配到元素，可能结构异常: {:?}",
               preview
             );
@@ -1058,7 +1422,7 @@ impl PandocService {
     }
     let stripped = original_len - body_content.len();
     if stripped > 0 {
-      eprintln!(
+      tracing::debug!(
         "[Bug1-Debug] strip_leading_empty_blocks 完成: 共移除 {} 字节",
         stripped
       );
@@ -1071,7 +1435,7 @@ impl PandocService {
         .map(|b| format!("{:02x}", b))
         .collect::<Vec<_>>()
         .join(" ");
-      eprintln!("[Bug1-Debug] strip_leading_empty_blocks 未移除任何内容，body_content以换行开头={}, 首10字节hex={}, 开头200字符: {:?}", starts_with_newline, first_10_hex, first200);
+      tracing::debug!("[Bug1-Debug] strip_leading_empty_blocks 未移除任何内容，body_content以换行开头={}, 首10字节hex={}, 开头200字符: {:?}", starts_with_newline, first_10_hex, first200);
     }
     // Bug1：body_content 可能含前导换行（如 <body>\n<div>），strip 未移除时保留，导致前端解析出空段落。
     // 重组前 trim 前导空白，避免 body 后首字符为换行。
@@ -1082,27 +1446,25 @@ impl PandocService {
       body_content
     };
     let new_body = format!("<body{}>{}</body>", body_attrs, body_content);
-    body_re.replace(html, new_body.as_str()).into_owned()
+    BODY_RE.replace(html, new_body.as_str()).into_owned()
   }
 
   /// 将 CSS 类转换为内联样式
   /// 处理 Pandoc 生成的 HTML 中的 CSS 类，转换为内联样式以便 TipTap 正确解析
-  fn convert_css_classes_to_inline_styles(html: &str) -> String {
-    use regex::Regex;
-    let mut result = html.to_string();
+  ///
+  /// 先从 `<style>` 中收集类 / 标签规则，再一次遍历所有开始标签，依次合并：
+  /// 标签规则 → 类规则 → 常见居中类 → data-custom-style，最后清除 body 顶部留白
+  #[doc(hidden)]
+  pub fn convert_css_classes_to_inline_styles(html: &str) -> String {
+    let mut style_map: HashMap<String, String> = HashMap::new();
+    let mut tag_style_map: HashMap<String, String> = HashMap::new();
 
     // 1. 提取 <style> 标签中的所有 CSS 规则
-    let style_regex = Regex::new(r#"<style[^>]*>([\s\S]*?)</style>"#).unwrap();
-    let mut style_map: std::collections::HashMap<String, String> = std::collections::HashMap::new();
-    let mut tag_style_map: std::collections::HashMap<String, String> =
-      std::collections::HashMap::new();
-
-    for cap in style_regex.captures_iter(&result) {
+    for cap in STYLE_BLOCK_RE.captures_iter(html) {
       let style_content = &cap[1];
 
       // 解析 CSS 类规则：.class-name { property: value; }
-      let rule_regex = Regex::new(r#"\.([a-zA-Z0-9_-]+)\s*\{([^}]+)\}"#).unwrap();
-      for rule_cap in rule_regex.captures_iter(style_content) {
+      for rule_cap in CSS_CLASS_RULE_RE.captures_iter(style_content) {
         let class_name = rule_cap[1].to_string();
         let styles = rule_cap[2].trim().to_string();
         // 合并相同类名的样式（如果存在）
@@ -1116,64 +1478,46 @@ impl PandocService {
 
       // 解析标签选择器规则：p { ... }, h1 { ... }, div { ... } 等
       // 支持单个标签和组合标签（如 h1, h2, h3 { ... }）
-      let tag_rule_regex = Regex::new(r#"((?:p|h[1-6]|div|span|td|th|body|html)(?:\s*,\s*(?:p|h[1-6]|div|span|td|th|body|html))*)\s*\{([^}]+)\}"#).unwrap();
-      for tag_cap in tag_rule_regex.captures_iter(style_content) {
-        let tag_selector = tag_cap[1].to_string();
-        let styles = tag_cap[2].trim().to_string();
-        // 解析组合选择器中的每个标签
-        let tags: Vec<&str> = tag_selector.split(',').map(|s| s.trim()).collect();
-        for tag_name in tags {
-          // 合并相同标签的样式（追加，而不是覆盖）
+      for tag_cap in CSS_TAG_RULE_RE.captures_iter(style_content) {
+        let tag_selector = &tag_cap[1];
+        let styles = tag_cap[2].trim();
+        for tag_name in tag_selector.split(',').map(str::trim) {
+          // 合并相同标签的样式（追加缺少的属性，而不是覆盖）
           tag_style_map
             .entry(tag_name.to_string())
             .and_modify(|e| {
-              // 先收集现有属性
               let existing_props: Vec<&str> = e
                 .split(';')
                 .map(|s| s.split(':').next().unwrap_or("").trim())
                 .collect();
-
-              // 收集需要添加的新样式
-              let mut new_styles = Vec::new();
-              for style_part in styles.split(';') {
-                let style_part = style_part.trim();
-                if !style_part.is_empty() {
-                  let prop = style_part.split(':').next().unwrap_or("").trim();
-                  // 如果属性不存在，则添加到列表
-                  if !existing_props.contains(&prop) {
-                    new_styles.push(style_part);
-                  }
-                }
-              }
-
-              // 一次性添加所有新样式
+              let new_styles: Vec<&str> = styles
+                .split(';')
+                .map(str::trim)
+                .filter(|part| !part.is_empty())
+                .filter(|part| !existing_props.contains(&css_property(part)))
+                .collect();
               if !new_styles.is_empty() {
-                let new_styles_str = new_styles.join("; ");
-                *e = format!("{}; {}", e, new_styles_str);
+                *e = format!("{}; {}", e, new_styles.join("; "));
               }
             })
-            .or_insert(styles.clone());
+            .or_insert_with(|| styles.to_string());
         }
 
         // 诊断：如果包含 text-align，输出日志
         if styles.contains("text-align") {
-          eprintln!(
-            "📝 提取到包含 text-align 的规则: {} {{ {} }}",
-            tag_selector, styles
+          tracing::debug!(
+            "提取到包含 text-align 的规则: {} {{ {} }}",
+            tag_selector,
+            styles
           );
         }
       }
 
-      // 对齐信息通过 CSS 类转换保留（convert_css_classes_to_inline_styles）
-
-      // 解析 ID 选择器规则：#id { ... }（用于特定元素）
-      let id_rule_regex = Regex::new(r#"#([a-zA-Z0-9_-]+)\s*\{([^}]+)\}"#).unwrap();
-      for id_cap in id_rule_regex.captures_iter(style_content) {
-        let id_name = id_cap[1].to_string();
+      // 解析 ID 选择器规则：#id { ... }，使用特殊前缀存入 style_map
+      for id_cap in CSS_ID_RULE_RE.captures_iter(style_content) {
         let styles = id_cap[2].trim().to_string();
-        // 将 ID 样式也存储到 style_map 中，使用特殊前缀
         style_map
-          .entry(format!("#{}", id_name))
+          .entry(format!("#{}", &id_cap[1]))
           .and_modify(|e| {
             *e = format!("{}; {}", e, styles);
           })
@@ -1181,272 +1525,75 @@ impl PandocService {
       }
     }
 
-    // 统计 ID 选择器数量
     let id_selector_count = style_map.keys().filter(|k| k.starts_with('#')).count();
-    let class_selector_count = style_map.len() - id_selector_count;
-
-    eprintln!("📝 提取到 {} 个 CSS 类规则", class_selector_count);
-    eprintln!("📝 提取到 {} 个 ID 选择器规则", id_selector_count);
-    eprintln!("📝 提取到 {} 个标签样式规则", tag_style_map.len());
-
-    // 2. 将标签样式应用到所有对应标签的元素
-    for (tag_name, styles) in tag_style_map.iter() {
-      // 查找所有该标签的元素（不区分大小写）
-      let tag_pattern = format!(r#"<{}[^>]*>"#, regex::escape(tag_name));
-      let tag_re = Regex::new(&tag_pattern).unwrap();
-      let mut replacements: Vec<(usize, usize, String)> = Vec::new();
-
-      // 诊断：检查样式内容
-      if styles.contains("text-align") {
-        eprintln!("🔍 为 <{}> 应用样式，包含 text-align: {}", tag_name, styles);
-      }
-
-      for cap in tag_re.captures_iter(&result) {
-        let full_match = cap.get(0).unwrap();
-        let match_start = full_match.start();
-        let match_end = full_match.end();
-        let element = &result[match_start..match_end];
-
-        // 检查是否已有 style 属性
-        let new_element = if element.contains("style=") {
-          let style_re = Regex::new(r#"style="([^"]*)""#).unwrap();
-          style_re
-            .replace(element, |caps: &regex::Captures| {
-              let existing_style = &caps[1];
-              let mut new_styles = existing_style.to_string();
-              for style_part in styles.split(';') {
-                let style_part = style_part.trim();
-                if !style_part.is_empty() {
-                  let prop = style_part.split(':').next().unwrap_or("").trim();
-                  // 对于 text-align，需要检查是否已存在（可能以不同形式存在）
-                  let prop_lower = prop.to_lowercase();
-                  if prop_lower == "text-align" {
-                    // 检查是否已有 text-align 或 textAlign
-                    if !existing_style.to_lowercase().contains("text-align") {
-                      new_styles.push_str(&format!("; {}", style_part));
-                    }
-                  } else if !existing_style.to_lowercase().contains(&prop_lower) {
-                    new_styles.push_str(&format!("; {}", style_part));
-                  }
-                }
-              }
-              format!(r#"style="{}""#, new_styles)
-            })
-            .to_string()
-        } else {
-          element.replace('>', &format!(r#" style="{}">"#, styles))
-        };
-
-        replacements.push((match_start, match_end, new_element));
-      }
+    tracing::debug!(
+      "提取到 {} 个 CSS 类规则，{} 个 ID 选择器规则，{} 个标签样式规则",
+      style_map.len() - id_selector_count,
+      id_selector_count,
+      tag_style_map.len()
+    );
 
-      // 从后往前替换，避免索引偏移
-      for (start, end, new_elem) in replacements.iter().rev() {
-        result.replace_range(*start..*end, new_elem);
-      }
+    // 2. 一次遍历所有开始标签，合并内联样式
+    let mut styled_elements = 0usize;
+    let result = START_TAG_RE.replace_all(html, |caps: &regex::Captures| {
+      let original = &caps[0];
+      let tag_name = &caps[1];
+      let mut element = original.to_string();
 
-      if !replacements.is_empty() {
-        eprintln!(
-          "✅ 为 {} 个 <{}> 元素应用样式: {}",
-          replacements.len(),
-          tag_name,
-          styles
-        );
+      // 2.1 标签样式（属性名不区分大小写判断是否已存在）
+      if let Some(styles) = tag_style_map.get(tag_name) {
+        element = merge_inline_style(&element, styles, true);
       }
-    }
 
-    // 3. 将 CSS 类转换为内联样式
-    for (class_name, styles) in style_map.iter() {
-      let pattern = format!(r#"class="([^"]*\b{}\b[^"]*)""#, regex::escape(class_name));
-      let re = Regex::new(&pattern).unwrap();
-
-      // 收集所有需要替换的位置（从后往前，避免索引偏移）
-      let mut replacements: Vec<(usize, usize, String)> = Vec::new();
-
-      for cap in re.captures_iter(&result) {
-        let full_match = cap.get(0).unwrap();
-        let match_start = full_match.start();
-        let _match_end = full_match.end();
-
-        // 找到对应的元素开始标签
-        if let Some(elem_start) = result[..match_start].rfind('<') {
-          if let Some(elem_end) = result[match_start..].find('>') {
-            let elem_end = match_start + elem_end;
-            let element = &result[elem_start..elem_end + 1];
-
-            // 检查是否已有 style 属性
-            let new_element = if element.contains("style=") {
-              // 追加样式（合并，避免重复）
-              let style_re = Regex::new(r#"style="([^"]*)""#).unwrap();
-              style_re
-                .replace(element, |caps: &regex::Captures| {
-                  let existing_style = &caps[1];
-                  // 检查样式是否已存在，避免重复
-                  let mut new_styles = existing_style.to_string();
-                  for style_part in styles.split(';') {
-                    let style_part = style_part.trim();
-                    if !style_part.is_empty() {
-                      let prop = style_part.split(':').next().unwrap_or("").trim();
-                      if !existing_style.contains(prop) {
-                        new_styles.push_str(&format!("; {}", style_part));
-                      }
-                    }
-                  }
-                  format!(r#"style="{}""#, new_styles)
-                })
-                .to_string()
-            } else {
-              // 添加 style 属性
-              element.replace('>', &format!(r#" style="{}">"#, styles))
-            };
+      let classes: Vec<String> = CLASS_ATTR_RE
+        .captures(original)
+        .map(|c| c[1].split_whitespace().map(str::to_string).collect())
+        .unwrap_or_default();
 
-            replacements.push((elem_start, elem_end + 1, new_element));
-          }
+      // 2.2 CSS 类样式
+      for class_name in &classes {
+        if let Some(styles) = style_map.get(class_name) {
+          element = merge_inline_style(&element, styles, false);
         }
       }
 
-      // 从后往前替换，避免索引偏移
-      for (start, end, new_elem) in replacements.iter().rev() {
-        result.replace_range(*start..*end, new_elem);
+      // 2.3 常见居中类（即使不在 style 标签中）
+      if classes.iter().any(|c| c == "center" || c == "text-center")
+        && !element.contains("text-align")
+      {
+        element = merge_inline_style(&element, "text-align: center", false);
       }
-    }
 
-    // 3. 处理特定的常见格式类（即使不在 style 标签中）
-    // 处理居中对齐
-    for class_name in &["center", "text-center"] {
-      let pattern = format!(r#"class="([^"]*\b{}\b[^"]*)""#, regex::escape(class_name));
-      let re = Regex::new(&pattern).unwrap();
-      let mut replacements: Vec<(usize, usize, String)> = Vec::new();
-
-      for cap in re.captures_iter(&result) {
-        let full_match = cap.get(0).unwrap();
-        let match_start = full_match.start();
-        let _match_end = full_match.end();
-
-        if let Some(elem_start) = result[..match_start].rfind('<') {
-          if let Some(elem_end) = result[match_start..].find('>') {
-            let elem_end = match_start + elem_end;
-            let element = &result[elem_start..elem_end + 1];
-
-            let new_element = if element.contains("style=") {
-              if !element.contains("text-align") {
-                let style_re = Regex::new(r#"style="([^"]*)""#).unwrap();
-                style_re
-                  .replace(element, |caps: &regex::Captures| {
-                    format!(r#"style="{}; text-align: center""#, &caps[1])
-                  })
-                  .to_string()
-              } else {
-                element.to_string()
-              }
-            } else {
-              element.replace('>', r#" style="text-align: center">"#)
-            };
-
-            replacements.push((elem_start, elem_end + 1, new_element));
-          }
+      // 2.4 data-custom-style（Pandoc 用此属性保留样式名），有对应样式时转换为内联样式并移除属性
+      if let Some(style_name) = DATA_CUSTOM_STYLE_RE
+        .captures(original)
+        .map(|c| c[1].to_string())
+      {
+        if let Some(styles) = style_map.get(&style_name) {
+          element = merge_inline_style(&element, styles, false)
+            .replace(&format!(r#" data-custom-style="{}""#, style_name), "");
         }
       }
 
-      for (start, end, new_elem) in replacements.iter().rev() {
-        result.replace_range(*start..*end, new_elem);
+      // 2.5 Bug1 修复：移除 body 的 padding-top、margin-top，消除 DOCX 顶部空白行
+      // Pandoc 默认模板会给 body 添加 padding-top: 50px、padding: 12px、margin-top: 0.5em 等，导致约 70px 顶部空白
+      if tag_name == "body" {
+        element = override_body_top_spacing(&element);
       }
-    }
 
-    // 4. 处理 data-custom-style 属性（Pandoc 可能使用此属性保留样式信息）
-    // 将 data-custom-style 转换为内联样式
-    let data_style_regex = Regex::new(r#"data-custom-style="([^"]+)""#).unwrap();
-    let mut replacements: Vec<(usize, usize, String)> = Vec::new();
-
-    for cap in data_style_regex.captures_iter(&result) {
-      let full_match = cap.get(0).unwrap();
-      let match_start = full_match.start();
-      let match_end = full_match.end();
-      let style_name = &cap[1];
-
-      // 查找对应的元素开始标签
-      if let Some(elem_start) = result[..match_start].rfind('<') {
-        if let Some(elem_end) = result[match_start..].find('>') {
-          let elem_end = match_start + elem_end;
-          let element = &result[elem_start..elem_end + 1];
-
-          // 检查 style_map 中是否有对应的样式
-          if let Some(styles) = style_map.get(style_name) {
-            let new_element = if element.contains("style=") {
-              // 合并样式
-              let style_re = Regex::new(r#"style="([^"]*)""#).unwrap();
-              style_re
-                .replace(element, |caps: &regex::Captures| {
-                  let existing_style = &caps[1];
-                  let mut new_styles = existing_style.to_string();
-                  for style_part in styles.split(';') {
-                    let style_part = style_part.trim();
-                    if !style_part.is_empty() {
-                      let prop = style_part.split(':').next().unwrap_or("").trim();
-                      if !existing_style.contains(prop) {
-                        new_styles.push_str(&format!("; {}", style_part));
-                      }
-                    }
-                  }
-                  format!(r#"style="{}""#, new_styles)
-                })
-                .to_string()
-            } else {
-              // 添加 style 属性
-              element.replace('>', &format!(r#" style="{}">"#, styles))
-            };
-
-            // 移除 data-custom-style 属性
-            let final_element =
-              new_element.replace(&format!(r#" data-custom-style="{}""#, style_name), "");
-            replacements.push((elem_start, elem_end + 1, final_element));
-          }
-        }
+      if element != original {
+        styled_elements += 1;
       }
-    }
-
-    // 从后往前替换
-    for (start, end, new_elem) in replacements.iter().rev() {
-      result.replace_range(*start..*end, new_elem);
-    }
-
-    // Bug1 修复：移除 body 的 padding-top、margin-top，消除 DOCX 顶部空白行
-    // Pandoc 默认模板会给 body 添加 padding-top: 50px、padding: 12px、margin-top: 0.5em 等，导致约 70px 顶部空白
-    let body_tag_re = Regex::new(r#"<body([^>]*)>"#).unwrap();
-    result = body_tag_re
-      .replace_all(&result, |caps: &regex::Captures| {
-        let rest = caps.get(1).map(|m| m.as_str()).unwrap_or("");
-        let new_rest = if rest.contains("style=") {
-          let style_re = Regex::new(r#"style="([^"]*)""#).unwrap();
-          style_re
-            .replace(rest, |scaps: &regex::Captures| {
-              let s = scaps.get(1).map(|m| m.as_str()).unwrap_or("");
-              let override_s = if s.trim().is_empty() {
-                "padding-top: 0; margin-top: 0".to_string()
-              } else {
-                format!(
-                  "{}; padding-top: 0; margin-top: 0",
-                  s.trim_end_matches(';').trim()
-                )
-              };
-              format!(r#"style="{}""#, override_s)
-            })
-            .to_string()
-        } else {
-          format!(r#"{} style="padding-top: 0; margin-top: 0""#, rest)
-        };
-        format!("<body{}>", new_rest)
-      })
-      .into_owned();
+      element
+    });
 
-    eprintln!("✅ CSS 类转内联样式处理完成");
-    result
+    tracing::info!("CSS 类转内联样式处理完成，更新 {} 个元素", styled_elements);
+    result.into_owned()
   }
 
   /// 从 DOCX 文件中提取格式信息（段落级别和运行级别）
   /// 从 XML 片段中提取属性值
   fn extract_attribute_value(xml_fragment: &str, attr_name: &str) -> Option<String> {
-    use regex::Regex;
     let pattern = format!(r#"{}=\"([^\"]+)\""#, attr_name);
     let re = match Regex::new(&pattern) {
       Ok(r) => r,
@@ -1598,7 +1745,7 @@ impl PandocService {
       styles.insert(style_id, style_def);
     }
 
-    eprintln!("📝 从 styles.xml 提取到 {} 个样式定义", styles.len());
+    tracing::debug!("从 styles.xml 提取到 {} 个样式定义", styles.len());
     styles
   }
 
@@ -1613,7 +1760,7 @@ impl PandocService {
     let file = match std::fs::File::open(doc_path) {
       Ok(f) => f,
       Err(e) => {
-        eprintln!("⚠️ 无法打开 DOCX 文件提取格式信息: {}", e);
+        tracing::warn!("无法打开 DOCX 文件提取格式信息: {}", e);
         return paragraphs_formatting;
       }
     };
@@ -1621,7 +1768,7 @@ impl PandocService {
     let mut archive = match ZipArchive::new(file) {
       Ok(a) => a,
       Err(e) => {
-        eprintln!("⚠️ 无法读取 DOCX ZIP 文件: {}", e);
+        tracing::warn!("无法读取 DOCX ZIP 文件: {}", e);
         return paragraphs_formatting;
       }
     };
@@ -1631,14 +1778,14 @@ impl PandocService {
       let mut doc_xml = match archive.by_name("word/document.xml") {
         Ok(f) => f,
         Err(e) => {
-          eprintln!("⚠️ 无法读取 document.xml: {}", e);
+          tracing::warn!("无法读取 document.xml: {}", e);
           return paragraphs_formatting;
         }
       };
 
       let mut content = String::new();
       if doc_xml.read_to_string(&mut content).is_err() {
-        eprintln!("⚠️ 无法读取 document.xml 内容");
+        tracing::warn!("无法读取 document.xml 内容");
         return paragraphs_formatting;
       }
       content
@@ -1651,7 +1798,7 @@ impl PandocService {
         let _ = styles_file.read_to_string(&mut content);
         content
       } else {
-        eprintln!("⚠️ 无法读取 styles.xml，将跳过样式定义查找");
+        tracing::warn!("无法读取 styles.xml，将跳过样式定义查找");
         String::new()
       }
     };
@@ -1704,12 +1851,12 @@ impl PandocService {
                   // 自动行距：line/240 = 倍数
                   let multiple = line_int as f32 / 240.0;
                   para_formatting.line_height = Some(format!("{:.1}", multiple));
-                  eprintln!("📝 提取到行距: {} 倍", multiple);
+                  tracing::debug!("提取到行距: {} 倍", multiple);
                 } else {
                   // 固定行距：line/20 = pt
                   let pt = line_int as f32 / 20.0;
                   para_formatting.line_height = Some(format!("{}pt", pt));
-                  eprintln!("📝 提取到行距: {}pt", pt);
+                  tracing::debug!("提取到行距: {}pt", pt);
                 }
               }
             }
@@ -1725,7 +1872,7 @@ impl PandocService {
                 let pt = first_line_int as f32 / 20.0;
                 let em = pt / 12.0; // 假设基础字号 12pt
                 para_formatting.text_indent = Some(format!("{:.2}em", em));
-                eprintln!("📝 提取到首行缩进: {}em", em);
+                tracing::debug!("提取到首行缩进: {}em", em);
               }
             }
           }
@@ -1741,7 +1888,7 @@ impl PandocService {
                     fill
                   };
                   para_formatting.background_color = Some(color.clone());
-                  eprintln!("📝 提取到段落背景色: {}", color);
+                  tracing::debug!("提取到段落背景色: {}", color);
                 }
               }
             }
@@ -1757,7 +1904,7 @@ impl PandocService {
                 if let Some(ascii) = Self::extract_attribute_value(&r_pr[fonts_start..], "w:ascii")
                 {
                   para_formatting.paragraph_font_family = Some(ascii.clone());
-                  eprintln!("📝 提取到段落字体: {}", ascii);
+                  tracing::debug!("提取到段落字体: {}", ascii);
                 }
               }
 
@@ -1767,7 +1914,7 @@ impl PandocService {
                   if let Ok(sz_int) = sz_val.parse::<u32>() {
                     let pt = sz_int as f32 / 2.0;
                     para_formatting.paragraph_font_size = Some(format!("{}pt", pt));
-                    eprintln!("📝 提取到段落字号: {}pt", pt);
+                    tracing::debug!("提取到段落字号: {}pt", pt);
                   }
                 }
               }
@@ -1890,7 +2037,7 @@ impl PandocService {
                   color_val.to_string()
                 };
               run_formatting.color = Some(color.clone());
-              eprintln!("🎨 提取到运行颜色: 颜色={}", color);
+              tracing::debug!("提取到运行颜色: 颜色={}", color);
             }
           }
         }
@@ -1899,9 +2046,10 @@ impl PandocService {
         if r_pr.is_none() && run.contains("<w:t") && run_formatting.color.is_none() {
           // 安全截取：使用字符迭代器避免 UTF-8 字符边界问题
           let preview: String = run.chars().take(200).collect();
-          eprintln!(
-            "⚠️ 运行 {}: 未找到 <w:rPr>，运行预览: {}",
-            run_position, preview
+          tracing::warn!(
+            "运行 {}: 未找到 <w:rPr>，运行预览: {}",
+            run_position,
+            preview
           );
         }
 
@@ -1910,8 +2058,8 @@ impl PandocService {
         let search_text = if let Some(r_pr) = r_pr {
           // 安全截取：使用字符迭代器避免 UTF-8 字符边界问题
           let preview: String = r_pr.chars().take(150).collect();
-          eprintln!(
-            "✅ 运行 {}: 找到 r_pr，长度={}, 预览={:?}",
+          tracing::info!(
+            "运行 {}: 找到 r_pr，长度={}, 预览={:?}",
             run_position,
             r_pr.len(),
             preview
@@ -1919,8 +2067,8 @@ impl PandocService {
           r_pr
         } else {
           // 如果没有找到 r_pr，在整个运行片段中查找格式
-          eprintln!(
-            "⚠️ 运行 {}: 未找到 <w:rPr>，在整个运行片段中查找格式",
+          tracing::warn!(
+            "运行 {}: 未找到 <w:rPr>，在整个运行片段中查找格式",
             run_position
           );
           run
@@ -1933,7 +2081,7 @@ impl PandocService {
             if let Some(ascii_end) = search_text[ascii_start..].find('"') {
               let font_name = &search_text[ascii_start..ascii_start + ascii_end];
               run_formatting.font_family = Some(font_name.to_string());
-              eprintln!("📝 提取到字体: {}", font_name);
+              tracing::debug!("提取到字体: {}", font_name);
             }
           }
         }
@@ -1946,7 +2094,7 @@ impl PandocService {
               if let Ok(sz_val) = search_text[val_start..val_start + val_end].parse::<u32>() {
                 let pt = sz_val as f32 / 2.0;
                 run_formatting.font_size = Some(format!("{}pt", pt));
-                eprintln!("📝 提取到字号: {}pt", pt);
+                tracing::debug!("提取到字号: {}pt", pt);
               }
             }
           }
@@ -1955,19 +2103,19 @@ impl PandocService {
         // 提取粗体（<w:b/> 或 <w:bCs/>）
         if search_text.contains("<w:b") && !search_text.contains("w:val=\"false\"") {
           run_formatting.bold = true;
-          eprintln!("📝 提取到粗体");
+          tracing::debug!("提取到粗体");
         }
 
         // 提取斜体（<w:i/> 或 <w:iCs/>）
         if search_text.contains("<w:i") && !search_text.contains("w:val=\"false\"") {
           run_formatting.italic = true;
-          eprintln!("📝 提取到斜体");
+          tracing::debug!("提取到斜体");
         }
 
         // 提取下划线（<w:u w:val="..."/>）
         if search_text.contains("<w:u") && !search_text.contains("w:val=\"none\"") {
           run_formatting.underline = true;
-          eprintln!("📝 提取到下划线");
+          tracing::debug!("提取到下划线");
         }
 
         // 提取运行级别背景色/高亮
@@ -1998,7 +2146,7 @@ impl PandocService {
               };
               if let Some(color) = highlight_color {
                 run_formatting.background_color = Some(color.to_string());
-                eprintln!("📝 提取到运行背景色（高亮）: {}", color);
+                tracing::debug!("提取到运行背景色（高亮）: {}", color);
               }
             }
           }
@@ -2015,7 +2163,7 @@ impl PandocService {
                   fill
                 };
                 run_formatting.background_color = Some(color.clone());
-                eprintln!("📝 提取到运行背景色（填充）: {}", color);
+                tracing::debug!("提取到运行背景色（填充）: {}", color);
               }
             }
           }
@@ -2043,21 +2191,21 @@ impl PandocService {
         if run_formatting.font_family.is_none() {
           if let Some(para_font) = &para_formatting.paragraph_font_family {
             run_formatting.font_family = Some(para_font.clone());
-            eprintln!("📝 运行继承段落字体: {}", para_font);
+            tracing::debug!("运行继承段落字体: {}", para_font);
           }
         }
 
         if run_formatting.font_size.is_none() {
           if let Some(para_size) = &para_formatting.paragraph_font_size {
             run_formatting.font_size = Some(para_size.clone());
-            eprintln!("📝 运行继承段落字号: {}", para_size);
+            tracing::debug!("运行继承段落字号: {}", para_size);
           }
         }
 
         // 如果运行有文本，添加到段落
         if !run_formatting.text.is_empty() {
           if run_formatting.has_formatting() {
-            eprintln!("📝 运行有格式: 文本=\"{}\", 颜色={:?}, 字体={:?}, 字号={:?}, 粗体={}, 斜体={}, 下划线={}, 背景色={:?}", 
+            tracing::debug!("运行有格式: 文本=\"{}\", 颜色={:?}, 字体={:?}, 字号={:?}, 粗体={}, 斜体={}, 下划线={}, 背景色={:?}", 
                             run_formatting.text, 
                             run_formatting.color, 
                             run_formatting.font_family, 
@@ -2078,8 +2226,8 @@ impl PandocService {
       }
     }
 
-    eprintln!(
-      "📝 从 DOCX 提取到 {} 个段落格式信息",
+    tracing::debug!(
+      "从 DOCX 提取到 {} 个段落格式信息",
       paragraphs_formatting.len()
     );
     paragraphs_formatting
@@ -2089,7 +2237,6 @@ impl PandocService {
   /// 包括段落级别的对齐和运行级别的格式（颜色、字体、字号等）
   /// 注意：编辑模式不再使用此函数，只保留换行和结构
   fn apply_docx_formatting(html: &str, paragraphs_formatting: &[ParagraphFormatting]) -> String {
-    use regex::Regex;
     let mut result = html.to_string();
 
     // 遍历每个段落格式信息
@@ -2098,8 +2245,8 @@ impl PandocService {
       let para_text = para_formatting.get_full_text();
       let normalized_para_text = Self::normalize_text(&para_text);
 
-      eprintln!(
-        "🔍 [段落 {}/{}] 查找元素: 文本=\"{}\", 运行数={}, 有格式运行数={}",
+      tracing::debug!(
+        "[段落 {}/{}] 查找元素: 文本=\"{}\", 运行数={}, 有格式运行数={}",
         para_idx + 1,
         paragraphs_formatting.len(),
         normalized_para_text,
@@ -2115,10 +2262,7 @@ impl PandocService {
       let mut element_found = false;
       if para_formatting.paragraph_id.is_some() {
         // 尝试通过 ID 匹配（Pandoc 为标题生成 ID）
-        let id_pattern = format!(r#"<(h[1-6]|p)[^>]*id="[^"]*"[^>]*>"#);
-        let id_re = Regex::new(&id_pattern).unwrap();
-
-        for cap in id_re.captures_iter(&result) {
+        for cap in TEXT_BLOCK_WITH_ID_RE.captures_iter(&result) {
           let full_match = cap.get(0).unwrap();
           let match_start = full_match.start();
           let match_end = full_match.end();
@@ -2153,15 +2297,11 @@ impl PandocService {
       // 策略2：通过文本内容匹配（如果 ID 匹配失败）
       // 使用更宽松的匹配策略：支持部分匹配和模糊匹配
       if !element_found {
-        // 尝试精确匹配
-        let escaped_text = regex::escape(&normalized_para_text);
-        let pattern = format!(
-          r#"<(h[1-6]|p)([^>]*)>([^<]*{}[^<]*)</(h[1-6]|p)>"#,
-          escaped_text
-        );
-        let re = Regex::new(&pattern).unwrap();
-
-        for cap in re.captures_iter(&result) {
+        // 尝试精确匹配：只含文本且包含段落文字的元素
+        for cap in PLAIN_TEXT_BLOCK_RE
+          .captures_iter(&result)
+          .filter(|cap| cap[3].contains(normalized_para_text.as_str()))
+        {
           let full_match = cap.get(0).unwrap();
           let tag_start = full_match.start();
           let match_end = full_match.end();
@@ -2186,7 +2326,7 @@ impl PandocService {
 
         // 策略3：如果精确匹配失败，尝试模糊匹配（忽略空格和HTML实体差异）
         if !element_found {
-          eprintln!("⚠️ 精确匹配失败，尝试模糊匹配");
+          tracing::warn!("精确匹配失败，尝试模糊匹配");
 
           // 查找所有可能的段落元素
           let para_elements = Self::find_paragraph_elements(&result);
@@ -2214,7 +2354,7 @@ impl PandocService {
 
           // 如果找到最佳匹配，应用格式
           if let Some((tag_start, content_start, content_end, similarity)) = best_match {
-            eprintln!("✅ 模糊匹配成功，相似度: {:.2}", similarity);
+            tracing::info!("模糊匹配成功，相似度: {:.2}", similarity);
 
             result = Self::apply_formatting_to_element(
               &result,
@@ -2225,15 +2365,15 @@ impl PandocService {
             );
             element_found = true;
           } else {
-            eprintln!("⚠️ 模糊匹配也失败，未找到相似段落");
+            tracing::warn!("模糊匹配也失败，未找到相似段落");
           }
         }
       }
     }
 
     if !paragraphs_formatting.is_empty() {
-      eprintln!(
-        "✅ 已应用 DOCX 格式信息到 HTML（{} 个段落）",
+      tracing::info!(
+        "已应用 DOCX 格式信息到 HTML（{} 个段落）",
         paragraphs_formatting.len()
       );
     }
@@ -2243,10 +2383,8 @@ impl PandocService {
 
   /// 规范化文本（去除 HTML 标签，统一空白字符）
   fn normalize_text(text: &str) -> String {
-    use regex::Regex;
     // 去除 HTML 标签
-    let re_tags = Regex::new(r"<[^>]+>").unwrap();
-    let text = re_tags.replace_all(text, "");
+    let text = HTML_TAG_RE.replace_all(text, "");
 
     // 转换 HTML 实体
     let text = text
@@ -2260,8 +2398,7 @@ impl PandocService {
       .replace("&#xa0;", " "); // 非断行空格（十六进制）
 
     // 规范化空白字符（多个空格合并为一个，去除首尾空白）
-    let re_whitespace = Regex::new(r"\s+").unwrap();
-    re_whitespace.replace_all(&text, " ").trim().to_string()
+    WHITESPACE_RE.replace_all(&text, " ").trim().to_string()
   }
 
   /// 应用格式到 HTML 元素（段落对齐 + 运行级别格式）
@@ -2272,7 +2409,6 @@ impl PandocService {
     content_end: usize,
     para_formatting: &ParagraphFormatting,
   ) -> String {
-    use regex::Regex;
     let mut result = html.to_string();
 
     // 1. 应用段落级别的格式（对齐、行距、首行缩进、背景色）
@@ -2294,10 +2430,9 @@ impl PandocService {
     }
 
     if !para_styles.is_empty() {
-      let style_re = Regex::new(r#"style="([^"]*)""#).unwrap();
-      let new_tag = if start_tag.contains("style=") {
+      let new_tag = if STYLE_ATTR_RE.is_match(start_tag) {
         // 合并到现有样式
-        style_re
+        STYLE_ATTR_RE
           .replace(start_tag, |caps: &regex::Captures| {
             let existing_style = &caps[1];
             let merged_style = if existing_style.is_empty() {
@@ -2305,7 +2440,7 @@ impl PandocService {
             } else {
               format!("{}; {}", existing_style, para_styles.join("; "))
             };
-            format!(r#"style="{}""#, merged_style)
+            format!(r#" style="{}""#, merged_style)
           })
           .to_string()
       } else {
@@ -2322,10 +2457,10 @@ impl PandocService {
       // 2. 应用运行级别的格式（在内容中插入 <span> 标签）
       let content = &result[new_content_start..new_content_end];
       let preview: String = content.chars().take(200).collect();
-      eprintln!("📝 原始内容: {}", preview);
+      tracing::debug!("原始内容: {}", preview);
       let new_content = Self::apply_run_formatting_to_content(content, &para_formatting.runs);
       let new_preview: String = new_content.chars().take(200).collect();
-      eprintln!("📝 新内容: {}", new_preview);
+      tracing::debug!("新内容: {}", new_preview);
 
       // 替换内容
       result.replace_range(new_content_start..new_content_end, &new_content);
@@ -2333,10 +2468,10 @@ impl PandocService {
       // 没有段落级别格式，只应用运行格式
       let content = &result[content_start..content_end];
       let preview: String = content.chars().take(200).collect();
-      eprintln!("📝 原始内容（无对齐）: {}", preview);
+      tracing::debug!("原始内容（无对齐）: {}", preview);
       let new_content = Self::apply_run_formatting_to_content(content, &para_formatting.runs);
       let new_preview: String = new_content.chars().take(200).collect();
-      eprintln!("📝 新内容（无对齐）: {}", new_preview);
+      tracing::debug!("新内容（无对齐）: {}", new_preview);
       result.replace_range(content_start..content_end, &new_content);
     }
 
@@ -2347,8 +2482,8 @@ impl PandocService {
   /// 优化策略：尝试在保留原有 HTML 标签的基础上应用格式
   /// 安全保护：添加性能限制，防止内存问题和无限循环
   fn apply_run_formatting_to_content(content: &str, runs: &[RunFormatting]) -> String {
-    eprintln!(
-      "🔍 应用运行格式: 内容长度={}, 运行数={}",
+    tracing::debug!(
+      "应用运行格式: 内容长度={}, 运行数={}",
       content.len(),
       runs.len()
     );
@@ -2358,34 +2493,34 @@ impl PandocService {
     const MAX_RUNS: usize = 500;
 
     if content.len() > MAX_CONTENT_LENGTH {
-      eprintln!("⚠️ 内容过长 ({} 字节)，直接使用顺序拼接策略", content.len());
+      tracing::warn!("内容过长 ({} 字节)，直接使用顺序拼接策略", content.len());
       return Self::apply_run_formatting_with_concatenation(runs);
     }
 
     if runs.len() > MAX_RUNS {
-      eprintln!("⚠️ 运行数过多 ({} 个)，直接使用顺序拼接策略", runs.len());
+      tracing::warn!("运行数过多 ({} 个)，直接使用顺序拼接策略", runs.len());
       return Self::apply_run_formatting_with_concatenation(runs);
     }
 
     // 安全地截取预览：使用字符迭代器，避免在 UTF-8 字符中间截断
     let preview: String = content.chars().take(150).collect();
-    eprintln!("🔍 HTML 内容预览: {}", preview);
+    tracing::debug!("HTML 内容预览: {}", preview);
 
     // 如果只有一个运行且没有格式，直接返回原内容
     if runs.len() == 1 && !runs[0].has_formatting() {
-      eprintln!("⚠️ 只有一个运行且无格式，跳过");
+      tracing::warn!("只有一个运行且无格式，跳过");
       return content.to_string();
     }
 
     // 如果所有运行都没有格式，直接返回原内容
     if runs.iter().all(|r| !r.has_formatting()) {
-      eprintln!("⚠️ 所有运行都无格式，跳过");
+      tracing::warn!("所有运行都无格式，跳过");
       return content.to_string();
     }
 
     // 统计有格式的运行数
     let formatted_runs_count = runs.iter().filter(|r| r.has_formatting()).count();
-    eprintln!("✅ 找到 {} 个有格式的运行，开始应用", formatted_runs_count);
+    tracing::info!("找到 {} 个有格式的运行，开始应用", formatted_runs_count);
 
     // 策略1：检查是否包含格式标签（<strong>, <em>, <u>）
     // 根本修复：当Pandoc已经生成了格式标签时，直接保留原始HTML，不应用额外格式
@@ -2400,33 +2535,33 @@ impl PandocService {
         || content.contains("</u>");
 
       if has_format_tags {
-        eprintln!("📝 检测到格式标签（<strong>/<em>/<u>），保留原始HTML确保内容完整性");
-        eprintln!("⚠️ 注意：颜色等样式信息可能不会应用，但内容不会丢失");
+        tracing::debug!("检测到格式标签（<strong>/<em>/<u>），保留原始HTML确保内容完整性");
+        tracing::warn!("注意：颜色等样式信息可能不会应用，但内容不会丢失");
         // 直接返回原始内容，不应用额外格式
         // 这样可以确保内容不会丢失，格式标签也不会被破坏
         return content.to_string();
       } else if content.contains("<span") || content.contains("</span>") {
-        eprintln!("⚠️ 检测到 <span> 标签，跳过智能匹配，直接使用顺序拼接策略");
+        tracing::warn!("检测到 <span> 标签，跳过智能匹配，直接使用顺序拼接策略");
       } else {
         // 没有格式标签，可以尝试智能匹配
-        eprintln!("📝 检测到 HTML 标签（无格式标签），尝试智能匹配策略");
+        tracing::debug!("检测到 HTML 标签（无格式标签），尝试智能匹配策略");
         if let Some(result) = Self::apply_formatting_preserving_html_tags(content, runs) {
-          eprintln!("✅ 智能匹配成功，保留 HTML 标签");
+          tracing::info!("智能匹配成功，保留 HTML 标签");
           let preview: String = result.chars().take(200).collect();
-          eprintln!("🔍 生成的 HTML 预览: {}", preview);
+          tracing::debug!("生成的 HTML 预览: {}", preview);
           return result;
         }
-        eprintln!("⚠️ 智能匹配失败，回退到顺序拼接策略");
+        tracing::warn!("智能匹配失败，回退到顺序拼接策略");
       }
     }
 
     // 策略2：使用顺序拼接策略（回退方案）
     // 原因：HTML 中的文本可能与 DOCX 中的文本有差异（HTML 实体、空格等），
     // 但运行顺序是确定的，按顺序拼接可以确保格式应用正确
-    eprintln!("📝 使用顺序拼接策略应用运行格式");
+    tracing::debug!("使用顺序拼接策略应用运行格式");
     let result = Self::apply_run_formatting_with_concatenation(runs);
     let preview: String = result.chars().take(200).collect();
-    eprintln!("🔍 生成的 HTML 预览: {}", preview);
+    tracing::debug!("生成的 HTML 预览: {}", preview);
     result
   }
 
@@ -2438,19 +2573,17 @@ impl PandocService {
     content: &str,
     runs: &[RunFormatting],
   ) -> Option<String> {
-    use regex::Regex;
-
     // 安全限制：如果内容或运行数过大，直接返回 None，使用回退策略
     const MAX_CONTENT_LENGTH: usize = 100_000; // 100KB
     const MAX_RUNS: usize = 1000;
 
     if content.len() > MAX_CONTENT_LENGTH {
-      eprintln!("⚠️ 内容过长 ({} 字节)，跳过智能匹配", content.len());
+      tracing::warn!("内容过长 ({} 字节)，跳过智能匹配", content.len());
       return None;
     }
 
     if runs.len() > MAX_RUNS {
-      eprintln!("⚠️ 运行数过多 ({} 个)，跳过智能匹配", runs.len());
+      tracing::warn!("运行数过多 ({} 个)，跳过智能匹配", runs.len());
       return None;
     }
 
@@ -2467,9 +2600,10 @@ impl PandocService {
 
     // 如果文本不匹配，返回 None
     if normalized_text != normalized_runs_text {
-      eprintln!(
-        "⚠️ 文本不匹配，无法使用智能匹配: 原始={}, 运行={}",
-        normalized_text, normalized_runs_text
+      tracing::warn!(
+        "文本不匹配，无法使用智能匹配: 原始={}, 运行={}",
+        normalized_text,
+        normalized_runs_text
       );
       return None;
     }
@@ -2539,10 +2673,7 @@ impl PandocService {
 
     // 如果达到字符限制，记录警告
     if char_count >= max_chars {
-      eprintln!(
-        "⚠️ 达到字符处理限制 ({} 个字符)，可能影响格式应用",
-        max_chars
-      );
+      tracing::warn!("达到字符处理限制 ({} 个字符)，可能影响格式应用", max_chars);
     }
 
     // 按顺序处理每个运行，在 HTML 中查找对应的文本位置
@@ -2553,7 +2684,7 @@ impl PandocService {
     for run in runs.iter() {
       // 性能保护：限制处理的运行数
       if processed_count >= MAX_PROCESSED_RUNS {
-        eprintln!("⚠️ 达到运行处理限制 ({} 个)，停止处理", MAX_PROCESSED_RUNS);
+        tracing::warn!("达到运行处理限制 ({} 个)，停止处理", MAX_PROCESSED_RUNS);
         break;
       }
 
@@ -2607,7 +2738,7 @@ impl PandocService {
       }
 
       if !found {
-        eprintln!("⚠️ 无法在 HTML 中找到运行文本: \"{}\"", run.text);
+        tracing::warn!("无法在 HTML 中找到运行文本: \"{}\"", run.text);
         return None;
       }
 
@@ -2708,7 +2839,7 @@ impl PandocService {
           // 范围计算错误：包含了 <span> 标签
           // 根本修复：直接使用 html_start 和 html_end（它们指向纯文本位置）
           // 不向前查找，直接使用纯文本位置
-          eprintln!("⚠️ 检测到范围计算错误（包含 <span> 标签），使用纯文本位置");
+          tracing::warn!("检测到范围计算错误（包含 <span> 标签），使用纯文本位置");
           actual_start = html_start;
           actual_end = html_end;
 
@@ -2722,7 +2853,7 @@ impl PandocService {
             || text_to_wrap.contains("style=")
             || text_to_wrap.contains("color:")
           {
-            eprintln!("⚠️ html_start/html_end 仍然包含 HTML 代码，直接使用 run.text");
+            tracing::warn!("html_start/html_end 仍然包含 HTML 代码，直接使用 run.text");
             // 直接使用 run.text，不尝试从 HTML 中提取
             let plain_text = run.text.clone();
             let escaped_text = Self::escape_html(&plain_text);
@@ -2752,7 +2883,7 @@ impl PandocService {
                   actual_start = safe_start;
                   actual_end = safe_end;
                 } else {
-                  eprintln!("⚠️ 无法找到安全的字符边界，跳过此替换");
+                  tracing::warn!("无法找到安全的字符边界，跳过此替换");
                   continue;
                 }
               }
@@ -2800,19 +2931,19 @@ impl PandocService {
         {
           let preview_len = text_to_wrap.len().min(100);
           let preview: String = text_to_wrap.chars().take(preview_len).collect();
-          eprintln!(
-            "🔍 检测到 HTML 标签（包含 <span> 或其他标签），使用纯文本: {}",
+          tracing::debug!(
+            "检测到 HTML 标签（包含 <span> 或其他标签），使用纯文本: {}",
             preview
           );
           // 安全截取：使用字符迭代器避免 UTF-8 字符边界问题
           let preview: String = plain_text.chars().take(50).collect();
-          eprintln!("🔍 提取的纯文本: \"{}\"", preview);
+          tracing::debug!("提取的纯文本: \"{}\"", preview);
 
           // 包含 <span> 或其他非格式标签，直接使用纯文本并转义
           let escaped_text = Self::escape_html(&plain_text);
           // 安全截取：使用字符迭代器避免 UTF-8 字符边界问题
           let preview: String = escaped_text.chars().take(50).collect();
-          eprintln!("🔍 转义后的文本: \"{}\"", preview);
+          tracing::debug!("转义后的文本: \"{}\"", preview);
           let mut inner_content = escaped_text;
 
           // 如果运行有斜体，添加 <em>
@@ -2835,10 +2966,7 @@ impl PandocService {
           {
             // 验证字节索引是否在字符边界上
             if !result.is_char_boundary(actual_start) || !result.is_char_boundary(actual_end) {
-              eprintln!(
-                "⚠️ 无效的字符边界: start={}, end={}",
-                actual_start, actual_end
-              );
+              tracing::warn!("无效的字符边界: start={}, end={}", actual_start, actual_end);
               // 尝试找到最近的字符边界
               let safe_start = Self::find_char_boundary(&result, actual_start);
               let safe_end = Self::find_char_boundary(&result, actual_end);
@@ -2846,7 +2974,7 @@ impl PandocService {
                 actual_start = safe_start;
                 actual_end = safe_end;
               } else {
-                eprintln!("⚠️ 无法找到安全的字符边界，跳过此替换");
+                tracing::warn!("无法找到安全的字符边界，跳过此替换");
                 continue;
               }
             }
@@ -2856,7 +2984,7 @@ impl PandocService {
             const MAX_RESULT_LENGTH: usize = 200_000; // 200KB
 
             if new_length > MAX_RESULT_LENGTH {
-              eprintln!("⚠️ 替换后字符串过长 ({} 字节)，跳过此替换", new_length);
+              tracing::warn!("替换后字符串过长 ({} 字节)，跳过此替换", new_length);
               continue;
             }
 
@@ -2867,8 +2995,8 @@ impl PandocService {
               *last_range = (actual_start, actual_end);
             }
           } else {
-            eprintln!(
-              "⚠️ 无效的范围: start={}, end={}, result_len={}",
+            tracing::warn!(
+              "无效的范围: start={}, end={}, result_len={}",
               actual_start,
               actual_end,
               result.len()
@@ -2882,9 +3010,9 @@ impl PandocService {
         let wrapped = {
           let trimmed = text_to_wrap.trim();
           // 检查是否是完整的格式标签包裹：<em>text</em>, <strong>text</strong>, <u>text</u>
-          let em_pattern = Regex::new(r#"^<em([^>]*)>(.*)</em>$"#).ok();
-          let strong_pattern = Regex::new(r#"^<strong([^>]*)>(.*)</strong>$"#).ok();
-          let u_pattern = Regex::new(r#"^<u([^>]*)>(.*)</u>$"#).ok();
+          let em_pattern = Some(&*WRAPPED_EM_RE);
+          let strong_pattern = Some(&*WRAPPED_STRONG_RE);
+          let u_pattern = Some(&*WRAPPED_U_RE);
 
           // 检查是否是 <em> 标签
           if let Some(re) = em_pattern {
@@ -2966,10 +3094,7 @@ impl PandocService {
         if actual_start < result.len() && actual_end <= result.len() && actual_start < actual_end {
           // 验证字节索引是否在字符边界上
           if !result.is_char_boundary(actual_start) || !result.is_char_boundary(actual_end) {
-            eprintln!(
-              "⚠️ 无效的字符边界: start={}, end={}",
-              actual_start, actual_end
-            );
+            tracing::warn!("无效的字符边界: start={}, end={}", actual_start, actual_end);
             // 尝试找到最近的字符边界
             let safe_start = Self::find_char_boundary(&result, actual_start);
             let safe_end = Self::find_char_boundary(&result, actual_end);
@@ -2977,7 +3102,7 @@ impl PandocService {
               actual_start = safe_start;
               actual_end = safe_end;
             } else {
-              eprintln!("⚠️ 无法找到安全的字符边界，跳过此替换");
+              tracing::warn!("无法找到安全的字符边界，跳过此替换");
               continue;
             }
           }
@@ -2987,7 +3112,7 @@ impl PandocService {
           const MAX_RESULT_LENGTH: usize = 200_000; // 200KB
 
           if new_length > MAX_RESULT_LENGTH {
-            eprintln!("⚠️ 替换后字符串过长 ({} 字节)，跳过此替换", new_length);
+            tracing::warn!("替换后字符串过长 ({} 字节)，跳过此替换", new_length);
             continue;
           }
 
@@ -2998,8 +3123,8 @@ impl PandocService {
             *last_range = (actual_start, actual_end);
           }
         } else {
-          eprintln!(
-            "⚠️ 无效的范围: start={}, end={}, result_len={}",
+          tracing::warn!(
+            "无效的范围: start={}, end={}, result_len={}",
             actual_start,
             actual_end,
             result.len()
@@ -3047,16 +3172,12 @@ impl PandocService {
   /// 从 HTML 中提取纯文本（去除所有标签）
   /// 根本修复：彻底清理所有 HTML 代码片段，包括不完整的标签和属性
   fn extract_text_from_html(html: &str) -> String {
-    use regex::Regex;
-
     // 第一步：去除所有完整的 HTML 标签：<tag> 或 <tag attr="...">
-    let re_tags = Regex::new(r"<[^>]+>").unwrap();
-    let mut text = re_tags.replace_all(html, "").to_string();
+    let mut text = HTML_TAG_RE.replace_all(html, "").to_string();
 
     // 第二步：处理不完整的 HTML 标签片段（如 `style="...">` 或 `">`）
     // 这些可能是由于范围计算错误导致的
-    let re_incomplete = Regex::new(r#"[a-zA-Z-]+="[^"]*">"#).unwrap();
-    text = re_incomplete.replace_all(&text, "").to_string();
+    text = INCOMPLETE_ATTR_RE.replace_all(&text, "").to_string();
 
     // 第三步：处理所有 HTML 实体
     text = text
@@ -3069,16 +3190,13 @@ impl PandocService {
 
     // 第四步：处理所有样式属性片段（更严格的匹配）
     // 匹配：color: #FF0000; font-weight: bold; font-style: italic 等
-    let re_style_fragment = Regex::new(r#"(color|font-weight|font-style|font-size|text-align|text-decoration|font-family|background-color|text-decoration-line|text-decoration-style|text-decoration-color|vertical-align|letter-spacing|word-spacing|line-height|text-indent|margin|padding|border|width|height|display|position|float|clear|overflow|z-index|opacity|visibility|white-space|word-wrap|word-break|text-overflow|text-transform|text-shadow|box-shadow|transform|transition|animation):\s*[^;"]*[;"]?"#).unwrap();
-    text = re_style_fragment.replace_all(&text, "").to_string();
+    text = CSS_DECLARATION_FRAGMENT_RE
+      .replace_all(&text, "")
+      .to_string();
 
     // 第五步：处理所有颜色代码片段（更严格的匹配）
     // 匹配：#FF0000, #FF0000;, #FF0000", rgb(255,0,0), rgba(255,0,0,1) 等
-    let re_color = Regex::new(
-      r#"#?[0-9A-Fa-f]{3,8}[;:"]?|rgb\([^)]*\)|rgba\([^)]*\)|hsl\([^)]*\)|hsla\([^)]*\)"#,
-    )
-    .unwrap();
-    text = re_color.replace_all(&text, "").to_string();
+    text = COLOR_FRAGMENT_RE.replace_all(&text, "").to_string();
 
     // 第六步：处理所有可能的标签片段字符
     // 去除所有可能来自 HTML 标签的字符
@@ -3095,12 +3213,10 @@ impl PandocService {
 
     // 第七步：处理可能的单词片段（如 "font-s", "spantyle", "italicorlor", "olor" 等）
     // 这些可能是由于范围计算错误，包含了 HTML 属性的一部分
-    let re_word_fragments = Regex::new(r#"\b(font|style|span|color|weight|size|align|decoration|italic|bold|underline|normal|inherit|initial|unset|transparent|auto|none|solid|dashed|dotted|double|groove|ridge|inset|outset|left|right|center|justify|start|end|baseline|top|middle|bottom|sub|super|text-top|text-bottom|block|inline|inline-block|flex|grid|table|list-item|run-in|table-row-group|table-header-group|table-footer-group|table-row|table-cell|table-column-group|table-column|table-caption|inherit|initial|unset|normal|bold|bolder|lighter|100|200|300|400|500|600|700|800|900|italic|oblique|normal|small-caps|all-small-caps|petite-caps|all-petite-caps|unicase|titling-caps)\w*\b"#).unwrap();
-    text = re_word_fragments.replace_all(&text, "").to_string();
+    text = STYLE_WORD_FRAGMENT_RE.replace_all(&text, "").to_string();
 
     // 第八步：清理多余的空格和空白字符
-    let re_whitespace = Regex::new(r"\s+").unwrap();
-    text = re_whitespace.replace_all(&text, " ").to_string();
+    text = WHITESPACE_RE.replace_all(&text, " ").to_string();
 
     // 第九步：去除首尾空白
     text.trim().to_string()
@@ -3156,14 +3272,10 @@ impl PandocService {
 
   /// 查找段落元素（支持模糊匹配）
   fn find_paragraph_elements(html: &str) -> Vec<(usize, usize, usize)> {
-    use regex::Regex;
     let mut elements = Vec::new();
 
     // 查找所有段落和标题元素的开始标签
-    let tag_pattern = r#"<(h[1-6]|p)([^>]*)>"#;
-    let tag_re = Regex::new(tag_pattern).unwrap();
-
-    for tag_match in tag_re.find_iter(html) {
+    for tag_match in TEXT_BLOCK_START_RE.find_iter(html) {
       let tag_start = tag_match.start();
       let tag_full = tag_match.as_str();
 
@@ -3250,6 +3362,7 @@ impl PandocService {
   /// 返回：
   /// - HTML 内容字符串
   /// - 错误信息
+  #[tracing::instrument(skip_all, fields(path = %docx_path.display()))]
   pub async fn convert_docx_to_html_preview(
     &self,
     docx_path: &Path,
@@ -3278,10 +3391,7 @@ impl PandocService {
     }
 
     // 3. 检查磁盘空间（需要至少 2 倍文件大小的可用空间）
-    let output_dir_metadata = std::fs::metadata(output_dir.parent().unwrap_or(output_dir)).ok();
-
-    // 注意：跨平台磁盘空间检查较复杂，这里简化处理
-    // 实际实现可以使用 sysinfo 或其他库检查可用空间
+    crate::utils::disk_space::ensure_space_for_conversion(output_dir, file_size)?;
 
     // 4. 发送开始转换事件（添加错误处理）
     if let Some(handle) = &app_handle {
@@ -3293,7 +3403,7 @@ impl PandocService {
           message: "正在预览".to_string(),
         },
       ) {
-        eprintln!("发送预览进度事件失败: {}", e);
+        tracing::warn!("发送预览进度事件失败: {}", e);
       }
     }
 
@@ -3318,13 +3428,15 @@ impl PandocService {
       .arg("") // 空 CSS，使用内联样式
       .stdout(std::process::Stdio::piped())
       .stderr(std::process::Stdio::piped());
+    let output_file = PandocOutputFile::new();
+    cmd.arg("--output").arg(output_file.path());
 
     // 必须：添加 Lua 过滤器（如果存在）
     if let Some(lua_filter) = Self::get_lua_filter_path() {
-      eprintln!("✅ [预览日志] 使用 Lua 过滤器: {:?}", lua_filter);
+      tracing::info!("[预览日志] 使用 Lua 过滤器: {:?}", lua_filter);
       cmd.arg("--lua-filter").arg(lua_filter);
     } else {
-      eprintln!("⚠️ [预览日志] 未找到 Lua 过滤器，格式保留可能不完整");
+      tracing::warn!("[预览日志] 未找到 Lua 过滤器，格式保留可能不完整");
     }
 
     // 7. 执行命令（带超时：30 秒）
@@ -3342,10 +3454,10 @@ impl PandocService {
       let error = String::from_utf8_lossy(&output.stderr);
       let stdout = String::from_utf8_lossy(&output.stdout);
 
-      eprintln!("❌ [预览日志] Pandoc 转换失败");
-      eprintln!("   - 状态码: {:?}", output.status.code());
-      eprintln!("   - STDERR: {}", error);
-      eprintln!("   - STDOUT: {}", stdout);
+      tracing::error!("[预览日志] Pandoc 转换失败");
+      tracing::debug!("   - 状态码: {:?}", output.status.code());
+      tracing::debug!("   - STDERR: {}", error);
+      tracing::debug!("   - STDOUT: {}", stdout);
 
       // 发送失败事件（添加错误处理）
       if let Some(handle) = &app_handle {
@@ -3357,29 +3469,33 @@ impl PandocService {
             message: format!("转换失败: {}", error),
           },
         ) {
-          eprintln!("发送预览失败事件失败: {}", e);
+          tracing::warn!("发送预览失败事件失败: {}", e);
         }
       }
 
       return Err(format!("Pandoc 转换失败: {}\nSTDOUT: {}", error, stdout));
     }
 
-    // 9. 读取 HTML 内容
-    let html_content =
-      String::from_utf8(output.stdout).map_err(|e| format!("读取转换结果失败: {}", e))?;
+    // 9. 读取 HTML 内容（输出过大时跳过格式增强）
+    let level = ConversionLevel::for_output(
+      output_file.len(),
+      crate::services::settings_service::conversion_memory_limit_bytes(),
+    );
+    let html_content = output_file.read()?;
+    drop(output_file);
 
     // 读取 stderr（可能包含 Lua 过滤器的日志）
     let stderr_content = String::from_utf8_lossy(&output.stderr);
     if !stderr_content.trim().is_empty() {
-      eprintln!("📋 [预览日志] Pandoc STDERR 输出（包含 Lua 过滤器日志）:");
+      tracing::debug!("[预览日志] Pandoc STDERR 输出（包含 Lua 过滤器日志）:");
       for line in stderr_content.lines() {
-        eprintln!("   {}", line);
+        tracing::debug!("   {}", line);
       }
     }
 
-    eprintln!("📄 [预览日志] Pandoc 转换完成");
-    eprintln!("   - HTML 内容长度: {} 字节", html_content.len());
-    eprintln!("   - 输出目录: {:?}", output_dir);
+    tracing::debug!("[预览日志] Pandoc 转换完成");
+    tracing::debug!("   - HTML 内容长度: {} 字节", html_content.len());
+    tracing::debug!("   - 输出目录: {:?}", output_dir);
 
     // 检查输出是否为空
     if html_content.trim().is_empty() {
@@ -3394,7 +3510,7 @@ impl PandocService {
             message: error_msg.to_string(),
           },
         ) {
-          eprintln!("发送预览失败事件失败: {}", e);
+          tracing::warn!("发送预览失败事件失败: {}", e);
         }
       }
 
@@ -3408,38 +3524,47 @@ impl PandocService {
     let p_count = html_content.matches("<p").count();
     let div_count = html_content.matches("<div").count();
 
-    eprintln!("📊 [预览日志] Pandoc 输出诊断:");
-    eprintln!("   - 包含 <body>: {}", has_body);
-    eprintln!("   - 包含 <style>: {}", has_style);
-    eprintln!("   - 图片数量: {}", img_count);
-    eprintln!("   - 段落数量: {}", p_count);
-    eprintln!("   - div 数量: {}", div_count);
-    eprintln!(
+    tracing::debug!("[预览日志] Pandoc 输出诊断:");
+    tracing::debug!("   - 包含 <body>: {}", has_body);
+    tracing::debug!("   - 包含 <style>: {}", has_style);
+    tracing::debug!("   - 图片数量: {}", img_count);
+    tracing::debug!("   - 段落数量: {}", p_count);
+    tracing::debug!("   - div 数量: {}", div_count);
+    tracing::debug!(
       "   - HTML 预览（前500字符）: {}",
       &html_content.chars().take(500).collect::<String>()
     );
 
     // 9. 格式保留机制（必须步骤，用于保留颜色、对齐、行距等格式）
     // 预览方案：Pandoc + docx+styles + Lua 过滤器 + 格式提取 + CSS 类转换 + 格式应用
-    eprintln!("🎨 [预览日志] 开始格式保留处理...");
-
-    // 9.1 提取 DOCX 格式信息（复用编辑模式的格式提取方法）
-    // 注意：如果格式提取失败，返回空 Vec，后续格式应用会跳过
-    let docx_formatting = Self::extract_docx_formatting(docx_path);
-    eprintln!("   - 格式提取完成，段落数: {}", docx_formatting.len());
-
-    // 9.2 转换 CSS 类为内联样式（复用编辑模式的 CSS 转换方法）
-    let html_with_inline_styles = Self::convert_css_classes_to_inline_styles(&html_content);
-    eprintln!("   - CSS 类转换完成");
-
-    // 9.3 应用格式信息到 HTML（复用编辑模式的格式应用方法）
-    // 注意：格式应用总是成功（返回 String），如果格式提取失败（空 Vec），则不会应用任何格式
-    let html_with_formatting =
-      Self::apply_docx_formatting(&html_with_inline_styles, &docx_formatting);
-    eprintln!("   - 格式应用完成");
+    tracing::debug!("[预览日志] 开始格式保留处理...");
+
+    let html_with_formatting: Cow<str> = if level == ConversionLevel::Full {
+      // 9.1 提取 DOCX 格式信息（复用编辑模式的格式提取方法）
+      // 注意：如果格式提取失败，返回空 Vec，后续格式应用会跳过
+      let docx_formatting = Self::extract_docx_formatting(docx_path);
+      tracing::debug!("   - 格式提取完成，段落数: {}", docx_formatting.len());
+
+      // 9.2 转换 CSS 类为内联样式（复用编辑模式的 CSS 转换方法）
+      let html_with_inline_styles = Self::convert_css_classes_to_inline_styles(&html_content);
+      tracing::debug!("   - CSS 类转换完成");
+
+      // 9.3 应用格式信息到 HTML（复用编辑模式的格式应用方法）
+      // 注意：格式应用总是成功（返回 String），如果格式提取失败（空 Vec），则不会应用任何格式
+      let html_with_formatting =
+        Self::apply_docx_formatting(&html_with_inline_styles, &docx_formatting);
+      tracing::debug!("   - 格式应用完成");
+      Cow::Owned(html_with_formatting)
+    } else {
+      tracing::warn!(
+        "[预览日志] HTML {} 字节，预计超出转换内存上限，跳过格式增强",
+        html_content.len()
+      );
+      Cow::Borrowed(&html_content)
+    };
 
     // 10. 后处理 HTML（图片路径处理、文本框处理、样式增强）
-    eprintln!("🔧 [预览日志] 开始后处理 HTML...");
+    tracing::debug!("[预览日志] 开始后处理 HTML...");
     let processed_html = self.post_process_preview_html(
       &html_with_formatting,
       output_dir,
@@ -3447,9 +3572,9 @@ impl PandocService {
       app_handle.as_ref(),
     )?;
 
-    eprintln!("✅ [预览日志] 后处理完成");
-    eprintln!("   - 处理后 HTML 长度: {} 字节", processed_html.len());
-    eprintln!(
+    tracing::info!("[预览日志] 后处理完成");
+    tracing::debug!("   - 处理后 HTML 长度: {} 字节", processed_html.len());
+    tracing::debug!(
       "   - 长度变化: {} 字节",
       processed_html.len() as i64 - html_content.len() as i64
     );
@@ -3464,7 +3589,7 @@ impl PandocService {
           message: "预览完成".to_string(),
         },
       ) {
-        eprintln!("发送预览完成事件失败: {}", e);
+        tracing::warn!("发送预览完成事件失败: {}", e);
       }
     }
 
@@ -3483,14 +3608,13 @@ impl PandocService {
     app_handle: Option<&tauri::AppHandle>,
   ) -> Result<String, String> {
     use crate::services::textbox_service::TextBoxService;
-    use regex::Regex;
 
     let mut processed = html.to_string();
 
     // 0. 移除 Pandoc 自动生成的标题（如果存在）
     // Pandoc 使用 --metadata title 时，可能会在 body 开头自动添加 <h1> 标题
     // 注意：只移除 body 开头的第一个 <h1>，避免误删文档原有的标题
-    eprintln!("📝 [后处理日志] 步骤 0: 移除自动生成的标题");
+    tracing::debug!("[后处理日志] 步骤 0: 移除自动生成的标题");
     let body_pattern =
       Regex::new(r#"<body[^>]*>([\s\S]*?)"#).map_err(|e| format!("正则表达式错误: {}", e))?;
 
@@ -3514,20 +3638,20 @@ impl PandocService {
 
           // 移除这个 <h1> 标签
           processed.replace_range(h1_start..h1_end, "");
-          eprintln!(
+          tracing::debug!(
             "   - 已移除 body 开头的自动生成标题（减少 {} 字节）",
             h1_end - h1_start
           );
         }
       } else {
-        eprintln!("   - 未发现 body 开头的自动生成标题");
+        tracing::debug!("   - 未发现 body 开头的自动生成标题");
       }
     } else {
-      eprintln!("   - 未找到 <body> 标签，跳过标题移除");
+      tracing::debug!("   - 未找到 <body> 标签，跳过标题移除");
     }
 
-    eprintln!("📝 [后处理日志] 步骤 1: 处理图片路径");
-    eprintln!("   - 原始 HTML 长度: {} 字节", processed.len());
+    tracing::debug!("[后处理日志] 步骤 1: 处理图片路径");
+    tracing::debug!("   - 原始 HTML 长度: {} 字节", processed.len());
 
     // 1. 处理图片路径（使用 ImageService）
     // Pandoc 提取的图片路径可能是相对路径，需要转换为绝对路径或 base64
@@ -3538,8 +3662,8 @@ impl PandocService {
       .parent()
       .ok_or_else(|| "无法获取文件目录".to_string())?;
 
-    eprintln!("   - 工作区根目录: {:?}", workspace_root);
-    eprintln!("   - 媒体目录: {:?}", media_dir);
+    tracing::debug!("   - 工作区根目录: {:?}", workspace_root);
+    tracing::debug!("   - 媒体目录: {:?}", media_dir);
 
     let image_service = ImageService;
     let img_pattern = Regex::new(r#"<img\s+([^>]*src=["'])([^"']+)(["'][^>]*)>"#)
@@ -3562,23 +3686,24 @@ impl PandocService {
           || src.starts_with("https://")
           || src.starts_with("data:")
         {
-          eprintln!(
+          tracing::debug!(
             "   - 图片 {}: 已处理（HTTP/HTTPS/data URL）",
             img_processed_count
           );
           src.to_string()
         } else if src.starts_with("/") {
-          eprintln!("   - 图片 {}: 已处理（绝对路径）", img_processed_count);
+          tracing::debug!("   - 图片 {}: 已处理（绝对路径）", img_processed_count);
           src.to_string() // 已经是绝对路径
         } else {
           // 相对路径，使用 ImageService 处理
           let img_path = media_dir.join(src);
-          eprintln!("   - 图片 {}: 原始路径: {:?}", img_processed_count, src);
-          eprintln!(
+          tracing::debug!("   - 图片 {}: 原始路径: {:?}", img_processed_count, src);
+          tracing::debug!(
             "   - 图片 {}: 完整路径: {:?}",
-            img_processed_count, img_path
+            img_processed_count,
+            img_path
           );
-          eprintln!(
+          tracing::debug!(
             "   - 图片 {}: 文件存在: {}",
             img_processed_count,
             img_path.exists()
@@ -3590,26 +3715,27 @@ impl PandocService {
               Ok(processed_src) => {
                 if processed_src.starts_with("data:") {
                   img_base64_count += 1;
-                  eprintln!("   - 图片 {}: 转换为 base64", img_processed_count);
+                  tracing::debug!("   - 图片 {}: 转换为 base64", img_processed_count);
                 } else {
                   img_file_count += 1;
-                  eprintln!(
+                  tracing::debug!(
                     "   - 图片 {}: 使用文件路径: {}",
-                    img_processed_count, processed_src
+                    img_processed_count,
+                    processed_src
                   );
                 }
                 processed_src
               }
               Err(e) => {
                 img_error_count += 1;
-                eprintln!("   - 图片 {}: 处理失败: {}", img_processed_count, e);
+                tracing::warn!("   - 图片 {}: 处理失败: {}", img_processed_count, e);
                 // 处理失败，保持原路径
                 src.to_string()
               }
             }
           } else {
             img_error_count += 1;
-            eprintln!("   - 图片 {}: 文件不存在，保持原路径", img_processed_count);
+            tracing::debug!("   - 图片 {}: 文件不存在，保持原路径", img_processed_count);
             // 文件不存在，保持原路径（可能在其他位置）
             src.to_string()
           }
@@ -3619,18 +3745,21 @@ impl PandocService {
       })
       .to_string();
 
-    eprintln!(
+    tracing::warn!(
       "   - 图片处理完成: 总计 {} 个，base64 {} 个，文件路径 {} 个，错误 {} 个",
-      img_processed_count, img_base64_count, img_file_count, img_error_count
+      img_processed_count,
+      img_base64_count,
+      img_file_count,
+      img_error_count
     );
 
     // 2. 提取文本框信息
-    eprintln!("📝 [后处理日志] 步骤 2: 提取文本框信息");
+    tracing::debug!("[后处理日志] 步骤 2: 提取文本框信息");
     let textboxes = match TextBoxService::extract_textboxes(docx_path) {
       Ok(tb) => {
-        eprintln!("   - 找到 {} 个文本框", tb.len());
+        tracing::debug!("   - 找到 {} 个文本框", tb.len());
         for (i, tb) in tb.iter().enumerate() {
-          eprintln!(
+          tracing::debug!(
             "   - 文本框 {}: ID={}, 位置=({:.2}, {:.2}), 大小=({:.2}x{:.2}), 内容长度={}",
             i + 1,
             tb.id,
@@ -3644,116 +3773,122 @@ impl PandocService {
         tb
       }
       Err(e) => {
-        eprintln!("   - 提取文本框失败: {}", e);
+        tracing::warn!("   - 提取文本框失败: {}", e);
         Vec::new() // 失败时继续处理，不影响其他功能
       }
     };
 
     // 3. 提取并应用分栏样式（在文本框之前，应用到 .word-page 容器）
     // 注意：分栏样式应用在 .word-page 上，文本框是绝对定位不受影响
-    eprintln!("📝 [后处理日志] 步骤 3: 提取并应用分栏样式");
+    tracing::debug!("[后处理日志] 步骤 3: 提取并应用分栏样式");
     match self.extract_column_info(docx_path) {
       Ok(Some(cols)) => {
-        eprintln!(
+        tracing::debug!(
           "   - 找到分栏信息: 列数={}, 列间距={:.2}px, 分隔线={}, 等宽={}",
-          cols.column_count, cols.column_gap, cols.separator, cols.equal_width
+          cols.column_count,
+          cols.column_gap,
+          cols.separator,
+          cols.equal_width
         );
         processed = self.apply_columns_to_html(&processed, &cols)?;
-        eprintln!("   - 分栏样式已应用");
+        tracing::debug!("   - 分栏样式已应用");
       }
       Ok(None) => {
-        eprintln!("   - 没有分栏信息（单栏）");
+        tracing::debug!("   - 没有分栏信息（单栏）");
         // 没有分栏信息，继续处理
       }
       Err(e) => {
-        eprintln!("   - 提取分栏信息失败: {}，继续处理", e);
+        tracing::warn!("   - 提取分栏信息失败: {}，继续处理", e);
         // 继续处理，不影响其他功能
       }
     }
 
     // 4. 增强样式（模拟 Word 页面效果）
-    eprintln!("📝 [后处理日志] 步骤 4: 增强 Word 页面样式");
+    tracing::debug!("[后处理日志] 步骤 4: 增强 Word 页面样式");
     processed = self.enhance_word_page_style(&processed)?;
-    eprintln!("   - Word 页面样式已添加");
+    tracing::debug!("   - Word 页面样式已添加");
 
     // 5. 添加暗色模式支持（使用应用主题系统）
-    eprintln!("📝 [后处理日志] 步骤 5: 添加暗色模式支持");
+    tracing::debug!("[后处理日志] 步骤 5: 添加暗色模式支持");
     processed = self.add_dark_mode_support(&processed, app_handle)?;
-    eprintln!("   - 暗色模式支持已添加");
+    tracing::debug!("   - 暗色模式支持已添加");
 
     // 6. 添加页面标记（用于页码和跳转）
     // 注意：Pandoc 不会生成 .word-page 元素，需要通过后处理添加
-    eprintln!("📝 [后处理日志] 步骤 6: 添加页面标记");
+    tracing::debug!("[后处理日志] 步骤 6: 添加页面标记");
     let page_count_before = processed.matches("word-page").count();
     processed = self.add_page_markers(&processed)?;
     let page_count_after = processed.matches("word-page").count();
-    eprintln!(
+    tracing::debug!(
       "   - 页面标记: 之前 {} 个，之后 {} 个",
-      page_count_before, page_count_after
+      page_count_before,
+      page_count_after
     );
 
     // 7. 从 Pandoc 生成的 HTML 中移除文本框内容（避免重复显示）
     // 使用更精确的匹配算法
     if !textboxes.is_empty() {
-      eprintln!("📝 [后处理日志] 步骤 7: 移除重复的文本框内容");
-      eprintln!("   - 需要移除 {} 个文本框的内容", textboxes.len());
+      tracing::debug!("[后处理日志] 步骤 7: 移除重复的文本框内容");
+      tracing::debug!("   - 需要移除 {} 个文本框的内容", textboxes.len());
       let html_before_remove = processed.len();
       let p_count_before = processed.matches("<p").count();
       processed = self.remove_textbox_content_from_html(&processed, &textboxes)?;
       let html_after_remove = processed.len();
       let p_count_after = processed.matches("<p").count();
-      eprintln!(
+      tracing::debug!(
         "   - 移除前: {} 字节, {} 个段落",
-        html_before_remove, p_count_before
+        html_before_remove,
+        p_count_before
       );
-      eprintln!(
+      tracing::debug!(
         "   - 移除后: {} 字节, {} 个段落",
-        html_after_remove, p_count_after
+        html_after_remove,
+        p_count_after
       );
-      eprintln!(
+      tracing::debug!(
         "   - 减少: {} 字节, {} 个段落",
         html_before_remove - html_after_remove,
         p_count_before - p_count_after
       );
     } else {
-      eprintln!("📝 [后处理日志] 步骤 7: 跳过（无文本框）");
+      tracing::debug!("[后处理日志] 步骤 7: 跳过（无文本框）");
     }
 
     // 8. 插入文本框（使用绝对定位）
     if !textboxes.is_empty() {
-      eprintln!("📝 [后处理日志] 步骤 8: 插入文本框");
+      tracing::debug!("[后处理日志] 步骤 8: 插入文本框");
       let html_before_insert = processed.len();
       processed = self.insert_textboxes(&processed, &textboxes)?;
       let html_after_insert = processed.len();
-      eprintln!("   - 插入前长度: {} 字节", html_before_insert);
-      eprintln!("   - 插入后长度: {} 字节", html_after_insert);
-      eprintln!("   - 增加: {} 字节", html_after_insert - html_before_insert);
-      eprintln!("   - 已插入 {} 个文本框", textboxes.len());
-      eprintln!(
+      tracing::debug!("   - 插入前长度: {} 字节", html_before_insert);
+      tracing::debug!("   - 插入后长度: {} 字节", html_after_insert);
+      tracing::debug!("   - 增加: {} 字节", html_after_insert - html_before_insert);
+      tracing::debug!("   - 已插入 {} 个文本框", textboxes.len());
+      tracing::debug!(
         "   - 包含 textbox-container: {}",
         processed.contains("textbox-container")
       );
     } else {
-      eprintln!("📝 [后处理日志] 步骤 8: 跳过（无文本框）");
+      tracing::debug!("[后处理日志] 步骤 8: 跳过（无文本框）");
     }
 
     // 最终诊断
-    eprintln!("📊 [后处理日志] 最终 HTML 诊断:");
-    eprintln!("   - 最终长度: {} 字节", processed.len());
-    eprintln!("   - 包含 .word-page: {}", processed.contains("word-page"));
-    eprintln!(
+    tracing::debug!("[后处理日志] 最终 HTML 诊断:");
+    tracing::debug!("   - 最终长度: {} 字节", processed.len());
+    tracing::debug!("   - 包含 .word-page: {}", processed.contains("word-page"));
+    tracing::debug!(
       "   - 包含 textbox-container: {}",
       processed.contains("textbox-container")
     );
-    eprintln!(
+    tracing::debug!(
       "   - 包含 word-page-style: {}",
       processed.contains("word-page-style")
     );
-    eprintln!(
+    tracing::debug!(
       "   - 包含 dark-mode-style: {}",
       processed.contains("dark-mode-style")
     );
-    eprintln!(
+    tracing::debug!(
       "   - 包含 column-count: {}",
       processed.contains("column-count")
     );
@@ -3792,8 +3927,6 @@ impl PandocService {
 
       // 方法3：使用更精确的正则表达式匹配
       // 匹配包含文本框文本的段落，考虑可能的格式差异
-      use regex::Regex;
-
       // 尝试匹配完整的段落结构
       for para_text in &textbox_paragraphs {
         if para_text.trim().is_empty() {
@@ -3908,10 +4041,10 @@ impl PandocService {
     if let Some(pos) = html.find("</head>") {
       let mut result = html.to_string();
       result.insert_str(pos, page_style);
-      eprintln!("   - Word 页面样式已插入到 </head> 之前");
+      tracing::debug!("   - Word 页面样式已插入到 </head> 之前");
       Ok(result)
     } else {
-      eprintln!("   - 警告: 未找到 </head>，在开头添加样式");
+      tracing::warn!("   - 警告: 未找到 </head>，在开头添加样式");
       // 如果没有 </head>，在开头添加
       Ok(format!("<html><head>{}</head>{}", page_style, html))
     }
@@ -4051,16 +4184,14 @@ impl PandocService {
   /// 策略：通过后处理将内容分割为页面，每个页面包装在 .word-page 容器中
   #[allow(dead_code)]
   fn add_page_markers(&self, html: &str) -> Result<String, String> {
-    use regex::Regex;
-
-    eprintln!("   - 开始添加页面标记");
+    tracing::debug!("   - 开始添加页面标记");
 
     // 策略1：如果 HTML 中已有 .word-page 元素，直接添加 data-page 属性
     let page_pattern = Regex::new(r#"<div\s+class=["']word-page["']"#)
       .map_err(|e| format!("正则表达式错误: {}", e))?;
 
     if page_pattern.is_match(html) {
-      eprintln!("   - 发现已有的 .word-page 元素，添加 data-page 属性");
+      tracing::debug!("   - 发现已有的 .word-page 元素，添加 data-page 属性");
       // 已有 .word-page 元素，添加 data-page 属性
       let mut page_num = 1;
       let processed = page_pattern.replace_all(html, |_caps: &regex::Captures| {
@@ -4069,11 +4200,11 @@ impl PandocService {
         marker
       });
       let page_count = page_num - 1;
-      eprintln!("   - 已标记 {} 个页面", page_count);
+      tracing::debug!("   - 已标记 {} 个页面", page_count);
       return Ok(processed.to_string());
     }
 
-    eprintln!("   - 未找到 .word-page 元素，尝试包装 body 内容");
+    tracing::debug!("   - 未找到 .word-page 元素，尝试包装 body 内容");
 
     // 策略2：Pandoc 没有生成 .word-page，需要通过后处理添加
     // 将 body 内容按页面高度（297mm）分割，每个页面包装在 .word-page 中
@@ -4149,8 +4280,6 @@ impl PandocService {
     html: &str,
     column_info: &crate::services::column_service::ColumnInfo,
   ) -> Result<String, String> {
-    use regex::Regex;
-
     // 如果只有 1 列，不需要应用分栏样式
     if column_info.column_count <= 1 {
       return Ok(html.to_string());
@@ -4172,7 +4301,7 @@ impl PandocService {
       .map_err(|e| format!("正则表达式错误: {}", e))?;
 
     let page_count = page_pattern.find_iter(html).count();
-    eprintln!("   - 找到 {} 个 .word-page 元素", page_count);
+    tracing::debug!("   - 找到 {} 个 .word-page 元素", page_count);
 
     let processed = page_pattern.replace_all(html, |caps: &regex::Captures| {
       let attrs = caps.get(1).map(|m| m.as_str()).unwrap_or("");
@@ -4207,7 +4336,7 @@ impl PandocService {
 
     // 如果没有 .word-page 元素，应用在 <body> 上（向后兼容）
     if !page_pattern.is_match(&processed) {
-      eprintln!("   - 未找到 .word-page，应用在 <body> 上");
+      tracing::debug!("   - 未找到 .word-page，应用在 <body> 上");
       let body_pattern =
         Regex::new(r#"<body([^>]*)>"#).map_err(|e| format!("正则表达式错误: {}", e))?;
 
@@ -4217,11 +4346,11 @@ impl PandocService {
           format!("<body{} style=\"{}\">", attrs, column_style)
         })
         .to_string();
-      eprintln!("   - 分栏样式已应用到 <body>");
+      tracing::debug!("   - 分栏样式已应用到 <body>");
       return Ok(result);
     }
 
-    eprintln!("   - 分栏样式已应用到 {} 个 .word-page 元素", page_count);
+    tracing::debug!("   - 分栏样式已应用到 {} 个 .word-page 元素", page_count);
     Ok(processed.to_string())
   }
 
@@ -4233,27 +4362,27 @@ impl PandocService {
   /// 3. 所有图片都转换为 base64，不使用 file:// 路径
   /// 4. 内存限制：单个文档总图片 base64 不超过 15MB（与 open_docx_for_edit 返回上限一致，避免超大 HTML 导致 WebView 崩溃）
   fn process_images_for_edit(html: &str, doc_path: &Path) -> Result<String, String> {
-    eprintln!("🖼️ [图片处理] 开始处理编辑模式图片...");
-    eprintln!("🖼️ [图片处理] 文档路径: {:?}", doc_path);
-    eprintln!("🖼️ [图片处理] HTML 长度: {} 字符", html.len());
+    tracing::debug!("[图片处理] 开始处理编辑模式图片...");
+    tracing::debug!("[图片处理] 文档路径: {:?}", doc_path);
+    tracing::debug!("[图片处理] HTML 长度: {} 字符", html.len());
 
     use crate::services::image_service::ImageService;
 
     // 使用 scraper 解析 HTML（比正则表达式更可靠）
-    eprintln!("🖼️ [图片处理] 步骤 1: 解析 HTML...");
+    tracing::debug!("[图片处理] 步骤 1: 解析 HTML...");
     let document = match std::panic::catch_unwind(|| Html::parse_document(html)) {
       Ok(doc) => doc,
       Err(e) => {
-        eprintln!("❌ [图片处理] HTML 解析 panic: {:?}", e);
+        tracing::error!("[图片处理] HTML 解析 panic: {:?}", e);
         return Err("HTML 解析失败（panic）".to_string());
       }
     };
 
-    eprintln!("🖼️ [图片处理] 步骤 2: 创建图片选择器...");
+    tracing::debug!("[图片处理] 步骤 2: 创建图片选择器...");
     let img_selector = match Selector::parse("img") {
       Ok(sel) => sel,
       Err(e) => {
-        eprintln!("❌ [图片处理] 选择器解析失败: {}", e);
+        tracing::error!("[图片处理] 选择器解析失败: {}", e);
         return Err(format!("选择器解析失败: {}", e));
       }
     };
@@ -4262,12 +4391,12 @@ impl PandocService {
     let media_dir = match doc_path.parent() {
       Some(dir) => dir,
       None => {
-        eprintln!("❌ [图片处理] 无法获取文件目录");
+        tracing::error!("[图片处理] 无法获取文件目录");
         return Err("无法获取文件目录".to_string());
       }
     };
 
-    eprintln!("🖼️ [图片处理] 媒体目录: {:?}", media_dir);
+    tracing::debug!("[图片处理] 媒体目录: {:?}", media_dir);
 
     let mut processed_html = html.to_string();
     let mut replacements = Vec::new();
@@ -4279,27 +4408,27 @@ impl PandocService {
     let mut error_count = 0;
     let mut skipped_count = 0;
 
-    eprintln!("🖼️ [图片处理] 步骤 3: 查找所有图片标签...");
+    tracing::debug!("[图片处理] 步骤 3: 查找所有图片标签...");
     let img_elements: Vec<_> = document.select(&img_selector).collect();
-    eprintln!("🖼️ [图片处理] 找到 {} 个图片标签", img_elements.len());
+    tracing::debug!("[图片处理] 找到 {} 个图片标签", img_elements.len());
 
     // 从后往前替换，避免索引偏移
     for (index, element) in img_elements.iter().enumerate() {
-      eprintln!("🖼️ [图片处理] 处理第 {} 个图片标签...", index + 1);
+      tracing::debug!("[图片处理] 处理第 {} 个图片标签...", index + 1);
 
       if let Some(src_attr) = element.value().attr("src") {
-        eprintln!("🖼️ [图片处理] 图片 {}: src = {}", index + 1, src_attr);
+        tracing::debug!("[图片处理] 图片 {}: src = {}", index + 1, src_attr);
 
         // 跳过已经是 data URL 的图片
         if src_attr.starts_with("data:") {
-          eprintln!("🖼️ [图片处理] 图片 {}: 跳过（已是 data URL）", index + 1);
+          tracing::debug!("[图片处理] 图片 {}: 跳过（已是 data URL）", index + 1);
           skipped_count += 1;
           continue;
         }
 
         // 跳过 HTTP/HTTPS 图片
         if src_attr.starts_with("http://") || src_attr.starts_with("https://") {
-          eprintln!("🖼️ [图片处理] 图片 {}: 跳过（HTTP/HTTPS）", index + 1);
+          tracing::debug!("[图片处理] 图片 {}: 跳过（HTTP/HTTPS）", index + 1);
           skipped_count += 1;
           continue;
         }
@@ -4309,23 +4438,19 @@ impl PandocService {
         // 处理相对路径
         let img_path = if src_attr.starts_with("/") {
           // 绝对路径
-          eprintln!("🖼️ [图片处理] 图片 {}: 绝对路径", index + 1);
+          tracing::debug!("[图片处理] 图片 {}: 绝对路径", index + 1);
           PathBuf::from(src_attr)
         } else {
           // 相对路径（Pandoc 提取的图片）
-          eprintln!("🖼️ [图片处理] 图片 {}: 相对路径，拼接媒体目录", index + 1);
+          tracing::debug!("[图片处理] 图片 {}: 相对路径，拼接媒体目录", index + 1);
           media_dir.join(src_attr)
         };
 
-        eprintln!(
-          "🖼️ [图片处理] 图片 {}: 完整路径 = {:?}",
-          index + 1,
-          img_path
-        );
+        tracing::debug!("[图片处理] 图片 {}: 完整路径 = {:?}", index + 1, img_path);
 
         if !img_path.exists() {
-          eprintln!(
-            "⚠️ [图片处理] 图片 {}: 文件不存在: {:?} (原始路径: {})",
+          tracing::warn!(
+            "[图片处理] 图片 {}: 文件不存在: {:?} (原始路径: {})",
             index + 1,
             img_path,
             src_attr
@@ -4338,38 +4463,34 @@ impl PandocService {
         let file_size = match std::fs::metadata(&img_path) {
           Ok(meta) => meta.len(),
           Err(e) => {
-            eprintln!(
-              "❌ [图片处理] 图片 {}: 无法读取文件元数据: {}",
-              index + 1,
-              e
-            );
+            tracing::error!("[图片处理] 图片 {}: 无法读取文件元数据: {}", index + 1, e);
             error_count += 1;
             continue;
           }
         };
-        eprintln!(
-          "🖼️ [图片处理] 图片 {}: 文件大小 = {} 字节 ({} MB)",
+        tracing::debug!(
+          "[图片处理] 图片 {}: 文件大小 = {} 字节 ({} MB)",
           index + 1,
           file_size,
           file_size / 1024 / 1024
         );
 
         // 处理图片
-        eprintln!("🖼️ [图片处理] 图片 {}: 开始处理...", index + 1);
+        tracing::debug!("[图片处理] 图片 {}: 开始处理...", index + 1);
         match std::panic::catch_unwind(|| {
           Self::process_single_image_for_edit(&img_path, &image_service)
         }) {
           Ok(Ok((data_url, size))) => {
-            eprintln!(
-              "🖼️ [图片处理] 图片 {}: 处理成功，大小 = {} 字节",
+            tracing::debug!(
+              "[图片处理] 图片 {}: 处理成功，大小 = {} 字节",
               index + 1,
               size
             );
 
             // 检查总大小限制
             if total_base64_size + size > MAX_TOTAL_SIZE {
-              eprintln!(
-                "⚠️ [图片处理] 图片 {}: 总大小超过限制 ({}MB > 15MB)，跳过剩余图片",
+              tracing::warn!(
+                "[图片处理] 图片 {}: 总大小超过限制 ({}MB > 15MB)，跳过剩余图片",
                 index + 1,
                 (total_base64_size + size) / 1024 / 1024
               );
@@ -4382,15 +4503,15 @@ impl PandocService {
 
             if size < 1024 * 1024 {
               base64_count += 1;
-              eprintln!("🖼️ [图片处理] 图片 {}: 直接 base64", index + 1);
+              tracing::debug!("[图片处理] 图片 {}: 直接 base64", index + 1);
             } else {
               compressed_count += 1;
-              eprintln!("🖼️ [图片处理] 图片 {}: 压缩后 base64", index + 1);
+              tracing::debug!("[图片处理] 图片 {}: 压缩后 base64", index + 1);
             }
           }
           Ok(Err(e)) => {
-            eprintln!(
-              "❌ [图片处理] 图片 {}: 处理失败: {:?}, 错误: {}",
+            tracing::error!(
+              "[图片处理] 图片 {}: 处理失败: {:?}, 错误: {}",
               index + 1,
               img_path,
               e
@@ -4398,8 +4519,8 @@ impl PandocService {
             error_count += 1;
           }
           Err(panic_info) => {
-            eprintln!(
-              "❌ [图片处理] 图片 {}: 处理 panic: {:?}",
+            tracing::error!(
+              "[图片处理] 图片 {}: 处理 panic: {:?}",
               index + 1,
               panic_info
             );
@@ -4407,17 +4528,17 @@ impl PandocService {
           }
         }
       } else {
-        eprintln!("⚠️ [图片处理] 图片 {}: 没有 src 属性", index + 1);
+        tracing::warn!("[图片处理] 图片 {}: 没有 src 属性", index + 1);
       }
     }
 
-    eprintln!("🖼️ [图片处理] 步骤 4: 替换图片路径...");
-    eprintln!("🖼️ [图片处理] 需要替换 {} 个图片路径", replacements.len());
+    tracing::debug!("[图片处理] 步骤 4: 替换图片路径...");
+    tracing::debug!("[图片处理] 需要替换 {} 个图片路径", replacements.len());
 
     // 替换所有找到的图片路径
     for (i, (old_src, new_src)) in replacements.iter().enumerate() {
-      eprintln!(
-        "🖼️ [图片处理] 替换 {}: {} -> {}...",
+      tracing::debug!(
+        "[图片处理] 替换 {}: {} -> {}...",
         i + 1,
         old_src,
         &new_src[..50.min(new_src.len())]
@@ -4425,11 +4546,17 @@ impl PandocService {
       processed_html = processed_html.replace(old_src, new_src);
     }
 
-    eprintln!("🖼️ [图片处理] 完成: 总计 {} 个，base64 {} 个，压缩 {} 个，跳过 {} 个，错误 {} 个，总大小 {}MB",
-                 processed_count, base64_count, compressed_count, skipped_count, error_count,
-                 total_base64_size / 1024 / 1024);
-    eprintln!(
-      "🖼️ [图片处理] 处理后的 HTML 长度: {} 字符",
+    tracing::warn!(
+      "[图片处理] 完成: 总计 {} 个，base64 {} 个，压缩 {} 个，跳过 {} 个，错误 {} 个，总大小 {}MB",
+      processed_count,
+      base64_count,
+      compressed_count,
+      skipped_count,
+      error_count,
+      total_base64_size / 1024 / 1024
+    );
+    tracing::debug!(
+      "[图片处理] 处理后的 HTML 长度: {} 字符",
       processed_html.len()
     );
 
@@ -4444,61 +4571,61 @@ impl PandocService {
   ) -> Result<(String, u64), String> {
     use base64::{engine::general_purpose, Engine as _};
 
-    eprintln!("  📸 [单图处理] 开始处理: {:?}", img_path);
+    tracing::debug!("  [单图处理] 开始处理: {:?}", img_path);
 
     let metadata = match std::fs::metadata(img_path) {
       Ok(meta) => meta,
       Err(e) => {
-        eprintln!("  ❌ [单图处理] 无法读取图片元数据: {}", e);
+        tracing::error!("  [单图处理] 无法读取图片元数据: {}", e);
         return Err(format!("无法读取图片元数据: {}", e));
       }
     };
     let file_size = metadata.len();
-    eprintln!(
-      "  📸 [单图处理] 文件大小: {} 字节 ({} MB)",
+    tracing::debug!(
+      "  [单图处理] 文件大小: {} 字节 ({} MB)",
       file_size,
       file_size / 1024 / 1024
     );
 
     let (image_data, mime_type) = if file_size < 1024 * 1024 {
       // 小图片（< 1MB）：直接读取
-      eprintln!("  📸 [单图处理] 小图片，直接读取...");
+      tracing::debug!("  [单图处理] 小图片，直接读取...");
       let img_data = match std::fs::read(img_path) {
         Ok(data) => {
-          eprintln!("  📸 [单图处理] 读取成功，大小: {} 字节", data.len());
+          tracing::debug!("  [单图处理] 读取成功，大小: {} 字节", data.len());
           data
         }
         Err(e) => {
-          eprintln!("  ❌ [单图处理] 读取图片失败: {}", e);
+          tracing::error!("  [单图处理] 读取图片失败: {}", e);
           return Err(format!("读取图片失败: {}", e));
         }
       };
 
       let mime_type = match image_service.detect_image_mime_type(img_path) {
         Ok(mt) => {
-          eprintln!("  📸 [单图处理] MIME 类型: {}", mt);
+          tracing::debug!("  [单图处理] MIME 类型: {}", mt);
           mt
         }
         Err(e) => {
-          eprintln!("  ❌ [单图处理] 检测 MIME 类型失败: {}", e);
+          tracing::error!("  [单图处理] 检测 MIME 类型失败: {}", e);
           return Err(format!("检测 MIME 类型失败: {}", e));
         }
       };
       (img_data, mime_type)
     } else {
       // 大图片（≥ 1MB）：压缩后读取
-      eprintln!("  📸 [单图处理] 大图片，开始压缩...");
+      tracing::debug!("  [单图处理] 大图片，开始压缩...");
       let compressed = match image_service.compress_image(img_path, 1024, 85) {
         Ok(data) => {
-          eprintln!(
-            "  📸 [单图处理] 压缩成功，原始大小: {} 字节，压缩后: {} 字节",
+          tracing::debug!(
+            "  [单图处理] 压缩成功，原始大小: {} 字节，压缩后: {} 字节",
             file_size,
             data.len()
           );
           data
         }
         Err(e) => {
-          eprintln!("  ❌ [单图处理] 压缩图片失败: {}", e);
+          tracing::error!("  [单图处理] 压缩图片失败: {}", e);
           return Err(format!("压缩图片失败: {}", e));
         }
       };
@@ -4506,16 +4633,16 @@ impl PandocService {
       // 检测压缩后的格式（WebP）
       let mime_type =
         if compressed.len() > 12 && &compressed[0..4] == b"RIFF" && &compressed[8..12] == b"WEBP" {
-          eprintln!("  📸 [单图处理] 检测到 WebP 格式");
+          tracing::debug!("  [单图处理] 检测到 WebP 格式");
           "image/webp"
         } else {
           match image_service.detect_image_mime_type(img_path) {
             Ok(mt) => {
-              eprintln!("  📸 [单图处理] MIME 类型: {}", mt);
+              tracing::debug!("  [单图处理] MIME 类型: {}", mt);
               mt
             }
             Err(e) => {
-              eprintln!("  ❌ [单图处理] 检测 MIME 类型失败: {}", e);
+              tracing::error!("  [单图处理] 检测 MIME 类型失败: {}", e);
               return Err(format!("检测 MIME 类型失败: {}", e));
             }
           }
@@ -4524,21 +4651,120 @@ impl PandocService {
       (compressed, mime_type)
     };
 
-    eprintln!("  📸 [单图处理] 开始 base64 编码...");
+    tracing::debug!("  [单图处理] 开始 base64 编码...");
     // 转换为 base64 data URL
     let base64_str = general_purpose::STANDARD.encode(&image_data);
-    eprintln!(
-      "  📸 [单图处理] base64 编码完成，长度: {} 字符",
+    tracing::debug!(
+      "  [单图处理] base64 编码完成，长度: {} 字符",
       base64_str.len()
     );
 
     let data_url = format!("data:{};base64,{}", mime_type, base64_str);
-    eprintln!(
-      "  📸 [单图处理] data URL 创建完成，总长度: {} 字符",
+    tracing::debug!(
+      "  [单图处理] data URL 创建完成，总长度: {} 字符",
       data_url.len()
     );
-    eprintln!("  ✅ [单图处理] 处理成功");
+    tracing::info!("  [单图处理] 处理成功");
 
     Ok((data_url, image_data.len() as u64))
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn inline(html: &str) -> String {
+    PandocService::convert_css_classes_to_inline_styles(html)
+  }
+
+  #[test]
+  fn css_rules_are_merged_into_inline_styles() {
+    let html = concat!(
+      "<html><head><style>p { margin: 1em 0 } .c1 { color: red } .center { font-weight: bold }",
+      " td, th { padding: 2px }</style></head><body style=\"padding-top: 50px;\">",
+      "<p class=\"c1\" style=\"margin: 0\">a</p>",
+      "<p class=\"center\">b</p>",
+      "<div data-custom-style=\"c1\"><p>c</p></div>",
+      "<table><thead><tr><th>h</th></tr></thead></table>",
+      "<img src=\"a.png\" class=\"text-center\"/>",
+      "</body></html>"
+    );
+    let result = inline(html);
+
+    // 原有属性优先，缺少的属性追加
+    assert!(result.contains(r#"<p class="c1" style="margin: 0; color: red">a</p>"#));
+    assert!(result.contains(
+      r#"<p class="center" style="margin: 1em 0; font-weight: bold; text-align: center">b</p>"#
+    ));
+    assert!(result.contains(r#"<div style="color: red"><p style="margin: 1em 0">c</p></div>"#));
+    // th 规则不能套到 thead 上
+    assert!(result.contains(r#"<thead><tr><th style="padding: 2px">h</th>"#));
+    assert!(result.contains(r#"<img src="a.png" class="text-center" style="text-align: center"/>"#));
+    assert!(result.contains(r#"<body style="padding-top: 50px; padding-top: 0; margin-top: 0">"#));
+  }
+
+  #[test]
+  fn empty_paragraph_placeholders_round_trip() {
+    let html = "<p></p><p class=\"x\"><br/></p><p><span></span></p><p>text</p>";
+    let saved = PandocService::ensure_empty_paragraphs_placeholder(html);
+    assert_eq!(
+      saved,
+      "<p>\u{FEFF}</p><p class=\"x\">\u{FEFF}</p><p>\u{FEFF}</p><p>text</p>"
+    );
+    let loaded = PandocService::restore_empty_paragraphs_placeholder(&format!(
+      "{}<p>&#xFEFF;</p><p>&#65279;</p>",
+      saved
+    ));
+    assert_eq!(
+      loaded,
+      "<p></p><p class=\"x\"></p><p></p><p>text</p><p></p><p></p>"
+    );
+  }
+
+  #[test]
+  fn leading_empty_blocks_are_stripped() {
+    let html =
+      "<html><body>\n<p></p>\n<div><span> </span></div>\n<h1>Title</h1><p></p></body></html>";
+    assert_eq!(
+      PandocService::strip_leading_empty_blocks(html),
+      "<html><body><h1>Title</h1><p></p></body></html>"
+    );
+  }
+
+  #[test]
+  fn normalize_and_extract_text_strip_markup() {
+    assert_eq!(
+      PandocService::normalize_text("<p>a&nbsp;<b>b</b>\n  c</p>"),
+      "a b c"
+    );
+    assert_eq!(
+      PandocService::extract_text_from_html(r#"<span style="color: #FF0000">你好</span> 世界"#),
+      "你好 世界"
+    );
+  }
+
+  #[test]
+  fn oversized_output_skips_enrichment() {
+    let limit = 512 * 1024 * 1024;
+    assert_eq!(
+      ConversionLevel::for_output(10 * 1024 * 1024, limit),
+      ConversionLevel::Full
+    );
+    assert_eq!(
+      ConversionLevel::for_output(200 * 1024 * 1024, limit),
+      ConversionLevel::Essential
+    );
+  }
+
+  #[test]
+  fn pandoc_output_file_is_removed_on_drop() {
+    let output = PandocOutputFile::new();
+    std::fs::write(output.path(), "<p>x</p>").unwrap();
+    assert_eq!(output.len(), 8);
+    assert_eq!(output.read().unwrap(), "<p>x</p>");
+    let path = output.path().to_path_buf();
+    drop(output);
+    assert!(!path.exists());
+  }
+}
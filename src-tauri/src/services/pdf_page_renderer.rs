@@ -0,0 +1,207 @@
+//! PDF 按页渲染：调用 poppler 的 `pdfinfo` / `pdftoppm` 获取页数、把单页渲染为 PNG，
+//! 供前端虚拟化预览只渲染可见页，避免一次性把上百页的 PDF 载入 webview。
+//!
+//! 渲染结果缓存在预览缓存目录的 `pages` 子目录，按 PDF 路径 + 修改时间 + 页码 + 宽度（+ 暗色）命名。
+//! 暗色主题下可渲染反转亮度、保留色相的页面，白底黑字变为深灰底浅色字，彩色图表仍可辨认。
+
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::UNIX_EPOCH;
+use which::which;
+
+use crate::services::preview_cache::PreviewCache;
+
+/// 暗色页面的背景与前景亮度上下限，避免纯黑底纯白字过于刺眼
+const DARK_PAGE_MIN: f32 = 0x1e as f32;
+const DARK_PAGE_MAX: f32 = 0xe6 as f32;
+
+/// 未指定宽度时的渲染宽度（像素）
+pub const PDF_PAGE_DEFAULT_WIDTH: u32 = 1200;
+const PDF_PAGE_MIN_WIDTH: u32 = 100;
+const PDF_PAGE_MAX_WIDTH: u32 = 4000;
+
+pub struct PdfPageRenderer {
+  pdfinfo_path: Option<PathBuf>,
+  pdftoppm_path: Option<PathBuf>,
+  pages_dir: PathBuf,
+}
+
+impl PdfPageRenderer {
+  pub fn new() -> Result<Self, String> {
+    Ok(Self {
+      pdfinfo_path: Self::find_executable("pdfinfo"),
+      pdftoppm_path: Self::find_executable("pdftoppm"),
+      pages_dir: PreviewCache::default_dir()?.join("pages"),
+    })
+  }
+
+  /// 先查 PATH，再查 Homebrew / 系统默认位置（GUI 启动的应用在 macOS 上通常拿不到 Homebrew 的 PATH）
+  fn find_executable(name: &str) -> Option<PathBuf> {
+    which(name).ok().or_else(|| {
+      ["/opt/homebrew/bin", "/usr/local/bin", "/usr/bin"]
+        .iter()
+        .map(|dir| PathBuf::from(dir).join(name))
+        .find(|p| p.is_file())
+    })
+  }
+
+  fn executable<'a>(path: &'a Option<PathBuf>, name: &str) -> Result<&'a PathBuf, String> {
+    path.as_ref().ok_or_else(|| {
+      format!(
+        "未检测到 {}（poppler），无法按页渲染 PDF。请先安装 poppler。",
+        name
+      )
+    })
+  }
+
+  /// PDF 总页数
+  pub fn page_count(&self, pdf_path: &Path) -> Result<u32, String> {
+    let output = Command::new(Self::executable(&self.pdfinfo_path, "pdfinfo")?)
+      .arg(pdf_path.as_os_str())
+      .output()
+      .map_err(|e| format!("执行 pdfinfo 失败: {}", e))?;
+    if !output.status.success() {
+      return Err(format!(
+        "读取 PDF 信息失败: {}",
+        String::from_utf8_lossy(&output.stderr).trim()
+      ));
+    }
+    parse_page_count(&String::from_utf8_lossy(&output.stdout))
+      .ok_or_else(|| "无法从 pdfinfo 输出中读取页数".to_string())
+  }
+
+  /// 渲染第 page 页（从 1 开始）为指定宽度的 PNG，返回图片路径；已有缓存时直接返回
+  ///
+  /// dark 为 true 时返回暗色版本（基于同一张页面图片转换并单独缓存）
+  pub fn render_page(
+    &self,
+    pdf_path: &Path,
+    page: u32,
+    width: u32,
+    dark: bool,
+  ) -> Result<PathBuf, String> {
+    if dark {
+      let light_path = self.render_page(pdf_path, page, width, false)?;
+      let dark_path = light_path.with_file_name(format!(
+        "{}_dark.png",
+        light_path.file_stem().unwrap_or_default().to_string_lossy()
+      ));
+      if !dark_path.is_file() {
+        let mut image = image::open(&light_path)
+          .map_err(|e| format!("读取页面图片失败: {}", e))?
+          .into_rgb8();
+        darken_page(&mut image);
+        // 同样先写临时文件再改名，避免并发请求读到半成品
+        let temp_path = dark_path.with_extension(format!("{}.png", uuid::Uuid::new_v4()));
+        image
+          .save_with_format(&temp_path, image::ImageFormat::Png)
+          .map_err(|e| format!("保存暗色页面图片失败: {}", e))?;
+        std::fs::rename(&temp_path, &dark_path)
+          .map_err(|e| format!("保存暗色页面图片失败: {}", e))?;
+      }
+      return Ok(dark_path);
+    }
+
+    if page == 0 {
+      return Err("页码从 1 开始".to_string());
+    }
+    let pdftoppm = Self::executable(&self.pdftoppm_path, "pdftoppm")?;
+    let width = width.clamp(PDF_PAGE_MIN_WIDTH, PDF_PAGE_MAX_WIDTH);
+
+    let output_dir = self.pages_dir.join(pdf_cache_key(pdf_path)?);
+    let output_path = output_dir.join(format!("p{}_w{}.png", page, width));
+    if output_path.is_file() {
+      return Ok(output_path);
+    }
+    std::fs::create_dir_all(&output_dir).map_err(|e| format!("创建页面缓存目录失败: {}", e))?;
+
+    // -singlefile 时输出文件名为 <前缀>.png；先写到唯一前缀再改名，避免并发请求读到半成品
+    let prefix = output_dir.join(format!("p{}_w{}_{}", page, width, uuid::Uuid::new_v4()));
+    let output = Command::new(pdftoppm)
+      .arg("-png")
+      .arg("-singlefile")
+      .arg("-f")
+      .arg(page.to_string())
+      .arg("-l")
+      .arg(page.to_string())
+      .arg("-scale-to-x")
+      .arg(width.to_string())
+      .arg("-scale-to-y")
+      .arg("-1")
+      .arg(pdf_path.as_os_str())
+      .arg(&prefix)
+      .output()
+      .map_err(|e| format!("执行 pdftoppm 失败: {}", e))?;
+    let rendered = prefix.with_extension("png");
+    if !output.status.success() || !rendered.is_file() {
+      let _ = std::fs::remove_file(&rendered);
+      return Err(format!(
+        "渲染 PDF 第 {} 页失败: {}",
+        page,
+        String::from_utf8_lossy(&output.stderr).trim()
+      ));
+    }
+    std::fs::rename(&rendered, &output_path).map_err(|e| format!("保存页面图片失败: {}", e))?;
+    Ok(output_path)
+  }
+}
+
+/// 反转亮度并保留色相与饱和度（相当于反色后再旋转色相 180°），再压缩到深灰-浅灰区间
+fn darken_page(image: &mut image::RgbImage) {
+  let scale = (DARK_PAGE_MAX - DARK_PAGE_MIN) / 255.0;
+  for pixel in image.pixels_mut() {
+    let [r, g, b] = pixel.0;
+    let shift = 255 - r.max(g).max(b) as i32 - r.min(g).min(b) as i32;
+    pixel.0 = [r, g, b].map(|c| {
+      let inverted = (c as i32 + shift).clamp(0, 255) as f32;
+      (DARK_PAGE_MIN + inverted * scale).round() as u8
+    });
+  }
+}
+
+/// PDF 路径与修改时间的哈希，PDF 重新生成后旧的页面图片不再命中
+fn pdf_cache_key(pdf_path: &Path) -> Result<String, String> {
+  let modified = std::fs::metadata(pdf_path)
+    .and_then(|m| m.modified())
+    .map_err(|e| format!("读取 PDF 文件信息失败: {}", e))?
+    .duration_since(UNIX_EPOCH)
+    .map(|d| d.as_secs())
+    .unwrap_or(0);
+  let mut hasher = Sha256::new();
+  hasher.update(pdf_path.to_string_lossy().as_bytes());
+  hasher.update(modified.to_le_bytes());
+  Ok(format!("{:x}", hasher.finalize())[..16].to_string())
+}
+
+/// 解析 pdfinfo 输出中的 `Pages:` 行
+fn parse_page_count(info: &str) -> Option<u32> {
+  info
+    .lines()
+    .find_map(|line| line.strip_prefix("Pages:"))
+    .and_then(|v| v.trim().parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn darkens_pages_without_losing_hue() {
+    let mut image =
+      image::RgbImage::from_vec(3, 1, vec![255, 255, 255, 0, 0, 0, 200, 30, 30]).unwrap();
+    darken_page(&mut image);
+    // 白底变深灰，黑字变浅灰，红色仍以红色为主
+    assert_eq!(image.get_pixel(0, 0).0, [0x1e; 3]);
+    assert_eq!(image.get_pixel(1, 0).0, [0xe6; 3]);
+    let [r, g, b] = image.get_pixel(2, 0).0;
+    assert!(r > g && g == b);
+  }
+
+  #[test]
+  fn parses_page_count_from_pdfinfo_output() {
+    let info = "Title:          Report\nProducer:       LibreOffice 7.6\nPages:          214\nEncrypted:      no\n";
+    assert_eq!(parse_page_count(info), Some(214));
+    assert_eq!(parse_page_count("Title: x\n"), None);
+  }
+}
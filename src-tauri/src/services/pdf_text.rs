@@ -0,0 +1,235 @@
+//! PDF 文本提取：按页返回文本与简单的版面信息，供搜索索引、AI 分析与预览中的复制文本使用。
+//!
+//! 加密 PDF 先尝试空密码解密（只限制打印/复制的文件通常如此），仍无法解密时返回 `encrypted: true` 而不是报错。
+
+use pdf_extract::{Dictionary, Document, Object, PlainTextOutput};
+use serde::{Deserialize, Serialize};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::Path;
+
+/// 单页的版面信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PdfPageLayout {
+  /// 页面尺寸（点，来自 MediaBox；缺失时为 A4）
+  pub width: f32,
+  pub height: f32,
+  /// 顺时针旋转角度（0 / 90 / 180 / 270）
+  pub rotation: i64,
+  pub line_count: usize,
+  /// 按空行分隔的段落数
+  pub paragraph_count: usize,
+  /// 没有可提取的文字，可能是扫描页，需要 OCR
+  pub needs_ocr: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PdfPageText {
+  /// 页码（从 1 开始）
+  pub page: u32,
+  pub text: String,
+  pub layout: PdfPageLayout,
+  /// 本页解析失败的原因（其余页面不受影响）
+  pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PdfTextExtraction {
+  pub page_count: u32,
+  /// 需要密码才能打开，此时 pages 为空
+  pub encrypted: bool,
+  pub pages: Vec<PdfPageText>,
+}
+
+impl PdfTextExtraction {
+  /// 所有已提取页面的文本，页与页之间空一行
+  pub fn full_text(&self) -> String {
+    self
+      .pages
+      .iter()
+      .map(|p| p.text.trim_end())
+      .filter(|t| !t.is_empty())
+      .collect::<Vec<_>>()
+      .join("\n\n")
+  }
+}
+
+/// 提取 PDF 文本；pages 为要提取的页码（从 1 开始），为 None 时提取全部页面，超出范围的页码会被忽略
+pub fn extract_pdf_text(path: &Path, pages: Option<&[u32]>) -> Result<PdfTextExtraction, String> {
+  let mut doc = Document::load(path).map_err(|e| format!("打开 PDF 失败: {}", e))?;
+  let page_ids = doc.get_pages();
+  let page_count = page_ids.len() as u32;
+
+  if doc.is_encrypted() && doc.decrypt("").is_err() {
+    return Ok(PdfTextExtraction {
+      page_count,
+      encrypted: true,
+      pages: Vec::new(),
+    });
+  }
+
+  let mut selected: Vec<u32> = match pages {
+    Some(pages) => pages
+      .iter()
+      .copied()
+      .filter(|p| page_ids.contains_key(p))
+      .collect(),
+    None => page_ids.keys().copied().collect(),
+  };
+  selected.sort_unstable();
+  selected.dedup();
+
+  let pages = selected
+    .into_iter()
+    .map(|page| {
+      let page_dict = doc.get_dictionary(page_ids[&page]).ok();
+      let (width, height) = page_dict
+        .and_then(|d| media_box_size(&doc, d))
+        .unwrap_or((595.0, 842.0));
+      let rotation = page_dict
+        .and_then(|d| inherited(&doc, d, b"Rotate"))
+        .and_then(|o| o.as_i64().ok())
+        .map_or(0, |r| r.rem_euclid(360));
+
+      // pdf-extract 遇到异常内容可能 panic，逐页隔离，单页失败不影响其他页
+      let extracted = catch_unwind(AssertUnwindSafe(|| {
+        let mut text = String::new();
+        pdf_extract::output_doc_page(&doc, &mut PlainTextOutput::new(&mut text), page)
+          .map(|_| text)
+          .map_err(|e| e.to_string())
+      }))
+      .unwrap_or_else(|_| Err("解析页面内容时发生内部错误".to_string()));
+      let (text, error) = match extracted {
+        Ok(text) => (text, None),
+        Err(e) => (String::new(), Some(e)),
+      };
+
+      PdfPageText {
+        page,
+        layout: PdfPageLayout {
+          width,
+          height,
+          rotation,
+          line_count: text.lines().filter(|l| !l.trim().is_empty()).count(),
+          paragraph_count: count_paragraphs(&text),
+          needs_ocr: error.is_none() && text.trim().is_empty(),
+        },
+        text,
+        error,
+      }
+    })
+    .collect();
+
+  Ok(PdfTextExtraction {
+    page_count,
+    encrypted: false,
+    pages,
+  })
+}
+
+/// 提取全文；加密或无法解析时返回错误（搜索索引、AI 读取文档使用）
+pub fn extract_pdf_plain_text(path: &Path) -> Result<String, String> {
+  let extraction = extract_pdf_text(path, None)?;
+  if extraction.encrypted {
+    return Err("PDF 已加密，需要密码才能提取文本".to_string());
+  }
+  Ok(extraction.full_text())
+}
+
+/// 查找页面属性，页面本身没有时沿 Parent 向上继承
+fn inherited<'a>(doc: &'a Document, dict: &'a Dictionary, key: &[u8]) -> Option<&'a Object> {
+  let mut current = dict;
+  // 页面树深度有限，防止循环引用
+  for _ in 0..32 {
+    if let Ok(value) = current.get(key) {
+      return doc.dereference(value).ok().map(|(_, o)| o);
+    }
+    let parent = current.get(b"Parent").ok()?.as_reference().ok()?;
+    current = doc.get_dictionary(parent).ok()?;
+  }
+  None
+}
+
+fn media_box_size(doc: &Document, page: &Dictionary) -> Option<(f32, f32)> {
+  let values: Vec<f32> = inherited(doc, page, b"MediaBox")?
+    .as_array()
+    .ok()?
+    .iter()
+    .map(|o| doc.dereference(o).ok()?.1.as_float().ok())
+    .collect::<Option<_>>()?;
+  match values[..] {
+    [x0, y0, x1, y1] => Some(((x1 - x0).abs(), (y1 - y0).abs())),
+    _ => None,
+  }
+}
+
+fn count_paragraphs(text: &str) -> usize {
+  text
+    .split("\n\n")
+    .filter(|block| !block.trim().is_empty())
+    .count()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use pdf_extract::{dictionary, Stream};
+
+  #[test]
+  fn extracts_selected_pages_with_layout() {
+    let mut doc = Document::with_version("1.5");
+    let pages_id = doc.new_object_id();
+    let font_id = doc.add_object(dictionary! {
+      "Type" => "Font",
+      "Subtype" => "Type1",
+      "BaseFont" => "Helvetica",
+    });
+    let resources_id = doc.add_object(dictionary! {
+      "Font" => dictionary! { "F1" => font_id },
+    });
+    let kids: Vec<Object> = ["Hello", "World"]
+      .iter()
+      .map(|word| {
+        let content = format!("BT /F1 24 Tf 72 700 Td ({}) Tj ET", word);
+        let content_id = doc.add_object(Stream::new(dictionary! {}, content.into_bytes()));
+        doc
+          .add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "Contents" => content_id,
+          })
+          .into()
+      })
+      .collect();
+    doc.objects.insert(
+      pages_id,
+      Object::Dictionary(dictionary! {
+        "Type" => "Pages",
+        "Kids" => kids,
+        "Count" => 2,
+        "Resources" => resources_id,
+        "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+        "Rotate" => 90,
+      }),
+    );
+    let catalog_id = doc.add_object(dictionary! {
+      "Type" => "Catalog",
+      "Pages" => pages_id,
+    });
+    doc.trailer.set("Root", catalog_id);
+
+    let path = std::env::temp_dir().join(format!("binder_pdf_text_{}.pdf", uuid::Uuid::new_v4()));
+    doc.save(&path).unwrap();
+    let result = extract_pdf_text(&path, Some(&[2, 5])).unwrap();
+    let _ = std::fs::remove_file(&path);
+
+    assert_eq!(result.page_count, 2);
+    assert!(!result.encrypted);
+    assert_eq!(result.pages.len(), 1);
+    let page = &result.pages[0];
+    assert_eq!(page.page, 2);
+    assert!(page.text.contains("World"));
+    assert_eq!((page.layout.width, page.layout.height), (612.0, 792.0));
+    assert_eq!(page.layout.rotation, 90);
+    assert!(!page.layout.needs_ocr);
+  }
+}
@@ -0,0 +1,440 @@
+//! 插件：从应用数据目录的 `plugins/<插件>/plugin.toml` 加载声明式扩展，无需重新编译应用。
+//!
+//! 一个插件可以声明：
+//! - `[[providers]]`：OpenAI 兼容的 AI 提供商（如本地 Ollama、其他云服务），按模型名路由；
+//! - `[[tools]]`：Agent 工具，格式与工作区 `.binder/tools/*.toml` 相同；
+//! - `[[converters]]`：文档转换命令，模板中的 `{{input}}`、`{{output}}` 替换为文件路径。
+//!
+//! 目前只支持声明式清单，不加载 WASM 或动态库。清单有误的插件记录错误后跳过，不影响其他插件。
+
+use crate::services::ai_providers::{AIProvider, OpenAIProvider};
+use crate::services::custom_tools::{self, CustomToolSpec};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+const MANIFEST_FILE: &str = "plugin.toml";
+const BUILTIN_PROVIDERS: &[&str] = &["deepseek", "openai"];
+static PLUGIN_ID: Lazy<Regex> = Lazy::new(|| Regex::new(r"^[a-z][a-z0-9_-]{0,63}$").unwrap());
+
+/// 插件清单 `plugin.toml`
+#[derive(Debug, Clone, Deserialize)]
+pub struct PluginManifest {
+  pub id: String,
+  pub name: String,
+  #[serde(default)]
+  pub version: String,
+  #[serde(default)]
+  pub description: String,
+  #[serde(default = "default_enabled")]
+  pub enabled: bool,
+  #[serde(default)]
+  pub providers: Vec<ProviderSpec>,
+  #[serde(default)]
+  pub tools: Vec<CustomToolSpec>,
+  #[serde(default)]
+  pub converters: Vec<ConverterSpec>,
+}
+
+/// OpenAI 兼容的 AI 提供商
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProviderSpec {
+  /// 提供商名称，不能与内置的 deepseek / openai 重复
+  pub name: String,
+  /// 如 `http://localhost:11434/v1`
+  pub base_url: String,
+  /// 由该提供商处理的模型名
+  pub models: Vec<String>,
+  /// 读取 API 密钥的环境变量；未设置时使用密钥管理中以提供商名称保存的密钥，都没有则不带密钥
+  pub api_key_env: Option<String>,
+}
+
+/// 文档转换命令
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConverterSpec {
+  pub name: String,
+  /// 支持的源文件扩展名（不含点）
+  pub from: Vec<String>,
+  /// 输出文件扩展名
+  pub to: String,
+  /// 如 `asciidoctor -o {{output}} {{input}}`
+  pub command: String,
+  pub timeout_secs: Option<u64>,
+}
+
+fn default_enabled() -> bool {
+  true
+}
+
+/// 前端展示用的插件信息
+#[derive(Debug, Clone, Serialize)]
+pub struct PluginInfo {
+  pub id: String,
+  pub name: String,
+  pub version: String,
+  pub description: String,
+  pub enabled: bool,
+  pub path: String,
+  pub providers: Vec<String>,
+  pub tools: Vec<String>,
+  pub converters: Vec<ConverterSpec>,
+  /// 清单无法加载时的原因
+  pub error: Option<String>,
+}
+
+impl PluginManifest {
+  fn validate(&self, builtin_tools: &[String]) -> Result<(), String> {
+    if !PLUGIN_ID.is_match(&self.id) {
+      return Err(format!(
+        "插件 id {} 不合法（小写字母开头，仅含小写字母、数字、_ 和 -）",
+        self.id
+      ));
+    }
+    for provider in &self.providers {
+      if BUILTIN_PROVIDERS.contains(&provider.name.as_str()) || provider.name.trim().is_empty() {
+        return Err(format!("提供商名称 {} 不可用", provider.name));
+      }
+      if !provider.base_url.starts_with("http://") && !provider.base_url.starts_with("https://") {
+        return Err(format!(
+          "提供商 {} 的 base_url 必须以 http:// 或 https:// 开头",
+          provider.name
+        ));
+      }
+      if provider.models.is_empty() {
+        return Err(format!("提供商 {} 至少需要声明一个模型", provider.name));
+      }
+    }
+    for tool in &self.tools {
+      tool.validate(builtin_tools)?;
+    }
+    for converter in &self.converters {
+      if converter.from.is_empty() || converter.to.trim().is_empty() {
+        return Err(format!("转换器 {} 缺少 from 或 to", converter.name));
+      }
+      if !converter.command.contains("{{input}}") || !converter.command.contains("{{output}}") {
+        return Err(format!(
+          "转换器 {} 的 command 必须包含 {{{{input}}}} 和 {{{{output}}}}",
+          converter.name
+        ));
+      }
+    }
+    Ok(())
+  }
+}
+
+struct LoadedPlugin {
+  dir: PathBuf,
+  result: Result<PluginManifest, String>,
+}
+
+#[derive(Default)]
+pub struct PluginRegistry {
+  plugins: Vec<LoadedPlugin>,
+}
+
+impl PluginRegistry {
+  /// 扫描插件目录；同名提供商、工具、转换器以先加载（目录名排序靠前）的插件为准
+  pub fn load(root: &Path) -> Self {
+    let Ok(entries) = fs::read_dir(root) else {
+      return Self::default();
+    };
+    let mut dirs: Vec<PathBuf> = entries
+      .flatten()
+      .map(|e| e.path())
+      .filter(|p| p.join(MANIFEST_FILE).is_file())
+      .collect();
+    dirs.sort();
+
+    let builtin_tools = crate::services::tool_matrix::builtin_tool_names();
+    let mut registry = Self::default();
+    for dir in dirs {
+      let result = fs::read_to_string(dir.join(MANIFEST_FILE))
+        .map_err(|e| format!("读取插件清单失败: {}", e))
+        .and_then(|content| {
+          toml::from_str::<PluginManifest>(&content).map_err(|e| format!("解析插件清单失败: {}", e))
+        })
+        .and_then(|manifest| manifest.validate(&builtin_tools).map(|_| manifest))
+        .and_then(|manifest| registry.check_conflicts(&manifest).map(|_| manifest));
+      if let Err(e) = &result {
+        tracing::warn!("加载插件失败 {}: {}", dir.display(), e);
+      }
+      registry.plugins.push(LoadedPlugin { dir, result });
+    }
+    registry
+  }
+
+  fn check_conflicts(&self, manifest: &PluginManifest) -> Result<(), String> {
+    if self
+      .plugins
+      .iter()
+      .any(|p| p.result.as_ref().is_ok_and(|m| m.id == manifest.id))
+    {
+      return Err(format!("插件 id {} 重复", manifest.id));
+    }
+    for provider in &manifest.providers {
+      if self.providers().any(|p| p.name == provider.name) {
+        return Err(format!("提供商 {} 已由其他插件注册", provider.name));
+      }
+    }
+    for tool in &manifest.tools {
+      if self.tools().any(|t| t.name == tool.name) {
+        return Err(format!("工具 {} 已由其他插件注册", tool.name));
+      }
+    }
+    for converter in &manifest.converters {
+      if self.converters().any(|c| c.name == converter.name) {
+        return Err(format!("转换器 {} 已由其他插件注册", converter.name));
+      }
+    }
+    Ok(())
+  }
+
+  fn enabled(&self) -> impl Iterator<Item = &PluginManifest> {
+    self
+      .plugins
+      .iter()
+      .filter_map(|p| p.result.as_ref().ok())
+      .filter(|m| m.enabled)
+  }
+
+  pub fn providers(&self) -> impl Iterator<Item = &ProviderSpec> {
+    self.enabled().flat_map(|m| m.providers.iter())
+  }
+
+  pub fn tools(&self) -> impl Iterator<Item = &CustomToolSpec> {
+    self.enabled().flat_map(|m| m.tools.iter())
+  }
+
+  pub fn converters(&self) -> impl Iterator<Item = &ConverterSpec> {
+    self.enabled().flat_map(|m| m.converters.iter())
+  }
+
+  pub fn infos(&self) -> Vec<PluginInfo> {
+    self
+      .plugins
+      .iter()
+      .map(|plugin| {
+        let path = plugin.dir.to_string_lossy().to_string();
+        match &plugin.result {
+          Ok(m) => PluginInfo {
+            id: m.id.clone(),
+            name: m.name.clone(),
+            version: m.version.clone(),
+            description: m.description.clone(),
+            enabled: m.enabled,
+            path,
+            providers: m.providers.iter().map(|p| p.name.clone()).collect(),
+            tools: m.tools.iter().map(|t| t.name.clone()).collect(),
+            converters: m.converters.clone(),
+            error: None,
+          },
+          Err(e) => PluginInfo {
+            id: plugin
+              .dir
+              .file_name()
+              .map(|n| n.to_string_lossy().to_string())
+              .unwrap_or_default(),
+            name: String::new(),
+            version: String::new(),
+            description: String::new(),
+            enabled: false,
+            path,
+            providers: Vec::new(),
+            tools: Vec::new(),
+            converters: Vec::new(),
+            error: Some(e.clone()),
+          },
+        }
+      })
+      .collect()
+  }
+}
+
+pub fn plugins_dir() -> PathBuf {
+  dirs::data_dir()
+    .unwrap_or_else(std::env::temp_dir)
+    .join("binder")
+    .join("plugins")
+}
+
+static REGISTRY: Lazy<RwLock<PluginRegistry>> =
+  Lazy::new(|| RwLock::new(PluginRegistry::load(&plugins_dir())));
+
+/// 在已加载的插件上执行只读操作
+pub fn with_registry<R>(f: impl FnOnce(&PluginRegistry) -> R) -> R {
+  match REGISTRY.read() {
+    Ok(registry) => f(&registry),
+    Err(poisoned) => f(&poisoned.into_inner()),
+  }
+}
+
+/// 重新扫描插件目录
+pub fn reload() -> Vec<PluginInfo> {
+  let registry = PluginRegistry::load(&plugins_dir());
+  let infos = registry.infos();
+  match REGISTRY.write() {
+    Ok(mut current) => *current = registry,
+    Err(poisoned) => *poisoned.into_inner() = registry,
+  }
+  infos
+}
+
+/// 插件提供商名称（模型名由某个插件提供商声明时）
+pub fn provider_for_model(model: &str) -> Option<String> {
+  with_registry(|r| {
+    r.providers()
+      .find(|p| p.models.iter().any(|m| m == model))
+      .map(|p| p.name.clone())
+  })
+}
+
+/// 创建全部插件提供商
+pub fn build_providers(
+  key_manager: &crate::services::api_key_manager::APIKeyManager,
+) -> Vec<(String, Arc<dyn AIProvider>)> {
+  with_registry(|r| {
+    r.providers()
+      .map(|spec| {
+        let api_key = spec
+          .api_key_env
+          .as_deref()
+          .and_then(|name| std::env::var(name).ok())
+          .or_else(|| key_manager.get_key(&spec.name).ok())
+          .unwrap_or_default();
+        let provider: Arc<dyn AIProvider> = Arc::new(OpenAIProvider::with_base_url(
+          api_key,
+          spec.base_url.trim_end_matches('/').to_string(),
+        ));
+        (spec.name.clone(), provider)
+      })
+      .collect()
+  })
+}
+
+pub fn plugin_tools() -> Vec<CustomToolSpec> {
+  with_registry(|r| r.tools().cloned().collect())
+}
+
+/// 用插件转换器转换文档，返回输出文件路径（未指定时与源文件同目录、扩展名为 `to`）
+pub async fn convert(
+  converter_name: &str,
+  input: &Path,
+  output: Option<PathBuf>,
+) -> Result<PathBuf, String> {
+  let converter = with_registry(|r| r.converters().find(|c| c.name == converter_name).cloned())
+    .ok_or_else(|| format!("未找到转换器: {}", converter_name))?;
+  let extension = input
+    .extension()
+    .and_then(|e| e.to_str())
+    .unwrap_or_default()
+    .to_lowercase();
+  if !converter
+    .from
+    .iter()
+    .any(|f| f.trim_start_matches('.').eq_ignore_ascii_case(&extension))
+  {
+    return Err(format!(
+      "转换器 {} 不支持 .{} 文件",
+      converter.name, extension
+    ));
+  }
+  if !input.is_file() {
+    return Err(format!("文件不存在: {}", input.display()));
+  }
+  let output = output.unwrap_or_else(|| input.with_extension(converter.to.trim_start_matches('.')));
+
+  let args = serde_json::json!({
+    "input": input.to_string_lossy(),
+    "output": output.to_string_lossy(),
+  });
+  let invocation = custom_tools::render_shell_template(&converter.command, &args);
+  let timeout = Duration::from_secs(converter.timeout_secs.unwrap_or(120).clamp(1, 600));
+  tracing::info!(
+    "[plugins] 转换器 {}: {}",
+    converter.name,
+    invocation.command
+  );
+
+  let child = crate::services::tool_service::shell_template_command(&invocation)
+    .current_dir(input.parent().unwrap_or(Path::new(".")))
+    .stdin(std::process::Stdio::null())
+    .kill_on_drop(true)
+    .output();
+  let result = tokio::time::timeout(timeout, child)
+    .await
+    .map_err(|_| format!("转换超时（{} 秒）", timeout.as_secs()))?
+    .map_err(|e| format!("启动转换命令失败: {}", e))?;
+  if !result.status.success() {
+    return Err(format!(
+      "转换命令失败: {}",
+      String::from_utf8_lossy(&result.stderr).trim()
+    ));
+  }
+  if !output.is_file() {
+    return Err(format!("转换命令未生成输出文件: {}", output.display()));
+  }
+  Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const MANIFEST: &str = r#"
+id = "local-llm"
+name = "Local LLM"
+version = "0.1.0"
+
+[[providers]]
+name = "ollama"
+base_url = "http://localhost:11434/v1"
+models = ["llama3"]
+
+[[tools]]
+name = "word_count"
+description = "Counts words"
+[tools.parameters]
+type = "object"
+[tools.action]
+type = "shell"
+command = "wc -w"
+
+[[converters]]
+name = "upper"
+from = ["txt"]
+to = "up"
+command = "tr a-z A-Z < {{input}} > {{output}}"
+"#;
+
+  fn write_plugin(root: &Path, dir: &str, manifest: &str) {
+    fs::create_dir_all(root.join(dir)).unwrap();
+    fs::write(root.join(dir).join(MANIFEST_FILE), manifest).unwrap();
+  }
+
+  #[test]
+  fn loads_manifests_and_rejects_conflicts() {
+    let root = std::env::temp_dir().join(format!("binder-plugins-{}", uuid::Uuid::new_v4()));
+    write_plugin(&root, "a", MANIFEST);
+    write_plugin(&root, "b", &MANIFEST.replace("local-llm", "copy"));
+    write_plugin(&root, "c", "id = \"Bad Id\"\nname = \"x\"");
+
+    let registry = PluginRegistry::load(&root);
+    let infos = registry.infos();
+    assert_eq!(infos.len(), 3);
+    assert_eq!(infos[0].providers, vec!["ollama"]);
+    assert!(infos[0].error.is_none());
+    assert!(infos[1].error.as_deref().unwrap().contains("ollama"));
+    assert!(infos[2].error.is_some());
+    assert_eq!(registry.tools().count(), 1);
+    assert_eq!(registry.converters().count(), 1);
+
+    let builtin: PluginManifest =
+      toml::from_str(&MANIFEST.replace("\"ollama\"", "\"openai\"")).unwrap();
+    assert!(builtin.validate(&[]).is_err());
+    let _ = fs::remove_dir_all(&root);
+  }
+}
@@ -0,0 +1,366 @@
+//! 预览 PDF 缓存索引：记录每个缓存文件对应的源文件、源文件修改时间与最近访问时间。
+//!
+//! 索引保存在缓存目录的 `index.json`。源文件修改后重新生成的 PDF 会替换旧条目；
+//! 总大小超过上限时按最近访问时间（LRU）淘汰。
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// 预览缓存默认上限（512MB）
+pub const PREVIEW_CACHE_MAX_BYTES: u64 = 512 * 1024 * 1024;
+
+const INDEX_FILE: &str = "index.json";
+
+/// 多个预览请求可能同时更新索引
+static INDEX_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry {
+  /// 源文件路径；索引建立前已存在的缓存文件为 None
+  source: Option<String>,
+  /// 生成缓存时源文件的修改时间（Unix 秒）
+  source_modified: Option<u64>,
+  size: u64,
+  last_access: u64,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CacheIndex {
+  /// 缓存文件名 → 条目
+  entries: HashMap<String, CacheEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PreviewCacheEntryInfo {
+  pub file: String,
+  pub source: Option<String>,
+  pub size: u64,
+  pub last_access: u64,
+  /// 源文件已被删除或修改，缓存不会再被使用
+  pub stale: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PreviewCacheInfo {
+  pub cache_dir: String,
+  pub entry_count: usize,
+  pub total_bytes: u64,
+  pub max_bytes: u64,
+  pub entries: Vec<PreviewCacheEntryInfo>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PreviewCacheClearResult {
+  pub removed_count: usize,
+  pub freed_bytes: u64,
+}
+
+fn unix_secs(time: SystemTime) -> u64 {
+  time
+    .duration_since(UNIX_EPOCH)
+    .map(|d| d.as_secs())
+    .unwrap_or(0)
+}
+
+fn source_modified(source: &Path) -> Option<u64> {
+  fs::metadata(source)
+    .and_then(|m| m.modified())
+    .ok()
+    .map(unix_secs)
+}
+
+pub struct PreviewCache {
+  dir: PathBuf,
+  max_bytes: u64,
+}
+
+impl PreviewCache {
+  pub fn new(dir: PathBuf) -> Self {
+    Self {
+      dir,
+      max_bytes: PREVIEW_CACHE_MAX_BYTES,
+    }
+  }
+
+  /// 应用数据目录下的默认预览缓存（`binder/cache/preview`）
+  pub fn default_dir() -> Result<PathBuf, String> {
+    Ok(
+      dirs::data_dir()
+        .ok_or_else(|| "无法获取应用数据目录".to_string())?
+        .join("binder")
+        .join("cache")
+        .join("preview"),
+    )
+  }
+
+  /// 读取索引并与目录中的实际 PDF 文件对齐：删除失效条目，补登未记录的文件
+  fn load_index(&self) -> CacheIndex {
+    let mut index: CacheIndex = fs::read_to_string(self.dir.join(INDEX_FILE))
+      .ok()
+      .and_then(|s| serde_json::from_str(&s).ok())
+      .unwrap_or_default();
+
+    let mut present = HashMap::new();
+    if let Ok(entries) = fs::read_dir(&self.dir) {
+      for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if !name.ends_with(".pdf") {
+          continue;
+        }
+        if let Ok(metadata) = entry.metadata() {
+          let modified = metadata.modified().map(unix_secs).unwrap_or(0);
+          present.insert(name, (metadata.len(), modified));
+        }
+      }
+    }
+
+    index.entries.retain(|name, _| present.contains_key(name));
+    for (name, (size, modified)) in present {
+      let entry = index.entries.entry(name).or_insert(CacheEntry {
+        source: None,
+        source_modified: None,
+        size,
+        last_access: modified,
+      });
+      entry.size = size;
+    }
+    index
+  }
+
+  fn save_index(&self, index: &CacheIndex) -> Result<(), String> {
+    let content =
+      serde_json::to_string(index).map_err(|e| format!("序列化预览缓存索引失败: {}", e))?;
+    fs::write(self.dir.join(INDEX_FILE), content)
+      .map_err(|e| format!("写入预览缓存索引失败: {}", e))
+  }
+
+  fn cache_file_name(cached: &Path) -> String {
+    cached
+      .file_name()
+      .map(|n| n.to_string_lossy().to_string())
+      .unwrap_or_default()
+  }
+
+  /// 登记新生成的缓存：移除同一源文件的旧版本，并按 LRU 淘汰超出上限的条目
+  pub fn record(&self, source: &Path, cached: &Path) -> Result<(), String> {
+    let _guard = INDEX_LOCK
+      .lock()
+      .map_err(|e| format!("获取缓存索引锁失败: {}", e))?;
+    let mut index = self.load_index();
+    let name = Self::cache_file_name(cached);
+    let source_str = source.to_string_lossy().to_string();
+
+    let outdated: Vec<String> = index
+      .entries
+      .iter()
+      .filter(|(n, e)| **n != name && e.source.as_deref() == Some(source_str.as_str()))
+      .map(|(n, _)| n.clone())
+      .collect();
+    for outdated in outdated {
+      let _ = fs::remove_file(self.dir.join(&outdated));
+      index.entries.remove(&outdated);
+    }
+
+    let size = fs::metadata(cached).map(|m| m.len()).unwrap_or(0);
+    index.entries.insert(
+      name.clone(),
+      CacheEntry {
+        source: Some(source_str),
+        source_modified: source_modified(source),
+        size,
+        last_access: unix_secs(SystemTime::now()),
+      },
+    );
+    self.evict(&mut index, &name);
+    self.save_index(&index)
+  }
+
+  /// 删除源文件修改时间已变化的缓存条目，返回删除数量
+  pub fn invalidate_if_modified(&self, source: &Path) -> usize {
+    let Ok(_guard) = INDEX_LOCK.lock() else {
+      return 0;
+    };
+    let mut index = self.load_index();
+    let source_str = source.to_string_lossy();
+    let current = source_modified(source);
+    let stale: Vec<String> = index
+      .entries
+      .iter()
+      .filter(|(_, e)| {
+        e.source.as_deref() == Some(source_str.as_ref()) && e.source_modified != current
+      })
+      .map(|(name, _)| name.clone())
+      .collect();
+    for name in &stale {
+      let _ = fs::remove_file(self.dir.join(name));
+      index.entries.remove(name);
+    }
+    if !stale.is_empty() {
+      if let Err(e) = self.save_index(&index) {
+        tracing::warn!("{}", e);
+      }
+    }
+    stale.len()
+  }
+
+  /// 命中缓存时更新最近访问时间
+  pub fn touch(&self, cached: &Path) {
+    let Ok(_guard) = INDEX_LOCK.lock() else {
+      return;
+    };
+    let mut index = self.load_index();
+    if let Some(entry) = index.entries.get_mut(&Self::cache_file_name(cached)) {
+      entry.last_access = unix_secs(SystemTime::now());
+      if let Err(e) = self.save_index(&index) {
+        tracing::warn!("{}", e);
+      }
+    }
+  }
+
+  /// 总大小超过上限时按最近访问时间从旧到新删除，keep 为刚写入的条目（不会被淘汰）
+  fn evict(&self, index: &mut CacheIndex, keep: &str) {
+    let mut total: u64 = index.entries.values().map(|e| e.size).sum();
+    if total <= self.max_bytes {
+      return;
+    }
+    let mut candidates: Vec<(String, u64, u64)> = index
+      .entries
+      .iter()
+      .filter(|(name, _)| name.as_str() != keep)
+      .map(|(name, e)| (name.clone(), e.last_access, e.size))
+      .collect();
+    candidates.sort_by_key(|(_, last_access, _)| *last_access);
+    for (name, _, size) in candidates {
+      if total <= self.max_bytes {
+        break;
+      }
+      let _ = fs::remove_file(self.dir.join(&name));
+      index.entries.remove(&name);
+      total = total.saturating_sub(size);
+      tracing::debug!("预览缓存超出上限，淘汰: {}", name);
+    }
+  }
+
+  pub fn info(&self) -> Result<PreviewCacheInfo, String> {
+    let _guard = INDEX_LOCK
+      .lock()
+      .map_err(|e| format!("获取缓存索引锁失败: {}", e))?;
+    let index = self.load_index();
+    let mut entries: Vec<PreviewCacheEntryInfo> = index
+      .entries
+      .iter()
+      .map(|(file, e)| PreviewCacheEntryInfo {
+        file: file.clone(),
+        source: e.source.clone(),
+        size: e.size,
+        last_access: e.last_access,
+        stale: e
+          .source
+          .as_ref()
+          .is_some_and(|s| source_modified(Path::new(s)) != e.source_modified),
+      })
+      .collect();
+    entries.sort_by_key(|e| std::cmp::Reverse(e.last_access));
+    Ok(PreviewCacheInfo {
+      cache_dir: self.dir.to_string_lossy().to_string(),
+      entry_count: entries.len(),
+      total_bytes: entries.iter().map(|e| e.size).sum(),
+      max_bytes: self.max_bytes,
+      entries,
+    })
+  }
+
+  /// 清除缓存 PDF：older_than 为 None 时全部清除（同时清理 temp 与按页渲染的 pages 目录），否则只清除超过该时长未访问的条目。
+  /// lo_user（LibreOffice 字体配置 profile）始终保留，保证预览默认字体一致。
+  pub fn clear(&self, older_than: Option<Duration>) -> Result<PreviewCacheClearResult, String> {
+    let _guard = INDEX_LOCK
+      .lock()
+      .map_err(|e| format!("获取缓存索引锁失败: {}", e))?;
+    let mut index = self.load_index();
+    let now = unix_secs(SystemTime::now());
+    let removable: Vec<String> = index
+      .entries
+      .iter()
+      .filter(|(_, e)| {
+        older_than.map_or(true, |age| {
+          now.saturating_sub(e.last_access) >= age.as_secs()
+        })
+      })
+      .map(|(name, _)| name.clone())
+      .collect();
+
+    let mut result = PreviewCacheClearResult {
+      removed_count: 0,
+      freed_bytes: 0,
+    };
+    for name in removable {
+      if fs::remove_file(self.dir.join(&name)).is_ok() {
+        if let Some(entry) = index.entries.remove(&name) {
+          result.removed_count += 1;
+          result.freed_bytes += entry.size;
+        }
+      }
+    }
+    if older_than.is_none() {
+      let _ = fs::remove_dir_all(self.dir.join("temp"));
+      let _ = fs::remove_dir_all(self.dir.join("pages"));
+    }
+    self.save_index(&index)?;
+    Ok(result)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn replaces_outdated_versions_and_evicts_least_recently_used() {
+    let dir = std::env::temp_dir().join(format!("binder_preview_cache_{}", uuid::Uuid::new_v4()));
+    fs::create_dir_all(&dir).unwrap();
+    let source_a = dir.join("a.docx");
+    let source_b = dir.join("b.docx");
+    fs::write(&source_a, b"a").unwrap();
+    fs::write(&source_b, b"b").unwrap();
+    let cache = PreviewCache {
+      dir: dir.clone(),
+      max_bytes: 150,
+    };
+
+    let write_pdf = |name: &str| {
+      let path = dir.join(name);
+      fs::write(&path, vec![0u8; 60]).unwrap();
+      path
+    };
+
+    // 同一源文件的新版本替换旧缓存
+    cache.record(&source_a, &write_pdf("a1.pdf")).unwrap();
+    cache.record(&source_a, &write_pdf("a2.pdf")).unwrap();
+    assert!(!dir.join("a1.pdf").exists());
+
+    // 超过 150 字节时淘汰最久未访问的 a2
+    cache.record(&source_b, &write_pdf("b1.pdf")).unwrap();
+    let mut index = cache.load_index();
+    index.entries.get_mut("a2.pdf").unwrap().last_access = 0;
+    cache.save_index(&index).unwrap();
+    cache
+      .record(&dir.join("c.docx"), &write_pdf("c1.pdf"))
+      .unwrap();
+
+    let info = cache.info().unwrap();
+    let mut files: Vec<&str> = info.entries.iter().map(|e| e.file.as_str()).collect();
+    files.sort();
+    assert_eq!(files, vec!["b1.pdf", "c1.pdf"]);
+    assert_eq!(info.total_bytes, 120);
+
+    let cleared = cache.clear(None).unwrap();
+    assert_eq!(cleared.removed_count, 2);
+    assert_eq!(cleared.freed_bytes, 120);
+    let _ = fs::remove_dir_all(&dir);
+  }
+}
@@ -143,7 +143,7 @@ impl PreviewService {
         if duration > one_hour {
           // 删除缓存目录
           if let Err(e) = std::fs::remove_dir_all(&cached.media_dir) {
-            eprintln!("清理缓存目录失败: {:?}, 错误: {}", cached.media_dir, e);
+            tracing::warn!("清理缓存目录失败: {:?}, 错误: {}", cached.media_dir, e);
           }
           return false;
         }
@@ -160,7 +160,7 @@ impl PreviewService {
     if let Some(cached) = self.cache.remove(&cache_key) {
       // 删除缓存目录
       if let Err(e) = std::fs::remove_dir_all(&cached.media_dir) {
-        eprintln!("清理缓存目录失败: {:?}, 错误: {}", cached.media_dir, e);
+        tracing::warn!("清理缓存目录失败: {:?}, 错误: {}", cached.media_dir, e);
       }
     }
 
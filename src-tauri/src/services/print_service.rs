@@ -0,0 +1,230 @@
+//! 打印：先把文档转换为 PDF（Office 文档经 LibreOffice，Markdown / HTML 经 Pandoc 转 DOCX 后再转 PDF），
+//! 再交给系统打印。
+//!
+//! - macOS / Linux：CUPS 的 `lp` 直接送入默认打印机队列，支持页码范围与份数
+//! - Windows：装有 SumatraPDF 时用它静默打印（支持页码范围与份数），否则调用默认 PDF 程序的“打印”动作（选项由其打印对话框设置）
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use which::which;
+
+use crate::services::export_numbering;
+use crate::services::libreoffice_service::{LibreOfficeService, OfficeDocumentKind};
+use crate::services::pandoc_service::PandocService;
+use crate::services::temp_service;
+
+/// 页码范围形如 `1-3,5,8-10`
+static PAGE_RANGE_PATTERN: Lazy<Regex> =
+  Lazy::new(|| Regex::new(r"^\d+(-\d+)?(,\d+(-\d+)?)*$").unwrap());
+
+const MAX_COPIES: u32 = 99;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PrintOptions {
+  /// 页码范围（从 1 开始），如 `1-3,5`；为空时打印全部页面
+  pub page_range: Option<String>,
+  /// 份数，默认 1
+  pub copies: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrintResult {
+  /// 实际送去打印的 PDF
+  pub pdf_path: String,
+  /// 使用的打印方式（lp / SumatraPDF / shell）
+  pub method: String,
+  /// 页码范围与份数是否已应用（为 false 时需在系统打印对话框中设置）
+  pub options_applied: bool,
+}
+
+impl PrintOptions {
+  /// 校验并规范化：去掉空白，空范围视为全部页面，份数限制在 1..=99
+  fn normalized(&self) -> Result<(Option<String>, u32), String> {
+    let page_range = self
+      .page_range
+      .as_deref()
+      .map(|r| r.chars().filter(|c| !c.is_whitespace()).collect::<String>())
+      .filter(|r| !r.is_empty());
+    if let Some(range) = &page_range {
+      let valid = PAGE_RANGE_PATTERN.is_match(range)
+        && range.split(',').all(|part| {
+          let mut bounds = part.split('-').map(|n| n.parse::<u32>().unwrap_or(0));
+          let start = bounds.next().unwrap_or(0);
+          let end = bounds.next().unwrap_or(start);
+          start >= 1 && end >= start
+        });
+      if !valid {
+        return Err(format!("无效的页码范围: {}（示例: 1-3,5）", range));
+      }
+    }
+    let copies = self.copies.unwrap_or(1);
+    if !(1..=MAX_COPIES).contains(&copies) {
+      return Err(format!("份数必须在 1 到 {} 之间", MAX_COPIES));
+    }
+    Ok((page_range, copies))
+  }
+}
+
+pub struct PrintService;
+
+impl PrintService {
+  /// 转换（如需要）并打印文档
+  pub fn print_document(path: &Path, options: &PrintOptions) -> Result<PrintResult, String> {
+    if !path.is_file() {
+      return Err(format!("文件不存在: {}", path.display()));
+    }
+    let (page_range, copies) = options.normalized()?;
+    let pdf_path = Self::ensure_pdf(path)?;
+    let (method, options_applied) =
+      Self::send_to_printer(&pdf_path, page_range.as_deref(), copies)?;
+    Ok(PrintResult {
+      pdf_path: pdf_path.to_string_lossy().to_string(),
+      method,
+      options_applied,
+    })
+  }
+
+  /// 返回可打印的 PDF：PDF 原样使用，其他格式转换到预览缓存目录
+  pub fn ensure_pdf(path: &Path) -> Result<PathBuf, String> {
+    let ext = path
+      .extension()
+      .and_then(|e| e.to_str())
+      .map(|e| e.to_lowercase())
+      .unwrap_or_default();
+    if ext == "pdf" {
+      return Ok(path.to_path_buf());
+    }
+    if OfficeDocumentKind::from_path(path).is_some() {
+      return LibreOfficeService::new()?.convert_office_to_pdf(path);
+    }
+    if !matches!(ext.as_str(), "md" | "markdown" | "html" | "htm") {
+      return Err(format!("不支持打印该文件类型: {}", path.display()));
+    }
+
+    let pandoc = PandocService::new();
+    if !pandoc.is_available() {
+      return Err("Pandoc 不可用，无法打印该文档。请安装 Pandoc 后重试。".to_string());
+    }
+    let content = std::fs::read_to_string(path).map_err(|e| format!("读取文件失败: {}", e))?;
+    let content = if ext.starts_with("htm") {
+      export_numbering::apply_workspace_numbering(path, &content)
+    } else {
+      content
+    };
+    // 临时 DOCX 沿用原文件名，转换出的 PDF 缓存也以此命名
+    let temp_dir = temp_service::temp_dir("print")?;
+    let docx_path = temp_dir
+      .path()
+      .join(path.with_extension("docx").file_name().unwrap_or_default());
+    let result = if ext.starts_with("htm") {
+      pandoc.convert_html_to_docx(&content, &docx_path)
+    } else {
+      pandoc.convert_markdown_to_docx(&content, &docx_path, None)
+    }
+    .and_then(|_| LibreOfficeService::new()?.convert_docx_to_pdf(&docx_path));
+    drop(temp_dir);
+    result
+  }
+
+  fn send_to_printer(
+    pdf_path: &Path,
+    page_range: Option<&str>,
+    copies: u32,
+  ) -> Result<(String, bool), String> {
+    if cfg!(target_os = "windows") {
+      if let Some(sumatra) = Self::find_sumatra() {
+        let mut settings = format!("{}x", copies);
+        if let Some(range) = page_range {
+          settings = format!("{},{}", range, settings);
+        }
+        Self::run(
+          Command::new(sumatra)
+            .arg("-print-to-default")
+            .arg("-print-settings")
+            .arg(settings)
+            .arg("-silent")
+            .arg(pdf_path.as_os_str()),
+          "SumatraPDF",
+        )?;
+        return Ok(("SumatraPDF".to_string(), true));
+      }
+      // 交给默认 PDF 程序，由其打印对话框设置范围与份数
+      let script = format!(
+        "Start-Process -FilePath '{}' -Verb Print",
+        pdf_path.to_string_lossy().replace('\'', "''")
+      );
+      Self::run(
+        Command::new("powershell")
+          .arg("-NoProfile")
+          .arg("-Command")
+          .arg(script),
+        "PowerShell",
+      )?;
+      return Ok(("shell".to_string(), page_range.is_none() && copies == 1));
+    }
+
+    let lp = which("lp")
+      .ok()
+      .or_else(|| Some(PathBuf::from("/usr/bin/lp")).filter(|p| p.is_file()))
+      .ok_or_else(|| "未检测到打印系统（CUPS 的 lp 命令），无法打印".to_string())?;
+    let mut command = Command::new(lp);
+    command.arg("-n").arg(copies.to_string());
+    if let Some(range) = page_range {
+      command.arg("-o").arg(format!("page-ranges={}", range));
+    }
+    command.arg(pdf_path.as_os_str());
+    Self::run(&mut command, "lp")?;
+    Ok(("lp".to_string(), true))
+  }
+
+  fn find_sumatra() -> Option<PathBuf> {
+    which("SumatraPDF").ok().or_else(|| {
+      let mut candidates = vec![
+        PathBuf::from(r"C:\Program Files\SumatraPDF\SumatraPDF.exe"),
+        PathBuf::from(r"C:\Program Files (x86)\SumatraPDF\SumatraPDF.exe"),
+      ];
+      if let Some(local) = dirs::data_local_dir() {
+        candidates.push(local.join("SumatraPDF").join("SumatraPDF.exe"));
+      }
+      candidates.into_iter().find(|p| p.is_file())
+    })
+  }
+
+  fn run(command: &mut Command, name: &str) -> Result<(), String> {
+    let output = command
+      .output()
+      .map_err(|e| format!("执行 {} 失败: {}", name, e))?;
+    if !output.status.success() {
+      return Err(format!(
+        "打印失败: {}",
+        String::from_utf8_lossy(&output.stderr).trim()
+      ));
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn validates_page_range_and_copies() {
+    let options = |range: &str, copies| PrintOptions {
+      page_range: Some(range.to_string()),
+      copies,
+    };
+    assert_eq!(
+      options(" 1-3, 5 ", Some(2)).normalized().unwrap(),
+      (Some("1-3,5".to_string()), 2)
+    );
+    assert_eq!(options("", None).normalized().unwrap(), (None, 1));
+    for bad in ["0", "3-1", "1-", "a", "1;2"] {
+      assert!(options(bad, None).normalized().is_err(), "{}", bad);
+    }
+    assert!(options("1", Some(0)).normalized().is_err());
+  }
+}
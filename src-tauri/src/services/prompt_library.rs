@@ -0,0 +1,349 @@
+//! 提示词库：工作区 `.binder/prompts.json` 中保存的可复用提示词，带斜杠命令触发词与适用位置
+//! （AI 对话、Inline Assist、选中文字菜单）。
+//!
+//! 模板可使用 `{{selection}}`（当前选中文字）、`{{document}}`（当前文档内容）、文件模板变量
+//! （`{{title}}`、`{{date}}` 等，见 file_templates）以及 `{{snippet:触发词}}` 文本片段。
+
+use crate::services::file_templates::{expand_variables, TemplateContext};
+use crate::services::snippets::{self, SnippetContext};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// 串行化 prompts.json 的读改写
+static STORE_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+const MAX_TRIGGER_CHARS: usize = 32;
+/// `{{document}}` 填入的最大字符数
+const MAX_DOCUMENT_CHARS: usize = 20000;
+
+/// 提示词的适用位置
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PromptTarget {
+  /// AI 对话输入框
+  Chat,
+  /// Inline Assist
+  Inline,
+  /// 选中文字后的操作菜单，渲染时必须有选中文字
+  Selection,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedPrompt {
+  /// 新建时由后端生成
+  #[serde(default)]
+  pub id: String,
+  pub name: String,
+  /// 斜杠命令触发词（不含 `/`），不区分大小写，在工作区内唯一
+  pub trigger: String,
+  pub template: String,
+  pub target: PromptTarget,
+  #[serde(default)]
+  pub description: Option<String>,
+  #[serde(default)]
+  pub updated_at: i64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct PromptsFile {
+  #[serde(default)]
+  prompts: Vec<SavedPrompt>,
+}
+
+/// 渲染时的上下文
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PromptRenderContext {
+  /// 当前文档路径，`{{title}}` 取其文件名
+  pub file_path: Option<String>,
+  pub selection: Option<String>,
+  /// 当前文档内容（编辑器中的最新内容）
+  pub document: Option<String>,
+  /// 额外的变量值，优先于内置变量
+  pub values: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RenderedPrompt {
+  pub id: String,
+  pub name: String,
+  pub target: PromptTarget,
+  pub text: String,
+}
+
+fn prompts_path(workspace_path: &Path) -> PathBuf {
+  workspace_path.join(".binder").join("prompts.json")
+}
+
+fn load_file(workspace_path: &Path) -> Result<PromptsFile, String> {
+  let path = prompts_path(workspace_path);
+  if !path.exists() {
+    return Ok(PromptsFile::default());
+  }
+  let content = fs::read_to_string(&path).map_err(|e| format!("读取提示词库失败: {}", e))?;
+  serde_json::from_str(&content).map_err(|e| format!("解析提示词库失败: {}", e))
+}
+
+fn save_file(workspace_path: &Path, file: &PromptsFile) -> Result<(), String> {
+  let path = prompts_path(workspace_path);
+  if let Some(parent) = path.parent() {
+    fs::create_dir_all(parent).map_err(|e| format!("创建 .binder 目录失败: {}", e))?;
+  }
+  let json =
+    serde_json::to_string_pretty(file).map_err(|e| format!("序列化提示词库失败: {}", e))?;
+  fs::write(&path, json).map_err(|e| format!("写入提示词库失败: {}", e))
+}
+
+/// 清理并校验提示词；触发词去掉前导 `/`
+fn normalize(prompt: &mut SavedPrompt, existing: &[SavedPrompt]) -> Result<(), String> {
+  prompt.name = prompt.name.trim().to_string();
+  prompt.trigger = prompt.trigger.trim().trim_start_matches('/').to_string();
+  prompt.description = prompt.description.take().filter(|d| !d.trim().is_empty());
+  if prompt.name.is_empty() {
+    return Err("提示词名称不能为空".to_string());
+  }
+  if prompt.template.trim().is_empty() {
+    return Err("提示词内容不能为空".to_string());
+  }
+  if prompt.trigger.is_empty() {
+    return Err("斜杠命令不能为空".to_string());
+  }
+  if prompt.trigger.chars().count() > MAX_TRIGGER_CHARS {
+    return Err(format!("斜杠命令不能超过 {} 个字符", MAX_TRIGGER_CHARS));
+  }
+  if prompt
+    .trigger
+    .chars()
+    .any(|c| c.is_whitespace() || c == '/' || c == '{' || c == '}')
+  {
+    return Err(format!(
+      "斜杠命令不能包含空白、斜杠或花括号: {}",
+      prompt.trigger
+    ));
+  }
+  if existing
+    .iter()
+    .any(|p| p.id != prompt.id && p.trigger.eq_ignore_ascii_case(&prompt.trigger))
+  {
+    return Err(format!("斜杠命令 /{} 已被使用", prompt.trigger));
+  }
+  prompt.updated_at = chrono::Utc::now().timestamp_millis();
+  Ok(())
+}
+
+/// 列出提示词（按名称排序）；指定 target 时只返回该位置可用的提示词
+pub fn list_prompts(
+  workspace_path: &Path,
+  target: Option<PromptTarget>,
+) -> Result<Vec<SavedPrompt>, String> {
+  let mut prompts = load_file(workspace_path)?.prompts;
+  prompts.retain(|p| target.map_or(true, |t| p.target == t));
+  prompts.sort_by_key(|p| p.name.to_lowercase());
+  Ok(prompts)
+}
+
+/// 新建提示词，id 由后端生成
+pub fn create_prompt(
+  workspace_path: &Path,
+  mut prompt: SavedPrompt,
+) -> Result<SavedPrompt, String> {
+  let _guard = STORE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+  let mut file = load_file(workspace_path)?;
+  prompt.id = uuid::Uuid::new_v4().to_string();
+  normalize(&mut prompt, &file.prompts)?;
+  file.prompts.push(prompt.clone());
+  save_file(workspace_path, &file)?;
+  Ok(prompt)
+}
+
+/// 按 id 更新提示词
+pub fn update_prompt(
+  workspace_path: &Path,
+  mut prompt: SavedPrompt,
+) -> Result<SavedPrompt, String> {
+  let _guard = STORE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+  let mut file = load_file(workspace_path)?;
+  normalize(&mut prompt, &file.prompts)?;
+  let Some(slot) = file.prompts.iter_mut().find(|p| p.id == prompt.id) else {
+    return Err(format!("提示词不存在: {}", prompt.id));
+  };
+  *slot = prompt.clone();
+  save_file(workspace_path, &file)?;
+  Ok(prompt)
+}
+
+/// 删除提示词，返回是否存在
+pub fn delete_prompt(workspace_path: &Path, id: &str) -> Result<bool, String> {
+  let _guard = STORE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+  let mut file = load_file(workspace_path)?;
+  let before = file.prompts.len();
+  file.prompts.retain(|p| p.id != id);
+  if file.prompts.len() == before {
+    return Ok(false);
+  }
+  save_file(workspace_path, &file)?;
+  Ok(true)
+}
+
+/// 按 id 或斜杠命令（可带 `/`）渲染提示词；不存在时返回 None
+pub fn render_prompt(
+  workspace_path: &Path,
+  key: &str,
+  context: &PromptRenderContext,
+) -> Result<Option<RenderedPrompt>, String> {
+  let key = key.trim();
+  let trigger = key.trim_start_matches('/');
+  let Some(prompt) = load_file(workspace_path)?
+    .prompts
+    .into_iter()
+    .find(|p| p.id == key || p.trigger.eq_ignore_ascii_case(trigger))
+  else {
+    return Ok(None);
+  };
+  let selection = context.selection.clone().unwrap_or_default();
+  if prompt.target == PromptTarget::Selection && selection.trim().is_empty() {
+    return Err(format!("提示词「{}」需要先选中文字", prompt.name));
+  }
+
+  let file = context.file_path.as_deref().unwrap_or_default();
+  let template = TemplateContext::new(workspace_path, Path::new(file));
+  let document: String = context
+    .document
+    .as_deref()
+    .unwrap_or_default()
+    .chars()
+    .take(MAX_DOCUMENT_CHARS)
+    .collect();
+  // 先展开文本片段，再填入变量；填入的选中文字与文档内容不再展开
+  let snippet_context = SnippetContext {
+    file_path: context.file_path.clone(),
+    selection: context.selection.clone(),
+    values: context.values.clone(),
+  };
+  let body =
+    snippets::expand_snippet_references(workspace_path, &prompt.template, &snippet_context);
+  let text = expand_variables(&body, |name| {
+    context.values.get(name).cloned().or_else(|| match name {
+      "selection" => Some(selection.clone()),
+      "document" => Some(document.clone()),
+      _ => template.value(name),
+    })
+  });
+  Ok(Some(RenderedPrompt {
+    id: prompt.id,
+    name: prompt.name,
+    target: prompt.target,
+    text,
+  }))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn workspace() -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("binder_prompts_{}", uuid::Uuid::new_v4()));
+    fs::create_dir_all(&dir).unwrap();
+    dir
+  }
+
+  fn prompt(name: &str, trigger: &str, template: &str, target: PromptTarget) -> SavedPrompt {
+    SavedPrompt {
+      id: String::new(),
+      name: name.to_string(),
+      trigger: trigger.to_string(),
+      template: template.to_string(),
+      target,
+      description: Some(" ".to_string()),
+      updated_at: 0,
+    }
+  }
+
+  #[test]
+  fn creates_updates_and_deletes_prompts() {
+    let ws = workspace();
+    let polish = create_prompt(
+      &ws,
+      prompt(
+        "润色",
+        "/polish",
+        "润色：{{selection}}",
+        PromptTarget::Selection,
+      ),
+    )
+    .unwrap();
+    assert_eq!(polish.trigger, "polish");
+    assert_eq!(polish.description, None);
+    create_prompt(&ws, prompt("周报", "weekly", "写周报", PromptTarget::Chat)).unwrap();
+    assert!(create_prompt(&ws, prompt("重复", "POLISH", "x", PromptTarget::Chat)).is_err());
+    assert!(create_prompt(&ws, prompt("空格", "a b", "x", PromptTarget::Chat)).is_err());
+
+    let mut renamed = polish.clone();
+    renamed.name = "改写".to_string();
+    renamed.trigger = "rewrite".to_string();
+    update_prompt(&ws, renamed).unwrap();
+    let names: Vec<String> = list_prompts(&ws, None)
+      .unwrap()
+      .into_iter()
+      .map(|p| format!("{}:/{}", p.name, p.trigger))
+      .collect();
+    assert_eq!(names, vec!["周报:/weekly", "改写:/rewrite"]);
+    assert_eq!(
+      list_prompts(&ws, Some(PromptTarget::Chat)).unwrap().len(),
+      1
+    );
+
+    let mut missing = polish.clone();
+    missing.id = "missing".to_string();
+    assert!(update_prompt(&ws, missing).is_err());
+    assert!(delete_prompt(&ws, &polish.id).unwrap());
+    assert!(!delete_prompt(&ws, &polish.id).unwrap());
+    let _ = fs::remove_dir_all(&ws);
+  }
+
+  #[test]
+  fn renders_with_selection_document_and_snippets() {
+    let ws = workspace();
+    snippets::save_snippet(
+      &ws,
+      snippets::Snippet {
+        trigger: "tone".to_string(),
+        body: "语气正式".to_string(),
+        description: None,
+        updated_at: 0,
+      },
+      None,
+    )
+    .unwrap();
+    create_prompt(
+      &ws,
+      prompt(
+        "改写",
+        "rewrite",
+        "《{{title}}》中的「{{selection}}」，{{snippet:tone}}，{{extra}}。全文：{{document}}",
+        PromptTarget::Selection,
+      ),
+    )
+    .unwrap();
+
+    let context = PromptRenderContext {
+      file_path: Some("/ws/合同.md".to_string()),
+      selection: Some("{{document}}".to_string()),
+      document: Some("甲乙双方".to_string()),
+      values: HashMap::from([("extra".to_string(), "保留条款编号".to_string())]),
+    };
+    let rendered = render_prompt(&ws, "/REWRITE", &context).unwrap().unwrap();
+    assert_eq!(rendered.target, PromptTarget::Selection);
+    assert_eq!(
+      rendered.text,
+      "《合同》中的「{{document}}」，语气正式，保留条款编号。全文：甲乙双方"
+    );
+    assert!(render_prompt(&ws, "rewrite", &PromptRenderContext::default()).is_err());
+    assert!(render_prompt(&ws, "missing", &context).unwrap().is_none());
+    let _ = fs::remove_dir_all(&ws);
+  }
+}
@@ -0,0 +1,355 @@
+//! AI 校对：让模型以 JSON 返回逐条问题（类别、原文、建议、说明），
+//! 再在正文中定位每条问题的范围，供编辑器逐条接受 / 拒绝。
+//!
+//! 模型给出的位置不可靠，因此只要求它原样摘录有问题的文本，由本模块按顺序在正文中查找。
+
+use serde::{Deserialize, Serialize};
+
+/// 每次请求最多发送的字符数，长文档按行切分为多段依次校对
+pub const PROOFREAD_CHUNK_CHARS: usize = 3000;
+
+/// 问题类别
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProofreadCategory {
+  Spelling,    // 错别字 / 拼写
+  Grammar,     // 语法
+  Punctuation, // 标点
+  Style,       // 用词与文风
+  Clarity,     // 表达不清、冗余
+  Consistency, // 术语、格式前后不一致
+}
+
+impl ProofreadCategory {
+  pub const ALL: [ProofreadCategory; 6] = [
+    ProofreadCategory::Spelling,
+    ProofreadCategory::Grammar,
+    ProofreadCategory::Punctuation,
+    ProofreadCategory::Style,
+    ProofreadCategory::Clarity,
+    ProofreadCategory::Consistency,
+  ];
+
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      ProofreadCategory::Spelling => "spelling",
+      ProofreadCategory::Grammar => "grammar",
+      ProofreadCategory::Punctuation => "punctuation",
+      ProofreadCategory::Style => "style",
+      ProofreadCategory::Clarity => "clarity",
+      ProofreadCategory::Consistency => "consistency",
+    }
+  }
+
+  /// 模型返回的类别名不在列表中时归为 style
+  fn parse(value: &str) -> Self {
+    Self::ALL
+      .into_iter()
+      .find(|c| c.as_str().eq_ignore_ascii_case(value.trim()))
+      .unwrap_or(ProofreadCategory::Style)
+  }
+}
+
+/// 校对选项
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProofreadOptions {
+  /// 检查的问题类别，为空时检查全部
+  pub categories: Vec<ProofreadCategory>,
+  /// 文本语言（如 `zh-CN`、`en`），为空时由模型判断
+  pub language: Option<String>,
+  /// 额外的文风要求（如“正式书面语”）
+  pub style_guide: Option<String>,
+  /// 最多返回的问题数
+  pub max_issues: usize,
+}
+
+impl Default for ProofreadOptions {
+  fn default() -> Self {
+    Self {
+      categories: Vec::new(),
+      language: None,
+      style_guide: None,
+      max_issues: 50,
+    }
+  }
+}
+
+impl ProofreadOptions {
+  fn enabled_categories(&self) -> Vec<ProofreadCategory> {
+    if self.categories.is_empty() {
+      ProofreadCategory::ALL.to_vec()
+    } else {
+      self.categories.clone()
+    }
+  }
+}
+
+/// 一条校对问题；start / end 为正文中的字符偏移
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProofreadIssue {
+  pub id: String,
+  pub start: usize,
+  pub end: usize,
+  pub category: ProofreadCategory,
+  pub original: String,
+  pub suggestion: String,
+  pub explanation: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProofreadResult {
+  pub issues: Vec<ProofreadIssue>,
+  /// 达到 max_issues 后停止，后文未校对或问题未全部返回
+  pub truncated: bool,
+}
+
+/// 待校对的一段正文
+pub struct ProofreadChunk<'a> {
+  pub text: &'a str,
+  /// 该段在全文中的字符偏移
+  pub char_offset: usize,
+}
+
+#[derive(Deserialize)]
+struct RawIssue {
+  #[serde(default)]
+  category: String,
+  #[serde(default)]
+  original: String,
+  #[serde(default)]
+  suggestion: String,
+  #[serde(default)]
+  explanation: String,
+}
+
+#[derive(Deserialize)]
+struct RawResponse {
+  #[serde(default)]
+  issues: Vec<RawIssue>,
+}
+
+pub struct ProofreadService;
+
+impl ProofreadService {
+  /// 按行切分为不超过 PROOFREAD_CHUNK_CHARS 的段（单行超长时单独成段）
+  pub fn split_chunks(content: &str) -> Vec<ProofreadChunk<'_>> {
+    let mut chunks = Vec::new();
+    let (mut start, mut start_chars, mut len_chars) = (0, 0, 0);
+    let mut pos = 0;
+    for line in content.split_inclusive('\n') {
+      let line_chars = line.chars().count();
+      if len_chars > 0 && len_chars + line_chars > PROOFREAD_CHUNK_CHARS {
+        chunks.push(ProofreadChunk {
+          text: &content[start..pos],
+          char_offset: start_chars,
+        });
+        start = pos;
+        start_chars += len_chars;
+        len_chars = 0;
+      }
+      pos += line.len();
+      len_chars += line_chars;
+    }
+    chunks.push(ProofreadChunk {
+      text: &content[start..],
+      char_offset: start_chars,
+    });
+    chunks.retain(|c| !c.text.trim().is_empty());
+    chunks
+  }
+
+  pub fn build_prompt(text: &str, options: &ProofreadOptions) -> String {
+    let categories = options
+      .enabled_categories()
+      .iter()
+      .map(|c| c.as_str())
+      .collect::<Vec<_>>()
+      .join(", ");
+    let mut requirements = vec![
+      "original 必须从原文逐字摘录（包含问题的最短片段，保留原有标点与空格），不要改写".to_string(),
+      "suggestion 是替换 original 的完整文本，只修改有问题的部分".to_string(),
+      "explanation 用一句话说明原因".to_string(),
+      "没有问题时返回空数组，不要为了凑数而修改正确的内容".to_string(),
+      format!("最多返回 {} 条", options.max_issues),
+    ];
+    if let Some(language) = options.language.as_deref().filter(|l| !l.trim().is_empty()) {
+      requirements.push(format!("文本语言：{}", language));
+    }
+    if let Some(style) = options
+      .style_guide
+      .as_deref()
+      .filter(|s| !s.trim().is_empty())
+    {
+      requirements.push(format!("文风要求：{}", style));
+    }
+    let requirements: String = requirements
+      .iter()
+      .enumerate()
+      .map(|(i, r)| format!("{}. {}\n", i + 1, r))
+      .collect();
+    format!(
+      "请校对下面的文本，只找出以下类别的问题：{}。\n要求：\n{}\n\
+       只输出 JSON，不要输出 markdown 或额外说明，格式：\n\
+       {{\"issues\": [{{\"category\": \"grammar\", \"original\": \"原文片段\", \
+       \"suggestion\": \"修改后\", \"explanation\": \"原因\"}}]}}\n\n\
+       文本：\n{}",
+      categories, requirements, text
+    )
+  }
+
+  /// 解析模型返回的 JSON，并在 chunk 中定位每条问题。
+  /// 找不到原文、建议与原文相同、与已有问题重叠或类别未启用的条目被丢弃
+  pub fn parse_response(
+    response: &str,
+    chunk: &ProofreadChunk<'_>,
+    options: &ProofreadOptions,
+  ) -> Result<Vec<ProofreadIssue>, String> {
+    let json = extract_json_object(response)
+      .ok_or_else(|| format!("校对结果不是有效 JSON: {}", preview(response)))?;
+    let raw: RawResponse =
+      serde_json::from_str(json).map_err(|e| format!("校对结果解析失败: {}", e))?;
+    let enabled = options.enabled_categories();
+
+    let mut issues: Vec<ProofreadIssue> = Vec::new();
+    // 按字节定位，按顺序查找：模型一般按原文顺序返回，找不到时再从头查找
+    let mut cursor = 0;
+    let mut taken: Vec<(usize, usize)> = Vec::new();
+    for item in raw.issues {
+      let category = ProofreadCategory::parse(&item.category);
+      if item.original.is_empty()
+        || item.original == item.suggestion
+        || !enabled.contains(&category)
+      {
+        continue;
+      }
+      let found = chunk.text[cursor..]
+        .find(&item.original)
+        .map(|p| p + cursor)
+        .or_else(|| chunk.text.find(&item.original));
+      let Some(start) = found else {
+        tracing::debug!("校对问题在原文中找不到: {}", preview(&item.original));
+        continue;
+      };
+      let end = start + item.original.len();
+      if taken.iter().any(|&(s, e)| start < e && s < end) {
+        continue;
+      }
+      taken.push((start, end));
+      cursor = end;
+
+      let start_chars = chunk.char_offset + chunk.text[..start].chars().count();
+      issues.push(ProofreadIssue {
+        id: String::new(),
+        start: start_chars,
+        end: start_chars + item.original.chars().count(),
+        category,
+        original: item.original,
+        suggestion: item.suggestion,
+        explanation: item.explanation,
+      });
+    }
+    Ok(issues)
+  }
+
+  /// 合并各段结果：按位置排序、编号，并按 max_issues 截断
+  pub fn finish(mut issues: Vec<ProofreadIssue>, options: &ProofreadOptions) -> ProofreadResult {
+    issues.sort_by_key(|issue| issue.start);
+    let truncated = issues.len() > options.max_issues;
+    issues.truncate(options.max_issues);
+    for (index, issue) in issues.iter_mut().enumerate() {
+      issue.id = format!("issue_{}", index + 1);
+    }
+    ProofreadResult { issues, truncated }
+  }
+}
+
+/// 取最外层 `{...}`，忽略 ```json 代码块标记与前后说明
+fn extract_json_object(text: &str) -> Option<&str> {
+  let start = text.find('{')?;
+  let end = text.rfind('}')?;
+  (end > start).then(|| &text[start..=end])
+}
+
+fn preview(text: &str) -> String {
+  text.chars().take(100).collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn locates_issues_by_quoted_original() {
+    let content = "第一行没问题。\n他们在次见面了，我门很高兴。在次";
+    let chunk = ProofreadChunk {
+      text: content,
+      char_offset: 10,
+    };
+    let response = r#"```json
+{"issues": [
+  {"category": "spelling", "original": "在次", "suggestion": "再次", "explanation": "“再次”表示重复"},
+  {"category": "SPELLING", "original": "我门", "suggestion": "我们", "explanation": "错别字"},
+  {"category": "grammar", "original": "不存在的句子", "suggestion": "x", "explanation": ""},
+  {"category": "style", "original": "很高兴", "suggestion": "很高兴", "explanation": "无修改"}
+]}
+```"#;
+    let issues =
+      ProofreadService::parse_response(response, &chunk, &ProofreadOptions::default()).unwrap();
+    assert_eq!(issues.len(), 2);
+    let chars: Vec<char> = content.chars().collect();
+    for issue in &issues {
+      let text: String = chars[issue.start - 10..issue.end - 10].iter().collect();
+      assert_eq!(text, issue.original);
+      assert_eq!(issue.category, ProofreadCategory::Spelling);
+    }
+    // 第一个“在次”是正文中的第一次出现
+    assert_eq!(issues[0].start, 10 + 10);
+
+    let only_grammar = ProofreadOptions {
+      categories: vec![ProofreadCategory::Grammar],
+      ..Default::default()
+    };
+    assert!(
+      ProofreadService::parse_response(response, &chunk, &only_grammar)
+        .unwrap()
+        .is_empty()
+    );
+    assert!(ProofreadService::parse_response("无法校对", &chunk, &only_grammar).is_err());
+  }
+
+  #[test]
+  fn splits_long_content_by_lines_with_char_offsets() {
+    let line = format!("{}\n", "字".repeat(PROOFREAD_CHUNK_CHARS / 2 - 1));
+    let content = line.repeat(3);
+    let chunks = ProofreadService::split_chunks(&content);
+    assert_eq!(chunks.len(), 2);
+    assert_eq!(chunks[0].char_offset, 0);
+    assert_eq!(chunks[1].char_offset, chunks[0].text.chars().count());
+    assert_eq!(chunks.iter().map(|c| c.text).collect::<String>(), content);
+
+    let issues = (0..5)
+      .rev()
+      .map(|i| ProofreadIssue {
+        id: String::new(),
+        start: i,
+        end: i + 1,
+        category: ProofreadCategory::Grammar,
+        original: String::new(),
+        suggestion: String::new(),
+        explanation: String::new(),
+      })
+      .collect();
+    let options = ProofreadOptions {
+      max_issues: 3,
+      ..Default::default()
+    };
+    let result = ProofreadService::finish(issues, &options);
+    assert!(result.truncated);
+    assert_eq!(
+      result.issues.iter().map(|i| i.start).collect::<Vec<_>>(),
+      vec![0, 1, 2]
+    );
+    assert_eq!(result.issues[2].id, "issue_3");
+  }
+}
@@ -0,0 +1,345 @@
+//! 可读性与阅读时长：句长分布、英文 Flesch 指标与中日韩文本的句长指标。
+//!
+//! 文本先经 word_count 去掉标记，句子按 `。！？!?…` 与后跟空白的 `.` 切分，换行也视为句子边界。
+//! 英文按 230 词/分钟、中日韩文字按 400 字/分钟估算阅读时长。
+//!
+//! 中日韩可读性分数为 Binder 的经验公式：`100 − 1.5 × max(平均句长 − 12, 0) − 60 × 长句比例`，
+//! 平均句长按字计，超过 50 字为长句，结果截取到 0–100，越高越易读。
+
+use crate::services::word_count::TextCounts;
+use serde::{Deserialize, Serialize};
+
+const LATIN_WORDS_PER_MINUTE: f64 = 230.0;
+const CJK_CHARS_PER_MINUTE: f64 = 400.0;
+/// 句长分布的分段上限（词），最后一段为超过 40 词
+const SENTENCE_BUCKETS: [usize; 4] = [10, 20, 30, 40];
+/// 长句阈值：中日韩句子按字计，其余按词计
+const LONG_CJK_SENTENCE: usize = 50;
+const LONG_LATIN_SENTENCE: usize = 25;
+const LONGEST_SENTENCES: usize = 3;
+const SENTENCE_PREVIEW_CHARS: usize = 80;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LengthBucket {
+  pub min: usize,
+  /// None 表示不设上限
+  pub max: Option<usize>,
+  pub count: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SentenceLengthStats {
+  /// 平均 / 中位 / 最长句长（词，中日韩文字按字计）
+  pub average: f64,
+  pub median: f64,
+  pub max: usize,
+  pub buckets: Vec<LengthBucket>,
+  pub long_sentences: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SentencePreview {
+  /// 句子开头（最多 80 字符）
+  pub text: String,
+  pub words: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TextLanguage {
+  Cjk,
+  Latin,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReadabilityReport {
+  pub words: usize,
+  pub cjk_characters: usize,
+  pub sentences: usize,
+  pub paragraphs: usize,
+  pub reading_time_seconds: u64,
+  /// 向上取整的分钟数，有内容时至少为 1
+  pub reading_time_minutes: u64,
+  pub sentence_length: SentenceLengthStats,
+  pub longest_sentences: Vec<SentencePreview>,
+  /// 英文 Flesch Reading Ease（0–100，越高越易读），没有英文句子时为 None
+  pub flesch_reading_ease: Option<f64>,
+  /// 英文 Flesch-Kincaid 年级
+  pub flesch_kincaid_grade: Option<f64>,
+  /// 中日韩可读性分数（0–100），没有中日韩句子时为 None
+  pub cjk_readability: Option<f64>,
+  /// 主要语言：中日韩文字多于英文词时为 cjk
+  pub language: TextLanguage,
+  /// 主要语言对应的分数
+  pub score: Option<f64>,
+}
+
+struct Sentence<'a> {
+  text: &'a str,
+  counts: TextCounts,
+}
+
+impl Sentence<'_> {
+  fn is_cjk(&self) -> bool {
+    self.counts.cjk_characters * 2 > self.counts.words
+  }
+
+  fn is_long(&self) -> bool {
+    if self.is_cjk() {
+      self.counts.words > LONG_CJK_SENTENCE
+    } else {
+      self.counts.words > LONG_LATIN_SENTENCE
+    }
+  }
+}
+
+fn is_closing(ch: char) -> bool {
+  matches!(
+    ch,
+    '"' | '\'' | '”' | '’' | '」' | '』' | ')' | '）' | '》' | ']'
+  )
+}
+
+/// 切分句子：`.` 只在其后为空白或文本结尾时断句，句末的引号与括号归入前一句
+fn split_sentences(text: &str) -> Vec<&str> {
+  let chars: Vec<(usize, char)> = text.char_indices().collect();
+  let mut sentences = Vec::new();
+  let mut start = 0;
+  let mut i = 0;
+  while i < chars.len() {
+    let (_, ch) = chars[i];
+    let next = chars.get(i + 1).map(|&(_, c)| c);
+    let ends = match ch {
+      '\n' => true,
+      '。' | '！' | '？' | '!' | '?' | '…' => true,
+      '.' => next.map_or(true, |c| c.is_whitespace() || is_closing(c)),
+      _ => false,
+    };
+    if ends {
+      // 连续的终止符（`?!`、`……`）与随后的右引号、右括号并入本句
+      let mut end = i + 1;
+      while end < chars.len()
+        && (matches!(chars[end].1, '。' | '！' | '？' | '!' | '?' | '…' | '.')
+          || is_closing(chars[end].1))
+      {
+        end += 1;
+      }
+      let end_byte = chars.get(end).map_or(text.len(), |&(b, _)| b);
+      sentences.push(&text[start..end_byte]);
+      start = end_byte;
+      i = end;
+      continue;
+    }
+    i += 1;
+  }
+  sentences.push(&text[start..]);
+  sentences
+    .into_iter()
+    .map(str::trim)
+    .filter(|s| TextCounts::of(s).words > 0)
+    .collect()
+}
+
+/// 英文单词音节数的近似：元音组数，去掉词尾不发音的 e，至少为 1
+fn syllables(word: &str) -> usize {
+  let word = word.to_ascii_lowercase();
+  let mut count = 0;
+  let mut previous_vowel = false;
+  for ch in word.chars() {
+    let vowel = matches!(ch, 'a' | 'e' | 'i' | 'o' | 'u' | 'y');
+    if vowel && !previous_vowel {
+      count += 1;
+    }
+    previous_vowel = vowel;
+  }
+  if count > 1 && word.ends_with('e') && !word.ends_with("le") {
+    count -= 1;
+  }
+  count.max(1)
+}
+
+fn latin_words(text: &str) -> impl Iterator<Item = &str> {
+  text
+    .split(|c: char| !c.is_ascii_alphabetic() && c != '\'')
+    .filter(|w| w.chars().any(|c| c.is_ascii_alphabetic()))
+}
+
+fn median(sorted: &[usize]) -> f64 {
+  match sorted.len() {
+    0 => 0.0,
+    n if n % 2 == 1 => sorted[n / 2] as f64,
+    n => (sorted[n / 2 - 1] + sorted[n / 2]) as f64 / 2.0,
+  }
+}
+
+fn round1(value: f64) -> f64 {
+  (value * 10.0).round() / 10.0
+}
+
+fn sentence_length_stats(sentences: &[Sentence]) -> SentenceLengthStats {
+  let mut lengths: Vec<usize> = sentences.iter().map(|s| s.counts.words).collect();
+  lengths.sort_unstable();
+  let mut buckets = Vec::new();
+  let mut min = 1;
+  for max in SENTENCE_BUCKETS.into_iter().map(Some).chain([None]) {
+    buckets.push(LengthBucket {
+      min,
+      max,
+      count: lengths
+        .iter()
+        .filter(|&&len| len >= min && max.map_or(true, |max| len <= max))
+        .count(),
+    });
+    min = max.unwrap_or(0) + 1;
+  }
+  SentenceLengthStats {
+    average: if lengths.is_empty() {
+      0.0
+    } else {
+      round1(lengths.iter().sum::<usize>() as f64 / lengths.len() as f64)
+    },
+    median: median(&lengths),
+    max: lengths.last().copied().unwrap_or(0),
+    buckets,
+    long_sentences: sentences.iter().filter(|s| s.is_long()).count(),
+  }
+}
+
+/// Flesch Reading Ease 与 Flesch-Kincaid 年级，只统计以英文为主的句子
+fn flesch_scores(sentences: &[&Sentence]) -> Option<(f64, f64)> {
+  let words: Vec<&str> = sentences.iter().flat_map(|s| latin_words(s.text)).collect();
+  if sentences.is_empty() || words.is_empty() {
+    return None;
+  }
+  let words_per_sentence = words.len() as f64 / sentences.len() as f64;
+  let syllables_per_word =
+    words.iter().map(|w| syllables(w)).sum::<usize>() as f64 / words.len() as f64;
+  let ease = 206.835 - 1.015 * words_per_sentence - 84.6 * syllables_per_word;
+  let grade = 0.39 * words_per_sentence + 11.8 * syllables_per_word - 15.59;
+  Some((round1(ease.clamp(0.0, 100.0)), round1(grade.max(0.0))))
+}
+
+/// 中日韩可读性分数（见模块说明）
+fn cjk_score(sentences: &[&Sentence]) -> Option<f64> {
+  if sentences.is_empty() {
+    return None;
+  }
+  let total = sentences.len() as f64;
+  let average = sentences.iter().map(|s| s.counts.words).sum::<usize>() as f64 / total;
+  let long_ratio = sentences.iter().filter(|s| s.is_long()).count() as f64 / total;
+  let score = 100.0 - 1.5 * (average - 12.0).max(0.0) - 60.0 * long_ratio;
+  Some(round1(score.clamp(0.0, 100.0)))
+}
+
+/// 分析纯文本（已去掉标记）
+pub fn analyze_text(text: &str) -> ReadabilityReport {
+  let totals = TextCounts::of(text);
+  let sentences: Vec<Sentence> = split_sentences(text)
+    .into_iter()
+    .map(|text| Sentence {
+      text,
+      counts: TextCounts::of(text),
+    })
+    .collect();
+
+  let latin = totals.words - totals.cjk_characters;
+  let seconds = (latin as f64 / LATIN_WORDS_PER_MINUTE
+    + totals.cjk_characters as f64 / CJK_CHARS_PER_MINUTE)
+    * 60.0;
+  let reading_time_seconds = seconds.round() as u64;
+  let reading_time_minutes = if totals.words == 0 {
+    0
+  } else {
+    (seconds / 60.0).ceil().max(1.0) as u64
+  };
+
+  let mut longest: Vec<&Sentence> = sentences.iter().collect();
+  longest.sort_by_key(|s| std::cmp::Reverse(s.counts.words));
+  let longest_sentences = longest
+    .iter()
+    .take(LONGEST_SENTENCES)
+    .map(|s| SentencePreview {
+      text: s.text.chars().take(SENTENCE_PREVIEW_CHARS).collect(),
+      words: s.counts.words,
+    })
+    .collect();
+
+  let (cjk_sentences, latin_sentences): (Vec<&Sentence>, Vec<&Sentence>) =
+    sentences.iter().partition(|s| s.is_cjk());
+  let flesch = flesch_scores(&latin_sentences);
+  let cjk_readability = cjk_score(&cjk_sentences);
+  let language = if totals.cjk_characters >= latin && totals.cjk_characters > 0 {
+    TextLanguage::Cjk
+  } else {
+    TextLanguage::Latin
+  };
+
+  ReadabilityReport {
+    words: totals.words,
+    cjk_characters: totals.cjk_characters,
+    sentences: sentences.len(),
+    paragraphs: totals.paragraphs,
+    reading_time_seconds,
+    reading_time_minutes,
+    sentence_length: sentence_length_stats(&sentences),
+    longest_sentences,
+    flesch_reading_ease: flesch.map(|(ease, _)| ease),
+    flesch_kincaid_grade: flesch.map(|(_, grade)| grade),
+    cjk_readability,
+    language,
+    score: match language {
+      TextLanguage::Cjk => cjk_readability,
+      TextLanguage::Latin => flesch.map(|(ease, _)| ease),
+    },
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn splits_sentences_on_cjk_and_latin_terminators() {
+    let text = "今天天气很好。你去吗？“去！”\nVersion 2.5 is out. Really?! Yes…… 好的";
+    assert_eq!(
+      split_sentences(text),
+      vec![
+        "今天天气很好。",
+        "你去吗？",
+        "“去！”",
+        "Version 2.5 is out.",
+        "Really?!",
+        "Yes……",
+        "好的",
+      ]
+    );
+    assert_eq!(syllables("readability"), 5);
+    assert_eq!(syllables("make"), 1);
+    assert_eq!(syllables("table"), 2);
+  }
+
+  #[test]
+  fn scores_english_and_cjk_text() {
+    let english = analyze_text("The cat sat on the mat. It was a good day. We all went home.");
+    assert_eq!(english.sentences, 3);
+    assert_eq!(english.language, TextLanguage::Latin);
+    assert!(english.flesch_reading_ease.unwrap() > 90.0);
+    assert!(english.flesch_kincaid_grade.unwrap() < 3.0);
+    assert_eq!(english.cjk_readability, None);
+    assert_eq!(english.reading_time_minutes, 1);
+    assert_eq!(english.sentence_length.buckets[0].count, 3);
+
+    let long = "这".repeat(60);
+    let chinese = analyze_text(&format!("第一句很短。{}。", long));
+    assert_eq!(chinese.language, TextLanguage::Cjk);
+    assert_eq!(chinese.sentences, 2);
+    assert_eq!(chinese.sentence_length.max, 60);
+    assert_eq!(chinese.sentence_length.long_sentences, 1);
+    // 平均 32.5 字、长句一半：100 − 30.75 − 30
+    assert_eq!(chinese.cjk_readability, Some(39.3));
+    assert_eq!(chinese.score, chinese.cjk_readability);
+    assert_eq!(chinese.flesch_reading_ease, None);
+    assert_eq!(chinese.longest_sentences[0].words, 60);
+    assert_eq!(chinese.reading_time_seconds, 10);
+  }
+}
@@ -0,0 +1,148 @@
+//! 扫描版 PDF 理解：逐页取得文本后交给 AI 分析（总结、关键词、引用、实体）。
+//!
+//! 有文本层的页面直接使用提取的文本；没有文本层的页面用 `PdfPageRenderer` 渲染为图片
+//! （复用预览的页面缓存）后交给 Tesseract 识别。逐页处理，便于汇报进度与中途取消。
+
+use crate::services::ocr_service::OcrService;
+use crate::services::pdf_page_renderer::PdfPageRenderer;
+use crate::services::pdf_text::PdfPageText;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// OCR 渲染宽度（像素），约相当于 A4 页面 300 DPI
+pub const SCANNED_PDF_OCR_WIDTH: u32 = 2400;
+/// 文本层少于该字符数时视为扫描页（页眉页码之类的零星文字不算正文）
+const MIN_TEXT_LAYER_CHARS: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PageSource {
+  TextLayer,
+  Ocr,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScannedPage {
+  /// 页码（从 1 开始）
+  pub page: u32,
+  pub text: String,
+  pub source: PageSource,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScannedPdfAnalysis {
+  pub path: String,
+  pub analysis_type: String,
+  /// AI 分析结果（Markdown 或 JSON 字符串，取决于分析类型）
+  pub analysis: String,
+  /// OCR 语言，没有页面需要识别时为空
+  pub lang: Option<String>,
+  pub page_count: u32,
+  pub pages: Vec<ScannedPage>,
+  /// 超出页数上限，只分析了前面的页面
+  pub truncated: bool,
+}
+
+/// 文本层是否足以代替 OCR
+pub fn has_text_layer(page: &PdfPageText) -> bool {
+  page.error.is_none()
+    && !page.layout.needs_ocr
+    && page.text.chars().filter(|c| !c.is_whitespace()).count() >= MIN_TEXT_LAYER_CHARS
+}
+
+/// 取得单页文本：有文本层时直接使用，否则渲染后识别
+pub fn page_text(
+  renderer: &PdfPageRenderer,
+  ocr: &OcrService,
+  pdf_path: &Path,
+  page: u32,
+  text_layer: Option<&PdfPageText>,
+  lang: &str,
+) -> Result<ScannedPage, String> {
+  if let Some(layer) = text_layer.filter(|l| has_text_layer(l)) {
+    return Ok(ScannedPage {
+      page,
+      text: layer.text.trim().to_string(),
+      source: PageSource::TextLayer,
+    });
+  }
+  let image = renderer.render_page(pdf_path, page, SCANNED_PDF_OCR_WIDTH, false)?;
+  let output = ocr
+    .recognize_image(&image, Some(lang))
+    .map_err(|e| format!("第 {} 页识别失败: {}", page, e))?;
+  Ok(ScannedPage {
+    page,
+    text: output.text,
+    source: PageSource::Ocr,
+  })
+}
+
+/// 交给 AI 的全文：按页标注页码，跳过空白页
+pub fn combined_text(pages: &[ScannedPage]) -> String {
+  pages
+    .iter()
+    .filter(|p| !p.text.trim().is_empty())
+    .map(|p| format!("[第 {} 页]\n{}", p.page, p.text.trim()))
+    .collect::<Vec<_>>()
+    .join("\n\n")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::services::pdf_text::PdfPageLayout;
+
+  fn layer(text: &str, needs_ocr: bool) -> PdfPageText {
+    PdfPageText {
+      page: 1,
+      text: text.to_string(),
+      layout: PdfPageLayout {
+        width: 595.0,
+        height: 842.0,
+        rotation: 0,
+        line_count: 1,
+        paragraph_count: 1,
+        needs_ocr,
+      },
+      error: None,
+    }
+  }
+
+  #[test]
+  fn uses_text_layer_only_with_enough_text() {
+    assert!(has_text_layer(&layer(
+      "本合同由甲乙双方于二〇二四年签订，约定以下条款。",
+      false
+    )));
+    assert!(!has_text_layer(&layer("  第 3 页  ", false)));
+    assert!(!has_text_layer(&layer("", true)));
+    let mut failed = layer("本合同由甲乙双方于二〇二四年签订，约定以下条款。", false);
+    failed.error = Some("解析失败".to_string());
+    assert!(!has_text_layer(&failed));
+  }
+
+  #[test]
+  fn combines_pages_with_numbers() {
+    let pages = vec![
+      ScannedPage {
+        page: 1,
+        text: " 甲方：某公司\n".to_string(),
+        source: PageSource::Ocr,
+      },
+      ScannedPage {
+        page: 2,
+        text: "  \n".to_string(),
+        source: PageSource::Ocr,
+      },
+      ScannedPage {
+        page: 3,
+        text: "乙方：某个人".to_string(),
+        source: PageSource::TextLayer,
+      },
+    ];
+    assert_eq!(
+      combined_text(&pages),
+      "[第 1 页]\n甲方：某公司\n\n[第 3 页]\n乙方：某个人"
+    );
+  }
+}
@@ -1,12 +1,25 @@
-use crate::utils::error_helpers::{db_lock_error, get_current_timestamp, time_error};
+use crate::services::workspace_settings::{SearchSettings, WorkspaceSettings};
+use crate::utils::error_helpers::{db_lock_error, get_current_timestamp};
+use crate::utils::glob_patterns::{build_glob_set, relative_slash_path};
+use globset::GlobSet;
+use once_cell::sync::Lazy;
+use regex::Regex;
 use rusqlite::{params, Connection, Result as SqlResult};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
+use walkdir::WalkDir;
+
+static HTML_HEADING_RE: Lazy<Regex> =
+  Lazy::new(|| Regex::new(r"(?is)<h[1-6][^>]*>(.*?)</h[1-6]>").unwrap());
+static HTML_TAG_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?s)<[^>]+>").unwrap());
 
 pub struct SearchService {
   db: Arc<Mutex<Connection>>,
   workspace_path: PathBuf,
+  settings: SearchSettings,
+  exclude: GlobSet,
 }
 
 impl SearchService {
@@ -25,24 +38,28 @@ impl SearchService {
 
     let conn = Connection::open(&db_path)?;
 
-    // 创建 FTS5 虚拟表用于全文搜索
-    conn.execute(
-      "CREATE VIRTUAL TABLE IF NOT EXISTS documents_fts USING fts5(
-                path UNINDEXED,
-                title,
-                content,
-                content_tokenize='unicode61 remove_diacritics=2'
-            )",
-      [],
-    )?;
-
     // 创建文档元数据表
     conn.execute(
       "CREATE TABLE IF NOT EXISTS documents (
                 path TEXT PRIMARY KEY,
                 title TEXT,
                 modified_time INTEGER,
-                indexed_time INTEGER
+                indexed_time INTEGER,
+                body_length INTEGER NOT NULL DEFAULT 0
+            )",
+      [],
+    )?;
+
+    Self::migrate_schema(&conn)?;
+
+    // 创建 FTS5 虚拟表用于全文搜索（标题、小标题、正文分列，便于分字段加权）
+    conn.execute(
+      "CREATE VIRTUAL TABLE IF NOT EXISTS documents_fts USING fts5(
+                path UNINDEXED,
+                title,
+                headings,
+                content,
+                tokenize='unicode61 remove_diacritics 2'
             )",
       [],
     )?;
@@ -53,29 +70,107 @@ impl SearchService {
       [],
     )?;
 
+    let settings = WorkspaceSettings::load_or_default(workspace_path).search;
+    let exclude = build_glob_set(&settings.exclude_patterns).unwrap_or_else(|e| {
+      tracing::debug!("{}，忽略索引排除规则", e);
+      GlobSet::empty()
+    });
+
     Ok(Self {
       db: Arc::new(Mutex::new(conn)),
       workspace_path: workspace_path.to_path_buf(),
+      settings,
+      exclude,
     })
   }
 
-  /// 索引或更新文档
-  pub fn index_document(&self, path: &Path, content: &str) -> SqlResult<()> {
+  /// 路径是否命中工作区设置中的索引排除规则
+  pub fn is_excluded(&self, path: &Path) -> bool {
+    if self.exclude.is_empty() {
+      return false;
+    }
+    self
+      .exclude
+      .is_match(relative_slash_path(path, &self.workspace_path))
+  }
+
+  /// 删除已索引但命中排除规则的文档，返回删除数量
+  pub fn purge_excluded_documents(&self) -> SqlResult<usize> {
+    if self.exclude.is_empty() {
+      return Ok(0);
+    }
     let conn = self.db.lock().map_err(db_lock_error)?;
 
-    // 获取文件的相对路径
-    let relative_path = path
-      .strip_prefix(&self.workspace_path)
-      .unwrap_or(path)
-      .to_string_lossy()
-      .to_string();
+    let mut stmt = conn.prepare("SELECT path FROM documents")?;
+    let paths = stmt
+      .query_map([], |row| row.get::<_, String>(0))?
+      .collect::<SqlResult<Vec<_>>>()?;
+
+    let mut purged = 0;
+    for relative_path in paths {
+      if self.exclude.is_match(relative_path.replace('\\', "/")) {
+        conn.execute(
+          "DELETE FROM documents WHERE path = ?1",
+          params![relative_path],
+        )?;
+        conn.execute(
+          "DELETE FROM documents_fts WHERE path = ?1",
+          params![relative_path],
+        )?;
+        purged += 1;
+      }
+    }
+
+    Ok(purged)
+  }
+
+  /// 旧版索引没有 headings 列和 body_length 字段：重建 FTS 表并清空元数据，
+  /// 让下一次 build_index_async 全量重新索引
+  fn migrate_schema(conn: &Connection) -> SqlResult<()> {
+    let has_body_length = conn
+      .prepare("SELECT name FROM pragma_table_info('documents')")?
+      .query_map([], |row| row.get::<_, String>(0))?
+      .filter_map(|name| name.ok())
+      .any(|name| name == "body_length");
+    if !has_body_length {
+      conn.execute(
+        "ALTER TABLE documents ADD COLUMN body_length INTEGER NOT NULL DEFAULT 0",
+        [],
+      )?;
+    }
+
+    let fts_sql: Option<String> = conn
+      .query_row(
+        "SELECT sql FROM sqlite_master WHERE type = 'table' AND name = 'documents_fts'",
+        [],
+        |row| row.get(0),
+      )
+      .ok();
+    let needs_rebuild = match fts_sql {
+      Some(sql) => !sql.contains("headings"),
+      None => false,
+    };
+    if needs_rebuild || !has_body_length {
+      conn.execute("DROP TABLE IF EXISTS documents_fts", [])?;
+      conn.execute("DELETE FROM documents", [])?;
+    }
 
-    // 提取标题（文件名的第一行或文件名）
-    let title = Path::new(&relative_path)
+    Ok(())
+  }
+
+  fn upsert_document(
+    conn: &Connection,
+    relative_path: &str,
+    path: &Path,
+    content: &str,
+  ) -> SqlResult<()> {
+    // 提取标题（文件名）
+    let title = Path::new(relative_path)
       .file_stem()
       .and_then(|s| s.to_str())
-      .unwrap_or(&relative_path)
+      .unwrap_or(relative_path)
       .to_string();
+    let headings = extract_headings(path, content);
 
     let modified_time = path
       .metadata()
@@ -86,24 +181,54 @@ impl SearchService {
       .unwrap_or(0);
 
     let indexed_time = get_current_timestamp()?;
+    let body_length = content.chars().count() as i64;
 
     // 更新或插入文档元数据
     conn.execute(
-      "INSERT OR REPLACE INTO documents (path, title, modified_time, indexed_time)
-             VALUES (?1, ?2, ?3, ?4)",
-      params![relative_path, title, modified_time, indexed_time],
+      "INSERT OR REPLACE INTO documents (path, title, modified_time, indexed_time, body_length)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+      params![
+        relative_path,
+        title,
+        modified_time,
+        indexed_time,
+        body_length
+      ],
     )?;
 
-    // 更新或插入 FTS5 索引
+    // FTS5 表没有主键，先删除旧记录再插入，避免重复
+    conn.execute(
+      "DELETE FROM documents_fts WHERE path = ?1",
+      params![relative_path],
+    )?;
     conn.execute(
-      "INSERT OR REPLACE INTO documents_fts (path, title, content)
-             VALUES (?1, ?2, ?3)",
-      params![relative_path, title, content],
+      "INSERT INTO documents_fts (path, title, headings, content)
+             VALUES (?1, ?2, ?3, ?4)",
+      params![relative_path, title, headings, content],
     )?;
 
     Ok(())
   }
 
+  /// 索引或更新文档；命中排除规则的文档不索引，并清除已有的旧索引
+  pub fn index_document(&self, path: &Path, content: &str) -> SqlResult<()> {
+    if self.is_excluded(path) {
+      return self.remove_document(path);
+    }
+    let conn = self.db.lock().map_err(db_lock_error)?;
+
+    // 获取文件的相对路径
+    let relative_path = path
+      .strip_prefix(&self.workspace_path)
+      .unwrap_or(path)
+      .to_string_lossy()
+      .to_string();
+
+    Self::upsert_document(&conn, &relative_path, path, content)?;
+
+    Ok(())
+  }
+
   /// 删除文档索引
   pub fn remove_document(&self, path: &Path) -> SqlResult<()> {
     let conn = self.db.lock().map_err(db_lock_error)?;
@@ -127,36 +252,105 @@ impl SearchService {
   }
 
   /// 全文搜索
+  ///
+  /// 先用 FTS5 的加权 bm25 取出候选集，再按工作区设置中的 BM25 参数、字段权重
+  /// 和最近编辑加权重新打分。返回的 rank 越小越相关（与 FTS5 rank 约定一致）。
   pub fn search(&self, query: &str, limit: usize) -> SqlResult<Vec<SearchResult>> {
     let conn = self.db.lock().map_err(db_lock_error)?;
-
-    // 使用 FTS5 的 MATCH 语法进行搜索
-    let sql = format!(
-      "SELECT path, title, 
-                    snippet(documents_fts, 2, '<mark>', '</mark>', '...', 64) as snippet,
-                    rank
+    let settings = &self.settings;
+    let candidate_limit = (limit * 5).max(50);
+
+    let mut stmt = conn.prepare(
+      "SELECT documents_fts.path, documents_fts.title,
+                    snippet(documents_fts, 3, '<mark>', '</mark>', '...', 64),
+                    documents_fts.headings, documents_fts.content,
+                    bm25(documents_fts, 0.0, ?3, ?4, ?5) AS score,
+                    COALESCE(documents.modified_time, 0),
+                    COALESCE(documents.body_length, 0)
              FROM documents_fts
+             LEFT JOIN documents ON documents.path = documents_fts.path
              WHERE documents_fts MATCH ?1
-             ORDER BY rank
-             LIMIT ?2"
-    );
-
-    let mut stmt = conn.prepare(&sql)?;
-    let rows = stmt.query_map(params![query, limit as i64], |row| {
-      Ok(SearchResult {
-        path: row.get(0)?,
-        title: row.get(1)?,
-        snippet: row.get(2)?,
-        rank: row.get(3)?,
-      })
-    })?;
+             ORDER BY score
+             LIMIT ?2",
+    )?;
+    let rows = stmt.query_map(
+      params![
+        query,
+        candidate_limit as i64,
+        settings.title_boost,
+        settings.headings_boost,
+        settings.body_boost
+      ],
+      |row| {
+        Ok(RankCandidate {
+          result: SearchResult {
+            path: row.get(0)?,
+            title: row.get(1)?,
+            snippet: row.get(2)?,
+            rank: row.get(5)?,
+          },
+          headings: row.get(3)?,
+          content: row.get(4)?,
+          modified_time: row.get(6)?,
+          body_length: row.get(7)?,
+        })
+      },
+    )?;
 
-    let mut results = Vec::new();
+    let mut candidates = Vec::new();
     for row in rows {
-      results.push(row?);
+      candidates.push(row?);
     }
 
-    Ok(results)
+    let terms = query_terms(query);
+    if !terms.is_empty() && !candidates.is_empty() {
+      let (doc_count, avg_body_length): (i64, f64) = conn.query_row(
+        "SELECT COUNT(*), COALESCE(AVG(body_length), 0) FROM documents",
+        [],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+      )?;
+      let mut doc_freq = HashMap::new();
+      for term in &terms {
+        let df: i64 = conn.query_row(
+          "SELECT COUNT(*) FROM documents_fts WHERE documents_fts MATCH ?1",
+          params![format!("\"{}\"", term.replace('"', "\"\""))],
+          |row| row.get(0),
+        )?;
+        doc_freq.insert(term.clone(), df);
+      }
+
+      let now = get_current_timestamp()?;
+      let stats = CorpusStats {
+        doc_count: doc_count.max(1) as f64,
+        avg_body_length: avg_body_length.max(1.0),
+        doc_freq,
+        now,
+      };
+      for candidate in &mut candidates {
+        candidate.result.rank = -score_candidate(candidate, &terms, &stats, settings);
+      }
+    }
+
+    candidates.sort_by(|a, b| {
+      a.result
+        .rank
+        .partial_cmp(&b.result.rank)
+        .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    candidates.truncate(limit);
+
+    Ok(candidates.into_iter().map(|c| c.result).collect())
+  }
+
+  /// 已索引的正文（path 为 search 返回的相对路径），未索引时返回 None
+  pub fn document_content(&self, path: &str) -> SqlResult<Option<String>> {
+    let conn = self.db.lock().map_err(db_lock_error)?;
+    let mut stmt = conn.prepare("SELECT content FROM documents_fts WHERE path = ?1 LIMIT 1")?;
+    let mut rows = stmt.query(params![path])?;
+    match rows.next()? {
+      Some(row) => row.get(0),
+      None => Ok(None),
+    }
   }
 
   /// 检查文档是否需要重新索引
@@ -217,6 +411,51 @@ impl SearchService {
     Ok(deleted_count)
   }
 
+  /// 同步只读参考资料文件夹的索引。参考资料位于工作区外，索引中以绝对路径保存；
+  /// 已不在任何参考资料文件夹中的绝对路径文档会被移除。返回本次（重新）索引的文档数
+  pub fn sync_reference_folders(&self, roots: &[PathBuf]) -> SqlResult<usize> {
+    {
+      let conn = self.db.lock().map_err(db_lock_error)?;
+      let paths = conn
+        .prepare("SELECT path FROM documents")?
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<SqlResult<Vec<_>>>()?;
+      for path in paths {
+        let stale = Path::new(&path).is_absolute()
+          && !roots.iter().any(|root| Path::new(&path).starts_with(root));
+        if stale {
+          conn.execute("DELETE FROM documents WHERE path = ?1", params![path])?;
+          conn.execute("DELETE FROM documents_fts WHERE path = ?1", params![path])?;
+        }
+      }
+    }
+
+    let mut indexed = 0;
+    let mut updates = Vec::new();
+    for root in roots {
+      let entries = WalkDir::new(root)
+        .into_iter()
+        .filter_entry(|e| e.depth() == 0 || !e.file_name().to_string_lossy().starts_with('.'))
+        .flatten();
+      for entry in entries {
+        let path = entry.path();
+        if !entry.file_type().is_file() || !self.should_index(path)? {
+          continue;
+        }
+        if let Ok(content) = std::fs::read_to_string(path) {
+          updates.push((path.to_path_buf(), content));
+        }
+        if updates.len() >= 50 {
+          indexed += updates.len();
+          self.batch_update_index(std::mem::take(&mut updates))?;
+        }
+      }
+    }
+    indexed += updates.len();
+    self.batch_update_index(updates)?;
+    Ok(indexed)
+  }
+
   // ⚠️ Week 19.1：批量索引更新（提高性能）
   pub fn batch_update_index(
     &self,
@@ -233,43 +472,68 @@ impl SearchService {
         .to_string_lossy()
         .to_string();
 
-      // 提取标题
-      let title = Path::new(&relative_path)
-        .file_stem()
-        .and_then(|s| s.to_str())
-        .unwrap_or(&relative_path)
-        .to_string();
-
-      let modified_time = path
-        .metadata()
-        .and_then(|m| m.modified())
-        .map(|t| t.duration_since(std::time::UNIX_EPOCH).unwrap().as_secs() as i64)
-        .unwrap_or(0);
+      Self::upsert_document(&tx, &relative_path, &path, &content)?;
+    }
 
-      let indexed_time = get_current_timestamp()?;
+    tx.commit()?;
+    Ok(())
+  }
 
-      // 使用 UPSERT 避免重复
-      tx.execute(
-        "INSERT OR REPLACE INTO documents (path, title, modified_time, indexed_time)
-                 VALUES (?1, ?2, ?3, ?4)",
-        params![relative_path, title, modified_time, indexed_time],
-      )?;
+  /// 遍历工作区，为新增或修改过的文本文件建立索引，返回本次索引的文件数
+  ///
+  /// 每读取一个文件回调一次已索引数量，回调返回 false 时停止（已读取的文件仍会写入索引）。
+  /// 单个批次写入失败只记录警告，不中止整体构建。
+  #[tracing::instrument(skip_all, fields(workspace = %self.workspace_path.display()))]
+  pub fn build_index(&self, mut on_progress: impl FnMut(usize) -> bool) -> usize {
+    let mut updates = Vec::new();
+    let mut count = 0;
+
+    tracing::debug!("开始构建索引: {}", self.workspace_path.display());
+
+    // 排除规则命中的目录整体跳过，不再深入遍历
+    for entry in WalkDir::new(&self.workspace_path)
+      .follow_links(false)
+      .into_iter()
+      .filter_entry(|e| e.depth() == 0 || !self.is_excluded(e.path()))
+      .filter_map(|e| e.ok())
+    {
+      let path = entry.path();
+      if !path.is_file() || !self.should_index(path).unwrap_or(false) {
+        continue;
+      }
+      if let Ok(content) = std::fs::read_to_string(path) {
+        updates.push((path.to_path_buf(), content));
+        count += 1;
+        if !on_progress(count) {
+          tracing::debug!("索引构建已中止");
+          break;
+        }
+
+        // 每 100 个文件批量提交一次
+        if updates.len() >= 100 {
+          if let Err(e) = self.batch_update_index(std::mem::take(&mut updates)) {
+            tracing::warn!("批量更新索引失败: {}", e);
+          }
+          tracing::debug!("已索引 {} 个文件...", count);
+        }
+      }
+    }
 
-      tx.execute(
-        "INSERT OR REPLACE INTO documents_fts (path, title, content)
-                 VALUES (?1, ?2, ?3)",
-        params![relative_path, title, content],
-      )?;
+    // 提交剩余的文件
+    if !updates.is_empty() {
+      if let Err(e) = self.batch_update_index(updates) {
+        tracing::warn!("批量更新索引失败: {}", e);
+      }
     }
 
-    tx.commit()?;
-    Ok(())
+    tracing::debug!("索引构建完成，共索引 {} 个文件", count);
+    count
   }
 
   // ⚠️ Week 19.1：检查文件是否需要索引（基于修改时间）
   pub fn should_index(&self, path: &Path) -> SqlResult<bool> {
-    // 只索引文本文件
-    if !self.is_text_file(path) {
+    // 只索引文本文件，且跳过排除规则命中的路径
+    if !self.is_text_file(path) || self.is_excluded(path) {
       return Ok(false);
     }
 
@@ -318,3 +582,422 @@ impl SearchResult {
     workspace_path.join(&self.path)
   }
 }
+
+/// 把自然语言文本转成安全的 FTS5 查询：取前 10 个词，加引号后以 OR 连接
+pub fn natural_language_query(text: &str) -> String {
+  let mut terms: Vec<String> = Vec::new();
+  for word in text.split(|c: char| !c.is_alphanumeric()) {
+    let word = word.to_lowercase();
+    if word.chars().count() >= 2 && !terms.contains(&word) {
+      terms.push(word);
+    }
+    if terms.len() >= 10 {
+      break;
+    }
+  }
+  terms
+    .iter()
+    .map(|t| format!("\"{}\"", t))
+    .collect::<Vec<_>>()
+    .join(" OR ")
+}
+
+/// 将搜索结果格式化为 [工作区相关片段]...[/工作区相关片段] 注入字符串，超出预算的结果被丢弃
+pub fn format_snippets_for_injection(results: &[SearchResult], budget_chars: usize) -> String {
+  const WRAPPER: &str = "[工作区相关片段]\n\n[/工作区相关片段]";
+  let mut used = WRAPPER.len();
+  let mut lines: Vec<String> = Vec::new();
+  for result in results {
+    let snippet = result.snippet.replace("<mark>", "").replace("</mark>", "");
+    let line = format!("- {}: {}", result.path, snippet.trim());
+    if used + line.len() + 1 > budget_chars {
+      break;
+    }
+    used += line.len() + 1;
+    lines.push(line);
+  }
+  if lines.is_empty() {
+    return String::new();
+  }
+  format!("[工作区相关片段]\n{}\n[/工作区相关片段]", lines.join("\n"))
+}
+
+/// 需要经 Pandoc 提取文本后才能索引的文档格式
+fn is_pandoc_document(path: &Path) -> bool {
+  path
+    .extension()
+    .and_then(|e| e.to_str())
+    .map(|e| matches!(e.to_lowercase().as_str(), "docx" | "odt" | "rtf"))
+    .unwrap_or(false)
+}
+
+fn is_pdf(path: &Path) -> bool {
+  path
+    .extension()
+    .and_then(|e| e.to_str())
+    .is_some_and(|e| e.eq_ignore_ascii_case("pdf"))
+}
+
+/// 保存后立即刷新单个文件的索引，不必等待文件监听的防抖扫描
+///
+/// 在后台阻塞线程中执行，不影响保存流程；`content` 为已知的文本内容（为空时从磁盘读取）。
+/// DOCX 等文档通过 Pandoc 提取纯文本后索引，PDF 按页提取文本后索引。
+pub fn refresh_index_after_save(workspace_path: &Path, path: &Path, content: Option<String>) {
+  let workspace_path = workspace_path.to_path_buf();
+  let path = path.to_path_buf();
+  tauri::async_runtime::spawn_blocking(move || {
+    refresh_document_index(&workspace_path, &path, content)
+  });
+}
+
+fn refresh_document_index(workspace_path: &Path, path: &Path, content: Option<String>) {
+  let service = match SearchService::new(workspace_path) {
+    Ok(service) => service,
+    Err(e) => {
+      tracing::warn!("初始化搜索服务失败（跳过保存后索引）: {}", e);
+      return;
+    }
+  };
+  if service.is_excluded(path) {
+    return;
+  }
+
+  let text = if is_pandoc_document(path) {
+    crate::services::pandoc_service::PandocService::new().extract_plain_text(path)
+  } else if is_pdf(path) {
+    crate::services::pdf_text::extract_pdf_plain_text(path)
+  } else if service.is_text_file(path) {
+    match content {
+      Some(content) => Ok(content),
+      None => std::fs::read_to_string(path).map_err(|e| format!("读取文件失败: {}", e)),
+    }
+  } else {
+    return;
+  };
+
+  match text {
+    Ok(text) => {
+      if let Err(e) = service.index_document(path, &text) {
+        tracing::warn!("保存后更新索引失败 ({}): {}", path.display(), e);
+      }
+    }
+    Err(e) => tracing::warn!("保存后提取文本失败 ({}): {}", path.display(), e),
+  }
+}
+
+/// 删除文件后立即移除索引
+pub fn remove_index_after_delete(workspace_path: &Path, path: &Path) {
+  let workspace_path = workspace_path.to_path_buf();
+  let path = path.to_path_buf();
+  tauri::async_runtime::spawn_blocking(move || remove_document_index(&workspace_path, &path));
+}
+
+fn remove_document_index(workspace_path: &Path, path: &Path) {
+  if let Err(e) =
+    SearchService::new(workspace_path).and_then(|service| service.remove_document(path))
+  {
+    tracing::warn!("删除文件后移除索引失败 ({}): {}", path.display(), e);
+  }
+}
+
+/// 重新打分所需的候选文档信息
+struct RankCandidate {
+  result: SearchResult,
+  headings: String,
+  content: String,
+  modified_time: i64,
+  body_length: i64,
+}
+
+/// 全库统计信息（文档数、平均正文长度、每个检索词的文档频率）
+struct CorpusStats {
+  doc_count: f64,
+  avg_body_length: f64,
+  doc_freq: HashMap<String, i64>,
+  now: i64,
+}
+
+/// 简化的 BM25F：字段词频按权重合并，正文按长度归一化，再乘以最近编辑加权
+fn score_candidate(
+  candidate: &RankCandidate,
+  terms: &[String],
+  stats: &CorpusStats,
+  settings: &SearchSettings,
+) -> f64 {
+  let title = candidate.result.title.to_lowercase();
+  let headings = candidate.headings.to_lowercase();
+  let content = candidate.content.to_lowercase();
+  let length_norm = 1.0 - settings.bm25_b
+    + settings.bm25_b * (candidate.body_length as f64 / stats.avg_body_length);
+
+  let mut score = 0.0;
+  for term in terms {
+    let weighted_tf = settings.title_boost * title.matches(term.as_str()).count() as f64
+      + settings.headings_boost * headings.matches(term.as_str()).count() as f64
+      + settings.body_boost * content.matches(term.as_str()).count() as f64 / length_norm;
+    if weighted_tf <= 0.0 {
+      continue;
+    }
+
+    let df = *stats.doc_freq.get(term).unwrap_or(&0) as f64;
+    let idf = (1.0 + (stats.doc_count - df + 0.5) / (df + 0.5)).ln();
+    score += idf * weighted_tf * (settings.bm25_k1 + 1.0) / (weighted_tf + settings.bm25_k1);
+  }
+
+  score * recency_factor(candidate.modified_time, stats.now, settings)
+}
+
+/// 最近编辑加权：按半衰期指数衰减，刚编辑的文档得到 1 + recency_weight 倍加权
+fn recency_factor(modified_time: i64, now: i64, settings: &SearchSettings) -> f64 {
+  if !settings.recency_enabled || modified_time <= 0 {
+    return 1.0;
+  }
+  let age_days = (now - modified_time).max(0) as f64 / 86_400.0;
+  1.0 + settings.recency_weight * 0.5_f64.powf(age_days / settings.recency_half_life_days)
+}
+
+/// 从 FTS5 查询中提取检索词（去掉运算符、引号和前缀通配符）
+fn query_terms(query: &str) -> Vec<String> {
+  let mut terms: Vec<String> = Vec::new();
+  for raw in query.split_whitespace() {
+    if matches!(raw, "AND" | "OR" | "NOT" | "NEAR") {
+      continue;
+    }
+    let term = raw
+      .trim_matches(|c: char| matches!(c, '"' | '(' | ')' | '*' | '^' | '+' | '-'))
+      .to_lowercase();
+    if !term.is_empty() && !terms.contains(&term) {
+      terms.push(term);
+    }
+  }
+  terms
+}
+
+/// 提取文档中的小标题文本（Markdown 的 # 标题、HTML 的 h1-h6）
+fn extract_headings(path: &Path, content: &str) -> String {
+  let ext = path
+    .extension()
+    .and_then(|e| e.to_str())
+    .map(|e| e.to_lowercase())
+    .unwrap_or_default();
+
+  match ext.as_str() {
+    "md" | "markdown" => content
+      .lines()
+      .map(|line| line.trim_start())
+      .filter(|line| line.starts_with('#'))
+      .map(|line| line.trim_start_matches('#').trim())
+      .filter(|line| !line.is_empty())
+      .collect::<Vec<_>>()
+      .join("\n"),
+    "html" | "htm" => HTML_HEADING_RE
+      .captures_iter(content)
+      .map(|caps| HTML_TAG_RE.replace_all(&caps[1], "").trim().to_string())
+      .filter(|text| !text.is_empty())
+      .collect::<Vec<_>>()
+      .join("\n"),
+    _ => String::new(),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{
+    extract_headings, format_snippets_for_injection, natural_language_query, query_terms,
+    refresh_document_index, remove_document_index, SearchService,
+  };
+  use crate::services::workspace_settings::WorkspaceSettings;
+  use std::path::{Path, PathBuf};
+
+  fn create_workspace() -> PathBuf {
+    let workspace =
+      std::env::temp_dir().join(format!("binder-search-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&workspace).expect("create temp workspace");
+    workspace
+  }
+
+  fn write_and_index(service: &SearchService, workspace: &Path, name: &str, content: &str) {
+    let path = workspace.join(name);
+    std::fs::write(&path, content).expect("write document");
+    service
+      .index_document(&path, content)
+      .expect("index document");
+  }
+
+  #[test]
+  fn title_match_outranks_body_match() {
+    let workspace = create_workspace();
+    let service = SearchService::new(&workspace).expect("search service init");
+    write_and_index(
+      &service,
+      &workspace,
+      "notes.md",
+      "# Notes\nThe budget is discussed briefly here.",
+    );
+    write_and_index(&service, &workspace, "budget.md", "# Plan\nNumbers only.");
+
+    let results = service.search("budget", 10).expect("search");
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0].path, "budget.md");
+
+    let _ = std::fs::remove_dir_all(&workspace);
+  }
+
+  #[test]
+  fn reindexing_does_not_duplicate_results() {
+    let workspace = create_workspace();
+    let service = SearchService::new(&workspace).expect("search service init");
+    write_and_index(&service, &workspace, "a.md", "alpha");
+    write_and_index(&service, &workspace, "a.md", "alpha again");
+
+    let results = service.search("alpha", 10).expect("search");
+    assert_eq!(results.len(), 1);
+
+    let _ = std::fs::remove_dir_all(&workspace);
+  }
+
+  #[test]
+  fn heading_boost_can_be_disabled() {
+    let workspace = create_workspace();
+    let mut settings = WorkspaceSettings::default();
+    settings.search.headings_boost = 0.0;
+    settings.save(&workspace).expect("save workspace settings");
+    let service = SearchService::new(&workspace).expect("search service init");
+    write_and_index(&service, &workspace, "a.md", "# roadmap\nintro");
+    write_and_index(&service, &workspace, "b.md", "# intro\nroadmap roadmap");
+
+    let results = service.search("roadmap", 10).expect("search");
+    assert_eq!(results[0].path, "b.md");
+
+    let _ = std::fs::remove_dir_all(&workspace);
+  }
+
+  #[test]
+  fn save_and_delete_refresh_the_index() {
+    let workspace = create_workspace();
+    let path = workspace.join("notes.md");
+    std::fs::write(&path, "# Notes\n\nquarterly budget review").expect("write document");
+
+    // 未提供内容时从磁盘读取
+    refresh_document_index(&workspace, &path, None);
+    let service = SearchService::new(&workspace).expect("search service init");
+    assert_eq!(service.search("budget", 10).expect("search").len(), 1);
+
+    // 保存时传入的新内容替换旧索引
+    refresh_document_index(
+      &workspace,
+      &path,
+      Some("# Notes\n\nhiring plan".to_string()),
+    );
+    assert!(service.search("budget", 10).expect("search").is_empty());
+    assert_eq!(
+      service.search("hiring", 10).expect("search")[0].path,
+      "notes.md"
+    );
+
+    remove_document_index(&workspace, &path);
+    assert!(service.search("hiring", 10).expect("search").is_empty());
+
+    let _ = std::fs::remove_dir_all(&workspace);
+  }
+
+  #[test]
+  fn exclude_patterns_skip_and_purge_documents() {
+    let workspace = create_workspace();
+    let service = SearchService::new(&workspace).expect("search service init");
+    std::fs::create_dir_all(workspace.join("archive")).expect("create archive dir");
+    write_and_index(&service, &workspace, "archive/old.md", "legacy plan");
+    write_and_index(&service, &workspace, "current.md", "current plan");
+
+    let mut settings = WorkspaceSettings::default();
+    settings.search.exclude_patterns = vec!["archive/**".to_string(), "*.log".to_string()];
+    settings.save(&workspace).expect("save workspace settings");
+    let service = SearchService::new(&workspace).expect("search service init");
+
+    assert!(!service
+      .should_index(&workspace.join("archive/old.md"))
+      .expect("should_index"));
+    assert!(service.is_excluded(&workspace.join("logs/app.log")));
+    assert_eq!(service.purge_excluded_documents().expect("purge"), 1);
+
+    // 直接索引命中排除规则的文档同样被跳过
+    write_and_index(&service, &workspace, "archive/new.md", "draft plan");
+
+    let results = service.search("plan", 10).expect("search");
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].path, "current.md");
+
+    let _ = std::fs::remove_dir_all(&workspace);
+  }
+
+  #[test]
+  fn natural_language_query_matches_indexed_snippets() {
+    let workspace = create_workspace();
+    let service = SearchService::new(&workspace).expect("create search service");
+    write_and_index(
+      &service,
+      &workspace,
+      "roadmap.md",
+      "# Roadmap\n\nBeta launch in spring.",
+    );
+
+    let query = natural_language_query("When is the \"beta\" launch? (roadmap)");
+    assert_eq!(
+      query,
+      "\"when\" OR \"is\" OR \"the\" OR \"beta\" OR \"launch\" OR \"roadmap\""
+    );
+    let results = service.search(&query, 5).expect("search");
+    assert_eq!(results[0].path, "roadmap.md");
+
+    let injected = format_snippets_for_injection(&results, 500);
+    assert!(injected.starts_with("[工作区相关片段]\n- roadmap.md: "));
+    assert!(!injected.contains("<mark>"));
+    assert!(format_snippets_for_injection(&results, 10).is_empty());
+
+    let _ = std::fs::remove_dir_all(&workspace);
+  }
+
+  #[test]
+  fn extracts_markdown_and_html_headings() {
+    assert_eq!(
+      extract_headings(Path::new("a.md"), "# One\ntext\n## Two\n"),
+      "One\nTwo"
+    );
+    assert_eq!(
+      extract_headings(
+        Path::new("a.html"),
+        "<h1 class=\"x\">One <b>!</b></h1><p>x</p>"
+      ),
+      "One !"
+    );
+    assert_eq!(
+      query_terms("\"Budget\" OR plan*"),
+      vec!["budget".to_string(), "plan".to_string()]
+    );
+  }
+
+  #[test]
+  fn reference_folders_are_indexed_by_absolute_path() {
+    let workspace = create_workspace();
+    let reference = create_workspace();
+    std::fs::write(reference.join("论文.md"), "# Lattice\nreference notes").unwrap();
+    std::fs::write(reference.join("scan.bin"), "lattice").unwrap();
+    let service = SearchService::new(&workspace).expect("search service init");
+
+    assert_eq!(
+      service
+        .sync_reference_folders(std::slice::from_ref(&reference))
+        .unwrap(),
+      1
+    );
+    let results = service.search("lattice", 10).expect("search");
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].full_path(&workspace), reference.join("论文.md"));
+
+    service.sync_reference_folders(&[]).unwrap();
+    assert!(service.search("lattice", 10).unwrap().is_empty());
+
+    let _ = std::fs::remove_dir_all(&workspace);
+    let _ = std::fs::remove_dir_all(&reference);
+  }
+}
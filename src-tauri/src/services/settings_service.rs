@@ -0,0 +1,336 @@
+//! 应用级设置（与工作区无关），保存在应用配置目录的 `settings.json`。
+//!
+//! 启动时由 `init` 加载；未初始化（如测试中）时各访问函数返回默认值。
+//! 设置变更后通过 `subscribe` 通知后端订阅者（如代理变更后重建 AI 提供商），
+//! 命令层另向前端发送 `app-settings-changed` 事件。
+
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use tokio::sync::watch;
+
+const SETTINGS_FILE: &str = "settings.json";
+const AI_PROVIDERS: &[&str] = &["deepseek", "openai"];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppSettings {
+  /// 默认 AI 提供商（deepseek / openai 或插件提供商）
+  pub default_ai_provider: String,
+  /// 自动保存间隔（秒），0 表示关闭
+  pub autosave_interval_secs: u64,
+  /// Office 文档预览方式
+  pub preview_engine: PreviewEngine,
+  /// 网络代理，如 `http://127.0.0.1:7890`；为空时不使用代理
+  pub proxy: Option<String>,
+  /// 自动更新的发布渠道
+  pub update_channel: UpdateChannel,
+  /// 单次文档转换的内存上限（MB），预计超出时跳过格式增强
+  pub conversion_memory_limit_mb: u64,
+  /// 信任其中 DOCX 原始 HTML 的工作区根目录，跳过脚本 / iframe 等可执行内容的清理；
+  /// 按工作区保存在应用配置中，工作区内的文件无法自行开启
+  pub trusted_html_workspaces: Vec<PathBuf>,
+  /// 允许 AI 执行命令（run_command 与 shell 动作的自定义工具）的工作区根目录；
+  /// 保存在应用配置中，克隆或共享的工作区无法自带开启状态
+  pub shell_command_workspaces: Vec<PathBuf>,
+  /// 用户允许免确认执行的 HTTP 自定义工具（声明中还需 `confirm = false`）
+  pub approved_custom_tools: Vec<ApprovedCustomTool>,
+}
+
+/// 在某个工作区中允许免确认执行的自定义工具
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApprovedCustomTool {
+  pub workspace: PathBuf,
+  pub name: String,
+}
+
+impl Default for AppSettings {
+  fn default() -> Self {
+    Self {
+      default_ai_provider: "deepseek".to_string(),
+      autosave_interval_secs: 30,
+      preview_engine: PreviewEngine::default(),
+      proxy: None,
+      update_channel: UpdateChannel::default(),
+      conversion_memory_limit_mb: 512,
+      trusted_html_workspaces: Vec::new(),
+      shell_command_workspaces: Vec::new(),
+      approved_custom_tools: Vec::new(),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PreviewEngine {
+  /// 用 LibreOffice 转换为 PDF 预览，版式最准确
+  #[default]
+  LibreOffice,
+  /// 用 Pandoc 转换为 HTML 预览，不依赖 LibreOffice
+  Pandoc,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UpdateChannel {
+  #[default]
+  Stable,
+  /// 预发布版本，先于正式版收到更新
+  Beta,
+}
+
+impl UpdateChannel {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      UpdateChannel::Stable => "stable",
+      UpdateChannel::Beta => "beta",
+    }
+  }
+}
+
+impl AppSettings {
+  pub fn validate(&self) -> Result<(), String> {
+    let provider = self.default_ai_provider.as_str();
+    let plugin_provider =
+      || crate::services::plugins::with_registry(|r| r.providers().any(|p| p.name == provider));
+    if !AI_PROVIDERS.contains(&provider) && !plugin_provider() {
+      return Err(format!(
+        "不支持的 AI 提供商: {}（可选 {} 或插件提供商）",
+        provider,
+        AI_PROVIDERS.join(" / ")
+      ));
+    }
+    if self.autosave_interval_secs != 0 && !(5..=3600).contains(&self.autosave_interval_secs) {
+      return Err("自动保存间隔必须在 5-3600 秒之间，或为 0 表示关闭".to_string());
+    }
+    if !(128..=16384).contains(&self.conversion_memory_limit_mb) {
+      return Err("文档转换内存上限必须在 128-16384 MB 之间".to_string());
+    }
+    if let Some(proxy) = &self.proxy {
+      let scheme_ok = ["http://", "https://", "socks5://"]
+        .iter()
+        .any(|scheme| proxy.starts_with(scheme));
+      if !scheme_ok || reqwest::Url::parse(proxy).is_err() {
+        return Err(format!("代理地址无效: {}", proxy));
+      }
+    }
+    Ok(())
+  }
+
+  /// 去掉首尾空白，空代理视为未设置
+  fn normalized(mut self) -> Self {
+    self.default_ai_provider = self.default_ai_provider.trim().to_lowercase();
+    self.proxy = self
+      .proxy
+      .map(|p| p.trim().to_string())
+      .filter(|p| !p.is_empty());
+    self
+  }
+}
+
+pub struct SettingsService {
+  path: PathBuf,
+  settings: RwLock<AppSettings>,
+  changes: watch::Sender<AppSettings>,
+}
+
+impl SettingsService {
+  /// 读取 `config_dir/settings.json`；文件不存在或损坏时使用默认值
+  pub fn load(config_dir: &Path) -> Self {
+    let path = config_dir.join(SETTINGS_FILE);
+    let settings = match fs::read_to_string(&path) {
+      Ok(content) => serde_json::from_str::<AppSettings>(&content).unwrap_or_else(|e| {
+        tracing::warn!("应用设置文件损坏，使用默认设置: {}", e);
+        AppSettings::default()
+      }),
+      Err(_) => AppSettings::default(),
+    };
+    let (changes, _) = watch::channel(settings.clone());
+    Self {
+      path,
+      settings: RwLock::new(settings),
+      changes,
+    }
+  }
+
+  pub fn get(&self) -> AppSettings {
+    self.settings.read().map(|s| s.clone()).unwrap_or_default()
+  }
+
+  /// 校验并保存；设置有变化时通知订阅者。返回保存后的设置
+  pub fn update(&self, settings: AppSettings) -> Result<AppSettings, String> {
+    let settings = settings.normalized();
+    settings.validate()?;
+    if let Some(parent) = self.path.parent() {
+      fs::create_dir_all(parent).map_err(|e| format!("创建配置目录失败: {}", e))?;
+    }
+    let json =
+      serde_json::to_string_pretty(&settings).map_err(|e| format!("序列化应用设置失败: {}", e))?;
+    fs::write(&self.path, json).map_err(|e| format!("写入应用设置失败: {}", e))?;
+
+    *self
+      .settings
+      .write()
+      .map_err(|e| format!("更新应用设置失败: {}", e))? = settings.clone();
+    self.changes.send_if_modified(|current| {
+      let changed = *current != settings;
+      *current = settings.clone();
+      changed
+    });
+    Ok(settings)
+  }
+
+  pub fn subscribe(&self) -> watch::Receiver<AppSettings> {
+    self.changes.subscribe()
+  }
+}
+
+static SETTINGS: OnceCell<SettingsService> = OnceCell::new();
+
+/// 加载应用设置（应用启动时调用一次）
+pub fn init(config_dir: &Path) {
+  if SETTINGS.set(SettingsService::load(config_dir)).is_err() {
+    tracing::debug!("应用设置已初始化，忽略重复调用");
+  }
+}
+
+pub fn service() -> Result<&'static SettingsService, String> {
+  SETTINGS
+    .get()
+    .ok_or_else(|| "应用设置尚未初始化".to_string())
+}
+
+pub fn current() -> AppSettings {
+  SETTINGS.get().map(|s| s.get()).unwrap_or_default()
+}
+
+pub fn default_ai_provider() -> String {
+  current().default_ai_provider
+}
+
+pub fn conversion_memory_limit_bytes() -> u64 {
+  current().conversion_memory_limit_mb * 1024 * 1024
+}
+
+/// 工作区是否在列表中（按规范化后的路径比较）
+fn contains_workspace(workspaces: &[PathBuf], workspace_path: &Path) -> bool {
+  let canonical = |path: &Path| path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+  let workspace = canonical(workspace_path);
+  workspaces.iter().any(|w| canonical(w) == workspace)
+}
+
+/// 用户是否信任该工作区中文档的原始 HTML
+pub fn document_html_trusted(workspace_path: &Path) -> bool {
+  contains_workspace(&current().trusted_html_workspaces, workspace_path)
+}
+
+/// 用户是否允许 AI 在该工作区执行命令
+pub fn shell_commands_allowed(workspace_path: &Path) -> bool {
+  contains_workspace(&current().shell_command_workspaces, workspace_path)
+}
+
+/// 用户是否允许该工作区中的自定义工具免确认执行
+pub fn custom_tool_approved(workspace_path: &Path, name: &str) -> bool {
+  let approved: Vec<PathBuf> = current()
+    .approved_custom_tools
+    .into_iter()
+    .filter(|tool| tool.name == name)
+    .map(|tool| tool.workspace)
+    .collect();
+  contains_workspace(&approved, workspace_path)
+}
+
+/// 测试中修改全局应用设置；首次调用时在临时目录初始化
+#[cfg(test)]
+pub(crate) fn update_for_tests(f: impl FnOnce(&mut AppSettings)) {
+  static LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+  let _guard = LOCK.lock().unwrap_or_else(|e| e.into_inner());
+  init(&std::env::temp_dir().join(format!("binder-test-settings-{}", std::process::id())));
+  let mut settings = current();
+  f(&mut settings);
+  service().unwrap().update(settings).unwrap();
+}
+
+/// 为 HTTP 客户端设置代理；未设置代理时沿用 reqwest 默认行为（读取 HTTP(S)_PROXY 环境变量）
+pub fn apply_proxy(builder: reqwest::ClientBuilder) -> reqwest::ClientBuilder {
+  let Some(proxy) = current().proxy else {
+    return builder;
+  };
+  match reqwest::Proxy::all(&proxy) {
+    Ok(proxy) => builder.proxy(proxy),
+    Err(e) => {
+      tracing::warn!("代理地址无效，忽略: {}", e);
+      builder
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn html_trust_is_per_workspace() {
+    let trusted = std::env::temp_dir().join(format!("binder-trusted-{}", uuid::Uuid::new_v4()));
+    let other = std::env::temp_dir().join(format!("binder-untrusted-{}", uuid::Uuid::new_v4()));
+    update_for_tests(|s| s.trusted_html_workspaces.push(trusted.clone()));
+    assert!(document_html_trusted(&trusted));
+    assert!(!document_html_trusted(&other));
+  }
+
+  #[test]
+  fn update_persists_validates_and_notifies() {
+    let dir = std::env::temp_dir().join(format!("binder-settings-{}", uuid::Uuid::new_v4()));
+    let service = SettingsService::load(&dir);
+    assert_eq!(service.get(), AppSettings::default());
+    let mut changes = service.subscribe();
+
+    let saved = service
+      .update(AppSettings {
+        default_ai_provider: " OpenAI ".to_string(),
+        autosave_interval_secs: 0,
+        preview_engine: PreviewEngine::Pandoc,
+        proxy: Some("  ".to_string()),
+        update_channel: UpdateChannel::Beta,
+        conversion_memory_limit_mb: 1024,
+        trusted_html_workspaces: Vec::new(),
+        shell_command_workspaces: Vec::new(),
+        approved_custom_tools: Vec::new(),
+      })
+      .unwrap();
+    assert_eq!(saved.default_ai_provider, "openai");
+    assert_eq!(saved.proxy, None);
+    assert!(changes.has_changed().unwrap());
+    assert_eq!(*changes.borrow_and_update(), saved);
+
+    service.update(saved.clone()).unwrap();
+    assert!(!changes.has_changed().unwrap());
+    assert_eq!(SettingsService::load(&dir).get(), saved);
+
+    let invalid = [
+      AppSettings {
+        default_ai_provider: "claude".to_string(),
+        ..Default::default()
+      },
+      AppSettings {
+        autosave_interval_secs: 2,
+        ..Default::default()
+      },
+      AppSettings {
+        proxy: Some("127.0.0.1:7890".to_string()),
+        ..Default::default()
+      },
+      AppSettings {
+        conversion_memory_limit_mb: 16,
+        ..Default::default()
+      },
+    ];
+    for settings in invalid {
+      assert!(service.update(settings).is_err());
+    }
+    assert_eq!(service.get(), saved);
+    let _ = fs::remove_dir_all(&dir);
+  }
+}
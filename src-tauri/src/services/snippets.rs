@@ -0,0 +1,350 @@
+//! 文本片段：工作区 `.binder/snippets.json` 中保存的「触发词 → 内容」，用于签名、常用条款等
+//! 反复使用的文字。编辑器输入触发词后展开，AI 对话中的 `{{snippet:触发词}}` 在发送前展开。
+//!
+//! 内容中可使用模板变量（见 file_templates）：`{{title}}`（当前文档名）、`{{date}}`、`{{time}}`、
+//! `{{datetime}}`、`{{workspace}}`、`{{uuid}}`，以及 `{{selection}}`（当前选中文字）、
+//! `{{cursor}}`（展开后光标位置）和嵌套的 `{{snippet:其他触发词}}`。
+
+use crate::services::file_templates::{expand_variables, TemplateContext};
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+static SNIPPET_REF_RE: Lazy<Regex> =
+  Lazy::new(|| Regex::new(r"\{\{\s*snippet:\s*([^\s{}]+)\s*\}\}").unwrap());
+static CURSOR_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\{\{\s*cursor\s*\}\}").unwrap());
+
+/// 串行化 snippets.json 的读改写
+static STORE_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+/// 嵌套片段的最大展开层数，防止互相引用时无限展开
+const MAX_NESTING: usize = 4;
+const MAX_TRIGGER_CHARS: usize = 64;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snippet {
+  /// 触发词：不含空白与花括号，不区分大小写
+  pub trigger: String,
+  pub body: String,
+  #[serde(default)]
+  pub description: Option<String>,
+  #[serde(default)]
+  pub updated_at: i64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct SnippetsFile {
+  #[serde(default)]
+  snippets: Vec<Snippet>,
+}
+
+/// 展开时的上下文
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SnippetContext {
+  /// 当前文档路径，`{{title}}` 取其文件名
+  pub file_path: Option<String>,
+  pub selection: Option<String>,
+  /// 额外的变量值，优先于内置变量
+  pub values: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExpandedSnippet {
+  pub text: String,
+  /// `{{cursor}}` 所在位置（字符偏移），未声明时为 None
+  pub cursor: Option<usize>,
+}
+
+fn snippets_path(workspace_path: &Path) -> PathBuf {
+  workspace_path.join(".binder").join("snippets.json")
+}
+
+fn load_file(workspace_path: &Path) -> Result<SnippetsFile, String> {
+  let path = snippets_path(workspace_path);
+  if !path.exists() {
+    return Ok(SnippetsFile::default());
+  }
+  let content = fs::read_to_string(&path).map_err(|e| format!("读取文本片段失败: {}", e))?;
+  serde_json::from_str(&content).map_err(|e| format!("解析文本片段失败: {}", e))
+}
+
+fn save_file(workspace_path: &Path, file: &SnippetsFile) -> Result<(), String> {
+  let path = snippets_path(workspace_path);
+  if let Some(parent) = path.parent() {
+    fs::create_dir_all(parent).map_err(|e| format!("创建 .binder 目录失败: {}", e))?;
+  }
+  let json =
+    serde_json::to_string_pretty(file).map_err(|e| format!("序列化文本片段失败: {}", e))?;
+  fs::write(&path, json).map_err(|e| format!("写入文本片段失败: {}", e))
+}
+
+fn validate_trigger(trigger: &str) -> Result<(), String> {
+  if trigger.is_empty() {
+    return Err("触发词不能为空".to_string());
+  }
+  if trigger.chars().count() > MAX_TRIGGER_CHARS {
+    return Err(format!("触发词不能超过 {} 个字符", MAX_TRIGGER_CHARS));
+  }
+  if trigger
+    .chars()
+    .any(|c| c.is_whitespace() || c == '{' || c == '}')
+  {
+    return Err(format!("触发词不能包含空白或花括号: {}", trigger));
+  }
+  Ok(())
+}
+
+/// 列出工作区的文本片段（按触发词排序）
+pub fn list_snippets(workspace_path: &Path) -> Result<Vec<Snippet>, String> {
+  let mut snippets = load_file(workspace_path)?.snippets;
+  snippets.sort_by_key(|s| s.trigger.to_lowercase());
+  Ok(snippets)
+}
+
+/// 按触发词（不区分大小写）查找片段
+pub fn get_snippet(workspace_path: &Path, trigger: &str) -> Result<Option<Snippet>, String> {
+  Ok(
+    load_file(workspace_path)?
+      .snippets
+      .into_iter()
+      .find(|s| s.trigger.eq_ignore_ascii_case(trigger.trim())),
+  )
+}
+
+/// 新增或更新片段；previous_trigger 用于改名，同名片段会被覆盖
+pub fn save_snippet(
+  workspace_path: &Path,
+  mut snippet: Snippet,
+  previous_trigger: Option<&str>,
+) -> Result<Snippet, String> {
+  snippet.trigger = snippet.trigger.trim().to_string();
+  validate_trigger(&snippet.trigger)?;
+  snippet.description = snippet.description.filter(|d| !d.trim().is_empty());
+  snippet.updated_at = chrono::Utc::now().timestamp_millis();
+
+  let _guard = STORE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+  let mut file = load_file(workspace_path)?;
+  file.snippets.retain(|s| {
+    !s.trigger.eq_ignore_ascii_case(&snippet.trigger)
+      && previous_trigger.map_or(true, |old| !s.trigger.eq_ignore_ascii_case(old.trim()))
+  });
+  file.snippets.push(snippet.clone());
+  save_file(workspace_path, &file)?;
+  Ok(snippet)
+}
+
+/// 删除片段，返回是否存在
+pub fn delete_snippet(workspace_path: &Path, trigger: &str) -> Result<bool, String> {
+  let _guard = STORE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+  let mut file = load_file(workspace_path)?;
+  let before = file.snippets.len();
+  file
+    .snippets
+    .retain(|s| !s.trigger.eq_ignore_ascii_case(trigger.trim()));
+  if file.snippets.len() == before {
+    return Ok(false);
+  }
+  save_file(workspace_path, &file)?;
+  Ok(true)
+}
+
+/// 展开片段内容中的变量与嵌套片段（不处理 `{{cursor}}`）；替换进来的值不再展开
+fn expand_body(
+  body: &str,
+  snippets: &[Snippet],
+  template: &TemplateContext,
+  context: &SnippetContext,
+  depth: usize,
+) -> String {
+  let lookup = |name: &str| {
+    context.values.get(name).cloned().or_else(|| match name {
+      "selection" => Some(context.selection.clone().unwrap_or_default()),
+      "cursor" => None,
+      _ => template.value(name),
+    })
+  };
+  let mut text = String::new();
+  let mut last = 0;
+  for caps in SNIPPET_REF_RE.captures_iter(body) {
+    let whole = caps.get(0).unwrap();
+    text.push_str(&expand_variables(&body[last..whole.start()], lookup));
+    last = whole.end();
+    let found = snippets
+      .iter()
+      .find(|s| s.trigger.eq_ignore_ascii_case(&caps[1]));
+    match found {
+      Some(snippet) if depth < MAX_NESTING => {
+        let inner = expand_body(&snippet.body, snippets, template, context, depth + 1);
+        // 嵌套片段中的光标标记无效
+        text.push_str(&CURSOR_RE.replace_all(&inner, ""));
+      }
+      _ => text.push_str(whole.as_str()),
+    }
+  }
+  text.push_str(&expand_variables(&body[last..], lookup));
+  text
+}
+
+fn template_context(workspace_path: &Path, context: &SnippetContext) -> TemplateContext {
+  let file = context.file_path.as_deref().unwrap_or_default();
+  TemplateContext::new(workspace_path, Path::new(file))
+}
+
+/// 按触发词展开片段；不存在时返回 None
+pub fn expand_snippet(
+  workspace_path: &Path,
+  trigger: &str,
+  context: &SnippetContext,
+) -> Result<Option<ExpandedSnippet>, String> {
+  let snippets = load_file(workspace_path)?.snippets;
+  let Some(snippet) = snippets
+    .iter()
+    .find(|s| s.trigger.eq_ignore_ascii_case(trigger.trim()))
+  else {
+    return Ok(None);
+  };
+  let template = template_context(workspace_path, context);
+  let text = expand_body(&snippet.body, &snippets, &template, context, 0);
+
+  // 第一个 `{{cursor}}` 作为光标位置，其余移除
+  let cursor = CURSOR_RE
+    .find(&text)
+    .map(|m| text[..m.start()].chars().count());
+  Ok(Some(ExpandedSnippet {
+    text: CURSOR_RE.replace_all(&text, "").into_owned(),
+    cursor,
+  }))
+}
+
+/// 展开文本（如 AI 对话消息）中的 `{{snippet:触发词}}`；未知触发词保持原样
+pub fn expand_snippet_references(
+  workspace_path: &Path,
+  text: &str,
+  context: &SnippetContext,
+) -> String {
+  if !SNIPPET_REF_RE.is_match(text) {
+    return text.to_string();
+  }
+  let snippets = match load_file(workspace_path) {
+    Ok(file) => file.snippets,
+    Err(e) => {
+      tracing::warn!("{}", e);
+      return text.to_string();
+    }
+  };
+  let template = template_context(workspace_path, context);
+  let expanded = SNIPPET_REF_RE.replace_all(text, |caps: &Captures| {
+    match snippets
+      .iter()
+      .find(|s| s.trigger.eq_ignore_ascii_case(&caps[1]))
+    {
+      Some(snippet) => {
+        let body = expand_body(&snippet.body, &snippets, &template, context, 1);
+        CURSOR_RE.replace_all(&body, "").into_owned()
+      }
+      None => caps[0].to_string(),
+    }
+  });
+  expanded.into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn workspace() -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("binder_snippets_{}", uuid::Uuid::new_v4()));
+    fs::create_dir_all(&dir).unwrap();
+    dir
+  }
+
+  fn snippet(trigger: &str, body: &str) -> Snippet {
+    Snippet {
+      trigger: trigger.to_string(),
+      body: body.to_string(),
+      description: None,
+      updated_at: 0,
+    }
+  }
+
+  #[test]
+  fn saves_renames_and_deletes_snippets() {
+    let ws = workspace();
+    save_snippet(&ws, snippet(" sig ", "张三"), None).unwrap();
+    save_snippet(&ws, snippet("addr", "北京"), None).unwrap();
+    assert!(save_snippet(&ws, snippet("two words", "x"), None).is_err());
+
+    save_snippet(&ws, snippet("SIG", "李四"), None).unwrap();
+    let triggers: Vec<String> = list_snippets(&ws)
+      .unwrap()
+      .into_iter()
+      .map(|s| s.trigger)
+      .collect();
+    assert_eq!(triggers, vec!["addr", "SIG"]);
+
+    save_snippet(&ws, snippet("signature", "李四"), Some("sig")).unwrap();
+    assert!(get_snippet(&ws, "sig").unwrap().is_none());
+    assert_eq!(get_snippet(&ws, "Signature").unwrap().unwrap().body, "李四");
+
+    assert!(delete_snippet(&ws, "addr").unwrap());
+    assert!(!delete_snippet(&ws, "addr").unwrap());
+    let _ = fs::remove_dir_all(&ws);
+  }
+
+  #[test]
+  fn expands_variables_nested_snippets_and_cursor() {
+    let ws = workspace();
+    save_snippet(
+      &ws,
+      snippet("sig", "{{name}}\n{{cursor}}{{workspace}}"),
+      None,
+    )
+    .unwrap();
+    save_snippet(
+      &ws,
+      snippet(
+        "reply",
+        "关于《{{title}}》：{{cursor}}{{selection}}\n{{snippet:sig}}",
+      ),
+      None,
+    )
+    .unwrap();
+    save_snippet(&ws, snippet("loop", "a{{snippet:loop}}"), None).unwrap();
+
+    let context = SnippetContext {
+      file_path: Some("/ws/合同草案.md".to_string()),
+      selection: Some("第三条".to_string()),
+      values: HashMap::from([("name".to_string(), "张三".to_string())]),
+    };
+    let expanded = expand_snippet(&ws, "REPLY", &context).unwrap().unwrap();
+    let ws_name = ws.file_name().unwrap().to_string_lossy().to_string();
+    assert_eq!(
+      expanded.text,
+      format!("关于《合同草案》：第三条\n张三\n{}", ws_name)
+    );
+    assert_eq!(expanded.cursor, Some("关于《合同草案》：".chars().count()));
+    assert!(expand_snippet(&ws, "missing", &context).unwrap().is_none());
+
+    let looped = expand_snippet(&ws, "loop", &context).unwrap().unwrap();
+    assert_eq!(looped.text, "aaaaa{{snippet:loop}}");
+
+    let message = expand_snippet_references(
+      &ws,
+      "请在结尾加上 {{snippet:sig}}，保留 {{snippet:unknown}}",
+      &context,
+    );
+    assert_eq!(
+      message,
+      format!(
+        "请在结尾加上 张三\n{}，保留 {{{{snippet:unknown}}}}",
+        ws_name
+      )
+    );
+    let _ = fs::remove_dir_all(&ws);
+  }
+}
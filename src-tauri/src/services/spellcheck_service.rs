@@ -0,0 +1,403 @@
+//! 拼写检查：调用系统安装的 Hunspell 命令行（管道模式 `-a`）
+//!
+//! 词典按语言名（如 `en_US`）查找，工作区 `.binder/dictionaries/` 下的 `<lang>.aff` / `<lang>.dic`
+//! 优先于系统词典目录。“加入词典”的词写入工作区的用户词典 `.binder/dictionaries/user.dic`，
+//! 作为 Hunspell 个人词典传入；“忽略”的词只在本次运行内有效。
+
+use crate::services::workspace_settings::WorkspaceSettings;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use which::which;
+
+/// 未配置语言时使用的词典
+pub const DEFAULT_SPELLCHECK_LANGUAGE: &str = "en_US";
+
+const USER_DICTIONARY: &str = "user.dic";
+
+/// 词典名形如 `en_US`、`de_DE_frami`
+pub static DICTIONARY_NAME_PATTERN: Lazy<Regex> =
+  Lazy::new(|| Regex::new(r"^[A-Za-z]{2,3}([_-][A-Za-z0-9]+)*$").unwrap());
+
+/// 本次运行中被忽略的词
+static IGNORED_WORDS: Lazy<Mutex<HashSet<String>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+/// 一个拼写错误：字节范围 + 行号（从 1 开始）+ 建议
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Misspelling {
+  pub word: String,
+  pub start: usize,
+  pub end: usize,
+  pub line: usize,
+  pub suggestions: Vec<String>,
+}
+
+pub struct SpellcheckService {
+  hunspell_path: Option<PathBuf>,
+  workspace_path: Option<PathBuf>,
+}
+
+impl SpellcheckService {
+  pub fn new(workspace_path: Option<&Path>) -> Self {
+    Self {
+      hunspell_path: Self::find_hunspell(),
+      workspace_path: workspace_path.map(Path::to_path_buf),
+    }
+  }
+
+  /// 先查 PATH，再查各平台常见安装位置（GUI 启动的应用在 macOS 上通常拿不到 Homebrew 的 PATH）
+  fn find_hunspell() -> Option<PathBuf> {
+    if let Ok(path) = which("hunspell") {
+      return Some(path);
+    }
+    [
+      "/opt/homebrew/bin/hunspell",
+      "/usr/local/bin/hunspell",
+      "/usr/bin/hunspell",
+    ]
+    .iter()
+    .map(PathBuf::from)
+    .find(|p| p.is_file())
+  }
+
+  pub fn is_available(&self) -> bool {
+    self.hunspell_path.is_some()
+  }
+
+  pub fn get_path(&self) -> Option<&PathBuf> {
+    self.hunspell_path.as_ref()
+  }
+
+  fn workspace_dictionary_dir(&self) -> Option<PathBuf> {
+    self
+      .workspace_path
+      .as_ref()
+      .map(|w| w.join(".binder").join("dictionaries"))
+  }
+
+  /// 用户词典路径；未打开工作区时为 None
+  pub fn user_dictionary_path(&self) -> Option<PathBuf> {
+    self
+      .workspace_dictionary_dir()
+      .map(|d| d.join(USER_DICTIONARY))
+  }
+
+  /// 词典目录：工作区优先，其次是各平台的系统词典目录
+  fn dictionary_dirs(&self) -> Vec<PathBuf> {
+    let mut candidates: Vec<PathBuf> = self.workspace_dictionary_dir().into_iter().collect();
+    if let Some(home) = dirs::home_dir() {
+      candidates.push(home.join("Library").join("Spelling"));
+      candidates.push(home.join(".local").join("share").join("hunspell"));
+    }
+    for dir in [
+      "/Library/Spelling",
+      "/opt/homebrew/share/hunspell",
+      "/usr/local/share/hunspell",
+      "/usr/share/hunspell",
+      "/usr/share/myspell",
+      "/usr/share/myspell/dicts",
+    ] {
+      candidates.push(PathBuf::from(dir));
+    }
+    candidates
+  }
+
+  /// 已安装的词典（同时存在 .aff 与 .dic 的语言名）
+  pub fn available_languages(&self) -> Vec<String> {
+    let mut languages: Vec<String> = self
+      .dictionary_dirs()
+      .iter()
+      .filter_map(|dir| std::fs::read_dir(dir).ok())
+      .flatten()
+      .flatten()
+      .filter_map(|entry| {
+        let path = entry.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("aff") {
+          return None;
+        }
+        let name = path.file_stem()?.to_str()?.to_string();
+        path.with_extension("dic").is_file().then_some(name)
+      })
+      .collect();
+    languages.sort();
+    languages.dedup();
+    languages
+  }
+
+  /// 词典路径（不含扩展名，即 Hunspell `-d` 参数）
+  fn find_dictionary(&self, lang: &str) -> Option<PathBuf> {
+    find_dictionary_in(&self.dictionary_dirs(), lang)
+  }
+
+  /// 语言优先级：参数 → 工作区设置 → en_US
+  pub fn resolve_language(&self, lang: Option<&str>) -> Result<String, String> {
+    let lang = match lang.map(str::trim).filter(|l| !l.is_empty()) {
+      Some(lang) => lang.to_string(),
+      None => self
+        .workspace_path
+        .as_deref()
+        .map(|w| WorkspaceSettings::load_or_default(w).spellcheck.language)
+        .unwrap_or_else(|| DEFAULT_SPELLCHECK_LANGUAGE.to_string()),
+    };
+    if !DICTIONARY_NAME_PATTERN.is_match(&lang) {
+      return Err(format!("无效的拼写检查语言: {}", lang));
+    }
+    Ok(lang)
+  }
+
+  /// 检查文本拼写，返回拼写错误及建议（已排除被忽略的词）
+  pub fn check_text(&self, text: &str, lang: Option<&str>) -> Result<Vec<Misspelling>, String> {
+    let hunspell = self
+      .hunspell_path
+      .as_ref()
+      .ok_or_else(|| "未检测到 Hunspell，无法进行拼写检查。请先安装 Hunspell。".to_string())?;
+    let lang = self.resolve_language(lang)?;
+    let dictionary = self
+      .find_dictionary(&lang)
+      .ok_or_else(|| format!("未找到 {} 词典，请安装对应的 Hunspell 词典", lang))?;
+    if text.trim().is_empty() {
+      return Ok(Vec::new());
+    }
+
+    let mut cmd = Command::new(hunspell);
+    cmd
+      .arg("-a")
+      .arg("-i")
+      .arg("utf-8")
+      .arg("-d")
+      .arg(&dictionary);
+    if let Some(user_dic) = self.user_dictionary_path().filter(|p| p.is_file()) {
+      cmd.arg("-p").arg(user_dic);
+    }
+    let mut child = cmd
+      .stdin(Stdio::piped())
+      .stdout(Stdio::piped())
+      .stderr(Stdio::piped())
+      .spawn()
+      .map_err(|e| format!("执行 Hunspell 失败: {}", e))?;
+
+    // 每行前加 `^`，避免以 `*`、`#` 等开头的行被当作管道命令；另起线程写入，避免输出填满管道后互相等待
+    let input: String = text
+      .split('\n')
+      .map(|line| format!("^{}\n", line.trim_end_matches('\r')))
+      .collect();
+    let mut stdin = child.stdin.take().ok_or("无法写入 Hunspell 输入")?;
+    let writer = std::thread::spawn(move || stdin.write_all(input.as_bytes()));
+    let output = child
+      .wait_with_output()
+      .map_err(|e| format!("执行 Hunspell 失败: {}", e))?;
+    let _ = writer.join();
+    if !output.status.success() {
+      return Err(format!(
+        "拼写检查失败: {}",
+        String::from_utf8_lossy(&output.stderr).trim()
+      ));
+    }
+
+    let ignored = IGNORED_WORDS.lock().unwrap_or_else(|e| e.into_inner());
+    Ok(
+      parse_pipe_output(text, &String::from_utf8_lossy(&output.stdout))
+        .into_iter()
+        .filter(|m| !ignored.contains(&m.word))
+        .collect(),
+    )
+  }
+
+  /// 用户词典中的词
+  pub fn user_words(&self) -> Vec<String> {
+    self
+      .user_dictionary_path()
+      .and_then(|p| std::fs::read_to_string(p).ok())
+      .map(|content| {
+        content
+          .lines()
+          .map(str::trim)
+          .filter(|l| !l.is_empty())
+          .map(str::to_string)
+          .collect()
+      })
+      .unwrap_or_default()
+  }
+
+  /// 加入用户词典（已存在时不重复写入）
+  pub fn add_word(&self, word: &str) -> Result<(), String> {
+    let word = validate_word(word)?;
+    let path = self
+      .user_dictionary_path()
+      .ok_or("未打开工作区，无法保存用户词典")?;
+    let mut words = self.user_words();
+    if words.iter().any(|w| w == word) {
+      return Ok(());
+    }
+    words.push(word.to_string());
+    if let Some(parent) = path.parent() {
+      std::fs::create_dir_all(parent).map_err(|e| format!("创建词典目录失败: {}", e))?;
+    }
+    std::fs::write(&path, format!("{}\n", words.join("\n")))
+      .map_err(|e| format!("写入用户词典失败: {}", e))
+  }
+
+  /// 从用户词典中移除
+  pub fn remove_word(&self, word: &str) -> Result<bool, String> {
+    let Some(path) = self.user_dictionary_path() else {
+      return Ok(false);
+    };
+    let mut words = self.user_words();
+    let before = words.len();
+    words.retain(|w| w != word.trim());
+    if words.len() == before {
+      return Ok(false);
+    }
+    let content = if words.is_empty() {
+      String::new()
+    } else {
+      format!("{}\n", words.join("\n"))
+    };
+    std::fs::write(&path, content).map_err(|e| format!("写入用户词典失败: {}", e))?;
+    Ok(true)
+  }
+}
+
+/// 本次运行内忽略该词
+pub fn ignore_word(word: &str) -> Result<(), String> {
+  let word = validate_word(word)?;
+  IGNORED_WORDS
+    .lock()
+    .unwrap_or_else(|e| e.into_inner())
+    .insert(word.to_string());
+  Ok(())
+}
+
+/// 单个词：非空、不含空白，且不含 Hunspell 词典的词缀标记分隔符 `/`
+fn validate_word(word: &str) -> Result<&str, String> {
+  let word = word.trim();
+  if word.is_empty() || word.chars().count() > 100 {
+    return Err("词长度必须在 1-100 个字符之间".to_string());
+  }
+  if word.chars().any(|c| c.is_whitespace() || c == '/') {
+    return Err(format!("无效的词: {}", word));
+  }
+  Ok(word)
+}
+
+fn find_dictionary_in(dirs: &[PathBuf], lang: &str) -> Option<PathBuf> {
+  dirs
+    .iter()
+    .map(|dir| dir.join(lang))
+    .find(|base| base.with_extension("aff").is_file() && base.with_extension("dic").is_file())
+}
+
+/// 解析 Hunspell 管道模式输出：首行是版本说明，之后每个输入行对应一组结果，以空行结束。
+/// `& 词 建议数 偏移: 建议1, 建议2` 表示有建议，`# 词 偏移` 表示无建议，`*` / `+` / `-` 表示正确。
+/// 偏移在不同版本中对前缀 `^` 与多字节字符的处理不一致，因此按顺序在原行中查找词的位置
+fn parse_pipe_output(text: &str, output: &str) -> Vec<Misspelling> {
+  let mut results = output.lines().skip_while(|l| l.starts_with("@(#)"));
+  let mut misspellings = Vec::new();
+  let mut line_start = 0;
+  for (index, line) in text.split('\n').enumerate() {
+    let mut cursor = 0;
+    for result in results.by_ref() {
+      if result.is_empty() {
+        break;
+      }
+      let (word, suggestions) = if let Some(rest) = result.strip_prefix('&') {
+        let (head, tail) = rest.split_once(':').unwrap_or((rest, ""));
+        let suggestions = tail
+          .split(", ")
+          .map(str::trim)
+          .filter(|s| !s.is_empty())
+          .map(str::to_string)
+          .collect();
+        (head.split_whitespace().next(), suggestions)
+      } else if let Some(rest) = result.strip_prefix('#') {
+        (rest.split_whitespace().next(), Vec::new())
+      } else {
+        continue;
+      };
+      let Some(word) = word else {
+        continue;
+      };
+      let Some(pos) = line[cursor..].find(word).map(|p| p + cursor) else {
+        continue;
+      };
+      cursor = pos + word.len();
+      misspellings.push(Misspelling {
+        word: word.to_string(),
+        start: line_start + pos,
+        end: line_start + cursor,
+        line: index + 1,
+        suggestions,
+      });
+    }
+    line_start += line.len() + 1;
+  }
+  misspellings
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn parses_pipe_output_into_byte_ranges() {
+    let text = "Teh cat\n\n中文 recieve, teh end\r\nok";
+    let output = "@(#) International Ispell Version 3.2.06 (but really Hunspell 1.7.2)\n\
+                  & Teh 2 0: The, Tech\n\
+                  *\n\
+                  \n\
+                  \n\
+                  & recieve 1 3: receive\n\
+                  # teh 12\n\
+                  *\n\
+                  \n\
+                  *\n\
+                  \n";
+    let found = parse_pipe_output(text, output);
+    assert_eq!(found.len(), 3);
+    assert_eq!(found[0].suggestions, vec!["The", "Tech"]);
+    assert_eq!((found[0].start, found[0].end, found[0].line), (0, 3, 1));
+    for m in &found[1..] {
+      assert_eq!(&text[m.start..m.end], m.word);
+      assert_eq!(m.line, 3);
+    }
+    assert_eq!(found[1].suggestions, vec!["receive"]);
+    assert!(found[2].suggestions.is_empty());
+  }
+
+  #[test]
+  fn workspace_dictionaries_and_user_words() {
+    let workspace =
+      std::env::temp_dir().join(format!("binder_spellcheck_{}", uuid::Uuid::new_v4()));
+    let dict_dir = workspace.join(".binder").join("dictionaries");
+    std::fs::create_dir_all(&dict_dir).unwrap();
+    std::fs::write(dict_dir.join("xx_TEST.aff"), "SET UTF-8\n").unwrap();
+    std::fs::write(dict_dir.join("xx_TEST.dic"), "1\nword\n").unwrap();
+
+    let service = SpellcheckService::new(Some(&workspace));
+    assert!(service
+      .available_languages()
+      .contains(&"xx_TEST".to_string()));
+    assert_eq!(
+      service.find_dictionary("xx_TEST"),
+      Some(dict_dir.join("xx_TEST"))
+    );
+    assert_eq!(
+      service.resolve_language(None).unwrap(),
+      DEFAULT_SPELLCHECK_LANGUAGE
+    );
+    assert!(service.resolve_language(Some("../etc")).is_err());
+
+    service.add_word("Binder").unwrap();
+    service.add_word("Binder").unwrap();
+    assert!(service.add_word("two words").is_err());
+    assert_eq!(service.user_words(), vec!["Binder"]);
+    assert!(service.remove_word("Binder").unwrap());
+    assert!(service.user_words().is_empty());
+    let _ = std::fs::remove_dir_all(&workspace);
+  }
+}
@@ -36,8 +36,8 @@ impl StreamingResponseHandler {
 
     // 检查1：content是否完全等于累积文本的末尾
     if accumulated.ends_with(text) {
-      eprintln!(
-        "⚠️ [StreamingResponseHandler] 检测到重复文本（完全重复），跳过: '{}'",
+      tracing::warn!(
+        "[StreamingResponseHandler] 检测到重复文本（完全重复），跳过: '{}'",
         safe_truncate(text, 50)
       );
       return None;
@@ -57,8 +57,8 @@ impl StreamingResponseHandler {
       // 检查是否形成了明显的重复模式（连续出现2次或更多）
       let pattern = format!("{}{}", text, text);
       if check_part.contains(&pattern) {
-        eprintln!(
-          "⚠️ [StreamingResponseHandler] 检测到重复文本（重复模式），跳过: '{}'",
+        tracing::warn!(
+          "[StreamingResponseHandler] 检测到重复文本（重复模式），跳过: '{}'",
           safe_truncate(text, 50)
         );
         return None;
@@ -81,8 +81,8 @@ impl StreamingResponseHandler {
           // 如果content在最后部分出现了多次，说明是重复的
           let occurrences = last_part.matches(text).count();
           if occurrences >= 2 {
-            eprintln!(
-              "⚠️ [StreamingResponseHandler] 检测到重复文本（多次出现），跳过: '{}'",
+            tracing::warn!(
+              "[StreamingResponseHandler] 检测到重复文本（多次出现），跳过: '{}'",
               safe_truncate(text, 50)
             );
             return None;
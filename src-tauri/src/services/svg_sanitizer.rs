@@ -0,0 +1,170 @@
+//! SVG 清理：插入工作区或嵌入预览前移除可执行内容与外部引用。
+//!
+//! 删除 `<script>`、`<foreignObject>`、`<style>`、动画（可改写属性）等元素及其子树、`on*` 事件属性、
+//! 指向外部资源或 `javascript:` 的链接与 `url(...)`，以及 DOCTYPE（可定义实体）与处理指令。
+
+use quick_xml::events::attributes::Attribute;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::{Reader, Writer};
+
+/// 整个子树都会被删除的元素（小写比较）
+const FORBIDDEN_ELEMENTS: [&str; 11] = [
+  "script",
+  "foreignobject",
+  "iframe",
+  "embed",
+  "object",
+  "style",
+  "set",
+  "animate",
+  "animatemotion",
+  "animatetransform",
+  "animatecolor",
+];
+
+pub fn is_svg_path(path: &std::path::Path) -> bool {
+  path
+    .extension()
+    .and_then(|e| e.to_str())
+    .is_some_and(|e| e.eq_ignore_ascii_case("svg"))
+}
+
+/// 返回清理后的 SVG 文本；无法解析时返回错误（调用方不应再使用原文件内容）
+pub fn sanitize_svg(svg: &str) -> Result<String, String> {
+  let mut reader = Reader::from_str(svg);
+  let mut writer = Writer::new(Vec::new());
+  // 处于被删除子树内时记录嵌套深度
+  let mut skip_depth = 0usize;
+
+  loop {
+    let event = reader
+      .read_event()
+      .map_err(|e| format!("SVG 解析失败（位置 {}）: {}", reader.buffer_position(), e))?;
+    let output = match event {
+      Event::Eof => break,
+      Event::Start(e) => {
+        if skip_depth > 0 || is_forbidden(&e) {
+          skip_depth += 1;
+          None
+        } else {
+          Some(Event::Start(clean_element(&e)))
+        }
+      }
+      Event::End(_) if skip_depth > 0 => {
+        skip_depth -= 1;
+        None
+      }
+      Event::Empty(e) => {
+        if skip_depth > 0 || is_forbidden(&e) {
+          None
+        } else {
+          Some(Event::Empty(clean_element(&e)))
+        }
+      }
+      Event::DocType(_) | Event::PI(_) => None,
+      _ if skip_depth > 0 => None,
+      other => Some(other),
+    };
+    if let Some(event) = output {
+      writer
+        .write_event(event)
+        .map_err(|e| format!("SVG 写入失败: {}", e))?;
+    }
+  }
+
+  String::from_utf8(writer.into_inner()).map_err(|e| format!("SVG 编码无效: {}", e))
+}
+
+fn is_forbidden(element: &BytesStart) -> bool {
+  let name = String::from_utf8_lossy(element.local_name().as_ref()).to_ascii_lowercase();
+  FORBIDDEN_ELEMENTS.contains(&name.as_str())
+}
+
+/// 复制元素并只保留安全的属性
+fn clean_element(element: &BytesStart) -> BytesStart<'static> {
+  let mut cleaned = element.to_owned();
+  cleaned.clear_attributes();
+  for attr in element.attributes().flatten() {
+    if is_safe_attribute(&attr) {
+      cleaned.push_attribute(attr);
+    }
+  }
+  cleaned.into_owned()
+}
+
+fn is_safe_attribute(attr: &Attribute) -> bool {
+  let name = String::from_utf8_lossy(attr.key.local_name().as_ref()).to_ascii_lowercase();
+  if name.starts_with("on") {
+    return false;
+  }
+  // 按解码后的值判断，避免 `&#106;avascript:` 之类的绕过；忽略空白与控制字符
+  let Ok(value) = attr.unescape_value() else {
+    return false;
+  };
+  let value: String = value
+    .chars()
+    .filter(|c| !c.is_whitespace() && !c.is_control())
+    .collect::<String>()
+    .to_ascii_lowercase();
+  if value.contains("javascript:") || value.contains("vbscript:") {
+    return false;
+  }
+  // `fill="url(...)"`、`style` 中的 `url(...)` 只允许引用文档内的元素
+  let external_url = value.match_indices("url(").any(|(i, _)| {
+    !value[i + 4..]
+      .trim_start_matches(['"', '\''])
+      .starts_with('#')
+  });
+  if external_url {
+    return false;
+  }
+  if name == "href" {
+    // 只允许文档内锚点与内嵌位图，外部 URL、相对文件、内嵌 SVG 都会被移除
+    return value.starts_with('#')
+      || (value.starts_with("data:image/") && !value.starts_with("data:image/svg"));
+  }
+  true
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn removes_scripts_handlers_and_external_links() {
+    let svg = r##"<?xml version="1.0"?>
+<!DOCTYPE svg [<!ENTITY x "boom">]>
+<svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink" onload="alert(1)">
+  <script>alert(1)</script>
+  <foreignObject><div><iframe src="https://evil"/></div></foreignObject>
+  <a xlink:href="&#106;avascript:alert(1)"><rect width="10" height="10" onclick="x()" fill="red"/></a>
+  <use href="#shape"/><image href="https://tracker/pixel.png"/>
+  <style>@import url(https://evil/a.css);</style>
+  <set attributeName="href" to="javascript:alert(1)"/>
+  <animate attributeName="href" values="https://evil"/>
+  <circle fill="url(#grad)" stroke="url( 'https://evil/s' )" style="background:URL(//evil/b)"/>
+</svg>"##;
+
+    let cleaned = sanitize_svg(svg).unwrap();
+    for removed in [
+      "<script",
+      "foreignObject",
+      "iframe",
+      "onload",
+      "onclick",
+      "javascript",
+      "tracker",
+      "ENTITY",
+      "<style",
+      "<set",
+      "<animate",
+      "evil",
+    ] {
+      assert!(!cleaned.contains(removed), "{} 未被移除", removed);
+    }
+    assert!(cleaned.contains(r#"<rect width="10" height="10" fill="red"/>"#));
+    assert!(cleaned.contains(r##"<use href="#shape"/><image/>"##));
+    assert!(cleaned.contains(r##"<circle fill="url(#grad)"/>"##));
+    assert!(sanitize_svg("<svg><g></svg>").is_err());
+  }
+}
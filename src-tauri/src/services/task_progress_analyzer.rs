@@ -247,9 +247,9 @@ impl TaskProgressAnalyzer {
 
     // 判断任务类型（在进度计算之前确定，以便进度段按类型分支）
     let task_type = if has_edit_doc {
-      let has_update_file = tool_results
-        .iter()
-        .any(|(_, name, result)| name == "update_file" && result.success);
+      let has_update_file = tool_results.iter().any(|(_, name, result)| {
+        (name == "update_file" || name == "apply_patch") && result.success
+      });
       if has_update_file {
         TaskType::MultiDocumentEdit
       } else {
@@ -261,7 +261,10 @@ impl TaskProgressAnalyzer {
       TaskType::FileMove
     } else if is_delete_task {
       TaskType::FileDelete
-    } else if tool_results.iter().any(|(_, name, _)| name == "read_file") {
+    } else if tool_results
+      .iter()
+      .any(|(_, name, _)| name == "read_file" || name == "read_document")
+    {
       TaskType::FileRead
     } else {
       TaskType::Unknown
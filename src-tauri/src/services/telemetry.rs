@@ -0,0 +1,450 @@
+//! 匿名使用统计与崩溃报告（默认关闭）。
+//!
+//! 开启后在本地记录功能使用次数和崩溃（panic）信息，并定期上传到设置的地址；关闭期间不记录任何数据。
+//! 上传内容只包含随机安装 ID、版本、系统类型、功能计数和崩溃信息（panic 位置、Rust 源码调用栈与消息哈希，
+//! 原始 panic 消息只写入本地日志），
+//! 可用 `preview_telemetry_payload` 查看完整内容，`purge_telemetry_data` 清除。
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// 本地最多保留的崩溃记录
+const MAX_CRASHES: usize = 20;
+const MAX_BACKTRACE_BYTES: usize = 8 * 1024;
+/// 自动上传间隔
+const UPLOAD_INTERVAL_MS: i64 = 24 * 60 * 60 * 1000;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TelemetrySettings {
+  pub enabled: bool,
+  /// 上传地址；未设置时只在本地记录
+  pub endpoint: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CrashReport {
+  /// 毫秒时间戳
+  pub timestamp: i64,
+  /// panic 消息的哈希（数字与路径归一化后计算，同类崩溃相同）；消息可能带有文档内容，不上传原文
+  pub message_hash: String,
+  pub location: Option<String>,
+  /// 只保留带 `.rs` 源码位置的调用栈帧
+  pub backtrace: String,
+}
+
+/// 上传的数据（`preview_telemetry_payload` 返回的即为实际发送内容）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TelemetryPayload {
+  pub install_id: String,
+  pub app_version: String,
+  pub os: String,
+  pub arch: String,
+  pub feature_usage: BTreeMap<String, u64>,
+  pub crashes: Vec<CrashReport>,
+}
+
+impl TelemetryPayload {
+  pub fn is_empty(&self) -> bool {
+    self.feature_usage.is_empty() && self.crashes.is_empty()
+  }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct TelemetryData {
+  settings: TelemetrySettings,
+  /// 随机生成，与用户身份无关；清除数据时重新生成
+  install_id: String,
+  feature_usage: BTreeMap<String, u64>,
+  crashes: Vec<CrashReport>,
+  last_upload_ms: Option<i64>,
+}
+
+pub struct Telemetry {
+  path: PathBuf,
+  data: TelemetryData,
+}
+
+impl Telemetry {
+  /// 读取本地数据；文件不存在或损坏时从空数据开始
+  pub fn open(path: PathBuf) -> Self {
+    let data = std::fs::read_to_string(&path)
+      .ok()
+      .and_then(|content| serde_json::from_str(&content).ok())
+      .unwrap_or_default();
+    Self { path, data }
+  }
+
+  fn default_path() -> PathBuf {
+    dirs::data_dir()
+      .unwrap_or_else(std::env::temp_dir)
+      .join("binder")
+      .join("telemetry.json")
+  }
+
+  fn save(&self) -> Result<(), String> {
+    if let Some(parent) = self.path.parent() {
+      std::fs::create_dir_all(parent).map_err(|e| format!("创建统计数据目录失败: {}", e))?;
+    }
+    let json =
+      serde_json::to_string_pretty(&self.data).map_err(|e| format!("序列化统计数据失败: {}", e))?;
+    std::fs::write(&self.path, json).map_err(|e| format!("写入统计数据失败: {}", e))
+  }
+
+  pub fn settings(&self) -> TelemetrySettings {
+    self.data.settings.clone()
+  }
+
+  /// 更新设置；关闭时清除已记录的数据
+  pub fn set_settings(&mut self, settings: TelemetrySettings) -> Result<(), String> {
+    if let Some(endpoint) = &settings.endpoint {
+      if !endpoint.starts_with("https://") && !endpoint.starts_with("http://") {
+        return Err(format!("上传地址必须是 http(s) 地址: {}", endpoint));
+      }
+    }
+    if !settings.enabled {
+      self.data.feature_usage.clear();
+      self.data.crashes.clear();
+    }
+    if settings.enabled && self.data.install_id.is_empty() {
+      self.data.install_id = uuid::Uuid::new_v4().to_string();
+    }
+    self.data.settings = settings;
+    self.save()
+  }
+
+  /// 功能名只允许小写字母、数字和 `_.-`，避免把文件名等用户内容记进统计
+  pub fn record_feature(&mut self, feature: &str) -> Result<(), String> {
+    let valid = !feature.is_empty()
+      && feature.len() <= 64
+      && feature
+        .chars()
+        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || "_.-".contains(c));
+    if !valid {
+      return Err(format!("功能名无效: {}", feature));
+    }
+    if !self.data.settings.enabled {
+      return Ok(());
+    }
+    *self
+      .data
+      .feature_usage
+      .entry(feature.to_string())
+      .or_insert(0) += 1;
+    self.save()
+  }
+
+  pub fn record_crash(
+    &mut self,
+    message: &str,
+    location: Option<String>,
+    backtrace: &str,
+  ) -> Result<(), String> {
+    if !self.data.settings.enabled {
+      return Ok(());
+    }
+    let mut backtrace = anonymize(&source_frames(backtrace));
+    truncate_at_char_boundary(&mut backtrace, MAX_BACKTRACE_BYTES);
+    self.data.crashes.push(CrashReport {
+      timestamp: chrono::Utc::now().timestamp_millis(),
+      message_hash: message_hash(message),
+      location: location.map(|l| anonymize(&l)),
+      backtrace,
+    });
+    let overflow = self.data.crashes.len().saturating_sub(MAX_CRASHES);
+    self.data.crashes.drain(..overflow);
+    self.save()
+  }
+
+  pub fn payload(&self) -> TelemetryPayload {
+    TelemetryPayload {
+      install_id: self.data.install_id.clone(),
+      app_version: env!("CARGO_PKG_VERSION").to_string(),
+      os: std::env::consts::OS.to_string(),
+      arch: std::env::consts::ARCH.to_string(),
+      feature_usage: self.data.feature_usage.clone(),
+      crashes: self.data.crashes.clone(),
+    }
+  }
+
+  /// 清除已记录的数据并重新生成安装 ID
+  pub fn purge(&mut self) -> Result<(), String> {
+    self.data.feature_usage.clear();
+    self.data.crashes.clear();
+    self.data.last_upload_ms = None;
+    self.data.install_id = if self.data.settings.enabled {
+      uuid::Uuid::new_v4().to_string()
+    } else {
+      String::new()
+    };
+    self.save()
+  }
+
+  fn upload_due(&self, now_ms: i64) -> bool {
+    self.data.settings.enabled
+      && self.data.settings.endpoint.is_some()
+      && self
+        .data
+        .last_upload_ms
+        .map_or(true, |last| now_ms - last >= UPLOAD_INTERVAL_MS)
+  }
+
+  /// 上传成功后扣除已发送的部分（上传期间新增的记录保留到下一批）
+  fn mark_uploaded(&mut self, sent: &TelemetryPayload, now_ms: i64) -> Result<(), String> {
+    for (feature, count) in &sent.feature_usage {
+      if let Some(current) = self.data.feature_usage.get_mut(feature) {
+        *current = current.saturating_sub(*count);
+      }
+    }
+    self.data.feature_usage.retain(|_, count| *count > 0);
+    self
+      .data
+      .crashes
+      .retain(|crash| !sent.crashes.contains(crash));
+    self.data.last_upload_ms = Some(now_ms);
+    self.save()
+  }
+}
+
+/// Unix、Windows 盘符与 UNC 绝对路径；前一个字符不能是路径或 URL 的一部分
+static ABSOLUTE_PATH: Lazy<Regex> = Lazy::new(|| {
+  Regex::new(r#"(^|[^A-Za-z0-9_.~:/\\-])((?:/|[A-Za-z]:[\\/]|\\\\)[^\s"'<>()\[\]{},;:]+)"#).unwrap()
+});
+
+/// 把绝对路径替换为 `<path>`（其中可能含用户名和文档名）；
+/// Rust 源文件保留文件名，便于从调用栈定位
+fn anonymize(text: &str) -> String {
+  ABSOLUTE_PATH
+    .replace_all(text, |caps: &regex::Captures| {
+      let path = &caps[2];
+      let file_name = path.rsplit(['/', '\\']).next().unwrap_or_default();
+      if file_name.ends_with(".rs") {
+        format!("{}<path>/{}", &caps[1], file_name)
+      } else {
+        format!("{}<path>", &caps[1])
+      }
+    })
+    .into_owned()
+}
+
+static DIGITS: Lazy<Regex> = Lazy::new(|| Regex::new(r"[0-9]+").unwrap());
+
+/// 消息归一化（去掉路径、数字）后的 SHA-256 前 16 位十六进制
+fn message_hash(message: &str) -> String {
+  use sha2::{Digest, Sha256};
+  let anonymized = anonymize(message);
+  let normalized = DIGITS.replace_all(&anonymized, "N");
+  let hash = format!("{:x}", Sha256::digest(normalized.as_bytes()));
+  hash[..16].to_string()
+}
+
+/// 从 `Backtrace` 的文本中挑出带 `.rs` 源码位置的帧（帧标题行及其 `at` 行）
+fn source_frames(backtrace: &str) -> String {
+  let mut frames: Vec<Vec<&str>> = Vec::new();
+  for line in backtrace.lines() {
+    match frames.last_mut() {
+      Some(frame) if line.trim_start().starts_with("at ") => frame.push(line),
+      _ => frames.push(vec![line]),
+    }
+  }
+  frames
+    .into_iter()
+    .filter(|frame| frame[1..].iter().any(|line| line.contains(".rs:")))
+    .flatten()
+    .collect::<Vec<_>>()
+    .join("\n")
+}
+
+fn truncate_at_char_boundary(text: &mut String, max_bytes: usize) {
+  if text.len() > max_bytes {
+    let mut end = max_bytes;
+    while !text.is_char_boundary(end) {
+      end -= 1;
+    }
+    text.truncate(end);
+  }
+}
+
+static TELEMETRY: Lazy<Mutex<Telemetry>> =
+  Lazy::new(|| Mutex::new(Telemetry::open(Telemetry::default_path())));
+
+pub fn with_telemetry<R>(f: impl FnOnce(&mut Telemetry) -> R) -> Result<R, String> {
+  let mut telemetry = TELEMETRY
+    .lock()
+    .map_err(|e| format!("获取统计数据失败: {}", e))?;
+  Ok(f(&mut telemetry))
+}
+
+/// 记录一次功能使用（未开启统计时忽略）
+pub fn record_feature(feature: &str) {
+  if let Err(e) = with_telemetry(|t| t.record_feature(feature)).and_then(|r| r) {
+    tracing::debug!("记录功能使用失败: {}", e);
+  }
+}
+
+/// 安装 panic 钩子：原始消息写入本地日志，开启统计时记录脱敏的崩溃信息，之后交给原有钩子处理
+pub fn install_panic_hook() {
+  let previous = std::panic::take_hook();
+  std::panic::set_hook(Box::new(move |info| {
+    let message = info
+      .payload()
+      .downcast_ref::<&str>()
+      .map(|s| s.to_string())
+      .or_else(|| info.payload().downcast_ref::<String>().cloned())
+      .unwrap_or_else(|| "未知 panic".to_string());
+    let location = info
+      .location()
+      .map(|l| format!("{}:{}", l.file(), l.line()));
+    tracing::error!(
+      "panic: {} ({})",
+      message,
+      location.as_deref().unwrap_or("-")
+    );
+    let backtrace = std::backtrace::Backtrace::force_capture().to_string();
+    // panic 可能发生在持有锁期间，拿不到锁时放弃记录
+    if let Ok(mut telemetry) = TELEMETRY.try_lock() {
+      let _ = telemetry.record_crash(&message, location, &backtrace);
+    }
+    previous(info);
+  }));
+}
+
+/// 上传待发送的数据；返回是否实际发送
+pub async fn upload(force: bool) -> Result<bool, String> {
+  let now_ms = chrono::Utc::now().timestamp_millis();
+  let (endpoint, payload) = with_telemetry(|t| {
+    let due = if force {
+      t.data.settings.enabled && t.data.settings.endpoint.is_some()
+    } else {
+      t.upload_due(now_ms)
+    };
+    due.then(|| (t.data.settings.endpoint.clone(), t.payload()))
+  })?
+  .and_then(|(endpoint, payload)| endpoint.map(|e| (e, payload)))
+  .ok_or_else(|| "未开启使用统计或未设置上传地址".to_string())?;
+  if payload.is_empty() {
+    return Ok(false);
+  }
+
+  let response = reqwest::Client::new()
+    .post(&endpoint)
+    .timeout(Duration::from_secs(30))
+    .json(&payload)
+    .send()
+    .await
+    .map_err(|e| format!("上传使用统计失败: {}", e))?;
+  if !response.status().is_success() {
+    return Err(format!("上传使用统计失败: HTTP {}", response.status()));
+  }
+  with_telemetry(|t| t.mark_uploaded(&payload, now_ms))??;
+  Ok(true)
+}
+
+/// 启动时调用：距上次上传超过一天时上传
+pub async fn upload_if_due() {
+  let due =
+    with_telemetry(|t| t.upload_due(chrono::Utc::now().timestamp_millis())).unwrap_or(false);
+  if due {
+    if let Err(e) = upload(false).await {
+      tracing::warn!("{}", e);
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn records_only_when_enabled_and_purges() {
+    let path = std::env::temp_dir().join(format!("binder_telemetry_{}.json", uuid::Uuid::new_v4()));
+    let mut telemetry = Telemetry::open(path.clone());
+    telemetry.record_feature("organize_files").unwrap();
+    assert!(telemetry.payload().is_empty());
+    assert!(telemetry.record_feature("报告.docx").is_err());
+
+    telemetry
+      .set_settings(TelemetrySettings {
+        enabled: true,
+        endpoint: Some("https://telemetry.example.com/v1".to_string()),
+      })
+      .unwrap();
+    telemetry.record_feature("organize_files").unwrap();
+    telemetry.record_feature("organize_files").unwrap();
+    telemetry
+      .record_crash(
+        "无法打开 /home/alice/secret/notes.md\n正文内容",
+        Some("src/main.rs:3".to_string()),
+        "   0: std::backtrace::Backtrace::force_capture\n             at /rustc/abc/library/std/src/backtrace.rs:101:18\n   1: main\n   2: binder::open\n             at /home/alice/binder/src/open.rs:7:5",
+      )
+      .unwrap();
+
+    let reopened = Telemetry::open(path.clone());
+    let payload = reopened.payload();
+    assert_eq!(payload.feature_usage.get("organize_files"), Some(&2));
+    assert!(!payload.install_id.is_empty());
+    let crash = &payload.crashes[0];
+    assert_eq!(
+      crash.message_hash,
+      message_hash("无法打开 /home/bob/other.md\n正文内容")
+    );
+    assert_ne!(
+      crash.message_hash,
+      message_hash("无法保存 /home/alice/secret/notes.md")
+    );
+    assert!(!serde_json::to_string(crash).unwrap().contains("正文"));
+    assert_eq!(crash.location.as_deref(), Some("src/main.rs:3"));
+    assert_eq!(
+      crash.backtrace,
+      "   0: std::backtrace::Backtrace::force_capture\n             at <path>/backtrace.rs:101:18\n   2: binder::open\n             at <path>/open.rs:7:5"
+    );
+    assert!(reopened.upload_due(0));
+
+    telemetry.record_feature("chat").unwrap();
+    telemetry.mark_uploaded(&payload, 1).unwrap();
+    assert_eq!(
+      telemetry.payload().feature_usage,
+      BTreeMap::from([("chat".to_string(), 1)])
+    );
+    assert!(telemetry.payload().crashes.is_empty() && !telemetry.upload_due(2));
+
+    let install_id = payload.install_id;
+    telemetry.purge().unwrap();
+    assert!(telemetry.payload().is_empty());
+    assert_ne!(telemetry.payload().install_id, install_id);
+    let _ = std::fs::remove_file(&path);
+  }
+
+  #[test]
+  fn anonymize_strips_absolute_paths_but_keeps_urls_and_source_names() {
+    for (text, expected) in [
+      ("打开 /Users/alice/报告.docx 失败", "打开 <path> 失败"),
+      (r"读取 C:\Users\alice\notes.md 失败", "读取 <path> 失败"),
+      (r"(\\server\share\plan.xlsx)", "(<path>)"),
+      (
+        "at /home/alice/.cargo/registry/src/serde-1.0/src/de.rs:10:5",
+        "at <path>/de.rs:10:5",
+      ),
+      (
+        "请求 https://api.example.com/v1 失败",
+        "请求 https://api.example.com/v1 失败",
+      ),
+      (
+        "src/services/telemetry.rs:12",
+        "src/services/telemetry.rs:12",
+      ),
+    ] {
+      assert_eq!(anonymize(text), expected, "{}", text);
+    }
+
+    let mut message = "错".repeat(100);
+    truncate_at_char_boundary(&mut message, 200);
+    assert_eq!(message, "错".repeat(66));
+  }
+}
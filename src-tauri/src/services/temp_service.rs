@@ -0,0 +1,375 @@
+//! 临时文件管理：记录本应用创建的每个临时文件或目录（所属模块、过期时间），统一清理。
+//!
+//! 索引保存在应用缓存目录的 `temp_index.json`，登记 / 注销后延迟合并写入。启动时（`init`）删除上次运行
+//! 遗留的会话级临时文件与已过期的临时文件，运行期间（`spawn_expiry_sweep`）定期删除过期的临时文件，
+//! 退出时（`cleanup_session`）删除本次运行的会话级临时文件。
+//! `TempPath` 离开作用域时立即删除并注销；未初始化（如测试、命令行模式）时只在内存中记录。
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const INDEX_FILE: &str = "temp_index.json";
+/// 登记 / 注销后延迟写索引，期间的变化合并为一次写入
+const SAVE_DELAY: Duration = Duration::from_secs(2);
+/// 运行期间清理过期临时文件的间隔
+const SWEEP_INTERVAL: Duration = Duration::from_secs(3600);
+
+static REGISTRY: Lazy<Mutex<TempRegistry>> = Lazy::new(|| Mutex::new(TempRegistry::default()));
+/// 已安排延迟写索引
+static SAVE_PENDING: AtomicBool = AtomicBool::new(false);
+
+/// 一个已登记的临时文件或目录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TempArtifact {
+  pub path: String,
+  /// 创建者，如 `pandoc`、`print`、`external_file`
+  pub owner: String,
+  /// 创建时间（Unix 秒）
+  pub created_at: u64,
+  /// 过期时间（Unix 秒）；None 表示会话级，应用退出时删除
+  pub expires_at: Option<u64>,
+}
+
+impl TempArtifact {
+  fn is_expired(&self, now: u64) -> bool {
+    self.expires_at.is_some_and(|at| at <= now)
+  }
+}
+
+#[derive(Default)]
+struct TempRegistry {
+  /// 索引文件路径；None 时不持久化
+  index_path: Option<PathBuf>,
+  entries: HashMap<String, TempArtifact>,
+}
+
+fn unix_now() -> u64 {
+  SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|d| d.as_secs())
+    .unwrap_or(0)
+}
+
+fn path_key(path: &Path) -> String {
+  path.to_string_lossy().to_string()
+}
+
+/// 删除文件或目录；已不存在视为成功
+fn remove_path(path: &Path) -> bool {
+  let result = if path.is_dir() {
+    fs::remove_dir_all(path)
+  } else {
+    fs::remove_file(path)
+  };
+  match result {
+    Ok(()) => true,
+    Err(e) if e.kind() == std::io::ErrorKind::NotFound => true,
+    Err(e) => {
+      tracing::warn!("删除临时文件失败: {:?} - {}", path, e);
+      false
+    }
+  }
+}
+
+impl TempRegistry {
+  fn load(cache_dir: &Path) -> Self {
+    let index_path = cache_dir.join(INDEX_FILE);
+    let entries = fs::read_to_string(&index_path)
+      .ok()
+      .and_then(|content| serde_json::from_str(&content).ok())
+      .unwrap_or_default();
+    Self {
+      index_path: Some(index_path),
+      entries,
+    }
+  }
+
+  fn save(&self) {
+    let Some(index_path) = &self.index_path else {
+      return;
+    };
+    if let Some(parent) = index_path.parent() {
+      let _ = fs::create_dir_all(parent);
+    }
+    match serde_json::to_string(&self.entries) {
+      Ok(json) => {
+        if let Err(e) = fs::write(index_path, json) {
+          tracing::warn!("写入临时文件索引失败: {}", e);
+        }
+      }
+      Err(e) => tracing::warn!("序列化临时文件索引失败: {}", e),
+    }
+  }
+
+  fn register(&mut self, path: &Path, owner: &str, ttl: Option<Duration>) {
+    let created_at = unix_now();
+    self.entries.insert(
+      path_key(path),
+      TempArtifact {
+        path: path_key(path),
+        owner: owner.to_string(),
+        created_at,
+        expires_at: ttl.map(|ttl| created_at + ttl.as_secs()),
+      },
+    );
+  }
+
+  fn unregister(&mut self, path: &Path) -> bool {
+    self.entries.remove(&path_key(path)).is_some()
+  }
+
+  fn persistent(&self) -> bool {
+    self.index_path.is_some()
+  }
+
+  /// 删除满足条件的临时文件；删除失败的保留记录，下次再试
+  fn sweep(&mut self, should_remove: impl Fn(&TempArtifact) -> bool) -> usize {
+    let targets: Vec<String> = self
+      .entries
+      .values()
+      .filter(|artifact| should_remove(artifact))
+      .map(|artifact| artifact.path.clone())
+      .collect();
+    let mut removed = 0;
+    for key in targets {
+      if remove_path(Path::new(&key)) {
+        self.entries.remove(&key);
+        removed += 1;
+      }
+    }
+    if removed > 0 {
+      self.save();
+    }
+    removed
+  }
+
+  /// 删除会话级与已过期的临时文件
+  fn sweep_leftovers(&mut self, now: u64) -> usize {
+    self.sweep(|artifact| artifact.expires_at.is_none() || artifact.is_expired(now))
+  }
+}
+
+fn with_registry<T>(f: impl FnOnce(&mut TempRegistry) -> T) -> T {
+  let mut registry = REGISTRY.lock().unwrap_or_else(|e| e.into_inner());
+  f(&mut registry)
+}
+
+/// 加载索引并清理上次运行遗留的临时文件（应用启动时调用一次），返回删除的数量
+pub fn init(cache_dir: &Path) -> usize {
+  with_registry(|registry| {
+    let mut loaded = TempRegistry::load(cache_dir);
+    let removed = loaded.sweep_leftovers(unix_now());
+    if removed > 0 {
+      tracing::info!("已清理 {} 个遗留的临时文件", removed);
+    }
+    // 初始化前已在内存中登记的（本次运行创建的）保留
+    loaded.entries.extend(registry.entries.drain());
+    loaded.save();
+    *registry = loaded;
+    removed
+  })
+}
+
+/// 在 SAVE_DELAY 后写一次索引；已安排时不重复安排（调用时不能持有 REGISTRY 锁）
+fn schedule_save() {
+  if SAVE_PENDING.swap(true, Ordering::AcqRel) {
+    return;
+  }
+  let spawned = std::thread::Builder::new()
+    .name("temp-index-save".to_string())
+    .spawn(|| {
+      std::thread::sleep(SAVE_DELAY);
+      SAVE_PENDING.store(false, Ordering::Release);
+      with_registry(|registry| registry.save());
+    });
+  if spawned.is_err() {
+    SAVE_PENDING.store(false, Ordering::Release);
+    with_registry(|registry| registry.save());
+  }
+}
+
+/// 登记临时文件或目录；ttl 为 None 时为会话级
+pub fn register(path: &Path, owner: &str, ttl: Option<Duration>) {
+  let persistent = with_registry(|registry| {
+    registry.register(path, owner, ttl);
+    registry.persistent()
+  });
+  if persistent {
+    schedule_save();
+  }
+}
+
+/// 删除临时文件或目录并注销
+pub fn remove(path: &Path) -> bool {
+  let removed = remove_path(path);
+  let changed = with_registry(|registry| registry.unregister(path) && registry.persistent());
+  if changed {
+    schedule_save();
+  }
+  removed
+}
+
+/// 删除已过期的临时文件
+pub fn cleanup_expired() -> usize {
+  let now = unix_now();
+  with_registry(|registry| registry.sweep(|artifact| artifact.is_expired(now)))
+}
+
+/// 定期删除运行期间过期的临时文件（应用启动后调用一次）
+pub fn spawn_expiry_sweep() {
+  tauri::async_runtime::spawn(async {
+    let start = tokio::time::Instant::now() + SWEEP_INTERVAL;
+    let mut interval = tokio::time::interval_at(start, SWEEP_INTERVAL);
+    loop {
+      interval.tick().await;
+      let removed = tokio::task::spawn_blocking(cleanup_expired)
+        .await
+        .unwrap_or(0);
+      if removed > 0 {
+        tracing::info!("已清理 {} 个过期的临时文件", removed);
+      }
+    }
+  });
+}
+
+/// 删除会话级与已过期的临时文件（应用退出时调用），并写入尚未保存的索引
+pub fn cleanup_session() -> usize {
+  with_registry(|registry| {
+    let removed = registry.sweep_leftovers(unix_now());
+    registry.save();
+    removed
+  })
+}
+
+/// 删除目录下的文件（max_age 为 None 时全部，否则只删修改时间早于 max_age 的），并注销对应记录。
+/// 目录中可能有索引建立前留下的文件，因此按目录扫描而不是只看索引
+pub fn cleanup_dir(dir: &Path, max_age: Option<Duration>) -> Result<usize, String> {
+  if !dir.exists() {
+    return Ok(0);
+  }
+  let now = SystemTime::now();
+  let entries = fs::read_dir(dir).map_err(|e| format!("读取临时目录失败: {}", e))?;
+  let mut cleaned = 0;
+  for entry in entries {
+    let path = entry.map_err(|e| format!("读取目录项失败: {}", e))?.path();
+    if !path.is_file() {
+      continue;
+    }
+    let expired = match max_age {
+      None => true,
+      Some(max_age) => path
+        .metadata()
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|modified| now.duration_since(modified).ok())
+        .is_some_and(|age| age > max_age),
+    };
+    if expired && remove(&path) {
+      cleaned += 1;
+    }
+  }
+  Ok(cleaned)
+}
+
+/// 当前登记的临时文件（按创建时间排序）
+pub fn list() -> Vec<TempArtifact> {
+  let mut artifacts: Vec<TempArtifact> =
+    with_registry(|registry| registry.entries.values().cloned().collect());
+  artifacts.sort_by_key(|artifact| artifact.created_at);
+  artifacts
+}
+
+/// 会话级临时路径：离开作用域时删除并注销
+pub struct TempPath {
+  path: PathBuf,
+}
+
+impl TempPath {
+  pub fn path(&self) -> &Path {
+    &self.path
+  }
+}
+
+impl Drop for TempPath {
+  fn drop(&mut self) {
+    remove(&self.path);
+  }
+}
+
+/// 在系统临时目录下登记一个新的临时文件路径（不创建文件），如 `binder_pandoc_<uuid>.html`
+pub fn temp_file(owner: &str, extension: &str) -> TempPath {
+  let path = std::env::temp_dir().join(format!(
+    "binder_{}_{}.{}",
+    owner,
+    uuid::Uuid::new_v4(),
+    extension
+  ));
+  register(&path, owner, None);
+  TempPath { path }
+}
+
+/// 在系统临时目录下创建并登记一个临时目录
+pub fn temp_dir(owner: &str) -> Result<TempPath, String> {
+  let path = std::env::temp_dir().join(format!("binder_{}_{}", owner, uuid::Uuid::new_v4()));
+  fs::create_dir_all(&path).map_err(|e| format!("创建临时目录失败: {}", e))?;
+  register(&path, owner, None);
+  Ok(TempPath { path })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn temp_paths_are_removed_on_drop() {
+    let dir = temp_dir("test").unwrap();
+    fs::write(dir.path().join("a.txt"), "x").unwrap();
+    let file = temp_file("test", "html");
+    fs::write(file.path(), "<p>x</p>").unwrap();
+    let paths = [dir.path().to_path_buf(), file.path().to_path_buf()];
+    assert!(list()
+      .iter()
+      .any(|artifact| Path::new(&artifact.path) == paths[0] && artifact.owner == "test"));
+
+    drop((dir, file));
+    for path in &paths {
+      assert!(!path.exists());
+      assert!(list()
+        .iter()
+        .all(|artifact| Path::new(&artifact.path) != path));
+    }
+  }
+
+  #[test]
+  fn startup_sweep_removes_leftover_session_and_expired_artifacts() {
+    let cache_dir =
+      std::env::temp_dir().join(format!("binder_temp_index_{}", uuid::Uuid::new_v4()));
+    let session = cache_dir.join("session.html");
+    let expired = cache_dir.join("expired.png");
+    let fresh = cache_dir.join("fresh.png");
+    fs::create_dir_all(&cache_dir).unwrap();
+    for path in [&session, &expired, &fresh] {
+      fs::write(path, "x").unwrap();
+    }
+
+    let mut registry = TempRegistry::load(&cache_dir);
+    registry.register(&session, "pandoc", None);
+    registry.register(&expired, "external_file", Some(Duration::ZERO));
+    registry.register(&fresh, "external_file", Some(Duration::from_secs(3600)));
+    registry.save();
+
+    // 模拟下次启动：重新加载索引后清理
+    let mut reloaded = TempRegistry::load(&cache_dir);
+    assert_eq!(reloaded.entries.len(), 3);
+    assert_eq!(reloaded.sweep_leftovers(unix_now()), 2);
+    assert!(!session.exists() && !expired.exists() && fresh.exists());
+    assert_eq!(TempRegistry::load(&cache_dir).entries.len(), 1);
+    let _ = fs::remove_dir_all(&cache_dir);
+  }
+}
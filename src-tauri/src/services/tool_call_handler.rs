@@ -2,8 +2,153 @@
 //!
 //! 负责执行工具调用，处理工具结果，管理工具调用状态
 
+use crate::services::tool_matrix::ToolCategory;
 use crate::services::tool_service::{ToolCall, ToolResult, ToolService};
 use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+/// 同一轮多个工具调用并发执行时的上限
+pub const MAX_PARALLEL_TOOL_CALLS: usize = 4;
+
+/// 工具参数中表示文件/目录路径的字段
+const PATH_ARGUMENT_KEYS: [&str; 8] = [
+  "path",
+  "source",
+  "destination",
+  "old_path",
+  "new_path",
+  "source_path",
+  "target_path",
+  "current_file",
+];
+
+/// 统一路径写法（`./docs/a.md`、`docs\a.md` -> `docs/a.md`），工作区根目录返回空串
+fn normalize_tool_path(raw: &str) -> String {
+  let normalized = raw.trim().replace('\\', "/");
+  let normalized = normalized.trim_start_matches("./").trim_end_matches('/');
+  if normalized == "." {
+    String::new()
+  } else {
+    normalized.to_string()
+  }
+}
+
+/// 空串表示工作区根目录，与任何路径重叠
+fn paths_overlap(a: &str, b: &str) -> bool {
+  a.is_empty()
+    || b.is_empty()
+    || a == b
+    || a.strip_prefix(b).is_some_and(|rest| rest.starts_with('/'))
+    || b.strip_prefix(a).is_some_and(|rest| rest.starts_with('/'))
+}
+
+/// 单个工具调用的作用范围
+struct CallScope {
+  /// 独占工具（编辑器编辑、命令执行，以及未登记在工具矩阵中的自定义工具），
+  /// 作用范围无法从参数判断，与所有调用冲突
+  exclusive: bool,
+  /// 会修改工作区（只读调用之间互不冲突）
+  mutates: bool,
+  /// 涉及的路径；未给出路径时视为整个工作区（空串）
+  paths: Vec<String>,
+}
+
+impl CallScope {
+  fn conflicts_with(&self, other: &CallScope) -> bool {
+    if self.exclusive || other.exclusive {
+      return true;
+    }
+    (self.mutates || other.mutates)
+      && self
+        .paths
+        .iter()
+        .any(|p| other.paths.iter().any(|q| paths_overlap(p, q)))
+  }
+}
+
+/// 按路径冲突把一批工具调用分组：修改操作与涉及相同路径（或父子目录）的调用归入同一组，
+/// 未给出路径或路径为工作区根目录的调用与所有修改操作同组，独占工具与所有调用同组。
+/// 组内保持调用顺序，组按首个调用的位置排序
+pub fn group_conflicting_tool_calls(tool_calls: &[ToolCall]) -> Vec<Vec<usize>> {
+  let matrix = crate::services::tool_matrix::build_tool_matrix();
+  let scopes: Vec<CallScope> = tool_calls
+    .iter()
+    .map(|call| {
+      let category = matrix
+        .iter()
+        .find(|entry| entry.definition.name == call.name)
+        .map(|entry| entry.category);
+      let exclusive = matches!(
+        category,
+        None | Some(ToolCategory::EditorEdit) | Some(ToolCategory::Shell)
+      );
+      let mutates = !matches!(
+        category,
+        Some(ToolCategory::FileRead) | Some(ToolCategory::Memory)
+      );
+      let mut paths: Vec<String> = PATH_ARGUMENT_KEYS
+        .iter()
+        .filter_map(|key| call.arguments.get(*key).and_then(|v| v.as_str()))
+        .map(normalize_tool_path)
+        .collect();
+      if paths.is_empty() {
+        paths.push(String::new());
+      }
+      CallScope {
+        exclusive,
+        mutates,
+        paths,
+      }
+    })
+    .collect();
+
+  // 并查集：父节点总是较早的调用，保证组代表为组内首个调用
+  let mut parent: Vec<usize> = (0..tool_calls.len()).collect();
+  fn find(parent: &mut [usize], i: usize) -> usize {
+    let mut root = i;
+    while parent[root] != root {
+      root = parent[root];
+    }
+    parent[i] = root;
+    root
+  }
+  for i in 0..tool_calls.len() {
+    for j in 0..i {
+      if scopes[i].conflicts_with(&scopes[j]) {
+        let (ri, rj) = (find(&mut parent, i), find(&mut parent, j));
+        if ri != rj {
+          parent[ri.max(rj)] = ri.min(rj);
+        }
+      }
+    }
+  }
+
+  let mut groups: Vec<Vec<usize>> = Vec::new();
+  let mut group_of_root = std::collections::HashMap::new();
+  for i in 0..tool_calls.len() {
+    let root = find(&mut parent, i);
+    let slot = *group_of_root.entry(root).or_insert_with(|| {
+      groups.push(Vec::new());
+      groups.len() - 1
+    });
+    groups[slot].push(i);
+  }
+  groups
+}
+
+fn is_cancelled(cancel_flag: &Mutex<bool>) -> bool {
+  *cancel_flag.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn cancelled_result(tool_call: &ToolCall) -> ToolResult {
+  ToolResult {
+    success: false,
+    error: Some(format!("用户取消了请求，工具 {} 未执行", tool_call.name)),
+    ..Default::default()
+  }
+}
 
 /// 工具调用处理器
 pub struct ToolCallHandler {
@@ -36,10 +181,7 @@ impl ToolCallHandler {
         Ok(result) => {
           if result.success {
             if attempt > 1 {
-              eprintln!(
-                "✅ 工具执行成功（第 {} 次尝试）: {}",
-                attempt, tool_call.name
-              );
+              tracing::info!("工具执行成功（第 {} 次尝试）: {}", attempt, tool_call.name);
             }
             return (result, attempt);
           } else {
@@ -49,16 +191,18 @@ impl ToolCallHandler {
         }
         Err(e) => {
           last_error = Some(e.clone());
-          eprintln!(
-            "⚠️ 工具执行失败（第 {} 次尝试）: {} - {}",
-            attempt, tool_call.name, e
+          tracing::warn!(
+            "工具执行失败（第 {} 次尝试）: {} - {}",
+            attempt,
+            tool_call.name,
+            e
           );
           if attempt < max_retries {
             // 等待一小段时间后重试（指数退避）
             let delay_ms = 100u64 * attempt as u64;
             tokio::time::sleep(tokio::time::Duration::from_millis(delay_ms)).await;
-            eprintln!(
-              "🔄 重试工具调用: {} (尝试 {}/{})",
+            tracing::debug!(
+              "重试工具调用: {} (尝试 {}/{})",
               tool_call.name,
               attempt + 1,
               max_retries
@@ -70,9 +214,11 @@ impl ToolCallHandler {
 
     // 所有重试都失败了
     let error_msg = last_error.unwrap_or_else(|| "未知错误".to_string());
-    eprintln!(
-      "❌ 工具执行最终失败（已重试 {} 次）: {} - {}",
-      max_retries, tool_call.name, error_msg
+    tracing::error!(
+      "工具执行最终失败（已重试 {} 次）: {} - {}",
+      max_retries,
+      tool_call.name,
+      error_msg
     );
 
     (
@@ -92,16 +238,103 @@ impl ToolCallHandler {
     )
   }
 
+  /// 并发执行一批工具调用（带重试机制）
+  ///
+  /// 互不相关的调用并行执行（最多 `max_concurrency` 个），涉及相同路径的调用按顺序串行；
+  /// 每个调用开始前检查 `cancel_flag`，已取消时不再执行并返回失败结果。
+  /// 返回结果与 `tool_calls` 顺序一致，保证对话历史确定。
+  pub async fn execute_tools_concurrently(
+    &self,
+    tool_calls: &[ToolCall],
+    workspace_path: &PathBuf,
+    max_retries: usize,
+    max_concurrency: usize,
+    cancel_flag: &Arc<Mutex<bool>>,
+  ) -> Vec<(ToolResult, usize)> {
+    if tool_calls.len() <= 1 {
+      let mut results = Vec::with_capacity(tool_calls.len());
+      for tool_call in tool_calls {
+        if is_cancelled(cancel_flag) {
+          results.push((cancelled_result(tool_call), 0));
+          continue;
+        }
+        results.push(
+          self
+            .execute_tool_with_retry(tool_call, workspace_path, max_retries)
+            .await,
+        );
+      }
+      return results;
+    }
+
+    let semaphore = Arc::new(Semaphore::new(max_concurrency.max(1)));
+    let mut join_set = JoinSet::new();
+    for group in group_conflicting_tool_calls(tool_calls) {
+      let calls: Vec<(usize, ToolCall)> = group
+        .into_iter()
+        .map(|index| (index, tool_calls[index].clone()))
+        .collect();
+      let semaphore = semaphore.clone();
+      let workspace_path = workspace_path.clone();
+      let cancel_flag = cancel_flag.clone();
+      join_set.spawn(async move {
+        let handler = ToolCallHandler::new();
+        let mut results = Vec::with_capacity(calls.len());
+        for (index, tool_call) in calls {
+          let _permit = semaphore.acquire().await;
+          if is_cancelled(&cancel_flag) {
+            results.push((index, (cancelled_result(&tool_call), 0)));
+            continue;
+          }
+          let result = handler
+            .execute_tool_with_retry(&tool_call, &workspace_path, max_retries)
+            .await;
+          results.push((index, result));
+        }
+        results
+      });
+    }
+
+    let mut slots: Vec<Option<(ToolResult, usize)>> = vec![None; tool_calls.len()];
+    while let Some(joined) = join_set.join_next().await {
+      match joined {
+        Ok(results) => {
+          for (index, result) in results {
+            slots[index] = Some(result);
+          }
+        }
+        Err(e) => tracing::error!("并发工具调用任务异常退出: {}", e),
+      }
+    }
+
+    slots
+      .into_iter()
+      .zip(tool_calls)
+      .map(|(slot, tool_call)| {
+        slot.unwrap_or_else(|| {
+          (
+            ToolResult {
+              success: false,
+              error: Some(format!("工具 {} 执行任务异常中止", tool_call.name)),
+              ..Default::default()
+            },
+            0,
+          )
+        })
+      })
+      .collect()
+  }
+
   /// 解析工具调用参数（带增强修复）
   pub fn parse_tool_arguments(arguments: &str) -> serde_json::Value {
     match serde_json::from_str::<serde_json::Value>(arguments) {
       Ok(args) => {
-        eprintln!("✅ 成功解析工具调用参数");
+        tracing::info!("成功解析工具调用参数");
         args
       }
       Err(e) => {
-        eprintln!(
-          "⚠️ 工具调用参数 JSON 解析失败: {}, arguments 长度: {}",
+        tracing::warn!(
+          "工具调用参数 JSON 解析失败: {}, arguments 长度: {}",
           e,
           arguments.len()
         );
@@ -112,11 +345,11 @@ impl ToolCallHandler {
         // 尝试解析修复后的 JSON
         match serde_json::from_str::<serde_json::Value>(&repaired) {
           Ok(args) => {
-            eprintln!("✅ JSON 修复成功");
+            tracing::info!("JSON 修复成功");
             args
           }
           Err(e2) => {
-            eprintln!("❌ JSON 修复后仍然解析失败: {}", e2);
+            tracing::error!("JSON 修复后仍然解析失败: {}", e2);
             // 尝试从部分 JSON 中提取可用字段
             Self::extract_partial_json(arguments)
           }
@@ -199,13 +432,13 @@ impl ToolCallHandler {
 
   /// 从部分 JSON 中提取可用字段（作为最后的备选方案）
   fn extract_partial_json(broken: &str) -> serde_json::Value {
-    eprintln!("🔍 尝试从部分 JSON 中提取可用字段...");
+    tracing::debug!("尝试从部分 JSON 中提取可用字段...");
     let mut extracted = serde_json::json!({});
 
     // ⚠️ 特殊处理：如果 JSON 很大（>5000 字符）且被截断，尝试修复
     if broken.len() > 5000 && !broken.trim().ends_with('}') {
-      eprintln!(
-        "⚠️ 检测到大 JSON 被截断（长度: {}），尝试修复...",
+      tracing::warn!(
+        "检测到大 JSON 被截断（长度: {}），尝试修复...",
         broken.len()
       );
       // 尝试找到最后一个完整的字段
@@ -219,8 +452,8 @@ impl ToolCallHandler {
           if value_str.starts_with('"') {
             // 尝试找到最后一个完整的引号对
             // 由于内容可能包含转义字符，我们需要更智能的解析
-            eprintln!(
-              "⚠️ content 字段可能被截断，但已提取部分内容（长度: {}）",
+            tracing::warn!(
+              "content 字段可能被截断，但已提取部分内容（长度: {}）",
               broken.len() - value_start
             );
             // 暂时不提取被截断的 content，只提取 path
@@ -263,7 +496,7 @@ impl ToolCallHandler {
 
           if !path_value.is_empty() {
             extracted["path"] = serde_json::json!(path_value);
-            eprintln!("✅ 成功提取 path 字段: {}", path_value);
+            tracing::info!("成功提取 path 字段: {}", path_value);
           }
         }
       }
@@ -271,10 +504,10 @@ impl ToolCallHandler {
 
     // 如果提取到了字段，返回提取的结果；否则返回空对象
     if extracted.as_object().unwrap().is_empty() {
-      eprintln!("❌ 无法提取任何字段，使用空对象（工具调用将失败）");
+      tracing::error!("无法提取任何字段，使用空对象（工具调用将失败）");
       serde_json::json!({})
     } else {
-      eprintln!("✅ 部分提取成功，返回提取的字段");
+      tracing::info!("部分提取成功，返回提取的字段");
       extracted
     }
   }
@@ -285,3 +518,87 @@ impl Default for ToolCallHandler {
     Self::new()
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::{group_conflicting_tool_calls, ToolCallHandler};
+  use crate::services::tool_service::ToolCall;
+  use std::path::PathBuf;
+  use std::sync::{Arc, Mutex};
+
+  fn call(name: &str, arguments: serde_json::Value) -> ToolCall {
+    ToolCall {
+      id: format!("call-{}", name),
+      name: name.to_string(),
+      arguments,
+    }
+  }
+
+  #[test]
+  fn conflicting_paths_share_a_group_in_call_order() {
+    let calls = vec![
+      call("read_file", serde_json::json!({ "path": "a.md" })),
+      call("read_file", serde_json::json!({ "path": "b.md" })),
+      call("update_file", serde_json::json!({ "path": "./a.md" })),
+      call("create_folder", serde_json::json!({ "path": "docs" })),
+      call("create_file", serde_json::json!({ "path": "docs/new.md" })),
+      call("read_file", serde_json::json!({ "path": "b.md" })),
+    ];
+    assert_eq!(
+      group_conflicting_tool_calls(&calls),
+      vec![vec![0, 2], vec![1], vec![3, 4], vec![5]]
+    );
+
+    let mut with_exclusive = calls.clone();
+    with_exclusive.push(call("run_command", serde_json::json!({ "command": "ls" })));
+    assert_eq!(group_conflicting_tool_calls(&with_exclusive).len(), 1);
+  }
+
+  #[test]
+  fn root_or_missing_paths_conflict_with_every_mutation() {
+    let calls = vec![
+      call("update_file", serde_json::json!({ "path": "a.md" })),
+      call("search_workspace", serde_json::json!({ "query": "x" })),
+      call("list_files", serde_json::json!({ "path": "." })),
+      call("read_file", serde_json::json!({ "path": "b.md" })),
+      call("create_file", serde_json::json!({ "path": "docs/new.md" })),
+    ];
+    assert_eq!(
+      group_conflicting_tool_calls(&calls),
+      vec![vec![0, 1, 2, 4], vec![3]]
+    );
+
+    // 只读调用之间即使范围重叠也可并行
+    let reads = vec![
+      call("search_workspace", serde_json::json!({ "query": "x" })),
+      call("list_files", serde_json::json!({ "path": "./" })),
+      call("read_file", serde_json::json!({ "path": "a.md" })),
+    ];
+    assert_eq!(
+      group_conflicting_tool_calls(&reads),
+      vec![vec![0], vec![1], vec![2]]
+    );
+  }
+
+  #[tokio::test]
+  async fn cancelled_batches_skip_every_call() {
+    let calls = vec![
+      call(
+        "create_file",
+        serde_json::json!({ "path": "a.md", "content": "a" }),
+      ),
+      call(
+        "create_file",
+        serde_json::json!({ "path": "b.md", "content": "b" }),
+      ),
+    ];
+    let cancel_flag = Arc::new(Mutex::new(true));
+    let results = ToolCallHandler::new()
+      .execute_tools_concurrently(&calls, &PathBuf::from("/nonexistent"), 3, 4, &cancel_flag)
+      .await;
+    assert_eq!(results.len(), 2);
+    assert!(results
+      .iter()
+      .all(|(result, attempts)| !result.success && *attempts == 0));
+  }
+}
@@ -2,11 +2,21 @@
 // Phase 2: 主源已迁移到 tool_matrix.rs，此处为兼容入口。
 use crate::services::ai_providers::ToolDefinition;
 use serde_json::json;
+use std::path::Path;
 
 /// 兼容入口：从 tool_matrix 获取工具定义。
 /// 旧静态定义保留在 `get_tool_definitions_legacy` 中作为参照。
-pub fn get_tool_definitions() -> Vec<ToolDefinition> {
-  crate::services::tool_matrix::definitions_from_matrix()
+/// 应用设置允许该工作区执行命令后额外暴露 run_command，并追加 `.binder/tools` 下的自定义工具和插件工具。
+pub fn get_tool_definitions(workspace_path: &Path) -> Vec<ToolDefinition> {
+  let mut definitions = crate::services::tool_matrix::definitions_from_matrix(
+    crate::services::settings_service::shell_commands_allowed(workspace_path),
+  );
+  definitions.extend(
+    crate::services::custom_tools::available_custom_tools(workspace_path)
+      .iter()
+      .map(|tool| tool.to_definition()),
+  );
+  definitions
 }
 
 /// 旧静态定义（保留用于参照与回退，不再作为主源）
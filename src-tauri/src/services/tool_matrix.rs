@@ -9,14 +9,18 @@ use serde_json::json;
 /// 工具类别
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ToolCategory {
-  /// 文件读取（read_file, list_files, search_files）
+  /// 文件读取（read_file, read_document, list_files, list_directory, search_files, search_workspace）
   FileRead,
-  /// 文件写入（create_file, update_file, delete_file, move_file, rename_file, create_folder）
+  /// 文件写入（create_file, create_docx_from_markdown, update_file, apply_patch, delete_file, move_file, copy_file, rename_file, create_folder）
   FileWrite,
   /// 编辑器交互（edit_current_editor_document）
   EditorEdit,
   /// 元数据（save_file_dependency）
   Metadata,
+  /// 记忆检索（search_memories）
+  Memory,
+  /// 命令执行（run_command）
+  Shell,
 }
 
 /// 工具可见性——决定工具在哪些模式下暴露给模型
@@ -30,6 +34,8 @@ pub enum ToolVisibility {
   /// 保留的可见性分组；当前未绑定任何独立模式入口
   #[allow(dead_code)]
   Reserved,
+  /// 需在工作区设置中显式开启才可见
+  OptIn,
 }
 
 /// 工具矩阵条目
@@ -60,6 +66,23 @@ pub fn build_tool_matrix() -> Vec<ToolMatrixEntry> {
                 }),
             },
         },
+        ToolMatrixEntry {
+            category: ToolCategory::FileRead,
+            visibility: ToolVisibility::Always,
+            definition: ToolDefinition {
+                name: "read_document".to_string(),
+                description: "Extracts readable text from an office document or PDF in the workspace. DOCX, ODT, RTF, EPUB and HTML are converted to Markdown; PDFs return their text layer; scanned PDFs and images (PNG, JPEG, TIFF, …) are run through OCR when Tesseract is installed. Output is capped; when `truncated` is true, call again with the returned `offset` to continue. Use this to understand document contents; use `read_file` when you need the editor-compatible form before editing.".to_string(),
+                parameters: json!({
+                    "type": "object",
+                    "properties": {
+                        "path": { "type": "string", "description": "The relative path to the document (relative to workspace root)" },
+                        "offset": { "type": "integer", "description": "Character offset to start reading from (default: 0)" },
+                        "max_chars": { "type": "integer", "description": "Maximum characters to return (default: 50000, max: 200000)" }
+                    },
+                    "required": ["path"]
+                }),
+            },
+        },
         ToolMatrixEntry {
             category: ToolCategory::FileRead,
             visibility: ToolVisibility::Always,
@@ -78,6 +101,22 @@ pub fn build_tool_matrix() -> Vec<ToolMatrixEntry> {
                 }),
             },
         },
+        ToolMatrixEntry {
+            category: ToolCategory::FileRead,
+            visibility: ToolVisibility::Always,
+            definition: ToolDefinition {
+                name: "list_directory".to_string(),
+                description: "Returns a compact tree of a directory (types and file sizes) down to the given depth. Use this to explore the project structure; directories beyond the depth show only their item count. Hidden entries are skipped and the listing is capped at 500 entries.".to_string(),
+                parameters: json!({
+                    "type": "object",
+                    "properties": {
+                        "path": { "type": "string", "description": "The relative path to the directory (relative to workspace root). Defaults to root directory" },
+                        "depth": { "type": "integer", "description": "How many levels to expand (1-5, default 2)" }
+                    },
+                    "required": []
+                }),
+            },
+        },
         ToolMatrixEntry {
             category: ToolCategory::FileRead,
             visibility: ToolVisibility::Always,
@@ -96,6 +135,26 @@ pub fn build_tool_matrix() -> Vec<ToolMatrixEntry> {
                 }),
             },
         },
+        ToolMatrixEntry {
+            category: ToolCategory::FileRead,
+            visibility: ToolVisibility::Always,
+            definition: ToolDefinition {
+                name: "search_workspace".to_string(),
+                description: "Searches file CONTENTS across the workspace and returns matching files with line previews or snippets. Use this to locate the relevant files before reading or editing instead of guessing paths.\n\nUses the full-text index when available; falls back to a text scan (or when regex is true). Results are capped (default 20, max 100).".to_string(),
+                parameters: json!({
+                    "type": "object",
+                    "properties": {
+                        "query": { "type": "string", "description": "Text (or regex when regex=true) to search for" },
+                        "path": { "type": "string", "description": "Optional sub-directory to limit the search (relative to workspace root)" },
+                        "mode": { "type": "string", "description": "auto (default) | index | text" },
+                        "regex": { "type": "boolean", "description": "Treat query as a regular expression (text scan only). Defaults to false." },
+                        "case_sensitive": { "type": "boolean", "description": "Defaults to false." },
+                        "max_results": { "type": "integer", "description": "Maximum results to return (1-100, default 20)" }
+                    },
+                    "required": ["query"]
+                }),
+            },
+        },
         ToolMatrixEntry {
             category: ToolCategory::FileWrite,
             visibility: ToolVisibility::Always,
@@ -144,6 +203,23 @@ pub fn build_tool_matrix() -> Vec<ToolMatrixEntry> {
                 }),
             },
         },
+        ToolMatrixEntry {
+            category: ToolCategory::FileWrite,
+            visibility: ToolVisibility::Always,
+            definition: ToolDefinition {
+                name: "apply_patch".to_string(),
+                description: "Applies targeted edits to an existing file on disk without resending the whole file. Prefer this over update_file for small or local changes.\n\nThe patch may be a unified diff (with @@ hunk headers) or one or more SEARCH/REPLACE blocks:\n<<<<<<< SEARCH\nexact original lines\n=======\nreplacement lines\n>>>>>>> REPLACE\n\nMatching tolerates whitespace differences and small drift. All edits apply or none do; on failure the result includes the closest matching region with line numbers so you can correct the patch and retry. Document-like files are routed into pending diffs for user review, same as update_file. Do NOT use this for the file currently open in the editor.".to_string(),
+                parameters: json!({
+                    "type": "object",
+                    "properties": {
+                        "path": { "type": "string", "description": "The relative path to the file (relative to workspace root)" },
+                        "patch": { "type": "string", "description": "Unified diff or SEARCH/REPLACE blocks" },
+                        "use_diff": { "type": "boolean", "description": "Same as update_file.use_diff" }
+                    },
+                    "required": ["path", "patch"]
+                }),
+            },
+        },
         ToolMatrixEntry {
             category: ToolCategory::FileWrite,
             visibility: ToolVisibility::Always,
@@ -178,6 +254,38 @@ pub fn build_tool_matrix() -> Vec<ToolMatrixEntry> {
                 }),
             },
         },
+        ToolMatrixEntry {
+            category: ToolCategory::FileWrite,
+            visibility: ToolVisibility::Always,
+            definition: ToolDefinition {
+                name: "copy_file".to_string(),
+                description: "Copies a file or folder (recursively) to a new location inside the workspace. The destination must not already exist; missing parent folders are created.".to_string(),
+                parameters: json!({
+                    "type": "object",
+                    "properties": {
+                        "source": { "type": "string", "description": "The relative path to the source file or folder" },
+                        "destination": { "type": "string", "description": "The destination path including the new name (relative to workspace root)" }
+                    },
+                    "required": ["source", "destination"]
+                }),
+            },
+        },
+        ToolMatrixEntry {
+            category: ToolCategory::FileWrite,
+            visibility: ToolVisibility::Always,
+            definition: ToolDefinition {
+                name: "create_docx_from_markdown".to_string(),
+                description: "Creates a Word document (.docx) from Markdown content. The conversion uses the workspace reference template (.binder/reference.docx if present, otherwise the built-in template) so headings, lists and tables get consistent Word styles. Use this when the user wants a Word deliverable instead of a markdown file. Returns an error if the file already exists.".to_string(),
+                parameters: json!({
+                    "type": "object",
+                    "properties": {
+                        "path": { "type": "string", "description": "The relative path of the .docx file to create (relative to workspace root)" },
+                        "markdown": { "type": "string", "description": "Document content in Markdown (headings, lists, tables, emphasis, links, code blocks)" }
+                    },
+                    "required": ["path", "markdown"]
+                }),
+            },
+        },
         ToolMatrixEntry {
             category: ToolCategory::FileWrite,
             visibility: ToolVisibility::Always,
@@ -247,14 +355,61 @@ pub fn build_tool_matrix() -> Vec<ToolMatrixEntry> {
                 }),
             },
         },
+        ToolMatrixEntry {
+            category: ToolCategory::Memory,
+            visibility: ToolVisibility::Always,
+            definition: ToolDefinition {
+                name: "search_memories".to_string(),
+                description: "Searches the user's stored memories: writing and style preferences, project facts, terminology and decisions remembered from earlier conversations and documents. Use it when the answer may depend on something the user told you before or on project conventions that are not in the current context. Returns the most relevant memories with their content and tags.".to_string(),
+                parameters: json!({
+                    "type": "object",
+                    "properties": {
+                        "query": { "type": "string", "description": "What to look up, e.g. 'citation style' or 'project deadline'" },
+                        "scope": { "type": "string", "enum": ["all", "workspace", "content", "user"], "description": "Where to search: all (default), workspace long-term memories, memories extracted from workspace documents, or user-level memories shared across workspaces" },
+                        "tags": { "type": "array", "items": { "type": "string" }, "description": "Optional tag filter; memories matching any tag are returned (e.g. style, project_fact)" },
+                        "limit": { "type": "integer", "description": "Maximum number of memories to return (default: 8, max: 20)" }
+                    },
+                    "required": ["query"]
+                }),
+            },
+        },
+        ToolMatrixEntry {
+            category: ToolCategory::Shell,
+            visibility: ToolVisibility::OptIn,
+            definition: ToolDefinition {
+                name: "run_command".to_string(),
+                description: "Runs a shell command inside the workspace (e.g. build scripts, pandoc, git status). Every call requires explicit user approval before it runs. Commands are killed after a timeout, output is truncated, and destructive commands (recursive delete, sudo, force push, disk formatting, piping remote scripts to a shell) are rejected outright. Prefer the dedicated file tools for reading, searching and editing files.".to_string(),
+                parameters: json!({
+                    "type": "object",
+                    "properties": {
+                        "command": { "type": "string", "description": "The shell command to run" },
+                        "cwd": { "type": "string", "description": "Optional working directory relative to the workspace root (default: workspace root)" }
+                    },
+                    "required": ["command"]
+                }),
+            },
+        },
     ]
 }
 
+/// 全部内置工具名（含需显式开启的工具），自定义工具与插件工具不得与之重名
+pub fn builtin_tool_names() -> Vec<String> {
+  build_tool_matrix()
+    .into_iter()
+    .map(|e| e.definition.name)
+    .collect()
+}
+
 /// 从矩阵中提取 ToolDefinition 列表（兼容旧接口）
-pub fn definitions_from_matrix() -> Vec<ToolDefinition> {
+///
+/// `include_opt_in` 为 true 时一并包含需在工作区设置中显式开启的工具。
+pub fn definitions_from_matrix(include_opt_in: bool) -> Vec<ToolDefinition> {
   build_tool_matrix()
     .into_iter()
-    .filter(|e| e.visibility == ToolVisibility::Always)
+    .filter(|e| {
+      e.visibility == ToolVisibility::Always
+        || (include_opt_in && e.visibility == ToolVisibility::OptIn)
+    })
     .map(|e| e.definition)
     .collect()
 }
@@ -1,18 +1,36 @@
 // 工具调用服务
+use crate::services::agent_transaction;
+use crate::services::custom_tools::{
+  self, CustomToolAction, CustomToolSpec, ShellInvocation, TemplateEscape,
+};
+use crate::services::in_file_search::{InFileSearchOptions, InFileSearchService};
+use crate::services::ocr_service::{OcrService, OCR_DEFAULT_MAX_PAGES, OCR_IMAGE_EXTENSIONS};
+use crate::services::search_service::{
+  natural_language_query, refresh_index_after_save, remove_index_after_delete, SearchService,
+};
+use crate::services::workspace_settings::{AgentToolSettings, WorkspaceSettings};
+use crate::utils::command_guard;
+use crate::utils::glob_patterns::build_glob_set;
 use crate::utils::path_validator::PathValidator;
 use crate::workspace::canonical_html::{
   canonical_html_for_workspace_cache, materialize_cached_body_if_stale_hash,
   should_run_workspace_canonical_pipeline,
 };
 use crate::workspace::diff_engine;
+use crate::workspace::patch_apply;
 use crate::workspace::timeline_support::{
   record_file_content_timeline_node, record_resource_structure_timeline_node,
 };
 use crate::workspace::workspace_db::WorkspaceDb;
+use once_cell::sync::Lazy;
 use scraper::{Html, Selector};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::process::Stdio;
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::io::AsyncReadExt;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ToolCall {
@@ -21,6 +39,17 @@ pub struct ToolCall {
   pub arguments: serde_json::Value,
 }
 
+impl ToolCall {
+  /// 由模型输出构造工具调用：去掉模型不应提供的确认字段
+  pub fn from_model(id: String, name: String, arguments: serde_json::Value) -> Self {
+    Self {
+      id,
+      name,
+      arguments: strip_internal_gate_fields(&arguments),
+    }
+  }
+}
+
 /// 工具错误类型：供上层调度或恢复策略区分重试/跳过/中止。
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -179,7 +208,7 @@ fn build_failure_meta(tool_name: &str, reason: &str) -> ToolResultMeta {
 fn tool_requires_confirmation(tool_name: &str) -> bool {
   matches!(
     tool_name,
-    "delete_file" | "move_file" | "rename_file" | "create_folder"
+    "delete_file" | "move_file" | "rename_file" | "create_folder" | "run_command"
   )
 }
 
@@ -226,22 +255,63 @@ fn normalize_json_for_gate(value: &serde_json::Value) -> serde_json::Value {
   }
 }
 
-fn confirmation_record_id(tool_call: &ToolCall) -> String {
+/// 待确认记录的有效期
+const CONFIRMATION_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// 待用户确认的工具调用
+struct PendingConfirmation {
+  fingerprint: String,
+  workspace: PathBuf,
+  created_at: Instant,
+}
+
+/// 后端签发的确认记录：记录 ID 为随机值，只能由前端确认命令使用一次
+static PENDING_CONFIRMATIONS: Lazy<Mutex<HashMap<String, PendingConfirmation>>> =
+  Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// 工具调用的确认指纹：调用 ID、工具名与规范化后的参数
+fn confirmation_fingerprint(tool_call: &ToolCall) -> String {
   let normalized_args = normalize_json_for_gate(&strip_internal_gate_fields(&tool_call.arguments));
   let args_str = serde_json::to_string(&normalized_args).unwrap_or_else(|_| "{}".to_string());
-  format!(
-    "tool-confirm:{}:{}:{}",
-    tool_call.id, tool_call.name, args_str
-  )
+  format!("{}:{}:{}", tool_call.id, tool_call.name, args_str)
+}
+
+fn issue_confirmation(tool_call: &ToolCall, workspace_path: &Path) -> String {
+  let record_id = format!("tool-confirm-{}", uuid::Uuid::new_v4());
+  let mut pending = PENDING_CONFIRMATIONS
+    .lock()
+    .unwrap_or_else(|e| e.into_inner());
+  pending.retain(|_, p| p.created_at.elapsed() < CONFIRMATION_TTL);
+  pending.insert(
+    record_id.clone(),
+    PendingConfirmation {
+      fingerprint: confirmation_fingerprint(tool_call),
+      workspace: workspace_path.to_path_buf(),
+      created_at: Instant::now(),
+    },
+  );
+  record_id
+}
+
+/// 取走确认记录（无论是否匹配都作废），与待执行的调用和工作区一致时返回 true
+fn take_confirmation(record_id: &str, tool_call: &ToolCall, workspace_path: &Path) -> bool {
+  let pending = PENDING_CONFIRMATIONS
+    .lock()
+    .unwrap_or_else(|e| e.into_inner())
+    .remove(record_id);
+  pending.is_some_and(|p| {
+    p.created_at.elapsed() < CONFIRMATION_TTL
+      && p.workspace == workspace_path
+      && p.fingerprint == confirmation_fingerprint(tool_call)
+  })
 }
 
-fn build_confirmation_pending_result(tool_call: &ToolCall) -> ToolResult {
-  let record_id = confirmation_record_id(tool_call);
+/// 记录 ID 只放在 meta 中交给前端确认卡片，不放进回传给模型的 data
+fn build_confirmation_pending_result(tool_call: &ToolCall, record_id: String) -> ToolResult {
   ToolResult {
     success: true,
     data: Some(serde_json::json!({
       "awaiting_confirmation": true,
-      "confirmation_record_id": record_id,
       "tool_name": tool_call.name,
       "tool_call_id": tool_call.id,
     })),
@@ -307,18 +377,6 @@ fn build_confirmation_rejected_result(tool_call: &ToolCall, record_id: String) -
   }
 }
 
-fn sanitize_confirmation_arguments(tool_call: &ToolCall) -> serde_json::Value {
-  strip_internal_gate_fields(&tool_call.arguments)
-}
-
-fn parse_confirmation_action(tool_call: &ToolCall) -> Option<String> {
-  tool_call
-    .arguments
-    .get("_confirmation_action")
-    .and_then(|v| v.as_str())
-    .map(|s| s.to_lowercase())
-}
-
 fn requires_reviewed_document_write(file_path: &Path) -> bool {
   matches!(
     file_path
@@ -339,6 +397,539 @@ fn requires_reviewed_document_write(file_path: &Path) -> bool {
   )
 }
 
+/// 递归复制文件或文件夹（跳过符号链接），返回复制的文件数
+fn copy_path_recursively(source: &Path, dest: &Path) -> std::io::Result<usize> {
+  let file_type = std::fs::symlink_metadata(source)?.file_type();
+  if file_type.is_symlink() {
+    return Ok(0);
+  }
+  if file_type.is_file() {
+    std::fs::copy(source, dest)?;
+    return Ok(1);
+  }
+  std::fs::create_dir_all(dest)?;
+  let mut copied = 0;
+  for entry in std::fs::read_dir(source)? {
+    let entry = entry?;
+    copied += copy_path_recursively(&entry.path(), &dest.join(entry.file_name()))?;
+  }
+  Ok(copied)
+}
+
+/// read_document 默认/最大返回字符数
+const READ_DOCUMENT_DEFAULT_CHARS: usize = 50_000;
+const READ_DOCUMENT_MAX_CHARS: usize = 200_000;
+
+/// 需经 Pandoc 转换为 Markdown 的文档格式
+const PANDOC_DOCUMENT_EXTENSIONS: [&str; 6] = ["docx", "odt", "rtf", "epub", "html", "htm"];
+
+/// 整理提取出的文本：统一换行、去掉行尾空白、把连续空行压缩为一行
+fn normalize_extracted_text(text: &str) -> String {
+  let mut out = String::with_capacity(text.len());
+  let mut blank_run = 0;
+  for line in text.replace("\r\n", "\n").replace('\r', "\n").lines() {
+    let line = line.trim_end();
+    if line.is_empty() {
+      blank_run += 1;
+      if blank_run > 1 {
+        continue;
+      }
+    } else {
+      blank_run = 0;
+    }
+    out.push_str(line);
+    out.push('\n');
+  }
+  out.trim().to_string()
+}
+
+/// 按字符截取 [offset, offset + max_chars)，返回 (片段, 总字符数, 之后是否还有内容)
+fn slice_chars(text: &str, offset: usize, max_chars: usize) -> (String, usize, bool) {
+  let total = text.chars().count();
+  let slice: String = text.chars().skip(offset).take(max_chars).collect();
+  (slice, total, offset.saturating_add(max_chars) < total)
+}
+
+/// list_directory 默认/最大深度与条目上限
+const LIST_DIRECTORY_DEFAULT_DEPTH: usize = 2;
+const LIST_DIRECTORY_MAX_DEPTH: usize = 5;
+const LIST_DIRECTORY_MAX_ENTRIES: usize = 500;
+
+struct DirectoryListing {
+  tree: String,
+  entries: usize,
+  truncated: bool,
+}
+
+/// 生成紧凑树形目录列表：目录以 `/` 结尾，文件附带大小，超出深度的目录只显示子项数量。
+/// 跳过隐藏项、node_modules 和 target；符号链接只列出、不展开。
+fn render_directory_tree(root: &Path, depth: usize, max_entries: usize) -> DirectoryListing {
+  fn visible_children(dir: &Path) -> Vec<std::fs::DirEntry> {
+    let mut children: Vec<_> = std::fs::read_dir(dir)
+      .map(|entries| entries.flatten().collect())
+      .unwrap_or_default();
+    children.retain(|e| {
+      let name = e.file_name();
+      let name = name.to_string_lossy();
+      !name.starts_with('.') && name != "node_modules" && name != "target"
+    });
+    children.sort_by_key(|e| {
+      let is_dir = e.file_type().map(|t| t.is_dir()).unwrap_or(false);
+      (!is_dir, e.file_name().to_string_lossy().to_lowercase())
+    });
+    children
+  }
+
+  fn walk(dir: &Path, level: usize, depth: usize, max_entries: usize, out: &mut DirectoryListing) {
+    for entry in visible_children(dir) {
+      if out.entries >= max_entries {
+        out.truncated = true;
+        return;
+      }
+      out.entries += 1;
+      let indent = "  ".repeat(level);
+      let name = entry.file_name().to_string_lossy().to_string();
+      let Ok(file_type) = entry.file_type() else {
+        continue;
+      };
+      if file_type.is_symlink() {
+        out
+          .tree
+          .push_str(&format!("{}{} -> (link)\n", indent, name));
+      } else if file_type.is_dir() {
+        if level + 1 < depth {
+          out.tree.push_str(&format!("{}{}/\n", indent, name));
+          walk(&entry.path(), level + 1, depth, max_entries, out);
+        } else {
+          let count = visible_children(&entry.path()).len();
+          out
+            .tree
+            .push_str(&format!("{}{}/ ({} 项)\n", indent, name, count));
+        }
+      } else {
+        let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+        out
+          .tree
+          .push_str(&format!("{}{}  {}\n", indent, name, format_file_size(size)));
+      }
+    }
+  }
+
+  let mut listing = DirectoryListing {
+    tree: String::new(),
+    entries: 0,
+    truncated: false,
+  };
+  walk(root, 0, depth, max_entries, &mut listing);
+  listing
+}
+
+fn format_file_size(bytes: u64) -> String {
+  const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+  let mut size = bytes as f64;
+  let mut unit = 0;
+  while size >= 1024.0 && unit < UNITS.len() - 1 {
+    size /= 1024.0;
+    unit += 1;
+  }
+  if unit == 0 {
+    format!("{} B", bytes)
+  } else {
+    format!("{:.1} {}", size, UNITS[unit])
+  }
+}
+
+/// search_workspace 默认/最大返回条数
+const SEARCH_WORKSPACE_DEFAULT_RESULTS: usize = 20;
+const SEARCH_WORKSPACE_MAX_RESULTS: usize = 100;
+
+/// 子目录相对工作区的前缀（`docs/`），用于过滤索引结果；根目录返回 None
+fn sub_path_prefix(dir: &Path, workspace_path: &Path) -> Option<String> {
+  let relative = crate::utils::glob_patterns::relative_slash_path(dir, workspace_path);
+  (!relative.is_empty()).then(|| format!("{}/", relative))
+}
+
+/// run_command 结束后等待输出读取完成的宽限时间
+const RUN_COMMAND_DRAIN_GRACE: Duration = Duration::from_secs(2);
+
+/// run_command 的前置检查：未开启或命中黑名单时返回拒绝结果（不进入确认门）
+/// 未开启或命中黑名单的命令直接拒绝，不再请求用户确认
+fn run_command_rejection_for(tool_call: &ToolCall, workspace_path: &Path) -> Option<ToolResult> {
+  if tool_call.name != "run_command" {
+    return None;
+  }
+  let command = tool_call
+    .arguments
+    .get("command")
+    .and_then(|v| v.as_str())
+    .unwrap_or_default();
+  run_command_rejection(
+    command,
+    crate::services::settings_service::shell_commands_allowed(workspace_path),
+  )
+}
+
+/// 路径是否位于工作区的 `.binder` 配置目录内
+fn is_workspace_config_path(target: &Path, workspace_path: &Path) -> bool {
+  let root = workspace_path
+    .canonicalize()
+    .unwrap_or_else(|_| workspace_path.to_path_buf());
+  target
+    .strip_prefix(&root)
+    .ok()
+    .and_then(|relative| relative.components().next())
+    .is_some_and(|first| first.as_os_str().eq_ignore_ascii_case(".binder"))
+}
+
+fn run_command_rejection(command: &str, shell_allowed: bool) -> Option<ToolResult> {
+  let reason = if !shell_allowed {
+    "应用设置中未允许该工作区执行命令".to_string()
+  } else {
+    format!("{}: {}", command_guard::denied_reason(command)?, command)
+  };
+  Some(ToolResult {
+    success: false,
+    error: Some(reason.clone()),
+    error_kind: Some(ToolErrorKind::Fatal),
+    display_error: Some("该命令已被拒绝执行".to_string()),
+    meta: Some(build_failure_meta("run_command", &reason)),
+    ..Default::default()
+  })
+}
+
+pub(crate) fn shell_command(command: &str) -> tokio::process::Command {
+  #[cfg(target_os = "windows")]
+  {
+    let mut cmd = tokio::process::Command::new("cmd");
+    cmd.arg("/C").arg(command);
+    cmd
+  }
+
+  #[cfg(not(target_os = "windows"))]
+  {
+    let mut cmd = tokio::process::Command::new("sh");
+    // 独立进程组，便于结束时连同后台子进程一起终止
+    cmd.arg("-c").arg(command).process_group(0);
+    cmd
+  }
+}
+
+/// 执行自定义工具的 shell 模板：参数值经环境变量传入。
+/// Windows 下开启延迟展开，`!VAR!` 在命令解析后才展开，参数中的元字符不会被当作命令
+pub(crate) fn shell_template_command(invocation: &ShellInvocation) -> tokio::process::Command {
+  #[cfg(target_os = "windows")]
+  let mut cmd = {
+    let mut cmd = tokio::process::Command::new("cmd");
+    cmd.args(["/V:ON", "/C"]).arg(&invocation.command);
+    cmd
+  };
+  #[cfg(not(target_os = "windows"))]
+  let mut cmd = shell_command(&invocation.command);
+  cmd.envs(invocation.envs.iter().cloned());
+  cmd
+}
+
+/// 终止命令及其派生的子进程；`pid` 需在等待前取得（子进程回收后 `id()` 返回 None）
+async fn kill_process_tree(pid: Option<u32>, child: &mut tokio::process::Child) {
+  if let Some(pid) = pid {
+    #[cfg(target_os = "windows")]
+    let mut killer = {
+      let mut cmd = tokio::process::Command::new("taskkill");
+      cmd.args(["/T", "/F", "/PID", &pid.to_string()]);
+      cmd
+    };
+    #[cfg(not(target_os = "windows"))]
+    let mut killer = {
+      let mut cmd = tokio::process::Command::new("kill");
+      cmd.args(["-KILL", "--", &format!("-{}", pid)]);
+      cmd
+    };
+    let _ = killer
+      .stdout(Stdio::null())
+      .stderr(Stdio::null())
+      .status()
+      .await;
+  }
+  let _ = child.kill().await;
+}
+
+/// 命令输出缓冲：(已保留的字节, 是否截断)
+type CapturedOutput = std::sync::Arc<std::sync::Mutex<(Vec<u8>, bool)>>;
+
+/// 持续读取输出直到管道关闭：只保留前 `limit` 字节，其余丢弃以免子进程阻塞在写管道上
+fn capture_output<R: tokio::io::AsyncRead + Unpin + Send + 'static>(
+  reader: Option<R>,
+  limit: usize,
+) -> (CapturedOutput, tokio::task::JoinHandle<()>) {
+  let captured = CapturedOutput::default();
+  let sink = captured.clone();
+  let task = tokio::spawn(async move {
+    let Some(mut reader) = reader else {
+      return;
+    };
+    let mut buf = [0u8; 8192];
+    loop {
+      match reader.read(&mut buf).await {
+        Ok(0) | Err(_) => break,
+        Ok(n) => {
+          let mut guard = sink.lock().unwrap();
+          let room = limit.saturating_sub(guard.0.len());
+          guard.1 |= n > room;
+          guard.0.extend_from_slice(&buf[..n.min(room)]);
+        }
+      }
+    }
+  });
+  (captured, task)
+}
+
+fn captured_text(captured: &CapturedOutput, limit: usize) -> (String, bool) {
+  let guard = captured.lock().unwrap();
+  let (text, cut) = command_guard::truncate_output(&guard.0, limit);
+  (text, guard.1 || cut)
+}
+
+/// 在 `cwd` 下执行 shell 命令：带超时、输出上限，结束时清理整个进程组
+async fn execute_shell(
+  tool_name: &str,
+  mut cmd: tokio::process::Command,
+  command: &str,
+  cwd: &Path,
+  cwd_label: &str,
+  timeout_secs: u64,
+  limit: usize,
+) -> ToolResult {
+  let mut child = match cmd
+    .current_dir(cwd)
+    .stdin(Stdio::null())
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped())
+    .kill_on_drop(true)
+    .spawn()
+  {
+    Ok(child) => child,
+    Err(e) => {
+      let reason = format!("启动命令失败: {}", e);
+      return ToolResult {
+        success: false,
+        error: Some(reason.clone()),
+        error_kind: Some(ToolErrorKind::Fatal),
+        meta: Some(build_failure_meta(tool_name, &reason)),
+        ..Default::default()
+      };
+    }
+  };
+
+  let (stdout_buf, stdout_task) = capture_output(child.stdout.take(), limit);
+  let (stderr_buf, stderr_task) = capture_output(child.stderr.take(), limit);
+  let pid = child.id();
+  let started = Instant::now();
+  let timeout = Duration::from_secs(timeout_secs);
+
+  let waited = tokio::time::timeout(timeout, child.wait()).await;
+  // 无论正常结束与否都清理整个进程组，避免后台子进程残留
+  kill_process_tree(pid, &mut child).await;
+  let (exit_code, timed_out) = match waited {
+    Ok(Ok(status)) => (status.code(), false),
+    Ok(Err(e)) => {
+      let reason = format!("等待命令结束失败: {}", e);
+      return ToolResult {
+        success: false,
+        error: Some(reason.clone()),
+        error_kind: Some(ToolErrorKind::Fatal),
+        meta: Some(build_failure_meta(tool_name, &reason)),
+        ..Default::default()
+      };
+    }
+    Err(_) => (None, true),
+  };
+  let duration_ms = started.elapsed().as_millis() as u64;
+  let _ = tokio::time::timeout(RUN_COMMAND_DRAIN_GRACE, async {
+    let _ = stdout_task.await;
+    let _ = stderr_task.await;
+  })
+  .await;
+  let (stdout, stdout_truncated) = captured_text(&stdout_buf, limit);
+  let (stderr, stderr_truncated) = captured_text(&stderr_buf, limit);
+
+  let data = serde_json::json!({
+      "command": command,
+      "cwd": cwd_label,
+      "exit_code": exit_code,
+      "timed_out": timed_out,
+      "duration_ms": duration_ms,
+      "stdout": stdout,
+      "stderr": stderr,
+      "stdout_truncated": stdout_truncated,
+      "stderr_truncated": stderr_truncated,
+  });
+
+  if timed_out {
+    // 不使用“超时”字样，避免前端重试逻辑把命令再执行一遍
+    let reason = format!("命令运行超过 {} 秒，已被终止", timeout_secs);
+    return ToolResult {
+      success: false,
+      data: Some(data),
+      error: Some(reason.clone()),
+      error_kind: Some(ToolErrorKind::Skippable),
+      meta: Some(build_failure_meta(tool_name, &reason)),
+      ..Default::default()
+    };
+  }
+
+  ToolResult {
+    success: true,
+    message: Some(match exit_code {
+      Some(code) => format!("命令执行完成，退出码 {}", code),
+      None => "命令被信号终止".to_string(),
+    }),
+    data: Some(data),
+    ..Default::default()
+  }
+}
+
+/// run_command 的外层超时需留出命令自身超时后终止进程、收集输出的余量
+const RUN_COMMAND_TIMEOUT_MARGIN_SECS: u64 = 10;
+/// 小于该字节数的字符串 / 数组不参与截断，避免反复截断截断标记本身
+const TRUNCATE_MIN_NODE_BYTES: usize = 256;
+
+/// 单个工具的执行限制（由工作区设置解析）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ToolLimits {
+  /// None 表示不限制
+  timeout: Option<Duration>,
+  max_output_bytes: usize,
+}
+
+impl ToolLimits {
+  fn resolve(tool_name: &str, settings: &AgentToolSettings) -> Self {
+    let overrides = settings.tool_limits.get(tool_name);
+    let mut timeout_secs = overrides
+      .and_then(|o| o.timeout_secs)
+      .unwrap_or(settings.tool_timeout_secs);
+    if tool_name == "run_command" && timeout_secs > 0 {
+      timeout_secs =
+        timeout_secs.max(settings.command_timeout_secs + RUN_COMMAND_TIMEOUT_MARGIN_SECS);
+    }
+    Self {
+      timeout: (timeout_secs > 0).then(|| Duration::from_secs(timeout_secs)),
+      max_output_bytes: overrides
+        .and_then(|o| o.max_output_bytes)
+        .unwrap_or(settings.tool_max_output_bytes),
+    }
+  }
+}
+
+fn serialized_len(value: &serde_json::Value) -> usize {
+  serde_json::to_string(value).map(|s| s.len()).unwrap_or(0)
+}
+
+/// 找到序列化后最大的字符串或数组节点，返回 (JSON Pointer, 字节数)
+fn largest_truncatable_node(
+  value: &serde_json::Value,
+  pointer: String,
+  best: &mut Option<(String, usize)>,
+) {
+  let size = match value {
+    serde_json::Value::String(_) | serde_json::Value::Array(_) => serialized_len(value),
+    _ => 0,
+  };
+  if size >= TRUNCATE_MIN_NODE_BYTES && best.as_ref().map_or(true, |(_, s)| size > *s) {
+    *best = Some((pointer.clone(), size));
+  }
+  match value {
+    serde_json::Value::Object(map) => {
+      for (key, child) in map {
+        let escaped = key.replace('~', "~0").replace('/', "~1");
+        largest_truncatable_node(child, format!("{}/{}", pointer, escaped), best);
+      }
+    }
+    serde_json::Value::Array(items) => {
+      for (i, child) in items.iter().enumerate() {
+        largest_truncatable_node(child, format!("{}/{}", pointer, i), best);
+      }
+    }
+    _ => {}
+  }
+}
+
+/// 将工具输出截断到 `max_bytes` 以内：优先截断最大的字符串（末尾附带字节数标记），
+/// 其次丢弃最大数组的尾部元素。返回 (原始字节数, 截断后字节数)，未超限时返回 None
+fn truncate_tool_output(value: &mut serde_json::Value, max_bytes: usize) -> Option<(usize, usize)> {
+  let original = serialized_len(value);
+  if original <= max_bytes {
+    return None;
+  }
+
+  let mut size = original;
+  while size > max_bytes {
+    let mut best = None;
+    largest_truncatable_node(value, String::new(), &mut best);
+    let Some((pointer, _)) = best else {
+      break;
+    };
+    let excess = size - max_bytes;
+    match value.pointer_mut(&pointer) {
+      Some(serde_json::Value::String(text)) => {
+        let total = text.len();
+        let mut keep = total.saturating_sub(excess + 96);
+        while !text.is_char_boundary(keep) {
+          keep -= 1;
+        }
+        text.truncate(keep);
+        text.push_str(&format!(
+          "\n…[内容已截断：原始 {} 字节，保留前 {} 字节]",
+          total, keep
+        ));
+      }
+      Some(serde_json::Value::Array(items)) => {
+        let mut removed = 0;
+        while removed < excess {
+          match items.pop() {
+            Some(item) => removed += serialized_len(&item) + 1,
+            None => break,
+          }
+        }
+      }
+      _ => break,
+    }
+    let next = serialized_len(value);
+    if next >= size {
+      break;
+    }
+    size = next;
+  }
+  Some((original, size))
+}
+
+/// 对工具结果应用输出上限，截断时在 data 中记录 `output_truncated` 并提示模型
+fn apply_output_limit(mut result: ToolResult, max_output_bytes: usize) -> ToolResult {
+  let Some(data) = result.data.as_mut() else {
+    return result;
+  };
+  if let Some((original, returned)) = truncate_tool_output(data, max_output_bytes) {
+    if let serde_json::Value::Object(map) = data {
+      map.insert(
+        "output_truncated".to_string(),
+        serde_json::json!({
+          "original_bytes": original,
+          "returned_bytes": returned,
+          "max_output_bytes": max_output_bytes,
+        }),
+      );
+    }
+    let note = format!(
+      "输出过大已截断（原始 {} 字节，返回 {} 字节）",
+      original, returned
+    );
+    result.message = Some(match result.message.take() {
+      Some(message) => format!("{}；{}", message, note),
+      None => note,
+    });
+  }
+  result
+}
+
 fn map_path_validation_error(err: crate::utils::path_validator::PathValidationError) -> String {
   err.to_string()
 }
@@ -481,12 +1072,21 @@ impl ToolService {
     PathValidator::validate_workspace_path(path, workspace_path).map_err(map_path_validation_error)
   }
 
+  /// 写入目标须在工作区内，且不能是 `.binder` 配置目录（自定义工具、工作区设置等）
   fn validate_write_target(&self, path: &Path, workspace_path: &Path) -> Result<PathBuf, String> {
-    PathValidator::validate_workspace_write_target(path, workspace_path)
-      .map_err(map_path_validation_error)
+    let target = PathValidator::validate_workspace_write_target(path, workspace_path)
+      .map_err(map_path_validation_error)?;
+    if is_workspace_config_path(&target, workspace_path) {
+      return Err(format!(
+        "不允许修改工作区配置目录 .binder: {}",
+        path.display()
+      ));
+    }
+    Ok(target)
   }
 
-  /// 执行工具调用
+  /// 执行工具调用；需要确认的工具只返回待确认结果，由用户通过 `resolve_confirmation` 批准。
+  /// 参数中的确认字段一律忽略，模型无法自行完成确认
   pub async fn execute_tool(
     &self,
     tool_call: &ToolCall,
@@ -496,79 +1096,183 @@ impl ToolService {
     if !workspace_path.exists() {
       return Err("工作区路径不存在".to_string());
     }
+    let tool_call = ToolCall {
+      id: tool_call.id.clone(),
+      name: tool_call.name.clone(),
+      arguments: strip_internal_gate_fields(&tool_call.arguments),
+    };
+    if let Some(rejected) = run_command_rejection_for(&tool_call, workspace_path) {
+      return Ok(rejected);
+    }
 
-    if tool_requires_confirmation(&tool_call.name) {
-      let expected_record_id = confirmation_record_id(tool_call);
-      match parse_confirmation_action(tool_call).as_deref() {
-        Some("confirm") => {
-          let provided_record_id = tool_call
-            .arguments
-            .get("_confirmation_id")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| "缺少 confirmation_id 参数".to_string())?;
-          if provided_record_id != expected_record_id {
-            return Err("确认记录与待执行工具不匹配".to_string());
-          }
-        }
-        Some("deny") => {
-          let provided_record_id = tool_call
-            .arguments
-            .get("_confirmation_id")
-            .and_then(|v| v.as_str())
-            .unwrap_or(expected_record_id.as_str())
-            .to_string();
-          return Ok(build_confirmation_rejected_result(
-            tool_call,
-            provided_record_id,
-          ));
-        }
-        Some(other) => {
-          return Err(format!("未知的 confirmation_action: {}", other));
-        }
-        None => return Ok(build_confirmation_pending_result(tool_call)),
-      }
+    // 自定义工具默认同样需要用户确认（免确认须经用户在应用设置中允许，shell 动作除外）
+    let custom_tool = custom_tools::find_custom_tool(workspace_path, &tool_call.name);
+    let requires_confirmation = tool_requires_confirmation(&tool_call.name)
+      || custom_tool
+        .as_ref()
+        .is_some_and(|tool| tool.requires_confirmation(workspace_path));
+    if requires_confirmation {
+      let record_id = issue_confirmation(&tool_call, workspace_path);
+      return Ok(build_confirmation_pending_result(&tool_call, record_id));
     }
 
-    let sanitized_tool_call = if tool_requires_confirmation(&tool_call.name) {
-      ToolCall {
-        id: tool_call.id.clone(),
-        name: tool_call.name.clone(),
-        arguments: sanitize_confirmation_arguments(tool_call),
-      }
-    } else {
-      tool_call.clone()
+    self
+      .run_tool(&tool_call, workspace_path, custom_tool.is_some())
+      .await
+  }
+
+  /// 用户在前端确认或拒绝待确认的工具调用；记录 ID 只能使用一次
+  pub async fn resolve_confirmation(
+    &self,
+    tool_call: &ToolCall,
+    workspace_path: &Path,
+    record_id: &str,
+    approved: bool,
+  ) -> Result<ToolResult, String> {
+    let tool_call = ToolCall {
+      id: tool_call.id.clone(),
+      name: tool_call.name.clone(),
+      arguments: strip_internal_gate_fields(&tool_call.arguments),
+    };
+    if !take_confirmation(record_id, &tool_call, workspace_path) {
+      return Err("确认记录无效、已使用或与待执行工具不匹配".to_string());
+    }
+    if !approved {
+      return Ok(build_confirmation_rejected_result(
+        &tool_call,
+        record_id.to_string(),
+      ));
+    }
+    // 确认期间设置可能已变更，执行前重新检查
+    if let Some(rejected) = run_command_rejection_for(&tool_call, workspace_path) {
+      return Ok(rejected);
+    }
+    let custom = custom_tools::find_custom_tool(workspace_path, &tool_call.name).is_some();
+    self.run_tool(&tool_call, workspace_path, custom).await
+  }
+
+  /// 执行已通过确认检查的工具调用
+  async fn run_tool(
+    &self,
+    sanitized_tool_call: &ToolCall,
+    workspace_path: &Path,
+    custom: bool,
+  ) -> Result<ToolResult, String> {
+    let limits = ToolLimits::resolve(
+      &sanitized_tool_call.name,
+      &WorkspaceSettings::load_or_default(workspace_path).agent,
+    );
+    // 快照本次调用会修改的路径，归入当前轮次的撤销事务（读文件放到阻塞线程池）
+    let pending_snapshot = {
+      let tool_call = sanitized_tool_call.clone();
+      let workspace = workspace_path.to_path_buf();
+      tokio::task::spawn_blocking(move || {
+        agent_transaction::prepare_snapshot(&tool_call, &workspace, custom)
+      })
+      .await
+      .map_err(|e| format!("采集撤销快照失败: {}", e))?
+    };
+    let dispatched = self.dispatch_tool(sanitized_tool_call, workspace_path);
+    let result = match limits.timeout {
+      Some(timeout) => match tokio::time::timeout(timeout, dispatched).await {
+        Ok(result) => result,
+        Err(_) => {
+          // 不使用“超时”字样，避免前端重试逻辑把同一调用再执行一遍
+          let reason = format!(
+            "工具 {} 执行超过 {} 秒，已中止",
+            sanitized_tool_call.name,
+            timeout.as_secs()
+          );
+          Ok(ToolResult {
+            success: false,
+            error: Some(reason.clone()),
+            error_kind: Some(ToolErrorKind::Skippable),
+            meta: Some(build_failure_meta(&sanitized_tool_call.name, &reason)),
+            ..Default::default()
+          })
+        }
+      },
+      None => dispatched.await,
     };
+    if let Some(pending) = pending_snapshot {
+      let succeeded = result.as_ref().is_ok_and(|r| r.success);
+      let workspace = workspace_path.to_path_buf();
+      if let Err(e) = tokio::task::spawn_blocking(move || {
+        agent_transaction::record_tool_changes(&workspace, pending, succeeded)
+      })
+      .await
+      {
+        tracing::warn!("记录撤销快照失败: {}", e);
+      }
+    }
+    result.map(|r| apply_output_limit(r, limits.max_output_bytes))
+  }
 
+  /// 按工具名分发到具体实现
+  async fn dispatch_tool(
+    &self,
+    sanitized_tool_call: &ToolCall,
+    workspace_path: &Path,
+  ) -> Result<ToolResult, String> {
     match sanitized_tool_call.name.as_str() {
-      "read_file" => self.read_file(&sanitized_tool_call, workspace_path).await,
-      "create_file" => self.create_file(&sanitized_tool_call, workspace_path).await,
-      "update_file" => self.update_file(&sanitized_tool_call, workspace_path).await,
-      "delete_file" => self.delete_file(&sanitized_tool_call, workspace_path).await,
-      "list_files" => self.list_files(&sanitized_tool_call, workspace_path).await,
-      "search_files" => {
+      "read_file" => self.read_file(sanitized_tool_call, workspace_path).await,
+      "create_file" => self.create_file(sanitized_tool_call, workspace_path).await,
+      "update_file" => self.update_file(sanitized_tool_call, workspace_path).await,
+      "delete_file" => self.delete_file(sanitized_tool_call, workspace_path).await,
+      "list_files" => self.list_files(sanitized_tool_call, workspace_path).await,
+      "search_files" => self.search_files(sanitized_tool_call, workspace_path).await,
+      "apply_patch" => self.apply_patch(sanitized_tool_call, workspace_path).await,
+      "run_command" => self.run_command(sanitized_tool_call, workspace_path).await,
+      "search_memories" => {
         self
-          .search_files(&sanitized_tool_call, workspace_path)
+          .search_memories(sanitized_tool_call, workspace_path)
           .await
       }
-      "move_file" => self.move_file(&sanitized_tool_call, workspace_path).await,
-      "rename_file" => self.rename_file(&sanitized_tool_call, workspace_path).await,
-      "create_folder" => {
+      "read_document" => {
         self
-          .create_folder(&sanitized_tool_call, workspace_path)
+          .read_document(sanitized_tool_call, workspace_path)
           .await
       }
-      "get_current_editor_file" => self.get_current_editor_file(&sanitized_tool_call).await,
-      "edit_current_editor_document" => {
+      "list_directory" => {
+        self
+          .list_directory(sanitized_tool_call, workspace_path)
+          .await
+      }
+      "search_workspace" => {
+        self
+          .search_workspace(sanitized_tool_call, workspace_path)
+          .await
+      }
+      "move_file" => self.move_file(sanitized_tool_call, workspace_path).await,
+      "copy_file" => self.copy_file(sanitized_tool_call, workspace_path).await,
+      "create_docx_from_markdown" => {
+        self
+          .create_docx_from_markdown(sanitized_tool_call, workspace_path)
+          .await
+      }
+      "rename_file" => self.rename_file(sanitized_tool_call, workspace_path).await,
+      "create_folder" => {
         self
-          .edit_current_editor_document(&sanitized_tool_call)
+          .create_folder(sanitized_tool_call, workspace_path)
           .await
       }
+      "get_current_editor_file" => self.get_current_editor_file(sanitized_tool_call).await,
+      "edit_current_editor_document" => {
+        self.edit_current_editor_document(sanitized_tool_call).await
+      }
       "save_file_dependency" => {
         self
-          .save_file_dependency(&sanitized_tool_call, workspace_path)
+          .save_file_dependency(sanitized_tool_call, workspace_path)
           .await
       }
-      _ => Err(format!("未知的工具: {}", tool_call.name)),
+      other => match custom_tools::find_custom_tool(workspace_path, other) {
+        Some(tool) => {
+          self
+            .run_custom_tool(&tool, sanitized_tool_call, workspace_path)
+            .await
+        }
+        None => Err(format!("未知的工具: {}", sanitized_tool_call.name)),
+      },
     }
   }
 
@@ -687,63 +1391,316 @@ impl ToolService {
     }
   }
 
-  /// 创建文件（原子写入）
-  async fn create_file(
+  /// 读取文档并提取为 Markdown/纯文本：DOCX/ODT/RTF/EPUB/HTML 经 Pandoc 转换，PDF 提取文字层
+  async fn read_document(
     &self,
     tool_call: &ToolCall,
     workspace_path: &Path,
   ) -> Result<ToolResult, String> {
-    eprintln!(
-      "🔧 create_file 调用参数: {}",
-      serde_json::to_string(&tool_call.arguments).unwrap_or_default()
-    );
-
     let file_path = tool_call
       .arguments
       .get("path")
       .and_then(|v| v.as_str())
-      .ok_or_else(|| {
-        eprintln!(
-          "❌ create_file 缺少 path 参数，arguments: {:?}",
-          tool_call.arguments
-        );
-        "缺少 path 参数".to_string()
-      })?;
-
-    // content 可以为空字符串，但不能缺失
-    let content = tool_call
+      .ok_or_else(|| "缺少 path 参数".to_string())?;
+    let offset = tool_call
       .arguments
-      .get("content")
-      .and_then(|v| v.as_str())
-      .unwrap_or(""); // 如果 content 不存在，使用空字符串
+      .get("offset")
+      .and_then(|v| v.as_u64())
+      .unwrap_or(0) as usize;
+    let max_chars = tool_call
+      .arguments
+      .get("max_chars")
+      .and_then(|v| v.as_u64())
+      .map(|n| (n as usize).clamp(1_000, READ_DOCUMENT_MAX_CHARS))
+      .unwrap_or(READ_DOCUMENT_DEFAULT_CHARS);
 
     let full_path = self.resolve_relative_path(workspace_path, file_path)?;
-    self.validate_write_target(&full_path, workspace_path)?;
-
-    // 检查文件是否已存在
-    if full_path.exists() {
+    if !full_path.is_file() {
       return Ok(ToolResult {
         success: false,
-        data: None,
-        error: Some(format!("文件已存在: {}", file_path)),
-        message: None,
-        error_kind: None,
-        display_error: None,
-        meta: None,
+        error: Some(format!("文件不存在: {}", file_path)),
+        error_kind: Some(ToolErrorKind::Skippable),
+        ..Default::default()
       });
     }
+    let full_path = self.validate_existing_path(&full_path, workspace_path)?;
 
-    // 创建父目录
-    if let Some(parent) = full_path.parent() {
-      if let Err(e) = std::fs::create_dir_all(parent) {
-        return Ok(ToolResult {
-          success: false,
-          data: None,
-          error: Some(format!("创建目录失败: {}", e)),
-          message: None,
-          error_kind: None,
-          display_error: None,
-          meta: None,
+    let ext = full_path
+      .extension()
+      .and_then(|e| e.to_str())
+      .map(|e| e.to_lowercase())
+      .unwrap_or_default();
+    let (format, extracted) = if ext == "pdf" {
+      // pdf-extract 遇到异常文件可能 panic，放到阻塞线程中隔离
+      let path = full_path.clone();
+      let extracted = tokio::task::spawn_blocking(move || {
+        crate::services::pdf_text::extract_pdf_plain_text(&path)
+      })
+      .await
+      .unwrap_or_else(|_| Err("解析 PDF 时发生内部错误".to_string()));
+      (
+        "text",
+        extracted.map_err(|e| format!("提取 PDF 文本失败: {}", e)),
+      )
+    } else if PANDOC_DOCUMENT_EXTENSIONS.contains(&ext.as_str()) {
+      use crate::services::pandoc_service::PandocService;
+      let path = full_path.clone();
+      let extracted =
+        tokio::task::spawn_blocking(move || PandocService::new().extract_markdown(&path))
+          .await
+          .map_err(|e| format!("转换文档失败: {}", e))?;
+      ("markdown", extracted)
+    } else if OCR_IMAGE_EXTENSIONS.contains(&ext.as_str()) {
+      let path = full_path.clone();
+      let extracted = tokio::task::spawn_blocking(move || {
+        OcrService::new()
+          .recognize_image(&path, None)
+          .map(|output| output.text)
+      })
+      .await
+      .map_err(|e| format!("识别图片文字失败: {}", e))?;
+      ("ocr", extracted)
+    } else {
+      match std::fs::read(&full_path) {
+        Ok(bytes) => match String::from_utf8(bytes) {
+          Ok(text) => ("text", Ok(text)),
+          Err(_) => (
+            "text",
+            Err(format!(
+              "不支持的文档格式: {}（支持 PDF、DOCX、ODT、RTF、EPUB、HTML、图片及文本文件）",
+              file_path
+            )),
+          ),
+        },
+        Err(e) => ("text", Err(format!("读取文件失败: {}", e))),
+      }
+    };
+
+    let mut format = format;
+    let mut text = match extracted {
+      Ok(text) => normalize_extracted_text(&text),
+      Err(e) => {
+        return Ok(ToolResult {
+          success: false,
+          error: Some(e.clone()),
+          error_kind: Some(ToolErrorKind::Skippable),
+          meta: Some(build_failure_meta("read_document", &e)),
+          ..Default::default()
+        });
+      }
+    };
+    // 没有文字层的扫描版 PDF：OCR 可用时改为逐页识别
+    if text.is_empty() && ext == "pdf" && OcrService::new().supports_pdf() {
+      let path = full_path.clone();
+      let recognized = tokio::task::spawn_blocking(move || {
+        OcrService::new().recognize_pdf(&path, None, OCR_DEFAULT_MAX_PAGES)
+      })
+      .await
+      .map_err(|e| format!("识别 PDF 文字失败: {}", e))?;
+      match recognized {
+        Ok(output) => {
+          text = normalize_extracted_text(&output.text);
+          format = "ocr";
+        }
+        Err(e) => tracing::warn!("扫描版 PDF 文字识别失败 {}: {}", file_path, e),
+      }
+    }
+    if text.is_empty() && ext == "pdf" {
+      return Ok(ToolResult {
+        success: false,
+        error: Some(format!(
+          "未能从 {} 中提取到文字（可能是扫描件或纯图片 PDF）",
+          file_path
+        )),
+        error_kind: Some(ToolErrorKind::Skippable),
+        ..Default::default()
+      });
+    }
+
+    let (content, total_chars, has_more) = slice_chars(&text, offset, max_chars);
+    let returned_chars = content.chars().count();
+    Ok(ToolResult {
+      success: true,
+      message: Some(if has_more {
+        format!(
+          "已读取 {} 的第 {}-{} 个字符（共 {} 个），可用 offset = {} 继续读取",
+          file_path,
+          offset,
+          offset + returned_chars,
+          total_chars,
+          offset + returned_chars
+        )
+      } else {
+        format!("成功读取文档: {}", file_path)
+      }),
+      data: Some(serde_json::json!({
+          "path": file_path,
+          "format": format,
+          "source_format": ext,
+          "content": content,
+          "offset": offset,
+          "returned_chars": returned_chars,
+          "total_chars": total_chars,
+          "truncated": has_more,
+      })),
+      ..Default::default()
+    })
+  }
+
+  /// 检索已保存的记忆（工作区记忆与用户级记忆合并），供模型在对话中按需查询偏好和项目事实
+  async fn search_memories(
+    &self,
+    tool_call: &ToolCall,
+    workspace_path: &Path,
+  ) -> Result<ToolResult, String> {
+    use crate::services::memory_service::{
+      merge_with_user_memories, ExtractionConfig, MemorySearchScope, MemoryService,
+      SearchMemoriesParams,
+    };
+
+    let query = tool_call
+      .arguments
+      .get("query")
+      .and_then(|v| v.as_str())
+      .ok_or_else(|| "缺少 query 参数".to_string())?
+      .to_string();
+    if !ExtractionConfig::load().enabled {
+      return Ok(ToolResult {
+        success: false,
+        error: Some("记忆功能未启用".to_string()),
+        error_kind: Some(ToolErrorKind::Fatal),
+        ..Default::default()
+      });
+    }
+    // 标签页记忆与具体对话绑定，这里只开放工作区与用户级范围
+    let scope = match tool_call.arguments.get("scope").and_then(|v| v.as_str()) {
+      Some("workspace") => MemorySearchScope::WorkspaceLongTerm,
+      Some("content") => MemorySearchScope::Content,
+      Some("user") => MemorySearchScope::User,
+      _ => MemorySearchScope::All,
+    };
+    let include_user = matches!(scope, MemorySearchScope::All | MemorySearchScope::User);
+    let tags = tool_call
+      .arguments
+      .get("tags")
+      .and_then(|v| v.as_array())
+      .map(|tags| {
+        tags
+          .iter()
+          .filter_map(|t| t.as_str().map(str::to_string))
+          .collect::<Vec<_>>()
+      });
+    let limit = tool_call
+      .arguments
+      .get("limit")
+      .and_then(|v| v.as_u64())
+      .map(|n| (n as usize).clamp(1, 20))
+      .unwrap_or(8);
+
+    let service =
+      MemoryService::new(workspace_path).map_err(|e| format!("MemoryService 初始化失败: {}", e))?;
+    let response = service
+      .search_memories(SearchMemoriesParams {
+        query: query.clone(),
+        tab_id: None,
+        workspace_path: Some(workspace_path.to_string_lossy().to_string()),
+        scope,
+        limit: Some(limit),
+        entity_types: None,
+        tags,
+      })
+      .await
+      .map_err(|e| e.to_string())?;
+    let items = merge_with_user_memories(response.items, &query, limit, include_user).await;
+
+    let memories: Vec<serde_json::Value> = items
+      .iter()
+      .map(|r| {
+        serde_json::json!({
+            "id": r.item.id,
+            "layer": r.item.layer,
+            "name": r.item.entity_name,
+            "type": r.item.entity_type,
+            "content": r.item.content,
+            "tags": r.item.tags,
+            "source": r.source_label,
+            "updated_at": r.item.updated_at,
+        })
+      })
+      .collect();
+    Ok(ToolResult {
+      success: true,
+      message: Some(if memories.is_empty() {
+        format!("没有找到与“{}”相关的记忆", query)
+      } else {
+        format!("找到 {} 条相关记忆", memories.len())
+      }),
+      data: Some(serde_json::json!({
+          "query": query,
+          "memories": memories,
+          "timed_out": response.timed_out,
+      })),
+      ..Default::default()
+    })
+  }
+
+  /// 创建文件（原子写入）
+  async fn create_file(
+    &self,
+    tool_call: &ToolCall,
+    workspace_path: &Path,
+  ) -> Result<ToolResult, String> {
+    tracing::debug!(
+      "create_file 调用参数: {}",
+      serde_json::to_string(&tool_call.arguments).unwrap_or_default()
+    );
+
+    let file_path = tool_call
+      .arguments
+      .get("path")
+      .and_then(|v| v.as_str())
+      .ok_or_else(|| {
+        tracing::error!(
+          "create_file 缺少 path 参数，arguments: {:?}",
+          tool_call.arguments
+        );
+        "缺少 path 参数".to_string()
+      })?;
+
+    // content 可以为空字符串，但不能缺失
+    let content = tool_call
+      .arguments
+      .get("content")
+      .and_then(|v| v.as_str())
+      .unwrap_or(""); // 如果 content 不存在，使用空字符串
+
+    let full_path = self.resolve_relative_path(workspace_path, file_path)?;
+    self.validate_write_target(&full_path, workspace_path)?;
+
+    // 检查文件是否已存在
+    if full_path.exists() {
+      return Ok(ToolResult {
+        success: false,
+        data: None,
+        error: Some(format!("文件已存在: {}", file_path)),
+        message: None,
+        error_kind: None,
+        display_error: None,
+        meta: None,
+      });
+    }
+
+    // 创建父目录
+    if let Some(parent) = full_path.parent() {
+      if let Err(e) = std::fs::create_dir_all(parent) {
+        return Ok(ToolResult {
+          success: false,
+          data: None,
+          error: Some(format!("创建目录失败: {}", e)),
+          message: None,
+          error_kind: None,
+          display_error: None,
+          meta: None,
         });
       }
     }
@@ -786,6 +1743,7 @@ impl ToolService {
             "ai",
             &[full_path.clone()],
           )?;
+          refresh_index_after_save(workspace_path, &full_path, None);
           Ok(ToolResult {
             success: true,
             data: Some(serde_json::json!({
@@ -823,6 +1781,7 @@ impl ToolService {
             "ai",
             &[full_path.clone()],
           )?;
+          refresh_index_after_save(workspace_path, &full_path, Some(content.to_string()));
           Ok(ToolResult {
             success: true,
             data: Some(serde_json::json!({
@@ -849,6 +1808,115 @@ impl ToolService {
     }
   }
 
+  /// 将 Markdown 转换为 DOCX（使用工作区参考模板）
+  async fn create_docx_from_markdown(
+    &self,
+    tool_call: &ToolCall,
+    workspace_path: &Path,
+  ) -> Result<ToolResult, String> {
+    let file_path = tool_call
+      .arguments
+      .get("path")
+      .and_then(|v| v.as_str())
+      .ok_or_else(|| "缺少 path 参数".to_string())?;
+    let markdown = tool_call
+      .arguments
+      .get("markdown")
+      .and_then(|v| v.as_str())
+      .ok_or_else(|| "缺少 markdown 参数".to_string())?
+      .to_string();
+
+    let full_path = self.resolve_relative_path(workspace_path, file_path)?;
+    self.validate_write_target(&full_path, workspace_path)?;
+
+    let is_docx = full_path
+      .extension()
+      .and_then(|s| s.to_str())
+      .is_some_and(|ext| ext.eq_ignore_ascii_case("docx"));
+    if !is_docx {
+      return Ok(ToolResult {
+        success: false,
+        error: Some(format!("目标路径必须以 .docx 结尾: {}", file_path)),
+        error_kind: Some(ToolErrorKind::Fatal),
+        ..Default::default()
+      });
+    }
+
+    if full_path.exists() {
+      return Ok(ToolResult {
+        success: false,
+        error: Some(format!("文件已存在: {}", file_path)),
+        ..Default::default()
+      });
+    }
+
+    use crate::services::pandoc_service::PandocService;
+    let pandoc_service = PandocService::new();
+    if !pandoc_service.is_available() {
+      return Ok(ToolResult {
+        success: false,
+        error: Some("Pandoc 不可用，无法生成 DOCX 文件。请安装 Pandoc 后再试。".to_string()),
+        error_kind: Some(ToolErrorKind::Fatal),
+        ..Default::default()
+      });
+    }
+
+    if let Some(parent) = full_path.parent() {
+      if let Err(e) = std::fs::create_dir_all(parent) {
+        return Ok(ToolResult {
+          success: false,
+          error: Some(format!("创建目录失败: {}", e)),
+          ..Default::default()
+        });
+      }
+    }
+
+    let reference_doc = PandocService::workspace_reference_docx(workspace_path);
+    let (output, reference) = (full_path.clone(), reference_doc.clone());
+    let converted = tokio::task::spawn_blocking(move || {
+      pandoc_service.convert_markdown_to_docx(&markdown, &output, reference.as_deref())
+    })
+    .await
+    .map_err(|e| format!("转换 DOCX 失败: {}", e))?;
+
+    if let Err(e) = converted {
+      let _ = std::fs::remove_file(&full_path);
+      return Ok(ToolResult {
+        success: false,
+        error: Some(format!("转换 DOCX 失败: {}", e)),
+        ..Default::default()
+      });
+    }
+
+    let db =
+      WorkspaceDb::new(workspace_path).map_err(|e| format!("WorkspaceDb 初始化失败: {}", e))?;
+    let _ = record_resource_structure_timeline_node(
+      &db,
+      workspace_path,
+      "create_file",
+      &format!("AI 创建文件：{}", file_path),
+      "ai",
+      std::slice::from_ref(&full_path),
+    )?;
+    refresh_index_after_save(workspace_path, &full_path, None);
+
+    let template = reference_doc.map(|p| {
+      p.strip_prefix(workspace_path)
+        .map(|rel| rel.to_string_lossy().to_string())
+        .unwrap_or_else(|_| "builtin".to_string())
+    });
+    Ok(ToolResult {
+      success: true,
+      data: Some(serde_json::json!({
+          "path": file_path,
+          "format": "docx",
+          "reference_template": template,
+      })),
+      message: Some(format!("成功生成 DOCX 文件: {}", file_path)),
+      ..Default::default()
+    })
+  }
+
   /// 更新文件（原子写入）
   async fn update_file(
     &self,
@@ -869,7 +1937,7 @@ impl ToolService {
 
     {
       let content_preview: String = content.chars().take(80).collect();
-      eprintln!(
+      tracing::debug!(
         "[CROSS_FILE_TRACE][TOOL] {{\"tool\":\"update_file\",\"target_file\":{:?},\"tool_call_id\":{:?},\"content_len\":{},\"content_preview\":{:?}}}",
         file_path,
         tool_call.id,
@@ -897,67 +1965,22 @@ impl ToolService {
     let db =
       WorkspaceDb::new(workspace_path).map_err(|e| format!("WorkspaceDb 初始化失败: {}", e))?;
 
-    let mtime = std::fs::metadata(&full_path)
-      .and_then(|m| m.modified())
-      .map(|t| {
-        t.duration_since(std::time::UNIX_EPOCH)
-          .unwrap_or_default()
-          .as_secs() as i64
-      })
-      .unwrap_or(0);
-
     let file_type = full_path
       .extension()
       .and_then(|e| e.to_str())
       .unwrap_or("txt")
       .to_lowercase();
 
-    let old_content = match db.get_file_cache(file_path)? {
-      Some(entry) if entry.mtime == mtime => materialize_cached_body_if_stale_hash(
-        &db,
-        file_path,
-        &file_type,
-        entry.cached_content.clone(),
-        entry.content_hash.clone(),
-        mtime,
-      )?,
-      _ => {
-        let raw = if file_type == "docx" {
-          use crate::services::pandoc_service::PandocService;
-          let pandoc = PandocService::new();
-          if pandoc.is_available() {
-            pandoc
-              .convert_document_to_html(&full_path, full_path.parent())
-              .map_err(|e| format!("读取 DOCX 失败: {}", e))?
-          } else {
-            return Ok(ToolResult {
-              success: false,
-              data: None,
-              error: Some("Pandoc 不可用，无法读取 DOCX".to_string()),
-              message: None,
-              error_kind: None,
-              display_error: None,
-              meta: Some(build_failure_meta("update_file", "pandoc unavailable")),
-            });
-          }
-        } else {
-          std::fs::read_to_string(&full_path).map_err(|e| format!("读取文件失败: {}", e))?
-        };
-        if should_run_workspace_canonical_pipeline(&file_type) {
-          let (html, hash) = canonical_html_for_workspace_cache(&raw);
-          db.upsert_file_cache(
-            file_path,
-            &file_type,
-            Some(&html),
-            Some(hash.as_str()),
-            mtime,
-          )?;
-          html
-        } else {
-          db.upsert_file_cache(file_path, &file_type, Some(&raw), None, mtime)?;
-          raw
-        }
-      }
+    let Some(old_content) = self.load_update_base(&db, file_path, &full_path, &file_type)? else {
+      return Ok(ToolResult {
+        success: false,
+        data: None,
+        error: Some("Pandoc 不可用，无法读取 DOCX".to_string()),
+        message: None,
+        error_kind: None,
+        display_error: None,
+        meta: Some(build_failure_meta("update_file", "pandoc unavailable")),
+      });
     };
 
     // use_diff：生成 pending diffs，不写盘
@@ -1043,29 +2066,415 @@ impl ToolService {
           &old_content,
           content,
         )?;
+        refresh_index_after_save(workspace_path, &full_path, Some(content.to_string()));
         Ok(ToolResult {
           success: true,
           data: Some(serde_json::json!({
               "path": file_path,
-              "size": content.len(),
+              "size": content.len(),
+          })),
+          error: None,
+          message: Some(format!("成功更新文件: {}", file_path)),
+          error_kind: None,
+          display_error: None,
+          meta: None,
+        })
+      }
+      Err(e) => Ok(ToolResult {
+        success: false,
+        data: None,
+        error: Some(format!("写入文件失败: {}", e)),
+        message: None,
+        error_kind: None,
+        display_error: None,
+        meta: Some(build_failure_meta("update_file", "write failed")),
+      }),
+    }
+  }
+
+  /// 读取 update_file 比对用的当前内容：mtime 未变时用 file_cache，否则重新读取并刷新缓存。
+  /// HTML/DOCX 为规范化后的 HTML（与编辑器同源）；Pandoc 不可用时返回 None。
+  fn load_update_base(
+    &self,
+    db: &WorkspaceDb,
+    file_path: &str,
+    full_path: &Path,
+    file_type: &str,
+  ) -> Result<Option<String>, String> {
+    let mtime = std::fs::metadata(full_path)
+      .and_then(|m| m.modified())
+      .map(|t| {
+        t.duration_since(std::time::UNIX_EPOCH)
+          .unwrap_or_default()
+          .as_secs() as i64
+      })
+      .unwrap_or(0);
+
+    if let Some(entry) = db.get_file_cache(file_path)? {
+      if entry.mtime == mtime {
+        return materialize_cached_body_if_stale_hash(
+          db,
+          file_path,
+          file_type,
+          entry.cached_content.clone(),
+          entry.content_hash.clone(),
+          mtime,
+        )
+        .map(Some);
+      }
+    }
+
+    let raw = if file_type == "docx" {
+      use crate::services::pandoc_service::PandocService;
+      let pandoc = PandocService::new();
+      if !pandoc.is_available() {
+        return Ok(None);
+      }
+      pandoc
+        .convert_document_to_html(full_path, full_path.parent())
+        .map_err(|e| format!("读取 DOCX 失败: {}", e))?
+    } else {
+      std::fs::read_to_string(full_path).map_err(|e| format!("读取文件失败: {}", e))?
+    };
+    if should_run_workspace_canonical_pipeline(file_type) {
+      let (html, hash) = canonical_html_for_workspace_cache(&raw);
+      db.upsert_file_cache(
+        file_path,
+        file_type,
+        Some(&html),
+        Some(hash.as_str()),
+        mtime,
+      )?;
+      Ok(Some(html))
+    } else {
+      db.upsert_file_cache(file_path, file_type, Some(&raw), None, mtime)?;
+      Ok(Some(raw))
+    }
+  }
+
+  /// 在工作区内执行 shell 命令：需工作区设置开启且经用户确认，带超时、输出上限与黑名单
+  async fn run_command(
+    &self,
+    tool_call: &ToolCall,
+    workspace_path: &Path,
+  ) -> Result<ToolResult, String> {
+    let command = tool_call
+      .arguments
+      .get("command")
+      .and_then(|v| v.as_str())
+      .map(str::trim)
+      .filter(|c| !c.is_empty())
+      .ok_or_else(|| "缺少 command 参数".to_string())?;
+    // 确认后再检查一次，防止确认期间设置被关闭
+    let settings = WorkspaceSettings::load_or_default(workspace_path).agent;
+    if let Some(rejected) = run_command_rejection(command, &settings) {
+      return Ok(rejected);
+    }
+
+    let cwd_arg = tool_call
+      .arguments
+      .get("cwd")
+      .and_then(|v| v.as_str())
+      .filter(|p| !p.is_empty() && *p != ".");
+    let cwd = match cwd_arg {
+      Some(p) => {
+        let full = self.resolve_relative_path(workspace_path, p)?;
+        if !full.is_dir() {
+          return Ok(ToolResult {
+            success: false,
+            error: Some(format!("目录不存在: {}", p)),
+            error_kind: Some(ToolErrorKind::Skippable),
+            ..Default::default()
+          });
+        }
+        self.validate_existing_path(&full, workspace_path)?
+      }
+      None => workspace_path.to_path_buf(),
+    };
+
+    Ok(
+      execute_shell(
+        "run_command",
+        shell_command(command),
+        command,
+        &cwd,
+        cwd_arg.unwrap_or("."),
+        settings.command_timeout_secs,
+        settings.command_output_limit_bytes,
+      )
+      .await,
+    )
+  }
+
+  /// 执行 `.binder/tools` 中声明的自定义工具（shell 命令模板或 HTTP 请求）
+  async fn run_custom_tool(
+    &self,
+    tool: &CustomToolSpec,
+    tool_call: &ToolCall,
+    workspace_path: &Path,
+  ) -> Result<ToolResult, String> {
+    let args = &tool_call.arguments;
+    if let Err(e) = tool.check_required_arguments(args) {
+      return Ok(ToolResult {
+        success: false,
+        error: Some(e),
+        error_kind: Some(ToolErrorKind::Retryable),
+        ..Default::default()
+      });
+    }
+    let settings = WorkspaceSettings::load_or_default(workspace_path).agent;
+
+    match &tool.action {
+      CustomToolAction::Shell {
+        command,
+        cwd,
+        timeout_secs,
+      } => {
+        let invocation = custom_tools::render_shell_template(command, args);
+        if let Some(reason) = command_guard::denied_reason(&invocation.command) {
+          let reason = format!("{}: {}", reason, invocation.command);
+          return Ok(ToolResult {
+            success: false,
+            error: Some(reason.clone()),
+            error_kind: Some(ToolErrorKind::Fatal),
+            display_error: Some("该命令已被拒绝执行".to_string()),
+            meta: Some(build_failure_meta(&tool.name, &reason)),
+            ..Default::default()
+          });
+        }
+        let cwd_label = cwd.as_deref().filter(|p| !p.is_empty() && *p != ".");
+        let cwd_path = match cwd_label {
+          Some(p) => {
+            let full = self.resolve_relative_path(workspace_path, p)?;
+            if !full.is_dir() {
+              return Err(format!("自定义工具 {} 的工作目录不存在: {}", tool.name, p));
+            }
+            self.validate_existing_path(&full, workspace_path)?
+          }
+          None => workspace_path.to_path_buf(),
+        };
+        Ok(
+          execute_shell(
+            &tool.name,
+            shell_template_command(&invocation),
+            &invocation.command,
+            &cwd_path,
+            cwd_label.unwrap_or("."),
+            timeout_secs
+              .unwrap_or(settings.command_timeout_secs)
+              .clamp(1, 600),
+            settings.command_output_limit_bytes,
+          )
+          .await,
+        )
+      }
+      CustomToolAction::Http {
+        method,
+        url,
+        headers,
+        body,
+        timeout_secs,
+      } => {
+        let url = custom_tools::render_template(url, args, TemplateEscape::Url);
+        let method = reqwest::Method::from_bytes(method.to_ascii_uppercase().as_bytes())
+          .map_err(|e| format!("无效的请求方法: {}", e))?;
+        let timeout = Duration::from_secs(timeout_secs.unwrap_or(30).clamp(1, 600));
+        let client = reqwest::Client::builder()
+          .timeout(timeout)
+          .build()
+          .map_err(|e| format!("创建 HTTP 客户端失败: {}", e))?;
+        let mut request = client.request(method, &url);
+        for (name, value) in headers {
+          request = request.header(
+            name.as_str(),
+            custom_tools::render_template(value, args, TemplateEscape::Plain),
+          );
+        }
+        if let Some(body) = body {
+          request = request.body(custom_tools::render_template(
+            body,
+            args,
+            TemplateEscape::Json,
+          ));
+        }
+
+        let mut response = match request.send().await {
+          Ok(response) => response,
+          Err(e) => {
+            // 不使用“网络错误 / 超时”字样，避免前端重试逻辑重复发送请求
+            let reason = format!("请求 {} 失败: {}", url, e);
+            return Ok(ToolResult {
+              success: false,
+              error: Some(reason.clone()),
+              error_kind: Some(ToolErrorKind::Skippable),
+              meta: Some(build_failure_meta(&tool.name, &reason)),
+              ..Default::default()
+            });
+          }
+        };
+        let status = response.status();
+        // 分块读取，超过输出上限即停止，避免把超大响应整体读入内存
+        let limit = settings.command_output_limit_bytes;
+        let mut bytes: Vec<u8> = Vec::new();
+        let mut over_limit = false;
+        while let Some(chunk) = response
+          .chunk()
+          .await
+          .map_err(|e| format!("读取响应失败: {}", e))?
+        {
+          let remaining = limit - bytes.len();
+          if chunk.len() > remaining {
+            bytes.extend_from_slice(&chunk[..remaining]);
+            over_limit = true;
+            break;
+          }
+          bytes.extend_from_slice(&chunk);
+        }
+        let (text, cut) = command_guard::truncate_output(&bytes, limit);
+        let truncated = over_limit || cut;
+
+        Ok(ToolResult {
+          success: status.is_success(),
+          error: (!status.is_success()).then(|| format!("请求返回状态码 {}", status.as_u16())),
+          error_kind: (!status.is_success()).then_some(ToolErrorKind::Skippable),
+          message: Some(format!("{} 返回 {}", tool.name, status.as_u16())),
+          data: Some(serde_json::json!({
+              "url": url,
+              "status": status.as_u16(),
+              "body": text,
+              "body_truncated": truncated,
+          })),
+          ..Default::default()
+        })
+      }
+    }
+  }
+
+  /// 按补丁修改文件：补丁应用到与 update_file 相同的比对基线上，
+  /// 成功后交给 update_file（文档型文件仍生成待确认 diff）；失败时返回最接近的上下文供重试
+  async fn apply_patch(
+    &self,
+    tool_call: &ToolCall,
+    workspace_path: &Path,
+  ) -> Result<ToolResult, String> {
+    let file_path = tool_call
+      .arguments
+      .get("path")
+      .and_then(|v| v.as_str())
+      .ok_or_else(|| "缺少 path 参数".to_string())?;
+    let patch = tool_call
+      .arguments
+      .get("patch")
+      .and_then(|v| v.as_str())
+      .ok_or_else(|| "缺少 patch 参数".to_string())?;
+
+    let full_path = self.resolve_relative_path(workspace_path, file_path)?;
+    self.validate_write_target(&full_path, workspace_path)?;
+    if !full_path.exists() {
+      return Ok(ToolResult {
+        success: false,
+        error: Some(format!("文件不存在: {}", file_path)),
+        meta: Some(build_failure_meta("apply_patch", "file not found")),
+        ..Default::default()
+      });
+    }
+
+    let edits = match patch_apply::parse_patch(patch) {
+      Ok(edits) => edits,
+      Err(e) => {
+        return Ok(ToolResult {
+          success: false,
+          error: Some(e),
+          error_kind: Some(ToolErrorKind::Retryable),
+          meta: Some(build_failure_meta("apply_patch", "invalid patch")),
+          ..Default::default()
+        });
+      }
+    };
+
+    let db =
+      WorkspaceDb::new(workspace_path).map_err(|e| format!("WorkspaceDb 初始化失败: {}", e))?;
+    let file_type = full_path
+      .extension()
+      .and_then(|e| e.to_str())
+      .unwrap_or("txt")
+      .to_lowercase();
+    let Some(base) = self.load_update_base(&db, file_path, &full_path, &file_type)? else {
+      return Ok(ToolResult {
+        success: false,
+        error: Some("Pandoc 不可用，无法读取 DOCX".to_string()),
+        meta: Some(build_failure_meta("apply_patch", "pandoc unavailable")),
+        ..Default::default()
+      });
+    };
+
+    let outcome = match patch_apply::apply_patch(&base, &edits) {
+      Ok(outcome) => outcome,
+      Err(failure) => {
+        return Ok(ToolResult {
+          success: false,
+          error: Some(format!(
+            "第 {}/{} 处修改应用失败，文件未改动：{}",
+            failure.edit_index + 1,
+            edits.len(),
+            failure.reason
+          )),
+          data: Some(serde_json::json!({
+              "path": file_path,
+              "failed_edit": failure.edit_index,
+              "closest_context": failure.closest_context,
           })),
-          error: None,
-          message: Some(format!("成功更新文件: {}", file_path)),
-          error_kind: None,
-          display_error: None,
-          meta: None,
-        })
+          error_kind: Some(ToolErrorKind::Retryable),
+          meta: Some(build_failure_meta("apply_patch", "patch did not apply")),
+          ..Default::default()
+        });
       }
-      Err(e) => Ok(ToolResult {
-        success: false,
-        data: None,
-        error: Some(format!("写入文件失败: {}", e)),
-        message: None,
-        error_kind: None,
-        display_error: None,
-        meta: Some(build_failure_meta("update_file", "write failed")),
-      }),
+    };
+
+    if outcome.content == base {
+      return Ok(ToolResult {
+        success: true,
+        data: Some(serde_json::json!({ "path": file_path, "written": false })),
+        message: Some("补丁未产生任何变化".to_string()),
+        meta: Some(build_noop_meta("apply_patch")),
+        ..Default::default()
+      });
+    }
+
+    let mut arguments = serde_json::json!({
+        "path": file_path,
+        "content": outcome.content,
+    });
+    if let Some(use_diff) = tool_call.arguments.get("use_diff") {
+      arguments["use_diff"] = use_diff.clone();
     }
+    let update_call = ToolCall {
+      id: tool_call.id.clone(),
+      name: "update_file".to_string(),
+      arguments,
+    };
+    let mut result = self.update_file(&update_call, workspace_path).await?;
+    if result.success {
+      let fuzzy = outcome
+        .matches
+        .iter()
+        .filter(|m| **m == patch_apply::MatchKind::Fuzzy)
+        .count();
+      result.message = Some(format!(
+        "已应用 {} 处修改{}；{}",
+        edits.len(),
+        if fuzzy > 0 {
+          format!("（其中 {} 处为近似匹配，请核对）", fuzzy)
+        } else {
+          String::new()
+        },
+        result.message.unwrap_or_default()
+      ));
+      if let Some(data) = result.data.as_mut() {
+        data["matches"] = serde_json::json!(outcome.matches);
+      }
+    }
+    Ok(result)
   }
 
   /// 删除文件
@@ -1133,6 +2542,9 @@ impl ToolService {
           "ai",
           &[full_path.clone()],
         )?;
+        if !metadata.is_dir() {
+          remove_index_after_delete(workspace_path, &full_path);
+        }
         Ok(ToolResult {
           success: true,
           data: Some(serde_json::json!({
@@ -1315,6 +2727,207 @@ impl ToolService {
     Ok(())
   }
 
+  /// 以紧凑树形列出目录结构（含类型与大小），不跟随符号链接
+  async fn list_directory(
+    &self,
+    tool_call: &ToolCall,
+    workspace_path: &Path,
+  ) -> Result<ToolResult, String> {
+    let dir_path = tool_call
+      .arguments
+      .get("path")
+      .and_then(|v| v.as_str())
+      .unwrap_or(".");
+    let depth = tool_call
+      .arguments
+      .get("depth")
+      .and_then(|v| v.as_u64())
+      .map(|d| (d as usize).clamp(1, LIST_DIRECTORY_MAX_DEPTH))
+      .unwrap_or(LIST_DIRECTORY_DEFAULT_DEPTH);
+
+    let full_path = if dir_path == "." || dir_path.is_empty() {
+      workspace_path.to_path_buf()
+    } else {
+      self.resolve_relative_path(workspace_path, dir_path)?
+    };
+    if !full_path.is_dir() {
+      return Ok(ToolResult {
+        success: false,
+        error: Some(format!("目录不存在: {}", dir_path)),
+        error_kind: Some(ToolErrorKind::Skippable),
+        ..Default::default()
+      });
+    }
+    let full_path = self.validate_existing_path(&full_path, workspace_path)?;
+
+    let listing = tokio::task::spawn_blocking(move || {
+      render_directory_tree(&full_path, depth, LIST_DIRECTORY_MAX_ENTRIES)
+    })
+    .await
+    .map_err(|e| format!("列出目录失败: {}", e))?;
+
+    Ok(ToolResult {
+      success: true,
+      message: Some(format!(
+        "列出 {} 个条目{}",
+        listing.entries,
+        if listing.truncated {
+          "（已截断）"
+        } else {
+          ""
+        }
+      )),
+      data: Some(serde_json::json!({
+          "path": dir_path,
+          "depth": depth,
+          "tree": listing.tree,
+          "entries": listing.entries,
+          "truncated": listing.truncated,
+      })),
+      ..Default::default()
+    })
+  }
+
+  /// 搜索工作区内容：优先使用全文索引，索引缺失/无结果/正则查询时回退到文本扫描
+  async fn search_workspace(
+    &self,
+    tool_call: &ToolCall,
+    workspace_path: &Path,
+  ) -> Result<ToolResult, String> {
+    let args = &tool_call.arguments;
+    let query = args
+      .get("query")
+      .and_then(|v| v.as_str())
+      .map(str::trim)
+      .filter(|q| !q.is_empty())
+      .ok_or_else(|| "缺少 query 参数".to_string())?
+      .to_string();
+    let mode = args
+      .get("mode")
+      .and_then(|v| v.as_str())
+      .unwrap_or("auto")
+      .to_string();
+    let regex = args.get("regex").and_then(|v| v.as_bool()).unwrap_or(false);
+    let case_sensitive = args
+      .get("case_sensitive")
+      .and_then(|v| v.as_bool())
+      .unwrap_or(false);
+    let max_results = args
+      .get("max_results")
+      .and_then(|v| v.as_u64())
+      .map(|n| (n as usize).clamp(1, SEARCH_WORKSPACE_MAX_RESULTS))
+      .unwrap_or(SEARCH_WORKSPACE_DEFAULT_RESULTS);
+    let sub_path = args
+      .get("path")
+      .and_then(|v| v.as_str())
+      .filter(|p| !p.is_empty() && *p != ".");
+
+    let search_root = match sub_path {
+      Some(p) => {
+        let full = self.resolve_relative_path(workspace_path, p)?;
+        if !full.is_dir() {
+          return Ok(ToolResult {
+            success: false,
+            error: Some(format!("目录不存在: {}", p)),
+            error_kind: Some(ToolErrorKind::Skippable),
+            ..Default::default()
+          });
+        }
+        self.validate_existing_path(&full, workspace_path)?
+      }
+      None => workspace_path.to_path_buf(),
+    };
+
+    let use_index = match mode.as_str() {
+      "index" => true,
+      "text" => false,
+      _ => !regex && workspace_path.join(".binder").join("search.db").exists(),
+    };
+
+    let workspace = workspace_path.to_path_buf();
+    let outcome = tokio::task::spawn_blocking(move || -> Result<serde_json::Value, String> {
+      let fts_query = natural_language_query(&query);
+      if use_index && !fts_query.is_empty() {
+        let prefix = sub_path_prefix(&search_root, &workspace);
+        let service =
+          SearchService::new(&workspace).map_err(|e| format!("打开搜索索引失败: {}", e))?;
+        let results: Vec<serde_json::Value> = service
+          .search(&fts_query, max_results * 2)
+          .map_err(|e| format!("索引检索失败: {}", e))?
+          .into_iter()
+          .filter(|r| {
+            prefix
+              .as_deref()
+              .map_or(true, |p| r.path.replace('\\', "/").starts_with(p))
+          })
+          .take(max_results)
+          .map(|r| {
+            serde_json::json!({
+                "path": r.path,
+                "title": r.title,
+                "snippet": r.snippet.replace("<mark>", "").replace("</mark>", ""),
+            })
+          })
+          .collect();
+        if !results.is_empty() || mode == "index" {
+          return Ok(serde_json::json!({
+              "query": query,
+              "mode": "index",
+              "results": results,
+              "truncated": false,
+          }));
+        }
+      }
+
+      let exclude = build_glob_set(
+        &WorkspaceSettings::load_or_default(&workspace)
+          .search
+          .exclude_patterns,
+      )
+      .ok();
+      let options = InFileSearchOptions {
+        regex,
+        case_sensitive,
+        max_results,
+        ..InFileSearchOptions::default()
+      };
+      let scan = InFileSearchService::search_directory(
+        &workspace,
+        &search_root,
+        &query,
+        &options,
+        exclude.as_ref(),
+      )?;
+      Ok(serde_json::json!({
+          "query": query,
+          "mode": "text",
+          "results": scan.matches,
+          "files_scanned": scan.files_scanned,
+          "truncated": scan.truncated,
+      }))
+    })
+    .await
+    .map_err(|e| format!("搜索任务失败: {}", e))?;
+
+    match outcome {
+      Ok(data) => {
+        let count = data["results"].as_array().map(|r| r.len()).unwrap_or(0);
+        Ok(ToolResult {
+          success: true,
+          message: Some(format!("找到 {} 条匹配", count)),
+          data: Some(data),
+          ..Default::default()
+        })
+      }
+      Err(e) => Ok(ToolResult {
+        success: false,
+        error: Some(e),
+        error_kind: Some(ToolErrorKind::Retryable),
+        ..Default::default()
+      }),
+    }
+  }
+
   /// 移动文件
   async fn move_file(
     &self,
@@ -1431,6 +3044,109 @@ impl ToolService {
     }
   }
 
+  /// 复制文件或文件夹到新位置（目标不能已存在）
+  async fn copy_file(
+    &self,
+    tool_call: &ToolCall,
+    workspace_path: &Path,
+  ) -> Result<ToolResult, String> {
+    let source_path = tool_call
+      .arguments
+      .get("source")
+      .and_then(|v| v.as_str())
+      .ok_or_else(|| "缺少 source 参数".to_string())?;
+
+    let dest_path = tool_call
+      .arguments
+      .get("destination")
+      .and_then(|v| v.as_str())
+      .ok_or_else(|| "缺少 destination 参数".to_string())?;
+
+    let source_full = self.resolve_relative_path(workspace_path, source_path)?;
+    let dest_full = self.resolve_relative_path(workspace_path, dest_path)?;
+    self.validate_write_target(&dest_full, workspace_path)?;
+
+    if !source_full.exists() {
+      return Ok(ToolResult {
+        success: false,
+        error: Some(format!("源文件不存在: {}", source_path)),
+        ..Default::default()
+      });
+    }
+    let source_full = self.validate_existing_path(&source_full, workspace_path)?;
+
+    if dest_full.exists() {
+      return Ok(ToolResult {
+        success: false,
+        error: Some(format!("目标文件已存在: {}", dest_path)),
+        ..Default::default()
+      });
+    }
+
+    if dest_full.starts_with(&source_full) {
+      return Ok(ToolResult {
+        success: false,
+        error: Some("不能将资源复制到其自身子路径内".to_string()),
+        ..Default::default()
+      });
+    }
+
+    if let Some(parent) = dest_full.parent() {
+      if let Err(e) = std::fs::create_dir_all(parent) {
+        return Ok(ToolResult {
+          success: false,
+          error: Some(format!("创建目标目录失败: {}", e)),
+          ..Default::default()
+        });
+      }
+    }
+
+    let (source, dest) = (source_full.clone(), dest_full.clone());
+    let copied = tokio::task::spawn_blocking(move || copy_path_recursively(&source, &dest))
+      .await
+      .map_err(|e| format!("复制文件失败: {}", e))?;
+    match copied {
+      Ok(files) => {
+        let db =
+          WorkspaceDb::new(workspace_path).map_err(|e| format!("WorkspaceDb 初始化失败: {}", e))?;
+        let _ = record_resource_structure_timeline_node(
+          &db,
+          workspace_path,
+          "copy_file",
+          &format!("AI 复制资源：{} -> {}", source_path, dest_path),
+          "ai",
+          &[source_full.clone(), dest_full.clone()],
+        )?;
+        if dest_full.is_file() {
+          refresh_index_after_save(workspace_path, &dest_full, None);
+        }
+        Ok(ToolResult {
+          success: true,
+          data: Some(serde_json::json!({
+              "source": source_path,
+              "destination": dest_path,
+              "files_copied": files,
+          })),
+          message: Some(format!("成功复制: {} -> {}", source_path, dest_path)),
+          ..Default::default()
+        })
+      }
+      Err(e) => {
+        // 复制中途失败时清理已写入的部分，避免留下残缺副本
+        if dest_full.is_dir() {
+          let _ = std::fs::remove_dir_all(&dest_full);
+        } else {
+          let _ = std::fs::remove_file(&dest_full);
+        }
+        Ok(ToolResult {
+          success: false,
+          error: Some(format!("复制文件失败: {}", e)),
+          ..Default::default()
+        })
+      }
+    }
+  }
+
   /// 重命名文件
   async fn rename_file(
     &self,
@@ -1545,26 +3261,26 @@ impl ToolService {
     tool_call: &ToolCall,
     workspace_path: &Path,
   ) -> Result<ToolResult, String> {
-    eprintln!(
-      "🔧 create_folder 调用参数: {}",
+    tracing::debug!(
+      "create_folder 调用参数: {}",
       serde_json::to_string(&tool_call.arguments).unwrap_or_default()
     );
-    eprintln!("🔧 工作区路径: {:?}", workspace_path);
+    tracing::debug!("工作区路径: {:?}", workspace_path);
 
     let folder_path = tool_call
       .arguments
       .get("path")
       .and_then(|v| v.as_str())
       .ok_or_else(|| {
-        eprintln!(
-          "❌ create_folder 缺少 path 参数，arguments: {:?}",
+        tracing::error!(
+          "create_folder 缺少 path 参数，arguments: {:?}",
           tool_call.arguments
         );
         "缺少 path 参数".to_string()
       })?;
 
     let full_path = self.resolve_relative_path(workspace_path, folder_path)?;
-    eprintln!("🔧 完整路径: {:?}", full_path);
+    tracing::debug!("完整路径: {:?}", full_path);
 
     self.validate_write_target(&full_path, workspace_path)?;
 
@@ -1573,7 +3289,7 @@ impl ToolService {
     // 检查文件夹是否已存在
     if full_path.exists() {
       if full_path.is_dir() {
-        eprintln!("✅ 文件夹已存在: {:?}", full_path);
+        tracing::info!("文件夹已存在: {:?}", full_path);
         return Ok(ToolResult {
           success: true,
           data: Some(serde_json::json!({
@@ -1588,7 +3304,7 @@ impl ToolService {
           meta: None,
         });
       } else {
-        eprintln!("❌ 路径已存在但不是文件夹: {:?}", full_path);
+        tracing::error!("路径已存在但不是文件夹: {:?}", full_path);
         return Ok(ToolResult {
           success: false,
           data: None,
@@ -1602,10 +3318,10 @@ impl ToolService {
     }
 
     // 创建文件夹
-    eprintln!("🚀 开始创建文件夹: {:?}", full_path);
+    tracing::info!("开始创建文件夹: {:?}", full_path);
     match std::fs::create_dir_all(&full_path) {
       Ok(_) => {
-        eprintln!("✅ 文件夹创建成功: {:?}", full_path);
+        tracing::info!("文件夹创建成功: {:?}", full_path);
         // 验证文件夹是否真的创建成功
         if full_path.exists() && full_path.is_dir() {
           if !existed_before {
@@ -1633,7 +3349,7 @@ impl ToolService {
             meta: None,
           })
         } else {
-          eprintln!("⚠️ 文件夹创建后验证失败: {:?}", full_path);
+          tracing::warn!("文件夹创建后验证失败: {:?}", full_path);
           Ok(ToolResult {
             success: false,
             data: None,
@@ -1646,7 +3362,7 @@ impl ToolService {
         }
       }
       Err(e) => {
-        eprintln!("❌ 创建文件夹失败: {:?} - {}", full_path, e);
+        tracing::error!("创建文件夹失败: {:?} - {}", full_path, e);
         Ok(ToolResult {
           success: false,
           data: None,
@@ -1801,7 +3517,7 @@ impl ToolService {
             )
           })
           .unwrap_or_else(|| "none".to_string());
-        eprintln!(
+        tracing::debug!(
           "[positioning][BlockTree] baseline_id={:?} cache_hit={} nodes={} content_hash={}.. first_node={}",
           acquired.index.baseline_id,
           acquired.cache_hit,
@@ -1829,13 +3545,16 @@ impl ToolService {
       }
       Err(err) => {
         let code = err.error_code().to_string();
-        eprintln!(
+        tracing::debug!(
           "[positioning][BlockTree] {} code={} -> linear fallback",
-          err, code
+          err,
+          code
         );
         let fallback = Self::extract_block_map(html);
         if fallback.is_empty() {
-          eprintln!("[positioning][BlockTree] linear fallback failed: no [data-block-id] nodes");
+          tracing::warn!(
+            "[positioning][BlockTree] linear fallback failed: no [data-block-id] nodes"
+          );
         }
         (fallback, vec![code])
       }
@@ -2085,7 +3804,7 @@ impl ToolService {
     if let Some(ref sbid) = input.selection_start_block_id {
       if is_block_scoped_mode {
         // 明确跳过 selection 路径，fall-through 到 Step 2d/2e。
-        eprintln!(
+        tracing::debug!(
           "[positioning] SELECTION_GUARD: mode={} has _sel_* fields but is block-scoped; skipping selection route",
           mode
         );
@@ -2365,11 +4084,11 @@ impl ToolService {
           "未命中目标文本，且不满足块级降级白名单，AI 正在重试精确定位",
         ));
       }
-      eprintln!(
-                "📝 [Resolver] target 未在 block {} 中命中，命中严格降级白名单 {:?}，降级为 block_level 替换",
-                block_index,
-                whitelist_hit.map(|c| c.as_str())
-            );
+      tracing::debug!(
+        "[Resolver] target 未在 block {} 中命中，命中严格降级白名单 {:?}，降级为 block_level 替换",
+        block_index,
+        whitelist_hit.map(|c| c.as_str())
+      );
       let new_text = input.content.clone().unwrap_or_default();
       return Ok(CanonicalDiffBuilt {
         start_block_id: block.block_id.clone(),
@@ -2542,9 +4261,9 @@ impl ToolService {
   /// 编辑当前编辑器打开的文档
   /// 新实现：获取当前编辑器内容，计算 diff，返回完整的编辑信息
   async fn edit_current_editor_document(&self, tool_call: &ToolCall) -> Result<ToolResult, String> {
-    eprintln!("📝 [edit_current_editor_document] 开始处理文档编辑请求");
-    eprintln!(
-      "📝 [edit_current_editor_document] 工具调用参数: {:?}",
+    tracing::debug!("[edit_current_editor_document] 开始处理文档编辑请求");
+    tracing::debug!(
+      "[edit_current_editor_document] 工具调用参数: {:?}",
       tool_call.arguments
     );
     // 前置参数校验（冻结协议）
@@ -2776,7 +4495,7 @@ impl ToolService {
             "execution_anchor": execution_anchor,
           });
           let diff_area_id = format!("diff_area_{}", uuid::Uuid::new_v4());
-          eprintln!(
+          tracing::warn!(
             "[positioning] path=Resolver2 file={} diff_type={} route_source={} edit_mode={} document_revision={:?} resolver_error_codes={:?}",
             current_file_new, diff_type, route_source, edit_mode, doc_rev, resolver_error_codes
           );
@@ -2790,7 +4509,7 @@ impl ToolService {
             } else {
               "reference"
             };
-            eprintln!(
+            tracing::debug!(
               "[CROSS_FILE_TRACE][TOOL] {{\"tool\":\"edit_current_editor_document\",\"target_file\":{:?},\"tool_call_id\":{:?},\"route_source\":{:?},\"resolved_target_type\":{:?},\"original_text_len\":{},\"original_text_preview\":{:?},\"new_text_len\":{},\"new_text_preview\":{:?}}}",
               current_file_new,
               tool_call.id,
@@ -2929,7 +4648,379 @@ impl ToolService {
 
 #[cfg(test)]
 mod tests {
-  use super::{ResolverInput, ToolService};
+  use super::{
+    copy_path_recursively, normalize_extracted_text, render_directory_tree, run_command_rejection,
+    slice_chars, truncate_tool_output, ResolverInput, ToolCall, ToolLimits, ToolService,
+  };
+  use crate::services::tool_definitions::get_tool_definitions;
+  use crate::services::workspace_settings::{AgentToolSettings, ToolLimitOverride};
+
+  #[test]
+  fn oversized_tool_output_is_truncated_with_marker() {
+    let mut data = serde_json::json!({
+      "path": "big.md",
+      "content": "字".repeat(4000),
+      "matches": (0..200).map(|i| format!("line {}", i)).collect::<Vec<_>>(),
+    });
+    let (original, returned) = truncate_tool_output(&mut data, 4096).unwrap();
+    assert!(original > 4096 && returned <= 4096);
+    assert!(data["content"].as_str().unwrap().contains("内容已截断"));
+    assert_eq!(data["path"], "big.md");
+    assert_eq!(data["matches"].as_array().unwrap().len(), 200);
+
+    let mut small = serde_json::json!({ "content": "ok" });
+    assert!(truncate_tool_output(&mut small, 4096).is_none());
+  }
+
+  #[test]
+  fn tool_limits_apply_overrides_and_cover_command_timeout() {
+    let mut settings = AgentToolSettings::default();
+    settings.tool_limits.insert(
+      "search_workspace".to_string(),
+      ToolLimitOverride {
+        timeout_secs: Some(0),
+        max_output_bytes: Some(2048),
+      },
+    );
+    let search = ToolLimits::resolve("search_workspace", &settings);
+    assert_eq!(search.timeout, None);
+    assert_eq!(search.max_output_bytes, 2048);
+
+    settings.tool_timeout_secs = 5;
+    settings.command_timeout_secs = 60;
+    let command = ToolLimits::resolve("run_command", &settings);
+    assert_eq!(command.timeout.unwrap().as_secs(), 70);
+  }
+
+  #[test]
+  fn run_command_requires_opt_in_and_rejects_denied_commands() {
+    assert!(run_command_rejection("ls", false).is_some());
+
+    assert!(run_command_rejection("ls -la", true).is_none());
+    let rejected = run_command_rejection("rm -rf docs", true).unwrap();
+    assert!(!rejected.success);
+    assert!(rejected.error.unwrap().contains("rm -rf docs"));
+  }
+
+  #[cfg(not(target_os = "windows"))]
+  #[tokio::test]
+  async fn shell_custom_tools_need_opt_in_and_confirmation() {
+    let root = std::env::temp_dir().join(format!("binder-custom-tool-{}", uuid::Uuid::new_v4()));
+    let tools_dir = root.join(".binder").join("tools");
+    std::fs::create_dir_all(&tools_dir).unwrap();
+    std::fs::write(
+      tools_dir.join("echo_arg.toml"),
+      r#"
+name = "echo_arg"
+description = "Echoes its argument"
+confirm = false
+
+[parameters]
+type = "object"
+required = ["text"]
+
+[parameters.properties.text]
+type = "string"
+
+[action]
+type = "shell"
+command = "printf %s {{text}}"
+"#,
+    )
+    .unwrap();
+    let service = ToolService::new();
+    let call = ToolCall {
+      id: "call-echo".to_string(),
+      name: "echo_arg".to_string(),
+      arguments: serde_json::json!({ "text": "a; echo injected $(id)" }),
+    };
+
+    // 未开启命令执行时既不暴露也不执行
+    assert!(!get_tool_definitions(&root)
+      .iter()
+      .any(|d| d.name == "echo_arg"));
+    assert!(service
+      .execute_tool(&call, &root)
+      .await
+      .unwrap_err()
+      .contains("未知的工具"));
+
+    // 工作区自带的设置不能开启命令执行
+    std::fs::write(
+      root.join(".binder").join("settings.json"),
+      r#"{"agent": {"allow_shell_commands": true}}"#,
+    )
+    .unwrap();
+    assert!(!get_tool_definitions(&root)
+      .iter()
+      .any(|d| d.name == "echo_arg"));
+
+    crate::services::settings_service::update_for_tests(|s| {
+      s.shell_command_workspaces.push(root.clone())
+    });
+    assert!(get_tool_definitions(&root)
+      .iter()
+      .any(|d| d.name == "echo_arg"));
+
+    // 声明了 confirm = false 仍需确认
+    let pending = service.execute_tool(&call, &root).await.unwrap();
+    assert_eq!(pending.data.unwrap()["awaiting_confirmation"], true);
+    let record_id = pending
+      .meta
+      .unwrap()
+      .confirmation
+      .unwrap()
+      .record_id
+      .unwrap();
+
+    let result = service
+      .resolve_confirmation(&call, &root, &record_id, true)
+      .await
+      .unwrap();
+    assert!(result.success);
+    let output = result.data.unwrap();
+    assert_eq!(output["stdout"], "a; echo injected $(id)");
+    assert_eq!(output["command"], "printf %s \"$BINDER_ARG_text\"");
+
+    let _ = std::fs::remove_dir_all(&root);
+  }
+
+  #[tokio::test]
+  async fn agent_writes_to_workspace_config_are_rejected() {
+    let root = std::env::temp_dir().join(format!("binder-config-write-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&root).unwrap();
+    let service = ToolService::new();
+    let create = |path: &str| ToolCall {
+      id: format!("call-{}", path),
+      name: "create_file".to_string(),
+      arguments: serde_json::json!({ "path": path, "content": "x" }),
+    };
+
+    for path in [".binder/tools/leak.toml", ".binder/settings.json"] {
+      let result = service.execute_tool(&create(path), &root).await;
+      assert!(!result.is_ok_and(|r| r.success), "{}", path);
+      assert!(!root.join(path).exists());
+    }
+    let result = service.execute_tool(&create("notes.txt"), &root).await;
+    assert!(result.unwrap().success);
+
+    let _ = std::fs::remove_dir_all(&root);
+  }
+
+  #[tokio::test]
+  async fn model_supplied_confirmation_is_ignored() {
+    let root = std::env::temp_dir().join(format!("binder-confirm-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&root).unwrap();
+    std::fs::write(root.join("keep.md"), "keep").unwrap();
+    let service = ToolService::new();
+    let call = ToolCall {
+      id: "call-delete".to_string(),
+      name: "delete_file".to_string(),
+      arguments: serde_json::json!({ "path": "keep.md" }),
+    };
+    let pending = service.execute_tool(&call, &root).await.unwrap();
+    let record_id = pending
+      .meta
+      .unwrap()
+      .confirmation
+      .unwrap()
+      .record_id
+      .unwrap();
+
+    // 模型在参数中自带确认字段（即使记录 ID 正确）也只会得到新的待确认结果
+    let mut forged = call.clone();
+    forged.arguments["_confirmation_action"] = serde_json::json!("confirm");
+    forged.arguments["_confirmation_id"] = serde_json::json!(record_id);
+    let result = service.execute_tool(&forged, &root).await.unwrap();
+    assert_eq!(result.data.unwrap()["awaiting_confirmation"], true);
+    let model_call = ToolCall::from_model(
+      forged.id.clone(),
+      forged.name.clone(),
+      forged.arguments.clone(),
+    );
+    assert!(model_call.arguments.get("_confirmation_action").is_none());
+    assert!(root.join("keep.md").exists());
+
+    // 记录只对签发时的调用有效，且只能使用一次
+    let other = ToolCall {
+      arguments: serde_json::json!({ "path": "other.md" }),
+      ..call.clone()
+    };
+    assert!(service
+      .resolve_confirmation(&other, &root, &record_id, true)
+      .await
+      .is_err());
+    assert!(service
+      .resolve_confirmation(&call, &root, &record_id, true)
+      .await
+      .is_err());
+    assert!(root.join("keep.md").exists());
+
+    let _ = std::fs::remove_dir_all(&root);
+  }
+
+  #[tokio::test]
+  async fn search_memories_tool_finds_seeded_workspace_memories() {
+    use crate::services::memory_service::{
+      MemoryItemInput, MemoryLayer, MemoryScopeType, MemoryService, MemorySourceKind,
+    };
+    use crate::workspace::workspace_db::WorkspaceDb;
+
+    let root = std::env::temp_dir().join(format!("binder-memory-tool-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&root).unwrap();
+    let _db = WorkspaceDb::new(&root).unwrap();
+    let memories = MemoryService::new(&root).unwrap();
+    for (name, content, tags) in [
+      (
+        "citation_style",
+        "Use APA citation style in reports.",
+        vec!["style"],
+      ),
+      (
+        "launch_date",
+        "The product launch is planned for June.",
+        vec!["fact"],
+      ),
+    ] {
+      memories
+        .upsert_workspace_long_term_memory(MemoryItemInput {
+          layer: MemoryLayer::WorkspaceLongTerm,
+          scope_type: MemoryScopeType::Workspace,
+          scope_id: root.to_string_lossy().to_string(),
+          entity_type: "preference".to_string(),
+          entity_name: name.to_string(),
+          content: content.to_string(),
+          summary: String::new(),
+          tags: tags.into_iter().map(str::to_string).collect(),
+          source_kind: MemorySourceKind::UserPreference,
+          source_ref: String::new(),
+          confidence: 0.9,
+        })
+        .await
+        .unwrap();
+    }
+
+    let call = ToolCall {
+      id: "call-memory".to_string(),
+      name: "search_memories".to_string(),
+      arguments: serde_json::json!({ "query": "citation style", "scope": "workspace" }),
+    };
+    let result = ToolService::new().execute_tool(&call, &root).await.unwrap();
+    assert!(result.success, "{:?}", result.error);
+    let data = result.data.unwrap();
+    let found = data["memories"].as_array().unwrap();
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0]["name"], "citation_style");
+    assert_eq!(found[0]["layer"], "workspace_long_term");
+
+    let mut tagged = call.clone();
+    tagged.arguments =
+      serde_json::json!({ "query": "launch", "scope": "workspace", "tags": ["style"] });
+    let result = ToolService::new()
+      .execute_tool(&tagged, &root)
+      .await
+      .unwrap();
+    assert!(result.data.unwrap()["memories"]
+      .as_array()
+      .unwrap()
+      .is_empty());
+
+    let _ = std::fs::remove_dir_all(&root);
+  }
+
+  #[tokio::test]
+  async fn create_docx_from_markdown_validates_target_and_template() {
+    use crate::services::pandoc_service::PandocService;
+
+    let root = std::env::temp_dir().join(format!("binder-docx-tool-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(root.join(".binder")).unwrap();
+    std::fs::write(root.join("exists.docx"), b"old").unwrap();
+    let call = |path: &str| ToolCall {
+      id: "call-docx".to_string(),
+      name: "create_docx_from_markdown".to_string(),
+      arguments: serde_json::json!({ "path": path, "markdown": "# Title\n\nBody" }),
+    };
+    let service = ToolService::new();
+
+    let wrong_ext = service
+      .execute_tool(&call("notes.md"), &root)
+      .await
+      .unwrap();
+    assert!(!wrong_ext.success);
+    assert!(matches!(
+      wrong_ext.error_kind,
+      Some(super::ToolErrorKind::Fatal)
+    ));
+    assert!(!root.join("notes.md").exists());
+
+    let existing = service
+      .execute_tool(&call("exists.docx"), &root)
+      .await
+      .unwrap();
+    assert!(!existing.success);
+    assert_eq!(std::fs::read(root.join("exists.docx")).unwrap(), b"old");
+
+    // 工作区模板优先于内置模板
+    let custom = root.join(".binder").join("reference.docx");
+    std::fs::write(&custom, b"template").unwrap();
+    assert_eq!(PandocService::workspace_reference_docx(&root), Some(custom));
+
+    let _ = std::fs::remove_dir_all(&root);
+  }
+
+  #[test]
+  fn directory_tree_respects_depth_and_entry_cap() {
+    let root = std::env::temp_dir().join(format!("binder-tree-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(root.join("docs/drafts")).unwrap();
+    std::fs::create_dir_all(root.join(".binder")).unwrap();
+    std::fs::write(root.join("README.md"), "x".repeat(2048)).unwrap();
+    std::fs::write(root.join("docs/guide.md"), "hello").unwrap();
+    std::fs::write(root.join("docs/drafts/a.md"), "").unwrap();
+
+    let listing = render_directory_tree(&root, 2, 100);
+    assert_eq!(
+      listing.tree,
+      "docs/\n  drafts/ (1 项)\n  guide.md  5 B\nREADME.md  2.0 KB\n"
+    );
+    assert_eq!(listing.entries, 4);
+    assert!(!listing.truncated);
+
+    let capped = render_directory_tree(&root, 5, 2);
+    assert_eq!(capped.entries, 2);
+    assert!(capped.truncated);
+
+    let _ = std::fs::remove_dir_all(&root);
+  }
+
+  #[test]
+  fn copy_path_recursively_copies_nested_folders() {
+    let root = std::env::temp_dir().join(format!("binder-copy-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(root.join("src/nested/empty")).unwrap();
+    std::fs::write(root.join("src/a.md"), "a").unwrap();
+    std::fs::write(root.join("src/nested/b.md"), "b").unwrap();
+
+    let copied = copy_path_recursively(&root.join("src"), &root.join("dst")).unwrap();
+    assert_eq!(copied, 2);
+    assert_eq!(
+      std::fs::read_to_string(root.join("dst/nested/b.md")).unwrap(),
+      "b"
+    );
+    assert!(root.join("dst/nested/empty").is_dir());
+    assert!(root.join("src/a.md").exists());
+
+    let _ = std::fs::remove_dir_all(&root);
+  }
+
+  #[test]
+  fn extracted_document_text_is_normalized_and_paged() {
+    let text = normalize_extracted_text("标题  \r\n\r\n\r\n\n第一段\r\n第二段\n\n\n");
+    assert_eq!(text, "标题\n\n第一段\n第二段");
+
+    let (head, total, has_more) = slice_chars(&text, 0, 4);
+    assert_eq!((head.as_str(), total, has_more), ("标题\n\n", 11, true));
+    let (tail, _, has_more) = slice_chars(&text, 8, 4);
+    assert_eq!((tail.as_str(), has_more), ("第二段", false));
+  }
 
   /// 构造一个最小 4-段 HTML 文档，带 data-block-id。
   fn make_html() -> String {
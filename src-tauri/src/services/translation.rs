@@ -0,0 +1,384 @@
+//! AI 翻译：长文档按段落切分后逐段翻译，并按用户维护的术语表约束译名。
+//!
+//! 术语表保存在工作区 `.binder/glossaries.json`，每张表包含若干「原文术语 → 译名」。
+//! 每段只把其中出现的术语放进提示词；译文缺少规定译名时带着缺失项重译一次，
+//! 仍缺少的作为术语冲突返回，由用户确认。
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// 每次请求最多发送的字符数
+pub const TRANSLATE_CHUNK_CHARS: usize = 2000;
+/// 单段提示词中最多列出的术语数
+const MAX_PROMPT_TERMS: usize = 80;
+
+/// 串行化 glossaries.json 的读改写
+static STORE_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GlossaryTerm {
+  pub source: String,
+  pub target: String,
+  #[serde(default)]
+  pub note: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Glossary {
+  /// 为空时保存时生成
+  #[serde(default)]
+  pub id: String,
+  pub name: String,
+  /// 适用的目标语言，为空时不限
+  #[serde(default)]
+  pub target_lang: Option<String>,
+  #[serde(default)]
+  pub terms: Vec<GlossaryTerm>,
+  #[serde(default)]
+  pub updated_at: i64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct GlossariesFile {
+  #[serde(default)]
+  glossaries: Vec<Glossary>,
+}
+
+/// 一段译文缺少的规定译名
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TermViolation {
+  /// 所在分段（从 0 开始）
+  pub chunk: usize,
+  pub source: String,
+  pub target: String,
+}
+
+/// 每段译完后推送给前端的进度
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranslationProgress {
+  pub job_id: String,
+  pub index: usize,
+  pub total: usize,
+  /// 该段译文（含原有的首尾空白）
+  pub text: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranslationResult {
+  pub text: String,
+  pub chunks: usize,
+  pub glossary_violations: Vec<TermViolation>,
+}
+
+fn glossaries_path(workspace_path: &Path) -> PathBuf {
+  workspace_path.join(".binder").join("glossaries.json")
+}
+
+fn load_file(workspace_path: &Path) -> Result<GlossariesFile, String> {
+  let path = glossaries_path(workspace_path);
+  if !path.exists() {
+    return Ok(GlossariesFile::default());
+  }
+  let content = fs::read_to_string(&path).map_err(|e| format!("读取术语表失败: {}", e))?;
+  serde_json::from_str(&content).map_err(|e| format!("解析术语表失败: {}", e))
+}
+
+fn save_file(workspace_path: &Path, file: &GlossariesFile) -> Result<(), String> {
+  let path = glossaries_path(workspace_path);
+  if let Some(parent) = path.parent() {
+    fs::create_dir_all(parent).map_err(|e| format!("创建 .binder 目录失败: {}", e))?;
+  }
+  let json = serde_json::to_string_pretty(file).map_err(|e| format!("序列化术语表失败: {}", e))?;
+  fs::write(&path, json).map_err(|e| format!("写入术语表失败: {}", e))
+}
+
+/// 列出工作区的术语表（按名称排序）
+pub fn list_glossaries(workspace_path: &Path) -> Result<Vec<Glossary>, String> {
+  let mut glossaries = load_file(workspace_path)?.glossaries;
+  glossaries.sort_by_key(|g| g.name.to_lowercase());
+  Ok(glossaries)
+}
+
+pub fn get_glossary(workspace_path: &Path, id: &str) -> Result<Option<Glossary>, String> {
+  Ok(
+    load_file(workspace_path)?
+      .glossaries
+      .into_iter()
+      .find(|g| g.id == id),
+  )
+}
+
+/// 新增或更新术语表；去掉空术语，同一原文术语只保留最后一条
+pub fn save_glossary(workspace_path: &Path, mut glossary: Glossary) -> Result<Glossary, String> {
+  glossary.name = glossary.name.trim().to_string();
+  if glossary.name.is_empty() {
+    return Err("术语表名称不能为空".to_string());
+  }
+  if glossary.id.trim().is_empty() {
+    glossary.id = uuid::Uuid::new_v4().to_string();
+  }
+  glossary.target_lang = glossary.target_lang.filter(|l| !l.trim().is_empty());
+  let mut terms: Vec<GlossaryTerm> = Vec::new();
+  for mut term in glossary.terms {
+    term.source = term.source.trim().to_string();
+    term.target = term.target.trim().to_string();
+    term.note = term.note.filter(|n| !n.trim().is_empty());
+    if term.source.is_empty() || term.target.is_empty() {
+      continue;
+    }
+    terms.retain(|t| !t.source.eq_ignore_ascii_case(&term.source));
+    terms.push(term);
+  }
+  glossary.terms = terms;
+  glossary.updated_at = chrono::Utc::now().timestamp_millis();
+
+  let _guard = STORE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+  let mut file = load_file(workspace_path)?;
+  file.glossaries.retain(|g| g.id != glossary.id);
+  file.glossaries.push(glossary.clone());
+  save_file(workspace_path, &file)?;
+  Ok(glossary)
+}
+
+/// 删除术语表，返回是否存在
+pub fn delete_glossary(workspace_path: &Path, id: &str) -> Result<bool, String> {
+  let _guard = STORE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+  let mut file = load_file(workspace_path)?;
+  let before = file.glossaries.len();
+  file.glossaries.retain(|g| g.id != id);
+  if file.glossaries.len() == before {
+    return Ok(false);
+  }
+  save_file(workspace_path, &file)?;
+  Ok(true)
+}
+
+/// 按空行切分段落，再合并为不超过 TRANSLATE_CHUNK_CHARS 的分段；单个段落超长时按行切分。
+/// 各段首尾相接即为原文
+pub fn split_chunks(content: &str) -> Vec<&str> {
+  let mut units: Vec<&str> = Vec::new();
+  let mut start = 0;
+  let mut pos = 0;
+  let mut previous_blank = false;
+  for line in content.split_inclusive('\n') {
+    let blank = line.trim().is_empty();
+    // 空行之后的第一行开始新段落
+    if previous_blank && !blank && pos > start {
+      units.push(&content[start..pos]);
+      start = pos;
+    }
+    pos += line.len();
+    previous_blank = blank;
+  }
+  if start < content.len() {
+    units.push(&content[start..]);
+  }
+
+  let mut chunks: Vec<&str> = Vec::new();
+  let (mut chunk_start, mut chunk_chars) = (0, 0);
+  let mut offset = 0;
+  for unit in units {
+    let pieces: Vec<&str> = if unit.chars().count() > TRANSLATE_CHUNK_CHARS {
+      unit.split_inclusive('\n').collect()
+    } else {
+      vec![unit]
+    };
+    for piece in pieces {
+      let piece_chars = piece.chars().count();
+      if chunk_chars > 0 && chunk_chars + piece_chars > TRANSLATE_CHUNK_CHARS {
+        chunks.push(&content[chunk_start..offset]);
+        chunk_start = offset;
+        chunk_chars = 0;
+      }
+      offset += piece.len();
+      chunk_chars += piece_chars;
+    }
+  }
+  if chunk_start < content.len() {
+    chunks.push(&content[chunk_start..]);
+  }
+  chunks
+}
+
+fn contains_term(text: &str, term: &str) -> bool {
+  if term.is_ascii() {
+    text
+      .to_ascii_lowercase()
+      .contains(&term.to_ascii_lowercase())
+  } else {
+    text.contains(term)
+  }
+}
+
+/// 在该段原文中出现的术语，长术语优先
+pub fn relevant_terms<'a>(terms: &'a [GlossaryTerm], text: &str) -> Vec<&'a GlossaryTerm> {
+  let mut found: Vec<&GlossaryTerm> = terms
+    .iter()
+    .filter(|t| contains_term(text, &t.source))
+    .collect();
+  found.sort_by_key(|t| std::cmp::Reverse(t.source.chars().count()));
+  found.truncate(MAX_PROMPT_TERMS);
+  found
+}
+
+fn glossary_lines(terms: &[&GlossaryTerm]) -> String {
+  terms
+    .iter()
+    .map(|t| match &t.note {
+      Some(note) => format!("- {} → {}（{}）\n", t.source, t.target, note),
+      None => format!("- {} → {}\n", t.source, t.target),
+    })
+    .collect()
+}
+
+pub fn build_prompt(text: &str, target_lang: &str, terms: &[&GlossaryTerm]) -> String {
+  let glossary = if terms.is_empty() {
+    String::new()
+  } else {
+    format!(
+      "必须使用以下术语译名，不得改用其他译法：\n{}\n",
+      glossary_lines(terms)
+    )
+  };
+  format!(
+    "请把下面的文本翻译为{}。\n要求：\n\
+     1. 保留原有的 Markdown / HTML 标记、链接地址、代码块与换行结构，只翻译文字\n\
+     2. 专有名词与术语前后一致\n\
+     3. 只输出译文，不要输出说明、引号或额外的标记\n\
+     {}\n文本：\n{}",
+    target_lang, glossary, text
+  )
+}
+
+/// 译文缺少规定译名时的重译提示
+pub fn build_correction_prompt(
+  text: &str,
+  translation: &str,
+  target_lang: &str,
+  missing: &[&GlossaryTerm],
+) -> String {
+  format!(
+    "下面的{}译文没有按术语表翻译这些术语：\n{}\n\
+     请修改译文，使用规定的译名，其余内容保持不变，只输出修改后的完整译文。\n\n\
+     原文：\n{}\n\n译文：\n{}",
+    target_lang,
+    glossary_lines(missing),
+    text,
+    translation
+  )
+}
+
+/// 译文中缺少的规定译名
+pub fn missing_terms<'a>(translation: &str, terms: &[&'a GlossaryTerm]) -> Vec<&'a GlossaryTerm> {
+  terms
+    .iter()
+    .copied()
+    .filter(|t| !contains_term(translation, &t.target))
+    .collect()
+}
+
+/// 去掉模型可能加上的代码围栏，并恢复原文的首尾空白
+pub fn finish_chunk(source: &str, translation: &str) -> String {
+  let mut text = translation.trim();
+  if !source.trim_start().starts_with("```") {
+    if let Some(inner) = text
+      .strip_prefix("```")
+      .and_then(|rest| rest.split_once('\n'))
+      .and_then(|(_, body)| body.trim_end().strip_suffix("```"))
+    {
+      text = inner.trim();
+    }
+  }
+  let leading = &source[..source.len() - source.trim_start().len()];
+  let trailing = &source[source.trim_end().len()..];
+  format!("{}{}{}", leading, text, trailing)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn term(source: &str, target: &str) -> GlossaryTerm {
+    GlossaryTerm {
+      source: source.to_string(),
+      target: target.to_string(),
+      note: None,
+    }
+  }
+
+  #[test]
+  fn splits_on_paragraphs_and_rejoins_exactly() {
+    let paragraph = "句子。".repeat(400);
+    let content = format!("# 标题\n\n{}\n\n{}\n\n\n结尾\n", paragraph, paragraph);
+    let chunks = split_chunks(&content);
+    assert_eq!(chunks.concat(), content);
+    assert_eq!(chunks.len(), 2);
+    assert!(chunks[0].starts_with("# 标题\n\n句子"));
+    assert!(chunks[1].ends_with("结尾\n"));
+    assert!(chunks
+      .iter()
+      .all(|c| c.chars().count() <= TRANSLATE_CHUNK_CHARS));
+    assert_eq!(split_chunks("短文"), vec!["短文"]);
+  }
+
+  #[test]
+  fn enforces_glossary_terms() {
+    let terms = vec![
+      term("workspace", "工作区"),
+      term("work", "工作"),
+      term("binder", "活页夹"),
+    ];
+    let relevant = relevant_terms(&terms, "Open the Workspace and start work.");
+    assert_eq!(
+      relevant
+        .iter()
+        .map(|t| t.target.as_str())
+        .collect::<Vec<_>>(),
+      vec!["工作区", "工作"]
+    );
+    let prompt = build_prompt("text", "中文", &relevant);
+    assert!(prompt.contains("- workspace → 工作区\n"));
+    assert_eq!(
+      missing_terms("打开空间并开始工作。", &relevant)
+        .iter()
+        .map(|t| t.source.as_str())
+        .collect::<Vec<_>>(),
+      vec!["workspace"]
+    );
+    assert_eq!(
+      finish_chunk("\n  Hello\n\n", "```text\n你好\n```"),
+      "\n  你好\n\n"
+    );
+  }
+
+  #[test]
+  fn saves_glossaries_with_deduplicated_terms() {
+    let dir = std::env::temp_dir().join(format!("binder_glossary_{}", uuid::Uuid::new_v4()));
+    let saved = save_glossary(
+      &dir,
+      Glossary {
+        id: String::new(),
+        name: " 产品术语 ".to_string(),
+        target_lang: Some(" ".to_string()),
+        terms: vec![
+          term("Binder", "Binder"),
+          term(" ", "x"),
+          term("binder", "活页夹"),
+        ],
+        updated_at: 0,
+      },
+    )
+    .unwrap();
+    assert!(!saved.id.is_empty());
+    assert_eq!(saved.name, "产品术语");
+    assert_eq!(saved.target_lang, None);
+    assert_eq!(saved.terms, vec![term("binder", "活页夹")]);
+    assert_eq!(list_glossaries(&dir).unwrap().len(), 1);
+    assert!(delete_glossary(&dir, &saved.id).unwrap());
+    assert!(get_glossary(&dir, &saved.id).unwrap().is_none());
+    let _ = fs::remove_dir_all(&dir);
+  }
+}
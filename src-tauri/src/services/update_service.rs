@@ -0,0 +1,227 @@
+//! 自动更新：按应用设置中的发布渠道（stable / beta）检查新版本，在后台下载，完成后由前端提示重启安装。
+//!
+//! 各渠道的 `latest.json` 发布在 GitHub Releases 的 `updater-<渠道>` 标签下，由发布流程生成并签名。
+//! 签名公钥配置在 tauri.conf.json 的 `plugins.updater.pubkey`，为空（如开发构建）时不检查更新；
+//! 发布构建还需开启 `bundle.createUpdaterArtifacts` 并提供 `TAURI_SIGNING_PRIVATE_KEY`。
+//!
+//! 事件：`update-available`（发现新版本）、`update-download-progress`、`update-ready`（可重启安装）、
+//! `update-download-failed`。
+
+use crate::services::settings_service::{self, UpdateChannel};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tauri_plugin_updater::{Update, UpdaterExt};
+
+const ENDPOINT_TEMPLATE: &str =
+  "https://github.com/chenadu5299/binder-project/releases/download/updater-{channel}/latest.json";
+const STARTUP_CHECK_DELAY: Duration = Duration::from_secs(15);
+const CHECK_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// 返回给前端的版本信息
+#[derive(Debug, Clone, Serialize)]
+pub struct UpdateInfo {
+  pub current_version: String,
+  pub version: String,
+  pub channel: UpdateChannel,
+  /// 发布说明（Markdown）
+  pub notes: Option<String>,
+  pub date: Option<String>,
+}
+
+impl UpdateInfo {
+  fn new(update: &Update, channel: UpdateChannel) -> Self {
+    Self {
+      current_version: update.current_version.clone(),
+      version: update.version.clone(),
+      channel,
+      notes: update.body.clone(),
+      date: update.date.map(|d| d.to_string()),
+    }
+  }
+}
+
+enum DownloadState {
+  Idle,
+  Downloading(String),
+  Ready { update: Box<Update>, bytes: Vec<u8> },
+}
+
+static DOWNLOAD: Lazy<Mutex<DownloadState>> = Lazy::new(|| Mutex::new(DownloadState::Idle));
+
+fn download_state() -> MutexGuard<'static, DownloadState> {
+  DOWNLOAD
+    .lock()
+    .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+pub fn endpoint(channel: UpdateChannel) -> String {
+  ENDPOINT_TEMPLATE.replace("{channel}", channel.as_str())
+}
+
+fn pubkey_configured(app: &AppHandle) -> bool {
+  app
+    .config()
+    .plugins
+    .0
+    .get("updater")
+    .and_then(|config| config.get("pubkey"))
+    .and_then(|pubkey| pubkey.as_str())
+    .is_some_and(|pubkey| !pubkey.trim().is_empty())
+}
+
+/// 检查指定渠道是否有新版本
+pub async fn check(app: &AppHandle, channel: UpdateChannel) -> Result<Option<Update>, String> {
+  if !pubkey_configured(app) {
+    return Err("当前构建未配置更新签名公钥，无法检查更新".to_string());
+  }
+  let url = endpoint(channel)
+    .parse()
+    .map_err(|e| format!("更新地址无效: {}", e))?;
+  let mut builder = app
+    .updater_builder()
+    .endpoints(vec![url])
+    .map_err(|e| format!("初始化更新器失败: {}", e))?
+    .timeout(CHECK_TIMEOUT);
+  if let Some(proxy) = settings_service::current()
+    .proxy
+    .and_then(|p| p.parse().ok())
+  {
+    builder = builder.proxy(proxy);
+  }
+  builder
+    .build()
+    .map_err(|e| format!("初始化更新器失败: {}", e))?
+    .check()
+    .await
+    .map_err(|e| format!("检查更新失败: {}", e))
+}
+
+/// 检查更新，有新版本时发送 `update-available` 并开始后台下载
+pub async fn check_and_download(
+  app: &AppHandle,
+  channel: UpdateChannel,
+) -> Result<Option<UpdateInfo>, String> {
+  let Some(update) = check(app, channel).await? else {
+    return Ok(None);
+  };
+  let info = UpdateInfo::new(&update, channel);
+  tracing::info!("发现新版本 {}（{}）", info.version, channel.as_str());
+  if let Err(e) = app.emit("update-available", &info) {
+    tracing::warn!("发送 update-available 事件失败: {}", e);
+  }
+  spawn_download(app.clone(), update, info.clone());
+  Ok(Some(info))
+}
+
+/// 后台下载更新；同一版本正在下载或已下载完成时不重复下载
+fn spawn_download(app: AppHandle, update: Update, info: UpdateInfo) {
+  {
+    let mut state = download_state();
+    let same_version = match &*state {
+      DownloadState::Downloading(version) => *version == update.version,
+      DownloadState::Ready { update: ready, .. } => ready.version == update.version,
+      DownloadState::Idle => false,
+    };
+    if same_version {
+      if matches!(&*state, DownloadState::Ready { .. }) {
+        let _ = app.emit("update-ready", &info);
+      }
+      return;
+    }
+    *state = DownloadState::Downloading(update.version.clone());
+  }
+
+  tauri::async_runtime::spawn(async move {
+    let mut downloaded: u64 = 0;
+    let mut last_percent = None;
+    let result = update
+      .download(
+        |chunk, total| {
+          downloaded += chunk as u64;
+          // 总大小已知时按百分比节流
+          let percent = total.map(|total| downloaded * 100 / total.max(1));
+          if percent.is_some() && percent == last_percent {
+            return;
+          }
+          last_percent = percent;
+          let _ = app.emit(
+            "update-download-progress",
+            serde_json::json!({
+              "version": info.version,
+              "downloaded": downloaded,
+              "total": total,
+            }),
+          );
+        },
+        || {},
+      )
+      .await;
+
+    match result {
+      Ok(bytes) => {
+        tracing::info!("新版本 {} 下载完成", info.version);
+        *download_state() = DownloadState::Ready {
+          update: Box::new(update),
+          bytes,
+        };
+        if let Err(e) = app.emit("update-ready", &info) {
+          tracing::warn!("发送 update-ready 事件失败: {}", e);
+        }
+      }
+      Err(e) => {
+        tracing::warn!("下载新版本 {} 失败: {}", info.version, e);
+        *download_state() = DownloadState::Idle;
+        let _ = app.emit(
+          "update-download-failed",
+          serde_json::json!({ "version": info.version, "error": e.to_string() }),
+        );
+      }
+    }
+  });
+}
+
+/// 安装已下载的更新并重启应用
+pub fn install_and_restart(app: &AppHandle) -> Result<(), String> {
+  let state = std::mem::replace(&mut *download_state(), DownloadState::Idle);
+  let DownloadState::Ready { update, bytes } = state else {
+    *download_state() = state;
+    return Err("没有已下载完成的更新".to_string());
+  };
+  tracing::info!("安装新版本 {} 并重启", update.version);
+  update
+    .install(&bytes)
+    .map_err(|e| format!("安装更新失败: {}", e))?;
+  app.restart()
+}
+
+/// 启动后延迟检查一次当前渠道的更新（开发构建或未配置公钥时跳过）
+pub fn spawn_startup_check(app: AppHandle) {
+  if cfg!(debug_assertions) || !pubkey_configured(&app) {
+    tracing::debug!("跳过启动时的更新检查");
+    return;
+  }
+  tauri::async_runtime::spawn(async move {
+    tokio::time::sleep(STARTUP_CHECK_DELAY).await;
+    let channel = settings_service::current().update_channel;
+    if let Err(e) = check_and_download(&app, channel).await {
+      tracing::warn!("启动时检查更新失败: {}", e);
+    }
+  });
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn endpoint_depends_on_channel() {
+    assert!(endpoint(UpdateChannel::Stable).ends_with("/updater-stable/latest.json"));
+    assert!(endpoint(UpdateChannel::Beta).ends_with("/updater-beta/latest.json"));
+    assert!(endpoint(UpdateChannel::Beta)
+      .parse::<reqwest::Url>()
+      .is_ok());
+  }
+}
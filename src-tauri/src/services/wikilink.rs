@@ -0,0 +1,299 @@
+//! Wiki 链接：`[[笔记]]`、`[[笔记|显示文字]]`、`[[笔记#小节]]`、`[[文件夹/笔记]]`。
+//!
+//! 笔记名按文件名（不含扩展名）、front matter 中的 `title` 与 `aliases` 匹配，不区分大小写；
+//! 链接指向的笔记不存在时按工作区设置 `notes` 的文件夹与模板新建。
+
+use crate::services::file_templates::{FileTemplate, TemplateContext};
+use crate::services::image_references::has_extension;
+use crate::services::link_graph::{self, LINK_SOURCE_EXTENSIONS};
+use crate::services::workspace_settings::{NoteSettings, WorkspaceSettings};
+use crate::utils::path_validator::PathValidator;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+/// 未配置模板（或模板读取失败）时新笔记的内容
+pub const DEFAULT_NOTE_TEMPLATE: &str = "# {{title}}\n\n";
+
+/// 文件名中不允许的字符，新建笔记时替换为 `-`
+const INVALID_NAME_CHARS: [char; 8] = ['\\', ':', '*', '?', '"', '<', '>', '|'];
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WikiLink {
+  /// 笔记名或相对路径（`文件夹/笔记`）
+  pub target: String,
+  /// `#` 之后的小节标题
+  pub heading: Option<String>,
+  /// `|` 之后的显示文字
+  pub display: Option<String>,
+}
+
+impl WikiLink {
+  /// 解析 `[[...]]` 或其中的文本；笔记名为空时返回 None
+  pub fn parse(text: &str) -> Option<Self> {
+    let text = text.trim();
+    let inner = text
+      .strip_prefix("[[")
+      .and_then(|s| s.strip_suffix("]]"))
+      .unwrap_or(text);
+    let non_empty = |s: &str| Some(s.trim().to_string()).filter(|s| !s.is_empty());
+    let (link, display) = match inner.split_once('|') {
+      Some((link, display)) => (link, non_empty(display)),
+      None => (inner, None),
+    };
+    let (target, heading) = match link.split_once('#') {
+      Some((target, heading)) => (target, non_empty(heading)),
+      None => (link, None),
+    };
+    Some(WikiLink {
+      target: non_empty(target)?,
+      heading,
+      display,
+    })
+  }
+}
+
+/// 笔记名的比较键：去掉 Markdown 扩展名，统一小写并合并连续空白
+pub fn note_key(name: &str) -> String {
+  let name = name.trim();
+  let lower = name.to_lowercase();
+  let stem = [".md", ".markdown"]
+    .iter()
+    .find_map(|ext| lower.strip_suffix(ext))
+    .unwrap_or(&lower);
+  stem.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// 文档可被 Wiki 链接匹配的名字（比较键）：文件名与 front matter 中的 title / aliases
+pub fn note_keys(relative_path: &str, content: &str) -> Vec<String> {
+  let file_name = relative_path.rsplit('/').next().unwrap_or(relative_path);
+  let mut keys = vec![note_key(file_name)];
+  for name in front_matter_names(content) {
+    let key = note_key(&name);
+    if !key.is_empty() && !keys.contains(&key) {
+      keys.push(key);
+    }
+  }
+  keys
+}
+
+/// 读取 YAML front matter 中的 `title`、`aliases` / `alias`（支持 `[a, b]`、单值与 `- a` 列表）
+fn front_matter_names(content: &str) -> Vec<String> {
+  let mut lines = content.lines();
+  if lines.next().map(str::trim_end) != Some("---") {
+    return Vec::new();
+  }
+  let unquote = |s: &str| s.trim().trim_matches(|c| c == '"' || c == '\'').to_string();
+  let mut names = Vec::new();
+  let mut in_aliases = false;
+  for line in lines {
+    let trimmed = line.trim();
+    if trimmed == "---" || trimmed == "..." {
+      break;
+    }
+    if in_aliases {
+      if let Some(item) = trimmed.strip_prefix("- ") {
+        names.push(unquote(item));
+        continue;
+      }
+      in_aliases = false;
+    }
+    let Some((key, value)) = trimmed.split_once(':') else {
+      continue;
+    };
+    let value = value.trim();
+    match key.trim() {
+      "title" => names.push(unquote(value)),
+      "aliases" | "alias" if value.is_empty() => in_aliases = true,
+      "aliases" | "alias" => {
+        let list = value
+          .strip_prefix('[')
+          .and_then(|v| v.strip_suffix(']'))
+          .unwrap_or(value);
+        names.extend(list.split(',').map(unquote));
+      }
+      _ => {}
+    }
+  }
+  names.retain(|name| !name.is_empty());
+  names
+}
+
+/// Wiki 链接的解析结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WikiLinkResolution {
+  pub path: String,
+  /// 相对工作区根目录（`/` 分隔）
+  pub relative_path: String,
+  pub heading: Option<String>,
+  /// 本次新建了该笔记
+  pub created: bool,
+  /// 同名笔记有多个时，未选中的其余路径
+  pub alternatives: Vec<String>,
+}
+
+/// 把 Wiki 链接解析为笔记路径；笔记不存在时 create 为 true 则按模板新建，否则返回 None
+pub fn resolve_wikilink(
+  workspace_path: &Path,
+  text: &str,
+  create: bool,
+) -> Result<Option<WikiLinkResolution>, String> {
+  let link = WikiLink::parse(text).ok_or_else(|| format!("无效的 Wiki 链接: {}", text))?;
+  let mut matches = link_graph::find_notes(workspace_path, &link.target);
+  let (relative_path, created) = if matches.is_empty() {
+    if !create {
+      return Ok(None);
+    }
+    (create_note(workspace_path, &link.target)?, true)
+  } else {
+    (matches.remove(0), false)
+  };
+  Ok(Some(WikiLinkResolution {
+    path: workspace_path
+      .join(&relative_path)
+      .to_string_lossy()
+      .to_string(),
+    relative_path,
+    heading: link.heading,
+    created,
+    alternatives: matches,
+  }))
+}
+
+/// 新笔记的相对路径：`文件夹/笔记` 形式按原路径，否则放在设置的新笔记文件夹下
+fn new_note_path(settings: &NoteSettings, target: &str) -> String {
+  let mut segments: Vec<String> = if target.contains('/') {
+    Vec::new()
+  } else {
+    settings
+      .new_note_folder
+      .split(['/', '\\'])
+      .filter(|s| !s.trim().is_empty())
+      .map(str::to_string)
+      .collect()
+  };
+  segments.extend(
+    target
+      .split('/')
+      .map(|s| s.trim().replace(INVALID_NAME_CHARS, "-"))
+      .filter(|s| !s.is_empty() && s != "." && s != ".."),
+  );
+  let mut relative = segments.join("/");
+  if !has_extension(Path::new(&relative), &LINK_SOURCE_EXTENSIONS) {
+    relative.push_str(".md");
+  }
+  relative
+}
+
+/// 用模板内容新建笔记（`{{title}}` 为笔记名，需填写的变量取默认值），返回相对路径；
+/// 文件已存在时不覆盖
+fn create_note(workspace_path: &Path, target: &str) -> Result<String, String> {
+  let settings = WorkspaceSettings::load_or_default(workspace_path).notes;
+  let relative = new_note_path(&settings, target);
+  let path = PathValidator::resolve_workspace_relative_path(workspace_path, &relative)
+    .map_err(|e| format!("笔记路径非法: {}", e))?;
+  if let Some(parent) = path.parent() {
+    fs::create_dir_all(parent).map_err(|e| format!("创建笔记文件夹失败: {}", e))?;
+  }
+
+  let template = settings
+    .new_note_template
+    .as_deref()
+    .filter(|t| !t.trim().is_empty())
+    .and_then(|t| {
+      fs::read_to_string(workspace_path.join(t))
+        .map_err(|e| tracing::warn!("读取笔记模板失败 {}: {}", t, e))
+        .ok()
+    })
+    .unwrap_or_else(|| DEFAULT_NOTE_TEMPLATE.to_string());
+  let context = TemplateContext {
+    title: target
+      .rsplit('/')
+      .next()
+      .unwrap_or(target)
+      .trim()
+      .to_string(),
+    ..TemplateContext::new(workspace_path, &path)
+  };
+  let content = FileTemplate::parse(&template).render(&context, &HashMap::new());
+
+  match fs::OpenOptions::new()
+    .write(true)
+    .create_new(true)
+    .open(&path)
+  {
+    Ok(mut file) => file
+      .write_all(content.as_bytes())
+      .map_err(|e| format!("写入笔记失败: {}", e))?,
+    Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {}
+    Err(e) => return Err(format!("创建笔记失败: {}", e)),
+  }
+  link_graph::refresh_file(workspace_path, &workspace_path.join(&relative));
+  Ok(relative)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn parses_wiki_link_parts() {
+    assert_eq!(
+      WikiLink::parse("[[项目/周报 #本周进展| 周报 ]]"),
+      Some(WikiLink {
+        target: "项目/周报".to_string(),
+        heading: Some("本周进展".to_string()),
+        display: Some("周报".to_string()),
+      })
+    );
+    assert_eq!(WikiLink::parse("Note").unwrap().heading, None);
+    assert_eq!(WikiLink::parse("[[#只有小节]]"), None);
+    assert_eq!(note_key("  Meeting   Notes.MD "), "meeting notes");
+  }
+
+  #[test]
+  fn collects_names_from_file_name_and_front_matter() {
+    let content = "---\ntitle: \"Project Plan\"\naliases: [计划, 'plan v2']\nalias:\n  - 路线图\ntags: [a]\n---\n# 正文\n";
+    assert_eq!(
+      note_keys("docs/plan.md", content),
+      vec!["plan", "project plan", "计划", "plan v2", "路线图"]
+    );
+    assert_eq!(note_keys("a.md", "# 没有 front matter"), vec!["a"]);
+  }
+
+  #[test]
+  fn creates_missing_notes_from_template() {
+    let workspace = std::env::temp_dir().join(format!("binder_wiki_{}", uuid::Uuid::new_v4()));
+    fs::create_dir_all(workspace.join(".binder")).unwrap();
+    fs::write(workspace.join("tpl.md"), "---\ntitle: {{title}}\n---\n").unwrap();
+    fs::write(
+      workspace.join(".binder/settings.json"),
+      r#"{"notes": {"new_note_folder": "notes", "new_note_template": "tpl.md"}}"#,
+    )
+    .unwrap();
+
+    assert!(resolve_wikilink(&workspace, "[[新想法]]", false)
+      .unwrap()
+      .is_none());
+    let created = resolve_wikilink(&workspace, "[[新想法#细节]]", true)
+      .unwrap()
+      .unwrap();
+    assert!(created.created);
+    assert_eq!(created.relative_path, "notes/新想法.md");
+    assert_eq!(created.heading.as_deref(), Some("细节"));
+    assert_eq!(
+      fs::read_to_string(&created.path).unwrap(),
+      "---\ntitle: 新想法\n---\n"
+    );
+
+    // 再次解析时找到已建的笔记，不重复创建
+    let found = resolve_wikilink(&workspace, "新想法", true)
+      .unwrap()
+      .unwrap();
+    assert!(!found.created);
+    assert_eq!(found.relative_path, "notes/新想法.md");
+    let _ = fs::remove_dir_all(&workspace);
+  }
+}
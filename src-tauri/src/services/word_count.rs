@@ -0,0 +1,385 @@
+//! 字数统计：中日韩文字每字计一词，其余文字按连续的字母数字计词，并按标题分节统计。
+//!
+//! Markdown 经 pulldown-cmark 解析（不计标记符号、链接地址与图片），HTML 取正文文本，
+//! Word / ODT / RTF / EPUB 先经 Pandoc 转为 Markdown，PDF 取文本层（不分节）。
+
+use crate::services::pandoc_service::PandocService;
+use crate::services::pdf_text::extract_pdf_plain_text;
+use pulldown_cmark::{Event, Options, Parser, Tag, TagEnd};
+use scraper::{Html, Node};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TextCounts {
+  /// 词数：中日韩文字每字计 1，其余按单词计
+  pub words: usize,
+  /// 其中的中日韩文字数
+  pub cjk_characters: usize,
+  /// 字符数（含空格，不含换行）
+  pub characters: usize,
+  /// 字符数（不含空白）
+  pub characters_no_spaces: usize,
+  /// 段落数（非空文本块）
+  pub paragraphs: usize,
+}
+
+impl TextCounts {
+  pub fn of(text: &str) -> Self {
+    let mut counts = TextCounts {
+      paragraphs: text.lines().filter(|l| !l.trim().is_empty()).count(),
+      ..Default::default()
+    };
+    let mut in_word = false;
+    for ch in text.chars() {
+      if ch == '\n' || ch == '\r' {
+        in_word = false;
+        continue;
+      }
+      counts.characters += 1;
+      if !ch.is_whitespace() {
+        counts.characters_no_spaces += 1;
+      }
+      if is_cjk(ch) {
+        counts.cjk_characters += 1;
+        counts.words += 1;
+        in_word = false;
+      } else if ch.is_alphanumeric() {
+        if !in_word {
+          counts.words += 1;
+          in_word = true;
+        }
+      } else if !(in_word && matches!(ch, '\'' | '’' | '-' | '_')) {
+        // 撇号、连字符连接的 don't、well-known 算一个词
+        in_word = false;
+      }
+    }
+    counts
+  }
+
+  fn add(&mut self, other: &TextCounts) {
+    self.words += other.words;
+    self.cjk_characters += other.cjk_characters;
+    self.characters += other.characters;
+    self.characters_no_spaces += other.characters_no_spaces;
+    self.paragraphs += other.paragraphs;
+  }
+}
+
+/// 一节的统计（含标题本身）；第一个标题之前的内容为 level 0、标题为空的一节
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SectionCount {
+  pub heading: String,
+  pub level: u8,
+  pub counts: TextCounts,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WordCountResult {
+  pub total: TextCounts,
+  pub sections: Vec<SectionCount>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CountFormat {
+  Markdown,
+  Html,
+  Plain,
+}
+
+impl CountFormat {
+  pub fn parse(value: &str) -> Option<Self> {
+    match value.trim().to_lowercase().as_str() {
+      "md" | "markdown" => Some(CountFormat::Markdown),
+      "html" | "htm" => Some(CountFormat::Html),
+      "txt" | "text" | "plain" => Some(CountFormat::Plain),
+      _ => None,
+    }
+  }
+}
+
+pub(crate) fn is_cjk(ch: char) -> bool {
+  matches!(
+    ch as u32,
+    0x4E00..=0x9FFF
+      | 0x3400..=0x4DBF
+      | 0x20000..=0x2A6DF
+      | 0xF900..=0xFAFF
+      | 0x3040..=0x30FF
+      | 0xAC00..=0xD7AF
+  )
+}
+
+/// 按标题收集各节文本
+#[derive(Default)]
+struct SectionBuilder {
+  sections: Vec<(String, u8, String)>,
+  in_heading: bool,
+}
+
+impl SectionBuilder {
+  fn start_heading(&mut self, level: u8) {
+    self.sections.push((String::new(), level, String::new()));
+    self.in_heading = true;
+  }
+
+  fn end_heading(&mut self) {
+    self.in_heading = false;
+  }
+
+  fn push_text(&mut self, text: &str) {
+    if self.sections.is_empty() {
+      self.sections.push((String::new(), 0, String::new()));
+    }
+    let (heading, _, body) = self.sections.last_mut().unwrap();
+    if self.in_heading {
+      heading.push_str(text);
+    } else {
+      body.push_str(text);
+    }
+  }
+
+  fn end_block(&mut self) {
+    if !self.in_heading {
+      self.push_text("\n");
+    }
+  }
+
+  /// 各节的标题与正文，按顺序拼接为纯文本（标题单独成段）
+  fn text(&self) -> String {
+    self
+      .sections
+      .iter()
+      .map(|(heading, _, body)| format!("{}\n{}", heading.trim(), body))
+      .collect::<Vec<_>>()
+      .join("\n")
+  }
+
+  fn finish(self) -> WordCountResult {
+    let mut total = TextCounts::default();
+    let sections = self
+      .sections
+      .into_iter()
+      .filter(|(heading, level, body)| *level > 0 || !body.trim().is_empty() || !heading.is_empty())
+      .map(|(heading, level, body)| {
+        let heading = heading.trim().to_string();
+        let counts = TextCounts::of(&format!("{}\n{}", heading, body));
+        total.add(&counts);
+        SectionCount {
+          heading,
+          level,
+          counts,
+        }
+      })
+      .collect();
+    WordCountResult { total, sections }
+  }
+}
+
+fn collect_markdown(content: &str) -> SectionBuilder {
+  let options = Options::ENABLE_TABLES
+    | Options::ENABLE_STRIKETHROUGH
+    | Options::ENABLE_TASKLISTS
+    | Options::ENABLE_FOOTNOTES
+    | Options::ENABLE_YAML_STYLE_METADATA_BLOCKS;
+  let mut builder = SectionBuilder::default();
+  // 图片 alt 文本与 front matter 不计入
+  let mut skip_depth = 0usize;
+  for event in Parser::new_ext(content, options) {
+    match event {
+      Event::Start(Tag::Image { .. }) | Event::Start(Tag::MetadataBlock(_)) => skip_depth += 1,
+      Event::End(TagEnd::Image) | Event::End(TagEnd::MetadataBlock(_)) => {
+        skip_depth = skip_depth.saturating_sub(1)
+      }
+      _ if skip_depth > 0 => {}
+      Event::Start(Tag::Heading { level, .. }) => builder.start_heading(level as u8),
+      Event::End(TagEnd::Heading(_)) => builder.end_heading(),
+      Event::Text(text) | Event::Code(text) => builder.push_text(&text),
+      Event::SoftBreak | Event::HardBreak => builder.push_text(" "),
+      Event::End(TagEnd::TableCell) => builder.push_text(" "),
+      Event::End(
+        TagEnd::Paragraph | TagEnd::Item | TagEnd::CodeBlock | TagEnd::TableRow | TagEnd::TableHead,
+      ) => builder.end_block(),
+      _ => {}
+    }
+  }
+  builder
+}
+
+fn heading_level(tag: &str) -> Option<u8> {
+  match tag {
+    "h1" => Some(1),
+    "h2" => Some(2),
+    "h3" => Some(3),
+    "h4" => Some(4),
+    "h5" => Some(5),
+    "h6" => Some(6),
+    _ => None,
+  }
+}
+
+fn collect_html(content: &str) -> SectionBuilder {
+  const BLOCK_TAGS: [&str; 10] = [
+    "p",
+    "li",
+    "div",
+    "tr",
+    "pre",
+    "blockquote",
+    "br",
+    "section",
+    "article",
+    "table",
+  ];
+  let document = Html::parse_document(content);
+  let mut builder = SectionBuilder::default();
+  let mut heading_node = None;
+  for node in document.root_element().descendants() {
+    // 离开标题后恢复正文收集
+    if let Some(id) = heading_node {
+      if !node.ancestors().any(|a| a.id() == id) {
+        builder.end_heading();
+        heading_node = None;
+      }
+    }
+    match node.value() {
+      Node::Element(element) => {
+        let name = element.name();
+        if let Some(level) = heading_level(name) {
+          builder.start_heading(level);
+          heading_node = Some(node.id());
+        } else if BLOCK_TAGS.contains(&name) {
+          builder.end_block();
+        }
+      }
+      Node::Text(text) => {
+        let hidden = node.ancestors().any(|a| {
+          a.value()
+            .as_element()
+            .is_some_and(|e| matches!(e.name(), "script" | "style" | "head"))
+        });
+        if !hidden {
+          builder.push_text(text);
+        }
+      }
+      _ => {}
+    }
+  }
+  builder
+}
+
+fn collect_text(content: &str, format: CountFormat) -> SectionBuilder {
+  match format {
+    CountFormat::Markdown => collect_markdown(content),
+    CountFormat::Html => collect_html(content),
+    CountFormat::Plain => {
+      let mut builder = SectionBuilder::default();
+      builder.push_text(content);
+      builder
+    }
+  }
+}
+
+/// 统计文本内容
+pub fn count_text(content: &str, format: CountFormat) -> WordCountResult {
+  collect_text(content, format).finish()
+}
+
+/// 去掉标记后的正文（不含 front matter、图片与链接地址），每个文本块一行
+pub fn plain_text(content: &str, format: CountFormat) -> String {
+  collect_text(content, format).text()
+}
+
+/// 按扩展名选择读取方式；format 只对文本文件生效
+fn collect_file(path: &Path, format: Option<CountFormat>) -> Result<SectionBuilder, String> {
+  let ext = path
+    .extension()
+    .and_then(|e| e.to_str())
+    .map(|e| e.to_lowercase())
+    .unwrap_or_default();
+  match ext.as_str() {
+    "docx" | "doc" | "odt" | "rtf" | "epub" => {
+      let markdown = PandocService::new().extract_markdown(path)?;
+      Ok(collect_markdown(&markdown))
+    }
+    "pdf" => Ok(collect_text(
+      &extract_pdf_plain_text(path)?,
+      CountFormat::Plain,
+    )),
+    _ => {
+      let content = std::fs::read_to_string(path).map_err(|e| format!("读取文件失败: {}", e))?;
+      let format = format
+        .or_else(|| CountFormat::parse(&ext))
+        .unwrap_or(CountFormat::Plain);
+      Ok(collect_text(&content, format))
+    }
+  }
+}
+
+/// 统计文件
+pub fn count_file(path: &Path, format: Option<CountFormat>) -> Result<WordCountResult, String> {
+  Ok(collect_file(path, format)?.finish())
+}
+
+/// 文件的正文纯文本，读取方式同 count_file
+pub fn file_plain_text(path: &Path, format: Option<CountFormat>) -> Result<String, String> {
+  Ok(collect_file(path, format)?.text())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn counts_cjk_characters_and_latin_words() {
+    let counts = TextCounts::of("你好，world! Don't stop well-known 2024\n\n第二段");
+    assert_eq!(counts.cjk_characters, 5);
+    assert_eq!(counts.words, 5 + 5);
+    assert_eq!(counts.paragraphs, 2);
+    assert_eq!(
+      counts.characters,
+      "你好，world! Don't stop well-known 2024第二段"
+        .chars()
+        .count()
+    );
+  }
+
+  #[test]
+  fn markdown_sections_exclude_markup() {
+    let markdown = "---\ntitle: 草稿\n---\n前言一句。\n\n# 第一章\n\n正文 **加粗** [链接](https://example.com/very/long)。\n\n![图片说明](a.png)\n\n## 1.1 小节\n\n- one two\n- `code`\n";
+    let result = count_text(markdown, CountFormat::Markdown);
+    let headings: Vec<(&str, u8)> = result
+      .sections
+      .iter()
+      .map(|s| (s.heading.as_str(), s.level))
+      .collect();
+    assert_eq!(headings, vec![("", 0), ("第一章", 1), ("1.1 小节", 2)]);
+    assert_eq!(result.sections[0].counts.words, 4);
+    // 标题 3 + 正文 2 + 加粗 2 + 链接 2
+    assert_eq!(result.sections[1].counts.words, 9);
+    // 标题 "1.1 小节" 计 1.1 为 2 个数字词 + 2 字，列表 3 词
+    assert_eq!(result.sections[2].counts.words, 7);
+    assert_eq!(
+      result.total.words,
+      result
+        .sections
+        .iter()
+        .map(|s| s.counts.words)
+        .sum::<usize>()
+    );
+  }
+
+  #[test]
+  fn html_sections_follow_headings() {
+    let html = "<html><head><title>忽略</title><style>p{}</style></head><body>\
+                <h1>Title <em>here</em></h1><p>第一段</p><p>Second para</p>\
+                <h2>小节</h2><ul><li>甲</li><li>乙</li></ul></body></html>";
+    let result = count_text(html, CountFormat::Html);
+    assert_eq!(result.sections.len(), 2);
+    assert_eq!(result.sections[0].heading, "Title here");
+    assert_eq!(result.sections[0].counts.words, 2 + 3 + 2);
+    assert_eq!(result.sections[0].counts.paragraphs, 3);
+    assert_eq!(result.sections[1].heading, "小节");
+    assert_eq!(result.sections[1].counts.words, 4);
+  }
+}
@@ -0,0 +1,500 @@
+use crate::services::file_watcher::DEFAULT_WATCHER_IGNORE;
+use crate::services::memory_service::MEMORY_INJECT_BUDGET_CHARS;
+use crate::services::spellcheck_service::{DEFAULT_SPELLCHECK_LANGUAGE, DICTIONARY_NAME_PATTERN};
+use crate::utils::glob_patterns::build_glob_set;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::time::Duration;
+
+/// 工作区级设置，保存在 `.binder/settings.json`
+///
+/// 所有字段都带默认值，旧版本的设置文件缺少字段时可以正常加载。
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WorkspaceSettings {
+  pub search: SearchSettings,
+  pub context: ContextInjectionSettings,
+  pub agent: AgentToolSettings,
+  pub images: ImageSettings,
+  pub preview: PreviewSettings,
+  pub watcher: WatcherSettings,
+  pub spellcheck: SpellcheckSettings,
+  pub notes: NoteSettings,
+  pub export: ExportSettings,
+}
+
+/// 搜索相关设置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchSettings {
+  /// BM25 词频饱和参数，默认 1.2
+  pub bm25_k1: f64,
+  /// BM25 文档长度归一化参数，默认 0.75
+  pub bm25_b: f64,
+  /// 字段权重：标题 > 小标题 > 正文
+  pub title_boost: f64,
+  pub headings_boost: f64,
+  pub body_boost: f64,
+  /// 是否启用最近编辑加权
+  pub recency_enabled: bool,
+  /// 最近编辑加权的半衰期（天）
+  pub recency_half_life_days: f64,
+  /// 最近编辑加权的最大增益（0.5 表示刚编辑的文档得分最多提高 50%）
+  pub recency_weight: f64,
+  /// 不参与索引的路径规则（相对工作区根目录的 glob，如 `archive/**`、`*.log`）
+  pub exclude_patterns: Vec<String>,
+}
+
+impl Default for SearchSettings {
+  fn default() -> Self {
+    Self {
+      bm25_k1: 1.2,
+      bm25_b: 0.75,
+      title_boost: 10.0,
+      headings_boost: 4.0,
+      body_boost: 1.0,
+      recency_enabled: false,
+      recency_half_life_days: 30.0,
+      recency_weight: 0.5,
+      exclude_patterns: Vec::new(),
+    }
+  }
+}
+
+impl SearchSettings {
+  pub fn validate(&self) -> Result<(), String> {
+    if !(0.0..=3.0).contains(&self.bm25_k1) {
+      return Err("BM25 k1 必须在 0-3 之间".to_string());
+    }
+    if !(0.0..=1.0).contains(&self.bm25_b) {
+      return Err("BM25 b 必须在 0-1 之间".to_string());
+    }
+    if self.title_boost < 0.0 || self.headings_boost < 0.0 || self.body_boost < 0.0 {
+      return Err("字段权重不能为负数".to_string());
+    }
+    if self.recency_half_life_days <= 0.0 {
+      return Err("最近编辑加权的半衰期必须大于 0".to_string());
+    }
+    if self.recency_weight < 0.0 {
+      return Err("最近编辑加权不能为负数".to_string());
+    }
+    build_glob_set(&self.exclude_patterns)?;
+    Ok(())
+  }
+}
+
+/// 聊天前自动注入的检索上下文（记忆 + 工作区片段）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ContextInjectionSettings {
+  /// 是否注入相关记忆
+  pub inject_memories: bool,
+  /// 是否同时检索全局（跨工作区）用户偏好记忆
+  pub inject_global_memories: bool,
+  /// 注入的记忆条数上限
+  pub memory_top_k: usize,
+  /// 是否从搜索索引中检索工作区片段一并注入
+  pub inject_workspace_snippets: bool,
+  /// 注入的工作区片段条数上限
+  pub workspace_snippet_limit: usize,
+  /// 注入内容的总 token 预算（记忆优先，片段使用剩余额度）
+  pub token_budget: usize,
+}
+
+impl Default for ContextInjectionSettings {
+  fn default() -> Self {
+    Self {
+      inject_memories: true,
+      inject_global_memories: true,
+      memory_top_k: 10,
+      inject_workspace_snippets: false,
+      workspace_snippet_limit: 3,
+      token_budget: MEMORY_INJECT_BUDGET_CHARS / 4,
+    }
+  }
+}
+
+impl ContextInjectionSettings {
+  /// 与 ContextManager 的估算保持一致：1 token ≈ 4 字节
+  pub fn budget_chars(&self) -> usize {
+    self.token_budget * 4
+  }
+
+  pub fn validate(&self) -> Result<(), String> {
+    if !(1..=50).contains(&self.memory_top_k) {
+      return Err("注入记忆条数必须在 1-50 之间".to_string());
+    }
+    if self.workspace_snippet_limit > 20 {
+      return Err("注入工作区片段条数不能超过 20".to_string());
+    }
+    if !(50..=8000).contains(&self.token_budget) {
+      return Err("上下文注入 token 预算必须在 50-8000 之间".to_string());
+    }
+    Ok(())
+  }
+}
+
+/// Agent 工具的执行限制
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AgentToolSettings {
+  /// 单条命令的超时时间（秒）
+  pub command_timeout_secs: u64,
+  /// stdout / stderr 各自保留的最大字节数
+  pub command_output_limit_bytes: usize,
+  /// 工具执行超时（秒），0 表示不限制
+  pub tool_timeout_secs: u64,
+  /// 单次工具结果的最大字节数，超出部分截断并附带标记
+  pub tool_max_output_bytes: usize,
+  /// 按工具名覆盖超时与输出上限，如 `{"search_workspace": {"timeout_secs": 10}}`
+  pub tool_limits: HashMap<String, ToolLimitOverride>,
+}
+
+/// 单个工具的超时 / 输出上限覆盖项，未设置的字段沿用全局值
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ToolLimitOverride {
+  pub timeout_secs: Option<u64>,
+  pub max_output_bytes: Option<usize>,
+}
+
+impl Default for AgentToolSettings {
+  fn default() -> Self {
+    Self {
+      command_timeout_secs: 30,
+      command_output_limit_bytes: 64 * 1024,
+      tool_timeout_secs: 120,
+      tool_max_output_bytes: 200 * 1024,
+      tool_limits: HashMap::new(),
+    }
+  }
+}
+
+impl AgentToolSettings {
+  pub fn validate(&self) -> Result<(), String> {
+    if !(1..=600).contains(&self.command_timeout_secs) {
+      return Err("命令超时时间必须在 1-600 秒之间".to_string());
+    }
+    if !(1024..=1024 * 1024).contains(&self.command_output_limit_bytes) {
+      return Err("命令输出上限必须在 1KB-1MB 之间".to_string());
+    }
+    let overrides = self.tool_limits.values();
+    let mut timeouts = std::iter::once(self.tool_timeout_secs)
+      .chain(overrides.clone().filter_map(|o| o.timeout_secs));
+    if timeouts.any(|secs| secs > 3600) {
+      return Err("工具超时时间不能超过 3600 秒".to_string());
+    }
+    let mut output_limits = std::iter::once(self.tool_max_output_bytes)
+      .chain(overrides.filter_map(|o| o.max_output_bytes));
+    if output_limits.any(|bytes| !(1024..=10 * 1024 * 1024).contains(&bytes)) {
+      return Err("工具输出上限必须在 1KB-10MB 之间".to_string());
+    }
+    Ok(())
+  }
+}
+
+/// 插入图片时的处理设置：缩放重编码默认关闭，元数据清理默认开启
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ImageSettings {
+  /// 插入图片时是否缩放并重新编码
+  pub optimize_on_insert: bool,
+  /// 长边最大像素，超出时等比缩小
+  pub max_dimension: u32,
+  /// JPEG / WebP 重新编码质量（1-100），PNG 始终无损
+  pub quality: u8,
+  /// 插入时移除 EXIF 等元数据（GPS 位置、相机信息）
+  pub strip_metadata_on_insert: bool,
+  /// 是否允许插入 SVG（允许时插入前会清理脚本与外部引用）
+  pub allow_svg: bool,
+}
+
+impl Default for ImageSettings {
+  fn default() -> Self {
+    Self {
+      optimize_on_insert: false,
+      max_dimension: 2048,
+      quality: 82,
+      strip_metadata_on_insert: true,
+      allow_svg: true,
+    }
+  }
+}
+
+impl ImageSettings {
+  pub fn validate(&self) -> Result<(), String> {
+    if !(256..=16384).contains(&self.max_dimension) {
+      return Err("图片最大边长必须在 256-16384 像素之间".to_string());
+    }
+    if !(1..=100).contains(&self.quality) {
+      return Err("图片压缩质量必须在 1-100 之间".to_string());
+    }
+    Ok(())
+  }
+}
+
+/// 文档预览设置：LibreOffice 转换超时按文件大小放宽
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PreviewSettings {
+  /// 基础转换超时（秒）
+  pub conversion_timeout_secs: u64,
+  /// 文件每 MB 额外增加的超时（秒）
+  pub timeout_secs_per_mb: u64,
+  /// 超时上限（秒）
+  pub max_conversion_timeout_secs: u64,
+}
+
+impl Default for PreviewSettings {
+  fn default() -> Self {
+    Self {
+      conversion_timeout_secs: 30,
+      timeout_secs_per_mb: 10,
+      max_conversion_timeout_secs: 300,
+    }
+  }
+}
+
+impl PreviewSettings {
+  /// 按文件大小计算转换超时
+  pub fn conversion_timeout(&self, size_bytes: u64) -> Duration {
+    const MB: u64 = 1024 * 1024;
+    let size_mb = size_bytes / MB + u64::from(size_bytes % MB != 0);
+    let secs = self
+      .conversion_timeout_secs
+      .saturating_add(size_mb.saturating_mul(self.timeout_secs_per_mb));
+    Duration::from_secs(secs.min(self.max_conversion_timeout_secs))
+  }
+
+  pub fn validate(&self) -> Result<(), String> {
+    if !(5..=600).contains(&self.conversion_timeout_secs) {
+      return Err("预览转换超时必须在 5-600 秒之间".to_string());
+    }
+    if self.timeout_secs_per_mb > 600 {
+      return Err("每 MB 增加的预览超时不能超过 600 秒".to_string());
+    }
+    if !(self.conversion_timeout_secs..=3600).contains(&self.max_conversion_timeout_secs) {
+      return Err("预览超时上限必须不小于基础超时且不超过 3600 秒".to_string());
+    }
+    Ok(())
+  }
+}
+
+/// 文件监听设置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WatcherSettings {
+  /// 不触发文件变化事件的路径规则（相对工作区根目录的 glob），替换默认的 `.binder/**`、`.git/**`
+  pub ignore_patterns: Vec<String>,
+  /// 只读参考资料文件夹（绝对路径）：在文件树中单独显示、参与搜索，但不允许写入
+  pub reference_folders: Vec<String>,
+  /// 防抖窗口（毫秒）：最后一次变化后静默这么久才通知
+  pub debounce_ms: u64,
+  /// 持续变化时最长等待（毫秒），到时即使仍有变化也先通知一次
+  pub max_wait_ms: u64,
+  /// 单次通知最多携带的变化数，超过时只通知整体刷新（如一次复制上万个文件）
+  pub max_batch_size: usize,
+}
+
+impl Default for WatcherSettings {
+  fn default() -> Self {
+    Self {
+      ignore_patterns: DEFAULT_WATCHER_IGNORE
+        .iter()
+        .map(|p| p.to_string())
+        .collect(),
+      reference_folders: Vec::new(),
+      debounce_ms: 500,
+      max_wait_ms: 5000,
+      max_batch_size: 500,
+    }
+  }
+}
+
+impl WatcherSettings {
+  pub fn validate(&self) -> Result<(), String> {
+    build_glob_set(&self.ignore_patterns)?;
+    if !(50..=10_000).contains(&self.debounce_ms) {
+      return Err("文件监听防抖窗口必须在 50-10000 毫秒之间".to_string());
+    }
+    if self.max_wait_ms < self.debounce_ms {
+      return Err("文件监听最长等待时间不能小于防抖窗口".to_string());
+    }
+    if self.max_batch_size == 0 {
+      return Err("文件监听单次通知的变化数必须大于 0".to_string());
+    }
+    if let Some(folder) = self
+      .reference_folders
+      .iter()
+      .find(|f| !Path::new(f.trim()).is_absolute())
+    {
+      return Err(format!("参考资料文件夹必须是绝对路径: {}", folder));
+    }
+    Ok(())
+  }
+
+  /// 可监听的参考资料文件夹：跳过不存在的文件夹，以及与工作区互相包含的文件夹
+  pub fn reference_roots(&self, workspace_path: &Path) -> Vec<PathBuf> {
+    let mut roots: Vec<PathBuf> = Vec::new();
+    for folder in &self.reference_folders {
+      let root = PathBuf::from(folder.trim());
+      if !root.is_absolute()
+        || !root.is_dir()
+        || root.starts_with(workspace_path)
+        || workspace_path.starts_with(&root)
+        || roots.contains(&root)
+      {
+        continue;
+      }
+      roots.push(root);
+    }
+    roots
+  }
+}
+
+/// 拼写检查设置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SpellcheckSettings {
+  /// 词典语言（Hunspell 词典名，如 `en_US`、`de_DE`）
+  pub language: String,
+}
+
+impl Default for SpellcheckSettings {
+  fn default() -> Self {
+    Self {
+      language: DEFAULT_SPELLCHECK_LANGUAGE.to_string(),
+    }
+  }
+}
+
+impl SpellcheckSettings {
+  pub fn validate(&self) -> Result<(), String> {
+    if !DICTIONARY_NAME_PATTERN.is_match(&self.language) {
+      return Err(format!("无效的拼写检查语言: {}", self.language));
+    }
+    Ok(())
+  }
+}
+
+/// 笔记设置：Wiki 链接指向的笔记不存在时，按此新建
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NoteSettings {
+  /// 新笔记所在文件夹（相对工作区根目录），为空时放在根目录
+  pub new_note_folder: String,
+  /// 新笔记的模板文件（相对工作区根目录），变量见 file_templates，其中 `{{title}}` 为笔记名
+  pub new_note_template: Option<String>,
+}
+
+impl NoteSettings {
+  pub fn validate(&self) -> Result<(), String> {
+    let paths =
+      std::iter::once(self.new_note_folder.as_str()).chain(self.new_note_template.as_deref());
+    for path in paths {
+      let escapes = Path::new(path)
+        .components()
+        .any(|c| !matches!(c, Component::Normal(_) | Component::CurDir));
+      if escapes {
+        return Err(format!("笔记路径必须是工作区内的相对路径: {}", path));
+      }
+    }
+    Ok(())
+  }
+}
+
+/// 导出设置：HTML 转 DOCX / PDF 时为标题、图片与表格编号，并解析指向它们的交叉引用
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExportSettings {
+  /// 标题编号（1.、1.1、1.1.1 …）
+  pub number_headings: bool,
+  /// 参与编号的标题层数，从文档中最高一级标题算起，默认 3
+  pub heading_number_depth: u8,
+  pub number_figures: bool,
+  pub number_tables: bool,
+  /// 图片、表格题注的前缀，如“图 1”“表 1”
+  pub figure_label: String,
+  pub table_label: String,
+}
+
+impl Default for ExportSettings {
+  fn default() -> Self {
+    Self {
+      number_headings: false,
+      heading_number_depth: 3,
+      number_figures: false,
+      number_tables: false,
+      figure_label: "图".to_string(),
+      table_label: "表".to_string(),
+    }
+  }
+}
+
+impl ExportSettings {
+  pub fn is_enabled(&self) -> bool {
+    self.number_headings || self.number_figures || self.number_tables
+  }
+
+  pub fn validate(&self) -> Result<(), String> {
+    if !(1..=6).contains(&self.heading_number_depth) {
+      return Err("标题编号层数必须在 1-6 之间".to_string());
+    }
+    if self.figure_label.trim().is_empty() || self.table_label.trim().is_empty() {
+      return Err("图片与表格的题注前缀不能为空".to_string());
+    }
+    Ok(())
+  }
+}
+
+impl WorkspaceSettings {
+  pub fn settings_path(workspace_path: &Path) -> PathBuf {
+    workspace_path.join(".binder").join("settings.json")
+  }
+
+  /// 加载工作区设置，文件不存在时返回默认设置（不写盘）
+  pub fn load(workspace_path: &Path) -> Result<Self, String> {
+    let path = Self::settings_path(workspace_path);
+    if !path.exists() {
+      return Ok(Self::default());
+    }
+
+    let content = fs::read_to_string(&path).map_err(|e| format!("读取工作区设置失败: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("解析工作区设置失败: {}", e))
+  }
+
+  /// 加载失败时打印日志并回退到默认设置，供后台任务使用
+  pub fn load_or_default(workspace_path: &Path) -> Self {
+    Self::load(workspace_path).unwrap_or_else(|e| {
+      tracing::debug!("{}，使用默认工作区设置", e);
+      Self::default()
+    })
+  }
+
+  pub fn save(&self, workspace_path: &Path) -> Result<(), String> {
+    self.validate()?;
+
+    let path = Self::settings_path(workspace_path);
+    if let Some(parent) = path.parent() {
+      fs::create_dir_all(parent).map_err(|e| format!("创建 .binder 目录失败: {}", e))?;
+    }
+
+    let json =
+      serde_json::to_string_pretty(self).map_err(|e| format!("序列化工作区设置失败: {}", e))?;
+    fs::write(&path, json).map_err(|e| format!("写入工作区设置失败: {}", e))
+  }
+
+  pub fn validate(&self) -> Result<(), String> {
+    self.search.validate()?;
+    self.context.validate()?;
+    self.agent.validate()?;
+    self.images.validate()?;
+    self.preview.validate()?;
+    self.watcher.validate()?;
+    self.spellcheck.validate()?;
+    self.notes.validate()?;
+    self.export.validate()
+  }
+}
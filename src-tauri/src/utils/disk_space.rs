@@ -0,0 +1,85 @@
+//! 磁盘可用空间检查：文档转换前确认输出目录所在磁盘有足够空间，
+//! 不足时返回 `INSUFFICIENT_DISK_SPACE`，而不是让转换工具中途写盘失败。
+
+use crate::error::AppError;
+use std::path::{Path, PathBuf};
+use sysinfo::Disks;
+
+/// 转换需要的可用空间 = 源文件大小 × 该倍数（输出文件与中间文件）
+pub const CONVERSION_SPACE_FACTOR: u64 = 2;
+
+/// 在挂载点列表中找到 path 所在磁盘（最长前缀匹配），返回其可用字节数
+fn available_on(mounts: &[(PathBuf, u64)], path: &Path) -> Option<u64> {
+  mounts
+    .iter()
+    .filter(|(mount_point, _)| path.starts_with(mount_point))
+    .max_by_key(|(mount_point, _)| mount_point.components().count())
+    .map(|(_, available)| *available)
+}
+
+/// path 所在磁盘的可用字节数；path 尚不存在时按最近的已存在上级目录查找，无法确定时返回 None
+pub fn available_space(path: &Path) -> Option<u64> {
+  let existing = path.ancestors().find(|p| p.exists())?;
+  let resolved = existing.canonicalize().ok()?;
+  let disks = Disks::new_with_refreshed_list();
+  let mounts: Vec<(PathBuf, u64)> = disks
+    .list()
+    .iter()
+    .map(|disk| (disk.mount_point().to_path_buf(), disk.available_space()))
+    .collect();
+  available_on(&mounts, &resolved)
+}
+
+/// 确认 output_dir 所在磁盘至少有源文件大小 2 倍的可用空间；无法获取磁盘信息时不阻止转换
+pub fn ensure_space_for_conversion(output_dir: &Path, source_size: u64) -> Result<(), AppError> {
+  let required = source_size.saturating_mul(CONVERSION_SPACE_FACTOR);
+  match available_space(output_dir) {
+    Some(available) if available < required => Err(AppError::InsufficientDiskSpace {
+      path: output_dir.to_string_lossy().to_string(),
+      required,
+      available,
+    }),
+    Some(_) => Ok(()),
+    None => {
+      tracing::debug!("无法获取 {:?} 所在磁盘的可用空间，跳过检查", output_dir);
+      Ok(())
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn picks_the_most_specific_mount_point() {
+    let mounts = vec![
+      (PathBuf::from("/"), 100),
+      (PathBuf::from("/home"), 200),
+      (PathBuf::from("/home/user/cache"), 300),
+    ];
+    assert_eq!(
+      available_on(&mounts, Path::new("/home/user/a.docx")),
+      Some(200)
+    );
+    assert_eq!(
+      available_on(&mounts, Path::new("/home/user/cache/x")),
+      Some(300)
+    );
+    assert_eq!(available_on(&mounts, Path::new("/tmp")), Some(100));
+    // 按路径组件匹配，不是字符串前缀
+    assert_eq!(available_on(&mounts, Path::new("/homework")), Some(100));
+    assert_eq!(available_on(&[], Path::new("/tmp")), None);
+  }
+
+  #[test]
+  fn reports_shortfall_with_required_and_available_bytes() {
+    let dir = std::env::temp_dir();
+    let Some(available) = available_space(&dir) else {
+      return;
+    };
+    assert!(ensure_space_for_conversion(&dir.join("not-created-yet"), 1024).is_ok());
+    let error = ensure_space_for_conversion(&dir, available).unwrap_err();
+    assert_eq!(error.code(), "INSUFFICIENT_DISK_SPACE");
+  }
+}
@@ -0,0 +1,29 @@
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use std::path::Path;
+
+/// 将用户配置的 glob 列表编译为 GlobSet（如 `archive/**`、`*.log`）
+pub fn build_glob_set(patterns: &[String]) -> Result<GlobSet, String> {
+  let mut builder = GlobSetBuilder::new();
+  for pattern in patterns {
+    let pattern = pattern.trim();
+    if pattern.is_empty() {
+      continue;
+    }
+    let glob = Glob::new(pattern).map_err(|e| format!("无效的匹配规则 {}: {}", pattern, e))?;
+    builder.add(glob);
+  }
+  builder
+    .build()
+    .map_err(|e| format!("编译匹配规则失败: {}", e))
+}
+
+/// 计算相对于根目录的路径，统一使用 `/` 分隔，便于跨平台匹配 glob
+pub fn relative_slash_path(path: &Path, root: &Path) -> String {
+  path
+    .strip_prefix(root)
+    .unwrap_or(path)
+    .components()
+    .map(|c| c.as_os_str().to_string_lossy())
+    .collect::<Vec<_>>()
+    .join("/")
+}
@@ -0,0 +1,232 @@
+//! 后端消息本地化：按前端传入的语言（`set_locale`）返回错误与进度消息。
+//!
+//! 消息表每条包含中文、英文、日文三种文本，`{name}` 为占位符；未识别的语言使用中文。
+//! 日志仍统一使用中文，只有返回给前端的消息经过本地化。服务层返回的自由文本暂不翻译。
+
+use once_cell::sync::Lazy;
+use std::sync::RwLock;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Locale {
+  #[default]
+  Zh,
+  En,
+  Ja,
+}
+
+impl Locale {
+  /// 解析 BCP 47 语言标签（如 `en-US`、`ja`、`zh-CN`），无法识别时为中文
+  pub fn parse(tag: &str) -> Self {
+    let language = tag
+      .trim()
+      .split(['-', '_'])
+      .next()
+      .unwrap_or_default()
+      .to_ascii_lowercase();
+    match language.as_str() {
+      "en" => Locale::En,
+      "ja" => Locale::Ja,
+      _ => Locale::Zh,
+    }
+  }
+
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      Locale::Zh => "zh",
+      Locale::En => "en",
+      Locale::Ja => "ja",
+    }
+  }
+}
+
+static CURRENT_LOCALE: Lazy<RwLock<Locale>> = Lazy::new(Default::default);
+
+/// 设置当前语言，返回实际生效的语言
+pub fn set_locale(tag: &str) -> Locale {
+  let locale = Locale::parse(tag);
+  if let Ok(mut current) = CURRENT_LOCALE.write() {
+    *current = locale;
+  }
+  locale
+}
+
+pub fn current_locale() -> Locale {
+  CURRENT_LOCALE.read().map(|l| *l).unwrap_or_default()
+}
+
+/// (key, 中文, 英文, 日文)
+const MESSAGES: &[(&str, &str, &str, &str)] = &[
+  (
+    "error.not_found",
+    "文件不存在: {path}",
+    "File not found: {path}",
+    "ファイルが見つかりません: {path}",
+  ),
+  (
+    "error.already_exists",
+    "文件已存在: {path}",
+    "File already exists: {path}",
+    "ファイルは既に存在します: {path}",
+  ),
+  (
+    "error.permission_denied",
+    "没有权限访问: {path}",
+    "Permission denied: {path}",
+    "アクセス権限がありません: {path}",
+  ),
+  (
+    "error.read_only",
+    "参考资料文件夹为只读，不能修改: {path}",
+    "The references folder is read-only: {path}",
+    "参考資料フォルダは読み取り専用です: {path}",
+  ),
+  (
+    "error.pandoc_unavailable",
+    "Pandoc 不可用，请安装 Pandoc 或确保内置 Pandoc 可用。\n访问 https://pandoc.org/installing.html 获取安装指南。",
+    "Pandoc is not available. Install Pandoc or make sure the bundled Pandoc is present.\nSee https://pandoc.org/installing.html for instructions.",
+    "Pandoc を利用できません。Pandoc をインストールするか、同梱の Pandoc を確認してください。\nhttps://pandoc.org/installing.html を参照してください。",
+  ),
+  (
+    "error.libreoffice_unavailable",
+    "{reason}",
+    "LibreOffice is not available: {reason}",
+    "LibreOffice を利用できません: {reason}",
+  ),
+  (
+    "error.conversion_failed",
+    "{tool} 转换失败: {message}",
+    "{tool} conversion failed: {message}",
+    "{tool} での変換に失敗しました: {message}",
+  ),
+  (
+    "error.insufficient_disk_space",
+    "磁盘空间不足: {path} 所在磁盘需要 {required} 可用空间，当前仅剩 {available}。请清理磁盘或预览缓存后重试。",
+    "Not enough disk space: the disk holding {path} needs {required} free but only {available} is left. Free up disk space or clear the preview cache and try again.",
+    "ディスク容量が不足しています: {path} のディスクには {required} の空きが必要ですが、残りは {available} です。ディスクまたはプレビューキャッシュを整理してから再試行してください。",
+  ),
+  (
+    "error.ai_not_configured",
+    "未配置 AI 提供商，请先设置 API 密钥",
+    "No AI provider is configured. Please set an API key first.",
+    "AI プロバイダーが設定されていません。先に API キーを設定してください。",
+  ),
+  (
+    "error.ai_network",
+    "网络错误: {detail}",
+    "Network error: {detail}",
+    "ネットワークエラー: {detail}",
+  ),
+  (
+    "error.ai_rate_limited",
+    "请求频率限制，请在 {seconds} 秒后重试",
+    "Rate limited. Please retry in {seconds} seconds.",
+    "リクエストが制限されています。{seconds} 秒後に再試行してください。",
+  ),
+  (
+    "error.ai_model_unavailable",
+    "模型不可用",
+    "The model is unavailable",
+    "モデルを利用できません",
+  ),
+  (
+    "error.ai_context_too_long",
+    "上下文过长",
+    "The context is too long",
+    "コンテキストが長すぎます",
+  ),
+  ("error.ai_timeout", "请求超时", "Request timed out", "リクエストがタイムアウトしました"),
+  ("error.ai_cancelled", "请求已取消", "Request cancelled", "リクエストはキャンセルされました"),
+  (
+    "error.ai_unknown",
+    "未知错误: {detail}",
+    "Unknown error: {detail}",
+    "不明なエラー: {detail}",
+  ),
+  (
+    "error.ai_request_failed",
+    "AI 请求失败: {detail}",
+    "AI request failed: {detail}",
+    "AI リクエストに失敗しました: {detail}",
+  ),
+  (
+    "error.encrypted_file_locked",
+    "加密文件尚未解锁，请输入口令: {path}",
+    "This encrypted file is locked. Enter the passphrase to open it: {path}",
+    "暗号化されたファイルはロックされています。パスフレーズを入力してください: {path}",
+  ),
+  (
+    "error.wrong_passphrase",
+    "口令错误或文件已损坏",
+    "Wrong passphrase or the file is damaged",
+    "パスフレーズが違うか、ファイルが破損しています",
+  ),
+  (
+    "preview.converting",
+    "正在预览...",
+    "Preparing preview...",
+    "プレビューを準備しています...",
+  ),
+  (
+    "preview.converting_progress",
+    "正在预览... {progress}%",
+    "Preparing preview... {progress}%",
+    "プレビューを準備しています... {progress}%",
+  ),
+  ("preview.completed", "预览完成", "Preview ready", "プレビューの準備ができました"),
+  (
+    "preview.timeout",
+    "预览失败，你的文件过大或存在无法预览的格式，请调整文档。",
+    "Preview failed. The file is too large or contains content that cannot be previewed.",
+    "プレビューに失敗しました。ファイルが大きすぎるか、プレビューできない内容が含まれています。",
+  ),
+];
+
+/// 指定语言的消息；未知 key 原样返回
+pub fn translate(locale: Locale, key: &str, args: &[(&str, &str)]) -> String {
+  let Some(&(_, zh, en, ja)) = MESSAGES.iter().find(|(k, ..)| *k == key) else {
+    return key.to_string();
+  };
+  let template = match locale {
+    Locale::Zh => zh,
+    Locale::En => en,
+    Locale::Ja => ja,
+  };
+  args
+    .iter()
+    .fold(template.to_string(), |message, (name, value)| {
+      message.replace(&format!("{{{}}}", name), value)
+    })
+}
+
+/// 当前语言的消息
+pub fn t(key: &str, args: &[(&str, &str)]) -> String {
+  translate(current_locale(), key, args)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn translates_with_placeholders_and_locale_tags() {
+    assert_eq!(Locale::parse("en-US"), Locale::En);
+    assert_eq!(Locale::parse("ja_JP"), Locale::Ja);
+    assert_eq!(Locale::parse("fr"), Locale::Zh);
+    assert_eq!(
+      translate(Locale::En, "error.not_found", &[("path", "/ws/a.md")]),
+      "File not found: /ws/a.md"
+    );
+    assert_eq!(
+      translate(
+        Locale::Ja,
+        "error.conversion_failed",
+        &[("tool", "Pandoc"), ("message", "x")]
+      ),
+      "Pandoc での変換に失敗しました: x"
+    );
+    assert_eq!(translate(Locale::En, "error.missing", &[]), "error.missing");
+    assert!(MESSAGES
+      .iter()
+      .all(|(_, zh, en, ja)| !zh.is_empty() && !en.is_empty() && !ja.is_empty()));
+  }
+}
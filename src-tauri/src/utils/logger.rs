@@ -1,78 +1,331 @@
-// 日志工具模块
-// 提供统一的日志输出，方便调试和问题排查
+//! 日志：基于 tracing，同时输出到终端和应用日志目录（按天滚动，保留 7 天）。
+//!
+//! 默认级别为依赖库 `warn`、本应用 `info`；环境变量 `BINDER_LOG`（语法同 `RUST_LOG`）可覆盖，
+//! 运行时可用 `set_log_level` 按模块调整，如 `services::pandoc_service` 设为 `debug`。
+//! span 关闭时记录耗时（pandoc / LibreOffice / AI 调用），同时汇总到 `perf` 供 `get_performance_report` 查询。
+//!
+//! 最近的日志保存在内存中供 `get_recent_logs` 查询，警告与错误同时以 `log-event` 事件推送给前端。
 
-use std::fs::{File, OpenOptions};
-use std::io::Write;
-use std::path::PathBuf;
+use once_cell::sync::{Lazy, OnceCell};
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt::Write as _;
+use std::path::Path;
 use std::sync::Mutex;
-use dirs::home_dir;
+use tauri::{AppHandle, Emitter};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::layer::{Context, Layer};
+use tracing_subscriber::prelude::*;
+use tracing_subscriber::{fmt, reload, EnvFilter, Registry};
 
-lazy_static::lazy_static! {
-    static ref LOG_FILE: Mutex<Option<File>> = Mutex::new(None);
+const CRATE_TARGET: &str = "binder";
+const LOG_ENV: &str = "BINDER_LOG";
+
+/// 各 target 的级别；本应用默认 info
+static LEVELS: Lazy<Mutex<BTreeMap<String, LevelFilter>>> = Lazy::new(|| {
+  Mutex::new(BTreeMap::from([(
+    CRATE_TARGET.to_string(),
+    LevelFilter::INFO,
+  )]))
+});
+
+static FILTER_HANDLE: OnceCell<reload::Handle<EnvFilter, Registry>> = OnceCell::new();
+/// 后台写文件线程的句柄，释放后不再写入日志文件
+static FILE_GUARD: OnceCell<WorkerGuard> = OnceCell::new();
+
+/// 内存中保留的最近日志条数
+const RECENT_LOG_CAPACITY: usize = 2000;
+static RECENT_LOGS: Lazy<Mutex<VecDeque<LogEntry>>> = Lazy::new(Default::default);
+static APP_HANDLE: OnceCell<AppHandle> = OnceCell::new();
+
+thread_local! {
+  /// 正在发送 `log-event`；发送过程中产生的日志不再推送，避免递归
+  static EMITTING: Cell<bool> = const { Cell::new(false) };
 }
 
-/// 初始化日志文件
-pub fn init_logger() {
-    if let Some(home) = home_dir() {
-        let log_path = home.join(".binder").join("logs").join("binder.log");
-        if let Some(parent) = log_path.parent() {
-            let _ = std::fs::create_dir_all(parent);
-        }
-        
-        if let Ok(file) = OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(&log_path)
-        {
-            *LOG_FILE.lock().unwrap() = Some(file);
-            eprintln!("📝 日志文件已初始化: {:?}", log_path);
-        }
-    }
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogEntry {
+  /// RFC 3339 本地时间
+  pub timestamp: String,
+  /// ERROR / WARN / INFO / DEBUG / TRACE
+  pub level: String,
+  pub target: String,
+  pub message: String,
+}
+
+/// `get_recent_logs` 的过滤条件，均为可选
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LogFilter {
+  /// 最低级别，如 `warn` 只返回警告和错误
+  pub level: Option<String>,
+  /// target 前缀，如 `binder::services::pandoc_service`
+  pub target: Option<String>,
+  /// 消息包含的文字（忽略大小写）
+  pub query: Option<String>,
+}
+
+/// 拼接事件的 message 与其余字段
+#[derive(Default)]
+struct MessageVisitor {
+  message: String,
+  fields: String,
 }
 
-/// 写入日志
-pub fn log(level: &str, message: &str) {
-    let timestamp = chrono::Local::now().format("%Y-%m-%d %H:%M:%S%.3f");
-    let log_message = format!("[{}] [{}] {}\n", timestamp, level, message);
-    
-    // 输出到 stderr（终端可见）
-    eprintln!("{}", log_message.trim());
-    
-    // 写入文件
-    if let Ok(mut file) = LOG_FILE.lock() {
-        if let Some(ref mut f) = *file {
-            let _ = f.write_all(log_message.as_bytes());
-            let _ = f.flush();
-        }
+impl Visit for MessageVisitor {
+  fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+    if field.name() == "message" {
+      let _ = write!(self.message, "{:?}", value);
+    } else {
+      let _ = write!(self.fields, " {}={:?}", field.name(), value);
     }
+  }
+
+  fn record_str(&mut self, field: &Field, value: &str) {
+    if field.name() == "message" {
+      self.message.push_str(value);
+    } else {
+      let _ = write!(self.fields, " {}={}", field.name(), value);
+    }
+  }
 }
 
-/// 日志宏
-#[macro_export]
-macro_rules! log_info {
-    ($($arg:tt)*) => {
-        $crate::utils::logger::log("INFO", &format!($($arg)*));
+/// 记录最近日志并推送警告 / 错误
+struct RecentLogLayer;
+
+impl<S: Subscriber> Layer<S> for RecentLogLayer {
+  fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+    let metadata = event.metadata();
+    let mut visitor = MessageVisitor::default();
+    event.record(&mut visitor);
+    let entry = LogEntry {
+      timestamp: chrono::Local::now().to_rfc3339(),
+      level: metadata.level().to_string(),
+      target: metadata.target().to_string(),
+      message: visitor.message + &visitor.fields,
     };
+
+    if *metadata.level() <= Level::WARN && !EMITTING.with(|f| f.get()) {
+      if let Some(app) = APP_HANDLE.get() {
+        EMITTING.with(|f| f.set(true));
+        let _ = app.emit("log-event", &entry);
+        EMITTING.with(|f| f.set(false));
+      }
+    }
+    if let Ok(mut logs) = RECENT_LOGS.lock() {
+      if logs.len() >= RECENT_LOG_CAPACITY {
+        logs.pop_front();
+      }
+      logs.push_back(entry);
+    }
+  }
 }
 
-#[macro_export]
-macro_rules! log_warn {
-    ($($arg:tt)*) => {
-        $crate::utils::logger::log("WARN", &format!($($arg)*));
-    };
+/// 设置推送 `log-event` 使用的 AppHandle（应用启动后调用一次）
+pub fn attach_app_handle(app: AppHandle) {
+  let _ = APP_HANDLE.set(app);
 }
 
-#[macro_export]
-macro_rules! log_error {
-    ($($arg:tt)*) => {
-        $crate::utils::logger::log("ERROR", &format!($($arg)*));
-    };
+/// 最近的日志（按时间先后），最多 limit 条
+pub fn recent_logs(filter: &LogFilter, limit: usize) -> Result<Vec<LogEntry>, String> {
+  let min_level = filter
+    .level
+    .as_deref()
+    .map(|level| {
+      level
+        .trim()
+        .parse::<Level>()
+        .map_err(|_| format!("未知日志级别: {}", level))
+    })
+    .transpose()?;
+  let query = filter.query.as_deref().map(str::to_lowercase);
+  let logs = RECENT_LOGS.lock().map_err(|e| e.to_string())?;
+  let mut matched: Vec<LogEntry> = logs
+    .iter()
+    .rev()
+    .filter(|entry| {
+      min_level.map_or(true, |min| {
+        entry.level.parse::<Level>().is_ok_and(|level| level <= min)
+      })
+    })
+    .filter(|entry| {
+      filter
+        .target
+        .as_deref()
+        .map_or(true, |target| entry.target.starts_with(target))
+    })
+    .filter(|entry| {
+      query
+        .as_deref()
+        .map_or(true, |query| entry.message.to_lowercase().contains(query))
+    })
+    .take(limit)
+    .cloned()
+    .collect();
+  matched.reverse();
+  Ok(matched)
 }
 
-#[macro_export]
-macro_rules! log_debug {
-    ($($arg:tt)*) => {
-        $crate::utils::logger::log("DEBUG", &format!($($arg)*));
-    };
+fn render_directives(levels: &BTreeMap<String, LevelFilter>) -> String {
+  std::iter::once("warn".to_string())
+    .chain(
+      levels
+        .iter()
+        .map(|(target, level)| format!("{}={}", target, level)),
+    )
+    .collect::<Vec<_>>()
+    .join(",")
+}
+
+/// 初始化日志；重复调用时忽略
+pub fn init_logger(log_dir: &Path) -> Result<(), String> {
+  if FILTER_HANDLE.get().is_some() {
+    return Ok(());
+  }
+  std::fs::create_dir_all(log_dir).map_err(|e| format!("创建日志目录失败: {}", e))?;
+  let appender = RollingFileAppender::builder()
+    .rotation(Rotation::DAILY)
+    .filename_prefix("binder")
+    .filename_suffix("log")
+    .max_log_files(7)
+    .build(log_dir)
+    .map_err(|e| format!("创建日志文件失败: {}", e))?;
+  let (file_writer, guard) = tracing_appender::non_blocking(appender);
+
+  let directives = match std::env::var(LOG_ENV) {
+    Ok(directives) => directives,
+    Err(_) => render_directives(&*LEVELS.lock().map_err(|e| e.to_string())?),
+  };
+  let filter = EnvFilter::try_new(&directives).map_err(|e| format!("日志级别配置无效: {}", e))?;
+  let (filter, handle) = reload::Layer::new(filter);
+
+  tracing_subscriber::registry()
+    .with(filter)
+    .with(
+      fmt::layer()
+        .with_writer(std::io::stderr)
+        .with_span_events(FmtSpan::CLOSE),
+    )
+    .with(
+      fmt::layer()
+        .with_ansi(false)
+        .with_writer(file_writer)
+        .with_span_events(FmtSpan::CLOSE),
+    )
+    .with(RecentLogLayer)
+    .with(super::perf::PerfLayer)
+    .try_init()
+    .map_err(|e| format!("初始化日志失败: {}", e))?;
+
+  let _ = FILTER_HANDLE.set(handle);
+  let _ = FILE_GUARD.set(guard);
+  tracing::info!("日志目录: {}", log_dir.display());
+  Ok(())
+}
+
+/// 设置日志级别；module 为空时设置整个应用，否则只设置该模块（如 `services::pandoc_service`）。
+/// 返回生效的过滤规则
+pub fn set_log_level(module: Option<&str>, level: &str) -> Result<String, String> {
+  let level: LevelFilter = level.trim().parse().map_err(|_| {
+    format!(
+      "未知日志级别: {}（可选 off/error/warn/info/debug/trace）",
+      level
+    )
+  })?;
+  let target = match module.map(str::trim).filter(|m| !m.is_empty()) {
+    None => CRATE_TARGET.to_string(),
+    Some(module) => {
+      let module = module
+        .strip_prefix("binder::")
+        .unwrap_or(module)
+        .trim_matches(':');
+      let valid = !module.is_empty()
+        && module.split("::").all(|part| {
+          !part.is_empty() && part.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        });
+      if !valid {
+        return Err(format!("模块名无效: {}", module));
+      }
+      format!("{}::{}", CRATE_TARGET, module)
+    }
+  };
+
+  let directives = {
+    let mut levels = LEVELS.lock().map_err(|e| e.to_string())?;
+    // 设置整个应用时清除各模块的单独设置
+    if target == CRATE_TARGET {
+      levels.retain(|t, _| t == CRATE_TARGET);
+    }
+    levels.insert(target, level);
+    render_directives(&levels)
+  };
+  if let Some(handle) = FILTER_HANDLE.get() {
+    let filter = EnvFilter::try_new(&directives).map_err(|e| format!("日志级别配置无效: {}", e))?;
+    handle
+      .reload(filter)
+      .map_err(|e| format!("更新日志级别失败: {}", e))?;
+  }
+  Ok(directives)
 }
 
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn module_levels_render_as_directives() {
+    assert_eq!(
+      set_log_level(Some("services::pandoc_service"), "debug").unwrap(),
+      "warn,binder=info,binder::services::pandoc_service=debug"
+    );
+    assert_eq!(
+      set_log_level(Some("binder::services::ai_service"), "TRACE").unwrap(),
+      "warn,binder=info,binder::services::ai_service=trace,binder::services::pandoc_service=debug"
+    );
+    assert!(set_log_level(None, "verbose").is_err());
+    assert!(set_log_level(Some("services/../x"), "debug").is_err());
+    assert_eq!(set_log_level(None, "warn").unwrap(), "warn,binder=warn");
+  }
+
+  #[test]
+  fn recent_logs_are_filtered_newest_last() {
+    let subscriber = tracing_subscriber::registry().with(RecentLogLayer);
+    tracing::subscriber::with_default(subscriber, || {
+      tracing::info!(target: "binder::test_recent", "pandoc 开始转换");
+      tracing::warn!(target: "binder::test_recent", path = "a.docx", "Pandoc 转换失败: {}", 1);
+      tracing::error!(target: "binder::test_recent", "Pandoc 转换失败: {}", 2);
+      tracing::warn!(target: "binder::other", "Pandoc 转换失败: {}", 3);
+    });
+
+    let filter = LogFilter {
+      level: Some("warn".to_string()),
+      target: Some("binder::test_recent".to_string()),
+      query: Some("PANDOC".to_string()),
+    };
+    let logs = recent_logs(&filter, 10).unwrap();
+    let messages: Vec<&str> = logs.iter().map(|l| l.message.as_str()).collect();
+    assert_eq!(
+      messages,
+      vec!["Pandoc 转换失败: 1 path=a.docx", "Pandoc 转换失败: 2"]
+    );
+    assert_eq!(logs[1].level, "ERROR");
+    assert_eq!(
+      recent_logs(&filter, 1).unwrap()[0].message,
+      "Pandoc 转换失败: 2"
+    );
+    assert!(recent_logs(
+      &LogFilter {
+        level: Some("loud".to_string()),
+        ..Default::default()
+      },
+      1
+    )
+    .is_err());
+  }
+}
@@ -1,4 +1,10 @@
 // 工具函数模块
 
+pub mod command_guard;
+pub mod disk_space;
 pub mod error_helpers;
+pub mod glob_patterns;
+pub mod i18n;
+pub mod logger;
 pub mod path_validator;
+pub mod perf;
@@ -209,6 +209,31 @@ impl PathValidator {
     Ok(())
   }
 
+  /// 把标题等任意文本转换为可用的文件名主体：非法字符与控制字符替换为空格，合并空白，
+  /// 去掉首尾的点与空格，最多保留 max_chars 个字符；结果为空时返回 None，保留名称前加 `_`
+  pub fn sanitize_filename(text: &str, max_chars: usize) -> Option<String> {
+    let replaced: String = text
+      .chars()
+      .map(|c| {
+        if c.is_control() || matches!(c, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|') {
+          ' '
+        } else {
+          c
+        }
+      })
+      .collect();
+    let collapsed = replaced.split_whitespace().collect::<Vec<_>>().join(" ");
+    let truncated: String = collapsed.chars().take(max_chars).collect();
+    let name = truncated.trim_matches(|c: char| c == '.' || c.is_whitespace());
+    if name.is_empty() {
+      return None;
+    }
+    match Self::validate_filename(name) {
+      Ok(()) => Some(name.to_string()),
+      Err(_) => Some(format!("_{}", name)),
+    }
+  }
+
   /// 安全地拼接路径
   pub fn join_paths(base: &Path, relative: &str) -> Result<PathBuf, PathValidationError> {
     let sanitized = Self::sanitize_relative_path(Path::new(relative))?;
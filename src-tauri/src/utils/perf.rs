@@ -0,0 +1,212 @@
+//! 性能统计：记录本应用 tracing span 从创建到关闭的耗时，保存在内存环形缓冲区中，
+//! 供 `get_performance_report` 汇总（文件树构建、pandoc / LibreOffice 转换、索引构建、AI 调用等）。
+//!
+//! 只统计 `#[tracing::instrument]` 标注的函数；本应用日志级别高于 info 时这些 span 不会创建，也就不计时。
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Write as _;
+use std::sync::Mutex;
+use std::time::Instant;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id};
+use tracing::Subscriber;
+use tracing_subscriber::layer::{Context, Layer};
+use tracing_subscriber::registry::LookupSpan;
+
+const CRATE_TARGET: &str = "binder";
+/// 内存中保留的最近耗时记录条数
+const SAMPLE_CAPACITY: usize = 1000;
+/// 报告中附带的最近记录条数
+const REPORT_RECENT: usize = 50;
+
+static SAMPLES: Lazy<Mutex<VecDeque<PerfSample>>> = Lazy::new(Default::default);
+
+/// 一次操作的耗时
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PerfSample {
+  /// 模块名::函数名，如 `pandoc_service::convert_document_to_html`
+  pub operation: String,
+  /// span 上的字段，如 `path=/a/b.docx`
+  pub detail: String,
+  pub duration_ms: f64,
+  /// 结束时间（RFC 3339 本地时间）
+  pub finished_at: String,
+}
+
+/// 同一操作的耗时汇总
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OperationStats {
+  pub operation: String,
+  pub count: usize,
+  pub total_ms: f64,
+  pub avg_ms: f64,
+  pub p50_ms: f64,
+  pub p95_ms: f64,
+  pub max_ms: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PerformanceReport {
+  /// 按总耗时从高到低
+  pub operations: Vec<OperationStats>,
+  /// 最近的记录（按时间先后）
+  pub recent: Vec<PerfSample>,
+}
+
+/// 存放在 span 扩展中的开始时间与字段
+struct SpanTiming {
+  start: Instant,
+  detail: String,
+}
+
+#[derive(Default)]
+struct FieldVisitor(String);
+
+impl Visit for FieldVisitor {
+  fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+    let sep = if self.0.is_empty() { "" } else { " " };
+    let _ = write!(self.0, "{}{}={:?}", sep, field.name(), value);
+  }
+
+  fn record_str(&mut self, field: &Field, value: &str) {
+    let sep = if self.0.is_empty() { "" } else { " " };
+    let _ = write!(self.0, "{}{}={}", sep, field.name(), value);
+  }
+}
+
+/// 为本应用的 span 计时，关闭时写入环形缓冲区
+pub struct PerfLayer;
+
+impl<S> Layer<S> for PerfLayer
+where
+  S: Subscriber + for<'a> LookupSpan<'a>,
+{
+  fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+    if !attrs.metadata().target().starts_with(CRATE_TARGET) {
+      return;
+    }
+    let Some(span) = ctx.span(id) else {
+      return;
+    };
+    let mut visitor = FieldVisitor::default();
+    attrs.record(&mut visitor);
+    span.extensions_mut().insert(SpanTiming {
+      start: Instant::now(),
+      detail: visitor.0,
+    });
+  }
+
+  fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+    let Some(span) = ctx.span(&id) else {
+      return;
+    };
+    let extensions = span.extensions();
+    let Some(timing) = extensions.get::<SpanTiming>() else {
+      return;
+    };
+    let metadata = span.metadata();
+    let module = metadata.target().rsplit("::").next().unwrap_or_default();
+    record_sample(PerfSample {
+      operation: format!("{}::{}", module, metadata.name()),
+      detail: timing.detail.clone(),
+      duration_ms: timing.start.elapsed().as_secs_f64() * 1000.0,
+      finished_at: chrono::Local::now().to_rfc3339(),
+    });
+  }
+}
+
+fn record_sample(sample: PerfSample) {
+  if let Ok(mut samples) = SAMPLES.lock() {
+    if samples.len() >= SAMPLE_CAPACITY {
+      samples.pop_front();
+    }
+    samples.push_back(sample);
+  }
+}
+
+/// 已排序耗时的百分位数（最近秩法）
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+  let rank = ((p / 100.0) * sorted.len() as f64).ceil() as usize;
+  sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// 汇总缓冲区中的耗时记录；operation 不为空时只统计名称包含该文字的操作
+pub fn performance_report(operation: Option<&str>) -> Result<PerformanceReport, String> {
+  let samples = SAMPLES.lock().map_err(|e| e.to_string())?;
+  let matched: Vec<&PerfSample> = samples
+    .iter()
+    .filter(|s| operation.map_or(true, |op| s.operation.contains(op)))
+    .collect();
+
+  let mut durations: HashMap<&str, Vec<f64>> = HashMap::new();
+  for sample in &matched {
+    durations
+      .entry(sample.operation.as_str())
+      .or_default()
+      .push(sample.duration_ms);
+  }
+  let mut operations: Vec<OperationStats> = durations
+    .into_iter()
+    .map(|(operation, mut values)| {
+      values.sort_by(f64::total_cmp);
+      let total_ms: f64 = values.iter().sum();
+      OperationStats {
+        operation: operation.to_string(),
+        count: values.len(),
+        total_ms,
+        avg_ms: total_ms / values.len() as f64,
+        p50_ms: percentile(&values, 50.0),
+        p95_ms: percentile(&values, 95.0),
+        max_ms: values[values.len() - 1],
+      }
+    })
+    .collect();
+  operations.sort_by(|a, b| b.total_ms.total_cmp(&a.total_ms));
+
+  let skip = matched.len().saturating_sub(REPORT_RECENT);
+  let recent = matched.into_iter().skip(skip).cloned().collect();
+  Ok(PerformanceReport { operations, recent })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use tracing_subscriber::prelude::*;
+
+  #[tracing::instrument(skip_all, fields(path = path))]
+  fn perf_test_operation(path: &str) {
+    std::thread::sleep(std::time::Duration::from_millis(5));
+  }
+
+  #[test]
+  fn closed_spans_are_aggregated_by_operation() {
+    let subscriber = tracing_subscriber::registry().with(PerfLayer);
+    tracing::subscriber::with_default(subscriber, || {
+      for path in ["a.docx", "b.docx", "c.docx"] {
+        perf_test_operation(path);
+      }
+      // 依赖库的 span 不计时
+      tracing::info_span!(target: "other_crate", "perf_test_operation").in_scope(|| {});
+    });
+
+    let report = performance_report(Some("perf_test_operation")).unwrap();
+    assert_eq!(report.operations.len(), 1);
+    let stats = &report.operations[0];
+    assert_eq!(stats.operation, "tests::perf_test_operation");
+    assert_eq!(stats.count, 3);
+    assert!(stats.p50_ms >= 5.0);
+    assert!(stats.max_ms >= stats.p95_ms && stats.p95_ms >= stats.p50_ms);
+    assert_eq!(report.recent.len(), 3);
+    assert_eq!(report.recent[2].detail, "path=c.docx");
+  }
+
+  #[test]
+  fn percentile_uses_nearest_rank() {
+    let values = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0];
+    assert_eq!(percentile(&values, 50.0), 5.0);
+    assert_eq!(percentile(&values, 95.0), 10.0);
+    assert_eq!(percentile(&[3.0], 95.0), 3.0);
+  }
+}
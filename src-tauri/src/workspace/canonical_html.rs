@@ -167,7 +167,7 @@ pub fn materialize_cached_body_if_stale_hash(
       Some(hash.as_str()),
       mtime,
     )?;
-    eprintln!(
+    tracing::debug!(
       "[p4/canonical] upgraded cache (no hash): path={} len={} hash={}..",
       file_path,
       html.len(),
@@ -5,6 +5,7 @@
 pub mod canonical_html;
 pub mod canonical_service;
 pub mod diff_engine;
+pub mod patch_apply;
 pub mod timeline_support;
 pub mod workspace_commands;
 pub mod workspace_db;
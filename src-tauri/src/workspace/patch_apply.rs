@@ -0,0 +1,390 @@
+//! apply_patch 工具的补丁解析与应用
+//!
+//! 支持两种格式：unified diff（`@@ -l,n +l,n @@` 分块）和 SEARCH/REPLACE 块。
+//! 每处修改依次尝试：精确子串 → 逐行精确 → 忽略空白 → 行相似度，
+//! 全部成功才返回新内容；失败时附带最接近的原文片段，便于模型修正后重试。
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::Serialize;
+
+/// 行相似度匹配所需的最低相同行比例
+pub const FUZZY_LINE_RATIO: f64 = 0.75;
+
+static HUNK_HEADER_RE: Lazy<Regex> =
+  Lazy::new(|| Regex::new(r"^@@ -(\d+)(?:,(\d+))? \+\d+(?:,\d+)? @@").unwrap());
+static SEARCH_START_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^<{5,}\s*SEARCH\s*$").unwrap());
+static DIVIDER_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^={5,}\s*$").unwrap());
+static REPLACE_END_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^>{5,}\s*REPLACE\s*$").unwrap());
+
+/// 一处修改：把 `search` 行替换为 `replace` 行
+#[derive(Debug, Clone, PartialEq)]
+pub struct PatchEdit {
+  pub search: Vec<String>,
+  pub replace: Vec<String>,
+  /// 期望位置（0 起始行号），来自 unified diff 的 hunk 头，用于消歧和纯插入
+  pub line_hint: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchKind {
+  Exact,
+  Whitespace,
+  Fuzzy,
+  Insert,
+}
+
+#[derive(Debug, Clone)]
+pub struct PatchOutcome {
+  pub content: String,
+  pub matches: Vec<MatchKind>,
+}
+
+/// 应用失败：第几处修改（0 起始）、原因、最接近的原文片段（带行号）
+#[derive(Debug, Clone, Serialize)]
+pub struct PatchFailure {
+  pub edit_index: usize,
+  pub reason: String,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub closest_context: Option<String>,
+}
+
+/// 解析补丁文本；自动识别 SEARCH/REPLACE 块或 unified diff
+pub fn parse_patch(patch: &str) -> Result<Vec<PatchEdit>, String> {
+  let patch = patch.replace("\r\n", "\n");
+  let edits = if patch.lines().any(|l| SEARCH_START_RE.is_match(l)) {
+    parse_search_replace(&patch)?
+  } else if patch.lines().any(|l| l.starts_with("@@")) {
+    parse_unified_diff(&patch)?
+  } else {
+    return Err("无法识别的补丁格式：请使用 unified diff 或 SEARCH/REPLACE 块".to_string());
+  };
+  if edits.is_empty() {
+    return Err("补丁中没有任何修改".to_string());
+  }
+  Ok(edits)
+}
+
+fn parse_search_replace(patch: &str) -> Result<Vec<PatchEdit>, String> {
+  enum State {
+    Outside,
+    Search,
+    Replace,
+  }
+  let mut edits = Vec::new();
+  let mut state = State::Outside;
+  let mut search = Vec::new();
+  let mut replace = Vec::new();
+
+  for line in patch.lines() {
+    match state {
+      State::Outside => {
+        if SEARCH_START_RE.is_match(line) {
+          state = State::Search;
+        }
+      }
+      State::Search => {
+        if DIVIDER_RE.is_match(line) {
+          state = State::Replace;
+        } else {
+          search.push(line.to_string());
+        }
+      }
+      State::Replace => {
+        if REPLACE_END_RE.is_match(line) {
+          edits.push(PatchEdit {
+            search: std::mem::take(&mut search),
+            replace: std::mem::take(&mut replace),
+            line_hint: None,
+          });
+          state = State::Outside;
+        } else {
+          replace.push(line.to_string());
+        }
+      }
+    }
+  }
+  if !matches!(state, State::Outside) {
+    return Err(format!(
+      "第 {} 个 SEARCH/REPLACE 块不完整：缺少 ======= 或 >>>>>>> REPLACE",
+      edits.len() + 1
+    ));
+  }
+  Ok(edits)
+}
+
+fn parse_unified_diff(patch: &str) -> Result<Vec<PatchEdit>, String> {
+  let mut edits = Vec::new();
+  let mut current: Option<PatchEdit> = None;
+
+  for line in patch.lines() {
+    if let Some(caps) = HUNK_HEADER_RE.captures(line) {
+      edits.extend(current.take());
+      let old_start: usize = caps[1].parse().unwrap_or(1);
+      let old_count: usize = caps
+        .get(2)
+        .and_then(|m| m.as_str().parse().ok())
+        .unwrap_or(1);
+      // 纯插入 hunk（旧行数为 0）的 old_start 指插入点之前的行
+      let hint = if old_count == 0 {
+        old_start
+      } else {
+        old_start.saturating_sub(1)
+      };
+      current = Some(PatchEdit {
+        search: Vec::new(),
+        replace: Vec::new(),
+        line_hint: Some(hint),
+      });
+      continue;
+    }
+    if line.starts_with("@@") {
+      return Err(format!("无效的 hunk 头: {}", line));
+    }
+    let Some(edit) = current.as_mut() else {
+      continue; // 文件头（---/+++/diff --git 等）
+    };
+    if let Some(rest) = line.strip_prefix('-') {
+      edit.search.push(rest.to_string());
+    } else if let Some(rest) = line.strip_prefix('+') {
+      edit.replace.push(rest.to_string());
+    } else if line.starts_with('\\') {
+      // "\ No newline at end of file"
+    } else {
+      let rest = line.strip_prefix(' ').unwrap_or(line);
+      edit.search.push(rest.to_string());
+      edit.replace.push(rest.to_string());
+    }
+  }
+  edits.extend(current);
+  Ok(edits)
+}
+
+/// 依次应用全部修改；任何一处失败都不返回部分结果
+pub fn apply_patch(content: &str, edits: &[PatchEdit]) -> Result<PatchOutcome, PatchFailure> {
+  let crlf = content.contains("\r\n");
+  let mut text = content.to_string();
+  let mut matches = Vec::with_capacity(edits.len());
+  // 前面的修改改变了行数，后续 hunk 的行号提示随之平移
+  let mut line_shift: isize = 0;
+
+  for (index, edit) in edits.iter().enumerate() {
+    let hint = edit
+      .line_hint
+      .map(|h| (h as isize + line_shift).max(0) as usize);
+    let (next, kind) =
+      apply_edit(&text, edit, hint, crlf).map_err(|(reason, closest_context)| PatchFailure {
+        edit_index: index,
+        reason,
+        closest_context,
+      })?;
+    line_shift += edit.replace.len() as isize - edit.search.len() as isize;
+    text = next;
+    matches.push(kind);
+  }
+  Ok(PatchOutcome {
+    content: text,
+    matches,
+  })
+}
+
+type EditError = (String, Option<String>);
+type LineEq = fn(&str, &str) -> bool;
+
+fn apply_edit(
+  text: &str,
+  edit: &PatchEdit,
+  hint: Option<usize>,
+  crlf: bool,
+) -> Result<(String, MatchKind), EditError> {
+  let eol = if crlf { "\r\n" } else { "\n" };
+  let mut lines: Vec<&str> = text.split('\n').collect();
+  let replacement: Vec<String> = edit
+    .replace
+    .iter()
+    .map(|l| if crlf { format!("{}\r", l) } else { l.clone() })
+    .collect();
+
+  // 纯插入
+  if edit.search.iter().all(|l| l.trim().is_empty()) && edit.search.len() <= 1 {
+    if text.is_empty() {
+      return Ok((edit.replace.join(eol), MatchKind::Insert));
+    }
+    let Some(at) = hint else {
+      return Err((
+        "SEARCH 内容为空：插入新内容时请使用带行号的 unified diff，或提供插入点附近的原文"
+          .to_string(),
+        None,
+      ));
+    };
+    let at = at.min(lines.len());
+    let mut out: Vec<String> = lines[..at].iter().map(|s| s.to_string()).collect();
+    out.extend(replacement);
+    out.extend(lines[at..].iter().map(|s| s.to_string()));
+    return Ok((out.join("\n"), MatchKind::Insert));
+  }
+
+  // 1. 精确子串（可匹配行内片段）
+  let search_text = edit.search.join("\n");
+  let positions: Vec<usize> = text.match_indices(&search_text).map(|(i, _)| i).collect();
+  if positions.len() == 1 {
+    let at = positions[0];
+    let mut out = String::with_capacity(text.len());
+    out.push_str(&text[..at]);
+    out.push_str(&edit.replace.join("\n"));
+    out.push_str(&text[at + search_text.len()..]);
+    return Ok((out, MatchKind::Exact));
+  }
+
+  // 2-4. 逐行匹配：精确 → 忽略空白 → 相似度
+  let n = edit.search.len();
+  let strip_cr = |s: &str| s.trim_end_matches('\r').to_string();
+  let tiers: [(MatchKind, LineEq); 2] = [
+    (MatchKind::Exact, |a, b| a == b),
+    (MatchKind::Whitespace, |a, b| {
+      normalize_ws(a) == normalize_ws(b)
+    }),
+  ];
+  for (kind, eq) in tiers {
+    let candidates: Vec<usize> = windows(lines.len(), n)
+      .filter(|&start| (0..n).all(|i| eq(&strip_cr(lines[start + i]), edit.search[i].as_str())))
+      .collect();
+    if let Some(start) = pick_candidate(&candidates, hint)? {
+      lines.splice(start..start + n, replacement.iter().map(|s| s.as_str()));
+      return Ok((lines.join("\n"), kind));
+    }
+  }
+
+  let scored: Vec<(usize, f64)> = windows(lines.len(), n)
+    .map(|start| {
+      let same = (0..n)
+        .filter(|&i| normalize_ws(&strip_cr(lines[start + i])) == normalize_ws(&edit.search[i]))
+        .count();
+      (start, same as f64 / n as f64)
+    })
+    .collect();
+  let best = scored.iter().map(|(_, r)| *r).fold(0.0, f64::max);
+  if n >= 2 && best >= FUZZY_LINE_RATIO {
+    let candidates: Vec<usize> = scored
+      .iter()
+      .filter(|(_, r)| *r == best)
+      .map(|(s, _)| *s)
+      .collect();
+    if let Some(start) = pick_candidate(&candidates, hint)? {
+      lines.splice(start..start + n, replacement.iter().map(|s| s.as_str()));
+      return Ok((lines.join("\n"), MatchKind::Fuzzy));
+    }
+  }
+
+  let closest = scored
+    .iter()
+    .filter(|(_, r)| *r > 0.0)
+    .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal))
+    .map(|(start, _)| numbered_context(&lines, *start, n));
+  Err((
+    "在文件中找不到要修改的原文，请根据最接近的片段修正 SEARCH/上下文后重试".to_string(),
+    closest,
+  ))
+}
+
+/// 长度为 n 的窗口的所有起始行
+fn windows(total: usize, n: usize) -> std::ops::Range<usize> {
+  if n == 0 || n > total {
+    0..0
+  } else {
+    0..total - n + 1
+  }
+}
+
+/// 多处命中时按行号提示取最近的一处；没有提示则视为歧义
+fn pick_candidate(candidates: &[usize], hint: Option<usize>) -> Result<Option<usize>, EditError> {
+  match (candidates.len(), hint) {
+    (0, _) => Ok(None),
+    (1, _) => Ok(Some(candidates[0])),
+    (_, Some(h)) => Ok(candidates.iter().copied().min_by_key(|&s| s.abs_diff(h))),
+    (count, None) => Err((
+      format!(
+        "要修改的原文在文件中出现了 {} 次，请在 SEARCH 中加入更多上下文以唯一定位",
+        count
+      ),
+      None,
+    )),
+  }
+}
+
+fn normalize_ws(s: &str) -> String {
+  s.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// 带行号的片段（前后各多给 2 行）
+fn numbered_context(lines: &[&str], start: usize, len: usize) -> String {
+  let from = start.saturating_sub(2);
+  let to = (start + len + 2).min(lines.len());
+  (from..to)
+    .map(|i| format!("{:>5}| {}", i + 1, lines[i].trim_end_matches('\r')))
+    .collect::<Vec<_>>()
+    .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn search_replace_blocks_apply_with_whitespace_tolerance() {
+    let content = "fn main() {\n    let a = 1;\n    let b = 2;\n}\n";
+    let patch = "<<<<<<< SEARCH\nlet a = 1;\n  let b   = 2;\n=======\n    let a = 10;\n    let b = 20;\n>>>>>>> REPLACE\n";
+    let edits = parse_patch(patch).expect("parse");
+    let outcome = apply_patch(content, &edits).expect("apply");
+    assert_eq!(
+      outcome.content,
+      "fn main() {\n    let a = 10;\n    let b = 20;\n}\n"
+    );
+    assert_eq!(outcome.matches, vec![MatchKind::Whitespace]);
+  }
+
+  #[test]
+  fn unified_diff_uses_line_hint_for_repeated_context() {
+    let content = "title\nitem\nend\ntitle\nitem\nend\n";
+    let patch =
+      "--- a/list.md\n+++ b/list.md\n@@ -4,3 +4,3 @@\n title\n-item\n+second item\n end\n";
+    let edits = parse_patch(patch).expect("parse");
+    assert_eq!(edits[0].line_hint, Some(3));
+    let outcome = apply_patch(content, &edits).expect("apply");
+    assert_eq!(
+      outcome.content,
+      "title\nitem\nend\ntitle\nsecond item\nend\n"
+    );
+  }
+
+  #[test]
+  fn fuzzy_match_tolerates_one_stale_line_and_keeps_crlf() {
+    let content = "a\r\nb\r\nc\r\nd\r\n";
+    let edits = vec![PatchEdit {
+      search: vec!["a".into(), "b".into(), "c".into(), "x".into()],
+      replace: vec!["A".into()],
+      line_hint: None,
+    }];
+    let outcome = apply_patch(content, &edits).expect("apply");
+    assert_eq!(outcome.content, "A\r\n");
+    assert_eq!(outcome.matches, vec![MatchKind::Fuzzy]);
+  }
+
+  #[test]
+  fn failure_reports_closest_context_and_ambiguity() {
+    let content = "one\ntwo\nthree\nfour\n";
+    let edits = parse_patch("<<<<<<< SEARCH\ntwo\nTHREE!\n=======\n2\n>>>>>>> REPLACE").unwrap();
+    let failure = apply_patch(content, &edits).expect_err("should fail");
+    assert_eq!(failure.edit_index, 0);
+    let context = failure.closest_context.expect("closest context");
+    assert!(context.contains("    2| two"));
+
+    let repeated = parse_patch("<<<<<<< SEARCH\nx\n=======\ny\n>>>>>>> REPLACE").unwrap();
+    let failure = apply_patch("x\nx\n", &repeated).expect_err("ambiguous");
+    assert!(failure.reason.contains("2 次"));
+
+    assert!(parse_patch("just some text").is_err());
+    assert!(parse_patch("<<<<<<< SEARCH\na\n=======\nb\n").is_err());
+  }
+}
@@ -3,6 +3,8 @@
 //! open_file_with_cache、open_docx_with_cache、ai_edit_file_with_diff、accept_file_diffs、reject_file_diffs
 
 use crate::commands::file_commands::{open_docx_for_edit, read_file_content};
+use crate::services::html_sanitizer::sanitize_html_document;
+use crate::services::settings_service;
 use crate::utils::path_validator::PathValidator;
 use crate::workspace::canonical_html::{
   canonical_html_for_workspace_cache, content_hash_hex, inject_blockids_for_plain_text, inspect_block_id_map,
@@ -22,10 +24,10 @@ use crate::workspace::workspace_db::{
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
 use tauri::{Emitter, State};
 
-type AIServiceState = Arc<Mutex<crate::services::ai_service::AIService>>;
+type AIServiceState = Arc<crate::services::ai_service::AIService>;
 
 /// open_file_with_cache 返回结构
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -220,7 +222,7 @@ pub async fn open_file_with_cache(
           Some(hash.as_str()),
           mtime,
         )?;
-        eprintln!(
+        tracing::debug!(
           "[p4/canonical] open_file_with_cache: path={} len={}",
           file_path,
           html.len()
@@ -300,23 +302,31 @@ pub async fn open_docx_with_cache(
   let cached = db.get_file_cache(&file_path)?;
   let mut route_scene = "4".to_string();
   let content = match &cached {
-    Some(entry) if entry.mtime == mtime => materialize_cached_body_if_stale_hash(
-      &db,
-      &file_path,
-      "docx",
-      entry.cached_content.clone(),
-      entry.content_hash.clone(),
-      mtime,
-    )?,
+    Some(entry) if entry.mtime == mtime => {
+      let html = materialize_cached_body_if_stale_hash(
+        &db,
+        &file_path,
+        "docx",
+        entry.cached_content.clone(),
+        entry.content_hash.clone(),
+        mtime,
+      )?;
+      // 缓存可能由旧版本或信任开关打开时写入，每次读取都重新清理
+      if settings_service::document_html_trusted(Path::new(&workspace_path)) {
+        html
+      } else {
+        sanitize_html_document(&html)
+      }
+    }
     _ => {
       route_scene = "5".to_string();
       let path_str = full_path.to_string_lossy().to_string();
-      let raw = open_docx_for_edit(path_str)
+      let raw = open_docx_for_edit(path_str, Some(workspace_path.clone()))
         .await
         .map_err(|e| format!("打开 DOCX 失败: {}", e))?;
       let (html, hash) = canonical_html_for_workspace_cache(&raw);
       db.upsert_file_cache(&file_path, "docx", Some(&html), Some(hash.as_str()), mtime)?;
-      eprintln!(
+      tracing::debug!(
         "[p4/canonical] open_docx_with_cache: path={} len={}",
         file_path,
         html.len()
@@ -496,7 +506,7 @@ pub async fn accept_file_diffs(
         d.diff_index, d.para_index, d.diff_type, orig_preview, new_preview
       )
     }).collect();
-    eprintln!(
+    tracing::debug!(
       "[CROSS_FILE_TRACE][WRITE] {{\"op\":\"accept_file_diffs:pre-apply\",\"file_path\":{:?},\"workspace_path\":{:?},\"diff_count\":{},\"diff_indices\":{:?},\"diffs\":[{}]}}",
       file_path,
       workspace_path,
@@ -504,7 +514,7 @@ pub async fn accept_file_diffs(
       diff_indices,
       diffs_summary.join(","),
     );
-    eprintln!(
+    tracing::debug!(
       "[CROSS_FILE_TRACE][BACKEND_ACCEPT] {{\"phase\":\"query\",\"queried_file_path\":{:?},\"returned_count\":{},\"record_file_paths\":{:?},\"workspace_path\":{:?},\"tool_call_id\":null,\"message_id\":null}}",
       file_path,
       diffs.len(),
@@ -525,7 +535,7 @@ pub async fn accept_file_diffs(
   let before_dedupe = diffs.len();
   diffs = dedupe_pending_diffs_by_index_keep_latest(diffs);
   if before_dedupe != diffs.len() {
-    eprintln!(
+    tracing::debug!(
       "[CROSS_FILE_TRACE][BACKEND_ACCEPT] {{\"phase\":\"dedupe\",\"queried_file_path\":{:?},\"before\":{},\"after\":{},\"reason\":\"duplicate_diff_index\"}}",
       file_path,
       before_dedupe,
@@ -551,7 +561,7 @@ pub async fn accept_file_diffs(
     None => {
       let path_str = full_path.to_string_lossy().to_string();
       let raw = if file_type == "docx" {
-        open_docx_for_edit(path_str)
+        open_docx_for_edit(path_str, Some(workspace_path.clone()))
           .await
           .map_err(|e| format!("读取 DOCX 失败: {}", e))?
       } else {
@@ -573,7 +583,7 @@ pub async fn accept_file_diffs(
 
   let (body_to_store, content_hash_opt) = if should_run_workspace_canonical_pipeline(&file_type) {
     let (html, hash) = canonical_html_for_workspace_cache(&final_content);
-    eprintln!(
+    tracing::debug!(
       "[p4/canonical] accept_file_diffs: path={} cache_len={}",
       file_path,
       html.len()
@@ -584,7 +594,7 @@ pub async fn accept_file_diffs(
   };
   let after_hash = content_hash_hex(&body_to_store);
   let after_len = body_to_store.len();
-  eprintln!(
+  tracing::debug!(
     "[CROSS_FILE_TRACE][BACKEND_ACCEPT] {{\"phase\":\"apply_ready\",\"queried_file_path\":{:?},\"write_target_full_path\":{:?},\"write_target_relative\":{:?},\"record_paths_all_match_query\":{},\"before_len\":{},\"before_hash\":{:?},\"after_len\":{},\"after_hash\":{:?}}}",
     file_path,
     full_path.to_string_lossy(),
@@ -611,14 +621,14 @@ pub async fn accept_file_diffs(
 
   {
     let final_preview: String = body_to_store.chars().take(80).collect();
-    eprintln!(
+    tracing::debug!(
       "[CROSS_FILE_TRACE][WRITE] {{\"op\":\"accept_file_diffs:post-write\",\"file_path\":{:?},\"full_path\":{:?},\"final_content_len\":{},\"final_content_preview\":{:?}}}",
       file_path,
       full_path.to_string_lossy(),
       body_to_store.len(),
       final_preview,
     );
-    eprintln!(
+    tracing::debug!(
       "[CROSS_FILE_TRACE][BACKEND_ACCEPT] {{\"phase\":\"post_write\",\"queried_file_path\":{:?},\"write_target_full_path\":{:?},\"before_len\":{},\"before_hash\":{:?},\"after_len\":{},\"after_hash\":{:?}}}",
       file_path,
       full_path.to_string_lossy(),
@@ -695,13 +705,9 @@ pub async fn sync_workspace_file_cache_after_save(
     };
     if is_text && !html_content.trim().is_empty() {
       // 获取 AI provider（fire-and-forget，失败静默）
-      let provider_opt = {
-        let guard = service.lock().ok();
-        guard.and_then(|g| {
-          g.get_provider("deepseek")
-            .or_else(|| g.get_provider("openai"))
-        })
-      };
+      let provider_opt = service
+        .get_provider("deepseek")
+        .or_else(|| service.get_provider("openai"));
       if let Some(provider) = provider_opt {
         let ws = std::path::PathBuf::from(workspace_path.clone());
         let fp = file_absolute_path.clone();
@@ -718,12 +724,12 @@ pub async fn sync_workspace_file_cache_after_save(
             .as_secs() as i64;
           if let Some(t) = svc.get_last_content_extraction_time(&fp).await {
             if now - t < 60 {
-              eprintln!("[memory] content extraction skipped: cooldown for {}", fp);
+              tracing::debug!("[memory] content extraction skipped: cooldown for {}", fp);
               return;
             }
           }
           drop(svc); // release before task
-          eprintln!("[memory] content extraction triggered for {}", fp);
+          tracing::debug!("[memory] content extraction triggered for {}", fp);
           crate::services::memory_service::memory_generation_task_content(provider, ws, fp, html)
             .await;
         });
@@ -752,7 +758,7 @@ pub async fn sync_workspace_file_cache_after_save(
 
   let cache_key = sync_cache_key(&workspace_path, &rel);
   if should_skip_duplicate_sync(&cache_key, &html_content) {
-    eprintln!(
+    tracing::debug!(
       "[p4/canonical] sync after editor save skipped (duplicate content) rel={}",
       rel
     );
@@ -764,7 +770,7 @@ pub async fn sync_workspace_file_cache_after_save(
     if !html_content.trim().is_empty() {
       db.upsert_file_cache(&rel, &file_type, Some(&html_content), None, mtime)?;
       record_sync_success(&cache_key, &html_content);
-      eprintln!(
+      tracing::debug!(
         "[p4/canonical] sync after editor save (non-canonical) rel={} len={}",
         rel,
         html_content.len()
@@ -775,7 +781,7 @@ pub async fn sync_workspace_file_cache_after_save(
   let (body, hash) = canonical_html_for_workspace_cache(&html_content);
   db.upsert_file_cache(&rel, &file_type, Some(&body), Some(hash.as_str()), mtime)?;
   record_sync_success(&cache_key, &html_content);
-  eprintln!(
+  tracing::debug!(
     "[p4/canonical] sync after editor save rel={} len={}",
     rel,
     body.len()
@@ -2,7 +2,7 @@
 //!
 //! 存储路径：.binder/workspace.db（位于 workspace 根目录下）
 
-use rusqlite::{params, Connection};
+use rusqlite::{params, Connection, OptionalExtension};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::path::{Path, PathBuf};
@@ -13,7 +13,9 @@ use crate::services::template::{
   WorkflowTemplate, WorkflowTemplateDocument, WorkflowTemplateStatus,
 };
 
-const SCHEMA_VERSION: i32 = 8;
+const SCHEMA_VERSION: i32 = 10;
+/// 旧版文件来源记录（迁移到 file_sources 表后重命名为 .migrated）
+const LEGACY_FILES_METADATA: &str = "files_metadata.json";
 
 /// 文件缓存条目
 #[derive(Debug, Clone)]
@@ -410,6 +412,47 @@ impl WorkspaceDb {
         .map_err(|e| format!("执行 migration 8 失败: {}", e))?;
     }
 
+    if version < 9 {
+      // 导入与建表放在同一事务中，导入失败时下次启动重试
+      let tx = conn
+        .unchecked_transaction()
+        .map_err(|e| format!("执行 migration 9 失败: {}", e))?;
+      tx.execute_batch(
+        r#"
+                CREATE TABLE IF NOT EXISTS file_sources (
+                    file_path TEXT PRIMARY KEY,
+                    source TEXT NOT NULL,
+                    created_at INTEGER NOT NULL
+                );
+
+                INSERT INTO _schema_version (version) VALUES (9);
+                "#,
+      )
+      .map_err(|e| format!("执行 migration 9 失败: {}", e))?;
+      let legacy = self
+        .workspace_path
+        .join(".binder")
+        .join(LEGACY_FILES_METADATA);
+      let imported = Self::import_legacy_files_metadata(&tx, &legacy)?;
+      tx.commit()
+        .map_err(|e| format!("执行 migration 9 失败: {}", e))?;
+      if imported.is_some() {
+        if let Err(e) = std::fs::rename(&legacy, legacy.with_extension("json.migrated")) {
+          tracing::warn!("重命名 {} 失败: {}", legacy.display(), e);
+        }
+      }
+    }
+
+    if version < 10 {
+      // 记忆与标签的表结构统一由这里维护：补齐 migration 3 之后新增的列与索引，
+      // MemoryService 不再在打开时自行建表
+      crate::services::memory_service::ensure_workspace_memory_schema(&conn)
+        .map_err(|e| format!("执行 migration 10 失败: {}", e))?;
+      conn
+        .execute("INSERT INTO _schema_version (version) VALUES (10)", [])
+        .map_err(|e| format!("写入 schema 版本 10 失败: {}", e))?;
+    }
+
     let _ = SCHEMA_VERSION;
 
     Ok(())
@@ -507,7 +550,7 @@ impl WorkspaceDb {
       )
       .map_err(|e| format!("replace pending_diffs 失败: {}", e))?;
     if replaced > 0 {
-      eprintln!(
+      tracing::debug!(
         "[CROSS_FILE_TRACE][DB] {{\"op\":\"insert_pending_diffs:replace_existing\",\"file_path\":{:?},\"deleted\":{}}}",
         file_path,
         replaced,
@@ -580,7 +623,7 @@ impl WorkspaceDb {
           d.id, d.file_path, d.diff_index, d.para_index, d.diff_type, orig_preview, new_preview
         )
       }).collect();
-      eprintln!(
+      tracing::debug!(
         "[CROSS_FILE_TRACE][DB] {{\"op\":\"get_pending_diffs\",\"queried_file_path\":{:?},\"returned_count\":{},\"records\":[{}]}}",
         file_path,
         result.len(),
@@ -666,6 +709,79 @@ impl WorkspaceDb {
     &self.workspace_path
   }
 
+  /// 导入旧版 files_metadata.json（`{相对路径: {source, created_at}}`），返回导入条数；
+  /// 文件不存在时返回 None，内容损坏时跳过并保留原文件
+  fn import_legacy_files_metadata(
+    conn: &Connection,
+    legacy: &Path,
+  ) -> Result<Option<usize>, String> {
+    let Ok(content) = std::fs::read_to_string(legacy) else {
+      return Ok(None);
+    };
+    let entries: std::collections::HashMap<String, Value> = match serde_json::from_str(&content) {
+      Ok(entries) => entries,
+      Err(e) => {
+        tracing::warn!("{} 已损坏，跳过导入: {}", legacy.display(), e);
+        return Ok(None);
+      }
+    };
+    let now = chrono::Utc::now().timestamp();
+    let mut imported = 0;
+    for (file_path, entry) in &entries {
+      let Some(source) = entry.get("source").and_then(|s| s.as_str()) else {
+        continue;
+      };
+      let created_at = entry
+        .get("created_at")
+        .and_then(|t| t.as_i64())
+        .unwrap_or(now);
+      imported += conn
+        .execute(
+          "INSERT OR IGNORE INTO file_sources (file_path, source, created_at) VALUES (?1, ?2, ?3)",
+          params![file_path, source, created_at],
+        )
+        .map_err(|e| format!("导入文件来源记录失败: {}", e))?;
+    }
+    tracing::info!("已从 {} 导入 {} 条文件来源记录", legacy.display(), imported);
+    Ok(Some(imported))
+  }
+
+  /// 记录 Binder 创建的文件的来源（"new" / "ai_generated"），file_path 为工作区相对路径
+  pub fn set_file_source(&self, file_path: &str, source: &str) -> Result<(), String> {
+    let conn = self.conn.lock().map_err(|e| format!("锁失败: {}", e))?;
+    let now = chrono::Utc::now().timestamp();
+    conn
+      .execute(
+        "INSERT INTO file_sources (file_path, source, created_at) VALUES (?1, ?2, ?3)
+         ON CONFLICT(file_path) DO UPDATE SET source = excluded.source, created_at = excluded.created_at",
+        params![file_path, source, now],
+      )
+      .map_err(|e| format!("写入文件来源失败: {}", e))?;
+    Ok(())
+  }
+
+  pub fn get_file_source(&self, file_path: &str) -> Result<Option<String>, String> {
+    let conn = self.conn.lock().map_err(|e| format!("锁失败: {}", e))?;
+    conn
+      .query_row(
+        "SELECT source FROM file_sources WHERE file_path = ?1",
+        params![file_path],
+        |row| row.get(0),
+      )
+      .optional()
+      .map_err(|e| format!("读取文件来源失败: {}", e))
+  }
+
+  pub fn remove_file_source(&self, file_path: &str) -> Result<usize, String> {
+    let conn = self.conn.lock().map_err(|e| format!("锁失败: {}", e))?;
+    conn
+      .execute(
+        "DELETE FROM file_sources WHERE file_path = ?1",
+        params![file_path],
+      )
+      .map_err(|e| format!("删除文件来源失败: {}", e))
+  }
+
   fn workflow_template_status_from_db(status: &str) -> WorkflowTemplateStatus {
     match status {
       "active" => WorkflowTemplateStatus::Active,
@@ -1,6 +1,13 @@
 import { useEffect } from 'react';
+import { invoke } from '@tauri-apps/api/core';
 import MainLayout from "./components/Layout/MainLayout";
 import { useThemeStore } from "./stores/themeStore";
+import { updateService } from "./services/updateService";
+import { bufferJournalService } from "./services/bufferJournalService";
+import { documentService } from "./services/documentService";
+import { useEditorStore } from "./stores/editorStore";
+import { toErrorMessage } from "./utils/errorHandler";
+import { toast } from "./components/Common/Toast";
 
 function App() {
   const { theme } = useThemeStore();
@@ -24,6 +31,64 @@ function App() {
     }
   }, [theme]);
 
+  // 后端错误与进度消息按系统语言本地化
+  useEffect(() => {
+    invoke('set_locale', { locale: navigator.language }).catch((error) => {
+      console.warn('设置后端语言失败:', error);
+    });
+  }, []);
+
+  // 恢复上次异常退出前未保存的内容，之后定时记录未保存的修改
+  useEffect(() => {
+    let stop: (() => void) | undefined;
+    let cancelled = false;
+    const recover = async () => {
+      const buffers = await bufferJournalService.listRecoverable().catch((error) => {
+        console.warn('读取未保存内容日志失败:', error);
+        return [];
+      });
+      if (buffers.length > 0) {
+        const names = buffers.map((b) => b.path.split(/[\\/]/).pop()).join('\n');
+        const restore = window.confirm(`以下文件有上次未保存的内容，是否恢复？\n\n${names}`);
+        for (const buffer of buffers) {
+          if (!restore) {
+            await bufferJournalService.discard(buffer.path).catch(() => undefined);
+            continue;
+          }
+          try {
+            await documentService.openFile(buffer.path);
+            const tab = useEditorStore.getState().getTabByFilePath(buffer.path);
+            if (tab) useEditorStore.getState().updateTabContent(tab.id, buffer.content);
+          } catch (error) {
+            toast.error(`恢复未保存内容失败: ${toErrorMessage(error)}`);
+          }
+        }
+      }
+      if (!cancelled) stop = bufferJournalService.start();
+    };
+    void recover();
+    return () => {
+      cancelled = true;
+      stop?.();
+    };
+  }, []);
+
+  // 新版本在后台下载完成后提示重启安装
+  useEffect(() => {
+    const unlisten = updateService.onUpdateReady((info) => {
+      const notes = info.notes ? `\n\n${info.notes}` : '';
+      if (!window.confirm(`新版本 ${info.version} 已下载完成，是否立即重启并安装？${notes}`)) {
+        return;
+      }
+      updateService.installUpdate().catch((error) => {
+        toast.error(`安装更新失败: ${toErrorMessage(error)}`);
+      });
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
   return <MainLayout />;
 }
 
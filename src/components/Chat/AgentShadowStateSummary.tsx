@@ -3,6 +3,7 @@ import { useAgentStore } from '../../stores/agentStore';
 import { useFileStore } from '../../stores/fileStore';
 import { templateService } from '../../services/templateService';
 import { toast } from '../Common/Toast';
+import { toErrorMessage } from '../../utils/errorHandler';
 
 interface AgentShadowStateSummaryProps {
   chatTabId: string;
@@ -76,7 +77,7 @@ export const AgentShadowStateSummary: React.FC<AgentShadowStateSummaryProps> = (
       }
       useAgentStore.getState().setWorkflowExecution(chatTabId, nextRuntime);
     } catch (error) {
-      toast.error(`更新执行状态失败: ${error instanceof Error ? error.message : String(error)}`);
+      toast.error(`更新执行状态失败: ${toErrorMessage(error)}`);
     }
   };
 
@@ -23,6 +23,7 @@ import { UnopenedDocumentDiffRuntime } from '../../services/unopenedDocumentDiff
 import { DiffRetryController } from '../../services/DiffRetryController';
 import type { KnowledgeInjectionSlice } from '../../types/knowledge';
 import { getAbsolutePath, normalizePath, normalizeWorkspacePath } from '../../utils/pathUtils';
+import { toErrorMessage } from '../../utils/errorHandler';
 import './InlineChatInput.css';
 
 interface ChatMessagesProps {
@@ -98,20 +99,12 @@ export const ChatMessages: React.FC<ChatMessagesProps> = ({
             return;
         }
 
-        const nextArguments = {
-            ...toolCall.arguments,
-            _confirmation_action: action,
-            _confirmation_id: recordId,
-        };
-
         const nextToolCall: ToolCall = {
             ...toolCall,
-            arguments: nextArguments,
             status: action === 'confirm' ? 'executing' : 'pending',
         };
 
         updateToolCall(tabId, messageId, toolCall.id, {
-            arguments: nextArguments,
             status: action === 'confirm' ? 'executing' : 'pending',
         });
         updateContentBlock(tabId, messageId, blockId, {
@@ -121,18 +114,17 @@ export const ChatMessages: React.FC<ChatMessagesProps> = ({
         });
 
         try {
-            const command = action === 'confirm' ? 'execute_tool_with_retry' : 'execute_tool';
-            const payload = {
+            // 确认记录由后端签发且只能使用一次，只能经由该命令批准或拒绝
+            const result = await invoke<ToolResult>('resolve_tool_confirmation', {
                 toolCall: {
                     id: toolCall.id,
                     name: toolCall.name,
-                    arguments: nextArguments,
+                    arguments: toolCall.arguments,
                 },
                 workspacePath: currentWorkspace,
-                ...(action === 'confirm' ? { maxRetries: 3 } : {}),
-            };
-
-            const result = await invoke<ToolResult>(command, payload);
+                confirmationId: recordId,
+                approved: action === 'confirm',
+            });
             const awaiting = result.meta?.gate?.status === 'awaiting_confirmation';
             const status: ToolCall['status'] = awaiting
                 ? 'pending'
@@ -141,7 +133,6 @@ export const ChatMessages: React.FC<ChatMessagesProps> = ({
                     : 'failed';
             const finalToolCall: ToolCall = {
                 ...toolCall,
-                arguments: nextArguments,
                 status,
                 result,
                 error: result.error,
@@ -149,7 +140,6 @@ export const ChatMessages: React.FC<ChatMessagesProps> = ({
             const stillAwaiting = isAwaitingAuthorization(finalToolCall, currentWorkspace ?? undefined);
 
             updateToolCall(tabId, messageId, toolCall.id, {
-                arguments: nextArguments,
                 status,
                 result,
                 error: result.error,
@@ -164,7 +154,7 @@ export const ChatMessages: React.FC<ChatMessagesProps> = ({
                 toast.info('已拒绝该工具操作。');
             }
         } catch (error) {
-            const message = error instanceof Error ? error.message : String(error);
+            const message = toErrorMessage(error);
             updateToolCall(tabId, messageId, toolCall.id, {
                 status: 'failed',
                 error: message,
@@ -756,7 +746,7 @@ export const ChatMessages: React.FC<ChatMessagesProps> = ({
                                                             toast.warning(result.toastMessage);
                                                         }
                                                     } catch (error) {
-                                                        toast.error(`写盘失败（后端）：${error instanceof Error ? error.message : String(error)}`);
+                                                        toast.error(`写盘失败（后端）：${toErrorMessage(error)}`);
                                                     }
                                                 }}
                                                 onReject={() => {
@@ -810,7 +800,7 @@ export const ChatMessages: React.FC<ChatMessagesProps> = ({
                                                     agentTaskId: entry.agentTaskId,
                                                 }).catch((error) => {
                                                     toast.error(
-                                                        `写盘失败（后端）：${error instanceof Error ? error.message : String(error)}`,
+                                                        `写盘失败（后端）：${toErrorMessage(error)}`,
                                                     );
                                                 });
                                             }}
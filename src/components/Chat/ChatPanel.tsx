@@ -20,6 +20,7 @@ import { resolveEditorTabForEditResultWithRequestContext, inferPositioningPath }
 import { getPositioningRequestContextForChat } from '../../utils/requestContext';
 import { sha256HexUtf8, blockOrderSnapshotHashFromHtml } from '../../utils/contentSnapshotHash';
 import { isAwaitingAuthorization } from '../../utils/toolDescription';
+import { appendPartialArguments } from '../../utils/partialToolArguments';
 import { useAgentStore } from '../../stores/agentStore';
 import type { KnowledgeInjectionSlice, KnowledgeQueryMetadata, KnowledgeQueryWarning } from '../../types/knowledge';
 import { AgentTaskController } from '../../services/AgentTaskController';
@@ -109,6 +110,10 @@ const ChatPanel: React.FC<ChatPanelProps> = ({ isFullscreen = false }) => {
                             status?: 'pending' | 'executing' | 'completed' | 'failed';
                             result?: any;
                             error?: string;
+                            /** false 表示参数仍在流式生成，arguments 只是新增的 JSON 片段 */
+                            is_complete?: boolean;
+                            /** 部分参数片段在完整参数中的起始位置（UTF-8 字节） */
+                            arguments_offset?: number;
                         };
                         knowledge_retrieval?: {
                             triggered: boolean;
@@ -324,6 +329,8 @@ const ChatPanel: React.FC<ChatPanelProps> = ({ isFullscreen = false }) => {
                             return;
                         }
                         
+                        const isPartial = toolCall.is_complete === false;
+
                         try {
                             // 安全解析 arguments
                             let parsedArguments: any = toolCall.arguments;
@@ -381,6 +388,13 @@ const ChatPanel: React.FC<ChatPanelProps> = ({ isFullscreen = false }) => {
                                 timestamp: Date.now(),
                                 result: toolCall.result,
                                 error: toolCall.error,
+                                partialArguments: isPartial
+                                    ? appendPartialArguments(
+                                        lastMessage.toolCalls?.find(tc => tc.id === toolCall.id)?.partialArguments,
+                                        toolCall.arguments_offset,
+                                        String(toolCall.arguments),
+                                    )
+                                    : undefined,
                             };
                             
                             console.log('🔧 处理工具调用:', {
@@ -410,6 +424,7 @@ const ChatPanel: React.FC<ChatPanelProps> = ({ isFullscreen = false }) => {
                                         status: toolCallStatus,
                                         result: toolCall.result,
                                         error: toolCall.error,
+                                        partialArguments: toolCallObj.partialArguments,
                                     });
                                     
                                     // 更新工具调用引用
@@ -431,7 +446,7 @@ const ChatPanel: React.FC<ChatPanelProps> = ({ isFullscreen = false }) => {
                                             (b.type === 'tool' || b.type === 'authorization') && b.toolCall?.id === toolCallObj.id
                                         );
                                         if (blockIndex >= 0) {
-                                            const needsAuth = isAwaitingAuthorization(toolCallObj, currentWorkspace || undefined);
+                                            const needsAuth = !isPartial && isAwaitingAuthorization(toolCallObj, currentWorkspace || undefined);
                                             updateContentBlock(tabId, messageId, currentMessage.contentBlocks[blockIndex].id, {
                                                 type: needsAuth ? 'authorization' : 'tool',
                                                 toolCall: toolCallObj,
@@ -454,7 +469,7 @@ const ChatPanel: React.FC<ChatPanelProps> = ({ isFullscreen = false }) => {
                                     toolCallsRef.current.set(cacheKey, toolCalls);
                                     
                                     // 实时添加工具调用内容块
-                                    const needsAuth = isAwaitingAuthorization(toolCallObj, currentWorkspace || undefined);
+                                    const needsAuth = !isPartial && isAwaitingAuthorization(toolCallObj, currentWorkspace || undefined);
                                     
                                     const contentBlock: MessageContentBlock = {
                                         id: toolCallObj.id,
@@ -14,6 +14,7 @@ import { useFileStore } from '../../stores/fileStore';
 import { useChatStore } from '../../stores/chatStore';
 import { toast } from '../Common/Toast';
 import { DiffActionService } from '../../services/DiffActionService';
+import { toErrorMessage } from '../../utils/errorHandler';
 
 const SCOPE_LABELS: Record<DiffBulkScope, string> = {
   current_chat_tab: '本对话',
@@ -202,7 +203,7 @@ export const DiffAllActionsBar: React.FC = () => {
           backendWriteFailedFiles++;
           toast.error(
             `写盘失败（后端）: ${filePath.split('/').pop() || filePath} · ${
-              error instanceof Error ? error.message : String(error)
+              toErrorMessage(error)
             }`,
           );
         }
@@ -270,7 +271,7 @@ export const DiffAllActionsBar: React.FC = () => {
           backendRejectFailedFiles++;
           toast.error(
             `后端队列清理失败: ${filePath.split('/').pop() || filePath} · ${
-              error instanceof Error ? error.message : String(error)
+              toErrorMessage(error)
             }`,
           );
         }
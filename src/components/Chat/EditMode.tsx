@@ -4,6 +4,7 @@ import { useFileStore } from '../../stores/fileStore';
 import { invoke } from '@tauri-apps/api/core';
 import { DocumentDiffView } from './DocumentDiffView';
 import { FileSelector } from './FileSelector';
+import type { InlineAssistResult } from '../../hooks/useInlineAssist';
 
 interface EditModeProps {
     tabId: string;
@@ -64,7 +65,7 @@ export const EditMode: React.FC<EditModeProps> = ({
 
         try {
             // 使用 AI 生成修改后的内容
-            const result = await invoke<string>('ai_inline_assist', {
+            const { text: result } = await invoke<InlineAssistResult>('ai_inline_assist', {
                 instruction: editText,
                 text: currentContent,
                 context: `文件路径: ${currentFile}\n\n原文件内容:\n${currentContent}`,
@@ -4,6 +4,7 @@ import { useFileStore } from '../../stores/fileStore';
 import { useLayoutStore } from '../../stores/layoutStore';
 import { fileService } from '../../services/fileService';
 import { toast } from '../Common/Toast';
+import { toErrorMessage } from '../../utils/errorHandler';
 
 const FloatingActionButton: React.FC = () => {
   const { setCurrentWorkspace } = useFileStore();
@@ -22,7 +23,7 @@ const FloatingActionButton: React.FC = () => {
       }
     } catch (error) {
       console.error('打开工作区失败:', error);
-      toast.error(`打开工作区失败: ${error instanceof Error ? error.message : String(error)}`);
+      toast.error(`打开工作区失败: ${toErrorMessage(error)}`);
     }
   };
 
@@ -39,7 +40,7 @@ const FloatingActionButton: React.FC = () => {
       }
     } catch (error) {
       console.error('创建工作区失败:', error);
-      toast.error(`创建工作区失败: ${error instanceof Error ? error.message : String(error)}`);
+      toast.error(`创建工作区失败: ${toErrorMessage(error)}`);
     }
   };
 
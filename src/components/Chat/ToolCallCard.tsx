@@ -10,7 +10,8 @@ import {
     CheckCircleIcon,
     XCircleIcon,
     ClockIcon,
-    ArrowPathIcon
+    ArrowPathIcon,
+    CommandLineIcon
 } from '@heroicons/react/24/outline';
 import { invoke } from '@tauri-apps/api/core';
 // import { emit } from '@tauri-apps/api/event'; // ⚠️ 已废弃：不再使用事件系统，统一使用 EditorStore
@@ -19,6 +20,7 @@ import { documentService } from '../../services/documentService';
 import { DocumentDiffView } from './DocumentDiffView';
 import { getAbsolutePath, normalizePath, normalizeWorkspacePath } from '../../utils/pathUtils';
 import { AgentShadowStateSummary } from './AgentShadowStateSummary';
+import { toErrorMessage } from '../../utils/errorHandler';
 
 interface ToolCallCardProps {
     toolCall: ToolCall;
@@ -103,16 +105,23 @@ export const ToolCallCard: React.FC<ToolCallCardProps> = ({ toolCall, legacyMode
     const getToolIcon = () => {
         switch (toolCall.name) {
             case 'read_file':
+            case 'read_document':
                 return <DocumentIcon className="w-5 h-5" />;
             case 'create_file':
+            case 'copy_file':
+            case 'create_docx_from_markdown':
                 return <PlusIcon className="w-5 h-5" />;
             case 'update_file':
+            case 'apply_patch':
                 return <PencilIcon className="w-5 h-5" />;
             case 'delete_file':
                 return <TrashIcon className="w-5 h-5" />;
             case 'list_files':
+            case 'list_directory':
                 return <FolderIcon className="w-5 h-5" />;
             case 'search_files':
+            case 'search_workspace':
+            case 'search_memories':
                 return <MagnifyingGlassIcon className="w-5 h-5" />;
             case 'move_file':
                 return <ArrowPathIcon className="w-5 h-5" />;
@@ -120,6 +129,8 @@ export const ToolCallCard: React.FC<ToolCallCardProps> = ({ toolCall, legacyMode
                 return <PencilIcon className="w-5 h-5" />;
             case 'create_folder':
                 return <FolderIcon className="w-5 h-5" />;
+            case 'run_command':
+                return <CommandLineIcon className="w-5 h-5" />;
             default:
                 return <DocumentIcon className="w-5 h-5" />;
         }
@@ -128,14 +139,22 @@ export const ToolCallCard: React.FC<ToolCallCardProps> = ({ toolCall, legacyMode
     const getToolName = () => {
         const names: Record<string, string> = {
             read_file: '读取文件',
+            read_document: '读取文档',
             create_file: '创建文件',
             update_file: '更新文件',
+            apply_patch: '局部修改文件',
             delete_file: '删除文件',
             list_files: '列出文件',
+            list_directory: '目录结构',
             search_files: '搜索文件',
+            search_workspace: '搜索内容',
+            search_memories: '查询记忆',
             move_file: '移动文件',
+            copy_file: '复制文件',
+            create_docx_from_markdown: '生成 Word 文档',
             rename_file: '重命名文件',
             create_folder: '创建文件夹',
+            run_command: '执行命令',
         };
         return names[toolCall.name] || toolCall.name;
     };
@@ -221,7 +240,7 @@ export const ToolCallCard: React.FC<ToolCallCardProps> = ({ toolCall, legacyMode
             if (onResult) {
                 onResult({
                     success: false,
-                    error: error instanceof Error ? error.message : String(error),
+                    error: toErrorMessage(error),
                 });
             }
         } finally {
@@ -15,6 +15,7 @@ import {
     ClockIcon
 } from '@heroicons/react/24/outline';
 import { generateToolDescription } from '../../utils/toolDescription';
+import { extractPartialJsonString, tailPreview } from '../../utils/partialToolArguments';
 
 interface ToolCallSummaryProps {
     toolCall: ToolCall;
@@ -43,6 +44,7 @@ export const ToolCallSummary: React.FC<ToolCallSummaryProps> = ({
             case 'read_file':
                 return <DocumentIcon className="w-4 h-4 text-gray-500" />;
             case 'create_file':
+            case 'create_docx_from_markdown':
                 return <PlusIcon className="w-4 h-4 text-green-500" />;
             case 'update_file':
                 return <PencilIcon className="w-4 h-4 text-yellow-500" />;
@@ -78,6 +80,9 @@ export const ToolCallSummary: React.FC<ToolCallSummaryProps> = ({
         if (toolCall.result?.meta?.gate?.status === 'awaiting_confirmation') {
             return '⏸️ 等待确认';
         }
+        if (toolCall.partialArguments !== undefined) {
+            return '✍️ 生成中';
+        }
         switch (toolCall.status) {
             case 'completed':
                 if (toolCall.name === 'list_files' && toolCall.result?.data?.files) {
@@ -98,6 +103,16 @@ export const ToolCallSummary: React.FC<ToolCallSummaryProps> = ({
 
     const description = generateToolDescription(toolCall);
 
+    // 参数仍在流式生成：显示目标路径与正在写入内容的末尾几行
+    const partialPath = toolCall.partialArguments !== undefined
+        ? extractPartialJsonString(toolCall.partialArguments, 'path')
+        : undefined;
+    const partialContent = toolCall.partialArguments !== undefined
+        ? extractPartialJsonString(toolCall.partialArguments, 'content')
+            ?? extractPartialJsonString(toolCall.partialArguments, 'markdown')
+            ?? extractPartialJsonString(toolCall.partialArguments, 'patch')
+        : undefined;
+
     return (
         <div 
             className={`
@@ -123,6 +138,20 @@ export const ToolCallSummary: React.FC<ToolCallSummaryProps> = ({
                 </div>
             </div>
 
+            {/* 流式预览 */}
+            {toolCall.partialArguments !== undefined && (partialPath || partialContent) && (
+                <div className="mt-1 text-xs text-gray-600 dark:text-gray-400">
+                    {partialPath && (
+                        <div className="truncate">正在写入 {partialPath}…</div>
+                    )}
+                    {partialContent && (
+                        <pre className="mt-1 max-h-32 overflow-hidden whitespace-pre-wrap break-all font-mono text-gray-500 dark:text-gray-500">
+                            {tailPreview(partialContent)}
+                        </pre>
+                    )}
+                </div>
+            )}
+
             {/* 展开内容 */}
             {isExpanded && (
                 <div className="mt-2 pt-2 border-t border-blue-200 dark:border-blue-800 space-y-2">
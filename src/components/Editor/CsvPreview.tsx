@@ -20,6 +20,7 @@ import { invoke } from '@tauri-apps/api/core';
 import Papa from 'papaparse';
 import { useVirtualizer } from '@tanstack/react-virtual';
 import { MagnifyingGlassIcon, LinkIcon } from '@heroicons/react/24/outline';
+import { toErrorMessage } from '../../utils/errorHandler';
 
 interface CsvPreviewProps {
   filePath: string;
@@ -590,7 +591,7 @@ const CsvPreview: React.FC<CsvPreviewProps> = ({ filePath }) => {
         }
       } catch (err: unknown) {
         console.error('读取 CSV 文件失败:', err);
-        setError(err instanceof Error ? err.message : String(err) || '读取文件失败');
+        setError(toErrorMessage(err) || '读取文件失败');
         setLoading(false);
       }
     };
@@ -1,5 +1,6 @@
 import React, { useState, useCallback } from 'react';
 import { invoke } from '@tauri-apps/api/core';
+import { toErrorMessage } from '../../utils/errorHandler';
 
 interface Reference {
   text: string;
@@ -70,7 +71,7 @@ const DocumentAnalysisPanel: React.FC<DocumentAnalysisPanelProps> = ({
       }
     } catch (err) {
       console.error('分析失败:', err);
-      setError(err instanceof Error ? err.message : '分析失败，请稍后重试');
+      setError(toErrorMessage(err, '分析失败，请稍后重试'));
     } finally {
       setIsAnalyzing(false);
     }
@@ -36,6 +36,9 @@ import React, { useState, useEffect, useRef, useCallback, useMemo } from 'react'
 import { invoke } from '@tauri-apps/api/core';
 import { listen } from '@tauri-apps/api/event';
 import { PrinterIcon, PencilIcon, MagnifyingGlassIcon, LinkIcon } from '@heroicons/react/24/outline';
+import LibreOfficeInstallHelper from './LibreOfficeInstallHelper';
+import { PREVIEW_DARK_FILTER, usePreviewDarkMode } from '../../stores/themeStore';
+import { getErrorCode, toErrorMessage } from '../../utils/errorHandler';
 
 interface DocxPdfPreviewProps {
   filePath: string;
@@ -45,14 +48,20 @@ interface PreviewProgressEvent {
   status: 'started' | 'converting' | 'completed' | 'failed';
   message: string;
   pdf_path?: string;
+  /** 转换中的估算进度（10-95） */
+  progress?: number;
 }
 
 const DocxPdfPreview: React.FC<DocxPdfPreviewProps> = ({ filePath }) => {
+  const previewDark = usePreviewDarkMode();
   const [previewUrl, setPreviewUrl] = useState<string>('');
   const [loading, setLoading] = useState(true);
   const [error, setError] = useState<string | null>(null);
+  const [libreOfficeMissing, setLibreOfficeMissing] = useState(false);
   const [progress, setProgress] = useState(0);
   const [progressMessage, setProgressMessage] = useState('');
+  // 递增后重新执行转换（如安装 LibreOffice 后）
+  const [reloadKey, setReloadKey] = useState(0);
   
   // 引用功能状态
   const [selectedText, setSelectedText] = useState<string>('');
@@ -81,7 +90,7 @@ const DocxPdfPreview: React.FC<DocxPdfPreviewProps> = ({ filePath }) => {
     const setupListener = async () => {
       try {
         unlistenFn = await listen<PreviewProgressEvent>('preview-progress', (event) => {
-          const { status, message } = event.payload;
+          const { status, message, progress: percent } = event.payload;
           
           setProgressMessage(message);
           
@@ -89,7 +98,7 @@ const DocxPdfPreview: React.FC<DocxPdfPreviewProps> = ({ filePath }) => {
             setLoading(true);
             setProgress(10);
           } else if (status === 'converting') {
-            setProgress(50);
+            setProgress(percent ?? 50);
           } else if (status === 'completed') {
             // 不在此处 setProgress(100)：等 invoke 返回并 setPreviewUrl 后再设为 100%，避免界面卡在「预览完成」却无内容
           } else if (status === 'failed') {
@@ -212,13 +221,14 @@ const DocxPdfPreview: React.FC<DocxPdfPreviewProps> = ({ filePath }) => {
           return;
         }
 
-        const errorMessage = err instanceof Error ? err.message : String(err) || 'PDF 转换失败';
+        const errorMessage = toErrorMessage(err) || 'PDF 转换失败';
         console.error('[预览] PDF 转换或加载失败:', err);
         console.log('[预览] 错误消息:', errorMessage);
         console.log('[预览] 当前重试次数:', retryCount);
 
         // 如果是文件未生成的错误，不显示错误，保持 loading 状态，自动延长等待并重试
-        const isFileNotFoundError = errorMessage.includes('PDF 文件未生成') || 
+        const isFileNotFoundError = getErrorCode(err) === 'FILE_NOT_FOUND' ||
+                                    errorMessage.includes('PDF 文件未生成') || 
                                     errorMessage.includes('未返回文件路径') ||
                                     errorMessage.includes('文件不存在');
         
@@ -244,6 +254,7 @@ const DocxPdfPreview: React.FC<DocxPdfPreviewProps> = ({ filePath }) => {
 
         // 只有在达到最大重试次数或非文件未生成错误时才显示错误
         console.log('[预览] 设置错误状态并停止加载');
+        setLibreOfficeMissing(getErrorCode(err) === 'LIBREOFFICE_UNAVAILABLE');
         setError(errorMessage);
         setLoading(false);
         setProgress(0);
@@ -261,7 +272,7 @@ const DocxPdfPreview: React.FC<DocxPdfPreviewProps> = ({ filePath }) => {
         blobUrlRef.current = null;
       }
     };
-  }, [filePath]);
+  }, [filePath, reloadKey]);
   
   // 监听文本选择（用于引用功能）
   // 优化：同时监听主窗口和 iframe 内的选择
@@ -463,8 +474,10 @@ const DocxPdfPreview: React.FC<DocxPdfPreviewProps> = ({ filePath }) => {
       });
 
       // 打开草稿文件进行编辑（使用新方案：LibreOffice + ODT 解析）
+      const { useFileStore } = await import('../../stores/fileStore');
       const htmlContent = await invoke<string>('open_docx_for_edit', {
         path: draftPath,
+        workspacePath: useFileStore.getState().currentWorkspace ?? null,
       });
 
       // 打开草稿文件到新标签页
@@ -488,7 +501,7 @@ const DocxPdfPreview: React.FC<DocxPdfPreviewProps> = ({ filePath }) => {
       setError(null); // 清除错误状态
     } catch (err: unknown) {
       console.error('创建草稿失败:', err);
-      setError(`创建草稿失败: ${err instanceof Error ? err.message : String(err)}`);
+      setError(`创建草稿失败: ${toErrorMessage(err)}`);
     }
   };
 
@@ -530,6 +543,11 @@ const DocxPdfPreview: React.FC<DocxPdfPreviewProps> = ({ filePath }) => {
         <div className="text-center max-w-md">
           <div className="text-red-500 text-xl font-semibold mb-4">预览失败</div>
           <div className="text-gray-600 dark:text-gray-400 mb-6">{error}</div>
+          {libreOfficeMissing && (
+            <LibreOfficeInstallHelper
+              onInstalled={() => setReloadKey((key) => key + 1)}
+            />
+          )}
           
           {/* 操作按钮 */}
           <div className="flex flex-col space-y-3">
@@ -629,6 +647,7 @@ const DocxPdfPreview: React.FC<DocxPdfPreviewProps> = ({ filePath }) => {
           ref={iframeRef}
           src={previewUrl}
           className="w-full h-full border-0"
+          style={previewDark ? { filter: PREVIEW_DARK_FILTER } : undefined}
           title="PDF 预览"
           onLoad={() => {
             const info = previewDebugRef.current;
@@ -25,6 +25,9 @@ import { invoke } from '@tauri-apps/api/core';
 import { listen } from '@tauri-apps/api/event';
 import { XMarkIcon } from '@heroicons/react/24/outline';
 import { findBlockAtPos } from '../../utils/anchorFromSelection';
+import { normalizePath, normalizeWorkspacePath } from '../../utils/pathUtils';
+import type { ExternalFileChangedEvent } from '../../types/file';
+import { toErrorMessage } from '../../utils/errorHandler';
 
 // 保存进度事件类型
 interface SaveProgressEvent {
@@ -67,7 +70,11 @@ const EditorPanel: React.FC = () => {
   const editorZoom = editorLayout?.zoom ?? 100;
 
   // 外部修改对话框状态
-  const [externalModifiedTab, setExternalModifiedTab] = useState<{ id: string; filePath: string } | null>(null);
+  const [externalModifiedTab, setExternalModifiedTab] = useState<{
+    id: string;
+    filePath: string;
+    diff?: ExternalFileChangedEvent['diff'];
+  } | null>(null);
 
   // 使用 useMemo 稳定 activeTab 引用
   const activeTab = useMemo(() => {
@@ -160,8 +167,10 @@ const EditorPanel: React.FC = () => {
   
   // ⚠️ Week 17.1.2：定期检查外部修改（每 5 秒）
   // 检测到外部修改时弹出对话框让用户选择
+  // 工作区内的文件由文件监听推送 external-file-changed，这里只轮询工作区外的文件
   useEffect(() => {
     if (tabs.length === 0) return;
+    const workspacePrefix = currentWorkspace ? `${normalizeWorkspacePath(currentWorkspace)}/` : null;
 
     const checkInterval = setInterval(async () => {
       // 如果对话框已弹出，跳过本轮检查
@@ -170,6 +179,7 @@ const EditorPanel: React.FC = () => {
       for (const tab of tabs) {
         // 只检查非脏文件（未修改的文件）
         if (tab.isDirty || !tab.filePath || tab.isReadOnly) continue;
+        if (workspacePrefix && normalizePath(tab.filePath).startsWith(workspacePrefix)) continue;
 
         // ⚠️ 关键修复：如果 lastModifiedTime 为 0 或无效，跳过检查
         if (!tab.lastModifiedTime || tab.lastModifiedTime === 0) {
@@ -194,7 +204,26 @@ const EditorPanel: React.FC = () => {
     }, 5000); // 每 5 秒检查一次
 
     return () => clearInterval(checkInterval);
-  }, [tabs, externalModifiedTab]);
+  }, [tabs, externalModifiedTab, currentWorkspace]);
+
+  // 跟踪已打开文件的磁盘快照，外部修改时后端推送 external-file-changed
+  const trackedPathsRef = useRef<Set<string>>(new Set());
+  useEffect(() => {
+    const openPaths = new Set(tabs.map((tab) => tab.filePath).filter(Boolean));
+    for (const path of openPaths) {
+      if (!trackedPathsRef.current.has(path)) {
+        invoke('track_open_file', { path }).catch((error) => {
+          console.warn(`跟踪文件 ${path} 失败:`, error);
+        });
+      }
+    }
+    for (const path of trackedPathsRef.current) {
+      if (!openPaths.has(path)) {
+        invoke('untrack_open_file', { path }).catch(() => {});
+      }
+    }
+    trackedPathsRef.current = openPaths;
+  }, [tabs]);
 
   // 外部修改对话框：继续覆盖（保持编辑器内容，仅更新 mtime）
   const handleContinueOverwrite = useCallback(async () => {
@@ -209,11 +238,8 @@ const EditorPanel: React.FC = () => {
     }
   }, [externalModifiedTab, updateTabModifiedTime]);
 
-  // 外部修改对话框：加载外部更改（重新读取磁盘内容，并使所有 pending diffs 失效）
-  const handleLoadExternalChanges = useCallback(async () => {
-    if (!externalModifiedTab) return;
-    const { id, filePath } = externalModifiedTab;
-    setExternalModifiedTab(null);
+  // 重新读取磁盘内容，并使所有 pending diffs 失效
+  const reloadTabFromDisk = useCallback(async (id: string, filePath: string) => {
     try {
       // 使该文件所有 pending diffs 静默失效（与用户手动编辑 diff 区域的处理一致）
       const diffStore = useDiffStore.getState();
@@ -225,7 +251,7 @@ const EditorPanel: React.FC = () => {
       const ext = filePath.split('.').pop()?.toLowerCase();
       const isDocx = ['docx', 'doc', 'odt', 'rtf'].includes(ext || '');
       const newContent = isDocx
-        ? await invoke<string>('open_docx_for_edit', { path: filePath })
+        ? await invoke<string>('open_docx_for_edit', { path: filePath, workspacePath: currentWorkspace ?? null })
         : await invoke<string>('read_file_content', { path: filePath });
       const activeEditorTab = useEditorStore.getState().tabs.find((tab) => tab.id === useEditorStore.getState().activeTabId) ?? null;
       const targetTab = useEditorStore.getState().tabs.find((tab) => tab.id === id);
@@ -266,11 +292,63 @@ const EditorPanel: React.FC = () => {
       // 更新 mtime
       const newModifiedTime = await invoke<number>('get_file_modified_time', { path: filePath });
       updateTabModifiedTime(id, newModifiedTime);
+      return true;
     } catch (e) {
       console.error('加载外部更改失败:', e);
-      toast.error(`加载外部更改失败: ${e instanceof Error ? e.message : String(e)}`);
+      toast.error(`加载外部更改失败: ${toErrorMessage(e)}`);
+      return false;
     }
-  }, [externalModifiedTab, updateTabModifiedTime]);
+  }, [updateTabModifiedTime]);
+
+  // 外部修改对话框：加载外部更改
+  const handleLoadExternalChanges = useCallback(async () => {
+    if (!externalModifiedTab) return;
+    const { id, filePath } = externalModifiedTab;
+    setExternalModifiedTab(null);
+    await reloadTabFromDisk(id, filePath);
+  }, [externalModifiedTab, reloadTabFromDisk]);
+
+  // 文件监听推送的外部修改：未编辑且没有待确认修改的标签页直接重新加载，否则弹窗让用户选择
+  useEffect(() => {
+    const setupListener = async () => {
+      return listen<ExternalFileChangedEvent>('external-file-changed', async (event) => {
+        const { path, modified_ms, diff, deleted } = event.payload;
+        const tab = useEditorStore
+          .getState()
+          .tabs.find((t) => normalizePath(t.filePath) === normalizePath(path));
+        if (!tab) return;
+        const fileName = tab.filePath.split('/').pop() || tab.filePath;
+        if (deleted) {
+          toast.warning(`${fileName} 已被外部删除或移动`);
+          return;
+        }
+        // 自己保存产生的事件：修改时间不晚于标签页记录的时间
+        if (modified_ms !== null && tab.lastModifiedTime && modified_ms <= tab.lastModifiedTime) return;
+        if (tab.isReadOnly) return;
+
+        const hasPendingDiffs = useDiffStore.getState().getPendingDiffs(tab.filePath).length > 0;
+        if (tab.isDirty || hasPendingDiffs) {
+          setExternalModifiedTab((current) => current ?? { id: tab.id, filePath: tab.filePath, diff });
+          return;
+        }
+        if (await reloadTabFromDisk(tab.id, tab.filePath)) {
+          const summary = diff ? `（+${diff.lines_added} / -${diff.lines_removed} 行）` : '';
+          toast.info(`${fileName} 已被外部修改，已重新加载${summary}`);
+        }
+      });
+    };
+
+    let unlistenFn: (() => void) | null = null;
+    setupListener().then(unlisten => {
+      unlistenFn = unlisten;
+    });
+
+    return () => {
+      if (unlistenFn) {
+        unlistenFn();
+      }
+    };
+  }, [reloadTabFromDisk]);
   
   // Cmd+J（辅助续写）与 Cmd+K（局部修改）快捷键处理 - 使用 capture 阶段确保优先处理
   useEffect(() => {
@@ -423,7 +501,7 @@ const EditorPanel: React.FC = () => {
       console.log('✅ 文件保存成功');
     } catch (error) {
       console.error('❌ 保存失败:', error);
-      const errorMessage = error instanceof Error ? error.message : String(error);
+      const errorMessage = toErrorMessage(error);
       toast.error(`保存失败: ${errorMessage}`);
     } finally {
       setTabSaving(activeTab.id, false);
@@ -884,6 +962,7 @@ const EditorPanel: React.FC = () => {
         return (
           <ExternalModificationDialog
             filePath={externalModifiedTab.filePath}
+            diffSummary={externalModifiedTab.diff}
             hasPendingDiffs={hasPendingDiffs}
             onContinueOverwrite={handleContinueOverwrite}
             onLoadChanges={handleLoadExternalChanges}
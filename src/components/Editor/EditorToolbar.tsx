@@ -16,12 +16,14 @@ import {
   AdjustmentsHorizontalIcon,
 } from '@heroicons/react/24/outline';
 import { useLayoutStore } from '../../stores/layoutStore';
+import { useFileStore } from '../../stores/fileStore';
 import { usePaginationFromEditor } from '../../hooks/usePaginationFromEditor';
 import { ChevronLeftIcon, ChevronRightIcon } from '@heroicons/react/24/outline';
 import { toast } from '../Common/Toast';
 import ToolbarDropdown from './ToolbarDropdown';
 import PageSizeDropdown from './PageSizeDropdown';
 import MarginsModal from './MarginsModal';
+import { toErrorMessage } from '../../utils/errorHandler';
 
 interface EditorToolbarProps {
   editor: Editor | null;
@@ -442,17 +444,26 @@ const EditorToolbar: React.FC<EditorToolbarProps> = ({ editor, fileType, documen
                   return;
                 }
                 
-                // 调用后端插入图片（返回 { dataUrl, relativePath }）
-                const result = await invoke<{ dataUrl: string; relativePath: string }>('insert_image', {
+                // 调用后端插入图片（返回 { dataUrl, relativePath }，开启工作区图片优化时附带 optimization）
+                const result = await invoke<{
+                  dataUrl: string;
+                  relativePath: string;
+                  optimization?: { saved_bytes: number; width: number; height: number };
+                }>('insert_image', {
                   documentPath,
                   imageSource: selected,
+                  workspacePath: useFileStore.getState().currentWorkspace ?? undefined,
                 });
+                if (result.optimization && result.optimization.saved_bytes > 0) {
+                  const savedKb = Math.round(result.optimization.saved_bytes / 1024);
+                  toast.info(`图片已优化为 ${result.optimization.width}×${result.optimization.height}，节省 ${savedKb} KB`);
+                }
                 
                 // 在编辑器中插入图片（使用 base64 data URL）
                 editor.chain().focus().setImage({ src: result.dataUrl }).run();
               } catch (error) {
                 console.error('插入图片失败:', error);
-                toast.error(`插入图片失败: ${error instanceof Error ? error.message : String(error)}`);
+                toast.error(`插入图片失败: ${toErrorMessage(error)}`);
               }
             }}
             className="p-1.5 rounded hover:bg-gray-100 dark:hover:bg-gray-700 shrink-0"
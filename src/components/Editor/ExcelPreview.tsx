@@ -24,6 +24,8 @@ import React, { useState, useEffect, useRef, useCallback, useMemo } from 'react'
 import { invoke } from '@tauri-apps/api/core';
 import { listen } from '@tauri-apps/api/event';
 import { PrinterIcon, MagnifyingGlassIcon, LinkIcon } from '@heroicons/react/24/outline';
+import { PREVIEW_DARK_FILTER, usePreviewDarkMode } from '../../stores/themeStore';
+import { toErrorMessage } from '../../utils/errorHandler';
 
 interface ExcelPreviewProps {
   filePath: string;
@@ -33,9 +35,12 @@ interface PreviewProgressEvent {
   status: 'started' | 'converting' | 'completed' | 'failed';
   message: string;
   pdf_path?: string;
+  /** 转换中的估算进度（10-95） */
+  progress?: number;
 }
 
 const ExcelPreview: React.FC<ExcelPreviewProps> = ({ filePath }) => {
+  const previewDark = usePreviewDarkMode();
   const [previewUrl, setPreviewUrl] = useState<string>('');
   const [loading, setLoading] = useState(true);
   const [error, setError] = useState<string | null>(null);
@@ -64,7 +69,7 @@ const ExcelPreview: React.FC<ExcelPreviewProps> = ({ filePath }) => {
     const setupListener = async () => {
       try {
         unlistenFn = await listen<PreviewProgressEvent>('preview-progress', (event) => {
-          const { status, message } = event.payload;
+          const { status, message, progress: percent } = event.payload;
           
           setProgressMessage(message);
           
@@ -72,7 +77,7 @@ const ExcelPreview: React.FC<ExcelPreviewProps> = ({ filePath }) => {
             setLoading(true);
             setProgress(10);
           } else if (status === 'converting') {
-            setProgress(50);
+            setProgress(percent ?? 50);
           } else if (status === 'completed') {
             setProgress(100);
           } else if (status === 'failed') {
@@ -145,7 +150,7 @@ const ExcelPreview: React.FC<ExcelPreviewProps> = ({ filePath }) => {
         setProgress(100);
       } catch (err: unknown) {
         console.error('PDF 转换或加载失败:', err);
-        setError(err instanceof Error ? err.message : String(err) || 'PDF 转换失败');
+        setError(toErrorMessage(err) || 'PDF 转换失败');
         setLoading(false);
         setProgress(0);
       }
@@ -355,6 +360,7 @@ const ExcelPreview: React.FC<ExcelPreviewProps> = ({ filePath }) => {
           ref={iframeRef}
           src={previewUrl}
           className="w-full h-full border-0"
+          style={previewDark ? { filter: PREVIEW_DARK_FILTER } : undefined}
           title="Excel PDF 预览"
           onLoad={() => {
             setLoading(false);
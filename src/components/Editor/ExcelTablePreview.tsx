@@ -20,6 +20,7 @@ import { invoke } from '@tauri-apps/api/core';
 import * as XLSX from 'xlsx';
 import { useVirtualizer } from '@tanstack/react-virtual';
 import { MagnifyingGlassIcon, LinkIcon } from '@heroicons/react/24/outline';
+import { toErrorMessage } from '../../utils/errorHandler';
 
 interface ExcelTablePreviewProps {
   filePath: string;
@@ -510,7 +511,7 @@ const ExcelTablePreview: React.FC<ExcelTablePreviewProps> = ({ filePath }) => {
         setLoading(false);
       } catch (err: unknown) {
         console.error('解析 Excel 文件失败:', err);
-        setError(err instanceof Error ? err.message : String(err) || '解析 Excel 文件失败');
+        setError(toErrorMessage(err) || '解析 Excel 文件失败');
         setLoading(false);
       }
     };
@@ -4,6 +4,8 @@ import { ExclamationTriangleIcon } from '@heroicons/react/24/outline';
 
 interface ExternalModificationDialogProps {
   filePath: string;
+  /** 外部修改的行级变化摘要（文件监听推送时提供） */
+  diffSummary?: { lines_added: number; lines_removed: number } | null;
   onContinueOverwrite: () => void;
   onLoadChanges: () => void;
   onCompare?: () => void;
@@ -15,6 +17,7 @@ interface ExternalModificationDialogProps {
 
 const ExternalModificationDialog: React.FC<ExternalModificationDialogProps> = ({
   filePath,
+  diffSummary,
   onContinueOverwrite,
   onLoadChanges,
   onCompare,
@@ -36,6 +39,12 @@ const ExternalModificationDialog: React.FC<ExternalModificationDialogProps> = ({
         
         <p className="text-sm text-gray-600 dark:text-gray-400 mb-6">
           文件 <span className="font-mono text-xs bg-gray-100 dark:bg-gray-800 px-1 py-0.5 rounded">{fileName}</span> 已被外部程序修改。
+          {diffSummary && (
+            <span className="ml-1">
+              外部新增 <span className="text-green-600">{diffSummary.lines_added}</span> 行、
+              删除 <span className="text-red-600">{diffSummary.lines_removed}</span> 行。
+            </span>
+          )}
         </p>
         
         <p className="text-sm text-gray-600 dark:text-gray-400 mb-6">
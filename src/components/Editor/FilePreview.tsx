@@ -1,5 +1,6 @@
 import React from 'react';
 import { invoke } from '@tauri-apps/api/core';
+import { toErrorMessage } from '../../utils/errorHandler';
 
 interface FilePreviewProps {
   filePath: string;
@@ -34,7 +35,7 @@ const FilePreview: React.FC<FilePreviewProps> = ({ filePath, fileType }) => {
         setError('');
       } catch (err) {
         console.error('加载文件失败:', err);
-        setError(`加载文件失败: ${err instanceof Error ? err.message : String(err)}`);
+        setError(`加载文件失败: ${toErrorMessage(err)}`);
       }
     };
     
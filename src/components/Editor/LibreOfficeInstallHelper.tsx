@@ -0,0 +1,110 @@
+// src/components/Editor/LibreOfficeInstallHelper.tsx
+// LibreOffice 未安装时的引导：显示检测结果、安装命令，并可通过包管理器一键安装
+
+import React, { useEffect, useState } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
+import { toErrorMessage } from '../../utils/errorHandler';
+
+interface LibreOfficeStatus {
+  available: boolean;
+  is_bundled: boolean;
+  path: string | null;
+  version: string | null;
+  install_command: string | null;
+  download_url: string;
+}
+
+interface InstallProgressEvent {
+  status: 'started' | 'output' | 'completed' | 'failed';
+  message: string;
+}
+
+interface LibreOfficeInstallHelperProps {
+  /** 安装完成并检测到 LibreOffice 后回调（通常用于重试预览） */
+  onInstalled: () => void;
+}
+
+const LibreOfficeInstallHelper: React.FC<LibreOfficeInstallHelperProps> = ({ onInstalled }) => {
+  const [status, setStatus] = useState<LibreOfficeStatus | null>(null);
+  const [installing, setInstalling] = useState(false);
+  const [lastLine, setLastLine] = useState('');
+  const [installError, setInstallError] = useState<string | null>(null);
+
+  useEffect(() => {
+    invoke<LibreOfficeStatus>('check_libreoffice_available')
+      .then(setStatus)
+      .catch((e) => console.error('检测 LibreOffice 失败:', e));
+  }, []);
+
+  useEffect(() => {
+    const unlisten = listen<InstallProgressEvent>('libreoffice-install-progress', (event) => {
+      setLastLine(event.payload.message);
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  const handleInstall = async () => {
+    setInstalling(true);
+    setInstallError(null);
+    try {
+      const result = await invoke<LibreOfficeStatus>('install_libreoffice');
+      setStatus(result);
+      if (result.available) {
+        onInstalled();
+      }
+    } catch (e) {
+      setInstallError(toErrorMessage(e));
+    } finally {
+      setInstalling(false);
+    }
+  };
+
+  if (!status || status.available) return null;
+
+  return (
+    <div className="mt-4 mb-6 p-4 rounded-lg border border-amber-300 dark:border-amber-700 bg-amber-50 dark:bg-amber-900/20 text-left text-sm">
+      <div className="font-medium text-amber-800 dark:text-amber-300 mb-2">未检测到 LibreOffice</div>
+      <div className="text-gray-600 dark:text-gray-400 mb-3">
+        Office 文档预览需要 LibreOffice。
+        {status.install_command ? (
+          <>
+            可以一键安装，或在终端执行：
+            <code className="block mt-1 px-2 py-1 rounded bg-gray-100 dark:bg-gray-800 text-xs break-all">
+              {status.install_command}
+            </code>
+          </>
+        ) : (
+          '请从官网下载并安装后重试。'
+        )}
+      </div>
+      <div className="flex space-x-2">
+        {status.install_command && (
+          <button
+            onClick={handleInstall}
+            disabled={installing}
+            className="px-4 py-1.5 bg-amber-500 text-white rounded hover:bg-amber-600 disabled:opacity-50 transition-colors"
+          >
+            {installing ? '安装中…' : '一键安装'}
+          </button>
+        )}
+        <button
+          onClick={() => window.open(status.download_url, '_blank', 'noopener,noreferrer')}
+          className="px-4 py-1.5 border border-gray-300 dark:border-gray-600 text-gray-700 dark:text-gray-300 rounded hover:bg-gray-100 dark:hover:bg-gray-700 transition-colors"
+        >
+          前往官网下载
+        </button>
+      </div>
+      {installing && lastLine && (
+        <div className="mt-2 text-xs text-gray-500 dark:text-gray-400 truncate" title={lastLine}>
+          {lastLine}
+        </div>
+      )}
+      {installError && <div className="mt-2 text-xs text-red-500">{installError}</div>}
+    </div>
+  );
+};
+
+export default LibreOfficeInstallHelper;
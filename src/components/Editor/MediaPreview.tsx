@@ -1,6 +1,7 @@
 import React, { useState, useEffect, useRef } from 'react';
 import { invoke } from '@tauri-apps/api/core';
 import { PlayIcon, PauseIcon, SpeakerWaveIcon, SpeakerXMarkIcon, ArrowsPointingOutIcon } from '@heroicons/react/24/outline';
+import { toErrorMessage } from '../../utils/errorHandler';
 
 interface MediaPreviewProps {
   filePath: string;
@@ -174,7 +175,7 @@ const MediaPreview: React.FC<MediaPreviewProps> = ({ filePath, fileType }) => {
       } catch (err) {
         if (isCancelled) return;
         console.error('加载文件失败:', err);
-        setError(`加载文件失败: ${err instanceof Error ? err.message : String(err)}`);
+        setError(`加载文件失败: ${toErrorMessage(err)}`);
         setLoading(false);
         setFormatSupported(false);
       }
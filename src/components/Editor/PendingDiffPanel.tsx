@@ -10,6 +10,7 @@ import { useFileStore } from '../../stores/fileStore';
 import { useChatStore } from '../../stores/chatStore';
 import { documentService } from '../../services/documentService';
 import { toast } from '../Common/Toast';
+import { toErrorMessage } from '../../utils/errorHandler';
 
 export const PendingDiffPanel: React.FC = () => {
   const { currentWorkspace } = useFileStore();
@@ -81,7 +82,7 @@ export const PendingDiffPanel: React.FC = () => {
                   <button
                     onClick={() => {
                       void documentService.openFile(filePath).catch((error) => {
-                        toast.error(`打开文件失败: ${error instanceof Error ? error.message : String(error)}`);
+                        toast.error(`打开文件失败: ${toErrorMessage(error)}`);
                       });
                     }}
                     className="px-2 py-1 text-xs rounded bg-blue-600 text-white hover:bg-blue-700"
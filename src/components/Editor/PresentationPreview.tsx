@@ -22,6 +22,8 @@ import React, { useState, useEffect, useRef, useCallback, useMemo } from 'react'
 import { invoke } from '@tauri-apps/api/core';
 import { listen } from '@tauri-apps/api/event';
 import { PrinterIcon, MagnifyingGlassIcon, LinkIcon } from '@heroicons/react/24/outline';
+import { PREVIEW_DARK_FILTER, usePreviewDarkMode } from '../../stores/themeStore';
+import { getErrorCode, toErrorMessage } from '../../utils/errorHandler';
 
 interface PresentationPreviewProps {
   filePath: string;
@@ -31,9 +33,12 @@ interface PreviewProgressEvent {
   status: 'started' | 'converting' | 'completed' | 'failed';
   message: string;
   pdf_path?: string;
+  /** 转换中的估算进度（10-95） */
+  progress?: number;
 }
 
 const PresentationPreview: React.FC<PresentationPreviewProps> = ({ filePath }) => {
+  const previewDark = usePreviewDarkMode();
   const [previewUrl, setPreviewUrl] = useState<string>('');
   const [loading, setLoading] = useState(true);
   const [error, setError] = useState<string | null>(null);
@@ -69,7 +74,7 @@ const PresentationPreview: React.FC<PresentationPreviewProps> = ({ filePath }) =
     const setupListener = async () => {
       try {
         unlistenFn = await listen<PreviewProgressEvent>('preview-progress', (event) => {
-          const { status, message } = event.payload;
+          const { status, message, progress: percent } = event.payload;
           
           setProgressMessage(message);
           
@@ -80,7 +85,7 @@ const PresentationPreview: React.FC<PresentationPreviewProps> = ({ filePath }) =
               setProgress(10);
             }
           } else if (status === 'converting') {
-            if (!hasPreviewUrlRef.current) setProgress(50);
+            if (!hasPreviewUrlRef.current) setProgress(percent ?? 50);
           } else if (status === 'completed') {
             // 不在此处 setProgress(100)：等 invoke 返回并 setPreviewUrl 后再设为 100%，避免界面卡在「预览完成」却无内容
           } else if (status === 'failed') {
@@ -198,10 +203,11 @@ const PresentationPreview: React.FC<PresentationPreviewProps> = ({ filePath }) =
           return;
         }
 
-        const errorMessage = err instanceof Error ? err.message : String(err) || 'PDF 转换失败';
+        const errorMessage = toErrorMessage(err) || 'PDF 转换失败';
         console.error('[PPT预览] PDF 转换或加载失败:', err);
 
         const isFileNotFoundError =
+          getErrorCode(err) === 'FILE_NOT_FOUND' ||
           errorMessage.includes('PDF 文件未生成') ||
           errorMessage.includes('未返回文件路径') ||
           errorMessage.includes('文件不存在');
@@ -536,6 +542,7 @@ const PresentationPreview: React.FC<PresentationPreviewProps> = ({ filePath }) =
           ref={iframeRef}
           src={previewUrl}
           className="w-full h-full border-0"
+          style={previewDark ? { filter: PREVIEW_DARK_FILTER } : undefined}
           title="演示文稿 PDF 预览"
           onLoad={() => {
             const info = previewDebugRef.current;
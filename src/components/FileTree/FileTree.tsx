@@ -1,4 +1,4 @@
-import { useState, useEffect, useImperativeHandle, forwardRef } from 'react';
+import { useState, useEffect, useRef, useImperativeHandle, forwardRef } from 'react';
 import { useFileStore, shouldIgnoreFileTreeRefresh } from '../../stores/fileStore';
 import { fileService } from '../../services/fileService';
 import { documentService } from '../../services/documentService';
@@ -10,6 +10,9 @@ import LoadingSpinner from '../Common/LoadingSpinner';
 import Modal from '../Common/Modal';
 import { toast } from '../Common/Toast';
 import { listen } from '@tauri-apps/api/event';
+import { FileTreeNode as FileTreeNodeType, WorkspaceFileChangesEvent } from '../../types/file';
+import { applyFileChanges } from '../../utils/fileTreeUtils';
+import { toErrorMessage } from '../../utils/errorHandler';
 
 export interface FileTreeRef {
   refresh: () => Promise<void>;
@@ -23,6 +26,13 @@ const FileTree = forwardRef<FileTreeRef>((_props, ref) => {
   const [organizeFiles, setOrganizeFiles] = useState<string[] | null>(null);
   const [renameTargetPath, setRenameTargetPath] = useState<string | null>(null);
   const [deleteTargetPath, setDeleteTargetPath] = useState<string | null>(null);
+  // 只读参考资料文件夹，各自作为独立根节点显示在工作区文件树下方
+  const [referenceTrees, setReferenceTrees] = useState<FileTreeNodeType[]>([]);
+  const referenceTreesRef = useRef<FileTreeNodeType[]>([]);
+  const updateReferenceTrees = (trees: FileTreeNodeType[]) => {
+    referenceTreesRef.current = trees;
+    setReferenceTrees(trees);
+  };
 
   /** @param preserveExpanded 为 true 时保持当前展开状态（用于外部修改刷新），否则重置为仅根目录 */
   const loadFileTree = async (preserveExpanded = false) => {
@@ -41,6 +51,22 @@ const FileTree = forwardRef<FileTreeRef>((_props, ref) => {
     }
   };
 
+  const loadReferenceTrees = async () => {
+    if (!currentWorkspace) return;
+    try {
+      const folders = await fileService.listReferenceFolders(currentWorkspace);
+      const trees = await Promise.all(
+        folders
+          .filter((folder) => folder.available)
+          .map((folder) => fileService.buildFileTree(folder.path, 5))
+      );
+      updateReferenceTrees(trees);
+    } catch (error) {
+      console.error('加载参考资料文件夹失败:', error);
+      updateReferenceTrees([]);
+    }
+  };
+
   // 导出刷新函数供外部调用
   useImperativeHandle(ref, () => ({
     refresh: loadFileTree,
@@ -49,6 +75,7 @@ const FileTree = forwardRef<FileTreeRef>((_props, ref) => {
   useEffect(() => {
     if (currentWorkspace) {
       loadFileTree();
+      loadReferenceTrees();
     }
   }, [currentWorkspace]);
 
@@ -58,13 +85,40 @@ const FileTree = forwardRef<FileTreeRef>((_props, ref) => {
 
     let unlisten: (() => void) | null = null;
 
-    // 监听文件树变化事件
-    listen<string>('file-tree-changed', (event) => {
-      if (event.payload !== currentWorkspace) return;
+    // 监听文件变化事件：能局部更新时只更新受影响的节点，否则整体刷新
+    listen<WorkspaceFileChangesEvent>('workspace-file-changes', (event) => {
+      const { workspace_path, overflowed } = event.payload;
+      if (workspace_path !== currentWorkspace) return;
+      // 参考资料文件夹的结构变化只刷新对应的参考资料树
+      const referenceChanges = event.payload.changes.filter(
+        (change) => change.reference_root && change.kind !== 'modified'
+      );
+      if (overflowed) {
+        loadReferenceTrees();
+      } else if (referenceChanges.length > 0) {
+        const updated = referenceTreesRef.current.map((tree) => {
+          const own = referenceChanges.filter((change) => change.reference_root === tree.path);
+          return own.length > 0 ? applyFileChanges(tree, own) : tree;
+        });
+        if (updated.some((tree) => tree === null)) {
+          loadReferenceTrees();
+        } else {
+          updateReferenceTrees(updated as FileTreeNodeType[]);
+        }
+      }
+      const changes = event.payload.changes.filter((change) => !change.reference_root);
+      // 只有内容修改时文件树无需变化
+      if (!overflowed && changes.every((change) => change.kind === 'modified')) return;
       // 优化1：忽略自身保存触发的刷新（编辑时自动保存会导致文件树折叠）
       if (shouldIgnoreFileTreeRefresh(currentWorkspace)) {
         return;
       }
+      const currentTree = useFileStore.getState().fileTree;
+      const updated = !overflowed && currentTree ? applyFileChanges(currentTree, changes) : null;
+      if (updated) {
+        setFileTree(updated);
+        return;
+      }
       console.log('检测到文件系统变化，自动刷新文件树');
       // 优化2：外部修改刷新时保持文件树展开状态
       loadFileTree(true);
@@ -93,6 +147,16 @@ const FileTree = forwardRef<FileTreeRef>((_props, ref) => {
     });
   };
 
+  const handleReferenceFileSelect = async (path: string) => {
+    setSelectedFile(path);
+    try {
+      await documentService.openFile(path, { readOnly: true });
+    } catch (error) {
+      console.error('打开参考资料失败:', error);
+      toast.error(`打开参考资料失败: ${toErrorMessage(error)}`);
+    }
+  };
+
   const handleFileSelect = async (path: string) => {
     setSelectedFile(path);
     
@@ -184,7 +248,7 @@ const FileTree = forwardRef<FileTreeRef>((_props, ref) => {
         addOpenFile(path);
       } catch (error) {
         console.error('打开文件失败:', error);
-        toast.error(`打开文件失败: ${error instanceof Error ? error.message : String(error)}`);
+        toast.error(`打开文件失败: ${toErrorMessage(error)}`);
       }
     } else {
       // 不支持的文件类型，提示用户
@@ -278,7 +342,7 @@ const FileTree = forwardRef<FileTreeRef>((_props, ref) => {
         console.log(`✅ 文件已导入: ${file.name}`);
           } catch (error) {
             console.error(`❌ 导入文件失败: ${file.name}`, error);
-            toast.error(`导入文件失败: ${file.name} - ${error instanceof Error ? error.message : String(error)}`);
+            toast.error(`导入文件失败: ${file.name} - ${toErrorMessage(error)}`);
           }
     }
 
@@ -302,7 +366,7 @@ const FileTree = forwardRef<FileTreeRef>((_props, ref) => {
       await loadFileTree(true);
     } catch (error) {
       console.error('重命名文件失败:', error);
-      toast.error(`重命名文件失败: ${error instanceof Error ? error.message : String(error)}`);
+      toast.error(`重命名文件失败: ${toErrorMessage(error)}`);
     }
   };
 
@@ -324,7 +388,7 @@ const FileTree = forwardRef<FileTreeRef>((_props, ref) => {
       await loadFileTree(true);
     } catch (error) {
       console.error('删除文件失败:', error);
-      toast.error(`删除文件失败: ${error instanceof Error ? error.message : String(error)}`);
+      toast.error(`删除文件失败: ${toErrorMessage(error)}`);
     }
   };
 
@@ -335,7 +399,7 @@ const FileTree = forwardRef<FileTreeRef>((_props, ref) => {
       await loadFileTree(true);
     } catch (error) {
       console.error('复制文件失败:', error);
-      toast.error(`复制文件失败: ${error instanceof Error ? error.message : String(error)}`);
+      toast.error(`复制文件失败: ${toErrorMessage(error)}`);
     }
   };
 
@@ -359,7 +423,7 @@ const FileTree = forwardRef<FileTreeRef>((_props, ref) => {
       window.dispatchEvent(new CustomEvent('binder-knowledge-changed'));
     } catch (error) {
       console.error('存入知识库失败:', error);
-      toast.error(`存入知识库失败: ${error instanceof Error ? error.message : String(error)}`);
+      toast.error(`存入知识库失败: ${toErrorMessage(error)}`);
     }
   };
 
@@ -377,7 +441,7 @@ const FileTree = forwardRef<FileTreeRef>((_props, ref) => {
       await loadFileTree(true);
     } catch (error) {
       console.error('移动文件失败:', error);
-      toast.error(`移动文件失败: ${error instanceof Error ? error.message : String(error)}`);
+      toast.error(`移动文件失败: ${toErrorMessage(error)}`);
     }
   };
 
@@ -429,6 +493,23 @@ const FileTree = forwardRef<FileTreeRef>((_props, ref) => {
             文件树为空
           </div>
         )}
+
+        {/* 参考资料文件夹：只读，不提供重命名、删除、整理等操作 */}
+        {referenceTrees.length > 0 && (
+          <div className="mt-2 pt-2 border-t border-gray-200 dark:border-gray-700">
+            <div className="px-2 pb-1 text-xs text-gray-500 dark:text-gray-400">参考资料（只读）</div>
+            {referenceTrees.map((tree) => (
+              <FileTreeNode
+                key={tree.path}
+                node={tree}
+                level={0}
+                expandedPaths={expandedPaths}
+                onToggleExpand={toggleExpand}
+                onSelectFile={handleReferenceFileSelect}
+              />
+            ))}
+          </div>
+        )}
       </div>
 
       {/* ⚠️ Week 20：AI 智能分类整理对话框 */}
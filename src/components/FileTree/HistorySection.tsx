@@ -4,6 +4,7 @@ import { useFileStore } from '../../stores/fileStore';
 import { useTimelineStore } from '../../stores/timelineStore';
 import { timelineService } from '../../services/timelineService';
 import type { TimelineNode } from '../../types/timeline';
+import { toErrorMessage } from '../../utils/errorHandler';
 
 interface HistorySectionProps {
   isExpanded: boolean;
@@ -20,6 +21,7 @@ const OPERATION_LABELS: Record<string, string> = {
   delete_file: '删除资源',
   duplicate_file: '复制文件',
   move_file: '移动资源',
+  copy_file: '复制资源',
   update_file: '直接更新文件',
   restore: '时间轴还原',
 };
@@ -72,7 +74,7 @@ const HistorySection: React.FC<HistorySectionProps> = ({ isExpanded }) => {
       await loadNodes(currentWorkspace, MAX_RECORDS);
       toast.success('时间轴还原成功');
     } catch (error) {
-      const message = error instanceof Error ? error.message : String(error);
+      const message = toErrorMessage(error);
       if (message === 'RESTORE_CANCELLED') return;
       toast.error(`时间轴还原失败: ${message}`);
     }
@@ -14,6 +14,7 @@ import type {
   WorkflowTemplate,
   WorkflowTemplateStatus,
 } from '../../types/template';
+import { toErrorMessage } from '../../utils/errorHandler';
 
 interface InstructionSectionProps {
   isExpanded: boolean;
@@ -118,7 +119,7 @@ const InstructionSection: React.FC<InstructionSectionProps> = ({ isExpanded }) =
       }
     } catch (error) {
       console.error('加载工作流模板失败:', error);
-      toast.error(`加载模板失败: ${error instanceof Error ? error.message : String(error)}`);
+      toast.error(`加载模板失败: ${toErrorMessage(error)}`);
     } finally {
       setIsLoading(false);
     }
@@ -135,7 +136,7 @@ const InstructionSection: React.FC<InstructionSectionProps> = ({ isExpanded }) =
         setEditorContent(payload.document.content);
       } catch (error) {
         console.error('加载模板详情失败:', error);
-        toast.error(`打开模板失败: ${error instanceof Error ? error.message : String(error)}`);
+        toast.error(`打开模板失败: ${toErrorMessage(error)}`);
       }
     },
     [currentWorkspace, setActiveTemplate, upsertTemplate, upsertTemplateDocument],
@@ -177,7 +178,7 @@ const InstructionSection: React.FC<InstructionSectionProps> = ({ isExpanded }) =
         toast.success(`已创建工作流模板：${created.name}`);
       } catch (error) {
         console.error('创建工作流模板失败:', error);
-        toast.error(`创建模板失败: ${error instanceof Error ? error.message : String(error)}`);
+        toast.error(`创建模板失败: ${toErrorMessage(error)}`);
       }
     },
     [currentWorkspace, setActiveTemplate, upsertTemplate, upsertTemplateDocument],
@@ -197,7 +198,7 @@ const InstructionSection: React.FC<InstructionSectionProps> = ({ isExpanded }) =
       toast.success('模板文档已保存');
     } catch (error) {
       console.error('保存模板文档失败:', error);
-      toast.error(`保存模板失败: ${error instanceof Error ? error.message : String(error)}`);
+      toast.error(`保存模板失败: ${toErrorMessage(error)}`);
     } finally {
       setIsSaving(false);
     }
@@ -220,7 +221,7 @@ const InstructionSection: React.FC<InstructionSectionProps> = ({ isExpanded }) =
       }
     } catch (error) {
       console.error('解析工作流模板失败:', error);
-      toast.error(`解析模板失败: ${error instanceof Error ? error.message : String(error)}`);
+      toast.error(`解析模板失败: ${toErrorMessage(error)}`);
     } finally {
       setIsParsing(false);
     }
@@ -241,7 +242,7 @@ const InstructionSection: React.FC<InstructionSectionProps> = ({ isExpanded }) =
       }
     } catch (error) {
       console.error('编译工作流模板失败:', error);
-      toast.error(`编译模板失败: ${error instanceof Error ? error.message : String(error)}`);
+      toast.error(`编译模板失败: ${toErrorMessage(error)}`);
     } finally {
       setIsCompiling(false);
     }
@@ -263,7 +264,7 @@ const InstructionSection: React.FC<InstructionSectionProps> = ({ isExpanded }) =
         toast.success(`模板状态已更新为：${statusLabel[status]}`);
       } catch (error) {
         console.error('更新模板状态失败:', error);
-        toast.error(`更新模板状态失败: ${error instanceof Error ? error.message : String(error)}`);
+        toast.error(`更新模板状态失败: ${toErrorMessage(error)}`);
       }
     },
     [activeTemplate, currentWorkspace, upsertTemplate],
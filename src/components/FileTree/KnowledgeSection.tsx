@@ -12,6 +12,7 @@ import type {
   KnowledgeVerificationStatus,
   KnowledgeVisibilityScope,
 } from '../../types/knowledge';
+import { toErrorMessage } from '../../utils/errorHandler';
 
 interface KnowledgeSectionProps {
   isExpanded: boolean;
@@ -196,7 +197,7 @@ const KnowledgeSection: React.FC<KnowledgeSectionProps> = ({ isExpanded }) => {
         window.dispatchEvent(new CustomEvent('binder-knowledge-changed'));
       } catch (error) {
         console.error('知识库操作失败:', error);
-        toast.error(`知识库操作失败: ${error instanceof Error ? error.message : String(error)}`);
+        toast.error(`知识库操作失败: ${toErrorMessage(error)}`);
       } finally {
         setBusyKey(null);
       }
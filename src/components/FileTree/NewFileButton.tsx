@@ -4,6 +4,7 @@ import { fileService } from '../../services/fileService';
 import { FileTreeRef } from './FileTree';
 import InputDialog from './InputDialog';
 import { toast } from '../Common/Toast';
+import { toErrorMessage } from '../../utils/errorHandler';
 
 interface NewFileButtonProps {
   fileTreeRef?: React.RefObject<FileTreeRef>;
@@ -135,7 +136,7 @@ const NewFileButton: React.FC<NewFileButtonProps> = ({ fileTreeRef }) => {
       }
     } catch (error) {
       console.error('[NewFileButton] ❌ 创建文件失败:', error);
-      const errorMessage = error instanceof Error ? error.message : String(error);
+      const errorMessage = toErrorMessage(error);
       toast.error(`创建${fileType === 'folder' ? '文件夹' : '文件'}失败: ${errorMessage}`);
     }
   };
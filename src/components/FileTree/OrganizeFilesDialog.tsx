@@ -1,9 +1,24 @@
-import React, { useState, useEffect } from 'react';
+import React, { useState, useEffect, useRef } from 'react';
+import { listen } from '@tauri-apps/api/event';
 import Modal from '../Common/Modal';
 import { SparklesIcon, CheckCircleIcon, XCircleIcon } from '@heroicons/react/24/outline';
-import { fileService, FileClassification, FileMoveResult } from '../../services/fileService';
+import {
+  fileService,
+  FileBatchProgress,
+  FileMoveResult,
+  OrganizePlan,
+  PlannedMove,
+} from '../../services/fileService';
 import { useFileStore } from '../../stores/fileStore';
 import { toast } from '../Common/Toast';
+import { toErrorMessage } from '../../utils/errorHandler';
+
+const CONTENT_TYPE_LABELS: Record<string, string> = {
+  invoice: '发票',
+  contract: '合同',
+  meeting_notes: '会议纪要',
+  scanned_document: '扫描件',
+};
 
 interface OrganizeFilesDialogProps {
   filePaths: string[];
@@ -18,60 +33,172 @@ const OrganizeFilesDialog: React.FC<OrganizeFilesDialogProps> = ({
 }) => {
   const { currentWorkspace } = useFileStore();
   const [isClassifying, setIsClassifying] = useState(false);
-  const [classifications, setClassifications] = useState<FileClassification[]>([]);
+  const [plan, setPlan] = useState<OrganizePlan | null>(null);
+  // 用户取消勾选、不参与整理的文件
+  const [excluded, setExcluded] = useState<Set<string>>(new Set());
   const [results, setResults] = useState<FileMoveResult[]>([]);
   const [step, setStep] = useState<'preview' | 'organizing' | 'complete'>('preview');
+  const [progress, setProgress] = useState<FileBatchProgress | null>(null);
+  // 本次分类 / 整理的请求 ID，用于过滤进度事件与取消
+  const requestIdRef = useRef(
+    crypto.randomUUID?.() ?? `organize_${Date.now()}_${Math.random().toString(36).slice(2)}`
+  );
+  const cancelledRef = useRef(false);
+
+  // 监听分类与整理进度
+  useEffect(() => {
+    const unlistenFns: Array<() => void> = [];
+    const onProgress = (payload: FileBatchProgress) => {
+      if (payload.request_id === requestIdRef.current) {
+        setProgress(payload);
+      }
+    };
+    (async () => {
+      for (const event of ['classify-progress', 'organize-progress']) {
+        unlistenFns.push(await listen<FileBatchProgress>(event, (e) => onProgress(e.payload)));
+      }
+    })();
+    return () => unlistenFns.forEach((unlisten) => unlisten());
+  }, []);
 
-  // 加载分类预览
+  const handleCancel = async () => {
+    cancelledRef.current = true;
+    await fileService.cancelFileOrganize(requestIdRef.current).catch(() => false);
+  };
+
+  // 加载整理计划（dry run，不移动文件）
   useEffect(() => {
-    const loadClassifications = async () => {
+    const loadPlan = async () => {
       if (!currentWorkspace || filePaths.length === 0) return;
 
       setIsClassifying(true);
       try {
-        const classifications = await fileService.classifyFiles(filePaths, currentWorkspace);
-        setClassifications(classifications);
+        const report = await fileService.organizeFiles(
+          filePaths,
+          currentWorkspace,
+          true,
+          requestIdRef.current
+        );
+        setPlan(report.plan);
       } catch (error) {
+        if (cancelledRef.current) {
+          toast.info('已取消分类');
+          onClose();
+          return;
+        }
         console.error('分类文件失败:', error);
-        toast.error(`分类文件失败: ${error instanceof Error ? error.message : String(error)}`);
+        toast.error(`分类文件失败: ${toErrorMessage(error)}`);
         onClose();
       } finally {
         setIsClassifying(false);
+        setProgress(null);
       }
     };
 
-    loadClassifications();
+    loadPlan();
   }, [filePaths, currentWorkspace, onClose]);
 
   const handleOrganize = async () => {
-    if (!currentWorkspace) return;
+    if (!currentWorkspace || !plan) return;
 
     setStep('organizing');
 
     try {
-      const results = await fileService.organizeFiles(filePaths, currentWorkspace);
+      // 按预览的计划执行，不再重新分类
+      const moves = plan.moves.filter((move) => !excluded.has(move.file_path));
+      const results = await fileService.applyOrganizePlan(
+        currentWorkspace,
+        { ...plan, moves },
+        requestIdRef.current
+      );
       setResults(results);
+      if (cancelledRef.current) {
+        toast.info('已取消整理，已移动的文件可通过“撤销整理”还原');
+      }
       setStep('complete');
     } catch (error) {
       console.error('整理文件失败:', error);
-      toast.error(`整理文件失败: ${error instanceof Error ? error.message : String(error)}`);
+      toast.error(`整理文件失败: ${toErrorMessage(error)}`);
       onClose();
     }
   };
 
+  const handleUndo = async () => {
+    if (!currentWorkspace) return;
+
+    try {
+      const undoResults = await fileService.undoLastOrganize(currentWorkspace);
+      const failed = undoResults.filter((result) => !result.success);
+      if (failed.length > 0) {
+        toast.warning(`已撤销整理，${failed.length} 个文件无法还原：${failed[0].message}`);
+      } else {
+        toast.success(`已撤销整理，还原 ${undoResults.length} 个文件`);
+      }
+      onComplete();
+      onClose();
+    } catch (error) {
+      console.error('撤销整理失败:', error);
+      toast.error(`撤销整理失败: ${toErrorMessage(error)}`);
+    }
+  };
+
   const getFileName = (path: string): string => {
     return path.split('/').pop() || path;
   };
 
+  const renderProgress = () =>
+    progress && (
+      <div className="mt-4 text-left">
+        <div className="h-2 bg-gray-200 dark:bg-gray-700 rounded overflow-hidden">
+          <div
+            className="h-full bg-blue-500 transition-all"
+            style={{ width: `${progress.total ? (progress.processed / progress.total) * 100 : 0}%` }}
+          />
+        </div>
+        <div className="flex justify-between text-xs text-gray-500 dark:text-gray-400 mt-1 gap-2">
+          <span className="truncate">{getFileName(progress.current_file)}</span>
+          <span className="flex-shrink-0">
+            {progress.processed} / {progress.total}
+            {progress.errors > 0 && `，失败 ${progress.errors}`}
+          </span>
+        </div>
+      </div>
+    );
+
+  const cancelButton = (
+    <button
+      onClick={handleCancel}
+      className="mt-4 px-3 py-1 text-sm bg-gray-100 dark:bg-gray-800 text-gray-700 dark:text-gray-300 rounded hover:bg-gray-200 dark:hover:bg-gray-700 transition-colors"
+    >
+      取消
+    </button>
+  );
+
+  const toggleExcluded = (filePath: string) => {
+    setExcluded((prev) => {
+      const next = new Set(prev);
+      if (next.has(filePath)) {
+        next.delete(filePath);
+      } else {
+        next.add(filePath);
+      }
+      return next;
+    });
+  };
+
   // 按分类分组
-  const groupedByCategory = classifications.reduce((acc, classification) => {
-    const category = classification.category;
+  const groupedByCategory = (plan?.moves ?? []).reduce((acc, move) => {
+    const category = move.destination ? move.category : '';
     if (!acc[category]) {
       acc[category] = [];
     }
-    acc[category].push(classification);
+    acc[category].push(move);
     return acc;
-  }, {} as Record<string, FileClassification[]>);
+  }, {} as Record<string, PlannedMove[]>);
+
+  const selectedMoveCount = (plan?.moves ?? []).filter(
+    (move) => move.destination && !excluded.has(move.file_path)
+  ).length;
 
   return (
     <Modal isOpen={true} onClose={onClose}>
@@ -89,11 +216,16 @@ const OrganizeFilesDialog: React.FC<OrganizeFilesDialogProps> = ({
               <div className="py-8 text-center">
                 <div className="animate-spin rounded-full h-12 w-12 border-b-2 border-blue-600 mx-auto mb-4"></div>
                 <p className="text-gray-600 dark:text-gray-400">正在分析文件内容...</p>
+                {renderProgress()}
+                {cancelButton}
               </div>
             ) : (
               <>
                 <p className="text-sm text-gray-600 dark:text-gray-400 mb-6">
-                  已分析 {filePaths.length} 个文件，建议分类如下：
+                  已分析 {plan?.total ?? filePaths.length} 个文件：将移动 {plan?.move_count ?? 0} 个，
+                  保持原位置 {plan?.unchanged_count ?? 0} 个
+                  {plan && plan.conflict_count > 0 && `，${plan.conflict_count} 个同名冲突将自动改名`}
+                  {plan && plan.new_folders.length > 0 && `，新建文件夹：${plan.new_folders.join('、')}`}
                 </p>
 
                 <div className="space-y-4 mb-6">
@@ -103,19 +235,46 @@ const OrganizeFilesDialog: React.FC<OrganizeFilesDialogProps> = ({
                       className="border border-gray-200 dark:border-gray-700 rounded-lg p-4"
                     >
                       <div className="font-semibold text-gray-900 dark:text-gray-100 mb-2">
-                        📁 {category} ({files.length} 个文件)
+                        📁 {category || '保持原位置'} ({files.length} 个文件)
                       </div>
                       <div className="space-y-1">
-                        {files.map((classification) => (
-                          <div
-                            key={classification.file_path}
-                            className="text-sm text-gray-600 dark:text-gray-400 flex items-center justify-between"
+                        {files.map((move) => (
+                          <label
+                            key={move.file_path}
+                            className="text-sm text-gray-600 dark:text-gray-400 flex items-center justify-between gap-2"
                           >
-                            <span className="truncate flex-1">{getFileName(classification.file_path)}</span>
-                            <span className="text-xs text-gray-500 dark:text-gray-500 ml-2">
-                              {Math.round(classification.confidence * 100)}%
-                            </span>
-                          </div>
+                            {move.destination && (
+                              <input
+                                type="checkbox"
+                                checked={!excluded.has(move.file_path)}
+                                onChange={() => toggleExcluded(move.file_path)}
+                              />
+                            )}
+                            <span className="truncate flex-1">{getFileName(move.file_path)}</span>
+                            {move.content_type && (
+                              <span className="text-xs px-1.5 py-0.5 rounded bg-gray-100 text-gray-600 dark:bg-gray-800 dark:text-gray-300">
+                                {CONTENT_TYPE_LABELS[move.content_type] ?? move.content_type}
+                              </span>
+                            )}
+                            {move.tag && (
+                              <span className="text-xs px-1.5 py-0.5 rounded bg-blue-50 text-blue-600 dark:bg-blue-900/30 dark:text-blue-300">
+                                {move.tag}
+                              </span>
+                            )}
+                            {move.destination && (
+                              <span
+                                className={`text-xs truncate max-w-[45%] ${
+                                  move.conflict
+                                    ? 'text-amber-600 dark:text-amber-400'
+                                    : 'text-gray-500 dark:text-gray-500'
+                                }`}
+                                title={move.destination}
+                              >
+                                → {getFileName(move.destination)}
+                                {move.conflict && '（同名已改名）'}
+                              </span>
+                            )}
+                          </label>
                         ))}
                       </div>
                       {files[0]?.reason && (
@@ -136,10 +295,11 @@ const OrganizeFilesDialog: React.FC<OrganizeFilesDialogProps> = ({
                   </button>
                   <button
                     onClick={handleOrganize}
-                    className="px-4 py-2 bg-blue-500 text-white rounded hover:bg-blue-600 transition-colors flex items-center gap-2"
+                    disabled={selectedMoveCount === 0}
+                    className="px-4 py-2 bg-blue-500 text-white rounded hover:bg-blue-600 transition-colors flex items-center gap-2 disabled:opacity-50 disabled:cursor-not-allowed"
                   >
                     <SparklesIcon className="w-4 h-4" />
-                    开始整理
+                    开始整理（{selectedMoveCount}）
                   </button>
                 </div>
               </>
@@ -151,6 +311,8 @@ const OrganizeFilesDialog: React.FC<OrganizeFilesDialogProps> = ({
           <div className="py-8 text-center">
             <div className="animate-spin rounded-full h-12 w-12 border-b-2 border-blue-600 mx-auto mb-4"></div>
             <p className="text-gray-600 dark:text-gray-400">正在整理文件...</p>
+            {renderProgress()}
+            {cancelButton}
           </div>
         )}
 
@@ -186,7 +348,15 @@ const OrganizeFilesDialog: React.FC<OrganizeFilesDialogProps> = ({
               </div>
             </div>
 
-            <div className="flex justify-end">
+            <div className="flex gap-2 justify-end">
+              {results.some((result) => result.success && result.message.startsWith('已移动')) && (
+                <button
+                  onClick={handleUndo}
+                  className="px-4 py-2 bg-gray-100 dark:bg-gray-800 text-gray-700 dark:text-gray-300 rounded hover:bg-gray-200 dark:hover:bg-gray-700 transition-colors"
+                >
+                  撤销整理
+                </button>
+              )}
               <button
                 onClick={() => {
                   onComplete();
@@ -10,6 +10,7 @@ import { useFileStore } from '../../stores/fileStore';
 import { fileService } from '../../services/fileService';
 import { toast } from '../Common/Toast';
 import InputDialog from './InputDialog';
+import { toErrorMessage } from '../../utils/errorHandler';
 
 interface ResourceToolbarProps {
   onSearch?: () => void;
@@ -122,7 +123,7 @@ const ResourceToolbar: React.FC<ResourceToolbarProps> = ({ onSearch, onRefresh }
       }
     } catch (error) {
       console.error('创建失败:', error);
-      const errorMessage = error instanceof Error ? error.message : String(error);
+      const errorMessage = toErrorMessage(error);
       toast.error(`创建失败: ${errorMessage}`);
     }
   };
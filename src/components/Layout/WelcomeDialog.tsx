@@ -5,6 +5,7 @@ import APIKeyConfig from '../Settings/APIKeyConfig';
 import { KeyIcon } from '@heroicons/react/24/outline';
 import { Workspace } from '../../types/workspace';
 import { toast } from '../Common/Toast';
+import { toErrorMessage } from '../../utils/errorHandler';
 
 interface WelcomeDialogProps {
   onClose: () => void;
@@ -38,7 +39,7 @@ const WelcomeDialog: React.FC<WelcomeDialogProps> = ({ onClose }) => {
       }
     } catch (error) {
       console.error('打开工作区失败:', error);
-      toast.error(`打开工作区失败: ${error instanceof Error ? error.message : String(error)}`);
+      toast.error(`打开工作区失败: ${toErrorMessage(error)}`);
     }
   };
 
@@ -52,7 +53,7 @@ const WelcomeDialog: React.FC<WelcomeDialogProps> = ({ onClose }) => {
       }
     } catch (error) {
       console.error('创建工作区失败:', error);
-      toast.error(`创建工作区失败: ${error instanceof Error ? error.message : String(error)}`);
+      toast.error(`创建工作区失败: ${toErrorMessage(error)}`);
     }
   };
 
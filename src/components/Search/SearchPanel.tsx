@@ -4,6 +4,8 @@ import { useFileStore } from '../../stores/fileStore';
 import { MagnifyingGlassIcon, XMarkIcon } from '@heroicons/react/24/outline';
 import { documentService } from '../../services/documentService';
 import { toast } from '../Common/Toast';
+import { isAbsolutePath } from '../../utils/pathUtils';
+import { toErrorMessage } from '../../utils/errorHandler';
 
 interface SearchResult {
     path: string;
@@ -35,7 +37,7 @@ const SearchPanel: React.FC = () => {
             setResults(searchResults);
         } catch (error) {
             console.error('搜索失败:', error);
-            toast.error(`搜索失败: ${error instanceof Error ? error.message : String(error)}`);
+            toast.error(`搜索失败: ${toErrorMessage(error)}`);
             setResults([]);
         } finally {
             setIsSearching(false);
@@ -57,6 +59,11 @@ const SearchPanel: React.FC = () => {
     const handleResultClick = useCallback(async (result: SearchResult) => {
         if (!currentWorkspace) return;
 
+        // 参考资料文件夹中的文档以绝对路径索引，只读打开
+        if (isAbsolutePath(result.path)) {
+            await documentService.openFile(result.path, { readOnly: true });
+            return;
+        }
             const fullPath = currentWorkspace + '/' + result.path;
         await documentService.openFile(fullPath);
     }, [currentWorkspace]);
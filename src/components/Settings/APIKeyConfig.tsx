@@ -2,6 +2,7 @@ import React, { useState, useEffect } from 'react';
 import { invoke } from '@tauri-apps/api/core';
 import { KeyIcon, EyeIcon, EyeSlashIcon } from '@heroicons/react/24/outline';
 import ThemeSelector from './ThemeSelector';
+import { toErrorMessage } from '../../utils/errorHandler';
 
 interface APIKeyConfigProps {
     onClose?: () => void;
@@ -74,7 +75,7 @@ const APIKeyConfig: React.FC<APIKeyConfigProps> = ({ onClose }) => {
             console.error('保存 API key 失败:', error);
             setMessage({
                 type: 'error',
-                text: `保存失败: ${error instanceof Error ? error.message : String(error)}`,
+                text: `保存失败: ${toErrorMessage(error)}`,
             });
         } finally {
             setIsLoading(false);
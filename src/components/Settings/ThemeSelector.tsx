@@ -3,7 +3,7 @@ import { useThemeStore } from '../../stores/themeStore';
 import { SunIcon, MoonIcon, ComputerDesktopIcon } from '@heroicons/react/24/outline';
 
 const ThemeSelector: React.FC = () => {
-  const { theme, setTheme } = useThemeStore();
+  const { theme, setTheme, darkPreview, setDarkPreview } = useThemeStore();
 
   return (
     <div className="p-4">
@@ -45,6 +45,14 @@ const ThemeSelector: React.FC = () => {
           <span>跟随系统</span>
         </button>
       </div>
+      <label className="mt-4 flex items-center gap-2 text-sm text-gray-700 dark:text-gray-300">
+        <input
+          type="checkbox"
+          checked={darkPreview}
+          onChange={(e) => setDarkPreview(e.target.checked)}
+        />
+        暗色主题下以暗色显示文档预览（PDF）
+      </label>
     </div>
   );
 };
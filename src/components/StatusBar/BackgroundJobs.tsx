@@ -0,0 +1,141 @@
+import React, { useEffect, useState } from 'react';
+import { QueueListIcon, XMarkIcon } from '@heroicons/react/24/outline';
+import { jobService } from '../../services/jobService';
+import type { JobInfo, JobStatus } from '../../types/job';
+import { toErrorMessage } from '../../utils/errorHandler';
+import { toast } from '../Common/Toast';
+
+const STATUS_LABELS: Record<JobStatus, string> = {
+  queued: '排队中',
+  running: '进行中',
+  completed: '已完成',
+  failed: '失败',
+  cancelled: '已取消',
+};
+
+const isActive = (job: JobInfo) => job.status === 'queued' || job.status === 'running';
+
+// 状态栏“后台任务”按钮与面板：初始拉取 list_jobs，之后由 job-updated 事件增量更新
+const BackgroundJobs: React.FC = () => {
+  const [jobs, setJobs] = useState<JobInfo[]>([]);
+  const [open, setOpen] = useState(false);
+
+  useEffect(() => {
+    let unlisten: (() => void) | undefined;
+    let disposed = false;
+    jobService
+      .listJobs()
+      .then((list) => !disposed && setJobs(list))
+      .catch((e) => console.warn('获取后台任务失败:', e));
+    jobService
+      .onJobUpdated((job) => {
+        setJobs((prev) => {
+          const index = prev.findIndex((j) => j.id === job.id);
+          if (index === -1) return [job, ...prev];
+          const next = [...prev];
+          next[index] = job;
+          return next;
+        });
+      })
+      .then((fn) => {
+        if (disposed) fn();
+        else unlisten = fn;
+      });
+    return () => {
+      disposed = true;
+      unlisten?.();
+    };
+  }, []);
+
+  const activeCount = jobs.filter(isActive).length;
+
+  const handleCancel = async (id: string) => {
+    try {
+      await jobService.cancelJob(id);
+    } catch (e) {
+      toast.error(toErrorMessage(e));
+    }
+  };
+
+  const handleClearFinished = async () => {
+    try {
+      await jobService.clearFinished();
+      setJobs((prev) => prev.filter(isActive));
+    } catch (e) {
+      toast.error(toErrorMessage(e));
+    }
+  };
+
+  return (
+    <div className="relative">
+      <button
+        onClick={() => setOpen(!open)}
+        className={`
+          h-5 px-1 rounded flex items-center justify-center gap-0.5
+          transition-all duration-150
+          ${activeCount > 0
+            ? 'text-blue-600 dark:text-blue-400'
+            : 'text-gray-500 dark:text-gray-400 hover:text-gray-700 dark:hover:text-gray-300 hover:bg-gray-100 dark:hover:bg-gray-700/50'
+          }
+        `}
+        title="后台任务"
+      >
+        <QueueListIcon className={`w-3.5 h-3.5 ${activeCount > 0 ? 'animate-pulse' : ''}`} />
+        {activeCount > 0 && <span>{activeCount}</span>}
+      </button>
+
+      {open && (
+        <div className="absolute right-0 bottom-7 w-72 max-h-80 overflow-y-auto rounded border border-gray-200 dark:border-gray-700 bg-white dark:bg-gray-800 shadow-lg z-50">
+          <div className="px-3 py-2 flex items-center justify-between border-b border-gray-200 dark:border-gray-700">
+            <span className="font-medium text-gray-700 dark:text-gray-200">后台任务</span>
+            {jobs.some((j) => !isActive(j)) && (
+              <button
+                onClick={handleClearFinished}
+                className="text-gray-500 hover:text-gray-700 dark:hover:text-gray-300"
+              >
+                清除已结束
+              </button>
+            )}
+          </div>
+          {jobs.length === 0 ? (
+            <div className="px-3 py-4 text-center text-gray-400 dark:text-gray-500">没有后台任务</div>
+          ) : (
+            jobs.map((job) => (
+              <div key={job.id} className="px-3 py-2 border-b last:border-b-0 border-gray-100 dark:border-gray-700/50">
+                <div className="flex items-center gap-2">
+                  <span className="flex-1 min-w-0 truncate text-gray-700 dark:text-gray-200" title={job.title}>
+                    {job.title}
+                  </span>
+                  <span className={job.status === 'failed' ? 'text-red-500' : 'text-gray-400'}>
+                    {STATUS_LABELS[job.status]}
+                  </span>
+                  {isActive(job) && (
+                    <button
+                      onClick={() => handleCancel(job.id)}
+                      className="w-4 h-4 rounded flex items-center justify-center text-gray-400 hover:text-red-500"
+                      title="取消"
+                    >
+                      <XMarkIcon className="w-3 h-3" />
+                    </button>
+                  )}
+                </div>
+                {job.status === 'running' && job.progress !== null && (
+                  <div className="mt-1 h-1 rounded bg-gray-200 dark:bg-gray-700 overflow-hidden">
+                    <div className="h-full bg-blue-500" style={{ width: `${Math.round(job.progress * 100)}%` }} />
+                  </div>
+                )}
+                {(job.error || job.message) && (
+                  <div className={`mt-0.5 truncate ${job.error ? 'text-red-500' : 'text-gray-400'}`} title={job.error ?? job.message ?? ''}>
+                    {job.error ?? job.message}
+                  </div>
+                )}
+              </div>
+            ))
+          )}
+        </div>
+      )}
+    </div>
+  );
+};
+
+export default BackgroundJobs;
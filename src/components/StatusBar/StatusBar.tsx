@@ -8,6 +8,8 @@ import {
   ChatBubbleLeftRightIcon,
   TrashIcon,
 } from '@heroicons/react/24/outline';
+import { toErrorMessage } from '../../utils/errorHandler';
+import BackgroundJobs from './BackgroundJobs';
 
 const StatusBar: React.FC = () => {
   const { currentWorkspace } = useFileStore();
@@ -26,11 +28,12 @@ const StatusBar: React.FC = () => {
     setCacheClearing(true);
     setCacheTip(null);
     try {
-      const result = await invoke<string>('clear_preview_cache');
-      setCacheTip(result);
+      const result = await invoke<{ removed_count: number; freed_bytes: number }>('clear_preview_cache');
+      const freedMb = (result.freed_bytes / 1024 / 1024).toFixed(1);
+      setCacheTip(`预览缓存已清除 ${result.removed_count} 个文件，释放 ${freedMb} MB（默认字体配置已保留）`);
       setTimeout(() => setCacheTip(null), 2500);
     } catch (e) {
-      setCacheTip(e instanceof Error ? e.message : String(e));
+      setCacheTip(toErrorMessage(e));
       setTimeout(() => setCacheTip(null), 2500);
     } finally {
       setCacheClearing(false);
@@ -55,6 +58,9 @@ const StatusBar: React.FC = () => {
 
       {/* 右侧：窗口切换按钮 */}
       <div className="flex items-center gap-1 flex-shrink-0">
+        {/* 后台任务 */}
+        <BackgroundJobs />
+
         {/* 文件树切换按钮 */}
         <button
           onClick={() => setFileTreeVisible(!fileTree.visible)}
@@ -4,6 +4,7 @@ import { useFileStore } from '../../stores/fileStore';
 import { useLayoutStore } from '../../stores/layoutStore';
 import { fileService } from '../../services/fileService';
 import { toast } from '../Common/Toast';
+import { toErrorMessage } from '../../utils/errorHandler';
 
 interface QuickActionsProps {
   onClose: () => void;
@@ -28,7 +29,7 @@ const QuickActions: React.FC<QuickActionsProps> = ({ onClose, onAPIKeyConfig })
       }
     } catch (error) {
       console.error('打开工作区失败:', error);
-      toast.error(`打开工作区失败: ${error instanceof Error ? error.message : String(error)}`);
+      toast.error(`打开工作区失败: ${toErrorMessage(error)}`);
     }
   };
 
@@ -46,7 +47,7 @@ const QuickActions: React.FC<QuickActionsProps> = ({ onClose, onAPIKeyConfig })
       }
     } catch (error) {
       console.error('创建工作区失败:', error);
-      toast.error(`创建工作区失败: ${error instanceof Error ? error.message : String(error)}`);
+      toast.error(`创建工作区失败: ${toErrorMessage(error)}`);
     }
   };
 
@@ -8,9 +8,9 @@
 import { useState, useCallback, useEffect } from 'react';
 import { invoke } from '@tauri-apps/api/core';
 import type { Editor } from '@tiptap/react';
+import type { Node as PMNode } from '@tiptap/pm/model';
+import { toErrorMessage } from '../utils/errorHandler';
 
-const CONTEXT_BEFORE_LEN = 600;
-const CONTEXT_AFTER_LEN = 400;
 const MAX_LENGTH = 80;
 
 export interface AutoCompleteState {
@@ -62,20 +62,10 @@ export function useAutoComplete(
     if (!editor || editor.isDestroyed) return;
 
     const { from } = editor.state.selection;
-    const doc = editor.state.doc;
-    const fullText = doc.textContent;
-    const textBefore = fullText.slice(0, from);
-    const textAfter = fullText.slice(from);
-
-    const context_before = textBefore.length > CONTEXT_BEFORE_LEN
-      ? textBefore.slice(-CONTEXT_BEFORE_LEN)
-      : textBefore;
-    const context_after = textAfter.length > CONTEXT_AFTER_LEN
-      ? textAfter.slice(0, CONTEXT_AFTER_LEN)
-      : textAfter || undefined;
+    const { content, cursor } = serializeDocument(editor.state.doc, from);
 
     const minContext = options?.minContextLength ?? 50;
-    if (context_before.length < minContext) {
+    if (cursor < minContext) {
       setState((prev) => ({ ...prev, error: '上下文不足，请至少输入 50 字符后再试', isVisible: false, isLoading: false }));
       return;
     }
@@ -89,15 +79,15 @@ export function useAutoComplete(
     }));
 
     try {
+      // 上下文（前后段落、大纲、front matter）由后端按 token 预算组织
       const result = await invoke<string[] | null>('ai_autocomplete', {
-        contextBefore: context_before,
-        contextAfter: context_after || null,
-        position: from,
+        position: cursor,
         maxLength: options?.maxLength ?? MAX_LENGTH,
         editorState: null,
         memoryItems: null,
         documentFormat: options?.documentPath ? getDocumentFormat(options.documentPath) : null,
-        documentOverview: null,
+        path: options?.documentPath ?? null,
+        content,
       });
 
       const suggestions = Array.isArray(result) ? result.slice(0, 3) : [];
@@ -124,7 +114,7 @@ export function useAutoComplete(
         error: null,
       }));
     } catch (err) {
-      const msg = err instanceof Error ? err.message : String(err);
+      const msg = toErrorMessage(err);
       setState((prev) => ({
         ...prev,
         suggestions: [],
@@ -189,3 +179,25 @@ function getDocumentFormat(path: string): string {
   if (ext === 'html' || ext === 'htm') return 'html';
   return 'txt';
 }
+
+/** 编辑器内容转为段落文本（标题加 # 前缀、段落以空行分隔），并换算光标对应的字符串下标 */
+function serializeDocument(doc: PMNode, pos: number): { content: string; cursor: number } {
+  const blocks: string[] = [];
+  let length = 0;
+  let cursor = -1;
+  doc.descendants((node, nodePos) => {
+    if (!node.isTextblock) return true;
+    const prefix = node.type.name === 'heading' ? `${'#'.repeat(node.attrs.level ?? 1)} ` : '';
+    const text = node.textBetween(0, node.content.size);
+    if (blocks.length > 0) length += 2;
+    const start = nodePos + 1;
+    if (cursor < 0 && pos >= start && pos <= start + node.content.size) {
+      cursor = length + prefix.length + node.textBetween(0, pos - start).length;
+    }
+    blocks.push(prefix + text);
+    length += prefix.length + text.length;
+    return false;
+  });
+  const content = blocks.join('\n\n');
+  return { content, cursor: cursor < 0 ? content.length : cursor };
+}
@@ -10,11 +10,29 @@ import { Editor } from '@tiptap/react';
 export type InlineAssistMessageRole = 'user' | 'assistant';
 export type InlineAssistMessageKind = 'reply' | 'edit';
 
+/** 相对选中文本的修改块，start/end 为选中文本内的字符串下标 */
+export interface InlineAssistChange {
+    start: number;
+    end: number;
+    before: string;
+    after: string;
+}
+
+/** ai_inline_assist 的返回结构 */
+export interface InlineAssistResult {
+    kind: InlineAssistMessageKind;
+    text: string;
+    before: string | null;
+    changes: InlineAssistChange[];
+}
+
 export interface InlineAssistMessage {
     id: string;
     role: InlineAssistMessageRole;
     kind: InlineAssistMessageKind;
     text: string;
+    /** edit 消息的修改块，用于修订预览与逐块接受 */
+    changes?: InlineAssistChange[];
     applied?: boolean;
     createdAt: number;
 }
@@ -173,47 +191,23 @@ export function useInlineAssist(editor: Editor | null) {
                 messagesCount: messagesForBackend.length,
             });
 
-            // 调用后端（返回 JSON 格式）
-            const result = await invoke<string>('ai_inline_assist', {
+            // 调用后端（返回 kind / text 与修改块）
+            const result = await invoke<InlineAssistResult>('ai_inline_assist', {
                 instruction: currentInstruction,
                 text: state.selectedText,
                 context,
                 messages: messagesForBackend.length > 0 ? messagesForBackend : undefined,
             });
             
-            console.log('✅ Inline Assist 执行成功，原始响应:', result.substring(0, 200));
-            
-            // 解析 JSON 响应
-            let parsedResult: { kind: InlineAssistMessageKind; text: string };
-            try {
-                // 尝试解析 JSON
-                const jsonMatch = result.match(/\{[\s\S]*\}/);
-                if (jsonMatch) {
-                    parsedResult = JSON.parse(jsonMatch[0]);
-                } else {
-                    // 如果不是 JSON，使用启发式判断
-                    const isEditInstruction = /改|润色|翻译|改写|优化|修正|调整/i.test(currentInstruction);
-                    parsedResult = {
-                        kind: (state.selectedText && isEditInstruction) ? 'edit' : 'reply',
-                        text: result,
-                    };
-                }
-            } catch (e) {
-                // JSON 解析失败，使用启发式判断
-                console.warn('⚠️ JSON 解析失败，使用启发式判断:', e);
-                const isEditInstruction = /改|润色|翻译|改写|优化|修正|调整/i.test(currentInstruction);
-                parsedResult = {
-                    kind: (state.selectedText && isEditInstruction) ? 'edit' : 'reply',
-                    text: result,
-                };
-            }
+            console.log('✅ Inline Assist 执行成功:', result.kind, result.changes.length);
             
             // 添加 AI 回复消息
             const assistantMessage: InlineAssistMessage = {
                 id: `assistant-${Date.now()}`,
                 role: 'assistant',
-                kind: parsedResult.kind,
-                text: parsedResult.text,
+                kind: result.kind,
+                text: result.text,
+                changes: result.changes,
                 applied: false,
                 createdAt: Date.now(),
             };
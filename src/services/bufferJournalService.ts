@@ -0,0 +1,93 @@
+import { invoke } from '@tauri-apps/api/core';
+import { useEditorStore } from '../stores/editorStore';
+import type { BufferDelta, RecoverableBuffer } from '../types/journal';
+
+const JOURNAL_INTERVAL_MS = 3000;
+
+// 用公共前缀 / 后缀求出单个替换增量，内容相同时返回 null
+export const computeDelta = (prev: string, next: string): BufferDelta | null => {
+  if (prev === next) return null;
+  let start = 0;
+  const maxPrefix = Math.min(prev.length, next.length);
+  while (start < maxPrefix && prev.charCodeAt(start) === next.charCodeAt(start)) {
+    start++;
+  }
+  let suffix = 0;
+  const maxSuffix = Math.min(prev.length, next.length) - start;
+  while (
+    suffix < maxSuffix &&
+    prev.charCodeAt(prev.length - 1 - suffix) === next.charCodeAt(next.length - 1 - suffix)
+  ) {
+    suffix++;
+  }
+  return {
+    start,
+    delete: prev.length - start - suffix,
+    insert: next.slice(start, next.length - suffix),
+  };
+};
+
+export const bufferJournalService = {
+  async listRecoverable(): Promise<RecoverableBuffer[]> {
+    return await invoke<RecoverableBuffer[]>('list_recoverable_buffers');
+  },
+
+  async discard(path: string): Promise<void> {
+    await invoke('discard_buffer_journal', { path });
+  },
+
+  /**
+   * 每隔几秒把有未保存修改的标签页增量写入日志；保存或关闭后删除日志。
+   * 返回停止函数。
+   */
+  start(): () => void {
+    // 已写入日志的内容，按文件路径
+    const journaled = new Map<string, string>();
+    let flushing = false;
+
+    const flush = async () => {
+      if (flushing) return;
+      flushing = true;
+      try {
+        const dirtyTabs = useEditorStore
+          .getState()
+          .tabs.filter((tab) => tab.isDirty && !tab.isReadOnly && tab.filePath);
+        const dirtyPaths = new Set(dirtyTabs.map((tab) => tab.filePath));
+
+        for (const tab of dirtyTabs) {
+          const prev = journaled.get(tab.filePath);
+          try {
+            if (prev === undefined) {
+              await invoke('journal_buffer', { path: tab.filePath, base: tab.content, deltas: [] });
+            } else {
+              const delta = computeDelta(prev, tab.content);
+              if (!delta) continue;
+              await invoke('journal_buffer', { path: tab.filePath, base: null, deltas: [delta] });
+            }
+            journaled.set(tab.filePath, tab.content);
+          } catch (error) {
+            // 下次重新发送完整内容
+            journaled.delete(tab.filePath);
+            console.warn('[bufferJournal] 写入日志失败:', tab.filePath, error);
+          }
+        }
+
+        // 已保存或已关闭的文件不再需要日志
+        for (const path of Array.from(journaled.keys())) {
+          if (dirtyPaths.has(path)) continue;
+          journaled.delete(path);
+          await bufferJournalService.discard(path).catch((error) => {
+            console.warn('[bufferJournal] 删除日志失败:', path, error);
+          });
+        }
+      } finally {
+        flushing = false;
+      }
+    };
+
+    const timer = setInterval(() => {
+      void flush();
+    }, JOURNAL_INTERVAL_MS);
+    return () => clearInterval(timer);
+  },
+};
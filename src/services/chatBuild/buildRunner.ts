@@ -2,6 +2,7 @@ import type { BuildExecutionState, BuildOutlineDraft, BuildTerminalSummary } fro
 import { useChatBuildStore } from '../../stores/chatBuildStore';
 import { buildInterruptSignal } from './buildInterruptSignal';
 import { workspaceBuildWriter } from './workspaceBuildWriter';
+import { toErrorMessage } from '../../utils/errorHandler';
 
 function createExecutionState(outline: BuildOutlineDraft): BuildExecutionState {
   return {
@@ -203,7 +204,7 @@ export const buildRunner = {
         const terminal: BuildTerminalSummary = {
           kind: 'failed',
           title: outline.title,
-          summary: error instanceof Error ? error.message : '正式构建失败',
+          summary: toErrorMessage(error, '正式构建失败'),
           finishedAt: Date.now(),
           buildRootPath: target.buildRootPath,
           metaPath: target.metaPath,
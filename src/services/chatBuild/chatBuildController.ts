@@ -10,6 +10,7 @@ import { outlineGenerator } from './outlineGenerator';
 import { buildRunner } from './buildRunner';
 import { buildInterruptSignal } from './buildInterruptSignal';
 import { fileService } from '../fileService';
+import { toErrorMessage } from '../../utils/errorHandler';
 
 function generateId(prefix: string): string {
   return `${prefix}-${Date.now()}-${Math.random().toString(36).slice(2, 8)}`;
@@ -110,7 +111,7 @@ export const chatBuildController = {
       buildStore.setOutlineConfirmation(tabId, null);
       buildStore.setStatus(tabId, 'outline_pending_confirm');
     } catch (error) {
-      const message = error instanceof Error ? error.message : '大纲生成失败';
+      const message = toErrorMessage(error, '大纲生成失败');
       buildStore.setStatus(tabId, 'failed');
       buildStore.setLastError(tabId, message);
       buildStore.setTerminal(tabId, {
@@ -165,7 +166,7 @@ export const chatBuildController = {
       const tree = await fileService.buildFileTree(workspacePath, 5);
       useFileStore.getState().setFileTree(tree);
     } catch (error) {
-      const message = error instanceof Error ? error.message : 'dry-run 构建失败';
+      const message = toErrorMessage(error, 'dry-run 构建失败');
       buildStore.setStatus(tabId, 'failed');
       buildStore.setLastError(tabId, message);
       buildStore.setTerminal(tabId, {
@@ -5,6 +5,7 @@ import type {
   BuildOutlineDraft,
   BuildTerminalSummary,
 } from '../../types/chatBuild';
+import { getErrorCode } from '../../utils/errorHandler';
 
 interface BuildMetaPayload {
   run_id: string;
@@ -129,8 +130,7 @@ async function ensureUniqueBuildRoot(workspacePath: string, seed: string): Promi
       await fileService.createFolder(candidate);
       return candidate;
     } catch (error) {
-      const message = error instanceof Error ? error.message : String(error);
-      if (!message.includes('已存在')) {
+      if (getErrorCode(error) !== 'FILE_EXISTS') {
         throw error;
       }
     }
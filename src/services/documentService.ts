@@ -4,6 +4,7 @@ import { useFileStore } from '../stores/fileStore';
 import { UnopenedDocumentDiffRuntime } from './unopenedDocumentDiffRuntime';
 import { getRelativePath, normalizeWorkspacePath } from '../utils/pathUtils';
 import { FileType, FileSource, FileOpenStrategy } from '../types/file';
+import { getErrorCode, toErrorMessage } from '../utils/errorHandler';
 
 /** Phase 2：open_file_with_cache / open_docx_with_cache 返回结构 */
 interface OpenFileResult {
@@ -209,6 +210,16 @@ async function detectFileSource(
   return 'external';
 }
 
+/**
+ * 把已打开的标签页标记为只读（参考资料文件夹中的文件不允许写回）
+ */
+function markTabReadOnly(filePath: string): void {
+  const tab = useEditorStore.getState().getTabByFilePath(filePath);
+  if (tab) {
+    useEditorStore.getState().markTabReadOnly(tab.id);
+  }
+}
+
 /**
  * 创建错误占位符
  */
@@ -241,6 +252,7 @@ export const documentService = {
     options?: {
       source?: FileSource;  // 显式指定来源
       forceEdit?: boolean;  // 强制编辑模式
+      readOnly?: boolean;   // 只读打开（参考资料文件夹中的文件）
     }
   ): Promise<void> {
     try {
@@ -354,6 +366,7 @@ export const documentService = {
             throw new Error(`不支持的文件类型: ${fileType}`);
           }
           await this.openFileWithStrategy(filePath, defaultStrategy, lastModifiedTime, options);
+          if (options?.readOnly) markTabReadOnly(filePath);
           return;
         }
       }
@@ -364,11 +377,12 @@ export const documentService = {
       
       // 5. 根据策略打开文件
       await this.openFileWithStrategy(filePath, strategy, lastModifiedTime, options);
+      if (options?.readOnly) markTabReadOnly(filePath);
       
     } catch (error) {
       console.error('[documentService.openFile] 打开文件失败:', error);
       // ⚠️ 关键修复：抛出错误时包含更多上下文信息
-      const errorMessage = error instanceof Error ? error.message : String(error);
+      const errorMessage = toErrorMessage(error);
       throw new Error(`打开文件失败: ${errorMessage} (文件: ${filePath})`);
     }
   },
@@ -431,7 +445,7 @@ export const documentService = {
             }
           } catch (error) {
             console.error('[documentService.openFileWithStrategy] 读取文件内容失败:', error);
-            throw new Error(`读取文件内容失败: ${error instanceof Error ? error.message : String(error)}`);
+            throw new Error(`读取文件内容失败: ${toErrorMessage(error)}`);
           }
           try {
             const isReadOnly = false;
@@ -455,7 +469,7 @@ export const documentService = {
             }
           } catch (error) {
             console.error('[documentService.openFileWithStrategy] 添加标签页失败:', error);
-            throw new Error(`添加标签页失败: ${error instanceof Error ? error.message : String(error)}`);
+            throw new Error(`添加标签页失败: ${toErrorMessage(error)}`);
           }
           break;
         }
@@ -481,7 +495,7 @@ export const documentService = {
             }
           } catch (error) {
             console.error('[documentService.openFileWithStrategy] 读取文件内容失败:', error);
-            throw new Error(`读取文件内容失败: ${error instanceof Error ? error.message : String(error)}`);
+            throw new Error(`读取文件内容失败: ${toErrorMessage(error)}`);
           }
           try {
             const isReadOnly = false;
@@ -505,7 +519,7 @@ export const documentService = {
             }
           } catch (error) {
             console.error('[documentService.openFileWithStrategy] 添加标签页失败:', error);
-            throw new Error(`添加标签页失败: ${error instanceof Error ? error.message : String(error)}`);
+            throw new Error(`添加标签页失败: ${toErrorMessage(error)}`);
           }
           break;
         }
@@ -586,7 +600,7 @@ export const documentService = {
               }
             } catch (error) {
               // LibreOffice + ODT 转换失败，显示错误提示
-              const errorMessage = error instanceof Error ? error.message : String(error);
+              const errorMessage = toErrorMessage(error);
               
               // 分析错误类型
               let userFriendlyError = errorMessage;
@@ -603,7 +617,7 @@ export const documentService = {
                   `- 检查文件大小是否正常（损坏的文件可能很小）`;
               } else if (errorMessage.includes('文档内容过大') || errorMessage.includes('超过 15 MB') || errorMessage.includes('超过编辑模式限制')) {
                 userFriendlyError = errorMessage; // 后端已返回完整友好说明，直接使用
-              } else if (errorMessage.includes('Pandoc 不可用')) {
+              } else if (getErrorCode(error) === 'PANDOC_UNAVAILABLE') {
                 // 尝试检查 Pandoc 状态
                 try {
                   const pandocStatus = await invoke<{ available: boolean; is_bundled: boolean; path: string | null }>('check_pandoc_available');
@@ -689,7 +703,7 @@ export const documentService = {
           );
         } catch (error) {
           console.error('[documentService.openFileWithStrategy] 添加标签页失败:', error);
-          throw new Error(`添加标签页失败: ${error instanceof Error ? error.message : String(error)}`);
+          throw new Error(`添加标签页失败: ${toErrorMessage(error)}`);
         }
         break;
       }
@@ -701,7 +715,7 @@ export const documentService = {
     } catch (error) {
       // ⚠️ 关键修复：捕获 switch 语句中的所有错误
       console.error('[documentService.openFileWithStrategy] 打开文件失败:', error);
-      const errorMessage = error instanceof Error ? error.message : String(error);
+      const errorMessage = toErrorMessage(error);
       throw new Error(`打开文件失败: ${errorMessage} (文件: ${filePath}, 类型: ${fileType})`);
     }
   },
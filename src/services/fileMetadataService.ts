@@ -6,6 +6,7 @@
 import { invoke } from '@tauri-apps/api/core';
 import { FileSource } from '../types/file';
 import { normalizePath, normalizeWorkspacePath } from '../utils/pathUtils';
+import { toErrorMessage } from '../utils/errorHandler';
 
 /**
  * 记录文件为 Binder 创建的文件
@@ -65,7 +66,7 @@ export async function recordBinderFile(
         return;
       }
     } catch (error) {
-      lastError = error instanceof Error ? error : new Error(String(error));
+      lastError = new Error(toErrorMessage(error));
       if (attempt < retries) {
         console.warn(`记录文件元数据失败（尝试 ${attempt}/${retries}），重试...`, error);
         await new Promise(resolve => setTimeout(resolve, 100 * attempt)); // 递增延迟
@@ -1,6 +1,6 @@
 import { invoke } from '@tauri-apps/api/core';
 import { open } from '@tauri-apps/plugin-dialog';
-import { FileTreeNode } from '../types/file';
+import { FileTreeNode, ReferenceFolder, WatcherStatus } from '../types/file';
 import { Workspace } from '../types/workspace';
 
 export const fileService = {
@@ -11,6 +11,23 @@ export const fileService = {
     });
   },
 
+  async listReferenceFolders(workspacePath: string): Promise<ReferenceFolder[]> {
+    return await invoke<ReferenceFolder[]>('list_reference_folders', { workspacePath });
+  },
+
+  // 批量操作前后暂停 / 恢复文件监听；恢复后后端会通知整体刷新文件树
+  async pauseWatcher(workspacePath: string): Promise<void> {
+    await invoke('pause_watcher', { workspacePath });
+  },
+
+  async resumeWatcher(workspacePath: string): Promise<boolean> {
+    return await invoke<boolean>('resume_watcher', { workspacePath });
+  },
+
+  async getWatcherStatus(workspacePath: string): Promise<WatcherStatus> {
+    return await invoke<WatcherStatus>('get_watcher_status', { workspacePath });
+  },
+
   async readFile(path: string): Promise<string> {
     return await invoke<string>('read_file_content', { path });
   },
@@ -96,19 +113,54 @@ export const fileService = {
   },
 
   // ⚠️ Week 20：AI 智能分类整理
-  async classifyFiles(filePaths: string[], workspacePath: string): Promise<FileClassification[]> {
+  async classifyFiles(
+    filePaths: string[],
+    workspacePath: string,
+    requestId?: string
+  ): Promise<FileClassification[]> {
     return await invoke<FileClassification[]>('classify_files', {
       filePaths,
       workspacePath,
+      requestId,
     });
   },
 
-  async organizeFiles(filePaths: string[], workspacePath: string): Promise<FileMoveResult[]> {
-    return await invoke<FileMoveResult[]>('organize_files', {
+  // dryRun 为 true 时只返回移动计划，不移动文件
+  async organizeFiles(
+    filePaths: string[],
+    workspacePath: string,
+    dryRun = false,
+    requestId?: string
+  ): Promise<OrganizeReport> {
+    return await invoke<OrganizeReport>('organize_files', {
       filePaths,
       workspacePath,
+      dryRun,
+      requestId,
     });
   },
+
+  async applyOrganizePlan(
+    workspacePath: string,
+    plan: OrganizePlan,
+    requestId?: string
+  ): Promise<FileMoveResult[]> {
+    return await invoke<FileMoveResult[]>('apply_organize_plan', {
+      workspacePath,
+      plan,
+      requestId,
+    });
+  },
+
+  // 取消进行中的分类 / 整理（按 requestId）
+  async cancelFileOrganize(requestId: string): Promise<boolean> {
+    return await invoke<boolean>('cancel_file_organize', { requestId });
+  },
+
+  // 撤销最近一次整理
+  async undoLastOrganize(workspacePath: string): Promise<FileMoveResult[]> {
+    return await invoke<FileMoveResult[]>('undo_last_organize', { workspacePath });
+  },
 };
 
 // ⚠️ Week 20：文件分类相关类型
@@ -117,6 +169,10 @@ export interface FileClassification {
   category: string;
   reason: string;
   confidence: number;
+  /** 分类规则给出的标签 */
+  tag?: string | null;
+  /** 内容类型：invoice / contract / meeting_notes / scanned_document */
+  content_type?: string | null;
 }
 
 export interface FileMoveResult {
@@ -124,3 +180,39 @@ export interface FileMoveResult {
   success: boolean;
   message: string;
 }
+
+/** classify-progress / organize-progress 事件 */
+export interface FileBatchProgress {
+  request_id: string | null;
+  current_file: string;
+  processed: number;
+  total: number;
+  errors: number;
+}
+
+export interface PlannedMove {
+  file_path: string;
+  category: string;
+  tag?: string | null;
+  reason: string;
+  content_type?: string | null;
+  /** 目标路径；为空表示保持原位置 */
+  destination: string | null;
+  /** 目标位置已有同名文件，已改名 */
+  conflict: boolean;
+}
+
+export interface OrganizePlan {
+  moves: PlannedMove[];
+  total: number;
+  move_count: number;
+  unchanged_count: number;
+  conflict_count: number;
+  new_folders: string[];
+}
+
+export interface OrganizeReport {
+  dry_run: boolean;
+  plan: OrganizePlan;
+  results: FileMoveResult[];
+}
@@ -0,0 +1,22 @@
+import { invoke } from '@tauri-apps/api/core';
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
+import type { JobInfo } from '../types/job';
+
+export const jobService = {
+  async listJobs(): Promise<JobInfo[]> {
+    return await invoke<JobInfo[]>('list_jobs');
+  },
+
+  // 任务不存在或已结束时返回 false
+  async cancelJob(id: string): Promise<boolean> {
+    return await invoke<boolean>('cancel_job', { id });
+  },
+
+  async clearFinished(): Promise<void> {
+    await invoke('clear_finished_jobs');
+  },
+
+  async onJobUpdated(handler: (job: JobInfo) => void): Promise<UnlistenFn> {
+    return await listen<JobInfo>('job-updated', (event) => handler(event.payload));
+  },
+};
@@ -0,0 +1,24 @@
+import { invoke } from '@tauri-apps/api/core';
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
+import type { LogEntry, LogFilter, PerformanceReport } from '../types/log';
+
+export const logService = {
+  async getRecentLogs(filter?: LogFilter, limit?: number): Promise<LogEntry[]> {
+    return await invoke<LogEntry[]>('get_recent_logs', { filter, limit });
+  },
+
+  // module 为空时设置整个应用，返回生效的过滤规则
+  async setLogLevel(level: string, module?: string): Promise<string> {
+    return await invoke<string>('set_log_level', { level, module });
+  },
+
+  // 耗时统计；operation 为空时返回全部操作
+  async getPerformanceReport(operation?: string): Promise<PerformanceReport> {
+    return await invoke<PerformanceReport>('get_performance_report', { operation });
+  },
+
+  // 后端警告与错误实时推送
+  async onLogEvent(handler: (entry: LogEntry) => void): Promise<UnlistenFn> {
+    return await listen<LogEntry>('log-event', (event) => handler(event.payload));
+  },
+};
@@ -0,0 +1,27 @@
+import { invoke } from '@tauri-apps/api/core';
+import type { PluginInfo } from '../types/plugin';
+
+export const pluginService = {
+  async listPlugins(): Promise<PluginInfo[]> {
+    return await invoke<PluginInfo[]>('list_plugins');
+  },
+
+  async getPluginsDir(): Promise<string> {
+    return await invoke<string>('get_plugins_dir');
+  },
+
+  // 重新扫描插件目录，插件提供商随之重新注册
+  async reloadPlugins(): Promise<PluginInfo[]> {
+    return await invoke<PluginInfo[]>('reload_plugins');
+  },
+
+  // 源文件与输出路径须在工作区内；未指定 outputPath 时输出到源文件同目录
+  async convert(
+    converter: string,
+    path: string,
+    workspacePath: string,
+    outputPath?: string,
+  ): Promise<string> {
+    return await invoke<string>('convert_with_plugin', { converter, path, outputPath, workspacePath });
+  },
+};
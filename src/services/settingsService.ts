@@ -0,0 +1,18 @@
+import { invoke } from '@tauri-apps/api/core';
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
+import type { AppSettings } from '../types/settings';
+
+export const settingsService = {
+  async getSettings(): Promise<AppSettings> {
+    return await invoke<AppSettings>('get_settings');
+  },
+
+  // 后端校验失败时抛出错误，成功后广播 app-settings-changed
+  async updateSettings(settings: AppSettings): Promise<AppSettings> {
+    return await invoke<AppSettings>('update_settings', { settings });
+  },
+
+  async onSettingsChanged(handler: (settings: AppSettings) => void): Promise<UnlistenFn> {
+    return await listen<AppSettings>('app-settings-changed', (event) => handler(event.payload));
+  },
+};
@@ -0,0 +1,30 @@
+import { invoke } from '@tauri-apps/api/core';
+import type { TelemetryPayload, TelemetrySettings } from '../types/telemetry';
+
+export const telemetryService = {
+  async getSettings(): Promise<TelemetrySettings> {
+    return await invoke<TelemetrySettings>('get_telemetry_settings');
+  },
+
+  // 关闭时后端会清除已记录的数据
+  async updateSettings(settings: TelemetrySettings): Promise<TelemetrySettings> {
+    return await invoke<TelemetrySettings>('update_telemetry_settings', { settings });
+  },
+
+  async previewPayload(): Promise<TelemetryPayload> {
+    return await invoke<TelemetryPayload>('preview_telemetry_payload');
+  },
+
+  async upload(): Promise<boolean> {
+    return await invoke<boolean>('upload_telemetry');
+  },
+
+  async purge(): Promise<void> {
+    await invoke('purge_telemetry_data');
+  },
+
+  // 功能名只能用小写字母、数字和 _.-；未开启统计时后端直接忽略
+  recordFeature(feature: string): void {
+    invoke('record_feature_usage', { feature }).catch(() => {});
+  },
+};
@@ -0,0 +1,25 @@
+import { invoke } from '@tauri-apps/api/core';
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
+import type { UpdateChannel } from '../types/settings';
+import type { UpdateDownloadProgress, UpdateInfo } from '../types/update';
+
+export const updateService = {
+  // 有新版本时返回版本与发布说明，并在后台开始下载；未指定渠道时使用应用设置中的渠道
+  async checkForUpdates(channel?: UpdateChannel): Promise<UpdateInfo | null> {
+    return await invoke<UpdateInfo | null>('check_for_updates', { channel: channel ?? null });
+  },
+
+  // 安装已下载的更新并重启应用
+  async installUpdate(): Promise<void> {
+    await invoke('install_update');
+  },
+
+  async onDownloadProgress(handler: (progress: UpdateDownloadProgress) => void): Promise<UnlistenFn> {
+    return await listen<UpdateDownloadProgress>('update-download-progress', (event) => handler(event.payload));
+  },
+
+  // 后台下载完成，可以提示用户重启
+  async onUpdateReady(handler: (info: UpdateInfo) => void): Promise<UnlistenFn> {
+    return await listen<UpdateInfo>('update-ready', (event) => handler(event.payload));
+  },
+};
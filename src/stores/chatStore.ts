@@ -17,6 +17,7 @@ import {
     createShadowTaskRecord,
 } from '../types/agent_state';
 import { AgentTaskController } from '../services/AgentTaskController';
+import { getErrorCode, toErrorMessage } from '../utils/errorHandler';
 
 /**
  * Phase 1 边界说明：
@@ -756,14 +757,15 @@ export const useChatStore = create<ChatState>()(persist((set, get) => {
                     if (errorMessage) {
                         // 提供更友好的错误信息
                         let errorText = '发送消息失败';
-                        if (error instanceof Error) {
-                            const errorMsg = error.message;
+                        const errorCode = getErrorCode(error);
+                        const errorMsg = toErrorMessage(error, '');
+                        if (errorMsg) {
                             // 检测 API key 未配置错误
-                            if (errorMsg.includes('未配置') && (errorMsg.includes('提供商') || errorMsg.includes('API key'))) {
+                            if (errorCode === 'AI_NOT_CONFIGURED') {
                                 errorText = '❌ AI 功能未配置\n\n请先配置 API Key 才能使用 AI 功能。\n\n配置方法：\n1. 点击右上角设置图标\n2. 选择"配置 API Key"\n3. 输入 DeepSeek 或 OpenAI 的 API Key\n\n或者：\n- 在欢迎页面点击"配置 API Key"按钮';
-                            } else if (errorMsg.includes('网络错误') || errorMsg.includes('connection') || errorMsg.includes('网络') || errorMsg.includes('Connection refused') || errorMsg.includes('tcp connect')) {
+                            } else if (errorCode === 'AI_NETWORK_ERROR' || errorMsg.includes('网络错误') || errorMsg.includes('connection') || errorMsg.includes('网络') || errorMsg.includes('Connection refused') || errorMsg.includes('tcp connect')) {
                                 errorText = '❌ 网络连接失败\n\n无法连接到 AI 服务器，可能的原因：\n1. 网络连接问题（请检查网络连接）\n2. 防火墙或代理设置阻止了连接\n3. 需要配置代理（如果使用代理）\n4. DNS 解析问题\n5. AI 服务器暂时不可用\n\n建议：\n- 检查网络连接\n- 检查防火墙设置\n- 如果使用代理，请配置代理\n- 稍后重试';
-                            } else if (errorMsg.includes('timeout') || errorMsg.includes('超时')) {
+                            } else if (errorCode === 'AI_TIMEOUT' || errorMsg.includes('timeout') || errorMsg.includes('超时')) {
                                 errorText = '请求超时，请稍后重试';
                             } else if (errorMsg.includes('API') || errorMsg.includes('api')) {
                                 errorText = 'API 调用失败，请检查 API 密钥配置';
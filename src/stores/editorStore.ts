@@ -41,6 +41,7 @@ interface EditorState {
   /** 按文件路径解析已打开 tab（P3）；无匹配返回 null */
   getTabByFilePath: (filePath: string) => EditorTab | null;
   enableEditMode: (tabId: string) => void; // ⚠️ 新增：启用编辑模式
+  markTabReadOnly: (tabId: string) => void; // 只读打开（如参考资料文件夹中的文件）
   updateTabPath: (tabId: string, newPath: string) => void; // ⚠️ 新增：更新标签页路径
   markTabConflict: (tabId: string) => void; // ⚠️ 新增：标记冲突
   updateTabModifiedTime: (tabId: string, modifiedTime: number) => void; // ⚠️ Week 17.1.2：更新文件修改时间
@@ -208,6 +209,16 @@ export const useEditorStore = create<EditorStoreState>((set, get) => ({
     }));
   },
 
+  markTabReadOnly: (tabId) => {
+    set((state) => ({
+      tabs: state.tabs.map((tab) =>
+        tab.id === tabId
+          ? { ...tab, isReadOnly: true }
+          : tab
+      ),
+    }));
+  },
+
   // ⚠️ 新增：更新标签页路径
   updateTabPath: (tabId, newPath) => {
     set((state) => ({
@@ -1,3 +1,4 @@
+import { useEffect, useState } from 'react';
 import { create } from 'zustand';
 import { persist } from 'zustand/middleware';
 
@@ -5,9 +6,15 @@ export type Theme = 'light' | 'dark' | 'auto';
 
 interface ThemeState {
   theme: Theme;
+  /** 暗色主题下以反转亮度的方式显示文档预览（PDF） */
+  darkPreview: boolean;
   setTheme: (theme: Theme) => void;
+  setDarkPreview: (enabled: boolean) => void;
 }
 
+/** 暗色预览使用的 CSS 滤镜：反转亮度并把色相转回原色，略微降低对比度 */
+export const PREVIEW_DARK_FILTER = 'invert(0.88) hue-rotate(180deg)';
+
 const applyTheme = (theme: Theme) => {
   const root = document.documentElement;
   
@@ -41,10 +48,12 @@ export const useThemeStore = create<ThemeState>()(
   persist(
     (set) => ({
       theme: 'light',
+      darkPreview: true,
       setTheme: (theme) => {
         set({ theme });
         applyTheme(theme);
       },
+      setDarkPreview: (enabled) => set({ darkPreview: enabled }),
     }),
     {
       name: 'binder-theme-storage',
@@ -58,6 +67,23 @@ export const useThemeStore = create<ThemeState>()(
   )
 );
 
+/** 当前是否应以暗色显示文档预览：主题（含跟随系统）为暗色且开启了暗色预览 */
+export const usePreviewDarkMode = (): boolean => {
+  const { theme, darkPreview } = useThemeStore();
+  const [systemDark, setSystemDark] = useState(
+    () => window.matchMedia('(prefers-color-scheme: dark)').matches
+  );
+
+  useEffect(() => {
+    const mediaQuery = window.matchMedia('(prefers-color-scheme: dark)');
+    const onChange = (e: MediaQueryListEvent) => setSystemDark(e.matches);
+    mediaQuery.addEventListener('change', onChange);
+    return () => mediaQuery.removeEventListener('change', onChange);
+  }, []);
+
+  return darkPreview && (theme === 'dark' || (theme === 'auto' && systemDark));
+};
+
 // 初始化时应用主题
 if (typeof window !== 'undefined') {
   const savedTheme = localStorage.getItem('binder-theme-storage');
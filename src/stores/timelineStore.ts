@@ -1,6 +1,7 @@
 import { create } from 'zustand';
 import { timelineService } from '../services/timelineService';
 import type { TimelineNode } from '../types/timeline';
+import { toErrorMessage } from '../utils/errorHandler';
 
 interface TimelineState {
   nodes: TimelineNode[];
@@ -23,7 +24,7 @@ export const useTimelineStore = create<TimelineState>((set) => ({
       const nodes = await timelineService.listNodes(workspacePath, limit);
       set({ nodes, isLoading: false, error: null, lastWorkspacePath: workspacePath });
     } catch (error) {
-      const message = error instanceof Error ? error.message : String(error);
+      const message = toErrorMessage(error);
       set({ isLoading: false, error: message });
     }
   },
@@ -5,6 +5,63 @@ export interface FileTreeNode {
   children?: FileTreeNode[];
 }
 
+// 文件监听推送的单个变化（workspace-file-changes 事件）
+export type FileChangeKind = 'created' | 'modified' | 'deleted' | 'renamed';
+
+export interface WorkspaceFileChange {
+  kind: FileChangeKind;
+  /** 相对所在根目录（工作区或参考资料文件夹）的路径（/ 分隔） */
+  path: string;
+  old_path: string | null;
+  absolute_path: string;
+  old_absolute_path: string | null;
+  is_dir: boolean;
+  /** 变化发生在只读参考资料文件夹中时为该文件夹路径 */
+  reference_root: string | null;
+}
+
+export interface WorkspaceFileChangesEvent {
+  workspace_path: string;
+  changes: WorkspaceFileChange[];
+  /** 事件积压丢失，需要整体刷新 */
+  overflowed: boolean;
+}
+
+// 已打开文件被外部修改（external-file-changed 事件）
+export interface ExternalFileChangedEvent {
+  path: string;
+  /** 新的修改时间（毫秒），文件被删除时为 null */
+  modified_ms: number | null;
+  /** 行级变化摘要，二进制文件或被删除时为 null */
+  diff: { lines_added: number; lines_removed: number } | null;
+  deleted: boolean;
+}
+
+// 只读参考资料文件夹（工作区设置 watcher.reference_folders）
+export interface ReferenceFolder {
+  path: string;
+  name: string;
+  /** 文件夹存在且正在监听 */
+  available: boolean;
+}
+
+// 文件监听状态（get_watcher_status），用于排查文件树不刷新
+export interface WatcherStatus {
+  active: boolean;
+  paused: boolean;
+  backend: 'FSEvents' | 'inotify' | 'ReadDirectoryChanges' | 'kqueue' | 'poll';
+  watched_paths: string[];
+  watched_directories: number;
+  /** inotify watch 上限，仅 Linux */
+  max_user_watches: number | null;
+  events_received: number;
+  batches_sent: number;
+  dropped_events: number;
+  lagged_batches: number;
+  errors: number;
+  last_error: string | null;
+}
+
 // 文件类型枚举
 export type FileType = 
   | 'markdown'      // .md
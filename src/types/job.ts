@@ -0,0 +1,19 @@
+// 后台任务：list_jobs 返回值与 job-updated 事件载荷
+export type JobPriority = 'low' | 'normal' | 'high';
+export type JobStatus = 'queued' | 'running' | 'completed' | 'failed' | 'cancelled';
+
+export interface JobInfo {
+  id: string;
+  /** 任务类型，如 build_index、convert_images */
+  kind: string;
+  title: string;
+  priority: JobPriority;
+  status: JobStatus;
+  /** 0-1，无法估计时为 null */
+  progress: number | null;
+  message: string | null;
+  error: string | null;
+  created_at: number;
+  started_at: number | null;
+  finished_at: number | null;
+}
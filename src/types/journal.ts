@@ -0,0 +1,13 @@
+// 未保存内容日志：增量位置以 JS 字符串下标（UTF-16 码元）计
+export interface BufferDelta {
+  start: number;
+  delete: number;
+  insert: string;
+}
+
+export interface RecoverableBuffer {
+  path: string;
+  content: string;
+  /** 最后一次记录的时间（毫秒时间戳） */
+  updated_at: number;
+}
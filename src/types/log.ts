@@ -0,0 +1,44 @@
+// 后端日志（get_recent_logs / log-event）
+export type LogLevel = 'ERROR' | 'WARN' | 'INFO' | 'DEBUG' | 'TRACE';
+
+export interface LogEntry {
+  timestamp: string;
+  level: LogLevel;
+  target: string;
+  message: string;
+}
+
+export interface LogFilter {
+  /** 最低级别，如 'warn' 只返回警告和错误 */
+  level?: string;
+  /** target 前缀，如 'binder::services::pandoc_service' */
+  target?: string;
+  /** 消息包含的文字（忽略大小写） */
+  query?: string;
+}
+
+// 耗时统计（get_performance_report）
+export interface PerfSample {
+  /** 模块名::函数名，如 'pandoc_service::convert_document_to_html' */
+  operation: string;
+  detail: string;
+  duration_ms: number;
+  finished_at: string;
+}
+
+export interface OperationStats {
+  operation: string;
+  count: number;
+  total_ms: number;
+  avg_ms: number;
+  p50_ms: number;
+  p95_ms: number;
+  max_ms: number;
+}
+
+export interface PerformanceReport {
+  /** 按总耗时从高到低 */
+  operations: OperationStats[];
+  /** 最近的记录（按时间先后） */
+  recent: PerfSample[];
+}
@@ -0,0 +1,26 @@
+// 插件（应用数据目录 plugins/<插件>/plugin.toml 中的声明式清单）
+export interface PluginConverter {
+  name: string;
+  /** 支持的源文件扩展名（不含点） */
+  from: string[];
+  /** 输出文件扩展名 */
+  to: string;
+  command: string;
+  timeout_secs: number | null;
+}
+
+export interface PluginInfo {
+  id: string;
+  name: string;
+  version: string;
+  description: string;
+  enabled: boolean;
+  path: string;
+  /** 注册的 AI 提供商名称 */
+  providers: string[];
+  /** 注册的 Agent 工具名称 */
+  tools: string[];
+  converters: PluginConverter[];
+  /** 清单无法加载时的原因 */
+  error: string | null;
+}
@@ -0,0 +1,24 @@
+// 应用级设置（与工作区无关），保存在应用配置目录
+export type PreviewEngine = 'libreoffice' | 'pandoc';
+export type UpdateChannel = 'stable' | 'beta';
+
+export interface AppSettings {
+  /** 默认 AI 提供商：deepseek / openai */
+  default_ai_provider: string;
+  /** 自动保存间隔（秒），0 表示关闭 */
+  autosave_interval_secs: number;
+  /** Office 文档预览方式 */
+  preview_engine: PreviewEngine;
+  /** 网络代理，如 http://127.0.0.1:7890 */
+  proxy: string | null;
+  /** 自动更新的发布渠道 */
+  update_channel: UpdateChannel;
+  /** 单次文档转换的内存上限（MB），预计超出时跳过格式增强 */
+  conversion_memory_limit_mb: number;
+  /** 信任其中 DOCX 原始 HTML 的工作区根目录，跳过可执行内容的清理 */
+  trusted_html_workspaces: string[];
+  /** 允许 AI 执行命令的工作区根目录 */
+  shell_command_workspaces: string[];
+  /** 允许免确认执行的 HTTP 自定义工具（声明中还需 confirm = false） */
+  approved_custom_tools: { workspace: string; name: string }[];
+}
@@ -0,0 +1,25 @@
+// 匿名使用统计（默认关闭）
+export interface TelemetrySettings {
+  enabled: boolean;
+  /** 上传地址；未设置时只在本地记录 */
+  endpoint: string | null;
+}
+
+export interface CrashReport {
+  timestamp: number;
+  /** panic 消息的哈希；原始消息只写入本地日志 */
+  message_hash: string;
+  location: string | null;
+  /** 只含带 .rs 源码位置的调用栈帧 */
+  backtrace: string;
+}
+
+// 实际上传的内容
+export interface TelemetryPayload {
+  install_id: string;
+  app_version: string;
+  os: string;
+  arch: string;
+  feature_usage: Record<string, number>;
+  crashes: CrashReport[];
+}
@@ -9,6 +9,8 @@ export interface ToolCall {
     result?: ToolResult;
     error?: string;
     timestamp: number;
+    /** 参数仍在流式生成时的原始 JSON 片段（完整参数到达后清空） */
+    partialArguments?: string;
 }
 
 export interface ToolResult {
@@ -75,14 +77,22 @@ export interface ToolParameter {
 // 预定义的工具类型
 export enum ToolType {
     READ_FILE = 'read_file',
+    READ_DOCUMENT = 'read_document',
     CREATE_FILE = 'create_file',
     UPDATE_FILE = 'update_file',
+    APPLY_PATCH = 'apply_patch',
     DELETE_FILE = 'delete_file',
     LIST_FILES = 'list_files',
+    LIST_DIRECTORY = 'list_directory',
     SEARCH_FILES = 'search_files',
+    SEARCH_WORKSPACE = 'search_workspace',
+    SEARCH_MEMORIES = 'search_memories',
     MOVE_FILE = 'move_file',
+    COPY_FILE = 'copy_file',
+    CREATE_DOCX_FROM_MARKDOWN = 'create_docx_from_markdown',
     RENAME_FILE = 'rename_file',
     CREATE_FOLDER = 'create_folder',
+    RUN_COMMAND = 'run_command',
 }
 
 // 工具调用请求
@@ -0,0 +1,18 @@
+import type { UpdateChannel } from './settings';
+
+// 自动更新：check_for_updates 返回值及 update-available / update-ready 事件载荷
+export interface UpdateInfo {
+  current_version: string;
+  version: string;
+  channel: UpdateChannel;
+  /** 发布说明（Markdown） */
+  notes: string | null;
+  date: string | null;
+}
+
+export interface UpdateDownloadProgress {
+  version: string;
+  downloaded: number;
+  /** 总字节数，服务器未返回时为 null */
+  total: number | null;
+}
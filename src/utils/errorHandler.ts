@@ -2,8 +2,72 @@
  * 统一错误处理工具
  */
 
+/**
+ * 后端 AppError 的错误码（与 src-tauri/src/error.rs 保持一致，只增不改）
+ */
+export type AppErrorCode =
+  | 'FILE_NOT_FOUND'
+  | 'FILE_EXISTS'
+  | 'PERMISSION_DENIED'
+  | 'READ_ONLY'
+  | 'INVALID_INPUT'
+  | 'IO_ERROR'
+  | 'PANDOC_UNAVAILABLE'
+  | 'LIBREOFFICE_UNAVAILABLE'
+  | 'CONVERSION_FAILED'
+  | 'INSUFFICIENT_DISK_SPACE'
+  | 'AI_NOT_CONFIGURED'
+  | 'AI_NETWORK_ERROR'
+  | 'AI_RATE_LIMITED'
+  | 'AI_MODEL_UNAVAILABLE'
+  | 'AI_CONTEXT_TOO_LONG'
+  | 'AI_TIMEOUT'
+  | 'AI_CANCELLED'
+  | 'AI_ERROR'
+  | 'UNKNOWN';
+
+/**
+ * 命令返回的结构化错误（invoke reject 的值）
+ */
+export interface AppErrorPayload {
+  code: AppErrorCode;
+  message: string;
+  details?: {
+    path?: string;
+    tool?: string;
+    retry_after?: number;
+    required_bytes?: number;
+    available_bytes?: number;
+  } | null;
+}
+
+export const isAppError = (error: unknown): error is AppErrorPayload =>
+  typeof error === 'object' &&
+  error !== null &&
+  typeof (error as AppErrorPayload).code === 'string' &&
+  typeof (error as AppErrorPayload).message === 'string';
+
+/**
+ * 错误码；非 AppError（旧命令返回的字符串、前端异常）返回 undefined
+ */
+export const getErrorCode = (error: unknown): AppErrorCode | undefined =>
+  isAppError(error) ? error.code : undefined;
+
+/**
+ * 取出可展示的错误消息，兼容 AppError、Error 和字符串；都不是时返回 fallback
+ */
+export const toErrorMessage = (error: unknown, fallback?: string): string => {
+  if (isAppError(error) || error instanceof Error) {
+    return error.message;
+  }
+  if (typeof error === 'string') {
+    return error;
+  }
+  return fallback ?? String(error);
+};
+
 export const handleError = (error: unknown, context?: string): string => {
-  const message = error instanceof Error ? error.message : '未知错误';
+  const message = toErrorMessage(error, '未知错误');
   const errorContext = context || 'Unknown';
   
   console.error(`[${errorContext}]`, error);
@@ -58,6 +122,21 @@ export enum ErrorType {
  * 根据错误类型返回用户友好的消息
  */
 export const getErrorMessage = (error: unknown, type?: ErrorType): string => {
+  switch (getErrorCode(error)) {
+    case 'FILE_NOT_FOUND':
+      return type || ErrorType.FileNotFound;
+    case 'PERMISSION_DENIED':
+      return type || ErrorType.PermissionDenied;
+    case 'INVALID_INPUT':
+      return type || ErrorType.InvalidInput;
+    case 'AI_NETWORK_ERROR':
+    case 'AI_TIMEOUT':
+      return type || ErrorType.NetworkError;
+  }
+  if (isAppError(error)) {
+    return error.message;
+  }
+
   if (error instanceof Error) {
     const message = error.message.toLowerCase();
     
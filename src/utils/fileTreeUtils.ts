@@ -1,5 +1,5 @@
 // 文件树工具函数
-import { FileTreeNode } from '../types/file';
+import { FileTreeNode, WorkspaceFileChange } from '../types/file';
 
 // 扁平化文件树为文件列表
 export function flattenFileTree(node: FileTreeNode, basePath: string = ''): Array<{ name: string; path: string; isDirectory: boolean }> {
@@ -30,3 +30,138 @@ export function filterFiles(nodes: Array<{ name: string; path: string; isDirecto
         .map(node => ({ name: node.name, path: node.path }));
 }
 
+const splitParent = (path: string): { parent: string; name: string } => {
+    const index = Math.max(path.lastIndexOf('/'), path.lastIndexOf('\\'));
+    return { parent: path.slice(0, index), name: path.slice(index + 1) };
+};
+
+// 与后端 FileTreeService 一致：目录在前，然后按名称排序
+const compareNodes = (a: FileTreeNode, b: FileTreeNode): number => {
+    if (a.is_directory !== b.is_directory) {
+        return a.is_directory ? -1 : 1;
+    }
+    return a.name < b.name ? -1 : a.name > b.name ? 1 : 0;
+};
+
+// 重命名目录时同步更新子节点路径
+const rebaseNode = (node: FileTreeNode, oldPrefix: string, newPrefix: string): FileTreeNode => ({
+    ...node,
+    path: newPrefix + node.path.slice(oldPrefix.length),
+    children: node.children?.map((child) => rebaseNode(child, oldPrefix, newPrefix)),
+});
+
+/**
+ * 按文件变化局部更新文件树（不修改原树）。
+ * 无法局部更新时（新建目录内容未知、父目录不在已加载的树中）返回 null，调用方应整体刷新。
+ */
+export function applyFileChanges(tree: FileTreeNode, changes: WorkspaceFileChange[]): FileTreeNode | null {
+    let root = tree;
+
+    // 在路径上复制节点，返回可修改的目标目录
+    const findDirForUpdate = (dirPath: string): FileTreeNode | null => {
+        const visit = (node: FileTreeNode): FileTreeNode | null => {
+            if (node.path === dirPath) {
+                return node;
+            }
+            if (!node.children || !dirPath.startsWith(node.path)) {
+                return null;
+            }
+            for (let i = 0; i < node.children.length; i++) {
+                const found = visit(node.children[i]);
+                if (found) {
+                    return found;
+                }
+            }
+            return null;
+        };
+        if (!visit(root)) {
+            return null;
+        }
+        const cloneTo = (node: FileTreeNode): FileTreeNode => {
+            if (node.path === dirPath) {
+                return { ...node, children: node.children ? [...node.children] : undefined };
+            }
+            return {
+                ...node,
+                children: node.children?.map((child) =>
+                    dirPath.startsWith(child.path) ? cloneTo(child) : child
+                ),
+            };
+        };
+        root = cloneTo(root);
+        return visit(root);
+    };
+
+    const removeNode = (path: string): FileTreeNode | null | undefined => {
+        const { parent } = splitParent(path);
+        const dir = findDirForUpdate(parent);
+        if (!dir) {
+            return null;
+        }
+        const index = dir.children?.findIndex((child) => child.path === path) ?? -1;
+        if (!dir.children || index < 0) {
+            return undefined;
+        }
+        return dir.children.splice(index, 1)[0];
+    };
+
+    const insertNode = (node: FileTreeNode): boolean => {
+        const { parent } = splitParent(node.path);
+        const dir = findDirForUpdate(parent);
+        if (!dir) {
+            return false;
+        }
+        // 父目录未展开加载（超出深度）时无需处理
+        if (!dir.children) {
+            return true;
+        }
+        dir.children = dir.children.filter((child) => child.path !== node.path);
+        dir.children.push(node);
+        dir.children.sort(compareNodes);
+        return true;
+    };
+
+    for (const change of changes) {
+        const { name } = splitParent(change.absolute_path);
+        // 隐藏文件不显示在文件树中
+        if (name.startsWith('.')) {
+            continue;
+        }
+        switch (change.kind) {
+            case 'modified':
+                break;
+            case 'created':
+                // 新出现的目录可能带有内容（如从外部移入），需要整体刷新
+                if (change.is_dir) {
+                    return null;
+                }
+                if (!insertNode({ name, path: change.absolute_path, is_directory: false })) {
+                    return null;
+                }
+                break;
+            case 'deleted':
+                if (removeNode(change.absolute_path) === null) {
+                    return null;
+                }
+                break;
+            case 'renamed': {
+                if (!change.old_absolute_path) {
+                    return null;
+                }
+                const removed = removeNode(change.old_absolute_path);
+                if (removed === null || (change.is_dir && !removed)) {
+                    return null;
+                }
+                const node = removed
+                    ? { ...rebaseNode(removed, change.old_absolute_path, change.absolute_path), name }
+                    : { name, path: change.absolute_path, is_directory: false };
+                if (!insertNode(node)) {
+                    return null;
+                }
+                break;
+            }
+        }
+    }
+
+    return root;
+}
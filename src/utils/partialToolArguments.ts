@@ -0,0 +1,67 @@
+/**
+ * 流式工具调用参数解析：
+ * - 参数 JSON 尚未闭合时无法 JSON.parse，这里只按键名抽取字符串字段的已到达部分
+ * - 用于在 create_file / update_file 等参数生成过程中实时预览路径与内容
+ */
+
+const ESCAPES: Record<string, string> = {
+    '"': '"',
+    '\\': '\\',
+    '/': '/',
+    b: '\b',
+    f: '\f',
+    n: '\n',
+    r: '\r',
+    t: '\t',
+};
+
+/**
+ * 从未闭合的 JSON 文本中取出 `key` 对应的字符串值（可能不完整）。
+ * 键不存在或值不是字符串时返回 undefined；末尾被截断的转义序列会被忽略。
+ */
+export function extractPartialJsonString(partial: string, key: string): string | undefined {
+    const escapedKey = key.replace(/[.*+?^${}()|[\]\\]/g, '\\$&');
+    const match = new RegExp(`"${escapedKey}"\\s*:\\s*"`).exec(partial);
+    if (!match) return undefined;
+
+    let result = '';
+    let i = match.index + match[0].length;
+    while (i < partial.length) {
+        const ch = partial[i];
+        if (ch === '"') break;
+        if (ch !== '\\') {
+            result += ch;
+            i += 1;
+            continue;
+        }
+        const next = partial[i + 1];
+        if (next === undefined) break;
+        if (next === 'u') {
+            const hex = partial.slice(i + 2, i + 6);
+            if (!/^[0-9a-fA-F]{4}$/.test(hex)) break;
+            result += String.fromCharCode(parseInt(hex, 16));
+            i += 6;
+            continue;
+        }
+        result += ESCAPES[next] ?? next;
+        i += 2;
+    }
+    return result;
+}
+
+/** 取文本末尾若干行作为预览 */
+export function tailPreview(text: string, maxLines = 6, maxChars = 400): string {
+    const tail = text.length > maxChars ? text.slice(-maxChars) : text;
+    return tail.split('\n').slice(-maxLines).join('\n');
+}
+
+/**
+ * 后端只推送新增的参数片段：按 offset 拼接到已收到的部分参数之后。
+ * offset 与已收到的长度对不上时（例如中途漏收事件）保留原内容，等完整参数到达。
+ * offset 为 UTF-8 字节数，这里按字节长度比较。
+ */
+export function appendPartialArguments(previous: string | undefined, offset: number | undefined, delta: string): string {
+    const current = previous ?? '';
+    if (!offset) return delta;
+    return new TextEncoder().encode(current).length === offset ? current + delta : current;
+}
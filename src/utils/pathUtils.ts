@@ -84,7 +84,7 @@ export function getAbsolutePath(relativePath: string, workspacePath: string): st
   const normalizedWorkspace = normalizePath(workspacePath);
   
   // 如果相对路径已经是绝对路径，直接返回
-  if (normalizedRelative.startsWith('/') || /^[A-Za-z]:/.test(normalizedRelative)) {
+  if (isAbsolutePath(normalizedRelative)) {
     return normalizedRelative;
   }
   
@@ -94,6 +94,14 @@ export function getAbsolutePath(relativePath: string, workspacePath: string): st
   return normalizePath(combined);
 }
 
+/**
+ * 是否为绝对路径（Unix 或 Windows 盘符）
+ */
+export function isAbsolutePath(path: string): boolean {
+  const normalized = normalizePath(path);
+  return normalized.startsWith('/') || /^[A-Za-z]:/.test(normalized);
+}
+
 /**
  * 判断两个路径是否指向同一文档（用于 edit_target 路径匹配）
  * Phase 1：简单规范化后比较；Phase 2 将支持 workspacePath 统一相对/绝对
@@ -7,6 +7,7 @@ const CONFIRMATION_REQUIRED_TOOLS = new Set([
     'move_file',
     'rename_file',
     'create_folder',
+    'run_command',
 ]);
 
 export function isAwaitingAuthorization(toolCall?: Pick<ToolCall, 'name' | 'arguments' | 'result' | 'status'> | null, workspacePath?: string): boolean {
@@ -26,24 +27,40 @@ export function generateToolDescription(toolCall: ToolCall): string {
     switch (name) {
         case 'list_files':
             return `查看目录: ${args.path || '.'}`;
+        case 'list_directory':
+            return `查看目录结构: ${args.path || '.'}`;
         case 'create_folder':
             return `创建文件夹: ${args.path || ''}`;
         case 'move_file':
             return `移动文件: ${args.source || ''} → ${args.destination || ''}`;
+        case 'copy_file':
+            return `复制文件: ${args.source || ''} → ${args.destination || ''}`;
         case 'read_file':
             return `读取文件: ${args.path || ''}`;
+        case 'read_document':
+            return `读取文档: ${args.path || ''}`;
         case 'create_file':
             return `创建文件: ${args.path || ''}`;
+        case 'create_docx_from_markdown':
+            return `生成 Word 文档: ${args.path || ''}`;
         case 'update_file':
             return `更新文件: ${args.path || ''}`;
+        case 'apply_patch':
+            return `局部修改文件: ${args.path || ''}`;
         case 'delete_file':
             return `删除文件: ${args.path || ''}`;
         case 'rename_file':
             return `重命名: ${args.old_path || ''} → ${args.new_path || ''}`;
         case 'search_files':
             return `搜索文件: ${args.query || ''}`;
+        case 'search_workspace':
+            return `搜索内容: ${args.query || ''}`;
+        case 'search_memories':
+            return `查询记忆: ${args.query || ''}`;
         case 'edit_current_editor_document':
             return `编辑当前文档`;
+        case 'run_command':
+            return `执行命令: ${args.command || ''}`;
         default:
             return `执行操作: ${name}`;
     }